@@ -68,8 +68,10 @@ async fn main() -> std::io::Result<()> {
         Commands::Domain(command) => command.exec(client).await,
         Commands::List(command) => command.exec(client).await,
         Commands::Group(command) => command.exec(client).await,*/
+        Commands::Ip(command) => command.exec(client).await,
         Commands::Queue(command) => command.exec(client).await,
         Commands::Report(command) => command.exec(client).await,
+        Commands::Generate(command) => command.exec(client).await,
     }
 
     Ok(())
@@ -218,6 +220,20 @@ impl Client {
         method: Method,
         url: &str,
         body: Option<B>,
+    ) -> Option<R> {
+        self.try_http_request_raw(
+            method,
+            url,
+            body.map(|body| serde_json::to_vec(&body).unwrap_result("serialize body")),
+        )
+        .await
+    }
+
+    pub async fn try_http_request_raw<R: DeserializeOwned>(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<Vec<u8>>,
     ) -> Option<R> {
         let url = format!(
             "{}{}{}",
@@ -244,7 +260,7 @@ impl Client {
             );
 
         if let Some(body) = body {
-            request = request.body(serde_json::to_string(&body).unwrap_result("serialize body"));
+            request = request.body(body);
         }
 
         let response = request.send().await.unwrap_result("send HTTP request");
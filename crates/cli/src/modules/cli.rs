@@ -56,6 +56,10 @@ pub enum Commands {
     #[clap(subcommand)]
     Server(ServerCommands),
 
+    /// Manage IP address allow and block lists
+    #[clap(subcommand)]
+    Ip(IpCommands),
+
     /// Manage SMTP message queue
     #[clap(subcommand)]
     Queue(QueueCommands),
@@ -63,6 +67,10 @@ pub enum Commands {
     /// Manage SMTP DMARC/TLS report queue
     #[clap(subcommand)]
     Report(ReportCommands),
+
+    /// Generate synthetic mailboxes for benchmarking and load testing
+    #[clap(subcommand)]
+    Generate(GenerateCommands),
 }
 
 pub struct Client {
@@ -363,6 +371,22 @@ pub enum ImportCommands {
         /// Path to the mailbox to import, or '-' for stdin (stdin only supported for mbox)
         path: String,
     },
+    /// Upload an mbox file or a zip of EML files to be imported by the server
+    Bulk {
+        #[clap(value_enum)]
+        #[clap(short, long)]
+        format: BulkFormat,
+
+        /// Mailbox to import messages into, defaults to the Inbox
+        #[clap(short, long)]
+        mailbox: Option<String>,
+
+        /// Account name or email to import messages into
+        account: String,
+
+        /// Path to the file to import, or '-' for stdin
+        path: String,
+    },
     /// Import a JMAP account
     Account {
         /// Number of concurrent requests, defaults to the number of CPUs.
@@ -423,6 +447,129 @@ pub enum ServerCommands {
         /// Prefix to filter configuration entries by
         prefix: Option<String>,
     },
+
+    /// Export the spam filter Bayes tokens and reputation data
+    ExportSpamFilter {
+        /// Lookup store to export from, defaults to the spam filter store
+        #[clap(short, long)]
+        store: Option<String>,
+
+        /// Path to the file to export the training data to
+        path: String,
+    },
+
+    /// Import spam filter Bayes tokens and reputation data
+    ImportSpamFilter {
+        /// Merge with the existing training data instead of replacing it
+        #[clap(short, long)]
+        merge: bool,
+
+        /// Lookup store to import into, defaults to the spam filter store
+        #[clap(short, long)]
+        store: Option<String>,
+
+        /// Path to the exported training data
+        path: String,
+    },
+
+    /// Train the spam filter Bayes classifier from labeled mbox or Maildir corpora
+    TrainSpamFilter {
+        /// Path to a spam corpus, either an mbox file, a Maildir or a directory of messages
+        #[clap(long)]
+        spam: Vec<String>,
+
+        /// Path to a ham corpus, either an mbox file, a Maildir or a directory of messages
+        #[clap(long)]
+        ham: Vec<String>,
+
+        /// Maximum number of tokens to learn from each message, defaults to unlimited
+        #[clap(short = 't', long)]
+        max_tokens: Option<usize>,
+
+        /// Maximum number of messages to learn from each corpus, defaults to unlimited
+        #[clap(short = 'n', long)]
+        max_messages: Option<usize>,
+
+        /// Lookup store to train, defaults to the spam filter store
+        #[clap(short, long)]
+        store: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum IpCommands {
+    /// List the entries of an IP list
+    List {
+        /// IP list to show
+        #[clap(value_enum)]
+        list: IpList,
+    },
+
+    /// Add IP addresses or networks to a list
+    Add {
+        /// IP list to add the entries to
+        #[clap(value_enum)]
+        list: IpList,
+        /// IP addresses or networks in CIDR notation
+        #[clap(required = true)]
+        addresses: Vec<String>,
+        /// Comment describing the entries
+        #[clap(short, long)]
+        comment: Option<String>,
+        /// Remove the entries at a certain datetime
+        #[clap(short, long)]
+        #[arg(value_parser = parse_datetime)]
+        expires: Option<DateTime>,
+    },
+
+    /// Remove IP addresses or networks from a list
+    Remove {
+        /// IP list to remove the entries from
+        #[clap(value_enum)]
+        list: IpList,
+        /// IP addresses or networks in CIDR notation
+        #[clap(required = true)]
+        addresses: Vec<String>,
+    },
+
+    /// Import the entries of a configured IP feed now
+    ImportFeed {
+        /// Feed id
+        id: String,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum IpList {
+    /// Addresses denied access to all listeners
+    Blocked,
+    /// Addresses never blocked by fail2ban
+    Allowed,
+}
+
+#[derive(Subcommand)]
+pub enum GenerateCommands {
+    /// Import a reproducible set of synthetic messages into an account
+    Messages {
+        /// Number of messages to generate
+        #[clap(short = 'm', long, default_value_t = 1000)]
+        num_messages: usize,
+
+        /// Random seed, the same seed always generates the same messages
+        #[clap(short, long, default_value_t = 0)]
+        seed: u64,
+
+        /// Mailbox to import messages into, defaults to the Inbox
+        #[clap(short, long)]
+        folder: Option<String>,
+
+        /// Number of messages to import concurrently, defaults to the number of CPUs.
+        #[clap(short, long)]
+        num_concurrent: Option<usize>,
+
+        /// Account name or email to import messages into
+        account: String,
+    },
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -435,6 +582,14 @@ pub enum MailboxFormat {
     MaildirNested,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum BulkFormat {
+    /// Mbox format
+    Mbox,
+    /// Zip archive of EML files, Maildir flags in file names are preserved
+    Zip,
+}
+
 #[derive(Subcommand)]
 pub enum QueueCommands {
     /// Shows messages queued for delivery
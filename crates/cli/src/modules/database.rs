@@ -4,13 +4,16 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Cursor, path::Path, time::Duration};
 
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+use mail_parser::mailbox::{maildir, mbox::MessageIterator};
 use prettytable::{Attr, Cell, Row, Table};
 use reqwest::Method;
 use serde_json::Value;
 
-use crate::modules::Response;
+use crate::modules::{read_file, Response, UnwrapResult};
 
 use super::cli::{Client, ServerCommands};
 
@@ -109,6 +112,176 @@ impl ServerCommands {
                     if results.len() == 1 { "" } else { "s" }
                 );
             }
+            ServerCommands::ExportSpamFilter { store, path } => {
+                let data = client
+                    .http_request::<Value, String>(
+                        Method::GET,
+                        &spam_filter_url(store.as_deref(), false),
+                        None,
+                    )
+                    .await;
+                std::fs::write(
+                    &path,
+                    serde_json::to_vec(&data).unwrap_result("serialize training data"),
+                )
+                .unwrap_result("write training data");
+                eprintln!(
+                    "Exported {} Bayes tokens and {} reputation entries to {path}.",
+                    data["bayes"]["tokens"].as_array().map_or(0, |v| v.len()),
+                    data["reputation"].as_array().map_or(0, |v| v.len())
+                );
+            }
+            ServerCommands::ImportSpamFilter { merge, store, path } => {
+                let data = std::fs::read(&path).unwrap_result("read training data");
+                let results = client
+                    .try_http_request_raw::<HashMap<String, usize>>(
+                        Method::POST,
+                        &spam_filter_url(store.as_deref(), merge),
+                        Some(data),
+                    )
+                    .await
+                    .unwrap_or_default();
+                eprintln!(
+                    "Imported {} Bayes tokens and {} reputation entries.",
+                    results.get("tokens").copied().unwrap_or_default(),
+                    results.get("reputation").copied().unwrap_or_default()
+                );
+            }
+            ServerCommands::TrainSpamFilter {
+                spam,
+                ham,
+                max_tokens,
+                max_messages,
+                store,
+            } => {
+                if spam.is_empty() && ham.is_empty() {
+                    eprintln!("No spam or ham corpora specified.");
+                    std::process::exit(1);
+                }
+
+                let spinner_style =
+                    ProgressStyle::with_template("{prefix:.bold.dim} {spinner} {wide_msg}")
+                        .unwrap()
+                        .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ");
+                let corpora = spam
+                    .into_iter()
+                    .map(|path| (path, true))
+                    .chain(ham.into_iter().map(|path| (path, false)))
+                    .collect::<Vec<_>>();
+                let num_corpora = corpora.len();
+                let mut total_messages = 0;
+                let mut total_skipped = 0;
+
+                for (corpus_num, (path, is_spam)) in corpora.into_iter().enumerate() {
+                    let class = if is_spam { "spam" } else { "ham" };
+                    let url = train_url(class, store.as_deref(), max_tokens);
+                    let pb = ProgressBar::new_spinner();
+                    pb.set_style(spinner_style.clone());
+                    pb.set_prefix(format!("[{}/{num_corpora}]", corpus_num + 1));
+                    pb.enable_steady_tick(Duration::from_millis(100));
+
+                    let mut messages = 0;
+                    let mut tokens = 0;
+                    for raw_message in corpus_messages(&path)
+                        .take(max_messages.unwrap_or(usize::MAX))
+                    {
+                        let learned = client
+                            .try_http_request_raw::<HashMap<String, usize>>(
+                                Method::POST,
+                                &url,
+                                Some(raw_message),
+                            )
+                            .await
+                            .and_then(|result| result.get("tokens").copied())
+                            .unwrap_or_default();
+                        if learned > 0 {
+                            messages += 1;
+                            tokens += learned;
+                        } else {
+                            total_skipped += 1;
+                        }
+                        pb.set_message(format!(
+                            "Training {class} from {path}: {messages} messages, {tokens} tokens"
+                        ));
+                    }
+
+                    pb.finish_with_message(format!(
+                        "Trained {class} from {path}: {messages} messages, {tokens} tokens"
+                    ));
+                    total_messages += messages;
+                }
+
+                eprintln!(
+                    "{} Trained the spam filter with {total_messages} messages ({total_skipped} skipped).",
+                    style("[done]").bold().dim(),
+                );
+            }
         }
     }
 }
+
+fn spam_filter_url(store: Option<&str>, merge: bool) -> String {
+    let mut url = "/api/spam-filter/training".to_string();
+    let mut separator = '?';
+    if let Some(store) = store {
+        url.push(separator);
+        url.push_str("store=");
+        url.push_str(&form_urlencoded::byte_serialize(store.as_bytes()).collect::<String>());
+        separator = '&';
+    }
+    if merge {
+        url.push(separator);
+        url.push_str("merge=true");
+    }
+    url
+}
+
+fn train_url(class: &str, store: Option<&str>, max_tokens: Option<usize>) -> String {
+    let mut url = format!("/api/spam-filter/train/{class}");
+    let mut separator = '?';
+    if let Some(store) = store {
+        url.push(separator);
+        url.push_str("store=");
+        url.push_str(&form_urlencoded::byte_serialize(store.as_bytes()).collect::<String>());
+        separator = '&';
+    }
+    if let Some(max_tokens) = max_tokens {
+        url.push(separator);
+        url.push_str(&format!("max-tokens={max_tokens}"));
+    }
+    url
+}
+
+// Iterates over the messages of a corpus, which can be an mbox file, a Maildir
+// or a plain directory with one message per file.
+fn corpus_messages(path: &str) -> Box<dyn Iterator<Item = Vec<u8>>> {
+    let corpus = Path::new(path);
+    if !corpus.is_dir() {
+        Box::new(
+            MessageIterator::new(Cursor::new(read_file(path)))
+                .filter_map(|message| message.ok().map(|message| message.unwrap_contents())),
+        )
+    } else if corpus.join("cur").is_dir() {
+        Box::new(
+            maildir::FolderIterator::new(path.to_string(), None)
+                .unwrap_result("read Maildir folder")
+                .flat_map(|folder| folder.unwrap_result("read Maildir folder"))
+                .filter_map(|message| message.ok().map(|message| message.unwrap_contents())),
+        )
+    } else {
+        let mut files = std::fs::read_dir(corpus)
+            .unwrap_result("read corpus directory")
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                (path.is_file()
+                    && !path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .map_or(true, |name| name.starts_with('.')))
+                .then_some(path)
+            })
+            .collect::<Vec<_>>();
+        files.sort_unstable();
+        Box::new(files.into_iter().filter_map(|path| std::fs::read(path).ok()))
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use console::style;
+use futures::{stream::FuturesUnordered, StreamExt};
+use generator::MessageGenerator;
+use indicatif::{ProgressBar, ProgressStyle};
+use jmap_client::mailbox::{query::Filter, Role};
+
+use crate::modules::{name_to_id, UnwrapResult, RETRY_ATTEMPTS};
+
+use super::cli::{Client, GenerateCommands};
+
+impl GenerateCommands {
+    pub async fn exec(self, client: Client) {
+        let mut client = client.into_jmap_client().await;
+
+        match self {
+            GenerateCommands::Messages {
+                num_messages,
+                seed,
+                folder,
+                num_concurrent,
+                account,
+            } => {
+                client.set_default_account_id(name_to_id(&client, &account).await);
+
+                // Obtain mailbox id
+                let filter = if let Some(folder) = &folder {
+                    Filter::name(folder)
+                } else {
+                    Filter::role(Role::Inbox)
+                };
+                let mailbox_id = client
+                    .mailbox_query(Some(filter), None::<Vec<_>>)
+                    .await
+                    .unwrap_result("query mailboxes")
+                    .take_ids()
+                    .pop()
+                    .unwrap_or_else(|| {
+                        eprintln!(
+                            "Error: Mailbox '{}' not found.",
+                            folder.as_deref().unwrap_or("Inbox")
+                        );
+                        std::process::exit(1);
+                    });
+
+                // Import generated messages
+                let num_concurrent = num_concurrent.unwrap_or_else(num_cpus::get);
+                let pb = ProgressBar::new(num_messages as u64);
+                pb.set_style(
+                    ProgressStyle::default_bar()
+                        .template("{bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
+                        .unwrap(),
+                );
+                let total_imported = Arc::new(AtomicUsize::from(0));
+                let total_bytes = Arc::new(AtomicUsize::from(0));
+                let mut futures = FuturesUnordered::new();
+                let start_time = Instant::now();
+
+                for message in MessageGenerator::new(seed).take(num_messages) {
+                    let client = &client;
+                    let mailbox_id = &mailbox_id;
+                    let pb = &pb;
+                    let total_imported = total_imported.clone();
+                    let total_bytes = total_bytes.clone();
+
+                    futures.push(async move {
+                        let mut retry_count = 0;
+                        loop {
+                            match client
+                                .email_import(
+                                    message.contents.clone(),
+                                    [mailbox_id.as_str()],
+                                    Some(message.keywords.clone()),
+                                    Some(message.received_at),
+                                )
+                                .await
+                            {
+                                Ok(_) => {
+                                    total_imported.fetch_add(1, Ordering::Relaxed);
+                                    total_bytes
+                                        .fetch_add(message.contents.len(), Ordering::Relaxed);
+                                }
+                                Err(_) if retry_count < RETRY_ATTEMPTS => {
+                                    retry_count += 1;
+                                    continue;
+                                }
+                                Err(err) => {
+                                    pb.println(format!("Failed to import message: {err}"));
+                                }
+                            }
+                            break;
+                        }
+                        pb.inc(1);
+                    });
+
+                    if futures.len() == num_concurrent {
+                        futures.next().await.unwrap();
+                    }
+                }
+
+                // Wait for remaining futures
+                while futures.next().await.is_some() {}
+                pb.finish_and_clear();
+
+                let elapsed = start_time.elapsed();
+                let total_imported = total_imported.load(Ordering::Relaxed);
+                eprintln!(
+                    "{} Imported {} messages ({} bytes) in {:.2}s, {:.1} messages/s.",
+                    style("[done]").bold().dim(),
+                    total_imported,
+                    total_bytes.load(Ordering::Relaxed),
+                    elapsed.as_secs_f64(),
+                    total_imported as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+                );
+            }
+        }
+    }
+}
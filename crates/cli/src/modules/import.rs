@@ -27,13 +27,14 @@ use mail_parser::mailbox::{
     mbox::{self, MessageIterator},
 };
 use rand::Rng;
-use serde::de::DeserializeOwned;
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Deserialize};
 use tokio::{fs::File, io::AsyncReadExt};
 
 use crate::modules::{name_to_id, UnwrapResult, RETRY_ATTEMPTS};
 
 use super::{
-    cli::{Client, ImportCommands, MailboxFormat},
+    cli::{BulkFormat, Client, ImportCommands, MailboxFormat},
     export::{
         fetch_emails, fetch_identities, fetch_mailboxes, fetch_sieve_scripts,
         fetch_vacation_responses,
@@ -54,6 +55,14 @@ enum MailboxId<'x> {
     None,
 }
 
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+enum BulkImportResult {
+    Success {},
+    Error { name: String, reason: String },
+}
+
 #[derive(Debug)]
 struct Message {
     identifier: String,
@@ -63,8 +72,6 @@ struct Message {
 }
 impl ImportCommands {
     pub async fn exec(self, client: Client) {
-        let mut client = client.into_jmap_client().await;
-
         match self {
             ImportCommands::Messages {
                 num_concurrent,
@@ -72,6 +79,7 @@ impl ImportCommands {
                 account,
                 path,
             } => {
+                let mut client = client.into_jmap_client().await;
                 client.set_default_account_id(name_to_id(&client, &account).await);
                 let mut create_mailboxes = Vec::new();
                 let mut create_mailbox_names = Vec::new();
@@ -419,11 +427,59 @@ impl ImportCommands {
                 }
             }
 
+            ImportCommands::Bulk {
+                format,
+                mailbox,
+                account,
+                path,
+            } => {
+                let mut query = form_urlencoded::Serializer::new(format!(
+                    "/api/principal/{}/messages?",
+                    form_urlencoded::byte_serialize(account.as_bytes()).collect::<String>()
+                ));
+                query.append_pair(
+                    "format",
+                    match format {
+                        BulkFormat::Mbox => "mbox",
+                        BulkFormat::Zip => "zip",
+                    },
+                );
+                if let Some(mailbox) = &mailbox {
+                    query.append_pair("mailbox", mailbox);
+                }
+
+                eprintln!("Uploading {path}...");
+                let results = client
+                    .try_http_request_raw::<Vec<BulkImportResult>>(
+                        Method::POST,
+                        &query.finish(),
+                        Some(read_file(&path)),
+                    )
+                    .await
+                    .unwrap_or_else(|| {
+                        eprintln!("Account {account} does not exist.");
+                        std::process::exit(1);
+                    });
+
+                let mut success_count = 0;
+                for result in results {
+                    match result {
+                        BulkImportResult::Success { .. } => {
+                            success_count += 1;
+                        }
+                        BulkImportResult::Error { name, reason } => {
+                            eprintln!("Failed to import message {name}: {reason}");
+                        }
+                    }
+                }
+                eprintln!("Successfully imported {success_count} message(s).");
+            }
             ImportCommands::Account {
                 num_concurrent,
                 account,
                 path,
             } => {
+                let mut client = client.into_jmap_client().await;
                 client.set_default_account_id(name_to_id(&client, &account).await);
                 let path = PathBuf::from(path);
                 if !path.exists() {
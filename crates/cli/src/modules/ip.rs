@@ -0,0 +1,147 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use mail_parser::DateTime;
+use prettytable::{Attr, Cell, Row, Table};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::modules::List;
+
+use super::cli::{Client, IpCommands, IpList};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct IpListEntry {
+    address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<u64>,
+}
+
+impl IpCommands {
+    pub async fn exec(self, client: Client) {
+        match self {
+            IpCommands::List { list } => {
+                let entries = client
+                    .http_request::<List<IpListEntry>, String>(
+                        Method::GET,
+                        &format!("/api/ip/{}", list.id()),
+                        None,
+                    )
+                    .await;
+
+                if !entries.items.is_empty() {
+                    let mut table = Table::new();
+                    table.add_row(Row::new(vec![
+                        Cell::new("Address").with_style(Attr::Bold),
+                        Cell::new("Comment").with_style(Attr::Bold),
+                        Cell::new("Expires").with_style(Attr::Bold),
+                    ]));
+
+                    for entry in &entries.items {
+                        table.add_row(Row::new(vec![
+                            Cell::new(&entry.address),
+                            Cell::new(entry.comment.as_deref().unwrap_or_default()),
+                            Cell::new(
+                                &entry
+                                    .expires
+                                    .map(|expires| {
+                                        DateTime::from_timestamp(expires as i64).to_rfc822()
+                                    })
+                                    .unwrap_or_else(|| "Never".to_string()),
+                            ),
+                        ]));
+                    }
+
+                    eprintln!();
+                    table.printstd();
+                    eprintln!();
+                }
+
+                eprintln!(
+                    "\n\n{} entr{} found.\n",
+                    entries.total,
+                    if entries.total == 1 { "y" } else { "ies" }
+                );
+            }
+            IpCommands::Add {
+                list,
+                addresses,
+                comment,
+                expires,
+            } => {
+                let total = addresses.len();
+                client
+                    .http_request::<Value, _>(
+                        Method::POST,
+                        &format!("/api/ip/{}", list.id()),
+                        Some(
+                            addresses
+                                .into_iter()
+                                .map(|address| IpListEntry {
+                                    address,
+                                    comment: comment.clone(),
+                                    expires: expires
+                                        .as_ref()
+                                        .map(|expires| expires.to_timestamp() as u64),
+                                })
+                                .collect::<Vec<_>>(),
+                        ),
+                    )
+                    .await;
+                eprintln!(
+                    "Successfully added {total} entr{} to the {} list.",
+                    if total == 1 { "y" } else { "ies" },
+                    list.id()
+                );
+            }
+            IpCommands::Remove { list, addresses } => {
+                for address in addresses {
+                    client
+                        .http_request::<Value, String>(
+                            Method::DELETE,
+                            &format!(
+                                "/api/ip/{}/{}",
+                                list.id(),
+                                form_urlencoded::byte_serialize(address.as_bytes())
+                                    .collect::<String>()
+                            ),
+                            None,
+                        )
+                        .await;
+                    eprintln!("Successfully removed {address:?}.");
+                }
+            }
+            IpCommands::ImportFeed { id } => {
+                let total = client
+                    .http_request::<usize, String>(
+                        Method::POST,
+                        &format!(
+                            "/api/ip/feed/{}",
+                            form_urlencoded::byte_serialize(id.as_bytes()).collect::<String>()
+                        ),
+                        None,
+                    )
+                    .await;
+                eprintln!(
+                    "Successfully imported {total} entr{} from feed {id:?}.",
+                    if total == 1 { "y" } else { "ies" }
+                );
+            }
+        }
+    }
+}
+
+impl IpList {
+    fn id(&self) -> &'static str {
+        match self {
+            IpList::Blocked => "blocked",
+            IpList::Allowed => "allowed",
+        }
+    }
+}
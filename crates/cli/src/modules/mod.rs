@@ -17,8 +17,10 @@ pub mod cli;
 pub mod database;
 pub mod domain;
 pub mod export;
+pub mod generate;
 pub mod group;
 pub mod import;
+pub mod ip;
 pub mod list;
 pub mod queue;
 pub mod report;
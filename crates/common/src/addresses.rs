@@ -6,7 +6,11 @@
 
 use std::borrow::Cow;
 
-use directory::Directory;
+use directory::{
+    backend::internal::{manage::ManageDirectory, PrincipalField},
+    Directory, Type,
+};
+use trc::AddContext;
 use utils::config::{utils::AsKey, Config};
 
 use crate::{
@@ -33,11 +37,27 @@ impl Core {
             .await;
 
         for _ in 0..2 {
-            let result = directory.email_to_ids(address.as_ref()).await?;
+            let mut result = directory.email_to_ids(address.as_ref()).await?;
 
             if !result.is_empty() {
                 return Ok(result);
-            } else if let Some(catch_all) = self
+            }
+
+            // Route role addresses to the configured accounts
+            if let Some(targets) = self.smtp.session.rcpt.roles.targets(address.as_ref()) {
+                for target in targets {
+                    for id in directory.email_to_ids(target).await? {
+                        if !result.contains(&id) {
+                            result.push(id);
+                        }
+                    }
+                }
+                if !result.is_empty() {
+                    return Ok(result);
+                }
+            }
+
+            if let Some(catch_all) = self
                 .smtp
                 .session
                 .rcpt
@@ -72,7 +92,18 @@ impl Core {
         for _ in 0..2 {
             if directory.rcpt(address.as_ref()).await? {
                 return Ok(true);
-            } else if let Some(catch_all) = self
+            }
+
+            // Route role addresses to the configured accounts
+            if let Some(targets) = self.smtp.session.rcpt.roles.targets(address.as_ref()) {
+                for target in targets {
+                    if directory.rcpt(target).await? {
+                        return Ok(true);
+                    }
+                }
+            }
+
+            if let Some(catch_all) = self
                 .smtp
                 .session
                 .rcpt
@@ -89,6 +120,57 @@ impl Core {
         Ok(false)
     }
 
+    // Rewrites the envelope sender of a message forwarded to external recipients
+    // using SRS, returns None if the sender is local or no rewriting is needed
+    pub async fn srs_forward<'x>(
+        &self,
+        sender: &str,
+        recipient_domains: impl IntoIterator<Item = &'x str>,
+        domain: &str,
+    ) -> trc::Result<Option<String>> {
+        let srs = &self.smtp.mail_auth.srs;
+        let directory = &self.storage.directory;
+        match sender.rsplit_once('@') {
+            Some((_, sender_domain))
+                if srs.is_enabled()
+                    && !directory
+                        .is_local_domain(&sender_domain.to_lowercase())
+                        .await? => {}
+            _ => return Ok(None),
+        }
+
+        for rcpt_domain in recipient_domains {
+            if !directory.is_local_domain(rcpt_domain).await? {
+                return Ok(srs.forward(sender, domain));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub async fn missing_role_addresses(&self) -> trc::Result<Vec<String>> {
+        let directory = &self.storage.directory;
+        let mut missing = Vec::new();
+
+        for domain in self
+            .storage
+            .data
+            .list_principals(None, None, &[Type::Domain], &[PrincipalField::Name], 0, 0)
+            .await
+            .caused_by(trc::location!())?
+            .items
+        {
+            for role in &self.smtp.session.rcpt.roles.required {
+                let address = format!("{role}@{}", domain.name());
+                if !self.rcpt(directory, &address, 0).await? {
+                    missing.push(address);
+                }
+            }
+        }
+
+        Ok(missing)
+    }
+
     pub async fn vrfy(
         &self,
         directory: &Directory,
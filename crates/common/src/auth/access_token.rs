@@ -14,7 +14,7 @@ use jmap_proto::{
 };
 use std::{
     hash::{DefaultHasher, Hash, Hasher},
-    sync::Arc,
+    sync::{atomic::Ordering, Arc},
     time::Instant,
 };
 use store::query::acl::AclQuery;
@@ -171,10 +171,12 @@ impl Core {
                     if acl.contains(Acl::Read) || acl.contains(Acl::Administer) {
                         collections.insert(collection);
                     }
-                    if collection == Collection::Mailbox
-                        && (acl.contains(Acl::ReadItems) || acl.contains(Acl::Administer))
-                    {
-                        collections.insert(Collection::Email);
+                    if acl.contains(Acl::ReadItems) || acl.contains(Acl::Administer) {
+                        match collection {
+                            Collection::Mailbox => collections.insert(Collection::Email),
+                            Collection::AddressBook => collections.insert(Collection::ContactCard),
+                            _ => (),
+                        }
                     }
 
                     if !collections.is_empty() {
@@ -198,6 +200,24 @@ impl Core {
         );
     }
 
+    // Expires the cached access tokens of the given accounts, cluster peers
+    // are notified through gossip and flush their access token caches.
+    pub fn invalidate_access_tokens(&self, account_ids: impl IntoIterator<Item = u32>) {
+        for account_id in account_ids {
+            self.security.access_tokens.remove(&account_id);
+        }
+        self.security
+            .access_tokens_version
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn invalidate_all_access_tokens(&self) {
+        self.security.access_tokens.clear();
+        self.security
+            .access_tokens_version
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
     pub async fn get_cached_access_token(&self, primary_id: u32) -> trc::Result<Arc<AccessToken>> {
         if let Some(access_token) = self.security.access_tokens.get_with_ttl(&primary_id) {
             Ok(access_token)
@@ -366,6 +386,22 @@ impl AccessToken {
                     Permission::JmapPrincipalGet
                 }
                 jmap_proto::method::get::RequestArguments::Quota => Permission::JmapQuotaGet,
+                jmap_proto::method::get::RequestArguments::Activity => Permission::JmapActivityGet,
+                jmap_proto::method::get::RequestArguments::DeletedEmail => {
+                    Permission::JmapDeletedEmailGet
+                }
+                jmap_proto::method::get::RequestArguments::Calendar => Permission::JmapCalendarGet,
+                jmap_proto::method::get::RequestArguments::CalendarEvent => {
+                    Permission::JmapCalendarEventGet
+                }
+                jmap_proto::method::get::RequestArguments::AddressBook => {
+                    Permission::JmapAddressBookGet
+                }
+                jmap_proto::method::get::RequestArguments::ContactCard => {
+                    Permission::JmapContactCardGet
+                }
+                jmap_proto::method::get::RequestArguments::TaskList => Permission::JmapTaskListGet,
+                jmap_proto::method::get::RequestArguments::Task => Permission::JmapTaskGet,
                 jmap_proto::method::get::RequestArguments::Blob(_) => Permission::JmapBlobGet,
             },
             RequestMethod::Set(m) => match &m.arguments {
@@ -384,6 +420,22 @@ impl AccessToken {
                 jmap_proto::method::set::RequestArguments::VacationResponse => {
                     Permission::JmapVacationResponseSet
                 }
+                jmap_proto::method::set::RequestArguments::Calendar(_) => {
+                    Permission::JmapCalendarSet
+                }
+                jmap_proto::method::set::RequestArguments::CalendarEvent => {
+                    Permission::JmapCalendarEventSet
+                }
+                jmap_proto::method::set::RequestArguments::AddressBook(_) => {
+                    Permission::JmapAddressBookSet
+                }
+                jmap_proto::method::set::RequestArguments::ContactCard => {
+                    Permission::JmapContactCardSet
+                }
+                jmap_proto::method::set::RequestArguments::TaskList(_) => {
+                    Permission::JmapTaskListSet
+                }
+                jmap_proto::method::set::RequestArguments::Task => Permission::JmapTaskSet,
             },
             RequestMethod::Changes(m) => match m.arguments {
                 jmap_proto::method::changes::RequestArguments::Email => {
@@ -404,6 +456,28 @@ impl AccessToken {
                 jmap_proto::method::changes::RequestArguments::Quota => {
                     Permission::JmapQuotaChanges
                 }
+                jmap_proto::method::changes::RequestArguments::Activity => {
+                    Permission::JmapActivityChanges
+                }
+                jmap_proto::method::changes::RequestArguments::Calendar => {
+                    Permission::JmapCalendarChanges
+                }
+                jmap_proto::method::changes::RequestArguments::CalendarEvent => {
+                    Permission::JmapCalendarEventChanges
+                }
+                jmap_proto::method::changes::RequestArguments::AddressBook => {
+                    Permission::JmapAddressBookChanges
+                }
+                jmap_proto::method::changes::RequestArguments::ContactCard => {
+                    Permission::JmapContactCardChanges
+                }
+                jmap_proto::method::changes::RequestArguments::TaskList => {
+                    Permission::JmapTaskListChanges
+                }
+                jmap_proto::method::changes::RequestArguments::Task => Permission::JmapTaskChanges,
+                jmap_proto::method::changes::RequestArguments::SieveScript => {
+                    Permission::JmapSieveScriptChanges
+                }
             },
             RequestMethod::Copy(m) => match m.arguments {
                 jmap_proto::method::copy::RequestArguments::Email => Permission::JmapEmailCopy,
@@ -411,6 +485,10 @@ impl AccessToken {
             RequestMethod::CopyBlob(_) => Permission::JmapBlobCopy,
             RequestMethod::ImportEmail(_) => Permission::JmapEmailImport,
             RequestMethod::ParseEmail(_) => Permission::JmapEmailParse,
+            RequestMethod::ParseContactCard(_) => Permission::JmapContactCardParse,
+            RequestMethod::SendMdn(_) => Permission::JmapMdnSend,
+            RequestMethod::ParseMdn(_) => Permission::JmapMdnParse,
+            RequestMethod::Restore(_) => Permission::JmapDeletedEmailRestore,
             RequestMethod::QueryChanges(m) => match m.arguments {
                 jmap_proto::method::query::RequestArguments::Email(_) => {
                     Permission::JmapEmailQueryChanges
@@ -430,6 +508,15 @@ impl AccessToken {
                 jmap_proto::method::query::RequestArguments::Quota => {
                     Permission::JmapQuotaQueryChanges
                 }
+                jmap_proto::method::query::RequestArguments::CalendarEvent => {
+                    Permission::JmapCalendarEventQueryChanges
+                }
+                jmap_proto::method::query::RequestArguments::ContactCard => {
+                    Permission::JmapContactCardQueryChanges
+                }
+                jmap_proto::method::query::RequestArguments::Task => {
+                    Permission::JmapTaskQueryChanges
+                }
             },
             RequestMethod::Query(m) => match m.arguments {
                 jmap_proto::method::query::RequestArguments::Email(_) => Permission::JmapEmailQuery,
@@ -446,9 +533,17 @@ impl AccessToken {
                     Permission::JmapPrincipalQuery
                 }
                 jmap_proto::method::query::RequestArguments::Quota => Permission::JmapQuotaQuery,
+                jmap_proto::method::query::RequestArguments::CalendarEvent => {
+                    Permission::JmapCalendarEventQuery
+                }
+                jmap_proto::method::query::RequestArguments::ContactCard => {
+                    Permission::JmapContactCardQuery
+                }
+                jmap_proto::method::query::RequestArguments::Task => Permission::JmapTaskQuery,
             },
             RequestMethod::SearchSnippet(_) => Permission::JmapSearchSnippet,
             RequestMethod::ValidateScript(_) => Permission::JmapSieveScriptValidate,
+            RequestMethod::GetAvailability(_) => Permission::JmapPrincipalGetAvailability,
             RequestMethod::LookupBlob(_) => Permission::JmapBlobLookup,
             RequestMethod::UploadBlob(_) => Permission::JmapBlobUpload,
             RequestMethod::Echo(_) => Permission::JmapEcho,
@@ -0,0 +1,385 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU8, Ordering},
+    time::Duration,
+};
+
+use trc::StoreEvent;
+use utils::config::Config;
+
+use super::health::HealthAlert;
+
+// Last status reported by the watchdog, kept across configuration reloads
+static DISK_STATUS: AtomicU8 = AtomicU8::new(0);
+
+#[derive(Clone)]
+pub struct DiskWatchdog {
+    pub enable: bool,
+    pub interval: Duration,
+    pub paths: Vec<PathBuf>,
+    pub thresholds: Vec<DiskThreshold>,
+    pub alert: Option<HealthAlert>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiskThreshold {
+    pub status: DiskStatus,
+    pub free_percent: Option<f64>,
+    pub free_space: Option<u64>,
+    pub max_message_size: Option<usize>,
+    pub defer_inbound: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiskStatus {
+    #[default]
+    Normal = 0,
+    Low = 1,
+    Critical = 2,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub total: u64,
+    pub available: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskTransition {
+    pub from: DiskStatus,
+    pub to: DiskStatus,
+    pub path: PathBuf,
+    pub usage: DiskUsage,
+}
+
+impl DiskWatchdog {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut thresholds = Vec::with_capacity(2);
+        for (status, key, free_percent, max_message_size, defer_inbound) in [
+            (DiskStatus::Low, "low", "10", "10485760", "false"),
+            (DiskStatus::Critical, "critical", "2", "false", "true"),
+        ] {
+            thresholds.push(DiskThreshold {
+                status,
+                free_percent: config
+                    .property_or_default::<Option<f64>>(
+                        ("storage.disk", key, "free-percent"),
+                        free_percent,
+                    )
+                    .unwrap_or_default(),
+                free_space: config
+                    .property_or_default::<Option<u64>>(
+                        ("storage.disk", key, "free-space"),
+                        "false",
+                    )
+                    .unwrap_or_default(),
+                max_message_size: config
+                    .property_or_default::<Option<usize>>(
+                        ("storage.disk", key, "max-message-size"),
+                        max_message_size,
+                    )
+                    .unwrap_or_default(),
+                defer_inbound: config
+                    .property_or_default(("storage.disk", key, "defer-inbound"), defer_inbound)
+                    .unwrap_or_default(),
+            });
+        }
+
+        // Monitor the volumes used by local stores unless paths are configured
+        let mut paths = config
+            .values("storage.disk.paths")
+            .map(|(_, path)| PathBuf::from(path.trim()))
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            for store_id in config
+                .sub_keys("store", ".type")
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+            {
+                if let Some(path) = config.value(("store", store_id.as_str(), "path")) {
+                    let path = PathBuf::from(path);
+                    if !paths.contains(&path) {
+                        paths.push(path);
+                    }
+                }
+            }
+        }
+
+        DiskWatchdog {
+            enable: config
+                .property_or_default("storage.disk.enable", "true")
+                .unwrap_or(true),
+            interval: config
+                .property_or_default("storage.disk.interval", "1m")
+                .unwrap_or_else(|| Duration::from_secs(60)),
+            paths,
+            thresholds,
+            alert: HealthAlert::parse(config, "storage.disk.alert"),
+        }
+    }
+
+    // Returns true if new inbound messages should be temporarily rejected
+    pub fn defer_inbound(&self) -> bool {
+        self.current_threshold()
+            .is_some_and(|threshold| threshold.defer_inbound)
+    }
+
+    // Returns the maximum size of inbound messages while disk space is low
+    pub fn max_message_size(&self) -> Option<usize> {
+        self.current_threshold()
+            .and_then(|threshold| threshold.max_message_size)
+    }
+
+    fn current_threshold(&self) -> Option<&DiskThreshold> {
+        let status = DiskStatus::current();
+        if status != DiskStatus::Normal {
+            self.thresholds
+                .iter()
+                .find(|threshold| threshold.status == status)
+        } else {
+            None
+        }
+    }
+
+    pub fn check(&self) -> Option<DiskTransition> {
+        let mut usage = Vec::with_capacity(self.paths.len());
+        for path in &self.paths {
+            match DiskUsage::read(path) {
+                Ok(path_usage) => {
+                    usage.push((path.as_path(), path_usage));
+                }
+                Err(err) => {
+                    trc::event!(
+                        Store(StoreEvent::FilesystemError),
+                        Path = path.to_string_lossy().into_owned(),
+                        Reason = err.to_string(),
+                    );
+                }
+            }
+        }
+
+        self.evaluate(&usage)
+    }
+
+    // Sets the status of the most constrained volume, a transition is
+    // returned when it differs from the previous check.
+    pub fn evaluate(&self, usage: &[(&Path, DiskUsage)]) -> Option<DiskTransition> {
+        let mut new_status = DiskStatus::Normal;
+        let mut worst = usage.first().copied();
+        for threshold in &self.thresholds {
+            if threshold.status > new_status {
+                if let Some(item) = usage.iter().find(|(_, usage)| threshold.is_exceeded(usage)) {
+                    new_status = threshold.status;
+                    worst = Some(*item);
+                }
+            }
+        }
+
+        let (path, usage) = worst?;
+        let from = DiskStatus::current();
+        if new_status != from {
+            new_status.set();
+
+            Some(DiskTransition {
+                from,
+                to: new_status,
+                path: path.to_path_buf(),
+                usage,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for DiskWatchdog {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            interval: Duration::from_secs(60),
+            paths: vec![],
+            thresholds: vec![],
+            alert: None,
+        }
+    }
+}
+
+impl DiskThreshold {
+    fn is_exceeded(&self, usage: &DiskUsage) -> bool {
+        self.free_percent
+            .is_some_and(|free_percent| usage.free_percent() < free_percent)
+            || self
+                .free_space
+                .is_some_and(|free_space| usage.available < free_space)
+    }
+}
+
+impl DiskStatus {
+    pub fn current() -> Self {
+        match DISK_STATUS.load(Ordering::Relaxed) {
+            1 => DiskStatus::Low,
+            2 => DiskStatus::Critical,
+            _ => DiskStatus::Normal,
+        }
+    }
+
+    pub fn set(self) {
+        DISK_STATUS.store(self as u8, Ordering::Relaxed);
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiskStatus::Normal => "normal",
+            DiskStatus::Low => "low",
+            DiskStatus::Critical => "critical",
+        }
+    }
+}
+
+impl DiskUsage {
+    #[cfg(unix)]
+    #[allow(clippy::useless_conversion)]
+    pub fn read(path: &Path) -> std::io::Result<Self> {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } == 0 {
+            let block_size = u64::from(stat.f_frsize);
+            Ok(DiskUsage {
+                total: u64::from(stat.f_blocks) * block_size,
+                available: u64::from(stat.f_bavail) * block_size,
+            })
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn read(_path: &Path) -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Disk usage is not available on this platform",
+        ))
+    }
+
+    pub fn free_percent(&self) -> f64 {
+        if self.total > 0 {
+            self.available as f64 * 100.0 / self.total as f64
+        } else {
+            100.0
+        }
+    }
+}
+
+impl DiskTransition {
+    pub fn event_type(&self) -> StoreEvent {
+        match self.to {
+            DiskStatus::Normal => StoreEvent::DiskSpaceRecovered,
+            DiskStatus::Low => StoreEvent::DiskSpaceLow,
+            DiskStatus::Critical => StoreEvent::DiskSpaceCritical,
+        }
+    }
+
+    pub fn build_alert(&self, alert: &HealthAlert) -> Vec<u8> {
+        alert.build(
+            format!(
+                "Disk space changed from {} to {}",
+                self.from.as_str(),
+                self.to.as_str()
+            ),
+            format!(
+                concat!(
+                    "The disk space status changed from {} to {}.\r\n\r\n",
+                    "Path: {}\r\n",
+                    "Available: {} MB ({:.1}%)\r\n",
+                    "Total: {} MB\r\n"
+                ),
+                self.from.as_str(),
+                self.to.as_str(),
+                self.path.display(),
+                self.usage.available / (1024 * 1024),
+                self.usage.free_percent(),
+                self.usage.total / (1024 * 1024)
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use utils::config::Config;
+
+    use super::{DiskStatus, DiskUsage, DiskWatchdog};
+
+    #[test]
+    fn disk_watchdog() {
+        let mut config = Config::new(
+            r#"
+[storage.disk]
+paths = ["/var/lib/data", "/var/lib/blobs"]
+
+[storage.disk.low]
+free-percent = 20
+max-message-size = 1024
+
+[storage.disk.critical]
+free-percent = false
+free-space = 1000
+"#,
+        )
+        .unwrap();
+        let config = DiskWatchdog::parse(&mut config);
+        let data = Path::new("/var/lib/data");
+        let blobs = Path::new("/var/lib/blobs");
+        let usage = |available: u64| DiskUsage {
+            total: 10000,
+            available,
+        };
+        assert_eq!(config.paths, [data, blobs]);
+
+        // Plenty of free space
+        assert_eq!(
+            config.evaluate(&[(data, usage(5000)), (blobs, usage(9000))]),
+            None
+        );
+        assert!(!config.defer_inbound());
+        assert_eq!(config.max_message_size(), None);
+
+        // Low disk space rejects large messages
+        let transition = config
+            .evaluate(&[(data, usage(5000)), (blobs, usage(1500))])
+            .unwrap();
+        assert_eq!(transition.from, DiskStatus::Normal);
+        assert_eq!(transition.to, DiskStatus::Low);
+        assert_eq!(transition.path, blobs);
+        assert!(!config.defer_inbound());
+        assert_eq!(config.max_message_size(), Some(1024));
+
+        // Critical disk space defers all inbound messages
+        let transition = config
+            .evaluate(&[(data, usage(500)), (blobs, usage(1500))])
+            .unwrap();
+        assert_eq!(transition.from, DiskStatus::Low);
+        assert_eq!(transition.to, DiskStatus::Critical);
+        assert_eq!(transition.path, data);
+        assert!(config.defer_inbound());
+        assert_eq!(config.max_message_size(), None);
+
+        // Recovery
+        let transition = config
+            .evaluate(&[(data, usage(5000)), (blobs, usage(9000))])
+            .unwrap();
+        assert_eq!(transition.to, DiskStatus::Normal);
+        assert!(!config.defer_inbound());
+    }
+}
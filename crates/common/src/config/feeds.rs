@@ -0,0 +1,585 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ahash::{AHashMap, AHashSet};
+use parking_lot::Mutex;
+use reqwest::{
+    header::{ETAG, IF_NONE_MATCH},
+    StatusCode,
+};
+use store::LookupStore;
+use utils::config::{utils::ParseValue, Config};
+
+use crate::{HttpLimitResponse, USER_AGENT};
+
+#[derive(Clone)]
+pub struct ThreatFeed {
+    pub id: String,
+    pub url: String,
+    pub format: FeedFormat,
+    pub kind: FeedKind,
+    pub store: LookupStore,
+    pub prefix: String,
+    pub frequency: Duration,
+    pub expires: Duration,
+    pub stale_after: Option<Duration>,
+    pub max_size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Text,
+    Csv {
+        column: usize,
+        separator: char,
+        skip_first: bool,
+    },
+    Stix,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedKind {
+    Ip,
+    Domain,
+    Url,
+}
+
+pub enum FeedFetch {
+    NotModified,
+    Updated {
+        entries: AHashSet<String>,
+        etag: Option<String>,
+    },
+}
+
+// Fetch state of each feed, kept across configuration reloads
+#[derive(Default)]
+pub struct FeedStates {
+    states: Mutex<AHashMap<String, FeedState>>,
+}
+
+#[derive(Clone)]
+struct FeedState {
+    etag: Option<String>,
+    entries: Option<Arc<AHashSet<String>>>,
+    last_update: Instant,
+    last_success: Instant,
+}
+
+impl ThreatFeed {
+    pub fn parse_all(
+        config: &mut Config,
+        lookup_stores: &AHashMap<String, LookupStore>,
+        default_store: &LookupStore,
+    ) -> Vec<ThreatFeed> {
+        let mut feeds = Vec::new();
+
+        for id in config
+            .sub_keys("feed", ".url")
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+        {
+            if let Some(feed) = ThreatFeed::parse(config, id, lookup_stores, default_store) {
+                feeds.push(feed);
+            }
+        }
+
+        feeds
+    }
+
+    fn parse(
+        config: &mut Config,
+        id: String,
+        lookup_stores: &AHashMap<String, LookupStore>,
+        default_store: &LookupStore,
+    ) -> Option<ThreatFeed> {
+        let id_ = id.as_str();
+        let url = config.value_require(("feed", id_, "url"))?.to_string();
+        let store = if let Some(store_id) = config.value(("feed", id_, "store")) {
+            if let Some(store) = lookup_stores.get(store_id) {
+                store.clone()
+            } else {
+                let err = format!("Lookup store {store_id:?} not found");
+                config.new_parse_error(("feed", id_, "store"), err);
+                return None;
+            }
+        } else {
+            default_store.clone()
+        };
+        if matches!(store, LookupStore::Memory(_)) {
+            config.new_parse_error(
+                ("feed", id_, "store"),
+                "In-memory lookup stores cannot be used for feeds",
+            );
+            return None;
+        }
+
+        let format = match config.value(("feed", id_, "format")).unwrap_or("text") {
+            "text" | "txt" => FeedFormat::Text,
+            "csv" => FeedFormat::Csv {
+                column: config
+                    .property_or_default(("feed", id_, "csv.column"), "0")
+                    .unwrap_or_default(),
+                separator: config
+                    .value(("feed", id_, "csv.separator"))
+                    .and_then(|s| s.chars().next())
+                    .unwrap_or(','),
+                skip_first: config
+                    .property_or_default(("feed", id_, "csv.skip-first"), "false")
+                    .unwrap_or_default(),
+            },
+            "stix" => FeedFormat::Stix,
+            format => {
+                let err = format!("Invalid feed format {format:?}");
+                config.new_parse_error(("feed", id_, "format"), err);
+                return None;
+            }
+        };
+
+        Some(ThreatFeed {
+            url,
+            format,
+            kind: config.property_require(("feed", id_, "type"))?,
+            store,
+            prefix: config
+                .value(("feed", id_, "prefix"))
+                .unwrap_or_default()
+                .to_string(),
+            frequency: config
+                .property_or_default(("feed", id_, "frequency"), "1h")
+                .unwrap_or_else(|| Duration::from_secs(3600)),
+            expires: config
+                .property_or_default(("feed", id_, "expires"), "2d")
+                .unwrap_or_else(|| Duration::from_secs(2 * 86400)),
+            stale_after: config
+                .property_or_default::<Option<Duration>>(("feed", id_, "stale-after"), "1d")
+                .unwrap_or_default(),
+            max_size: config
+                .property_or_default(("feed", id_, "max-size"), "104857600")
+                .unwrap_or(104857600),
+            id,
+        })
+    }
+
+    // Downloads the feed, returning NotModified when the ETag still matches
+    pub async fn fetch(&self, etag: Option<&str>) -> trc::Result<FeedFetch> {
+        let mut request = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap_or_default()
+            .get(&self.url);
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+
+        let response = request.send().await.map_err(|err| {
+            trc::ResourceEvent::Error
+                .into_err()
+                .reason(err)
+                .ctx(trc::Key::Url, self.url.clone())
+                .details("Failed to fetch feed")
+        })?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(FeedFetch::NotModified);
+        } else if !response.status().is_success() {
+            return Err(trc::ResourceEvent::Error
+                .into_err()
+                .ctx(trc::Key::Url, self.url.clone())
+                .ctx(trc::Key::Code, response.status().as_u16())
+                .details("Failed to fetch feed"));
+        }
+
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.to_string());
+        let bytes = response
+            .bytes_with_limit(self.max_size)
+            .await
+            .map_err(|err| {
+                trc::ResourceEvent::Error
+                    .into_err()
+                    .reason(err)
+                    .ctx(trc::Key::Url, self.url.clone())
+                    .details("Failed to fetch feed")
+            })?
+            .ok_or_else(|| {
+                trc::ResourceEvent::Error
+                    .into_err()
+                    .ctx(trc::Key::Url, self.url.clone())
+                    .details("Feed is too large")
+            })?;
+
+        Ok(FeedFetch::Updated {
+            entries: self.parse_entries(&bytes),
+            etag,
+        })
+    }
+
+    // Writes the entries to the lookup store, entries expire unless they are
+    // refreshed by a later write. When the previously written entries are given
+    // only the differences are applied, returning the number of keys changed.
+    pub async fn apply(
+        &self,
+        entries: &AHashSet<String>,
+        previous: Option<&AHashSet<String>>,
+    ) -> trc::Result<usize> {
+        let mut changes = 0;
+
+        for entry in entries {
+            if !previous.is_some_and(|previous| previous.contains(entry)) {
+                self.store
+                    .key_set(
+                        format!("{}{}", self.prefix, entry).into_bytes(),
+                        vec![],
+                        self.expires.as_secs().into(),
+                    )
+                    .await?;
+                changes += 1;
+            }
+        }
+
+        for entry in previous.into_iter().flatten() {
+            if !entries.contains(entry) {
+                self.store
+                    .key_delete(format!("{}{}", self.prefix, entry).into_bytes())
+                    .await?;
+                changes += 1;
+            }
+        }
+
+        Ok(changes)
+    }
+
+    pub fn parse_entries(&self, bytes: &[u8]) -> AHashSet<String> {
+        let mut entries = AHashSet::new();
+
+        match self.format {
+            FeedFormat::Text => {
+                for line in String::from_utf8_lossy(bytes).lines() {
+                    let line = line.trim();
+                    if line.starts_with(['#', ';']) || line.starts_with("//") {
+                        continue;
+                    }
+                    let mut value = line.split_ascii_whitespace().next().unwrap_or_default();
+                    if self.kind != FeedKind::Url {
+                        value = value.split(['#', ';']).next().unwrap_or_default();
+                    }
+                    if let Some(entry) = self.kind.normalize(value) {
+                        entries.insert(entry);
+                    }
+                }
+            }
+            FeedFormat::Csv {
+                column,
+                separator,
+                skip_first,
+            } => {
+                for line in String::from_utf8_lossy(bytes)
+                    .lines()
+                    .skip(usize::from(skip_first))
+                {
+                    if line.starts_with('#') {
+                        continue;
+                    }
+                    if let Some(entry) = line
+                        .split(separator)
+                        .nth(column)
+                        .and_then(|value| self.kind.normalize(value.trim().trim_matches('"')))
+                    {
+                        entries.insert(entry);
+                    }
+                }
+            }
+            FeedFormat::Stix => {
+                let bundle = serde_json::from_slice::<serde_json::Value>(bytes).unwrap_or_default();
+                for object in bundle
+                    .get("objects")
+                    .and_then(|objects| objects.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    match object.get("type").and_then(|typ| typ.as_str()) {
+                        Some("indicator") => {
+                            for value in object
+                                .get("pattern")
+                                .and_then(|pattern| pattern.as_str())
+                                .map(|pattern| stix_pattern_values(pattern, self.kind))
+                                .unwrap_or_default()
+                            {
+                                if let Some(entry) = self.kind.normalize(&value) {
+                                    entries.insert(entry);
+                                }
+                            }
+                        }
+                        Some(typ) if self.kind.stix_types().contains(&typ) => {
+                            if let Some(entry) = object
+                                .get("value")
+                                .and_then(|value| value.as_str())
+                                .and_then(|value| self.kind.normalize(value))
+                            {
+                                entries.insert(entry);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        entries
+    }
+}
+
+impl FeedKind {
+    fn normalize(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        match self {
+            FeedKind::Ip => value.parse::<IpAddr>().ok().map(|ip| ip.to_string()),
+            FeedKind::Domain => {
+                let value = value.trim_end_matches('.').to_lowercase();
+                (value.contains('.')
+                    && !value.contains(|ch: char| ch.is_whitespace() || matches!(ch, '/' | '@')))
+                .then_some(value)
+            }
+            FeedKind::Url => (!value.is_empty() && !value.contains(char::is_whitespace))
+                .then(|| value.to_string()),
+        }
+    }
+
+    fn stix_types(&self) -> &'static [&'static str] {
+        match self {
+            FeedKind::Ip => &["ipv4-addr", "ipv6-addr"],
+            FeedKind::Domain => &["domain-name"],
+            FeedKind::Url => &["url"],
+        }
+    }
+}
+
+// Extracts the values compared for equality in a STIX pattern,
+// for example "[ipv4-addr:value = '198.51.100.1']"
+fn stix_pattern_values(pattern: &str, kind: FeedKind) -> Vec<String> {
+    let mut values = Vec::new();
+
+    for (pos, _) in pattern.match_indices(":value") {
+        let typ = pattern[..pos]
+            .rsplit(|ch: char| ch.is_whitespace() || matches!(ch, '[' | '('))
+            .next()
+            .unwrap_or_default();
+        if !kind.stix_types().contains(&typ) {
+            continue;
+        }
+        let Some(value) = pattern[pos + 6..]
+            .trim_start()
+            .strip_prefix('=')
+            .and_then(|value| value.trim_start().strip_prefix('\''))
+        else {
+            continue;
+        };
+
+        let mut result = String::new();
+        let mut chars = value.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => result.extend(chars.next()),
+                '\'' => {
+                    values.push(result);
+                    break;
+                }
+                _ => result.push(ch),
+            }
+        }
+    }
+
+    values
+}
+
+impl ParseValue for FeedKind {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "ip" => Ok(FeedKind::Ip),
+            "domain" => Ok(FeedKind::Domain),
+            "url" => Ok(FeedKind::Url),
+            _ => Err(format!("Invalid feed type {value:?}.")),
+        }
+    }
+}
+
+impl FeedStates {
+    // Downloads a feed and applies the changes since the last download. While
+    // the stored entries are not halfway to expiring the last ETag is sent and
+    // only added or removed entries are written, otherwise every entry is written
+    // again to extend its expiration. Raises an alarm when the feed becomes stale.
+    pub async fn refresh(&self, feed: &ThreatFeed) -> trc::Result<usize> {
+        let state = self
+            .states
+            .lock()
+            .entry(feed.id.clone())
+            .or_insert_with(|| FeedState {
+                etag: None,
+                entries: None,
+                last_update: Instant::now(),
+                last_success: Instant::now(),
+            })
+            .clone();
+        let previous = state
+            .entries
+            .filter(|_| state.last_update.elapsed() < feed.expires / 2);
+        let etag = state.etag.as_deref().filter(|_| previous.is_some());
+
+        let result = match feed.fetch(etag).await {
+            Ok(FeedFetch::Updated { entries, etag }) => {
+                match feed.apply(&entries, previous.as_deref()).await {
+                    Ok(changes) => {
+                        trc::event!(
+                            Resource(trc::ResourceEvent::DownloadExternal),
+                            Id = feed.id.clone(),
+                            Url = feed.url.clone(),
+                            Total = entries.len(),
+                        );
+
+                        self.states.lock().insert(
+                            feed.id.clone(),
+                            FeedState {
+                                etag,
+                                entries: Some(Arc::new(entries)),
+                                last_update: if previous.is_some() {
+                                    state.last_update
+                                } else {
+                                    Instant::now()
+                                },
+                                last_success: Instant::now(),
+                            },
+                        );
+
+                        return Ok(changes);
+                    }
+                    Err(err) => {
+                        // Entries might have been partially written, write them all next time
+                        if let Some(state) = self.states.lock().get_mut(&feed.id) {
+                            state.entries = None;
+                        }
+
+                        err
+                    }
+                }
+            }
+            Ok(FeedFetch::NotModified) => {
+                if let Some(state) = self.states.lock().get_mut(&feed.id) {
+                    state.last_success = Instant::now();
+                }
+
+                return Ok(0);
+            }
+            Err(err) => err,
+        };
+
+        if feed
+            .stale_after
+            .is_some_and(|stale_after| state.last_success.elapsed() > stale_after)
+        {
+            trc::event!(
+                Resource(trc::ResourceEvent::FeedStale),
+                Id = feed.id.clone(),
+                Url = feed.url.clone(),
+                Elapsed = state.last_success.elapsed(),
+            );
+        }
+
+        Err(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use store::LookupStore;
+
+    use super::{FeedFormat, FeedKind, ThreatFeed};
+
+    #[test]
+    fn parse_feed_entries() {
+        let mut feed = ThreatFeed {
+            id: "test".to_string(),
+            url: "https://127.0.0.1/feed".to_string(),
+            format: FeedFormat::Text,
+            kind: FeedKind::Ip,
+            store: LookupStore::default(),
+            prefix: String::new(),
+            frequency: Duration::from_secs(3600),
+            expires: Duration::from_secs(86400),
+            stale_after: None,
+            max_size: 1024,
+        };
+
+        for (format, kind, contents, expected) in [
+            (
+                FeedFormat::Text,
+                FeedKind::Ip,
+                concat!(
+                    "# Comment\n",
+                    "192.0.2.1 ; SBL123\n",
+                    "192.0.2.2;SBL456\n",
+                    "\n",
+                    "not-an-ip\n",
+                    "2001:db8::1\n"
+                ),
+                vec!["192.0.2.1", "192.0.2.2", "2001:db8::1"],
+            ),
+            (
+                FeedFormat::Text,
+                FeedKind::Domain,
+                "Example.COM.\nfoo bar\n# example.net\nexample.org # Comment\n",
+                vec!["example.com", "example.org"],
+            ),
+            (
+                FeedFormat::Csv {
+                    column: 1,
+                    separator: ',',
+                    skip_first: true,
+                },
+                FeedKind::Url,
+                concat!(
+                    "id,url,date\n",
+                    "1,\"https://example.com/phish#a\",2024-01-01\n",
+                    "2,https://example.org/malware,2024-01-01\n"
+                ),
+                vec!["https://example.com/phish#a", "https://example.org/malware"],
+            ),
+            (
+                FeedFormat::Stix,
+                FeedKind::Domain,
+                concat!(
+                    "{\"type\": \"bundle\", \"objects\": [",
+                    "{\"type\": \"indicator\", \"pattern\": \"[domain-name:value = 'evil.example'] ",
+                    "OR [domain-name:value = 'Bad.Example'] OR [ipv4-addr:value = '192.0.2.1']\"},",
+                    "{\"type\": \"domain-name\", \"value\": \"worse.example\"},",
+                    "{\"type\": \"url\", \"value\": \"https://evil.example/\"}",
+                    "]}"
+                ),
+                vec!["bad.example", "evil.example", "worse.example"],
+            ),
+        ] {
+            feed.format = format;
+            feed.kind = kind;
+            let mut entries = feed
+                .parse_entries(contents.as_bytes())
+                .into_iter()
+                .collect::<Vec<_>>();
+            entries.sort();
+            assert_eq!(entries, expected, "failed for {format:?}");
+        }
+    }
+}
@@ -0,0 +1,384 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use mail_builder::{
+    headers::{
+        address::{Address, EmailAddress},
+        HeaderType,
+    },
+    MessageBuilder,
+};
+use parking_lot::Mutex;
+use store::dispatch::health::{HealthSample, HealthStatus, BLOB_HEALTH, DATA_HEALTH};
+use trc::StoreEvent;
+use utils::config::Config;
+
+#[derive(Clone)]
+pub struct HealthCheck {
+    pub enable: bool,
+    pub interval: Duration,
+    pub min_operations: u64,
+    pub thresholds: Vec<HealthThreshold>,
+    pub alert: Option<HealthAlert>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthThreshold {
+    pub status: HealthStatus,
+    pub error_rate: Option<f64>,
+    pub latency: Option<Duration>,
+    pub sustain: u32,
+    pub defer_inbound: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthAlert {
+    pub from_name: Option<String>,
+    pub from_addr: String,
+    pub to: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthTransition {
+    pub from: HealthStatus,
+    pub to: HealthStatus,
+    pub store: &'static str,
+    pub error_rate: f64,
+    pub latency: Duration,
+}
+
+// Samples taken on the previous check, kept across configuration reloads
+#[derive(Default)]
+pub struct HealthMonitor {
+    state: Mutex<MonitorState>,
+}
+
+#[derive(Default)]
+struct MonitorState {
+    data: HealthSample,
+    blob: HealthSample,
+    breaches: [u32; 2],
+    status: HealthStatus,
+}
+
+impl HealthCheck {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut thresholds = Vec::with_capacity(2);
+        for (status, key, error_rate, latency, sustain, defer_inbound) in [
+            (HealthStatus::Degraded, "degraded", "5", "1s", "3", "false"),
+            (HealthStatus::Critical, "critical", "25", "5s", "2", "true"),
+        ] {
+            thresholds.push(HealthThreshold {
+                status,
+                error_rate: config
+                    .property_or_default::<Option<f64>>(
+                        ("storage.health", key, "error-rate"),
+                        error_rate,
+                    )
+                    .unwrap_or_default(),
+                latency: config
+                    .property_or_default::<Option<Duration>>(
+                        ("storage.health", key, "latency"),
+                        latency,
+                    )
+                    .unwrap_or_default(),
+                sustain: config
+                    .property_or_default::<u32>(("storage.health", key, "sustain"), sustain)
+                    .unwrap_or(1)
+                    .max(1),
+                defer_inbound: config
+                    .property_or_default(("storage.health", key, "defer-inbound"), defer_inbound)
+                    .unwrap_or_default(),
+            });
+        }
+
+        let alert = HealthAlert::parse(config, "storage.health.alert");
+
+        HealthCheck {
+            enable: config
+                .property_or_default("storage.health.enable", "true")
+                .unwrap_or(true),
+            interval: config
+                .property_or_default("storage.health.interval", "1m")
+                .unwrap_or_else(|| Duration::from_secs(60)),
+            min_operations: config
+                .property_or_default("storage.health.min-operations", "10")
+                .unwrap_or(10),
+            thresholds,
+            alert,
+        }
+    }
+
+    // Returns true if new inbound messages should be temporarily rejected
+    pub fn defer_inbound(&self) -> bool {
+        let status = HealthStatus::current();
+        status != HealthStatus::Healthy
+            && self
+                .thresholds
+                .iter()
+                .any(|threshold| threshold.status == status && threshold.defer_inbound)
+    }
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            interval: Duration::from_secs(60),
+            min_operations: 10,
+            thresholds: vec![],
+            alert: None,
+        }
+    }
+}
+
+impl HealthThreshold {
+    fn is_exceeded(&self, sample: &HealthSample) -> bool {
+        self.error_rate
+            .is_some_and(|error_rate| sample.error_rate() >= error_rate)
+            || self
+                .latency
+                .is_some_and(|latency| sample.avg_latency() >= latency)
+    }
+}
+
+impl HealthMonitor {
+    pub fn check(&self, config: &HealthCheck) -> Option<HealthTransition> {
+        self.evaluate(config, DATA_HEALTH.sample(), BLOB_HEALTH.sample())
+    }
+
+    // Compares the operations recorded since the last check against the configured
+    // thresholds, a status change requires the threshold to be exceeded for
+    // a number of consecutive checks while recovery is immediate.
+    pub fn evaluate(
+        &self,
+        config: &HealthCheck,
+        data: HealthSample,
+        blob: HealthSample,
+    ) -> Option<HealthTransition> {
+        let mut state = self.state.lock();
+        let samples = [
+            ("data", data.since(&state.data)),
+            ("blob", blob.since(&state.blob)),
+        ];
+        state.data = data;
+        state.blob = blob;
+
+        let mut new_status = HealthStatus::Healthy;
+        let mut worst = samples[0];
+        for threshold in &config.thresholds {
+            let idx = threshold.status as usize - 1;
+            if let Some(sample) = samples.iter().find(|(_, sample)| {
+                sample.operations >= config.min_operations && threshold.is_exceeded(sample)
+            }) {
+                state.breaches[idx] += 1;
+                if state.breaches[idx] >= threshold.sustain && threshold.status > new_status {
+                    new_status = threshold.status;
+                    worst = *sample;
+                }
+            } else {
+                state.breaches[idx] = 0;
+            }
+        }
+
+        if new_status != state.status {
+            let from = std::mem::replace(&mut state.status, new_status);
+            new_status.set();
+
+            Some(HealthTransition {
+                from,
+                to: new_status,
+                store: worst.0,
+                error_rate: worst.1.error_rate(),
+                latency: worst.1.avg_latency(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl HealthTransition {
+    pub fn event_type(&self) -> StoreEvent {
+        match self.to {
+            HealthStatus::Healthy => StoreEvent::HealthRecovered,
+            HealthStatus::Degraded => StoreEvent::HealthDegraded,
+            HealthStatus::Critical => StoreEvent::HealthCritical,
+        }
+    }
+
+    pub fn build_alert(&self, alert: &HealthAlert) -> Vec<u8> {
+        alert.build(
+            format!(
+                "Store health changed from {} to {}",
+                self.from.as_str(),
+                self.to.as_str()
+            ),
+            format!(
+                concat!(
+                    "The storage health status changed from {} to {}.\r\n\r\n",
+                    "Store: {}\r\n",
+                    "Error rate: {:.1}%\r\n",
+                    "Average latency: {} ms\r\n"
+                ),
+                self.from.as_str(),
+                self.to.as_str(),
+                self.store,
+                self.error_rate,
+                self.latency.as_millis()
+            ),
+        )
+    }
+}
+
+impl HealthAlert {
+    pub fn parse(config: &mut Config, prefix: &str) -> Option<Self> {
+        let to = config
+            .values((prefix, "to"))
+            .filter_map(|(_, s)| {
+                if s.contains('@') {
+                    s.trim().to_string().into()
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        if to.is_empty() {
+            return None;
+        }
+
+        let from_addr = config
+            .value((prefix, "from-addr"))
+            .map(|s| s.trim().to_string())
+            .unwrap_or_else(|| "MAILER-DAEMON@localhost".to_string());
+        if !from_addr.contains('@') {
+            config.new_build_error((prefix, "from-addr"), "Invalid from email address");
+        }
+
+        Some(HealthAlert {
+            from_name: config.value((prefix, "from-name")).map(|s| s.to_string()),
+            from_addr,
+            to,
+        })
+    }
+
+    pub fn build(&self, subject: String, body: String) -> Vec<u8> {
+        MessageBuilder::new()
+            .from(Address::Address(EmailAddress {
+                name: self.from_name.as_ref().map(|s| s.into()),
+                email: self.from_addr.as_str().into(),
+            }))
+            .header(
+                "To",
+                HeaderType::Address(Address::List(
+                    self.to
+                        .iter()
+                        .map(|to| {
+                            Address::Address(EmailAddress {
+                                name: None,
+                                email: to.as_str().into(),
+                            })
+                        })
+                        .collect(),
+                )),
+            )
+            .header("Auto-Submitted", HeaderType::Text("auto-generated".into()))
+            .subject(subject)
+            .text_body(body)
+            .write_to_vec()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use store::dispatch::health::{HealthSample, HealthStatus};
+    use utils::config::Config;
+
+    use super::{HealthCheck, HealthMonitor};
+
+    #[test]
+    fn health_monitor() {
+        let mut config = Config::new(
+            r#"
+[storage.health]
+min-operations = 10
+
+[storage.health.degraded]
+error-rate = 10
+latency = "500ms"
+sustain = 2
+
+[storage.health.critical]
+error-rate = 50
+latency = false
+sustain = 1
+"#,
+        )
+        .unwrap();
+        let config = HealthCheck::parse(&mut config);
+        let monitor = HealthMonitor::default();
+        let mut data = HealthSample::default();
+        let blob = HealthSample::default();
+        let mut add = |operations: u64, errors: u64, elapsed: Duration| {
+            data.operations += operations;
+            data.errors += errors;
+            data.elapsed += elapsed.as_micros() as u64 * operations;
+            data
+        };
+
+        // Healthy store
+        assert_eq!(
+            monitor.evaluate(&config, add(100, 0, Duration::from_millis(5)), blob),
+            None
+        );
+
+        // Degraded status requires two consecutive breaches
+        assert_eq!(
+            monitor.evaluate(&config, add(100, 0, Duration::from_secs(1)), blob),
+            None
+        );
+        let transition = monitor
+            .evaluate(&config, add(100, 20, Duration::from_millis(5)), blob)
+            .unwrap();
+        assert_eq!(transition.from, HealthStatus::Healthy);
+        assert_eq!(transition.to, HealthStatus::Degraded);
+        assert_eq!(transition.store, "data");
+        assert_eq!(transition.error_rate, 20.0);
+        assert!(!config.defer_inbound());
+
+        // Too few operations are not evaluated
+        assert_eq!(
+            monitor.evaluate(&config, add(5, 5, Duration::from_millis(5)), blob),
+            Some(super::HealthTransition {
+                from: HealthStatus::Degraded,
+                to: HealthStatus::Healthy,
+                store: "data",
+                error_rate: 100.0,
+                latency: Duration::from_millis(5),
+            })
+        );
+
+        // Critical status defers inbound messages
+        let transition = monitor
+            .evaluate(&config, add(10, 6, Duration::from_millis(5)), blob)
+            .unwrap();
+        assert_eq!(transition.to, HealthStatus::Critical);
+        assert!(config.defer_inbound());
+
+        // Recovery
+        let transition = monitor
+            .evaluate(&config, add(10, 0, Duration::from_millis(5)), blob)
+            .unwrap();
+        assert_eq!(transition.from, HealthStatus::Critical);
+        assert_eq!(transition.to, HealthStatus::Healthy);
+        assert!(!config.defer_inbound());
+    }
+}
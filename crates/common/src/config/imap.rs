@@ -8,11 +8,20 @@ use std::time::Duration;
 
 use utils::config::{Config, Rate};
 
-#[derive(Default, Clone)]
+use crate::{
+    expr::{
+        functions::ResolveVariable, if_block::IfBlock, tokenizer::TokenMap, Variable,
+        V_AUTHENTICATED_AS,
+    },
+    Core,
+};
+
+#[derive(Clone)]
 pub struct ImapConfig {
     pub max_request_size: usize,
     pub max_auth_failures: u32,
     pub allow_plain_auth: bool,
+    pub gmail_extensions: bool,
 
     pub timeout_auth: Duration,
     pub timeout_unauth: Duration,
@@ -20,6 +29,8 @@ pub struct ImapConfig {
 
     pub rate_requests: Option<Rate>,
     pub rate_concurrent: Option<u64>,
+
+    pub pop3_expire: IfBlock,
 }
 
 impl ImapConfig {
@@ -49,6 +60,73 @@ impl ImapConfig {
             allow_plain_auth: config
                 .property_or_default("imap.auth.allow-plain-text", "false")
                 .unwrap_or(false),
+            gmail_extensions: config
+                .property_or_default("imap.extensions.gmail", "false")
+                .unwrap_or(false),
+            pop3_expire: IfBlock::try_parse(
+                config,
+                "pop3.expire",
+                &TokenMap::default().with_variables(&[V_AUTHENTICATED_AS]),
+            )
+            .unwrap_or_else(|| IfBlock::empty("pop3.expire")),
+        }
+    }
+}
+
+impl Default for ImapConfig {
+    fn default() -> Self {
+        Self {
+            max_request_size: Default::default(),
+            max_auth_failures: Default::default(),
+            allow_plain_auth: Default::default(),
+            gmail_extensions: Default::default(),
+            timeout_auth: Default::default(),
+            timeout_unauth: Default::default(),
+            timeout_idle: Default::default(),
+            rate_requests: Default::default(),
+            rate_concurrent: Default::default(),
+            pop3_expire: IfBlock::empty("pop3.expire"),
+        }
+    }
+}
+
+struct AccountName<'x>(&'x str);
+
+impl ResolveVariable for AccountName<'_> {
+    fn resolve_variable(&self, variable: u32) -> Variable<'_> {
+        match variable {
+            V_AUTHENTICATED_AS => Variable::from(self.0),
+            _ => Variable::default(),
         }
     }
 }
+
+impl Core {
+    // Returns the POP3 expiration period of an account or the server default,
+    // the account name is only resolved when the policy depends on it.
+    pub async fn pop3_expire(&self, account_id: Option<u32>, session_id: u64) -> Option<Duration> {
+        let if_block = &self.imap.pop3_expire;
+        let account_name = match account_id {
+            Some(account_id) if self.pop3_expire_per_account() => {
+                match self.get_cached_access_token(account_id).await {
+                    Ok(access_token) => access_token.name.clone(),
+                    Err(err) => {
+                        trc::error!(err
+                            .account_id(account_id)
+                            .details("Failed to obtain access token."));
+                        return None;
+                    }
+                }
+            }
+            _ => String::new(),
+        };
+
+        self.eval_if::<Duration, _>(if_block, &AccountName(&account_name), session_id)
+            .await
+    }
+
+    // Whether the POP3 expiration period depends on the account
+    pub fn pop3_expire_per_account(&self) -> bool {
+        !self.imap.pop3_expire.if_then.is_empty()
+    }
+}
@@ -8,13 +8,15 @@ use ahash::AHashSet;
 use jmap_proto::{
     request::capability::{
         BlobCapabilities, Capabilities, Capability, CoreCapabilities, EmptyCapabilities,
-        MailCapabilities, SieveAccountCapabilities, SieveSessionCapabilities,
-        SubmissionCapabilities,
+        MailCapabilities, PrincipalCapabilities, SieveAccountCapabilities,
+        SieveSessionCapabilities, SubmissionCapabilities, WebPushVapidCapabilities,
     },
     types::type_state::DataType,
 };
 use utils::{config::Config, map::vec_map::VecMap};
 
+use crate::config::scripts::{SIEVE_ADDRBOOK_DEFAULT_LIST, SIEVE_ADDRBOOK_LIST};
+
 use super::settings::JmapConfig;
 
 impl JmapConfig {
@@ -69,6 +71,17 @@ impl JmapConfig {
         );
 
         // Add submission capabilities
+        let max_delayed_send = self.mail_max_delayed_send.as_secs() as usize;
+        let mut submission_extensions = VecMap::from_iter([
+            ("SIZE".to_string(), Vec::new()),
+            ("DSN".to_string(), Vec::new()),
+            ("DELIVERYBY".to_string(), Vec::new()),
+            ("MT-PRIORITY".to_string(), vec!["MIXER".to_string()]),
+            ("REQUIRETLS".to_string(), vec![]),
+        ]);
+        if max_delayed_send > 0 {
+            submission_extensions.append("FUTURERELEASE".to_string(), Vec::new());
+        }
         self.capabilities.session.append(
             Capability::Submission,
             Capabilities::Empty(EmptyCapabilities::default()),
@@ -76,15 +89,8 @@ impl JmapConfig {
         self.capabilities.account.append(
             Capability::Submission,
             Capabilities::Submission(SubmissionCapabilities {
-                max_delayed_send: 86400 * 30,
-                submission_extensions: VecMap::from_iter([
-                    ("FUTURERELEASE".to_string(), Vec::new()),
-                    ("SIZE".to_string(), Vec::new()),
-                    ("DSN".to_string(), Vec::new()),
-                    ("DELIVERYBY".to_string(), Vec::new()),
-                    ("MT-PRIORITY".to_string(), vec!["MIXER".to_string()]),
-                    ("REQUIRETLS".to_string(), vec![]),
-                ]),
+                max_delayed_send,
+                submission_extensions,
             }),
         );
 
@@ -98,6 +104,66 @@ impl JmapConfig {
             Capabilities::Empty(EmptyCapabilities::default()),
         );
 
+        // Add calendar capabilities
+        self.capabilities.session.append(
+            Capability::Calendars,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+        self.capabilities.account.append(
+            Capability::Calendars,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+
+        // Add contacts capabilities
+        self.capabilities.session.append(
+            Capability::Contacts,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+        self.capabilities.account.append(
+            Capability::Contacts,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+
+        // Add tasks capabilities
+        self.capabilities.session.append(
+            Capability::Tasks,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+        self.capabilities.account.append(
+            Capability::Tasks,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+
+        // Add principals capabilities
+        self.capabilities.session.append(
+            Capability::Principals,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+        self.capabilities.account.append(
+            Capability::Principals,
+            Capabilities::Principals(PrincipalCapabilities::default()),
+        );
+
+        // Add MDN capabilities
+        self.capabilities.session.append(
+            Capability::Mdn,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+        self.capabilities.account.append(
+            Capability::Mdn,
+            Capabilities::Empty(EmptyCapabilities::default()),
+        );
+
+        // Add WebPush VAPID capabilities
+        if let Some(vapid) = &self.push_vapid {
+            self.capabilities.session.append(
+                Capability::WebPushVapid,
+                Capabilities::WebPushVapid(WebPushVapidCapabilities {
+                    application_server_key: vapid.public_key.clone(),
+                }),
+            );
+        }
+
         // Add Sieve capabilities
         let mut notification_methods = Vec::new();
 
@@ -142,7 +208,17 @@ impl JmapConfig {
                 } else {
                     None
                 },
-                ext_lists: None,
+                ext_lists: Some(
+                    [SIEVE_ADDRBOOK_LIST, SIEVE_ADDRBOOK_DEFAULT_LIST]
+                        .into_iter()
+                        .map(|list| list.to_string())
+                        .chain(
+                            config
+                                .values("sieve.untrusted.ext-lists")
+                                .map(|(_, list)| list.to_string()),
+                        )
+                        .collect(),
+                ),
             }),
         );
 
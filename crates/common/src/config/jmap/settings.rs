@@ -4,12 +4,21 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{str::FromStr, time::Duration};
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 
 use jmap_proto::request::capability::BaseCapabilities;
 use mail_parser::HeaderName;
 use nlp::language::Language;
-use store::rand::{distributions::Alphanumeric, thread_rng, Rng};
+use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use store::{
+    rand::{distributions::Alphanumeric, thread_rng, Rng},
+    write::now,
+};
 use utils::config::{cron::SimpleCron, utils::ParseValue, Config, Rate};
 
 #[derive(Default, Clone)]
@@ -17,6 +26,7 @@ pub struct JmapConfig {
     pub default_language: Language,
     pub query_max_results: usize,
     pub snippet_max_results: usize,
+    pub snippet_context_length: usize,
 
     pub changes_max_results: usize,
     pub changes_max_history: Option<Duration>,
@@ -40,10 +50,27 @@ pub struct JmapConfig {
     pub mail_attachments_max_size: usize,
     pub mail_parse_max_items: usize,
     pub mail_max_size: usize,
+    pub mail_import_max_size: usize,
     pub mail_autoexpunge_after: Option<Duration>,
+    pub mail_activity_max_entries: usize,
+    pub mail_recycle_retention: Option<Duration>,
+    pub mail_recycle_max_entries: usize,
+    pub mail_recycle_count_quota: bool,
+    pub mail_draft_replace_rate: Option<Rate>,
+    pub mail_undo_send: Option<Duration>,
+    pub mail_max_delayed_send: Duration,
+    pub quota_warn_threshold: u64,
+    pub calendar_imip: bool,
 
     pub sieve_max_script_name: usize,
+    pub sieve_max_script_size: usize,
     pub sieve_max_scripts: usize,
+    pub sieve_max_scripts_size: u64,
+    pub sieve_imapsieve_script: String,
+    pub sieve_imapsieve_max_messages: usize,
+    pub sieve_notify_rate: Option<Rate>,
+    pub sieve_notify_timeout: Duration,
+    pub sieve_notify_allow_private: bool,
 
     pub session_cache_ttl: Duration,
     pub rate_authenticated: Option<Rate>,
@@ -58,6 +85,7 @@ pub struct JmapConfig {
     pub push_timeout: Duration,
     pub push_verify_timeout: Duration,
     pub push_throttle: Duration,
+    pub push_vapid: Option<VapidKey>,
 
     pub web_socket_throttle: Duration,
     pub web_socket_timeout: Duration,
@@ -74,8 +102,13 @@ pub struct JmapConfig {
     pub master_user: Option<(String, String)>,
 
     pub spam_header: Option<(HeaderName<'static>, String)>,
+    pub spam_score_header: Option<HeaderName<'static>>,
+    pub spam_score_threshold: f64,
+    pub spam_report_header: Option<HeaderName<'static>>,
+    pub virus_header: Option<HeaderName<'static>>,
     pub default_folders: Vec<DefaultFolder>,
     pub shared_folder: String,
+    pub account_template: AccountTemplate,
 
     pub http_headers: Vec<(hyper::header::HeaderName, hyper::header::HeaderValue)>,
     pub http_use_forwarded: bool,
@@ -97,6 +130,36 @@ pub struct DefaultFolder {
     pub create: bool,
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct AccountTemplate {
+    pub folders: Vec<TemplateFolder>,
+    pub sieve_script: Option<(String, String)>,
+    pub text_signature: Option<String>,
+    pub html_signature: Option<String>,
+    pub welcome_message: Option<WelcomeMessage>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TemplateFolder {
+    pub path: String,
+    pub special_use: SpecialUse,
+}
+
+#[derive(Clone)]
+pub struct VapidKey {
+    pub subject: String,
+    pub public_key: String,
+    pub key_pair: Arc<EcdsaKeyPair>,
+}
+
+#[derive(Clone, Debug)]
+pub struct WelcomeMessage {
+    pub from: String,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: Option<String>,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum SpecialUse {
     Inbox,
@@ -259,6 +322,9 @@ impl JmapConfig {
             snippet_max_results: config
                 .property("jmap.protocol.search-snippet.max-results")
                 .unwrap_or(100),
+            snippet_context_length: config
+                .property("jmap.protocol.search-snippet.context-length")
+                .unwrap_or(40),
             request_max_size: config
                 .property("jmap.protocol.request.max-size")
                 .unwrap_or(10000000),
@@ -298,16 +364,71 @@ impl JmapConfig {
                 .property("jmap.email.max-attachment-size")
                 .unwrap_or(50000000),
             mail_max_size: config.property("jmap.email.max-size").unwrap_or(75000000),
+            mail_import_max_size: config
+                .property("jmap.email.import.max-size")
+                .unwrap_or(1024 * 1024 * 1024),
             mail_parse_max_items: config.property("jmap.email.parse.max-items").unwrap_or(10),
             mail_autoexpunge_after: config
                 .property_or_default::<Option<Duration>>("jmap.email.auto-expunge", "30d")
                 .unwrap_or_default(),
+            mail_activity_max_entries: config
+                .property("jmap.email.activity.max-entries")
+                .unwrap_or(100),
+            mail_recycle_retention: config
+                .property_or_default::<Option<Duration>>(
+                    "jmap.email.recycle-bin.retention",
+                    "false",
+                )
+                .unwrap_or_default(),
+            mail_recycle_max_entries: config
+                .property("jmap.email.recycle-bin.max-entries")
+                .unwrap_or(10000),
+            mail_recycle_count_quota: config
+                .property("jmap.email.recycle-bin.count-quota")
+                .unwrap_or(false),
+            mail_draft_replace_rate: config
+                .property_or_default::<Option<Rate>>("jmap.email.drafts.rate-limit", "false")
+                .unwrap_or_default(),
+            mail_undo_send: config
+                .property_or_default::<Option<Duration>>("jmap.email.submission.undo-send", "0")
+                .unwrap_or_default(),
+            mail_max_delayed_send: config
+                .property_or_default::<Option<Duration>>(
+                    "jmap.email.submission.max-delayed-send",
+                    "30d",
+                )
+                .unwrap_or_default()
+                .unwrap_or_default(),
+            quota_warn_threshold: config.property("jmap.quota.warn-threshold").unwrap_or(90),
+            calendar_imip: config.property("jmap.calendar.imip.enable").unwrap_or(true),
             sieve_max_script_name: config
                 .property("sieve.untrusted.limits.name-length")
                 .unwrap_or(512),
+            sieve_max_script_size: config
+                .property("sieve.untrusted.limits.script-size")
+                .unwrap_or(1024 * 1024),
             sieve_max_scripts: config
                 .property("sieve.untrusted.limits.max-scripts")
                 .unwrap_or(256),
+            sieve_max_scripts_size: config
+                .property("sieve.untrusted.limits.total-size")
+                .unwrap_or(0),
+            sieve_imapsieve_script: config
+                .value("sieve.untrusted.imapsieve.script-name")
+                .unwrap_or("imapsieve")
+                .to_string(),
+            sieve_imapsieve_max_messages: config
+                .property("sieve.untrusted.imapsieve.outgoing-messages")
+                .unwrap_or(10),
+            sieve_notify_rate: config
+                .property_or_default::<Option<Rate>>("sieve.untrusted.notify.rate-limit", "10/1h")
+                .unwrap_or_default(),
+            sieve_notify_timeout: config
+                .property("sieve.untrusted.notify.timeout")
+                .unwrap_or(Duration::from_secs(10)),
+            sieve_notify_allow_private: config
+                .property("sieve.untrusted.notify.allow-private-ips")
+                .unwrap_or(false),
             capabilities: BaseCapabilities::default(),
             session_cache_ttl: config
                 .property("cache.session.ttl")
@@ -386,6 +507,21 @@ impl JmapConfig {
                         )
                     })
                 }),
+            spam_score_header: config
+                .property_or_default::<Option<String>>("spam.header.score", "X-Spam-Status")
+                .unwrap_or_default()
+                .and_then(|v| mail_parser::HeaderName::parse(v.trim().to_string())),
+            spam_score_threshold: config
+                .property_or_default("spam.score.spam", "5.0")
+                .unwrap_or(5.0),
+            spam_report_header: config
+                .property_or_default::<Option<String>>("spam.header.report", "X-Spam-Report")
+                .unwrap_or_default()
+                .and_then(|v| mail_parser::HeaderName::parse(v.trim().to_string())),
+            virus_header: config
+                .property_or_default::<Option<String>>("spam.header.virus", "X-Virus-Status")
+                .unwrap_or_default()
+                .and_then(|v| mail_parser::HeaderName::parse(v.trim().to_string())),
             http_use_forwarded: config
                 .property("server.http.use-x-forwarded")
                 .unwrap_or(false),
@@ -408,6 +544,7 @@ impl JmapConfig {
             push_throttle: config
                 .property_or_default("jmap.push.throttle", "1s")
                 .unwrap_or_else(|| Duration::from_secs(1)),
+            push_vapid: VapidKey::parse(config),
             session_purge_frequency: config
                 .property_or_default::<SimpleCron>("jmap.session.purge.frequency", "15 * *")
                 .unwrap_or_else(|| SimpleCron::parse_value("15 * *").unwrap()),
@@ -428,6 +565,7 @@ impl JmapConfig {
             }),
             default_folders,
             shared_folder,
+            account_template: AccountTemplate::parse(config),
         };
 
         // Add capabilities
@@ -436,6 +574,161 @@ impl JmapConfig {
     }
 }
 
+impl AccountTemplate {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut folders = Vec::new();
+        for key in config
+            .sub_keys("jmap.account.template.folders", ".name")
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+        {
+            let special_use = match config
+                .value(("jmap.account.template.folders", key.as_str(), "special-use"))
+                .map(|value| value.to_string())
+            {
+                Some(value) => match SpecialUse::parse_value(&value) {
+                    Ok(SpecialUse::Shared) | Err(_) => {
+                        config.new_parse_error(
+                            ("jmap.account.template.folders", key.as_str(), "special-use"),
+                            format!("Invalid folder role {value:?}"),
+                        );
+                        continue;
+                    }
+                    Ok(special_use) => special_use,
+                },
+                None => SpecialUse::None,
+            };
+            if let Some(path) = config
+                .value(("jmap.account.template.folders", key.as_str(), "name"))
+                .map(|name| name.trim())
+                .filter(|name| !name.is_empty())
+            {
+                folders.push(TemplateFolder {
+                    path: path.to_string(),
+                    special_use,
+                });
+            }
+        }
+
+        AccountTemplate {
+            folders,
+            sieve_script: config
+                .value("jmap.account.template.sieve.script")
+                .map(|script| {
+                    (
+                        config
+                            .value("jmap.account.template.sieve.name")
+                            .unwrap_or("default")
+                            .to_string(),
+                        script.to_string(),
+                    )
+                }),
+            text_signature: config
+                .value("jmap.account.template.identity.text-signature")
+                .map(|s| s.to_string()),
+            html_signature: config
+                .value("jmap.account.template.identity.html-signature")
+                .map(|s| s.to_string()),
+            welcome_message: config
+                .value("jmap.account.template.welcome.subject")
+                .and_then(|subject| {
+                    Some(WelcomeMessage {
+                        from: config
+                            .value("jmap.account.template.welcome.from")
+                            .unwrap_or("postmaster@localhost")
+                            .to_string(),
+                        subject: subject.to_string(),
+                        text_body: config
+                            .value("jmap.account.template.welcome.text-body")?
+                            .to_string(),
+                        html_body: config
+                            .value("jmap.account.template.welcome.html-body")
+                            .map(|s| s.to_string()),
+                    })
+                }),
+        }
+    }
+}
+
+impl VapidKey {
+    pub fn parse(config: &mut Config) -> Option<Self> {
+        let key = config.value("jmap.push.vapid.private-key")?.to_string();
+        let key_pair = pem::parse(key.as_bytes())
+            .map_err(|err| err.to_string())
+            .and_then(|pem| {
+                EcdsaKeyPair::from_pkcs8(
+                    &ECDSA_P256_SHA256_FIXED_SIGNING,
+                    pem.contents(),
+                    &SystemRandom::new(),
+                )
+                .map_err(|err| err.to_string())
+            });
+        match key_pair {
+            Ok(key_pair) => VapidKey {
+                subject: config
+                    .value("jmap.push.vapid.subject")
+                    .unwrap_or("mailto:postmaster@localhost")
+                    .to_string(),
+                public_key: URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+                key_pair: Arc::new(key_pair),
+            }
+            .into(),
+            Err(err) => {
+                config.new_parse_error(
+                    "jmap.push.vapid.private-key",
+                    format!("Invalid P-256 PKCS#8 private key: {err}"),
+                );
+                None
+            }
+        }
+    }
+
+    /// Builds the VAPID (RFC 8292) authorization header for a push service URL
+    pub fn authorization(&self, url: &str) -> Option<String> {
+        let audience = url.split('/').take(3).collect::<Vec<_>>().join("/");
+        let claims = serde_json::json!({
+            "aud": audience,
+            "exp": now() + 12 * 3600,
+            "sub": self.subject,
+        });
+        let token = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = self
+            .key_pair
+            .sign(&SystemRandom::new(), token.as_bytes())
+            .ok()?;
+
+        Some(format!(
+            "vapid t={token}.{}, k={}",
+            URL_SAFE_NO_PAD.encode(signature.as_ref()),
+            self.public_key
+        ))
+    }
+}
+
+impl AccountTemplate {
+    pub fn is_empty(&self) -> bool {
+        self.folders.is_empty() && self.sieve_script.is_none() && self.welcome_message.is_none()
+    }
+}
+
+impl SpecialUse {
+    pub fn as_role(&self) -> Option<&'static str> {
+        match self {
+            SpecialUse::Inbox => Some("inbox"),
+            SpecialUse::Trash => Some("trash"),
+            SpecialUse::Junk => Some("junk"),
+            SpecialUse::Drafts => Some("drafts"),
+            SpecialUse::Archive => Some("archive"),
+            SpecialUse::Sent => Some("sent"),
+            SpecialUse::Shared | SpecialUse::None => None,
+        }
+    }
+}
+
 impl ParseValue for SpecialUse {
     fn parse_value(value: &str) -> Result<Self, String> {
         match value {
@@ -0,0 +1,123 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use ahash::AHashSet;
+use directory::backend::internal::manage::ManageDirectory;
+use store::Store;
+use utils::config::Config;
+
+const DEFAULT_MESSAGE: &str =
+    "The server is undergoing maintenance and is read-only, please try again later.";
+
+// Read-only mode for store maintenance windows, reads are still served while
+// writes and message ingestion are rejected with a temporary failure.
+#[derive(Debug, Clone, Default)]
+pub struct Maintenance {
+    pub enable: bool,
+    pub accounts: AHashSet<u32>,
+    pub message: Option<String>,
+}
+
+impl Maintenance {
+    pub async fn parse(config: &mut Config, data: &Store) -> Self {
+        let mut maintenance = Maintenance {
+            enable: config
+                .property_or_default("server.maintenance.enable", "false")
+                .unwrap_or_default(),
+            accounts: AHashSet::new(),
+            message: config
+                .value("server.maintenance.message")
+                .map(|s| s.trim().replace(['\r', '\n'], " "))
+                .filter(|s| !s.is_empty()),
+        };
+
+        for name in config
+            .values("server.maintenance.accounts")
+            .map(|(_, name)| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>()
+        {
+            match data.get_principal_id(&name).await {
+                Ok(Some(account_id)) => {
+                    maintenance.accounts.insert(account_id);
+                }
+                Ok(None) => {
+                    config.new_build_warning(
+                        "server.maintenance.accounts",
+                        format!("Account {name:?} not found"),
+                    );
+                }
+                Err(err) => {
+                    trc::error!(err.caused_by(trc::location!()));
+                    config.new_build_error(
+                        "server.maintenance.accounts",
+                        format!("Failed to obtain id for account {name:?}"),
+                    );
+                }
+            }
+        }
+
+        maintenance
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enable
+    }
+
+    // Returns true if writes to the account are currently not allowed
+    pub fn is_read_only(&self, account_id: u32) -> bool {
+        self.enable || (!self.accounts.is_empty() && self.accounts.contains(&account_id))
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_deref().unwrap_or(DEFAULT_MESSAGE)
+    }
+
+    pub fn assert_writable(&self, account_id: u32) -> trc::Result<()> {
+        if !self.is_read_only(account_id) {
+            Ok(())
+        } else {
+            Err(trc::EventType::Server(trc::ServerEvent::MaintenanceMode)
+                .into_err()
+                .account_id(account_id)
+                .details(self.message().to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Maintenance;
+
+    #[test]
+    fn maintenance_mode() {
+        let mut maintenance = Maintenance::default();
+        assert!(maintenance.assert_writable(1).is_ok());
+
+        // Per-account maintenance
+        maintenance.accounts.insert(2);
+        assert!(!maintenance.is_enabled());
+        assert!(maintenance.assert_writable(1).is_ok());
+        let err = maintenance.assert_writable(2).unwrap_err();
+        assert!(err.matches(trc::EventType::Server(trc::ServerEvent::MaintenanceMode)));
+        assert_eq!(
+            err.value_as_str(trc::Key::Details),
+            Some(super::DEFAULT_MESSAGE)
+        );
+
+        // Server-wide maintenance
+        maintenance.enable = true;
+        maintenance.message = Some("Upgrading storage".to_string());
+        assert!(maintenance.is_read_only(1));
+        assert_eq!(
+            maintenance
+                .assert_writable(1)
+                .unwrap_err()
+                .value_as_str(trc::Key::Details),
+            Some("Upgrading storage")
+        );
+    }
+}
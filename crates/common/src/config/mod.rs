@@ -8,7 +8,10 @@ use std::sync::Arc;
 
 use arc_swap::ArcSwap;
 use directory::{Directories, Directory};
-use store::{BlobBackend, BlobStore, FtsStore, LookupStore, Store, Stores};
+use store::{
+    dispatch::replication::CHANGE_STREAM, BlobBackend, BlobStore, FtsStore, LookupStore, Store,
+    Stores,
+};
 use telemetry::Metrics;
 use utils::{
     config::Config,
@@ -20,13 +23,19 @@ use crate::{
 };
 
 use self::{
-    imap::ImapConfig, jmap::settings::JmapConfig, scripts::Scripting, smtp::SmtpConfig,
-    storage::Storage,
+    disk::DiskWatchdog, feeds::ThreatFeed, health::HealthCheck, imap::ImapConfig,
+    jmap::settings::JmapConfig, maintenance::Maintenance, replication::Replication,
+    scripts::Scripting, smtp::SmtpConfig, storage::Storage,
 };
 
+pub mod disk;
+pub mod feeds;
+pub mod health;
 pub mod imap;
 pub mod jmap;
+pub mod maintenance;
 pub mod network;
+pub mod replication;
 pub mod scripts;
 pub mod server;
 pub mod smtp;
@@ -157,6 +166,30 @@ impl Core {
             )
         }
 
+        let feeds = ThreatFeed::parse_all(config, &stores.lookup_stores, &lookup);
+        let mut maintenance = Maintenance::parse(config, &data).await;
+
+        // Standby instances are read-only until promoted
+        let replication = Replication::parse(config);
+        if replication.is_standby() {
+            maintenance.enable = true;
+            maintenance.message.get_or_insert_with(|| {
+                "This server is a standby replica and is read-only.".to_string()
+            });
+        }
+        if replication.is_primary() {
+            CHANGE_STREAM.enable(
+                &data,
+                &blob,
+                config
+                    .property::<u64>("cluster.node-id")
+                    .unwrap_or_default(),
+                replication.max_pending,
+            );
+        } else {
+            CHANGE_STREAM.disable();
+        }
+
         Self {
             #[cfg(feature = "enterprise")]
             enterprise,
@@ -175,6 +208,7 @@ impl Core {
                     32,
                 ),
                 permissions_version: Default::default(),
+                access_tokens_version: Default::default(),
                 logos: Default::default(),
             },
             storage: Storage {
@@ -185,6 +219,11 @@ impl Core {
                 directory,
                 directories: directories.directories,
                 purge_schedules: stores.purge_schedules,
+                feeds,
+                health: HealthCheck::parse(config),
+                disk: DiskWatchdog::parse(config),
+                maintenance,
+                replication,
                 config: config_manager,
                 stores: stores.stores,
                 lookups: stores.lookup_stores,
@@ -6,7 +6,10 @@
 
 use crate::{
     expr::{if_block::IfBlock, tokenizer::TokenMap},
-    listener::blocked::{AllowedIps, BlockedIps},
+    listener::{
+        blocked::{AllowedIps, BlockedIps, IpFeed, TrustedNetworks},
+        tarpit::Tarpit,
+    },
     Network,
 };
 use utils::config::Config;
@@ -32,6 +35,7 @@ impl Default for Network {
         Self {
             blocked_ips: Default::default(),
             allowed_ips: Default::default(),
+            trusted_networks: Default::default(),
             node_id: 0,
             http_response_url: IfBlock::new::<()>(
                 "server.http.url",
@@ -39,6 +43,24 @@ impl Default for Network {
                 "protocol + '://' + key_get('default', 'hostname') + ':' + local_port",
             ),
             http_allowed_endpoint: IfBlock::new::<()>("server.http.allowed-endpoint", [], "200"),
+            imap_greeting: IfBlock::new::<()>(
+                "server.greeting.imap",
+                [],
+                "'Stalwart IMAP4rev2 at your service.'",
+            ),
+            pop3_greeting: IfBlock::new::<()>(
+                "server.greeting.pop3",
+                [],
+                "'Stalwart POP3 at your service.'",
+            ),
+            sieve_greeting: IfBlock::new::<()>(
+                "server.greeting.sieve",
+                [],
+                "'Stalwart ManageSieve at your service.'",
+            ),
+            hide_version: false,
+            tarpit: Default::default(),
+            ip_feeds: Default::default(),
         }
     }
 }
@@ -49,6 +71,12 @@ impl Network {
             node_id: config.property("cluster.node-id").unwrap_or_default(),
             blocked_ips: BlockedIps::parse(config),
             allowed_ips: AllowedIps::parse(config),
+            trusted_networks: TrustedNetworks::parse(config),
+            tarpit: Tarpit::parse(config),
+            ip_feeds: IpFeed::parse_all(config),
+            hide_version: config
+                .property_or_default("server.hide-version", "false")
+                .unwrap_or_default(),
             ..Default::default()
         };
         let token_map = &TokenMap::default().with_variables(HTTP_VARS);
@@ -65,6 +93,18 @@ impl Network {
             }
         }
 
+        // Greetings are evaluated before any command is received
+        let token_map = &TokenMap::default().with_variables(CONNECTION_VARS);
+        for (value, key) in [
+            (&mut network.imap_greeting, "server.greeting.imap"),
+            (&mut network.pop3_greeting, "server.greeting.pop3"),
+            (&mut network.sieve_greeting, "server.greeting.sieve"),
+        ] {
+            if let Some(if_block) = IfBlock::try_parse(config, key, token_map) {
+                *value = if_block;
+            }
+        }
+
         network
     }
 }
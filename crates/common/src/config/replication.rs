@@ -0,0 +1,320 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+// Warm standby replication for deployments that cannot run a multi-region
+// FoundationDB cluster. The primary ships the changes applied to its data and
+// blob stores to a standby instance at another site, which serves reads only
+// and does not deliver queued messages or reports.
+//
+// Setting up a standby:
+//  1. Stop the primary and copy its data and blob stores to the standby site
+//     using a backup.
+//  2. Start the standby with `storage.replication.role = "standby"`.
+//  3. Start the primary with `storage.replication.role = "primary"` and the
+//     standby's management URL and credentials under `storage.replication.standby`.
+//
+// Failing over:
+//  1. Stop the primary or make sure it no longer accepts connections.
+//  2. Promote the standby with `POST /api/replication/promote`, which sets its
+//     role to "none" and enables writes, queue delivery and reports.
+//  3. Point DNS records and clients to the standby site.
+//
+// Unshipped changes are stored in the data store along with the changes they
+// describe and are shipped by one cluster node at a time. If they exceed
+// `storage.replication.max-pending`, the standby must be resynced from a new
+// copy of the primary before replication is resumed with
+// `POST /api/replication/reset`. Changes that commit after newer changes were
+// shipped are shipped with the next change set.
+// Replication keys are local to each node and are not stored in the shared
+// settings, and each node needs a unique `cluster.node-id`.
+
+use std::time::Duration;
+
+use store::{
+    dispatch::replication::{ChangeOp, CHANGE_STREAM},
+    write::Bincode,
+    BlobStore, LookupStore, Serialize,
+};
+use trc::AddContext;
+use utils::config::{utils::ParseValue, Config};
+
+#[derive(Debug, Clone, Default)]
+pub struct Replication {
+    pub role: ReplicationRole,
+    pub standby: Option<StandbyTarget>,
+    pub interval: Duration,
+    pub batch_size: usize,
+    pub max_pending: usize,
+    pub lag_alert: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplicationRole {
+    #[default]
+    None,
+    Primary,
+    Standby,
+}
+
+#[derive(Debug, Clone)]
+pub struct StandbyTarget {
+    pub url: String,
+    pub username: String,
+    pub secret: String,
+    pub timeout: Duration,
+    pub tls_allow_invalid_certs: bool,
+}
+
+const SHIP_LOCK: &[u8] = b"replication:ship";
+const SHIP_LOCK_EXPIRY: u64 = 300;
+
+#[derive(Debug, serde::Deserialize)]
+struct ApplyResponse {
+    data: u64,
+}
+
+impl Replication {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut replication = Replication {
+            role: config
+                .property_or_default("storage.replication.role", "none")
+                .unwrap_or_default(),
+            standby: None,
+            interval: config
+                .property_or_default("storage.replication.interval", "1s")
+                .unwrap_or_else(|| Duration::from_secs(1)),
+            batch_size: config
+                .property_or_default("storage.replication.batch-size", "4194304")
+                .unwrap_or(4 * 1024 * 1024),
+            max_pending: config
+                .property_or_default("storage.replication.max-pending", "536870912")
+                .unwrap_or(512 * 1024 * 1024),
+            lag_alert: config
+                .property_or_default::<Option<Duration>>("storage.replication.lag-alert", "5m")
+                .unwrap_or_default(),
+        };
+
+        if replication.role == ReplicationRole::Primary {
+            if let Some(url) = config.value_require("storage.replication.standby.url") {
+                replication.standby = Some(StandbyTarget {
+                    url: url.trim_end_matches('/').to_string(),
+                    username: config
+                        .value("storage.replication.standby.auth.username")
+                        .unwrap_or_default()
+                        .to_string(),
+                    secret: config
+                        .value("storage.replication.standby.auth.secret")
+                        .unwrap_or_default()
+                        .to_string(),
+                    timeout: config
+                        .property_or_default("storage.replication.standby.timeout", "30s")
+                        .unwrap_or_else(|| Duration::from_secs(30)),
+                    tls_allow_invalid_certs: config
+                        .property_or_default(
+                            "storage.replication.standby.allow-invalid-certs",
+                            "false",
+                        )
+                        .unwrap_or_default(),
+                });
+            } else {
+                replication.role = ReplicationRole::None;
+            }
+        }
+
+        replication
+    }
+
+    pub fn is_primary(&self) -> bool {
+        self.role == ReplicationRole::Primary
+    }
+
+    pub fn is_standby(&self) -> bool {
+        self.role == ReplicationRole::Standby
+    }
+
+    // Ships all pending changes to the standby, returning the number of
+    // change records acknowledged.
+    pub async fn ship(&self, lookup: &LookupStore, blob_store: &BlobStore) -> trc::Result<usize> {
+        let Some(standby) = &self.standby else {
+            return Ok(0);
+        };
+
+        // Only one cluster node ships changes at a time
+        if lookup
+            .counter_incr(SHIP_LOCK.to_vec(), 1, Some(SHIP_LOCK_EXPIRY), true)
+            .await
+            .caused_by(trc::location!())?
+            != 1
+        {
+            return Ok(0);
+        }
+        let result = self.ship_pending(standby, blob_store).await;
+        lookup
+            .counter_delete(SHIP_LOCK.to_vec())
+            .await
+            .caused_by(trc::location!())?;
+        result
+    }
+
+    async fn ship_pending(
+        &self,
+        standby: &StandbyTarget,
+        blob_store: &BlobStore,
+    ) -> trc::Result<usize> {
+        let url = format!("{}/api/replication/apply", standby.url);
+        let client = reqwest::Client::builder()
+            .timeout(standby.timeout)
+            .danger_accept_invalid_certs(standby.tls_allow_invalid_certs)
+            .build()
+            .map_err(|err| {
+                trc::StoreEvent::ReplicationError
+                    .into_err()
+                    .reason(err)
+                    .details("Failed to create HTTP client")
+            })?;
+        let mut total = 0;
+
+        while let Some(mut changes) = CHANGE_STREAM
+            .pending(self.batch_size)
+            .await
+            .caused_by(trc::location!())?
+        {
+            // Read the contents of the blobs written since the last shipment,
+            // blobs deleted in the meantime are removed on the standby as well.
+            for record in &mut changes.records {
+                for op in &mut record.ops {
+                    if let ChangeOp::BlobPut { key, data } = op {
+                        match blob_store
+                            .get_blob(key, 0..usize::MAX)
+                            .await
+                            .caused_by(trc::location!())?
+                        {
+                            Some(blob) => {
+                                *data = blob;
+                            }
+                            None => {
+                                *op = ChangeOp::BlobDelete {
+                                    key: std::mem::take(key),
+                                };
+                            }
+                        }
+                    }
+                }
+            }
+
+            let seqs = changes
+                .records
+                .iter()
+                .map(|record| record.seq)
+                .collect::<Vec<_>>();
+            let response = client
+                .post(&url)
+                .basic_auth(&standby.username, Some(&standby.secret))
+                .body(Bincode::new(changes).serialize())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|err| {
+                    trc::StoreEvent::ReplicationError
+                        .into_err()
+                        .reason(err)
+                        .ctx(trc::Key::Url, url.clone())
+                        .details("Failed to ship changes to standby")
+                })?
+                .bytes()
+                .await
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| {
+                    serde_json::from_slice::<ApplyResponse>(&bytes).map_err(|err| err.to_string())
+                })
+                .map_err(|err| {
+                    trc::StoreEvent::ReplicationError
+                        .into_err()
+                        .reason(err)
+                        .ctx(trc::Key::Url, url.clone())
+                        .details("Invalid response from standby")
+                })?;
+
+            // The standby applies records in the order they were shipped
+            let applied = seqs
+                .iter()
+                .position(|seq| *seq == response.data)
+                .map_or(0, |pos| pos + 1);
+            CHANGE_STREAM
+                .acknowledge(&seqs[..applied])
+                .await
+                .caused_by(trc::location!())?;
+            total += applied;
+            if applied < seqs.len() {
+                break;
+            }
+        }
+
+        Ok(total)
+    }
+}
+
+impl ParseValue for ReplicationRole {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "none" | "false" | "disable" | "disabled" => Ok(ReplicationRole::None),
+            "primary" => Ok(ReplicationRole::Primary),
+            "standby" => Ok(ReplicationRole::Standby),
+            _ => Err(format!("Invalid replication role: {value}")),
+        }
+    }
+}
+
+impl ReplicationRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReplicationRole::None => "none",
+            ReplicationRole::Primary => "primary",
+            ReplicationRole::Standby => "standby",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use utils::config::Config;
+
+    use super::{Replication, ReplicationRole};
+
+    #[test]
+    fn replication_config() {
+        let mut config = Config::new(
+            r#"
+[storage.replication]
+role = "primary"
+lag-alert = false
+
+[storage.replication.standby]
+url = "https://standby.example.org/"
+auth.username = "admin"
+auth.secret = "secret"
+"#,
+        )
+        .unwrap();
+        let replication = Replication::parse(&mut config);
+        assert!(replication.is_primary());
+        assert_eq!(replication.lag_alert, None);
+        assert_eq!(replication.interval, Duration::from_secs(1));
+        let standby = replication.standby.unwrap();
+        assert_eq!(standby.url, "https://standby.example.org");
+        assert_eq!(standby.username, "admin");
+
+        // A primary without a standby does not capture changes
+        let mut config = Config::new("[storage.replication]\nrole = \"primary\"\n").unwrap();
+        assert_eq!(Replication::parse(&mut config).role, ReplicationRole::None);
+        assert!(!config.errors.is_empty());
+
+        let mut config = Config::new("[storage.replication]\nrole = \"standby\"\n").unwrap();
+        assert!(Replication::parse(&mut config).is_standby());
+    }
+}
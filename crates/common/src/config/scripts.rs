@@ -10,8 +10,11 @@ use std::{
     time::{Duration, Instant},
 };
 
-use ahash::AHashMap;
-use nlp::bayes::cache::BayesTokenCache;
+use ahash::{AHashMap, AHashSet};
+use nlp::bayes::{
+    cache::BayesTokenCache,
+    tokenize::{BayesTokenizerConfig, CjkSegmentation},
+};
 use parking_lot::RwLock;
 use sieve::{compiler::grammar::Capability, Compiler, Runtime, Sieve};
 use store::Stores;
@@ -24,6 +27,8 @@ use super::{if_block::IfBlock, smtp::SMTP_RCPT_TO_VARS, tokenizer::TokenMap};
 pub struct Scripting {
     pub untrusted_compiler: Compiler,
     pub untrusted_runtime: Runtime,
+    pub untrusted_ext_lists: AHashSet<String>,
+    pub untrusted_notify_methods: AHashSet<String>,
     pub trusted_runtime: Runtime,
     pub from_addr: IfBlock,
     pub from_name: IfBlock,
@@ -31,8 +36,19 @@ pub struct Scripting {
     pub sign: IfBlock,
     pub trusted_scripts: AHashMap<String, Arc<Sieve>>,
     pub untrusted_scripts: AHashMap<String, Arc<Sieve>>,
+    pub bayes: BayesConfig,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BayesConfig {
+    pub tokenizer: BayesTokenizerConfig,
+    // Whether learned tokens are removed when the tokenizer settings change
+    pub reset_on_change: bool,
 }
 
+pub const SIEVE_ADDRBOOK_LIST: &str = "urn:ietf:params:sieve:addrbook";
+pub const SIEVE_ADDRBOOK_DEFAULT_LIST: &str = "urn:ietf:params:sieve:addrbook:default";
+
 pub struct ScriptCache {
     pub bayes_cache: BayesTokenCache,
     pub remote_lists: RwLock<AHashMap<String, RemoteList>>,
@@ -99,6 +115,29 @@ impl Scripting {
                     .unwrap_or(3),
             );
 
+        // Parse lookup lists available to untrusted scripts
+        let mut untrusted_ext_lists = AHashSet::new();
+        for (key, list) in config
+            .values("sieve.untrusted.ext-lists")
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>()
+        {
+            if stores.lookup_stores.contains_key(&list) {
+                untrusted_ext_lists.insert(list);
+            } else {
+                config.new_build_error(key, format!("Lookup store {list:?} not found"));
+            }
+        }
+
+        // Parse notification methods available to untrusted scripts
+        let mut untrusted_notify_methods = config
+            .values("sieve.untrusted.notification-uris")
+            .map(|(_, v)| v.to_ascii_lowercase())
+            .collect::<AHashSet<_>>();
+        if untrusted_notify_methods.is_empty() {
+            untrusted_notify_methods.insert("mailto".to_string());
+        }
+
         // Parse untrusted runtime
         let untrusted_runtime = Runtime::new()
             .with_max_nested_includes(
@@ -153,17 +192,7 @@ impl Scripting {
                     .values("sieve.untrusted.disable-capabilities")
                     .map(|(_, v)| v),
             )
-            .with_valid_notification_uris({
-                let values = config
-                    .values("sieve.untrusted.notification-uris")
-                    .map(|(_, v)| v.to_string())
-                    .collect::<Vec<_>>();
-                if !values.is_empty() {
-                    values
-                } else {
-                    vec!["mailto".to_string()]
-                }
-            })
+            .with_valid_notification_uris(untrusted_notify_methods.iter().cloned())
             .with_protected_headers({
                 let values = config
                     .values("sieve.untrusted.protected-headers")
@@ -195,7 +224,13 @@ impl Scripting {
             .with_env_variable("name", "Stalwart Mail Server")
             .with_env_variable("version", env!("CARGO_PKG_VERSION"))
             .with_env_variable("location", "MS")
-            .with_env_variable("phase", "during");
+            .with_env_variable("phase", "during")
+            .with_valid_ext_lists(
+                [SIEVE_ADDRBOOK_LIST, SIEVE_ADDRBOOK_DEFAULT_LIST]
+                    .into_iter()
+                    .map(|list| list.to_string())
+                    .chain(untrusted_ext_lists.iter().cloned()),
+            );
 
         // Parse trusted compiler and runtime
         let mut fnc_map = register_functions().register_plugins();
@@ -331,6 +366,8 @@ impl Scripting {
         Scripting {
             untrusted_compiler,
             untrusted_runtime,
+            untrusted_ext_lists,
+            untrusted_notify_methods,
             trusted_runtime,
             from_addr: IfBlock::try_parse(config, "sieve.trusted.from-addr", &token_map)
                 .unwrap_or_else(|| {
@@ -360,6 +397,53 @@ impl Scripting {
             ),
             untrusted_scripts,
             trusted_scripts,
+            bayes: BayesConfig::parse(config),
+        }
+    }
+}
+
+impl BayesConfig {
+    pub fn parse(config: &mut Config) -> Self {
+        let default = BayesTokenizerConfig::default();
+        let cjk = match config.value("spam-filter.bayes.tokenizer.cjk") {
+            Some("dictionary") | None => CjkSegmentation::Dictionary,
+            Some("bigram") => CjkSegmentation::Bigram,
+            Some(value) => {
+                let err = format!("Invalid CJK segmentation method {value:?}");
+                config.new_parse_error("spam-filter.bayes.tokenizer.cjk", err);
+                CjkSegmentation::Dictionary
+            }
+        };
+        let reset_on_change = match config.value("spam-filter.bayes.migrate") {
+            Some("keep") | None => false,
+            Some("reset") => true,
+            Some(value) => {
+                let err = format!("Invalid token migration method {value:?}");
+                config.new_parse_error("spam-filter.bayes.migrate", err);
+                false
+            }
+        };
+
+        BayesConfig {
+            tokenizer: BayesTokenizerConfig {
+                cjk,
+                split_scripts: config
+                    .property("spam-filter.bayes.tokenizer.split-scripts")
+                    .unwrap_or(default.split_scripts),
+                stemming: config
+                    .property("spam-filter.bayes.normalize.stemming")
+                    .unwrap_or(default.stemming),
+                stop_words: config
+                    .property("spam-filter.bayes.normalize.stop-words")
+                    .unwrap_or(default.stop_words),
+                strip_accents: config
+                    .property("spam-filter.bayes.normalize.strip-accents")
+                    .unwrap_or(default.strip_accents),
+                fold_width: config
+                    .property("spam-filter.bayes.normalize.fold-width")
+                    .unwrap_or(default.fold_width),
+            },
+            reset_on_change,
         }
     }
 }
@@ -388,6 +472,8 @@ impl Default for Scripting {
         Scripting {
             untrusted_compiler: Compiler::new(),
             untrusted_runtime: Runtime::new(),
+            untrusted_ext_lists: AHashSet::new(),
+            untrusted_notify_methods: AHashSet::new(),
             trusted_runtime: Runtime::new(),
             from_addr: IfBlock::new::<()>(
                 "sieve.trusted.from-addr",
@@ -406,6 +492,7 @@ impl Default for Scripting {
             ),
             untrusted_scripts: AHashMap::new(),
             trusted_scripts: AHashMap::new(),
+            bayes: BayesConfig::default(),
         }
     }
 }
@@ -428,6 +515,8 @@ impl Clone for Scripting {
         Self {
             untrusted_compiler: self.untrusted_compiler.clone(),
             untrusted_runtime: self.untrusted_runtime.clone(),
+            untrusted_ext_lists: self.untrusted_ext_lists.clone(),
+            untrusted_notify_methods: self.untrusted_notify_methods.clone(),
             trusted_runtime: self.trusted_runtime.clone(),
             from_addr: self.from_addr.clone(),
             from_name: self.from_name.clone(),
@@ -435,6 +524,7 @@ impl Clone for Scripting {
             sign: self.sign.clone(),
             trusted_scripts: self.trusted_scripts.clone(),
             untrusted_scripts: self.untrusted_scripts.clone(),
+            bayes: self.bayes,
         }
     }
 }
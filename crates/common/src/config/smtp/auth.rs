@@ -7,11 +7,14 @@
 use std::{sync::Arc, time::Duration};
 
 use ahash::AHashMap;
+use base64::{engine::general_purpose::STANDARD, Engine};
 use mail_auth::{
     common::crypto::{Algorithm, Ed25519Key, HashAlgorithm, RsaKey, Sha256, SigningKey},
     dkim::{Canonicalization, Done},
 };
 use mail_parser::decoders::base64::base64_decode;
+use ring::hmac;
+use store::write::now;
 use utils::config::{
     utils::{AsKey, ParseValue},
     Config,
@@ -30,7 +33,9 @@ pub struct MailAuthConfig {
     pub arc: ArcAuthConfig,
     pub spf: SpfAuthConfig,
     pub dmarc: DmarcAuthConfig,
+    pub bimi: BimiAuthConfig,
     pub iprev: IpRevAuthConfig,
+    pub srs: SrsAuthConfig,
 
     pub signers: AHashMap<String, Arc<DkimSigner>>,
     pub sealers: AHashMap<String, Arc<ArcSealer>>,
@@ -60,11 +65,28 @@ pub struct DmarcAuthConfig {
     pub verify: IfBlock,
 }
 
+#[derive(Clone)]
+pub struct BimiAuthConfig {
+    pub verify: IfBlock,
+    pub indicator: bool,
+    pub require_vmc: bool,
+    pub max_size: usize,
+    pub timeout: Duration,
+    pub vmc_roots: Vec<Vec<u8>>,
+}
+
 #[derive(Clone)]
 pub struct IpRevAuthConfig {
     pub verify: IfBlock,
 }
 
+#[derive(Clone, Default)]
+pub struct SrsAuthConfig {
+    pub key: Option<hmac::Key>,
+    pub domain: Option<String>,
+    pub validity: u64,
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub enum VerifyStrategy {
     #[default]
@@ -140,6 +162,14 @@ impl Default for MailAuthConfig {
                     "relaxed",
                 ),
             },
+            bimi: BimiAuthConfig {
+                verify: IfBlock::new::<()>("auth.bimi.verify", [], "false"),
+                indicator: true,
+                require_vmc: false,
+                max_size: 32 * 1024,
+                timeout: Duration::from_secs(10),
+                vmc_roots: Vec::new(),
+            },
             iprev: IpRevAuthConfig {
                 verify: IfBlock::new::<VerifyStrategy>(
                     "auth.iprev.verify",
@@ -150,6 +180,11 @@ impl Default for MailAuthConfig {
                     "relaxed",
                 ),
             },
+            srs: SrsAuthConfig {
+                key: None,
+                domain: None,
+                validity: 21,
+            },
             signers: Default::default(),
             sealers: Default::default(),
         }
@@ -182,6 +217,7 @@ impl MailAuthConfig {
                 &conn_vars,
             ),
             (&mut mail_auth.dmarc.verify, "auth.dmarc.verify", &rcpt_vars),
+            (&mut mail_auth.bimi.verify, "auth.bimi.verify", &rcpt_vars),
             (&mut mail_auth.iprev.verify, "auth.iprev.verify", &conn_vars),
         ] {
             if let Some(if_block) = IfBlock::try_parse(config, key, token_map) {
@@ -191,6 +227,56 @@ impl MailAuthConfig {
         mail_auth.dkim.strict = config
             .property_or_default("auth.dkim.strict", "true")
             .unwrap_or(true);
+        mail_auth.bimi.indicator = config
+            .property_or_default("auth.bimi.indicator", "true")
+            .unwrap_or(true);
+        mail_auth.bimi.require_vmc = config
+            .property_or_default("auth.bimi.require-vmc", "false")
+            .unwrap_or(false);
+        mail_auth.bimi.max_size = config
+            .property_or_default("auth.bimi.max-size", "32768")
+            .unwrap_or(32 * 1024);
+        mail_auth.bimi.timeout = config
+            .property_or_default("auth.bimi.timeout", "10s")
+            .unwrap_or_else(|| Duration::from_secs(10));
+        for (key, value) in config
+            .values("auth.bimi.vmc-roots")
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+        {
+            match rustls_pemfile::certs(&mut value.as_bytes()).collect::<Result<Vec<_>, _>>() {
+                Ok(certs) if !certs.is_empty() => {
+                    mail_auth
+                        .bimi
+                        .vmc_roots
+                        .extend(certs.into_iter().map(|cert| cert.to_vec()));
+                }
+                Ok(_) => {
+                    config.new_parse_error(key, "No certificates found.");
+                }
+                Err(err) => {
+                    config.new_parse_error(key, format!("Failed to read certificates: {err}"));
+                }
+            }
+        }
+
+        // Parse SRS settings
+        if config
+            .property_or_default("auth.srs.enable", "false")
+            .unwrap_or(false)
+        {
+            if let Some(secret) = config.value_require("auth.srs.secret") {
+                mail_auth.srs.key =
+                    hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret.as_bytes()).into();
+            }
+            mail_auth.srs.domain = config
+                .value("auth.srs.domain")
+                .map(|domain| domain.to_lowercase());
+            mail_auth.srs.validity = config
+                .property_or_default::<Duration>("auth.srs.validity", "21d")
+                .map(|validity| (validity.as_secs() / 86400).clamp(1, SRS_TIMESTAMP_RANGE - 1))
+                .unwrap_or(21);
+        }
 
         // Parse signatures
         for id in config
@@ -481,3 +567,204 @@ impl Default for DkimCanonicalization {
         }
     }
 }
+
+const SRS_BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const SRS_TIMESTAMP_RANGE: u64 = 1024;
+const SRS_HASH_LEN: usize = 4;
+
+impl SrsAuthConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.key.is_some()
+    }
+
+    // Rewrites the envelope sender of a forwarded message, returns None if no rewriting is needed
+    pub fn forward(&self, sender: &str, domain: &str) -> Option<String> {
+        let key = self.key.as_ref()?;
+        let domain = self.domain.as_deref().unwrap_or(domain);
+        let (local, sender_domain) = sender.rsplit_once('@')?;
+        if local.is_empty()
+            || sender_domain.is_empty()
+            || sender_domain.eq_ignore_ascii_case(domain)
+        {
+            return None;
+        }
+
+        if let Some(srs0) = strip_srs_prefix(local, "SRS0=") {
+            // Address was rewritten by another forwarder
+            let hash = srs_hash(key, &[sender_domain, srs0]);
+            Some(format!("SRS1={hash}={sender_domain}=={srs0}@{domain}"))
+        } else if let Some(srs1) = strip_srs_prefix(local, "SRS1=") {
+            // Preserve the original forwarder
+            let (_, opaque) = srs1.split_once('=')?;
+            let (first, srs0) = opaque.split_once("==")?;
+            let hash = srs_hash(key, &[first, srs0]);
+            Some(format!("SRS1={hash}={first}=={srs0}@{domain}"))
+        } else {
+            let day = now() / 86400 % SRS_TIMESTAMP_RANGE;
+            let timestamp = [
+                SRS_BASE32[(day >> 5) as usize] as char,
+                SRS_BASE32[(day & 31) as usize] as char,
+            ]
+            .iter()
+            .collect::<String>();
+            let hash = srs_hash(key, &[&timestamp, sender_domain, local]);
+            Some(format!(
+                "SRS0={hash}={timestamp}={sender_domain}={local}@{domain}"
+            ))
+        }
+    }
+
+    // Obtains the address an SRS address was rewritten from
+    pub fn reverse(&self, address: &str) -> Result<Option<String>, &'static str> {
+        let (key, (local, _)) = match (self.key.as_ref(), address.rsplit_once('@')) {
+            (Some(key), Some(address)) => (key, address),
+            _ => return Ok(None),
+        };
+
+        if let Some(srs0) = strip_srs_prefix(local, "SRS0=") {
+            let mut parts = srs0.splitn(4, '=');
+            let (hash, timestamp, domain, local) =
+                match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                    (Some(hash), Some(timestamp), Some(domain), Some(local))
+                        if !domain.is_empty() && !local.is_empty() =>
+                    {
+                        (hash, timestamp, domain, local)
+                    }
+                    _ => return Err("Malformed SRS address"),
+                };
+
+            if !srs_hash(key, &[timestamp, domain, local]).eq_ignore_ascii_case(hash) {
+                return Err("Invalid SRS hash");
+            }
+
+            let mut day = 0;
+            for ch in timestamp.bytes() {
+                day = (day << 5)
+                    | SRS_BASE32
+                        .iter()
+                        .position(|b| b.eq_ignore_ascii_case(&ch))
+                        .ok_or("Invalid SRS timestamp")? as u64;
+            }
+            let today = now() / 86400 % SRS_TIMESTAMP_RANGE;
+            if timestamp.len() != 2
+                || (today + SRS_TIMESTAMP_RANGE - day) % SRS_TIMESTAMP_RANGE > self.validity
+            {
+                return Err("SRS address has expired");
+            }
+
+            Ok(Some(format!("{local}@{domain}")))
+        } else if let Some(srs1) = strip_srs_prefix(local, "SRS1=") {
+            let (hash, opaque) = srs1.split_once('=').ok_or("Malformed SRS address")?;
+            let (first, srs0) = opaque
+                .split_once("==")
+                .filter(|(first, srs0)| !first.is_empty() && !srs0.is_empty())
+                .ok_or("Malformed SRS address")?;
+
+            if srs_hash(key, &[first, srs0]).eq_ignore_ascii_case(hash) {
+                Ok(Some(format!("SRS0={srs0}@{first}")))
+            } else {
+                Err("Invalid SRS hash")
+            }
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+fn strip_srs_prefix<'x>(local: &'x str, prefix: &str) -> Option<&'x str> {
+    local
+        .get(..prefix.len())
+        .filter(|p| p.eq_ignore_ascii_case(prefix))
+        .map(|_| &local[prefix.len()..])
+}
+
+fn srs_hash(key: &hmac::Key, parts: &[&str]) -> String {
+    let mut ctx = hmac::Context::with_key(key);
+    for part in parts {
+        ctx.update(part.to_lowercase().as_bytes());
+    }
+    let mut hash = STANDARD.encode(ctx.sign().as_ref());
+    hash.truncate(SRS_HASH_LEN);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::hmac;
+    use store::write::now;
+
+    use super::{srs_hash, SrsAuthConfig, SRS_BASE32};
+
+    #[test]
+    fn srs_rewrite() {
+        let srs = SrsAuthConfig {
+            key: hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, b"secret").into(),
+            domain: None,
+            validity: 21,
+        };
+        let other = SrsAuthConfig {
+            key: hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, b"other").into(),
+            domain: "forwarder.org".to_string().into(),
+            validity: 21,
+        };
+
+        // Local senders are not rewritten
+        assert_eq!(srs.forward("john@example.org", "example.org"), None);
+        assert_eq!(srs.reverse("john@example.org"), Ok(None));
+
+        // SRS0 round trip
+        let srs0 = srs.forward("jane=doe@foobar.org", "example.org").unwrap();
+        assert!(srs0.starts_with("SRS0="), "{srs0}");
+        assert!(srs0.ends_with("=foobar.org=jane=doe@example.org"), "{srs0}");
+        assert_eq!(
+            srs.reverse(&srs0),
+            Ok(Some("jane=doe@foobar.org".to_string()))
+        );
+        assert_eq!(
+            srs.reverse(&srs0.to_lowercase()),
+            Ok(Some("jane=doe@foobar.org".to_string()))
+        );
+        assert!(other.reverse(&srs0).is_err());
+
+        // SRS1 round trip
+        let srs1 = other.forward(&srs0, "ignored.org").unwrap();
+        assert!(srs1.starts_with("SRS1="), "{srs1}");
+        assert!(srs1.ends_with("@forwarder.org"), "{srs1}");
+        assert_eq!(other.reverse(&srs1), Ok(Some(srs0.clone())));
+        let srs1_again = SrsAuthConfig {
+            key: hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, b"third").into(),
+            domain: "third.org".to_string().into(),
+            validity: 21,
+        }
+        .forward(&srs1, "ignored.org")
+        .unwrap();
+        assert!(srs1_again.starts_with("SRS1="), "{srs1_again}");
+        assert!(srs1_again.contains("=example.org=="), "{srs1_again}");
+
+        // Tampered and expired addresses
+        let tampered = srs0.replace("=jane=doe@", "=john@");
+        assert_eq!(srs.reverse(&tampered), Err("Invalid SRS hash"));
+        let day = now() / 86400 % 1024;
+        let expired_day = (day + 1024 - 30) % 1024;
+        let timestamp = [
+            SRS_BASE32[(expired_day >> 5) as usize] as char,
+            SRS_BASE32[(expired_day & 31) as usize] as char,
+        ]
+        .iter()
+        .collect::<String>();
+        let hash = srs_hash(
+            srs.key.as_ref().unwrap(),
+            &[&timestamp, "foobar.org", "jane"],
+        );
+        assert_eq!(
+            srs.reverse(&format!(
+                "SRS0={hash}={timestamp}=foobar.org=jane@example.org"
+            )),
+            Err("SRS address has expired")
+        );
+        assert_eq!(
+            srs.reverse("SRS0=abcd@example.org"),
+            Err("Malformed SRS address")
+        );
+    }
+}
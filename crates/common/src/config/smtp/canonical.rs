@@ -0,0 +1,159 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use ahash::AHashSet;
+use utils::config::Config;
+
+#[derive(Debug, Clone)]
+pub struct AddressCanonicalization {
+    pub lowercase: bool,
+    pub idna: bool,
+    pub strip_subaddress: bool,
+    pub subaddress_separator: char,
+    pub ignore_dots: AHashSet<String>,
+}
+
+impl AddressCanonicalization {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut canonical = AddressCanonicalization::default();
+        canonical.lowercase = config
+            .property_or_default("address-canonicalization.lowercase", "true")
+            .unwrap_or(canonical.lowercase);
+        canonical.idna = config
+            .property_or_default("address-canonicalization.idna", "true")
+            .unwrap_or(canonical.idna);
+        canonical.strip_subaddress = config
+            .property_or_default("address-canonicalization.strip-subaddress", "false")
+            .unwrap_or(canonical.strip_subaddress);
+        if let Some(separator) = config.value("address-canonicalization.subaddress-separator") {
+            let mut chars = separator.chars();
+            match (chars.next(), chars.next()) {
+                (Some(separator), None) => {
+                    canonical.subaddress_separator = separator;
+                }
+                _ => {
+                    config.new_parse_error(
+                        "address-canonicalization.subaddress-separator",
+                        "Separator must be a single character",
+                    );
+                }
+            }
+        }
+        for (_, domain) in config.values("address-canonicalization.ignore-dots") {
+            let domain = domain.trim().trim_end_matches('.').to_lowercase();
+            if !domain.is_empty() {
+                canonical
+                    .ignore_dots
+                    .insert(idna::domain_to_ascii(&domain).unwrap_or(domain));
+            }
+        }
+
+        canonical
+    }
+
+    // Returns the key used to identify an address in the spam, rate limiting
+    // and report subsystems, so that related addresses are treated as one.
+    pub fn address(&self, address: &str) -> String {
+        let address = address.trim();
+        let Some((local_part, domain_part)) = address.rsplit_once('@') else {
+            return if self.lowercase {
+                address.to_lowercase()
+            } else {
+                address.to_string()
+            };
+        };
+
+        let domain_part = self.domain(domain_part);
+        let mut local_part = if self.lowercase {
+            local_part.to_lowercase()
+        } else {
+            local_part.to_string()
+        };
+        if self.strip_subaddress {
+            if let Some(pos) = local_part
+                .find(self.subaddress_separator)
+                .filter(|pos| *pos > 0)
+            {
+                local_part.truncate(pos);
+            }
+        }
+        if !self.ignore_dots.is_empty() && self.ignore_dots.contains(&domain_part.to_lowercase()) {
+            local_part.retain(|ch| ch != '.');
+        }
+
+        format!("{local_part}@{domain_part}")
+    }
+
+    pub fn domain(&self, domain: &str) -> String {
+        let domain = domain.trim().trim_end_matches('.');
+        if self.idna && !domain.is_ascii() {
+            if let Ok(domain) = idna::domain_to_ascii(domain) {
+                return domain;
+            }
+        }
+
+        if self.lowercase {
+            domain.to_lowercase()
+        } else {
+            domain.to_string()
+        }
+    }
+}
+
+impl Default for AddressCanonicalization {
+    fn default() -> Self {
+        Self {
+            lowercase: true,
+            idna: true,
+            strip_subaddress: false,
+            subaddress_separator: '+',
+            ignore_dots: AHashSet::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AddressCanonicalization;
+
+    #[test]
+    fn canonicalize_address() {
+        let mut canonical = AddressCanonicalization::default();
+
+        // Case folding and IDN domains
+        assert_eq!(
+            canonical.address("John.Doe@Example.ORG"),
+            "john.doe@example.org"
+        );
+        assert_eq!(
+            canonical.address("josé@Bücher.de."),
+            "josé@xn--bcher-kva.de"
+        );
+        assert_eq!(
+            canonical.address("jane+news@example.org"),
+            "jane+news@example.org"
+        );
+        assert_eq!(canonical.address(""), "");
+
+        // Plus-tag stripping and dot-insensitive domains
+        canonical.strip_subaddress = true;
+        canonical.ignore_dots.insert("gmail.com".to_string());
+        assert_eq!(
+            canonical.address("Jane+News@example.org"),
+            "jane@example.org"
+        );
+        assert_eq!(canonical.address("+news@example.org"), "+news@example.org");
+        assert_eq!(canonical.address("J.Doe+list@GMail.com"), "jdoe@gmail.com");
+        assert_eq!(canonical.address("j.doe@example.org"), "j.doe@example.org");
+
+        // Case preserving
+        canonical.lowercase = false;
+        assert_eq!(
+            canonical.address("Jane+News@Example.org"),
+            "Jane@Example.org"
+        );
+    }
+}
@@ -4,9 +4,20 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{atomic::AtomicUsize, Arc},
+    time::Duration,
+};
+
 use ahash::AHashMap;
 use mail_auth::IpLookupStrategy;
 use mail_send::Credentials;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use sha2::{Digest, Sha256};
 use utils::config::{
     utils::{AsKey, ParseValue},
     Config,
@@ -43,16 +54,46 @@ pub struct QueueConfig {
 
     // Throttle and Quotas
     pub throttle: QueueThrottle,
+    pub adaptive_throttle: QueueAdaptiveThrottle,
     pub quota: QueueQuotas,
 
     // Relay hosts
     pub relay_hosts: AHashMap<String, RelayHost>,
+
+    // Smart host routing table
+    pub routes: Vec<RelayRoute>,
+
+    // Source IP pools
+    pub ip_pools: AHashMap<String, IpPool>,
+
+    // Encryption at rest
+    pub encryption: QueueEncryption,
+
+    // Priority classes
+    pub priority: QueuePriority,
 }
 
 #[derive(Clone)]
 pub struct QueueOutboundSourceIp {
     pub ipv4: IfBlock,
     pub ipv6: IfBlock,
+    pub pool: IfBlock,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IpPool {
+    pub strategy: IpPoolStrategy,
+    pub ipv4: Vec<(Ipv4Addr, u32)>,
+    pub ipv6: Vec<(Ipv6Addr, u32)>,
+    pub next_ipv4: Arc<AtomicUsize>,
+    pub next_ipv6: Arc<AtomicUsize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IpPoolStrategy {
+    #[default]
+    RoundRobin,
+    Random,
 }
 
 #[derive(Clone)]
@@ -60,6 +101,32 @@ pub struct Dsn {
     pub name: IfBlock,
     pub address: IfBlock,
     pub sign: IfBlock,
+    pub language: IfBlock,
+    pub support: IfBlock,
+    pub template_set: IfBlock,
+    pub templates: AHashMap<String, DsnTemplate>,
+    pub template_sets: AHashMap<String, AHashMap<String, DsnTemplate>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DsnTemplate {
+    pub subject_success: String,
+    pub subject_delay: String,
+    pub subject_failure: String,
+    pub subject_partial: String,
+    pub subject_mixed: String,
+    pub body_success: String,
+    pub body_delay: String,
+    pub body_failure: String,
+    pub body_partial: String,
+    pub body_mixed: String,
+    pub section_success: String,
+    pub section_delay: String,
+    pub section_failure: String,
+    pub recipient_success: String,
+    pub recipient_delay: String,
+    pub recipient_failure: String,
+    pub support: String,
 }
 
 #[derive(Clone)]
@@ -112,9 +179,58 @@ pub struct RelayHost {
     pub protocol: ServerProtocol,
     pub auth: Option<Credentials<String>>,
     pub tls_implicit: bool,
+    pub tls_required: bool,
     pub tls_allow_invalid_certs: bool,
 }
 
+#[derive(Debug, Clone)]
+pub struct RelayRoute {
+    pub id: String,
+    pub recipient_domains: Vec<String>,
+    pub sender_domains: Vec<String>,
+    pub host: RelayHost,
+}
+
+#[derive(Clone, Default)]
+pub struct QueueEncryption {
+    pub enable: bool,
+    // The first key is used for encryption, all keys are tried for decryption
+    keys: Vec<QueueEncryptionKey>,
+}
+
+#[derive(Clone)]
+struct QueueEncryptionKey {
+    id: [u8; 4],
+    key: [u8; 32],
+}
+
+const QUEUE_ENCRYPTION_MAGIC: &[u8] = b"\xffSQE\x01";
+const QUEUE_ENCRYPTION_HEADER_LEN: usize = QUEUE_ENCRYPTION_MAGIC.len() + 4 + NONCE_LEN;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueAdaptiveThrottle {
+    pub enable: bool,
+    // Temporary failure codes that signal the remote host is throttling
+    pub codes: Vec<u16>,
+    pub min_concurrency: u64,
+    pub max_concurrency: u64,
+    pub max_interval: Duration,
+    pub recovery: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueuePriority {
+    pub high: i16,
+    pub bulk: i16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PriorityClass {
+    Bulk,
+    Normal,
+    High,
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub enum RequireOptional {
     #[default]
@@ -156,6 +272,7 @@ impl Default for QueueConfig {
             source_ip: QueueOutboundSourceIp {
                 ipv4: IfBlock::empty("queue.outbound.source-ip.v4"),
                 ipv6: IfBlock::empty("queue.outbound.source-ip.v6"),
+                pool: IfBlock::empty("queue.outbound.source-ip.pool"),
             },
             tls: QueueOutboundTls {
                 dane: IfBlock::new::<RequireOptional>("queue.outbound.tls.dane", [], "optional"),
@@ -193,6 +310,16 @@ impl Default for QueueConfig {
                     [],
                     "['rsa-' + key_get('default', 'domain'), 'ed25519-' + key_get('default', 'domain')]",
                 ),
+                language: IfBlock::new::<()>("report.dsn.language", [], "'en'"),
+                support: IfBlock::new::<()>("report.dsn.support-contact", [], "false"),
+                template_set: IfBlock::new::<()>("report.dsn.template-set", [], "false"),
+                templates: ["en", "es", "fr", "de"]
+                    .into_iter()
+                    .filter_map(|lang| {
+                        DsnTemplate::builtin(lang).map(|template| (lang.to_string(), template))
+                    })
+                    .collect(),
+                template_sets: AHashMap::new(),
             },
             timeout: QueueOutboundTimeout {
                 connect: IfBlock::new::<()>("queue.outbound.timeouts.connect", [], "5m"),
@@ -209,12 +336,17 @@ impl Default for QueueConfig {
                 rcpt: Default::default(),
                 host: Default::default(),
             },
+            adaptive_throttle: QueueAdaptiveThrottle::default(),
             quota: QueueQuotas {
                 sender: Default::default(),
                 rcpt: Default::default(),
                 rcpt_domain: Default::default(),
             },
             relay_hosts: Default::default(),
+            routes: Default::default(),
+            ip_pools: Default::default(),
+            encryption: Default::default(),
+            priority: QueuePriority::default(),
         }
     }
 }
@@ -256,6 +388,11 @@ impl QueueConfig {
                 "queue.outbound.source-ip.v6",
                 &mx_vars,
             ),
+            (
+                &mut queue.source_ip.pool,
+                "queue.outbound.source-ip.pool",
+                &mx_vars,
+            ),
             (&mut queue.next_hop, "queue.outbound.next-hop", &rcpt_vars),
             (&mut queue.tls.dane, "queue.outbound.tls.dane", &dane_vars),
             (
@@ -320,14 +457,65 @@ impl QueueConfig {
                 &sender_vars,
             ),
             (&mut queue.dsn.sign, "report.dsn.sign", &sender_vars),
+            (&mut queue.dsn.language, "report.dsn.language", &sender_vars),
+            (
+                &mut queue.dsn.support,
+                "report.dsn.support-contact",
+                &sender_vars,
+            ),
+            (
+                &mut queue.dsn.template_set,
+                "report.dsn.template-set",
+                &sender_vars,
+            ),
         ] {
             if let Some(if_block) = IfBlock::try_parse(config, key, token_map) {
                 *value = if_block;
             }
         }
 
+        // Parse DSN templates
+        for lang in config
+            .sub_keys("report.dsn.template", "")
+            .map(|lang| lang.to_lowercase())
+            .collect::<Vec<_>>()
+        {
+            let mut template = queue
+                .dsn
+                .templates
+                .get(&lang)
+                .or_else(|| queue.dsn.templates.get("en"))
+                .cloned()
+                .unwrap_or_default();
+            template.parse(config, ("report.dsn.template", lang.as_str()));
+            queue.dsn.templates.insert(lang, template);
+        }
+
+        // Parse DSN template sets, missing texts are taken from the default templates
+        for set in config
+            .sub_keys("report.dsn.templates", "")
+            .map(|set| set.to_string())
+            .collect::<Vec<_>>()
+        {
+            let mut templates = AHashMap::new();
+            for lang in config
+                .sub_keys(("report.dsn.templates", set.as_str()), "")
+                .map(|lang| lang.to_lowercase())
+                .collect::<Vec<_>>()
+            {
+                let mut template = queue.dsn.template("", &lang).clone();
+                template.parse(
+                    config,
+                    ("report.dsn.templates", set.as_str(), lang.as_str()),
+                );
+                templates.insert(lang, template);
+            }
+            queue.dsn.template_sets.insert(set, templates);
+        }
+
         // Parse queue quotas and throttles
         queue.throttle = parse_queue_throttle(config);
+        queue.adaptive_throttle = QueueAdaptiveThrottle::parse(config);
         queue.quota = parse_queue_quota(config);
 
         // Parse relay hosts
@@ -336,7 +524,7 @@ impl QueueConfig {
             .map(|id| id.to_string())
             .collect::<Vec<_>>()
             .into_iter()
-            .filter_map(|id| parse_relay_host(config, &id).map(|host| (id, host)))
+            .filter_map(|id| parse_relay_host(config, "remote", &id).map(|host| (id, host)))
             .collect();
 
         // Add local delivery host
@@ -347,41 +535,372 @@ impl QueueConfig {
                 port: 0,
                 protocol: ServerProtocol::Http,
                 tls_implicit: Default::default(),
+                tls_required: Default::default(),
                 tls_allow_invalid_certs: Default::default(),
                 auth: None,
             },
         );
 
+        // Parse smart host routes
+        queue.routes = config
+            .sub_keys("queue.route", ".address")
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|id| parse_relay_route(config, &id))
+            .collect();
+
+        // Parse source IP pools
+        queue.ip_pools = config
+            .sub_keys("queue.ip-pool", "")
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|id| {
+                let pool = parse_ip_pool(config, &id);
+                (id, pool)
+            })
+            .collect();
+
+        // Parse encryption keys
+        queue.encryption = QueueEncryption::parse(config);
+
+        // Parse priority classes
+        queue.priority = QueuePriority::parse(config);
+
         queue
     }
 }
 
-fn parse_relay_host(config: &mut Config, id: &str) -> Option<RelayHost> {
+impl QueueAdaptiveThrottle {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut throttle = QueueAdaptiveThrottle {
+            enable: config
+                .property_or_default("queue.outbound.adaptive-throttle.enable", "false")
+                .unwrap_or(false),
+            codes: config
+                .properties::<u16>("queue.outbound.adaptive-throttle.codes")
+                .into_iter()
+                .map(|(_, code)| code)
+                .collect(),
+            min_concurrency: config
+                .property_or_default("queue.outbound.adaptive-throttle.min-concurrency", "1")
+                .unwrap_or(1),
+            max_concurrency: config
+                .property_or_default("queue.outbound.adaptive-throttle.max-concurrency", "10")
+                .unwrap_or(10),
+            max_interval: config
+                .property_or_default("queue.outbound.adaptive-throttle.max-interval", "10m")
+                .unwrap_or_else(|| Duration::from_secs(600)),
+            recovery: config
+                .property_or_default("queue.outbound.adaptive-throttle.recovery", "5m")
+                .unwrap_or_else(|| Duration::from_secs(300)),
+        };
+        if throttle.codes.is_empty() {
+            throttle.codes = QueueAdaptiveThrottle::default().codes;
+        } else if let Some(code) = throttle
+            .codes
+            .iter()
+            .find(|code| !(400..500).contains(*code))
+        {
+            config.new_build_error(
+                "queue.outbound.adaptive-throttle.codes",
+                format!("Invalid temporary failure code {code}"),
+            );
+        }
+        if throttle.min_concurrency == 0 || throttle.min_concurrency > throttle.max_concurrency {
+            config.new_build_error(
+                "queue.outbound.adaptive-throttle.min-concurrency",
+                "Minimum concurrency must be between 1 and the maximum concurrency",
+            );
+            throttle.enable = false;
+        }
+
+        throttle
+    }
+}
+
+impl Default for QueueAdaptiveThrottle {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            codes: vec![421, 450, 451],
+            min_concurrency: 1,
+            max_concurrency: 10,
+            max_interval: Duration::from_secs(600),
+            recovery: Duration::from_secs(300),
+        }
+    }
+}
+
+impl QueuePriority {
+    pub fn parse(config: &mut Config) -> Self {
+        let priority = QueuePriority {
+            high: config
+                .property_or_default::<i16>("queue.priority.high", "1")
+                .unwrap_or(1),
+            bulk: config
+                .property_or_default::<i16>("queue.priority.bulk", "-1")
+                .unwrap_or(-1),
+        };
+        if priority.bulk >= priority.high {
+            config.new_build_error(
+                "queue.priority.bulk",
+                "Bulk priority must be lower than high priority",
+            );
+            QueuePriority::default()
+        } else {
+            priority
+        }
+    }
+
+    // Maps an MT-PRIORITY value (RFC 6710) to its scheduling class
+    pub fn class(&self, priority: i16) -> PriorityClass {
+        if priority >= self.high {
+            PriorityClass::High
+        } else if priority <= self.bulk {
+            PriorityClass::Bulk
+        } else {
+            PriorityClass::Normal
+        }
+    }
+}
+
+impl Default for QueuePriority {
+    fn default() -> Self {
+        Self { high: 1, bulk: -1 }
+    }
+}
+
+impl QueueEncryption {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut secrets = Vec::new();
+        if let Some(secret) = config.value("queue.encryption.key") {
+            secrets.push(secret.to_string());
+        }
+        secrets.extend(
+            config
+                .values("queue.encryption.previous-keys")
+                .map(|(_, secret)| secret.to_string()),
+        );
+
+        let mut enable = config
+            .property_or_default::<bool>("queue.encryption.enable", "false")
+            .unwrap_or_default();
+        if enable && secrets.is_empty() {
+            config.new_build_error(
+                "queue.encryption.key",
+                "Queue encryption is enabled but no key has been provided",
+            );
+            enable = false;
+        }
+
+        QueueEncryption {
+            enable,
+            keys: secrets
+                .into_iter()
+                .map(|secret| {
+                    let key: [u8; 32] = Sha256::digest(secret.as_bytes()).into();
+                    let id: [u8; 32] = Sha256::digest(key).into();
+                    QueueEncryptionKey {
+                        id: id[..4].try_into().unwrap(),
+                        key,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn has_keys(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub fn is_encrypted(message: &[u8]) -> bool {
+        message.starts_with(QUEUE_ENCRYPTION_MAGIC)
+    }
+
+    pub fn encrypt(&self, message: &[u8]) -> trc::Result<Vec<u8>> {
+        let key = self.keys.first().ok_or_else(|| {
+            trc::StoreEvent::CryptoError
+                .into_err()
+                .details("No queue encryption key configured")
+        })?;
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).map_err(|_| {
+            trc::StoreEvent::CryptoError
+                .into_err()
+                .details("Failed to generate nonce")
+        })?;
+
+        let mut output =
+            Vec::with_capacity(QUEUE_ENCRYPTION_HEADER_LEN + message.len() + AES_256_GCM.tag_len());
+        output.extend_from_slice(QUEUE_ENCRYPTION_MAGIC);
+        output.extend_from_slice(&key.id);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(message);
+
+        let mut contents = output.split_off(QUEUE_ENCRYPTION_HEADER_LEN);
+        key.cipher()
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut contents,
+            )
+            .map_err(|_| {
+                trc::StoreEvent::CryptoError
+                    .into_err()
+                    .details("Failed to encrypt message")
+            })?;
+        output.extend_from_slice(&contents);
+
+        Ok(output)
+    }
+
+    pub fn decrypt(&self, mut message: Vec<u8>) -> trc::Result<Vec<u8>> {
+        if !Self::is_encrypted(&message) {
+            return Ok(message);
+        } else if message.len() < QUEUE_ENCRYPTION_HEADER_LEN {
+            return Err(trc::StoreEvent::DataCorruption
+                .into_err()
+                .details("Truncated encrypted message"));
+        }
+
+        let key_id = &message[QUEUE_ENCRYPTION_MAGIC.len()..QUEUE_ENCRYPTION_MAGIC.len() + 4];
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.id == key_id)
+            .ok_or_else(|| {
+                trc::StoreEvent::CryptoError
+                    .into_err()
+                    .details("Message was encrypted with an unknown queue key")
+            })?;
+        let nonce = Nonce::try_assume_unique_for_key(
+            &message[QUEUE_ENCRYPTION_HEADER_LEN - NONCE_LEN..QUEUE_ENCRYPTION_HEADER_LEN],
+        )
+        .map_err(|_| trc::StoreEvent::DataCorruption.into_err())?;
+
+        let mut contents = message.split_off(QUEUE_ENCRYPTION_HEADER_LEN);
+        let len = key
+            .cipher()
+            .open_in_place(nonce, Aad::empty(), &mut contents)
+            .map_err(|_| {
+                trc::StoreEvent::CryptoError
+                    .into_err()
+                    .details("Failed to decrypt message")
+            })?
+            .len();
+        contents.truncate(len);
+
+        Ok(contents)
+    }
+}
+
+impl QueueEncryptionKey {
+    fn cipher(&self) -> LessSafeKey {
+        LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &self.key).unwrap())
+    }
+}
+
+fn parse_relay_host(config: &mut Config, prefix: &str, id: &str) -> Option<RelayHost> {
     Some(RelayHost {
-        address: config.property_require(("remote", id, "address"))?,
-        port: config
-            .property_require(("remote", id, "port"))
-            .unwrap_or(25),
+        address: config.property_require((prefix, id, "address"))?,
+        port: config.property_require((prefix, id, "port")).unwrap_or(25),
         protocol: config
-            .property_require(("remote", id, "protocol"))
+            .property_require((prefix, id, "protocol"))
             .unwrap_or(ServerProtocol::Smtp),
         auth: if let (Some(username), Some(secret)) = (
-            config.value(("remote", id, "auth.username")),
-            config.value(("remote", id, "auth.secret")),
+            config.value((prefix, id, "auth.username")),
+            config.value((prefix, id, "auth.secret")),
         ) {
             Credentials::new(username.to_string(), secret.to_string()).into()
         } else {
             None
         },
         tls_implicit: config
-            .property(("remote", id, "tls.implicit"))
+            .property((prefix, id, "tls.implicit"))
             .unwrap_or(true),
+        tls_required: config
+            .property((prefix, id, "tls.required"))
+            .unwrap_or(false),
         tls_allow_invalid_certs: config
-            .property(("remote", id, "tls.allow-invalid-certs"))
+            .property((prefix, id, "tls.allow-invalid-certs"))
             .unwrap_or(false),
     })
 }
 
+fn parse_relay_route(config: &mut Config, id: &str) -> Option<RelayRoute> {
+    let host = parse_relay_host(config, "queue.route", id)?;
+    let mut domains = [Vec::new(), Vec::new()];
+    for (domains, key) in domains
+        .iter_mut()
+        .zip(["recipient-domains", "sender-domains"])
+    {
+        *domains = config
+            .values(("queue.route", id, key))
+            .map(|(_, domain)| domain.trim().to_lowercase())
+            .filter(|domain| !domain.is_empty())
+            .collect();
+    }
+    let [recipient_domains, sender_domains] = domains;
+
+    if recipient_domains.is_empty() && sender_domains.is_empty() {
+        config.new_build_error(
+            ("queue.route", id),
+            "Route does not match any recipient or sender domains",
+        );
+        return None;
+    }
+
+    Some(RelayRoute {
+        id: id.to_string(),
+        recipient_domains,
+        sender_domains,
+        host,
+    })
+}
+
+fn parse_ip_pool(config: &mut Config, id: &str) -> IpPool {
+    let mut pool = IpPool {
+        strategy: config
+            .property_or_default(("queue.ip-pool", id, "strategy"), "round-robin")
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    // Addresses are optionally followed by their weight, e.g. "192.0.2.1 3"
+    for family in ["v4", "v6"] {
+        for (key, value) in config
+            .values(("queue.ip-pool", id, family))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+        {
+            let (addr, weight) = match value.trim().split_once(' ') {
+                Some((addr, weight)) => (addr, weight.trim().parse::<u32>().ok()),
+                None => (value.trim(), Some(1)),
+            };
+
+            match (addr.parse::<IpAddr>(), weight, family) {
+                (Ok(IpAddr::V4(addr)), Some(weight), "v4") if weight > 0 => {
+                    pool.ipv4.push((addr, weight));
+                }
+                (Ok(IpAddr::V6(addr)), Some(weight), "v6") if weight > 0 => {
+                    pool.ipv6.push((addr, weight));
+                }
+                _ => {
+                    config.new_parse_error(key, format!("Invalid source address {value:?}"));
+                }
+            }
+        }
+    }
+
+    if pool.ipv4.is_empty() && pool.ipv6.is_empty() {
+        config.new_build_error(("queue.ip-pool", id), "IP pool has no source addresses");
+    }
+
+    pool
+}
+
 fn parse_queue_throttle(config: &mut Config) -> QueueThrottle {
     // Parse throttle
     let mut throttle = QueueThrottle {
@@ -535,6 +1054,16 @@ fn parse_queue_quota_item(config: &mut Config, prefix: impl AsKey, id: &str) ->
     }
 }
 
+impl ParseValue for IpPoolStrategy {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "round-robin" => Ok(IpPoolStrategy::RoundRobin),
+            "random" => Ok(IpPoolStrategy::Random),
+            _ => Err(format!("Invalid IP pool strategy {:?}.", value,)),
+        }
+    }
+}
+
 impl ParseValue for RequireOptional {
     fn parse_value(value: &str) -> Result<Self, String> {
         match value {
@@ -628,7 +1157,191 @@ impl std::fmt::Debug for RelayHost {
             .field("port", &self.port)
             .field("protocol", &self.protocol)
             .field("tls_implicit", &self.tls_implicit)
+            .field("tls_required", &self.tls_required)
             .field("tls_allow_invalid_certs", &self.tls_allow_invalid_certs)
             .finish()
     }
 }
+
+impl QueueConfig {
+    // Returns the first route matching the recipient and sender domains, routes
+    // are evaluated in the order of their identifiers.
+    pub fn route(&self, rcpt_domain: &str, sender_domain: &str) -> Option<&RelayRoute> {
+        self.routes.iter().find(|route| {
+            (route.recipient_domains.is_empty()
+                || route
+                    .recipient_domains
+                    .iter()
+                    .any(|pattern| domain_matches(pattern, rcpt_domain)))
+                && (route.sender_domains.is_empty()
+                    || route
+                        .sender_domains
+                        .iter()
+                        .any(|pattern| domain_matches(pattern, sender_domain)))
+        })
+    }
+}
+
+// Matches a domain against an exact name or a "*.example.org" wildcard, which
+// also matches subdomains at any depth.
+fn domain_matches(pattern: &str, domain: &str) -> bool {
+    if let Some(suffix) = pattern.strip_prefix("*.") {
+        domain
+            .strip_suffix(suffix)
+            .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.'))
+    } else {
+        pattern.eq_ignore_ascii_case(domain)
+    }
+}
+
+impl Dsn {
+    pub fn template(&self, set: &str, language: &str) -> &DsnTemplate {
+        let language = language.to_lowercase();
+        let set = self.template_sets.get(set);
+        set.and_then(|templates| find_template(templates, &language))
+            .or_else(|| find_template(&self.templates, &language))
+            .or_else(|| set.and_then(|templates| templates.get("en")))
+            .or_else(|| self.templates.get("en"))
+            .unwrap_or(&DEFAULT_DSN_TEMPLATE)
+    }
+}
+
+fn find_template<'x>(
+    templates: &'x AHashMap<String, DsnTemplate>,
+    language: &str,
+) -> Option<&'x DsnTemplate> {
+    templates.get(language).or_else(|| {
+        language
+            .split_once(['-', '_'])
+            .and_then(|(language, _)| templates.get(language))
+    })
+}
+
+static DEFAULT_DSN_TEMPLATE: std::sync::LazyLock<DsnTemplate> =
+    std::sync::LazyLock::new(DsnTemplate::default);
+
+impl DsnTemplate {
+    fn parse(&mut self, config: &Config, prefix: impl AsKey) {
+        let prefix = prefix.as_key();
+        for (value, key) in [
+            (&mut self.subject_success, "subject.success"),
+            (&mut self.subject_delay, "subject.delay"),
+            (&mut self.subject_failure, "subject.failure"),
+            (&mut self.subject_partial, "subject.partial"),
+            (&mut self.subject_mixed, "subject.mixed"),
+            (&mut self.body_success, "body.success"),
+            (&mut self.body_delay, "body.delay"),
+            (&mut self.body_failure, "body.failure"),
+            (&mut self.body_partial, "body.partial"),
+            (&mut self.body_mixed, "body.mixed"),
+            (&mut self.section_success, "section.success"),
+            (&mut self.section_delay, "section.delay"),
+            (&mut self.section_failure, "section.failure"),
+            (&mut self.recipient_success, "recipient.success"),
+            (&mut self.recipient_delay, "recipient.delay"),
+            (&mut self.recipient_failure, "recipient.failure"),
+            (&mut self.support, "support"),
+        ] {
+            if let Some(text) = config.value((prefix.as_str(), key)) {
+                *value = text.to_string();
+            }
+        }
+    }
+
+    pub fn builtin(language: &str) -> Option<Self> {
+        let [subject_success, subject_delay, subject_failure, subject_partial, subject_mixed, body_success, body_delay, body_failure, body_partial, body_mixed, section_success, section_delay, section_failure, support] =
+            match language {
+                "en" => [
+                    "Successfully delivered message",
+                    "Warning: Delay in message delivery",
+                    "Failed to deliver message",
+                    "Partially delivered message",
+                    "Warning: Temporary and permanent failures during message delivery",
+                    "Your message has been successfully delivered to the following recipients:",
+                    "There was a temporary problem delivering your message to the following recipients:",
+                    "Your message could not be delivered to the following recipients:",
+                    "Your message has been partially delivered:",
+                    "Your message could not be delivered to some recipients:",
+                    "Delivery to the following addresses was successful",
+                    "There was a temporary problem delivering to these addresses",
+                    "Delivery to the following addresses failed",
+                    "If you need assistance, please contact {contact}.",
+                ],
+                "es" => [
+                    "Mensaje entregado correctamente",
+                    "Aviso: Retraso en la entrega del mensaje",
+                    "No se pudo entregar el mensaje",
+                    "Mensaje entregado parcialmente",
+                    "Aviso: Errores temporales y permanentes durante la entrega del mensaje",
+                    "Su mensaje ha sido entregado correctamente a los siguientes destinatarios:",
+                    "Se produjo un problema temporal al entregar su mensaje a los siguientes destinatarios:",
+                    "Su mensaje no pudo ser entregado a los siguientes destinatarios:",
+                    "Su mensaje ha sido entregado parcialmente:",
+                    "Su mensaje no pudo ser entregado a algunos destinatarios:",
+                    "La entrega a las siguientes direcciones se realizó correctamente",
+                    "Se produjo un problema temporal al entregar a estas direcciones",
+                    "La entrega a las siguientes direcciones falló",
+                    "Si necesita ayuda, póngase en contacto con {contact}.",
+                ],
+                "fr" => [
+                    "Message distribué avec succès",
+                    "Avertissement : retard dans la distribution du message",
+                    "Échec de la distribution du message",
+                    "Message partiellement distribué",
+                    "Avertissement : échecs temporaires et permanents lors de la distribution du message",
+                    "Votre message a été distribué avec succès aux destinataires suivants :",
+                    "Un problème temporaire est survenu lors de la distribution de votre message aux destinataires suivants :",
+                    "Votre message n'a pas pu être distribué aux destinataires suivants :",
+                    "Votre message a été partiellement distribué :",
+                    "Votre message n'a pas pu être distribué à certains destinataires :",
+                    "La distribution aux adresses suivantes a réussi",
+                    "Un problème temporaire est survenu lors de la distribution à ces adresses",
+                    "La distribution aux adresses suivantes a échoué",
+                    "Si vous avez besoin d'aide, veuillez contacter {contact}.",
+                ],
+                "de" => [
+                    "Nachricht erfolgreich zugestellt",
+                    "Warnung: Verzögerung bei der Zustellung der Nachricht",
+                    "Nachricht konnte nicht zugestellt werden",
+                    "Nachricht teilweise zugestellt",
+                    "Warnung: Vorübergehende und dauerhafte Fehler bei der Zustellung der Nachricht",
+                    "Ihre Nachricht wurde erfolgreich an die folgenden Empfänger zugestellt:",
+                    "Bei der Zustellung Ihrer Nachricht an die folgenden Empfänger ist ein vorübergehendes Problem aufgetreten:",
+                    "Ihre Nachricht konnte an die folgenden Empfänger nicht zugestellt werden:",
+                    "Ihre Nachricht wurde teilweise zugestellt:",
+                    "Ihre Nachricht konnte an einige Empfänger nicht zugestellt werden:",
+                    "Die Zustellung an die folgenden Adressen war erfolgreich",
+                    "Bei der Zustellung an diese Adressen ist ein vorübergehendes Problem aufgetreten",
+                    "Die Zustellung an die folgenden Adressen ist fehlgeschlagen",
+                    "Wenn Sie Hilfe benötigen, wenden Sie sich bitte an {contact}.",
+                ],
+                _ => return None,
+            };
+
+        Some(DsnTemplate {
+            subject_success: subject_success.to_string(),
+            subject_delay: subject_delay.to_string(),
+            subject_failure: subject_failure.to_string(),
+            subject_partial: subject_partial.to_string(),
+            subject_mixed: subject_mixed.to_string(),
+            body_success: body_success.to_string(),
+            body_delay: body_delay.to_string(),
+            body_failure: body_failure.to_string(),
+            body_partial: body_partial.to_string(),
+            body_mixed: body_mixed.to_string(),
+            section_success: section_success.to_string(),
+            section_delay: section_delay.to_string(),
+            section_failure: section_failure.to_string(),
+            recipient_success: "<{recipient}> ({reason})".to_string(),
+            recipient_delay: "<{recipient}> ({reason})".to_string(),
+            recipient_failure: "<{recipient}> ({reason})".to_string(),
+            support: support.to_string(),
+        })
+    }
+}
+
+impl Default for DsnTemplate {
+    fn default() -> Self {
+        DsnTemplate::builtin("en").unwrap()
+    }
+}
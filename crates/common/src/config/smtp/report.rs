@@ -6,7 +6,7 @@
 
 use std::time::Duration;
 
-use utils::config::{utils::ParseValue, Config};
+use utils::config::{utils::ParseValue, Config, Rate};
 
 use crate::expr::{if_block::IfBlock, tokenizer::TokenMap, Constant, ConstantValue, Variable};
 
@@ -29,6 +29,7 @@ pub struct ReportAnalysis {
     pub addresses: Vec<AddressMatch>,
     pub forward: bool,
     pub store: Option<Duration>,
+    pub http: bool,
 }
 
 #[derive(Clone)]
@@ -56,6 +57,17 @@ pub struct Report {
     pub subject: IfBlock,
     pub sign: IfBlock,
     pub send: IfBlock,
+    pub domain_send: Option<Rate>,
+    pub redact: ReportRedaction,
+}
+
+// How much of the original message headers is included in failure reports (RFC 6590)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportRedaction {
+    #[default]
+    None,
+    Addresses,
+    Headers,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -91,6 +103,9 @@ impl ReportConfig {
                 store: config
                     .property_or_default::<Option<Duration>>("report.analysis.store", "30d")
                     .unwrap_or_default(),
+                http: config
+                    .property("report.analysis.http.enable")
+                    .unwrap_or(false),
             },
             dkim: Report::parse(config, "dkim", &rcpt_vars),
             spf: Report::parse(config, "spf", &sender_vars),
@@ -134,6 +149,12 @@ impl Report {
                 "['rsa-' + key_get('default', 'domain'), 'ed25519-' + key_get('default', 'domain')]",
             ),
             send: IfBlock::new::<()>(format!("report.{id}.send"), [], "[1, 1d]"),
+            domain_send: config
+                .property_or_default::<Option<Rate>>(("report", id, "domain-send"), "false")
+                .unwrap_or_default(),
+            redact: config
+                .property_or_default(("report", id, "redact"), "none")
+                .unwrap_or_default(),
         };
         for (value, key) in [
             (&mut report.name, "from-name"),
@@ -225,6 +246,17 @@ impl ParseValue for AggregateFrequency {
     }
 }
 
+impl ParseValue for ReportRedaction {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "none" | "false" => Ok(ReportRedaction::None),
+            "addresses" | "local-part" => Ok(ReportRedaction::Addresses),
+            "headers" => Ok(ReportRedaction::Headers),
+            _ => Err(format!("Invalid report redaction value {:?}.", value,)),
+        }
+    }
+}
+
 impl From<AggregateFrequency> for Constant {
     fn from(value: AggregateFrequency) -> Self {
         match value {
@@ -40,6 +40,7 @@ pub struct DnssecResolver {
 pub struct DnsRecordCache {
     pub tlsa: LruCache<String, Arc<Tlsa>>,
     pub mta_sts: LruCache<String, Arc<Policy>>,
+    pub bimi: LruCache<String, Arc<Bimi>>,
 }
 
 #[derive(Debug, Hash, PartialEq, Eq)]
@@ -71,6 +72,12 @@ pub enum MxPattern {
     StartsWith(String),
 }
 
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Bimi {
+    pub location: Option<String>,
+    pub authority: Option<String>,
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Policy {
     pub id: String,
@@ -232,23 +239,31 @@ impl Resolvers {
                         .property("cache.resolver.mta-sts.size")
                         .unwrap_or(1024),
                 ),
+                bimi: LruCache::with_capacity(
+                    config.property("cache.resolver.bimi.size").unwrap_or(1024),
+                ),
             },
         }
     }
 }
 
 impl Policy {
-    pub fn try_parse(config: &mut Config) -> Option<Self> {
+    pub fn try_parse(config: &mut Config, prefix: &str) -> Option<Self> {
         let mode = config
-            .property_or_default::<Option<Mode>>("session.mta-sts.mode", "testing")
+            .property_or_default::<Option<Mode>>(format!("{prefix}.mode"), "testing")
             .unwrap_or_default()?;
         let max_age = config
-            .property_or_default::<Duration>("session.mta-sts.max-age", "7d")
+            .property_or_default::<Duration>(format!("{prefix}.max-age"), "7d")
             .unwrap_or_else(|| Duration::from_secs(604800))
             .as_secs();
+        let id = config
+            .value(format!("{prefix}.id"))
+            .filter(|id| !id.is_empty())
+            .map(|id| id.to_string())
+            .unwrap_or_default();
         let mut mx = Vec::new();
 
-        for (_, item) in config.values("session.mta-sts.mx") {
+        for (_, item) in config.values(format!("{prefix}.mx")) {
             if let Some(item) = item.strip_prefix("*.") {
                 mx.push(MxPattern::StartsWith(item.to_string()));
             } else {
@@ -257,15 +272,18 @@ impl Policy {
         }
 
         let mut policy = Self {
-            id: Default::default(),
+            id,
             mode,
             mx,
             max_age,
         };
 
+        // The policy id is derived from its contents unless set explicitly
         if !policy.mx.is_empty() {
             policy.mx.sort_unstable();
-            policy.id = policy.hash().to_string();
+            if policy.id.is_empty() {
+                policy.id = policy.hash().to_string();
+            }
         }
 
         policy.into()
@@ -288,7 +306,9 @@ impl Policy {
 
             if !self.mx.is_empty() {
                 self.mx.sort_unstable();
-                self.id = self.hash().to_string();
+                if self.id.is_empty() {
+                    self.id = self.hash().to_string();
+                }
                 Some(self)
             } else {
                 None
@@ -308,14 +328,20 @@ impl Policy {
 }
 
 impl Core {
-    pub fn build_mta_sts_policy(&self) -> Option<Policy> {
-        self.smtp.session.mta_sts_policy.clone().and_then(|policy| {
-            policy.try_build(self.tls.certificates.load().keys().filter(|key| {
-                !key.starts_with("mta-sts.")
-                    && !key.starts_with("autoconfig.")
-                    && !key.starts_with("autodiscover.")
-            }))
-        })
+    pub fn build_mta_sts_policy(&self, domain: &str) -> Option<Policy> {
+        self.smtp
+            .session
+            .mta_sts_domains
+            .get(domain)
+            .or(self.smtp.session.mta_sts_policy.as_ref())
+            .cloned()
+            .and_then(|policy| {
+                policy.try_build(self.tls.certificates.load().keys().filter(|key| {
+                    !key.starts_with("mta-sts.")
+                        && !key.starts_with("autoconfig.")
+                        && !key.starts_with("autodiscover.")
+                }))
+            })
     }
 }
 
@@ -350,6 +376,7 @@ impl Default for Resolvers {
             cache: DnsRecordCache {
                 tlsa: LruCache::with_capacity(1024),
                 mta_sts: LruCache::with_capacity(1024),
+                bimi: LruCache::with_capacity(1024),
             },
         }
     }
@@ -405,6 +432,7 @@ impl Clone for DnsRecordCache {
         Self {
             tlsa: Mutex::new(self.tlsa.lock().clone()),
             mta_sts: Mutex::new(self.mta_sts.lock().clone()),
+            bimi: Mutex::new(self.bimi.lock().clone()),
         }
     }
 }
@@ -5,12 +5,12 @@
  */
 
 use std::{
-    net::{SocketAddr, ToSocketAddrs},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs},
     str::FromStr,
     time::Duration,
 };
 
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
 use base64::{engine::general_purpose::STANDARD, Engine};
 use hyper::{
     header::{HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
@@ -43,9 +43,11 @@ pub struct SessionConfig {
     pub data: Data,
     pub extensions: Extensions,
     pub mta_sts_policy: Option<Policy>,
+    pub mta_sts_domains: AHashMap<String, Policy>,
 
     pub milters: Vec<Milter>,
     pub hooks: Vec<MTAHook>,
+    pub http_lookups: AHashMap<String, HttpLookup>,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -81,6 +83,7 @@ pub struct Extensions {
     pub future_release: IfBlock,
     pub deliver_by: IfBlock,
     pub mt_priority: IfBlock,
+    pub limits: IfBlock,
 }
 
 #[derive(Clone)]
@@ -98,6 +101,9 @@ pub struct Mail {
     pub script: IfBlock,
     pub rewrite: IfBlock,
     pub is_allowed: IfBlock,
+    pub max_priority: IfBlock,
+    pub role_priority: Vec<(String, i16)>,
+    pub http_lookup: IfBlock,
 }
 
 #[derive(Clone)]
@@ -106,6 +112,7 @@ pub struct Rcpt {
     pub relay: IfBlock,
     pub directory: IfBlock,
     pub rewrite: IfBlock,
+    pub http_lookup: IfBlock,
 
     // Errors
     pub errors_max: IfBlock,
@@ -113,12 +120,46 @@ pub struct Rcpt {
 
     // Limits
     pub max_recipients: IfBlock,
+    pub max_domains: IfBlock,
+
+    // Greylisting
+    pub greylist: Greylist,
+
+    // Call-ahead verification
+    pub call_ahead: CallAhead,
+
+    // Role addresses
+    pub roles: RoleAddresses,
 
     // Catch-all and sub-addressing
     pub catch_all: AddressMapping,
     pub subaddressing: AddressMapping,
 }
 
+#[derive(Clone)]
+pub struct RoleAddresses {
+    pub routing: AHashMap<String, Vec<String>>,
+    pub required: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct Greylist {
+    pub enable: IfBlock,
+    pub delay: Duration,
+    pub expire: Duration,
+    pub allow_expire: Duration,
+    pub ipv4_prefix: u32,
+    pub ipv6_prefix: u32,
+}
+
+#[derive(Clone)]
+pub struct CallAhead {
+    pub host: IfBlock,
+    pub timeout: Duration,
+    pub cache_positive: Duration,
+    pub cache_negative: Duration,
+}
+
 #[derive(Debug, Default, Clone)]
 pub enum AddressMapping {
     Enable,
@@ -193,6 +234,25 @@ pub struct MTAHook {
     pub max_response_size: usize,
 }
 
+#[derive(Clone)]
+pub struct HttpLookup {
+    pub id: String,
+    pub url: String,
+    pub timeout: Duration,
+    pub headers: HeaderMap,
+    pub tls_allow_invalid_certs: bool,
+    pub on_error: HttpLookupFallback,
+    pub tag_header: String,
+    pub max_response_size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpLookupFallback {
+    Accept,
+    Reject,
+    TempFail,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Stage {
     Connect,
@@ -215,6 +275,23 @@ impl SessionConfig {
         let mut session = SessionConfig::default();
         session.rcpt.catch_all = AddressMapping::parse(config, "session.rcpt.catch-all");
         session.rcpt.subaddressing = AddressMapping::parse(config, "session.rcpt.sub-addressing");
+        session.rcpt.greylist.parse(config);
+        session.rcpt.call_ahead.parse(config);
+        session.rcpt.roles = RoleAddresses::parse(config);
+        for role in config
+            .sub_keys("session.mail.role-priority", "")
+            .map(|role| role.to_string())
+            .collect::<Vec<_>>()
+        {
+            if let Some(priority) =
+                config.property::<i16>(("session.mail.role-priority", role.as_str()))
+            {
+                session
+                    .mail
+                    .role_priority
+                    .push((role.to_lowercase(), priority.clamp(-9, 9)));
+            }
+        }
         session.milters = config
             .sub_keys("session.milter", ".hostname")
             .map(|s| s.to_string())
@@ -229,6 +306,13 @@ impl SessionConfig {
             .into_iter()
             .filter_map(|id| parse_hooks(config, &id, &has_rcpt_vars))
             .collect();
+        session.http_lookups = config
+            .sub_keys("session.http-lookup", ".url")
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|id| parse_http_lookup(config, &id).map(|lookup| (id, lookup)))
+            .collect();
         session.data.pipe_commands = config
             .sub_keys("session.data.pipe", "")
             .map(|s| s.to_string())
@@ -237,7 +321,20 @@ impl SessionConfig {
             .filter_map(|id| parse_pipe(config, &id, &has_rcpt_vars))
             .collect();
         session.throttle = SessionThrottle::parse(config);
-        session.mta_sts_policy = Policy::try_parse(config);
+        session.mta_sts_policy = Policy::try_parse(config, "session.mta-sts");
+        for domain in config
+            .sub_keys("session.mta-sts.domain", ".mode")
+            .map(|domain| domain.to_string())
+            .collect::<Vec<_>>()
+        {
+            if let Some(policy) =
+                Policy::try_parse(config, &format!("session.mta-sts.domain.{domain}"))
+            {
+                session
+                    .mta_sts_domains
+                    .insert(domain.to_lowercase(), policy);
+            }
+        }
 
         for (value, key, token_map) in [
             (&mut session.duration, "session.duration", &has_conn_vars),
@@ -312,6 +409,11 @@ impl SessionConfig {
                 "session.extensions.mt-priority",
                 &mt_priority_vars,
             ),
+            (
+                &mut session.extensions.limits,
+                "session.extensions.limits",
+                &has_sender_vars,
+            ),
             (
                 &mut session.ehlo.script,
                 "session.ehlo.script",
@@ -372,6 +474,16 @@ impl SessionConfig {
                 "session.mail.is-allowed",
                 &has_sender_vars,
             ),
+            (
+                &mut session.mail.max_priority,
+                "session.mail.max-priority",
+                &has_sender_vars,
+            ),
+            (
+                &mut session.mail.http_lookup,
+                "session.mail.http-lookup",
+                &has_sender_vars,
+            ),
             (
                 &mut session.rcpt.script,
                 "session.rcpt.script",
@@ -400,13 +512,23 @@ impl SessionConfig {
             (
                 &mut session.rcpt.max_recipients,
                 "session.rcpt.max-recipients",
-                &has_sender_vars,
+                &has_rcpt_vars,
+            ),
+            (
+                &mut session.rcpt.max_domains,
+                "session.rcpt.max-domains",
+                &has_rcpt_vars,
             ),
             (
                 &mut session.rcpt.rewrite,
                 "session.rcpt.rewrite",
                 &has_rcpt_vars,
             ),
+            (
+                &mut session.rcpt.http_lookup,
+                "session.rcpt.http-lookup",
+                &has_rcpt_vars,
+            ),
             (
                 &mut session.data.script,
                 "session.data.script",
@@ -467,6 +589,153 @@ impl SessionConfig {
     }
 }
 
+impl RoleAddresses {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut roles = RoleAddresses::default();
+
+        for role in config
+            .sub_keys("session.rcpt.role-routing", "")
+            .map(|role| role.to_string())
+            .collect::<Vec<_>>()
+        {
+            let targets = config
+                .values(("session.rcpt.role-routing", role.as_str()))
+                .map(|(_, target)| target.trim().to_lowercase())
+                .filter(|target| !target.is_empty())
+                .collect::<Vec<_>>();
+            if !targets.is_empty() {
+                roles.routing.insert(role.to_lowercase(), targets);
+            }
+        }
+
+        let required = config
+            .values("session.rcpt.required-roles")
+            .map(|(_, role)| role.trim().to_lowercase())
+            .filter(|role| !role.is_empty())
+            .collect::<Vec<_>>();
+        if !required.is_empty() {
+            roles.required = required;
+        }
+
+        roles
+    }
+
+    pub fn targets(&self, address: &str) -> Option<&[String]> {
+        address
+            .rsplit_once('@')
+            .and_then(|(local_part, _)| self.routing.get(&local_part.to_lowercase()))
+            .map(|targets| targets.as_slice())
+    }
+}
+
+impl Default for RoleAddresses {
+    fn default() -> Self {
+        Self {
+            routing: AHashMap::new(),
+            required: vec!["postmaster".to_string(), "abuse".to_string()],
+        }
+    }
+}
+
+impl Greylist {
+    pub fn parse(&mut self, config: &mut Config) {
+        if let Some(enable) = IfBlock::try_parse(
+            config,
+            "session.rcpt.greylist.enable",
+            &TokenMap::default().with_variables(SMTP_RCPT_TO_VARS),
+        ) {
+            self.enable = enable;
+        }
+        self.delay = config
+            .property_or_default("session.rcpt.greylist.delay", "5m")
+            .unwrap_or(self.delay);
+        self.expire = config
+            .property_or_default("session.rcpt.greylist.expire", "1d")
+            .unwrap_or(self.expire);
+        self.allow_expire = config
+            .property_or_default("session.rcpt.greylist.allow-list.expire", "30d")
+            .unwrap_or(self.allow_expire);
+        self.ipv4_prefix = config
+            .property_or_default::<u32>("session.rcpt.greylist.prefix.ipv4", "24")
+            .unwrap_or(self.ipv4_prefix)
+            .min(32);
+        self.ipv6_prefix = config
+            .property_or_default::<u32>("session.rcpt.greylist.prefix.ipv6", "64")
+            .unwrap_or(self.ipv6_prefix)
+            .min(128);
+    }
+
+    pub fn network(&self, ip: IpAddr) -> String {
+        match ip {
+            IpAddr::V4(ip) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.ipv4_prefix)
+                    .unwrap_or_default();
+                format!(
+                    "{}/{}",
+                    Ipv4Addr::from(u32::from(ip) & mask),
+                    self.ipv4_prefix
+                )
+            }
+            IpAddr::V6(ip) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.ipv6_prefix)
+                    .unwrap_or_default();
+                format!(
+                    "{}/{}",
+                    Ipv6Addr::from(u128::from(ip) & mask),
+                    self.ipv6_prefix
+                )
+            }
+        }
+    }
+}
+
+impl Default for Greylist {
+    fn default() -> Self {
+        Self {
+            enable: IfBlock::new::<()>("session.rcpt.greylist.enable", [], "false"),
+            delay: Duration::from_secs(5 * 60),
+            expire: Duration::from_secs(24 * 60 * 60),
+            allow_expire: Duration::from_secs(30 * 24 * 60 * 60),
+            ipv4_prefix: 24,
+            ipv6_prefix: 64,
+        }
+    }
+}
+
+impl CallAhead {
+    pub fn parse(&mut self, config: &mut Config) {
+        if let Some(host) = IfBlock::try_parse(
+            config,
+            "session.rcpt.call-ahead.host",
+            &TokenMap::default().with_variables(SMTP_RCPT_TO_VARS),
+        ) {
+            self.host = host;
+        }
+        self.timeout = config
+            .property_or_default("session.rcpt.call-ahead.timeout", "30s")
+            .unwrap_or(self.timeout);
+        self.cache_positive = config
+            .property_or_default("session.rcpt.call-ahead.cache.positive", "1d")
+            .unwrap_or(self.cache_positive);
+        self.cache_negative = config
+            .property_or_default("session.rcpt.call-ahead.cache.negative", "1h")
+            .unwrap_or(self.cache_negative);
+    }
+}
+
+impl Default for CallAhead {
+    fn default() -> Self {
+        Self {
+            host: IfBlock::new::<()>("session.rcpt.call-ahead.host", [], "false"),
+            timeout: Duration::from_secs(30),
+            cache_positive: Duration::from_secs(24 * 60 * 60),
+            cache_negative: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
 impl SessionThrottle {
     pub fn parse(config: &mut Config) -> Self {
         let mut throttle = SessionThrottle::default();
@@ -598,49 +867,7 @@ fn parse_milter(config: &mut Config, id: &str, token_map: &TokenMap) -> Option<M
 }
 
 fn parse_hooks(config: &mut Config, id: &str, token_map: &TokenMap) -> Option<MTAHook> {
-    let mut headers = HeaderMap::new();
-
-    for (header, value) in config
-        .values(("session.hook", id, "headers"))
-        .map(|(_, v)| {
-            if let Some((k, v)) = v.split_once(':') {
-                Ok((
-                    HeaderName::from_str(k.trim()).map_err(|err| {
-                        format!(
-                            "Invalid header found in property \"session.hook.{id}.headers\": {err}",
-                        )
-                    })?,
-                    HeaderValue::from_str(v.trim()).map_err(|err| {
-                        format!(
-                            "Invalid header found in property \"session.hook.{id}.headers\": {err}",
-                        )
-                    })?,
-                ))
-            } else {
-                Err(format!(
-                    "Invalid header found in property \"session.hook.{id}.headers\": {v}",
-                ))
-            }
-        })
-        .collect::<Result<Vec<(HeaderName, HeaderValue)>, String>>()
-        .map_err(|e| config.new_parse_error(("session.hook", id, "headers"), e))
-        .unwrap_or_default()
-    {
-        headers.insert(header, value);
-    }
-
-    headers.insert(CONTENT_TYPE, "application/json".parse().unwrap());
-    if let (Some(name), Some(secret)) = (
-        config.value(("session.hook", id, "auth.username")),
-        config.value(("session.hook", id, "auth.secret")),
-    ) {
-        headers.insert(
-            AUTHORIZATION,
-            format!("Basic {}", STANDARD.encode(format!("{}:{}", name, secret)))
-                .parse()
-                .unwrap(),
-        );
-    }
+    let headers = parse_http_headers(config, "session.hook", id);
 
     Some(MTAHook {
         enable: IfBlock::try_parse(config, ("session.hook", id, "enable"), token_map)
@@ -671,6 +898,86 @@ fn parse_hooks(config: &mut Config, id: &str, token_map: &TokenMap) -> Option<MT
     })
 }
 
+fn parse_http_headers(config: &mut Config, prefix: &str, id: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    for (header, value) in
+        config
+            .values((prefix, id, "headers"))
+            .map(|(_, v)| {
+                if let Some((k, v)) = v.split_once(':') {
+                    Ok((
+                        HeaderName::from_str(k.trim()).map_err(|err| {
+                            format!(
+                                "Invalid header found in property \"{prefix}.{id}.headers\": {err}",
+                            )
+                        })?,
+                        HeaderValue::from_str(v.trim()).map_err(|err| {
+                            format!(
+                                "Invalid header found in property \"{prefix}.{id}.headers\": {err}",
+                            )
+                        })?,
+                    ))
+                } else {
+                    Err(format!(
+                        "Invalid header found in property \"{prefix}.{id}.headers\": {v}",
+                    ))
+                }
+            })
+            .collect::<Result<Vec<(HeaderName, HeaderValue)>, String>>()
+            .map_err(|e| config.new_parse_error((prefix, id, "headers"), e))
+            .unwrap_or_default()
+    {
+        headers.insert(header, value);
+    }
+
+    headers.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    if let (Some(name), Some(secret)) = (
+        config.value((prefix, id, "auth.username")),
+        config.value((prefix, id, "auth.secret")),
+    ) {
+        headers.insert(
+            AUTHORIZATION,
+            format!("Basic {}", STANDARD.encode(format!("{}:{}", name, secret)))
+                .parse()
+                .unwrap(),
+        );
+    }
+
+    headers
+}
+
+fn parse_http_lookup(config: &mut Config, id: &str) -> Option<HttpLookup> {
+    let headers = parse_http_headers(config, "session.http-lookup", id);
+
+    Some(HttpLookup {
+        id: id.to_string(),
+        url: config
+            .value_require(("session.http-lookup", id, "url"))?
+            .to_string(),
+        timeout: config
+            .property_or_default(("session.http-lookup", id, "timeout"), "10s")
+            .unwrap_or_else(|| Duration::from_secs(10)),
+        tls_allow_invalid_certs: config
+            .property_or_default(("session.http-lookup", id, "allow-invalid-certs"), "false")
+            .unwrap_or_default(),
+        on_error: config
+            .property_or_default(("session.http-lookup", id, "options.on-error"), "tempfail")
+            .unwrap_or(HttpLookupFallback::TempFail),
+        tag_header: config
+            .value(("session.http-lookup", id, "options.tag-header"))
+            .unwrap_or("X-Lookup-Tag")
+            .to_string(),
+        max_response_size: config
+            .property_or_default(
+                ("session.http-lookup", id, "options.max-response-size"),
+                "1048576",
+            )
+            .unwrap_or(1048576),
+        headers,
+    })
+}
+
 fn parse_stages(config: &mut Config, prefix: &str, id: &str) -> AHashSet<Stage> {
     let mut stages = AHashSet::default();
     let mut invalid = Vec::new();
@@ -772,6 +1079,13 @@ impl Default for SessionConfig {
                     [],
                     "!is_empty(authenticated_as) || !key_exists('spam-block', sender_domain)",
                 ),
+                max_priority: IfBlock::new::<()>(
+                    "session.mail.max-priority",
+                    [("!is_empty(authenticated_as)", "6")],
+                    "0",
+                ),
+                role_priority: Vec::new(),
+                http_lookup: IfBlock::empty("session.mail.http-lookup"),
             },
             rcpt: Rcpt {
                 script: IfBlock::empty("session.rcpt.script"),
@@ -789,9 +1103,14 @@ impl Default for SessionConfig {
                     "'*'",
                 ),
                 rewrite: IfBlock::empty("session.rcpt.rewrite"),
+                http_lookup: IfBlock::empty("session.rcpt.http-lookup"),
                 errors_max: IfBlock::new::<()>("session.rcpt.errors.total", [], "5"),
                 errors_wait: IfBlock::new::<()>("session.rcpt.errors.wait", [], "5s"),
                 max_recipients: IfBlock::new::<()>("session.rcpt.max-recipients", [], "100"),
+                max_domains: IfBlock::new::<()>("session.rcpt.max-domains", [], "0"),
+                greylist: Greylist::default(),
+                call_ahead: CallAhead::default(),
+                roles: RoleAddresses::default(),
                 catch_all: AddressMapping::Enable,
                 subaddressing: AddressMapping::Enable,
             },
@@ -865,7 +1184,13 @@ impl Default for SessionConfig {
                 no_soliciting: IfBlock::new::<()>("session.extensions.no-soliciting", [], "''"),
                 future_release: IfBlock::new::<()>(
                     "session.extensions.future-release",
-                    [("!is_empty(authenticated_as)", "7d")],
+                    [
+                        ("!is_empty(authenticated_as)", "7d"),
+                        (
+                            "listener == 'submission' || listener == 'submissions'",
+                            "7d",
+                        ),
+                    ],
                     "false",
                 ),
                 deliver_by: IfBlock::new::<()>(
@@ -878,10 +1203,13 @@ impl Default for SessionConfig {
                     [("!is_empty(authenticated_as)", "mixer")],
                     "false",
                 ),
+                limits: IfBlock::new::<()>("session.extensions.limits", [], "true"),
             },
             mta_sts_policy: None,
+            mta_sts_domains: Default::default(),
             milters: Default::default(),
             hooks: Default::default(),
+            http_lookups: Default::default(),
         }
     }
 }
@@ -889,6 +1217,17 @@ impl Default for SessionConfig {
 #[derive(Default)]
 pub struct Mechanism(u64);
 
+impl ParseValue for HttpLookupFallback {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "accept" => Ok(HttpLookupFallback::Accept),
+            "reject" => Ok(HttpLookupFallback::Reject),
+            "tempfail" => Ok(HttpLookupFallback::TempFail),
+            _ => Err(format!("Invalid HTTP lookup fallback action {value:?}.")),
+        }
+    }
+}
+
 impl ParseValue for Mechanism {
     fn parse_value(value: &str) -> Result<Self, String> {
         Ok(Mechanism(match value.to_ascii_uppercase().as_str() {
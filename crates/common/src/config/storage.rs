@@ -12,6 +12,11 @@ use store::{write::purge::PurgeSchedule, BlobStore, FtsStore, LookupStore, Store
 
 use crate::manager::config::ConfigManager;
 
+use super::{
+    disk::DiskWatchdog, feeds::ThreatFeed, health::HealthCheck, maintenance::Maintenance,
+    replication::Replication,
+};
+
 #[derive(Default, Clone)]
 pub struct Storage {
     pub data: Store,
@@ -21,6 +26,11 @@ pub struct Storage {
     pub directory: Arc<Directory>,
     pub directories: AHashMap<String, Arc<Directory>>,
     pub purge_schedules: Vec<PurgeSchedule>,
+    pub feeds: Vec<ThreatFeed>,
+    pub health: HealthCheck,
+    pub disk: DiskWatchdog,
+    pub maintenance: Maintenance,
+    pub replication: Replication,
     pub config: ConfigManager,
 
     pub stores: AHashMap<String, Store>,
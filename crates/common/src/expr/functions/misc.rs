@@ -48,3 +48,7 @@ pub(crate) fn fn_ip_reverse_name(v: Vec<Variable>) -> Variable {
         .unwrap_or_default()
         .into()
 }
+
+pub(crate) fn fn_date(_: Vec<Variable>) -> Variable {
+    chrono::Utc::now().to_rfc2822().into()
+}
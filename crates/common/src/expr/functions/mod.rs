@@ -77,6 +77,7 @@ pub(crate) const FUNCTIONS: &[(&str, fn(Vec<Variable>) -> Variable, u32)] = &[
     ("split_once", text::fn_split_once, 2),
     ("rsplit_once", text::fn_rsplit_once, 2),
     ("split_words", text::fn_split_words, 1),
+    ("date", misc::fn_date, 0),
 ];
 
 pub const F_IS_LOCAL_DOMAIN: u32 = 0;
@@ -353,6 +353,7 @@ impl TokenMap {
             V_QUEUE_EXPIRES_IN,
             V_QUEUE_LAST_STATUS,
             V_QUEUE_LAST_ERROR,
+            V_MESSAGE_CLASS,
         ])
     }
 
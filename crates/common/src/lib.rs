@@ -7,6 +7,7 @@
 use std::{
     borrow::Cow,
     net::IpAddr,
+    ops::Range,
     sync::{atomic::AtomicU8, Arc},
 };
 
@@ -26,13 +27,15 @@ use config::{
     telemetry::Metrics,
 };
 use directory::{
-    backend::internal::manage::ManageDirectory, core::secret::verify_secret_hash, Directory,
-    Principal, QueryBy, Type,
+    backend::internal::{manage::ManageDirectory, PrincipalField},
+    core::secret::verify_secret_hash,
+    Directory, Permission, Principal, QueryBy, Type,
 };
 use expr::if_block::IfBlock;
 use futures::StreamExt;
 use listener::{
-    blocked::{AllowedIps, BlockedIps},
+    blocked::{AllowedIps, BlockedIps, IpFeed, TrustedNetworks},
+    tarpit::Tarpit,
     tls::TlsManager,
 };
 use mail_send::Credentials;
@@ -40,6 +43,7 @@ use mail_send::Credentials;
 use manager::webadmin::Resource;
 use parking_lot::Mutex;
 use reqwest::Response;
+use scripts::verdict::FilterVerdict;
 use sieve::Sieve;
 use store::{
     write::{QueueClass, ValueClass},
@@ -94,6 +98,7 @@ pub struct Security {
     pub access_tokens: TtlDashMap<u32, Arc<AccessToken>>,
     pub permissions: ADashMap<u32, Arc<RolePermissions>>,
     pub permissions_version: AtomicU8,
+    pub access_tokens_version: AtomicU8,
 }
 
 #[derive(Clone)]
@@ -101,8 +106,15 @@ pub struct Network {
     pub node_id: u64,
     pub blocked_ips: BlockedIps,
     pub allowed_ips: AllowedIps,
+    pub trusted_networks: TrustedNetworks,
     pub http_response_url: IfBlock,
     pub http_allowed_endpoint: IfBlock,
+    pub imap_greeting: IfBlock,
+    pub pop3_greeting: IfBlock,
+    pub sieve_greeting: IfBlock,
+    pub hide_version: bool,
+    pub tarpit: Tarpit,
+    pub ip_feeds: Vec<IpFeed>,
 }
 
 #[derive(Debug)]
@@ -124,6 +136,7 @@ pub struct IngestMessage {
     pub recipients: Vec<String>,
     pub message_blob: BlobHash,
     pub message_size: usize,
+    pub verdict: FilterVerdict,
     pub session_id: u64,
 }
 
@@ -332,6 +345,51 @@ impl Core {
             _ => {}
         }
 
+        // Full delegates can open another account by logging in as "account%delegate"
+        if let Credentials::Plain { username, secret } = credentials {
+            if let Some((account_name, delegate_name)) = username
+                .split_once('%')
+                .filter(|(account, delegate)| !account.is_empty() && !delegate.is_empty())
+            {
+                if let Some(delegate) = directory
+                    .query(
+                        QueryBy::Credentials(&Credentials::Plain {
+                            username: delegate_name.to_string(),
+                            secret: secret.to_string(),
+                        }),
+                        true,
+                    )
+                    .await?
+                {
+                    // Only individual accounts can be delegated, membership in a group
+                    // or mailing list does not grant access to log in as it
+                    if let Some(principal) = directory
+                        .query(QueryBy::Name(account_name), return_member_of)
+                        .await?
+                        .filter(|principal| principal.typ() == Type::Individual)
+                    {
+                        if delegate.has_int_value(PrincipalField::MemberOf, principal.id() as u64)
+                            || self
+                                .get_cached_access_token(delegate.id())
+                                .await?
+                                .has_permission(Permission::Impersonate)
+                        {
+                            trc::event!(
+                                Auth(trc::AuthEvent::Success),
+                                AccountName = account_name.to_string(),
+                                SpanId = session_id,
+                                AccountId = principal.id(),
+                                Type = principal.typ().as_str(),
+                                Details = delegate_name.to_string(),
+                            );
+
+                            return Ok(principal);
+                        }
+                    }
+                }
+            }
+        }
+
         if let Err(err) = result {
             Err(err)
         } else if self.has_auth_fail2ban() {
@@ -353,6 +411,28 @@ impl Core {
         }
     }
 
+    pub async fn get_queued_message(
+        &self,
+        hash: &[u8],
+        range: Range<usize>,
+    ) -> trc::Result<Option<Vec<u8>>> {
+        let encryption = &self.smtp.queue.encryption;
+        if !encryption.has_keys() {
+            return self.storage.blob.get_blob(hash, range).await;
+        }
+
+        // Encrypted messages have to be fetched in full before slicing
+        match self.storage.blob.get_blob(hash, 0..usize::MAX).await? {
+            Some(message) => {
+                let mut message = encryption.decrypt(message).caused_by(trc::location!())?;
+                message.truncate(range.end);
+                message.drain(..range.start.min(message.len()));
+                Ok(Some(message))
+            }
+            None => Ok(None),
+        }
+    }
+
     pub async fn total_queued_messages(&self) -> trc::Result<u64> {
         let mut total = 0;
         self.storage
@@ -413,6 +493,10 @@ impl Clone for Security {
                 self.permissions_version
                     .load(std::sync::atomic::Ordering::Relaxed),
             ),
+            access_tokens_version: AtomicU8::new(
+                self.access_tokens_version
+                    .load(std::sync::atomic::Ordering::Relaxed),
+            ),
             logos: Mutex::new(self.logos.lock().clone()),
         }
     }
@@ -4,67 +4,88 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{fmt::Debug, net::IpAddr, sync::atomic::AtomicU8};
+use std::{fmt::Debug, net::IpAddr, sync::atomic::AtomicU8, time::Duration};
 
 use ahash::AHashSet;
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use store::write::now;
 use utils::config::{
+    cron::SimpleCron,
     ipmask::{IpAddrMask, IpAddrOrMask},
     utils::ParseValue,
     Config, ConfigKey, Rate,
 };
 
-use crate::Core;
+use crate::{Core, HttpLimitResponse, USER_AGENT};
 
 pub struct BlockedIps {
     pub ip_addresses: RwLock<AHashSet<IpAddr>>,
     pub version: AtomicU8,
-    ip_networks: Vec<IpAddrMask>,
-    has_networks: bool,
+    ip_networks: RwLock<Vec<IpAddrMask>>,
     auth_fail_rate: Option<Rate>,
     rcpt_fail_rate: Option<Rate>,
     loiter_fail_rate: Option<Rate>,
+    early_talker_fail_rate: Option<Rate>,
 }
 
-#[derive(Clone)]
 pub struct AllowedIps {
+    ip_addresses: RwLock<AHashSet<IpAddr>>,
+    ip_networks: RwLock<Vec<IpAddrMask>>,
+}
+
+// Networks whose clients may relay, skip authentication and
+// have their spam and virus status headers trusted
+#[derive(Clone, Default)]
+pub struct TrustedNetworks {
     ip_addresses: AHashSet<IpAddr>,
     ip_networks: Vec<IpAddrMask>,
-    has_networks: bool,
+    lookup: Option<String>,
 }
 
 pub const BLOCKED_IP_KEY: &str = "server.blocked-ip";
 pub const BLOCKED_IP_PREFIX: &str = "server.blocked-ip.";
 pub const ALLOWED_IP_KEY: &str = "server.allowed-ip";
 pub const ALLOWED_IP_PREFIX: &str = "server.allowed-ip.";
+pub const TRUSTED_NETWORKS_KEY: &str = "server.trusted-networks";
+pub const TRUSTED_NETWORKS_LOOKUP_KEY: &str = "server.trusted-networks-lookup";
+
+const MAX_FEED_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpList {
+    Blocked,
+    Allowed,
+}
+
+// Entries are stored as "server.<list>-ip.<ip|cidr>" with an optional
+// "expires=<timestamp>;" header followed by the comment as the value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpListEntry {
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IpFeed {
+    pub id: String,
+    pub url: String,
+    pub list: IpList,
+    pub frequency: SimpleCron,
+    pub expires: Option<Duration>,
+}
 
 impl BlockedIps {
     pub fn parse(config: &mut Config) -> Self {
-        let mut ip_addresses = AHashSet::new();
-        let mut ip_networks = Vec::new();
-
-        for ip in config
-            .set_values(BLOCKED_IP_KEY)
-            .map(IpAddrOrMask::parse_value)
-            .collect::<Vec<_>>()
-        {
-            match ip {
-                Ok(IpAddrOrMask::Ip(ip)) => {
-                    ip_addresses.insert(ip);
-                }
-                Ok(IpAddrOrMask::Mask(ip)) => {
-                    ip_networks.push(ip);
-                }
-                Err(err) => {
-                    config.new_parse_error(BLOCKED_IP_KEY, err);
-                }
-            }
-        }
+        let (ip_addresses, ip_networks) = parse_ip_list(config, IpList::Blocked);
 
         BlockedIps {
             ip_addresses: RwLock::new(ip_addresses),
-            has_networks: !ip_networks.is_empty(),
-            ip_networks,
+            ip_networks: RwLock::new(ip_networks),
             auth_fail_rate: config
                 .property_or_default::<Option<Rate>>("server.fail2ban.authentication", "100/1d")
                 .unwrap_or_default(),
@@ -74,19 +95,182 @@ impl BlockedIps {
             loiter_fail_rate: config
                 .property_or_default::<Option<Rate>>("server.fail2ban.loitering", "150/1d")
                 .unwrap_or_default(),
+            early_talker_fail_rate: config
+                .property_or_default::<Option<Rate>>("server.fail2ban.early-talker", "25/1d")
+                .unwrap_or_default(),
             version: 0.into(),
         }
     }
+
+    pub(crate) fn update(&self, (ip_addresses, ip_networks): (AHashSet<IpAddr>, Vec<IpAddrMask>)) {
+        *self.ip_addresses.write() = ip_addresses;
+        *self.ip_networks.write() = ip_networks;
+    }
 }
 
 impl AllowedIps {
+    pub fn parse(config: &mut Config) -> Self {
+        let allowed_ips = AllowedIps::default();
+        allowed_ips.update(parse_ip_list(config, IpList::Allowed));
+        allowed_ips
+    }
+
+    pub(crate) fn update(&self, (ip_addresses, ip_networks): (AHashSet<IpAddr>, Vec<IpAddrMask>)) {
+        #[allow(unused_mut)]
+        let mut ip_addresses = ip_addresses;
+
+        #[cfg(not(feature = "test_mode"))]
+        {
+            // Add loopback addresses
+            ip_addresses.insert(IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
+            ip_addresses.insert(IpAddr::V6(std::net::Ipv6Addr::LOCALHOST));
+        }
+
+        *self.ip_addresses.write() = ip_addresses;
+        *self.ip_networks.write() = ip_networks;
+    }
+}
+
+// Parses the addresses and networks of a list, skipping expired entries
+pub(crate) fn parse_ip_list(
+    config: &mut Config,
+    list: IpList,
+) -> (AHashSet<IpAddr>, Vec<IpAddrMask>) {
+    let mut ip_addresses = AHashSet::new();
+    let mut ip_networks = Vec::new();
+    let now = now();
+
+    for ip in config
+        .iterate_prefix(list.key())
+        .map(|(address, value)| IpListEntry::parse(address, value))
+        .filter(|entry| !entry.is_expired(now))
+        .map(|entry| IpAddrOrMask::parse_value(&entry.address))
+        .collect::<Vec<_>>()
+    {
+        match ip {
+            Ok(IpAddrOrMask::Ip(ip)) => {
+                ip_addresses.insert(ip);
+            }
+            Ok(IpAddrOrMask::Mask(ip)) => {
+                ip_networks.push(ip);
+            }
+            Err(err) => {
+                config.new_parse_error(list.key(), err);
+            }
+        }
+    }
+
+    (ip_addresses, ip_networks)
+}
+
+impl IpList {
+    pub fn key(&self) -> &'static str {
+        match self {
+            IpList::Blocked => BLOCKED_IP_KEY,
+            IpList::Allowed => ALLOWED_IP_KEY,
+        }
+    }
+
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            IpList::Blocked => BLOCKED_IP_PREFIX,
+            IpList::Allowed => ALLOWED_IP_PREFIX,
+        }
+    }
+}
+
+impl ParseValue for IpList {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "blocked" | "block" | "deny" => Ok(IpList::Blocked),
+            "allowed" | "allow" => Ok(IpList::Allowed),
+            _ => Err(format!("Invalid IP list {value:?}.")),
+        }
+    }
+}
+
+impl IpListEntry {
+    pub fn parse(address: &str, value: &str) -> Self {
+        let (expires, comment) = match value
+            .strip_prefix("expires=")
+            .and_then(|value| value.split_once(';'))
+            .and_then(|(expires, comment)| Some((expires.trim().parse::<u64>().ok()?, comment)))
+        {
+            Some((expires, comment)) => (Some(expires), comment.trim()),
+            None => (None, value.trim()),
+        };
+
+        IpListEntry {
+            address: address.to_string(),
+            comment: (!comment.is_empty()).then(|| comment.to_string()),
+            expires,
+        }
+    }
+
+    pub fn value(&self) -> String {
+        let comment = self.comment.as_deref().unwrap_or_default();
+        if let Some(expires) = self.expires {
+            format!("expires={expires};{comment}")
+        } else {
+            comment.to_string()
+        }
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        matches!(self.expires, Some(expires) if expires <= now)
+    }
+}
+
+impl IpFeed {
+    pub fn parse_all(config: &mut Config) -> Vec<IpFeed> {
+        let mut feeds = Vec::new();
+
+        for id in config
+            .sub_keys("server.ip-feed", ".url")
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+        {
+            let url = config
+                .value_require(("server.ip-feed", id.as_str(), "url"))
+                .unwrap_or_default()
+                .to_string();
+            if let (Some(list), Some(frequency)) = (
+                config.property_or_default::<IpList>(
+                    ("server.ip-feed", id.as_str(), "list"),
+                    "blocked",
+                ),
+                config.property_or_default::<SimpleCron>(
+                    ("server.ip-feed", id.as_str(), "frequency"),
+                    "0 * *",
+                ),
+            ) {
+                feeds.push(IpFeed {
+                    url,
+                    list,
+                    frequency,
+                    expires: config
+                        .property_or_default::<Option<Duration>>(
+                            ("server.ip-feed", id.as_str(), "expires"),
+                            "1d",
+                        )
+                        .unwrap_or_default(),
+                    id,
+                });
+            }
+        }
+
+        feeds
+    }
+}
+
+impl TrustedNetworks {
     pub fn parse(config: &mut Config) -> Self {
         let mut ip_addresses = AHashSet::new();
         let mut ip_networks = Vec::new();
 
         for ip in config
-            .set_values(ALLOWED_IP_KEY)
-            .map(IpAddrOrMask::parse_value)
+            .values(TRUSTED_NETWORKS_KEY)
+            .map(|(_, ip)| IpAddrOrMask::parse_value(ip))
             .collect::<Vec<_>>()
         {
             match ip {
@@ -97,24 +281,28 @@ impl AllowedIps {
                     ip_networks.push(ip);
                 }
                 Err(err) => {
-                    config.new_parse_error(ALLOWED_IP_KEY, err);
+                    config.new_parse_error(TRUSTED_NETWORKS_KEY, err);
                 }
             }
         }
 
-        #[cfg(not(feature = "test_mode"))]
-        {
-            // Add loopback addresses
-            ip_addresses.insert(IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
-            ip_addresses.insert(IpAddr::V6(std::net::Ipv6Addr::LOCALHOST));
-        }
-
-        AllowedIps {
+        TrustedNetworks {
             ip_addresses,
-            has_networks: !ip_networks.is_empty(),
             ip_networks,
+            lookup: config
+                .value(TRUSTED_NETWORKS_LOOKUP_KEY)
+                .filter(|id| !id.is_empty())
+                .map(|id| id.to_string()),
         }
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.ip_addresses.is_empty() && self.ip_networks.is_empty() && self.lookup.is_none()
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.ip_addresses.contains(ip) || self.ip_networks.iter().any(|network| network.matches(ip))
+    }
 }
 
 impl Core {
@@ -154,6 +342,24 @@ impl Core {
         Ok(false)
     }
 
+    pub async fn is_early_talker_fail2banned(&self, ip: IpAddr) -> trc::Result<bool> {
+        if let Some(rate) = &self.network.blocked_ips.early_talker_fail_rate {
+            let is_allowed = self.is_ip_allowed(&ip)
+                || self
+                    .storage
+                    .lookup
+                    .is_rate_allowed(format!("e:{ip}").as_bytes(), rate, false)
+                    .await?
+                    .is_none();
+
+            if !is_allowed {
+                return self.block_ip(ip).await.map(|_| true);
+            }
+        }
+
+        Ok(false)
+    }
+
     pub async fn is_auth_fail2banned(&self, ip: IpAddr, login: &str) -> trc::Result<bool> {
         if let Some(rate) = &self.network.blocked_ips.auth_fail_rate {
             let is_allowed = self.is_ip_allowed(&ip)
@@ -196,30 +402,222 @@ impl Core {
         Ok(())
     }
 
+    // Blocks an IP address immediately, optionally until the given timestamp
+    pub async fn block_ip_entry(
+        &self,
+        ip: IpAddr,
+        expires: Option<u64>,
+        comment: Option<String>,
+    ) -> trc::Result<bool> {
+        if self.is_ip_allowed(&ip) || self.is_ip_trusted(&ip, 0).await {
+            return Ok(false);
+        }
+
+        self.network.blocked_ips.ip_addresses.write().insert(ip);
+        self.add_ip_entries(
+            IpList::Blocked,
+            [IpListEntry {
+                address: ip.to_string(),
+                comment,
+                expires,
+            }],
+        )
+        .await?;
+        self.network.blocked_ips.increment_version();
+
+        Ok(true)
+    }
+
+    pub async fn list_ip_entries(&self, list: IpList) -> trc::Result<Vec<IpListEntry>> {
+        let now = now();
+
+        self.storage
+            .config
+            .list(list.prefix(), true)
+            .await
+            .map(|entries| {
+                entries
+                    .into_iter()
+                    .map(|(address, value)| IpListEntry::parse(&address, &value))
+                    .filter(|entry| !entry.is_expired(now))
+                    .collect()
+            })
+    }
+
+    pub async fn add_ip_entries(
+        &self,
+        list: IpList,
+        entries: impl IntoIterator<Item = IpListEntry>,
+    ) -> trc::Result<()> {
+        let mut keys = Vec::new();
+
+        for entry in entries {
+            IpAddrOrMask::parse_value(&entry.address).map_err(|err| {
+                trc::ResourceEvent::BadParameters
+                    .into_err()
+                    .reason(err)
+                    .details(entry.address.clone())
+            })?;
+            keys.push(ConfigKey {
+                key: format!("{}{}", list.prefix(), entry.address),
+                value: entry.value(),
+            });
+        }
+
+        self.storage.config.set(keys).await
+    }
+
+    pub async fn remove_ip_entry(&self, list: IpList, address: &str) -> trc::Result<bool> {
+        let key = format!("{}{}", list.prefix(), address);
+
+        if self.storage.config.get(&key).await?.is_some() {
+            self.storage.config.clear(key).await.map(|_| true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    // Removes expired entries from both lists, returning the number of entries removed
+    pub async fn purge_expired_ip_entries(&self) -> trc::Result<usize> {
+        let now = now();
+        let mut removed = 0;
+
+        for list in [IpList::Blocked, IpList::Allowed] {
+            for (address, value) in self.storage.config.list(list.prefix(), true).await? {
+                if IpListEntry::parse(&address, &value).is_expired(now) {
+                    self.storage
+                        .config
+                        .clear(format!("{}{}", list.prefix(), address))
+                        .await?;
+                    removed += 1;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    // Fetches a feed of one address or network per line and adds its entries
+    // to the configured list, returning the number of entries imported
+    pub async fn import_ip_feed(&self, feed: &IpFeed) -> trc::Result<usize> {
+        let response = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap_or_default()
+            .get(&feed.url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| {
+                trc::ResourceEvent::Error
+                    .into_err()
+                    .reason(err)
+                    .ctx(trc::Key::Url, feed.url.clone())
+                    .details("Failed to fetch IP feed")
+            })?;
+        let bytes = response
+            .bytes_with_limit(MAX_FEED_SIZE)
+            .await
+            .map_err(|err| {
+                trc::ResourceEvent::Error
+                    .into_err()
+                    .reason(err)
+                    .ctx(trc::Key::Url, feed.url.clone())
+                    .details("Failed to fetch IP feed")
+            })?
+            .ok_or_else(|| {
+                trc::ResourceEvent::Error
+                    .into_err()
+                    .ctx(trc::Key::Url, feed.url.clone())
+                    .details("IP feed is too large")
+            })?;
+
+        let comment = format!("feed {}", feed.id);
+        let expires = feed.expires.map(|expires| now() + expires.as_secs());
+        let entries = String::from_utf8_lossy(&bytes)
+            .lines()
+            .filter_map(|line| {
+                let address = line
+                    .split(['#', ';'])
+                    .next()?
+                    .split_ascii_whitespace()
+                    .next()?;
+                IpAddrOrMask::parse_value(address)
+                    .is_ok()
+                    .then(|| IpListEntry {
+                        address: address.to_string(),
+                        comment: Some(comment.clone()),
+                        expires,
+                    })
+            })
+            .collect::<Vec<_>>();
+        let total = entries.len();
+
+        self.add_ip_entries(feed.list, entries).await?;
+
+        trc::event!(
+            Resource(trc::ResourceEvent::DownloadExternal),
+            Id = feed.id.clone(),
+            Url = feed.url.clone(),
+            Total = total,
+        );
+
+        Ok(total)
+    }
+
     pub fn has_auth_fail2ban(&self) -> bool {
         self.network.blocked_ips.auth_fail_rate.is_some()
     }
 
     pub fn is_ip_blocked(&self, ip: &IpAddr) -> bool {
         self.network.blocked_ips.ip_addresses.read().contains(ip)
-            || (self.network.blocked_ips.has_networks
-                && self
-                    .network
-                    .blocked_ips
-                    .ip_networks
-                    .iter()
-                    .any(|network| network.matches(ip)))
+            || self
+                .network
+                .blocked_ips
+                .ip_networks
+                .read()
+                .iter()
+                .any(|network| network.matches(ip))
+    }
+
+    pub async fn is_ip_trusted(&self, ip: &IpAddr, session_id: u64) -> bool {
+        let trusted_networks = &self.network.trusted_networks;
+        if trusted_networks.contains(ip) {
+            return true;
+        }
+
+        // Addresses can also be listed in a lookup store, in-memory lists
+        // accept glob patterns such as "10.0.0.*"
+        if let Some(id) = &trusted_networks.lookup {
+            match self
+                .get_lookup_store(id, session_id)
+                .key_exists(ip.to_string().into_bytes())
+                .await
+            {
+                Ok(is_trusted) => is_trusted,
+                Err(err) => {
+                    trc::error!(err
+                        .span_id(session_id)
+                        .caused_by(trc::location!())
+                        .details("Failed to look up trusted network."));
+                    false
+                }
+            }
+        } else {
+            false
+        }
     }
 
     pub fn is_ip_allowed(&self, ip: &IpAddr) -> bool {
-        self.network.allowed_ips.ip_addresses.contains(ip)
-            || (self.network.allowed_ips.has_networks
-                && self
-                    .network
-                    .allowed_ips
-                    .ip_networks
-                    .iter()
-                    .any(|network| network.matches(ip)))
+        self.network.allowed_ips.ip_addresses.read().contains(ip)
+            || self
+                .network
+                .allowed_ips
+                .ip_networks
+                .read()
+                .iter()
+                .any(|network| network.matches(ip))
     }
 }
 
@@ -235,11 +633,11 @@ impl Default for BlockedIps {
         Self {
             ip_addresses: RwLock::new(AHashSet::new()),
             ip_networks: Default::default(),
-            has_networks: Default::default(),
             version: Default::default(),
             auth_fail_rate: Default::default(),
             rcpt_fail_rate: Default::default(),
             loiter_fail_rate: Default::default(),
+            early_talker_fail_rate: Default::default(),
         }
     }
 }
@@ -250,14 +648,22 @@ impl Default for AllowedIps {
         // Add IPv4 and IPv6 loopback addresses
         Self {
             #[cfg(not(feature = "test_mode"))]
-            ip_addresses: AHashSet::from_iter([
+            ip_addresses: RwLock::new(AHashSet::from_iter([
                 IpAddr::V4(std::net::Ipv4Addr::LOCALHOST),
                 IpAddr::V6(std::net::Ipv6Addr::LOCALHOST),
-            ]),
+            ])),
             #[cfg(feature = "test_mode")]
             ip_addresses: Default::default(),
             ip_networks: Default::default(),
-            has_networks: Default::default(),
+        }
+    }
+}
+
+impl Clone for AllowedIps {
+    fn clone(&self) -> Self {
+        Self {
+            ip_addresses: RwLock::new(self.ip_addresses.read().clone()),
+            ip_networks: RwLock::new(self.ip_networks.read().clone()),
         }
     }
 }
@@ -266,8 +672,7 @@ impl Clone for BlockedIps {
     fn clone(&self) -> Self {
         Self {
             ip_addresses: RwLock::new(self.ip_addresses.read().clone()),
-            ip_networks: self.ip_networks.clone(),
-            has_networks: self.has_networks,
+            ip_networks: RwLock::new(self.ip_networks.read().clone()),
             version: self
                 .version
                 .load(std::sync::atomic::Ordering::Relaxed)
@@ -275,20 +680,92 @@ impl Clone for BlockedIps {
             auth_fail_rate: self.auth_fail_rate.clone(),
             rcpt_fail_rate: self.rcpt_fail_rate.clone(),
             loiter_fail_rate: self.loiter_fail_rate.clone(),
+            early_talker_fail_rate: self.early_talker_fail_rate.clone(),
         }
     }
 }
 
+impl Debug for TrustedNetworks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TrustedNetworks")
+            .field("ip_addresses", &self.ip_addresses)
+            .field("ip_networks", &self.ip_networks)
+            .field("lookup", &self.lookup)
+            .finish()
+    }
+}
+
 impl Debug for BlockedIps {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("BlockedIps")
             .field("ip_addresses", &self.ip_addresses)
             .field("ip_networks", &self.ip_networks)
-            .field("has_networks", &self.has_networks)
             .field("version", &self.version)
             .field("auth_fail_rate", &self.auth_fail_rate)
             .field("rcpt_fail_rate", &self.rcpt_fail_rate)
             .field("loiter_fail_rate", &self.loiter_fail_rate)
+            .field("early_talker_fail_rate", &self.early_talker_fail_rate)
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use store::write::now;
+    use utils::config::Config;
+
+    use super::{parse_ip_list, IpList, IpListEntry};
+
+    #[test]
+    fn ip_list_entries() {
+        for (value, expected) in [
+            ("", (None, None)),
+            ("spam source", (Some("spam source"), None)),
+            ("expires=1700000000;", (None, Some(1700000000))),
+            (
+                "expires=1700000000;feed spamhaus",
+                (Some("feed spamhaus"), Some(1700000000)),
+            ),
+            (
+                "expires=never;comment",
+                (Some("expires=never;comment"), None),
+            ),
+        ] {
+            let entry = IpListEntry::parse("10.0.0.1", value);
+            assert_eq!(
+                (entry.comment.as_deref(), entry.expires),
+                expected,
+                "failed for {value:?}"
+            );
+            assert_eq!(IpListEntry::parse("10.0.0.1", &entry.value()), entry);
+        }
+
+        let mut config = Config::new(format!(
+            concat!(
+                "[server.blocked-ip]\n",
+                "\"10.0.0.1\" = \"\"\n",
+                "\"10.0.0.2\" = \"expires=1;expired\"\n",
+                "\"10.0.0.3\" = \"expires={};not expired\"\n",
+                "\"192.168.0.0/16\" = \"network\"\n",
+                "\"10.0.1.0/24\" = \"expires=1;\"\n",
+            ),
+            now() + 3600
+        ))
+        .unwrap();
+        let (ip_addresses, ip_networks) = parse_ip_list(&mut config, IpList::Blocked);
+        let mut ip_addresses = ip_addresses.into_iter().collect::<Vec<_>>();
+        ip_addresses.sort();
+        assert_eq!(
+            ip_addresses,
+            vec![
+                "10.0.0.1".parse::<IpAddr>().unwrap(),
+                "10.0.0.3".parse::<IpAddr>().unwrap()
+            ]
+        );
+        assert_eq!(ip_networks.len(), 1);
+        assert!(ip_networks[0].matches(&"192.168.1.1".parse().unwrap()));
+        assert!(config.errors.is_empty(), "{:?}", config.errors);
+    }
+}
@@ -14,7 +14,7 @@ use arc_swap::ArcSwap;
 use proxy_header::io::ProxiedStream;
 use rustls::crypto::ring::cipher_suite::TLS13_AES_128_GCM_SHA256;
 use tokio::{
-    net::{TcpListener, TcpStream},
+    net::{TcpListener, TcpSocket, TcpStream},
     sync::watch,
 };
 use tokio_rustls::server::TlsStream;
@@ -330,6 +330,48 @@ impl Servers {
         }
     }
 
+    pub fn check_listeners(&self, config: &mut Config) {
+        // Bind each address and release it right away, ports held by a running server are
+        // only reported as warnings
+        let mut bound: Vec<(&str, SocketAddr)> = Vec::new();
+        for server in &self.servers {
+            for listener in &server.listeners {
+                let addr = listener.addr;
+                if let Some((other_id, other_addr)) = bound.iter().find(|(_, other)| {
+                    other.port() == addr.port()
+                        && other.is_ipv4() == addr.is_ipv4()
+                        && (other.ip() == addr.ip()
+                            || other.ip().is_unspecified()
+                            || addr.ip().is_unspecified())
+                }) {
+                    config.new_build_error(
+                        format!("server.listener.{}", server.id),
+                        format!(
+                            "Bind address {addr} conflicts with {other_addr} of listener {other_id:?}"
+                        ),
+                    );
+                } else {
+                    match check_bind(addr) {
+                        Ok(_) => (),
+                        Err(err) if err.kind() == std::io::ErrorKind::AddrInUse => {
+                            config.new_build_warning(
+                                format!("server.listener.{}", server.id),
+                                format!("Bind address {addr} is already in use"),
+                            );
+                        }
+                        Err(err) => {
+                            config.new_build_error(
+                                format!("server.listener.{}", server.id),
+                                format!("Failed to bind to {addr}: {err}"),
+                            );
+                        }
+                    }
+                    bound.push((server.id.as_str(), addr));
+                }
+            }
+        }
+    }
+
     pub fn spawn(
         mut self,
         spawn: impl Fn(Server, TcpAcceptor, watch::Receiver<bool>),
@@ -348,6 +390,17 @@ impl Servers {
     }
 }
 
+// Binds a new socket to the address, which is released when it is dropped
+fn check_bind(addr: SocketAddr) -> std::io::Result<()> {
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()
+    } else {
+        TcpSocket::new_v6()
+    }?;
+    socket.set_reuseaddr(true)?;
+    socket.bind(addr)
+}
+
 impl Listener {
     pub fn listen(self) -> Result<TcpListener, String> {
         self.socket
@@ -29,6 +29,7 @@ pub mod blocked;
 pub mod limiter;
 pub mod listen;
 pub mod stream;
+pub mod tarpit;
 pub mod tls;
 
 pub struct ServerInstance {
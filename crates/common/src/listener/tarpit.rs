@@ -0,0 +1,306 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+use utils::config::{utils::ParseValue, Config, Rate};
+
+use crate::{
+    config::CONNECTION_VARS,
+    expr::{
+        functions::ResolveVariable, if_block::IfBlock, tokenizer::TokenMap, Constant,
+        ConstantValue, Variable,
+    },
+    Core,
+};
+
+#[derive(Clone)]
+pub struct Tarpit {
+    pub delay: IfBlock,
+    pub greeting_wait: IfBlock,
+    pub early_talker_delay: Duration,
+    pub early_talker_action: IfBlock,
+    pub known_good_expire: Option<Duration>,
+    pub min_transfer_rate: Option<Rate>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EarlyTalkerAction {
+    #[default]
+    Tarpit,
+    Reject,
+    Score,
+}
+
+// Measures the rate at which a client sends a partially received request,
+// time spent idle between requests or processing them is not accounted for.
+#[derive(Debug, Clone, Default)]
+pub struct TransferRate {
+    min_rate: Option<Rate>,
+    bytes: u64,
+    elapsed: Duration,
+    waiting_since: Option<Instant>,
+}
+
+impl Default for Tarpit {
+    fn default() -> Self {
+        Self {
+            delay: IfBlock::empty("server.tarpit.delay"),
+            greeting_wait: IfBlock::empty("server.tarpit.greeting-wait"),
+            early_talker_delay: Duration::from_secs(5),
+            early_talker_action: IfBlock::new::<EarlyTalkerAction>(
+                "server.tarpit.early-talker-action",
+                [],
+                "tarpit",
+            ),
+            known_good_expire: Some(Duration::from_secs(7 * 86400)),
+            min_transfer_rate: None,
+        }
+    }
+}
+
+impl Tarpit {
+    pub fn parse(config: &mut Config) -> Self {
+        let mut tarpit = Tarpit {
+            early_talker_delay: config
+                .property_or_default("server.tarpit.early-talker", "5s")
+                .unwrap_or_else(|| Duration::from_secs(5)),
+            known_good_expire: config
+                .property_or_default::<Option<Duration>>("server.tarpit.known-good.expire", "7d")
+                .unwrap_or_default(),
+            min_transfer_rate: config
+                .property_or_default::<Option<Rate>>("server.min-transfer-rate", "false")
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+
+        let token_map = &TokenMap::default().with_variables(CONNECTION_VARS);
+        for (value, key) in [
+            (&mut tarpit.delay, "server.tarpit.delay"),
+            (&mut tarpit.greeting_wait, "server.tarpit.greeting-wait"),
+        ] {
+            if let Some(if_block) = IfBlock::try_parse(config, key, token_map) {
+                *value = if_block;
+            }
+        }
+        if let Some(if_block) = IfBlock::try_parse(
+            config,
+            "server.tarpit.early-talker-action",
+            &token_map.clone().with_constants::<EarlyTalkerAction>(),
+        ) {
+            tarpit.early_talker_action = if_block;
+        }
+
+        tarpit
+    }
+}
+
+impl ParseValue for EarlyTalkerAction {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "tarpit" | "delay" => Ok(EarlyTalkerAction::Tarpit),
+            "reject" | "disconnect" => Ok(EarlyTalkerAction::Reject),
+            "score" | "tag" => Ok(EarlyTalkerAction::Score),
+            _ => Err(format!("Invalid early talker action {value:?}.")),
+        }
+    }
+}
+
+impl<'x> TryFrom<Variable<'x>> for EarlyTalkerAction {
+    type Error = ();
+
+    fn try_from(value: Variable<'x>) -> Result<Self, Self::Error> {
+        match value {
+            Variable::Integer(0) => Ok(EarlyTalkerAction::Tarpit),
+            Variable::Integer(1) => Ok(EarlyTalkerAction::Reject),
+            Variable::Integer(2) => Ok(EarlyTalkerAction::Score),
+            Variable::String(value) => EarlyTalkerAction::parse_value(&value).map_err(|_| ()),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<EarlyTalkerAction> for Constant {
+    fn from(value: EarlyTalkerAction) -> Self {
+        Constant::Integer(match value {
+            EarlyTalkerAction::Tarpit => 0,
+            EarlyTalkerAction::Reject => 1,
+            EarlyTalkerAction::Score => 2,
+        })
+    }
+}
+
+impl ConstantValue for EarlyTalkerAction {
+    fn add_constants(token_map: &mut TokenMap) {
+        token_map
+            .add_constant("tarpit", EarlyTalkerAction::Tarpit)
+            .add_constant("delay", EarlyTalkerAction::Tarpit)
+            .add_constant("reject", EarlyTalkerAction::Reject)
+            .add_constant("disconnect", EarlyTalkerAction::Reject)
+            .add_constant("score", EarlyTalkerAction::Score)
+            .add_constant("tag", EarlyTalkerAction::Score);
+    }
+}
+
+impl TransferRate {
+    pub fn new(min_rate: Option<Rate>) -> Self {
+        TransferRate {
+            min_rate,
+            ..Default::default()
+        }
+    }
+
+    // Returns false if the client is sending the pending request below the minimum rate
+    pub fn record(&mut self, bytes: usize) -> bool {
+        match (&self.min_rate, self.waiting_since.take()) {
+            (Some(rate), Some(waiting_since)) => {
+                self.elapsed += waiting_since.elapsed();
+                self.bytes += bytes as u64;
+
+                self.elapsed < rate.period
+                    || self.bytes as u128 * rate.period.as_millis()
+                        >= rate.requests as u128 * self.elapsed.as_millis()
+            }
+            _ => true,
+        }
+    }
+
+    // Called after each read, measurement restarts once the request is complete
+    pub fn set_pending(&mut self, is_pending: bool) {
+        if is_pending {
+            if self.min_rate.is_some() {
+                self.waiting_since = Some(Instant::now());
+            }
+        } else {
+            self.bytes = 0;
+            self.elapsed = Duration::ZERO;
+            self.waiting_since = None;
+        }
+    }
+}
+
+impl Core {
+    pub async fn eval_tarpit(&self, resolver: &impl ResolveVariable, session_id: u64) -> Duration {
+        self.eval_if(&self.network.tarpit.delay, resolver, session_id)
+            .await
+            .unwrap_or_default()
+    }
+
+    pub async fn eval_greeting_wait(
+        &self,
+        resolver: &impl ResolveVariable,
+        session_id: u64,
+    ) -> Duration {
+        self.eval_if(&self.network.tarpit.greeting_wait, resolver, session_id)
+            .await
+            .unwrap_or_default()
+    }
+
+    pub async fn eval_early_talker_action(
+        &self,
+        resolver: &impl ResolveVariable,
+        session_id: u64,
+    ) -> EarlyTalkerAction {
+        self.eval_if(
+            &self.network.tarpit.early_talker_action,
+            resolver,
+            session_id,
+        )
+        .await
+        .unwrap_or_default()
+    }
+
+    // Clients that waited for the greeting in the past are not delayed again
+    pub async fn is_known_good_client(&self, ip: IpAddr) -> trc::Result<bool> {
+        if self.network.tarpit.known_good_expire.is_some() {
+            self.storage
+                .lookup
+                .key_exists(format!("known-good:{ip}").into_bytes())
+                .await
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub async fn set_known_good_client(&self, ip: IpAddr) -> trc::Result<()> {
+        if let Some(expire) = self.network.tarpit.known_good_expire {
+            self.storage
+                .lookup
+                .key_set(
+                    format!("known-good:{ip}").into_bytes(),
+                    vec![],
+                    expire.as_secs().into(),
+                )
+                .await
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// Waits before sending the greeting, returning any data sent by the client in the meantime.
+pub async fn greeting_wait<T: AsyncRead + Unpin>(
+    stream: &mut T,
+    wait: Duration,
+) -> std::io::Result<Vec<u8>> {
+    let started = Instant::now();
+    let mut buf = vec![0; 1024];
+
+    match tokio::time::timeout(wait, stream.read(&mut buf)).await {
+        Ok(Ok(0)) => Err(std::io::ErrorKind::UnexpectedEof.into()),
+        Ok(Ok(bytes_read)) => {
+            // Early talkers still wait for the full period
+            tokio::time::sleep(wait.saturating_sub(started.elapsed())).await;
+            buf.truncate(bytes_read);
+            Ok(buf)
+        }
+        Ok(Err(err)) => Err(err),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use utils::config::Rate;
+
+    use super::TransferRate;
+
+    #[test]
+    fn transfer_rate() {
+        let mut rate = TransferRate::new(Some(Rate {
+            requests: 1000,
+            period: Duration::from_millis(10),
+        }));
+
+        // Idle time between requests is not measured
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(rate.record(1));
+        rate.set_pending(true);
+        assert!(rate.record(100));
+
+        // Slow clients are detected once the period elapses
+        rate.set_pending(true);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!rate.record(1));
+
+        // Measurement restarts after the request completes
+        rate.set_pending(false);
+        rate.set_pending(true);
+        assert!(rate.record(1));
+
+        // No minimum rate configured
+        let mut rate = TransferRate::new(None);
+        rate.set_pending(true);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(rate.record(1));
+    }
+}
@@ -16,7 +16,7 @@ use ahash::{AHashMap, AHashSet};
 use jmap_proto::types::{collection::Collection, property::Property};
 use store::{
     write::{
-        key::DeserializeBigEndian, AnyKey, BitmapClass, BitmapHash, BlobOp, DirectoryClass,
+        key::DeserializeBigEndian, now, AnyKey, BitmapClass, BitmapHash, BlobOp, DirectoryClass,
         LookupClass, QueueClass, QueueEvent, TagValue, ValueClass,
     },
     BitmapKey, Deserialize, IndexKey, IterateParams, LogKey, Serialize, ValueKey,
@@ -32,6 +32,7 @@ use crate::Core;
 
 pub(super) const MAGIC_MARKER: u8 = 123;
 pub(super) const FILE_VERSION: u8 = 2;
+pub(super) const SNAPSHOT_TIMESTAMP: &str = "timestamp";
 
 #[derive(Debug)]
 pub(super) enum Op {
@@ -76,6 +77,10 @@ impl Core {
             std::process::exit(1);
         }
 
+        // Record when the snapshot was taken for point-in-time restores
+        std::fs::write(params.dest.join(SNAPSHOT_TIMESTAMP), now().to_string())
+            .failed("Failed to write snapshot timestamp");
+
         let mut sync_handles = Vec::new();
 
         for (async_handle, sync_handle) in [
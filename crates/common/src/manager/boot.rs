@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use arc_swap::ArcSwap;
 use pwhash::sha512_crypt;
@@ -25,6 +25,8 @@ use crate::{
 use super::{
     backup::BackupParams,
     config::{ConfigManager, Patterns},
+    migrate::{SchemaMigration, SCHEMA_VERSION},
+    restore::RestoreParams,
     WEBADMIN_KEY,
 };
 
@@ -46,6 +48,8 @@ Options:
   -e, --export <PATH>              Export all store data to a specific path
   -i, --import <PATH>              Import store data from a specific path
   -I, --init <PATH>                Initialize a new server at a specific path
+  -C, --check-config               Validate the configuration and exit without starting services
+  -M, --migrate-dry-run            List pending store schema migrations and exit without applying them
   -h, --help                       Print help
   -V, --version                    Print version
 "#
@@ -54,7 +58,9 @@ Options:
 #[derive(PartialEq, Eq)]
 enum ImportExport {
     Export(BackupParams),
-    Import(PathBuf),
+    Import(RestoreParams),
+    CheckConfig,
+    MigrateDryRun,
     None,
 }
 
@@ -73,6 +79,8 @@ impl BootManager {
             }) {
                 let (key, value) = if let Some((key, value)) = arg.split_once('=') {
                     (key.to_string(), Some(value.trim().to_string()))
+                } else if matches!(arg.as_str(), "check-config" | "C" | "migrate-dry-run" | "M") {
+                    (arg, None)
                 } else {
                     (arg, args.next())
                 };
@@ -97,7 +105,13 @@ impl BootManager {
                         import_export = ImportExport::Export(BackupParams::new(value.into()));
                     }
                     ("import" | "i", Some(value)) => {
-                        import_export = ImportExport::Import(value.into());
+                        import_export = ImportExport::Import(RestoreParams::new(value.into()));
+                    }
+                    ("check-config" | "C", _) => {
+                        import_export = ImportExport::CheckConfig;
+                    }
+                    ("migrate-dry-run" | "M", _) => {
+                        import_export = ImportExport::MigrateDryRun;
                     }
                     (_, None) => {
                         failed(&format!("Unrecognized command '{key}', try '--help'."));
@@ -112,7 +126,9 @@ impl BootManager {
                 if import_export == ImportExport::None {
                     eprintln!("{HELP}");
                 } else {
-                    eprintln!("Missing '--config' argument for import/export.")
+                    eprintln!(
+                        "Missing '--config' argument for import/export, config check or migration."
+                    )
                 }
                 std::process::exit(0);
             }
@@ -137,8 +153,12 @@ impl BootManager {
         // Parser servers
         let mut servers = Servers::parse(&mut config);
 
-        // Bind ports and drop privileges
-        servers.bind_and_drop_priv(&mut config);
+        // Bind ports and drop privileges, configuration checks only validate listeners
+        if import_export != ImportExport::CheckConfig {
+            servers.bind_and_drop_priv(&mut config);
+        } else {
+            servers.check_listeners(&mut config);
+        }
 
         // Resolve file and configuration macros
         config.resolve_macros(&["file", "cfg"]).await;
@@ -359,10 +379,80 @@ impl BootManager {
                     .await;
                 std::process::exit(0);
             }
+            ImportExport::CheckConfig => {
+                // Parse lookup stores
+                stores.parse_lookups(&mut config).await;
+
+                // Parse settings, expressions and certificates
+                let core = Core::parse(&mut config, stores, manager)
+                    .await
+                    .into_shared();
+
+                // Parse TCP acceptors
+                servers.parse_tcp_acceptors(&mut config, core);
+
+                // Print report without starting any services
+                let is_valid = config.errors.is_empty();
+                println!("{}", check_config_report(&config, &servers));
+                std::process::exit(if is_valid { 0 } else { 1 });
+            }
+            ImportExport::MigrateDryRun => {
+                // Parse settings and list pending migrations without applying them
+                let store = Core::parse(&mut config, stores, manager).await.storage.data;
+                let version = match store.schema_version().await {
+                    Ok(version) => version,
+                    Err(err) => failed(&format!("Failed to read store schema version: {err:?}")),
+                };
+                let pending = match store.pending_migrations().await {
+                    Ok(pending) => pending,
+                    Err(err) => failed(&format!("Failed to obtain pending migrations: {err:?}")),
+                };
+
+                println!("Current schema version: {version}");
+                println!("Target schema version: {SCHEMA_VERSION}");
+                if !pending.is_empty() {
+                    println!("Pending migrations:");
+                    for (version, migration) in pending {
+                        println!("  {version}: {}", migration.description());
+                    }
+                } else {
+                    println!("No pending migrations.");
+                }
+                std::process::exit(0);
+            }
         }
     }
 }
 
+fn check_config_report(config: &Config, servers: &Servers) -> String {
+    let errors = config.errors.iter().collect::<BTreeMap<_, _>>();
+    let warnings = config.warnings.iter().collect::<BTreeMap<_, _>>();
+    let listeners = servers
+        .servers
+        .iter()
+        .flat_map(|server| {
+            let key = format!("server.listener.{}", server.id);
+            server.listeners.iter().map(move |listener| {
+                serde_json::json!({
+                    "id": server.id,
+                    "protocol": server.protocol.as_str(),
+                    "bind": listener.addr.to_string(),
+                    "valid": !config.errors.contains_key(&key),
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "valid": errors.is_empty(),
+        "listeners": listeners,
+        "errors": errors,
+        "warnings": warnings,
+    }))
+    .unwrap_or_default()
+}
+
 fn quickstart(path: impl Into<PathBuf>) {
     let path = path.into();
 
@@ -548,3 +638,96 @@ enable = true
 user = "admin"
 secret = "_S_"
 "#;
+
+#[cfg(test)]
+mod tests {
+    use utils::config::Config;
+
+    use crate::config::server::Servers;
+
+    use super::check_config_report;
+
+    #[tokio::test]
+    async fn check_config_listeners() {
+        // Obtain a free port
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut config = Config::new(format!(
+            r#"
+[server.listener.smtp]
+bind = "127.0.0.1:{port}"
+protocol = "smtp"
+
+[server.listener.submission]
+bind = "0.0.0.0:{port}"
+protocol = "smtp"
+
+[server.listener.imap]
+bind = "127.0.0.1:0"
+protocol = "imap"
+"#
+        ))
+        .unwrap();
+        let servers = Servers::parse(&mut config);
+        servers.check_listeners(&mut config);
+
+        // Conflicting listeners are reported
+        let report: serde_json::Value =
+            serde_json::from_str(&check_config_report(&config, &servers)).unwrap();
+        assert_eq!(report["valid"], false);
+        for listener in report["listeners"].as_array().unwrap() {
+            assert_eq!(
+                listener["valid"],
+                listener["id"] != "submission",
+                "{listener:?}"
+            );
+        }
+
+        // Ports are released after checking
+        std::net::TcpListener::bind(("127.0.0.1", port)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn check_config_bind_failures() {
+        // Hold a port as a running server would
+        let running = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = running.local_addr().unwrap().port();
+        let mut config = Config::new(format!(
+            r#"
+[server.listener.smtp]
+bind = "127.0.0.1:{port}"
+protocol = "smtp"
+
+[server.listener.imap]
+bind = "192.0.2.1:0"
+protocol = "imap"
+"#
+        ))
+        .unwrap();
+        let servers = Servers::parse(&mut config);
+        servers.check_listeners(&mut config);
+
+        // Ports in use are reported as warnings, other bind failures as errors
+        let report: serde_json::Value =
+            serde_json::from_str(&check_config_report(&config, &servers)).unwrap();
+        assert_eq!(report["valid"], false);
+        for listener in report["listeners"].as_array().unwrap() {
+            assert_eq!(listener["valid"], listener["id"] == "smtp", "{listener:?}");
+        }
+        assert!(
+            report["warnings"]["server.listener.smtp"]
+                .to_string()
+                .contains("already in use"),
+            "{report}"
+        );
+        assert!(
+            report["errors"]["server.listener.imap"]
+                .to_string()
+                .contains("192.0.2.1:0"),
+            "{report}"
+        );
+    }
+}
@@ -508,6 +508,7 @@ impl Patterns {
                 Pattern::Include(MatchType::Equal("storage.lookup".to_string())),
                 Pattern::Include(MatchType::Equal("storage.fts".to_string())),
                 Pattern::Include(MatchType::Equal("storage.directory".to_string())),
+                Pattern::Include(MatchType::StartsWith("storage.replication.".to_string())),
                 Pattern::Include(MatchType::Equal("lookup.default.hostname".to_string())),
                 Pattern::Include(MatchType::Equal("enterprise.license-key".to_string())),
             ];
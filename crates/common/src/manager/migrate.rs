@@ -0,0 +1,250 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::{Duration, Instant};
+
+use directory::backend::internal::MigrateDirectory;
+use store::{
+    write::{assert::AssertValue, now, BatchBuilder, ValueClass},
+    Serialize, Store, ValueKey,
+};
+use trc::AddContext;
+
+// Ordered list of schema migrations, a store at schema version N has
+// all migrations up to and including MIGRATIONS[N - 1] applied.
+pub const MIGRATIONS: &[Migration] = &[Migration::DirectoryLayout];
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+// The schema version and migration lock are stored as properties of a reserved document
+const SCHEMA_VERSION_FIELD: u8 = u8::MAX;
+const MIGRATION_LOCK_FIELD: u8 = u8::MAX - 1;
+const LOCK_EXPIRY: u64 = 3600;
+const LOCK_WAIT: Duration = Duration::from_secs(5);
+const LOCK_RENEW: Duration = Duration::from_secs(LOCK_EXPIRY / 4);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Migration {
+    DirectoryLayout,
+}
+
+pub trait SchemaMigration: Sync + Send {
+    fn schema_version(&self) -> impl std::future::Future<Output = trc::Result<u32>> + Send;
+    fn pending_migrations(
+        &self,
+    ) -> impl std::future::Future<Output = trc::Result<Vec<(u32, Migration)>>> + Send;
+    fn migrate(&self) -> impl std::future::Future<Output = trc::Result<()>> + Send;
+    fn try_lock_migration(
+        &self,
+    ) -> impl std::future::Future<Output = trc::Result<Option<u64>>> + Send;
+}
+
+impl Migration {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Migration::DirectoryLayout => {
+                "Move principals and domains to the dynamic id directory layout"
+            }
+        }
+    }
+
+    async fn apply(&self, store: &Store) -> trc::Result<()> {
+        match self {
+            Migration::DirectoryLayout => store.migrate_directory().await,
+        }
+    }
+}
+
+impl SchemaMigration for Store {
+    async fn schema_version(&self) -> trc::Result<u32> {
+        self.get_value::<u32>(schema_key(SCHEMA_VERSION_FIELD))
+            .await
+            .caused_by(trc::location!())
+            .map(|version| version.unwrap_or_default())
+    }
+
+    async fn pending_migrations(&self) -> trc::Result<Vec<(u32, Migration)>> {
+        let version = self.schema_version().await?;
+        if version <= SCHEMA_VERSION {
+            Ok(MIGRATIONS
+                .iter()
+                .enumerate()
+                .skip(version as usize)
+                .map(|(idx, migration)| (idx as u32 + 1, *migration))
+                .collect())
+        } else {
+            Err(trc::StoreEvent::NotSupported
+                .into_err()
+                .details(format!(
+                    concat!(
+                        "Store schema version {} is newer than the version supported ",
+                        "by this server ({}), downgrades are not supported."
+                    ),
+                    version, SCHEMA_VERSION
+                ))
+                .caused_by(trc::location!()))
+        }
+    }
+
+    // Applies pending migrations in order, only one node in a cluster runs the
+    // migrations while the rest wait until the schema is up to date.
+    async fn migrate(&self) -> trc::Result<()> {
+        loop {
+            let pending = self.pending_migrations().await?;
+            if pending.is_empty() {
+                return Ok(());
+            }
+
+            let mut lock_expiry = if let Some(lock_expiry) = self.try_lock_migration().await? {
+                lock_expiry
+            } else {
+                trc::event!(
+                    Server(trc::ServerEvent::SchemaMigrationLocked),
+                    Version = SCHEMA_VERSION,
+                );
+                tokio::time::sleep(LOCK_WAIT).await;
+                continue;
+            };
+
+            // Release the lock also when a migration fails, so the next start can retry
+            let result = apply_migrations(self, &mut lock_expiry).await;
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(u32::MAX)
+                .with_collection(u8::MAX)
+                .update_document(u32::MAX)
+                .assert_value(ValueClass::Property(MIGRATION_LOCK_FIELD), lock_expiry)
+                .clear(ValueClass::Property(MIGRATION_LOCK_FIELD));
+            let released = self
+                .write(batch.build())
+                .await
+                .caused_by(trc::location!())
+                .map(|_| ());
+            return result.and(released);
+        }
+    }
+
+    // Returns the lock expiration time if the lock was acquired
+    async fn try_lock_migration(&self) -> trc::Result<Option<u64>> {
+        let current = self
+            .get_value::<u64>(schema_key(MIGRATION_LOCK_FIELD))
+            .await
+            .caused_by(trc::location!())?;
+        let now = now();
+        if current.is_some_and(|expiry| expiry > now) {
+            return Ok(None);
+        }
+
+        let lock_expiry = now + LOCK_EXPIRY;
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(u32::MAX)
+            .with_collection(u8::MAX)
+            .update_document(u32::MAX)
+            .assert_value(
+                ValueClass::Property(MIGRATION_LOCK_FIELD),
+                current.map_or(AssertValue::None, AssertValue::U64),
+            )
+            .set(
+                ValueClass::Property(MIGRATION_LOCK_FIELD),
+                lock_expiry.serialize(),
+            );
+        match self.write(batch.build()).await {
+            Ok(_) => Ok(Some(lock_expiry)),
+            Err(err) if err.is_assertion_failure() => Ok(None),
+            Err(err) => Err(err.caused_by(trc::location!())),
+        }
+    }
+}
+
+// Applies the pending migrations while holding the migration lock
+async fn apply_migrations(store: &Store, lock_expiry: &mut u64) -> trc::Result<()> {
+    // The schema might have been migrated before the lock was acquired
+    let pending = store.pending_migrations().await?;
+    for (version, migration) in pending {
+        let time = Instant::now();
+
+        // Keep the lock while the migration runs
+        let apply = migration.apply(store);
+        tokio::pin!(apply);
+        loop {
+            tokio::select! {
+                result = &mut apply => {
+                    result.map_err(|err| {
+                        err.details(format!(
+                            "Failed to apply schema migration {version}: {}",
+                            migration.description()
+                        ))
+                    })?;
+                    break;
+                }
+                _ = tokio::time::sleep(LOCK_RENEW) => {
+                    *lock_expiry = renew_migration_lock(store, *lock_expiry).await?;
+                }
+            }
+        }
+
+        // Record progress and extend the lock
+        let next_expiry = now() + LOCK_EXPIRY;
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(u32::MAX)
+            .with_collection(u8::MAX)
+            .update_document(u32::MAX)
+            .assert_value(ValueClass::Property(MIGRATION_LOCK_FIELD), *lock_expiry)
+            .set(
+                ValueClass::Property(SCHEMA_VERSION_FIELD),
+                version.serialize(),
+            )
+            .set(
+                ValueClass::Property(MIGRATION_LOCK_FIELD),
+                next_expiry.serialize(),
+            );
+        store
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())?;
+        *lock_expiry = next_expiry;
+
+        trc::event!(
+            Server(trc::ServerEvent::SchemaMigration),
+            Version = version,
+            Details = migration.description(),
+            Elapsed = time.elapsed(),
+        );
+    }
+
+    Ok(())
+}
+
+// Extends the migration lock held by this node
+async fn renew_migration_lock(store: &Store, lock_expiry: u64) -> trc::Result<u64> {
+    let next_expiry = now() + LOCK_EXPIRY;
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(u32::MAX)
+        .with_collection(u8::MAX)
+        .update_document(u32::MAX)
+        .assert_value(ValueClass::Property(MIGRATION_LOCK_FIELD), lock_expiry)
+        .set(
+            ValueClass::Property(MIGRATION_LOCK_FIELD),
+            next_expiry.serialize(),
+        );
+    store
+        .write(batch.build())
+        .await
+        .map_err(|err| err.details("Failed to renew schema migration lock"))
+        .caused_by(trc::location!())?;
+    Ok(next_expiry)
+}
+
+fn schema_key(field: u8) -> ValueKey<ValueClass<u32>> {
+    ValueKey {
+        account_id: u32::MAX,
+        collection: u8::MAX,
+        document_id: u32::MAX,
+        class: ValueClass::Property(field),
+    }
+}
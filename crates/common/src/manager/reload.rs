@@ -4,17 +4,16 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use ahash::AHashSet;
 use arc_swap::ArcSwap;
 use store::Stores;
-use utils::config::{ipmask::IpAddrOrMask, utils::ParseValue, Config};
+use utils::config::Config;
 
 use crate::{
     config::{
         server::{tls::parse_certificates, Servers},
         telemetry::Telemetry,
     },
-    listener::blocked::BLOCKED_IP_KEY,
+    listener::blocked::{parse_ip_list, IpList, ALLOWED_IP_KEY, BLOCKED_IP_KEY},
     Core,
 };
 
@@ -27,27 +26,20 @@ pub struct ReloadResult {
 }
 
 impl Core {
-    pub async fn reload_blocked_ips(&self) -> trc::Result<ReloadResult> {
-        let mut ip_addresses = AHashSet::new();
+    // Updates the blocked and allowed lists in place, without rebuilding the core
+    pub async fn reload_ip_lists(&self) -> trc::Result<ReloadResult> {
         let mut config = self.storage.config.build_config(BLOCKED_IP_KEY).await?;
-
-        for ip in config
-            .set_values(BLOCKED_IP_KEY)
-            .map(IpAddrOrMask::parse_value)
-            .collect::<Vec<_>>()
-        {
-            match ip {
-                Ok(IpAddrOrMask::Ip(ip)) => {
-                    ip_addresses.insert(ip);
-                }
-                Ok(IpAddrOrMask::Mask(_)) => {}
-                Err(err) => {
-                    config.new_parse_error(BLOCKED_IP_KEY, err);
-                }
-            }
-        }
-
-        *self.network.blocked_ips.ip_addresses.write() = ip_addresses;
+        self.storage
+            .config
+            .extend_config(&mut config, ALLOWED_IP_KEY)
+            .await?;
+
+        self.network
+            .blocked_ips
+            .update(parse_ip_list(&mut config, IpList::Blocked));
+        self.network
+            .allowed_ips
+            .update(parse_ip_list(&mut config, IpList::Allowed));
 
         Ok(config.into())
     }
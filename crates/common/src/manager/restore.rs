@@ -10,7 +10,10 @@ use std::{
 };
 
 use crate::Core;
+use ahash::AHashSet;
+use directory::backend::internal::manage::ManageDirectory;
 use jmap_proto::types::{collection::Collection, property::Property};
+use mail_parser::DateTime;
 use store::{
     roaring::RoaringBitmap,
     write::{
@@ -18,7 +21,7 @@ use store::{
         FtsQueueClass, LookupClass, MaybeDynamicId, MaybeDynamicValue, Operation, TagValue,
         ValueClass,
     },
-    BlobStore, Serialize, Store, U32_LEN,
+    BlobStore, IterateParams, Serialize, Store, ValueKey, U32_LEN,
 };
 use store::{
     write::{QueueClass, QueueEvent},
@@ -30,36 +33,281 @@ use tokio::{
 };
 use utils::{failed, BlobHash, UnwrapFailure};
 
-use super::backup::{DeserializeBytes, Family, Op, FILE_VERSION, MAGIC_MARKER};
+use super::backup::{DeserializeBytes, Family, Op, FILE_VERSION, MAGIC_MARKER, SNAPSHOT_TIMESTAMP};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RestoreParams {
+    src: PathBuf,
+    account: Option<String>,
+    target_account: Option<String>,
+    timestamp: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AccountFilter {
+    source: u32,
+    target: u32,
+}
 
 impl Core {
-    pub async fn restore(&self, src: PathBuf) {
-        // Backup the core
-        if src.is_dir() {
-            // Iterate directory and spawn a task for each file
-            let mut tasks = Vec::new();
+    pub async fn restore(&self, params: RestoreParams) {
+        // Pick the most recent snapshot taken at or before the requested time
+        let src = if let Some(timestamp) = params.timestamp {
+            select_snapshot(&params.src, timestamp)
+        } else {
+            params.src
+        };
+
+        // Validate the snapshot before making any changes
+        let files = if src.is_dir() {
+            let mut files = Vec::new();
             for entry in std::fs::read_dir(&src).failed("Failed to read directory") {
                 let entry = entry.failed("Failed to read entry");
                 let path = entry.path();
-                if path.is_file() {
-                    let storage = self.storage.clone();
-                    let blob_store = self.storage.blob.clone();
-                    tasks.push(tokio::spawn(async move {
-                        restore_file(storage.data, blob_store, &path).await;
-                    }));
+                if path.is_file() && entry.file_name() != SNAPSHOT_TIMESTAMP {
+                    files.push(path);
+                }
+            }
+            files
+        } else {
+            vec![src.clone()]
+        };
+        for path in &files {
+            OpReader::new(path).await;
+        }
+
+        // Restrict the import to a single account
+        let filter = if let Some(account) = &params.account {
+            let source = self.restore_account_id(account).await;
+            let target = if let Some(target) = &params.target_account {
+                self.restore_account_id(target).await
+            } else {
+                source
+            };
+
+            // Make sure the snapshot contains the account before purging the target
+            let mut has_account = false;
+            for path in &files {
+                if OpReader::new(path).await.has_account(source).await {
+                    has_account = true;
+                    break;
                 }
             }
+            if !has_account {
+                failed(&format!("Account {source} not found in {src:?}"));
+            }
+
+            // Permissions granted to the target by other accounts are kept, as the
+            // granting documents are not restored. Permissions granted by the target
+            // are replaced with the ones in the snapshot.
+            let store = &self.storage.data;
+            let mut granted = Vec::new();
+            let mut revoked = Vec::new();
+            store
+                .iterate(
+                    IterateParams::new(
+                        ValueKey {
+                            account_id: 0,
+                            collection: 0,
+                            document_id: 0,
+                            class: ValueClass::Acl(0),
+                        },
+                        ValueKey {
+                            account_id: u32::MAX,
+                            collection: u8::MAX,
+                            document_id: u32::MAX,
+                            class: ValueClass::Acl(u32::MAX),
+                        },
+                    ),
+                    |key, value| {
+                        let grant_account_id = key.deserialize_be_u32(0)?;
+                        let account_id = key.deserialize_be_u32(U32_LEN)?;
+                        let collection = key.deserialize_u8(U32_LEN * 2)?;
+                        let document_id = key.deserialize_be_u32((U32_LEN * 2) + 1)?;
+                        if account_id == target {
+                            revoked.push((grant_account_id, collection, document_id));
+                        } else if grant_account_id == target {
+                            granted.push((account_id, collection, document_id, value.to_vec()));
+                        }
+                        Ok(true)
+                    },
+                )
+                .await
+                .failed("Failed to read target account permissions");
 
-            for task in tasks {
-                task.await.failed("Failed to wait for task");
+            // Remove the current contents of the target account
+            store
+                .purge_account(target)
+                .await
+                .failed("Failed to purge target account");
+            let used_quota = store
+                .get_counter(ValueKey::from(ValueClass::Directory(
+                    DirectoryClass::UsedQuota(target),
+                )))
+                .await
+                .failed("Failed to obtain target account quota");
+            let mut batch = BatchBuilder::new();
+            if used_quota != 0 {
+                batch.add(
+                    ValueClass::Directory(DirectoryClass::UsedQuota(target)),
+                    -used_quota,
+                );
+            }
+            for (grant_account_id, collection, document_id) in revoked {
+                batch
+                    .with_account_id(target)
+                    .with_collection(collection)
+                    .update_document(document_id)
+                    .clear(ValueClass::Acl(grant_account_id));
+            }
+            for (account_id, collection, document_id, value) in granted {
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(collection)
+                    .update_document(document_id)
+                    .set(ValueClass::Acl(target), value);
+            }
+            if !batch.is_empty() {
+                store
+                    .write(batch.build())
+                    .await
+                    .failed("Failed to reset target account");
             }
+
+            println!("Restoring account {source} into account {target} from {src:?}.");
+
+            Some(AccountFilter { source, target })
         } else {
-            restore_file(self.storage.data.clone(), self.storage.blob.clone(), &src).await;
+            None
+        };
+
+        // Spawn a task for each file
+        let mut tasks = Vec::new();
+        for path in files {
+            let storage = self.storage.clone();
+            let blob_store = self.storage.blob.clone();
+            tasks.push(tokio::spawn(async move {
+                restore_file(storage.data, blob_store, &path, filter).await;
+            }));
+        }
+
+        for task in tasks {
+            task.await.failed("Failed to wait for task");
+        }
+    }
+
+    async fn restore_account_id(&self, account: &str) -> u32 {
+        if let Ok(account_id) = account.parse::<u32>() {
+            account_id
+        } else {
+            self.storage
+                .data
+                .get_principal_id(account)
+                .await
+                .failed("Failed to lookup account")
+                .unwrap_or_else(|| failed(&format!("Account {account:?} does not exist")))
         }
     }
 }
 
-async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
+impl RestoreParams {
+    pub fn new(src: PathBuf) -> Self {
+        let mut params = Self {
+            src,
+            ..Default::default()
+        };
+
+        if let Ok(account) = std::env::var("IMPORT_ACCOUNT") {
+            params.account = Some(account);
+        }
+
+        if let Ok(account) = std::env::var("IMPORT_TARGET_ACCOUNT") {
+            params.target_account = Some(account);
+        }
+
+        if let Ok(timestamp) = std::env::var("IMPORT_TIMESTAMP") {
+            match parse_timestamp(&timestamp) {
+                Some(timestamp) => {
+                    params.timestamp = Some(timestamp);
+                }
+                None => {
+                    eprintln!("Restore failed: Invalid timestamp {timestamp:?}.");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        if params.target_account.is_some() && params.account.is_none() {
+            eprintln!("Restore failed: IMPORT_TARGET_ACCOUNT requires IMPORT_ACCOUNT.");
+            std::process::exit(1);
+        }
+
+        params
+    }
+
+    pub fn with_account(mut self, account: impl Into<String>) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+
+    pub fn with_target_account(mut self, account: impl Into<String>) -> Self {
+        self.target_account = Some(account.into());
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+}
+
+fn parse_timestamp(value: &str) -> Option<u64> {
+    value.parse::<u64>().ok().or_else(|| {
+        DateTime::parse_rfc3339(value)
+            .map(|dt| dt.to_timestamp())
+            .filter(|ts| *ts >= 0)
+            .map(|ts| ts as u64)
+    })
+}
+
+fn select_snapshot(src: &Path, timestamp: u64) -> PathBuf {
+    let mut snapshot: Option<(u64, PathBuf)> = None;
+
+    for entry in std::fs::read_dir(src).failed("Failed to read directory") {
+        let path = entry.failed("Failed to read entry").path();
+        let created = if let Some(created) = std::fs::read_to_string(path.join(SNAPSHOT_TIMESTAMP))
+            .ok()
+            .and_then(|created| created.trim().parse::<u64>().ok())
+        {
+            created
+        } else {
+            continue;
+        };
+
+        if created <= timestamp
+            && snapshot
+                .as_ref()
+                .map_or(true, |(last_created, _)| created > *last_created)
+        {
+            snapshot = Some((created, path));
+        }
+    }
+
+    if let Some((_, path)) = snapshot {
+        path
+    } else {
+        failed(&format!(
+            "No snapshot in {src:?} was taken at or before {timestamp}"
+        ))
+    }
+}
+
+async fn restore_file(
+    store: Store,
+    blob_store: BlobStore,
+    path: &Path,
+    filter: Option<AccountFilter>,
+) {
     println!("Importing database dump from {}.", path.to_str().unwrap());
 
     let mut reader = OpReader::new(path).await;
@@ -69,6 +317,8 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
     let mut family = Family::None;
     let email_collection = u8::from(Collection::Email);
     let mut seq = 0;
+    let mut is_selected = true;
+    let mut linked_blobs = AHashSet::new();
 
     let mut batch_size = 0;
     let mut batch = BatchBuilder::new();
@@ -78,6 +328,12 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
             Op::Family(f) => family = f,
             Op::AccountId(a) => {
                 account_id = a;
+                if let Some(filter) = &filter {
+                    is_selected = a == filter.source;
+                    if is_selected {
+                        account_id = filter.target;
+                    }
+                }
                 batch.with_account_id(account_id);
             }
             Op::Collection(c) => {
@@ -91,6 +347,60 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
             Op::KeyValue((key, value)) => {
                 batch_size += key.len() + value.len() + U32_LEN * 2;
 
+                // Skip data not belonging to the restored account
+                if let Some(filter) = &filter {
+                    match family {
+                        Family::Property
+                        | Family::FtsIndex
+                        | Family::Index
+                        | Family::Bitmap
+                        | Family::Log => {
+                            if !is_selected {
+                                continue;
+                            }
+                        }
+                        Family::Acl => {
+                            // Permissions granted by other accounts are kept as they were,
+                            // permissions granted to the target itself are dropped.
+                            if !is_selected
+                                || key.as_slice().deserialize_be_u32(0).ok() == Some(filter.target)
+                            {
+                                continue;
+                            }
+                        }
+                        Family::Blob => {
+                            if account_id != u32::MAX && document_id != u32::MAX {
+                                if !is_selected {
+                                    continue;
+                                }
+                                linked_blobs.insert(key.clone());
+                            } else if !linked_blobs.contains(&key) {
+                                continue;
+                            }
+                        }
+                        Family::Directory => {
+                            let key = key.as_slice();
+                            if key.first() == Some(&4)
+                                && key
+                                    .get(1..)
+                                    .and_then(|key| key.deserialize_leb128::<u32>().ok())
+                                    == Some(filter.source)
+                            {
+                                batch.add(
+                                    ValueClass::Directory(DirectoryClass::UsedQuota(filter.target)),
+                                    i64::deserialize(&value).expect("Failed to deserialize quota"),
+                                );
+                            }
+                            continue;
+                        }
+                        Family::Config
+                        | Family::LookupValue
+                        | Family::LookupCounter
+                        | Family::Queue => continue,
+                        Family::None => failed("No family specified in file"),
+                    }
+                }
+
                 match family {
                     Family::Property => {
                         let field = key
@@ -435,6 +745,16 @@ impl OpReader {
         }
     }
 
+    // Returns whether the file contains data of the given account
+    async fn has_account(mut self, account_id: u32) -> bool {
+        while let Some(op) = self.next().await {
+            if matches!(op, Op::AccountId(id) if id == account_id) {
+                return true;
+            }
+        }
+        false
+    }
+
     async fn expect_u8(&mut self) -> u8 {
         self.file.read_u8().await.failed("Failed to read u8")
     }
@@ -13,6 +13,9 @@ use crate::IntoString;
 
 pub mod functions;
 pub mod plugins;
+pub mod stats;
+pub mod training;
+pub mod verdict;
 
 #[derive(Debug, serde::Serialize)]
 #[serde(tag = "action")]
@@ -0,0 +1,166 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::Permission;
+use jmap_proto::{
+    object::Object,
+    types::{collection::Collection, property::Property, value::Value},
+};
+use sieve::{runtime::Variable, FunctionMap};
+use store::{query::Filter, write::ValueClass, LookupStore, ValueKey};
+use trc::AddContext;
+
+use crate::Core;
+
+use super::PluginContext;
+
+pub fn register(plugin_id: u32, fnc_map: &mut FunctionMap) {
+    fnc_map.set_external_function("is_known_contact", plugin_id, 3);
+}
+
+pub async fn exec(ctx: PluginContext<'_>) -> trc::Result<Variable> {
+    let store = match &ctx.arguments[0] {
+        Variable::String(v) if !v.is_empty() => ctx.core.storage.lookups.get(v.as_ref()),
+        _ => Some(&ctx.core.storage.lookup),
+    }
+    .ok_or_else(|| {
+        trc::SieveEvent::RuntimeError
+            .ctx(trc::Key::Id, ctx.arguments[0].to_string().into_owned())
+            .details("Unknown store")
+    })?;
+    let sender = ctx.arguments[2].to_string().trim().to_lowercase();
+    let recipients = match &ctx.arguments[1] {
+        Variable::Array(items) => items
+            .iter()
+            .map(|item| item.to_string().trim().to_lowercase())
+            .collect::<Vec<_>>(),
+        v => vec![v.to_string().trim().to_lowercase()],
+    };
+
+    if sender.is_empty() || recipients.is_empty() {
+        return Ok(false.into());
+    }
+
+    // The sender has to be known to every recipient
+    for rcpt in &recipients {
+        if rcpt.is_empty() || !is_known_contact(ctx.core, store, rcpt, &sender).await? {
+            return Ok(false.into());
+        }
+    }
+
+    Ok(true.into())
+}
+
+async fn is_known_contact(
+    core: &Core,
+    store: &LookupStore,
+    rcpt: &str,
+    sender: &str,
+) -> trc::Result<bool> {
+    let account_ids = core
+        .storage
+        .directory
+        .email_to_ids(rcpt)
+        .await
+        .caused_by(trc::location!())?;
+
+    // Users can opt out from their account settings, or administrators by
+    // disabling the permission
+    for &account_id in &account_ids {
+        if !core
+            .get_cached_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?
+            .has_permission(Permission::SpamAllowContacts)
+            || core
+                .storage
+                .data
+                .get_value::<()>(ValueKey {
+                    account_id,
+                    collection: Collection::Principal.into(),
+                    document_id: 0,
+                    class: ValueClass::Property(Property::ContactsOptOut.into()),
+                })
+                .await
+                .caused_by(trc::location!())?
+                .is_some()
+        {
+            return Ok(false);
+        }
+    }
+
+    // Addresses the recipient has previously sent messages to
+    if store
+        .key_exists(format!("c:{rcpt}:{sender}").into_bytes())
+        .await?
+    {
+        return Ok(true);
+    }
+
+    // Addresses in the recipient's address books
+    for account_id in account_ids {
+        if core
+            .address_book_contains(account_id, sender)
+            .await
+            .caused_by(trc::location!())?
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+impl Core {
+    pub async fn address_book_contains(&self, account_id: u32, address: &str) -> trc::Result<bool> {
+        let document_ids = self
+            .storage
+            .data
+            .filter(
+                account_id,
+                Collection::ContactCard,
+                vec![Filter::has_text(Property::Email, address)],
+            )
+            .await
+            .caused_by(trc::location!())?
+            .results;
+
+        // The email index is tokenized, make sure the address is an exact match
+        for document_id in document_ids {
+            if let Some(card) = self
+                .storage
+                .data
+                .get_value::<Object<Value>>(ValueKey {
+                    account_id,
+                    collection: Collection::ContactCard.into(),
+                    document_id,
+                    class: ValueClass::Property(Property::Value.into()),
+                })
+                .await
+                .caused_by(trc::location!())?
+            {
+                if card
+                    .get(&Property::_T("emails".to_string()))
+                    .as_obj()
+                    .map_or(false, |emails| {
+                        emails.properties.values().any(|entry| {
+                            entry
+                                .as_obj()
+                                .and_then(|entry| {
+                                    entry.get(&Property::_T("address".to_string())).as_string()
+                                })
+                                .map_or(false, |entry| entry.trim().eq_ignore_ascii_case(address))
+                        })
+                    })
+                {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+}
@@ -0,0 +1,45 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::net::IpAddr;
+
+use sieve::{runtime::Variable, FunctionMap};
+use store::write::now;
+
+use super::PluginContext;
+
+pub fn register_block(plugin_id: u32, fnc_map: &mut FunctionMap) {
+    fnc_map.set_external_function("ip_block", plugin_id, 3);
+}
+
+pub async fn exec_block(ctx: PluginContext<'_>) -> trc::Result<Variable> {
+    let ip = match ctx.arguments[0].to_string().parse::<IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => return Ok(false.into()),
+    };
+    let expires = match ctx.arguments[1].to_integer() {
+        expires_in if expires_in > 0 => Some(now() + expires_in as u64),
+        _ => None,
+    };
+    let comment = ctx.arguments[2].to_string().into_owned();
+
+    let is_blocked = ctx
+        .core
+        .block_ip_entry(ip, expires, (!comment.is_empty()).then(|| comment.clone()))
+        .await?;
+
+    if is_blocked {
+        trc::event!(
+            Security(trc::SecurityEvent::ScriptBan),
+            SpanId = ctx.session_id,
+            RemoteIp = ip,
+            Expires = expires.map(trc::Value::Timestamp),
+            Reason = comment,
+        );
+    }
+
+    Ok(is_blocked.into())
+}
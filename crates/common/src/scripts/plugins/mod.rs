@@ -5,13 +5,16 @@
  */
 
 pub mod bayes;
+pub mod contacts;
 pub mod dns;
 pub mod exec;
 pub mod headers;
 pub mod http;
+pub mod ip;
 pub mod lookup;
 pub mod pyzor;
 pub mod query;
+pub mod stats;
 pub mod text;
 
 use mail_parser::Message;
@@ -32,7 +35,7 @@ pub struct PluginContext<'x> {
     pub arguments: Vec<Variable>,
 }
 
-const PLUGINS_REGISTER: [RegisterPluginFnc; 18] = [
+const PLUGINS_REGISTER: [RegisterPluginFnc; 22] = [
     query::register,
     exec::register,
     lookup::register,
@@ -51,6 +54,10 @@ const PLUGINS_REGISTER: [RegisterPluginFnc; 18] = [
     headers::register,
     text::register_tokenize,
     text::register_domain_part,
+    contacts::register,
+    ip::register_block,
+    text::register_canonical_address,
+    stats::register,
 ];
 
 pub trait RegisterSievePlugins {
@@ -98,6 +105,10 @@ impl Core {
             15 => headers::exec(ctx),
             16 => text::exec_tokenize(ctx),
             17 => text::exec_domain_part(ctx),
+            18 => contacts::exec(ctx).await,
+            19 => ip::exec_block(ctx).await,
+            20 => text::exec_canonical_address(ctx),
+            21 => stats::exec(ctx).await,
             _ => unreachable!(),
         };
 
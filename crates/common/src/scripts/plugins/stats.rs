@@ -0,0 +1,45 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use sieve::{runtime::Variable, FunctionMap};
+
+use crate::scripts::stats::record_symbol;
+
+use super::PluginContext;
+
+pub fn register(plugin_id: u32, fnc_map: &mut FunctionMap) {
+    fnc_map.set_external_function("symbol_stats", plugin_id, 4);
+}
+
+pub async fn exec(ctx: PluginContext<'_>) -> trc::Result<Variable> {
+    let store = match &ctx.arguments[0] {
+        Variable::String(v) if !v.is_empty() => ctx.core.storage.lookups.get(v.as_ref()),
+        _ => Some(&ctx.core.storage.lookup),
+    }
+    .ok_or_else(|| {
+        trc::SieveEvent::RuntimeError
+            .ctx(trc::Key::Id, ctx.arguments[0].to_string().into_owned())
+            .details("Unknown store")
+    })?;
+
+    let symbol = ctx.arguments[1].to_string();
+    let score = match &ctx.arguments[2] {
+        Variable::Float(score) => *score,
+        Variable::Integer(score) => *score as f64,
+        _ => return Ok(false.into()),
+    };
+    let expires = match ctx.arguments[3].to_integer() {
+        expires_in if expires_in > 0 => Some(expires_in as u64),
+        _ => None,
+    };
+
+    if !symbol.is_empty() {
+        record_symbol(store, symbol.as_ref(), score, expires).await?;
+        Ok(true.into())
+    } else {
+        Ok(false.into())
+    }
+}
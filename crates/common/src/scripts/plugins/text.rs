@@ -19,6 +19,10 @@ pub fn register_domain_part(plugin_id: u32, fnc_map: &mut FunctionMap) {
     fnc_map.set_external_function("domain_part", plugin_id, 2);
 }
 
+pub fn register_canonical_address(plugin_id: u32, fnc_map: &mut FunctionMap) {
+    fnc_map.set_external_function("canonical_address", plugin_id, 1);
+}
+
 pub fn exec_tokenize(ctx: PluginContext<'_>) -> trc::Result<Variable> {
     let mut v = ctx.arguments;
     let (urls, urls_without_scheme, emails) = match v[1].to_string().as_ref() {
@@ -58,6 +62,12 @@ enum DomainPart {
     Host,
 }
 
+pub fn exec_canonical_address(ctx: PluginContext<'_>) -> trc::Result<Variable> {
+    let canonical = &ctx.core.smtp.canonical;
+
+    Ok(ctx.arguments[0].transform(|address| Variable::from(canonical.address(address))))
+}
+
 pub fn exec_domain_part(ctx: PluginContext<'_>) -> trc::Result<Variable> {
     let v = ctx.arguments;
     let part = match v[1].to_string().as_ref() {
@@ -0,0 +1,187 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use ahash::AHashMap;
+use store::{
+    write::{now, LookupClass, ValueClass},
+    IterateParams, LookupStore, ValueKey,
+};
+use trc::AddContext;
+
+// Daily hit counts and score totals are kept as counters keyed by
+// prefix, day number and symbol name, e.g. "sh:20012:BAYES_SPAM".
+const HITS_PREFIX: &[u8] = b"sh:";
+const SCORE_PREFIX: &[u8] = b"ss:";
+
+// Scores are stored in thousandths of a point
+const SCORE_SCALE: f64 = 1000.0;
+const DAY: u64 = 86400;
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolStats {
+    pub symbol: String,
+    pub hits: u64,
+    pub total_score: f64,
+    pub average_score: f64,
+    pub history: Vec<SymbolDailyStats>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolDailyStats {
+    pub day: u64,
+    pub hits: u64,
+    pub total_score: f64,
+}
+
+pub async fn record_symbol(
+    lookup: &LookupStore,
+    symbol: &str,
+    score: f64,
+    expires: Option<u64>,
+) -> trc::Result<()> {
+    let day = now() / DAY;
+
+    lookup
+        .counter_incr(stats_key(HITS_PREFIX, day, symbol), 1, expires, false)
+        .await
+        .caused_by(trc::location!())?;
+
+    let score = (score * SCORE_SCALE).round() as i64;
+    if score != 0 {
+        lookup
+            .counter_incr(stats_key(SCORE_PREFIX, day, symbol), score, expires, false)
+            .await
+            .caused_by(trc::location!())?;
+    }
+
+    Ok(())
+}
+
+impl SymbolStats {
+    // Returns the statistics of the symbols triggered during the last `days` days,
+    // sorted by number of hits.
+    pub async fn fetch(lookup: &LookupStore, days: u64) -> trc::Result<Vec<SymbolStats>> {
+        let store = if let LookupStore::Store(store) = lookup {
+            store
+        } else {
+            return Err(trc::StoreEvent::NotSupported
+                .into_err()
+                .details("Spam symbol statistics can only be retrieved from a data store"));
+        };
+        let from_day = (now() / DAY).saturating_sub(days.saturating_sub(1));
+
+        let mut symbols: AHashMap<String, AHashMap<u64, SymbolDailyStats>> = AHashMap::new();
+        for prefix in [HITS_PREFIX, SCORE_PREFIX] {
+            let mut keys = Vec::new();
+            let mut end_key = prefix.to_vec();
+            end_key.push(u8::MAX);
+
+            store
+                .iterate(
+                    IterateParams::new(
+                        ValueKey::from(ValueClass::Lookup(LookupClass::Counter(prefix.to_vec()))),
+                        ValueKey::from(ValueClass::Lookup(LookupClass::Counter(end_key))),
+                    )
+                    .no_values(),
+                    |key, _| {
+                        if let Some((day, symbol)) = parse_key(prefix, key) {
+                            if day >= from_day {
+                                keys.push((key.to_vec(), day, symbol));
+                            }
+                        }
+
+                        Ok(true)
+                    },
+                )
+                .await
+                .caused_by(trc::location!())?;
+
+            for (key, day, symbol) in keys {
+                let value = store
+                    .get_counter(ValueKey::from(ValueClass::Lookup(LookupClass::Counter(
+                        key,
+                    ))))
+                    .await
+                    .caused_by(trc::location!())?;
+                let stats = symbols
+                    .entry(symbol)
+                    .or_default()
+                    .entry(day)
+                    .or_insert_with(|| SymbolDailyStats {
+                        day: day * DAY,
+                        ..Default::default()
+                    });
+
+                if prefix == HITS_PREFIX {
+                    stats.hits = value.max(0) as u64;
+                } else {
+                    stats.total_score = value as f64 / SCORE_SCALE;
+                }
+            }
+        }
+
+        let mut results = symbols
+            .into_iter()
+            .map(|(symbol, history)| {
+                let mut history = history.into_values().collect::<Vec<_>>();
+                history.sort_unstable_by_key(|stats| stats.day);
+                let hits = history.iter().map(|stats| stats.hits).sum::<u64>();
+                let total_score = history.iter().map(|stats| stats.total_score).sum::<f64>();
+
+                SymbolStats {
+                    symbol,
+                    hits,
+                    total_score,
+                    average_score: if hits > 0 {
+                        total_score / hits as f64
+                    } else {
+                        0.0
+                    },
+                    history,
+                }
+            })
+            .collect::<Vec<_>>();
+        results.sort_unstable_by(|a, b| b.hits.cmp(&a.hits).then_with(|| a.symbol.cmp(&b.symbol)));
+
+        Ok(results)
+    }
+}
+
+fn stats_key(prefix: &[u8], day: u64, symbol: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(prefix.len() + symbol.len() + 8);
+    key.extend_from_slice(prefix);
+    key.extend_from_slice(day.to_string().as_bytes());
+    key.push(b':');
+    key.extend_from_slice(symbol.as_bytes());
+    key
+}
+
+fn parse_key(prefix: &[u8], key: &[u8]) -> Option<(u64, String)> {
+    let (day, symbol) = std::str::from_utf8(key.strip_prefix(prefix)?)
+        .ok()?
+        .split_once(':')?;
+
+    Some((day.parse().ok()?, symbol.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbol_stats_key() {
+        let key = stats_key(HITS_PREFIX, 20012, "BAYES_SPAM");
+        assert_eq!(key, b"sh:20012:BAYES_SPAM");
+        assert_eq!(
+            parse_key(HITS_PREFIX, &key),
+            Some((20012, "BAYES_SPAM".to_string()))
+        );
+        assert_eq!(parse_key(SCORE_PREFIX, &key), None);
+        assert_eq!(parse_key(HITS_PREFIX, b"sh:abc:BAYES_SPAM"), None);
+    }
+}
@@ -0,0 +1,519 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use ahash::AHashSet;
+use mail_parser::{parsers::fields::thread::thread_name, MessageParser};
+use nlp::{
+    bayes::{
+        cache::BayesTokenCache,
+        tokenize::{BayesTokenizer, BayesTokenizerConfig},
+        BayesModel, TokenHash, Weights,
+    },
+    tokenizers::osb::OsbTokenizer,
+};
+use sieve::runtime::Variable;
+use store::{
+    write::{
+        key::{DeserializeBigEndian, KeySerializer},
+        now, BatchBuilder, LookupClass, Operation, ValueClass, ValueOp,
+    },
+    IterateParams, LookupStore, Store, ValueKey, U64_LEN,
+};
+use trc::AddContext;
+
+use crate::{config::scripts::BayesConfig, scripts::plugins::lookup::VariableWrapper, Core};
+
+pub const TRAINING_DATA_VERSION: u32 = 1;
+
+// Reputation tokens are keyed by remote IP, sender address, sender domain and ASN
+const REPUTATION_PREFIXES: [&[u8]; 4] = [b"i:", b"f:", b"d:", b"a:"];
+const BATCH_SIZE: usize = 1000;
+
+// Fingerprint of the tokenizer settings the Bayes tokens were learned with
+const TOKENIZER_KEY: &[u8] = b"bayes:tokenizer";
+
+// Portable snapshot of the Bayes token database and the sender reputation
+// data kept by the spam filter in its lookup store.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpamTrainingData {
+    pub version: u32,
+    pub bayes: BayesTrainingData,
+    pub reputation: Vec<ReputationEntry>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BayesTrainingData {
+    pub spam_learns: u32,
+    pub ham_learns: u32,
+    // Token hash (h1, h2) followed by its spam and ham weights
+    pub tokens: Vec<(u64, u64, u32, u32)>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReputationEntry {
+    pub token: String,
+    pub score: f64,
+    pub count: i64,
+    pub expires: u64,
+}
+
+impl SpamTrainingData {
+    pub async fn export(lookup: &LookupStore) -> trc::Result<Self> {
+        let store = data_store(lookup)?;
+        let current_time = now();
+
+        // Counters with an expiration are used for rate limiting
+        let mut expiring_counters = AHashSet::new();
+        let mut data = SpamTrainingData {
+            version: TRAINING_DATA_VERSION,
+            ..Default::default()
+        };
+        store
+            .iterate(
+                IterateParams::new(
+                    ValueKey::from(ValueClass::Lookup(LookupClass::Key(vec![0u8]))),
+                    ValueKey::from(ValueClass::Lookup(LookupClass::Key(vec![u8::MAX; 10]))),
+                ),
+                |key, value| {
+                    let expires = value.deserialize_be_u64(0).caused_by(trc::location!())?;
+                    if expires == 0 {
+                        expiring_counters.insert(key.to_vec());
+                    } else if expires > current_time
+                        && REPUTATION_PREFIXES
+                            .iter()
+                            .any(|prefix| key.starts_with(prefix))
+                    {
+                        if let Some((score, count)) = value
+                            .get(U64_LEN..)
+                            .and_then(|value| bincode::deserialize::<Variable>(value).ok())
+                            .and_then(|value| reputation_value(&value))
+                        {
+                            data.reputation.push(ReputationEntry {
+                                token: String::from_utf8_lossy(key).into_owned(),
+                                score,
+                                count,
+                                expires,
+                            });
+                        }
+                    }
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        // Bayes tokens are stored as counters keyed by their 128-bit hash
+        let mut token_keys = Vec::new();
+        store
+            .iterate(
+                IterateParams::new(
+                    ValueKey::from(ValueClass::Lookup(LookupClass::Counter(vec![0u8]))),
+                    ValueKey::from(ValueClass::Lookup(LookupClass::Counter(vec![u8::MAX; 17]))),
+                )
+                .no_values(),
+                |key, _| {
+                    if key.len() == U64_LEN * 2 && !expiring_counters.contains(key) {
+                        token_keys.push(key.to_vec());
+                    }
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        for key in token_keys {
+            let weights = Weights::from(
+                store
+                    .get_counter(ValueKey::from(ValueClass::Lookup(LookupClass::Counter(
+                        key.clone(),
+                    ))))
+                    .await
+                    .caused_by(trc::location!())?,
+            );
+            let h1 = key.as_slice().deserialize_be_u64(0)?;
+            let h2 = key.as_slice().deserialize_be_u64(U64_LEN)?;
+
+            if h1 == 0 && h2 == 0 {
+                data.bayes.spam_learns = weights.spam;
+                data.bayes.ham_learns = weights.ham;
+            } else if weights.spam != 0 || weights.ham != 0 {
+                data.bayes.tokens.push((h1, h2, weights.spam, weights.ham));
+            }
+        }
+
+        Ok(data)
+    }
+
+    // Imports the training data into a lookup store. When merging, the Bayes
+    // weights are added to the existing ones and reputation scores are averaged
+    // by their number of observations. Otherwise, the existing training data is
+    // removed first.
+    pub async fn import(&self, lookup: &LookupStore, merge: bool) -> trc::Result<()> {
+        if self.version != TRAINING_DATA_VERSION {
+            return Err(trc::ResourceEvent::BadParameters
+                .into_err()
+                .details(format!(
+                    "Unsupported training data version {}",
+                    self.version
+                )));
+        }
+
+        if !merge {
+            let existing = Self::export(lookup).await?;
+            let mut batch = BatchBuilder::new();
+            for (h1, h2, _, _) in existing.bayes.tokens.iter().copied().chain([(0, 0, 0, 0)]) {
+                batch.ops.push(Operation::Value {
+                    class: ValueClass::Lookup(LookupClass::Counter(token_key(h1, h2))),
+                    op: ValueOp::Clear,
+                });
+                batch = flush_batch(lookup, batch).await?;
+            }
+            for entry in existing.reputation {
+                batch.ops.push(Operation::Value {
+                    class: ValueClass::Lookup(LookupClass::Key(entry.token.into_bytes())),
+                    op: ValueOp::Clear,
+                });
+                batch = flush_batch(lookup, batch).await?;
+            }
+            write_batch(lookup, batch).await?;
+        }
+
+        // Add Bayes weights, in batches when the lookup store is a data store
+        let mut batch = BatchBuilder::new();
+        let learns = Weights {
+            spam: self.bayes.spam_learns,
+            ham: self.bayes.ham_learns,
+        };
+        for (h1, h2, weights) in self
+            .bayes
+            .tokens
+            .iter()
+            .map(|(h1, h2, spam, ham)| {
+                (
+                    *h1,
+                    *h2,
+                    Weights {
+                        spam: *spam,
+                        ham: *ham,
+                    },
+                )
+            })
+            .chain([(0, 0, learns)])
+        {
+            if let LookupStore::Store(_) = lookup {
+                batch.ops.push(Operation::Value {
+                    class: ValueClass::Lookup(LookupClass::Counter(token_key(h1, h2))),
+                    op: ValueOp::AtomicAdd(weights.into()),
+                });
+                batch = flush_batch(lookup, batch).await?;
+            } else {
+                lookup
+                    .counter_incr(token_key(h1, h2), weights.into(), None, false)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+        }
+        write_batch(lookup, batch).await?;
+
+        // Set reputation scores
+        let current_time = now();
+        for entry in &self.reputation {
+            if entry.expires <= current_time
+                || entry.count <= 0
+                || !REPUTATION_PREFIXES
+                    .iter()
+                    .any(|prefix| entry.token.as_bytes().starts_with(prefix))
+            {
+                continue;
+            }
+            let key = entry.token.as_bytes().to_vec();
+
+            let (score, count) = if merge {
+                match lookup
+                    .key_get::<VariableWrapper>(key.clone())
+                    .await
+                    .caused_by(trc::location!())?
+                    .and_then(|value| reputation_value(&value.into_inner()))
+                {
+                    Some((score, count)) if count > 0 => {
+                        let total = count + entry.count;
+                        (
+                            (score * count as f64 + entry.score * entry.count as f64)
+                                / total as f64,
+                            total,
+                        )
+                    }
+                    _ => (entry.score, entry.count),
+                }
+            } else {
+                (entry.score, entry.count)
+            };
+
+            lookup
+                .key_set(
+                    key,
+                    bincode::serialize(&Variable::Array(
+                        vec![Variable::Float(score), Variable::Integer(count)].into(),
+                    ))
+                    .unwrap_or_default(),
+                    Some(entry.expires - current_time),
+                )
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+}
+
+// Trains the Bayes classifier with a single message from a labeled corpus,
+// tokenizing the subject and text body the same way the training script does.
+// When a token limit is set, only the first tokens of the message are learned.
+// Returns the number of distinct tokens learned, messages without any text are
+// skipped rather than failing the whole corpus.
+pub async fn train_message(
+    lookup: &LookupStore,
+    cache: &BayesTokenCache,
+    tokenizer: BayesTokenizerConfig,
+    raw_message: &[u8],
+    is_spam: bool,
+    max_tokens: usize,
+) -> trc::Result<usize> {
+    let message = match MessageParser::new().parse(raw_message) {
+        Some(message) => message,
+        None => return Ok(0),
+    };
+    let mut text = thread_name(message.subject().unwrap_or_default()).to_string();
+    for idx in 0..message.text_body.len() {
+        if let Some(body) = message.body_text(idx) {
+            text.push(' ');
+            text.push_str(body.as_ref());
+        }
+    }
+
+    let mut model = BayesModel::default();
+    let tokens = OsbTokenizer::new(BayesTokenizer::with_config(&text, tokenizer), 5);
+    if max_tokens > 0 {
+        model.train(tokens.take(max_tokens), is_spam);
+    } else {
+        model.train(tokens, is_spam);
+    }
+    if model.weights.is_empty() {
+        return Ok(0);
+    }
+    let num_tokens = model.weights.len();
+
+    trc::event!(
+        Spam(trc::SpamEvent::Train),
+        Details = is_spam,
+        Total = num_tokens,
+    );
+
+    // Add weights and the training count, invalidating any cached weights
+    let learns = if is_spam {
+        Weights { spam: 1, ham: 0 }
+    } else {
+        Weights { spam: 0, ham: 1 }
+    };
+    let mut batch = BatchBuilder::new();
+    for (hash, weights) in model
+        .weights
+        .into_iter()
+        .chain([(TokenHash::default(), learns)])
+    {
+        if let LookupStore::Store(_) = lookup {
+            batch.ops.push(Operation::Value {
+                class: ValueClass::Lookup(LookupClass::Counter(token_key(hash.h1, hash.h2))),
+                op: ValueOp::AtomicAdd(weights.into()),
+            });
+            batch = flush_batch(lookup, batch).await?;
+        } else {
+            lookup
+                .counter_incr(token_key(hash.h1, hash.h2), weights.into(), None, false)
+                .await
+                .caused_by(trc::location!())?;
+        }
+        cache.invalidate(&hash);
+    }
+    write_batch(lookup, batch).await?;
+
+    Ok(num_tokens)
+}
+
+// Records the tokenizer settings the Bayes tokens in a lookup store are learned
+// with. Tokens learned with different settings no longer match any message, so
+// they are either left to be outweighed by new training or removed, depending
+// on the configuration. Returns whether the settings changed.
+pub async fn migrate_tokenizer(
+    lookup: &LookupStore,
+    cache: &BayesTokenCache,
+    config: &BayesConfig,
+) -> trc::Result<bool> {
+    // Tokens learned before the settings were recorded used the defaults
+    let fingerprint = config.tokenizer.fingerprint();
+    let previous = lookup
+        .key_get::<String>(TOKENIZER_KEY.to_vec())
+        .await
+        .caused_by(trc::location!())?
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or_else(|| BayesTokenizerConfig::default().fingerprint());
+    if previous == fingerprint {
+        return Ok(false);
+    }
+
+    if config.reset_on_change {
+        let existing = SpamTrainingData::export(lookup).await?;
+        let mut batch = BatchBuilder::new();
+        for (h1, h2, _, _) in existing.bayes.tokens.iter().copied().chain([(0, 0, 0, 0)]) {
+            batch.ops.push(Operation::Value {
+                class: ValueClass::Lookup(LookupClass::Counter(token_key(h1, h2))),
+                op: ValueOp::Clear,
+            });
+            batch = flush_batch(lookup, batch).await?;
+        }
+        write_batch(lookup, batch).await?;
+        cache.clear();
+    }
+
+    lookup
+        .key_set(
+            TOKENIZER_KEY.to_vec(),
+            fingerprint.to_string().into_bytes(),
+            None,
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+    trc::event!(
+        Spam(trc::SpamEvent::TokenizerChanged),
+        Details = config.reset_on_change,
+    );
+
+    Ok(true)
+}
+
+impl Core {
+    // Returns the lookup store configured in the spam filter settings, unless
+    // a different store is requested.
+    pub async fn spam_filter_store(&self, id: Option<&str>) -> trc::Result<LookupStore> {
+        let id = match id {
+            Some(id) => Some(id.to_string()),
+            None => match self.storage.lookups.get("spam-config") {
+                Some(config) => config
+                    .key_get::<VariableWrapper>("lookup".as_bytes().to_vec())
+                    .await?
+                    .map(|value| value.into_inner().to_string().into_owned()),
+                None => None,
+            },
+        };
+
+        match id.filter(|id| !id.is_empty()) {
+            Some(id) => self
+                .storage
+                .lookups
+                .get(&id)
+                .cloned()
+                .ok_or_else(|| trc::ResourceEvent::NotFound.into_err()),
+            None => Ok(self.storage.lookup.clone()),
+        }
+    }
+}
+
+fn data_store(lookup: &LookupStore) -> trc::Result<&Store> {
+    if let LookupStore::Store(store) = lookup {
+        Ok(store)
+    } else {
+        Err(trc::StoreEvent::NotSupported
+            .into_err()
+            .details("Spam filter training data can only be exported from a data store"))
+    }
+}
+
+async fn flush_batch(lookup: &LookupStore, batch: BatchBuilder) -> trc::Result<BatchBuilder> {
+    if batch.ops.len() >= BATCH_SIZE {
+        data_store(lookup)?
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())?;
+        Ok(BatchBuilder::new())
+    } else {
+        Ok(batch)
+    }
+}
+
+async fn write_batch(lookup: &LookupStore, batch: BatchBuilder) -> trc::Result<()> {
+    if !batch.ops.is_empty() {
+        data_store(lookup)?
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())?;
+    }
+    Ok(())
+}
+
+fn token_key(h1: u64, h2: u64) -> Vec<u8> {
+    KeySerializer::new(U64_LEN * 2)
+        .write(h1)
+        .write(h2)
+        .finalize()
+}
+
+fn reputation_value(value: &Variable) -> Option<(f64, i64)> {
+    let items = value.as_array()?;
+    let score = match items.first()? {
+        Variable::Float(score) => *score,
+        Variable::Integer(score) => *score as f64,
+        _ => return None,
+    };
+
+    Some((score, items.get(1)?.to_integer()))
+}
+
+#[cfg(test)]
+mod tests {
+    use sieve::runtime::Variable;
+
+    use super::{reputation_value, SpamTrainingData, TRAINING_DATA_VERSION};
+
+    #[test]
+    fn training_data_format() {
+        let data = serde_json::from_str::<SpamTrainingData>(
+            r#"{
+                "version": 1,
+                "bayes": {
+                    "spamLearns": 10,
+                    "hamLearns": 20,
+                    "tokens": [[1, 2, 3, 4]]
+                },
+                "reputation": [
+                    {"token": "i:192.0.2.1", "score": 5.5, "count": 2, "expires": 100}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(data.version, TRAINING_DATA_VERSION);
+        assert_eq!(data.bayes.tokens, vec![(1, 2, 3, 4)]);
+        assert_eq!(data.reputation[0].token, "i:192.0.2.1");
+        assert_eq!(
+            serde_json::from_str::<SpamTrainingData>(&serde_json::to_string(&data).unwrap())
+                .unwrap(),
+            data
+        );
+
+        assert_eq!(
+            reputation_value(&Variable::Array(
+                vec![Variable::Integer(3), Variable::Integer(1)].into()
+            )),
+            Some((3.0, 1))
+        );
+        assert_eq!(reputation_value(&Variable::Integer(3)), None);
+    }
+}
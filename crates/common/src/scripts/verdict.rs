@@ -0,0 +1,100 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::config::jmap::settings::JmapConfig;
+
+// Spam filter and antivirus verdicts obtained while a message is received,
+// passed on to local delivery so that Sieve scripts do not rely on headers
+// that could have been added by the sender.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FilterVerdict {
+    pub spam_score: Option<f64>,
+    pub is_spam: bool,
+    pub virus: VirusVerdict,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum VirusVerdict {
+    #[default]
+    Unknown = 0,
+    Clean = 1,
+    Replaced = 2,
+    Cured = 3,
+    MaybeVirus = 4,
+    Virus = 5,
+}
+
+impl FilterVerdict {
+    // Updates the verdict from a status header added by the filter
+    pub fn add_header(&mut self, config: &JmapConfig, name: &str, value: &str) {
+        let value = value.trim();
+        if let Some((spam_name, spam_value)) = &config.spam_header {
+            if spam_name.as_str().eq_ignore_ascii_case(name) && value.contains(spam_value.as_str())
+            {
+                self.is_spam = true;
+            }
+        }
+        if config
+            .spam_score_header
+            .as_ref()
+            .map_or(false, |header| header.as_str().eq_ignore_ascii_case(name))
+        {
+            if let Some(score) = value
+                .split_once("score=")
+                .and_then(|(_, score)| {
+                    score
+                        .split(|c: char| c == ',' || c == ';' || c.is_ascii_whitespace())
+                        .next()
+                })
+                .and_then(|score| score.parse::<f64>().ok())
+            {
+                self.spam_score = Some(score);
+            }
+        }
+        if config
+            .virus_header
+            .as_ref()
+            .map_or(false, |header| header.as_str().eq_ignore_ascii_case(name))
+        {
+            self.virus = VirusVerdict::parse(value);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spam_score.is_none() && !self.is_spam && self.virus == VirusVerdict::Unknown
+    }
+}
+
+impl VirusVerdict {
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim().to_ascii_lowercase();
+        if value.starts_with("clean") {
+            VirusVerdict::Clean
+        } else if value.starts_with("replaced") || value.starts_with("removed") {
+            VirusVerdict::Replaced
+        } else if value.starts_with("cured") || value.starts_with("repaired") {
+            VirusVerdict::Cured
+        } else if value.starts_with("suspect") || value.starts_with("maybe") {
+            VirusVerdict::MaybeVirus
+        } else if value.starts_with("infected") || value.starts_with("virus") {
+            VirusVerdict::Virus
+        } else {
+            VirusVerdict::Unknown
+        }
+    }
+
+    pub fn from_id(id: u8) -> Self {
+        match id {
+            1 => VirusVerdict::Clean,
+            2 => VirusVerdict::Replaced,
+            3 => VirusVerdict::Cured,
+            4 => VirusVerdict::MaybeVirus,
+            5 => VirusVerdict::Virus,
+            _ => VirusVerdict::Unknown,
+        }
+    }
+}
@@ -108,6 +108,9 @@ impl MetricsStore for Store {
                 EventType::Security(SecurityEvent::AuthenticationBan),
                 EventType::Security(SecurityEvent::BruteForceBan),
                 EventType::Security(SecurityEvent::LoiterBan),
+                EventType::Security(SecurityEvent::EarlyTalker),
+                EventType::Security(SecurityEvent::EarlyTalkerBan),
+                EventType::Security(SecurityEvent::ScriptBan),
                 EventType::Security(SecurityEvent::IpBlocked),
                 EventType::IncomingReport(IncomingReportEvent::DmarcReport),
                 EventType::IncomingReport(IncomingReportEvent::DmarcReportWithWarnings),
@@ -394,6 +394,11 @@ impl StoreTracer {
                                 | QueueEvent::RateLimitExceeded
                                 | QueueEvent::ConcurrencyLimitExceeded
                                 | QueueEvent::QuotaExceeded
+                                | QueueEvent::MessageAccepted
+                                | QueueEvent::MessageDelivered
+                                | QueueEvent::MessageDeferred
+                                | QueueEvent::MessageBounced
+                                | QueueEvent::MessageExpired
                         )
                         | EventType::Limit(_)
                         | EventType::Tls(_)
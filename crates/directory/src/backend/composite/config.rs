@@ -0,0 +1,64 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use ahash::AHashMap;
+use utils::config::{utils::AsKey, Config};
+
+use crate::Directory;
+
+use super::CompositeDirectory;
+
+impl CompositeDirectory {
+    pub fn from_config(
+        config: &mut Config,
+        prefix: impl AsKey,
+        directories: &AHashMap<String, Arc<Directory>>,
+    ) -> Option<Self> {
+        let prefix = prefix.as_key();
+        let mut composite = CompositeDirectory {
+            directories: Vec::new(),
+            domains: AHashMap::new(),
+        };
+
+        for (key, id) in config
+            .values((&prefix, "directories"))
+            .map(|(key, id)| (key.to_string(), id.to_string()))
+            .collect::<Vec<_>>()
+        {
+            if let Some(directory) = directories.get(&id) {
+                composite.directories.push(directory.clone());
+            } else {
+                config.new_parse_error(key, format!("Directory {id:?} does not exist"));
+            }
+        }
+
+        for (domain, id) in config
+            .iterate_prefix((&prefix, "routing"))
+            .map(|(domain, id)| (domain.to_string(), id.to_string()))
+            .collect::<Vec<_>>()
+        {
+            if let Some(directory) = directories.get(&id) {
+                composite
+                    .domains
+                    .insert(domain.to_lowercase(), directory.clone());
+            } else {
+                config.new_parse_error(
+                    (prefix.as_str(), "routing", domain.as_str()),
+                    format!("Directory {id:?} does not exist"),
+                );
+            }
+        }
+
+        if !composite.directories.is_empty() {
+            Some(composite)
+        } else {
+            config.new_parse_error((&prefix, "directories"), "No directories configured");
+            None
+        }
+    }
+}
@@ -0,0 +1,119 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use mail_send::Credentials;
+
+use crate::{Principal, QueryBy};
+
+use super::CompositeDirectory;
+
+impl CompositeDirectory {
+    pub async fn query(
+        &self,
+        by: QueryBy<'_>,
+        return_member_of: bool,
+    ) -> trc::Result<Option<Principal>> {
+        let name = match &by {
+            QueryBy::Name(name) => Some(*name),
+            QueryBy::Credentials(
+                Credentials::Plain { username, .. } | Credentials::XOauth2 { username, .. },
+            ) => Some(username.as_str()),
+            QueryBy::Credentials(Credentials::OAuthBearer { .. }) | QueryBy::Id(_) => None,
+        };
+        if let Some(directory) = name.and_then(|name| self.route(name)) {
+            return Box::pin(directory.query(by, return_member_of)).await;
+        }
+
+        // Fall back to the next directory if the principal is not found
+        for directory in &self.directories {
+            if let Some(principal) = Box::pin(directory.query(by, return_member_of)).await? {
+                return Ok(Some(principal));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub async fn email_to_ids(&self, address: &str) -> trc::Result<Vec<u32>> {
+        if let Some(directory) = self.route(address) {
+            return Box::pin(directory.email_to_ids(address)).await;
+        }
+
+        // Merge the accounts found on all directories
+        let mut ids = Vec::new();
+        for directory in &self.directories {
+            for id in Box::pin(directory.email_to_ids(address)).await? {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+
+    pub async fn is_local_domain(&self, domain: &str) -> trc::Result<bool> {
+        if let Some(directory) = self.domains.get(&domain.to_lowercase()) {
+            return Box::pin(directory.is_local_domain(domain)).await;
+        }
+
+        for directory in &self.directories {
+            if Box::pin(directory.is_local_domain(domain)).await? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub async fn rcpt(&self, address: &str) -> trc::Result<bool> {
+        if let Some(directory) = self.route(address) {
+            return Box::pin(directory.rcpt(address)).await;
+        }
+
+        for directory in &self.directories {
+            if Box::pin(directory.rcpt(address)).await? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub async fn vrfy(&self, address: &str) -> trc::Result<Vec<String>> {
+        if let Some(directory) = self.route(address) {
+            return Box::pin(directory.vrfy(address)).await;
+        }
+
+        let mut addresses = Vec::new();
+        for directory in &self.directories {
+            for address in Box::pin(directory.vrfy(address)).await? {
+                if !addresses.contains(&address) {
+                    addresses.push(address);
+                }
+            }
+        }
+
+        Ok(addresses)
+    }
+
+    pub async fn expn(&self, address: &str) -> trc::Result<Vec<String>> {
+        if let Some(directory) = self.route(address) {
+            return Box::pin(directory.expn(address)).await;
+        }
+
+        let mut addresses = Vec::new();
+        for directory in &self.directories {
+            for address in Box::pin(directory.expn(address)).await? {
+                if !addresses.contains(&address) {
+                    addresses.push(address);
+                }
+            }
+        }
+
+        Ok(addresses)
+    }
+}
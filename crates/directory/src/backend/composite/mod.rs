@@ -0,0 +1,37 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use ahash::AHashMap;
+
+use crate::{Directory, DirectoryInner};
+
+pub mod config;
+pub mod lookup;
+
+// Directories consulted in order, with domains optionally routed to
+// the single directory that is authoritative for them
+pub struct CompositeDirectory {
+    directories: Vec<Arc<Directory>>,
+    domains: AHashMap<String, Arc<Directory>>,
+}
+
+impl CompositeDirectory {
+    // Returns the directory authoritative for the domain of an address
+    fn route(&self, address: &str) -> Option<&Arc<Directory>> {
+        address
+            .rsplit_once('@')
+            .and_then(|(_, domain)| self.domains.get(&domain.to_lowercase()))
+    }
+
+    pub fn has_internal(&self) -> bool {
+        self.directories
+            .iter()
+            .chain(self.domains.values())
+            .any(|directory| matches!(directory.store, DirectoryInner::Internal(_)))
+    }
+}
@@ -0,0 +1,133 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{str::FromStr, time::Duration};
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use store::Store;
+use utils::config::{utils::AsKey, Config};
+
+use super::{CircuitBreaker, HttpDirectory, HttpEndpoints, HttpFields};
+
+impl HttpDirectory {
+    pub fn from_config(config: &mut Config, prefix: impl AsKey, data_store: Store) -> Option<Self> {
+        let prefix = prefix.as_key();
+        let url = config
+            .value_require((&prefix, "url"))?
+            .trim_end_matches('/')
+            .to_string();
+
+        let mut headers = HeaderMap::new();
+        for (key, value) in config
+            .values((&prefix, "headers"))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+        {
+            match value.split_once(':').and_then(|(name, value)| {
+                Some((
+                    HeaderName::from_str(name.trim()).ok()?,
+                    HeaderValue::from_str(value.trim()).ok()?,
+                ))
+            }) {
+                Some((name, value)) => {
+                    headers.insert(name, value);
+                }
+                None => {
+                    config.new_parse_error(key, format!("Invalid header {value:?}"));
+                }
+            }
+        }
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        let auth = config.value((&prefix, "auth.username")).map(|username| {
+            (
+                username.to_string(),
+                config
+                    .value((&prefix, "auth.secret"))
+                    .unwrap_or_default()
+                    .to_string(),
+            )
+        });
+
+        let client = reqwest::Client::builder()
+            .timeout(
+                config
+                    .property_or_default((&prefix, "timeout"), "15s")
+                    .unwrap_or_else(|| Duration::from_secs(15)),
+            )
+            .danger_accept_invalid_certs(
+                config
+                    .property_or_default((&prefix, "allow-invalid-certs"), "false")
+                    .unwrap_or_default(),
+            )
+            .build()
+            .map_err(|err| {
+                config.new_build_error(
+                    prefix.as_str(),
+                    format!("Failed to build HTTP client: {err}"),
+                )
+            })
+            .ok()?;
+
+        let mut endpoints = HttpEndpoints::default();
+        for (endpoint_id, endpoint) in [
+            ("name", &mut endpoints.name),
+            ("auth", &mut endpoints.auth),
+            ("recipients", &mut endpoints.recipients),
+            ("verify", &mut endpoints.verify),
+            ("expand", &mut endpoints.expand),
+            ("domains", &mut endpoints.domains),
+        ] {
+            *endpoint = config
+                .value((prefix.as_str(), "endpoints", endpoint_id))
+                .unwrap_or_default()
+                .to_string();
+        }
+        if endpoints.name.is_empty() {
+            config.new_parse_error(
+                (&prefix, "endpoints.name"),
+                "Missing principal lookup endpoint",
+            );
+            return None;
+        }
+
+        let mut fields = HttpFields::default();
+        for (field_id, field, default) in [
+            ("name", &mut fields.name, "/name"),
+            ("class", &mut fields.class, "/type"),
+            ("description", &mut fields.description, "/description"),
+            ("secret", &mut fields.secret, "/secret"),
+            ("email", &mut fields.email, "/emails"),
+            ("quota", &mut fields.quota, "/quota"),
+            ("member-of", &mut fields.member_of, "/memberOf"),
+        ] {
+            *field = config
+                .value((prefix.as_str(), "fields", field_id))
+                .unwrap_or(default)
+                .to_string();
+        }
+
+        Some(HttpDirectory {
+            client,
+            url,
+            headers,
+            auth,
+            max_response_size: config
+                .property_or_default((&prefix, "max-response-size"), "1048576")
+                .unwrap_or(1048576),
+            endpoints,
+            fields,
+            breaker: CircuitBreaker::new(
+                config
+                    .property_or_default((&prefix, "circuit-breaker.failures"), "5")
+                    .unwrap_or(5),
+                config
+                    .property_or_default((&prefix, "circuit-breaker.wait"), "30s")
+                    .unwrap_or_else(|| Duration::from_secs(30)),
+            ),
+            data_store,
+        })
+    }
+}
@@ -0,0 +1,369 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use mail_send::Credentials;
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use trc::AddContext;
+
+use crate::{
+    backend::internal::{
+        lookup::DirectoryStore,
+        manage::{self, ManageDirectory, UpdatePrincipal},
+        PrincipalField, PrincipalValue,
+    },
+    Principal, QueryBy, Type, ROLE_ADMIN, ROLE_USER,
+};
+
+use super::{HttpDirectory, HttpFields};
+
+impl HttpDirectory {
+    pub async fn query(
+        &self,
+        by: QueryBy<'_>,
+        return_member_of: bool,
+    ) -> trc::Result<Option<Principal>> {
+        let (external_principal, stored_principal) = match by {
+            QueryBy::Name(username) => (
+                self.send(Method::GET, &self.endpoints.name, username, None)
+                    .await
+                    .caused_by(trc::location!())?
+                    .map(|response| self.fields.to_principal(username, &response)),
+                None,
+            ),
+            QueryBy::Id(uid) => {
+                if let Some(principal) = self
+                    .data_store
+                    .query(QueryBy::Id(uid), return_member_of)
+                    .await
+                    .caused_by(trc::location!())?
+                {
+                    (
+                        self.send(Method::GET, &self.endpoints.name, principal.name(), None)
+                            .await
+                            .caused_by(trc::location!())?
+                            .map(|response| self.fields.to_principal(principal.name(), &response)),
+                        Some(principal),
+                    )
+                } else {
+                    return Ok(None);
+                }
+            }
+            QueryBy::Credentials(credentials) => {
+                let (username, secret) = match credentials {
+                    Credentials::Plain { username, secret } => (username, secret),
+                    Credentials::OAuthBearer { token } => (token, token),
+                    Credentials::XOauth2 { username, secret } => (username, secret),
+                };
+
+                if !self.endpoints.auth.is_empty() {
+                    // The backend verifies the credentials
+                    (
+                        self.send(
+                            Method::POST,
+                            &self.endpoints.auth,
+                            username,
+                            Some(json!({ "username": username, "secret": secret })),
+                        )
+                        .await
+                        .caused_by(trc::location!())?
+                        .map(|response| self.fields.to_principal(username, &response)),
+                        None,
+                    )
+                } else {
+                    match self
+                        .send(Method::GET, &self.endpoints.name, username, None)
+                        .await
+                        .caused_by(trc::location!())?
+                        .map(|response| self.fields.to_principal(username, &response))
+                    {
+                        Some((principal, member_of))
+                            if principal
+                                .verify_secret(secret)
+                                .await
+                                .caused_by(trc::location!())? =>
+                        {
+                            (Some((principal, member_of)), None)
+                        }
+                        _ => (None, None),
+                    }
+                }
+            }
+        };
+
+        let (mut external_principal, member_of) =
+            if let Some(external_principal) = external_principal {
+                external_principal
+            } else {
+                return Ok(None);
+            };
+
+        // Obtain members
+        if return_member_of {
+            for name in member_of {
+                external_principal.append_int(
+                    PrincipalField::MemberOf,
+                    self.data_store
+                        .get_or_create_principal_id(&name, Type::Group)
+                        .await
+                        .caused_by(trc::location!())?,
+                );
+            }
+        }
+
+        // Obtain account ID if not available
+        let mut principal = if let Some(stored_principal) = stored_principal {
+            stored_principal
+        } else {
+            let id = self
+                .data_store
+                .get_or_create_principal_id(external_principal.name(), Type::Individual)
+                .await
+                .caused_by(trc::location!())?;
+
+            self.data_store
+                .query(QueryBy::Id(id), return_member_of)
+                .await
+                .caused_by(trc::location!())?
+                .ok_or_else(|| manage::not_found(id).caused_by(trc::location!()))?
+        };
+
+        // Keep the internal store up to date with the HTTP backend
+        let changes = principal.update_external(external_principal);
+        if !changes.is_empty() {
+            self.data_store
+                .update_principal(
+                    UpdatePrincipal::by_id(principal.id)
+                        .with_updates(changes)
+                        .no_validate(),
+                )
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(Some(principal))
+    }
+
+    pub async fn email_to_ids(&self, address: &str) -> trc::Result<Vec<u32>> {
+        let names = self
+            .send_list(&self.endpoints.recipients, address)
+            .await
+            .caused_by(trc::location!())?;
+        let mut ids = Vec::with_capacity(names.len());
+
+        for name in names {
+            ids.push(
+                self.data_store
+                    .get_or_create_principal_id(&name, Type::Individual)
+                    .await
+                    .caused_by(trc::location!())?,
+            );
+        }
+
+        Ok(ids)
+    }
+
+    pub async fn rcpt(&self, address: &str) -> trc::Result<bool> {
+        self.send_list(&self.endpoints.recipients, address)
+            .await
+            .map(|names| !names.is_empty())
+    }
+
+    pub async fn vrfy(&self, address: &str) -> trc::Result<Vec<String>> {
+        self.send_list(&self.endpoints.verify, address).await
+    }
+
+    pub async fn expn(&self, address: &str) -> trc::Result<Vec<String>> {
+        self.send_list(&self.endpoints.expand, address).await
+    }
+
+    pub async fn is_local_domain(&self, domain: &str) -> trc::Result<bool> {
+        if !self.endpoints.domains.is_empty() {
+            self.send(Method::GET, &self.endpoints.domains, domain, None)
+                .await
+                .map(|response| response.is_some())
+        } else {
+            Ok(false)
+        }
+    }
+
+    async fn send_list(&self, endpoint: &str, param: &str) -> trc::Result<Vec<String>> {
+        if !endpoint.is_empty() {
+            self.send(Method::GET, endpoint, param, None)
+                .await
+                .map(|response| response.as_ref().map(to_list).unwrap_or_default())
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    // Returns `None` when the backend reports that the item does not exist
+    async fn send(
+        &self,
+        method: Method,
+        endpoint: &str,
+        param: &str,
+        body: Option<Value>,
+    ) -> trc::Result<Option<Value>> {
+        if self.breaker.is_open() {
+            return Err(trc::StoreEvent::HttpError
+                .reason("Circuit breaker open")
+                .ctx(trc::Key::Url, self.url.clone()));
+        }
+
+        let param = form_urlencoded::byte_serialize(param.as_bytes()).collect::<String>();
+        let url = format!(
+            "{}{}",
+            self.url,
+            endpoint
+                .replace("{name}", &param)
+                .replace("{address}", &param)
+                .replace("{domain}", &param)
+        );
+        let mut request = self
+            .client
+            .request(method, &url)
+            .headers(self.headers.clone());
+        if let Some((username, secret)) = &self.auth {
+            request = request.basic_auth(username, Some(secret));
+        }
+        if let Some(body) = body {
+            request = request.body(body.to_string());
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(err) => {
+                self.breaker.failure();
+                return Err(trc::StoreEvent::HttpError
+                    .reason(err)
+                    .ctx(trc::Key::Url, url));
+            }
+        };
+
+        let status = response.status();
+        if status.is_server_error() {
+            self.breaker.failure();
+        } else {
+            self.breaker.success();
+        }
+        match status {
+            StatusCode::NOT_FOUND | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                return Ok(None)
+            }
+            status if !status.is_success() => {
+                return Err(trc::StoreEvent::HttpError
+                    .reason("Unexpected HTTP status")
+                    .ctx(trc::Key::Url, url)
+                    .ctx(trc::Key::Code, status.as_u16()));
+            }
+            _ => (),
+        }
+
+        if response
+            .content_length()
+            .is_some_and(|len| len as usize > self.max_response_size)
+        {
+            return Err(trc::StoreEvent::HttpError
+                .reason("Response too large")
+                .ctx(trc::Key::Url, url));
+        }
+        let bytes = response.bytes().await.map_err(|err| {
+            trc::StoreEvent::HttpError
+                .reason(err)
+                .ctx(trc::Key::Url, url.clone())
+        })?;
+        if bytes.len() > self.max_response_size {
+            Err(trc::StoreEvent::HttpError
+                .reason("Response too large")
+                .ctx(trc::Key::Url, url))
+        } else if bytes.is_empty() {
+            Ok(Some(Value::Null))
+        } else {
+            serde_json::from_slice(&bytes).map(Some).map_err(|err| {
+                trc::StoreEvent::HttpError
+                    .reason(err)
+                    .ctx(trc::Key::Url, url)
+            })
+        }
+    }
+}
+
+impl HttpFields {
+    // Returns the principal along with the names of the groups it belongs to
+    pub fn to_principal(&self, username: &str, response: &Value) -> (Principal, Vec<String>) {
+        let mut principal = Principal::default();
+        let mut role = ROLE_USER;
+
+        principal.set(
+            PrincipalField::Name,
+            response
+                .pointer(&self.name)
+                .and_then(|name| name.as_str())
+                .unwrap_or(username)
+                .to_string(),
+        );
+        if let Some(class) = response
+            .pointer(&self.class)
+            .and_then(|class| class.as_str())
+        {
+            match class.to_lowercase().as_str() {
+                "individual" | "person" | "user" => {
+                    principal.typ = Type::Individual;
+                }
+                "group" => principal.typ = Type::Group,
+                "admin" | "superuser" | "administrator" => {
+                    principal.typ = Type::Individual;
+                    role = ROLE_ADMIN;
+                }
+                _ => (),
+            }
+        }
+        if let Some(description) = response
+            .pointer(&self.description)
+            .and_then(|description| description.as_str())
+        {
+            principal.set(PrincipalField::Description, description.to_string());
+        }
+        if let Some(quota) = response
+            .pointer(&self.quota)
+            .and_then(|quota| quota.as_u64())
+        {
+            principal.set(PrincipalField::Quota, quota);
+        }
+        if let Some(secrets) = response.pointer(&self.secret) {
+            for secret in to_list(secrets) {
+                principal.append_str(PrincipalField::Secrets, secret);
+            }
+        }
+        if let Some(emails) = response.pointer(&self.email) {
+            principal.set(
+                PrincipalField::Emails,
+                PrincipalValue::StringList(to_list(emails)),
+            );
+        }
+
+        (
+            principal.with_field(PrincipalField::Roles, role),
+            response
+                .pointer(&self.member_of)
+                .map(to_list)
+                .unwrap_or_default(),
+        )
+    }
+}
+
+// Accepts either a single string or an array of strings
+fn to_list(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(value) => vec![value.to_string()],
+        Value::Array(values) => values
+            .iter()
+            .filter_map(|value| value.as_str().map(|value| value.to_string()))
+            .collect(),
+        _ => vec![],
+    }
+}
@@ -0,0 +1,121 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::header::HeaderMap;
+use store::Store;
+
+pub mod config;
+pub mod lookup;
+
+pub struct HttpDirectory {
+    client: reqwest::Client,
+    url: String,
+    headers: HeaderMap,
+    auth: Option<(String, String)>,
+    max_response_size: usize,
+    endpoints: HttpEndpoints,
+    fields: HttpFields,
+    breaker: CircuitBreaker,
+    pub(crate) data_store: Store,
+}
+
+// Endpoint paths, relative to the directory URL
+#[derive(Debug, Default)]
+pub(crate) struct HttpEndpoints {
+    name: String,
+    auth: String,
+    recipients: String,
+    verify: String,
+    expand: String,
+    domains: String,
+}
+
+// JSON pointers to the principal fields in endpoint responses
+#[derive(Debug, Default)]
+pub(crate) struct HttpFields {
+    name: String,
+    class: String,
+    description: String,
+    secret: String,
+    email: String,
+    quota: String,
+    member_of: String,
+}
+
+// Stops sending requests to the backend for a while after repeated failures
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    failures: AtomicU32,
+    open_until: AtomicU64,
+    max_failures: u32,
+    wait: Duration,
+}
+
+impl CircuitBreaker {
+    pub fn new(max_failures: u32, wait: Duration) -> Self {
+        CircuitBreaker {
+            failures: AtomicU32::new(0),
+            open_until: AtomicU64::new(0),
+            max_failures,
+            wait,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open_until.load(Ordering::Relaxed) > now()
+    }
+
+    pub fn success(&self) {
+        self.failures.store(0, Ordering::Relaxed);
+    }
+
+    pub fn failure(&self) {
+        if self.max_failures > 0
+            && self.failures.fetch_add(1, Ordering::Relaxed) + 1 >= self.max_failures
+        {
+            self.failures.store(0, Ordering::Relaxed);
+            self.open_until
+                .store(now() + self.wait.as_secs(), Ordering::Relaxed);
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::CircuitBreaker;
+
+    #[test]
+    fn circuit_breaker() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        breaker.failure();
+        breaker.failure();
+        breaker.success();
+        breaker.failure();
+        breaker.failure();
+        assert!(!breaker.is_open());
+        breaker.failure();
+        assert!(breaker.is_open());
+
+        let breaker = CircuitBreaker::new(0, Duration::from_secs(60));
+        for _ in 0..10 {
+            breaker.failure();
+        }
+        assert!(!breaker.is_open());
+    }
+}
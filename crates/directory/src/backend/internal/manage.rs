@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::sync::Arc;
+
 use ahash::AHashSet;
 use jmap_proto::types::collection::Collection;
 use store::{
@@ -1759,7 +1761,7 @@ impl SerializeWithId for Principal {
 
 impl From<Principal> for MaybeDynamicValue {
     fn from(principal: Principal) -> Self {
-        MaybeDynamicValue::Dynamic(Box::new(principal))
+        MaybeDynamicValue::Dynamic(Arc::new(principal))
     }
 }
 
@@ -1855,7 +1857,7 @@ impl SerializeWithId for DynamicPrincipalInfo {
 
 impl From<DynamicPrincipalInfo> for MaybeDynamicValue {
     fn from(value: DynamicPrincipalInfo) -> Self {
-        MaybeDynamicValue::Dynamic(Box::new(value))
+        MaybeDynamicValue::Dynamic(Arc::new(value))
     }
 }
 
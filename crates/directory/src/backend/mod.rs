@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+pub mod composite;
+pub mod http;
 pub mod imap;
 pub mod internal;
 pub mod ldap;
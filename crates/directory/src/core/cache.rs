@@ -6,27 +6,40 @@
 
 use std::{
     borrow::Borrow,
+    future::Future,
     hash::Hash,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
+use ahash::AHashMap;
 use parking_lot::Mutex;
+use tokio::sync::OnceCell;
 use utils::config::{utils::AsKey, Config};
 
 pub struct CachedDirectory {
     cached_domains: Mutex<LookupCache<String>>,
     cached_rcpts: Mutex<LookupCache<String>>,
+    cached_ids: Mutex<LookupCache<String, Vec<u32>>>,
+    pub(crate) pending_domains: SingleFlight<bool>,
+    pub(crate) pending_rcpts: SingleFlight<bool>,
+    pub(crate) pending_ids: SingleFlight<Vec<u32>>,
 }
 
 #[allow(clippy::type_complexity)]
 #[derive(Debug)]
-pub struct LookupCache<T: Hash + Eq> {
-    cache_pos: lru_cache::LruCache<T, Instant, ahash::RandomState>,
+pub struct LookupCache<T: Hash + Eq, V = ()> {
+    cache_pos: lru_cache::LruCache<T, (V, Instant), ahash::RandomState>,
     cache_neg: lru_cache::LruCache<T, Instant, ahash::RandomState>,
     ttl_pos: Duration,
     ttl_neg: Duration,
 }
 
+// Concurrent lookups of the same key share a single backend request
+pub struct SingleFlight<V> {
+    pending: Mutex<AHashMap<String, Arc<OnceCell<V>>>>,
+}
+
 impl CachedDirectory {
     pub fn try_from_config(config: &mut Config, prefix: impl AsKey) -> Option<Self> {
         let prefix = prefix.as_key();
@@ -49,35 +62,68 @@ impl CachedDirectory {
                 cache_ttl_positive,
                 cache_ttl_negative,
             )),
+            cached_ids: Mutex::new(LookupCache::new(
+                cached_entries,
+                cache_ttl_positive,
+                cache_ttl_negative,
+            )),
+            pending_domains: SingleFlight::default(),
+            pending_rcpts: SingleFlight::default(),
+            pending_ids: SingleFlight::default(),
         })
     }
 
     pub fn get_rcpt(&self, address: &str) -> Option<bool> {
-        self.cached_rcpts.lock().get(address)
+        self.cached_rcpts
+            .lock()
+            .get(address)
+            .map(|result| result.is_some())
     }
 
     pub fn set_rcpt(&self, address: &str, exists: bool) {
         if exists {
-            self.cached_rcpts.lock().insert_pos(address.to_string());
+            self.cached_rcpts.lock().insert_pos(address.to_string(), ());
         } else {
             self.cached_rcpts.lock().insert_neg(address.to_string());
         }
     }
 
     pub fn get_domain(&self, domain: &str) -> Option<bool> {
-        self.cached_domains.lock().get(domain)
+        self.cached_domains
+            .lock()
+            .get(domain)
+            .map(|result| result.is_some())
     }
 
     pub fn set_domain(&self, domain: &str, exists: bool) {
         if exists {
-            self.cached_domains.lock().insert_pos(domain.to_string());
+            self.cached_domains
+                .lock()
+                .insert_pos(domain.to_string(), ());
         } else {
             self.cached_domains.lock().insert_neg(domain.to_string());
         }
     }
+
+    pub fn get_ids(&self, address: &str) -> Option<Vec<u32>> {
+        self.cached_ids
+            .lock()
+            .get(address)
+            .map(|result| result.unwrap_or_default())
+    }
+
+    pub fn set_ids(&self, address: &str, ids: &[u32]) {
+        if !ids.is_empty() {
+            self.cached_ids
+                .lock()
+                .insert_pos(address.to_string(), ids.to_vec());
+        } else {
+            self.cached_ids.lock().insert_neg(address.to_string());
+        }
+    }
 }
 
-impl<T: Hash + Eq> LookupCache<T> {
+impl<T: Hash + Eq, V: Clone> LookupCache<T, V> {
     pub fn new(capacity: usize, ttl_pos: Duration, ttl_neg: Duration) -> Self {
         Self {
             cache_pos: lru_cache::LruCache::with_hasher(capacity, ahash::RandomState::new()),
@@ -87,15 +133,16 @@ impl<T: Hash + Eq> LookupCache<T> {
         }
     }
 
-    pub fn get<Q>(&mut self, name: &Q) -> Option<bool>
+    // Returns `Some(None)` for names known not to exist
+    pub fn get<Q>(&mut self, name: &Q) -> Option<Option<V>>
     where
         T: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
         // Check positive cache
-        if let Some(valid_until) = self.cache_pos.get_mut(name) {
+        if let Some((value, valid_until)) = self.cache_pos.get_mut(name) {
             if *valid_until >= Instant::now() {
-                return Some(true);
+                return Some(Some(value.clone()));
             } else {
                 self.cache_pos.remove(name);
             }
@@ -104,18 +151,21 @@ impl<T: Hash + Eq> LookupCache<T> {
         // Check negative cache
         let valid_until = self.cache_neg.get_mut(name)?;
         if *valid_until >= Instant::now() {
-            Some(false)
+            Some(None)
         } else {
-            self.cache_pos.remove(name);
+            self.cache_neg.remove(name);
             None
         }
     }
 
-    pub fn insert_pos(&mut self, item: T) {
-        self.cache_pos.insert(item, Instant::now() + self.ttl_pos);
+    pub fn insert_pos(&mut self, item: T, value: V) {
+        self.cache_neg.remove(&item);
+        self.cache_pos
+            .insert(item, (value, Instant::now() + self.ttl_pos));
     }
 
     pub fn insert_neg(&mut self, item: T) {
+        self.cache_pos.remove(&item);
         self.cache_neg.insert(item, Instant::now() + self.ttl_neg);
     }
 
@@ -124,3 +174,85 @@ impl<T: Hash + Eq> LookupCache<T> {
         self.cache_neg.clear();
     }
 }
+
+impl<V: Clone> SingleFlight<V> {
+    pub async fn run<F, Fut>(&self, key: &str, lookup: F) -> trc::Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = trc::Result<V>>,
+    {
+        let cell = self
+            .pending
+            .lock()
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+
+        // Waiters retry the lookup if the request in progress fails
+        let result = cell.get_or_try_init(lookup).await.cloned();
+
+        let mut pending = self.pending.lock();
+        if pending
+            .get(key)
+            .is_some_and(|pending| Arc::ptr_eq(pending, &cell))
+        {
+            pending.remove(key);
+        }
+
+        result
+    }
+}
+
+impl<V> Default for SingleFlight<V> {
+    fn default() -> Self {
+        Self {
+            pending: Mutex::new(AHashMap::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::{LookupCache, SingleFlight};
+
+    #[test]
+    fn negative_cache() {
+        let mut cache = LookupCache::<String, Vec<u32>>::new(
+            10,
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+        cache.insert_neg("unknown@example.org".to_string());
+        cache.insert_pos("john@example.org".to_string(), vec![1]);
+        assert_eq!(cache.get("unknown@example.org"), Some(None));
+        assert_eq!(cache.get("john@example.org"), Some(Some(vec![1])));
+        assert_eq!(cache.get("jane@example.org"), None);
+
+        cache.insert_pos("unknown@example.org".to_string(), vec![2]);
+        assert_eq!(cache.get("unknown@example.org"), Some(Some(vec![2])));
+    }
+
+    #[tokio::test]
+    async fn single_flight() {
+        let pending = SingleFlight::<u32>::default();
+        let counter = AtomicUsize::new(0);
+        let lookups = &counter;
+        let lookup = move || async move {
+            lookups.fetch_add(1, Ordering::Relaxed);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(1)
+        };
+
+        let results =
+            futures::future::join_all((0..10).map(|_| pending.run("john@example.org", lookup)))
+                .await;
+        assert!(results.into_iter().all(|result| result.unwrap() == 1));
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+        assert!(pending.pending.lock().is_empty());
+    }
+}
@@ -16,8 +16,8 @@ use ahash::AHashMap;
 
 use crate::{
     backend::{
-        imap::ImapDirectory, ldap::LdapDirectory, memory::MemoryDirectory, smtp::SmtpDirectory,
-        sql::SqlDirectory,
+        composite::CompositeDirectory, http::HttpDirectory, imap::ImapDirectory,
+        ldap::LdapDirectory, memory::MemoryDirectory, smtp::SmtpDirectory, sql::SqlDirectory,
     },
     Directories, Directory, DirectoryInner,
 };
@@ -27,6 +27,7 @@ use super::cache::CachedDirectory;
 impl Directories {
     pub async fn parse(config: &mut Config, stores: &Stores, data_store: Store) -> Self {
         let mut directories = AHashMap::new();
+        let mut composites = Vec::new();
 
         for id in config
             .sub_keys("directory", ".type")
@@ -76,6 +77,13 @@ impl Directories {
                 "memory" => MemoryDirectory::from_config(config, prefix, data_store.clone())
                     .await
                     .map(DirectoryInner::Memory),
+                "http" => HttpDirectory::from_config(config, prefix, data_store.clone())
+                    .map(DirectoryInner::Http),
+                "composite" => {
+                    // Built once all other directories have been parsed
+                    composites.push(id.to_string());
+                    continue;
+                }
                 unknown => {
                     let err = format!("Unknown directory type: {unknown:?}");
                     config.new_parse_error(("directory", id, "type"), err);
@@ -95,6 +103,21 @@ impl Directories {
             }
         }
 
+        // Composite directories can only reference non-composite directories
+        let mut composite_directories = Vec::with_capacity(composites.len());
+        for id in composites {
+            if let Some(store) =
+                CompositeDirectory::from_config(config, ("directory", id.as_str()), &directories)
+            {
+                let directory = Arc::new(Directory {
+                    store: DirectoryInner::Composite(store),
+                    cache: CachedDirectory::try_from_config(config, ("directory", id.as_str())),
+                });
+                composite_directories.push((id, directory));
+            }
+        }
+        directories.extend(composite_directories);
+
         Directories { directories }
     }
 }
@@ -7,7 +7,8 @@
 use trc::AddContext;
 
 use crate::{
-    backend::internal::lookup::DirectoryStore, Directory, DirectoryInner, Principal, QueryBy,
+    backend::internal::{lookup::DirectoryStore, PrincipalField},
+    Directory, DirectoryInner, Principal, QueryBy,
 };
 
 impl Directory {
@@ -23,11 +24,33 @@ impl Directory {
             DirectoryInner::Imap(store) => store.query(by).await,
             DirectoryInner::Smtp(store) => store.query(by).await,
             DirectoryInner::Memory(store) => store.query(by).await,
+            DirectoryInner::Http(store) => store.query(by, return_member_of).await,
+            DirectoryInner::Composite(store) => store.query(by, return_member_of).await,
         }
         .caused_by(trc::location!())
     }
 
     pub async fn email_to_ids(&self, email: &str) -> trc::Result<Vec<u32>> {
+        // Check cache
+        if let Some(cache) = &self.cache {
+            if let Some(result) = cache.get_ids(email) {
+                return Ok(result);
+            }
+
+            return cache
+                .pending_ids
+                .run(email, || async {
+                    let result = self.lookup_email_to_ids(email).await?;
+                    cache.set_ids(email, &result);
+                    Ok(result)
+                })
+                .await;
+        }
+
+        self.lookup_email_to_ids(email).await
+    }
+
+    async fn lookup_email_to_ids(&self, email: &str) -> trc::Result<Vec<u32>> {
         match &self.store {
             DirectoryInner::Internal(store) => store.email_to_ids(email).await,
             DirectoryInner::Ldap(store) => store.email_to_ids(email).await,
@@ -35,6 +58,8 @@ impl Directory {
             DirectoryInner::Imap(store) => store.email_to_ids(email).await,
             DirectoryInner::Smtp(store) => store.email_to_ids(email).await,
             DirectoryInner::Memory(store) => store.email_to_ids(email).await,
+            DirectoryInner::Http(store) => store.email_to_ids(email).await,
+            DirectoryInner::Composite(store) => store.email_to_ids(email).await,
         }
         .caused_by(trc::location!())
     }
@@ -45,24 +70,32 @@ impl Directory {
             if let Some(result) = cache.get_domain(domain) {
                 return Ok(result);
             }
+
+            return cache
+                .pending_domains
+                .run(domain, || async {
+                    let result = self.lookup_local_domain(domain).await?;
+                    cache.set_domain(domain, result);
+                    Ok(result)
+                })
+                .await;
         }
 
-        let result = match &self.store {
+        self.lookup_local_domain(domain).await
+    }
+
+    async fn lookup_local_domain(&self, domain: &str) -> trc::Result<bool> {
+        match &self.store {
             DirectoryInner::Internal(store) => store.is_local_domain(domain).await,
             DirectoryInner::Ldap(store) => store.is_local_domain(domain).await,
             DirectoryInner::Sql(store) => store.is_local_domain(domain).await,
             DirectoryInner::Imap(store) => store.is_local_domain(domain).await,
             DirectoryInner::Smtp(store) => store.is_local_domain(domain).await,
             DirectoryInner::Memory(store) => store.is_local_domain(domain).await,
+            DirectoryInner::Http(store) => store.is_local_domain(domain).await,
+            DirectoryInner::Composite(store) => store.is_local_domain(domain).await,
         }
-        .caused_by(trc::location!())?;
-
-        // Update cache
-        if let Some(cache) = &self.cache {
-            cache.set_domain(domain, result);
-        }
-
-        Ok(result)
+        .caused_by(trc::location!())
     }
 
     pub async fn rcpt(&self, email: &str) -> trc::Result<bool> {
@@ -71,24 +104,32 @@ impl Directory {
             if let Some(result) = cache.get_rcpt(email) {
                 return Ok(result);
             }
+
+            return cache
+                .pending_rcpts
+                .run(email, || async {
+                    let result = self.lookup_rcpt(email).await?;
+                    cache.set_rcpt(email, result);
+                    Ok(result)
+                })
+                .await;
         }
 
-        let result = match &self.store {
+        self.lookup_rcpt(email).await
+    }
+
+    async fn lookup_rcpt(&self, email: &str) -> trc::Result<bool> {
+        match &self.store {
             DirectoryInner::Internal(store) => store.rcpt(email).await,
             DirectoryInner::Ldap(store) => store.rcpt(email).await,
             DirectoryInner::Sql(store) => store.rcpt(email).await,
             DirectoryInner::Imap(store) => store.rcpt(email).await,
             DirectoryInner::Smtp(store) => store.rcpt(email).await,
             DirectoryInner::Memory(store) => store.rcpt(email).await,
+            DirectoryInner::Http(store) => store.rcpt(email).await,
+            DirectoryInner::Composite(store) => store.rcpt(email).await,
         }
-        .caused_by(trc::location!())?;
-
-        // Update cache
-        if let Some(cache) = &self.cache {
-            cache.set_rcpt(email, result);
-        }
-
-        Ok(result)
+        .caused_by(trc::location!())
     }
 
     pub async fn vrfy(&self, address: &str) -> trc::Result<Vec<String>> {
@@ -99,6 +140,8 @@ impl Directory {
             DirectoryInner::Imap(store) => store.vrfy(address).await,
             DirectoryInner::Smtp(store) => store.vrfy(address).await,
             DirectoryInner::Memory(store) => store.vrfy(address).await,
+            DirectoryInner::Http(store) => store.vrfy(address).await,
+            DirectoryInner::Composite(store) => store.vrfy(address).await,
         }
         .caused_by(trc::location!())
     }
@@ -111,7 +154,30 @@ impl Directory {
             DirectoryInner::Imap(store) => store.expn(address).await,
             DirectoryInner::Smtp(store) => store.expn(address).await,
             DirectoryInner::Memory(store) => store.expn(address).await,
+            DirectoryInner::Http(store) => store.expn(address).await,
+            DirectoryInner::Composite(store) => store.expn(address).await,
         }
         .caused_by(trc::location!())
     }
+
+    // Addresses a principal can send from, including those of the lists it is a member of.
+    // The principal has to be obtained with `return_member_of` set for lists to be included.
+    pub async fn sender_addresses(&self, principal: &Principal) -> trc::Result<Vec<String>> {
+        let mut addresses = principal
+            .iter_str(PrincipalField::Emails)
+            .map(|email| email.trim().to_lowercase())
+            .collect::<Vec<_>>();
+        for list_id in principal.iter_int(PrincipalField::Lists) {
+            if let Some(list) = self.query(QueryBy::Id(list_id as u32), false).await? {
+                for email in list.iter_str(PrincipalField::Emails) {
+                    let email = email.trim().to_lowercase();
+                    if !addresses.contains(&email) {
+                        addresses.push(email);
+                    }
+                }
+            }
+        }
+
+        Ok(addresses)
+    }
 }
@@ -183,6 +183,57 @@ impl Permission {
             Permission::SieveRenameScript => "Rename Sieve scripts",
             Permission::SieveCheckScript => "Validate Sieve scripts",
             Permission::SieveHaveSpace => "Check available space for Sieve scripts",
+            Permission::JmapActivityGet => "Retrieve the account activity feed via JMAP",
+            Permission::JmapCalendarGet => "Retrieve calendars via JMAP",
+            Permission::JmapCalendarSet => "Modify calendars via JMAP",
+            Permission::JmapCalendarChanges => "Track changes to calendars via JMAP",
+            Permission::JmapCalendarEventGet => "Retrieve calendar events via JMAP",
+            Permission::JmapCalendarEventSet => "Modify calendar events via JMAP",
+            Permission::JmapCalendarEventChanges => "Track changes to calendar events via JMAP",
+            Permission::JmapCalendarEventQuery => "Perform calendar event queries via JMAP",
+            Permission::JmapCalendarEventQueryChanges => {
+                "Track changes in calendar event query results via JMAP"
+            }
+            Permission::JmapPrincipalGetAvailability => {
+                "Retrieve the free/busy availability of principals via JMAP"
+            }
+            Permission::JmapAddressBookGet => "Retrieve address books via JMAP",
+            Permission::JmapAddressBookSet => "Modify address books via JMAP",
+            Permission::JmapAddressBookChanges => "Track changes to address books via JMAP",
+            Permission::JmapContactCardGet => "Retrieve contact cards via JMAP",
+            Permission::JmapContactCardSet => "Modify contact cards via JMAP",
+            Permission::JmapContactCardChanges => "Track changes to contact cards via JMAP",
+            Permission::JmapContactCardQuery => "Perform contact card queries via JMAP",
+            Permission::JmapContactCardQueryChanges => {
+                "Track changes in contact card query results via JMAP"
+            }
+            Permission::JmapContactCardParse => "Parse vCard files via JMAP",
+            Permission::JmapTaskListGet => "Retrieve task lists via JMAP",
+            Permission::JmapTaskListSet => "Modify task lists via JMAP",
+            Permission::JmapTaskListChanges => "Track changes to task lists via JMAP",
+            Permission::JmapTaskGet => "Retrieve tasks via JMAP",
+            Permission::JmapTaskSet => "Modify tasks via JMAP",
+            Permission::JmapTaskChanges => "Track changes to tasks via JMAP",
+            Permission::JmapTaskQuery => "Perform task queries via JMAP",
+            Permission::JmapTaskQueryChanges => "Track changes in task query results via JMAP",
+            Permission::JmapSieveScriptChanges => "Track changes to Sieve scripts via JMAP",
+            Permission::JmapMdnSend => "Send message disposition notifications via JMAP",
+            Permission::JmapMdnParse => "Parse message disposition notifications via JMAP",
+            Permission::SpamAllowContacts => {
+                "Allow messages from known contacts to bypass greylisting and spam scoring"
+            }
+            Permission::CaldavAuthenticate => "Access calendars via CalDAV",
+            Permission::CarddavAuthenticate => "Access address books via CardDAV",
+            Permission::Replication => "Manage and apply store replication",
+            Permission::JmapDeletedEmailGet => {
+                "Retrieve deleted emails in the recycle bin via JMAP"
+            }
+            Permission::JmapDeletedEmailRestore => {
+                "Restore deleted emails from the recycle bin via JMAP"
+            }
+            Permission::JmapActivityChanges => {
+                "Track changes to the account activity feed via JMAP"
+            }
         }
     }
 }
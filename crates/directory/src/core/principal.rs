@@ -1078,6 +1078,42 @@ impl Permission {
                 | Permission::SieveRenameScript
                 | Permission::SieveCheckScript
                 | Permission::SieveHaveSpace
+                | Permission::JmapActivityGet
+                | Permission::JmapCalendarGet
+                | Permission::JmapCalendarSet
+                | Permission::JmapCalendarChanges
+                | Permission::JmapCalendarEventGet
+                | Permission::JmapCalendarEventSet
+                | Permission::JmapCalendarEventChanges
+                | Permission::JmapCalendarEventQuery
+                | Permission::JmapCalendarEventQueryChanges
+                | Permission::JmapPrincipalGetAvailability
+                | Permission::JmapAddressBookGet
+                | Permission::JmapAddressBookSet
+                | Permission::JmapAddressBookChanges
+                | Permission::JmapContactCardGet
+                | Permission::JmapContactCardSet
+                | Permission::JmapContactCardChanges
+                | Permission::JmapContactCardQuery
+                | Permission::JmapContactCardQueryChanges
+                | Permission::JmapContactCardParse
+                | Permission::JmapTaskListGet
+                | Permission::JmapTaskListSet
+                | Permission::JmapTaskListChanges
+                | Permission::JmapTaskGet
+                | Permission::JmapTaskSet
+                | Permission::JmapTaskChanges
+                | Permission::JmapTaskQuery
+                | Permission::JmapTaskQueryChanges
+                | Permission::JmapSieveScriptChanges
+                | Permission::JmapMdnSend
+                | Permission::JmapMdnParse
+                | Permission::SpamAllowContacts
+                | Permission::CaldavAuthenticate
+                | Permission::CarddavAuthenticate
+                | Permission::JmapDeletedEmailGet
+                | Permission::JmapDeletedEmailRestore
+                | Permission::JmapActivityChanges
         )
     }
 
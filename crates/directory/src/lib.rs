@@ -9,6 +9,8 @@ use std::{fmt::Debug, sync::Arc};
 
 use ahash::AHashMap;
 use backend::{
+    composite::CompositeDirectory,
+    http::HttpDirectory,
     imap::{ImapDirectory, ImapError},
     internal::{PrincipalField, PrincipalValue},
     ldap::LdapDirectory,
@@ -240,6 +242,45 @@ pub enum Permission {
     SieveRenameScript,
     SieveCheckScript,
     SieveHaveSpace,
+
+    // JMAP
+    JmapActivityGet,
+    JmapCalendarGet,
+    JmapCalendarSet,
+    JmapCalendarChanges,
+    JmapCalendarEventGet,
+    JmapCalendarEventSet,
+    JmapCalendarEventChanges,
+    JmapCalendarEventQuery,
+    JmapCalendarEventQueryChanges,
+    JmapPrincipalGetAvailability,
+    JmapAddressBookGet,
+    JmapAddressBookSet,
+    JmapAddressBookChanges,
+    JmapContactCardGet,
+    JmapContactCardSet,
+    JmapContactCardChanges,
+    JmapContactCardQuery,
+    JmapContactCardQueryChanges,
+    JmapContactCardParse,
+    JmapTaskListGet,
+    JmapTaskListSet,
+    JmapTaskListChanges,
+    JmapTaskGet,
+    JmapTaskSet,
+    JmapTaskChanges,
+    JmapTaskQuery,
+    JmapTaskQueryChanges,
+    JmapSieveScriptChanges,
+    JmapMdnSend,
+    JmapMdnParse,
+    SpamAllowContacts,
+    CaldavAuthenticate,
+    CarddavAuthenticate,
+    Replication,
+    JmapDeletedEmailGet,
+    JmapDeletedEmailRestore,
+    JmapActivityChanges,
     // WARNING: add new ids at the end (TODO: use static ids)
 }
 
@@ -258,8 +299,11 @@ pub enum DirectoryInner {
     Imap(ImapDirectory),
     Smtp(SmtpDirectory),
     Memory(MemoryDirectory),
+    Http(HttpDirectory),
+    Composite(CompositeDirectory),
 }
 
+#[derive(Clone, Copy)]
 pub enum QueryBy<'x> {
     Name(&'x str),
     Id(u32),
@@ -0,0 +1,317 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+// Synthetic mailbox generator, shared by the CLI load generator and the benchmarks.
+
+use std::fmt::Write;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// Fixed reference time so that the same seed always produces the same mailbox
+const BASE_TIMESTAMP: i64 = 1_700_000_000;
+const MAX_AGE: i64 = 2 * 365 * 86400;
+
+const WORDS: &[&str] = &[
+    "account",
+    "agenda",
+    "approval",
+    "budget",
+    "calendar",
+    "contract",
+    "customer",
+    "deadline",
+    "delivery",
+    "design",
+    "draft",
+    "estimate",
+    "feedback",
+    "forecast",
+    "invoice",
+    "meeting",
+    "milestone",
+    "minutes",
+    "notes",
+    "offer",
+    "order",
+    "payment",
+    "planning",
+    "pricing",
+    "project",
+    "proposal",
+    "quarter",
+    "release",
+    "report",
+    "request",
+    "review",
+    "roadmap",
+    "schedule",
+    "shipment",
+    "status",
+    "summary",
+    "support",
+    "team",
+    "ticket",
+    "travel",
+    "update",
+    "vendor",
+    "weekly",
+    "workshop",
+];
+const NAMES: &[&str] = &[
+    "alice", "bob", "carol", "dave", "erin", "frank", "grace", "heidi", "ivan", "judy", "mallory",
+    "oscar", "peggy", "rupert", "sybil", "trent", "victor", "walter",
+];
+const DOMAINS: &[&str] = &["example.com", "example.org", "example.net", "test.example"];
+
+pub struct MessageGenerator {
+    rng: StdRng,
+    seq: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedMessage {
+    pub contents: Vec<u8>,
+    pub keywords: Vec<&'static str>,
+    pub received_at: i64,
+}
+
+enum Shape {
+    Plain(usize),
+    Alternative(usize),
+    Attachment(usize, usize),
+}
+
+impl MessageGenerator {
+    pub fn new(seed: u64) -> Self {
+        MessageGenerator {
+            rng: StdRng::seed_from_u64(seed),
+            seq: 0,
+        }
+    }
+
+    pub fn next_message(&mut self) -> GeneratedMessage {
+        self.seq += 1;
+
+        // Size distribution: mostly small text messages, a few large attachments
+        let shape = match self.rng.gen_range(0..100) {
+            0..=59 => Shape::Plain(self.rng.gen_range(256..4096)),
+            60..=84 => Shape::Alternative(self.rng.gen_range(2048..32768)),
+            85..=96 => Shape::Attachment(
+                self.rng.gen_range(512..4096),
+                self.rng.gen_range(32768..262144),
+            ),
+            _ => Shape::Attachment(
+                self.rng.gen_range(512..4096),
+                self.rng.gen_range(262144..2097152),
+            ),
+        };
+
+        // Flag distribution: most messages read, some answered or flagged
+        let mut keywords = Vec::new();
+        if self.rng.gen_bool(0.75) {
+            keywords.push("$seen");
+        }
+        if self.rng.gen_bool(0.15) {
+            keywords.push("$answered");
+        }
+        if self.rng.gen_bool(0.08) {
+            keywords.push("$flagged");
+        }
+        if self.rng.gen_bool(0.01) {
+            keywords.push("$draft");
+        }
+
+        let received_at = BASE_TIMESTAMP - self.rng.gen_range(0..MAX_AGE);
+        let from = self.address();
+        let to = self.address();
+        let subject_words = self.rng.gen_range(3..10);
+        let subject = self.words(subject_words);
+
+        let mut message = String::with_capacity(match shape {
+            Shape::Plain(size) | Shape::Alternative(size) => size + 1024,
+            Shape::Attachment(size, attachment) => size + (attachment * 4 / 3) + 1024,
+        });
+        let _ = write!(
+            message,
+            concat!(
+                "From: {}\r\n",
+                "To: {}\r\n",
+                "Subject: {}\r\n",
+                "Message-ID: <{}.{}@generator.example>\r\n",
+                "Date: {}\r\n",
+                "MIME-Version: 1.0\r\n"
+            ),
+            from,
+            to,
+            subject,
+            self.seq,
+            received_at,
+            rfc822_date(received_at),
+        );
+
+        match shape {
+            Shape::Plain(size) => {
+                message.push_str("Content-Type: text/plain; charset=utf-8\r\n\r\n");
+                self.body(&mut message, size);
+            }
+            Shape::Alternative(size) => {
+                let boundary = format!("alt-{}", self.seq);
+                let _ = write!(
+                    message,
+                    "Content-Type: multipart/alternative; boundary=\"{boundary}\"\r\n\r\n"
+                );
+                let _ = write!(
+                    message,
+                    "--{boundary}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n"
+                );
+                self.body(&mut message, size / 2);
+                let _ = write!(
+                    message,
+                    "\r\n--{boundary}\r\nContent-Type: text/html; charset=utf-8\r\n\r\n<html><body><p>"
+                );
+                self.body(&mut message, size / 2);
+                let _ = write!(message, "</p></body></html>\r\n--{boundary}--\r\n");
+            }
+            Shape::Attachment(size, attachment_size) => {
+                let boundary = format!("mixed-{}", self.seq);
+                let _ = write!(
+                    message,
+                    "Content-Type: multipart/mixed; boundary=\"{boundary}\"\r\n\r\n"
+                );
+                let _ = write!(
+                    message,
+                    "--{boundary}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n"
+                );
+                self.body(&mut message, size);
+                let _ = write!(
+                    message,
+                    concat!(
+                        "\r\n--{}\r\n",
+                        "Content-Type: application/octet-stream\r\n",
+                        "Content-Disposition: attachment; filename=\"file{}.bin\"\r\n",
+                        "Content-Transfer-Encoding: base64\r\n\r\n"
+                    ),
+                    boundary, self.seq
+                );
+                self.base64(&mut message, attachment_size);
+                let _ = write!(message, "\r\n--{boundary}--\r\n");
+            }
+        }
+
+        GeneratedMessage {
+            contents: message.into_bytes(),
+            keywords,
+            received_at,
+        }
+    }
+
+    fn address(&mut self) -> String {
+        format!(
+            "{}@{}",
+            NAMES[self.rng.gen_range(0..NAMES.len())],
+            DOMAINS[self.rng.gen_range(0..DOMAINS.len())]
+        )
+    }
+
+    fn words(&mut self, count: usize) -> String {
+        let mut text = String::new();
+        for _ in 0..count {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(WORDS[self.rng.gen_range(0..WORDS.len())]);
+        }
+        text
+    }
+
+    fn body(&mut self, message: &mut String, size: usize) {
+        let mut line_len = 0;
+        let start = message.len();
+        while message.len() - start < size {
+            let word = WORDS[self.rng.gen_range(0..WORDS.len())];
+            if line_len + word.len() > 76 {
+                message.push_str("\r\n");
+                line_len = 0;
+            } else if line_len > 0 {
+                message.push(' ');
+                line_len += 1;
+            }
+            message.push_str(word);
+            line_len += word.len();
+        }
+        message.push_str("\r\n");
+    }
+
+    fn base64(&mut self, message: &mut String, size: usize) {
+        const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let encoded_len = size.div_ceil(3) * 4;
+        for pos in 0..encoded_len {
+            if pos > 0 && pos % 76 == 0 {
+                message.push_str("\r\n");
+            }
+            message.push(CHARS[self.rng.gen_range(0..CHARS.len())] as char);
+        }
+        message.push_str("\r\n");
+    }
+}
+
+impl Iterator for MessageGenerator {
+    type Item = GeneratedMessage;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_message())
+    }
+}
+
+impl GeneratedMessage {
+    pub fn imap_flags(&self) -> String {
+        let mut flags = String::new();
+        for keyword in &self.keywords {
+            if !flags.is_empty() {
+                flags.push(' ');
+            }
+            flags.push_str(match *keyword {
+                "$seen" => "\\Seen",
+                "$answered" => "\\Answered",
+                "$flagged" => "\\Flagged",
+                "$draft" => "\\Draft",
+                other => other,
+            });
+        }
+        flags
+    }
+}
+
+fn rfc822_date(timestamp: i64) -> String {
+    const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    // Civil date from days since epoch (Howard Hinnant's algorithm)
+    let days = timestamp.div_euclid(86400);
+    let secs = timestamp.rem_euclid(86400);
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{}, {} {} {} {:02}:{:02}:{:02} +0000",
+        DAYS[days.rem_euclid(7) as usize],
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    )
+}
@@ -313,6 +313,10 @@ impl Request<Command> {
                         attributes.push_unique(Attribute::EmailId);
                     } else if value.eq_ignore_ascii_case(b"THREADID") {
                         attributes.push_unique(Attribute::ThreadId);
+                    } else if value.eq_ignore_ascii_case(b"X-GM-MSGID") {
+                        attributes.push_unique(Attribute::XGmMsgId);
+                    } else if value.eq_ignore_ascii_case(b"X-GM-THRID") {
+                        attributes.push_unique(Attribute::XGmThrId);
                     } else {
                         return Err(bad(
                             self.tag,
@@ -758,6 +762,16 @@ mod tests {
                     include_vanished: true,
                 },
             ),
+            (
+                "10 UID FETCH 1:* (UID X-GM-MSGID X-GM-THRID)\r\n",
+                fetch::Arguments {
+                    tag: "10".to_string(),
+                    sequence_set: Sequence::range(1.into(), None),
+                    attributes: vec![Attribute::Uid, Attribute::XGmMsgId, Attribute::XGmThrId],
+                    changed_since: None,
+                    include_vanished: false,
+                },
+            ),
             (
                 "9 UID FETCH 1:* UID (VANISHED CHANGEDSINCE 1)\r\n",
                 fetch::Arguments {
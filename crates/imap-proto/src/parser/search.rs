@@ -8,6 +8,7 @@ use std::borrow::Cow;
 use std::iter::Peekable;
 use std::vec::IntoIter;
 
+use chrono::NaiveDate;
 use mail_parser::decoders::charsets::map::charset_decoder;
 use mail_parser::decoders::charsets::DecoderFnc;
 
@@ -312,6 +313,29 @@ pub fn parse_filters(
                             .ok_or_else(|| Cow::from("Expected an THREADID value."))?
                             .unwrap_string()?,
                     ));
+                } else if value.eq_ignore_ascii_case(b"X-GM-MSGID") {
+                    filters.push(Filter::XGmMsgId(parse_number::<u64>(
+                        &tokens
+                            .next()
+                            .ok_or_else(|| Cow::from("Expected an X-GM-MSGID value."))?
+                            .unwrap_bytes(),
+                    )?));
+                } else if value.eq_ignore_ascii_case(b"X-GM-THRID") {
+                    filters.push(Filter::XGmThrId(parse_number::<u64>(
+                        &tokens
+                            .next()
+                            .ok_or_else(|| Cow::from("Expected an X-GM-THRID value."))?
+                            .unwrap_bytes(),
+                    )?));
+                } else if value.eq_ignore_ascii_case(b"X-GM-RAW") {
+                    let mut raw_filters = parse_gm_raw(&decode_argument(tokens, decoder)?)?;
+                    if raw_filters.len() > 1 {
+                        filters.push(Filter::And);
+                        filters.append(&mut raw_filters);
+                        filters.push(Filter::End);
+                    } else {
+                        filters.append(&mut raw_filters);
+                    }
                 } else if value.eq_ignore_ascii_case(b"OR") {
                     if filters_stack.len() > 10 {
                         return Err(Cow::from("Too many nested filters"));
@@ -405,6 +429,248 @@ pub fn decode_argument(
     }
 }
 
+// Parses a Gmail-style raw query (X-GM-RAW) into the equivalent IMAP search filters.
+// Terms are implicitly ANDed, "OR" has a higher precedence than AND, a leading
+// "-" negates a term and parentheses are used for grouping.
+pub fn parse_gm_raw(query: &str) -> super::Result<Vec<Filter>> {
+    let mut tokens = tokenize_gm_raw(query)?.into_iter().peekable();
+    let mut filters = Vec::new();
+    parse_gm_raw_and(&mut tokens, &mut filters, 0)?;
+
+    if let Some(token) = tokens.next() {
+        Err(format!("Unexpected token {token:?} in X-GM-RAW query.").into())
+    } else if filters.is_empty() {
+        Err(Cow::from("Empty X-GM-RAW query."))
+    } else {
+        Ok(filters)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum GmRawToken {
+    Term(String),
+    Or,
+    Not,
+    ParenthesisOpen,
+    ParenthesisClose,
+}
+
+fn tokenize_gm_raw(query: &str) -> super::Result<Vec<GmRawToken>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '(' => tokens.push(GmRawToken::ParenthesisOpen),
+            ')' => tokens.push(GmRawToken::ParenthesisClose),
+            '-' if chars.peek().map_or(false, |ch| !ch.is_whitespace()) => {
+                tokens.push(GmRawToken::Not)
+            }
+            ch if ch.is_whitespace() => (),
+            ch => {
+                let mut term = String::new();
+                let mut is_quoted = ch == '"';
+                if !is_quoted {
+                    term.push(ch);
+                }
+
+                while let Some(&ch) = chars.peek() {
+                    if is_quoted {
+                        chars.next();
+                        if ch == '"' {
+                            is_quoted = false;
+                        } else {
+                            term.push(ch);
+                        }
+                    } else if ch.is_whitespace() || ch == '(' || ch == ')' {
+                        break;
+                    } else {
+                        chars.next();
+                        if ch == '"' {
+                            is_quoted = true;
+                        } else {
+                            term.push(ch);
+                        }
+                    }
+                }
+
+                if is_quoted {
+                    return Err(Cow::from("Unterminated quoted string in X-GM-RAW query."));
+                } else if term == "OR" && ch != '"' {
+                    tokens.push(GmRawToken::Or);
+                } else if !term.is_empty() {
+                    tokens.push(GmRawToken::Term(term));
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_gm_raw_and(
+    tokens: &mut Peekable<IntoIter<GmRawToken>>,
+    filters: &mut Vec<Filter>,
+    depth: usize,
+) -> super::Result<()> {
+    let mut items = Vec::new();
+    while tokens
+        .peek()
+        .map_or(false, |token| *token != GmRawToken::ParenthesisClose)
+    {
+        let mut item = Vec::new();
+        parse_gm_raw_or(tokens, &mut item, depth)?;
+        items.push(item);
+    }
+
+    push_gm_raw_group(filters, Filter::And, items);
+    Ok(())
+}
+
+fn parse_gm_raw_or(
+    tokens: &mut Peekable<IntoIter<GmRawToken>>,
+    filters: &mut Vec<Filter>,
+    depth: usize,
+) -> super::Result<()> {
+    let mut items = Vec::new();
+    loop {
+        let mut item = Vec::new();
+        parse_gm_raw_unary(tokens, &mut item, depth)?;
+        items.push(item);
+
+        if tokens.peek() == Some(&GmRawToken::Or) {
+            tokens.next();
+        } else {
+            break;
+        }
+    }
+
+    push_gm_raw_group(filters, Filter::Or, items);
+    Ok(())
+}
+
+fn parse_gm_raw_unary(
+    tokens: &mut Peekable<IntoIter<GmRawToken>>,
+    filters: &mut Vec<Filter>,
+    depth: usize,
+) -> super::Result<()> {
+    if depth > 10 {
+        return Err(Cow::from("Too many nested filters"));
+    }
+
+    match tokens.next() {
+        Some(GmRawToken::Not) => {
+            filters.push(Filter::Not);
+            parse_gm_raw_unary(tokens, filters, depth + 1)?;
+            filters.push(Filter::End);
+        }
+        Some(GmRawToken::ParenthesisOpen) => {
+            let mut group = Vec::new();
+            parse_gm_raw_and(tokens, &mut group, depth + 1)?;
+            if tokens.next() != Some(GmRawToken::ParenthesisClose) {
+                return Err(Cow::from("Missing closing parenthesis in X-GM-RAW query."));
+            } else if group.is_empty() {
+                return Err(Cow::from("Empty group in X-GM-RAW query."));
+            }
+            filters.extend(group);
+        }
+        Some(GmRawToken::Term(term)) => {
+            filters.push(parse_gm_raw_term(term)?);
+        }
+        Some(token) => {
+            return Err(format!("Unexpected token {token:?} in X-GM-RAW query.").into());
+        }
+        None => {
+            return Err(Cow::from("Unexpected end of X-GM-RAW query."));
+        }
+    }
+
+    Ok(())
+}
+
+fn push_gm_raw_group(filters: &mut Vec<Filter>, operator: Filter, mut items: Vec<Vec<Filter>>) {
+    if items.len() > 1 {
+        filters.push(operator);
+        for item in items {
+            filters.extend(item);
+        }
+        filters.push(Filter::End);
+    } else if let Some(item) = items.pop() {
+        filters.extend(item);
+    }
+}
+
+fn parse_gm_raw_term(term: String) -> super::Result<Filter> {
+    let (name, value) = match term.split_once(':') {
+        Some((name, value)) if !value.is_empty() => (name.to_ascii_lowercase(), value),
+        _ => return Ok(Filter::Text(term)),
+    };
+
+    Ok(match name.as_str() {
+        "from" => Filter::From(value.to_string()),
+        "to" => Filter::To(value.to_string()),
+        "cc" => Filter::Cc(value.to_string()),
+        "bcc" => Filter::Bcc(value.to_string()),
+        "subject" => Filter::Subject(value.to_string()),
+        "rfc822msgid" => Filter::Header("Message-ID".to_string(), value.to_string()),
+        "has" if value.eq_ignore_ascii_case("attachment") => Filter::HasAttachment,
+        "is" if value.eq_ignore_ascii_case("unread") => Filter::Unseen,
+        "is" if value.eq_ignore_ascii_case("read") => Filter::Seen,
+        "is" if value.eq_ignore_ascii_case("starred") => Filter::Flagged,
+        "has" | "is" => {
+            return Err(format!("Unsupported X-GM-RAW criteria {term:?}.").into());
+        }
+        "newer_than" => Filter::Younger(parse_gm_raw_period(value)?),
+        "older_than" => Filter::Older(parse_gm_raw_period(value)?),
+        "after" | "newer" => Filter::Since(parse_gm_raw_date(value)?),
+        "before" | "older" => Filter::Before(parse_gm_raw_date(value)?),
+        "larger" | "size" => Filter::Larger(parse_gm_raw_size(value)?),
+        "smaller" => Filter::Smaller(parse_gm_raw_size(value)?),
+        _ => Filter::Text(term),
+    })
+}
+
+fn parse_gm_raw_period(value: &str) -> super::Result<u32> {
+    let multiplier = match value.chars().last() {
+        Some('d' | 'D') => 86400,
+        Some('m' | 'M') => 30 * 86400,
+        Some('y' | 'Y') => 365 * 86400,
+        _ => return Err(format!("Invalid X-GM-RAW time period {value:?}.").into()),
+    };
+
+    value[..value.len() - 1]
+        .parse::<u32>()
+        .ok()
+        .and_then(|value| value.checked_mul(multiplier))
+        .ok_or_else(|| format!("Invalid X-GM-RAW time period {value:?}.").into())
+}
+
+fn parse_gm_raw_date(value: &str) -> super::Result<i64> {
+    NaiveDate::parse_from_str(value, "%Y/%m/%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d"))
+        .map_err(|_| Cow::from(format!("Invalid X-GM-RAW date {value:?}.")))
+        .map(|date| {
+            date.and_hms_opt(0, 0, 0)
+                .unwrap_or_default()
+                .and_utc()
+                .timestamp()
+        })
+}
+
+fn parse_gm_raw_size(value: &str) -> super::Result<u32> {
+    let (number, multiplier) = match value.chars().last() {
+        Some('k' | 'K') => (&value[..value.len() - 1], 1024),
+        Some('m' | 'M') => (&value[..value.len() - 1], 1024 * 1024),
+        _ => (value, 1),
+    };
+
+    number
+        .parse::<u32>()
+        .ok()
+        .and_then(|value| value.checked_mul(multiplier))
+        .ok_or_else(|| format!("Invalid X-GM-RAW size {value:?}.").into())
+}
+
 impl ResultOption {
     pub fn parse(value: &[u8]) -> super::Result<Self> {
         if value.eq_ignore_ascii_case(b"min") {
@@ -741,6 +1007,71 @@ mod tests {
                     sort: None,
                 },
             ),
+            (
+                b"6 SEARCH UNSEEN X-GM-RAW \"from:jane has:attachment newer_than:2d\"\r\n"
+                    .to_vec(),
+                search::Arguments {
+                    tag: "6".to_string(),
+                    result_options: vec![],
+                    filter: vec![
+                        Filter::Unseen,
+                        Filter::And,
+                        Filter::From("jane".to_string()),
+                        Filter::HasAttachment,
+                        Filter::Younger(2 * 86400),
+                        Filter::End,
+                    ],
+                    is_esearch: true,
+                    sort: None,
+                },
+            ),
+            (
+                b"7 SEARCH X-GM-RAW \"subject:\\\"hello world\\\" -(to:bob OR cc:bob) after:2023/12/01\"\r\n"
+                    .to_vec(),
+                search::Arguments {
+                    tag: "7".to_string(),
+                    result_options: vec![],
+                    filter: vec![
+                        Filter::And,
+                        Filter::Subject("hello world".to_string()),
+                        Filter::Not,
+                        Filter::Or,
+                        Filter::To("bob".to_string()),
+                        Filter::Cc("bob".to_string()),
+                        Filter::End,
+                        Filter::End,
+                        Filter::Since(1701388800),
+                        Filter::End,
+                    ],
+                    is_esearch: true,
+                    sort: None,
+                },
+            ),
+            (
+                b"9 UID SEARCH X-GM-MSGID 1278455344230334865\r\n".to_vec(),
+                search::Arguments {
+                    tag: "9".to_string(),
+                    result_options: vec![],
+                    filter: vec![Filter::XGmMsgId(1278455344230334865)],
+                    is_esearch: true,
+                    sort: None,
+                },
+            ),
+            (
+                b"8 SEARCH OR X-GM-RAW larger:1M X-GM-RAW invoice\r\n".to_vec(),
+                search::Arguments {
+                    tag: "8".to_string(),
+                    result_options: vec![],
+                    filter: vec![
+                        Filter::Or,
+                        Filter::Larger(1024 * 1024),
+                        Filter::Text("invoice".to_string()),
+                        Filter::End,
+                    ],
+                    is_esearch: true,
+                    sort: None,
+                },
+            ),
         ] {
             let command_str = String::from_utf8_lossy(&command).into_owned();
             assert_eq!(
@@ -755,4 +1086,32 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn parse_gm_raw() {
+        for query in [
+            "",
+            "()",
+            "from:jane OR",
+            "(from:jane",
+            "subject:\"unterminated",
+            "newer_than:2w",
+            "has:drive",
+        ] {
+            assert!(super::parse_gm_raw(query).is_err(), "{query:?}");
+        }
+
+        assert_eq!(
+            super::parse_gm_raw("is:unread OR is:starred older_than:1y").unwrap(),
+            vec![
+                Filter::And,
+                Filter::Or,
+                Filter::Unseen,
+                Filter::Flagged,
+                Filter::End,
+                Filter::Older(365 * 86400),
+                Filter::End,
+            ]
+        );
+    }
 }
@@ -47,6 +47,7 @@ pub enum Capability {
     ObjectId,
     Preview,
     Utf8Accept,
+    XGmExt1, //X-GM-EXT-1
     Auth(Mechanism),
 }
 
@@ -92,10 +93,15 @@ impl Capability {
             Capability::CreateSpecialUse => b"CREATE-SPECIAL-USE",
             Capability::Move => b"MOVE",
             Capability::Utf8Accept => b"UTF8=ACCEPT",
+            Capability::XGmExt1 => b"X-GM-EXT-1",
         });
     }
 
-    pub fn all_capabilities(is_authenticated: bool, offer_tls: bool) -> Vec<Capability> {
+    pub fn all_capabilities(
+        is_authenticated: bool,
+        offer_tls: bool,
+        gmail_extensions: bool,
+    ) -> Vec<Capability> {
         let mut capabilities = vec![
             Capability::IMAP4rev2,
             Capability::IMAP4rev1,
@@ -134,6 +140,9 @@ impl Capability {
                 Capability::ObjectId,
                 Capability::Preview,
             ]);
+            if gmail_extensions {
+                capabilities.push(Capability::XGmExt1);
+            }
         } else {
             capabilities.extend([
                 Capability::Auth(Mechanism::OAuthBearer),
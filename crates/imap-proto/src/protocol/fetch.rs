@@ -64,6 +64,8 @@ pub enum Attribute {
     ModSeq,
     EmailId,
     ThreadId,
+    XGmMsgId, // X-GM-MSGID
+    XGmThrId, // X-GM-THRID
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -133,6 +135,12 @@ pub enum DataItem<'x> {
     ThreadId {
         thread_id: String,
     },
+    XGmMsgId {
+        msg_id: u64,
+    },
+    XGmThrId {
+        thread_id: u64,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -844,6 +852,14 @@ impl<'x> DataItem<'x> {
                 buf.extend_from_slice(thread_id.as_bytes());
                 buf.push(b')');
             }
+            DataItem::XGmMsgId { msg_id } => {
+                buf.extend_from_slice(b"X-GM-MSGID ");
+                buf.extend_from_slice(msg_id.to_string().as_bytes());
+            }
+            DataItem::XGmThrId { thread_id } => {
+                buf.extend_from_slice(b"X-GM-THRID ");
+                buf.extend_from_slice(thread_id.to_string().as_bytes());
+            }
         }
     }
 }
@@ -1358,6 +1374,18 @@ mod tests {
                 super::DataItem::InternalDate { date: 482374938 },
                 "INTERNALDATE \"15-Apr-1985 01:02:18 +0000\"",
             ),
+            (
+                super::DataItem::XGmMsgId {
+                    msg_id: 1278455344230334865,
+                },
+                "X-GM-MSGID 1278455344230334865",
+            ),
+            (
+                super::DataItem::XGmThrId {
+                    thread_id: 1266894439832287888,
+                },
+                "X-GM-THRID 1266894439832287888",
+            ),
         ] {
             let mut buf = Vec::with_capacity(100);
 
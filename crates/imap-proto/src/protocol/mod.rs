@@ -507,6 +507,9 @@ impl SerializeResponse for trc::Error {
                 trc::EventType::Limit(_) => Some(ResponseCode::Limit.as_str()),
                 trc::EventType::Auth(_) => Some(ResponseCode::AuthenticationFailed.as_str()),
                 trc::EventType::Security(_) => Some(ResponseCode::AuthorizationFailed.as_str()),
+                trc::EventType::Server(trc::ServerEvent::MaintenanceMode) => {
+                    Some(ResponseCode::Unavailable.as_str())
+                }
                 _ => None,
             })
         {
@@ -112,6 +112,11 @@ pub enum Filter {
     // RFC 8474 - ObjectID
     EmailId(String),
     ThreadId(String),
+
+    // Gmail extensions
+    HasAttachment,
+    XGmMsgId(u64),
+    XGmThrId(u64),
 }
 
 impl FilterItem for Filter {
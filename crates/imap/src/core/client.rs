@@ -268,6 +268,32 @@ impl<T: SessionStream> Session<T> {
                     return Err(trc::LimitEvent::TooManyRequests.into_err());
                 }
             }
+
+            // Reject writes while the account is in maintenance mode
+            if matches!(
+                request.command,
+                Command::Create
+                    | Command::Delete
+                    | Command::Rename
+                    | Command::Subscribe
+                    | Command::Unsubscribe
+                    | Command::Append
+                    | Command::Store(_)
+                    | Command::Expunge(_)
+                    | Command::Copy(_)
+                    | Command::Move(_)
+                    | Command::SetAcl
+                    | Command::DeleteAcl
+            ) {
+                let maintenance = &self.jmap.core.storage.maintenance;
+                let mut result = maintenance.assert_writable(data.account_id);
+                if let (Ok(_), State::Selected { mailbox, .. }) = (&result, state) {
+                    result = maintenance.assert_writable(mailbox.id.account_id);
+                }
+                if let Err(err) = result {
+                    return Err(err.id(request.tag));
+                }
+            }
         }
 
         match &request.command {
@@ -604,6 +604,20 @@ impl<T: SessionStream> SessionData<T> {
         None
     }
 
+    pub fn get_mailbox_name(&self, mailbox: &MailboxId) -> Option<String> {
+        self.mailboxes
+            .lock()
+            .iter()
+            .find(|account| account.account_id == mailbox.account_id)
+            .and_then(|account| {
+                account
+                    .mailbox_names
+                    .iter()
+                    .find(|(_, mailbox_id)| **mailbox_id == mailbox.mailbox_id)
+                    .map(|(mailbox_name, _)| mailbox_name.clone())
+            })
+    }
+
     pub async fn check_mailbox_acl(
         &self,
         account_id: u32,
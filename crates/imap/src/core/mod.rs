@@ -8,12 +8,13 @@ use std::{
     collections::BTreeMap,
     net::IpAddr,
     sync::{atomic::AtomicU32, Arc},
+    time::Duration,
 };
 
 use ahash::AHashMap;
 use common::{
     auth::AccessToken,
-    listener::{limiter::InFlight, ServerInstance, SessionStream},
+    listener::{limiter::InFlight, tarpit::TransferRate, ServerInstance, SessionStream},
 };
 use dashmap::DashMap;
 use imap_proto::{
@@ -21,7 +22,11 @@ use imap_proto::{
     receiver::Receiver,
     Command,
 };
-use jmap::{auth::rate_limit::ConcurrencyLimiters, JmapInstance, JMAP};
+use jmap::{
+    auth::rate_limit::ConcurrencyLimiters,
+    sieve::imapsieve::{ImapSieveCause, ImapSieveEvent},
+    JmapInstance, JMAP,
+};
 use tokio::{
     io::{ReadHalf, WriteHalf},
     sync::watch,
@@ -53,8 +58,8 @@ pub struct ImapInstance {
 
 pub struct Inner {
     pub rate_limiter: DashMap<u32, Arc<ConcurrencyLimiters>>,
-    pub cache_account: LruCache<AccountId, Arc<Account>>,
-    pub cache_mailbox: LruCache<MailboxId, Arc<MailboxState>>,
+    pub cache_account: Arc<LruCache<AccountId, Arc<Account>>>,
+    pub cache_mailbox: Arc<LruCache<MailboxId, Arc<MailboxState>>>,
 }
 
 pub struct IMAP {}
@@ -74,6 +79,8 @@ pub struct Session<T: SessionStream> {
     pub in_flight: InFlight,
     pub remote_addr: IpAddr,
     pub session_id: u64,
+    pub tarpit: Duration,
+    pub transfer_rate: TransferRate,
 }
 
 pub struct SessionData<T: SessionStream> {
@@ -224,6 +231,35 @@ impl<T: SessionStream> SessionData<T> {
             .caused_by(trc::location!())
     }
 
+    pub fn run_imap_sieve(
+        &self,
+        cause: ImapSieveCause,
+        mailbox: MailboxId,
+        mailbox_name: String,
+        document_ids: Vec<u32>,
+        changed_flags: Vec<String>,
+    ) {
+        if document_ids.is_empty() {
+            return;
+        }
+
+        let jmap = self.jmap.clone();
+        let event = ImapSieveEvent {
+            account_id: mailbox.account_id,
+            user: self.access_token.name.clone(),
+            cause,
+            mailbox_name,
+            document_ids,
+            changed_flags,
+            session_id: self.session_id,
+        };
+        tokio::spawn(async move {
+            if let Err(err) = jmap.sieve_script_imap_event(event).await {
+                trc::error!(err.caused_by(trc::location!()));
+            }
+        });
+    }
+
     pub fn replace_stream_tx<U: SessionStream>(
         self,
         new_stream: Arc<tokio::sync::Mutex<WriteHalf<U>>>,
@@ -6,16 +6,19 @@
 
 use std::sync::Arc;
 
-use common::listener::{stream::NullIo, SessionData, SessionManager, SessionResult, SessionStream};
+use common::listener::{
+    stream::NullIo, tarpit::TransferRate, SessionData, SessionManager, SessionResult, SessionStream,
+};
 use imap_proto::{
-    protocol::{ProtocolVersion, SerializeResponse},
+    protocol::{capability::Capability, ProtocolVersion, SerializeResponse},
     receiver::Receiver,
+    ResponseCode, StatusResponse,
 };
 use jmap::JMAP;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio_rustls::server::TlsStream;
 
-use crate::{GREETING_WITHOUT_TLS, GREETING_WITH_TLS};
+use crate::SERVER_GREETING;
 
 use super::{ImapSessionManager, Session, State};
 
@@ -59,8 +62,22 @@ impl<T: SessionStream> Session<T> {
                     match result {
                         Ok(Ok(bytes_read)) => {
                             if bytes_read > 0 {
+                                if !self.transfer_rate.record(bytes_read) {
+                                    trc::event!(
+                                        Security(trc::SecurityEvent::SlowTransfer),
+                                        SpanId = self.session_id,
+                                        RemoteIp = self.remote_addr,
+                                    );
+                                    self.write_bytes(&b"* BYE Transfer rate too low.\r\n"[..]).await.ok();
+                                    break;
+                                }
+                                if !self.tarpit.is_zero() {
+                                    tokio::time::sleep(self.tarpit).await;
+                                }
                                 match self.ingest(&buf[..bytes_read]).await {
-                                    SessionResult::Continue => (),
+                                    SessionResult::Continue => {
+                                        self.transfer_rate.set_pending(self.receiver.current_request_size > 0);
+                                    }
                                     SessionResult::UpgradeTls => {
                                         return true;
                                     }
@@ -118,14 +135,29 @@ impl<T: SessionStream> Session<T> {
         manager: ImapSessionManager,
     ) -> Result<Session<T>, ()> {
         // Write greeting
+        let jmap = JMAP::from(manager.imap.jmap_instance);
         let is_tls = session.stream.is_tls();
-        let greeting = if !is_tls && session.instance.acceptor.is_tls() {
-            &GREETING_WITH_TLS
-        } else {
-            &GREETING_WITHOUT_TLS
-        };
+        let greeting = StatusResponse::ok(
+            jmap.core
+                .eval_if::<String, _>(
+                    &jmap.core.network.imap_greeting,
+                    &session,
+                    session.session_id,
+                )
+                .await
+                .filter(|greeting| !greeting.is_empty())
+                .unwrap_or_else(|| SERVER_GREETING.to_string()),
+        )
+        .with_code(ResponseCode::Capability {
+            capabilities: Capability::all_capabilities(
+                false,
+                !is_tls && session.instance.acceptor.is_tls(),
+                false,
+            ),
+        })
+        .into_bytes();
 
-        if let Err(err) = session.stream.write_all(greeting).await {
+        if let Err(err) = session.stream.write_all(&greeting).await {
             trc::event!(
                 Network(trc::NetworkEvent::WriteError),
                 Reason = err.to_string(),
@@ -136,9 +168,12 @@ impl<T: SessionStream> Session<T> {
         }
         let _ = session.stream.flush().await;
 
+        // Tarpit clients with a bad reputation
+        let tarpit = jmap.core.eval_tarpit(&session, session.session_id).await;
+        let transfer_rate = TransferRate::new(jmap.core.network.tarpit.min_transfer_rate.clone());
+
         // Split stream into read and write halves
         let (stream_rx, stream_tx) = tokio::io::split(session.stream);
-        let jmap = JMAP::from(manager.imap.jmap_instance);
 
         Ok(Session {
             receiver: Receiver::with_max_request_size(jmap.core.imap.max_request_size),
@@ -153,6 +188,8 @@ impl<T: SessionStream> Session<T> {
             session_id: session.session_id,
             in_flight: session.in_flight,
             remote_addr: session.remote_ip,
+            tarpit,
+            transfer_rate,
             stream_rx,
             stream_tx: Arc::new(tokio::sync::Mutex::new(stream_tx)),
         })
@@ -208,6 +245,8 @@ impl<T: SessionStream> Session<T> {
             session_id: self.session_id,
             in_flight: self.in_flight,
             remote_addr: self.remote_addr,
+            tarpit: self.tarpit,
+            transfer_rate: self.transfer_rate,
             stream_rx,
             stream_tx,
         })
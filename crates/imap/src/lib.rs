@@ -5,13 +5,9 @@
  */
 
 use core::{ImapInstance, Inner, IMAP};
-use std::{
-    collections::hash_map::RandomState,
-    sync::{Arc, LazyLock},
-};
+use std::{collections::hash_map::RandomState, sync::Arc};
 
 use dashmap::DashMap;
-use imap_proto::{protocol::capability::Capability, ResponseCode, StatusResponse};
 use jmap::JmapInstance;
 use utils::{
     config::Config,
@@ -21,23 +17,7 @@ use utils::{
 pub mod core;
 pub mod op;
 
-static SERVER_GREETING: &str = "Stalwart IMAP4rev2 at your service.";
-
-pub(crate) static GREETING_WITH_TLS: LazyLock<Vec<u8>> = LazyLock::new(|| {
-    StatusResponse::ok(SERVER_GREETING)
-        .with_code(ResponseCode::Capability {
-            capabilities: Capability::all_capabilities(false, true),
-        })
-        .into_bytes()
-});
-
-pub(crate) static GREETING_WITHOUT_TLS: LazyLock<Vec<u8>> = LazyLock::new(|| {
-    StatusResponse::ok(SERVER_GREETING)
-        .with_code(ResponseCode::Capability {
-            capabilities: Capability::all_capabilities(false, false),
-        })
-        .into_bytes()
-});
+pub(crate) static SERVER_GREETING: &str = "Stalwart IMAP4rev2 at your service.";
 
 impl IMAP {
     pub async fn init(config: &mut Config, jmap_instance: JmapInstance) -> ImapInstance {
@@ -53,13 +33,16 @@ impl IMAP {
                 RandomState::default(),
                 shard_amount,
             ),
-            cache_account: LruCache::with_capacity(
+            cache_account: Arc::new(LruCache::with_capacity(
                 config.property("cache.account.size").unwrap_or(2048),
-            ),
-            cache_mailbox: LruCache::with_capacity(
+            )),
+            cache_mailbox: Arc::new(LruCache::with_capacity(
                 config.property("cache.mailbox.size").unwrap_or(2048),
-            ),
+            )),
         };
+        let caches = &jmap_instance.jmap_inner.caches;
+        caches.register("account", inner.cache_account.clone());
+        caches.register("mailbox", inner.cache_mailbox.clone());
 
         ImapInstance {
             jmap_instance,
@@ -365,11 +365,7 @@ impl<T: SessionStream> Session<T> {
             }
 
             // Invalidate ACLs
-            data.jmap
-                .core
-                .security
-                .access_tokens
-                .remove(&acl_account_id);
+            data.jmap.core.invalidate_access_tokens([acl_account_id]);
 
             trc::event!(
                 Imap(trc::ImapEvent::SetAcl),
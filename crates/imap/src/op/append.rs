@@ -18,7 +18,10 @@ use crate::{
     spawn_op,
 };
 use common::listener::SessionStream;
-use jmap::email::ingest::{IngestEmail, IngestSource};
+use jmap::{
+    email::ingest::{IngestEmail, IngestSource},
+    sieve::imapsieve::ImapSieveCause,
+};
 use jmap_proto::types::{acl::Acl, keyword::Keyword, state::StateChange, type_state::DataType};
 use mail_parser::MessageParser;
 
@@ -165,6 +168,15 @@ impl<T: SessionStream> SessionData<T> {
             Elapsed = op_start.elapsed()
         );
 
+        // Run imapsieve scripts
+        self.run_imap_sieve(
+            ImapSieveCause::Append,
+            mailbox,
+            arguments.mailbox_name,
+            created_ids.iter().map(|id| id.id).collect(),
+            vec![],
+        );
+
         if !created_ids.is_empty() {
             let uids = created_ids.iter().map(|id| id.uid).collect();
             let uid_validity = match selected_mailbox {
@@ -88,7 +88,10 @@ impl<T: SessionStream> Session<T> {
                     .validate_access_token("access_token", &token)
                     .await
                 {
-                    Ok((account_id, _, _)) => self.jmap.core.get_access_token(account_id).await,
+                    Ok((account_id, _, _)) => {
+                        self.jmap.account_login(account_id).await;
+                        self.jmap.core.get_access_token(account_id).await
+                    }
                     Err(err) => Err(err),
                 }
             }
@@ -143,6 +146,7 @@ impl<T: SessionStream> Session<T> {
                     capabilities: Capability::all_capabilities(
                         true,
                         !self.is_tls && self.instance.acceptor.is_tls(),
+                        self.jmap.core.imap.gmail_extensions,
                     ),
                 })
                 .with_tag(tag)
@@ -40,6 +40,7 @@ impl<T: SessionStream> Session<T> {
                         capabilities: Capability::all_capabilities(
                             self.state.is_authenticated(),
                             !self.is_tls && self.instance.acceptor.is_tls(),
+                            self.jmap.core.imap.gmail_extensions,
                         ),
                     }
                     .serialize(),
@@ -63,12 +64,20 @@ impl<T: SessionStream> Session<T> {
             StatusResponse::completed(Command::Id)
                 .with_tag(request.tag)
                 .serialize(
-                    concat!(
-                        "* ID (\"name\" \"Stalwart IMAP\" \"version\" \"",
-                        env!("CARGO_PKG_VERSION"),
-                        "\" \"vendor\" \"Stalwart Labs Ltd.\" ",
-                        "\"support-url\" \"https://stalw.art\")\r\n"
-                    )
+                    if !self.jmap.core.network.hide_version {
+                        concat!(
+                            "* ID (\"name\" \"Stalwart IMAP\" \"version\" \"",
+                            env!("CARGO_PKG_VERSION"),
+                            "\" \"vendor\" \"Stalwart Labs Ltd.\" ",
+                            "\"support-url\" \"https://stalw.art\")\r\n"
+                        )
+                    } else {
+                        concat!(
+                            "* ID (\"name\" \"Stalwart IMAP\" ",
+                            "\"vendor\" \"Stalwart Labs Ltd.\" ",
+                            "\"support-url\" \"https://stalw.art\")\r\n"
+                        )
+                    }
                     .as_bytes()
                     .to_vec(),
                 ),
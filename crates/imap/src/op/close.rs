@@ -16,7 +16,15 @@ impl<T: SessionStream> Session<T> {
         let op_start = Instant::now();
         let (data, mailbox) = self.state.select_data();
 
-        if mailbox.is_select {
+        // Deleted messages are kept while the account is in maintenance mode
+        if mailbox.is_select
+            && !self
+                .jmap
+                .core
+                .storage
+                .maintenance
+                .is_read_only(mailbox.id.account_id)
+        {
             data.expunge(mailbox.clone(), None, op_start)
                 .await
                 .caused_by(trc::location!())?;
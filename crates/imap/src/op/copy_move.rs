@@ -17,7 +17,7 @@ use crate::{
     spawn_op,
 };
 use common::listener::SessionStream;
-use jmap::{email::set::TagManager, mailbox::UidMailbox};
+use jmap::{email::set::TagManager, mailbox::UidMailbox, sieve::imapsieve::ImapSieveCause};
 use jmap_proto::{
     error::set::SetErrorType,
     types::{
@@ -165,6 +165,7 @@ impl<T: SessionStream> SessionData<T> {
         let mut changelog = ChangeLogBuilder::new();
         let mut did_move = false;
         let mut copied_ids = Vec::with_capacity(ids.len());
+        let mut sieve_ids = Vec::with_capacity(ids.len());
         if src_mailbox.id.account_id == dest_mailbox.account_id {
             // Mailboxes are in the same account
             let account_id = src_mailbox.id.account_id;
@@ -231,6 +232,7 @@ impl<T: SessionStream> SessionData<T> {
                     .imap_ctx(&arguments.tag, trc::location!())?;
                 changelog.log_update(Collection::Email, Id::from_parts(thread_id, id));
                 changelog.log_child_update(Collection::Mailbox, dest_mailbox_id.mailbox_id);
+                sieve_ids.push(id);
                 if is_move {
                     changelog.log_child_update(Collection::Mailbox, src_mailbox.id.mailbox_id);
                     did_move = true;
@@ -266,6 +268,7 @@ impl<T: SessionStream> SessionData<T> {
                 {
                     Ok(email) => {
                         dest_change_id = email.change_id.into();
+                        sieve_ids.push(email.id.document_id());
                         if let Some(assigned_uid) = email.imap_uids.first() {
                             debug_assert!(*assigned_uid > 0);
                             copied_ids.push((imap_id.uid, *assigned_uid));
@@ -330,6 +333,15 @@ impl<T: SessionStream> SessionData<T> {
                 .await;
         }
 
+        // Run imapsieve scripts
+        self.run_imap_sieve(
+            ImapSieveCause::Copy,
+            dest_mailbox,
+            arguments.mailbox_name.clone(),
+            sieve_ids,
+            vec![],
+        );
+
         // Map copied JMAP Ids to IMAP UIDs in the destination folder.
         if copied_ids.is_empty() {
             return Err(if response.rtype != ResponseType::Ok {
@@ -73,6 +73,15 @@ impl<T: SessionStream> SessionData<T> {
             .imap_ctx(&arguments.tag, trc::location!())?;
         debug_assert!(!params.path.is_empty());
 
+        // Restore subscriptions to previously deleted mailboxes
+        let is_subscribed = params.account_id == self.account_id
+            && self
+                .jmap
+                .mailbox_deleted_subscriptions(self.account_id)
+                .await
+                .imap_ctx(&arguments.tag, trc::location!())?
+                .contains(&arguments.mailbox_name);
+
         // Build batch
         let mut changes = self
             .jmap
@@ -94,6 +103,12 @@ impl<T: SessionStream> SessionData<T> {
                 if let Some(mailbox_role) = arguments.mailbox_role {
                     mailbox.set(Property::Role, mailbox_role);
                 }
+                if is_subscribed {
+                    mailbox.set(
+                        Property::IsSubscribed,
+                        Value::List(vec![Value::Id(Id::from(self.account_id))]),
+                    );
+                }
             }
             let mut batch = BatchBuilder::new();
             batch
@@ -143,10 +158,27 @@ impl<T: SessionStream> SessionData<T> {
         );
 
         // Add created mailboxes to session
-        std::mem::drop(
-            self.add_created_mailboxes(&mut params, change_id, create_ids)
-                .imap_ctx(&arguments.tag, trc::location!())?,
-        );
+        let mut mailboxes = self
+            .add_created_mailboxes(&mut params, change_id, create_ids)
+            .imap_ctx(&arguments.tag, trc::location!())?;
+        if is_subscribed {
+            if let Some(mailbox) = mailboxes
+                .iter_mut()
+                .find(|account| account.account_id == params.account_id)
+                .and_then(|account| account.mailbox_state.get_mut(&(parent_id - 1)))
+            {
+                mailbox.is_subscribed = true;
+            }
+        }
+        std::mem::drop(mailboxes);
+
+        // The subscription is now tracked by the new mailbox
+        if is_subscribed {
+            self.jmap
+                .mailbox_remove_deleted_subscription(self.account_id, &arguments.mailbox_name)
+                .await
+                .imap_ctx(&arguments.tag, trc::location!())?;
+        }
 
         // Build response
         Ok(StatusResponse::ok("Mailbox created.")
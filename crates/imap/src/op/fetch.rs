@@ -236,7 +236,7 @@ impl<T: SessionStream> SessionData<T> {
                     }
                     needs_blobs = true;
                 }
-                Attribute::ThreadId => {
+                Attribute::ThreadId | Attribute::XGmThrId => {
                     needs_thread_id = true;
                 }
                 _ => (),
@@ -244,14 +244,20 @@ impl<T: SessionStream> SessionData<T> {
         }
 
         if set_seen_flags
-            && !self
-                .check_mailbox_acl(
-                    mailbox.id.account_id,
-                    mailbox.id.mailbox_id,
-                    Acl::ModifyItems,
-                )
-                .await
-                .imap_ctx(&arguments.tag, trc::location!())?
+            && (self
+                .jmap
+                .core
+                .storage
+                .maintenance
+                .is_read_only(mailbox.id.account_id)
+                || !self
+                    .check_mailbox_acl(
+                        mailbox.id.account_id,
+                        mailbox.id.mailbox_id,
+                        Acl::ModifyItems,
+                    )
+                    .await
+                    .imap_ctx(&arguments.tag, trc::location!())?)
         {
             set_seen_flags = false;
         }
@@ -496,6 +502,16 @@ impl<T: SessionStream> SessionData<T> {
                             thread_id: Id::from_parts(account_id, thread_id).to_string(),
                         });
                     }
+                    Attribute::XGmMsgId => {
+                        items.push(DataItem::XGmMsgId {
+                            msg_id: Id::from_parts(account_id, id).id(),
+                        });
+                    }
+                    Attribute::XGmThrId => {
+                        items.push(DataItem::XGmThrId {
+                            thread_id: Id::from_parts(account_id, thread_id).id(),
+                        });
+                    }
                 }
             }
 
@@ -165,6 +165,16 @@ impl<T: SessionStream> SessionData<T> {
             })
         }
 
+        // Obtain subscriptions, which might include deleted mailboxes
+        let subscriptions = if filter_subscribed {
+            self.jmap
+                .mailbox_deleted_subscriptions(self.account_id)
+                .await
+                .imap_ctx(&tag, trc::location!())?
+        } else {
+            Vec::new()
+        };
+
         let mut list_items = Vec::with_capacity(10);
 
         // Add mailboxes
@@ -214,6 +224,10 @@ impl<T: SessionStream> SessionData<T> {
                                 break;
                             }
                         }
+                        if !has_recursive_match {
+                            has_recursive_match =
+                                subscriptions.iter().any(|name| name.starts_with(&prefix));
+                        }
                     }
                     if !filter_subscribed || mailbox.is_subscribed || has_recursive_match {
                         let mut attributes = Vec::with_capacity(2);
@@ -248,10 +262,35 @@ impl<T: SessionStream> SessionData<T> {
             }
         }
 
+        // Add subscribed mailboxes that no longer exist
+        if !subscriptions.is_empty() && !filter_special_use {
+            let mailboxes = self.mailboxes.lock();
+            for mailbox_name in subscriptions {
+                if matches_pattern(&patterns, &mailbox_name)
+                    && !mailboxes
+                        .iter()
+                        .any(|account| account.mailbox_names.contains_key(&mailbox_name))
+                {
+                    list_items.push(ListItem {
+                        mailbox_name,
+                        attributes: if !is_lsub {
+                            vec![Attribute::NonExistent, Attribute::Subscribed]
+                        } else {
+                            vec![Attribute::NoSelect]
+                        },
+                        tags: vec![],
+                    });
+                }
+            }
+        }
+
         // Add status response
         let mut status_items = Vec::new();
         if let Some(include_status) = include_status {
             for list_item in &list_items {
+                if list_item.attributes.contains(&Attribute::NonExistent) {
+                    continue;
+                }
                 match self
                     .status(list_item.mailbox_name.to_string(), include_status)
                     .await
@@ -14,14 +14,15 @@ impl<T: SessionStream> Session<T> {
     pub async fn handle_logout(&mut self, request: Request<Command>) -> trc::Result<()> {
         let op_start = Instant::now();
 
-        let mut response = StatusResponse::bye(
+        let mut response = StatusResponse::bye(if !self.jmap.core.network.hide_version {
             concat!(
                 "Stalwart IMAP4rev2 v",
                 env!("CARGO_PKG_VERSION"),
                 " bids you farewell."
             )
-            .to_string(),
-        )
+        } else {
+            "Stalwart IMAP4rev2 bids you farewell."
+        })
         .into_bytes();
 
         trc::event!(
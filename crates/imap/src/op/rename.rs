@@ -203,6 +203,14 @@ impl<T: SessionStream> SessionData<T> {
             )
             .await;
 
+        // The renamed mailbox supersedes any deleted mailbox subscription with the same name
+        if params.account_id == self.account_id {
+            self.jmap
+                .mailbox_remove_deleted_subscription(self.account_id, &arguments.new_mailbox_name)
+                .await
+                .imap_ctx(&arguments.tag, trc::location!())?;
+        }
+
         let mut mailboxes = if !create_ids.is_empty() {
             self.add_created_mailboxes(&mut params, change_id, create_ids)
                 .add_context(|err| err.id(arguments.tag.clone()))?
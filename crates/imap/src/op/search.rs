@@ -640,6 +640,9 @@ impl<T: SessionStream> SessionData<T> {
                                 .details(format!("Failed to parse email id '{id}'.",)));
                         }
                     }
+                    search::Filter::HasAttachment => {
+                        filters.push(query::Filter::is_in_bitmap(Property::HasAttachment, ()));
+                    }
                     search::Filter::ThreadId(id) => {
                         if let Some(id) = Id::from_bytes(id.as_bytes()) {
                             filters.push(query::Filter::is_in_bitmap(
@@ -652,6 +655,17 @@ impl<T: SessionStream> SessionData<T> {
                                 .details(format!("Failed to parse thread id '{id}'.",)));
                         }
                     }
+                    search::Filter::XGmMsgId(id) => {
+                        filters.push(query::Filter::is_in_set(
+                            RoaringBitmap::from_sorted_iter([Id::new(id).document_id()]).unwrap(),
+                        ));
+                    }
+                    search::Filter::XGmThrId(id) => {
+                        filters.push(query::Filter::is_in_bitmap(
+                            Property::ThreadId,
+                            Id::new(id).document_id(),
+                        ));
+                    }
                     _ => (),
                 },
             }
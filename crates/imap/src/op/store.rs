@@ -22,7 +22,7 @@ use imap_proto::{
     receiver::Request,
     Command, ResponseCode, ResponseType, StatusResponse,
 };
-use jmap::{email::set::TagManager, mailbox::UidMailbox};
+use jmap::{email::set::TagManager, mailbox::UidMailbox, sieve::imapsieve::ImapSieveCause};
 use jmap_proto::types::{
     acl::Acl, collection::Collection, id::Id, keyword::Keyword, property::Property,
     state::StateChange, type_state::DataType,
@@ -187,6 +187,8 @@ impl<T: SessionStream> SessionData<T> {
             .collect::<Vec<_>>();
         let mut changelog = ChangeLogBuilder::new();
         let mut changed_mailboxes = AHashSet::new();
+        let mut changed_ids = Vec::new();
+        let mut changed_flags = AHashSet::new();
         'outer: for (id, imap_id) in &ids {
             let mut try_count = 0;
             loop {
@@ -233,6 +235,7 @@ impl<T: SessionStream> SessionData<T> {
                     let seen_changed = keywords
                         .changed_tags()
                         .any(|keyword| keyword == &Keyword::Seen);
+                    let keywords_changed = keywords.changed_tags().cloned().collect::<Vec<_>>();
                     let flags = if !arguments.is_silent {
                         keywords
                             .current()
@@ -280,6 +283,8 @@ impl<T: SessionStream> SessionData<T> {
                                 }
                             }
                             changelog.log_update(Collection::Email, Id::from_parts(thread_id, *id));
+                            changed_ids.push(*id);
+                            changed_flags.extend(keywords_changed);
 
                             // Add item to response
                             let modseq = changelog.change_id + 1;
@@ -350,6 +355,24 @@ impl<T: SessionStream> SessionData<T> {
                 .await;
         }
 
+        // Run imapsieve scripts
+        if let Some(mailbox_name) = self.get_mailbox_name(&mailbox.id) {
+            self.run_imap_sieve(
+                ImapSieveCause::Flag,
+                mailbox.id,
+                mailbox_name,
+                changed_ids,
+                changed_flags
+                    .into_iter()
+                    .map(|keyword| {
+                        let mut buf = Vec::new();
+                        Flag::from(keyword).serialize(&mut buf);
+                        String::from_utf8(buf).unwrap_or_default()
+                    })
+                    .collect(),
+            );
+        }
+
         trc::event!(
             Imap(trc::ImapEvent::Store),
             SpanId = self.session_id,
@@ -70,6 +70,26 @@ impl<T: SessionStream> SessionData<T> {
         let (account_id, mailbox_id) = match self.get_mailbox_by_name(&mailbox_name) {
             Some(mailbox) => (mailbox.account_id, mailbox.mailbox_id),
             None => {
+                // Subscriptions to deleted mailboxes can still be removed
+                let mut did_unsubscribe = false;
+                if !subscribe {
+                    did_unsubscribe = self
+                        .jmap
+                        .mailbox_remove_deleted_subscription(self.account_id, &mailbox_name)
+                        .await
+                        .imap_ctx(&tag, trc::location!())?;
+                }
+                if did_unsubscribe {
+                    trc::event!(
+                        Imap(trc::ImapEvent::Unsubscribe),
+                        SpanId = self.session_id,
+                        MailboxName = mailbox_name,
+                        Elapsed = op_start.elapsed()
+                    );
+
+                    return Ok(StatusResponse::ok("Mailbox unsubscribed.").with_tag(tag));
+                }
+
                 return Err(trc::ImapEvent::Error
                     .into_err()
                     .details("Mailbox does not exist.")
@@ -165,6 +165,10 @@ impl Serialize for MethodErrorWrapper {
                     "This server is temporarily unavailable.",
                 ),
             },
+            trc::EventType::Server(trc::ServerEvent::MaintenanceMode) => (
+                "serverUnavailable",
+                description.unwrap_or("This server is temporarily read-only for maintenance."),
+            ),
             _ => (
                 "serverUnavailable",
                 concat!(
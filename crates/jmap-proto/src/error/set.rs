@@ -82,6 +82,14 @@ pub enum SetErrorType {
     InvalidScript,
     #[serde(rename = "scriptIsActive")]
     ScriptIsActive,
+    #[serde(rename = "calendarHasEvent")]
+    CalendarHasEvent,
+    #[serde(rename = "addressBookHasContents")]
+    AddressBookHasContents,
+    #[serde(rename = "taskListHasTask")]
+    TaskListHasTask,
+    #[serde(rename = "mdnAlreadySent")]
+    MdnAlreadySent,
 }
 
 impl SetErrorType {
@@ -112,6 +120,10 @@ impl SetErrorType {
             SetErrorType::AlreadyExists => "alreadyExists",
             SetErrorType::InvalidScript => "invalidScript",
             SetErrorType::ScriptIsActive => "scriptIsActive",
+            SetErrorType::CalendarHasEvent => "calendarHasEvent",
+            SetErrorType::AddressBookHasContents => "addressBookHasContents",
+            SetErrorType::TaskListHasTask => "taskListHasTask",
+            SetErrorType::MdnAlreadySent => "mdnAlreadySent",
         }
     }
 }
@@ -0,0 +1,87 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use serde::Serialize;
+
+use crate::{
+    parser::{json::Parser, JsonObjectParser, Token},
+    request::RequestProperty,
+    types::{date::UTCDate, id::Id},
+};
+
+#[derive(Debug, Clone)]
+pub struct GetAvailabilityRequest {
+    pub account_id: Id,
+    pub id: Id,
+    pub utc_start: UTCDate,
+    pub utc_end: UTCDate,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetAvailabilityResponse {
+    pub list: Vec<BusyPeriod>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BusyPeriod {
+    #[serde(rename = "utcStart")]
+    pub utc_start: UTCDate,
+    #[serde(rename = "utcEnd")]
+    pub utc_end: UTCDate,
+    #[serde(rename = "busyStatus")]
+    pub busy_status: BusyStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum BusyStatus {
+    #[serde(rename = "tentative")]
+    Tentative,
+    #[serde(rename = "confirmed")]
+    Confirmed,
+    #[serde(rename = "unavailable")]
+    Unavailable,
+}
+
+impl JsonObjectParser for GetAvailabilityRequest {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = GetAvailabilityRequest {
+            account_id: Id::default(),
+            id: Id::default(),
+            utc_start: UTCDate::default(),
+            utc_end: UTCDate::default(),
+        };
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x0064_4974_6e75_6f63_6361 if !key.is_ref => {
+                    request.account_id = parser.next_token::<Id>()?.unwrap_string("accountId")?;
+                }
+                0x6469 if !key.is_ref => {
+                    request.id = parser.next_token::<Id>()?.unwrap_string("id")?;
+                }
+                0x7472_6174_5363_7475 if !key.is_ref => {
+                    request.utc_start =
+                        parser.next_token::<UTCDate>()?.unwrap_string("utcStart")?;
+                }
+                0x646e_4563_7475 if !key.is_ref => {
+                    request.utc_end = parser.next_token::<UTCDate>()?.unwrap_string("utcEnd")?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
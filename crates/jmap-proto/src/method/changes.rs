@@ -51,6 +51,14 @@ pub enum RequestArguments {
     Identity,
     EmailSubmission,
     Quota,
+    Activity,
+    Calendar,
+    CalendarEvent,
+    AddressBook,
+    ContactCard,
+    TaskList,
+    Task,
+    SieveScript,
 }
 
 impl JsonObjectParser for ChangesRequest {
@@ -66,6 +74,14 @@ impl JsonObjectParser for ChangesRequest {
                 MethodObject::Identity => RequestArguments::Identity,
                 MethodObject::EmailSubmission => RequestArguments::EmailSubmission,
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::Activity => RequestArguments::Activity,
+                MethodObject::Calendar => RequestArguments::Calendar,
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::AddressBook => RequestArguments::AddressBook,
+                MethodObject::ContactCard => RequestArguments::ContactCard,
+                MethodObject::TaskList => RequestArguments::TaskList,
+                MethodObject::Task => RequestArguments::Task,
+                MethodObject::SieveScript => RequestArguments::SieveScript,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -35,6 +35,14 @@ pub enum RequestArguments {
     VacationResponse,
     Principal,
     Quota,
+    Activity,
+    Calendar,
+    CalendarEvent,
+    AddressBook,
+    ContactCard,
+    TaskList,
+    Task,
+    DeletedEmail,
     Blob(blob::GetArguments),
 }
 
@@ -71,6 +79,14 @@ impl JsonObjectParser for GetRequest<RequestArguments> {
                 MethodObject::Principal => RequestArguments::Principal,
                 MethodObject::Blob => RequestArguments::Blob(Default::default()),
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::Activity => RequestArguments::Activity,
+                MethodObject::Calendar => RequestArguments::Calendar,
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::AddressBook => RequestArguments::AddressBook,
+                MethodObject::ContactCard => RequestArguments::ContactCard,
+                MethodObject::TaskList => RequestArguments::TaskList,
+                MethodObject::Task => RequestArguments::Task,
+                MethodObject::DeletedEmail => RequestArguments::DeletedEmail,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -0,0 +1,154 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use utils::map::vec_map::VecMap;
+
+use crate::{
+    error::set::SetError,
+    object::Object,
+    parser::{json::Parser, JsonObjectParser, Token},
+    request::{reference::MaybeReference, RequestProperty},
+    types::{
+        blob::BlobId,
+        id::Id,
+        value::{SetValue, Value},
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct MdnSendRequest {
+    pub account_id: Id,
+    pub identity_id: Id,
+    pub send: VecMap<String, Object<Value>>,
+    pub on_success_update_email: Option<VecMap<MaybeReference<Id, String>, Object<SetValue>>>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MdnSendResponse {
+    #[serde(rename = "accountId")]
+    pub account_id: Id,
+
+    #[serde(rename = "sent")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub sent: VecMap<String, Object<Value>>,
+
+    #[serde(rename = "notSent")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub not_sent: VecMap<String, SetError>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MdnParseRequest {
+    pub account_id: Id,
+    pub blob_ids: Vec<BlobId>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MdnParseResponse {
+    #[serde(rename = "accountId")]
+    pub account_id: Id,
+
+    #[serde(rename = "parsed")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub parsed: VecMap<BlobId, Object<Value>>,
+
+    #[serde(rename = "notParsable")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub not_parsable: Vec<BlobId>,
+
+    #[serde(rename = "notFound")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub not_found: Vec<BlobId>,
+}
+
+impl JsonObjectParser for MdnSendRequest {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = MdnSendRequest {
+            account_id: Id::default(),
+            identity_id: Id::default(),
+            send: VecMap::new(),
+            on_success_update_email: None,
+        };
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match (&key.hash[0], &key.hash[1]) {
+                (0x0064_4974_6e75_6f63_6361, _) if !key.is_ref => {
+                    request.account_id = parser.next_token::<Id>()?.unwrap_string("accountId")?;
+                }
+                (0x6449_7974_6974_6e65_6469, _) if !key.is_ref => {
+                    request.identity_id = parser.next_token::<Id>()?.unwrap_string("identityId")?;
+                }
+                (0x646e_6573, _) if !key.is_ref => {
+                    // MDN objects are stored as-is and validated by the server
+                    parser
+                        .next_token::<String>()?
+                        .assert_jmap(Token::DictStart)?;
+                    while let Some(create_id) = parser.next_dict_key::<String>()? {
+                        match Value::parse::<String, String>(parser.next_token()?, parser)? {
+                            Value::Object(mdn) => {
+                                request.send.append(create_id, mdn);
+                            }
+                            _ => {
+                                return Err(trc::JmapEvent::InvalidArguments
+                                    .into_err()
+                                    .details(format!("Invalid MDN object {create_id:?}")));
+                            }
+                        }
+                    }
+                }
+                (0x4565_7461_6470_5573_7365_6363_7553_6e6f, 0x6c69_616d) if !key.is_ref => {
+                    request.on_success_update_email = <Option<
+                        VecMap<MaybeReference<Id, String>, Object<SetValue>>,
+                    >>::parse(parser)?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+impl JsonObjectParser for MdnParseRequest {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = MdnParseRequest {
+            account_id: Id::default(),
+            blob_ids: vec![],
+        };
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x0064_4974_6e75_6f63_6361 if !key.is_ref => {
+                    request.account_id = parser.next_token::<Id>()?.unwrap_string("accountId")?;
+                }
+                0x0073_6449_626f_6c62 => {
+                    request.blob_ids = <Vec<BlobId>>::parse(parser)?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
@@ -106,3 +106,64 @@ impl JsonObjectParser for ParseEmailRequest {
         Ok(request)
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct ParseContactCardRequest {
+    pub account_id: Id,
+    pub blob_ids: Vec<BlobId>,
+    pub properties: Option<Vec<Property>>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ParseContactCardResponse {
+    #[serde(rename = "accountId")]
+    pub account_id: Id,
+
+    #[serde(rename = "parsed")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub parsed: VecMap<BlobId, Object<Value>>,
+
+    #[serde(rename = "notParsable")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub not_parsable: Vec<BlobId>,
+
+    #[serde(rename = "notFound")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub not_found: Vec<BlobId>,
+}
+
+impl JsonObjectParser for ParseContactCardRequest {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = ParseContactCardRequest {
+            account_id: Id::default(),
+            blob_ids: vec![],
+            properties: None,
+        };
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x0064_4974_6e75_6f63_6361 if !key.is_ref => {
+                    request.account_id = parser.next_token::<Id>()?.unwrap_string("accountId")?;
+                }
+                0x0073_6449_626f_6c62 => {
+                    request.blob_ids = <Vec<BlobId>>::parse(parser)?;
+                }
+                0x7365_6974_7265_706f_7270 => {
+                    request.properties = <Option<Vec<Property>>>::parse(parser)?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
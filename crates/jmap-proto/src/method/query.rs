@@ -75,6 +75,7 @@ pub enum Filter {
     InMailboxOtherThan(Vec<Id>),
     MinSize(u32),
     MaxSize(u32),
+    AwaitingReply(u32),
     AllInThreadHaveKeyword(Keyword),
     SomeInThreadHaveKeyword(Keyword),
     NoneInThreadHaveKeyword(Keyword),
@@ -99,6 +100,13 @@ pub enum Filter {
     IsActive(bool),
     Scope(String),
     ResourceType(String),
+    InCalendars(Vec<Id>),
+    Title(String),
+    Uid(String),
+    InAddressBook(Id),
+    Kind(String),
+    InTaskLists(Vec<Id>),
+    Progress(String),
     _T(String),
 
     And,
@@ -136,6 +144,10 @@ pub enum SortProperty {
     AllInThreadHaveKeyword,
     SomeInThreadHaveKeyword,
     Used,
+    Start,
+    Uid,
+    Due,
+    Priority,
     _T(String),
 }
 
@@ -147,6 +159,9 @@ pub enum RequestArguments {
     SieveScript,
     Principal,
     Quota,
+    CalendarEvent,
+    ContactCard,
+    Task,
 }
 
 impl JsonObjectParser for QueryRequest<RequestArguments> {
@@ -162,6 +177,9 @@ impl JsonObjectParser for QueryRequest<RequestArguments> {
                 MethodObject::SieveScript => RequestArguments::SieveScript,
                 MethodObject::Principal => RequestArguments::Principal,
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::ContactCard => RequestArguments::ContactCard,
+                MethodObject::Task => RequestArguments::Task,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -330,6 +348,12 @@ pub fn parse_filter(parser: &mut Parser) -> trc::Result<Vec<Filter>> {
                                 .unwrap_uint_or_null("maxSize")?
                                 .unwrap_or_default() as u32,
                         ),
+                        (0x0079_6c70_6552_676e_6974_6961_7761, _) => Filter::AwaitingReply(
+                            parser
+                                .next_token::<String>()?
+                                .unwrap_uint_or_null("awaitingReply")?
+                                .unwrap_or_default() as u32,
+                        ),
                         (0x4b65_7661_4864_6165_7268_546e_496c_6c61, 0x6472_6f77_7965) => {
                             Filter::AllInThreadHaveKeyword(
                                 parser
@@ -416,6 +440,27 @@ pub fn parse_filter(parser: &mut Parser) -> trc::Result<Vec<Filter>> {
                         (0x6576_6974_6341_7369, _) => Filter::IsActive(
                             parser.next_token::<String>()?.unwrap_bool("isActive")?,
                         ),
+                        (0x0073_7261_646e_656c_6143_6e69, _) => {
+                            Filter::InCalendars(<Vec<Id>>::parse(parser)?)
+                        }
+                        (0x0065_6c74_6974, _) => {
+                            Filter::Title(parser.next_token::<String>()?.unwrap_string("title")?)
+                        }
+                        (0x0064_6975, _) => {
+                            Filter::Uid(parser.next_token::<String>()?.unwrap_string("uid")?)
+                        }
+                        (0x006b_6f6f_4273_7365_7264_6441_6e69, _) => Filter::InAddressBook(
+                            parser.next_token::<Id>()?.unwrap_string("inAddressBook")?,
+                        ),
+                        (0x646e_696b, _) => {
+                            Filter::Kind(parser.next_token::<String>()?.unwrap_string("kind")?)
+                        }
+                        (0x0073_7473_694c_6b73_6154_6e69, _) => {
+                            Filter::InTaskLists(<Vec<Id>>::parse(parser)?)
+                        }
+                        (0x7373_6572_676f_7270, _) => Filter::Progress(
+                            parser.next_token::<String>()?.unwrap_string("progress")?,
+                        ),
                         (0x0065_706f_6373, _) => {
                             Filter::Scope(parser.next_token::<String>()?.unwrap_string("scope")?)
                         }
@@ -566,6 +611,10 @@ impl JsonObjectParser for SortProperty {
             0x4b65_7661_4864_6165_7268_546e_496c_6c61 => Ok(SortProperty::AllInThreadHaveKeyword),
             0x6576_6148_6461_6572_6854_6e49_656d_6f73 => Ok(SortProperty::SomeInThreadHaveKeyword),
             0x6465_7375 => Ok(SortProperty::Used),
+            0x0074_7261_7473 => Ok(SortProperty::Start),
+            0x0064_6975 => Ok(SortProperty::Uid),
+            0x0065_7564 => Ok(SortProperty::Due),
+            0x7974_6972_6f69_7270 => Ok(SortProperty::Priority),
             _ => {
                 if parser.is_eof || parser.skip_string() {
                     Ok(SortProperty::_T(
@@ -604,6 +653,7 @@ impl Display for Filter {
             Filter::InMailboxOtherThan(_) => "inMailboxOtherThan",
             Filter::MinSize(_) => "minSize",
             Filter::MaxSize(_) => "maxSize",
+            Filter::AwaitingReply(_) => "awaitingReply",
             Filter::AllInThreadHaveKeyword(_) => "allInThreadHaveKeyword",
             Filter::SomeInThreadHaveKeyword(_) => "someInThreadHaveKeyword",
             Filter::NoneInThreadHaveKeyword(_) => "noneInThreadHaveKeyword",
@@ -627,6 +677,13 @@ impl Display for Filter {
             Filter::IsSubscribed(_) => "isSubscribed",
             Filter::IsActive(_) => "isActive",
             Filter::ResourceType(_) => "resourceType",
+            Filter::InCalendars(_) => "inCalendars",
+            Filter::Title(_) => "title",
+            Filter::Uid(_) => "uid",
+            Filter::InAddressBook(_) => "inAddressBook",
+            Filter::Kind(_) => "kind",
+            Filter::InTaskLists(_) => "inTaskLists",
+            Filter::Progress(_) => "progress",
             Filter::Scope(_) => "scope",
             Filter::_T(v) => v.as_str(),
             Filter::And => "and",
@@ -659,6 +716,10 @@ impl Display for SortProperty {
             SortProperty::AllInThreadHaveKeyword => "allInThreadHaveKeyword",
             SortProperty::SomeInThreadHaveKeyword => "someInThreadHaveKeyword",
             SortProperty::Used => "used",
+            SortProperty::Start => "start",
+            SortProperty::Uid => "uid",
+            SortProperty::Due => "due",
+            SortProperty::Priority => "priority",
             SortProperty::_T(s) => s,
         })
     }
@@ -69,6 +69,10 @@ impl JsonObjectParser for QueryChangesRequest {
                 MethodObject::Mailbox => RequestArguments::Mailbox(Default::default()),
                 MethodObject::EmailSubmission => RequestArguments::EmailSubmission,
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::ContactCard => RequestArguments::ContactCard,
+                MethodObject::Task => RequestArguments::Task,
+                MethodObject::SieveScript => RequestArguments::SieveScript,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
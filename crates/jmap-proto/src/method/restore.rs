@@ -0,0 +1,67 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use utils::map::vec_map::VecMap;
+
+use crate::{
+    error::set::SetError,
+    object::Object,
+    parser::{json::Parser, JsonObjectParser, Token},
+    request::RequestProperty,
+    types::{id::Id, value::Value},
+};
+
+#[derive(Debug, Clone)]
+pub struct RestoreRequest {
+    pub account_id: Id,
+    pub ids: Vec<Id>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RestoreResponse {
+    #[serde(rename = "accountId")]
+    pub account_id: Id,
+
+    #[serde(rename = "restored")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub restored: VecMap<Id, Object<Value>>,
+
+    #[serde(rename = "notRestored")]
+    #[serde(skip_serializing_if = "VecMap::is_empty")]
+    pub not_restored: VecMap<Id, SetError>,
+}
+
+impl JsonObjectParser for RestoreRequest {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut request = RestoreRequest {
+            account_id: Id::default(),
+            ids: vec![],
+        };
+
+        parser
+            .next_token::<String>()?
+            .assert_jmap(Token::DictStart)?;
+
+        while let Some(key) = parser.next_dict_key::<RequestProperty>()? {
+            match &key.hash[0] {
+                0x0064_4974_6e75_6f63_6361 if !key.is_ref => {
+                    request.account_id = parser.next_token::<Id>()?.unwrap_string("accountId")?;
+                }
+                0x0073_6469 if !key.is_ref => {
+                    request.ids = <Vec<Id>>::parse(parser)?;
+                }
+                _ => {
+                    parser.skip_token(parser.depth_array, parser.depth_dict)?;
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
@@ -9,7 +9,7 @@ use utils::map::{bitmap::Bitmap, vec_map::VecMap};
 
 use crate::{
     error::set::{InvalidProperty, SetError},
-    object::{email_submission, mailbox, sieve, Object},
+    object::{calendar, contact, email_submission, mailbox, sieve, task, Object},
     parser::{json::Parser, JsonObjectParser, Token},
     request::{
         method::MethodObject,
@@ -18,7 +18,7 @@ use crate::{
     },
     response::Response,
     types::{
-        acl::Acl,
+        acl::{Acl, CalendarRights, MailboxRights},
         any_id::AnyId,
         blob::BlobId,
         date::UTCDate,
@@ -51,6 +51,12 @@ pub enum RequestArguments {
     PushSubscription,
     SieveScript(sieve::SetArguments),
     VacationResponse,
+    Calendar(calendar::SetArguments),
+    CalendarEvent,
+    AddressBook(contact::SetArguments),
+    ContactCard,
+    TaskList(task::SetArguments),
+    Task,
 }
 
 #[derive(Debug, Clone, Default, serde::Serialize)]
@@ -111,6 +117,12 @@ impl JsonObjectParser for SetRequest<RequestArguments> {
                 MethodObject::PushSubscription => RequestArguments::PushSubscription,
                 MethodObject::VacationResponse => RequestArguments::VacationResponse,
                 MethodObject::SieveScript => RequestArguments::SieveScript(Default::default()),
+                MethodObject::Calendar => RequestArguments::Calendar(Default::default()),
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::AddressBook => RequestArguments::AddressBook(Default::default()),
+                MethodObject::ContactCard => RequestArguments::ContactCard,
+                MethodObject::TaskList => RequestArguments::TaskList(Default::default()),
+                MethodObject::Task => RequestArguments::Task,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -179,6 +191,17 @@ impl JsonObjectParser for Object<SetValue> {
         while let Some(mut key) = parser.next_dict_key::<SetProperty>()? {
             let value = if !key.is_ref {
                 match &key.property {
+                    property
+                        if matches!(parser.ctx, MethodObject::ContactCard)
+                            && key.patch.is_empty()
+                            && !matches!(property, Property::AddressBookIds) =>
+                    {
+                        // JSContact properties are stored as-is
+                        SetValue::Value(Value::parse::<String, String>(
+                            parser.next_token()?,
+                            parser,
+                        )?)
+                    }
                     Property::Id | Property::ThreadId => parser
                         .next_token::<Id>()?
                         .unwrap_string_or_null("")?
@@ -193,7 +216,10 @@ impl JsonObjectParser for Object<SetValue> {
                     | Property::ReceivedAt
                     | Property::Expires
                     | Property::FromDate
-                    | Property::ToDate => parser
+                    | Property::ToDate
+                    | Property::UtcStart
+                    | Property::UtcEnd
+                    | Property::UtcDue => parser
                         .next_token::<UTCDate>()?
                         .unwrap_string_or_null("")?
                         .map(|date| SetValue::Value(Value::Date(date)))
@@ -217,7 +243,18 @@ impl JsonObjectParser for Object<SetValue> {
                     | Property::Location
                     | Property::Cid
                     | Property::Role
-                    | Property::PartId => parser
+                    | Property::PartId
+                    | Property::Color
+                    | Property::Uid
+                    | Property::Title
+                    | Property::Start
+                    | Property::Duration
+                    | Property::TimeZone
+                    | Property::Status
+                    | Property::FreeBusyStatus
+                    | Property::Due
+                    | Property::EstimatedDuration
+                    | Property::Progress => parser
                         .next_token::<String>()?
                         .unwrap_string_or_null("")?
                         .map(|text| SetValue::Value(Value::Text(text)))
@@ -239,22 +276,32 @@ impl JsonObjectParser for Object<SetValue> {
                     Property::HasAttachment
                     | Property::IsSubscribed
                     | Property::IsEnabled
-                    | Property::IsActive => parser
+                    | Property::IsActive
+                    | Property::IsVisible
+                    | Property::IsDefault
+                    | Property::ShowWithoutTime => parser
                         .next_token::<String>()?
                         .unwrap_bool_or_null("")?
                         .map(|bool| SetValue::Value(Value::Bool(bool)))
                         .unwrap_or(SetValue::Value(Value::Null)),
-                    Property::Size | Property::SortOrder | Property::Quota => parser
+                    Property::Size
+                    | Property::SortOrder
+                    | Property::Quota
+                    | Property::PercentComplete
+                    | Property::Priority => parser
                         .next_token::<String>()?
                         .unwrap_uint_or_null("")?
                         .map(|uint| SetValue::Value(Value::UnsignedInt(uint)))
                         .unwrap_or(SetValue::Value(Value::Null)),
-                    Property::ParentId | Property::EmailId | Property::IdentityId => parser
+                    Property::ParentId
+                    | Property::EmailId
+                    | Property::IdentityId
+                    | Property::TaskListId => parser
                         .next_token::<MaybeReference<Id, String>>()?
                         .unwrap_string_or_null("")?
                         .map(SetValue::from)
                         .unwrap_or(SetValue::Value(Value::Null)),
-                    Property::MailboxIds => {
+                    Property::MailboxIds | Property::CalendarIds | Property::AddressBookIds => {
                         if key.patch.is_empty() {
                             SetValue::from(
                                 <SetValueMap<MaybeReference<Id, String>>>::parse(parser)?.values,
@@ -264,6 +311,21 @@ impl JsonObjectParser for Object<SetValue> {
                             SetValue::Patch(key.patch)
                         }
                     }
+                    Property::Keywords | Property::_T(_)
+                        if matches!(
+                            parser.ctx,
+                            MethodObject::Calendar
+                                | MethodObject::CalendarEvent
+                                | MethodObject::TaskList
+                                | MethodObject::Task
+                        ) =>
+                    {
+                        // JSCalendar properties without a dedicated variant are stored as-is
+                        SetValue::Value(Value::parse::<String, String>(
+                            parser.next_token()?,
+                            parser,
+                        )?)
+                    }
                     Property::Keywords => {
                         if key.patch.is_empty() {
                             SetValue::Value(Value::List(
@@ -302,6 +364,29 @@ impl JsonObjectParser for Object<SetValue> {
                         }
                         _ => unreachable!(),
                     },
+                    Property::ShareWith => match key.patch.len() {
+                        0 => {
+                            parser
+                                .next_token::<String>()?
+                                .assert_jmap(Token::DictStart)?;
+                            let mut share_with = Vec::new();
+                            while let Some(principal_id) = parser.next_dict_key::<Id>()? {
+                                share_with.push(Value::Id(principal_id));
+                                share_with.push(Value::UnsignedInt(parse_share_rights(parser)?));
+                            }
+                            SetValue::Value(Value::List(share_with))
+                        }
+                        1 => {
+                            key.patch
+                                .push(Value::UnsignedInt(parse_share_rights(parser)?));
+                            SetValue::Patch(key.patch)
+                        }
+                        2 => {
+                            key.patch.push(Value::Bool(bool::parse(parser)?));
+                            SetValue::Patch(key.patch)
+                        }
+                        _ => unreachable!(),
+                    },
                     Property::Aliases
                     | Property::Attachments
                     | Property::Bcc
@@ -325,10 +410,9 @@ impl JsonObjectParser for Object<SetValue> {
                         parser.next_token()?,
                         parser,
                     )?),
-                    Property::Parameters => SetValue::Value(Value::parse::<String, String>(
-                        parser.next_token()?,
-                        parser,
-                    )?),
+                    Property::Parameters | Property::RecurrenceRules => SetValue::Value(
+                        Value::parse::<String, String>(parser.next_token()?, parser)?,
+                    ),
                     Property::Members => SetValue::Value(Value::parse::<ObjectProperty, Id>(
                         parser.next_token()?,
                         parser,
@@ -355,6 +439,15 @@ impl JsonObjectParser for Object<SetValue> {
     }
 }
 
+// Calendars and mailboxes define different sets of rights
+fn parse_share_rights(parser: &mut Parser) -> trc::Result<u64> {
+    if let MethodObject::Calendar = &parser.ctx {
+        CalendarRights::parse(parser).map(|rights| rights.0.into())
+    } else {
+        MailboxRights::parse(parser).map(|rights| rights.0.into())
+    }
+}
+
 impl<T: Into<AnyId>> From<MaybeReference<T, String>> for SetValue {
     fn from(reference: MaybeReference<T, String>) -> Self {
         match reference {
@@ -386,6 +479,9 @@ impl RequestPropertyParser for RequestArguments {
             RequestArguments::Mailbox(args) => args.parse(parser, property),
             RequestArguments::EmailSubmission(args) => args.parse(parser, property),
             RequestArguments::SieveScript(args) => args.parse(parser, property),
+            RequestArguments::Calendar(args) => args.parse(parser, property),
+            RequestArguments::AddressBook(args) => args.parse(parser, property),
+            RequestArguments::TaskList(args) => args.parse(parser, property),
             _ => Ok(false),
         }
     }
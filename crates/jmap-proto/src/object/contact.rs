@@ -0,0 +1,30 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{
+    parser::{json::Parser, Ignore},
+    request::{RequestProperty, RequestPropertyParser},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct SetArguments {
+    pub on_destroy_remove_contents: Option<bool>,
+}
+
+impl RequestPropertyParser for SetArguments {
+    fn parse(&mut self, parser: &mut Parser, property: RequestProperty) -> trc::Result<bool> {
+        if property.hash[0] == 0x4365_766f_6d65_5279_6f72_7473_6544_6e6f
+            && property.hash[1] == 0x0073_746e_6574_6e6f
+        {
+            self.on_destroy_remove_contents = parser
+                .next_token::<Ignore>()?
+                .unwrap_bool_or_null("onDestroyRemoveContents")?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
@@ -5,11 +5,14 @@
  */
 
 pub mod blob;
+pub mod calendar;
+pub mod contact;
 pub mod email;
 pub mod email_submission;
 pub mod index;
 pub mod mailbox;
 pub mod sieve;
+pub mod task;
 
 use std::slice::Iter;
 
@@ -71,6 +71,14 @@ pub enum Capability {
     Blob = 1 << 8,
     #[serde(rename(serialize = "urn:ietf:params:jmap:quota"))]
     Quota = 1 << 9,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:tasks"))]
+    Tasks = 1 << 10,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:principals"))]
+    Principals = 1 << 11,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:mdn"))]
+    Mdn = 1 << 12,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:webpush-vapid"))]
+    WebPushVapid = 1 << 13,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -84,6 +92,8 @@ pub enum Capabilities {
     SieveAccount(SieveAccountCapabilities),
     SieveSession(SieveSessionCapabilities),
     Blob(BlobCapabilities),
+    Principals(PrincipalCapabilities),
+    WebPushVapid(WebPushVapidCapabilities),
     Empty(EmptyCapabilities),
 }
 
@@ -175,6 +185,18 @@ pub struct BlobCapabilities {
     pub supported_digest_algorithms: Vec<&'static str>,
 }
 
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct PrincipalCapabilities {
+    #[serde(rename(serialize = "currentUserPrincipalId"))]
+    pub current_user_principal_id: Option<Id>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WebPushVapidCapabilities {
+    #[serde(rename(serialize = "applicationServerKey"))]
+    pub application_server_key: String,
+}
+
 #[derive(Debug, Clone, Default, serde::Serialize)]
 pub struct EmptyCapabilities {}
 
@@ -233,10 +255,15 @@ impl Session {
             }
         }
 
-        self.accounts.set(
-            account_id,
-            Account::new(name, true, false).add_capabilities(capabilities, account_capabilities),
-        );
+        let mut account =
+            Account::new(name, true, false).add_capabilities(capabilities, account_capabilities);
+        if let Some(Capabilities::Principals(principals)) = account
+            .account_capabilities
+            .get_mut(&Capability::Principals)
+        {
+            principals.current_user_principal_id = account_id.into();
+        }
+        self.accounts.set(account_id, account);
     }
 
     pub fn add_account(
@@ -340,6 +367,10 @@ impl JsonObjectParser for Capability {
                 0x0065_7665_6973 => Ok(Capability::Sieve),
                 0x626f_6c62 => Ok(Capability::Blob),
                 0x0061_746f_7571 => Ok(Capability::Quota),
+                0x0073_6b73_6174 => Ok(Capability::Tasks),
+                0x736c_6170_6963_6e69_7270 => Ok(Capability::Principals),
+                0x006e_646d => Ok(Capability::Mdn),
+                0x0064_6970_6176_2d68_7375_7062_6577 => Ok(Capability::WebPushVapid),
                 _ => Err(parser.error_capability()),
             },
             Err(err) if err.is_jmap_method_error() => Err(parser.error_capability()),
@@ -29,6 +29,15 @@ pub enum MethodObject {
     SieveScript,
     Principal,
     Quota,
+    Activity,
+    Calendar,
+    CalendarEvent,
+    AddressBook,
+    ContactCard,
+    TaskList,
+    Task,
+    Mdn,
+    DeletedEmail,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -45,6 +54,9 @@ pub enum MethodFunction {
     Lookup,
     Upload,
     Echo,
+    GetAvailability,
+    Send,
+    Restore,
 }
 
 impl JsonObjectParser for MethodName {
@@ -96,6 +108,15 @@ impl JsonObjectParser for MethodName {
                 0x0074_7069_7263_5365_7665_6953 => MethodObject::SieveScript,
                 0x006c_6170_6963_6e69_7250 => MethodObject::Principal,
                 0x0061_746f_7551 => MethodObject::Quota,
+                0x7974_6976_6974_6341 => MethodObject::Activity,
+                0x7261_646e_656c_6143 => MethodObject::Calendar,
+                0x0074_6e65_7645_7261_646e_656c_6143 => MethodObject::CalendarEvent,
+                0x006b_6f6f_4273_7365_7264_6441 => MethodObject::AddressBook,
+                0x0064_7261_4374_6361_746e_6f43 => MethodObject::ContactCard,
+                0x7473_694c_6b73_6154 => MethodObject::TaskList,
+                0x6b73_6154 => MethodObject::Task,
+                0x004e_444d => MethodObject::Mdn,
+                0x6c69_616d_4564_6574_656c_6544 => MethodObject::DeletedEmail,
                 0x6572_6f43 => MethodObject::Core,
                 _ => return Err(parser.error_value()),
             },
@@ -112,6 +133,9 @@ impl JsonObjectParser for MethodName {
                 0x7075_6b6f_6f6c => MethodFunction::Lookup,
                 0x6461_6f6c_7075 => MethodFunction::Upload,
                 0x6f68_6365 => MethodFunction::Echo,
+                0x646e_6573 => MethodFunction::Send,
+                0x0065_726f_7473_6572 => MethodFunction::Restore,
+                0x0079_7469_6c69_6261_6c69_6176_4174_6567 => MethodFunction::GetAvailability,
                 _ => return Err(parser.error_value()),
             },
         })
@@ -184,12 +208,57 @@ impl MethodName {
             (MethodFunction::Get, MethodObject::Principal) => "Principal/get",
             (MethodFunction::Set, MethodObject::Principal) => "Principal/set",
             (MethodFunction::Query, MethodObject::Principal) => "Principal/query",
+            (MethodFunction::GetAvailability, MethodObject::Principal) => {
+                "Principal/getAvailability"
+            }
 
             (MethodFunction::Get, MethodObject::Quota) => "Quota/get",
             (MethodFunction::Changes, MethodObject::Quota) => "Quota/changes",
             (MethodFunction::Query, MethodObject::Quota) => "Quota/query",
             (MethodFunction::QueryChanges, MethodObject::Quota) => "Quota/queryChanges",
 
+            (MethodFunction::Get, MethodObject::Activity) => "Activity/get",
+            (MethodFunction::Changes, MethodObject::Activity) => "Activity/changes",
+
+            (MethodFunction::Get, MethodObject::Calendar) => "Calendar/get",
+            (MethodFunction::Changes, MethodObject::Calendar) => "Calendar/changes",
+            (MethodFunction::Set, MethodObject::Calendar) => "Calendar/set",
+
+            (MethodFunction::Get, MethodObject::CalendarEvent) => "CalendarEvent/get",
+            (MethodFunction::Changes, MethodObject::CalendarEvent) => "CalendarEvent/changes",
+            (MethodFunction::Query, MethodObject::CalendarEvent) => "CalendarEvent/query",
+            (MethodFunction::QueryChanges, MethodObject::CalendarEvent) => {
+                "CalendarEvent/queryChanges"
+            }
+            (MethodFunction::Set, MethodObject::CalendarEvent) => "CalendarEvent/set",
+
+            (MethodFunction::Get, MethodObject::AddressBook) => "AddressBook/get",
+            (MethodFunction::Changes, MethodObject::AddressBook) => "AddressBook/changes",
+            (MethodFunction::Set, MethodObject::AddressBook) => "AddressBook/set",
+
+            (MethodFunction::Get, MethodObject::ContactCard) => "ContactCard/get",
+            (MethodFunction::Changes, MethodObject::ContactCard) => "ContactCard/changes",
+            (MethodFunction::Query, MethodObject::ContactCard) => "ContactCard/query",
+            (MethodFunction::QueryChanges, MethodObject::ContactCard) => "ContactCard/queryChanges",
+            (MethodFunction::Set, MethodObject::ContactCard) => "ContactCard/set",
+            (MethodFunction::Parse, MethodObject::ContactCard) => "ContactCard/parse",
+
+            (MethodFunction::Get, MethodObject::TaskList) => "TaskList/get",
+            (MethodFunction::Changes, MethodObject::TaskList) => "TaskList/changes",
+            (MethodFunction::Set, MethodObject::TaskList) => "TaskList/set",
+
+            (MethodFunction::Get, MethodObject::Task) => "Task/get",
+            (MethodFunction::Changes, MethodObject::Task) => "Task/changes",
+            (MethodFunction::Query, MethodObject::Task) => "Task/query",
+            (MethodFunction::QueryChanges, MethodObject::Task) => "Task/queryChanges",
+            (MethodFunction::Set, MethodObject::Task) => "Task/set",
+
+            (MethodFunction::Send, MethodObject::Mdn) => "MDN/send",
+            (MethodFunction::Parse, MethodObject::Mdn) => "MDN/parse",
+
+            (MethodFunction::Get, MethodObject::DeletedEmail) => "DeletedEmail/get",
+            (MethodFunction::Restore, MethodObject::DeletedEmail) => "DeletedEmail/restore",
+
             (MethodFunction::Get, MethodObject::Blob) => "Blob/get",
             (MethodFunction::Copy, MethodObject::Blob) => "Blob/copy",
             (MethodFunction::Lookup, MethodObject::Blob) => "Blob/lookup",
@@ -217,6 +286,15 @@ impl Display for MethodObject {
             MethodObject::Thread => "Thread",
             MethodObject::Email => "Email",
             MethodObject::Quota => "Quota",
+            MethodObject::Activity => "Activity",
+            MethodObject::Calendar => "Calendar",
+            MethodObject::CalendarEvent => "CalendarEvent",
+            MethodObject::AddressBook => "AddressBook",
+            MethodObject::ContactCard => "ContactCard",
+            MethodObject::TaskList => "TaskList",
+            MethodObject::Task => "Task",
+            MethodObject::Mdn => "MDN",
+            MethodObject::DeletedEmail => "DeletedEmail",
         })
     }
 }
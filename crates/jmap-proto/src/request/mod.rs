@@ -18,14 +18,17 @@ use std::{
 
 use crate::{
     method::{
+        availability::GetAvailabilityRequest,
         changes::ChangesRequest,
         copy::{self, CopyBlobRequest, CopyRequest},
         get::{self, GetRequest},
         import::ImportEmailRequest,
         lookup::BlobLookupRequest,
-        parse::ParseEmailRequest,
+        mdn::{MdnParseRequest, MdnSendRequest},
+        parse::{ParseContactCardRequest, ParseEmailRequest},
         query::{self, QueryRequest},
         query_changes::QueryChangesRequest,
+        restore::RestoreRequest,
         search_snippet::GetSearchSnippetRequest,
         set::{self, SetRequest},
         upload::BlobUploadRequest,
@@ -66,10 +69,15 @@ pub enum RequestMethod {
     CopyBlob(CopyBlobRequest),
     ImportEmail(ImportEmailRequest),
     ParseEmail(ParseEmailRequest),
+    ParseContactCard(ParseContactCardRequest),
+    SendMdn(MdnSendRequest),
+    ParseMdn(MdnParseRequest),
+    Restore(RestoreRequest),
     QueryChanges(QueryChangesRequest),
     Query(QueryRequest<query::RequestArguments>),
     SearchSnippet(GetSearchSnippetRequest),
     ValidateScript(ValidateSieveScriptRequest),
+    GetAvailability(GetAvailabilityRequest),
     LookupBlob(BlobLookupRequest),
     UploadBlob(BlobUploadRequest),
     Echo(Echo),
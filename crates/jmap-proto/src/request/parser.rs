@@ -8,14 +8,17 @@ use std::collections::HashMap;
 
 use crate::{
     method::{
+        availability::GetAvailabilityRequest,
         changes::ChangesRequest,
         copy::{CopyBlobRequest, CopyRequest},
         get::GetRequest,
         import::ImportEmailRequest,
         lookup::BlobLookupRequest,
-        parse::ParseEmailRequest,
+        mdn::{MdnParseRequest, MdnSendRequest},
+        parse::{ParseContactCardRequest, ParseEmailRequest},
         query::QueryRequest,
         query_changes::QueryChangesRequest,
+        restore::RestoreRequest,
         search_snippet::GetSearchSnippetRequest,
         set::SetRequest,
         upload::BlobUploadRequest,
@@ -132,6 +135,14 @@ impl Request {
                                 | MethodObject::SieveScript
                                 | MethodObject::Principal
                                 | MethodObject::Quota
+                                | MethodObject::Activity
+                                | MethodObject::Calendar
+                                | MethodObject::CalendarEvent
+                                | MethodObject::AddressBook
+                                | MethodObject::ContactCard
+                                | MethodObject::TaskList
+                                | MethodObject::Task
+                                | MethodObject::DeletedEmail
                                 | MethodObject::Blob,
                             ) => GetRequest::parse(parser).map(RequestMethod::Get),
                             (MethodFunction::Get, MethodObject::SearchSnippet) => {
@@ -168,10 +179,27 @@ impl Request {
                             (MethodFunction::Parse, MethodObject::Email) => {
                                 ParseEmailRequest::parse(parser).map(RequestMethod::ParseEmail)
                             }
+                            (MethodFunction::Parse, MethodObject::ContactCard) => {
+                                ParseContactCardRequest::parse(parser)
+                                    .map(RequestMethod::ParseContactCard)
+                            }
+                            (MethodFunction::Send, MethodObject::Mdn) => {
+                                MdnSendRequest::parse(parser).map(RequestMethod::SendMdn)
+                            }
+                            (MethodFunction::Parse, MethodObject::Mdn) => {
+                                MdnParseRequest::parse(parser).map(RequestMethod::ParseMdn)
+                            }
+                            (MethodFunction::Restore, MethodObject::DeletedEmail) => {
+                                RestoreRequest::parse(parser).map(RequestMethod::Restore)
+                            }
                             (MethodFunction::Validate, MethodObject::SieveScript) => {
                                 ValidateSieveScriptRequest::parse(parser)
                                     .map(RequestMethod::ValidateScript)
                             }
+                            (MethodFunction::GetAvailability, MethodObject::Principal) => {
+                                GetAvailabilityRequest::parse(parser)
+                                    .map(RequestMethod::GetAvailability)
+                            }
                             (MethodFunction::Echo, MethodObject::Core) => {
                                 Echo::parse(parser).map(RequestMethod::Echo)
                             }
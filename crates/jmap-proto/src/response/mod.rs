@@ -12,14 +12,17 @@ use std::collections::HashMap;
 use crate::{
     error::method::MethodErrorWrapper,
     method::{
+        availability::GetAvailabilityResponse,
         changes::ChangesResponse,
         copy::{CopyBlobResponse, CopyResponse},
         get::GetResponse,
         import::ImportEmailResponse,
         lookup::BlobLookupResponse,
-        parse::ParseEmailResponse,
+        mdn::{MdnParseResponse, MdnSendResponse},
+        parse::{ParseContactCardResponse, ParseEmailResponse},
         query::QueryResponse,
         query_changes::QueryChangesResponse,
+        restore::RestoreResponse,
         search_snippet::GetSearchSnippetResponse,
         set::SetResponse,
         upload::BlobUploadResponse,
@@ -41,10 +44,15 @@ pub enum ResponseMethod {
     CopyBlob(CopyBlobResponse),
     ImportEmail(ImportEmailResponse),
     ParseEmail(ParseEmailResponse),
+    ParseContactCard(ParseContactCardResponse),
+    SendMdn(MdnSendResponse),
+    ParseMdn(MdnParseResponse),
+    Restore(RestoreResponse),
     QueryChanges(QueryChangesResponse),
     Query(QueryResponse),
     SearchSnippet(GetSearchSnippetResponse),
     ValidateScript(ValidateSieveScriptResponse),
+    GetAvailability(GetAvailabilityResponse),
     LookupBlob(BlobLookupResponse),
     UploadBlob(BlobUploadResponse),
     Echo(Echo),
@@ -154,6 +162,30 @@ impl From<ParseEmailResponse> for ResponseMethod {
     }
 }
 
+impl From<ParseContactCardResponse> for ResponseMethod {
+    fn from(parse_contact_card: ParseContactCardResponse) -> Self {
+        ResponseMethod::ParseContactCard(parse_contact_card)
+    }
+}
+
+impl From<MdnSendResponse> for ResponseMethod {
+    fn from(send_mdn: MdnSendResponse) -> Self {
+        ResponseMethod::SendMdn(send_mdn)
+    }
+}
+
+impl From<MdnParseResponse> for ResponseMethod {
+    fn from(parse_mdn: MdnParseResponse) -> Self {
+        ResponseMethod::ParseMdn(parse_mdn)
+    }
+}
+
+impl From<RestoreResponse> for ResponseMethod {
+    fn from(restore: RestoreResponse) -> Self {
+        ResponseMethod::Restore(restore)
+    }
+}
+
 impl From<QueryChangesResponse> for ResponseMethod {
     fn from(query_changes: QueryChangesResponse) -> Self {
         ResponseMethod::QueryChanges(query_changes)
@@ -178,6 +210,12 @@ impl From<ValidateSieveScriptResponse> for ResponseMethod {
     }
 }
 
+impl From<GetAvailabilityResponse> for ResponseMethod {
+    fn from(get_availability: GetAvailabilityResponse) -> Self {
+        ResponseMethod::GetAvailability(get_availability)
+    }
+}
+
 impl From<BlobUploadResponse> for ResponseMethod {
     fn from(upload_blob: BlobUploadResponse) -> Self {
         ResponseMethod::UploadBlob(upload_blob)
@@ -6,9 +6,17 @@
 
 use std::fmt::{self, Display};
 
-use utils::map::bitmap::BitmapItem;
+use utils::map::bitmap::{Bitmap, BitmapItem};
 
-use crate::parser::{json::Parser, JsonObjectParser};
+use crate::{
+    object::Object,
+    parser::{json::Parser, Ignore, JsonObjectParser, Token},
+};
+
+use super::{
+    property::{IntoProperty, ObjectProperty, Property},
+    value::Value,
+};
 
 #[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Clone, Copy)]
 #[repr(u8)]
@@ -59,6 +67,158 @@ impl JsonObjectParser for Acl {
     }
 }
 
+// Mailbox rights as exchanged in the JMAP shareWith property (RFC 9670),
+// stored as the equivalent IMAP ACLs
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MailboxRights(pub Bitmap<Acl>);
+
+impl MailboxRights {
+    pub fn from_right(right: &Property) -> Option<Self> {
+        let acls: &[Acl] = match right {
+            Property::MayReadItems => &[Acl::Read, Acl::ReadItems],
+            Property::MayAddItems => &[Acl::AddItems],
+            Property::MayRemoveItems => &[Acl::RemoveItems],
+            Property::MaySetSeen | Property::MaySetKeywords => &[Acl::ModifyItems],
+            Property::MayCreateChild => &[Acl::CreateChild],
+            Property::MayRename => &[Acl::Modify],
+            Property::MayDelete => &[Acl::Delete],
+            Property::MaySubmit => &[Acl::Submit],
+            Property::MayShare => &[Acl::Administer],
+            _ => return None,
+        };
+
+        Some(MailboxRights(acls.iter().copied().collect()))
+    }
+
+    pub fn to_object(&self) -> Object<Value> {
+        let acl = &self.0;
+        Object::with_capacity(10)
+            .with_property(Property::MayReadItems, acl.contains(Acl::ReadItems))
+            .with_property(Property::MayAddItems, acl.contains(Acl::AddItems))
+            .with_property(Property::MayRemoveItems, acl.contains(Acl::RemoveItems))
+            .with_property(Property::MaySetSeen, acl.contains(Acl::ModifyItems))
+            .with_property(Property::MaySetKeywords, acl.contains(Acl::ModifyItems))
+            .with_property(Property::MayCreateChild, acl.contains(Acl::CreateChild))
+            .with_property(Property::MayRename, acl.contains(Acl::Modify))
+            .with_property(Property::MayDelete, acl.contains(Acl::Delete))
+            .with_property(Property::MaySubmit, acl.contains(Acl::Submit))
+            .with_property(Property::MayShare, acl.contains(Acl::Administer))
+    }
+}
+
+impl JsonObjectParser for MailboxRights {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut rights = MailboxRights::default();
+        match parser.next_token::<Ignore>()? {
+            Token::DictStart => {
+                while let Some(right) = parser.next_dict_key::<ObjectProperty>()? {
+                    let right = right.into_property();
+                    let acls = MailboxRights::from_right(&right).ok_or_else(|| {
+                        trc::JmapEvent::InvalidArguments
+                            .into_err()
+                            .details(format!("Invalid mailbox right {:?}.", right.to_string()))
+                    })?;
+                    if bool::parse(parser)? {
+                        rights.0.union(&acls.0);
+                    }
+                }
+                Ok(rights)
+            }
+            Token::Null => Ok(rights),
+            token => Err(token.error("", "object or null")),
+        }
+    }
+}
+
+// Calendar rights as exchanged in the JMAP shareWith property, stored as the
+// equivalent ACLs. Reading the free-busy information only requires the Read ACL.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CalendarRights(pub Bitmap<Acl>);
+
+impl CalendarRights {
+    pub fn from_right(right: &Property) -> Option<Self> {
+        let acls: &[Acl] = match right {
+            Property::MayReadItems => &[Acl::Read, Acl::ReadItems],
+            Property::MayDelete => &[Acl::Delete],
+            Property::_T(right) => match right.as_str() {
+                "mayReadFreeBusy" => &[Acl::Read],
+                "mayWriteAll" => &[Acl::AddItems, Acl::ModifyItems, Acl::RemoveItems],
+                "mayWriteOwn" => &[Acl::AddItems],
+                "mayUpdatePrivate" => &[Acl::Modify],
+                "mayRSVP" => &[Acl::Submit],
+                "mayAdmin" => &[Acl::Administer],
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Some(CalendarRights(acls.iter().copied().collect()))
+    }
+
+    pub fn to_object(&self) -> Object<Value> {
+        let acl = &self.0;
+        Object::with_capacity(8)
+            .with_property(
+                Property::_T("mayReadFreeBusy".to_string()),
+                acl.contains(Acl::Read),
+            )
+            .with_property(Property::MayReadItems, acl.contains(Acl::ReadItems))
+            .with_property(
+                Property::_T("mayWriteAll".to_string()),
+                acl.contains(Acl::AddItems)
+                    && acl.contains(Acl::ModifyItems)
+                    && acl.contains(Acl::RemoveItems),
+            )
+            .with_property(
+                Property::_T("mayWriteOwn".to_string()),
+                acl.contains(Acl::AddItems),
+            )
+            .with_property(
+                Property::_T("mayUpdatePrivate".to_string()),
+                acl.contains(Acl::Modify),
+            )
+            .with_property(
+                Property::_T("mayRSVP".to_string()),
+                acl.contains(Acl::Submit),
+            )
+            .with_property(
+                Property::_T("mayAdmin".to_string()),
+                acl.contains(Acl::Administer),
+            )
+            .with_property(Property::MayDelete, acl.contains(Acl::Delete))
+    }
+}
+
+impl JsonObjectParser for CalendarRights {
+    fn parse(parser: &mut Parser<'_>) -> trc::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut rights = CalendarRights::default();
+        match parser.next_token::<Ignore>()? {
+            Token::DictStart => {
+                while let Some(right) = parser.next_dict_key::<ObjectProperty>()? {
+                    let right = right.into_property();
+                    let acls = CalendarRights::from_right(&right).ok_or_else(|| {
+                        trc::JmapEvent::InvalidArguments
+                            .into_err()
+                            .details(format!("Invalid calendar right {:?}.", right.to_string()))
+                    })?;
+                    if bool::parse(parser)? {
+                        rights.0.union(&acls.0);
+                    }
+                }
+                Ok(rights)
+            }
+            Token::Null => Ok(rights),
+            token => Err(token.error("", "object or null")),
+        }
+    }
+}
+
 impl Acl {
     fn as_str(&self) -> &'static str {
         match self {
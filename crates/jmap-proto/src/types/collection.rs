@@ -24,7 +24,14 @@ pub enum Collection {
     SieveScript = 5,
     PushSubscription = 6,
     Principal = 7,
-    None = 8,
+    Calendar = 8,
+    CalendarEvent = 9,
+    AddressBook = 10,
+    ContactCard = 11,
+    TaskList = 12,
+    Task = 13,
+    DeletedEmail = 14,
+    None = 15,
 }
 
 impl From<u8> for Collection {
@@ -38,6 +45,13 @@ impl From<u8> for Collection {
             5 => Collection::SieveScript,
             6 => Collection::PushSubscription,
             7 => Collection::Principal,
+            8 => Collection::Calendar,
+            9 => Collection::CalendarEvent,
+            10 => Collection::AddressBook,
+            11 => Collection::ContactCard,
+            12 => Collection::TaskList,
+            13 => Collection::Task,
+            14 => Collection::DeletedEmail,
             _ => Collection::None,
         }
     }
@@ -54,6 +68,13 @@ impl From<u64> for Collection {
             5 => Collection::SieveScript,
             6 => Collection::PushSubscription,
             7 => Collection::Principal,
+            8 => Collection::Calendar,
+            9 => Collection::CalendarEvent,
+            10 => Collection::AddressBook,
+            11 => Collection::ContactCard,
+            12 => Collection::TaskList,
+            13 => Collection::Task,
+            14 => Collection::DeletedEmail,
             _ => Collection::None,
         }
     }
@@ -83,6 +104,12 @@ impl TryFrom<Collection> for DataType {
             Collection::EmailSubmission => Ok(DataType::EmailSubmission),
             Collection::SieveScript => Ok(DataType::SieveScript),
             Collection::PushSubscription => Ok(DataType::PushSubscription),
+            Collection::Calendar => Ok(DataType::Calendar),
+            Collection::CalendarEvent => Ok(DataType::CalendarEvent),
+            Collection::AddressBook => Ok(DataType::AddressBook),
+            Collection::ContactCard => Ok(DataType::ContactCard),
+            Collection::TaskList => Ok(DataType::TaskList),
+            Collection::Task => Ok(DataType::Task),
             _ => Err(()),
         }
     }
@@ -105,6 +132,13 @@ impl Collection {
             Collection::EmailSubmission => "emailSubmission",
             Collection::SieveScript => "sieveScript",
             Collection::Principal => "principal",
+            Collection::Calendar => "calendar",
+            Collection::CalendarEvent => "calendarEvent",
+            Collection::AddressBook => "addressBook",
+            Collection::ContactCard => "contactCard",
+            Collection::TaskList => "taskList",
+            Collection::Task => "task",
+            Collection::DeletedEmail => "deletedEmail",
             Collection::None => "",
         }
     }
@@ -123,6 +157,13 @@ impl FromStr for Collection {
             "emailSubmission" => Ok(Collection::EmailSubmission),
             "sieveScript" => Ok(Collection::SieveScript),
             "principal" => Ok(Collection::Principal),
+            "calendar" => Ok(Collection::Calendar),
+            "calendarEvent" => Ok(Collection::CalendarEvent),
+            "addressBook" => Ok(Collection::AddressBook),
+            "contactCard" => Ok(Collection::ContactCard),
+            "taskList" => Ok(Collection::TaskList),
+            "task" => Ok(Collection::Task),
+            "deletedEmail" => Ok(Collection::DeletedEmail),
             _ => Err(()),
         }
     }
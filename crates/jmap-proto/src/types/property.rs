@@ -10,9 +10,17 @@ use mail_parser::HeaderName;
 use serde::Serialize;
 use store::write::{DeserializeFrom, SerializeInto};
 
-use crate::parser::{json::Parser, JsonObjectParser};
+use crate::{
+    parser::{json::Parser, JsonObjectParser},
+    request::method::MethodObject,
+};
 
-use super::{acl::Acl, id::Id, keyword::Keyword, value::Value};
+use super::{
+    acl::{Acl, CalendarRights, MailboxRights},
+    id::Id,
+    keyword::Keyword,
+    value::Value,
+};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Property {
@@ -119,6 +127,48 @@ pub enum Property {
     WarnLimit,
     SoftLimit,
     Scope,
+    RetrievedAt,
+    SyncPriority,
+    PreviewOnly,
+    CreatedAt,
+    Activity,
+    CalendarIds,
+    Color,
+    IsVisible,
+    IsDefault,
+    Uid,
+    Title,
+    Start,
+    Duration,
+    TimeZone,
+    ShowWithoutTime,
+    Status,
+    FreeBusyStatus,
+    RecurrenceRules,
+    UtcStart,
+    UtcEnd,
+    BusyStatus,
+    AddressBookIds,
+    Kind,
+    FullName,
+    TaskListId,
+    Due,
+    EstimatedDuration,
+    Progress,
+    PercentComplete,
+    Priority,
+    UtcDue,
+    MayShare,
+    ShareWith,
+    AwaitingReply,
+    Subscriptions,
+    SpamReport,
+    DeletedAt,
+    ExpiresAt,
+    AccountTemplate,
+    ContactsOptOut,
+    UndoSend,
+    IdentitySync,
     Digest(DigestProperty),
     Data(DataProperty),
     _T(String),
@@ -247,7 +297,10 @@ impl JsonObjectParser for SetProperty {
 
         if is_patch {
             match &property {
-                Property::MailboxIds | Property::Members => match Id::parse(parser) {
+                Property::MailboxIds
+                | Property::CalendarIds
+                | Property::AddressBookIds
+                | Property::Members => match Id::parse(parser) {
                     Ok(id) => {
                         patch.push(Value::Id(id));
                     }
@@ -304,6 +357,51 @@ impl JsonObjectParser for SetProperty {
                         }
                     }
                 }
+                Property::ShareWith => {
+                    let mut has_right = false;
+                    let mut principal_id = Vec::with_capacity(16);
+
+                    while let Some(ch) = parser.next_unescaped()? {
+                        if ch != b'/' {
+                            principal_id.push(ch);
+                        } else {
+                            has_right = true;
+                            break;
+                        }
+                    }
+
+                    match Id::from_bytes(&principal_id) {
+                        Some(principal_id) => {
+                            patch.push(Value::Id(principal_id));
+                            if has_right {
+                                let is_calendar = matches!(parser.ctx, MethodObject::Calendar);
+                                match ObjectProperty::parse(parser).map(|right| {
+                                    if is_calendar {
+                                        CalendarRights::from_right(&right.0).map(|rights| rights.0)
+                                    } else {
+                                        MailboxRights::from_right(&right.0).map(|rights| rights.0)
+                                    }
+                                }) {
+                                    Ok(Some(rights)) => {
+                                        patch.push(Value::UnsignedInt(rights.into()));
+                                    }
+                                    Ok(None) => {
+                                        property = parser.invalid_property()?;
+                                    }
+                                    Err(err) if err.is_jmap_method_error() => {
+                                        property = parser.invalid_property()?;
+                                    }
+                                    Err(err) => {
+                                        return Err(err);
+                                    }
+                                }
+                            }
+                        }
+                        None => {
+                            property = parser.invalid_property()?;
+                        }
+                    }
+                }
                 Property::Aliases => match String::parse(parser) {
                     Ok(text) if !text.is_empty() => {
                         patch.push(Value::Text(text));
@@ -335,6 +433,8 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x6c63 => Property::Acl,
             0x7365_7361_696c => Property::Aliases,
             0x7374_6e65_6d68_6361_7474 => Property::Attachments,
+            0x0073_6449_6b6f_6f42_7373_6572_6464 => Property::AddressBookIds,
+            0x796c_7065_5267_6e69_7469_6177 => Property::AwaitingReply,
             _ => return None,
         },
         b'b' => match hash {
@@ -342,6 +442,7 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0064_4962_6f6c => Property::BlobId,
             0x6572_7574_6375_7274_5379_646f => Property::BodyStructure,
             0x0073_6575_6c61_5679_646f => Property::BodyValues,
+            0x0073_7574_6174_5379_7375 => Property::BusyStatus,
             _ => return None,
         },
         b'c' => match hash {
@@ -349,6 +450,9 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x63 => Property::Cc,
             0x7465_7372_6168 => Property::Charset,
             0x6469 => Property::Cid,
+            0x7441_6465_7461_6572 => Property::CreatedAt,
+            0x7364_4972_6164_6e65_6c61 => Property::CalendarIds,
+            0x726f_6c6f => Property::Color,
             _ => return None,
         },
         b'd' => match hash {
@@ -358,6 +462,9 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x6e6f_6974_6973_6f70_7369 => Property::Disposition,
             0x0073_6449_626f_6c42_6e73 => Property::DsnBlobIds,
             0x0061_7461 => Property::Data(DataProperty::Default),
+            0x006e_6f69_7461_7275 => Property::Duration,
+            0x6575 => Property::Due,
+            0x7441_6465_7465_6c65 => Property::DeletedAt,
             _ => return None,
         },
         b'e' => match hash {
@@ -366,11 +473,15 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0073_6449_6c69_616d => Property::EmailIds,
             0x0065_706f_6c65_766e => Property::Envelope,
             0x7365_7269_7078 => Property::Expires,
+            0x6e6f_6974_6172_7544_6465_7461_6d69_7473 => Property::EstimatedDuration,
+            0x7441_7365_7269_7078 => Property::ExpiresAt,
             _ => return None,
         },
         b'f' => match hash {
             0x006d_6f72 => Property::From,
             0x0065_7461_446d_6f72 => Property::FromDate,
+            0x0073_7574_6174_5379_7375_4265_6572 => Property::FreeBusyStatus,
+            0x0065_6d61_4e6c_6c75 => Property::FullName,
             _ => return None,
         },
         b'h' => match hash {
@@ -387,11 +498,14 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0065_7669_7463_4173 => Property::IsActive,
             0x6465_6c62_616e_4573 => Property::IsEnabled,
             0x0064_6562_6972_6373_6275_5373 => Property::IsSubscribed,
+            0x746c_7561_6665_4473 => Property::IsDefault,
+            0x656c_6269_7369_5673 => Property::IsVisible,
             _ => return None,
         },
         b'k' => match hash {
             0x0073_7965 => Property::Keys,
             0x0073_6472_6f77_7965 => Property::Keywords,
+            0x0064_6e69 => Property::Kind,
             _ => return None,
         },
         b'l' => match hash {
@@ -417,6 +531,10 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0064_4974_7261 => Property::PartId,
             0x6572_7574_6369 => Property::Picture,
             0x7765_6976_6572 => Property::Preview,
+            0x796c_6e4f_7765_6976_6572 => Property::PreviewOnly,
+            0x0073_7365_7267_6f72 => Property::Progress,
+            0x6574_656c_706d_6f43_746e_6563_7265 => Property::PercentComplete,
+            0x0079_7469_726f_6972 => Property::Priority,
             _ => return None,
         },
         b'q' => match hash {
@@ -428,6 +546,7 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0073_6563_6e65_7265_6665 => Property::References,
             0x6f54_796c_7065 => Property::ReplyTo,
             0x0065_6c6f => Property::Role,
+            0x7365_6c75_5265_636e_6572_7275_6365 => Property::RecurrenceRules,
             _ => return None,
         },
         b's' => match hash {
@@ -439,6 +558,13 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x7265_6472_4f74_726f => Property::SortOrder,
             0x7463_656a_6275 => Property::Subject,
             0x7374_7261_5062_7573 => Property::SubParts,
+            0x0079_7469_726f_6972_5063_6e79 => Property::SyncPriority,
+            0x656d_6954_7475_6f68_7469_5777_6f68 => Property::ShowWithoutTime,
+            0x7472_6174 => Property::Start,
+            0x0073_7574_6174 => Property::Status,
+            0x6874_6957_6572_6168 => Property::ShareWith,
+            0x736e_6f69_7470_6972_6373_6275 => Property::Subscriptions,
+            0x0074_726f_7065_526d_6170 => Property::SpamReport,
             _ => return None,
         },
         b't' => match hash {
@@ -452,6 +578,9 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0073_6461_6572_6854_6c61_746f => Property::TotalThreads,
             0x0065_7079 => Property::Type,
             0x7365_7079 => Property::Types,
+            0x0065_6e6f_5a65_6d69 => Property::TimeZone,
+            0x656c_7469 => Property::Title,
+            0x0064_4974_7369_4c6b_7361 => Property::TaskListId,
             _ => return None,
         },
         b'u' => match hash {
@@ -459,6 +588,10 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0073_6c69_616d_4564_6165_726e => Property::UnreadEmails,
             0x7364_6165_7268_5464_6165_726e => Property::UnreadThreads,
             0x6c72 => Property::Url,
+            0x6469 => Property::Uid,
+            0x0064_6e45_6374 => Property::UtcEnd,
+            0x0074_7261_7453_6374 => Property::UtcStart,
+            0x0065_7544_6374 => Property::UtcDue,
             _ => return None,
         },
         b'v' => match hash {
@@ -660,6 +793,7 @@ impl JsonObjectParser for ObjectProperty {
                 0x656d_616e_6552_7961 => Property::MayRename,
                 0x6574_656c_6544_7961 => Property::MayDelete,
                 0x7469_6d62_7553_7961 => Property::MaySubmit,
+                0x0065_7261_6853_7961 => Property::MayShare,
                 _ => parser.invalid_property()?,
             },
             b'n' => match hash {
@@ -881,6 +1015,48 @@ impl Display for Property {
             Property::Used => write!(f, "used"),
             Property::HardLimit => write!(f, "hardLimit"),
             Property::Scope => write!(f, "scope"),
+            Property::RetrievedAt => write!(f, "retrievedAt"),
+            Property::SyncPriority => write!(f, "syncPriority"),
+            Property::PreviewOnly => write!(f, "previewOnly"),
+            Property::CreatedAt => write!(f, "createdAt"),
+            Property::Activity => write!(f, "activity"),
+            Property::CalendarIds => write!(f, "calendarIds"),
+            Property::Color => write!(f, "color"),
+            Property::IsVisible => write!(f, "isVisible"),
+            Property::IsDefault => write!(f, "isDefault"),
+            Property::Uid => write!(f, "uid"),
+            Property::Title => write!(f, "title"),
+            Property::Start => write!(f, "start"),
+            Property::Duration => write!(f, "duration"),
+            Property::TimeZone => write!(f, "timeZone"),
+            Property::ShowWithoutTime => write!(f, "showWithoutTime"),
+            Property::Status => write!(f, "status"),
+            Property::FreeBusyStatus => write!(f, "freeBusyStatus"),
+            Property::RecurrenceRules => write!(f, "recurrenceRules"),
+            Property::UtcStart => write!(f, "utcStart"),
+            Property::UtcEnd => write!(f, "utcEnd"),
+            Property::BusyStatus => write!(f, "busyStatus"),
+            Property::AddressBookIds => write!(f, "addressBookIds"),
+            Property::Kind => write!(f, "kind"),
+            Property::FullName => write!(f, "fullName"),
+            Property::TaskListId => write!(f, "taskListId"),
+            Property::Due => write!(f, "due"),
+            Property::EstimatedDuration => write!(f, "estimatedDuration"),
+            Property::Progress => write!(f, "progress"),
+            Property::PercentComplete => write!(f, "percentComplete"),
+            Property::Priority => write!(f, "priority"),
+            Property::UtcDue => write!(f, "utcDue"),
+            Property::MayShare => write!(f, "mayShare"),
+            Property::ShareWith => write!(f, "shareWith"),
+            Property::AwaitingReply => write!(f, "awaitingReply"),
+            Property::Subscriptions => write!(f, "subscriptions"),
+            Property::SpamReport => write!(f, "spamReport"),
+            Property::DeletedAt => write!(f, "deletedAt"),
+            Property::ExpiresAt => write!(f, "expiresAt"),
+            Property::AccountTemplate => write!(f, "accountTemplate"),
+            Property::ContactsOptOut => write!(f, "contactsOptOut"),
+            Property::UndoSend => write!(f, "undoSend"),
+            Property::IdentitySync => write!(f, "identitySync"),
             Property::WarnLimit => write!(f, "warnLimit"),
             Property::SoftLimit => write!(f, "softLimit"),
             Property::_T(s) => write!(f, "{s}"),
@@ -1063,6 +1239,48 @@ impl From<&Property> for u8 {
             Property::WarnLimit => 101,
             Property::SoftLimit => 102,
             Property::Scope => 103,
+            Property::RetrievedAt => 104,
+            Property::SyncPriority => 105,
+            Property::PreviewOnly => 106,
+            Property::CreatedAt => 107,
+            Property::Activity => 108,
+            Property::CalendarIds => 109,
+            Property::Color => 110,
+            Property::IsVisible => 111,
+            Property::IsDefault => 112,
+            Property::Uid => 113,
+            Property::Title => 114,
+            Property::Start => 115,
+            Property::Duration => 116,
+            Property::TimeZone => 117,
+            Property::ShowWithoutTime => 118,
+            Property::Status => 119,
+            Property::FreeBusyStatus => 120,
+            Property::RecurrenceRules => 121,
+            Property::UtcStart => 122,
+            Property::UtcEnd => 123,
+            Property::BusyStatus => 124,
+            Property::AddressBookIds => 125,
+            Property::Kind => 126,
+            Property::FullName => 127,
+            Property::TaskListId => 128,
+            Property::Due => 129,
+            Property::EstimatedDuration => 130,
+            Property::Progress => 131,
+            Property::PercentComplete => 132,
+            Property::Priority => 133,
+            Property::UtcDue => 134,
+            Property::MayShare => 135,
+            Property::ShareWith => 136,
+            Property::AwaitingReply => 137,
+            Property::Subscriptions => 138,
+            Property::SpamReport => 139,
+            Property::DeletedAt => 140,
+            Property::ExpiresAt => 141,
+            Property::AccountTemplate => 143,
+            Property::ContactsOptOut => 144,
+            Property::UndoSend => 145,
+            Property::IdentitySync => 146,
             Property::Digest(_) | Property::Data(_) => unreachable!("invalid property"),
         }
     }
@@ -1205,6 +1423,48 @@ impl SerializeInto for Property {
             Property::WarnLimit => 101,
             Property::SoftLimit => 102,
             Property::Scope => 103,
+            Property::RetrievedAt => 104,
+            Property::SyncPriority => 105,
+            Property::PreviewOnly => 106,
+            Property::CreatedAt => 107,
+            Property::Activity => 108,
+            Property::CalendarIds => 109,
+            Property::Color => 110,
+            Property::IsVisible => 111,
+            Property::IsDefault => 112,
+            Property::Uid => 113,
+            Property::Title => 114,
+            Property::Start => 115,
+            Property::Duration => 116,
+            Property::TimeZone => 117,
+            Property::ShowWithoutTime => 118,
+            Property::Status => 119,
+            Property::FreeBusyStatus => 120,
+            Property::RecurrenceRules => 121,
+            Property::UtcStart => 122,
+            Property::UtcEnd => 123,
+            Property::BusyStatus => 124,
+            Property::AddressBookIds => 125,
+            Property::Kind => 126,
+            Property::FullName => 127,
+            Property::TaskListId => 128,
+            Property::Due => 129,
+            Property::EstimatedDuration => 130,
+            Property::Progress => 131,
+            Property::PercentComplete => 132,
+            Property::Priority => 133,
+            Property::UtcDue => 134,
+            Property::MayShare => 135,
+            Property::ShareWith => 136,
+            Property::AwaitingReply => 137,
+            Property::Subscriptions => 138,
+            Property::SpamReport => 139,
+            Property::DeletedAt => 140,
+            Property::ExpiresAt => 141,
+            Property::AccountTemplate => 143,
+            Property::ContactsOptOut => 144,
+            Property::UndoSend => 145,
+            Property::IdentitySync => 146,
             Property::Digest(_) | Property::Data(_) => {
                 unreachable!("Property::Digest and Property::Data are not serializable")
             }
@@ -1323,6 +1583,48 @@ impl DeserializeFrom for Property {
             101 => Some(Property::WarnLimit),
             102 => Some(Property::SoftLimit),
             103 => Some(Property::Scope),
+            104 => Some(Property::RetrievedAt),
+            105 => Some(Property::SyncPriority),
+            106 => Some(Property::PreviewOnly),
+            107 => Some(Property::CreatedAt),
+            108 => Some(Property::Activity),
+            109 => Some(Property::CalendarIds),
+            110 => Some(Property::Color),
+            111 => Some(Property::IsVisible),
+            112 => Some(Property::IsDefault),
+            113 => Some(Property::Uid),
+            114 => Some(Property::Title),
+            115 => Some(Property::Start),
+            116 => Some(Property::Duration),
+            117 => Some(Property::TimeZone),
+            118 => Some(Property::ShowWithoutTime),
+            119 => Some(Property::Status),
+            120 => Some(Property::FreeBusyStatus),
+            121 => Some(Property::RecurrenceRules),
+            122 => Some(Property::UtcStart),
+            123 => Some(Property::UtcEnd),
+            124 => Some(Property::BusyStatus),
+            125 => Some(Property::AddressBookIds),
+            126 => Some(Property::Kind),
+            127 => Some(Property::FullName),
+            128 => Some(Property::TaskListId),
+            129 => Some(Property::Due),
+            130 => Some(Property::EstimatedDuration),
+            131 => Some(Property::Progress),
+            132 => Some(Property::PercentComplete),
+            133 => Some(Property::Priority),
+            134 => Some(Property::UtcDue),
+            135 => Some(Property::MayShare),
+            136 => Some(Property::ShareWith),
+            137 => Some(Property::AwaitingReply),
+            138 => Some(Property::Subscriptions),
+            139 => Some(Property::SpamReport),
+            140 => Some(Property::DeletedAt),
+            141 => Some(Property::ExpiresAt),
+            143 => Some(Property::AccountTemplate),
+            144 => Some(Property::ContactsOptOut),
+            145 => Some(Property::UndoSend),
+            146 => Some(Property::IdentitySync),
             _ => None,
         }
     }
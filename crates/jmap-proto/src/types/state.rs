@@ -32,6 +32,16 @@ pub enum State {
 pub struct StateChange {
     pub account_id: u32,
     pub types: Vec<(DataType, u64)>,
+    pub priority: SyncPriority,
+    pub preview_only: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SyncPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
 }
 
 impl StateChange {
@@ -39,9 +49,17 @@ impl StateChange {
         Self {
             account_id,
             types: Vec::with_capacity(0),
+            priority: SyncPriority::Normal,
+            preview_only: false,
         }
     }
 
+    pub fn with_sync_hints(mut self, priority: SyncPriority, preview_only: bool) -> Self {
+        self.priority = priority;
+        self.preview_only = preview_only;
+        self
+    }
+
     pub fn with_change(mut self, type_state: DataType, change_id: u64) -> Self {
         if let Some((_, last_change_id)) = self.types.iter_mut().find(|(ts, _)| ts == &type_state) {
             *last_change_id = change_id;
@@ -56,6 +74,25 @@ impl StateChange {
     }
 }
 
+impl SyncPriority {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "low" => Some(SyncPriority::Low),
+            "normal" => Some(SyncPriority::Normal),
+            "high" => Some(SyncPriority::High),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncPriority::Low => "low",
+            SyncPriority::Normal => "normal",
+            SyncPriority::High => "high",
+        }
+    }
+}
+
 impl From<ChangeId> for State {
     fn from(change_id: ChangeId) -> Self {
         State::Exact(change_id)
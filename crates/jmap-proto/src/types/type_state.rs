@@ -41,7 +41,19 @@ pub enum DataType {
     Quota = 11,
     #[serde(rename = "SieveScript")]
     SieveScript = 12,
-    None = 13,
+    #[serde(rename = "Calendar")]
+    Calendar = 13,
+    #[serde(rename = "CalendarEvent")]
+    CalendarEvent = 14,
+    #[serde(rename = "AddressBook")]
+    AddressBook = 15,
+    #[serde(rename = "ContactCard")]
+    ContactCard = 16,
+    #[serde(rename = "TaskList")]
+    TaskList = 17,
+    #[serde(rename = "Task")]
+    Task = 18,
+    None = 19,
 }
 
 impl BitmapItem for DataType {
@@ -70,6 +82,12 @@ impl From<u64> for DataType {
             10 => DataType::Mdn,
             11 => DataType::Quota,
             12 => DataType::SieveScript,
+            13 => DataType::Calendar,
+            14 => DataType::CalendarEvent,
+            15 => DataType::AddressBook,
+            16 => DataType::ContactCard,
+            17 => DataType::TaskList,
+            18 => DataType::Task,
             _ => {
                 debug_assert!(false, "Invalid type_state value: {}", value);
                 DataType::None
@@ -115,6 +133,12 @@ impl JsonObjectParser for DataType {
             0x004e_444d => Ok(DataType::Mdn),
             0x0061_746f_7551 => Ok(DataType::Quota),
             0x0074_7069_7263_5365_7665_6953 => Ok(DataType::SieveScript),
+            0x7261_646e_656c_6143 => Ok(DataType::Calendar),
+            0x0074_6e65_7645_7261_646e_656c_6143 => Ok(DataType::CalendarEvent),
+            0x006b_6f6f_4273_7365_7264_6441 => Ok(DataType::AddressBook),
+            0x0064_7261_4374_6361_746e_6f43 => Ok(DataType::ContactCard),
+            0x7473_694c_6b73_6154 => Ok(DataType::TaskList),
+            0x6b73_6154 => Ok(DataType::Task),
             _ => Err(parser.error_value()),
         }
     }
@@ -150,6 +174,12 @@ impl TryFrom<&str> for DataType {
             0x004e_444d => Ok(DataType::Mdn),
             0x0061_746f_7551 => Ok(DataType::Quota),
             0x0074_7069_7263_5365_7665_6953 => Ok(DataType::SieveScript),
+            0x7261_646e_656c_6143 => Ok(DataType::Calendar),
+            0x0074_6e65_7645_7261_646e_656c_6143 => Ok(DataType::CalendarEvent),
+            0x006b_6f6f_4273_7365_7264_6441 => Ok(DataType::AddressBook),
+            0x0064_7261_4374_6361_746e_6f43 => Ok(DataType::ContactCard),
+            0x7473_694c_6b73_6154 => Ok(DataType::TaskList),
+            0x6b73_6154 => Ok(DataType::Task),
             _ => Err(()),
         }
     }
@@ -171,6 +201,12 @@ impl DataType {
             DataType::Mdn => "MDN",
             DataType::Quota => "Quota",
             DataType::SieveScript => "SieveScript",
+            DataType::Calendar => "Calendar",
+            DataType::CalendarEvent => "CalendarEvent",
+            DataType::AddressBook => "AddressBook",
+            DataType::ContactCard => "ContactCard",
+            DataType::TaskList => "TaskList",
+            DataType::Task => "Task",
             DataType::None => "",
         }
     }
@@ -204,6 +240,12 @@ impl DeserializeFrom for DataType {
             10 => Some(DataType::Mdn),
             11 => Some(DataType::Quota),
             12 => Some(DataType::SieveScript),
+            13 => Some(DataType::Calendar),
+            14 => Some(DataType::CalendarEvent),
+            15 => Some(DataType::AddressBook),
+            16 => Some(DataType::ContactCard),
+            17 => Some(DataType::TaskList),
+            18 => Some(DataType::Task),
             _ => None,
         }
     }
@@ -0,0 +1,92 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    method::changes::{ChangesRequest, ChangesResponse},
+    types::{id::Id, state::State},
+};
+
+use crate::JMAP;
+
+impl JMAP {
+    pub async fn activity_changes(&self, request: ChangesRequest) -> trc::Result<ChangesResponse> {
+        let log = self
+            .get_activity_log(request.account_id.document_id())
+            .await?;
+        let max_changes = if self.core.jmap.changes_max_results > 0
+            && self.core.jmap.changes_max_results < request.max_changes.unwrap_or(0)
+        {
+            self.core.jmap.changes_max_results
+        } else {
+            request.max_changes.unwrap_or(0)
+        };
+
+        // Entries are never updated, only created and destroyed
+        let mut changes = match &request.since_state {
+            State::Initial => log
+                .entries
+                .iter()
+                .map(|entry| (entry.change_id, entry.id, true))
+                .collect::<Vec<_>>(),
+            State::Exact(since_change_id)
+                if *since_change_id <= log.change_id && *since_change_id >= log.min_change_id =>
+            {
+                log.entries
+                    .iter()
+                    .filter(|entry| entry.change_id > *since_change_id)
+                    .map(|entry| (entry.change_id, entry.id, true))
+                    .chain(
+                        log.destroyed
+                            .iter()
+                            .filter(|tombstone| tombstone.change_id > *since_change_id)
+                            .map(|tombstone| (tombstone.change_id, tombstone.id, false)),
+                    )
+                    .collect::<Vec<_>>()
+            }
+            _ => return Err(trc::JmapEvent::CannotCalculateChanges.into_err()),
+        };
+        changes.sort_unstable();
+
+        let mut response = ChangesResponse {
+            account_id: request.account_id,
+            old_state: request.since_state,
+            new_state: log.state(),
+            has_more_changes: false,
+            created: vec![],
+            updated: vec![],
+            destroyed: vec![],
+            updated_properties: None,
+        };
+
+        // Only return the changes of complete log updates
+        if max_changes > 0 && changes.len() > max_changes {
+            let next_change_id = changes[max_changes].0;
+            changes.truncate(max_changes);
+            while changes
+                .last()
+                .is_some_and(|(change_id, _, _)| *change_id == next_change_id)
+            {
+                changes.pop();
+            }
+            if let Some((change_id, _, _)) = changes.last() {
+                response.new_state = State::Exact(*change_id);
+                response.has_more_changes = true;
+            } else {
+                return Err(trc::JmapEvent::CannotCalculateChanges.into_err());
+            }
+        }
+
+        for (_, id, is_created) in changes {
+            if is_created {
+                response.created.push(Id::from(id));
+            } else {
+                response.destroyed.push(Id::from(id));
+            }
+        }
+
+        Ok(response)
+    }
+}
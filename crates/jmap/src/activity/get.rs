@@ -0,0 +1,77 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::Object,
+    types::{date::UTCDate, id::Id, property::Property, value::Value},
+};
+
+use crate::JMAP;
+
+impl JMAP {
+    pub async fn activity_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::Type,
+            Property::EmailId,
+            Property::ThreadId,
+            Property::CreatedAt,
+        ]);
+        let account_id = request.account_id.document_id();
+        let log = self.get_activity_log(account_id).await?;
+
+        // Return the most recent entries first
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            log.entries
+                .iter()
+                .rev()
+                .take(self.core.jmap.get_max_objects)
+                .map(|entry| Id::from(entry.id))
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: log.state().into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            let entry = if let Some(entry) = log.entries.iter().find(|entry| entry.id == id.id()) {
+                entry
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let email_id = Id::from(entry.email_id);
+
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    Property::Type => Value::Text(entry.typ.as_str().to_string()),
+                    Property::EmailId => Value::Id(email_id),
+                    Property::ThreadId => Value::Id(Id::from(email_id.prefix_id())),
+                    Property::CreatedAt => {
+                        Value::Date(UTCDate::from_timestamp(entry.created_at as i64))
+                    }
+                    _ => Value::Null,
+                };
+                result.append(property.clone(), value);
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
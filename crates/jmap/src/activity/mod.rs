@@ -0,0 +1,206 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::collections::VecDeque;
+
+use jmap_proto::types::{collection::Collection, id::Id, property::Property, state::State};
+use store::{
+    roaring::RoaringBitmap,
+    write::{
+        assert::{AssertValue, HashedValue, ToAssertValue},
+        now, BatchBuilder, Bincode, F_VALUE,
+    },
+};
+use trc::AddContext;
+
+use crate::JMAP;
+
+pub mod changes;
+pub mod get;
+
+const MAX_UPDATE_ATTEMPTS: usize = 10;
+
+// Every update to the log increments its change id, which is also the
+// Activity state. Removed entries are remembered as tombstones until
+// there are more of them than the maximum number of entries.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ActivityLog {
+    pub next_id: u64,
+    pub change_id: u64,
+    pub min_change_id: u64,
+    pub entries: VecDeque<ActivityEntry>,
+    pub destroyed: VecDeque<ActivityTombstone>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ActivityEntry {
+    pub id: u64,
+    pub typ: ActivityType,
+    pub email_id: u64,
+    pub created_at: u64,
+    pub change_id: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ActivityTombstone {
+    pub id: u64,
+    pub change_id: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ActivityType {
+    Received,
+    Sent,
+    Flagged,
+}
+
+impl JMAP {
+    pub async fn activity_log(
+        &self,
+        account_id: u32,
+        typ: ActivityType,
+        email_ids: impl IntoIterator<Item = Id>,
+    ) -> trc::Result<()> {
+        let max_entries = self.core.jmap.mail_activity_max_entries;
+        if max_entries == 0 {
+            return Ok(());
+        }
+
+        let email_ids = email_ids.into_iter().collect::<Vec<_>>();
+        if email_ids.is_empty() {
+            return Ok(());
+        }
+
+        // Append entries to the log, discarding the oldest ones
+        self.activity_update(account_id, |log| {
+            let created_at = now();
+            for email_id in &email_ids {
+                log.entries.push_back(ActivityEntry {
+                    id: log.next_id,
+                    typ,
+                    email_id: (*email_id).into(),
+                    created_at,
+                    change_id: log.change_id,
+                });
+                log.next_id += 1;
+            }
+            while log.entries.len() > max_entries {
+                if let Some(entry) = log.entries.pop_front() {
+                    log.destroy(entry.id);
+                }
+            }
+            true
+        })
+        .await
+    }
+
+    pub async fn activity_purge(
+        &self,
+        account_id: u32,
+        document_ids: &RoaringBitmap,
+    ) -> trc::Result<()> {
+        // Entries are removed but the log is kept to preserve the next id
+        self.activity_update(account_id, |log| {
+            let mut destroyed = Vec::new();
+            log.entries.retain(|entry| {
+                if document_ids.contains(Id::from(entry.email_id).document_id()) {
+                    destroyed.push(entry.id);
+                    false
+                } else {
+                    true
+                }
+            });
+            for id in &destroyed {
+                log.destroy(*id);
+            }
+            !destroyed.is_empty()
+        })
+        .await
+    }
+
+    pub async fn get_activity_log(&self, account_id: u32) -> trc::Result<ActivityLog> {
+        self.get_property::<Bincode<ActivityLog>>(
+            account_id,
+            Collection::Principal,
+            0,
+            Property::Activity,
+        )
+        .await
+        .map(|log| log.map(|log| log.inner).unwrap_or_default())
+    }
+
+    // Concurrent updates are detected by asserting the previous value of the log
+    async fn activity_update(
+        &self,
+        account_id: u32,
+        mut update: impl FnMut(&mut ActivityLog) -> bool,
+    ) -> trc::Result<()> {
+        let mut attempts = 0;
+        loop {
+            let (assert_value, mut log) = match self
+                .get_property::<HashedValue<Bincode<ActivityLog>>>(
+                    account_id,
+                    Collection::Principal,
+                    0,
+                    Property::Activity,
+                )
+                .await
+                .caused_by(trc::location!())?
+            {
+                Some(log) => (log.to_assert_value(), log.inner.inner),
+                None => (AssertValue::None, ActivityLog::default()),
+            };
+            log.change_id += 1;
+            if !update(&mut log) {
+                return Ok(());
+            }
+            let max_tombstones = self.core.jmap.mail_activity_max_entries;
+            while log.destroyed.len() > max_tombstones {
+                if let Some(tombstone) = log.destroyed.pop_front() {
+                    log.min_change_id = tombstone.change_id;
+                }
+            }
+
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::Principal)
+                .update_document(0)
+                .assert_value(Property::Activity, assert_value)
+                .value(Property::Activity, Bincode::new(log), F_VALUE);
+            match self.write_batch(batch).await {
+                Ok(_) => return Ok(()),
+                Err(err) if err.is_assertion_failure() && attempts < MAX_UPDATE_ATTEMPTS => {
+                    attempts += 1;
+                }
+                Err(err) => return Err(err.caused_by(trc::location!())),
+            }
+        }
+    }
+}
+
+impl ActivityLog {
+    fn destroy(&mut self, id: u64) {
+        self.destroyed.push_back(ActivityTombstone {
+            id,
+            change_id: self.change_id,
+        });
+    }
+
+    pub fn state(&self) -> State {
+        State::Exact(self.change_id)
+    }
+}
+
+impl ActivityType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityType::Received => "received",
+            ActivityType::Sent => "sent",
+            ActivityType::Flagged => "flagged",
+        }
+    }
+}
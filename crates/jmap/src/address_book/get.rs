@@ -0,0 +1,182 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::{index::ObjectIndexBuilder, Object},
+    types::{acl::Acl, collection::Collection, property::Property, value::Value},
+};
+use store::{roaring::RoaringBitmap, write::BatchBuilder};
+use trc::AddContext;
+
+use crate::{auth::acl::EffectiveAcl, JMAP};
+
+use super::set::SCHEMA;
+
+pub const DEFAULT_ADDRESS_BOOK_ID: u32 = 0;
+
+impl JMAP {
+    pub async fn address_book_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::Name,
+            Property::Description,
+            Property::SortOrder,
+            Property::IsDefault,
+            Property::IsSubscribed,
+            Property::Acl,
+            Property::MyRights,
+        ]);
+        let account_id = request.account_id.document_id();
+        let address_book_ids = if access_token.is_shared(account_id) {
+            self.shared_documents(access_token, account_id, Collection::AddressBook, Acl::Read)
+                .await?
+        } else {
+            self.address_book_get_or_create(account_id).await?
+        };
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            address_book_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self
+                .get_state(account_id, Collection::AddressBook)
+                .await?
+                .into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the address book object
+            let document_id = id.document_id();
+            if !address_book_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let mut address_book = if let Some(address_book) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::AddressBook,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                address_book
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    Property::SortOrder => address_book
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::UnsignedInt(0)),
+                    Property::IsSubscribed => address_book
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::Bool(true)),
+                    Property::IsDefault => address_book
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::Bool(false)),
+                    Property::MyRights => {
+                        let (may_read, may_write, may_share, may_delete) =
+                            if access_token.is_shared(account_id) {
+                                let acl = address_book.effective_acl(access_token);
+                                (
+                                    acl.contains(Acl::ReadItems),
+                                    acl.contains(Acl::AddItems)
+                                        && acl.contains(Acl::ModifyItems)
+                                        && acl.contains(Acl::RemoveItems),
+                                    acl.contains(Acl::Administer),
+                                    acl.contains(Acl::Delete),
+                                )
+                            } else {
+                                (true, true, true, true)
+                            };
+                        Object::with_capacity(4)
+                            .with_property(Property::_T("mayRead".to_string()), may_read)
+                            .with_property(Property::_T("mayWrite".to_string()), may_write)
+                            .with_property(Property::_T("mayShare".to_string()), may_share)
+                            .with_property(Property::_T("mayDelete".to_string()), may_delete)
+                            .into()
+                    }
+                    Property::Acl => {
+                        self.acl_get(
+                            address_book
+                                .properties
+                                .get(&Property::Acl)
+                                .and_then(|v| v.as_acl())
+                                .map(|v| &v[..])
+                                .unwrap_or_else(|| &[]),
+                            access_token,
+                            account_id,
+                        )
+                        .await
+                    }
+                    property => address_book.remove(property),
+                };
+                result.append(property.clone(), value);
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+
+    pub async fn address_book_get_or_create(&self, account_id: u32) -> trc::Result<RoaringBitmap> {
+        let mut address_book_ids = self
+            .get_document_ids(account_id, Collection::AddressBook)
+            .await?
+            .unwrap_or_default();
+        if !address_book_ids.is_empty() {
+            return Ok(address_book_ids);
+        } else if self.core.storage.maintenance.is_read_only(account_id) {
+            // The default address book is created once maintenance mode is over
+            return Ok(address_book_ids);
+        }
+
+        // Create the default address book
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::AddressBook)
+            .create_document_with_id(DEFAULT_ADDRESS_BOOK_ID)
+            .custom(
+                ObjectIndexBuilder::new(SCHEMA).with_changes(
+                    Object::with_capacity(2)
+                        .with_property(Property::Name, "Contacts")
+                        .with_property(Property::IsDefault, true),
+                ),
+            );
+        address_book_ids.insert(DEFAULT_ADDRESS_BOOK_ID);
+
+        self.core
+            .storage
+            .data
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())
+            .map(|_| address_book_ids)
+    }
+}
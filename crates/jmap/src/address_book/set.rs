@@ -0,0 +1,375 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::set::{SetRequest, SetResponse},
+    object::{
+        contact::SetArguments,
+        index::{IndexAs, IndexProperty, ObjectIndexBuilder},
+        Object,
+    },
+    response::references::EvalObjectReferences,
+    types::{
+        collection::Collection,
+        property::Property,
+        state::StateChange,
+        type_state::DataType,
+        value::{MaybePatchValue, SetValue, Value},
+    },
+};
+use store::{
+    query::Filter,
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder},
+};
+
+use crate::{contact_card::set::SCHEMA as CARD_SCHEMA, JMAP};
+
+pub static SCHEMA: &[IndexProperty] = &[
+    IndexProperty::new(Property::Name)
+        .index_as(IndexAs::Text {
+            tokenize: true,
+            index: true,
+        })
+        .max_size(255)
+        .required(),
+    IndexProperty::new(Property::Acl).index_as(IndexAs::Acl),
+];
+
+impl JMAP {
+    pub async fn address_book_set(
+        &self,
+        mut request: SetRequest<SetArguments>,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let on_destroy_remove_contents = request
+            .arguments
+            .on_destroy_remove_contents
+            .unwrap_or(false);
+        let mut address_book_ids = self.address_book_get_or_create(account_id).await?;
+        let mut response = self
+            .prepare_set_response(&request, Collection::AddressBook)
+            .await?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Process creates
+        let mut changes = ChangeLogBuilder::new();
+        for (id, object) in request.unwrap_create() {
+            match self.address_book_set_item(object, None, &response).await? {
+                Ok(builder) => {
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::AddressBook)
+                        .create_document()
+                        .custom(builder);
+                    let document_id = self.write_batch_expect_id(batch).await?;
+                    address_book_ids.insert(document_id);
+                    changes.log_insert(Collection::AddressBook, document_id);
+                    response.created(id, document_id);
+                }
+                Err(err) => {
+                    response.not_created.append(id, err);
+                }
+            }
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain address book
+            let document_id = id.document_id();
+            let address_book = if let Some(address_book) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::AddressBook,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                address_book
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+
+            match self
+                .address_book_set_item(object, Some(address_book), &response)
+                .await?
+            {
+                Ok(builder) => {
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::AddressBook)
+                        .update_document(document_id)
+                        .custom(builder);
+                    if !batch.is_empty() {
+                        match self.core.storage.data.write(batch.build()).await {
+                            Ok(_) => {
+                                changes.log_update(Collection::AddressBook, document_id);
+                            }
+                            Err(err) if err.is_assertion_failure() => {
+                                response.not_updated.append(
+                                    id,
+                                    SetError::forbidden().with_description(
+                                        "Another process modified this address book, please try again.",
+                                    ),
+                                );
+                                continue 'update;
+                            }
+                            Err(err) => {
+                                return Err(err.caused_by(trc::location!()));
+                            }
+                        }
+                    }
+                    response.updated.append(id, None);
+                }
+                Err(err) => {
+                    response.not_updated.append(id, err);
+                }
+            }
+        }
+
+        // Process deletions
+        let mut did_remove_cards = false;
+        for id in will_destroy {
+            let document_id = id.document_id();
+            if !address_book_ids.contains(document_id) {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            }
+
+            match self
+                .address_book_destroy(
+                    account_id,
+                    document_id,
+                    &mut changes,
+                    on_destroy_remove_contents,
+                )
+                .await?
+            {
+                Ok(removed_cards) => {
+                    did_remove_cards |= removed_cards;
+                    response.destroyed.push(id);
+                }
+                Err(err) => {
+                    response.not_destroyed.append(id, err);
+                }
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            let state_change =
+                StateChange::new(account_id).with_change(DataType::AddressBook, changes.change_id);
+            response.state_change = if did_remove_cards {
+                state_change.with_change(DataType::ContactCard, changes.change_id)
+            } else {
+                state_change
+            }
+            .into();
+            response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
+        }
+
+        Ok(response)
+    }
+
+    pub async fn address_book_destroy(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        changes: &mut ChangeLogBuilder,
+        remove_contents: bool,
+    ) -> trc::Result<Result<bool, SetError>> {
+        // Fetch record
+        let address_book = if let Some(address_book) = self
+            .get_property::<HashedValue<Object<Value>>>(
+                account_id,
+                Collection::AddressBook,
+                document_id,
+                Property::Value,
+            )
+            .await?
+        {
+            address_book
+        } else {
+            return Ok(Err(SetError::not_found()));
+        };
+
+        // Verify that the address book is empty
+        let card_ids = self
+            .filter(
+                account_id,
+                Collection::ContactCard,
+                vec![Filter::eq(Property::AddressBookIds, document_id)],
+            )
+            .await?
+            .results;
+        let did_remove_cards = !card_ids.is_empty();
+        if did_remove_cards {
+            if !remove_contents {
+                return Ok(Err(SetError::new(SetErrorType::AddressBookHasContents)
+                    .with_description("Address book is not empty.")));
+            }
+
+            // If the card is in multiple address books, remove it from the current one,
+            // otherwise delete it.
+            for (card_id, card) in self
+                .get_properties::<HashedValue<Object<Value>>, _, _>(
+                    account_id,
+                    Collection::ContactCard,
+                    &card_ids,
+                    Property::Value,
+                )
+                .await?
+            {
+                let mut address_book_ids = card
+                    .inner
+                    .properties
+                    .get(&Property::AddressBookIds)
+                    .and_then(|ids| ids.as_list())
+                    .cloned()
+                    .unwrap_or_default();
+                address_book_ids
+                    .retain(|id| !matches!(id, Value::Id(id) if id.document_id() == document_id));
+
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::ContactCard);
+                if !address_book_ids.is_empty() {
+                    batch.update_document(card_id).custom(
+                        ObjectIndexBuilder::new(CARD_SCHEMA)
+                            .with_current(card)
+                            .with_changes(Object::with_capacity(1).with_property(
+                                Property::AddressBookIds,
+                                Value::List(address_book_ids),
+                            )),
+                    );
+                    changes.log_update(Collection::ContactCard, card_id);
+                } else {
+                    self.contact_card_delete(&mut batch, card_id, card);
+                    changes.log_delete(Collection::ContactCard, card_id);
+                }
+
+                match self.core.storage.data.write(batch.build()).await {
+                    Ok(_) => (),
+                    Err(err) if err.is_assertion_failure() => {
+                        return Ok(Err(SetError::forbidden().with_description(concat!(
+                            "Another process modified a card in this address book ",
+                            "while deleting it, please try again."
+                        ))));
+                    }
+                    Err(err) => {
+                        return Err(err.caused_by(trc::location!()));
+                    }
+                }
+            }
+        }
+
+        // Revoke access from any principals the address book was shared with
+        let revoke = Object::with_capacity(1).with_property(Property::Acl, Value::Acl(vec![]));
+        let address_book = Some(address_book);
+        self.refresh_acls(&revoke, &address_book);
+
+        // Delete address book
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::AddressBook)
+            .delete_document(document_id)
+            .custom(ObjectIndexBuilder::new(SCHEMA).with_current_opt(address_book));
+        match self.core.storage.data.write(batch.build()).await {
+            Ok(_) => {
+                changes.log_delete(Collection::AddressBook, document_id);
+                Ok(Ok(did_remove_cards))
+            }
+            Err(err) if err.is_assertion_failure() => Ok(Err(SetError::forbidden()
+                .with_description(concat!(
+                    "Another process modified this address book ",
+                    "while deleting it, please try again."
+                )))),
+            Err(err) => Err(err.caused_by(trc::location!())),
+        }
+    }
+
+    async fn address_book_set_item(
+        &self,
+        changes_: Object<SetValue>,
+        current: Option<HashedValue<Object<Value>>>,
+        response: &SetResponse,
+    ) -> trc::Result<Result<ObjectIndexBuilder, SetError>> {
+        let mut changes = Object::with_capacity(changes_.properties.len());
+        let is_default = current.as_ref().map_or(false, |current| {
+            matches!(
+                current.inner.properties.get(&Property::IsDefault),
+                Some(Value::Bool(true))
+            )
+        });
+
+        for (property, value) in changes_.properties {
+            let value = match response.eval_object_references(value) {
+                Ok(value) => value,
+                Err(err) => return Ok(Err(err)),
+            };
+            let value = match (&property, value) {
+                (Property::Name, MaybePatchValue::Value(Value::Text(value))) => Value::Text(value),
+                (Property::Description, MaybePatchValue::Value(Value::Text(value)))
+                    if value.len() < 2048 =>
+                {
+                    Value::Text(value)
+                }
+                (Property::SortOrder, MaybePatchValue::Value(Value::UnsignedInt(value))) => {
+                    Value::UnsignedInt(value)
+                }
+                (Property::IsSubscribed, MaybePatchValue::Value(Value::Bool(value))) => {
+                    Value::Bool(value)
+                }
+                (
+                    Property::Description | Property::SortOrder,
+                    MaybePatchValue::Value(Value::Null),
+                ) => Value::Null,
+                (Property::IsDefault, MaybePatchValue::Value(Value::Bool(value)))
+                    if value == is_default =>
+                {
+                    // Read-only, only accepted when unchanged
+                    continue;
+                }
+                (Property::Acl, value) => {
+                    match self.acl_set(&mut changes, current.as_ref(), value).await {
+                        Ok(_) => continue,
+                        Err(err) => {
+                            return Ok(Err(err));
+                        }
+                    }
+                }
+                _ => {
+                    return Ok(Err(SetError::invalid_properties()
+                        .with_property(property)
+                        .with_description("Invalid property or value.".to_string())))
+                }
+            };
+            changes.append(property, value);
+        }
+
+        // Refresh ACLs
+        if changes.properties.contains_key(&Property::Acl) {
+            self.refresh_acls(&changes, &current);
+        }
+
+        Ok(ObjectIndexBuilder::new(SCHEMA)
+            .with_changes(changes)
+            .with_current_opt(current)
+            .validate())
+    }
+}
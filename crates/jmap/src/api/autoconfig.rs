@@ -8,13 +8,19 @@ use std::fmt::Write;
 
 use common::manager::webadmin::Resource;
 use directory::{backend::internal::PrincipalField, QueryBy};
+use hyper::StatusCode;
 use quick_xml::events::Event;
 use quick_xml::Reader;
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
 use utils::url_params::UrlParams;
 
 use crate::{api::http::ToHttpResponse, JMAP};
 
-use super::{HttpRequest, HttpResponse};
+use super::{
+    dav::{encode_path, xml::escape_xml},
+    HttpRequest, HttpResponse, JsonResponse,
+};
 
 impl JMAP {
     pub async fn handle_autoconfig_request(&self, req: &HttpRequest) -> trc::Result<HttpResponse> {
@@ -152,6 +158,55 @@ impl JMAP {
         )
     }
 
+    pub async fn handle_autodiscover_json_request(
+        &self,
+        req: &HttpRequest,
+    ) -> trc::Result<HttpResponse> {
+        // Obtain parameters, the address is either part of the path or a query parameter
+        let params = UrlParams::new(req.uri().query());
+        let emailaddress = req
+            .uri()
+            .path()
+            .split('/')
+            .skip_while(|part| *part != "v1.0")
+            .nth(1)
+            .or_else(|| params.get("Email"))
+            .unwrap_or_default()
+            .to_lowercase();
+        let protocol = params.get("Protocol").unwrap_or_default();
+        let (_, server_name, _) = self.autoconfig_parameters(&emailaddress).await?;
+
+        Ok(match autodiscover_json(protocol, &server_name) {
+            Ok(response) => JsonResponse::new(response).into_http_response(),
+            Err(response) => {
+                JsonResponse::with_status(StatusCode::BAD_REQUEST, response).into_http_response()
+            }
+        })
+    }
+
+    pub async fn handle_mobileconfig_request(
+        &self,
+        req: &HttpRequest,
+    ) -> trc::Result<HttpResponse> {
+        // Obtain parameters
+        let params = UrlParams::new(req.uri().query());
+        let emailaddress = params
+            .get("emailaddress")
+            .unwrap_or_default()
+            .to_lowercase();
+        let (account_name, server_name, domain) = self.autoconfig_parameters(&emailaddress).await?;
+        let services = self.core.storage.config.get_services().await?;
+
+        let mut response = Resource::new(
+            "application/x-apple-aspen-config",
+            build_mobileconfig(&emailaddress, &account_name, &server_name, &services).into_bytes(),
+        )
+        .into_http_response();
+        response.content_disposition =
+            format!("attachment; filename=\"{domain}.mobileconfig\"").into();
+        Ok(response)
+    }
+
     async fn autoconfig_parameters<'x>(
         &self,
         emailaddress: &'x str,
@@ -286,6 +341,194 @@ fn parse_autodiscover_request(bytes: &[u8]) -> Result<String, String> {
     ))
 }
 
+fn autodiscover_json(protocol: &str, server_name: &str) -> Result<Value, Value> {
+    if protocol.eq_ignore_ascii_case("AutodiscoverV1") {
+        Ok(json!({
+            "Protocol": "AutodiscoverV1",
+            "Url": format!("https://{server_name}/autodiscover/autodiscover.xml"),
+        }))
+    } else {
+        Err(json!({
+            "ErrorCode": "InvalidProtocol",
+            "ErrorMessage": format!(
+                "The given protocol value '{protocol}' is invalid. Supported values are 'AutodiscoverV1'"
+            ),
+        }))
+    }
+}
+
+fn build_mobileconfig(
+    emailaddress: &str,
+    account_name: &str,
+    server_name: &str,
+    services: &[(String, u16, bool)],
+) -> String {
+    // Services are sorted by protocol with implicit TLS listeners first
+    let service = |protocol: &str| {
+        services
+            .iter()
+            .find(|(name, port, _)| name == protocol && (protocol != "smtp" || *port != 25))
+            .map(|(_, port, is_tls)| (*port, *is_tls))
+    };
+    let identifier = server_name.rsplit('.').collect::<Vec<_>>().join(".");
+    let address = escape_xml(emailaddress);
+    let account_name_enc = escape_xml(account_name);
+    let server_name = escape_xml(server_name);
+
+    let mut config = String::with_capacity(2048);
+    config.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    config.push_str("<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n");
+    config.push_str("<plist version=\"1.0\">\n<dict>\n");
+    config.push_str("\t<key>PayloadContent</key>\n\t<array>\n");
+
+    // Mail account, IMAP is preferred over POP3
+    let incoming = service("imap")
+        .map(|service| ("EmailTypeIMAP", service))
+        .or_else(|| service("pop3").map(|service| ("EmailTypePOP", service)));
+    if let (Some((account_type, (in_port, in_tls))), Some((out_port, out_tls))) =
+        (incoming, service("smtp"))
+    {
+        config.push_str("\t\t<dict>\n");
+        for (key, value) in [
+            ("EmailAccountDescription", address.as_str()),
+            ("EmailAccountName", address.as_str()),
+            ("EmailAccountType", account_type),
+            ("EmailAddress", address.as_str()),
+            ("IncomingMailServerAuthentication", "EmailAuthPassword"),
+            ("IncomingMailServerHostName", server_name.as_str()),
+        ] {
+            plist_entry(&mut config, key, value);
+        }
+        let _ = writeln!(
+            &mut config,
+            "\t\t\t<key>IncomingMailServerPortNumber</key>\n\t\t\t<integer>{in_port}</integer>"
+        );
+        plist_bool(&mut config, "IncomingMailServerUseSSL", in_tls);
+        for (key, value) in [
+            ("IncomingMailServerUsername", account_name_enc.as_str()),
+            ("OutgoingMailServerAuthentication", "EmailAuthPassword"),
+            ("OutgoingMailServerHostName", server_name.as_str()),
+        ] {
+            plist_entry(&mut config, key, value);
+        }
+        let _ = writeln!(
+            &mut config,
+            "\t\t\t<key>OutgoingMailServerPortNumber</key>\n\t\t\t<integer>{out_port}</integer>"
+        );
+        plist_bool(&mut config, "OutgoingMailServerUseSSL", out_tls);
+        plist_entry(&mut config, "OutgoingMailServerUsername", &account_name_enc);
+        plist_bool(&mut config, "OutgoingPasswordSameAsIncomingPassword", true);
+        plist_payload(
+            &mut config,
+            "com.apple.mail.managed",
+            &format!("{identifier}.email"),
+            emailaddress,
+        );
+        config.push_str("\t\t</dict>\n");
+    }
+
+    // Calendar and contacts accounts
+    if let Some((port, is_tls)) = service("http") {
+        let principal = escape_xml(&format!("/dav/principals/{}/", encode_path(account_name)));
+        for (prefix, payload_type, name) in [
+            ("CalDAV", "com.apple.caldav.account", "caldav"),
+            ("CardDAV", "com.apple.carddav.account", "carddav"),
+        ] {
+            config.push_str("\t\t<dict>\n");
+            plist_entry(
+                &mut config,
+                &format!("{prefix}AccountDescription"),
+                &address,
+            );
+            plist_entry(&mut config, &format!("{prefix}HostName"), &server_name);
+            let _ = writeln!(
+                &mut config,
+                "\t\t\t<key>{prefix}Port</key>\n\t\t\t<integer>{port}</integer>"
+            );
+            plist_entry(&mut config, &format!("{prefix}PrincipalURL"), &principal);
+            plist_bool(&mut config, &format!("{prefix}UseSSL"), is_tls);
+            plist_entry(&mut config, &format!("{prefix}Username"), &account_name_enc);
+            plist_payload(
+                &mut config,
+                payload_type,
+                &format!("{identifier}.{name}"),
+                emailaddress,
+            );
+            config.push_str("\t\t</dict>\n");
+        }
+    }
+
+    config.push_str("\t</array>\n");
+    let _ = writeln!(
+        &mut config,
+        "\t<key>PayloadDisplayName</key>\n\t<string>{address}</string>"
+    );
+    let _ = writeln!(
+        &mut config,
+        "\t<key>PayloadIdentifier</key>\n\t<string>{identifier}</string>"
+    );
+    config.push_str("\t<key>PayloadRemovalDisallowed</key>\n\t<false/>\n");
+    config.push_str("\t<key>PayloadType</key>\n\t<string>Configuration</string>\n");
+    let _ = writeln!(
+        &mut config,
+        "\t<key>PayloadUUID</key>\n\t<string>{}</string>",
+        payload_uuid(emailaddress, "Configuration")
+    );
+    config.push_str("\t<key>PayloadVersion</key>\n\t<integer>1</integer>\n");
+    config.push_str("</dict>\n</plist>\n");
+    config
+}
+
+fn plist_entry(config: &mut String, key: &str, value: &str) {
+    let _ = writeln!(
+        config,
+        "\t\t\t<key>{key}</key>\n\t\t\t<string>{value}</string>"
+    );
+}
+
+fn plist_bool(config: &mut String, key: &str, value: bool) {
+    let _ = writeln!(
+        config,
+        "\t\t\t<key>{key}</key>\n\t\t\t<{}/>",
+        if value { "true" } else { "false" }
+    );
+}
+
+fn plist_payload(config: &mut String, payload_type: &str, identifier: &str, emailaddress: &str) {
+    plist_entry(config, "PayloadIdentifier", &escape_xml(identifier));
+    plist_entry(config, "PayloadType", payload_type);
+    plist_entry(
+        config,
+        "PayloadUUID",
+        &payload_uuid(emailaddress, payload_type),
+    );
+    let _ = writeln!(
+        config,
+        "\t\t\t<key>PayloadVersion</key>\n\t\t\t<integer>1</integer>"
+    );
+}
+
+// Profiles are regenerated on every request, payload identifiers are derived from the
+// address so that installing a profile again replaces the existing one
+fn payload_uuid(emailaddress: &str, payload_type: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(emailaddress.as_bytes());
+    hasher.update(payload_type.as_bytes());
+    let hash = hasher.finalize();
+    let hex = hash[..16]
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<String>();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -304,4 +547,54 @@ mod tests {
             "email@example.com"
         );
     }
+
+    #[test]
+    fn build_mobileconfig() {
+        let services = [
+            ("http".to_string(), 443, true),
+            ("imap".to_string(), 993, true),
+            ("imap".to_string(), 143, false),
+            ("smtp".to_string(), 25, false),
+            ("smtp".to_string(), 587, false),
+        ];
+        let config =
+            super::build_mobileconfig("jane@example.com", "jane", "mail.example.com", &services);
+
+        for expected in [
+            "<string>EmailTypeIMAP</string>",
+            "<key>IncomingMailServerPortNumber</key>\n\t\t\t<integer>993</integer>",
+            "<key>IncomingMailServerUseSSL</key>\n\t\t\t<true/>",
+            "<key>OutgoingMailServerPortNumber</key>\n\t\t\t<integer>587</integer>",
+            "<key>OutgoingMailServerUseSSL</key>\n\t\t\t<false/>",
+            "<string>com.apple.caldav.account</string>",
+            "<string>com.apple.carddav.account</string>",
+            "<string>/dav/principals/jane/</string>",
+            "<string>com.example.mail</string>",
+        ] {
+            assert!(
+                config.contains(expected),
+                "missing {expected:?} in {config}"
+            );
+        }
+
+        // Identifiers are stable across requests
+        assert_eq!(
+            config,
+            super::build_mobileconfig("jane@example.com", "jane", "mail.example.com", &services)
+        );
+        assert_eq!(
+            super::payload_uuid("jane@example.com", "Configuration").len(),
+            36
+        );
+
+        // Only AutodiscoverV1 is supported by the JSON endpoint
+        assert_eq!(
+            super::autodiscover_json("autodiscoverv1", "mail.example.com").unwrap()["Url"],
+            "https://mail.example.com/autodiscover/autodiscover.xml"
+        );
+        assert_eq!(
+            super::autodiscover_json("ActiveSync", "mail.example.com").unwrap_err()["ErrorCode"],
+            "InvalidProtocol"
+        );
+    }
 }
@@ -0,0 +1,436 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use common::auth::AccessToken;
+use directory::Permission;
+use hyper::{header, Method, StatusCode};
+use jmap_proto::{
+    object::Object,
+    types::{collection::Collection, id::Id, property::Property, value::Value},
+};
+use store::{query::Filter, roaring::RoaringBitmap, write::assert::HashedValue};
+use trc::AddContext;
+
+use crate::JMAP;
+
+use super::{
+    http::{fetch_body, HttpSessionData, ToHttpResponse},
+    HttpRequest, HttpResponse,
+};
+
+pub mod propfind;
+pub mod report;
+pub mod resource;
+pub mod schedule;
+pub mod xml;
+
+pub const NS_DAV: &str = "DAV:";
+pub const NS_CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+pub const NS_CALSERVER: &str = "http://calendarserver.org/ns/";
+pub const NS_APPLE: &str = "http://apple.com/ns/ical/";
+pub const NS_CARDDAV: &str = "urn:ietf:params:xml:ns:carddav";
+
+const SYNC_TOKEN_PREFIX: &str = "http://stalw.art/ns/sync/";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DavResource {
+    Root,
+    Principal,
+    CalendarHome,
+    Calendar(u32),
+    Inbox,
+    Outbox,
+    Event { calendar_id: u32, uid: String },
+    AddressBookHome,
+    AddressBook(u32),
+    Card { address_book_id: u32, uid: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DavCollection {
+    Calendar,
+    AddressBook,
+}
+
+pub struct DavContext {
+    pub account_id: u32,
+    pub access_token: Arc<AccessToken>,
+}
+
+impl JMAP {
+    pub async fn handle_dav_request(
+        &self,
+        req: &mut HttpRequest,
+        session: &HttpSessionData,
+    ) -> trc::Result<HttpResponse> {
+        if req.method() == Method::OPTIONS {
+            return Ok(HttpResponse::new_empty(StatusCode::OK)
+                .with_header(
+                    header::HeaderName::from_static("dav"),
+                    "1, 3, calendar-access, calendar-schedule, addressbook",
+                )
+                .with_header(
+                    header::ALLOW,
+                    "OPTIONS, GET, HEAD, PUT, DELETE, POST, PROPFIND, REPORT",
+                ));
+        }
+
+        // DAV clients only send credentials after being challenged
+        let (_in_flight, access_token) = match self.authenticate_headers(req, session).await {
+            Ok(result) => result,
+            Err(err) if err.matches(trc::EventType::Auth(trc::AuthEvent::Failed)) => {
+                trc::error!(err.span_id(session.session_id));
+                return Ok(HttpResponse::new_empty(StatusCode::UNAUTHORIZED)
+                    .with_header(header::WWW_AUTHENTICATE, "Basic realm=\"Stalwart Server\""));
+            }
+            Err(err) => return Err(err),
+        };
+        let ctx = DavContext {
+            account_id: access_token.primary_id(),
+            access_token,
+        };
+
+        // Resolve the requested resource
+        let resource = match ctx.parse_path(req.uri().path()) {
+            Ok(resource) => resource,
+            Err(status) => return Ok(status.into_http_response()),
+        };
+        match resource.collection() {
+            Some(DavCollection::Calendar) => {
+                ctx.access_token
+                    .assert_has_permission(Permission::CaldavAuthenticate)?;
+            }
+            Some(DavCollection::AddressBook) => {
+                ctx.access_token
+                    .assert_has_permission(Permission::CarddavAuthenticate)?;
+            }
+            None => {
+                if !ctx
+                    .access_token
+                    .has_permission(Permission::CaldavAuthenticate)
+                {
+                    ctx.access_token
+                        .assert_has_permission(Permission::CarddavAuthenticate)?;
+                }
+            }
+        }
+
+        // Writes are rejected while the account is in maintenance mode
+        if matches!(
+            req.method().as_str(),
+            "PUT" | "DELETE" | "MKCOL" | "PROPPATCH" | "MOVE" | "COPY"
+        ) {
+            self.core
+                .storage
+                .maintenance
+                .assert_writable(ctx.account_id)?;
+        }
+
+        if let Some((collection, container_id)) = resource.container() {
+            if !self
+                .dav_containers(ctx.account_id, collection)
+                .await?
+                .contains(container_id)
+            {
+                return Ok(StatusCode::NOT_FOUND.into_http_response());
+            }
+        }
+
+        let body = fetch_body(req, self.core.jmap.upload_max_size, session.session_id)
+            .await
+            .ok_or_else(|| trc::LimitEvent::SizeRequest.into_err())?;
+        match req.method().as_str() {
+            "PROPFIND" => self.handle_dav_propfind(req, &ctx, resource, &body).await,
+            "REPORT" => self.handle_dav_report(&ctx, resource, &body).await,
+            "GET" | "HEAD" => {
+                self.handle_dav_get(&ctx, resource, req.method() == Method::HEAD)
+                    .await
+            }
+            "PUT" => self.handle_dav_put(req, &ctx, resource, &body).await,
+            "DELETE" => self.handle_dav_delete(req, &ctx, resource).await,
+            "POST" if resource == DavResource::Outbox => {
+                self.handle_dav_schedule(&ctx, &body).await
+            }
+            _ => Ok(StatusCode::METHOD_NOT_ALLOWED.into_http_response()),
+        }
+    }
+
+    pub(crate) async fn dav_containers(
+        &self,
+        account_id: u32,
+        collection: DavCollection,
+    ) -> trc::Result<RoaringBitmap> {
+        match collection {
+            DavCollection::Calendar => self.calendar_get_or_create(account_id).await,
+            DavCollection::AddressBook => self.address_book_get_or_create(account_id).await,
+        }
+        .caused_by(trc::location!())
+    }
+
+    // Changes to any item of the account invalidate the sync token of all its collections
+    pub(crate) async fn dav_sync_token(
+        &self,
+        account_id: u32,
+        collection: DavCollection,
+    ) -> trc::Result<String> {
+        self.core
+            .storage
+            .data
+            .get_last_change_id(account_id, collection.item_collection())
+            .await
+            .caused_by(trc::location!())
+            .map(|change_id| format!("{SYNC_TOKEN_PREFIX}{}", change_id.unwrap_or_default()))
+    }
+
+    pub(crate) async fn dav_items(
+        &self,
+        account_id: u32,
+        collection: DavCollection,
+        container_id: u32,
+        mut filters: Vec<Filter>,
+    ) -> trc::Result<Vec<(u32, HashedValue<Object<Value>>)>> {
+        filters.push(Filter::eq(collection.container_property(), container_id));
+        let item_ids = self
+            .filter(account_id, collection.item_collection(), filters)
+            .await?
+            .results;
+        self.get_properties::<HashedValue<Object<Value>>, _, _>(
+            account_id,
+            collection.item_collection(),
+            &item_ids,
+            Property::Value,
+        )
+        .await
+    }
+
+    pub(crate) async fn dav_item_by_uid(
+        &self,
+        account_id: u32,
+        collection: DavCollection,
+        container_id: u32,
+        uid: &str,
+    ) -> trc::Result<Option<(u32, HashedValue<Object<Value>>)>> {
+        let document_id = if let Some(document_id) = self
+            .filter(
+                account_id,
+                collection.item_collection(),
+                vec![
+                    Filter::eq(collection.container_property(), container_id),
+                    Filter::eq(Property::Uid, uid),
+                ],
+            )
+            .await?
+            .results
+            .min()
+        {
+            document_id
+        } else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .get_property::<HashedValue<Object<Value>>>(
+                account_id,
+                collection.item_collection(),
+                document_id,
+                Property::Value,
+            )
+            .await?
+            .map(|item| (document_id, item)))
+    }
+}
+
+impl DavContext {
+    pub fn parse_path(&self, path: &str) -> Result<DavResource, StatusCode> {
+        let mut path = path
+            .strip_prefix("/dav")
+            .unwrap_or_default()
+            .split('/')
+            .filter(|part| !part.is_empty());
+        let (kind, name) = match (path.next(), path.next()) {
+            (None, _) => return Ok(DavResource::Root),
+            (Some(kind), Some(name)) => (kind, decode_path(name).ok_or(StatusCode::NOT_FOUND)?),
+            (Some(_), None) => return Err(StatusCode::NOT_FOUND),
+        };
+
+        // Only the collections of the authenticated account are available
+        if !name.eq_ignore_ascii_case(&self.access_token.name) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+
+        match (kind, path.next(), path.next(), path.next()) {
+            ("principals", None, _, _) => Ok(DavResource::Principal),
+            ("calendars", None, _, _) => Ok(DavResource::CalendarHome),
+            ("calendars", Some("inbox"), None, _) => Ok(DavResource::Inbox),
+            ("calendars", Some("outbox"), None, _) => Ok(DavResource::Outbox),
+            ("calendars", Some(calendar_id), resource, None) => {
+                let calendar_id = Id::from_bytes(calendar_id.as_bytes())
+                    .ok_or(StatusCode::NOT_FOUND)?
+                    .document_id();
+                match resource {
+                    Some(resource) => Ok(DavResource::Event {
+                        calendar_id,
+                        uid: resource
+                            .strip_suffix(".ics")
+                            .and_then(decode_path)
+                            .ok_or(StatusCode::NOT_FOUND)?,
+                    }),
+                    None => Ok(DavResource::Calendar(calendar_id)),
+                }
+            }
+            ("addressbooks", None, _, _) => Ok(DavResource::AddressBookHome),
+            ("addressbooks", Some(address_book_id), resource, None) => {
+                let address_book_id = Id::from_bytes(address_book_id.as_bytes())
+                    .ok_or(StatusCode::NOT_FOUND)?
+                    .document_id();
+                match resource {
+                    Some(resource) => Ok(DavResource::Card {
+                        address_book_id,
+                        uid: resource
+                            .strip_suffix(".vcf")
+                            .and_then(decode_path)
+                            .ok_or(StatusCode::NOT_FOUND)?,
+                    }),
+                    None => Ok(DavResource::AddressBook(address_book_id)),
+                }
+            }
+            _ => Err(StatusCode::NOT_FOUND),
+        }
+    }
+
+    pub fn href(&self, resource: &DavResource) -> String {
+        let name = encode_path(&self.access_token.name);
+        match resource {
+            DavResource::Root => "/dav/".to_string(),
+            DavResource::Principal => format!("/dav/principals/{name}/"),
+            DavResource::CalendarHome => format!("/dav/calendars/{name}/"),
+            DavResource::Calendar(calendar_id) => {
+                format!("/dav/calendars/{name}/{}/", Id::from(*calendar_id))
+            }
+            DavResource::Inbox => format!("/dav/calendars/{name}/inbox/"),
+            DavResource::Outbox => format!("/dav/calendars/{name}/outbox/"),
+            DavResource::Event { calendar_id, uid } => format!(
+                "/dav/calendars/{name}/{}/{}.ics",
+                Id::from(*calendar_id),
+                encode_path(uid)
+            ),
+            DavResource::AddressBookHome => format!("/dav/addressbooks/{name}/"),
+            DavResource::AddressBook(address_book_id) => {
+                format!("/dav/addressbooks/{name}/{}/", Id::from(*address_book_id))
+            }
+            DavResource::Card {
+                address_book_id,
+                uid,
+            } => format!(
+                "/dav/addressbooks/{name}/{}/{}.vcf",
+                Id::from(*address_book_id),
+                encode_path(uid)
+            ),
+        }
+    }
+}
+
+impl DavResource {
+    pub fn collection(&self) -> Option<DavCollection> {
+        match self {
+            DavResource::CalendarHome
+            | DavResource::Calendar(_)
+            | DavResource::Inbox
+            | DavResource::Outbox
+            | DavResource::Event { .. } => Some(DavCollection::Calendar),
+            DavResource::AddressBookHome
+            | DavResource::AddressBook(_)
+            | DavResource::Card { .. } => Some(DavCollection::AddressBook),
+            DavResource::Root | DavResource::Principal => None,
+        }
+    }
+
+    // Calendar or address book containing the resource
+    pub fn container(&self) -> Option<(DavCollection, u32)> {
+        match self {
+            DavResource::Calendar(calendar_id) | DavResource::Event { calendar_id, .. } => {
+                Some((DavCollection::Calendar, *calendar_id))
+            }
+            DavResource::AddressBook(address_book_id)
+            | DavResource::Card {
+                address_book_id, ..
+            } => Some((DavCollection::AddressBook, *address_book_id)),
+            _ => None,
+        }
+    }
+
+    pub fn item(collection: DavCollection, container_id: u32, uid: String) -> Self {
+        match collection {
+            DavCollection::Calendar => DavResource::Event {
+                calendar_id: container_id,
+                uid,
+            },
+            DavCollection::AddressBook => DavResource::Card {
+                address_book_id: container_id,
+                uid,
+            },
+        }
+    }
+}
+
+impl DavCollection {
+    pub fn item_collection(&self) -> Collection {
+        match self {
+            DavCollection::Calendar => Collection::CalendarEvent,
+            DavCollection::AddressBook => Collection::ContactCard,
+        }
+    }
+
+    pub fn container_collection(&self) -> Collection {
+        match self {
+            DavCollection::Calendar => Collection::Calendar,
+            DavCollection::AddressBook => Collection::AddressBook,
+        }
+    }
+
+    pub fn container_property(&self) -> Property {
+        match self {
+            DavCollection::Calendar => Property::CalendarIds,
+            DavCollection::AddressBook => Property::AddressBookIds,
+        }
+    }
+}
+
+pub fn etag(item: &HashedValue<Object<Value>>) -> String {
+    format!("\"{:x}\"", item.hash)
+}
+
+pub fn parse_sync_token(token: &str) -> Option<u64> {
+    token.trim().strip_prefix(SYNC_TOKEN_PREFIX)?.parse().ok()
+}
+
+pub(crate) fn encode_path(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for &byte in value.as_bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'@') {
+            result.push(byte as char);
+        } else {
+            result.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    result
+}
+
+fn decode_path(value: &str) -> Option<String> {
+    let mut result = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            result.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            result.push(byte);
+        }
+    }
+    String::from_utf8(result).ok()
+}
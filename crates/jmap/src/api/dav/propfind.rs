@@ -0,0 +1,465 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::{backend::internal::PrincipalField, QueryBy};
+use hyper::StatusCode;
+use jmap_proto::{
+    object::Object,
+    types::{property::Property, value::Value},
+};
+use store::write::assert::HashedValue;
+use trc::AddContext;
+
+use crate::{
+    api::{http::ToHttpResponse, HttpRequest, HttpResponse},
+    calendar_event::icalendar::build_icalendar,
+    contact_card::vcard::build_vcard,
+    JMAP,
+};
+
+use super::{
+    etag,
+    xml::{escape_xml, DavProperty, MultiStatus, XmlElement},
+    DavCollection, DavContext, DavResource, NS_APPLE, NS_CALDAV, NS_CALSERVER, NS_CARDDAV, NS_DAV,
+};
+
+pub enum DavItem<'x> {
+    Root,
+    Principal {
+        emails: &'x [String],
+    },
+    CalendarHome,
+    Calendar {
+        calendar: &'x Object<Value>,
+        sync_token: &'x str,
+    },
+    Inbox,
+    Outbox,
+    Event {
+        event: &'x HashedValue<Object<Value>>,
+    },
+    AddressBookHome,
+    AddressBook {
+        address_book: &'x Object<Value>,
+        sync_token: &'x str,
+    },
+    Card {
+        card: &'x HashedValue<Object<Value>>,
+    },
+}
+
+impl JMAP {
+    pub async fn handle_dav_propfind(
+        &self,
+        req: &HttpRequest,
+        ctx: &DavContext,
+        resource: DavResource,
+        body: &[u8],
+    ) -> trc::Result<HttpResponse> {
+        // Depth infinity is not supported, it is handled as depth 1
+        let depth = req
+            .headers()
+            .get("Depth")
+            .and_then(|depth| depth.to_str().ok())
+            .map_or(1, |depth| if depth.trim() == "0" { 0 } else { 1 });
+        let properties = if !body.is_empty() {
+            match XmlElement::parse(body).filter(|request| request.is(NS_DAV, "propfind")) {
+                Some(request) => request.properties(),
+                None => return Ok(StatusCode::BAD_REQUEST.into_http_response()),
+            }
+        } else {
+            None
+        };
+        let properties = properties.as_deref();
+        let mut response = MultiStatus::new();
+
+        match &resource {
+            DavResource::Principal => {
+                let emails = self
+                    .core
+                    .storage
+                    .directory
+                    .query(QueryBy::Id(ctx.account_id), false)
+                    .await
+                    .caused_by(trc::location!())?
+                    .and_then(|mut principal| principal.take_str_array(PrincipalField::Emails))
+                    .unwrap_or_default();
+                ctx.add_response(
+                    &mut response,
+                    &resource,
+                    &DavItem::Principal { emails: &emails },
+                    properties,
+                );
+            }
+            DavResource::CalendarHome | DavResource::AddressBookHome => {
+                let (collection, item) = if resource == DavResource::CalendarHome {
+                    (DavCollection::Calendar, DavItem::CalendarHome)
+                } else {
+                    (DavCollection::AddressBook, DavItem::AddressBookHome)
+                };
+                ctx.add_response(&mut response, &resource, &item, properties);
+                if depth > 0 {
+                    let sync_token = self.dav_sync_token(ctx.account_id, collection).await?;
+                    for (container_id, container) in self
+                        .get_properties::<Object<Value>, _, _>(
+                            ctx.account_id,
+                            collection.container_collection(),
+                            &self.dav_containers(ctx.account_id, collection).await?,
+                            Property::Value,
+                        )
+                        .await?
+                    {
+                        let (resource, item) =
+                            container_item(collection, container_id, &container, &sync_token);
+                        ctx.add_response(&mut response, &resource, &item, properties);
+                    }
+                    if collection == DavCollection::Calendar {
+                        for (resource, item) in [
+                            (DavResource::Inbox, DavItem::Inbox),
+                            (DavResource::Outbox, DavItem::Outbox),
+                        ] {
+                            ctx.add_response(&mut response, &resource, &item, properties);
+                        }
+                    }
+                }
+            }
+            DavResource::Calendar(_) | DavResource::AddressBook(_) => {
+                let (collection, container_id) = resource.container().unwrap();
+                let container = self
+                    .get_property::<Object<Value>>(
+                        ctx.account_id,
+                        collection.container_collection(),
+                        container_id,
+                        Property::Value,
+                    )
+                    .await?
+                    .unwrap_or_default();
+                let sync_token = self.dav_sync_token(ctx.account_id, collection).await?;
+                let (_, item) = container_item(collection, container_id, &container, &sync_token);
+                ctx.add_response(&mut response, &resource, &item, properties);
+                if depth > 0 {
+                    for (_, item) in self
+                        .dav_items(ctx.account_id, collection, container_id, vec![])
+                        .await?
+                    {
+                        ctx.add_item_response(
+                            &mut response,
+                            collection,
+                            container_id,
+                            &item,
+                            properties,
+                        );
+                    }
+                }
+            }
+            DavResource::Event { uid, .. } | DavResource::Card { uid, .. } => {
+                let (collection, container_id) = resource.container().unwrap();
+                match self
+                    .dav_item_by_uid(ctx.account_id, collection, container_id, uid)
+                    .await?
+                {
+                    Some((_, item)) => {
+                        ctx.add_item_response(
+                            &mut response,
+                            collection,
+                            container_id,
+                            &item,
+                            properties,
+                        );
+                    }
+                    None => return Ok(StatusCode::NOT_FOUND.into_http_response()),
+                }
+            }
+            DavResource::Root => {
+                ctx.add_response(&mut response, &resource, &DavItem::Root, properties);
+            }
+            DavResource::Inbox => {
+                ctx.add_response(&mut response, &resource, &DavItem::Inbox, properties);
+            }
+            DavResource::Outbox => {
+                ctx.add_response(&mut response, &resource, &DavItem::Outbox, properties);
+            }
+        }
+
+        Ok(response.build())
+    }
+}
+
+impl DavContext {
+    pub fn add_response(
+        &self,
+        response: &mut MultiStatus,
+        resource: &DavResource,
+        item: &DavItem<'_>,
+        properties: Option<&[DavProperty]>,
+    ) {
+        let mut found = Vec::new();
+        let mut not_found = Vec::new();
+        match properties {
+            Some(properties) => {
+                for property in properties {
+                    match self.property_value(item, property) {
+                        Some(value) => found.push((property.clone(), value)),
+                        None => not_found.push(property.clone()),
+                    }
+                }
+            }
+            None => {
+                for property in all_properties(item) {
+                    if let Some(value) = self.property_value(item, &property) {
+                        found.push((property, value));
+                    }
+                }
+            }
+        }
+        response.response(&self.href(resource), &found, &not_found);
+    }
+
+    pub fn add_item_response(
+        &self,
+        response: &mut MultiStatus,
+        collection: DavCollection,
+        container_id: u32,
+        item: &HashedValue<Object<Value>>,
+        properties: Option<&[DavProperty]>,
+    ) {
+        if let Some(uid) = item.inner.get(&Property::Uid).as_string() {
+            self.add_response(
+                response,
+                &DavResource::item(collection, container_id, uid.to_string()),
+                &match collection {
+                    DavCollection::Calendar => DavItem::Event { event: item },
+                    DavCollection::AddressBook => DavItem::Card { card: item },
+                },
+                properties,
+            );
+        }
+    }
+
+    fn property_value(&self, item: &DavItem<'_>, property: &DavProperty) -> Option<String> {
+        let value = match (property.namespace.as_str(), property.name.as_str(), item) {
+            (NS_DAV, "resourcetype", item) => match item {
+                DavItem::Root | DavItem::CalendarHome | DavItem::AddressBookHome => {
+                    "<D:collection/>".to_string()
+                }
+                DavItem::Principal { .. } => "<D:principal/>".to_string(),
+                DavItem::Calendar { .. } => "<D:collection/><C:calendar/>".to_string(),
+                DavItem::Inbox => "<D:collection/><C:schedule-inbox/>".to_string(),
+                DavItem::Outbox => "<D:collection/><C:schedule-outbox/>".to_string(),
+                DavItem::AddressBook { .. } => "<D:collection/><CR:addressbook/>".to_string(),
+                DavItem::Event { .. } | DavItem::Card { .. } => String::new(),
+            },
+            (NS_DAV, "displayname", item) => escape_xml(match item {
+                DavItem::Principal { .. } | DavItem::CalendarHome | DavItem::AddressBookHome => {
+                    self.access_token
+                        .description
+                        .as_deref()
+                        .unwrap_or(&self.access_token.name)
+                }
+                DavItem::Calendar {
+                    calendar: container,
+                    ..
+                }
+                | DavItem::AddressBook {
+                    address_book: container,
+                    ..
+                } => container
+                    .get(&Property::Name)
+                    .as_string()
+                    .unwrap_or_default(),
+                DavItem::Inbox => "Inbox",
+                DavItem::Outbox => "Outbox",
+                DavItem::Root | DavItem::Event { .. } | DavItem::Card { .. } => return None,
+            }),
+            (NS_DAV, "current-user-principal", _) => self.href_value(&DavResource::Principal),
+            (NS_DAV, "principal-URL", DavItem::Principal { .. })
+            | (
+                NS_DAV,
+                "owner",
+                DavItem::CalendarHome
+                | DavItem::Calendar { .. }
+                | DavItem::AddressBookHome
+                | DavItem::AddressBook { .. },
+            ) => self.href_value(&DavResource::Principal),
+            (NS_DAV, "current-user-privilege-set", item) => {
+                let mut privileges = String::new();
+                for privilege in match item {
+                    DavItem::Inbox => &["D:read", "C:schedule-deliver"][..],
+                    DavItem::Outbox => &["D:read", "C:schedule-send"][..],
+                    DavItem::AddressBookHome
+                    | DavItem::AddressBook { .. }
+                    | DavItem::Card { .. } => {
+                        &["D:read", "D:write", "D:write-content", "D:bind", "D:unbind"][..]
+                    }
+                    _ => &[
+                        "D:read",
+                        "D:write",
+                        "D:write-content",
+                        "D:bind",
+                        "D:unbind",
+                        "C:read-free-busy",
+                    ][..],
+                } {
+                    privileges.push_str(&format!("<D:privilege><{privilege}/></D:privilege>"));
+                }
+                privileges
+            }
+            (
+                NS_DAV,
+                "supported-report-set",
+                DavItem::Calendar { .. } | DavItem::AddressBook { .. },
+            ) => {
+                let reports = if matches!(item, DavItem::Calendar { .. }) {
+                    [
+                        "C:calendar-query",
+                        "C:calendar-multiget",
+                        "D:sync-collection",
+                    ]
+                } else {
+                    [
+                        "CR:addressbook-query",
+                        "CR:addressbook-multiget",
+                        "D:sync-collection",
+                    ]
+                };
+                let mut supported_reports = String::new();
+                for report in reports {
+                    supported_reports.push_str(&format!(
+                        "<D:supported-report><D:report><{report}/></D:report></D:supported-report>"
+                    ));
+                }
+                supported_reports
+            }
+            (
+                NS_DAV,
+                "sync-token",
+                DavItem::Calendar { sync_token, .. } | DavItem::AddressBook { sync_token, .. },
+            )
+            | (
+                NS_CALSERVER,
+                "getctag",
+                DavItem::Calendar { sync_token, .. } | DavItem::AddressBook { sync_token, .. },
+            ) => escape_xml(sync_token),
+            (NS_DAV, "getetag", DavItem::Event { event: item } | DavItem::Card { card: item }) => {
+                escape_xml(&etag(item))
+            }
+            (NS_DAV, "getcontenttype", DavItem::Event { .. }) => {
+                "text/calendar; charset=utf-8; component=vevent".to_string()
+            }
+            (NS_DAV, "getcontenttype", DavItem::Card { .. }) => {
+                "text/vcard; charset=utf-8".to_string()
+            }
+            (NS_CALDAV, "calendar-data", DavItem::Event { event }) => {
+                escape_xml(&build_icalendar(&event.inner))
+            }
+            (NS_CALDAV, "calendar-home-set", DavItem::Principal { .. }) => {
+                self.href_value(&DavResource::CalendarHome)
+            }
+            (NS_CALDAV, "schedule-inbox-URL", DavItem::Principal { .. }) => {
+                self.href_value(&DavResource::Inbox)
+            }
+            (NS_CALDAV, "schedule-outbox-URL", DavItem::Principal { .. }) => {
+                self.href_value(&DavResource::Outbox)
+            }
+            (NS_CALDAV, "calendar-user-address-set", DavItem::Principal { emails }) => emails
+                .iter()
+                .map(|email| format!("<D:href>mailto:{}</D:href>", escape_xml(email)))
+                .collect(),
+            (NS_CALDAV, "calendar-user-type", DavItem::Principal { .. }) => {
+                "INDIVIDUAL".to_string()
+            }
+            (NS_CALDAV, "supported-calendar-component-set", DavItem::Calendar { .. }) => {
+                "<C:comp name=\"VEVENT\"/>".to_string()
+            }
+            (NS_CALDAV, "supported-calendar-data", DavItem::Calendar { .. }) => {
+                "<C:calendar-data content-type=\"text/calendar\" version=\"2.0\"/>".to_string()
+            }
+            (NS_CALDAV, "calendar-description", DavItem::Calendar { calendar, .. }) => {
+                escape_xml(calendar.get(&Property::Description).as_string()?)
+            }
+            (NS_APPLE, "calendar-color", DavItem::Calendar { calendar, .. }) => {
+                escape_xml(calendar.get(&Property::Color).as_string()?)
+            }
+            (NS_CARDDAV, "address-data", DavItem::Card { card }) => {
+                escape_xml(&build_vcard(&card.inner))
+            }
+            (NS_CARDDAV, "addressbook-home-set", DavItem::Principal { .. }) => {
+                self.href_value(&DavResource::AddressBookHome)
+            }
+            (NS_CARDDAV, "supported-address-data", DavItem::AddressBook { .. }) => {
+                "<CR:address-data-type content-type=\"text/vcard\" version=\"3.0\"/>".to_string()
+            }
+            (NS_CARDDAV, "addressbook-description", DavItem::AddressBook { address_book, .. }) => {
+                escape_xml(address_book.get(&Property::Description).as_string()?)
+            }
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    fn href_value(&self, resource: &DavResource) -> String {
+        format!("<D:href>{}</D:href>", escape_xml(&self.href(resource)))
+    }
+}
+
+fn container_item<'x>(
+    collection: DavCollection,
+    container_id: u32,
+    container: &'x Object<Value>,
+    sync_token: &'x str,
+) -> (DavResource, DavItem<'x>) {
+    match collection {
+        DavCollection::Calendar => (
+            DavResource::Calendar(container_id),
+            DavItem::Calendar {
+                calendar: container,
+                sync_token,
+            },
+        ),
+        DavCollection::AddressBook => (
+            DavResource::AddressBook(container_id),
+            DavItem::AddressBook {
+                address_book: container,
+                sync_token,
+            },
+        ),
+    }
+}
+
+// Properties returned for DAV:allprop requests
+fn all_properties(item: &DavItem<'_>) -> Vec<DavProperty> {
+    let mut properties = vec![
+        DavProperty::new(NS_DAV, "resourcetype"),
+        DavProperty::new(NS_DAV, "displayname"),
+        DavProperty::new(NS_DAV, "current-user-principal"),
+    ];
+    match item {
+        DavItem::Principal { .. } => {
+            properties.push(DavProperty::new(NS_DAV, "principal-URL"));
+            properties.push(DavProperty::new(NS_CALDAV, "calendar-home-set"));
+            properties.push(DavProperty::new(NS_CARDDAV, "addressbook-home-set"));
+        }
+        DavItem::Calendar { .. } => {
+            properties.push(DavProperty::new(NS_DAV, "sync-token"));
+            properties.push(DavProperty::new(NS_CALSERVER, "getctag"));
+            properties.push(DavProperty::new(
+                NS_CALDAV,
+                "supported-calendar-component-set",
+            ));
+        }
+        DavItem::AddressBook { .. } => {
+            properties.push(DavProperty::new(NS_DAV, "sync-token"));
+            properties.push(DavProperty::new(NS_CALSERVER, "getctag"));
+        }
+        DavItem::Event { .. } | DavItem::Card { .. } => {
+            properties.push(DavProperty::new(NS_DAV, "getetag"));
+            properties.push(DavProperty::new(NS_DAV, "getcontenttype"));
+        }
+        _ => (),
+    }
+    properties
+}
@@ -0,0 +1,317 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use chrono::NaiveDateTime;
+use hyper::StatusCode;
+use jmap_proto::{
+    object::Object,
+    types::{property::Property, value::Value},
+};
+use store::{
+    ahash::AHashSet,
+    query::{log::Change, log::Query, Filter},
+    write::assert::HashedValue,
+};
+use trc::AddContext;
+
+use crate::{
+    api::{http::ToHttpResponse, HttpResponse},
+    calendar_event::EventSchedule,
+    JMAP,
+};
+
+use super::{
+    parse_sync_token,
+    xml::{dav_error, MultiStatus, XmlElement},
+    DavCollection, DavContext, DavResource, NS_CALDAV, NS_CARDDAV, NS_DAV,
+};
+
+impl JMAP {
+    pub async fn handle_dav_report(
+        &self,
+        ctx: &DavContext,
+        resource: DavResource,
+        body: &[u8],
+    ) -> trc::Result<HttpResponse> {
+        let (collection, container_id) = match resource {
+            DavResource::Calendar(_) | DavResource::AddressBook(_) => resource.container().unwrap(),
+            _ => return Ok(dav_error(StatusCode::FORBIDDEN, NS_DAV, "supported-report")),
+        };
+        let request = match XmlElement::parse(body) {
+            Some(request) => request,
+            None => return Ok(StatusCode::BAD_REQUEST.into_http_response()),
+        };
+        let properties = request.properties();
+        let properties = properties.as_deref();
+        let mut response = MultiStatus::new();
+
+        if request.is(NS_CALDAV, "calendar-query") && collection == DavCollection::Calendar {
+            // Only VEVENT components with an optional time range are supported
+            let mut time_range = None;
+            if let Some(filter) = request.child(NS_CALDAV, "filter") {
+                let calendar = match filter.child(NS_CALDAV, "comp-filter") {
+                    Some(calendar) if calendar.attribute("name") == Some("VCALENDAR") => calendar,
+                    _ => return Ok(dav_error(StatusCode::FORBIDDEN, NS_CALDAV, "valid-filter")),
+                };
+                if let Some(component) = calendar.child(NS_CALDAV, "comp-filter") {
+                    if component.attribute("name") != Some("VEVENT") {
+                        return Ok(response.build());
+                    } else if component.child(NS_CALDAV, "prop-filter").is_some() {
+                        return Ok(dav_error(
+                            StatusCode::FORBIDDEN,
+                            NS_CALDAV,
+                            "supported-filter",
+                        ));
+                    }
+                    if let Some(range) = component.child(NS_CALDAV, "time-range") {
+                        let start = range.attribute("start").map(parse_utc_time);
+                        let end = range.attribute("end").map(parse_utc_time);
+                        match (start, end) {
+                            (None | Some(Some(_)), None | Some(Some(_))) => {
+                                time_range = Some((
+                                    start.flatten().unwrap_or(i64::MIN),
+                                    end.flatten().unwrap_or(i64::MAX),
+                                ));
+                            }
+                            _ => {
+                                return Ok(dav_error(
+                                    StatusCode::FORBIDDEN,
+                                    NS_CALDAV,
+                                    "valid-filter",
+                                ))
+                            }
+                        }
+                    }
+                }
+            }
+
+            let mut filters = Vec::new();
+            if let Some((from, to)) = time_range {
+                filters.push(Filter::gt(Property::UtcEnd, from.max(0) as u64));
+                filters.push(Filter::lt(Property::UtcStart, to.max(0) as u64));
+            }
+            for (_, event) in self
+                .dav_items(ctx.account_id, collection, container_id, filters)
+                .await?
+            {
+                // Recurring events are only returned if an occurrence overlaps the range
+                if let Some((from, to)) = time_range {
+                    if !EventSchedule::from_object(&event.inner)
+                        .is_some_and(|schedule| schedule.occurrences(from, to).next().is_some())
+                    {
+                        continue;
+                    }
+                }
+                ctx.add_item_response(&mut response, collection, container_id, &event, properties);
+            }
+        } else if request.is(NS_CARDDAV, "addressbook-query")
+            && collection == DavCollection::AddressBook
+        {
+            let filters = match request.child(NS_CARDDAV, "filter") {
+                Some(filter) => match parse_addressbook_filter(filter) {
+                    Some(filters) => filters,
+                    None => {
+                        return Ok(dav_error(
+                            StatusCode::FORBIDDEN,
+                            NS_CARDDAV,
+                            "supported-filter",
+                        ))
+                    }
+                },
+                None => vec![],
+            };
+            for (_, card) in self
+                .dav_items(ctx.account_id, collection, container_id, filters)
+                .await?
+            {
+                ctx.add_item_response(&mut response, collection, container_id, &card, properties);
+            }
+        } else if (request.is(NS_CALDAV, "calendar-multiget")
+            && collection == DavCollection::Calendar)
+            || (request.is(NS_CARDDAV, "addressbook-multiget")
+                && collection == DavCollection::AddressBook)
+        {
+            for href in request
+                .children
+                .iter()
+                .filter(|child| child.is(NS_DAV, "href"))
+            {
+                let item = match ctx.parse_path(href.text.trim()) {
+                    Ok(
+                        DavResource::Event {
+                            calendar_id: item_container_id,
+                            uid,
+                        }
+                        | DavResource::Card {
+                            address_book_id: item_container_id,
+                            uid,
+                        },
+                    ) if item_container_id == container_id => {
+                        self.dav_item_by_uid(ctx.account_id, collection, container_id, &uid)
+                            .await?
+                    }
+                    _ => None,
+                };
+                match item {
+                    Some((_, item)) => ctx.add_item_response(
+                        &mut response,
+                        collection,
+                        container_id,
+                        &item,
+                        properties,
+                    ),
+                    None => response.status(href.text.trim(), StatusCode::NOT_FOUND),
+                }
+            }
+        } else if request.is(NS_DAV, "sync-collection") {
+            let sync_token = request
+                .child(NS_DAV, "sync-token")
+                .map(|token| token.text.trim())
+                .filter(|token| !token.is_empty());
+            let change_id = match sync_token.map(parse_sync_token) {
+                Some(Some(change_id)) => change_id,
+                Some(None) => {
+                    return Ok(dav_error(StatusCode::FORBIDDEN, NS_DAV, "valid-sync-token"))
+                }
+                None => 0,
+            };
+            let new_sync_token = self.dav_sync_token(ctx.account_id, collection).await?;
+
+            if change_id == 0 {
+                for (_, item) in self
+                    .dav_items(ctx.account_id, collection, container_id, vec![])
+                    .await?
+                {
+                    ctx.add_item_response(
+                        &mut response,
+                        collection,
+                        container_id,
+                        &item,
+                        properties,
+                    );
+                }
+            } else {
+                let changes = self
+                    .core
+                    .storage
+                    .data
+                    .changes(
+                        ctx.account_id,
+                        collection.item_collection(),
+                        Query::Since(change_id),
+                    )
+                    .await
+                    .caused_by(trc::location!())?;
+
+                // Deleted items can no longer be mapped to their href, force a full resync
+                let mut document_ids = AHashSet::new();
+                for change in changes.changes {
+                    match change {
+                        Change::Insert(id) | Change::Update(id) | Change::ChildUpdate(id) => {
+                            document_ids.insert(id as u32);
+                        }
+                        Change::Delete(_) => {
+                            return Ok(dav_error(StatusCode::FORBIDDEN, NS_DAV, "valid-sync-token"))
+                        }
+                    }
+                }
+
+                for document_id in document_ids {
+                    let item = match self
+                        .get_property::<HashedValue<Object<Value>>>(
+                            ctx.account_id,
+                            collection.item_collection(),
+                            document_id,
+                            Property::Value,
+                        )
+                        .await?
+                    {
+                        Some(item) => item,
+                        None => {
+                            return Ok(dav_error(StatusCode::FORBIDDEN, NS_DAV, "valid-sync-token"))
+                        }
+                    };
+                    let in_container = item
+                        .inner
+                        .get(&collection.container_property())
+                        .as_list()
+                        .is_some_and(|ids| {
+                            ids.iter().any(|id| {
+                                id.as_id()
+                                    .is_some_and(|id| id.document_id() == container_id)
+                            })
+                        });
+                    if in_container {
+                        ctx.add_item_response(
+                            &mut response,
+                            collection,
+                            container_id,
+                            &item,
+                            properties,
+                        );
+                    } else if let Some(uid) = item.inner.get(&Property::Uid).as_string() {
+                        // Items moved to another container are reported as removed
+                        response.status(
+                            &ctx.href(&DavResource::item(
+                                collection,
+                                container_id,
+                                uid.to_string(),
+                            )),
+                            StatusCode::NOT_FOUND,
+                        );
+                    }
+                }
+            }
+
+            response.sync_token(&new_sync_token);
+        } else {
+            return Ok(dav_error(StatusCode::FORBIDDEN, NS_DAV, "supported-report"));
+        }
+
+        Ok(response.build())
+    }
+}
+
+// Translates an addressbook-query filter (RFC 6352, section 10.5) into a store query,
+// returns `None` if the filter is not supported
+fn parse_addressbook_filter(filter: &XmlElement) -> Option<Vec<Filter>> {
+    let mut filters = Vec::new();
+    let is_and = filter.attribute("test") == Some("allof");
+    filters.push(if is_and { Filter::And } else { Filter::Or });
+    for prop_filter in filter
+        .children
+        .iter()
+        .filter(|child| child.is(NS_CARDDAV, "prop-filter"))
+    {
+        let text = match prop_filter.child(NS_CARDDAV, "text-match") {
+            Some(text_match) if text_match.attribute("negate-condition") != Some("yes") => {
+                text_match.text.trim()
+            }
+            _ => return None,
+        };
+        filters.push(
+            match prop_filter.attribute("name")?.to_ascii_uppercase().as_str() {
+                "FN" => Filter::has_text(Property::FullName, text),
+                "EMAIL" => Filter::has_text(Property::Email, text.to_lowercase()),
+                "UID" => Filter::eq(Property::Uid, text),
+                _ => return None,
+            },
+        );
+    }
+    if filters.len() > 1 {
+        filters.push(Filter::End);
+    } else {
+        filters.clear();
+    }
+    Some(filters)
+}
+
+// Parses a UTC date-time in basic iCalendar format (RFC 4791, section 9.9)
+fn parse_utc_time(value: &str) -> Option<i64> {
+    NaiveDateTime::parse_from_str(value.trim().strip_suffix('Z')?, "%Y%m%dT%H%M%S")
+        .ok()
+        .map(|date| date.and_utc().timestamp())
+}
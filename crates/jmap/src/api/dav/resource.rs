@@ -0,0 +1,324 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use hyper::{header, StatusCode};
+use jmap_proto::{
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    object::Object,
+    request::reference::MaybeReference,
+    types::{
+        id::Id,
+        property::Property,
+        value::{SetValue, Value},
+    },
+};
+use utils::map::vec_map::VecMap;
+
+use crate::{
+    api::{http::ToHttpResponse, HttpRequest, HttpResponse},
+    calendar_event::{
+        icalendar::{build_icalendar, parse_icalendar},
+        itip::{participants_property, sequence_property},
+    },
+    contact_card::vcard::{build_vcard, parse_contact},
+    JMAP,
+};
+
+use super::{etag, xml::dav_error, DavCollection, DavContext, DavResource, NS_CALDAV, NS_CARDDAV};
+
+// Properties obtained from iCalendar data, absent ones are removed on update
+static ICALENDAR_PROPERTIES: &[Property] = &[
+    Property::Title,
+    Property::Description,
+    Property::Duration,
+    Property::TimeZone,
+    Property::Status,
+    Property::FreeBusyStatus,
+    Property::ShowWithoutTime,
+    Property::RecurrenceRules,
+];
+
+// Card properties maintained by the server, never removed on update
+static DERIVED_CARD_PROPERTIES: &[Property] = &[
+    Property::Uid,
+    Property::AddressBookIds,
+    Property::FullName,
+    Property::Email,
+];
+
+impl JMAP {
+    pub async fn handle_dav_get(
+        &self,
+        ctx: &DavContext,
+        resource: DavResource,
+        is_head: bool,
+    ) -> trc::Result<HttpResponse> {
+        let (collection, container_id, uid) = match resource {
+            DavResource::Event { calendar_id, uid } => (DavCollection::Calendar, calendar_id, uid),
+            DavResource::Card {
+                address_book_id,
+                uid,
+            } => (DavCollection::AddressBook, address_book_id, uid),
+            _ => return Ok(StatusCode::METHOD_NOT_ALLOWED.into_http_response()),
+        };
+
+        match self
+            .dav_item_by_uid(ctx.account_id, collection, container_id, &uid)
+            .await?
+        {
+            Some((_, item)) => {
+                let (content_type, data): (_, fn(&Object<Value>) -> String) = match collection {
+                    DavCollection::Calendar => ("text/calendar; charset=utf-8", build_icalendar),
+                    DavCollection::AddressBook => ("text/vcard; charset=utf-8", build_vcard),
+                };
+                let data = if !is_head {
+                    data(&item.inner)
+                } else {
+                    String::new()
+                };
+                Ok(HttpResponse::new_text(StatusCode::OK, content_type, data)
+                    .with_header(header::ETAG, etag(&item)))
+            }
+            None => Ok(StatusCode::NOT_FOUND.into_http_response()),
+        }
+    }
+
+    pub async fn handle_dav_put(
+        &self,
+        req: &HttpRequest,
+        ctx: &DavContext,
+        resource: DavResource,
+        body: &[u8],
+    ) -> trc::Result<HttpResponse> {
+        let (collection, container_id, uid) = match resource {
+            DavResource::Event { calendar_id, uid } => (DavCollection::Calendar, calendar_id, uid),
+            DavResource::Card {
+                address_book_id,
+                uid,
+            } => (DavCollection::AddressBook, address_book_id, uid),
+            _ => return Ok(StatusCode::METHOD_NOT_ALLOWED.into_http_response()),
+        };
+        let (namespace, valid_data) = match collection {
+            DavCollection::Calendar => (NS_CALDAV, "valid-calendar-data"),
+            DavCollection::AddressBook => (NS_CARDDAV, "valid-address-data"),
+        };
+        let item = match collection {
+            DavCollection::Calendar => std::str::from_utf8(body)
+                .ok()
+                .and_then(|text| parse_icalendar(text).ok()),
+            DavCollection::AddressBook => parse_contact(body),
+        };
+        let mut item = match item {
+            Some(item) => item,
+            None => return Ok(dav_error(StatusCode::FORBIDDEN, namespace, valid_data)),
+        };
+
+        // The resource name is derived from the UID, which can't be changed
+        match item.get(&Property::Uid).as_string() {
+            Some(item_uid) if item_uid != uid => {
+                return Ok(dav_error(
+                    StatusCode::BAD_REQUEST,
+                    namespace,
+                    "no-uid-conflict",
+                ));
+            }
+            Some(_) => (),
+            None => {
+                item.set(Property::Uid, Value::Text(uid.clone()));
+            }
+        }
+
+        // Validate preconditions
+        let current = self
+            .dav_item_by_uid(ctx.account_id, collection, container_id, &uid)
+            .await?;
+        if !preconditions_match(req, current.as_ref().map(|(_, item)| etag(item))) {
+            return Ok(StatusCode::PRECONDITION_FAILED.into_http_response());
+        }
+
+        let mut request = SetRequest {
+            account_id: Id::from(ctx.account_id),
+            if_in_state: None,
+            create: None,
+            update: None,
+            destroy: None,
+            arguments: match collection {
+                DavCollection::Calendar => RequestArguments::CalendarEvent,
+                DavCollection::AddressBook => RequestArguments::ContactCard,
+            },
+        };
+        let is_create = current.is_none();
+        if let Some((document_id, current)) = current {
+            // Properties missing from the new representation are removed
+            let removed = match collection {
+                DavCollection::Calendar => ICALENDAR_PROPERTIES
+                    .iter()
+                    .cloned()
+                    .chain([participants_property(), sequence_property()])
+                    .collect::<Vec<_>>(),
+                DavCollection::AddressBook => current
+                    .inner
+                    .properties
+                    .into_iter()
+                    .map(|(property, _)| property)
+                    .filter(|property| !DERIVED_CARD_PROPERTIES.contains(property))
+                    .collect(),
+            }
+            .into_iter()
+            .filter(|property| !item.properties.contains_key(property))
+            .collect::<Vec<_>>();
+            let update = removed
+                .into_iter()
+                .map(|property| (property, SetValue::Value(Value::Null)))
+                .chain(
+                    item.properties
+                        .into_iter()
+                        .filter(|(property, _)| property != &Property::Uid)
+                        .map(|(property, value)| (property, SetValue::Value(value))),
+                )
+                .collect();
+            request.update = Some(VecMap::from_iter([(
+                Id::from(document_id),
+                Object { properties: update },
+            )]));
+        } else {
+            let create = [(
+                collection.container_property(),
+                SetValue::Value(Value::List(vec![Value::Id(Id::from(container_id))])),
+            )]
+            .into_iter()
+            .chain(
+                item.properties
+                    .into_iter()
+                    .map(|(property, value)| (property, SetValue::Value(value))),
+            )
+            .collect();
+            request.create = Some(VecMap::from_iter([(
+                "i".to_string(),
+                Object { properties: create },
+            )]));
+        }
+
+        let mut response = match collection {
+            DavCollection::Calendar => self.calendar_event_set(request).await?,
+            DavCollection::AddressBook => self.contact_card_set(request, &ctx.access_token).await?,
+        };
+        if set_error(&response) {
+            let error = match collection {
+                DavCollection::Calendar => "valid-calendar-object-resource",
+                DavCollection::AddressBook => "valid-address-data",
+            };
+            return Ok(dav_error(StatusCode::FORBIDDEN, namespace, error));
+        }
+        if let Some(state_change) = response.state_change.take() {
+            self.broadcast_state_change(state_change).await;
+        }
+
+        // Return the new entity tag
+        let status = if is_create {
+            StatusCode::CREATED
+        } else {
+            StatusCode::NO_CONTENT
+        };
+        Ok(
+            match self
+                .dav_item_by_uid(ctx.account_id, collection, container_id, &uid)
+                .await?
+            {
+                Some((_, item)) => {
+                    HttpResponse::new_empty(status).with_header(header::ETAG, etag(&item))
+                }
+                None => HttpResponse::new_empty(status),
+            },
+        )
+    }
+
+    pub async fn handle_dav_delete(
+        &self,
+        req: &HttpRequest,
+        ctx: &DavContext,
+        resource: DavResource,
+    ) -> trc::Result<HttpResponse> {
+        let (collection, container_id, uid) = match resource {
+            DavResource::Event { calendar_id, uid } => (DavCollection::Calendar, calendar_id, uid),
+            DavResource::Card {
+                address_book_id,
+                uid,
+            } => (DavCollection::AddressBook, address_book_id, uid),
+            _ => return Ok(StatusCode::FORBIDDEN.into_http_response()),
+        };
+        let (document_id, item) = match self
+            .dav_item_by_uid(ctx.account_id, collection, container_id, &uid)
+            .await?
+        {
+            Some(item) => item,
+            None => return Ok(StatusCode::NOT_FOUND.into_http_response()),
+        };
+        if !preconditions_match(req, Some(etag(&item))) {
+            return Ok(StatusCode::PRECONDITION_FAILED.into_http_response());
+        }
+
+        let request = SetRequest {
+            account_id: Id::from(ctx.account_id),
+            if_in_state: None,
+            create: None,
+            update: None,
+            destroy: Some(MaybeReference::Value(vec![Id::from(document_id)])),
+            arguments: match collection {
+                DavCollection::Calendar => RequestArguments::CalendarEvent,
+                DavCollection::AddressBook => RequestArguments::ContactCard,
+            },
+        };
+        let mut response = match collection {
+            DavCollection::Calendar => self.calendar_event_set(request).await?,
+            DavCollection::AddressBook => self.contact_card_set(request, &ctx.access_token).await?,
+        };
+        if set_error(&response) {
+            return Ok(StatusCode::CONFLICT.into_http_response());
+        }
+        if let Some(state_change) = response.state_change.take() {
+            self.broadcast_state_change(state_change).await;
+        }
+
+        Ok(HttpResponse::new_empty(StatusCode::NO_CONTENT))
+    }
+}
+
+// Evaluates the If-Match and If-None-Match headers against the current entity tag
+fn preconditions_match(req: &HttpRequest, current: Option<String>) -> bool {
+    let header = |name: header::HeaderName| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+    };
+    let matches = |value: &str| {
+        value == "*"
+            || current.as_ref().is_some_and(|current| {
+                value
+                    .split(',')
+                    .any(|tag| tag.trim().trim_start_matches("W/") == current)
+            })
+    };
+
+    if let Some(if_match) = header(header::IF_MATCH) {
+        if current.is_none() || !matches(&if_match) {
+            return false;
+        }
+    }
+    if let Some(if_none_match) = header(header::IF_NONE_MATCH) {
+        if current.is_some() && matches(&if_none_match) {
+            return false;
+        }
+    }
+    true
+}
+
+fn set_error(response: &SetResponse) -> bool {
+    !response.not_created.is_empty()
+        || !response.not_updated.is_empty()
+        || !response.not_destroyed.is_empty()
+}
@@ -0,0 +1,120 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::fmt::Write;
+
+use hyper::StatusCode;
+use jmap_proto::{
+    method::availability::GetAvailabilityRequest,
+    types::{date::UTCDate, id::Id},
+};
+use trc::AddContext;
+
+use crate::{
+    api::HttpResponse,
+    calendar_event::icalendar::{build_freebusy_reply, parse_freebusy_request},
+    JMAP,
+};
+
+use super::{
+    xml::{dav_error, escape_xml},
+    DavContext, NS_CALDAV, NS_DAV,
+};
+
+impl JMAP {
+    // Free-busy queries sent to the scheduling outbox (RFC 6638, section 5)
+    pub async fn handle_dav_schedule(
+        &self,
+        ctx: &DavContext,
+        body: &[u8],
+    ) -> trc::Result<HttpResponse> {
+        let request = match std::str::from_utf8(body)
+            .ok()
+            .and_then(parse_freebusy_request)
+        {
+            Some(request) => request,
+            None => {
+                return Ok(dav_error(
+                    StatusCode::FORBIDDEN,
+                    NS_CALDAV,
+                    "valid-calendar-data",
+                ))
+            }
+        };
+
+        let mut xml = String::with_capacity(1024);
+        xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        let _ = write!(
+            xml,
+            "<C:schedule-response xmlns:D=\"{NS_DAV}\" xmlns:C=\"{NS_CALDAV}\">"
+        );
+        for attendee in &request.attendees {
+            let account_id = match attendee
+                .get(..7)
+                .filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+                .map(|_| attendee[7..].trim())
+            {
+                Some(address) => self
+                    .core
+                    .storage
+                    .directory
+                    .email_to_ids(address)
+                    .await
+                    .caused_by(trc::location!())?
+                    .first()
+                    .copied(),
+                None => None,
+            };
+            let _ = write!(
+                xml,
+                "<C:response><C:recipient><D:href>{}</D:href></C:recipient>",
+                escape_xml(attendee)
+            );
+
+            match account_id {
+                Some(account_id) => {
+                    match self
+                        .calendar_event_availability(
+                            GetAvailabilityRequest {
+                                account_id: Id::from(ctx.account_id),
+                                id: Id::from(account_id),
+                                utc_start: UTCDate::from_timestamp(request.utc_start),
+                                utc_end: UTCDate::from_timestamp(request.utc_end),
+                            },
+                            &ctx.access_token,
+                        )
+                        .await
+                    {
+                        Ok(response) => {
+                            let _ = write!(
+                                xml,
+                                "<C:request-status>2.0;Success</C:request-status><C:calendar-data>{}</C:calendar-data>",
+                                escape_xml(&build_freebusy_reply(&request, attendee, &response.list))
+                            );
+                        }
+                        Err(err)
+                            if err.matches(trc::EventType::Jmap(trc::JmapEvent::Forbidden)) =>
+                        {
+                            xml.push_str("<C:request-status>3.8;No authority</C:request-status>");
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+                None => {
+                    xml.push_str("<C:request-status>3.7;Invalid calendar user</C:request-status>");
+                }
+            }
+            xml.push_str("</C:response>");
+        }
+        xml.push_str("</C:schedule-response>\n");
+
+        Ok(HttpResponse::new_text(
+            StatusCode::OK,
+            "application/xml; charset=utf-8",
+            xml,
+        ))
+    }
+}
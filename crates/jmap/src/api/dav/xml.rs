@@ -0,0 +1,313 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::fmt::Write;
+
+use hyper::StatusCode;
+use quick_xml::{
+    events::{BytesStart, Event},
+    name::{Namespace, ResolveResult},
+    NsReader,
+};
+
+use crate::api::HttpResponse;
+
+use super::{NS_APPLE, NS_CALDAV, NS_CALSERVER, NS_CARDDAV, NS_DAV};
+
+#[derive(Debug, Default)]
+pub struct XmlElement {
+    pub namespace: String,
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlElement>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DavProperty {
+    pub namespace: String,
+    pub name: String,
+}
+
+pub struct MultiStatus {
+    xml: String,
+}
+
+impl XmlElement {
+    pub fn parse(bytes: &[u8]) -> Option<XmlElement> {
+        let mut reader = NsReader::from_reader(bytes);
+        reader.config_mut().trim_text(true);
+        let mut stack: Vec<XmlElement> = Vec::new();
+
+        loop {
+            let (namespace, event) = reader.read_resolved_event().ok()?;
+            let namespace = match namespace {
+                ResolveResult::Bound(Namespace(namespace)) => {
+                    String::from_utf8_lossy(namespace).into_owned()
+                }
+                _ => String::new(),
+            };
+            match event {
+                Event::Start(element) => {
+                    stack.push(XmlElement::new(namespace, &element));
+                }
+                Event::Empty(element) => {
+                    let element = XmlElement::new(namespace, &element);
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Some(element),
+                    }
+                }
+                Event::End(_) => {
+                    let element = stack.pop()?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Some(element),
+                    }
+                }
+                Event::Text(text) => {
+                    stack.last_mut()?.text.push_str(&text.unescape().ok()?);
+                }
+                Event::CData(text) => {
+                    stack
+                        .last_mut()?
+                        .text
+                        .push_str(&String::from_utf8_lossy(&text));
+                }
+                Event::Eof => return None,
+                _ => (),
+            }
+        }
+    }
+
+    fn new(namespace: String, element: &BytesStart<'_>) -> Self {
+        XmlElement {
+            namespace,
+            name: String::from_utf8_lossy(element.local_name().as_ref()).into_owned(),
+            attributes: element
+                .attributes()
+                .flatten()
+                .filter_map(|attr| {
+                    Some((
+                        String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned(),
+                        attr.unescape_value().ok()?.into_owned(),
+                    ))
+                })
+                .collect(),
+            children: Vec::new(),
+            text: String::new(),
+        }
+    }
+
+    pub fn is(&self, namespace: &str, name: &str) -> bool {
+        self.namespace == namespace && self.name == name
+    }
+
+    pub fn child(&self, namespace: &str, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|child| child.is(namespace, name))
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Properties requested in a DAV:prop element
+    pub fn properties(&self) -> Option<Vec<DavProperty>> {
+        self.child(NS_DAV, "prop").map(|prop| {
+            prop.children
+                .iter()
+                .map(|child| DavProperty::new(&child.namespace, &child.name))
+                .collect()
+        })
+    }
+}
+
+impl DavProperty {
+    pub fn new(namespace: &str, name: &str) -> Self {
+        DavProperty {
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    pub fn is(&self, namespace: &str, name: &str) -> bool {
+        self.namespace == namespace && self.name == name
+    }
+
+    fn write(&self, xml: &mut String, value: Option<&str>) {
+        let prefix = match self.namespace.as_str() {
+            NS_DAV => "D",
+            NS_CALDAV => "C",
+            NS_CALSERVER => "CS",
+            NS_APPLE => "A",
+            NS_CARDDAV => "CR",
+            namespace => {
+                let _ = write!(
+                    xml,
+                    "<X:{} xmlns:X=\"{}\"",
+                    self.name,
+                    escape_xml(namespace)
+                );
+                match value {
+                    Some(value) if !value.is_empty() => {
+                        let _ = write!(xml, ">{value}</X:{}>", self.name);
+                    }
+                    _ => xml.push_str("/>"),
+                }
+                return;
+            }
+        };
+        match value {
+            Some(value) if !value.is_empty() => {
+                let _ = write!(
+                    xml,
+                    "<{prefix}:{}>{value}</{prefix}:{}>",
+                    self.name, self.name
+                );
+            }
+            _ => {
+                let _ = write!(xml, "<{prefix}:{}/>", self.name);
+            }
+        }
+    }
+}
+
+impl MultiStatus {
+    pub fn new() -> Self {
+        let mut xml = String::with_capacity(1024);
+        xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        let _ = write!(
+            xml,
+            "<D:multistatus xmlns:D=\"{NS_DAV}\" xmlns:C=\"{NS_CALDAV}\" xmlns:CS=\"{NS_CALSERVER}\" xmlns:A=\"{NS_APPLE}\" xmlns:CR=\"{NS_CARDDAV}\">"
+        );
+        MultiStatus { xml }
+    }
+
+    pub fn response(
+        &mut self,
+        href: &str,
+        found: &[(DavProperty, String)],
+        not_found: &[DavProperty],
+    ) {
+        let _ = write!(
+            self.xml,
+            "<D:response><D:href>{}</D:href>",
+            escape_xml(href)
+        );
+        if !found.is_empty() {
+            self.xml.push_str("<D:propstat><D:prop>");
+            for (property, value) in found {
+                property.write(&mut self.xml, Some(value));
+            }
+            self.xml
+                .push_str("</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat>");
+        }
+        if !not_found.is_empty() {
+            self.xml.push_str("<D:propstat><D:prop>");
+            for property in not_found {
+                property.write(&mut self.xml, None);
+            }
+            self.xml
+                .push_str("</D:prop><D:status>HTTP/1.1 404 Not Found</D:status></D:propstat>");
+        }
+        self.xml.push_str("</D:response>");
+    }
+
+    pub fn status(&mut self, href: &str, status: StatusCode) {
+        let _ = write!(
+            self.xml,
+            "<D:response><D:href>{}</D:href><D:status>HTTP/1.1 {}</D:status></D:response>",
+            escape_xml(href),
+            status
+        );
+    }
+
+    pub fn sync_token(&mut self, token: &str) {
+        let _ = write!(
+            self.xml,
+            "<D:sync-token>{}</D:sync-token>",
+            escape_xml(token)
+        );
+    }
+
+    pub fn build(mut self) -> HttpResponse {
+        self.xml.push_str("</D:multistatus>\n");
+        HttpResponse::new_text(
+            StatusCode::MULTI_STATUS,
+            "application/xml; charset=utf-8",
+            self.xml,
+        )
+    }
+}
+
+impl Default for MultiStatus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Error response with a precondition or postcondition element (RFC 4918, section 16)
+pub fn dav_error(status: StatusCode, namespace: &str, condition: &str) -> HttpResponse {
+    let mut xml = String::with_capacity(256);
+    xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    let _ = write!(
+        xml,
+        "<D:error xmlns:D=\"{NS_DAV}\" xmlns:C=\"{NS_CALDAV}\" xmlns:CR=\"{NS_CARDDAV}\">"
+    );
+    DavProperty::new(namespace, condition).write(&mut xml, None);
+    xml.push_str("</D:error>\n");
+    HttpResponse::new_text(status, "application/xml; charset=utf-8", xml)
+}
+
+pub fn escape_xml(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '&' => result.push_str("&amp;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            ch => result.push(ch),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::dav::{NS_CALDAV, NS_CALSERVER, NS_DAV};
+
+    use super::{DavProperty, XmlElement};
+
+    #[test]
+    fn parse_propfind() {
+        let request = XmlElement::parse(
+            br#"<?xml version="1.0" encoding="utf-8"?>
+            <d:propfind xmlns:d="DAV:" xmlns:cs="http://calendarserver.org/ns/">
+              <d:prop>
+                <d:displayname />
+                <cs:getctag />
+                <c:calendar-data xmlns:c="urn:ietf:params:xml:ns:caldav"/>
+              </d:prop>
+            </d:propfind>"#,
+        )
+        .unwrap();
+
+        assert!(request.is(NS_DAV, "propfind"));
+        assert_eq!(
+            request.properties().unwrap(),
+            vec![
+                DavProperty::new(NS_DAV, "displayname"),
+                DavProperty::new(NS_CALSERVER, "getctag"),
+                DavProperty::new(NS_CALDAV, "calendar-data"),
+            ]
+        );
+    }
+}
@@ -42,6 +42,8 @@ use super::{
     JmapSessionManager, JsonResponse,
 };
 
+const MAX_REPORT_SIZE: usize = 10 * 1024 * 1024;
+
 pub struct HttpSessionData {
     pub instance: Arc<ServerInstance>,
     pub local_ip: IpAddr,
@@ -191,6 +193,7 @@ impl JMAP {
                     _ => (),
                 }
             }
+            "dav" => return self.handle_dav_request(&mut req, &session).await,
             ".well-known" => match (path.next().unwrap_or_default(), req.method()) {
                 ("jmap", &Method::GET) => {
                     // Authenticate request
@@ -230,7 +233,17 @@ impl JMAP {
                     }
                 }
                 ("mta-sts.txt", &Method::GET) => {
-                    if let Some(policy) = self.core.build_mta_sts_policy() {
+                    // Policies are served from mta-sts.<domain>
+                    let host = req
+                        .headers()
+                        .get(header::HOST)
+                        .and_then(|h| h.to_str().ok())
+                        .map(|h| h.rsplit_once(':').map_or(h, |(h, _)| h))
+                        .unwrap_or_default()
+                        .to_lowercase();
+                    let domain = host.strip_prefix("mta-sts.").unwrap_or(&host);
+
+                    if let Some(policy) = self.core.build_mta_sts_policy(domain) {
                         return Ok(Resource::new("text/plain", policy.to_string().into_bytes())
                             .into_http_response());
                     } else {
@@ -247,6 +260,10 @@ impl JMAP {
                         return self.handle_autoconfig_request(&req).await;
                     }
                 }
+                ("caldav" | "carddav", _) => {
+                    return Ok(HttpResponse::new_empty(StatusCode::MOVED_PERMANENTLY)
+                        .with_header(header::LOCATION, "/dav/"));
+                }
                 (_, &Method::OPTIONS) => {
                     return Ok(StatusCode::NO_CONTENT.into_http_response());
                 }
@@ -282,7 +299,25 @@ impl JMAP {
                 // Authenticate user
                 match self.authenticate_headers(&req, &session).await {
                     Ok((_, access_token)) => {
-                        let body = fetch_body(&mut req, 1024 * 1024, session.session_id).await;
+                        // Change sets shipped by a primary include message blobs,
+                        // spam filter training data can contain millions of tokens
+                        // and training corpora can include large messages
+                        let max_size = match req.uri().path() {
+                            "/api/replication/apply"
+                                if access_token.has_permission(Permission::Replication) =>
+                            {
+                                0
+                            }
+                            "/api/spam-filter/training"
+                            | "/api/spam-filter/train/spam"
+                            | "/api/spam-filter/train/ham"
+                                if access_token.has_permission(Permission::UpdateSpamFilter) =>
+                            {
+                                0
+                            }
+                            _ => 1024 * 1024,
+                        };
+                        let body = fetch_body(&mut req, max_size, session.session_id).await;
                         return self
                             .handle_api_manage_request(&req, body, access_token, &session)
                             .await;
@@ -332,6 +367,25 @@ impl JMAP {
                     }
                 }
             }
+            "reports" => {
+                if req.method() == Method::POST
+                    && path.next().unwrap_or_default() == "tls"
+                    && self.core.smtp.report.analysis.http
+                {
+                    return match fetch_body(&mut req, MAX_REPORT_SIZE, session.session_id).await {
+                        Some(body) => self
+                            .smtp
+                            .ingest_tls_report(&body, session.session_id)
+                            .await
+                            .map(|_| StatusCode::CREATED.into_http_response())
+                            .map_err(|err| {
+                                trc::error!(err.span_id(session.session_id));
+                                trc::ResourceEvent::BadParameters.into_err()
+                            }),
+                        None => Err(trc::LimitEvent::SizeRequest.into_err()),
+                    };
+                }
+            }
             "mail" => {
                 if req.method() == Method::GET
                     && path.next().unwrap_or_default() == "config-v1.1.xml"
@@ -339,16 +393,23 @@ impl JMAP {
                     return self.handle_autoconfig_request(&req).await;
                 }
             }
-            "autodiscover" => {
-                if req.method() == Method::POST
-                    && path.next().unwrap_or_default() == "autodiscover.xml"
-                {
+            "autodiscover" => match (path.next().unwrap_or_default(), req.method()) {
+                ("autodiscover.xml", &Method::POST) => {
                     return self
                         .handle_autodiscover_request(
                             fetch_body(&mut req, 8192, session.session_id).await,
                         )
                         .await;
                 }
+                ("autodiscover.json", &Method::GET) => {
+                    return self.handle_autodiscover_json_request(&req).await;
+                }
+                _ => (),
+            },
+            "mobileconfig" => {
+                if req.method() == Method::GET {
+                    return self.handle_mobileconfig_request(&req).await;
+                }
             }
             "robots.txt" => {
                 return Ok(
@@ -735,6 +796,7 @@ impl HttpResponse {
             content_type: "".into(),
             content_disposition: "".into(),
             cache_control: "".into(),
+            headers: vec![],
             body: HttpResponseBody::Empty,
         }
     }
@@ -749,6 +811,7 @@ impl HttpResponse {
             content_type: content_type.into(),
             content_disposition: "".into(),
             cache_control: "".into(),
+            headers: vec![],
             body: HttpResponseBody::Text(body.into()),
         }
     }
@@ -763,10 +826,16 @@ impl HttpResponse {
             content_type: content_type.into(),
             content_disposition: "".into(),
             cache_control: "".into(),
+            headers: vec![],
             body: HttpResponseBody::Binary(body.into()),
         }
     }
 
+    pub fn with_header(mut self, name: header::HeaderName, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
     pub fn size(&self) -> usize {
         match &self.body {
             HttpResponseBody::Text(value) => value.len(),
@@ -779,7 +848,10 @@ impl HttpResponse {
         self,
     ) -> hyper::Response<http_body_util::combinators::BoxBody<hyper::body::Bytes, hyper::Error>>
     {
-        let builder = hyper::Response::builder().status(self.status);
+        let mut builder = hyper::Response::builder().status(self.status);
+        for (name, value) in self.headers {
+            builder = builder.header(name, value);
+        }
 
         match self.body {
             HttpResponseBody::Text(body) => builder
@@ -933,8 +1005,12 @@ impl ToRequestError for trc::Error {
                 trc::SecurityEvent::AuthenticationBan
                 | trc::SecurityEvent::BruteForceBan
                 | trc::SecurityEvent::LoiterBan
+                | trc::SecurityEvent::EarlyTalkerBan
+                | trc::SecurityEvent::ScriptBan
                 | trc::SecurityEvent::IpBlocked => RequestError::too_many_auth_attempts(),
-                trc::SecurityEvent::Unauthorized => RequestError::forbidden(),
+                trc::SecurityEvent::Unauthorized
+                | trc::SecurityEvent::EarlyTalker
+                | trc::SecurityEvent::SlowTransfer => RequestError::forbidden(),
             },
             trc::EventType::Resource(cause) => match cause {
                 trc::ResourceEvent::NotFound => RequestError::not_found(),
@@ -946,6 +1022,11 @@ impl ToRequestError for trc::Error {
                 trc::ResourceEvent::Error => RequestError::internal_server_error(),
                 _ => RequestError::internal_server_error(),
             },
+            trc::EventType::Server(trc::ServerEvent::MaintenanceMode) => RequestError::blank(
+                StatusCode::SERVICE_UNAVAILABLE.as_u16(),
+                "Maintenance in progress",
+                details,
+            ),
             _ => RequestError::internal_server_error(),
         }
     }
@@ -1006,6 +1087,7 @@ impl ToHttpResponse for DownloadResponse {
             )
             .into(),
             cache_control: "private, immutable, max-age=31536000".into(),
+            headers: vec![],
             body: HttpResponseBody::Binary(self.blob),
         }
     }
@@ -0,0 +1,713 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use directory::{
+    backend::internal::{
+        lookup::DirectoryStore,
+        manage::{self, not_found, ManageDirectory},
+        PrincipalField,
+    },
+    Permission, QueryBy, Type,
+};
+use jmap_proto::{
+    method::set::{self, SetRequest, SetResponse},
+    object::{mailbox, sieve, Object},
+    request::reference::MaybeReference,
+    types::{
+        collection::Collection,
+        date::UTCDate,
+        id::Id,
+        property::Property,
+        state::StateChange,
+        type_state::DataType,
+        value::{SetValue, Value},
+    },
+};
+use mail_parser::DateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use store::{ahash::AHashMap, query::Filter};
+use trc::AddContext;
+use utils::map::vec_map::VecMap;
+
+use crate::{
+    api::{
+        http::{HttpSessionData, ToHttpResponse},
+        HttpResponse, JsonResponse,
+    },
+    sieve::set::ObjectBlobId,
+    JMAP,
+};
+
+use super::decode_path_element;
+
+// Non-mail account state, messages are not included.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AccountBundle {
+    #[serde(default)]
+    pub mailboxes: Vec<MailboxBundle>,
+    #[serde(default)]
+    pub identities: Vec<IdentityBundle>,
+    #[serde(rename = "sieveScripts")]
+    #[serde(default)]
+    pub sieve_scripts: Vec<SieveScriptBundle>,
+    #[serde(rename = "vacationResponse")]
+    #[serde(default)]
+    pub vacation_response: Option<VacationResponseBundle>,
+    // Only the names are exported, app passwords are not restored on import.
+    #[serde(rename = "appPasswords")]
+    #[serde(default)]
+    pub app_passwords: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MailboxBundle {
+    pub path: String,
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(rename = "sortOrder")]
+    #[serde(default)]
+    pub sort_order: u64,
+    #[serde(rename = "isSubscribed")]
+    #[serde(default)]
+    pub is_subscribed: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IdentityBundle {
+    #[serde(default)]
+    pub name: String,
+    pub email: String,
+    #[serde(rename = "replyTo")]
+    #[serde(default)]
+    pub reply_to: Option<Vec<EmailAddressBundle>>,
+    #[serde(default)]
+    pub bcc: Option<Vec<EmailAddressBundle>>,
+    #[serde(rename = "textSignature")]
+    #[serde(default)]
+    pub text_signature: String,
+    #[serde(rename = "htmlSignature")]
+    #[serde(default)]
+    pub html_signature: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EmailAddressBundle {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub email: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SieveScriptBundle {
+    pub name: String,
+    #[serde(rename = "isActive")]
+    #[serde(default)]
+    pub is_active: bool,
+    pub script: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VacationResponseBundle {
+    #[serde(rename = "isEnabled")]
+    #[serde(default)]
+    pub is_enabled: bool,
+    #[serde(rename = "fromDate")]
+    #[serde(default)]
+    pub from_date: Option<String>,
+    #[serde(rename = "toDate")]
+    #[serde(default)]
+    pub to_date: Option<String>,
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(rename = "textBody")]
+    #[serde(default)]
+    pub text_body: Option<String>,
+    #[serde(rename = "htmlBody")]
+    #[serde(default)]
+    pub html_body: Option<String>,
+}
+
+impl JMAP {
+    pub async fn handle_export_account(
+        &self,
+        path: Vec<&str>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        // Validate the access token
+        access_token.assert_has_permission(Permission::IndividualGet)?;
+
+        let account_id = self.bundle_account_id(&path, access_token).await?;
+
+        Ok(JsonResponse::new(json!({
+            "data": self.export_account(account_id).await?,
+        }))
+        .into_http_response())
+    }
+
+    pub async fn handle_import_account(
+        &self,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        session: &HttpSessionData,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        // Validate the access token
+        access_token.assert_has_permission(Permission::IndividualUpdate)?;
+
+        let account_id = self.bundle_account_id(&path, access_token).await?;
+        let bundle = serde_json::from_slice::<AccountBundle>(body.as_deref().unwrap_or_default())
+            .map_err(|err| {
+            trc::EventType::Resource(trc::ResourceEvent::BadParameters).from_json_error(err)
+        })?;
+
+        Ok(JsonResponse::new(json!({
+            "data": self.import_account(account_id, bundle, session).await?,
+        }))
+        .into_http_response())
+    }
+
+    pub(super) async fn bundle_account_id(
+        &self,
+        path: &[&str],
+        access_token: &AccessToken,
+    ) -> trc::Result<u32> {
+        let name = decode_path_element(path.get(1).copied().unwrap_or_default());
+        let principal = self
+            .core
+            .storage
+            .data
+            .get_principal_info(name.as_ref())
+            .await?
+            .filter(|p| p.has_tenant_access(access_token.tenant.map(|t| t.id)))
+            .ok_or_else(|| not_found(name.to_string()))?;
+
+        if matches!(principal.typ, Type::Individual) {
+            Ok(principal.id)
+        } else {
+            Err(manage::unsupported(
+                "Only individual accounts can be exported or imported",
+            ))
+        }
+    }
+
+    pub async fn export_account(&self, account_id: u32) -> trc::Result<AccountBundle> {
+        let mut bundle = AccountBundle::default();
+
+        // Export mailbox structure
+        let mut mailboxes = AHashMap::new();
+        for document_id in self
+            .mailbox_get_or_create(account_id)
+            .await
+            .caused_by(trc::location!())?
+        {
+            if let Some(mut mailbox) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::Mailbox,
+                    document_id,
+                    Property::Value,
+                )
+                .await
+                .caused_by(trc::location!())?
+            {
+                let parent_id = match mailbox.remove(&Property::ParentId) {
+                    Value::Id(id) => id.document_id(),
+                    _ => 0,
+                };
+                mailboxes.insert(document_id + 1, (parent_id, mailbox));
+            }
+        }
+        for (parent_id, mailbox) in mailboxes.values() {
+            let mut path = vec![mailbox.get(&Property::Name).as_string().unwrap_or_default()];
+            let mut parent_id = *parent_id;
+            while let Some((next_parent_id, parent)) = mailboxes.get(&parent_id) {
+                if path.len() > self.core.jmap.mailbox_max_depth {
+                    break;
+                }
+                path.push(parent.get(&Property::Name).as_string().unwrap_or_default());
+                parent_id = *next_parent_id;
+            }
+            path.reverse();
+
+            bundle.mailboxes.push(MailboxBundle {
+                path: path.join("/"),
+                role: mailbox
+                    .get(&Property::Role)
+                    .as_string()
+                    .map(|role| role.to_string()),
+                sort_order: mailbox.get(&Property::SortOrder).as_uint().unwrap_or(0),
+                is_subscribed: mailbox
+                    .get(&Property::IsSubscribed)
+                    .as_list()
+                    .map_or(false, |ids| ids.contains(&Value::Id(account_id.into()))),
+            });
+        }
+        bundle
+            .mailboxes
+            .sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+        // Export identities
+        for document_id in self
+            .get_document_ids(account_id, Collection::Identity)
+            .await?
+            .unwrap_or_default()
+        {
+            if let Some(mut identity) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::Identity,
+                    document_id,
+                    Property::Value,
+                )
+                .await
+                .caused_by(trc::location!())?
+            {
+                bundle.identities.push(IdentityBundle {
+                    name: identity
+                        .remove(&Property::Name)
+                        .try_unwrap_string()
+                        .unwrap_or_default(),
+                    email: identity
+                        .remove(&Property::Email)
+                        .try_unwrap_string()
+                        .unwrap_or_default(),
+                    reply_to: export_addresses(identity.remove(&Property::ReplyTo)),
+                    bcc: export_addresses(identity.remove(&Property::Bcc)),
+                    text_signature: identity
+                        .remove(&Property::TextSignature)
+                        .try_unwrap_string()
+                        .unwrap_or_default(),
+                    html_signature: identity
+                        .remove(&Property::HtmlSignature)
+                        .try_unwrap_string()
+                        .unwrap_or_default(),
+                });
+            }
+        }
+
+        // Export Sieve scripts and vacation response
+        for document_id in self
+            .get_document_ids(account_id, Collection::SieveScript)
+            .await?
+            .unwrap_or_default()
+        {
+            let mut script = if let Some(script) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::SieveScript,
+                    document_id,
+                    Property::Value,
+                )
+                .await
+                .caused_by(trc::location!())?
+            {
+                script
+            } else {
+                continue;
+            };
+            let name = script
+                .remove(&Property::Name)
+                .try_unwrap_string()
+                .unwrap_or_default();
+            let is_active = script
+                .remove(&Property::IsActive)
+                .try_unwrap_bool()
+                .unwrap_or_default();
+
+            if name.eq_ignore_ascii_case("vacation") {
+                bundle.vacation_response = VacationResponseBundle {
+                    is_enabled: is_active,
+                    from_date: script
+                        .remove(&Property::FromDate)
+                        .try_unwrap_date()
+                        .map(|date| date.to_string()),
+                    to_date: script
+                        .remove(&Property::ToDate)
+                        .try_unwrap_date()
+                        .map(|date| date.to_string()),
+                    subject: script.remove(&Property::Subject).try_unwrap_string(),
+                    text_body: script.remove(&Property::TextBody).try_unwrap_string(),
+                    html_body: script.remove(&Property::HtmlBody).try_unwrap_string(),
+                }
+                .into();
+            } else if let Some((blob_hash, blob_section)) = script
+                .blob_id()
+                .and_then(|id| (id.hash.clone(), id.section.as_ref()?.clone()).into())
+            {
+                let script = self
+                    .get_blob_section(&blob_hash, &blob_section)
+                    .await
+                    .caused_by(trc::location!())?
+                    .ok_or_else(|| {
+                        trc::StoreEvent::NotFound
+                            .into_err()
+                            .details("Sieve script blob not found")
+                            .account_id(account_id)
+                            .document_id(document_id)
+                    })?;
+
+                bundle.sieve_scripts.push(SieveScriptBundle {
+                    name,
+                    is_active,
+                    script: String::from_utf8(script)
+                        .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned()),
+                });
+            }
+        }
+        bundle
+            .sieve_scripts
+            .sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+        // Export app password names
+        if let Some(principal) = self
+            .core
+            .storage
+            .data
+            .query(QueryBy::Id(account_id), false)
+            .await?
+        {
+            for secret in principal.iter_str(PrincipalField::Secrets) {
+                if let Some((app_name, _)) =
+                    secret.strip_prefix("$app$").and_then(|s| s.split_once('$'))
+                {
+                    bundle.app_passwords.push(app_name.to_string());
+                }
+            }
+        }
+
+        Ok(bundle)
+    }
+
+    pub async fn import_account(
+        &self,
+        account_id: u32,
+        bundle: AccountBundle,
+        session: &HttpSessionData,
+    ) -> trc::Result<Vec<String>> {
+        let access_token = self
+            .core
+            .get_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let mut errors = Vec::new();
+
+        // Import mailbox structure, making sure the default folders exist first
+        self.mailbox_get_or_create(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let mut update = VecMap::with_capacity(bundle.mailboxes.len());
+        for mailbox in bundle.mailboxes {
+            match self
+                .mailbox_create_path(account_id, &mailbox.path)
+                .await
+                .caused_by(trc::location!())?
+            {
+                Some((document_id, change_id)) => {
+                    if let Some(change_id) = change_id {
+                        self.broadcast_state_change(
+                            StateChange::new(account_id).with_change(DataType::Mailbox, change_id),
+                        )
+                        .await;
+                    }
+
+                    update.append(
+                        Id::from(document_id),
+                        set_object([
+                            (
+                                Property::Role,
+                                mailbox.role.map_or(Value::Null, Value::Text),
+                            ),
+                            (Property::SortOrder, Value::UnsignedInt(mailbox.sort_order)),
+                            (Property::IsSubscribed, Value::Bool(mailbox.is_subscribed)),
+                        ]),
+                    );
+                }
+                None => {
+                    errors.push(format!("Invalid mailbox path {:?}.", mailbox.path));
+                }
+            }
+        }
+        if !update.is_empty() {
+            let response = self
+                .mailbox_set(
+                    SetRequest {
+                        account_id: Id::from(account_id),
+                        if_in_state: None,
+                        create: None,
+                        update: Some(update),
+                        destroy: None,
+                        arguments: mailbox::SetArguments::default(),
+                    },
+                    &access_token,
+                )
+                .await?;
+            self.import_response("Mailbox", response, &mut errors).await;
+        }
+
+        // Import identities, updating the ones that already exist
+        if !bundle.identities.is_empty() {
+            let mut existing = AHashMap::new();
+            for document_id in self
+                .get_document_ids(account_id, Collection::Identity)
+                .await?
+                .unwrap_or_default()
+            {
+                if let Some(mut identity) = self
+                    .get_property::<Object<Value>>(
+                        account_id,
+                        Collection::Identity,
+                        document_id,
+                        Property::Value,
+                    )
+                    .await
+                    .caused_by(trc::location!())?
+                {
+                    existing.insert(
+                        (
+                            identity
+                                .remove(&Property::Email)
+                                .try_unwrap_string()
+                                .unwrap_or_default(),
+                            identity
+                                .remove(&Property::Name)
+                                .try_unwrap_string()
+                                .unwrap_or_default(),
+                        ),
+                        document_id,
+                    );
+                }
+            }
+
+            let mut create = VecMap::new();
+            let mut update = VecMap::new();
+            for (idx, identity) in bundle.identities.into_iter().enumerate() {
+                let reply_to = import_addresses(identity.reply_to);
+                let bcc = import_addresses(identity.bcc);
+                let text_signature = Value::Text(identity.text_signature);
+                let html_signature = Value::Text(identity.html_signature);
+                if let Some(document_id) =
+                    existing.remove(&(identity.email.clone(), identity.name.clone()))
+                {
+                    update.append(
+                        Id::from(document_id),
+                        set_object([
+                            (Property::ReplyTo, reply_to),
+                            (Property::Bcc, bcc),
+                            (Property::TextSignature, text_signature),
+                            (Property::HtmlSignature, html_signature),
+                        ]),
+                    );
+                } else {
+                    create.append(
+                        format!("i{idx}"),
+                        set_object([
+                            (Property::Name, Value::Text(identity.name)),
+                            (Property::Email, Value::Text(identity.email)),
+                            (Property::ReplyTo, reply_to),
+                            (Property::Bcc, bcc),
+                            (Property::TextSignature, text_signature),
+                            (Property::HtmlSignature, html_signature),
+                        ]),
+                    );
+                }
+            }
+            let response = self
+                .identity_set(SetRequest {
+                    account_id: Id::from(account_id),
+                    if_in_state: None,
+                    create: (!create.is_empty()).then_some(create),
+                    update: (!update.is_empty()).then_some(update),
+                    destroy: None,
+                    arguments: set::RequestArguments::Identity,
+                })
+                .await?;
+            self.import_response("Identity", response, &mut errors)
+                .await;
+        }
+
+        // Import Sieve scripts, blobs are only written for scripts that compile
+        if !bundle.sieve_scripts.is_empty() {
+            let mut create = VecMap::with_capacity(bundle.sieve_scripts.len());
+            let mut update = VecMap::new();
+            let mut activate_id = None;
+            for (idx, script) in bundle.sieve_scripts.into_iter().enumerate() {
+                if let Err(err) = self
+                    .core
+                    .sieve
+                    .untrusted_compiler
+                    .compile(script.script.as_bytes())
+                {
+                    errors.push(format!("SieveScript {:?} not imported: {err}", script.name));
+                    continue;
+                }
+                let existing_id = self
+                    .filter(
+                        account_id,
+                        Collection::SieveScript,
+                        vec![Filter::eq(Property::Name, &script.name)],
+                    )
+                    .await
+                    .caused_by(trc::location!())?
+                    .results
+                    .min();
+                let blob_id = self
+                    .put_blob(account_id, script.script.as_bytes(), true)
+                    .await
+                    .caused_by(trc::location!())?;
+                if let Some(document_id) = existing_id {
+                    if script.is_active {
+                        activate_id = Some(MaybeReference::Value(Id::from(document_id)));
+                    }
+                    update.append(
+                        Id::from(document_id),
+                        set_object([(Property::BlobId, Value::BlobId(blob_id))]),
+                    );
+                } else {
+                    let create_id = format!("s{idx}");
+                    if script.is_active {
+                        activate_id = Some(MaybeReference::Reference(create_id.clone()));
+                    }
+                    create.append(
+                        create_id,
+                        set_object([
+                            (Property::Name, Value::Text(script.name)),
+                            (Property::BlobId, Value::BlobId(blob_id)),
+                        ]),
+                    );
+                }
+            }
+            if !create.is_empty() || !update.is_empty() {
+                let response = self
+                    .sieve_script_set(
+                        SetRequest {
+                            account_id: Id::from(account_id),
+                            if_in_state: None,
+                            create: (!create.is_empty()).then_some(create),
+                            update: (!update.is_empty()).then_some(update),
+                            destroy: None,
+                            arguments: sieve::SetArguments {
+                                on_success_activate_script: activate_id,
+                                on_success_deactivate_script: None,
+                            },
+                        },
+                        &access_token,
+                        session,
+                    )
+                    .await?;
+                self.import_response("SieveScript", response, &mut errors)
+                    .await;
+            }
+        }
+
+        // Import vacation response
+        if let Some(vacation) = bundle.vacation_response {
+            let object = set_object([
+                (Property::IsEnabled, Value::Bool(vacation.is_enabled)),
+                (Property::FromDate, import_date(vacation.from_date)),
+                (Property::ToDate, import_date(vacation.to_date)),
+                (Property::Subject, vacation.subject.into()),
+                (Property::TextBody, vacation.text_body.into()),
+                (Property::HtmlBody, vacation.html_body.into()),
+            ]);
+            let (create, update) = if self
+                .get_vacation_sieve_script_id(account_id)
+                .await?
+                .is_some()
+            {
+                (None, Some(VecMap::from_iter([(Id::singleton(), object)])))
+            } else {
+                (Some(VecMap::from_iter([("v".to_string(), object)])), None)
+            };
+            let response = self
+                .vacation_response_set(
+                    SetRequest {
+                        account_id: Id::from(account_id),
+                        if_in_state: None,
+                        create,
+                        update,
+                        destroy: None,
+                        arguments: set::RequestArguments::VacationResponse,
+                    },
+                    &access_token,
+                )
+                .await?;
+            self.import_response("VacationResponse", response, &mut errors)
+                .await;
+        }
+
+        Ok(errors)
+    }
+
+    async fn import_response(&self, object: &str, response: SetResponse, errors: &mut Vec<String>) {
+        for (id, err) in response.not_created {
+            errors.push(format!(
+                "{object} {id} not created: {} {}",
+                err.type_.as_str(),
+                err.description.unwrap_or_default()
+            ));
+        }
+        for (id, err) in response.not_updated {
+            errors.push(format!(
+                "{object} {id} not updated: {} {}",
+                err.type_.as_str(),
+                err.description.unwrap_or_default()
+            ));
+        }
+        if let Some(state_change) = response.state_change {
+            self.broadcast_state_change(state_change).await;
+        }
+    }
+}
+
+fn set_object<const N: usize>(properties: [(Property, Value); N]) -> Object<SetValue> {
+    Object {
+        properties: properties
+            .into_iter()
+            .map(|(property, value)| (property, SetValue::Value(value)))
+            .collect(),
+    }
+}
+
+fn export_addresses(value: Value) -> Option<Vec<EmailAddressBundle>> {
+    value.try_unwrap_list().map(|addresses| {
+        addresses
+            .into_iter()
+            .filter_map(|address| {
+                let mut address = address.try_unwrap_object()?;
+                Some(EmailAddressBundle {
+                    name: address.remove(&Property::Name).try_unwrap_string(),
+                    email: address.remove(&Property::Email).try_unwrap_string()?,
+                })
+            })
+            .collect()
+    })
+}
+
+fn import_addresses(addresses: Option<Vec<EmailAddressBundle>>) -> Value {
+    addresses.map_or(Value::Null, |addresses| {
+        Value::List(
+            addresses
+                .into_iter()
+                .map(|address| {
+                    Value::Object(
+                        Object::with_capacity(2)
+                            .with_property(Property::Name, address.name)
+                            .with_property(Property::Email, address.email),
+                    )
+                })
+                .collect(),
+        )
+    })
+}
+
+fn import_date(date: Option<String>) -> Value {
+    date.and_then(|date| DateTime::parse_rfc3339(&date))
+        .map_or(Value::Null, |date| {
+            Value::Date(UTCDate::from_timestamp(date.to_timestamp()))
+        })
+}
@@ -0,0 +1,75 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use directory::Permission;
+use hyper::Method;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    api::{http::ToHttpResponse, HttpRequest, HttpResponse, JsonResponse},
+    JMAP,
+};
+
+use super::decode_path_element;
+
+#[derive(Debug, Deserialize)]
+struct UpdateCache {
+    capacity: usize,
+}
+
+impl JMAP {
+    pub async fn handle_manage_cache(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        match (path.get(1), req.method()) {
+            (None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::MetricsList)?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": self.inner.caches.stats(),
+                }))
+                .into_http_response())
+            }
+            (Some(name), &Method::PATCH) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SettingsUpdate)?;
+
+                let update =
+                    serde_json::from_slice::<UpdateCache>(body.as_deref().unwrap_or_default())
+                        .map_err(|err| {
+                            trc::EventType::Resource(trc::ResourceEvent::BadParameters)
+                                .from_json_error(err)
+                        })?;
+                if update.capacity == 0 {
+                    return Err(trc::ResourceEvent::BadParameters
+                        .into_err()
+                        .details("Capacity must be greater than zero"));
+                }
+
+                // Capacities set at runtime are not persisted, use cache.<name>.size instead
+                match self
+                    .inner
+                    .caches
+                    .set_capacity(decode_path_element(name).as_ref(), update.capacity)
+                {
+                    Some(stats) => Ok(JsonResponse::new(json!({
+                        "data": stats,
+                    }))
+                    .into_http_response()),
+                    None => Err(trc::ResourceEvent::NotFound.into_err()),
+                }
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+}
@@ -14,7 +14,7 @@ use hyper::Method;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sha1::Digest;
-use utils::config::Config;
+use utils::config::{Config, ConfigKey};
 use x509_parser::parse_x509_certificate;
 
 use crate::{
@@ -59,6 +59,57 @@ impl JMAP {
                 }))
                 .into_http_response())
             }
+            ("mta-sts", Some(domain), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::DomainGet)?;
+
+                let domain = decode_path_element(domain).to_lowercase();
+                let policy = self
+                    .core
+                    .build_mta_sts_policy(&domain)
+                    .ok_or_else(|| trc::ResourceEvent::NotFound.into_err())?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": mta_sts_record(&domain, &policy.id),
+                }))
+                .into_http_response())
+            }
+            ("mta-sts", Some(domain), &Method::POST) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::DomainUpdate)?;
+
+                let domain = decode_path_element(domain).to_lowercase();
+                if self.core.build_mta_sts_policy(&domain).is_none() {
+                    return Err(trc::ResourceEvent::NotFound.into_err());
+                }
+
+                // Bump the policy id of the domain, or the default policy if it has none
+                let prefix = if self.core.smtp.session.mta_sts_domains.contains_key(&domain) {
+                    format!("session.mta-sts.domain.{domain}")
+                } else {
+                    "session.mta-sts".to_string()
+                };
+                let id = chrono::Utc::now().format("%Y%m%d%H%M%S").to_string();
+                self.core
+                    .storage
+                    .config
+                    .set([ConfigKey {
+                        key: format!("{prefix}.id"),
+                        value: id.clone(),
+                    }])
+                    .await?;
+
+                // Reload configuration
+                if let Some(core) = self.core.reload().await?.new_core {
+                    self.shared_core.store(core.into());
+                    self.inner.increment_config_version();
+                }
+
+                Ok(JsonResponse::new(json!({
+                    "data": mta_sts_record(&domain, &id),
+                }))
+                .into_http_response())
+            }
             _ => Err(trc::ResourceEvent::NotFound.into_err()),
         }
     }
@@ -210,17 +261,13 @@ impl JMAP {
             });
 
             // Add MTA-STS records
-            if let Some(policy) = self.core.build_mta_sts_policy() {
+            if let Some(policy) = self.core.build_mta_sts_policy(domain_name) {
                 records.push(DnsRecord {
                     typ: "CNAME".to_string(),
                     name: format!("mta-sts.{domain_name}."),
                     content: format!("{server_name}."),
                 });
-                records.push(DnsRecord {
-                    typ: "TXT".to_string(),
-                    name: format!("_mta-sts.{domain_name}."),
-                    content: format!("v=STSv1; id={}", policy.id),
-                });
+                records.push(mta_sts_record(domain_name, &policy.id));
             }
         }
 
@@ -288,3 +335,11 @@ impl JMAP {
         Ok(records)
     }
 }
+
+fn mta_sts_record(domain_name: &str, id: &str) -> DnsRecord {
+    DnsRecord {
+        typ: "TXT".to_string(),
+        name: format!("_mta-sts.{domain_name}."),
+        content: format!("v=STSv1; id={id}"),
+    }
+}
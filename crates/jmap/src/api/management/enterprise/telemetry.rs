@@ -202,6 +202,7 @@ impl JMAP {
                     content_type: "text/event-stream".into(),
                     content_disposition: "".into(),
                     cache_control: "no-store".into(),
+                    headers: vec![],
                     body: HttpResponseBody::Stream(BoxBody::new(StreamBody::new(
                         async_stream::stream! {
                             let mut last_message = Instant::now() - throttle;
@@ -469,6 +470,7 @@ impl JMAP {
                     content_type: "text/event-stream".into(),
                     content_disposition: "".into(),
                     cache_control: "no-store".into(),
+                    headers: vec![],
                     body: HttpResponseBody::Stream(BoxBody::new(StreamBody::new(
                         async_stream::stream! {
 
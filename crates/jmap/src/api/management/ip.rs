@@ -0,0 +1,120 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{
+    auth::AccessToken,
+    listener::blocked::{IpList, IpListEntry},
+};
+use directory::Permission;
+use hyper::Method;
+use serde_json::json;
+use utils::config::utils::ParseValue;
+
+use crate::{
+    api::{http::ToHttpResponse, HttpRequest, HttpResponse, JsonResponse},
+    JMAP,
+};
+
+use super::decode_path_element;
+
+impl JMAP {
+    pub async fn handle_manage_ip(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        match (path.get(1).copied(), path.get(2).copied(), req.method()) {
+            (Some("feed"), Some(feed_id), &Method::POST) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SettingsUpdate)?;
+
+                let feed_id = decode_path_element(feed_id);
+                let feed = self
+                    .core
+                    .network
+                    .ip_feeds
+                    .iter()
+                    .find(|feed| feed.id == feed_id)
+                    .ok_or_else(|| trc::ResourceEvent::NotFound.into_err())?;
+                let total = self.core.import_ip_feed(feed).await?;
+                self.reload_ip_lists().await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": total,
+                }))
+                .into_http_response())
+            }
+            (Some(list), None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SettingsList)?;
+
+                let items = self.core.list_ip_entries(parse_list(list)?).await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "total": items.len(),
+                        "items": items,
+                    },
+                }))
+                .into_http_response())
+            }
+            (Some(list), None, &Method::POST) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SettingsUpdate)?;
+
+                let list = parse_list(list)?;
+                let entries =
+                    serde_json::from_slice::<Vec<IpListEntry>>(body.as_deref().unwrap_or_default())
+                        .map_err(|err| {
+                            trc::EventType::Resource(trc::ResourceEvent::BadParameters)
+                                .from_json_error(err)
+                        })?;
+                self.core.add_ip_entries(list, entries).await?;
+                self.reload_ip_lists().await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            (Some(list), Some(address), &Method::DELETE) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SettingsDelete)?;
+
+                let list = parse_list(list)?;
+                if !self
+                    .core
+                    .remove_ip_entry(list, decode_path_element(address).as_ref())
+                    .await?
+                {
+                    return Err(trc::ResourceEvent::NotFound.into_err());
+                }
+                self.reload_ip_lists().await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+
+    async fn reload_ip_lists(&self) -> trc::Result<()> {
+        self.core.reload_ip_lists().await?;
+
+        // Increment version counter so cluster peers reload their lists
+        self.core.network.blocked_ips.increment_version();
+
+        Ok(())
+    }
+}
+
+fn parse_list(list: &str) -> trc::Result<IpList> {
+    IpList::parse_value(list).map_err(|_| trc::ResourceEvent::NotFound.into_err())
+}
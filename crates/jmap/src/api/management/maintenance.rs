@@ -0,0 +1,122 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use directory::Permission;
+use hyper::Method;
+use serde::Deserialize;
+use serde_json::json;
+use utils::config::ConfigKey;
+
+use crate::{
+    api::{http::ToHttpResponse, HttpRequest, HttpResponse, JsonResponse},
+    JMAP,
+};
+
+#[derive(Debug, Deserialize)]
+struct UpdateMaintenance {
+    enable: Option<bool>,
+    accounts: Option<Vec<String>>,
+    message: Option<String>,
+}
+
+impl JMAP {
+    pub async fn handle_manage_maintenance(
+        &self,
+        req: &HttpRequest,
+        body: Option<Vec<u8>>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        match *req.method() {
+            Method::GET => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SettingsList)?;
+
+                let accounts = self
+                    .core
+                    .storage
+                    .config
+                    .list("server.maintenance.accounts", true)
+                    .await?
+                    .into_values()
+                    .collect::<Vec<_>>();
+                let maintenance = &self.core.storage.maintenance;
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "enable": maintenance.enable,
+                        "accounts": accounts,
+                        "message": maintenance.message(),
+                    },
+                }))
+                .into_http_response())
+            }
+            Method::POST => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::SettingsUpdate)?;
+
+                let update = serde_json::from_slice::<UpdateMaintenance>(
+                    body.as_deref().unwrap_or_default(),
+                )
+                .map_err(|err| {
+                    trc::EventType::Resource(trc::ResourceEvent::BadParameters).from_json_error(err)
+                })?;
+                let config = &self.core.storage.config;
+
+                if let Some(enable) = update.enable {
+                    config
+                        .set([ConfigKey {
+                            key: "server.maintenance.enable".to_string(),
+                            value: enable.to_string(),
+                        }])
+                        .await?;
+                }
+                if let Some(accounts) = update.accounts {
+                    config.clear_prefix("server.maintenance.accounts").await?;
+                    config
+                        .set(
+                            accounts
+                                .into_iter()
+                                .map(|name| name.trim().to_lowercase())
+                                .filter(|name| !name.is_empty())
+                                .enumerate()
+                                .map(|(pos, name)| ConfigKey {
+                                    key: format!("server.maintenance.accounts.{pos:04}"),
+                                    value: name,
+                                }),
+                        )
+                        .await?;
+                }
+                match update.message.as_deref().map(str::trim) {
+                    Some("") => {
+                        config.clear("server.maintenance.message").await?;
+                    }
+                    Some(message) => {
+                        config
+                            .set([ConfigKey {
+                                key: "server.maintenance.message".to_string(),
+                                value: message.to_string(),
+                            }])
+                            .await?;
+                    }
+                    None => (),
+                }
+
+                // Apply the new settings
+                if let Some(core) = self.core.reload().await?.new_core {
+                    self.shared_core.store(core.into());
+                    self.inner.increment_config_version();
+                }
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+}
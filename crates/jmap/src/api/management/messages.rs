@@ -0,0 +1,363 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::io::{Cursor, Read};
+
+use common::auth::AccessToken;
+use directory::Permission;
+use jmap_proto::types::{keyword::Keyword, state::StateChange, type_state::DataType};
+use mail_auth::zip;
+use mail_parser::{mailbox::mbox::MessageIterator, DateTime, MessageParser};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use trc::AddContext;
+use utils::url_params::UrlParams;
+
+use crate::{
+    api::{
+        http::{HttpSessionData, ToHttpResponse},
+        HttpRequest, HttpResponse, JsonResponse,
+    },
+    email::ingest::{IngestEmail, IngestSource},
+    mailbox::INBOX_ID,
+    JMAP,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+pub enum ImportMessageResult {
+    Success { name: String, id: String, uid: u32 },
+    Error { name: String, reason: String },
+}
+
+struct BatchMessage {
+    contents: Vec<u8>,
+    received_at: Option<u64>,
+    keywords: Vec<Keyword>,
+}
+
+impl JMAP {
+    pub async fn handle_import_messages(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        session: &HttpSessionData,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        // Validate the access token
+        access_token.assert_has_permission(Permission::IndividualUpdate)?;
+
+        let account_id = self.bundle_account_id(&path, access_token).await?;
+        let params = UrlParams::new(req.uri().query());
+        let body = body.unwrap_or_default();
+        let messages = match params.get("format").unwrap_or("mbox") {
+            "mbox" => parse_mbox(body),
+            "zip" => parse_zip(
+                body,
+                self.core.jmap.mail_max_size,
+                self.core.jmap.mail_import_max_size,
+            )?,
+            _ => return Err(trc::ResourceEvent::BadParameters.into_err()),
+        };
+
+        // Obtain the destination mailbox, creating it if necessary
+        self.mailbox_get_or_create(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let mailbox_id = match params.get("mailbox") {
+            Some(mailbox) if !mailbox.is_empty() => {
+                match self
+                    .mailbox_create_path(account_id, mailbox)
+                    .await
+                    .caused_by(trc::location!())?
+                {
+                    Some((document_id, change_id)) => {
+                        if let Some(change_id) = change_id {
+                            self.broadcast_state_change(
+                                StateChange::new(account_id)
+                                    .with_change(DataType::Mailbox, change_id),
+                            )
+                            .await;
+                        }
+                        document_id
+                    }
+                    None => {
+                        return Err(trc::ResourceEvent::BadParameters
+                            .into_err()
+                            .details("Invalid mailbox path"))
+                    }
+                }
+            }
+            _ => INBOX_ID,
+        };
+
+        Ok(JsonResponse::new(json!({
+            "data": self.import_messages(account_id, mailbox_id, messages, session).await?,
+        }))
+        .into_http_response())
+    }
+
+    // Messages are ingested one at a time so IMAP UIDs follow the batch order.
+    async fn import_messages(
+        &self,
+        account_id: u32,
+        mailbox_id: u32,
+        messages: Vec<(String, Result<BatchMessage, &'static str>)>,
+        session: &HttpSessionData,
+    ) -> trc::Result<Vec<ImportMessageResult>> {
+        let resource_token = self
+            .get_resource_token(&AccessToken::from_id(u32::MAX), account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let mut results = Vec::with_capacity(messages.len());
+        let mut last_change_id = None;
+
+        for (name, message) in messages {
+            let message = match message {
+                Ok(message) => message,
+                Err(reason) => {
+                    results.push(ImportMessageResult::Error {
+                        name,
+                        reason: reason.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            match self
+                .email_ingest(IngestEmail {
+                    raw_message: &message.contents,
+                    message: MessageParser::new().parse(&message.contents),
+                    resource: resource_token.clone(),
+                    mailbox_ids: vec![mailbox_id],
+                    keywords: message.keywords,
+                    received_at: message.received_at,
+                    source: IngestSource::Jmap,
+                    encrypt: self.core.jmap.encrypt && self.core.jmap.encrypt_append,
+                    session_id: session.session_id,
+                })
+                .await
+            {
+                Ok(email) => {
+                    last_change_id = Some(email.change_id);
+                    results.push(ImportMessageResult::Success {
+                        name,
+                        id: email.id.to_string(),
+                        uid: email.imap_uids.first().copied().unwrap_or_default(),
+                    });
+                }
+                Err(mut err)
+                    if err.matches(trc::EventType::MessageIngest(
+                        trc::MessageIngestEvent::Error,
+                    )) =>
+                {
+                    results.push(ImportMessageResult::Error {
+                        name,
+                        reason: err
+                            .take_value(trc::Key::Reason)
+                            .and_then(|v| v.into_string())
+                            .unwrap()
+                            .into_owned(),
+                    });
+                }
+                Err(err) if err.matches(trc::EventType::Limit(trc::LimitEvent::Quota)) => {
+                    results.push(ImportMessageResult::Error {
+                        name,
+                        reason: "Quota exceeded".to_string(),
+                    });
+                }
+                Err(err) => {
+                    return Err(err.caused_by(trc::location!()));
+                }
+            }
+        }
+
+        if let Some(change_id) = last_change_id {
+            self.broadcast_state_change(
+                StateChange::new(account_id)
+                    .with_change(DataType::Email, change_id)
+                    .with_change(DataType::Mailbox, change_id)
+                    .with_change(DataType::Thread, change_id),
+            )
+            .await;
+        }
+
+        Ok(results)
+    }
+}
+
+fn parse_mbox(body: Vec<u8>) -> Vec<(String, Result<BatchMessage, &'static str>)> {
+    MessageIterator::new(Cursor::new(body))
+        .enumerate()
+        .map(|(idx, message)| {
+            (
+                (idx + 1).to_string(),
+                message
+                    .map(|message| {
+                        let received_at = Some(message.internal_date()).filter(|&date| date > 0);
+                        let contents = message.unwrap_contents();
+                        BatchMessage {
+                            keywords: header_keywords(&contents),
+                            contents,
+                            received_at,
+                        }
+                    })
+                    .map_err(|_| "Failed to parse message from mbox file"),
+            )
+        })
+        .collect()
+}
+
+// Entries are read with a size limit, the sizes declared by the archive are not trusted.
+fn parse_zip(
+    body: Vec<u8>,
+    max_message_size: usize,
+    max_total_size: usize,
+) -> trc::Result<Vec<(String, Result<BatchMessage, &'static str>)>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(body)).map_err(|err| {
+        trc::ResourceEvent::BadParameters
+            .into_err()
+            .reason(err)
+            .details("Failed to read zip archive")
+    })?;
+    let mut messages = Vec::with_capacity(archive.len());
+    let mut total_size = 0;
+
+    for idx in 0..archive.len() {
+        let mut file = match archive.by_index(idx) {
+            Ok(file) => file,
+            Err(_) => {
+                messages.push(((idx + 1).to_string(), Err("Failed to read zip entry")));
+                continue;
+            }
+        };
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        let mut contents = Vec::new();
+        match (&mut file)
+            .take(max_message_size as u64 + 1)
+            .read_to_end(&mut contents)
+        {
+            Ok(size) if size > max_message_size => {
+                messages.push((name, Err("Message exceeds maximum size")));
+                continue;
+            }
+            Ok(size) => {
+                total_size += size;
+                if total_size > max_total_size {
+                    return Err(trc::ResourceEvent::BadParameters
+                        .into_err()
+                        .details("Zip archive exceeds maximum uncompressed size"));
+                }
+            }
+            Err(_) => {
+                messages.push((name, Err("Failed to decompress zip entry")));
+                continue;
+            }
+        }
+
+        // Maildir file names carry the delivery time and flags
+        let file_name = name.rsplit('/').next().unwrap_or_default();
+        let received_at = file_name
+            .split_once('.')
+            .and_then(|(timestamp, _)| timestamp.parse::<u64>().ok())
+            .or_else(|| {
+                file.last_modified()
+                    .filter(|date| date.year() > 1980)
+                    .map(|date| {
+                        DateTime {
+                            year: date.year(),
+                            month: date.month(),
+                            day: date.day(),
+                            hour: date.hour(),
+                            minute: date.minute(),
+                            second: date.second(),
+                            tz_before_gmt: false,
+                            tz_hour: 0,
+                            tz_minute: 0,
+                        }
+                        .to_timestamp() as u64
+                    })
+            });
+        let mut keywords = header_keywords(&contents);
+        if let Some((_, flags)) = file_name
+            .rsplit_once(":2,")
+            .or_else(|| file_name.rsplit_once("!2,"))
+        {
+            for flag in flags.chars() {
+                let keyword = match flag {
+                    'S' => Keyword::Seen,
+                    'R' => Keyword::Answered,
+                    'F' => Keyword::Flagged,
+                    'T' => Keyword::Deleted,
+                    'D' => Keyword::Draft,
+                    'P' => Keyword::Forwarded,
+                    _ => continue,
+                };
+                if !keywords.contains(&keyword) {
+                    keywords.push(keyword);
+                }
+            }
+        }
+
+        messages.push((
+            name,
+            Ok(BatchMessage {
+                contents,
+                received_at,
+                keywords,
+            }),
+        ));
+    }
+
+    Ok(messages)
+}
+
+// Keywords stored in headers by mbox based servers (Status, X-Status and X-Keywords)
+fn header_keywords(contents: &[u8]) -> Vec<Keyword> {
+    let mut keywords = Vec::new();
+    let headers = if let Some(headers) = MessageParser::new().parse_headers(contents) {
+        headers
+    } else {
+        return keywords;
+    };
+
+    for header in headers.headers() {
+        let value = header.value().as_text().unwrap_or_default();
+        let name = header.name.as_str();
+        if name.eq_ignore_ascii_case("Status") || name.eq_ignore_ascii_case("X-Status") {
+            for flag in value.chars() {
+                let keyword = match flag {
+                    'R' => Keyword::Seen,
+                    'A' => Keyword::Answered,
+                    'F' => Keyword::Flagged,
+                    'T' => Keyword::Draft,
+                    'D' => Keyword::Deleted,
+                    _ => continue,
+                };
+                if !keywords.contains(&keyword) {
+                    keywords.push(keyword);
+                }
+            }
+        } else if name.eq_ignore_ascii_case("X-Keywords") {
+            for keyword in value
+                .split([',', ' '])
+                .filter(|keyword| !keyword.is_empty())
+            {
+                let keyword = Keyword::from(keyword.to_ascii_lowercase());
+                if !keywords.contains(&keyword) {
+                    keywords.push(keyword);
+                }
+            }
+        }
+    }
+
+    keywords
+}
@@ -4,17 +4,24 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+pub mod bundle;
+pub mod cache;
 pub mod dkim;
 pub mod dns;
 #[cfg(feature = "enterprise")]
 pub mod enterprise;
+pub mod ip;
 pub mod log;
+pub mod maintenance;
+pub mod messages;
 pub mod principal;
 pub mod queue;
 pub mod reload;
+pub mod replication;
 pub mod report;
 pub mod settings;
 pub mod sieve;
+pub mod spam;
 pub mod stores;
 
 use std::{borrow::Cow, str::FromStr, sync::Arc};
@@ -71,16 +78,46 @@ impl JMAP {
                     .await
             }
             "reports" => self.handle_manage_reports(req, path, &access_token).await,
-            "principal" => {
-                self.handle_manage_principal(req, path, body, &access_token)
+            "cache" => {
+                self.handle_manage_cache(req, path, body, &access_token)
                     .await
             }
+            "principal" => match (path.get(2).copied(), req.method()) {
+                (Some("export"), &Method::GET) => {
+                    self.handle_export_account(path, &access_token).await
+                }
+                (Some("import"), &Method::POST) => {
+                    self.handle_import_account(path, body, session, &access_token)
+                        .await
+                }
+                (Some("messages"), &Method::POST) => {
+                    self.handle_import_messages(req, path, body, session, &access_token)
+                        .await
+                }
+                _ => {
+                    self.handle_manage_principal(req, path, body, &access_token)
+                        .await
+                }
+            },
             "dns" => self.handle_manage_dns(req, path, &access_token).await,
+            "ip" => self.handle_manage_ip(req, path, body, &access_token).await,
             "store" => {
                 self.handle_manage_store(req, path, body, session, &access_token)
                     .await
             }
             "reload" => self.handle_manage_reload(req, path, &access_token).await,
+            "maintenance" => {
+                self.handle_manage_maintenance(req, body, &access_token)
+                    .await
+            }
+            "replication" => {
+                self.handle_manage_replication(req, path, body, &access_token)
+                    .await
+            }
+            "spam-filter" => {
+                self.handle_manage_spam_filter(req, path, body, &access_token)
+                    .await
+            }
             "dkim" => {
                 self.handle_manage_dkim(req, path, body, &access_token)
                     .await
@@ -89,7 +126,12 @@ impl JMAP {
             "logs" if req.method() == Method::GET => {
                 self.handle_view_logs(req, &access_token).await
             }
-            "sieve" => self.handle_run_sieve(req, path, body, &access_token).await,
+            "sieve" => match (path.get(1).copied(), req.method()) {
+                (Some("check"), &Method::POST) => {
+                    self.handle_check_sieve(body, &access_token).await
+                }
+                _ => self.handle_run_sieve(req, path, body, &access_token).await,
+            },
             "restart" if req.method() == Method::GET => {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::Restart)?;
@@ -115,6 +157,31 @@ impl JMAP {
 
                     self.handle_crypto_get(access_token).await
                 }
+                ("spam-filter", &Method::GET) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::SpamAllowContacts)?;
+
+                    self.handle_account_spam_filter_get(access_token).await
+                }
+                ("spam-filter", &Method::POST) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::SpamAllowContacts)?;
+
+                    self.handle_account_spam_filter_post(access_token, body)
+                        .await
+                }
+                ("undo-send", &Method::GET) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::JmapEmailSubmissionSet)?;
+
+                    self.handle_account_undo_send_get(access_token).await
+                }
+                ("undo-send", &Method::POST) => {
+                    // Validate the access token
+                    access_token.assert_has_permission(Permission::JmapEmailSubmissionSet)?;
+
+                    self.handle_account_undo_send_post(access_token, body).await
+                }
                 ("auth", &Method::GET) => {
                     // Validate the access token
                     access_token.assert_has_permission(Permission::ManagePasswords)?;
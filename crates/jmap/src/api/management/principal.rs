@@ -96,6 +96,7 @@ impl JMAP {
                 }
 
                 // Create principal
+                let is_individual = matches!(principal.typ(), Type::Individual);
                 let result = self
                     .core
                     .storage
@@ -103,6 +104,11 @@ impl JMAP {
                     .create_principal(principal, access_token.tenant.map(|t| t.id))
                     .await?;
 
+                // Provision the new account
+                if is_individual {
+                    self.account_apply_template(result).await;
+                }
+
                 Ok(JsonResponse::new(json!({
                     "data": result,
                 }))
@@ -284,6 +290,13 @@ impl JMAP {
                             }
                         })?;
 
+                        // Members of deleted lists lose their identities
+                        let sync_ids = if matches!(typ, Type::List | Type::Group) {
+                            self.core.storage.data.get_members(account_id).await?
+                        } else {
+                            vec![]
+                        };
+
                         // Delete account
                         self.core
                             .storage
@@ -298,6 +311,15 @@ impl JMAP {
 
                         // Remove entries from cache
                         self.inner.sessions.retain(|_, id| id.item != account_id);
+                        match typ {
+                            Type::Individual => {
+                                self.core.invalidate_access_tokens([account_id]);
+                            }
+                            Type::Group | Type::List | Type::Role | Type::Tenant => {
+                                self.core.invalidate_all_access_tokens();
+                            }
+                            _ => (),
+                        }
 
                         if matches!(typ, Type::Role | Type::Tenant) {
                             // Update permissions cache
@@ -308,6 +330,8 @@ impl JMAP {
                                 .fetch_add(1, Ordering::Relaxed);
                         }
 
+                        self.identity_sync_accounts(sync_ids).await;
+
                         Ok(JsonResponse::new(json!({
                             "data": (),
                         }))
@@ -340,7 +364,9 @@ impl JMAP {
                         let mut needs_assert = false;
                         let mut expire_session = false;
                         let mut expire_token = false;
+                        let mut expire_all_tokens = false;
                         let mut is_role_change = false;
+                        let mut needs_identity_sync = false;
 
                         for change in &changes {
                             match change.field {
@@ -348,16 +374,26 @@ impl JMAP {
                                     expire_session = true;
                                     needs_assert = true;
                                 }
+                                PrincipalField::Emails => {
+                                    needs_identity_sync = true;
+                                }
                                 PrincipalField::Name
-                                | PrincipalField::Emails
                                 | PrincipalField::Quota
                                 | PrincipalField::UsedQuota
                                 | PrincipalField::Description
                                 | PrincipalField::Type
-                                | PrincipalField::Picture
-                                | PrincipalField::MemberOf
+                                | PrincipalField::Picture => (),
+                                PrincipalField::MemberOf
                                 | PrincipalField::Members
-                                | PrincipalField::Lists => (),
+                                | PrincipalField::Lists => {
+                                    // Group changes affect the access tokens of all members
+                                    needs_identity_sync = true;
+                                    if typ == Type::Individual {
+                                        expire_token = true;
+                                    } else {
+                                        expire_all_tokens = true;
+                                    }
+                                }
                                 PrincipalField::Tenant => {
                                     // Tenants are not allowed to change their tenantId
                                     if access_token.tenant.is_some() {
@@ -386,6 +422,17 @@ impl JMAP {
                             self.assert_supported_directory()?;
                         }
 
+                        // Identities are synchronized with the addresses of the lists
+                        // an account belongs to, so members are resynced before and after
+                        let mut sync_ids = Vec::new();
+                        if needs_identity_sync {
+                            if typ == Type::Individual {
+                                sync_ids.push(account_id);
+                            } else if matches!(typ, Type::List | Type::Group) {
+                                sync_ids = self.core.storage.data.get_members(account_id).await?;
+                            }
+                        }
+
                         // Update principal
                         self.core
                             .storage
@@ -411,9 +458,20 @@ impl JMAP {
                                 .fetch_add(1, Ordering::Relaxed);
                         }
 
-                        if expire_token {
-                            self.core.security.access_tokens.remove(&account_id);
+                        if expire_all_tokens {
+                            self.core.invalidate_all_access_tokens();
+                        } else if expire_token {
+                            self.core.invalidate_access_tokens([account_id]);
+                        }
+
+                        if needs_identity_sync && matches!(typ, Type::List | Type::Group) {
+                            for member_id in self.core.storage.data.get_members(account_id).await? {
+                                if !sync_ids.contains(&member_id) {
+                                    sync_ids.push(member_id);
+                                }
+                            }
                         }
+                        self.identity_sync_accounts(sync_ids).await;
 
                         Ok(JsonResponse::new(json!({
                             "data": (),
@@ -590,11 +648,14 @@ impl JMAP {
     pub fn assert_supported_directory(&self) -> trc::Result<()> {
         let class = match &self.core.storage.directory.store {
             DirectoryInner::Internal(_) => return Ok(()),
+            DirectoryInner::Composite(composite) if composite.has_internal() => return Ok(()),
             DirectoryInner::Ldap(_) => "LDAP",
             DirectoryInner::Sql(_) => "SQL",
             DirectoryInner::Imap(_) => "IMAP",
             DirectoryInner::Smtp(_) => "SMTP",
             DirectoryInner::Memory(_) => "In-Memory",
+            DirectoryInner::Http(_) => "HTTP",
+            DirectoryInner::Composite(_) => "Composite",
         };
 
         Err(manage::unsupported(format!(
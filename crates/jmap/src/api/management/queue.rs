@@ -19,7 +19,7 @@ use mail_auth::{
 use mail_parser::DateTime;
 use serde::{Deserializer, Serializer};
 use serde_json::json;
-use smtp::queue::{self, ErrorDetails, HostResponse, QueueId, Status};
+use smtp::queue::{self, ErrorDetails, HostResponse, QueueId, Status, MAIL_HELD};
 use store::{
     write::{key::DeserializeBigEndian, now, Bincode, QueueClass, ReportEvent, ValueClass},
     Deserialize, IterateParams, ValueKey,
@@ -48,6 +48,9 @@ pub struct Message {
     pub priority: i16,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub env_id: Option<String>,
+    #[serde(skip_serializing_if = "is_false")]
+    #[serde(default)]
+    pub held: bool,
     pub blob_hash: String,
 }
 
@@ -160,15 +163,7 @@ impl JMAP {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::MessageQueueList)?;
 
-                let text = params.get("text");
-                let from = params.get("from");
-                let to = params.get("to");
-                let before = params
-                    .parse::<FutureTimestamp>("before")
-                    .map(|t| t.into_inner());
-                let after = params
-                    .parse::<FutureTimestamp>("after")
-                    .map(|t| t.into_inner());
+                let filter = QueueFilter::parse(&params);
                 let page = params.parse::<usize>("page").unwrap_or_default();
                 let limit = params.parse::<usize>("limit").unwrap_or_default();
                 let values = params.has_key("values");
@@ -181,14 +176,10 @@ impl JMAP {
                 let mut result_values = Vec::new();
                 let from_key = ValueKey::from(ValueClass::Queue(QueueClass::Message(range_start)));
                 let to_key = ValueKey::from(ValueClass::Queue(QueueClass::Message(range_end)));
-                let has_filters = text.is_some()
-                    || from.is_some()
-                    || to.is_some()
-                    || before.is_some()
-                    || after.is_some();
                 let mut offset = page.saturating_sub(1) * limit;
                 let mut total = 0;
                 let mut total_returned = 0;
+                let now = now();
                 self.core
                     .storage
                     .data
@@ -199,32 +190,7 @@ impl JMAP {
                             let matches = tenant_domains
                                 .as_ref()
                                 .map_or(true, |domains| message.has_domain(domains))
-                                && (!has_filters
-                                    || (text
-                                        .as_ref()
-                                        .map(|text| {
-                                            message.return_path.contains(text)
-                                                || message
-                                                    .recipients
-                                                    .iter()
-                                                    .any(|r| r.address_lcase.contains(text))
-                                        })
-                                        .unwrap_or_else(|| {
-                                            from.as_ref().map_or(true, |from| {
-                                                message.return_path.contains(from)
-                                            }) && to.as_ref().map_or(true, |to| {
-                                                message
-                                                    .recipients
-                                                    .iter()
-                                                    .any(|r| r.address_lcase.contains(to))
-                                            })
-                                        })
-                                        && before.as_ref().map_or(true, |before| {
-                                            message.next_delivery_event() < *before
-                                        })
-                                        && after.as_ref().map_or(true, |after| {
-                                            message.next_delivery_event() > *after
-                                        })));
+                                && filter.matches(&message, now);
 
                             if matches {
                                 if offset == 0 {
@@ -265,6 +231,69 @@ impl JMAP {
                 }
                 .into_http_response())
             }
+            ("messages", None, &Method::PATCH) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::MessageQueueUpdate)?;
+
+                let action = QueueAction::parse(&params)?;
+                let time = params
+                    .parse::<FutureTimestamp>("at")
+                    .map(|t| t.into_inner())
+                    .unwrap_or_else(now);
+                let item = params.get("filter");
+                let filter = QueueFilter::parse(&params);
+
+                let mut total = 0;
+                for queue_id in self
+                    .matching_messages(&filter, tenant_domains.as_deref())
+                    .await?
+                {
+                    if let Some(message) = self.smtp.read_message(queue_id).await {
+                        if self.update_message(message, action, time, item).await {
+                            total += 1;
+                        }
+                    }
+                }
+
+                if total > 0 {
+                    let _ = self.smtp.inner.queue_tx.send(queue::Event::Reload).await;
+                }
+
+                Ok(JsonResponse::new(json!({
+                        "data": total,
+                }))
+                .into_http_response())
+            }
+            ("messages", None, &Method::DELETE) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::MessageQueueDelete)?;
+
+                // Refuse to empty the whole queue by accident
+                let filter = QueueFilter::parse(&params);
+                if !filter.has_filters() {
+                    return Err(trc::ResourceEvent::BadParameters
+                        .into_err()
+                        .details("At least one filter is required for bulk deletion"));
+                }
+                let item = params.get("filter");
+
+                let mut total = 0;
+                for queue_id in self
+                    .matching_messages(&filter, tenant_domains.as_deref())
+                    .await?
+                {
+                    if let Some(message) = self.smtp.read_message(queue_id).await {
+                        if self.cancel_message(message, item).await {
+                            total += 1;
+                        }
+                    }
+                }
+
+                Ok(JsonResponse::new(json!({
+                        "data": total,
+                }))
+                .into_http_response())
+            }
             ("messages", Some(queue_id), &Method::GET) => {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::MessageQueueGet)?;
@@ -291,13 +320,14 @@ impl JMAP {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::MessageQueueUpdate)?;
 
+                let action = QueueAction::parse(&params)?;
                 let time = params
                     .parse::<FutureTimestamp>("at")
                     .map(|t| t.into_inner())
                     .unwrap_or_else(now);
                 let item = params.get("filter");
 
-                if let Some(mut message) = self
+                if let Some(message) = self
                     .smtp
                     .read_message(queue_id.parse().unwrap_or_default())
                     .await
@@ -307,30 +337,8 @@ impl JMAP {
                             .map_or(true, |domains| message.has_domain(domains))
                     })
                 {
-                    let prev_event = message.next_event().unwrap_or_default();
-                    let mut found = false;
-
-                    for domain in &mut message.domains {
-                        if matches!(
-                            domain.status,
-                            Status::Scheduled | Status::TemporaryFailure(_)
-                        ) && item
-                            .as_ref()
-                            .map_or(true, |item| domain.domain.contains(item))
-                        {
-                            domain.retry.due = time;
-                            if domain.expires > time {
-                                domain.expires = time + 10;
-                            }
-                            found = true;
-                        }
-                    }
-
+                    let found = self.update_message(message, action, time, item).await;
                     if found {
-                        let next_event = message.next_event().unwrap_or_default();
-                        message
-                            .save_changes(&self.smtp, prev_event.into(), next_event.into())
-                            .await;
                         let _ = self.smtp.inner.queue_tx.send(queue::Event::Reload).await;
                     }
 
@@ -346,7 +354,7 @@ impl JMAP {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::MessageQueueDelete)?;
 
-                if let Some(mut message) = self
+                if let Some(message) = self
                     .smtp
                     .read_message(queue_id.parse().unwrap_or_default())
                     .await
@@ -356,74 +364,8 @@ impl JMAP {
                             .map_or(true, |domains| message.has_domain(domains))
                     })
                 {
-                    let mut found = false;
-                    let prev_event = message.next_event().unwrap_or_default();
-
-                    if let Some(item) = params.get("filter") {
-                        // Cancel delivery for all recipients that match
-                        for rcpt in &mut message.recipients {
-                            if rcpt.address_lcase.contains(item) {
-                                rcpt.status = Status::PermanentFailure(HostResponse {
-                                    hostname: ErrorDetails::default(),
-                                    response: smtp_proto::Response {
-                                        code: 0,
-                                        esc: [0, 0, 0],
-                                        message: "Delivery canceled.".to_string(),
-                                    },
-                                });
-                                found = true;
-                            }
-                        }
-                        if found {
-                            // Mark as completed domains without any pending deliveries
-                            for (domain_idx, domain) in message.domains.iter_mut().enumerate() {
-                                if matches!(
-                                    domain.status,
-                                    Status::TemporaryFailure(_) | Status::Scheduled
-                                ) {
-                                    let mut total_rcpt = 0;
-                                    let mut total_completed = 0;
-
-                                    for rcpt in &message.recipients {
-                                        if rcpt.domain_idx == domain_idx {
-                                            total_rcpt += 1;
-                                            if matches!(
-                                                rcpt.status,
-                                                Status::PermanentFailure(_) | Status::Completed(_)
-                                            ) {
-                                                total_completed += 1;
-                                            }
-                                        }
-                                    }
-
-                                    if total_rcpt == total_completed {
-                                        domain.status = Status::Completed(());
-                                    }
-                                }
-                            }
-
-                            // Delete message if there are no pending deliveries
-                            if message.domains.iter().any(|domain| {
-                                matches!(
-                                    domain.status,
-                                    Status::TemporaryFailure(_) | Status::Scheduled
-                                )
-                            }) {
-                                let next_event = message.next_event().unwrap_or_default();
-                                message
-                                    .save_changes(&self.smtp, next_event.into(), prev_event.into())
-                                    .await;
-                            } else {
-                                message.remove(&self.smtp, prev_event).await;
-                            }
-                        }
-                    } else {
-                        message.remove(&self.smtp, prev_event).await;
-                        found = true;
-                    }
-
                     Ok(JsonResponse::new(json!({
-                            "data": found,
+                            "data": self.cancel_message(message, params.get("filter")).await,
                     }))
                     .into_http_response())
                 } else {
@@ -598,6 +540,295 @@ impl JMAP {
             _ => Err(trc::ResourceEvent::NotFound.into_err()),
         }
     }
+
+    async fn matching_messages(
+        &self,
+        filter: &QueueFilter<'_>,
+        tenant_domains: Option<&[String]>,
+    ) -> trc::Result<Vec<QueueId>> {
+        let mut result = Vec::new();
+        let now = now();
+        self.core
+            .storage
+            .data
+            .iterate(
+                IterateParams::new(
+                    ValueKey::from(ValueClass::Queue(QueueClass::Message(0))),
+                    ValueKey::from(ValueClass::Queue(QueueClass::Message(u64::MAX))),
+                )
+                .ascending(),
+                |key, value| {
+                    let message = Bincode::<queue::Message>::deserialize(value)?.inner;
+                    if tenant_domains.map_or(true, |domains| message.has_domain(domains))
+                        && filter.matches(&message, now)
+                    {
+                        result.push(key.deserialize_be_u64(0)?);
+                    }
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok(result)
+    }
+
+    async fn update_message(
+        &self,
+        mut message: queue::Message,
+        action: QueueAction,
+        time: u64,
+        item: Option<&str>,
+    ) -> bool {
+        let prev_event = message.next_event().unwrap_or_default();
+        let mut found = false;
+
+        match action {
+            QueueAction::Retry => {
+                for domain in &mut message.domains {
+                    if matches!(
+                        domain.status,
+                        Status::Scheduled | Status::TemporaryFailure(_)
+                    ) && item.map_or(true, |item| domain.domain.contains(item))
+                    {
+                        domain.retry.due = time;
+                        if domain.expires > time {
+                            domain.expires = time + 10;
+                        }
+                        found = true;
+                    }
+                }
+
+                // Retrying a held message releases it
+                if found {
+                    message.flags &= !MAIL_HELD;
+                }
+            }
+            QueueAction::Hold => {
+                if !message.is_held() && message.next_event().is_some() {
+                    message.flags |= MAIL_HELD;
+                    found = true;
+                }
+            }
+            QueueAction::Release => {
+                if message.is_held() {
+                    message.flags &= !MAIL_HELD;
+                    found = true;
+                }
+            }
+        }
+
+        if found {
+            let next_event = message.next_event().unwrap_or_default();
+            message
+                .save_changes(&self.smtp, prev_event.into(), next_event.into())
+                .await;
+        }
+
+        found
+    }
+
+    async fn cancel_message(&self, mut message: queue::Message, item: Option<&str>) -> bool {
+        let mut found = false;
+        let prev_event = message.next_event().unwrap_or_default();
+
+        if let Some(item) = item {
+            // Cancel delivery for all recipients that match
+            for rcpt in &mut message.recipients {
+                if rcpt.address_lcase.contains(item) {
+                    rcpt.status = Status::PermanentFailure(HostResponse {
+                        hostname: ErrorDetails::default(),
+                        response: smtp_proto::Response {
+                            code: 0,
+                            esc: [0, 0, 0],
+                            message: "Delivery canceled.".to_string(),
+                        },
+                    });
+                    found = true;
+                }
+            }
+            if found {
+                // Mark as completed domains without any pending deliveries
+                for (domain_idx, domain) in message.domains.iter_mut().enumerate() {
+                    if matches!(
+                        domain.status,
+                        Status::TemporaryFailure(_) | Status::Scheduled
+                    ) {
+                        let mut total_rcpt = 0;
+                        let mut total_completed = 0;
+
+                        for rcpt in &message.recipients {
+                            if rcpt.domain_idx == domain_idx {
+                                total_rcpt += 1;
+                                if matches!(
+                                    rcpt.status,
+                                    Status::PermanentFailure(_) | Status::Completed(_)
+                                ) {
+                                    total_completed += 1;
+                                }
+                            }
+                        }
+
+                        if total_rcpt == total_completed {
+                            domain.status = Status::Completed(());
+                        }
+                    }
+                }
+
+                // Delete message if there are no pending deliveries
+                if message.domains.iter().any(|domain| {
+                    matches!(
+                        domain.status,
+                        Status::TemporaryFailure(_) | Status::Scheduled
+                    )
+                }) {
+                    let next_event = message.next_event().unwrap_or_default();
+                    message
+                        .save_changes(&self.smtp, next_event.into(), prev_event.into())
+                        .await;
+                } else {
+                    message.remove(&self.smtp, prev_event).await;
+                }
+            }
+        } else {
+            message.remove(&self.smtp, prev_event).await;
+            found = true;
+        }
+
+        found
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueueAction {
+    Retry,
+    Hold,
+    Release,
+}
+
+impl QueueAction {
+    fn parse(params: &UrlParams<'_>) -> trc::Result<Self> {
+        match params.get("action").unwrap_or("retry") {
+            "retry" => Ok(QueueAction::Retry),
+            "hold" => Ok(QueueAction::Hold),
+            "release" => Ok(QueueAction::Release),
+            action => Err(trc::ResourceEvent::BadParameters
+                .into_err()
+                .details(format!("Invalid queue action {action:?}"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueueStatus {
+    Scheduled,
+    Completed,
+    TemporaryFailure,
+    PermanentFailure,
+    Held,
+}
+
+#[derive(Debug, Default)]
+struct QueueFilter<'x> {
+    text: Option<&'x str>,
+    from: Option<&'x str>,
+    to: Option<&'x str>,
+    domain: Option<&'x str>,
+    status: Option<QueueStatus>,
+    before: Option<u64>,
+    after: Option<u64>,
+    min_age: Option<u64>,
+    max_age: Option<u64>,
+}
+
+impl<'x> QueueFilter<'x> {
+    fn parse(params: &'x UrlParams<'x>) -> Self {
+        QueueFilter {
+            text: params.get("text"),
+            from: params.get("from"),
+            to: params.get("to"),
+            domain: params.get("domain"),
+            status: params.get("status").and_then(|status| match status {
+                "scheduled" => Some(QueueStatus::Scheduled),
+                "completed" => Some(QueueStatus::Completed),
+                "temp_fail" => Some(QueueStatus::TemporaryFailure),
+                "perm_fail" => Some(QueueStatus::PermanentFailure),
+                "held" => Some(QueueStatus::Held),
+                _ => None,
+            }),
+            before: params
+                .parse::<FutureTimestamp>("before")
+                .map(|t| t.into_inner()),
+            after: params
+                .parse::<FutureTimestamp>("after")
+                .map(|t| t.into_inner()),
+            min_age: params.parse::<u64>("min-age"),
+            max_age: params.parse::<u64>("max-age"),
+        }
+    }
+
+    fn has_filters(&self) -> bool {
+        self.text.is_some()
+            || self.from.is_some()
+            || self.to.is_some()
+            || self.domain.is_some()
+            || self.status.is_some()
+            || self.before.is_some()
+            || self.after.is_some()
+            || self.min_age.is_some()
+            || self.max_age.is_some()
+    }
+
+    fn matches(&self, message: &queue::Message, now: u64) -> bool {
+        if !self.has_filters() {
+            return true;
+        }
+
+        let age = now.saturating_sub(message.created);
+
+        self.text
+            .map(|text| {
+                message.return_path.contains(text)
+                    || message
+                        .recipients
+                        .iter()
+                        .any(|r| r.address_lcase.contains(text))
+            })
+            .unwrap_or_else(|| {
+                self.from
+                    .map_or(true, |from| message.return_path.contains(from))
+                    && self.to.map_or(true, |to| {
+                        message
+                            .recipients
+                            .iter()
+                            .any(|r| r.address_lcase.contains(to))
+                    })
+            })
+            && self.domain.map_or(true, |domain| {
+                message.domains.iter().any(|d| d.domain.contains(domain))
+            })
+            && self.status.map_or(true, |status| match status {
+                QueueStatus::Held => message.is_held(),
+                status => message.domains.iter().any(|d| {
+                    matches!(
+                        (&d.status, status),
+                        (Status::Scheduled, QueueStatus::Scheduled)
+                            | (Status::Completed(_), QueueStatus::Completed)
+                            | (Status::TemporaryFailure(_), QueueStatus::TemporaryFailure)
+                            | (Status::PermanentFailure(_), QueueStatus::PermanentFailure)
+                    )
+                }),
+            })
+            && self
+                .before
+                .map_or(true, |before| message.next_delivery_event() < before)
+            && self
+                .after
+                .map_or(true, |after| message.next_delivery_event() > after)
+            && self.min_age.map_or(true, |min_age| age >= min_age)
+            && self.max_age.map_or(true, |max_age| age <= max_age)
+    }
 }
 
 impl From<&queue::Message> for Message {
@@ -611,6 +842,7 @@ impl From<&queue::Message> for Message {
             size: message.size,
             priority: message.priority,
             env_id: message.env_id.clone(),
+            held: message.is_held(),
             domains: message
                 .domains
                 .iter()
@@ -773,3 +1005,7 @@ where
 fn is_zero(num: &i16) -> bool {
     *num == 0
 }
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
@@ -43,8 +43,8 @@ impl JMAP {
                 "data": self.core.reload_certificates().await?.config,
             }))
             .into_http_response()),
-            (Some("server.blocked-ip"), &Method::GET) => {
-                let result = self.core.reload_blocked_ips().await?;
+            (Some("server.blocked-ip" | "server.allowed-ip"), &Method::GET) => {
+                let result = self.core.reload_ip_lists().await?;
                 // Increment version counter
                 self.core.network.blocked_ips.increment_version();
 
@@ -0,0 +1,138 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use directory::{backend::internal::manage, Permission};
+use hyper::Method;
+use serde_json::json;
+use smtp::queue;
+use store::{
+    dispatch::replication::{applied_position, ChangeSet, CHANGE_STREAM},
+    write::Bincode,
+    Deserialize,
+};
+use trc::AddContext;
+use utils::config::ConfigKey;
+
+use crate::{
+    api::{http::ToHttpResponse, HttpRequest, HttpResponse, JsonResponse},
+    JMAP,
+};
+
+impl JMAP {
+    pub async fn handle_manage_replication(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        // Validate the access token
+        access_token.assert_has_permission(Permission::Replication)?;
+
+        let replication = &self.core.storage.replication;
+        match (path.get(1).copied(), req.method()) {
+            (None, &Method::GET) => {
+                let status = if replication.is_primary() {
+                    CHANGE_STREAM.status().await.caused_by(trc::location!())?
+                } else {
+                    Default::default()
+                };
+                let applied = if replication.is_standby() {
+                    applied_position(&self.core.storage.data)
+                        .await
+                        .caused_by(trc::location!())?
+                } else {
+                    0
+                };
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "role": replication.role.as_str(),
+                        "shipped": status.shipped,
+                        "pending": status.pending,
+                        "pendingSize": status.pending_size,
+                        "lag": status.lag(),
+                        "resync": status.resync,
+                        "applied": applied,
+                    },
+                }))
+                .into_http_response())
+            }
+            (Some("apply"), &Method::POST) => {
+                if !replication.is_standby() {
+                    return Err(manage::error(
+                        "Not a standby",
+                        "Changes can only be applied on a standby.".into(),
+                    ));
+                }
+
+                let changes =
+                    Bincode::<ChangeSet>::deserialize(body.as_deref().unwrap_or_default())
+                        .caused_by(trc::location!())?
+                        .inner;
+                let seq = changes
+                    .apply(&self.core.storage.data, &self.core.storage.blob)
+                    .await
+                    .inspect_err(|err| {
+                        trc::error!(err.clone().details("Failed to apply replicated changes."));
+                    })?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": seq,
+                }))
+                .into_http_response())
+            }
+            (Some("promote"), &Method::POST) => {
+                if !replication.is_standby() {
+                    return Err(manage::error(
+                        "Not a standby",
+                        "Only a standby can be promoted.".into(),
+                    ));
+                }
+
+                // Writes, queue delivery and reports are enabled once reloaded
+                self.core
+                    .storage
+                    .config
+                    .set([ConfigKey {
+                        key: "storage.replication.role".to_string(),
+                        value: "none".to_string(),
+                    }])
+                    .await?;
+                if let Some(core) = self.core.reload().await?.new_core {
+                    self.shared_core.store(core.into());
+                    self.inner.increment_config_version();
+                }
+                let _ = self.smtp.inner.queue_tx.send(queue::Event::Reload).await;
+
+                trc::event!(Store(trc::StoreEvent::ReplicationPromoted));
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            (Some("reset"), &Method::POST) => {
+                if !replication.is_primary() {
+                    return Err(manage::error(
+                        "Not a primary",
+                        "Only the change stream of a primary can be reset.".into(),
+                    ));
+                }
+
+                // The standby was resynced from a copy of the primary
+                CHANGE_STREAM.reset().await.caused_by(trc::location!())?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+}
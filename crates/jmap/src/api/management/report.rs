@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::net::IpAddr;
+
 use common::auth::AccessToken;
 use directory::{
     backend::internal::{manage::ManageDirectory, PrincipalField},
@@ -11,12 +13,13 @@ use directory::{
 };
 use hyper::Method;
 use mail_auth::report::{
-    tlsrpt::{FailureDetails, Policy, TlsReport},
-    Feedback,
+    tlsrpt::{FailureDetails, Policy, ResultType, TlsReport},
+    ActionDisposition, DmarcResult, Feedback,
 };
 use serde_json::json;
 use smtp::reporting::analysis::IncomingReport;
 use store::{
+    ahash::AHashMap,
     write::{key::DeserializeBigEndian, BatchBuilder, Bincode, ReportClass, ValueClass},
     Deserialize, IterateParams, ValueKey, U64_LEN,
 };
@@ -229,6 +232,97 @@ impl JMAP {
                 }))
                 .into_http_response())
             }
+            (class @ ("dmarc" | "tls"), Some(stats), &Method::GET) if stats == "stats" => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::IncomingReportList)?;
+
+                let params = UrlParams::new(req.uri().query());
+                let domain = params.get("domain").map(|d| d.to_lowercase());
+                let limit: usize = params.parse::<usize>("limit").unwrap_or_default();
+                let range_start = params.parse::<u64>("range-start").unwrap_or_default();
+                let range_end = params.parse::<u64>("range-end").unwrap_or(u64::MAX);
+
+                let (from_key, to_key) = if class == "dmarc" {
+                    (
+                        ValueKey::from(ValueClass::Report(ReportClass::Dmarc {
+                            id: range_start,
+                            expires: 0,
+                        })),
+                        ValueKey::from(ValueClass::Report(ReportClass::Dmarc {
+                            id: range_end,
+                            expires: u64::MAX,
+                        })),
+                    )
+                } else {
+                    (
+                        ValueKey::from(ValueClass::Report(ReportClass::Tls {
+                            id: range_start,
+                            expires: 0,
+                        })),
+                        ValueKey::from(ValueClass::Report(ReportClass::Tls {
+                            id: range_end,
+                            expires: u64::MAX,
+                        })),
+                    )
+                };
+
+                let mut dmarc_stats = DmarcStats::default();
+                let mut tls_stats = TlsStats::default();
+                let mut last_id = 0;
+                self.core
+                    .storage
+                    .data
+                    .iterate(
+                        IterateParams::new(from_key, to_key).set_values(true),
+                        |key, value| {
+                            // Skip chunked records
+                            let id = key.deserialize_be_u64(U64_LEN + 1)?;
+                            if id == last_id {
+                                return Ok(true);
+                            }
+                            last_id = id;
+
+                            if class == "dmarc" {
+                                let report = Bincode::<
+                                    IncomingReport<mail_auth::report::Report>,
+                                >::deserialize(value)
+                                .caused_by(trc::location!())?
+                                .inner;
+
+                                if tenant_domains
+                                    .as_ref()
+                                    .map_or(true, |domains| report.has_domain(domains))
+                                {
+                                    dmarc_stats.add_report(&report.report, domain.as_deref());
+                                }
+                            } else {
+                                let report =
+                                    Bincode::<IncomingReport<TlsReport>>::deserialize(value)
+                                        .caused_by(trc::location!())?
+                                        .inner;
+
+                                if tenant_domains
+                                    .as_ref()
+                                    .map_or(true, |domains| report.has_domain(domains))
+                                {
+                                    tls_stats.add_report(&report.report, domain.as_deref());
+                                }
+                            }
+
+                            Ok(true)
+                        },
+                    )
+                    .await?;
+
+                Ok(JsonResponse::new(json!({
+                        "data": if class == "dmarc" {
+                            serde_json::to_value(dmarc_stats.finish(limit))
+                        } else {
+                            serde_json::to_value(tls_stats.finish(limit))
+                        }.unwrap_or_default(),
+                }))
+                .into_http_response())
+            }
             (class @ ("dmarc" | "tls" | "arf"), Some(report_id), &Method::GET) => {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::IncomingReportGet)?;
@@ -385,6 +479,200 @@ impl From<&str> for ReportType {
     }
 }
 
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct DmarcStats {
+    pub reports: u64,
+    pub messages: u64,
+    pub dmarc_fail: u64,
+    pub sources: Vec<DmarcSourceStats>,
+    #[serde(skip)]
+    by_source: AHashMap<Option<IpAddr>, DmarcSourceStats>,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct DmarcSourceStats {
+    pub source_ip: Option<IpAddr>,
+    pub messages: u64,
+    pub dkim_pass: u64,
+    pub dkim_fail: u64,
+    pub spf_pass: u64,
+    pub spf_fail: u64,
+    pub dmarc_fail: u64,
+    pub quarantine: u64,
+    pub reject: u64,
+    pub header_from: Vec<String>,
+    pub reporters: Vec<String>,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct TlsStats {
+    pub reports: u64,
+    pub success: u64,
+    pub failure: u64,
+    pub domains: Vec<TlsDomainStats>,
+    #[serde(skip)]
+    by_domain: AHashMap<String, TlsDomainStats>,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct TlsDomainStats {
+    pub policy_domain: String,
+    pub success: u64,
+    pub failure: u64,
+    pub failures: Vec<TlsFailureStats>,
+    pub reporters: Vec<String>,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct TlsFailureStats {
+    pub result_type: ResultType,
+    pub sending_mta_ip: Option<IpAddr>,
+    pub receiving_mx_hostname: Option<String>,
+    pub sessions: u64,
+}
+
+impl DmarcStats {
+    pub fn add_report(&mut self, report: &mail_auth::report::Report, domain: Option<&str>) {
+        if domain.map_or(false, |domain| {
+            !report.domain().eq_ignore_ascii_case(domain)
+        }) {
+            return;
+        }
+
+        self.reports += 1;
+        for record in report.records() {
+            let count = record.count() as u64;
+            let stats =
+                self.by_source
+                    .entry(record.source_ip())
+                    .or_insert_with(|| DmarcSourceStats {
+                        source_ip: record.source_ip(),
+                        ..Default::default()
+                    });
+            let dkim_pass = record.dmarc_dkim_result() == DmarcResult::Pass;
+            let spf_pass = record.dmarc_spf_result() == DmarcResult::Pass;
+
+            stats.messages += count;
+            if dkim_pass {
+                stats.dkim_pass += count;
+            } else {
+                stats.dkim_fail += count;
+            }
+            if spf_pass {
+                stats.spf_pass += count;
+            } else {
+                stats.spf_fail += count;
+            }
+            if !dkim_pass && !spf_pass {
+                stats.dmarc_fail += count;
+                self.dmarc_fail += count;
+            }
+            match record.action_disposition() {
+                ActionDisposition::Quarantine => stats.quarantine += count,
+                ActionDisposition::Reject => stats.reject += count,
+                _ => (),
+            }
+            let header_from = record.header_from().to_lowercase();
+            if !header_from.is_empty() && !stats.header_from.contains(&header_from) {
+                stats.header_from.push(header_from);
+            }
+            if !stats.reporters.iter().any(|r| r == report.org_name()) {
+                stats.reporters.push(report.org_name().to_string());
+            }
+            self.messages += count;
+        }
+    }
+
+    pub fn finish(mut self, limit: usize) -> Self {
+        let mut sources = self.by_source.drain().map(|(_, v)| v).collect::<Vec<_>>();
+        sources.sort_unstable_by(|a, b| {
+            b.dmarc_fail
+                .cmp(&a.dmarc_fail)
+                .then_with(|| b.messages.cmp(&a.messages))
+                .then_with(|| a.source_ip.cmp(&b.source_ip))
+        });
+        if limit > 0 {
+            sources.truncate(limit);
+        }
+        self.sources = sources;
+        self
+    }
+}
+
+impl TlsStats {
+    pub fn add_report(&mut self, report: &TlsReport, domain: Option<&str>) {
+        let mut matched = false;
+
+        for policy in &report.policies {
+            if domain.map_or(false, |domain| {
+                !policy.policy.policy_domain.eq_ignore_ascii_case(domain)
+            }) {
+                continue;
+            }
+            matched = true;
+
+            let stats = self
+                .by_domain
+                .entry(policy.policy.policy_domain.to_lowercase())
+                .or_insert_with(|| TlsDomainStats {
+                    policy_domain: policy.policy.policy_domain.to_lowercase(),
+                    ..Default::default()
+                });
+            stats.success += policy.summary.total_success as u64;
+            stats.failure += policy.summary.total_failure as u64;
+            self.success += policy.summary.total_success as u64;
+            self.failure += policy.summary.total_failure as u64;
+
+            for failure in &policy.failure_details {
+                let sessions = failure.failed_session_count as u64;
+                if let Some(stats) = stats.failures.iter_mut().find(|f| {
+                    f.result_type == failure.result_type
+                        && f.sending_mta_ip == failure.sending_mta_ip
+                        && f.receiving_mx_hostname == failure.receiving_mx_hostname
+                }) {
+                    stats.sessions += sessions;
+                } else {
+                    stats.failures.push(TlsFailureStats {
+                        result_type: failure.result_type,
+                        sending_mta_ip: failure.sending_mta_ip,
+                        receiving_mx_hostname: failure.receiving_mx_hostname.clone(),
+                        sessions,
+                    });
+                }
+            }
+
+            if let Some(org) = &report.organization_name {
+                if !stats.reporters.contains(org) {
+                    stats.reporters.push(org.clone());
+                }
+            }
+        }
+
+        if matched {
+            self.reports += 1;
+        }
+    }
+
+    pub fn finish(mut self, limit: usize) -> Self {
+        let mut domains = self.by_domain.drain().map(|(_, v)| v).collect::<Vec<_>>();
+        for domain in &mut domains {
+            domain
+                .failures
+                .sort_unstable_by(|a, b| b.sessions.cmp(&a.sessions));
+        }
+        domains.sort_unstable_by(|a, b| {
+            b.failure
+                .cmp(&a.failure)
+                .then_with(|| a.policy_domain.cmp(&b.policy_domain))
+        });
+        if limit > 0 {
+            domains.truncate(limit);
+        }
+        self.domains = domains;
+        self
+    }
+}
+
 trait Contains {
     fn contains(&self, text: &str) -> bool;
 }
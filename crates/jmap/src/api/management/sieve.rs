@@ -36,6 +36,18 @@ pub enum Response {
     Discard,
 }
 
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "result")]
+#[serde(rename_all = "lowercase")]
+pub enum CheckResponse {
+    Valid,
+    Invalid {
+        error: String,
+        line: usize,
+        column: usize,
+    },
+}
+
 impl JMAP {
     pub async fn handle_run_sieve(
         &self,
@@ -121,4 +133,28 @@ impl JMAP {
         }))
         .into_http_response())
     }
+
+    pub async fn handle_check_sieve(
+        &self,
+        body: Option<Vec<u8>>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        // Validate the access token
+        access_token.assert_has_permission(Permission::SieveCheckScript)?;
+
+        let script = body.unwrap_or_default();
+        let result = match self.core.sieve.untrusted_compiler.compile(&script) {
+            Ok(_) => CheckResponse::Valid,
+            Err(err) => CheckResponse::Invalid {
+                error: err.to_string(),
+                line: err.line_num(),
+                column: err.line_pos(),
+            },
+        };
+
+        Ok(JsonResponse::new(json!({
+            "data": result,
+        }))
+        .into_http_response())
+    }
 }
@@ -0,0 +1,163 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use common::{
+    auth::AccessToken,
+    scripts::{
+        stats::SymbolStats,
+        training::{train_message, SpamTrainingData},
+    },
+};
+use directory::Permission;
+use hyper::Method;
+use jmap_proto::types::{collection::Collection, property::Property};
+use serde_json::json;
+use store::write::{BatchBuilder, F_CLEAR, F_VALUE};
+use utils::url_params::UrlParams;
+
+use crate::{
+    api::{http::ToHttpResponse, HttpRequest, HttpResponse, JsonResponse},
+    JMAP,
+};
+
+impl JMAP {
+    pub async fn handle_manage_spam_filter(
+        &self,
+        req: &HttpRequest,
+        path: Vec<&str>,
+        body: Option<Vec<u8>>,
+        access_token: &AccessToken,
+    ) -> trc::Result<HttpResponse> {
+        // Validate the access token
+        access_token.assert_has_permission(Permission::UpdateSpamFilter)?;
+
+        let params = UrlParams::new(req.uri().query());
+        match (path.get(1).copied(), req.method()) {
+            (Some("training"), &Method::GET) => {
+                let store = self.core.spam_filter_store(params.get("store")).await?;
+                let data = SpamTrainingData::export(&store).await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": data,
+                }))
+                .into_http_response())
+            }
+            (Some("training"), &Method::POST) => {
+                let store = self.core.spam_filter_store(params.get("store")).await?;
+                let data =
+                    serde_json::from_slice::<SpamTrainingData>(body.as_deref().unwrap_or_default())
+                        .map_err(|err| {
+                            trc::EventType::Resource(trc::ResourceEvent::BadParameters)
+                                .from_json_error(err)
+                        })?;
+                data.import(&store, params.parse("merge").unwrap_or(false))
+                    .await?;
+
+                // Cached token weights are no longer valid
+                self.smtp.inner.script_cache.bayes_cache.clear();
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "tokens": data.bayes.tokens.len(),
+                        "reputation": data.reputation.len(),
+                    },
+                }))
+                .into_http_response())
+            }
+            (Some("train"), &Method::POST) => {
+                let is_spam = match path.get(2).copied() {
+                    Some("spam") => true,
+                    Some("ham") => false,
+                    _ => return Err(trc::ResourceEvent::NotFound.into_err()),
+                };
+                let store = self.core.spam_filter_store(params.get("store")).await?;
+                let tokens = train_message(
+                    &store,
+                    &self.smtp.inner.script_cache.bayes_cache,
+                    self.core.sieve.bayes.tokenizer,
+                    body.as_deref().unwrap_or_default(),
+                    is_spam,
+                    params.parse("max-tokens").unwrap_or(0),
+                )
+                .await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "tokens": tokens,
+                    },
+                }))
+                .into_http_response())
+            }
+            (Some("symbols"), &Method::GET) => {
+                let store = self.core.spam_filter_store(params.get("store")).await?;
+                let stats = SymbolStats::fetch(&store, params.parse("days").unwrap_or(30)).await?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": stats,
+                }))
+                .into_http_response())
+            }
+            _ => Err(trc::ResourceEvent::NotFound.into_err()),
+        }
+    }
+
+    pub async fn handle_account_spam_filter_get(
+        &self,
+        access_token: Arc<AccessToken>,
+    ) -> trc::Result<HttpResponse> {
+        let allow_contacts = self
+            .get_property::<()>(
+                access_token.primary_id(),
+                Collection::Principal,
+                0,
+                Property::ContactsOptOut,
+            )
+            .await?
+            .is_none();
+
+        Ok(JsonResponse::new(json!({
+            "data": AccountSpamFilter { allow_contacts },
+        }))
+        .into_http_response())
+    }
+
+    pub async fn handle_account_spam_filter_post(
+        &self,
+        access_token: Arc<AccessToken>,
+        body: Option<Vec<u8>>,
+    ) -> trc::Result<HttpResponse> {
+        let request =
+            serde_json::from_slice::<AccountSpamFilter>(body.as_deref().unwrap_or_default())
+                .map_err(|err| {
+                    trc::EventType::Resource(trc::ResourceEvent::BadParameters).from_json_error(err)
+                })?;
+
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(access_token.primary_id())
+            .with_collection(Collection::Principal)
+            .update_document(0);
+        if request.allow_contacts {
+            batch.value(Property::ContactsOptOut, (), F_VALUE | F_CLEAR);
+        } else {
+            batch.value(Property::ContactsOptOut, (), F_VALUE);
+        }
+        self.core.storage.data.write(batch.build()).await?;
+
+        Ok(JsonResponse::new(json!({
+            "data": (),
+        }))
+        .into_http_response())
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AccountSpamFilter {
+    #[serde(rename = "allowContacts")]
+    pub allow_contacts: bool,
+}
@@ -50,11 +50,10 @@ impl JMAP {
                         trc::EventType::Resource(trc::ResourceEvent::BadParameters)
                             .from_base64_error(err)
                     })?;
+                // Queued messages may be encrypted at rest
                 let contents = self
                     .core
-                    .storage
-                    .blob
-                    .get_blob(&blob_hash, 0..usize::MAX)
+                    .get_queued_message(&blob_hash, 0..usize::MAX)
                     .await?
                     .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
                 let params = UrlParams::new(req.uri().query());
@@ -14,6 +14,7 @@ use utils::map::vec_map::VecMap;
 use crate::JmapInstance;
 
 pub mod autoconfig;
+pub mod dav;
 pub mod event_source;
 pub mod http;
 pub mod management;
@@ -54,6 +55,7 @@ pub struct HttpResponse {
     pub content_type: Cow<'static, str>,
     pub content_disposition: Cow<'static, str>,
     pub cache_control: Cow<'static, str>,
+    pub headers: Vec<(hyper::header::HeaderName, String)>,
     pub body: HttpResponseBody,
 }
 
@@ -140,6 +140,34 @@ impl JMAP {
         // Check permissions
         access_token.assert_has_jmap_permission(&method)?;
 
+        // Writes are rejected while the account is in maintenance mode
+        let maintenance = &self.core.storage.maintenance;
+        match &method {
+            RequestMethod::Set(req) => maintenance.assert_writable(req.account_id.document_id())?,
+            RequestMethod::Copy(req) => {
+                maintenance.assert_writable(req.account_id.document_id())?;
+                if req.on_success_destroy_original.unwrap_or(false) {
+                    maintenance.assert_writable(req.from_account_id.document_id())?;
+                }
+            }
+            RequestMethod::CopyBlob(req) => {
+                maintenance.assert_writable(req.account_id.document_id())?
+            }
+            RequestMethod::ImportEmail(req) => {
+                maintenance.assert_writable(req.account_id.document_id())?
+            }
+            RequestMethod::SendMdn(req) => {
+                maintenance.assert_writable(req.account_id.document_id())?
+            }
+            RequestMethod::Restore(req) => {
+                maintenance.assert_writable(req.account_id.document_id())?
+            }
+            RequestMethod::UploadBlob(req) => {
+                maintenance.assert_writable(req.account_id.document_id())?
+            }
+            _ => (),
+        }
+
         // Handle method
         let response = match method {
             RequestMethod::Get(mut req) => match req.take_arguments() {
@@ -189,6 +217,46 @@ impl JMAP {
 
                     self.quota_get(req, access_token).await?.into()
                 }
+                get::RequestArguments::Activity => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.activity_get(req).await?.into()
+                }
+                get::RequestArguments::DeletedEmail => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.deleted_email_get(req).await?.into()
+                }
+                get::RequestArguments::Calendar => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.calendar_get(req).await?.into()
+                }
+                get::RequestArguments::CalendarEvent => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.calendar_event_get(req).await?.into()
+                }
+                get::RequestArguments::AddressBook => {
+                    access_token.assert_has_access(req.account_id, Collection::AddressBook)?;
+
+                    self.address_book_get(req, access_token).await?.into()
+                }
+                get::RequestArguments::ContactCard => {
+                    access_token.assert_has_access(req.account_id, Collection::ContactCard)?;
+
+                    self.contact_card_get(req, access_token).await?.into()
+                }
+                get::RequestArguments::TaskList => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.task_list_get(req).await?.into()
+                }
+                get::RequestArguments::Task => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.task_get(req).await?.into()
+                }
                 get::RequestArguments::Blob(arguments) => {
                     access_token.assert_is_member(req.account_id)?;
 
@@ -230,6 +298,21 @@ impl JMAP {
 
                     self.quota_query(req, access_token).await?.into()
                 }
+                query::RequestArguments::CalendarEvent => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.calendar_event_query(req).await?.into()
+                }
+                query::RequestArguments::ContactCard => {
+                    access_token.assert_has_access(req.account_id, Collection::ContactCard)?;
+
+                    self.contact_card_query(req, access_token).await?.into()
+                }
+                query::RequestArguments::Task => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.task_query(req).await?.into()
+                }
             },
             RequestMethod::Set(mut req) => match req.take_arguments() {
                 set::RequestArguments::Email => {
@@ -275,6 +358,42 @@ impl JMAP {
 
                     self.vacation_response_set(req, access_token).await?.into()
                 }
+                set::RequestArguments::Calendar(arguments) => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.calendar_set(req.with_arguments(arguments))
+                        .await?
+                        .into()
+                }
+                set::RequestArguments::CalendarEvent => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.calendar_event_set(req).await?.into()
+                }
+                set::RequestArguments::AddressBook(arguments) => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.address_book_set(req.with_arguments(arguments))
+                        .await?
+                        .into()
+                }
+                set::RequestArguments::ContactCard => {
+                    access_token.assert_has_access(req.account_id, Collection::ContactCard)?;
+
+                    self.contact_card_set(req, access_token).await?.into()
+                }
+                set::RequestArguments::TaskList(arguments) => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.task_list_set(req.with_arguments(arguments))
+                        .await?
+                        .into()
+                }
+                set::RequestArguments::Task => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.task_set(req).await?.into()
+                }
             },
             RequestMethod::Changes(req) => self.changes(req, access_token).await?.into(),
             RequestMethod::Copy(req) => {
@@ -296,6 +415,30 @@ impl JMAP {
 
                 self.email_parse(req, access_token).await?.into()
             }
+            RequestMethod::ParseContactCard(req) => {
+                access_token.assert_has_access(req.account_id, Collection::ContactCard)?;
+
+                self.contact_card_parse(req, access_token).await?.into()
+            }
+            RequestMethod::SendMdn(req) => {
+                access_token.assert_is_member(req.account_id)?;
+
+                self.mdn_send(req, &session.instance, next_call)
+                    .await?
+                    .into()
+            }
+            RequestMethod::ParseMdn(req) => {
+                access_token.assert_has_access(req.account_id, Collection::Email)?;
+
+                self.mdn_parse(req, access_token).await?.into()
+            }
+            RequestMethod::Restore(req) => {
+                access_token.assert_is_member(req.account_id)?;
+
+                self.deleted_email_restore(req, access_token, session)
+                    .await?
+                    .into()
+            }
             RequestMethod::QueryChanges(req) => self.query_changes(req, access_token).await?.into(),
             RequestMethod::SearchSnippet(req) => {
                 access_token.assert_has_access(req.account_id, Collection::Email)?;
@@ -307,6 +450,13 @@ impl JMAP {
 
                 self.sieve_script_validate(req, access_token).await?.into()
             }
+            RequestMethod::GetAvailability(req) => {
+                access_token.assert_is_member(req.account_id)?;
+
+                self.calendar_event_availability(req, access_token)
+                    .await?
+                    .into()
+            }
             RequestMethod::CopyBlob(req) => {
                 access_token.assert_is_member(req.account_id)?;
 
@@ -57,7 +57,12 @@ impl JMAP {
                     .unwrap_or_else(|| Id::from(*id).to_string()),
                 is_personal,
                 is_readonly,
-                Some(&[Capability::Mail, Capability::Quota, Capability::Blob]),
+                Some(&[
+                    Capability::Mail,
+                    Capability::Contacts,
+                    Capability::Quota,
+                    Capability::Blob,
+                ]),
                 &self.core.jmap.capabilities.account,
             );
         }
@@ -10,8 +10,9 @@ use jmap_proto::{
     error::set::SetError,
     object::Object,
     types::{
-        acl::Acl,
+        acl::{Acl, MailboxRights},
         collection::Collection,
+        id::Id,
         property::Property,
         value::{AclGrant, MaybePatchValue, Value},
     },
@@ -97,6 +98,42 @@ impl JMAP {
         Ok(shared_messages)
     }
 
+    pub async fn shared_contact_cards(
+        &self,
+        access_token: &AccessToken,
+        to_account_id: u32,
+        check_acls: impl Into<Bitmap<Acl>>,
+    ) -> trc::Result<RoaringBitmap> {
+        let check_acls = check_acls.into();
+        let shared_address_books = self
+            .shared_documents(
+                access_token,
+                to_account_id,
+                Collection::AddressBook,
+                check_acls,
+            )
+            .await?;
+        if shared_address_books.is_empty() {
+            return Ok(shared_address_books);
+        }
+        let mut shared_cards = RoaringBitmap::new();
+        for address_book_id in shared_address_books {
+            if let Some(cards_in_address_book) = self
+                .get_tag(
+                    to_account_id,
+                    Collection::ContactCard,
+                    Property::AddressBookIds,
+                    address_book_id,
+                )
+                .await?
+            {
+                shared_cards |= cards_in_address_book;
+            }
+        }
+
+        Ok(shared_cards)
+    }
+
     pub async fn owned_or_shared_documents(
         &self,
         access_token: &AccessToken,
@@ -251,6 +288,132 @@ impl JMAP {
         Ok(())
     }
 
+    pub async fn share_with_set(
+        &self,
+        changes: &mut Object<Value>,
+        current: Option<&HashedValue<Object<Value>>>,
+        share_with: MaybePatchValue,
+    ) -> Result<(), SetError> {
+        // shareWith is a view over the stored ACLs, so changes are
+        // visible to IMAP GETACL/MYRIGHTS as well
+        match share_with {
+            MaybePatchValue::Value(Value::List(values)) => {
+                let mut acls = Vec::with_capacity(values.len() / 2);
+                for item in values.chunks_exact(2) {
+                    if let (Value::Id(principal_id), Value::UnsignedInt(grants)) =
+                        (&item[0], &item[1])
+                    {
+                        let account_id = self.share_with_principal(*principal_id).await?;
+                        if *grants != 0 {
+                            acls.push(AclGrant {
+                                account_id,
+                                grants: Bitmap::from(*grants),
+                            });
+                        }
+                    } else {
+                        return Err(SetError::invalid_properties()
+                            .with_property(Property::ShareWith)
+                            .with_description("Invalid shareWith value found."));
+                    }
+                }
+                changes.properties.set(Property::Acl, Value::Acl(acls));
+            }
+            MaybePatchValue::Patch(patch) => {
+                let (principal_id, grants, is_set) = match (patch.first(), patch.get(1)) {
+                    (Some(Value::Id(principal_id)), Some(Value::UnsignedInt(grants))) => (
+                        *principal_id,
+                        Bitmap::<Acl>::from(*grants),
+                        patch.get(2).map(|v| v.as_bool().unwrap_or(false)),
+                    ),
+                    _ => {
+                        return Err(SetError::invalid_properties()
+                            .with_property(Property::ShareWith)
+                            .with_description("Invalid shareWith patch."));
+                    }
+                };
+                let account_id = self.share_with_principal(principal_id).await?;
+                let acl = if let Value::Acl(acl) =
+                    changes
+                        .properties
+                        .get_mut_or_insert_with(Property::Acl, || {
+                            current
+                                .and_then(|current| {
+                                    current.inner.properties.get(&Property::Acl).cloned()
+                                })
+                                .unwrap_or_else(|| Value::Acl(Vec::new()))
+                        }) {
+                    acl
+                } else {
+                    return Err(SetError::invalid_properties()
+                        .with_property(Property::ShareWith)
+                        .with_description("Invalid ACL value found."));
+                };
+
+                let current_grants = acl
+                    .iter()
+                    .find(|item| item.account_id == account_id)
+                    .map(|item| item.grants)
+                    .unwrap_or_default();
+                let new_grants = match is_set {
+                    Some(true) => {
+                        let mut new_grants = current_grants;
+                        new_grants.union(&grants);
+                        new_grants
+                    }
+                    Some(false) => {
+                        let mut new_grants = current_grants;
+                        for grant in grants {
+                            new_grants.remove(grant);
+                        }
+                        new_grants
+                    }
+                    None => grants,
+                };
+
+                if let Some(acl_item) = acl.iter_mut().find(|item| item.account_id == account_id) {
+                    acl_item.grants = new_grants;
+                } else {
+                    acl.push(AclGrant {
+                        account_id,
+                        grants: new_grants,
+                    });
+                }
+                acl.retain(|item| !item.grants.is_empty());
+            }
+            _ => {
+                return Err(SetError::invalid_properties()
+                    .with_property(Property::ShareWith)
+                    .with_description("Invalid shareWith property."))
+            }
+        }
+        Ok(())
+    }
+
+    pub fn share_with_get(
+        &self,
+        value: &[AclGrant],
+        access_token: &AccessToken,
+        account_id: u32,
+    ) -> Value {
+        if access_token.is_member(account_id)
+            || value.iter().any(|item| {
+                access_token.is_member(item.account_id) && item.grants.contains(Acl::Administer)
+            })
+        {
+            let mut share_with = Object::with_capacity(value.len());
+            for item in value {
+                share_with.append(
+                    Property::_T(Id::from(item.account_id).to_string()),
+                    MailboxRights(item.grants).to_object(),
+                );
+            }
+
+            Value::Object(share_with)
+        } else {
+            Value::Null
+        }
+    }
+
     pub async fn acl_get(
         &self,
         value: &[AclGrant],
@@ -293,7 +456,7 @@ impl JMAP {
         current: &Option<HashedValue<Object<Value>>>,
     ) {
         if let Value::Acl(acl_changes) = changes.get(&Property::Acl) {
-            let access_tokens = &self.core.security.access_tokens;
+            let mut invalidated = Vec::new();
             if let Some(Value::Acl(acl_current)) = current
                 .as_ref()
                 .and_then(|current| current.inner.properties.get(&Property::Acl))
@@ -307,7 +470,7 @@ impl JMAP {
                         }
                     }
                     if invalidate {
-                        access_tokens.remove(&current_item.account_id);
+                        invalidated.push(current_item.account_id);
                     }
                 }
 
@@ -320,13 +483,15 @@ impl JMAP {
                         }
                     }
                     if invalidate {
-                        access_tokens.remove(&change_item.account_id);
+                        invalidated.push(change_item.account_id);
                     }
                 }
             } else {
-                for value in acl_changes {
-                    access_tokens.remove(&value.account_id);
-                }
+                invalidated.extend(acl_changes.iter().map(|value| value.account_id));
+            }
+
+            if !invalidated.is_empty() {
+                self.core.invalidate_access_tokens(invalidated);
             }
         }
     }
@@ -369,6 +534,24 @@ impl JMAP {
         Ok(acls)
     }
 
+    async fn share_with_principal(&self, principal_id: Id) -> Result<u32, SetError> {
+        match self
+            .core
+            .storage
+            .directory
+            .query(QueryBy::Id(principal_id.document_id()), false)
+            .await
+        {
+            Ok(Some(principal)) => Ok(principal.id()),
+            Ok(None) => Err(SetError::invalid_properties()
+                .with_property(Property::ShareWith)
+                .with_description(format!("Principal {principal_id} does not exist."))),
+            _ => Err(SetError::forbidden()
+                .with_property(Property::ShareWith)
+                .with_description("Temporary server failure during lookup")),
+        }
+    }
+
     async fn map_acl_patch(
         &self,
         acl_patch: Vec<Value>,
@@ -63,6 +63,7 @@ impl JMAP {
 
                     let (account_id, _, _) =
                         self.validate_access_token("access_token", token).await?;
+                    self.account_login(account_id).await;
 
                     self.core.get_access_token(account_id).await?
                 } else {
@@ -126,15 +127,12 @@ impl JMAP {
             )
             .await
         {
-            Ok(principal) => self
-                .core
-                .build_access_token(principal)
-                .await
-                .and_then(|token| {
-                    token
-                        .assert_has_permission(Permission::Authenticate)
-                        .map(|_| token)
-                }),
+            Ok(principal) => {
+                let token = self.core.build_access_token(principal).await?;
+                token.assert_has_permission(Permission::Authenticate)?;
+                self.account_login(token.primary_id()).await;
+                Ok(token)
+            }
             Err(err) => {
                 if !err.matches(trc::EventType::Auth(trc::AuthEvent::MissingTotp)) {
                     let _ = self.is_auth_allowed_hard(&remote_ip).await;
@@ -143,6 +141,16 @@ impl JMAP {
             }
         }
     }
+
+    // Provisions the account template and synchronizes identities with the directory,
+    // accounts in maintenance mode are provisioned on their next login instead
+    pub async fn account_login(&self, account_id: u32) {
+        if self.core.storage.maintenance.is_read_only(account_id) {
+            return;
+        }
+        self.account_apply_template(account_id).await;
+        self.identity_sync_accounts([account_id]).await;
+    }
 }
 
 pub trait HttpHeaders {
@@ -139,6 +139,12 @@ impl JMAP {
                                 .shared_messages(access_token, *account_id, Acl::ReadItems)
                                 .await?
                                 .contains(*document_id)
+                    } else if Collection::from(*collection) == Collection::ContactCard {
+                        access_token.is_member(*account_id)
+                            || self
+                                .shared_contact_cards(access_token, *account_id, Acl::ReadItems)
+                                .await?
+                                .contains(*document_id)
                     } else {
                         access_token.is_member(*account_id)
                             || (access_token.has_access(*account_id, *collection)
@@ -24,6 +24,7 @@ use mail_builder::encoders::base64::base64_encode;
 use sha1::{Digest, Sha1};
 use sha2::{Sha256, Sha512};
 use store::BlobClass;
+use trc::AddContext;
 use utils::map::vec_map::VecMap;
 
 use crate::{mailbox::UidMailbox, JMAP};
@@ -186,77 +187,87 @@ impl JMAP {
         for id in request.ids {
             match id {
                 MaybeUnparsable::Value(id) => {
-                    let mut matched_ids = VecMap::new();
+                    // Find every document in the account that links to this blob
+                    let links = match &id.class {
+                        BlobClass::Linked { account_id, .. }
+                        | BlobClass::Reserved { account_id, .. }
+                            if *account_id == req_account_id =>
+                        {
+                            self.core
+                                .storage
+                                .data
+                                .blob_links(&id.hash, req_account_id)
+                                .await
+                                .caused_by(trc::location!())?
+                        }
+                        _ => {
+                            response.not_found.push(MaybeUnparsable::Value(id));
+                            continue;
+                        }
+                    };
 
-                    match &id.class {
-                        BlobClass::Linked {
-                            account_id,
-                            collection,
-                            document_id,
-                        } if *account_id == req_account_id => {
-                            let collection = Collection::from(*collection);
-                            if collection == Collection::Email {
-                                if include_email || include_thread {
-                                    if let Some(thread_id) = self
-                                        .get_property::<u32>(
-                                            req_account_id,
-                                            Collection::Email,
-                                            *document_id,
-                                            Property::ThreadId,
-                                        )
-                                        .await?
-                                    {
-                                        if include_email {
-                                            matched_ids.append(
-                                                DataType::Email,
-                                                vec![Id::from_parts(thread_id, *document_id)],
-                                            );
-                                        }
-                                        if include_thread {
-                                            matched_ids.append(
-                                                DataType::Thread,
-                                                vec![Id::from(thread_id)],
-                                            );
-                                        }
+                    let mut matched_ids: VecMap<DataType, Vec<Id>> = VecMap::new();
+                    for (collection, document_id) in links {
+                        let collection = Collection::from(collection);
+                        if collection == Collection::Email {
+                            if include_email || include_thread {
+                                if let Some(thread_id) = self
+                                    .get_property::<u32>(
+                                        req_account_id,
+                                        Collection::Email,
+                                        document_id,
+                                        Property::ThreadId,
+                                    )
+                                    .await?
+                                {
+                                    if include_email {
+                                        add_matched_id(
+                                            &mut matched_ids,
+                                            DataType::Email,
+                                            Id::from_parts(thread_id, document_id),
+                                        );
+                                    }
+                                    if include_thread {
+                                        add_matched_id(
+                                            &mut matched_ids,
+                                            DataType::Thread,
+                                            Id::from(thread_id),
+                                        );
                                     }
                                 }
-                                if include_mailbox {
-                                    if let Some(mailboxes) = self
-                                        .get_property::<Vec<UidMailbox>>(
-                                            req_account_id,
-                                            Collection::Email,
-                                            *document_id,
-                                            Property::MailboxIds,
-                                        )
-                                        .await?
-                                    {
-                                        matched_ids.append(
+                            }
+                            if include_mailbox {
+                                if let Some(mailboxes) = self
+                                    .get_property::<Vec<UidMailbox>>(
+                                        req_account_id,
+                                        Collection::Email,
+                                        document_id,
+                                        Property::MailboxIds,
+                                    )
+                                    .await?
+                                {
+                                    for mailbox in mailboxes {
+                                        debug_assert!(mailbox.uid != 0);
+                                        add_matched_id(
+                                            &mut matched_ids,
                                             DataType::Mailbox,
-                                            mailboxes
-                                                .into_iter()
-                                                .map(|m| {
-                                                    debug_assert!(m.uid != 0);
-                                                    Id::from(m.mailbox_id)
-                                                })
-                                                .collect::<Vec<_>>(),
+                                            Id::from(mailbox.mailbox_id),
                                         );
                                     }
                                 }
-                            } else {
-                                match DataType::try_from(collection) {
-                                    Ok(data_type) if type_names.contains(&data_type) => {
-                                        matched_ids.append(data_type, vec![Id::from(*document_id)]);
-                                    }
-                                    _ => (),
+                            }
+                        } else {
+                            match DataType::try_from(collection) {
+                                Ok(data_type) if type_names.contains(&data_type) => {
+                                    add_matched_id(
+                                        &mut matched_ids,
+                                        data_type,
+                                        Id::from(document_id),
+                                    );
                                 }
+                                _ => (),
                             }
                         }
-                        BlobClass::Reserved { account_id, .. } if *account_id == req_account_id => {
-                        }
-                        _ => {
-                            response.not_found.push(MaybeUnparsable::Value(id));
-                            continue;
-                        }
                     }
 
                     response.list.push(BlobInfo { id, matched_ids });
@@ -268,3 +279,10 @@ impl JMAP {
         Ok(response)
     }
 }
+
+fn add_matched_id(matched_ids: &mut VecMap<DataType, Vec<Id>>, data_type: DataType, id: Id) {
+    let ids = matched_ids.get_mut_or_insert(data_type);
+    if !ids.contains(&id) {
+        ids.push(id);
+    }
+}
@@ -185,6 +185,12 @@ impl JMAP {
         data: &[u8],
         access_token: Arc<AccessToken>,
     ) -> trc::Result<UploadResponse> {
+        // Uploads are not accepted during maintenance
+        self.core
+            .storage
+            .maintenance
+            .assert_writable(account_id.document_id())?;
+
         // Limit concurrent uploads
         let _in_flight = self
             .is_upload_allowed(&access_token)
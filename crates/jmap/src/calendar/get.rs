@@ -0,0 +1,183 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::{index::ObjectIndexBuilder, Object},
+    types::{
+        acl::CalendarRights, collection::Collection, id::Id, property::Property, value::Value,
+    },
+};
+use store::{roaring::RoaringBitmap, write::BatchBuilder};
+use trc::AddContext;
+
+use crate::JMAP;
+
+use super::set::{include_in_availability_property, SCHEMA};
+
+pub const DEFAULT_CALENDAR_ID: u32 = 0;
+
+impl JMAP {
+    pub async fn calendar_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::Name,
+            Property::Description,
+            Property::Color,
+            Property::SortOrder,
+            Property::IsSubscribed,
+            Property::IsVisible,
+            Property::IsDefault,
+            Property::TimeZone,
+            Property::ShareWith,
+            include_in_availability_property(),
+            Property::MyRights,
+        ]);
+        let account_id = request.account_id.document_id();
+        let calendar_ids = self.calendar_get_or_create(account_id).await?;
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            calendar_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self
+                .get_state(account_id, Collection::Calendar)
+                .await?
+                .into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the calendar object
+            let document_id = id.document_id();
+            if !calendar_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let mut calendar = if let Some(calendar) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::Calendar,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                calendar
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    Property::SortOrder => calendar
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::UnsignedInt(0)),
+                    Property::IsSubscribed | Property::IsVisible => calendar
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::Bool(true)),
+                    Property::IsDefault => calendar
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::Bool(false)),
+                    Property::ShareWith => {
+                        let mut share_with = Object::with_capacity(4);
+                        for item in calendar
+                            .properties
+                            .get(&Property::Acl)
+                            .and_then(|v| v.as_acl())
+                            .map(|v| &v[..])
+                            .unwrap_or_else(|| &[])
+                        {
+                            share_with.append(
+                                Property::_T(Id::from(item.account_id).to_string()),
+                                CalendarRights(item.grants).to_object(),
+                            );
+                        }
+                        Value::Object(share_with)
+                    }
+                    Property::_T(name) if name == "includeInAvailability" => calendar
+                        .properties
+                        .remove(property)
+                        .unwrap_or_else(|| Value::Text("all".to_string())),
+                    Property::MyRights => {
+                        // Calendars can only be accessed by their owner
+                        let mut rights = Object::with_capacity(8);
+                        for right in [
+                            "mayReadFreeBusy",
+                            "mayReadItems",
+                            "mayWriteAll",
+                            "mayWriteOwn",
+                            "mayUpdatePrivate",
+                            "mayRSVP",
+                            "mayAdmin",
+                            "mayDelete",
+                        ] {
+                            rights.append(Property::_T(right.to_string()), Value::Bool(true));
+                        }
+                        Value::Object(rights)
+                    }
+                    property => calendar.remove(property),
+                };
+                result.append(property.clone(), value);
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+
+    pub async fn calendar_get_or_create(&self, account_id: u32) -> trc::Result<RoaringBitmap> {
+        let mut calendar_ids = self
+            .get_document_ids(account_id, Collection::Calendar)
+            .await?
+            .unwrap_or_default();
+        if !calendar_ids.is_empty() {
+            return Ok(calendar_ids);
+        } else if self.core.storage.maintenance.is_read_only(account_id) {
+            // The default calendar is created once maintenance mode is over
+            return Ok(calendar_ids);
+        }
+
+        // Create the default calendar
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Calendar)
+            .create_document_with_id(DEFAULT_CALENDAR_ID)
+            .custom(
+                ObjectIndexBuilder::new(SCHEMA).with_changes(
+                    Object::with_capacity(2)
+                        .with_property(Property::Name, "Calendar")
+                        .with_property(Property::IsDefault, true),
+                ),
+            );
+        calendar_ids.insert(DEFAULT_CALENDAR_ID);
+
+        self.core
+            .storage
+            .data
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())
+            .map(|_| calendar_ids)
+    }
+}
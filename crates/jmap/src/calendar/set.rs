@@ -0,0 +1,392 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::set::{SetRequest, SetResponse},
+    object::{
+        calendar::SetArguments,
+        index::{IndexAs, IndexProperty, ObjectIndexBuilder},
+        Object,
+    },
+    response::references::EvalObjectReferences,
+    types::{
+        collection::Collection,
+        property::Property,
+        state::StateChange,
+        type_state::DataType,
+        value::{MaybePatchValue, SetValue, Value},
+    },
+};
+use store::{
+    query::Filter,
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder},
+};
+
+use crate::{calendar_event::set::SCHEMA as EVENT_SCHEMA, JMAP};
+
+pub static SCHEMA: &[IndexProperty] = &[
+    IndexProperty::new(Property::Name)
+        .index_as(IndexAs::Text {
+            tokenize: true,
+            index: true,
+        })
+        .max_size(255)
+        .required(),
+    IndexProperty::new(Property::Acl).index_as(IndexAs::Acl),
+];
+
+pub fn include_in_availability_property() -> Property {
+    Property::_T("includeInAvailability".to_string())
+}
+
+impl JMAP {
+    pub async fn calendar_set(
+        &self,
+        mut request: SetRequest<SetArguments>,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let on_destroy_remove_events = request.arguments.on_destroy_remove_events.unwrap_or(false);
+        let mut calendar_ids = self.calendar_get_or_create(account_id).await?;
+        let mut response = self
+            .prepare_set_response(&request, Collection::Calendar)
+            .await?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Process creates
+        let mut changes = ChangeLogBuilder::new();
+        for (id, object) in request.unwrap_create() {
+            match self.calendar_set_item(object, None, &response).await? {
+                Ok(builder) => {
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::Calendar)
+                        .create_document()
+                        .custom(builder);
+                    let document_id = self.write_batch_expect_id(batch).await?;
+                    calendar_ids.insert(document_id);
+                    changes.log_insert(Collection::Calendar, document_id);
+                    response.created(id, document_id);
+                }
+                Err(err) => {
+                    response.not_created.append(id, err);
+                }
+            }
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain calendar
+            let document_id = id.document_id();
+            let calendar = if let Some(calendar) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::Calendar,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                calendar
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+
+            match self
+                .calendar_set_item(object, Some(calendar), &response)
+                .await?
+            {
+                Ok(builder) => {
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::Calendar)
+                        .update_document(document_id)
+                        .custom(builder);
+                    if !batch.is_empty() {
+                        match self.core.storage.data.write(batch.build()).await {
+                            Ok(_) => {
+                                changes.log_update(Collection::Calendar, document_id);
+                            }
+                            Err(err) if err.is_assertion_failure() => {
+                                response.not_updated.append(
+                                    id,
+                                    SetError::forbidden().with_description(
+                                        "Another process modified this calendar, please try again.",
+                                    ),
+                                );
+                                continue 'update;
+                            }
+                            Err(err) => {
+                                return Err(err.caused_by(trc::location!()));
+                            }
+                        }
+                    }
+                    response.updated.append(id, None);
+                }
+                Err(err) => {
+                    response.not_updated.append(id, err);
+                }
+            }
+        }
+
+        // Process deletions
+        let mut did_remove_events = false;
+        for id in will_destroy {
+            let document_id = id.document_id();
+            if !calendar_ids.contains(document_id) {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            }
+
+            match self
+                .calendar_destroy(
+                    account_id,
+                    document_id,
+                    &mut changes,
+                    on_destroy_remove_events,
+                )
+                .await?
+            {
+                Ok(removed_events) => {
+                    did_remove_events |= removed_events;
+                    response.destroyed.push(id);
+                }
+                Err(err) => {
+                    response.not_destroyed.append(id, err);
+                }
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            let state_change =
+                StateChange::new(account_id).with_change(DataType::Calendar, changes.change_id);
+            response.state_change = if did_remove_events {
+                state_change.with_change(DataType::CalendarEvent, changes.change_id)
+            } else {
+                state_change
+            }
+            .into();
+            response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
+        }
+
+        Ok(response)
+    }
+
+    pub async fn calendar_destroy(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        changes: &mut ChangeLogBuilder,
+        remove_events: bool,
+    ) -> trc::Result<Result<bool, SetError>> {
+        // Fetch record
+        let calendar = if let Some(calendar) = self
+            .get_property::<HashedValue<Object<Value>>>(
+                account_id,
+                Collection::Calendar,
+                document_id,
+                Property::Value,
+            )
+            .await?
+        {
+            calendar
+        } else {
+            return Ok(Err(SetError::not_found()));
+        };
+
+        // Verify that the calendar is empty
+        let event_ids = self
+            .filter(
+                account_id,
+                Collection::CalendarEvent,
+                vec![Filter::eq(Property::CalendarIds, document_id)],
+            )
+            .await?
+            .results;
+        let did_remove_events = !event_ids.is_empty();
+        if did_remove_events {
+            if !remove_events {
+                return Ok(Err(SetError::new(SetErrorType::CalendarHasEvent)
+                    .with_description("Calendar is not empty.")));
+            }
+
+            // If the event is in multiple calendars, remove it from the current calendar,
+            // otherwise delete it.
+            for (event_id, event) in self
+                .get_properties::<HashedValue<Object<Value>>, _, _>(
+                    account_id,
+                    Collection::CalendarEvent,
+                    &event_ids,
+                    Property::Value,
+                )
+                .await?
+            {
+                let mut calendar_ids = event
+                    .inner
+                    .properties
+                    .get(&Property::CalendarIds)
+                    .and_then(|ids| ids.as_list())
+                    .cloned()
+                    .unwrap_or_default();
+                calendar_ids
+                    .retain(|id| !matches!(id, Value::Id(id) if id.document_id() == document_id));
+
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::CalendarEvent);
+                if !calendar_ids.is_empty() {
+                    batch.update_document(event_id).custom(
+                        ObjectIndexBuilder::new(EVENT_SCHEMA)
+                            .with_current(event)
+                            .with_changes(
+                                Object::with_capacity(1).with_property(
+                                    Property::CalendarIds,
+                                    Value::List(calendar_ids),
+                                ),
+                            ),
+                    );
+                    changes.log_update(Collection::CalendarEvent, event_id);
+                } else {
+                    batch
+                        .delete_document(event_id)
+                        .custom(ObjectIndexBuilder::new(EVENT_SCHEMA).with_current(event));
+                    changes.log_delete(Collection::CalendarEvent, event_id);
+                }
+
+                match self.core.storage.data.write(batch.build()).await {
+                    Ok(_) => (),
+                    Err(err) if err.is_assertion_failure() => {
+                        return Ok(Err(SetError::forbidden().with_description(concat!(
+                            "Another process modified an event in this calendar ",
+                            "while deleting it, please try again."
+                        ))));
+                    }
+                    Err(err) => {
+                        return Err(err.caused_by(trc::location!()));
+                    }
+                }
+            }
+        }
+
+        // Revoke access from any principals the calendar was shared with
+        let revoke = Object::with_capacity(1).with_property(Property::Acl, Value::Acl(vec![]));
+        let calendar = Some(calendar);
+        self.refresh_acls(&revoke, &calendar);
+
+        // Delete calendar
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Calendar)
+            .delete_document(document_id)
+            .custom(ObjectIndexBuilder::new(SCHEMA).with_current_opt(calendar));
+        match self.core.storage.data.write(batch.build()).await {
+            Ok(_) => {
+                changes.log_delete(Collection::Calendar, document_id);
+                Ok(Ok(did_remove_events))
+            }
+            Err(err) if err.is_assertion_failure() => Ok(Err(SetError::forbidden()
+                .with_description(concat!(
+                    "Another process modified this calendar ",
+                    "while deleting it, please try again."
+                )))),
+            Err(err) => Err(err.caused_by(trc::location!())),
+        }
+    }
+
+    async fn calendar_set_item(
+        &self,
+        changes_: Object<SetValue>,
+        current: Option<HashedValue<Object<Value>>>,
+        response: &SetResponse,
+    ) -> trc::Result<Result<ObjectIndexBuilder, SetError>> {
+        let mut changes = Object::with_capacity(changes_.properties.len());
+        let is_default = current.as_ref().map_or(false, |current| {
+            matches!(
+                current.inner.properties.get(&Property::IsDefault),
+                Some(Value::Bool(true))
+            )
+        });
+
+        for (property, value) in changes_.properties {
+            let value = match response.eval_object_references(value) {
+                Ok(value) => value,
+                Err(err) => return Ok(Err(err)),
+            };
+            let value = match (&property, value) {
+                (Property::Name, MaybePatchValue::Value(Value::Text(value))) => Value::Text(value),
+                (
+                    Property::Description | Property::Color | Property::TimeZone,
+                    MaybePatchValue::Value(Value::Text(value)),
+                ) if value.len() < 2048 => Value::Text(value),
+                (Property::SortOrder, MaybePatchValue::Value(Value::UnsignedInt(value))) => {
+                    Value::UnsignedInt(value)
+                }
+                (
+                    Property::IsSubscribed | Property::IsVisible,
+                    MaybePatchValue::Value(Value::Bool(value)),
+                ) => Value::Bool(value),
+                (
+                    Property::Description
+                    | Property::Color
+                    | Property::TimeZone
+                    | Property::SortOrder,
+                    MaybePatchValue::Value(Value::Null),
+                ) => Value::Null,
+                (Property::_T(name), MaybePatchValue::Value(Value::Text(value)))
+                    if name == "includeInAvailability"
+                        && matches!(value.as_str(), "all" | "attending" | "none") =>
+                {
+                    Value::Text(value)
+                }
+                (Property::IsDefault, MaybePatchValue::Value(Value::Bool(value)))
+                    if value == is_default =>
+                {
+                    // Read-only, only accepted when unchanged
+                    continue;
+                }
+                (Property::ShareWith, value) => {
+                    match self
+                        .share_with_set(&mut changes, current.as_ref(), value)
+                        .await
+                    {
+                        Ok(_) => continue,
+                        Err(err) => {
+                            return Ok(Err(err));
+                        }
+                    }
+                }
+                _ => {
+                    return Ok(Err(SetError::invalid_properties()
+                        .with_property(property)
+                        .with_description("Invalid property or value.".to_string())))
+                }
+            };
+            changes.append(property, value);
+        }
+
+        // Refresh ACLs
+        if changes.properties.contains_key(&Property::Acl) {
+            self.refresh_acls(&changes, &current);
+        }
+
+        Ok(ObjectIndexBuilder::new(SCHEMA)
+            .with_changes(changes)
+            .with_current_opt(current)
+            .validate())
+    }
+}
@@ -0,0 +1,187 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use directory::{backend::internal::PrincipalField, QueryBy};
+use jmap_proto::{
+    method::availability::{
+        BusyPeriod, BusyStatus, GetAvailabilityRequest, GetAvailabilityResponse,
+    },
+    object::Object,
+    types::{acl::Acl, collection::Collection, date::UTCDate, property::Property, value::Value},
+};
+use store::{query::Filter, roaring::RoaringBitmap};
+use trc::AddContext;
+
+use crate::{calendar::set::include_in_availability_property, JMAP};
+
+use super::{itip::event_participants, EventSchedule};
+
+impl JMAP {
+    // Only busy periods are disclosed, never the events themselves
+    pub async fn calendar_event_availability(
+        &self,
+        request: GetAvailabilityRequest,
+        access_token: &AccessToken,
+    ) -> trc::Result<GetAvailabilityResponse> {
+        let from = request.utc_start.timestamp();
+        let to = request.utc_end.timestamp();
+        if from >= to {
+            return Err(trc::JmapEvent::InvalidArguments
+                .into_err()
+                .details("utcEnd must be after utcStart."));
+        }
+
+        // Make sure the principal exists
+        let account_id = request.id.document_id();
+        let principal = self
+            .core
+            .storage
+            .directory
+            .query(QueryBy::Id(account_id), false)
+            .await
+            .caused_by(trc::location!())?
+            .ok_or_else(|| {
+                trc::JmapEvent::NotFound
+                    .into_err()
+                    .details("Principal not found.")
+            })?;
+
+        // Free-busy information is only disclosed for the calendars shared with
+        // the user with the mayReadFreeBusy right
+        let calendar_ids = self
+            .owned_or_shared_documents(access_token, account_id, Collection::Calendar, Acl::Read)
+            .await?;
+        if calendar_ids.is_empty() && !access_token.is_member(account_id) {
+            return Err(trc::JmapEvent::Forbidden
+                .into_err()
+                .details("You do not have access to this principal's availability."));
+        }
+
+        // Obtain the calendars included in the availability of the principal
+        let mut all_ids = RoaringBitmap::new();
+        let mut attending_ids = RoaringBitmap::new();
+        for (calendar_id, mut calendar) in self
+            .get_properties::<Object<Value>, _, _>(
+                account_id,
+                Collection::Calendar,
+                &calendar_ids,
+                Property::Value,
+            )
+            .await?
+        {
+            match calendar
+                .remove(&include_in_availability_property())
+                .as_string()
+            {
+                Some("none") => (),
+                Some("attending") => {
+                    attending_ids.insert(calendar_id);
+                }
+                _ => {
+                    all_ids.insert(calendar_id);
+                }
+            }
+        }
+        let addresses = principal
+            .iter_str(PrincipalField::Emails)
+            .map(|email| email.to_lowercase())
+            .collect::<Vec<_>>();
+
+        // Obtain the events overlapping the requested range
+        let event_ids = self
+            .filter(
+                account_id,
+                Collection::CalendarEvent,
+                vec![
+                    Filter::gt(Property::UtcEnd, from.max(0) as u64),
+                    Filter::lt(Property::UtcStart, to.max(0) as u64),
+                ],
+            )
+            .await?
+            .results;
+        let mut periods = Vec::new();
+        for (_, event) in self
+            .get_properties::<Object<Value>, _, _>(
+                account_id,
+                Collection::CalendarEvent,
+                &event_ids,
+                Property::Value,
+            )
+            .await?
+        {
+            let event_calendar_ids = event
+                .get(&Property::CalendarIds)
+                .as_list()
+                .into_iter()
+                .flatten()
+                .filter_map(|id| id.as_id().map(|id| id.document_id()))
+                .collect::<Vec<_>>();
+            let is_included = event_calendar_ids.iter().any(|id| all_ids.contains(*id))
+                || (event_calendar_ids
+                    .iter()
+                    .any(|id| attending_ids.contains(*id))
+                    && is_attending(&event, &addresses));
+            if !is_included {
+                continue;
+            }
+
+            let busy_status = match (
+                event.get(&Property::Status).as_string(),
+                event.get(&Property::FreeBusyStatus).as_string(),
+            ) {
+                (Some("cancelled"), _) | (_, Some("free")) => continue,
+                (Some("tentative"), _) => BusyStatus::Tentative,
+                _ => BusyStatus::Confirmed,
+            };
+
+            if let Some(schedule) = EventSchedule::from_object(&event) {
+                for (start, end) in schedule.occurrences(from, to) {
+                    let (start, end) = (start.max(from), end.min(to));
+                    if end > start {
+                        periods.push((start, end, busy_status));
+                    }
+                }
+            }
+        }
+
+        // Merge overlapping periods with the same status
+        periods.sort_unstable_by_key(|(start, end, busy_status)| (*busy_status, *start, *end));
+        let mut merged: Vec<(i64, i64, BusyStatus)> = Vec::with_capacity(periods.len());
+        for period in periods {
+            match merged.last_mut() {
+                Some(last) if last.2 == period.2 && period.0 <= last.1 => {
+                    last.1 = last.1.max(period.1);
+                }
+                _ => merged.push(period),
+            }
+        }
+        merged.sort_unstable_by_key(|(start, end, _)| (*start, *end));
+
+        Ok(GetAvailabilityResponse {
+            list: merged
+                .into_iter()
+                .map(|(start, end, busy_status)| BusyPeriod {
+                    utc_start: UTCDate::from_timestamp(start),
+                    utc_end: UTCDate::from_timestamp(end),
+                    busy_status,
+                })
+                .collect(),
+        })
+    }
+}
+
+// Events count towards the availability of calendars set to "attending" when they
+// have no participants, or when the principal organizes or has accepted them.
+fn is_attending(event: &Object<Value>, addresses: &[String]) -> bool {
+    let participants = event_participants(event);
+    participants.is_empty()
+        || participants.iter().any(|participant| {
+            addresses.contains(&participant.email.to_lowercase())
+                && (participant.is_owner
+                    || matches!(participant.status, Some("accepted" | "tentative")))
+        })
+}
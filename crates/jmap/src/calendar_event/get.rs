@@ -0,0 +1,123 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::Object,
+    types::{collection::Collection, date::UTCDate, property::Property, value::Value},
+};
+
+use crate::JMAP;
+
+use super::EventSchedule;
+
+impl JMAP {
+    pub async fn calendar_event_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::CalendarIds,
+            Property::Uid,
+            Property::Title,
+            Property::Description,
+            Property::Start,
+            Property::Duration,
+            Property::TimeZone,
+            Property::ShowWithoutTime,
+            Property::Status,
+            Property::FreeBusyStatus,
+            Property::RecurrenceRules,
+            Property::UtcStart,
+            Property::UtcEnd,
+        ]);
+        let account_id = request.account_id.document_id();
+        let event_ids = self
+            .get_document_ids(account_id, Collection::CalendarEvent)
+            .await?
+            .unwrap_or_default();
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            event_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self
+                .get_state(account_id, Collection::CalendarEvent)
+                .await?
+                .into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the event object
+            let document_id = id.document_id();
+            if !event_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let mut event = if let Some(event) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::CalendarEvent,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                event
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let schedule = EventSchedule::from_object(&event);
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    Property::Duration => event
+                        .properties
+                        .remove(property)
+                        .unwrap_or_else(|| Value::Text("PT0S".to_string())),
+                    Property::ShowWithoutTime => event
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::Bool(false)),
+                    Property::Status => event
+                        .properties
+                        .remove(property)
+                        .unwrap_or_else(|| Value::Text("confirmed".to_string())),
+                    Property::FreeBusyStatus => event
+                        .properties
+                        .remove(property)
+                        .unwrap_or_else(|| Value::Text("busy".to_string())),
+                    // The stored utcEnd spans all occurrences, the first one is returned here
+                    Property::UtcStart => schedule.as_ref().map_or(Value::Null, |schedule| {
+                        Value::Date(UTCDate::from_timestamp(schedule.utc_start()))
+                    }),
+                    Property::UtcEnd => schedule.as_ref().map_or(Value::Null, |schedule| {
+                        Value::Date(UTCDate::from_timestamp(
+                            schedule.utc_start() + schedule.duration,
+                        ))
+                    }),
+                    property => event.remove(property),
+                };
+                result.append(property.clone(), value);
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,1259 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::fmt::Write;
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime};
+use jmap_proto::{
+    method::availability::{BusyPeriod, BusyStatus},
+    object::Object,
+    types::{date::UTCDate, property::Property, value::Value},
+};
+
+use super::{
+    format_duration,
+    itip::{event_participants, participants_property, sequence_property, Participant},
+    parse_duration, time_zone_offset, EventSchedule, RecurrenceRule,
+};
+
+const PRODID: &str = "-//Stalwart Labs Ltd.//Stalwart Server//EN";
+
+#[derive(Debug, Default)]
+struct ICalendarProperty {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+#[derive(Debug, Default)]
+struct Component {
+    name: String,
+    properties: Vec<ICalendarProperty>,
+    components: Vec<Component>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreeBusyRequest {
+    pub uid: Option<String>,
+    pub organizer: Option<String>,
+    pub attendees: Vec<String>,
+    pub utc_start: i64,
+    pub utc_end: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ITipMethod {
+    Request,
+    Cancel,
+    Reply,
+}
+
+// Scheduling message (RFC 5546) received over iMIP
+#[derive(Debug, Clone)]
+pub struct ITipMessage {
+    pub method: ITipMethod,
+    pub event: Object<Value>,
+}
+
+#[derive(Debug, Default)]
+struct ParsedParticipant {
+    email: String,
+    name: Option<String>,
+    roles: Vec<&'static str>,
+    status: Option<String>,
+    expect_reply: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DateValue {
+    value: NaiveDateTime,
+    is_date: bool,
+    is_utc: bool,
+}
+
+/// Parses an iCalendar (RFC 5545) object containing a single event into a JSCalendar event.
+/// Overridden occurrences (VEVENTs with a RECURRENCE-ID) are not supported and are ignored.
+pub fn parse_icalendar(text: &str) -> Result<Object<Value>, &'static str> {
+    parse_components(text)
+        .ok_or("Invalid iCalendar object.")
+        .and_then(|calendar| parse_event(&calendar))
+}
+
+fn parse_event(calendar: &Component) -> Result<Object<Value>, &'static str> {
+    let mut master = None;
+    for component in &calendar.components {
+        match component.name.as_str() {
+            "VEVENT" if component.property("RECURRENCE-ID").is_none() => {
+                if master.is_some() {
+                    return Err("Only one event per calendar object is supported.");
+                }
+                master = Some(component);
+            }
+            "VEVENT" | "VTIMEZONE" => (),
+            "VTODO" | "VJOURNAL" | "VFREEBUSY" => {
+                return Err("Unsupported calendar component.");
+            }
+            _ => (),
+        }
+    }
+    let event = master.ok_or("Missing VEVENT component.")?;
+
+    let mut result = Object::with_capacity(10);
+    let mut start = None;
+    let mut time_zone = None;
+    let mut end = None;
+    let mut duration = None;
+    let mut rule = None;
+
+    for property in &event.properties {
+        match property.name.as_str() {
+            "UID" => {
+                result.append(Property::Uid, Value::Text(property.value.clone()));
+            }
+            "SUMMARY" => {
+                result.append(Property::Title, Value::Text(unescape(&property.value)));
+            }
+            "DESCRIPTION" => {
+                result.append(
+                    Property::Description,
+                    Value::Text(unescape(&property.value)),
+                );
+            }
+            "DTSTART" => {
+                start = Some(parse_date_value(property).ok_or("Invalid DTSTART.")?);
+                time_zone = property.param("tzid").map(|tz| tz.to_string());
+            }
+            "DTEND" => {
+                end = Some(parse_date_value(property).ok_or("Invalid DTEND.")?);
+            }
+            "DURATION" => {
+                duration = Some(
+                    parse_duration(property.value.trim_start_matches('+'))
+                        .ok_or("Invalid DURATION.")?,
+                );
+            }
+            "RRULE" => {
+                rule = Some(property.value.as_str());
+            }
+            "STATUS" => {
+                let status = property.value.to_ascii_lowercase();
+                if matches!(status.as_str(), "confirmed" | "cancelled" | "tentative") {
+                    result.append(Property::Status, Value::Text(status));
+                }
+            }
+            "TRANSP" => {
+                result.append(
+                    Property::FreeBusyStatus,
+                    Value::Text(
+                        if property.value.eq_ignore_ascii_case("TRANSPARENT") {
+                            "free"
+                        } else {
+                            "busy"
+                        }
+                        .to_string(),
+                    ),
+                );
+            }
+            _ => (),
+        }
+    }
+
+    // Resolve the start time and its offset from UTC
+    let start = start.ok_or("Missing DTSTART.")?;
+    let offset = if start.is_date {
+        result.append(Property::ShowWithoutTime, Value::Bool(true));
+        Some(0)
+    } else if start.is_utc {
+        result.append(Property::TimeZone, Value::Text("Etc/UTC".to_string()));
+        Some(0)
+    } else if let Some(time_zone) = time_zone {
+        let offset = match time_zone_offset(Some(&time_zone)) {
+            Some(offset) => Some(offset),
+            None => {
+                // Use the definition included in the calendar object for other zones
+                let offset = calendar
+                    .components
+                    .iter()
+                    .find(|component| {
+                        component.name == "VTIMEZONE"
+                            && component
+                                .property("TZID")
+                                .map_or(false, |tzid| tzid.value == time_zone)
+                    })
+                    .and_then(|component| observance_offset(component, &start.value));
+                if let Some(offset) = offset {
+                    result.append(
+                        Property::UtcStart,
+                        Value::Date(UTCDate::from_timestamp(
+                            start.value.and_utc().timestamp() + offset,
+                        )),
+                    );
+                }
+                offset
+            }
+        };
+        result.append(Property::TimeZone, Value::Text(time_zone));
+        offset
+    } else {
+        // Floating time
+        Some(0)
+    };
+    result.append(Property::Start, Value::Text(format_local(&start.value)));
+
+    // Resolve the duration
+    let duration = match (end, duration) {
+        (_, Some(duration)) => duration,
+        (Some(end), None) => {
+            let end_offset = if end.is_utc && !start.is_utc {
+                -offset.unwrap_or_default()
+            } else {
+                0
+            };
+            let duration =
+                end.value.and_utc().timestamp() + end_offset - start.value.and_utc().timestamp();
+            if duration < 0 {
+                return Err("DTEND cannot be before DTSTART.");
+            }
+            duration
+        }
+        // All-day events without an end last one day
+        (None, None) if start.is_date => 86400,
+        (None, None) => 0,
+    };
+    result.append(Property::Duration, Value::Text(format_duration(duration)));
+
+    if let Some(rule) = rule {
+        result.append(
+            Property::RecurrenceRules,
+            parse_recurrence_rule(rule, offset.unwrap_or_default(), start.is_utc)?,
+        );
+    }
+    parse_scheduling(event, &mut result);
+
+    Ok(result)
+}
+
+/// Parses an iTIP message. Requests contain the full event, while cancellations and
+/// replies only include the UID, sequence and participants.
+pub fn parse_itip(text: &str) -> Result<ITipMessage, &'static str> {
+    let calendar = parse_components(text).ok_or("Invalid iCalendar object.")?;
+    let method = match calendar
+        .property("METHOD")
+        .map(|method| method.value.trim().to_ascii_uppercase())
+        .as_deref()
+    {
+        Some("REQUEST") => ITipMethod::Request,
+        Some("CANCEL") => ITipMethod::Cancel,
+        Some("REPLY") => ITipMethod::Reply,
+        _ => return Err("Unsupported scheduling method."),
+    };
+
+    let event = if method == ITipMethod::Request {
+        parse_event(&calendar)?
+    } else {
+        let event = calendar
+            .components
+            .iter()
+            .find(|component| {
+                component.name == "VEVENT" && component.property("RECURRENCE-ID").is_none()
+            })
+            .ok_or("Missing VEVENT component.")?;
+        let mut result = Object::with_capacity(3);
+        result.append(
+            Property::Uid,
+            Value::Text(event.property("UID").ok_or("Missing UID.")?.value.clone()),
+        );
+        parse_scheduling(event, &mut result);
+        result
+    };
+    if event.get(&Property::Uid).as_string().is_none() {
+        return Err("Missing UID.");
+    }
+
+    Ok(ITipMessage { method, event })
+}
+
+/// Serializes a JSCalendar event as an iCalendar object.
+pub fn build_icalendar(event: &Object<Value>) -> String {
+    let schedule = EventSchedule::from_object(event);
+    let show_without_time = event
+        .get(&Property::ShowWithoutTime)
+        .as_bool()
+        .unwrap_or(false);
+    let time_zone = event
+        .get(&Property::TimeZone)
+        .as_string()
+        .filter(|_| !show_without_time);
+    let is_utc = time_zone.map_or(false, |tz| time_zone_offset(Some(tz)) == Some(0));
+    let offset = schedule.as_ref().map_or(0, |schedule| schedule.offset);
+
+    let mut ical = String::with_capacity(512);
+    ical.push_str("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n");
+    write_line(&mut ical, "PRODID", &[], PRODID);
+    if let Some(time_zone) = time_zone.filter(|_| !is_utc) {
+        // Only the offset of the event is known, describe the zone as a fixed offset
+        let tz_offset = format_utc_offset(-offset);
+        ical.push_str("BEGIN:VTIMEZONE\r\n");
+        write_line(&mut ical, "TZID", &[], time_zone);
+        ical.push_str("BEGIN:STANDARD\r\nDTSTART:19700101T000000\r\n");
+        write_line(&mut ical, "TZOFFSETFROM", &[], &tz_offset);
+        write_line(&mut ical, "TZOFFSETTO", &[], &tz_offset);
+        ical.push_str("END:STANDARD\r\nEND:VTIMEZONE\r\n");
+    }
+    ical.push_str("BEGIN:VEVENT\r\n");
+    if let Some(uid) = event.get(&Property::Uid).as_string() {
+        write_line(&mut ical, "UID", &[], uid);
+    }
+    write_line(
+        &mut ical,
+        "DTSTAMP",
+        &[],
+        &format_utc(chrono::Utc::now().timestamp()),
+    );
+
+    if let Some(schedule) = &schedule {
+        let duration = if show_without_time {
+            let days = (schedule.duration + 86399) / 86400;
+            write_line(
+                &mut ical,
+                "DTSTART",
+                &[("VALUE", "DATE")],
+                &schedule.start.format("%Y%m%d").to_string(),
+            );
+            format!("P{}D", days.max(1))
+        } else {
+            match time_zone {
+                Some(_) if is_utc => {
+                    write_line(&mut ical, "DTSTART", &[], &format_utc(schedule.utc_start()));
+                }
+                Some(time_zone) => {
+                    write_line(
+                        &mut ical,
+                        "DTSTART",
+                        &[("TZID", time_zone)],
+                        &format_floating(&schedule.start),
+                    );
+                }
+                None => {
+                    write_line(&mut ical, "DTSTART", &[], &format_floating(&schedule.start));
+                }
+            }
+            format_duration(schedule.duration)
+        };
+        write_line(&mut ical, "DURATION", &[], &duration);
+
+        if let Some(rule) = &schedule.rule {
+            write_line(
+                &mut ical,
+                "RRULE",
+                &[],
+                &format_recurrence_rule(rule, offset, time_zone.is_some(), show_without_time),
+            );
+        }
+    }
+
+    for (property, name) in [
+        (Property::Title, "SUMMARY"),
+        (Property::Description, "DESCRIPTION"),
+    ] {
+        if let Some(value) = event.get(&property).as_string() {
+            write_line(&mut ical, name, &[], &escape(value));
+        }
+    }
+    if let Some(status) = event.get(&Property::Status).as_string() {
+        write_line(&mut ical, "STATUS", &[], &status.to_ascii_uppercase());
+    }
+    if let Some(status) = event.get(&Property::FreeBusyStatus).as_string() {
+        write_line(
+            &mut ical,
+            "TRANSP",
+            &[],
+            if status == "free" {
+                "TRANSPARENT"
+            } else {
+                "OPAQUE"
+            },
+        );
+    }
+    if let Some(sequence) = event.get(&sequence_property()).as_uint() {
+        write_line(&mut ical, "SEQUENCE", &[], &sequence.to_string());
+    }
+    for participant in event_participants(event) {
+        write_participant(&mut ical, &participant);
+    }
+    ical.push_str("END:VEVENT\r\nEND:VCALENDAR\r\n");
+    ical
+}
+
+/// Builds the iTIP reply of an attendee to the organizer of an event.
+pub fn build_itip_reply(event: &Object<Value>, attendee: &Participant<'_>) -> Option<String> {
+    let participants = event_participants(event);
+    let organizer = participants
+        .iter()
+        .find(|participant| participant.is_owner)?;
+
+    let mut ical = String::with_capacity(512);
+    ical.push_str("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n");
+    write_line(&mut ical, "PRODID", &[], PRODID);
+    ical.push_str("METHOD:REPLY\r\nBEGIN:VEVENT\r\n");
+    write_line(
+        &mut ical,
+        "UID",
+        &[],
+        event.get(&Property::Uid).as_string()?,
+    );
+    write_line(
+        &mut ical,
+        "DTSTAMP",
+        &[],
+        &format_utc(chrono::Utc::now().timestamp()),
+    );
+    if let Some(sequence) = event.get(&sequence_property()).as_uint() {
+        write_line(&mut ical, "SEQUENCE", &[], &sequence.to_string());
+    }
+    if let Some(title) = event.get(&Property::Title).as_string() {
+        write_line(&mut ical, "SUMMARY", &[], &escape(title));
+    }
+    write_participant(
+        &mut ical,
+        &Participant {
+            is_attendee: false,
+            ..organizer.clone()
+        },
+    );
+    write_participant(
+        &mut ical,
+        &Participant {
+            is_owner: false,
+            ..attendee.clone()
+        },
+    );
+    ical.push_str("END:VEVENT\r\nEND:VCALENDAR\r\n");
+    Some(ical)
+}
+
+/// Parses a VFREEBUSY request (RFC 6638) sent to a scheduling outbox.
+pub fn parse_freebusy_request(text: &str) -> Option<FreeBusyRequest> {
+    let calendar = parse_components(text)?;
+    let freebusy = calendar
+        .components
+        .iter()
+        .find(|component| component.name == "VFREEBUSY")?;
+    let mut request = FreeBusyRequest {
+        uid: None,
+        organizer: None,
+        attendees: Vec::new(),
+        utc_start: 0,
+        utc_end: 0,
+    };
+    let mut start = None;
+    let mut end = None;
+
+    for property in &freebusy.properties {
+        match property.name.as_str() {
+            "UID" => request.uid = Some(property.value.clone()),
+            "ORGANIZER" => request.organizer = Some(property.value.clone()),
+            "ATTENDEE" => request.attendees.push(property.value.clone()),
+            "DTSTART" => start = parse_date_value(property).filter(|date| date.is_utc),
+            "DTEND" => end = parse_date_value(property).filter(|date| date.is_utc),
+            _ => (),
+        }
+    }
+    request.utc_start = start?.value.and_utc().timestamp();
+    request.utc_end = end?.value.and_utc().timestamp();
+
+    (request.utc_start < request.utc_end && !request.attendees.is_empty()).then_some(request)
+}
+
+/// Builds the VFREEBUSY reply of an attendee from its busy periods.
+pub fn build_freebusy_reply(
+    request: &FreeBusyRequest,
+    attendee: &str,
+    periods: &[BusyPeriod],
+) -> String {
+    let mut ical = String::with_capacity(256 + periods.len() * 64);
+    ical.push_str("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n");
+    write_line(&mut ical, "PRODID", &[], PRODID);
+    ical.push_str("METHOD:REPLY\r\nBEGIN:VFREEBUSY\r\n");
+    if let Some(uid) = &request.uid {
+        write_line(&mut ical, "UID", &[], uid);
+    }
+    write_line(
+        &mut ical,
+        "DTSTAMP",
+        &[],
+        &format_utc(chrono::Utc::now().timestamp()),
+    );
+    write_line(&mut ical, "DTSTART", &[], &format_utc(request.utc_start));
+    write_line(&mut ical, "DTEND", &[], &format_utc(request.utc_end));
+    if let Some(organizer) = &request.organizer {
+        write_line(&mut ical, "ORGANIZER", &[], organizer);
+    }
+    write_line(&mut ical, "ATTENDEE", &[], attendee);
+    for period in periods {
+        write_line(
+            &mut ical,
+            "FREEBUSY",
+            &[(
+                "FBTYPE",
+                match period.busy_status {
+                    BusyStatus::Tentative => "BUSY-TENTATIVE",
+                    BusyStatus::Unavailable => "BUSY-UNAVAILABLE",
+                    BusyStatus::Confirmed => "BUSY",
+                },
+            )],
+            &format!(
+                "{}/{}",
+                format_utc(period.utc_start.timestamp()),
+                format_utc(period.utc_end.timestamp())
+            ),
+        );
+    }
+    ical.push_str("END:VFREEBUSY\r\nEND:VCALENDAR\r\n");
+    ical
+}
+
+fn parse_components(text: &str) -> Option<Component> {
+    // Unfold lines
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        if let Some(continuation) = line.strip_prefix([' ', '\t']) {
+            lines.last_mut()?.push_str(continuation);
+        } else if !line.is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+
+    let mut stack: Vec<Component> = Vec::new();
+    for line in lines {
+        let (name_params, value) = split_unquoted(&line, ':')?;
+        let mut name_params = split_unquoted_all(name_params, ';').into_iter();
+        let name = name_params.next()?.to_ascii_uppercase();
+
+        match name.as_str() {
+            "BEGIN" => {
+                stack.push(Component {
+                    name: value.to_ascii_uppercase(),
+                    ..Default::default()
+                });
+            }
+            "END" => {
+                let component = stack.pop()?;
+                if !component.name.eq_ignore_ascii_case(value) {
+                    return None;
+                }
+                match stack.last_mut() {
+                    Some(parent) => parent.components.push(component),
+                    None if component.name == "VCALENDAR" => return Some(component),
+                    None => return None,
+                }
+            }
+            _ => {
+                let params = name_params
+                    .filter_map(|param| {
+                        param.split_once('=').map(|(key, value)| {
+                            (
+                                key.to_ascii_lowercase(),
+                                value.trim_matches('"').to_string(),
+                            )
+                        })
+                    })
+                    .collect();
+                stack.last_mut()?.properties.push(ICalendarProperty {
+                    name,
+                    params,
+                    value: value.to_string(),
+                });
+            }
+        }
+    }
+
+    None
+}
+
+impl Component {
+    fn property(&self, name: &str) -> Option<&ICalendarProperty> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+}
+
+impl ICalendarProperty {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Converts SEQUENCE, ORGANIZER and ATTENDEE into JSCalendar properties,
+// participants are keyed by their lowercase address
+fn parse_scheduling(event: &Component, result: &mut Object<Value>) {
+    let mut participants: Vec<ParsedParticipant> = Vec::new();
+    for property in &event.properties {
+        let is_organizer = match property.name.as_str() {
+            "ORGANIZER" => true,
+            "ATTENDEE" => false,
+            "SEQUENCE" => {
+                if let Ok(sequence) = property.value.trim().parse::<u64>() {
+                    result.set(sequence_property(), Value::UnsignedInt(sequence));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let email = match property.value.trim() {
+            value if value.len() > 7 && value[..7].eq_ignore_ascii_case("mailto:") => {
+                value[7..].to_lowercase()
+            }
+            _ => continue,
+        };
+        let participant = match participants
+            .iter()
+            .position(|participant| participant.email == email)
+        {
+            Some(pos) => &mut participants[pos],
+            None => {
+                participants.push(ParsedParticipant {
+                    email,
+                    ..Default::default()
+                });
+                participants.last_mut().unwrap()
+            }
+        };
+
+        if let Some(name) = property.param("cn") {
+            participant.name = Some(name.to_string());
+        }
+        if is_organizer {
+            participant.roles.push("owner");
+        } else {
+            participant.roles.push("attendee");
+            match property
+                .param("role")
+                .map(|role| role.to_ascii_uppercase())
+                .as_deref()
+            {
+                Some("CHAIR") => participant.roles.push("chair"),
+                Some("OPT-PARTICIPANT") => participant.roles.push("optional"),
+                Some("NON-PARTICIPANT") => participant.roles.push("informational"),
+                _ => (),
+            }
+            participant.status = Some(
+                property
+                    .param("partstat")
+                    .unwrap_or("NEEDS-ACTION")
+                    .to_ascii_lowercase(),
+            );
+            participant.expect_reply = property
+                .param("rsvp")
+                .is_some_and(|rsvp| rsvp.eq_ignore_ascii_case("TRUE"));
+        }
+    }
+
+    if !participants.is_empty() {
+        let field = |name: &str| Property::_T(name.to_string());
+        result.set(
+            participants_property(),
+            Value::Object(Object {
+                properties: participants
+                    .into_iter()
+                    .map(|participant| {
+                        let mut object = Object::with_capacity(6)
+                            .with_property(field("@type"), "Participant")
+                            .with_property(field("email"), participant.email.clone())
+                            .with_property(
+                                field("roles"),
+                                Value::Object(Object {
+                                    properties: participant
+                                        .roles
+                                        .into_iter()
+                                        .map(|role| (field(role), Value::Bool(true)))
+                                        .collect(),
+                                }),
+                            );
+                        if let Some(name) = participant.name {
+                            object.append(field("name"), name);
+                        }
+                        if let Some(status) = participant.status {
+                            object.append(field("participationStatus"), status);
+                        }
+                        if participant.expect_reply {
+                            object.append(field("expectReply"), true);
+                        }
+                        (Property::_T(participant.email), Value::Object(object))
+                    })
+                    .collect(),
+            }),
+        );
+    }
+}
+
+fn write_participant(ical: &mut String, participant: &Participant<'_>) {
+    let name = participant
+        .name
+        .map(|name| format!("\"{}\"", name.replace('"', "")));
+    let value = format!("mailto:{}", participant.email);
+    if participant.is_owner {
+        let mut params = Vec::with_capacity(1);
+        if let Some(name) = &name {
+            params.push(("CN", name.as_str()));
+        }
+        write_line(ical, "ORGANIZER", &params, &value);
+    }
+    if participant.is_attendee {
+        let status = participant
+            .status
+            .unwrap_or("needs-action")
+            .to_ascii_uppercase();
+        let mut params = Vec::with_capacity(4);
+        if let Some(name) = &name {
+            params.push(("CN", name.as_str()));
+        }
+        params.push(("PARTSTAT", status.as_str()));
+        params.push((
+            "ROLE",
+            if participant.is_chair {
+                "CHAIR"
+            } else if participant.is_optional {
+                "OPT-PARTICIPANT"
+            } else if participant.is_informational {
+                "NON-PARTICIPANT"
+            } else {
+                "REQ-PARTICIPANT"
+            },
+        ));
+        if participant.expect_reply {
+            params.push(("RSVP", "TRUE"));
+        }
+        write_line(ical, "ATTENDEE", &params, &value);
+    }
+}
+
+fn parse_date_value(property: &ICalendarProperty) -> Option<DateValue> {
+    let value = property.value.trim();
+    if property
+        .param("value")
+        .map_or(false, |v| v.eq_ignore_ascii_case("DATE"))
+        || value.len() == 8
+    {
+        NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|value| DateValue {
+                value,
+                is_date: true,
+                is_utc: false,
+            })
+    } else {
+        let (value, is_utc) = match value.strip_suffix(['Z', 'z']) {
+            Some(value) => (value, true),
+            None => (value, false),
+        };
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .ok()
+            .map(|value| DateValue {
+                value,
+                is_date: false,
+                is_utc,
+            })
+    }
+}
+
+// Returns the number of seconds to add to a local time to obtain UTC. Observances are
+// selected by month, which is accurate except for the days surrounding a transition.
+fn observance_offset(time_zone: &Component, start: &NaiveDateTime) -> Option<i64> {
+    let mut standard = None;
+    let mut daylight = None;
+    for observance in &time_zone.components {
+        let month = observance
+            .property("DTSTART")
+            .and_then(parse_date_value)
+            .map(|date| date.value.month())?;
+        let offset = observance
+            .property("TZOFFSETTO")
+            .and_then(|offset| parse_utc_offset(&offset.value))?;
+        match observance.name.as_str() {
+            "STANDARD" => standard = Some((month, offset)),
+            "DAYLIGHT" => daylight = Some((month, offset)),
+            _ => (),
+        }
+    }
+
+    let offset = match (standard, daylight) {
+        (Some((standard_month, standard)), Some((daylight_month, daylight))) => {
+            let month = start.month();
+            let is_daylight = if daylight_month < standard_month {
+                (daylight_month..standard_month).contains(&month)
+            } else {
+                month >= daylight_month || month < standard_month
+            };
+            if is_daylight {
+                daylight
+            } else {
+                standard
+            }
+        }
+        (Some((_, offset)), None) | (None, Some((_, offset))) => offset,
+        (None, None) => return None,
+    };
+
+    Some(-offset)
+}
+
+fn parse_recurrence_rule(rule: &str, offset: i64, is_utc: bool) -> Result<Value, &'static str> {
+    let mut result =
+        Object::with_capacity(4).with_property(Property::_T("@type".to_string()), "RecurrenceRule");
+    for part in rule.split(';').filter(|part| !part.is_empty()) {
+        let (key, value) = part.split_once('=').ok_or("Invalid RRULE.")?;
+        let (key, value) = match key.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                let frequency = value.to_ascii_lowercase();
+                if !matches!(
+                    frequency.as_str(),
+                    "yearly" | "monthly" | "weekly" | "daily" | "hourly" | "minutely" | "secondly"
+                ) {
+                    return Err("Invalid recurrence frequency.");
+                }
+                ("frequency", Value::Text(frequency))
+            }
+            "INTERVAL" => (
+                "interval",
+                Value::UnsignedInt(
+                    value
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|v| *v > 0)
+                        .ok_or("Invalid recurrence interval.")?,
+                ),
+            ),
+            "COUNT" => (
+                "count",
+                Value::UnsignedInt(
+                    value
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|v| *v > 0)
+                        .ok_or("Invalid recurrence count.")?,
+                ),
+            ),
+            "UNTIL" => {
+                let until = parse_date_value(&ICalendarProperty {
+                    value: value.to_string(),
+                    ..Default::default()
+                })
+                .ok_or("Invalid recurrence end date.")?;
+
+                // JSCalendar expresses the end of a recurrence in the time zone of the event
+                let until = if until.is_utc && !is_utc {
+                    until
+                        .value
+                        .checked_sub_signed(chrono::TimeDelta::try_seconds(offset).unwrap())
+                        .ok_or("Invalid recurrence end date.")?
+                } else if until.is_date {
+                    until
+                        .value
+                        .date()
+                        .and_hms_opt(23, 59, 59)
+                        .unwrap_or(until.value)
+                } else {
+                    until.value
+                };
+                ("until", Value::Text(format_local(&until)))
+            }
+            "WKST" => continue,
+            _ => return Err("Unsupported recurrence rule."),
+        };
+        result.append(Property::_T(key.to_string()), value);
+    }
+
+    Ok(Value::List(vec![Value::Object(result)]))
+}
+
+fn format_recurrence_rule(
+    rule: &RecurrenceRule,
+    offset: i64,
+    has_time_zone: bool,
+    show_without_time: bool,
+) -> String {
+    let mut result = format!("FREQ={:?}", rule.frequency).to_ascii_uppercase();
+    if rule.interval > 1 {
+        let _ = write!(result, ";INTERVAL={}", rule.interval);
+    }
+    if let Some(count) = rule.count {
+        let _ = write!(result, ";COUNT={count}");
+    }
+    if let Some(until) = &rule.until {
+        // UNTIL has to be in UTC when the start has a time zone
+        let until = if show_without_time {
+            until.format("%Y%m%d").to_string()
+        } else if has_time_zone {
+            format_utc(until.and_utc().timestamp() + offset)
+        } else {
+            format_floating(until)
+        };
+        let _ = write!(result, ";UNTIL={until}");
+    }
+    result
+}
+
+fn format_local(value: &NaiveDateTime) -> String {
+    value.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+fn format_floating(value: &NaiveDateTime) -> String {
+    value.format("%Y%m%dT%H%M%S").to_string()
+}
+
+fn format_utc(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+fn parse_utc_offset(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (sign, value) = match value.as_bytes().first()? {
+        b'+' => (1, &value[1..]),
+        b'-' => (-1, &value[1..]),
+        _ => return None,
+    };
+    if !matches!(value.len(), 4 | 6) || !value.bytes().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    let hours = value[0..2].parse::<i64>().ok()?;
+    let minutes = value[2..4].parse::<i64>().ok()?;
+    let seconds = value.get(4..6).map_or(Some(0), |s| s.parse::<i64>().ok())?;
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+fn format_utc_offset(offset: i64) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    format!("{sign}{:02}{:02}", offset / 3600, (offset % 3600) / 60)
+}
+
+// Writes a content line folded at 75 octets
+fn write_line(ical: &mut String, name: &str, params: &[(&str, &str)], value: &str) {
+    let mut line = String::with_capacity(name.len() + value.len() + 16);
+    line.push_str(name);
+    for (key, param) in params {
+        let _ = write!(line, ";{key}={param}");
+    }
+    line.push(':');
+    line.push_str(value);
+
+    let mut line_len = 0;
+    for ch in line.chars() {
+        if line_len + ch.len_utf8() > 75 {
+            ical.push_str("\r\n ");
+            line_len = 1;
+        }
+        ical.push(ch);
+        line_len += ch.len_utf8();
+    }
+    ical.push_str("\r\n");
+}
+
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' | ';' | ',' => {
+                result.push('\\');
+                result.push(ch);
+            }
+            '\n' => result.push_str("\\n"),
+            '\r' => (),
+            ch => result.push(ch),
+        }
+    }
+    result
+}
+
+fn split_unquoted(text: &str, separator: char) -> Option<(&str, &str)> {
+    let mut in_quotes = false;
+    for (pos, ch) in text.char_indices() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            ch if ch == separator && !in_quotes => {
+                return Some((&text[..pos], &text[pos + 1..]));
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+fn split_unquoted_all(mut text: &str, separator: char) -> Vec<&str> {
+    let mut items = Vec::new();
+    while let Some((item, rest)) = split_unquoted(text, separator) {
+        items.push(item);
+        text = rest;
+    }
+    items.push(text);
+    items
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            match chars.next() {
+                Some('n' | 'N') => result.push('\n'),
+                Some(ch) => result.push(ch),
+                None => (),
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use jmap_proto::types::{property::Property, value::Value};
+
+    use crate::calendar_event::itip::{event_participants, sequence_property, Participant};
+
+    use super::{
+        build_icalendar, build_itip_reply, parse_freebusy_request, parse_icalendar, parse_itip,
+        ITipMethod,
+    };
+
+    #[test]
+    fn parse_and_build_icalendar() {
+        let ical = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "VERSION:2.0\r\n",
+            "PRODID:-//Example Corp.//CalDAV Client//EN\r\n",
+            "BEGIN:VTIMEZONE\r\n",
+            "TZID:Europe/Berlin\r\n",
+            "BEGIN:DAYLIGHT\r\n",
+            "DTSTART:19700329T020000\r\n",
+            "TZOFFSETFROM:+0100\r\n",
+            "TZOFFSETTO:+0200\r\n",
+            "END:DAYLIGHT\r\n",
+            "BEGIN:STANDARD\r\n",
+            "DTSTART:19701025T030000\r\n",
+            "TZOFFSETFROM:+0200\r\n",
+            "TZOFFSETTO:+0100\r\n",
+            "END:STANDARD\r\n",
+            "END:VTIMEZONE\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:3f2a6b1e-9d1c-4a8e-bb1f-0c5d2e7a9f10\r\n",
+            "DTSTAMP:20240601T120000Z\r\n",
+            "DTSTART;TZID=Europe/Berlin:20240610T090000\r\n",
+            "DTEND;TZID=Europe/Berlin:20240610T103000\r\n",
+            "RRULE:FREQ=WEEKLY;INTERVAL=2;UNTIL=20240805T070000Z\r\n",
+            "SUMMARY:Team sync\\, weekly\r\n",
+            "DESCRIPTION:Agenda:\\n- Status upd\r\n",
+            " ates\r\n",
+            "STATUS:TENTATIVE\r\n",
+            "BEGIN:VALARM\r\n",
+            "TRIGGER:-PT15M\r\n",
+            "ACTION:DISPLAY\r\n",
+            "END:VALARM\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n"
+        );
+
+        let event = parse_icalendar(ical).unwrap();
+        for (property, expected) in [
+            (Property::Uid, "3f2a6b1e-9d1c-4a8e-bb1f-0c5d2e7a9f10"),
+            (Property::Title, "Team sync, weekly"),
+            (Property::Description, "Agenda:\n- Status updates"),
+            (Property::Start, "2024-06-10T09:00:00"),
+            (Property::Duration, "PT1H30M"),
+            (Property::TimeZone, "Europe/Berlin"),
+            (Property::Status, "tentative"),
+        ] {
+            assert_eq!(
+                event.get(&property).as_string(),
+                Some(expected),
+                "{property}"
+            );
+        }
+        let utc_start = match event.get(&Property::UtcStart) {
+            Value::Date(date) => date.timestamp(),
+            other => panic!("Unexpected utcStart {other:?}"),
+        };
+        assert_eq!(utc_start, 1718002800);
+        let rule = serde_json::to_value(event.get(&Property::RecurrenceRules)).unwrap();
+        assert_eq!(
+            rule,
+            serde_json::json!([{
+                "@type": "RecurrenceRule",
+                "frequency": "weekly",
+                "interval": 2,
+                "until": "2024-08-05T09:00:00"
+            }])
+        );
+
+        // Events are stored with the resolved offset, the zone is exported as a fixed offset
+        let mut stored = event.clone();
+        stored.remove(&Property::UtcStart);
+        stored.append(Property::UtcStart, Value::UnsignedInt(utc_start as u64));
+        let exported = build_icalendar(&stored);
+        assert!(exported.contains("TZOFFSETTO:+0200\r\n"), "{exported}");
+        assert!(
+            exported.contains("DTSTART;TZID=Europe/Berlin:20240610T090000\r\n"),
+            "{exported}"
+        );
+        assert!(
+            exported.contains("RRULE:FREQ=WEEKLY;INTERVAL=2;UNTIL=20240805T070000Z\r\n"),
+            "{exported}"
+        );
+        let reparsed = parse_icalendar(&exported).unwrap();
+        for property in [
+            Property::Uid,
+            Property::Title,
+            Property::Description,
+            Property::Start,
+            Property::Duration,
+            Property::TimeZone,
+            Property::Status,
+            Property::RecurrenceRules,
+        ] {
+            assert_eq!(reparsed.get(&property), event.get(&property), "{property}");
+        }
+
+        // All-day events
+        let event = parse_icalendar(concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:holiday-1\r\n",
+            "DTSTART;VALUE=DATE:20241225\r\n",
+            "DTEND;VALUE=DATE:20241227\r\n",
+            "TRANSP:TRANSPARENT\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n"
+        ))
+        .unwrap();
+        assert_eq!(event.get(&Property::Duration).as_string(), Some("P2D"));
+        assert_eq!(event.get(&Property::ShowWithoutTime).as_bool(), Some(true));
+        assert_eq!(
+            event.get(&Property::FreeBusyStatus).as_string(),
+            Some("free")
+        );
+        let exported = build_icalendar(&event);
+        assert!(exported.contains("DTSTART;VALUE=DATE:20241225\r\nDURATION:P2D\r\n"));
+
+        // Unsupported content
+        for ical in [
+            "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:a\r\nEND:VTODO\r\nEND:VCALENDAR\r\n",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:a\r\nEND:VEVENT\r\n",
+            concat!(
+                "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:a\r\nDTSTART:20240101T100000Z\r\n",
+                "RRULE:FREQ=WEEKLY;BYDAY=MO,WE\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+            ),
+        ] {
+            assert!(parse_icalendar(ical).is_err(), "{ical}");
+        }
+    }
+
+    #[test]
+    fn parse_freebusy() {
+        let request = parse_freebusy_request(concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "METHOD:REQUEST\r\n",
+            "BEGIN:VFREEBUSY\r\n",
+            "UID:fb-1\r\n",
+            "DTSTART:20240610T000000Z\r\n",
+            "DTEND:20240611T000000Z\r\n",
+            "ORGANIZER:mailto:jane@example.com\r\n",
+            "ATTENDEE:mailto:bill@example.com\r\n",
+            "ATTENDEE:mailto:john@example.com\r\n",
+            "END:VFREEBUSY\r\n",
+            "END:VCALENDAR\r\n"
+        ))
+        .unwrap();
+        assert_eq!(
+            request.attendees,
+            vec!["mailto:bill@example.com", "mailto:john@example.com"]
+        );
+        assert_eq!(request.utc_end - request.utc_start, 86400);
+    }
+
+    #[test]
+    fn parse_and_reply_itip() {
+        let request = parse_itip(concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "METHOD:REQUEST\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:itip-1\r\n",
+            "SEQUENCE:2\r\n",
+            "DTSTART:20240610T090000Z\r\n",
+            "DURATION:PT1H\r\n",
+            "SUMMARY:Planning\r\n",
+            "ORGANIZER;CN=Jane Doe:mailto:Jane@example.com\r\n",
+            "ATTENDEE;CN=John Doe;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:john@example.com\r\n",
+            "ATTENDEE;ROLE=OPT-PARTICIPANT:mailto:bill@example.com\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n"
+        ))
+        .unwrap();
+        assert_eq!(request.method, ITipMethod::Request);
+        assert_eq!(
+            request.event.get(&sequence_property()),
+            &Value::UnsignedInt(2)
+        );
+        let participants = event_participants(&request.event);
+        assert_eq!(participants.len(), 3);
+        let organizer = participants.iter().find(|p| p.is_owner).unwrap();
+        assert_eq!(organizer.email, "jane@example.com");
+        assert_eq!(organizer.name, Some("Jane Doe"));
+        let attendee = participants
+            .iter()
+            .find(|p| p.email == "john@example.com")
+            .unwrap();
+        assert!(attendee.is_attendee && attendee.expect_reply && !attendee.is_owner);
+        assert_eq!(attendee.status, Some("needs-action"));
+        assert!(participants
+            .iter()
+            .any(|p| p.email == "bill@example.com" && p.is_optional));
+
+        // Participants are preserved when exporting the event
+        let exported = build_icalendar(&request.event);
+        assert!(exported.contains("SEQUENCE:2\r\n"), "{exported}");
+        assert!(
+            exported.contains("ORGANIZER;CN=\"Jane Doe\":mailto:jane@example.com\r\n"),
+            "{exported}"
+        );
+
+        // Replies contain the organizer and the responding attendee
+        let reply = build_itip_reply(
+            &request.event,
+            &Participant {
+                status: Some("accepted"),
+                ..attendee.clone()
+            },
+        )
+        .unwrap();
+        assert!(reply.contains("METHOD:REPLY\r\n"), "{reply}");
+        assert!(!reply.contains("bill@example.com"), "{reply}");
+        let reply = parse_itip(&reply).unwrap();
+        assert_eq!(reply.method, ITipMethod::Reply);
+        assert_eq!(reply.event.get(&Property::Uid).as_string(), Some("itip-1"));
+        assert!(event_participants(&reply.event)
+            .iter()
+            .any(|p| p.email == "john@example.com" && p.status == Some("accepted")));
+
+        // Only scheduling methods that can be applied are accepted
+        assert!(parse_itip(concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "METHOD:PUBLISH\r\n",
+            "BEGIN:VEVENT\r\nUID:a\r\nEND:VEVENT\r\n",
+            "END:VCALENDAR\r\n"
+        ))
+        .is_err());
+    }
+}
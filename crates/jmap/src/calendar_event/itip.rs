@@ -0,0 +1,465 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::listener::stream::NullIo;
+use directory::{backend::internal::PrincipalField, QueryBy};
+use jmap_proto::{
+    method::set::{RequestArguments, SetRequest},
+    object::Object,
+    types::{
+        collection::Collection,
+        id::Id,
+        property::Property,
+        state::StateChange,
+        value::{SetValue, Value},
+    },
+};
+use mail_builder::{
+    headers::{address::Address, content_type::ContentType, HeaderType},
+    mime::{BodyPart, MimePart},
+    MessageBuilder,
+};
+use mail_parser::{Message, MimeHeaders};
+use smtp::core::{Session, SessionAddress};
+use store::query::Filter;
+use trc::AddContext;
+use utils::map::vec_map::VecMap;
+
+use crate::{calendar::get::DEFAULT_CALENDAR_ID, JMAP};
+
+use super::icalendar::{build_itip_reply, parse_itip, ITipMessage, ITipMethod};
+
+pub fn participants_property() -> Property {
+    Property::_T("participants".to_string())
+}
+
+pub fn sequence_property() -> Property {
+    Property::_T("sequence".to_string())
+}
+
+#[derive(Debug, Clone)]
+pub struct Participant<'x> {
+    pub email: &'x str,
+    pub name: Option<&'x str>,
+    pub status: Option<&'x str>,
+    pub is_owner: bool,
+    pub is_attendee: bool,
+    pub is_chair: bool,
+    pub is_optional: bool,
+    pub is_informational: bool,
+    pub expect_reply: bool,
+}
+
+/// Returns the participants of an event that have an email address,
+/// the organizer being the participant with the "owner" role.
+pub fn event_participants(event: &Object<Value>) -> Vec<Participant<'_>> {
+    event
+        .get(&participants_property())
+        .as_obj()
+        .map(|participants| {
+            participants
+                .properties
+                .values()
+                .filter_map(|participant| {
+                    let participant = participant.as_obj()?;
+                    let roles = field(participant, "roles").as_obj();
+                    let has_role = |role: &str| {
+                        roles.is_some_and(|roles| field(roles, role).as_bool() == Some(true))
+                    };
+
+                    Some(Participant {
+                        email: field(participant, "email").as_string()?,
+                        name: field(participant, "name").as_string(),
+                        status: field(participant, "participationStatus").as_string(),
+                        is_owner: has_role("owner"),
+                        is_attendee: has_role("attendee"),
+                        is_chair: has_role("chair"),
+                        is_optional: has_role("optional"),
+                        is_informational: has_role("informational"),
+                        expect_reply: field(participant, "expectReply").as_bool() == Some(true),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the first calendar invitation, cancellation or reply attached to a message.
+pub fn itip_from_message(message: &Message<'_>) -> Option<ITipMessage> {
+    message.parts.iter().find_map(|part| {
+        if part.is_content_type("text", "calendar") {
+            parse_itip(part.text_contents()?).ok()
+        } else {
+            None
+        }
+    })
+}
+
+impl JMAP {
+    /// Applies a scheduling message delivered to an account. Requests create or update
+    /// a tentative copy of the event, cancellations mark it as cancelled and replies
+    /// update the participation status of an attendee in the organizer's copy.
+    pub async fn calendar_event_ingest_itip(
+        &self,
+        account_id: u32,
+        itip: &ITipMessage,
+        sender: &str,
+        rcpt: &str,
+    ) -> trc::Result<Option<StateChange>> {
+        let uid = itip
+            .event
+            .get(&Property::Uid)
+            .as_string()
+            .unwrap_or_default();
+        let sequence = itip.event.get(&sequence_property()).as_uint().unwrap_or(0);
+        let incoming = event_participants(&itip.event);
+
+        // Obtain the current copy of the event
+        let current = if let Some(document_id) = self
+            .filter(
+                account_id,
+                Collection::CalendarEvent,
+                vec![Filter::eq(Property::Uid, uid)],
+            )
+            .await
+            .caused_by(trc::location!())?
+            .results
+            .min()
+        {
+            self.get_property::<Object<Value>>(
+                account_id,
+                Collection::CalendarEvent,
+                document_id,
+                Property::Value,
+            )
+            .await
+            .caused_by(trc::location!())?
+            .map(|event| (document_id, event))
+        } else {
+            None
+        };
+        let current_participants = current
+            .as_ref()
+            .map(|(_, event)| event_participants(event))
+            .unwrap_or_default();
+        let current_sequence = current
+            .as_ref()
+            .and_then(|(_, event)| event.get(&sequence_property()).as_uint())
+            .unwrap_or(0);
+
+        let mut changes = VecMap::new();
+        match itip.method {
+            ITipMethod::Request | ITipMethod::Cancel => {
+                // Only the organizer can modify the copy of an attendee
+                if !has_organizer(&incoming, sender)
+                    || !has_attendee(&incoming, rcpt)
+                    || current
+                        .as_ref()
+                        .map_or(itip.method == ITipMethod::Cancel, |_| {
+                            !has_organizer(&current_participants, sender)
+                                || sequence < current_sequence
+                        })
+                {
+                    return Ok(None);
+                }
+
+                if itip.method == ITipMethod::Request {
+                    let mut event = itip.event.clone();
+
+                    // Keep the response of the attendee unless the event was rescheduled
+                    if sequence == current_sequence {
+                        if let Some(status) = current_participants
+                            .iter()
+                            .find(|participant| participant.email.eq_ignore_ascii_case(rcpt))
+                            .and_then(|participant| participant.status)
+                        {
+                            set_participation_status(&mut event, rcpt, status);
+                        }
+                    }
+
+                    // Events remain tentative until the invitation is accepted
+                    let is_accepted = event_participants(&event).iter().any(|participant| {
+                        participant.email.eq_ignore_ascii_case(rcpt)
+                            && participant.status == Some("accepted")
+                    });
+                    if !is_accepted && event.get(&Property::Status).as_string() != Some("cancelled")
+                    {
+                        event.set(Property::Status, Value::Text("tentative".to_string()));
+                    }
+
+                    for (property, value) in event.properties {
+                        if current.is_none() || property != Property::Uid {
+                            changes.append(property, SetValue::Value(value));
+                        }
+                    }
+                } else {
+                    changes.append(
+                        Property::Status,
+                        SetValue::Value(Value::Text("cancelled".to_string())),
+                    );
+                    changes.append(
+                        sequence_property(),
+                        SetValue::Value(Value::UnsignedInt(sequence)),
+                    );
+                }
+            }
+            ITipMethod::Reply => {
+                // Replies are accepted from attendees of events organized by the recipient
+                let status = incoming
+                    .iter()
+                    .find(|participant| {
+                        participant.is_attendee && participant.email.eq_ignore_ascii_case(sender)
+                    })
+                    .and_then(|participant| participant.status);
+                let (status, mut event) = match (status, &current) {
+                    (Some(status), Some((_, event)))
+                        if has_organizer(&current_participants, rcpt)
+                            && has_attendee(&current_participants, sender)
+                            && sequence >= current_sequence =>
+                    {
+                        (status, event.clone())
+                    }
+                    _ => return Ok(None),
+                };
+                set_participation_status(&mut event, sender, status);
+                changes.append(
+                    participants_property(),
+                    SetValue::Value(event.remove(&participants_property())),
+                );
+            }
+        }
+
+        let mut request = SetRequest {
+            account_id: Id::from(account_id),
+            if_in_state: None,
+            create: None,
+            update: None,
+            destroy: None,
+            arguments: RequestArguments::CalendarEvent,
+        };
+        if let Some((document_id, _)) = &current {
+            request.update = Some(VecMap::from_iter([(
+                Id::from(*document_id),
+                Object {
+                    properties: changes,
+                },
+            )]));
+        } else {
+            changes.append(
+                Property::CalendarIds,
+                SetValue::Value(Value::List(vec![Value::Id(Id::from(
+                    self.calendar_default_id(account_id).await?,
+                ))])),
+            );
+            request.create = Some(VecMap::from_iter([(
+                "i".to_string(),
+                Object {
+                    properties: changes,
+                },
+            )]));
+        }
+
+        let response = self
+            .calendar_event_set(request)
+            .await
+            .caused_by(trc::location!())?;
+        if response.state_change.is_some() {
+            trc::event!(
+                MessageIngest(trc::MessageIngestEvent::Itip),
+                AccountId = account_id,
+                Id = uid.to_string(),
+                Type = match itip.method {
+                    ITipMethod::Request => "request",
+                    ITipMethod::Cancel => "cancel",
+                    ITipMethod::Reply => "reply",
+                },
+                From = sender.to_string(),
+            );
+        }
+
+        Ok(response.state_change)
+    }
+
+    /// Sends an iMIP reply to the organizer for each attendee of the account
+    /// whose participation status changed.
+    pub(crate) async fn calendar_event_send_replies(
+        &self,
+        account_id: u32,
+        previous: &Object<Value>,
+        current: &Object<Value>,
+    ) -> trc::Result<()> {
+        let participants = event_participants(current);
+        let previous = event_participants(previous);
+        let organizer =
+            if let Some(organizer) = participants.iter().find(|participant| participant.is_owner) {
+                organizer
+            } else {
+                return Ok(());
+            };
+        let changed = participants
+            .iter()
+            .filter(|participant| {
+                participant.is_attendee
+                    && participant.status
+                        != previous
+                            .iter()
+                            .find(|previous| previous.email.eq_ignore_ascii_case(participant.email))
+                            .and_then(|previous| previous.status)
+            })
+            .collect::<Vec<_>>();
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        // Only replies from addresses of the account are sent
+        let principal = self
+            .core
+            .storage
+            .directory
+            .query(QueryBy::Id(account_id), false)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default();
+        let addresses = principal
+            .iter_str(PrincipalField::Emails)
+            .map(|email| email.to_lowercase())
+            .collect::<Vec<_>>();
+        if addresses.contains(&organizer.email.to_lowercase()) {
+            return Ok(());
+        }
+
+        for attendee in changed {
+            let action = match attendee.status {
+                Some("accepted") => "Accepted",
+                Some("declined") => "Declined",
+                Some("tentative") => "Tentative",
+                _ => continue,
+            };
+            if !addresses.contains(&attendee.email.to_lowercase()) {
+                continue;
+            }
+            let reply = if let Some(reply) = build_itip_reply(current, attendee) {
+                reply
+            } else {
+                continue;
+            };
+            let title = current
+                .get(&Property::Title)
+                .as_string()
+                .unwrap_or_default();
+            let text_body = format!(
+                "{} has {} the invitation to \"{}\".",
+                attendee.name.unwrap_or(attendee.email),
+                action.to_lowercase(),
+                title
+            );
+            let message = MessageBuilder::new()
+                .from(Address::new_address(attendee.name, attendee.email))
+                .to(Address::new_address(organizer.name, organizer.email))
+                .subject(format!("{action}: {title}"))
+                .header("Auto-Submitted", HeaderType::Text("auto-replied".into()))
+                .body(MimePart::new(
+                    ContentType::new("multipart/alternative"),
+                    BodyPart::Multipart(vec![
+                        MimePart::new(
+                            ContentType::new("text/plain"),
+                            BodyPart::Text(text_body.into()),
+                        ),
+                        MimePart::new(
+                            ContentType::new("text/calendar").attribute("method", "REPLY"),
+                            BodyPart::Text(reply.into()),
+                        ),
+                    ]),
+                ))
+                .write_to_vec()
+                .unwrap_or_default();
+
+            Session::<NullIo>::sieve(
+                self.smtp.clone(),
+                SessionAddress::new(attendee.email.to_string()),
+                vec![SessionAddress::new(organizer.email.to_string())],
+                message,
+                0,
+            )
+            .await
+            .queue_message()
+            .await;
+        }
+
+        Ok(())
+    }
+
+    async fn calendar_default_id(&self, account_id: u32) -> trc::Result<u32> {
+        let calendar_ids = self.calendar_get_or_create(account_id).await?;
+        for calendar_id in &calendar_ids {
+            if self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::Calendar,
+                    calendar_id,
+                    Property::Value,
+                )
+                .await?
+                .is_some_and(|calendar| calendar.get(&Property::IsDefault).as_bool() == Some(true))
+            {
+                return Ok(calendar_id);
+            }
+        }
+
+        Ok(if calendar_ids.contains(DEFAULT_CALENDAR_ID) {
+            DEFAULT_CALENDAR_ID
+        } else {
+            calendar_ids.min().unwrap_or(DEFAULT_CALENDAR_ID)
+        })
+    }
+}
+
+// Nested objects may be keyed by known properties, so keys are compared by name
+fn field<'x>(object: &'x Object<Value>, name: &str) -> &'x Value {
+    object
+        .properties
+        .iter()
+        .find(|(key, _)| key.to_string() == name)
+        .map(|(_, value)| value)
+        .unwrap_or(&Value::Null)
+}
+
+fn set_participation_status(event: &mut Object<Value>, email: &str, status: &str) {
+    let participants = event
+        .properties
+        .get_mut(&participants_property())
+        .and_then(|participants| participants.as_obj_mut());
+    for participant in participants
+        .into_iter()
+        .flat_map(|participants| participants.properties.values_mut())
+        .filter_map(|participant| participant.as_obj_mut())
+    {
+        if field(participant, "email")
+            .as_string()
+            .is_some_and(|value| value.eq_ignore_ascii_case(email))
+        {
+            let key = participant
+                .properties
+                .keys()
+                .find(|key| key.to_string() == "participationStatus")
+                .cloned()
+                .unwrap_or_else(|| Property::_T("participationStatus".to_string()));
+            participant.set(key, Value::Text(status.to_string()));
+        }
+    }
+}
+
+fn has_organizer(participants: &[Participant<'_>], address: &str) -> bool {
+    participants
+        .iter()
+        .any(|participant| participant.is_owner && participant.email.eq_ignore_ascii_case(address))
+}
+
+fn has_attendee(participants: &[Participant<'_>], address: &str) -> bool {
+    participants.iter().any(|participant| {
+        participant.is_attendee && participant.email.eq_ignore_ascii_case(address)
+    })
+}
@@ -0,0 +1,356 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use chrono::{Months, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use jmap_proto::{
+    object::Object,
+    types::{property::Property, value::Value},
+};
+
+pub mod availability;
+pub mod get;
+pub mod icalendar;
+pub mod itip;
+pub mod query;
+pub mod set;
+
+// Stored as the end of unbounded recurring events (9999-12-31T23:59:59Z)
+pub const MAX_UTC_END: u64 = 253402300799;
+
+// Maximum number of occurrences expanded for a single event
+const MAX_OCCURRENCES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Yearly,
+    Monthly,
+    Weekly,
+    Daily,
+    Hourly,
+    Minutely,
+    Secondly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u64>,
+    pub until: Option<NaiveDateTime>,
+}
+
+// Event timing resolved to UTC. Occurrences in IANA time zones are resolved with
+// the offset in effect at each of them, so that recurrences follow DST changes.
+// For unknown zones the offset is derived from the utcStart supplied by the client
+// and applied to all occurrences.
+#[derive(Debug, Clone)]
+pub struct EventSchedule {
+    pub start: NaiveDateTime,
+    pub offset: i64,
+    pub duration: i64,
+    pub rule: Option<RecurrenceRule>,
+    pub time_zone: Option<Tz>,
+}
+
+impl EventSchedule {
+    pub fn from_object(event: &Object<Value>) -> Option<Self> {
+        let start = parse_local_datetime(event.properties.get(&Property::Start)?.as_string()?)?;
+        let offset = match event.properties.get(&Property::UtcStart) {
+            Some(Value::UnsignedInt(utc_start)) => *utc_start as i64 - start.and_utc().timestamp(),
+            _ => 0,
+        };
+        let duration = event
+            .properties
+            .get(&Property::Duration)
+            .and_then(|d| d.as_string())
+            .and_then(parse_duration)
+            .unwrap_or(0);
+        let rule = event
+            .properties
+            .get(&Property::RecurrenceRules)
+            .and_then(|rules| parse_recurrence_rules(rules).ok())
+            .flatten();
+        let time_zone = parse_time_zone(
+            event
+                .properties
+                .get(&Property::TimeZone)
+                .and_then(|tz| tz.as_string()),
+        );
+
+        Some(EventSchedule {
+            start,
+            offset,
+            duration,
+            rule,
+            time_zone,
+        })
+    }
+
+    pub fn utc_start(&self) -> i64 {
+        self.to_utc(&self.start)
+    }
+
+    // Converts a local time of the event to UTC
+    pub fn to_utc(&self, local: &NaiveDateTime) -> i64 {
+        local.and_utc().timestamp()
+            + self
+                .time_zone
+                .and_then(|tz| local_offset(tz, local))
+                .unwrap_or(self.offset)
+    }
+
+    // End of the last occurrence, or MAX_UTC_END for unbounded recurrences
+    pub fn utc_series_end(&self) -> u64 {
+        match &self.rule {
+            Some(rule) if rule.count.is_some() || rule.until.is_some() => {
+                let (total, end) = self
+                    .occurrences(i64::MIN, i64::MAX)
+                    .fold((0, 0), |(total, _), (_, end)| (total + 1, end));
+                if total < MAX_OCCURRENCES {
+                    end.max(0) as u64
+                } else {
+                    MAX_UTC_END
+                }
+            }
+            Some(_) => MAX_UTC_END,
+            None => (self.utc_start() + self.duration).max(0) as u64,
+        }
+    }
+
+    // Returns the (start, end) UTC timestamps of the occurrences overlapping [from, to)
+    pub fn occurrences(&self, from: i64, to: i64) -> impl Iterator<Item = (i64, i64)> + '_ {
+        let rule = self.rule.as_ref();
+        let step = rule.and_then(|rule| {
+            let seconds = match rule.frequency {
+                Frequency::Weekly => 7 * 86400,
+                Frequency::Daily => 86400,
+                Frequency::Hourly => 3600,
+                Frequency::Minutely => 60,
+                Frequency::Secondly => 1,
+                Frequency::Yearly | Frequency::Monthly => return None,
+            };
+            Some(seconds * rule.interval as i64)
+        });
+
+        // Skip occurrences ending before the requested range when the step is fixed,
+        // leaving a one day margin for occurrences shifted by DST transitions
+        let margin = if self.time_zone.is_some() { 86400 } else { 0 };
+        let first = match step {
+            Some(step) if from > self.utc_start() + self.duration + margin => {
+                ((from - self.utc_start() - self.duration - margin) / step) as u64
+            }
+            _ => 0,
+        };
+
+        (first..)
+            .map_while(move |n| {
+                let start = if n == 0 {
+                    self.start
+                } else {
+                    let rule = rule?;
+                    if rule.count.map_or(false, |count| n >= count) {
+                        return None;
+                    }
+                    let start = match rule.frequency {
+                        Frequency::Yearly => self.start.checked_add_months(Months::new(
+                            u32::try_from(n * 12 * rule.interval as u64).ok()?,
+                        ))?,
+                        Frequency::Monthly => self.start.checked_add_months(Months::new(
+                            u32::try_from(n * rule.interval as u64).ok()?,
+                        ))?,
+                        _ => self
+                            .start
+                            .checked_add_signed(chrono::TimeDelta::try_seconds(
+                                i64::try_from(n).ok()?.checked_mul(step?)?,
+                            )?)?,
+                    };
+                    if rule.until.map_or(false, |until| start > until) {
+                        return None;
+                    }
+                    start
+                };
+                let start = self.to_utc(&start);
+
+                (start < to).then_some((start, start + self.duration))
+            })
+            .filter(move |(start, end)| *end > from || *start >= from)
+            .take(MAX_OCCURRENCES)
+    }
+}
+
+pub fn parse_local_datetime(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").ok()
+}
+
+// Parses an RFC 8984 duration such as "P1DT12H" into seconds
+pub fn parse_duration(value: &str) -> Option<i64> {
+    let value = value.strip_prefix('P')?;
+    let (date, time) = match value.split_once('T') {
+        Some((_, "")) => return None,
+        Some((date, time)) => (date, time),
+        None if !value.is_empty() => (value, ""),
+        None => return None,
+    };
+
+    let mut seconds: i64 = 0;
+    for (part, units) in [
+        (date, &[('W', 7 * 86400), ('D', 86400)][..]),
+        (time, &[('H', 3600), ('M', 60), ('S', 1)][..]),
+    ] {
+        let mut units = units.iter();
+        let mut number: Option<i64> = None;
+        for ch in part.chars() {
+            if let Some(digit) = ch.to_digit(10) {
+                number = number
+                    .unwrap_or(0)
+                    .checked_mul(10)?
+                    .checked_add(digit as i64)?
+                    .into();
+            } else {
+                let (_, multiplier) = units.find(|(unit, _)| *unit == ch)?;
+                seconds = seconds.checked_add(number.take()?.checked_mul(*multiplier)?)?;
+            }
+        }
+        if number.is_some() {
+            return None;
+        }
+    }
+
+    Some(seconds)
+}
+
+pub fn format_duration(seconds: i64) -> String {
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+    let (hours, minutes, seconds) = (seconds / 3600, (seconds % 3600) / 60, seconds % 60);
+    let mut duration = String::from("P");
+    if days > 0 {
+        duration.push_str(&format!("{days}D"));
+    }
+    if hours > 0 || minutes > 0 || seconds > 0 || days == 0 {
+        duration.push('T');
+        if hours > 0 {
+            duration.push_str(&format!("{hours}H"));
+        }
+        if minutes > 0 {
+            duration.push_str(&format!("{minutes}M"));
+        }
+        if seconds > 0 || (hours == 0 && minutes == 0) {
+            duration.push_str(&format!("{seconds}S"));
+        }
+    }
+    duration
+}
+
+pub fn format_local_datetime(value: &NaiveDateTime) -> String {
+    value.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+// Returns the number of seconds to add to a local time in an IANA zone to obtain UTC.
+// Local times skipped by a DST transition use the offset in effect before it (RFC 5545).
+pub fn local_offset(tz: Tz, local: &NaiveDateTime) -> Option<i64> {
+    let offset_at = |local: &NaiveDateTime| {
+        tz.from_local_datetime(local)
+            .earliest()
+            .map(|utc| utc.timestamp() - local.and_utc().timestamp())
+    };
+    offset_at(local).or_else(|| offset_at(&(*local - chrono::TimeDelta::hours(1))))
+}
+
+// Returns the IANA time zone with a variable offset, fixed offset zones are
+// resolved by time_zone_offset.
+pub fn parse_time_zone(time_zone: Option<&str>) -> Option<Tz> {
+    time_zone
+        .filter(|time_zone| time_zone_offset(Some(time_zone)).is_none())
+        .and_then(|time_zone| time_zone.parse::<Tz>().ok())
+}
+
+// Returns the number of seconds to add to a local time in this zone to obtain UTC,
+// or None if the zone has no fixed offset.
+pub fn time_zone_offset(time_zone: Option<&str>) -> Option<i64> {
+    match time_zone {
+        None | Some("UTC" | "Etc/UTC" | "Etc/GMT" | "Etc/Zulu" | "GMT") => Some(0),
+        Some(time_zone) => {
+            // The sign of Etc/GMT zones is inverted, Etc/GMT+5 is five hours behind UTC
+            let offset = time_zone.strip_prefix("Etc/GMT")?;
+            let hours = offset
+                .strip_prefix('+')
+                .or_else(|| offset.strip_prefix('-'))?
+                .parse::<i64>()
+                .ok()
+                .filter(|hours| *hours <= 14)?;
+            Some(if offset.starts_with('+') {
+                hours * 3600
+            } else {
+                -hours * 3600
+            })
+        }
+    }
+}
+
+pub fn parse_recurrence_rules(value: &Value) -> Result<Option<RecurrenceRule>, &'static str> {
+    let rules = match value {
+        Value::List(rules) => rules,
+        Value::Null => return Ok(None),
+        _ => return Err("Invalid recurrence rules."),
+    };
+
+    match rules.as_slice() {
+        [] => Ok(None),
+        [Value::Object(rule)] => {
+            let mut frequency = None;
+            let mut interval = 1;
+            let mut count = None;
+            let mut until = None;
+
+            for (key, value) in &rule.properties {
+                match (key.to_string().as_str(), value) {
+                    ("@type", Value::Text(value)) if value == "RecurrenceRule" => (),
+                    ("rscale", Value::Text(value)) if value == "gregorian" => (),
+                    ("skip" | "firstDayOfWeek", Value::Text(_)) => (),
+                    ("frequency", Value::Text(value)) => {
+                        frequency = match value.as_str() {
+                            "yearly" => Frequency::Yearly,
+                            "monthly" => Frequency::Monthly,
+                            "weekly" => Frequency::Weekly,
+                            "daily" => Frequency::Daily,
+                            "hourly" => Frequency::Hourly,
+                            "minutely" => Frequency::Minutely,
+                            "secondly" => Frequency::Secondly,
+                            _ => return Err("Invalid recurrence frequency."),
+                        }
+                        .into();
+                    }
+                    ("interval", Value::UnsignedInt(value)) if *value > 0 => {
+                        interval = u32::try_from(*value).map_err(|_| "Invalid interval.")?;
+                    }
+                    ("count", Value::UnsignedInt(value)) if *value > 0 => {
+                        count = Some(*value);
+                    }
+                    ("until", Value::Text(value)) => {
+                        until = Some(
+                            parse_local_datetime(value).ok_or("Invalid recurrence end date.")?,
+                        );
+                    }
+                    _ => return Err("Unsupported recurrence rule property."),
+                }
+            }
+
+            if count.is_some() && until.is_some() {
+                return Err("Recurrence rules cannot have both count and until.");
+            }
+
+            Ok(Some(RecurrenceRule {
+                frequency: frequency.ok_or("Missing recurrence frequency.")?,
+                interval,
+                count,
+                until,
+            }))
+        }
+        [_] => Err("Invalid recurrence rule."),
+        _ => Err("Only one recurrence rule is supported."),
+    }
+}
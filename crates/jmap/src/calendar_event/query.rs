@@ -0,0 +1,99 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    method::query::{
+        Comparator, Filter, QueryRequest, QueryResponse, RequestArguments, SortProperty,
+    },
+    types::{collection::Collection, property::Property},
+};
+use store::query::{self};
+
+use crate::JMAP;
+
+impl JMAP {
+    pub async fn calendar_event_query(
+        &self,
+        mut request: QueryRequest<RequestArguments>,
+    ) -> trc::Result<QueryResponse> {
+        let account_id = request.account_id.document_id();
+        let mut filters = Vec::with_capacity(request.filter.len());
+
+        for cond in std::mem::take(&mut request.filter) {
+            match cond {
+                Filter::InCalendars(ids) => {
+                    filters.push(query::Filter::Or);
+                    for id in ids {
+                        filters.push(query::Filter::eq(Property::CalendarIds, id.document_id()));
+                    }
+                    filters.push(query::Filter::End);
+                }
+                // Events overlapping the [after, before) range
+                Filter::After(after) => filters.push(query::Filter::gt(
+                    Property::UtcEnd,
+                    after.timestamp().max(0) as u64,
+                )),
+                Filter::Before(before) => filters.push(query::Filter::lt(
+                    Property::UtcStart,
+                    before.timestamp().max(0) as u64,
+                )),
+                Filter::Text(text) => {
+                    filters.push(query::Filter::Or);
+                    filters.push(query::Filter::has_text(Property::Title, &text));
+                    filters.push(query::Filter::has_text(Property::Description, &text));
+                    filters.push(query::Filter::End);
+                }
+                Filter::Title(title) => {
+                    filters.push(query::Filter::has_text(Property::Title, &title))
+                }
+                Filter::Uid(uid) => filters.push(query::Filter::eq(Property::Uid, uid)),
+                Filter::And | Filter::Or | Filter::Not | Filter::Close => {
+                    filters.push(cond.into());
+                }
+                other => {
+                    return Err(trc::JmapEvent::UnsupportedFilter
+                        .into_err()
+                        .details(other.to_string()))
+                }
+            }
+        }
+
+        let result_set = self
+            .filter(account_id, Collection::CalendarEvent, filters)
+            .await?;
+
+        let (response, paginate) = self.build_query_response(&result_set, &request).await?;
+
+        if let Some(paginate) = paginate {
+            // Parse sort criteria
+            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
+            for comparator in request
+                .sort
+                .and_then(|s| if !s.is_empty() { s.into() } else { None })
+                .unwrap_or_else(|| vec![Comparator::ascending(SortProperty::Start)])
+            {
+                comparators.push(match comparator.property {
+                    SortProperty::Start => {
+                        query::Comparator::field(Property::UtcStart, comparator.is_ascending)
+                    }
+                    SortProperty::Uid => {
+                        query::Comparator::field(Property::Uid, comparator.is_ascending)
+                    }
+                    other => {
+                        return Err(trc::JmapEvent::UnsupportedSort
+                            .into_err()
+                            .details(other.to_string()))
+                    }
+                });
+            }
+
+            // Sort results
+            self.sort(result_set, comparators, paginate, response).await
+        } else {
+            Ok(response)
+        }
+    }
+}
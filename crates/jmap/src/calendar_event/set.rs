@@ -0,0 +1,501 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use chrono::DateTime;
+use jmap_proto::{
+    error::set::SetError,
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    object::{
+        index::{IndexAs, IndexProperty, ObjectIndexBuilder},
+        Object,
+    },
+    response::references::EvalObjectReferences,
+    types::{
+        collection::Collection,
+        id::Id,
+        property::Property,
+        state::StateChange,
+        type_state::DataType,
+        value::{MaybePatchValue, SetValue, Value},
+    },
+};
+use store::{
+    roaring::RoaringBitmap,
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder},
+};
+
+use crate::JMAP;
+
+use super::{
+    format_duration, format_local_datetime, itip::participants_property, local_offset,
+    parse_duration, parse_local_datetime, parse_recurrence_rules, parse_time_zone,
+    time_zone_offset, EventSchedule,
+};
+
+pub static SCHEMA: &[IndexProperty] = &[
+    IndexProperty::new(Property::Uid)
+        .index_as(IndexAs::Text {
+            tokenize: false,
+            index: true,
+        })
+        .max_size(255)
+        .required(),
+    IndexProperty::new(Property::Title)
+        .index_as(IndexAs::Text {
+            tokenize: true,
+            index: true,
+        })
+        .max_size(1024),
+    IndexProperty::new(Property::Description)
+        .index_as(IndexAs::Text {
+            tokenize: true,
+            index: false,
+        })
+        .max_size(65536),
+    IndexProperty::new(Property::CalendarIds).index_as(IndexAs::IntegerList),
+    IndexProperty::new(Property::UtcStart).index_as(IndexAs::LongInteger),
+    IndexProperty::new(Property::UtcEnd).index_as(IndexAs::LongInteger),
+];
+
+impl JMAP {
+    pub async fn calendar_event_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let calendar_ids = self.calendar_get_or_create(account_id).await?;
+        let mut response = self
+            .prepare_set_response(&request, Collection::CalendarEvent)
+            .await?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Process creates
+        let mut changes = ChangeLogBuilder::new();
+        for (id, object) in request.unwrap_create() {
+            match calendar_event_set_item(object, None, &calendar_ids, &response) {
+                Ok(builder) => {
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::CalendarEvent)
+                        .create_document()
+                        .custom(builder);
+                    let document_id = self.write_batch_expect_id(batch).await?;
+                    changes.log_insert(Collection::CalendarEvent, document_id);
+                    response.created(id, document_id);
+                }
+                Err(err) => {
+                    response.not_created.append(id, err);
+                }
+            }
+        }
+
+        // Process updates
+        let mut rescheduled = Vec::new();
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain event
+            let document_id = id.document_id();
+            let event = if let Some(event) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::CalendarEvent,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                event
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+            let previous = object
+                .properties
+                .contains_key(&participants_property())
+                .then(|| event.inner.clone());
+
+            match calendar_event_set_item(object, Some(event), &calendar_ids, &response) {
+                Ok(builder) => {
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::CalendarEvent)
+                        .update_document(document_id)
+                        .custom(builder);
+                    if !batch.is_empty() {
+                        match self.core.storage.data.write(batch.build()).await {
+                            Ok(_) => {
+                                changes.log_update(Collection::CalendarEvent, document_id);
+                                if let Some(previous) = previous {
+                                    rescheduled.push((document_id, previous));
+                                }
+                            }
+                            Err(err) if err.is_assertion_failure() => {
+                                response.not_updated.append(
+                                    id,
+                                    SetError::forbidden().with_description(
+                                        "Another process modified this event, please try again.",
+                                    ),
+                                );
+                                continue 'update;
+                            }
+                            Err(err) => {
+                                return Err(err.caused_by(trc::location!()));
+                            }
+                        }
+                    }
+                    response.updated.append(id, None);
+                }
+                Err(err) => {
+                    response.not_updated.append(id, err);
+                }
+            }
+        }
+
+        // Process deletions
+        for id in will_destroy {
+            let document_id = id.document_id();
+            let event = if let Some(event) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::CalendarEvent,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                event
+            } else {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            };
+
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::CalendarEvent)
+                .delete_document(document_id)
+                .custom(ObjectIndexBuilder::new(SCHEMA).with_current(event));
+            match self.core.storage.data.write(batch.build()).await {
+                Ok(_) => {
+                    changes.log_delete(Collection::CalendarEvent, document_id);
+                    response.destroyed.push(id);
+                }
+                Err(err) if err.is_assertion_failure() => {
+                    response.not_destroyed.append(
+                        id,
+                        SetError::forbidden().with_description(
+                            "Another process modified this event, please try again.",
+                        ),
+                    );
+                }
+                Err(err) => {
+                    return Err(err.caused_by(trc::location!()));
+                }
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            response.state_change = StateChange::new(account_id)
+                .with_change(DataType::CalendarEvent, changes.change_id)
+                .into();
+            response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
+        }
+
+        // Notify organizers of participation status changes
+        for (document_id, previous) in rescheduled {
+            if let Some(current) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::CalendarEvent,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                if let Err(err) = self
+                    .calendar_event_send_replies(account_id, &previous, &current)
+                    .await
+                {
+                    trc::error!(err
+                        .account_id(account_id)
+                        .document_id(document_id)
+                        .details("Failed to send iMIP reply"));
+                }
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+fn calendar_event_set_item(
+    changes_: Object<SetValue>,
+    current: Option<HashedValue<Object<Value>>>,
+    calendar_ids: &RoaringBitmap,
+    response: &SetResponse,
+) -> Result<ObjectIndexBuilder, SetError> {
+    let mut changes = Object::with_capacity(changes_.properties.len() + 2);
+    let mut event_calendar_ids = current
+        .as_ref()
+        .and_then(|current| current.inner.properties.get(&Property::CalendarIds))
+        .and_then(|ids| ids.as_list())
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_id().map(|id| id.document_id()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let mut has_calendar_ids = false;
+    let mut utc_start = None;
+    let mut utc_end = None;
+
+    for (property, value) in changes_.properties {
+        let value = match (&property, response.eval_object_references(value)?) {
+            (Property::CalendarIds, MaybePatchValue::Value(Value::List(ids))) => {
+                has_calendar_ids = true;
+                event_calendar_ids = ids
+                    .into_iter()
+                    .filter_map(|id| id.try_unwrap_id().map(|id| id.document_id()))
+                    .collect();
+                continue;
+            }
+            (Property::CalendarIds, MaybePatchValue::Patch(patch)) => {
+                has_calendar_ids = true;
+                let mut patch = patch.into_iter();
+                if let Some(document_id) = patch.next().unwrap().try_unwrap_id() {
+                    let document_id = document_id.document_id();
+                    if patch.next().unwrap().try_unwrap_bool().unwrap_or_default() {
+                        if !event_calendar_ids.contains(&document_id) {
+                            event_calendar_ids.push(document_id);
+                        }
+                    } else {
+                        event_calendar_ids.retain(|id| id != &document_id);
+                    }
+                }
+                continue;
+            }
+            (Property::Uid, MaybePatchValue::Value(Value::Text(value))) if current.is_none() => {
+                Value::Text(value)
+            }
+            (
+                Property::Title | Property::Description | Property::Color,
+                MaybePatchValue::Value(Value::Text(value)),
+            ) => Value::Text(value),
+            (Property::Start, MaybePatchValue::Value(Value::Text(value)))
+                if parse_local_datetime(&value).is_some() =>
+            {
+                Value::Text(value)
+            }
+            (Property::Duration, MaybePatchValue::Value(Value::Text(value)))
+                if parse_duration(&value).is_some() =>
+            {
+                Value::Text(value)
+            }
+            (Property::TimeZone, MaybePatchValue::Value(Value::Text(value)))
+                if value.len() < 255 =>
+            {
+                Value::Text(value)
+            }
+            (Property::Status, MaybePatchValue::Value(Value::Text(value)))
+                if matches!(value.as_str(), "confirmed" | "cancelled" | "tentative") =>
+            {
+                Value::Text(value)
+            }
+            (Property::FreeBusyStatus, MaybePatchValue::Value(Value::Text(value)))
+                if matches!(value.as_str(), "free" | "busy") =>
+            {
+                Value::Text(value)
+            }
+            (Property::ShowWithoutTime, MaybePatchValue::Value(Value::Bool(value))) => {
+                Value::Bool(value)
+            }
+            (Property::RecurrenceRules, MaybePatchValue::Value(value)) => {
+                if let Err(err) = parse_recurrence_rules(&value) {
+                    return Err(SetError::invalid_properties()
+                        .with_property(property)
+                        .with_description(err));
+                }
+                value
+            }
+            (Property::UtcStart, MaybePatchValue::Value(Value::Date(value))) => {
+                utc_start = Some(value.timestamp());
+                continue;
+            }
+            (Property::UtcEnd, MaybePatchValue::Value(Value::Date(value))) => {
+                utc_end = Some(value.timestamp());
+                continue;
+            }
+            (Property::Keywords | Property::_T(_), MaybePatchValue::Value(value)) => value,
+            (
+                Property::Title
+                | Property::Description
+                | Property::Color
+                | Property::Duration
+                | Property::TimeZone
+                | Property::Status
+                | Property::FreeBusyStatus
+                | Property::ShowWithoutTime,
+                MaybePatchValue::Value(Value::Null),
+            ) => Value::Null,
+            _ => {
+                return Err(SetError::invalid_properties()
+                    .with_property(property)
+                    .with_description("Invalid property or value.".to_string()))
+            }
+        };
+        changes.append(property, value);
+    }
+
+    // Validate calendars
+    if current.is_none() || has_calendar_ids {
+        if event_calendar_ids.is_empty() {
+            return Err(SetError::invalid_properties()
+                .with_property(Property::CalendarIds)
+                .with_description("Event has to belong to at least one calendar."));
+        }
+        for calendar_id in &event_calendar_ids {
+            if !calendar_ids.contains(*calendar_id) {
+                return Err(SetError::invalid_properties()
+                    .with_property(Property::CalendarIds)
+                    .with_description(format!(
+                        "calendarId {} does not exist.",
+                        Id::from(*calendar_id)
+                    )));
+            }
+        }
+        event_calendar_ids.sort_unstable();
+        event_calendar_ids.dedup();
+        changes.append(
+            Property::CalendarIds,
+            Value::List(
+                event_calendar_ids
+                    .into_iter()
+                    .map(|id| Value::Id(id.into()))
+                    .collect(),
+            ),
+        );
+    }
+
+    // Generate a UID if missing
+    if current.is_none() && !changes.properties.contains_key(&Property::Uid) {
+        changes.append(Property::Uid, Value::Text(generate_uid()));
+    }
+
+    // Resolve the event start and duration in UTC
+    let merged = |property: &Property| match changes.properties.get(property) {
+        Some(Value::Null) => None,
+        Some(value) => Some(value),
+        None => current
+            .as_ref()
+            .and_then(|current| current.inner.properties.get(property)),
+    };
+    let time_zone_name = merged(&Property::TimeZone).and_then(|value| value.as_string());
+    let zone_offset = time_zone_offset(time_zone_name);
+    let time_zone = parse_time_zone(time_zone_name);
+    let start = merged(&Property::Start)
+        .and_then(|value| value.as_string())
+        .and_then(parse_local_datetime);
+    let duration = merged(&Property::Duration)
+        .and_then(|value| value.as_string())
+        .and_then(parse_duration)
+        .unwrap_or_default();
+    let rule = merged(&Property::RecurrenceRules)
+        .and_then(|value| parse_recurrence_rules(value).ok())
+        .flatten();
+    let current_offset = current
+        .as_ref()
+        .and_then(|current| EventSchedule::from_object(&current.inner))
+        .map(|schedule| schedule.offset);
+    let has_start = changes.properties.contains_key(&Property::Start);
+    let has_duration = changes.properties.contains_key(&Property::Duration);
+
+    let start = match (start, utc_start) {
+        (Some(start), None) => start,
+        (Some(start), Some(_)) if has_start => start,
+        (_, Some(utc_start)) => {
+            // Only utcStart was provided, obtain the local start from it
+            let offset = zone_offset.or(current_offset).unwrap_or_default();
+            let start = match time_zone {
+                Some(time_zone) => DateTime::from_timestamp(utc_start, 0)
+                    .map(|start| start.with_timezone(&time_zone).naive_local()),
+                None => {
+                    DateTime::from_timestamp(utc_start - offset, 0).map(|start| start.naive_utc())
+                }
+            }
+            .ok_or_else(|| {
+                SetError::invalid_properties()
+                    .with_property(Property::UtcStart)
+                    .with_description("Invalid start date.")
+            })?;
+            changes.set(Property::Start, Value::Text(format_local_datetime(&start)));
+            start
+        }
+        (None, None) => {
+            return Err(SetError::invalid_properties()
+                .with_property(Property::Start)
+                .with_description("Missing start date."));
+        }
+    };
+    let offset = zone_offset
+        .or_else(|| time_zone.and_then(|time_zone| local_offset(time_zone, &start)))
+        .or_else(|| utc_start.map(|utc_start| utc_start - start.and_utc().timestamp()))
+        .or(current_offset)
+        .unwrap_or_default();
+    let duration = match utc_end {
+        Some(utc_end) if !has_duration => {
+            let duration = utc_end - start.and_utc().timestamp() - offset;
+            if duration < 0 {
+                return Err(SetError::invalid_properties()
+                    .with_property(Property::UtcEnd)
+                    .with_description("utcEnd cannot be before utcStart."));
+            }
+            changes.set(Property::Duration, Value::Text(format_duration(duration)));
+            duration
+        }
+        _ => duration,
+    };
+    let schedule = EventSchedule {
+        start,
+        offset,
+        duration,
+        rule,
+        time_zone,
+    };
+    changes.set(
+        Property::UtcStart,
+        Value::UnsignedInt(schedule.utc_start().max(0) as u64),
+    );
+    changes.set(
+        Property::UtcEnd,
+        Value::UnsignedInt(schedule.utc_series_end()),
+    );
+
+    ObjectIndexBuilder::new(SCHEMA)
+        .with_changes(changes)
+        .with_current_opt(current)
+        .validate()
+}
+
+// Random version 4 UUID
+pub(crate) fn generate_uid() -> String {
+    let uid = (rand::random::<u128>() & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        (uid >> 96) as u32,
+        (uid >> 80) as u16,
+        (uid >> 64) as u16,
+        (uid >> 48) as u16,
+        uid & 0xffff_ffff_ffff
+    )
+}
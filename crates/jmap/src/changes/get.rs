@@ -46,10 +46,50 @@ impl JMAP {
 
                 Collection::EmailSubmission
             }
+            RequestArguments::Calendar => {
+                access_token.assert_is_member(request.account_id)?;
+
+                Collection::Calendar
+            }
+            RequestArguments::CalendarEvent => {
+                access_token.assert_is_member(request.account_id)?;
+
+                Collection::CalendarEvent
+            }
+            RequestArguments::AddressBook => {
+                access_token.assert_has_access(request.account_id, Collection::AddressBook)?;
+
+                Collection::AddressBook
+            }
+            RequestArguments::ContactCard => {
+                access_token.assert_has_access(request.account_id, Collection::ContactCard)?;
+
+                Collection::ContactCard
+            }
+            RequestArguments::TaskList => {
+                access_token.assert_is_member(request.account_id)?;
+
+                Collection::TaskList
+            }
+            RequestArguments::Task => {
+                access_token.assert_is_member(request.account_id)?;
+
+                Collection::Task
+            }
+            RequestArguments::SieveScript => {
+                access_token.assert_is_member(request.account_id)?;
+
+                Collection::SieveScript
+            }
             RequestArguments::Quota => {
                 access_token.assert_is_member(request.account_id)?;
 
-                return Err(trc::JmapEvent::CannotCalculateChanges.into_err());
+                return self.quota_changes(request, access_token).await;
+            }
+            RequestArguments::Activity => {
+                access_token.assert_is_member(request.account_id)?;
+
+                return self.activity_changes(request).await;
             }
         };
 
@@ -33,6 +33,16 @@ impl JMAP {
                             changes::RequestArguments::EmailSubmission
                         }
                         query::RequestArguments::Quota => changes::RequestArguments::Quota,
+                        query::RequestArguments::CalendarEvent => {
+                            changes::RequestArguments::CalendarEvent
+                        }
+                        query::RequestArguments::ContactCard => {
+                            changes::RequestArguments::ContactCard
+                        }
+                        query::RequestArguments::Task => changes::RequestArguments::Task,
+                        query::RequestArguments::SieveScript => {
+                            changes::RequestArguments::SieveScript
+                        }
                         _ => {
                             return Err(trc::JmapEvent::UnknownMethod
                                 .into_err()
@@ -66,7 +76,15 @@ impl JMAP {
                 calculate_total: request.calculate_total,
                 arguments: query::RequestArguments::EmailSubmission,
             };
-            let is_mutable = query.filter.iter().any(|f| !f.is_immutable())
+            // Events, cards, tasks and scripts can be modified,
+            // so their filters are not immutable here
+            let is_mutable = matches!(
+                request.arguments,
+                query::RequestArguments::CalendarEvent
+                    | query::RequestArguments::ContactCard
+                    | query::RequestArguments::Task
+                    | query::RequestArguments::SieveScript
+            ) || query.filter.iter().any(|f| !f.is_immutable())
                 || query
                     .sort
                     .as_ref()
@@ -84,6 +102,12 @@ impl JMAP {
                     self.email_submission_query(query).await?
                 }
                 query::RequestArguments::Quota => self.quota_query(query, access_token).await?,
+                query::RequestArguments::CalendarEvent => self.calendar_event_query(query).await?,
+                query::RequestArguments::ContactCard => {
+                    self.contact_card_query(query, access_token).await?
+                }
+                query::RequestArguments::Task => self.task_query(query).await?,
+                query::RequestArguments::SieveScript => self.sieve_script_query(query).await?,
                 _ => unreachable!(),
             };
 
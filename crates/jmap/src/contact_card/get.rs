@@ -0,0 +1,109 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::Object,
+    types::{acl::Acl, collection::Collection, property::Property, value::Value},
+};
+
+use crate::JMAP;
+
+use super::link_media_blobs;
+
+impl JMAP {
+    pub async fn contact_card_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        // Cards are returned in full unless specific properties are requested
+        let properties = request.unwrap_properties(&[]);
+        let account_id = request.account_id.document_id();
+        let card_ids = if access_token.is_shared(account_id) {
+            self.shared_contact_cards(access_token, account_id, Acl::ReadItems)
+                .await?
+        } else {
+            self.get_document_ids(account_id, Collection::ContactCard)
+                .await?
+                .unwrap_or_default()
+        };
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            card_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self
+                .get_state(account_id, Collection::ContactCard)
+                .await?
+                .into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the card object
+            let document_id = id.document_id();
+            if !card_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let mut card = if let Some(card) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::ContactCard,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                card
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+
+            // Media blobs are downloaded through the card
+            if let Some(media) = card.properties.get_mut(&Property::_T("media".to_string())) {
+                link_media_blobs(media, account_id, document_id);
+            }
+
+            // Remove the fields derived for indexing
+            card.properties.remove(&Property::FullName);
+            card.properties.remove(&Property::Email);
+
+            let result = if properties.is_empty() {
+                let mut result = Object::with_capacity(card.properties.len() + 1);
+                result.append(Property::Id, Value::Id(id));
+                for (property, value) in card.properties {
+                    result.append(property, value);
+                }
+                result
+            } else {
+                let mut result = Object::with_capacity(properties.len());
+                for property in &properties {
+                    let value = match property {
+                        Property::Id => Value::Id(id),
+                        property => card.remove(property),
+                    };
+                    result.append(property.clone(), value);
+                }
+                result
+            };
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,163 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    object::Object,
+    types::{blob::BlobId, collection::Collection, property::Property, value::Value},
+};
+use store::BlobClass;
+
+pub mod get;
+pub mod parse;
+pub mod query;
+pub mod set;
+pub mod vcard;
+
+pub const CONTACT_KINDS: &[&str] = &[
+    "individual",
+    "group",
+    "org",
+    "location",
+    "device",
+    "application",
+];
+
+/// Returns the full name of a card, falling back to its name components,
+/// organization or email address.
+pub fn card_full_name(card: &Object<Value>) -> Option<String> {
+    let name = card
+        .properties
+        .get(&Property::Name)
+        .and_then(|v| v.as_obj());
+    if let Some(full) = name
+        .and_then(|name| name.properties.get(&Property::_T("full".to_string())))
+        .and_then(|full| full.as_string())
+        .filter(|full| !full.is_empty())
+    {
+        return Some(full.to_string());
+    }
+    if let Some(components) = name
+        .and_then(|name| name.properties.get(&Property::_T("components".to_string())))
+        .and_then(|components| components.as_list())
+    {
+        let mut full = String::new();
+        for kind in [
+            "title",
+            "given",
+            "given2",
+            "surname",
+            "surname2",
+            "credential",
+        ] {
+            for component in components.iter().filter_map(|c| c.as_obj()) {
+                if component.get(&Property::_T("kind".to_string())).as_string() == Some(kind) {
+                    if let Some(value) = component
+                        .get(&Property::_T("value".to_string()))
+                        .as_string()
+                        .filter(|value| !value.is_empty())
+                    {
+                        if !full.is_empty() {
+                            full.push(' ');
+                        }
+                        full.push_str(value);
+                    }
+                }
+            }
+        }
+        if !full.is_empty() {
+            return Some(full);
+        }
+    }
+
+    ["organizations", "emails"]
+        .into_iter()
+        .zip(["name", "address"])
+        .find_map(|(map, field)| {
+            card.get(&Property::_T(map.to_string()))
+                .as_obj()?
+                .properties
+                .values()
+                .find_map(|entry| {
+                    entry
+                        .as_obj()?
+                        .get(&Property::_T(field.to_string()))
+                        .as_string()
+                        .filter(|value| !value.is_empty())
+                        .map(|value| value.to_string())
+                })
+        })
+}
+
+/// Returns the lowercased email addresses of a card.
+pub fn card_emails(card: &Object<Value>) -> Vec<Value> {
+    let mut emails = Vec::new();
+    if let Some(map) = card.get(&Property::_T("emails".to_string())).as_obj() {
+        for entry in map.properties.values() {
+            if let Some(address) = entry
+                .as_obj()
+                .and_then(|entry| entry.get(&Property::_T("address".to_string())).as_string())
+            {
+                let address = Value::Text(address.trim().to_lowercase());
+                if !emails.contains(&address) {
+                    emails.push(address);
+                }
+            }
+        }
+    }
+    emails
+}
+
+/// Returns the blobs referenced by the media of a card, such as photos.
+pub fn card_media_blobs(card: &Object<Value>) -> Result<Vec<BlobId>, String> {
+    let mut blob_ids: Vec<BlobId> = Vec::new();
+    if let Some(map) = card.get(&Property::_T("media".to_string())).as_obj() {
+        for entry in map.properties.values() {
+            if let Some(blob_id) = entry
+                .as_obj()
+                .and_then(|entry| entry.get(&Property::_T("blobId".to_string())).as_string())
+            {
+                let blob_id = BlobId::from_base32(blob_id)
+                    .ok_or_else(|| format!("Invalid blobId {blob_id:?}."))?;
+                if !blob_ids.iter().any(|b| b.hash == blob_id.hash) {
+                    blob_ids.push(blob_id);
+                }
+            }
+        }
+    }
+    Ok(blob_ids)
+}
+
+/// Rewrites the media blobIds of a card so they can be downloaded
+/// through the card, the uploaded blobIds expire once linked.
+pub fn link_media_blobs(media: &mut Value, account_id: u32, document_id: u32) {
+    if let Some(map) = media.as_obj_mut() {
+        for entry in map.properties.values_mut() {
+            if let Some(blob_id) = entry.as_obj_mut().and_then(|entry| {
+                entry
+                    .properties
+                    .get_mut(&Property::_T("blobId".to_string()))
+            }) {
+                if let Some(hash) = blob_id
+                    .as_string()
+                    .and_then(BlobId::from_base32)
+                    .map(|blob_id| blob_id.hash)
+                {
+                    *blob_id = Value::Text(
+                        BlobId::new(
+                            hash,
+                            BlobClass::Linked {
+                                account_id,
+                                collection: Collection::ContactCard.into(),
+                                document_id,
+                            },
+                        )
+                        .to_string(),
+                    );
+                }
+            }
+        }
+    }
+}
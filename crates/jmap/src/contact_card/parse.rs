@@ -0,0 +1,67 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use jmap_proto::{
+    method::parse::{ParseContactCardRequest, ParseContactCardResponse},
+    object::Object,
+    types::property::Property,
+};
+use utils::map::vec_map::VecMap;
+
+use crate::JMAP;
+
+use super::vcard::parse_contact;
+
+impl JMAP {
+    pub async fn contact_card_parse(
+        &self,
+        request: ParseContactCardRequest,
+        access_token: &AccessToken,
+    ) -> trc::Result<ParseContactCardResponse> {
+        if request.blob_ids.len() > self.core.jmap.mail_parse_max_items {
+            return Err(trc::JmapEvent::RequestTooLarge.into_err());
+        }
+
+        let mut response = ParseContactCardResponse {
+            account_id: request.account_id,
+            parsed: VecMap::with_capacity(request.blob_ids.len()),
+            not_parsable: vec![],
+            not_found: vec![],
+        };
+
+        for blob_id in request.blob_ids {
+            // Fetch the vCard or jCard to parse
+            let raw_card = match self.blob_download(&blob_id, access_token).await? {
+                Some(raw_card) => raw_card,
+                None => {
+                    response.not_found.push(blob_id);
+                    continue;
+                }
+            };
+            let mut card = if let Some(card) = parse_contact(&raw_card) {
+                card
+            } else {
+                response.not_parsable.push(blob_id);
+                continue;
+            };
+
+            // Return the whole card unless specific properties are requested
+            if let Some(properties) = &request.properties {
+                let mut result = Object::with_capacity(properties.len());
+                for property in properties {
+                    if !matches!(property, Property::Id) {
+                        result.append(property.clone(), card.remove(property));
+                    }
+                }
+                card = result;
+            }
+            response.parsed.append(blob_id, card);
+        }
+
+        Ok(response)
+    }
+}
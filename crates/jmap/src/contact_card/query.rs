@@ -0,0 +1,103 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use jmap_proto::{
+    method::query::{
+        Comparator, Filter, QueryRequest, QueryResponse, RequestArguments, SortProperty,
+    },
+    types::{acl::Acl, collection::Collection, property::Property},
+};
+use store::query::{self};
+
+use crate::JMAP;
+
+impl JMAP {
+    pub async fn contact_card_query(
+        &self,
+        mut request: QueryRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<QueryResponse> {
+        let account_id = request.account_id.document_id();
+        let mut filters = Vec::with_capacity(request.filter.len());
+
+        for cond in std::mem::take(&mut request.filter) {
+            match cond {
+                Filter::InAddressBook(id) => filters.push(query::Filter::eq(
+                    Property::AddressBookIds,
+                    id.document_id(),
+                )),
+                Filter::Uid(uid) => filters.push(query::Filter::eq(Property::Uid, uid)),
+                Filter::Kind(kind) => filters.push(query::Filter::eq(Property::Kind, kind)),
+                Filter::Email(email) => filters.push(query::Filter::has_text(
+                    Property::Email,
+                    email.to_lowercase(),
+                )),
+                Filter::Name(name) => {
+                    filters.push(query::Filter::has_text(Property::FullName, &name))
+                }
+                Filter::Text(text) => {
+                    filters.push(query::Filter::Or);
+                    filters.push(query::Filter::has_text(Property::FullName, &text));
+                    filters.push(query::Filter::has_text(
+                        Property::Email,
+                        text.to_lowercase(),
+                    ));
+                    filters.push(query::Filter::End);
+                }
+                Filter::And | Filter::Or | Filter::Not | Filter::Close => {
+                    filters.push(cond.into());
+                }
+                other => {
+                    return Err(trc::JmapEvent::UnsupportedFilter
+                        .into_err()
+                        .details(other.to_string()))
+                }
+            }
+        }
+
+        let mut result_set = self
+            .filter(account_id, Collection::ContactCard, filters)
+            .await?;
+        if access_token.is_shared(account_id) {
+            result_set.apply_mask(
+                self.shared_contact_cards(access_token, account_id, Acl::ReadItems)
+                    .await?,
+            );
+        }
+
+        let (response, paginate) = self.build_query_response(&result_set, &request).await?;
+
+        if let Some(paginate) = paginate {
+            // Parse sort criteria
+            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
+            for comparator in request
+                .sort
+                .and_then(|s| if !s.is_empty() { s.into() } else { None })
+                .unwrap_or_else(|| vec![Comparator::ascending(SortProperty::Name)])
+            {
+                comparators.push(match comparator.property {
+                    SortProperty::Name => {
+                        query::Comparator::field(Property::FullName, comparator.is_ascending)
+                    }
+                    SortProperty::Uid => {
+                        query::Comparator::field(Property::Uid, comparator.is_ascending)
+                    }
+                    other => {
+                        return Err(trc::JmapEvent::UnsupportedSort
+                            .into_err()
+                            .details(other.to_string()))
+                    }
+                });
+            }
+
+            // Sort results
+            self.sort(result_set, comparators, paginate, response).await
+        } else {
+            Ok(response)
+        }
+    }
+}
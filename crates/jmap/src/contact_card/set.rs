@@ -0,0 +1,566 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    object::{
+        index::{IndexAs, IndexProperty, ObjectIndexBuilder},
+        Object,
+    },
+    response::references::EvalObjectReferences,
+    types::{
+        acl::Acl,
+        blob::BlobId,
+        collection::Collection,
+        id::Id,
+        property::Property,
+        state::StateChange,
+        type_state::DataType,
+        value::{MaybePatchValue, SetValue, Value},
+    },
+};
+use store::{
+    roaring::RoaringBitmap,
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, BlobOp},
+};
+
+use crate::{calendar_event::set::generate_uid, JMAP};
+
+use super::{card_emails, card_full_name, card_media_blobs, CONTACT_KINDS};
+
+pub static SCHEMA: &[IndexProperty] = &[
+    IndexProperty::new(Property::Uid)
+        .index_as(IndexAs::Text {
+            tokenize: false,
+            index: true,
+        })
+        .max_size(255)
+        .required(),
+    IndexProperty::new(Property::FullName)
+        .index_as(IndexAs::Text {
+            tokenize: true,
+            index: true,
+        })
+        .max_size(1024),
+    IndexProperty::new(Property::Email).index_as(IndexAs::TextList {
+        tokenize: true,
+        index: false,
+    }),
+    IndexProperty::new(Property::Kind)
+        .index_as(IndexAs::Text {
+            tokenize: false,
+            index: true,
+        })
+        .max_size(255),
+    IndexProperty::new(Property::AddressBookIds).index_as(IndexAs::IntegerList),
+];
+
+struct CardUpdate {
+    builder: ObjectIndexBuilder,
+    link_blobs: Vec<BlobId>,
+    unlink_blobs: Vec<BlobId>,
+}
+
+impl JMAP {
+    pub async fn contact_card_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let address_book_ids = self.address_book_get_or_create(account_id).await?;
+        let mut response = self
+            .prepare_set_response(&request, Collection::ContactCard)
+            .await?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Obtain the address books and cards the principal has access to on shared accounts
+        let (can_add_address_book_ids, can_remove_address_book_ids, can_modify_card_ids) =
+            if access_token.is_shared(account_id) {
+                (
+                    self.shared_documents(
+                        access_token,
+                        account_id,
+                        Collection::AddressBook,
+                        Acl::AddItems,
+                    )
+                    .await?
+                    .into(),
+                    self.shared_documents(
+                        access_token,
+                        account_id,
+                        Collection::AddressBook,
+                        Acl::RemoveItems,
+                    )
+                    .await?
+                    .into(),
+                    self.shared_contact_cards(access_token, account_id, Acl::ModifyItems)
+                        .await?
+                        .into(),
+                )
+            } else {
+                (None, None, None)
+            };
+
+        // Process creates
+        let mut changes = ChangeLogBuilder::new();
+        'create: for (id, object) in request.unwrap_create() {
+            let update = match contact_card_set_item(object, None, &address_book_ids, &response) {
+                Ok(update) => update,
+                Err(err) => {
+                    response.not_created.append(id, err);
+                    continue 'create;
+                }
+            };
+
+            // Verify permissions on shared accounts
+            if let Some(address_book_id) = update
+                .builder
+                .changes()
+                .map(card_address_book_ids)
+                .unwrap_or_default()
+                .into_iter()
+                .find(|address_book_id| {
+                    matches!(&can_add_address_book_ids, Some(ids) if !ids.contains(*address_book_id))
+                })
+            {
+                response.not_created.append(
+                    id,
+                    SetError::forbidden().with_description(format!(
+                        "You are not allowed to add cards to address book {}.",
+                        Id::from(address_book_id)
+                    )),
+                );
+                continue 'create;
+            }
+
+            // Verify access to the media blobs
+            if let Some(err) = self
+                .contact_card_verify_blobs(&update.link_blobs, access_token)
+                .await?
+            {
+                response.not_created.append(id, err);
+                continue 'create;
+            }
+
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::ContactCard)
+                .create_document();
+            for blob_id in update.link_blobs {
+                batch.set(BlobOp::Link { hash: blob_id.hash }, Vec::new());
+            }
+            batch.custom(update.builder);
+            let document_id = self.write_batch_expect_id(batch).await?;
+            changes.log_insert(Collection::ContactCard, document_id);
+            response.created(id, document_id);
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain card
+            let document_id = id.document_id();
+            let card = if let Some(card) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::ContactCard,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                card
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+
+            // Verify permissions on shared accounts
+            if matches!(&can_modify_card_ids, Some(ids) if !ids.contains(document_id)) {
+                response.not_updated.append(
+                    id,
+                    SetError::forbidden()
+                        .with_description("You are not allowed to modify this card."),
+                );
+                continue 'update;
+            }
+            let current_address_book_ids = card_address_book_ids(&card.inner);
+
+            let update =
+                match contact_card_set_item(object, Some(card), &address_book_ids, &response) {
+                    Ok(update) => update,
+                    Err(err) => {
+                        response.not_updated.append(id, err);
+                        continue 'update;
+                    }
+                };
+
+            if let Some(new_address_book_ids) = update
+                .builder
+                .changes()
+                .and_then(|changes| changes.properties.get(&Property::AddressBookIds))
+                .and_then(|ids| ids.as_list())
+                .map(|ids| {
+                    ids.iter()
+                        .filter_map(|id| id.as_id().map(|id| id.document_id()))
+                        .collect::<Vec<_>>()
+                })
+            {
+                for address_book_id in &new_address_book_ids {
+                    if !current_address_book_ids.contains(address_book_id)
+                        && matches!(&can_add_address_book_ids, Some(ids) if !ids.contains(*address_book_id))
+                    {
+                        response.not_updated.append(
+                            id,
+                            SetError::forbidden().with_description(format!(
+                                "You are not allowed to add cards to address book {}.",
+                                Id::from(*address_book_id)
+                            )),
+                        );
+                        continue 'update;
+                    }
+                }
+                for address_book_id in &current_address_book_ids {
+                    if !new_address_book_ids.contains(address_book_id)
+                        && matches!(&can_remove_address_book_ids, Some(ids) if !ids.contains(*address_book_id))
+                    {
+                        response.not_updated.append(
+                            id,
+                            SetError::forbidden().with_description(format!(
+                                "You are not allowed to remove cards from address book {}.",
+                                Id::from(*address_book_id)
+                            )),
+                        );
+                        continue 'update;
+                    }
+                }
+            }
+
+            // Verify access to the media blobs
+            if let Some(err) = self
+                .contact_card_verify_blobs(&update.link_blobs, access_token)
+                .await?
+            {
+                response.not_updated.append(id, err);
+                continue 'update;
+            }
+
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::ContactCard)
+                .update_document(document_id);
+            for blob_id in update.link_blobs {
+                batch.set(BlobOp::Link { hash: blob_id.hash }, Vec::new());
+            }
+            for blob_id in update.unlink_blobs {
+                batch.clear(BlobOp::Link { hash: blob_id.hash });
+            }
+            batch.custom(update.builder);
+            if !batch.is_empty() {
+                match self.core.storage.data.write(batch.build()).await {
+                    Ok(_) => {
+                        changes.log_update(Collection::ContactCard, document_id);
+                    }
+                    Err(err) if err.is_assertion_failure() => {
+                        response.not_updated.append(
+                            id,
+                            SetError::forbidden().with_description(
+                                "Another process modified this card, please try again.",
+                            ),
+                        );
+                        continue 'update;
+                    }
+                    Err(err) => {
+                        return Err(err.caused_by(trc::location!()));
+                    }
+                }
+            }
+            response.updated.append(id, None);
+        }
+
+        // Process deletions
+        if !will_destroy.is_empty() {
+            let can_destroy_card_ids = if access_token.is_shared(account_id) {
+                self.shared_contact_cards(access_token, account_id, Acl::RemoveItems)
+                    .await?
+                    .into()
+            } else {
+                None
+            };
+
+            for id in will_destroy {
+                let document_id = id.document_id();
+                let card = if let Some(card) = self
+                    .get_property::<HashedValue<Object<Value>>>(
+                        account_id,
+                        Collection::ContactCard,
+                        document_id,
+                        Property::Value,
+                    )
+                    .await?
+                {
+                    card
+                } else {
+                    response.not_destroyed.append(id, SetError::not_found());
+                    continue;
+                };
+                if matches!(&can_destroy_card_ids, Some(ids) if !ids.contains(document_id)) {
+                    response.not_destroyed.append(
+                        id,
+                        SetError::forbidden()
+                            .with_description("You are not allowed to delete this card."),
+                    );
+                    continue;
+                }
+
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::ContactCard);
+                self.contact_card_delete(&mut batch, document_id, card);
+                match self.core.storage.data.write(batch.build()).await {
+                    Ok(_) => {
+                        changes.log_delete(Collection::ContactCard, document_id);
+                        response.destroyed.push(id);
+                    }
+                    Err(err) if err.is_assertion_failure() => {
+                        response.not_destroyed.append(
+                            id,
+                            SetError::forbidden().with_description(
+                                "Another process modified this card, please try again.",
+                            ),
+                        );
+                    }
+                    Err(err) => {
+                        return Err(err.caused_by(trc::location!()));
+                    }
+                }
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            response.state_change = StateChange::new(account_id)
+                .with_change(DataType::ContactCard, changes.change_id)
+                .into();
+            response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
+        }
+
+        Ok(response)
+    }
+
+    pub fn contact_card_delete(
+        &self,
+        batch: &mut BatchBuilder,
+        document_id: u32,
+        card: HashedValue<Object<Value>>,
+    ) {
+        batch.delete_document(document_id);
+        for blob_id in card_media_blobs(&card.inner).unwrap_or_default() {
+            batch.clear(BlobOp::Link { hash: blob_id.hash });
+        }
+        batch.custom(ObjectIndexBuilder::new(SCHEMA).with_current(card));
+    }
+
+    async fn contact_card_verify_blobs(
+        &self,
+        blob_ids: &[BlobId],
+        access_token: &AccessToken,
+    ) -> trc::Result<Option<SetError>> {
+        for blob_id in blob_ids {
+            if !self.has_access_blob(blob_id, access_token).await? {
+                return Ok(Some(
+                    SetError::new(SetErrorType::BlobNotFound)
+                        .with_description(format!("blobId {blob_id} does not exist.")),
+                ));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+fn contact_card_set_item(
+    changes_: Object<SetValue>,
+    current: Option<HashedValue<Object<Value>>>,
+    address_book_ids: &RoaringBitmap,
+    response: &SetResponse,
+) -> Result<CardUpdate, SetError> {
+    let mut changes = Object::with_capacity(changes_.properties.len() + 3);
+    let mut card_address_book_ids_ = current
+        .as_ref()
+        .map(|current| card_address_book_ids(&current.inner))
+        .unwrap_or_default();
+    let mut has_address_book_ids = false;
+
+    for (property, value) in changes_.properties {
+        let value = match (&property, response.eval_object_references(value)?) {
+            (Property::AddressBookIds, MaybePatchValue::Value(Value::List(ids))) => {
+                has_address_book_ids = true;
+                card_address_book_ids_ = ids
+                    .into_iter()
+                    .filter_map(|id| id.try_unwrap_id().map(|id| id.document_id()))
+                    .collect();
+                continue;
+            }
+            (Property::AddressBookIds, MaybePatchValue::Patch(patch)) => {
+                has_address_book_ids = true;
+                let mut patch = patch.into_iter();
+                if let Some(document_id) = patch.next().unwrap().try_unwrap_id() {
+                    let document_id = document_id.document_id();
+                    if patch.next().unwrap().try_unwrap_bool().unwrap_or_default() {
+                        if !card_address_book_ids_.contains(&document_id) {
+                            card_address_book_ids_.push(document_id);
+                        }
+                    } else {
+                        card_address_book_ids_.retain(|id| id != &document_id);
+                    }
+                }
+                continue;
+            }
+            (Property::Uid, MaybePatchValue::Value(Value::Text(value))) if current.is_none() => {
+                Value::Text(value)
+            }
+            (Property::Kind, MaybePatchValue::Value(Value::Text(value)))
+                if CONTACT_KINDS.contains(&value.as_str()) =>
+            {
+                Value::Text(value)
+            }
+            (Property::Kind, MaybePatchValue::Value(Value::Null)) => Value::Null,
+            (Property::_T(name), _) if name.contains('/') => {
+                return Err(SetError::invalid_properties()
+                    .with_property(property)
+                    .with_description("Patching card properties is not supported."));
+            }
+            (
+                Property::_T(_)
+                | Property::Name
+                | Property::Keywords
+                | Property::Addresses
+                | Property::Language
+                | Property::Members,
+                MaybePatchValue::Value(value),
+            ) => value,
+            _ => {
+                return Err(SetError::invalid_properties()
+                    .with_property(property)
+                    .with_description("Invalid property or value.".to_string()))
+            }
+        };
+        changes.append(property, value);
+    }
+
+    // Validate address books
+    if current.is_none() || has_address_book_ids {
+        if card_address_book_ids_.is_empty() {
+            return Err(SetError::invalid_properties()
+                .with_property(Property::AddressBookIds)
+                .with_description("Card has to belong to at least one address book."));
+        }
+        for address_book_id in &card_address_book_ids_ {
+            if !address_book_ids.contains(*address_book_id) {
+                return Err(SetError::invalid_properties()
+                    .with_property(Property::AddressBookIds)
+                    .with_description(format!(
+                        "addressBookId {} does not exist.",
+                        Id::from(*address_book_id)
+                    )));
+            }
+        }
+        card_address_book_ids_.sort_unstable();
+        card_address_book_ids_.dedup();
+        changes.append(
+            Property::AddressBookIds,
+            Value::List(
+                card_address_book_ids_
+                    .into_iter()
+                    .map(|id| Value::Id(id.into()))
+                    .collect(),
+            ),
+        );
+    }
+
+    // Generate a UID if missing
+    if current.is_none() && !changes.properties.contains_key(&Property::Uid) {
+        changes.append(Property::Uid, Value::Text(generate_uid()));
+    }
+
+    // Derive the indexed fields from the merged card
+    let mut merged = current
+        .as_ref()
+        .map(|current| current.inner.clone())
+        .unwrap_or_else(|| Object::with_capacity(changes.properties.len()));
+    for (property, value) in changes.properties.iter() {
+        if matches!(value, Value::Null) {
+            merged.remove(property);
+        } else {
+            merged.set(property.clone(), value.clone());
+        }
+    }
+    let full_name = card_full_name(&merged).map_or(Value::Null, Value::Text);
+    let emails = card_emails(&merged);
+    let emails = if !emails.is_empty() {
+        Value::List(emails)
+    } else {
+        Value::Null
+    };
+    for (property, value) in [(Property::FullName, full_name), (Property::Email, emails)] {
+        if current.is_some() || !matches!(value, Value::Null) {
+            changes.set(property, value);
+        }
+    }
+
+    // Obtain the media blobs to link and unlink
+    let media_blobs = card_media_blobs(&merged).map_err(|err| {
+        SetError::invalid_properties()
+            .with_property(Property::_T("media".to_string()))
+            .with_description(err)
+    })?;
+    let current_media_blobs = current
+        .as_ref()
+        .and_then(|current| card_media_blobs(&current.inner).ok())
+        .unwrap_or_default();
+    let link_blobs = media_blobs
+        .iter()
+        .filter(|blob_id| !current_media_blobs.iter().any(|b| b.hash == blob_id.hash))
+        .cloned()
+        .collect();
+    let unlink_blobs = current_media_blobs
+        .into_iter()
+        .filter(|blob_id| !media_blobs.iter().any(|b| b.hash == blob_id.hash))
+        .collect();
+
+    Ok(CardUpdate {
+        builder: ObjectIndexBuilder::new(SCHEMA)
+            .with_changes(changes)
+            .with_current_opt(current)
+            .validate()?,
+        link_blobs,
+        unlink_blobs,
+    })
+}
+
+fn card_address_book_ids(card: &Object<Value>) -> Vec<u32> {
+    card.properties
+        .get(&Property::AddressBookIds)
+        .and_then(|ids| ids.as_list())
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_id().map(|id| id.document_id()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
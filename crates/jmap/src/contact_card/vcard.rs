@@ -0,0 +1,884 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::fmt::Write;
+
+use jmap_proto::{
+    object::Object,
+    types::{property::Property, value::Value},
+};
+
+use super::card_full_name;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct VCardProperty {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub values: Vec<String>,
+}
+
+// Properties with structured values, components are separated by semicolons
+const STRUCTURED: &[&str] = &["N", "ADR", "ORG", "GENDER"];
+
+const PRODID: &str = "-//Stalwart Labs Ltd.//Stalwart Server//EN";
+
+/// Parses a vCard (RFC 6350) or jCard (RFC 7095) document into a JSContact card.
+pub fn parse_contact(bytes: &[u8]) -> Option<Object<Value>> {
+    let properties = if bytes.iter().find(|ch| !ch.is_ascii_whitespace()) == Some(&b'[') {
+        parse_jcard(bytes)?
+    } else {
+        parse_vcard(std::str::from_utf8(bytes).ok()?)?
+    };
+
+    Some(to_jscontact(properties))
+}
+
+pub fn parse_vcard(text: &str) -> Option<Vec<VCardProperty>> {
+    // Unfold lines
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        if let Some(continuation) = line.strip_prefix([' ', '\t']) {
+            lines.last_mut()?.push_str(continuation);
+        } else if !line.is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+
+    let mut properties = Vec::new();
+    let mut in_card = false;
+    for line in lines {
+        let (name_params, value) = split_unquoted(&line, ':')?;
+        let mut name_params = split_unquoted_all(name_params, ';').into_iter();
+        let name = name_params.next()?;
+        let name = name
+            .rsplit_once('.')
+            .map_or(name, |(_, name)| name)
+            .to_ascii_uppercase();
+
+        match (name.as_str(), value.to_ascii_uppercase().as_str()) {
+            ("BEGIN", "VCARD") => {
+                in_card = true;
+                continue;
+            }
+            ("END", "VCARD") => break,
+            _ if !in_card => return None,
+            _ => (),
+        }
+
+        let mut params = Vec::new();
+        for param in name_params {
+            let (key, values) = match param.split_once('=') {
+                Some((key, values)) => (key.to_ascii_lowercase(), values),
+                // vCard 2.1 allows parameters without a name
+                None => ("type".to_string(), param),
+            };
+            for value in split_unquoted_all(values, ',') {
+                params.push((key.clone(), value.trim_matches('"').to_string()));
+            }
+        }
+
+        let values = if STRUCTURED.contains(&name.as_str()) {
+            split_escaped(value, ';')
+        } else {
+            vec![unescape(value)]
+        };
+
+        properties.push(VCardProperty {
+            name,
+            params,
+            values,
+        });
+    }
+
+    (in_card && !properties.is_empty()).then_some(properties)
+}
+
+pub fn parse_jcard(bytes: &[u8]) -> Option<Vec<VCardProperty>> {
+    let jcard: serde_json::Value = serde_json::from_slice(bytes).ok()?;
+    let jcard = jcard.as_array()?;
+    if jcard.first()?.as_str()? != "vcard" {
+        return None;
+    }
+
+    let mut properties = Vec::new();
+    for item in jcard.get(1)?.as_array()? {
+        let item = item.as_array()?;
+        let name = item.first()?.as_str()?.to_ascii_uppercase();
+        let mut params = Vec::new();
+        for (key, value) in item.get(1)?.as_object()? {
+            let key = key.to_ascii_lowercase();
+            match value {
+                serde_json::Value::Array(values) => {
+                    for value in values {
+                        params.push((key.clone(), json_to_string(value)));
+                    }
+                }
+                value => params.push((key, json_to_string(value))),
+            }
+        }
+
+        let mut values = Vec::new();
+        for value in item.iter().skip(3) {
+            match value {
+                serde_json::Value::Array(components) => {
+                    values.extend(components.iter().map(json_to_string));
+                }
+                value => values.push(json_to_string(value)),
+            }
+        }
+
+        properties.push(VCardProperty {
+            name,
+            params,
+            values,
+        });
+    }
+
+    (!properties.is_empty()).then_some(properties)
+}
+
+pub fn to_jscontact(properties: Vec<VCardProperty>) -> Object<Value> {
+    let mut card = Object::with_capacity(properties.len() + 2)
+        .with_property(Property::_T("@type".to_string()), "Card")
+        .with_property(Property::_T("version".to_string()), "1.0");
+    let mut name = Object::with_capacity(2);
+    let mut name_components = Vec::new();
+    let mut keywords = Object::with_capacity(0);
+
+    for property in properties {
+        let VCardProperty {
+            name: property_name,
+            params,
+            values,
+        } = property;
+        let mut values = values.into_iter();
+        let value = values.next().unwrap_or_default();
+
+        let (map, prefix, entry) = match property_name.as_str() {
+            "UID" => {
+                card.set(Property::Uid, value);
+                continue;
+            }
+            // Apple clients store the kind of vCard 3.0 groups in an extension
+            "KIND" | "X-ADDRESSBOOKSERVER-KIND" => {
+                card.set(Property::Kind, value.to_ascii_lowercase());
+                continue;
+            }
+            "FN" => {
+                if !value.is_empty() {
+                    name.set(Property::_T("full".to_string()), value);
+                }
+                continue;
+            }
+            "N" => {
+                for (kind, value) in ["surname", "given", "given2", "title", "credential"]
+                    .into_iter()
+                    .zip(std::iter::once(value).chain(values))
+                {
+                    for value in value.split(',').filter(|value| !value.is_empty()) {
+                        name_components.push(Value::Object(
+                            Object::with_capacity(2)
+                                .with_property(Property::_T("kind".to_string()), kind)
+                                .with_property(Property::_T("value".to_string()), value),
+                        ));
+                    }
+                }
+                continue;
+            }
+            "CATEGORIES" => {
+                for value in value.split(',').filter(|value| !value.is_empty()) {
+                    keywords.set(Property::_T(value.to_string()), true);
+                }
+                continue;
+            }
+            "NICKNAME" => (
+                "nicknames",
+                "k",
+                Object::with_capacity(1).with_property(Property::_T("name".to_string()), value),
+            ),
+            "EMAIL" => (
+                "emails",
+                "e",
+                Object::with_capacity(1).with_property(Property::_T("address".to_string()), value),
+            ),
+            "TEL" => {
+                let mut phone = Object::with_capacity(2)
+                    .with_property(Property::_T("number".to_string()), value);
+                let mut features = Object::with_capacity(0);
+                for (_, value) in params.iter().filter(|(key, _)| key == "type") {
+                    let feature = match value.to_ascii_lowercase().as_str() {
+                        "cell" => "mobile",
+                        "voice" => "voice",
+                        "fax" => "fax",
+                        "text" => "text",
+                        "video" => "video",
+                        "pager" => "pager",
+                        "textphone" => "textphone",
+                        _ => continue,
+                    };
+                    features.set(Property::_T(feature.to_string()), true);
+                }
+                if !features.properties.is_empty() {
+                    phone.set(Property::_T("features".to_string()), features);
+                }
+                ("phones", "p", phone)
+            }
+            "ADR" => {
+                let mut components = Vec::new();
+                for (kind, value) in [
+                    "postOfficeBox",
+                    "apartment",
+                    "name",
+                    "locality",
+                    "region",
+                    "postcode",
+                    "country",
+                ]
+                .into_iter()
+                .zip(std::iter::once(value).chain(values))
+                {
+                    if !value.is_empty() {
+                        components.push(Value::Object(
+                            Object::with_capacity(2)
+                                .with_property(Property::_T("kind".to_string()), kind)
+                                .with_property(Property::_T("value".to_string()), value),
+                        ));
+                    }
+                }
+                (
+                    "addresses",
+                    "a",
+                    Object::with_capacity(1).with_property(
+                        Property::_T("components".to_string()),
+                        Value::List(components),
+                    ),
+                )
+            }
+            "ORG" => {
+                let mut organization =
+                    Object::with_capacity(2).with_property(Property::_T("name".to_string()), value);
+                let units = values
+                    .filter(|unit| !unit.is_empty())
+                    .map(|unit| {
+                        Value::Object(
+                            Object::with_capacity(1)
+                                .with_property(Property::_T("name".to_string()), unit),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                if !units.is_empty() {
+                    organization.set(Property::_T("units".to_string()), Value::List(units));
+                }
+                ("organizations", "o", organization)
+            }
+            "TITLE" | "ROLE" => (
+                "titles",
+                "t",
+                Object::with_capacity(2)
+                    .with_property(Property::_T("name".to_string()), value)
+                    .with_property(
+                        Property::_T("kind".to_string()),
+                        if property_name == "TITLE" {
+                            "title"
+                        } else {
+                            "role"
+                        },
+                    ),
+            ),
+            "NOTE" => (
+                "notes",
+                "n",
+                Object::with_capacity(1).with_property(Property::_T("note".to_string()), value),
+            ),
+            "URL" => (
+                "links",
+                "l",
+                Object::with_capacity(1).with_property(Property::_T("uri".to_string()), value),
+            ),
+            "PHOTO" => {
+                // vCard 3.0 inlines photos as base64 encoded parameters
+                let is_base64 = params.iter().any(|(key, value)| {
+                    key == "encoding"
+                        && matches!(value.to_ascii_lowercase().as_str(), "b" | "base64")
+                });
+                let uri = if is_base64 {
+                    let media_type = params
+                        .iter()
+                        .find(|(key, _)| key == "type")
+                        .map_or("jpeg".to_string(), |(_, value)| value.to_ascii_lowercase());
+                    format!("data:image/{media_type};base64,{value}")
+                } else {
+                    value
+                };
+                (
+                    "media",
+                    "m",
+                    Object::with_capacity(2)
+                        .with_property(Property::_T("kind".to_string()), "photo")
+                        .with_property(Property::_T("uri".to_string()), uri),
+                )
+            }
+            "BDAY" | "ANNIVERSARY" => {
+                let Some(date) = parse_partial_date(&value) else {
+                    continue;
+                };
+                (
+                    "anniversaries",
+                    "n",
+                    Object::with_capacity(2)
+                        .with_property(
+                            Property::_T("kind".to_string()),
+                            if property_name == "BDAY" {
+                                "birth"
+                            } else {
+                                "wedding"
+                            },
+                        )
+                        .with_property(Property::_T("date".to_string()), date),
+                )
+            }
+            _ => continue,
+        };
+
+        let mut entry = entry;
+        if matches!(prefix, "e" | "p" | "a") {
+            add_contexts(&mut entry, &params);
+        }
+
+        let map = card
+            .properties
+            .get_mut_or_insert_with(Property::parse(map), || {
+                Value::Object(Object::with_capacity(1))
+            });
+        if let Value::Object(map) = map {
+            let id = format!("{prefix}{}", map.properties.len() + 1);
+            map.set(Property::_T(id), entry);
+        }
+    }
+
+    if !name_components.is_empty() {
+        name.set(
+            Property::_T("components".to_string()),
+            Value::List(name_components),
+        );
+    }
+    if !name.properties.is_empty() {
+        card.set(Property::Name, name);
+    }
+    if !keywords.properties.is_empty() {
+        card.set(Property::Keywords, keywords);
+    }
+
+    card
+}
+
+/// Builds a vCard 3.0 document from a JSContact card, as expected by most CardDAV clients.
+pub fn build_vcard(card: &Object<Value>) -> String {
+    let mut vcard = String::with_capacity(512);
+    vcard.push_str("BEGIN:VCARD\r\nVERSION:3.0\r\n");
+    write_line(&mut vcard, "PRODID", &[], PRODID);
+    if let Some(uid) = card.get(&Property::Uid).as_string() {
+        write_line(&mut vcard, "UID", &[], &escape(uid));
+    }
+    if let Some(kind) = card.get(&Property::Kind).as_string() {
+        write_line(&mut vcard, "X-ADDRESSBOOKSERVER-KIND", &[], kind);
+    }
+    write_line(
+        &mut vcard,
+        "FN",
+        &[],
+        &escape(&card_full_name(card).unwrap_or_default()),
+    );
+
+    // Name components
+    let components = card
+        .get(&Property::Name)
+        .as_obj()
+        .map(|name| obj_list(name, "components"))
+        .unwrap_or_default();
+    let name = ["surname", "given", "given2", "title", "credential"]
+        .into_iter()
+        .map(|kind| {
+            components
+                .iter()
+                .filter(|component| obj_str(component, "kind") == Some(kind))
+                .filter_map(|component| obj_str(component, "value"))
+                .map(escape)
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>()
+        .join(";");
+    write_line(&mut vcard, "N", &[], &name);
+
+    for entry in obj_entries(card, "nicknames") {
+        if let Some(name) = obj_str(entry, "name") {
+            write_line(&mut vcard, "NICKNAME", &[], &escape(name));
+        }
+    }
+    for entry in obj_entries(card, "emails") {
+        if let Some(address) = obj_str(entry, "address") {
+            let mut types = vec!["INTERNET"];
+            add_types(entry, &mut types);
+            write_line(
+                &mut vcard,
+                "EMAIL",
+                &[("TYPE", &types.join(","))],
+                &escape(address),
+            );
+        }
+    }
+    for entry in obj_entries(card, "phones") {
+        if let Some(number) = obj_str(entry, "number") {
+            let mut types = Vec::new();
+            if let Some(features) = entry.get(&Property::_T("features".to_string())).as_obj() {
+                for (feature, _) in features.properties.iter() {
+                    types.push(match feature.to_string().as_str() {
+                        "mobile" => "CELL",
+                        "voice" => "VOICE",
+                        "fax" => "FAX",
+                        "text" => "TEXT",
+                        "video" => "VIDEO",
+                        "pager" => "PAGER",
+                        "textphone" => "TEXTPHONE",
+                        _ => continue,
+                    });
+                }
+            }
+            add_types(entry, &mut types);
+            write_params_line(&mut vcard, "TEL", &types, &escape(number));
+        }
+    }
+    for entry in obj_entries(card, "addresses") {
+        let components = obj_list(entry, "components");
+        let address = [
+            "postOfficeBox",
+            "apartment",
+            "name",
+            "locality",
+            "region",
+            "postcode",
+            "country",
+        ]
+        .into_iter()
+        .map(|kind| {
+            components
+                .iter()
+                .filter(|component| obj_str(component, "kind") == Some(kind))
+                .filter_map(|component| obj_str(component, "value"))
+                .map(escape)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(";");
+        let mut types = Vec::new();
+        add_types(entry, &mut types);
+        write_params_line(&mut vcard, "ADR", &types, &address);
+    }
+    for entry in obj_entries(card, "organizations") {
+        let mut organization = escape(obj_str(entry, "name").unwrap_or_default());
+        for unit in obj_list(entry, "units") {
+            organization.push(';');
+            organization.push_str(&escape(obj_str(unit, "name").unwrap_or_default()));
+        }
+        write_line(&mut vcard, "ORG", &[], &organization);
+    }
+    for entry in obj_entries(card, "titles") {
+        if let Some(name) = obj_str(entry, "name") {
+            let property = if obj_str(entry, "kind") == Some("role") {
+                "ROLE"
+            } else {
+                "TITLE"
+            };
+            write_line(&mut vcard, property, &[], &escape(name));
+        }
+    }
+    for entry in obj_entries(card, "notes") {
+        if let Some(note) = obj_str(entry, "note") {
+            write_line(&mut vcard, "NOTE", &[], &escape(note));
+        }
+    }
+    for entry in obj_entries(card, "links") {
+        if let Some(uri) = obj_str(entry, "uri") {
+            write_line(&mut vcard, "URL", &[], uri);
+        }
+    }
+    for entry in obj_entries(card, "media") {
+        if let (Some("photo"), Some(uri)) = (obj_str(entry, "kind"), obj_str(entry, "uri")) {
+            // vCard 3.0 clients expect photos inlined as base64
+            if let Some((media_type, data)) = uri
+                .strip_prefix("data:image/")
+                .and_then(|uri| uri.split_once(";base64,"))
+            {
+                write_line(
+                    &mut vcard,
+                    "PHOTO",
+                    &[
+                        ("ENCODING", "b"),
+                        ("TYPE", &media_type.to_ascii_uppercase()),
+                    ],
+                    data,
+                );
+            } else {
+                write_line(&mut vcard, "PHOTO", &[("VALUE", "uri")], uri);
+            }
+        }
+    }
+    for entry in obj_entries(card, "anniversaries") {
+        let property = match obj_str(entry, "kind") {
+            Some("birth") => "BDAY",
+            Some("wedding") => "ANNIVERSARY",
+            _ => continue,
+        };
+        if let Some(date) = entry
+            .get(&Property::_T("date".to_string()))
+            .as_obj()
+            .and_then(format_partial_date)
+        {
+            write_line(&mut vcard, property, &[], &date);
+        }
+    }
+    if let Some(keywords) = card.get(&Property::Keywords).as_obj() {
+        let categories = keywords
+            .properties
+            .keys()
+            .map(|keyword| escape(&keyword.to_string()))
+            .collect::<Vec<_>>();
+        if !categories.is_empty() {
+            write_line(&mut vcard, "CATEGORIES", &[], &categories.join(","));
+        }
+    }
+
+    vcard.push_str("END:VCARD\r\n");
+    vcard
+}
+
+fn obj_str<'x>(object: &'x Object<Value>, key: &str) -> Option<&'x str> {
+    object
+        .get(&Property::_T(key.to_string()))
+        .as_string()
+        .filter(|value| !value.is_empty())
+}
+
+fn obj_list<'x>(object: &'x Object<Value>, key: &str) -> Vec<&'x Object<Value>> {
+    object
+        .get(&Property::_T(key.to_string()))
+        .as_list()
+        .map(|list| list.iter().filter_map(|item| item.as_obj()).collect())
+        .unwrap_or_default()
+}
+
+fn obj_entries<'x>(card: &'x Object<Value>, map: &str) -> Vec<&'x Object<Value>> {
+    card.get(&Property::parse(map))
+        .as_obj()
+        .map(|map| {
+            map.properties
+                .values()
+                .filter_map(|entry| entry.as_obj())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn add_types(entry: &Object<Value>, types: &mut Vec<&'static str>) {
+    if let Some(contexts) = entry.get(&Property::_T("contexts".to_string())).as_obj() {
+        for (context, _) in contexts.properties.iter() {
+            match context.to_string().as_str() {
+                "private" => types.push("HOME"),
+                "work" => types.push("WORK"),
+                _ => (),
+            }
+        }
+    }
+    if entry.get(&Property::_T("pref".to_string())).as_uint() == Some(1) {
+        types.push("PREF");
+    }
+}
+
+fn format_partial_date(date: &Object<Value>) -> Option<String> {
+    let part = |key: &str| date.get(&Property::_T(key.to_string())).as_uint();
+    match (part("year"), part("month"), part("day")) {
+        (Some(year), Some(month), Some(day)) => Some(format!("{year:04}-{month:02}-{day:02}")),
+        (None, Some(month), Some(day)) => Some(format!("--{month:02}{day:02}")),
+        (Some(year), None, None) => Some(format!("{year:04}")),
+        _ => None,
+    }
+}
+
+fn write_params_line(vcard: &mut String, name: &str, types: &[&str], value: &str) {
+    if !types.is_empty() {
+        write_line(vcard, name, &[("TYPE", &types.join(","))], value);
+    } else {
+        write_line(vcard, name, &[], value);
+    }
+}
+
+fn write_line(vcard: &mut String, name: &str, params: &[(&str, &str)], value: &str) {
+    let mut line = String::with_capacity(name.len() + value.len() + 16);
+    line.push_str(name);
+    for (key, param) in params {
+        let _ = write!(line, ";{key}={param}");
+    }
+    line.push(':');
+    line.push_str(value);
+
+    let mut line_len = 0;
+    for ch in line.chars() {
+        if line_len + ch.len_utf8() > 75 {
+            vcard.push_str("\r\n ");
+            line_len = 1;
+        }
+        vcard.push(ch);
+        line_len += ch.len_utf8();
+    }
+    vcard.push_str("\r\n");
+}
+
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' | ';' | ',' => {
+                result.push('\\');
+                result.push(ch);
+            }
+            '\n' => result.push_str("\\n"),
+            '\r' => (),
+            ch => result.push(ch),
+        }
+    }
+    result
+}
+
+fn add_contexts(entry: &mut Object<Value>, params: &[(String, String)]) {
+    let mut contexts = Object::with_capacity(0);
+    let mut pref = None;
+    for (key, value) in params {
+        match (key.as_str(), value.to_ascii_lowercase().as_str()) {
+            ("type", "home") => {
+                contexts.set(Property::_T("private".to_string()), true);
+            }
+            ("type", "work") => {
+                contexts.set(Property::_T("work".to_string()), true);
+            }
+            ("type", "pref") => {
+                pref = Some(1);
+            }
+            ("pref", value) => {
+                pref = value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|pref| (1..=100).contains(pref));
+            }
+            _ => (),
+        }
+    }
+    if !contexts.properties.is_empty() {
+        entry.set(Property::_T("contexts".to_string()), contexts);
+    }
+    if let Some(pref) = pref {
+        entry.set(Property::_T("pref".to_string()), pref);
+    }
+}
+
+fn parse_partial_date(value: &str) -> Option<Object<Value>> {
+    // Supports YYYYMMDD, YYYY-MM-DD and --MMDD, ignoring any time component
+    let value = value.split('T').next()?;
+    let (year, rest) = if let Some(rest) = value.strip_prefix("--") {
+        (None, rest.to_string())
+    } else {
+        let digits = value.replace('-', "");
+        if digits.len() < 4 {
+            return None;
+        }
+        let (year, rest) = digits.split_at(4);
+        (Some(year.parse::<u64>().ok()?), rest.to_string())
+    };
+    let rest = rest.replace('-', "");
+    let (month, day) = match rest.len() {
+        0 => (None, None),
+        2 => (Some(rest.parse::<u64>().ok()?), None),
+        4 => (
+            Some(rest[..2].parse::<u64>().ok()?),
+            Some(rest[2..].parse::<u64>().ok()?),
+        ),
+        _ => return None,
+    };
+    if month.map_or(false, |month| !(1..=12).contains(&month))
+        || day.map_or(false, |day| !(1..=31).contains(&day))
+    {
+        return None;
+    }
+
+    let mut date =
+        Object::with_capacity(4).with_property(Property::_T("@type".to_string()), "PartialDate");
+    for (key, value) in [("year", year), ("month", month), ("day", day)] {
+        if let Some(value) = value {
+            date.set(Property::_T(key.to_string()), value);
+        }
+    }
+    Some(date)
+}
+
+fn json_to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        serde_json::Value::Array(values) => values
+            .iter()
+            .map(json_to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        serde_json::Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+fn split_unquoted(text: &str, separator: char) -> Option<(&str, &str)> {
+    let mut in_quotes = false;
+    for (pos, ch) in text.char_indices() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            ch if ch == separator && !in_quotes => {
+                return Some((&text[..pos], &text[pos + 1..]));
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+fn split_unquoted_all(mut text: &str, separator: char) -> Vec<&str> {
+    let mut items = Vec::new();
+    while let Some((item, rest)) = split_unquoted(text, separator) {
+        items.push(item);
+        text = rest;
+    }
+    items.push(text);
+    items
+}
+
+fn split_escaped(text: &str, separator: char) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                if let Some(ch) = chars.next() {
+                    item.push('\\');
+                    item.push(ch);
+                }
+            }
+            ch if ch == separator => {
+                items.push(unescape(&item));
+                item.clear();
+            }
+            ch => item.push(ch),
+        }
+    }
+    items.push(unescape(&item));
+    items
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            match chars.next() {
+                Some('n' | 'N') => result.push('\n'),
+                Some(ch) => result.push(ch),
+                None => (),
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use jmap_proto::types::{property::Property, value::Value};
+
+    use super::{build_vcard, parse_contact};
+
+    #[test]
+    fn parse_vcard_and_jcard() {
+        let vcard = concat!(
+            "BEGIN:VCARD\r\n",
+            "VERSION:4.0\r\n",
+            "UID:urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1\r\n",
+            "FN:Jane Doe\r\n",
+            "N:Doe;Jane;;Dr.;\r\n",
+            "EMAIL;TYPE=work;PREF=1:jane@example.com\r\n",
+            "TEL;TYPE=home,cell:+1-555-555-5555\r\n",
+            "ADR;TYPE=home:;;123 Main St;Anytown;CA;91921;USA\r\n",
+            "NOTE:Met at the conference\\, very\r\n",
+            "  friendly\r\n",
+            "BDAY:19800131\r\n",
+            "END:VCARD\r\n"
+        );
+        let jcard = r#"["vcard",[["version",{},"text","4.0"],
+            ["uid",{},"uri","urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1"],
+            ["fn",{},"text","Jane Doe"],
+            ["n",{},"text",["Doe","Jane","","Dr.",""]],
+            ["email",{"type":"work","pref":"1"},"text","jane@example.com"],
+            ["tel",{"type":["home","cell"]},"uri","+1-555-555-5555"],
+            ["adr",{"type":"home"},"text",["","","123 Main St","Anytown","CA","91921","USA"]],
+            ["note",{},"text","Met at the conference, very friendly"],
+            ["bday",{},"date-and-or-time","1980-01-31"]]]"#;
+
+        let card = parse_contact(vcard.as_bytes()).unwrap();
+        assert_eq!(card, parse_contact(jcard.as_bytes()).unwrap());
+        assert_eq!(
+            card.get(&Property::Uid).as_string(),
+            Some("urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1")
+        );
+
+        let card = serde_json::to_value(&card).unwrap();
+        for (pointer, expected) in [
+            ("/name/full", "Jane Doe"),
+            ("/name/components/0/kind", "surname"),
+            ("/name/components/2/value", "Dr."),
+            ("/emails/e1/address", "jane@example.com"),
+            ("/phones/p1/number", "+1-555-555-5555"),
+            ("/addresses/a1/components/1/value", "Anytown"),
+            ("/notes/n1/note", "Met at the conference, very friendly"),
+            ("/anniversaries/n1/kind", "birth"),
+        ] {
+            assert_eq!(
+                card.pointer(pointer).and_then(|v| v.as_str()),
+                Some(expected),
+                "{pointer}"
+            );
+        }
+        for (pointer, expected) in [
+            ("/emails/e1/contexts/work", Value::Bool(true)),
+            ("/emails/e1/pref", Value::UnsignedInt(1)),
+            ("/phones/p1/contexts/private", Value::Bool(true)),
+            ("/phones/p1/features/mobile", Value::Bool(true)),
+            ("/anniversaries/n1/date/month", Value::UnsignedInt(1)),
+        ] {
+            assert_eq!(
+                card.pointer(pointer),
+                Some(&serde_json::to_value(&expected).unwrap()),
+                "{pointer}"
+            );
+        }
+
+        // Cards converted back to vCard have to parse to the same card
+        let card = parse_contact(vcard.as_bytes()).unwrap();
+        let vcard = build_vcard(&card);
+        assert!(vcard.contains("EMAIL;TYPE=INTERNET,WORK,PREF:jane@example.com\r\n"));
+        assert!(vcard.contains("N:Doe;Jane;;Dr.;\r\n"));
+        assert_eq!(parse_contact(vcard.as_bytes()).unwrap(), card);
+
+        assert!(parse_contact(b"BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n").is_none());
+        assert!(parse_contact(b"[\"vcalendar\",[]]").is_none());
+    }
+}
@@ -0,0 +1,128 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::Object,
+    types::{
+        blob::BlobId, collection::Collection, date::UTCDate, id::Id, property::Property,
+        value::Value,
+    },
+};
+use store::{write::now, BlobClass};
+
+use crate::JMAP;
+
+impl JMAP {
+    pub async fn deleted_email_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::BlobId,
+            Property::Size,
+            Property::ReceivedAt,
+            Property::DeletedAt,
+            Property::ExpiresAt,
+            Property::MailboxIds,
+            Property::Keywords,
+            Property::Subject,
+            Property::From,
+            Property::Preview,
+        ]);
+        let account_id = request.account_id.document_id();
+        let now = now();
+
+        // Return the most recently deleted messages first
+        let is_listing = ids.is_none();
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            self.recycle_bin_ids(
+                account_id,
+                Property::DeletedAt,
+                u64::MAX,
+                false,
+                self.core.jmap.get_max_objects,
+            )
+            .await?
+            .into_iter()
+            .map(Id::from)
+            .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self.get_state(account_id, Collection::Email).await?.into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            let email = if let Some(email) = self
+                .recycle_bin_get(account_id, id.document_id())
+                .await?
+                .map(|email| email.inner.inner)
+                .filter(|email| email.expires_at > now)
+            {
+                email
+            } else {
+                // Expired entries are removed by the purge task
+                if !is_listing {
+                    response.not_found.push(id.into());
+                }
+                continue;
+            };
+
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    Property::BlobId => Value::BlobId(BlobId::new(
+                        email.blob_hash.clone(),
+                        BlobClass::Reserved {
+                            account_id,
+                            expires: email.expires_at,
+                        },
+                    )),
+                    Property::Size => Value::UnsignedInt(email.size as u64),
+                    Property::ReceivedAt => {
+                        Value::Date(UTCDate::from_timestamp(email.received_at as i64))
+                    }
+                    Property::DeletedAt => {
+                        Value::Date(UTCDate::from_timestamp(email.deleted_at as i64))
+                    }
+                    Property::ExpiresAt => {
+                        Value::Date(UTCDate::from_timestamp(email.expires_at as i64))
+                    }
+                    Property::MailboxIds => {
+                        let mut obj = Object::with_capacity(email.mailbox_ids.len());
+                        for mailbox_id in &email.mailbox_ids {
+                            obj.append(Property::_T(Id::from(*mailbox_id).to_string()), true);
+                        }
+                        Value::Object(obj)
+                    }
+                    Property::Keywords => {
+                        let mut obj = Object::with_capacity(email.keywords.len());
+                        for keyword in &email.keywords {
+                            obj.append(Property::_T(keyword.clone()), true);
+                        }
+                        Value::Object(obj)
+                    }
+                    Property::Subject => Value::Text(email.subject.clone()),
+                    Property::From => Value::Text(email.from.clone()),
+                    Property::Preview => Value::Text(email.preview.clone()),
+                    _ => Value::Null,
+                };
+                result.append(property.clone(), value);
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
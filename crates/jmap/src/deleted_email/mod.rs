@@ -0,0 +1,365 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::types::{collection::Collection, keyword::Keyword, property::Property};
+use mail_parser::HeaderName;
+use store::{
+    write::{
+        assert::HashedValue, key::DeserializeBigEndian, now, BatchBuilder, Bincode, BlobOp,
+        DirectoryClass, ValueClass, F_CLEAR, F_INDEX, F_VALUE,
+    },
+    IndexKey, IterateParams, Serialize, ValueKey, U32_LEN,
+};
+use trc::AddContext;
+use utils::BlobHash;
+
+use crate::{email::metadata::MessageMetadata, JMAP};
+
+pub mod get;
+pub mod restore;
+
+// Each deleted message is stored as a document of the DeletedEmail collection,
+// indexed by its deletion and expiration times.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DeletedEmail {
+    #[serde(skip)]
+    pub id: u32,
+    pub blob_hash: BlobHash,
+    pub size: u32,
+    pub received_at: u64,
+    pub deleted_at: u64,
+    pub expires_at: u64,
+    pub mailbox_ids: Vec<u32>,
+    pub keywords: Vec<String>,
+    pub subject: String,
+    pub from: String,
+    pub preview: String,
+    pub counts_quota: bool,
+}
+
+impl JMAP {
+    pub async fn deleted_email_build(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        mailbox_ids: Vec<u32>,
+    ) -> trc::Result<Option<DeletedEmail>> {
+        let metadata = if let Some(metadata) = self
+            .core
+            .storage
+            .data
+            .get_value::<Bincode<MessageMetadata>>(ValueKey {
+                account_id,
+                collection: Collection::Email.into(),
+                document_id,
+                class: ValueClass::Property(Property::BodyStructure.into()),
+            })
+            .await
+            .caused_by(trc::location!())?
+        {
+            metadata.inner
+        } else {
+            return Ok(None);
+        };
+        let keywords = self
+            .get_property::<Vec<Keyword>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::Keywords,
+            )
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default();
+
+        let mut subject = String::new();
+        let mut from = String::new();
+        for header in metadata.contents.root_part().headers.iter().rev() {
+            match header.name {
+                HeaderName::Subject if subject.is_empty() => {
+                    if let Some(text) = header.value.as_text() {
+                        subject = text.to_string();
+                    }
+                }
+                HeaderName::From if from.is_empty() => {
+                    if let Some(addr) = header
+                        .value
+                        .as_address()
+                        .and_then(|addr| addr.first())
+                        .and_then(|addr| addr.address())
+                    {
+                        from = addr.to_string();
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(Some(DeletedEmail {
+            id: 0,
+            blob_hash: metadata.blob_hash,
+            size: metadata.size as u32,
+            received_at: metadata.received_at,
+            deleted_at: 0,
+            expires_at: 0,
+            mailbox_ids,
+            keywords: keywords.into_iter().map(|k| k.to_string()).collect(),
+            subject,
+            from,
+            preview: metadata.preview,
+            counts_quota: false,
+        }))
+    }
+
+    pub async fn recycle_bin_add(
+        &self,
+        account_id: u32,
+        emails: Vec<DeletedEmail>,
+    ) -> trc::Result<()> {
+        let retention = if let Some(retention) = self.core.jmap.mail_recycle_retention {
+            retention
+        } else {
+            return Ok(());
+        };
+
+        let deleted_at = now();
+        let expires_at = deleted_at + retention.as_secs();
+        let counts_quota = self.core.jmap.mail_recycle_count_quota;
+        let mut batch = BatchBuilder::new();
+        let mut quota = 0;
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::DeletedEmail);
+
+        // Keep the blobs around until the entries expire
+        for mut email in emails {
+            email.deleted_at = deleted_at;
+            email.expires_at = expires_at;
+            email.counts_quota = counts_quota;
+            if counts_quota {
+                quota += email.size as i64;
+            }
+            batch
+                .set(
+                    BlobOp::Reserve {
+                        hash: email.blob_hash.clone(),
+                        until: expires_at,
+                    },
+                    0u32.serialize(),
+                )
+                .create_document()
+                .value(Property::DeletedAt, deleted_at, F_INDEX)
+                .value(Property::ExpiresAt, expires_at, F_INDEX)
+                .value(Property::Value, Bincode::new(email), F_VALUE);
+        }
+        if quota != 0 {
+            self.recycle_bin_quota(&mut batch, account_id, quota)
+                .await?;
+        }
+        self.write_batch(batch).await.caused_by(trc::location!())?;
+
+        // Discard the oldest entries
+        let num_entries = self
+            .get_document_ids(account_id, Collection::DeletedEmail)
+            .await?
+            .map_or(0, |ids| ids.len() as usize);
+        if num_entries > self.core.jmap.mail_recycle_max_entries {
+            let discard_ids = self
+                .recycle_bin_ids(
+                    account_id,
+                    Property::DeletedAt,
+                    u64::MAX,
+                    true,
+                    num_entries - self.core.jmap.mail_recycle_max_entries,
+                )
+                .await?;
+            for document_id in discard_ids {
+                self.recycle_bin_remove(account_id, document_id, true)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Adds an entry that was removed from the recycle bin back, keeping its
+    // original expiration time.
+    pub(crate) async fn recycle_bin_insert(
+        &self,
+        account_id: u32,
+        email: DeletedEmail,
+    ) -> trc::Result<()> {
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::DeletedEmail);
+        if email.counts_quota {
+            self.recycle_bin_quota(&mut batch, account_id, email.size as i64)
+                .await?;
+        }
+        batch
+            .create_document()
+            .value(Property::DeletedAt, email.deleted_at, F_INDEX)
+            .value(Property::ExpiresAt, email.expires_at, F_INDEX)
+            .value(Property::Value, Bincode::new(email), F_VALUE);
+        self.write_batch(batch)
+            .await
+            .caused_by(trc::location!())
+            .map(|_| ())
+    }
+
+    pub async fn recycle_bin_purge(&self, account_id: u32) -> trc::Result<()> {
+        for document_id in self
+            .recycle_bin_ids(account_id, Property::ExpiresAt, now(), true, usize::MAX)
+            .await?
+        {
+            self.recycle_bin_remove(account_id, document_id, true)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn recycle_bin_get(
+        &self,
+        account_id: u32,
+        document_id: u32,
+    ) -> trc::Result<Option<HashedValue<Bincode<DeletedEmail>>>> {
+        self.get_property::<HashedValue<Bincode<DeletedEmail>>>(
+            account_id,
+            Collection::DeletedEmail,
+            document_id,
+            Property::Value,
+        )
+        .await
+        .caused_by(trc::location!())
+        .map(|email| {
+            email.map(|mut email| {
+                email.inner.inner.id = document_id;
+                email
+            })
+        })
+    }
+
+    // Returns up to `limit` entries ordered by the given time index, only
+    // including entries with a time before `until`.
+    pub(crate) async fn recycle_bin_ids(
+        &self,
+        account_id: u32,
+        property: Property,
+        until: u64,
+        ascending: bool,
+        limit: usize,
+    ) -> trc::Result<Vec<u32>> {
+        let mut document_ids = Vec::new();
+        if limit == 0 {
+            return Ok(document_ids);
+        }
+        self.core
+            .storage
+            .data
+            .iterate(
+                IterateParams::new(
+                    IndexKey {
+                        account_id,
+                        collection: Collection::DeletedEmail.into(),
+                        document_id: 0,
+                        field: property.clone().into(),
+                        key: 0u64.serialize(),
+                    },
+                    IndexKey {
+                        account_id,
+                        collection: Collection::DeletedEmail.into(),
+                        document_id: u32::MAX,
+                        field: property.into(),
+                        key: until.serialize(),
+                    },
+                )
+                .set_ascending(ascending)
+                .no_values(),
+                |key, _| {
+                    document_ids.push(key.deserialize_be_u32(key.len() - U32_LEN)?);
+                    Ok(document_ids.len() < limit)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok(document_ids)
+    }
+
+    // Removes an entry from the recycle bin, returning None if it was removed by
+    // a concurrent request.
+    pub(crate) async fn recycle_bin_remove(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        release_reservation: bool,
+    ) -> trc::Result<Option<DeletedEmail>> {
+        let email = if let Some(email) = self.recycle_bin_get(account_id, document_id).await? {
+            email
+        } else {
+            return Ok(None);
+        };
+
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::DeletedEmail)
+            .delete_document(document_id)
+            .assert_value(Property::Value, &email)
+            .value(Property::Value, (), F_VALUE | F_CLEAR)
+            .value(
+                Property::DeletedAt,
+                email.inner.inner.deleted_at,
+                F_INDEX | F_CLEAR,
+            )
+            .value(
+                Property::ExpiresAt,
+                email.inner.inner.expires_at,
+                F_INDEX | F_CLEAR,
+            );
+        if release_reservation {
+            batch.clear(BlobOp::Reserve {
+                hash: email.inner.inner.blob_hash.clone(),
+                until: email.inner.inner.expires_at,
+            });
+        }
+        if email.inner.inner.counts_quota {
+            self.recycle_bin_quota(&mut batch, account_id, -(email.inner.inner.size as i64))
+                .await?;
+        }
+
+        match self.write_batch(batch).await {
+            Ok(_) => Ok(Some(email.inner.inner)),
+            Err(err) if err.is_assertion_failure() => Ok(None),
+            Err(err) => Err(err.caused_by(trc::location!())),
+        }
+    }
+
+    // Messages in the recycle bin can count towards the account quota, in which case
+    // their size is charged when they are deleted and released once they expire or
+    // are restored.
+    pub(crate) async fn recycle_bin_quota(
+        &self,
+        batch: &mut BatchBuilder,
+        account_id: u32,
+        quota: i64,
+    ) -> trc::Result<()> {
+        batch.add(DirectoryClass::UsedQuota(account_id), quota);
+        if let Some(tenant) = self
+            .core
+            .get_cached_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?
+            .tenant
+        {
+            batch.add(DirectoryClass::UsedQuota(tenant.id), quota);
+        }
+
+        Ok(())
+    }
+}
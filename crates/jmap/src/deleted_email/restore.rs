@@ -0,0 +1,164 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::restore::{RestoreRequest, RestoreResponse},
+    types::{
+        collection::Collection,
+        keyword::Keyword,
+        state::{State, StateChange},
+        type_state::DataType,
+    },
+};
+use mail_parser::MessageParser;
+use store::write::{now, BatchBuilder, BlobOp};
+use trc::AddContext;
+use utils::map::vec_map::VecMap;
+
+use crate::{
+    api::http::HttpSessionData,
+    email::ingest::{IngestEmail, IngestSource},
+    mailbox::INBOX_ID,
+    JMAP,
+};
+
+impl JMAP {
+    pub async fn deleted_email_restore(
+        &self,
+        request: RestoreRequest,
+        access_token: &AccessToken,
+        session: &HttpSessionData,
+    ) -> trc::Result<RestoreResponse> {
+        let account_id = request.account_id.document_id();
+        let valid_mailbox_ids = self.mailbox_get_or_create(account_id).await?;
+        let resource_token = self.get_resource_token(access_token, account_id).await?;
+        let now = now();
+
+        let mut response = RestoreResponse {
+            account_id: request.account_id,
+            restored: VecMap::with_capacity(request.ids.len()),
+            not_restored: VecMap::new(),
+        };
+
+        for id in request.ids {
+            // Remove the entry before restoring it, so that concurrent requests
+            // do not restore the same message twice
+            let email = match self
+                .recycle_bin_get(account_id, id.document_id())
+                .await?
+                .filter(|email| email.inner.inner.expires_at > now)
+            {
+                Some(_) => {
+                    self.recycle_bin_remove(account_id, id.document_id(), false)
+                        .await?
+                }
+                None => None,
+            };
+            let email = if let Some(email) = email {
+                email
+            } else {
+                response.not_restored.append(id, SetError::not_found());
+                continue;
+            };
+
+            // Fetch the original message
+            let raw_message = if let Some(raw_message) = self
+                .get_blob(&email.blob_hash, 0..usize::MAX)
+                .await
+                .caused_by(trc::location!())?
+            {
+                raw_message
+            } else {
+                response.not_restored.append(id, SetError::blob_not_found());
+                continue;
+            };
+
+            // Restore the message to the mailboxes that still exist
+            let mut mailbox_ids = email
+                .mailbox_ids
+                .iter()
+                .copied()
+                .filter(|mailbox_id| valid_mailbox_ids.contains(*mailbox_id))
+                .collect::<Vec<_>>();
+            if mailbox_ids.is_empty() {
+                mailbox_ids.push(INBOX_ID);
+            }
+
+            match self
+                .email_ingest(IngestEmail {
+                    raw_message: &raw_message,
+                    message: MessageParser::new().parse(&raw_message),
+                    resource: resource_token.clone(),
+                    mailbox_ids,
+                    keywords: email
+                        .keywords
+                        .iter()
+                        .map(|keyword| Keyword::from(keyword.clone()))
+                        .collect(),
+                    received_at: email.received_at.into(),
+                    source: IngestSource::Jmap,
+                    encrypt: self.core.jmap.encrypt && self.core.jmap.encrypt_append,
+                    session_id: session.session_id,
+                })
+                .await
+            {
+                Ok(ingested) => {
+                    let mut batch = BatchBuilder::new();
+                    batch.with_account_id(account_id).clear(BlobOp::Reserve {
+                        hash: email.blob_hash,
+                        until: email.expires_at,
+                    });
+                    self.write_batch(batch).await?;
+                    response.restored.append(id, ingested.into());
+                }
+                Err(mut err) => {
+                    // Put the message back in the recycle bin
+                    self.recycle_bin_insert(account_id, email).await?;
+
+                    match err.as_ref() {
+                        trc::EventType::Limit(trc::LimitEvent::Quota) => {
+                            response.not_restored.append(
+                                id,
+                                SetError::new(SetErrorType::OverQuota)
+                                    .with_description("You have exceeded your disk quota."),
+                            );
+                        }
+                        trc::EventType::MessageIngest(trc::MessageIngestEvent::Error) => {
+                            response.not_restored.append(
+                                id,
+                                SetError::new(SetErrorType::InvalidEmail).with_description(
+                                    err.take_value(trc::Key::Reason)
+                                        .and_then(|v| v.into_string())
+                                        .unwrap(),
+                                ),
+                            );
+                        }
+                        _ => {
+                            return Err(err);
+                        }
+                    }
+                }
+            }
+        }
+
+        if !response.restored.is_empty() {
+            // Broadcast changes
+            if let State::Exact(change_id) = self.get_state(account_id, Collection::Email).await? {
+                self.broadcast_state_change(
+                    StateChange::new(account_id)
+                        .with_change(DataType::Email, change_id)
+                        .with_change(DataType::Mailbox, change_id)
+                        .with_change(DataType::Thread, change_id),
+                )
+                .await;
+            }
+        }
+
+        Ok(response)
+    }
+}
@@ -437,6 +437,11 @@ impl JMAP {
         // Request FTS index
         self.inner.request_fts_index();
 
+        // Notify clients if the quota warning threshold was crossed
+        self.notify_quota_threshold(resource_token, email.size as u64, change_id)
+            .await
+            .caused_by(trc::location!())?;
+
         // Update response
         email.id = Id::from_parts(thread_id, document_id);
         email.change_id = change_id;
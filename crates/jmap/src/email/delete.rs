@@ -14,16 +14,16 @@ use store::{
     ahash::AHashMap,
     roaring::RoaringBitmap,
     write::{
-        log::ChangeLogBuilder, BatchBuilder, Bincode, BitmapClass, MaybeDynamicId, TagValue,
-        ValueClass, F_BITMAP, F_CLEAR, F_VALUE,
+        key::DeserializeBigEndian, log::ChangeLogBuilder, now, BatchBuilder, Bincode, BitmapClass,
+        MaybeDynamicId, TagValue, ValueClass, F_BITMAP, F_CLEAR, F_INDEX, F_VALUE,
     },
-    BitmapKey, IterateParams, ValueKey, U32_LEN,
+    BitmapKey, IndexKey, IterateParams, Serialize, ValueKey, U32_LEN, U64_LEN,
 };
 use trc::{AddContext, StoreEvent};
 use utils::codec::leb128::Leb128Reader;
 
 use crate::{
-    mailbox::{UidMailbox, JUNK_ID, TOMBSTONE_ID, TRASH_ID},
+    mailbox::{UidMailbox, INBOX_ID, JUNK_ID, TOMBSTONE_ID, TRASH_ID},
     JMAP,
 };
 
@@ -117,6 +117,7 @@ impl JMAP {
             .caused_by(trc::location!())?;
 
         // Tombstone message and untag it from the mailboxes
+        let mut recycled = Vec::new();
         let mut batch = BatchBuilder::new();
         batch
             .with_account_id(account_id)
@@ -125,6 +126,24 @@ impl JMAP {
         for (document_id, delete_properties) in delete_properties {
             batch.update_document(document_id);
 
+            // Keep a copy of the message in the recycle bin
+            if self.core.jmap.mail_recycle_retention.is_some() {
+                if let Some(email) = self
+                    .deleted_email_build(
+                        account_id,
+                        document_id,
+                        delete_properties
+                            .mailboxes
+                            .iter()
+                            .map(|mailbox| mailbox.mailbox_id)
+                            .collect(),
+                    )
+                    .await?
+                {
+                    recycled.push(email);
+                }
+            }
+
             if !delete_properties.mailboxes.is_empty() {
                 for mailbox_id in &delete_properties.mailboxes {
                     debug_assert!(mailbox_id.uid != 0);
@@ -164,7 +183,7 @@ impl JMAP {
                     CausedBy = trc::location!(),
                 );
             }
-            batch.tag(
+            batch.tag(Property::AwaitingReply, (), F_CLEAR).tag(
                 Property::MailboxIds,
                 TagValue::Id(MaybeDynamicId::Static(TOMBSTONE_ID)),
                 0,
@@ -205,6 +224,15 @@ impl JMAP {
                 .caused_by(trc::location!())?;
         }
 
+        // Add destroyed messages to the recycle bin
+        if !recycled.is_empty() {
+            if let Err(err) = self.recycle_bin_add(account_id, recycled).await {
+                trc::error!(err
+                    .details("Failed to add messages to the recycle bin.")
+                    .account_id(account_id));
+            }
+        }
+
         Ok((changes, document_ids))
     }
 
@@ -223,6 +251,11 @@ impl JMAP {
     }
 
     pub async fn purge_account(&self, account_id: u32) {
+        // Accounts in maintenance mode are purged once it is over
+        if self.core.storage.maintenance.is_read_only(account_id) {
+            return;
+        }
+
         // Lock account
         match self
             .core
@@ -262,6 +295,15 @@ impl JMAP {
             }
         }
 
+        // Expire messages retrieved over POP3
+        if let Some(period) = self.core.pop3_expire(Some(account_id), 0).await {
+            if let Err(err) = self.emails_pop3_expire(account_id, period).await {
+                trc::error!(err
+                    .details("Failed to expire POP3 messages.")
+                    .account_id(account_id));
+            }
+        }
+
         // Purge tombstoned messages
         if let Err(err) = self.emails_purge_tombstoned(account_id).await {
             trc::error!(err
@@ -269,6 +311,13 @@ impl JMAP {
                 .account_id(account_id));
         }
 
+        // Purge expired recycle bin entries
+        if let Err(err) = self.recycle_bin_purge(account_id).await {
+            trc::error!(err
+                .details("Failed to purge recycle bin.")
+                .account_id(account_id));
+        }
+
         // Purge changelogs
         if let Some(history) = self.core.jmap.changes_max_history {
             if let Err(err) = self.delete_changes(account_id, history).await {
@@ -364,6 +413,127 @@ impl JMAP {
         Ok(())
     }
 
+    pub async fn emails_pop3_expire(&self, account_id: u32, period: Duration) -> trc::Result<()> {
+        // Find messages first retrieved before the expiration period
+        let expire_before = now().saturating_sub(period.as_secs());
+        let mut retrieved = Vec::new();
+        self.core
+            .storage
+            .data
+            .iterate(
+                IterateParams::new(
+                    IndexKey {
+                        account_id,
+                        collection: Collection::Email.into(),
+                        document_id: 0,
+                        field: Property::RetrievedAt.into(),
+                        key: 0u64.serialize(),
+                    },
+                    IndexKey {
+                        account_id,
+                        collection: Collection::Email.into(),
+                        document_id: u32::MAX,
+                        field: Property::RetrievedAt.into(),
+                        key: expire_before.serialize(),
+                    },
+                )
+                .no_values(),
+                |key, _| {
+                    retrieved.push((
+                        key.deserialize_be_u32(key.len() - U32_LEN)?,
+                        key.deserialize_be_u64(key.len() - U32_LEN - U64_LEN)?,
+                    ));
+
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        if retrieved.is_empty() {
+            return Ok(());
+        }
+
+        // Only messages that are still in the Inbox are expired, the retrieval
+        // time of messages that were moved or deleted is removed.
+        let inbox_ids = self
+            .get_tag(
+                account_id,
+                Collection::Email,
+                Property::MailboxIds,
+                TagValue::Id(INBOX_ID),
+            )
+            .await?
+            .unwrap_or_default();
+        let mut destroy_ids = RoaringBitmap::new();
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Email);
+        for (document_id, retrieved_at) in retrieved {
+            // Document ids are reused, so the index entry is only trusted when it
+            // matches the retrieval time stored for the current message.
+            let stored = self
+                .get_property::<u64>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::RetrievedAt,
+                )
+                .await?;
+            batch.update_document(document_id);
+            if stored == Some(retrieved_at) {
+                if inbox_ids.contains(document_id) {
+                    destroy_ids.insert(document_id);
+                }
+                batch
+                    .assert_value(Property::RetrievedAt, retrieved_at)
+                    .value(
+                        Property::RetrievedAt,
+                        retrieved_at,
+                        F_VALUE | F_INDEX | F_CLEAR,
+                    );
+            } else {
+                batch.value(Property::RetrievedAt, retrieved_at, F_INDEX | F_CLEAR);
+            }
+        }
+        match self.write_batch(batch).await {
+            Ok(_) => {}
+            Err(err) if err.is_assertion_failure() => {
+                // Messages were retrieved again, expiration is retried on the next run
+                return Ok(());
+            }
+            Err(err) => return Err(err.caused_by(trc::location!())),
+        }
+
+        if destroy_ids.is_empty() {
+            return Ok(());
+        }
+
+        trc::event!(
+            Purge(trc::PurgeEvent::Pop3Expire),
+            AccountId = account_id,
+            Total = destroy_ids.len(),
+        );
+
+        // Tombstone messages
+        let (changes, _) = self.emails_tombstone(account_id, destroy_ids).await?;
+
+        // Write and broadcast changes
+        if !changes.is_empty() {
+            let change_id = self.commit_changes(account_id, changes).await?;
+            self.broadcast_state_change(
+                StateChange::new(account_id)
+                    .with_change(DataType::Email, change_id)
+                    .with_change(DataType::Mailbox, change_id)
+                    .with_change(DataType::Thread, change_id),
+            )
+            .await;
+        }
+
+        Ok(())
+    }
+
     pub async fn emails_purge_tombstoned(&self, account_id: u32) -> trc::Result<()> {
         // Obtain tombstoned messages
         let tombstoned_ids = self
@@ -409,7 +579,7 @@ impl JMAP {
             .map(|t| t.id);
 
         // Delete messages
-        for document_id in tombstoned_ids {
+        for document_id in &tombstoned_ids {
             let mut batch = BatchBuilder::new();
             batch
                 .with_account_id(account_id)
@@ -422,6 +592,27 @@ impl JMAP {
                     F_CLEAR,
                 );
 
+            // Remove the POP3 retrieval time, so that the expire policy does not
+            // apply to a new message reusing this document id
+            if let Some(retrieved_at) = self
+                .core
+                .storage
+                .data
+                .get_value::<u64>(ValueKey {
+                    account_id,
+                    collection: Collection::Email.into(),
+                    document_id,
+                    class: ValueClass::Property(Property::RetrievedAt.into()),
+                })
+                .await?
+            {
+                batch.value(
+                    Property::RetrievedAt,
+                    retrieved_at,
+                    F_VALUE | F_INDEX | F_CLEAR,
+                );
+            }
+
             // Remove keywords
             if let Some(keywords) = self
                 .core
@@ -490,7 +681,10 @@ impl JMAP {
             }
         }
 
-        Ok(())
+        // Remove activity entries of purged messages
+        self.activity_purge(account_id, &tombstoned_ids)
+            .await
+            .caused_by(trc::location!())
     }
 }
 
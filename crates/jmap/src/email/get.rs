@@ -349,6 +349,21 @@ impl JMAP {
                                 .headers_to_value(&raw_message),
                         );
                     }
+                    Property::SpamReport => {
+                        email.append(
+                            Property::SpamReport,
+                            self.core
+                                .jmap
+                                .spam_report_header
+                                .as_ref()
+                                .map(|header_name| {
+                                    metadata.contents.parts[0]
+                                        .headers
+                                        .spam_report_to_value(header_name, &raw_message)
+                                })
+                                .unwrap_or_default(),
+                        );
+                    }
                     Property::TextBody | Property::HtmlBody | Property::Attachments => {
                         let list = match property {
                             Property::TextBody => &metadata.contents.text_body,
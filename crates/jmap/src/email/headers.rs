@@ -33,6 +33,7 @@ pub trait IntoForm {
 pub trait HeaderToValue {
     fn header_to_value(&self, property: &Property, raw_message: &[u8]) -> Value;
     fn headers_to_value(&self, raw_message: &[u8]) -> Value;
+    fn spam_report_to_value(&self, header_name: &HeaderName, raw_message: &[u8]) -> Value;
 }
 
 pub trait ValueToHeader<'x> {
@@ -127,6 +128,39 @@ impl HeaderToValue for Vec<Header<'_>> {
         }
         headers.into()
     }
+
+    fn spam_report_to_value(&self, header_name: &HeaderName, raw_message: &[u8]) -> Value {
+        // The spam filter prepends its report, so the first instance is the trusted one
+        let header_name = header_name.as_str();
+        let Some(report) = self
+            .iter()
+            .find(|header| header.name.as_str().eq_ignore_ascii_case(header_name))
+            .and_then(|header| raw_message.get(header.offset_start..header.offset_end))
+        else {
+            return Value::Null;
+        };
+
+        let mut result = Object::with_capacity(3);
+        let mut symbols = Object::with_capacity(8);
+        for item in String::from_utf8_lossy(report).split(';') {
+            if let Some((name, value)) = item.split_once('=') {
+                let name = name.trim();
+                let value = value.trim();
+                match name {
+                    "score" | "threshold" => {
+                        result.append(Property::_T(name.to_string()), value.to_string());
+                    }
+                    _ if !name.is_empty() => {
+                        symbols.append(Property::_T(name.to_string()), value.to_string());
+                    }
+                    _ => (),
+                }
+            }
+        }
+        result.append(Property::_T("symbols".to_string()), symbols);
+
+        Value::Object(result)
+    }
 }
 
 impl IntoForm for HeaderValue<'_> {
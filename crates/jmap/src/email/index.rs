@@ -13,7 +13,7 @@ use mail_parser::{
     Addr, Address, GetHeader, Group, Header, HeaderName, HeaderValue, Message, MessagePart,
     PartType,
 };
-use nlp::language::Language;
+use nlp::{language::Language, tokenizers::word::WordTokenizer};
 use store::{
     backend::MAX_TOKEN_LENGTH,
     fts::{index::FtsDocument, Field},
@@ -30,6 +30,7 @@ pub const MAX_ID_LENGTH: usize = 100;
 pub const MAX_SORT_FIELD_LENGTH: usize = 255;
 pub const MAX_STORED_FIELD_LENGTH: usize = 512;
 pub const PREVIEW_LENGTH: usize = 256;
+pub const MAX_HEADER_TOKENS: usize = 64;
 
 #[derive(Debug)]
 pub struct SortedAddressBuilder {
@@ -285,7 +286,22 @@ impl<'x> IndexMessageText<'x> for FtsDocument<'x, HeaderName<'x>> {
                 language = part_language;
 
                 for header in part.headers.iter().rev() {
-                    if matches!(header.name, HeaderName::Other(_)) {
+                    if let HeaderName::Other(name) = &header.name {
+                        // Index arbitrary headers as "name:token" keywords
+                        let name = name.to_ascii_lowercase();
+                        if !name.is_empty() && name.len() < MAX_TOKEN_LENGTH {
+                            header.value.visit_text(|text| {
+                                for token in WordTokenizer::new(text, MAX_TOKEN_LENGTH)
+                                    .take(MAX_HEADER_TOKENS)
+                                {
+                                    self.index_keyword(
+                                        Field::Header(HeaderName::Other(name.clone().into())),
+                                        format!("{name}:{}", token.word),
+                                    );
+                                }
+                            });
+                            self.index_keyword(Field::Keyword, name);
+                        }
                         continue;
                     }
                     // Index hasHeader property
@@ -6,10 +6,12 @@
 
 use std::{
     borrow::Cow,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
 use common::auth::ResourceToken;
+use directory::{backend::internal::PrincipalField, QueryBy};
 use jmap_proto::{
     object::Object,
     types::{
@@ -25,6 +27,7 @@ use rand::Rng;
 use store::{
     ahash::AHashSet,
     query::Filter,
+    roaring::RoaringBitmap,
     write::{
         log::{ChangeLogBuilder, Changes, LogInsert},
         now, AssignedIds, BatchBuilder, BitmapClass, FtsQueueClass, MaybeDynamicId,
@@ -36,7 +39,7 @@ use trc::{AddContext, MessageIngestEvent};
 use utils::map::vec_map::VecMap;
 
 use crate::{
-    email::index::{IndexMessage, VisitValues, MAX_ID_LENGTH},
+    email::index::{AddressElement, IndexMessage, VisitValues, MAX_ID_LENGTH},
     mailbox::{UidMailbox, INBOX_ID, JUNK_ID},
     JMAP,
 };
@@ -53,6 +56,7 @@ pub struct IngestedEmail {
     pub blob_id: BlobId,
     pub size: usize,
     pub imap_uids: Vec<u32>,
+    pub mailbox_ids: Vec<u32>,
 }
 
 pub struct IngestEmail<'x> {
@@ -115,6 +119,8 @@ impl JMAP {
 
         // Obtain message references and thread name
         let mut message_id = String::new();
+        let mut from_addresses = Vec::new();
+        let mut reply_ids = Vec::new();
         let thread_id = {
             let mut references = Vec::with_capacity(5);
             let mut subject = "";
@@ -131,9 +137,20 @@ impl JMAP {
                     HeaderName::InReplyTo
                     | HeaderName::References
                     | HeaderName::ResentMessageId => {
+                        let is_reply = header.name != HeaderName::ResentMessageId;
                         header.value.visit_text(|id| {
                             if !id.is_empty() && id.len() < MAX_ID_LENGTH {
                                 references.push(id);
+                                if is_reply {
+                                    reply_ids.push(id.to_string());
+                                }
+                            }
+                        });
+                    }
+                    HeaderName::From => {
+                        header.value.visit_addresses(|element, value| {
+                            if element == AddressElement::Address {
+                                from_addresses.push(value.trim().to_lowercase());
                             }
                         });
                     }
@@ -186,6 +203,7 @@ impl JMAP {
                     change_id: u64::MAX,
                     blob_id: BlobId::default(),
                     imap_uids: Vec::new(),
+                    mailbox_ids: Vec::new(),
                     size: 0,
                 });
             }
@@ -198,6 +216,49 @@ impl JMAP {
             }
         };
 
+        // Track replies to sent messages
+        let is_sent = params.source != IngestSource::Smtp
+            && !is_spam
+            && !params.keywords.contains(&Keyword::Draft)
+            && !from_addresses.is_empty()
+            && self
+                .core
+                .storage
+                .directory
+                .query(QueryBy::Id(account_id), false)
+                .await
+                .caused_by(trc::location!())?
+                .map_or(false, |principal| {
+                    from_addresses
+                        .iter()
+                        .any(|addr| principal.has_str_value(PrincipalField::Emails, addr))
+                });
+        let replied_ids = match thread_id {
+            Some(thread_id)
+                if is_sent
+                    || (!is_spam
+                        && !reply_ids.is_empty()
+                        && !params.keywords.contains(&Keyword::Draft)) =>
+            {
+                // A reply or a follow-up supersedes the messages awaiting a reply in the thread
+                self.core
+                    .storage
+                    .data
+                    .filter(
+                        account_id,
+                        Collection::Email,
+                        vec![
+                            Filter::is_in_bitmap(Property::ThreadId, thread_id),
+                            Filter::is_in_bitmap(Property::AwaitingReply, ()),
+                        ],
+                    )
+                    .await
+                    .caused_by(trc::location!())?
+                    .results
+            }
+            _ => RoaringBitmap::new(),
+        };
+
         // Encrypt message
         if params.encrypt && !message.is_encrypted() {
             if let Some(encrypt_params) = self
@@ -287,6 +348,14 @@ impl JMAP {
         } else {
             batch.create_document().log(LogInsert());
         }
+        if !replied_ids.is_empty() {
+            batch.with_collection(Collection::Email);
+            for document_id in replied_ids {
+                batch
+                    .update_document(document_id)
+                    .tag(Property::AwaitingReply, (), F_CLEAR);
+            }
+        }
 
         // Build write batch
         let mailbox_ids_event = mailbox_ids
@@ -321,6 +390,9 @@ impl JMAP {
                 }),
                 0u64.serialize(),
             );
+        if is_sent {
+            batch.tag(Property::AwaitingReply, (), 0);
+        }
 
         // Insert and obtain ids
         let ids = self
@@ -340,6 +412,11 @@ impl JMAP {
         // Request FTS index
         self.inner.request_fts_index();
 
+        // Notify clients if the quota warning threshold was crossed
+        self.notify_quota_threshold(&params.resource, raw_message_len, change_id)
+            .await
+            .caused_by(trc::location!())?;
+
         trc::event!(
             MessageIngest(match params.source {
                 IngestSource::Smtp =>
@@ -376,6 +453,7 @@ impl JMAP {
             },
             size: raw_message_len as usize,
             imap_uids,
+            mailbox_ids: params.mailbox_ids,
         })
     }
 
@@ -558,7 +636,7 @@ impl SerializeWithId for LogEmailInsert {
 
 impl From<LogEmailInsert> for MaybeDynamicValue {
     fn from(log: LogEmailInsert) -> Self {
-        MaybeDynamicValue::Dynamic(Box::new(log))
+        MaybeDynamicValue::Dynamic(Arc::new(log))
     }
 }
 
@@ -172,6 +172,21 @@ impl JMAP {
                             message.parts[0].headers.headers_to_value(&raw_message),
                         );
                     }
+                    Property::SpamReport => {
+                        email.append(
+                            Property::SpamReport,
+                            self.core
+                                .jmap
+                                .spam_report_header
+                                .as_ref()
+                                .map(|header_name| {
+                                    message.parts[0]
+                                        .headers
+                                        .spam_report_to_value(header_name, &raw_message)
+                                })
+                                .unwrap_or_default(),
+                        );
+                    }
                     Property::TextBody | Property::HtmlBody | Property::Attachments => {
                         let list = match property {
                             Property::TextBody => &message.text_body,
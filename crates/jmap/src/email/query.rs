@@ -11,12 +11,13 @@ use jmap_proto::{
     types::{acl::Acl, collection::Collection, keyword::Keyword, property::Property},
 };
 use mail_parser::HeaderName;
-use nlp::language::Language;
+use nlp::{language::Language, tokenizers::word::WordTokenizer};
 use store::{
+    backend::MAX_TOKEN_LENGTH,
     fts::{Field, FilterGroup, FtsFilter, IntoFilterGroup},
     query::{self},
     roaring::RoaringBitmap,
-    write::ValueClass,
+    write::{now, ValueClass},
     ValueKey,
 };
 
@@ -116,11 +117,35 @@ impl JMAP {
 
                                 match HeaderName::parse(header_name) {
                                     Some(HeaderName::Other(header_name)) => {
-                                        return Err(trc::JmapEvent::InvalidArguments
-                                            .into_err()
-                                            .details(format!(
-                                                "Querying header '{header_name}' is not supported.",
-                                            )));
+                                        let header_name = header_name.to_ascii_lowercase();
+                                        let tokens = header
+                                            .next()
+                                            .map(|header_value| {
+                                                WordTokenizer::new(&header_value, MAX_TOKEN_LENGTH)
+                                                    .map(|token| {
+                                                        format!("{header_name}:{}", token.word)
+                                                    })
+                                                    .collect::<Vec<_>>()
+                                            })
+                                            .unwrap_or_default();
+
+                                        if !tokens.is_empty() {
+                                            fts_filters.push(FtsFilter::And);
+                                            for token in tokens {
+                                                fts_filters.push(FtsFilter::has_keyword(
+                                                    Field::Header(HeaderName::Other(
+                                                        header_name.clone().into(),
+                                                    )),
+                                                    token,
+                                                ));
+                                            }
+                                            fts_filters.push(FtsFilter::End);
+                                        } else {
+                                            fts_filters.push(FtsFilter::has_keyword(
+                                                Field::Keyword,
+                                                header_name,
+                                            ));
+                                        }
                                     }
                                     Some(header_name) => {
                                         if let Some(header_value) = header.next() {
@@ -197,6 +222,15 @@ impl JMAP {
                         Filter::MaxSize(size) => {
                             filters.push(query::Filter::lt(Property::Size, size))
                         }
+                        Filter::AwaitingReply(days) => {
+                            filters.push(query::Filter::And);
+                            filters.push(query::Filter::is_in_bitmap(Property::AwaitingReply, ()));
+                            filters.push(query::Filter::le(
+                                Property::ReceivedAt,
+                                now().saturating_sub(days as u64 * 86400),
+                            ));
+                            filters.push(query::Filter::End);
+                        }
                         Filter::AllInThreadHaveKeyword(keyword) => {
                             filters.push(query::Filter::is_in_set(
                                 self.thread_keywords(account_id, keyword, true).await?,
@@ -14,6 +14,7 @@ use jmap_proto::{
     types::{
         acl::Acl,
         collection::Collection,
+        id::Id,
         keyword::Keyword,
         property::Property,
         state::{State, StateChange},
@@ -41,7 +42,7 @@ use store::{
 };
 use trc::AddContext;
 
-use crate::{api::http::HttpSessionData, mailbox::UidMailbox, JMAP};
+use crate::{activity::ActivityType, api::http::HttpSessionData, mailbox::UidMailbox, JMAP};
 
 use super::{
     headers::{BuildHeader, ValueToHeader},
@@ -87,6 +88,7 @@ impl JMAP {
         };
 
         let will_destroy = request.unwrap_destroy();
+        let mut rate_limited_drafts: Option<RoaringBitmap> = None;
 
         // Obtain quota
         let resource_token = self.get_resource_token(access_token, account_id).await?;
@@ -695,6 +697,27 @@ impl JMAP {
                 continue 'create;
             }
 
+            // Optionally limit how often drafts can be replaced, autosaves are retried by the client
+            if keywords.contains(&Keyword::Draft) && !will_destroy.is_empty() {
+                if rate_limited_drafts.is_none() {
+                    rate_limited_drafts = self
+                        .rate_limited_drafts(account_id, &will_destroy)
+                        .await?
+                        .into();
+                }
+                if rate_limited_drafts
+                    .as_ref()
+                    .is_some_and(|drafts| !drafts.is_empty())
+                {
+                    response.not_created.append(
+                        id,
+                        SetError::new(SetErrorType::RateLimit)
+                            .with_description("Too many draft replacements, try again later."),
+                    );
+                    continue 'create;
+                }
+            }
+
             // In test, sort headers to avoid randomness
             #[cfg(feature = "test_mode")]
             {
@@ -741,6 +764,7 @@ impl JMAP {
 
         // Process updates
         let mut changes = ChangeLogBuilder::new();
+        let mut flagged_ids = Vec::new();
         'update: for (id, object) in request.unwrap_update() {
             // Make sure id won't be destroyed
             if will_destroy.contains(&id) {
@@ -866,6 +890,11 @@ impl JMAP {
                     }
                 }
 
+                // Track newly flagged messages
+                if keywords.added().contains(&Keyword::Flagged) {
+                    flagged_ids.push(id);
+                }
+
                 // Update keywords property
                 keywords.update_batch(&mut batch, Property::Keywords);
 
@@ -996,7 +1025,13 @@ impl JMAP {
             for destroy_id in will_destroy {
                 let document_id = destroy_id.document_id();
 
-                if email_ids.contains(document_id) {
+                if matches!(&rate_limited_drafts, Some(ids) if ids.contains(document_id)) {
+                    response.not_destroyed.append(
+                        destroy_id,
+                        SetError::new(SetErrorType::RateLimit)
+                            .with_description("Too many draft replacements, try again later."),
+                    );
+                } else if email_ids.contains(document_id) {
                     if !matches!(&can_destroy_message_ids, Some(ids) if !ids.contains(document_id))
                     {
                         destroy_ids.insert(document_id);
@@ -1042,6 +1077,15 @@ impl JMAP {
             }
         }
 
+        // Record activity
+        flagged_ids.retain(|id| response.updated.contains_key(id));
+        if let Err(err) = self
+            .activity_log(account_id, ActivityType::Flagged, flagged_ids)
+            .await
+        {
+            trc::error!(err.caused_by(trc::location!()));
+        }
+
         // Update state
         if !changes.is_empty() || !response.created.is_empty() {
             let new_state = if !changes.is_empty() {
@@ -1062,7 +1106,45 @@ impl JMAP {
 
         Ok(response)
     }
+
+    async fn rate_limited_drafts(
+        &self,
+        account_id: u32,
+        destroy_ids: &[Id],
+    ) -> trc::Result<RoaringBitmap> {
+        if let Some(rate) = &self.core.jmap.mail_draft_replace_rate {
+            let mut drafts = self
+                .get_tag(
+                    account_id,
+                    Collection::Email,
+                    Property::Keywords,
+                    Keyword::Draft,
+                )
+                .await?
+                .unwrap_or_default();
+            drafts &= destroy_ids
+                .iter()
+                .map(|id| id.document_id())
+                .collect::<RoaringBitmap>();
+
+            if !drafts.is_empty()
+                && self
+                    .core
+                    .storage
+                    .lookup
+                    .is_rate_allowed(format!("jdraft:{account_id}").as_bytes(), rate, false)
+                    .await
+                    .caused_by(trc::location!())?
+                    .is_some()
+            {
+                return Ok(drafts);
+            }
+        }
+
+        Ok(RoaringBitmap::new())
+    }
 }
+
 pub struct TagManager<
     T: PartialEq + Clone + ToBitmaps + SerializeInto + Serialize + DeserializeFrom + Sync + Send,
 > {
@@ -123,7 +123,15 @@ impl JMAP {
                 .headers
                 .header_value(&HeaderName::Subject)
                 .and_then(|v| v.as_text())
-                .and_then(|v| generate_snippet(v, &terms, language, is_exact))
+                .and_then(|v| {
+                    generate_snippet(
+                        v,
+                        &terms,
+                        language,
+                        is_exact,
+                        self.core.jmap.snippet_context_length,
+                    )
+                })
             {
                 snippet.subject = subject.into();
             }
@@ -158,11 +166,17 @@ impl JMAP {
                     MetadataPartType::Text | MetadataPartType::Html => {
                         let text = match part.decode_contents(&raw_message) {
                             PartType::Text(text) => text,
-                            PartType::Html(html) => html_to_text(&html).into(),
+                            PartType::Html(html) => html_to_snippet_text(&html).into(),
                             _ => unreachable!(),
                         };
 
-                        if let Some(body) = generate_snippet(&text, &terms, language, is_exact) {
+                        if let Some(body) = generate_snippet(
+                            &text,
+                            &terms,
+                            language,
+                            is_exact,
+                            self.core.jmap.snippet_context_length,
+                        ) {
                             snippet.preview = body.into();
                             break;
                         }
@@ -172,13 +186,17 @@ impl JMAP {
                             if let MetadataPartType::Text | MetadataPartType::Html = part.body {
                                 let text = match part.decode_contents(&raw_message) {
                                     PartType::Text(text) => text,
-                                    PartType::Html(html) => html_to_text(&html).into(),
+                                    PartType::Html(html) => html_to_snippet_text(&html).into(),
                                     _ => unreachable!(),
                                 };
 
-                                if let Some(body) =
-                                    generate_snippet(&text, &terms, language, is_exact)
-                                {
+                                if let Some(body) = generate_snippet(
+                                    &text,
+                                    &terms,
+                                    language,
+                                    is_exact,
+                                    self.core.jmap.snippet_context_length,
+                                ) {
                                     snippet.preview = body.into();
                                     break 'outer;
                                 }
@@ -196,3 +214,32 @@ impl JMAP {
         Ok(response)
     }
 }
+
+// Scripts and stylesheets are not part of the visible text
+fn html_to_snippet_text(html: &str) -> String {
+    let html_lower = html.to_ascii_lowercase();
+    let mut visible = String::with_capacity(html.len());
+    let mut pos = 0;
+
+    while let Some((start, tag)) = ["script", "style"]
+        .into_iter()
+        .filter_map(|tag| {
+            html_lower[pos..]
+                .find(&format!("<{tag}"))
+                .map(|start| (pos + start, tag))
+        })
+        .min()
+    {
+        visible.push_str(&html[pos..start]);
+        pos = html_lower[start..]
+            .find(&format!("</{tag}"))
+            .and_then(|end| {
+                let end = start + end;
+                html_lower[end..].find('>').map(|close| end + close + 1)
+            })
+            .unwrap_or(html.len());
+    }
+    visible.push_str(&html[pos..]);
+
+    html_to_text(&visible)
+}
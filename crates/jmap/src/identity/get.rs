@@ -4,22 +4,15 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use directory::{backend::internal::PrincipalField, QueryBy};
 use jmap_proto::{
     method::get::{GetRequest, GetResponse, RequestArguments},
     object::Object,
     types::{collection::Collection, property::Property, value::Value},
 };
-use store::{
-    roaring::RoaringBitmap,
-    write::{BatchBuilder, F_VALUE},
-};
-use trc::AddContext;
+use store::roaring::RoaringBitmap;
 
 use crate::JMAP;
 
-use super::set::sanitize_email;
-
 impl JMAP {
     pub async fn identity_get(
         &self,
@@ -108,69 +101,23 @@ impl JMAP {
     }
 
     pub async fn identity_get_or_create(&self, account_id: u32) -> trc::Result<RoaringBitmap> {
-        let mut identity_ids = self
+        let identity_ids = self
             .get_document_ids(account_id, Collection::Identity)
             .await?
             .unwrap_or_default();
-        if !identity_ids.is_empty() {
+        if !identity_ids.is_empty()
+            || self
+                .get_property::<()>(account_id, Collection::Principal, 0, Property::IdentitySync)
+                .await?
+                .is_some()
+        {
             return Ok(identity_ids);
         }
 
-        // Obtain principal
-        let principal = self
-            .core
-            .storage
-            .directory
-            .query(QueryBy::Id(account_id), false)
+        // Provision identities for accounts that were never synchronized
+        self.identity_sync(account_id).await?;
+        self.get_document_ids(account_id, Collection::Identity)
             .await
-            .caused_by(trc::location!())?
-            .unwrap_or_default();
-        let num_emails = principal.field_len(PrincipalField::Emails);
-        if num_emails == 0 {
-            return Ok(identity_ids);
-        }
-
-        let mut batch = BatchBuilder::new();
-        batch
-            .with_account_id(account_id)
-            .with_collection(Collection::Identity);
-
-        // Create identities
-        let name = principal
-            .description()
-            .unwrap_or(principal.name())
-            .trim()
-            .to_string();
-        let has_many = num_emails > 1;
-        for (idx, email) in principal.iter_str(PrincipalField::Emails).enumerate() {
-            let document_id = idx as u32;
-            let email = sanitize_email(email).unwrap_or_default();
-            if email.is_empty() {
-                continue;
-            }
-            let name = if name.is_empty() {
-                email.clone()
-            } else if has_many {
-                format!("{} <{}>", name, email)
-            } else {
-                name.clone()
-            };
-            batch.create_document_with_id(document_id).value(
-                Property::Value,
-                Object::with_capacity(4)
-                    .with_property(Property::Name, name)
-                    .with_property(Property::Email, email),
-                F_VALUE,
-            );
-            identity_ids.insert(document_id);
-        }
-        self.core
-            .storage
-            .data
-            .write(batch.build())
-            .await
-            .caused_by(trc::location!())?;
-
-        Ok(identity_ids)
+            .map(|ids| ids.unwrap_or_default())
     }
 }
@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use directory::{backend::internal::PrincipalField, QueryBy};
+use directory::QueryBy;
 use jmap_proto::{
     error::set::SetError,
     method::set::{RequestArguments, SetRequest, SetResponse},
@@ -56,14 +56,18 @@ impl JMAP {
 
             // Validate email address
             if let Value::Text(email) = identity.get(&Property::Email) {
-                if !self
+                let principal = self
                     .core
                     .storage
                     .directory
-                    .query(QueryBy::Id(account_id), false)
+                    .query(QueryBy::Id(account_id), true)
+                    .await?
+                    .unwrap_or_default();
+                if !self
+                    .identity_addresses(&principal)
                     .await?
-                    .unwrap_or_default()
-                    .has_str_value(PrincipalField::Emails, email)
+                    .iter()
+                    .any(|address| address == email)
                 {
                     response.not_created.append(
                         id,
@@ -0,0 +1,215 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::{Principal, QueryBy};
+use jmap_proto::{
+    object::Object,
+    types::{collection::Collection, property::Property, value::Value},
+};
+use store::write::{
+    assert::{AssertValue, HashedValue},
+    log::ChangeLogBuilder,
+    BatchBuilder, Bincode, F_CLEAR, F_VALUE,
+};
+use trc::AddContext;
+
+use crate::JMAP;
+
+use super::set::sanitize_email;
+
+const MAX_UPDATE_ATTEMPTS: usize = 10;
+
+impl JMAP {
+    // Keeps identities in sync with the aliases and lists an account holds in the directory.
+    // This runs on login and when the directory changes, the addresses of the last sync
+    // are asserted so that concurrent runs do not create duplicate identities.
+    pub async fn identity_sync(&self, account_id: u32) -> trc::Result<()> {
+        // Identities are synchronized again once maintenance mode is over
+        if self.core.storage.maintenance.is_read_only(account_id) {
+            return Ok(());
+        }
+
+        let principal = if let Some(principal) = self
+            .core
+            .storage
+            .directory
+            .query(QueryBy::Id(account_id), true)
+            .await
+            .caused_by(trc::location!())?
+        {
+            principal
+        } else {
+            return Ok(());
+        };
+        let addresses = self
+            .identity_addresses(&principal)
+            .await
+            .caused_by(trc::location!())?;
+        let name = principal
+            .description()
+            .unwrap_or(principal.name())
+            .trim()
+            .to_string();
+        let has_many = addresses.len() > 1;
+
+        let mut attempts = 0;
+        loop {
+            let assert_value = match self
+                .get_property::<HashedValue<Bincode<Vec<String>>>>(
+                    account_id,
+                    Collection::Principal,
+                    0,
+                    Property::IdentitySync,
+                )
+                .await
+                .caused_by(trc::location!())?
+            {
+                Some(synced) if synced.inner.inner == addresses => return Ok(()),
+                Some(synced) => synced.to_assert_value(),
+                None => AssertValue::None,
+            };
+
+            // Find the identities that were added or removed from the directory
+            let identity_ids = self
+                .get_document_ids(account_id, Collection::Identity)
+                .await?
+                .unwrap_or_default();
+            let mut missing = addresses.clone();
+            let mut deleted = Vec::new();
+            for document_id in &identity_ids {
+                let email = self
+                    .get_property::<Object<Value>>(
+                        account_id,
+                        Collection::Identity,
+                        document_id,
+                        Property::Value,
+                    )
+                    .await?
+                    .and_then(|mut identity| identity.properties.remove(&Property::Email))
+                    .and_then(|email| email.try_unwrap_string());
+                if let Some(email) = email {
+                    if let Some(pos) = missing.iter().position(|address| address == &email) {
+                        missing.swap_remove(pos);
+                    } else if !addresses.contains(&email) {
+                        deleted.push(document_id);
+                    }
+                }
+            }
+
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::Principal)
+                .update_document(0)
+                .assert_value(Property::IdentitySync, assert_value)
+                .value(
+                    Property::IdentitySync,
+                    Bincode::new(addresses.clone()),
+                    F_VALUE,
+                )
+                .with_collection(Collection::Identity);
+            for document_id in &deleted {
+                batch
+                    .delete_document(*document_id)
+                    .value(Property::Value, (), F_VALUE | F_CLEAR);
+            }
+            for (idx, email) in missing.iter().enumerate() {
+                // Identities of new accounts are numbered in directory order
+                if identity_ids.is_empty() {
+                    batch.create_document_with_id(idx as u32);
+                } else {
+                    batch.create_document();
+                }
+                batch.value(
+                    Property::Value,
+                    self.identity_new(&name, email.clone(), has_many),
+                    F_VALUE,
+                );
+            }
+
+            match self.write_batch(batch).await {
+                Ok(assigned_ids) => {
+                    let mut changes = ChangeLogBuilder::new();
+                    for document_id in deleted {
+                        changes.log_delete(Collection::Identity, document_id);
+                    }
+                    if identity_ids.is_empty() {
+                        for idx in 0..missing.len() {
+                            changes.log_insert(Collection::Identity, idx as u32);
+                        }
+                    } else {
+                        for document_id in assigned_ids.document_ids {
+                            changes.log_insert(Collection::Identity, document_id);
+                        }
+                    }
+                    if !changes.is_empty() {
+                        self.commit_changes(account_id, changes).await?;
+                    }
+                    return Ok(());
+                }
+                Err(err) if err.is_assertion_failure() && attempts < MAX_UPDATE_ATTEMPTS => {
+                    attempts += 1;
+                }
+                Err(err) => return Err(err.caused_by(trc::location!())),
+            }
+        }
+    }
+
+    // Synchronizes identities after a directory change, failures are logged but
+    // not returned as the directory has already been updated.
+    pub async fn identity_sync_accounts(&self, account_ids: impl IntoIterator<Item = u32>) {
+        for account_id in account_ids {
+            if let Err(err) = self.identity_sync(account_id).await {
+                trc::error!(err
+                    .account_id(account_id)
+                    .details("Failed to synchronize identities"));
+            }
+        }
+    }
+
+    // Addresses a principal can use as identities, including the lists it is a member of
+    pub async fn identity_addresses(&self, principal: &Principal) -> trc::Result<Vec<String>> {
+        let mut addresses = Vec::new();
+        for email in self
+            .core
+            .storage
+            .directory
+            .sender_addresses(principal)
+            .await?
+        {
+            if let Some(email) = sanitize_email(&email) {
+                if !addresses.contains(&email) {
+                    addresses.push(email);
+                }
+            }
+        }
+
+        Ok(addresses)
+    }
+
+    fn identity_new(&self, name: &str, email: String, has_many: bool) -> Object<Value> {
+        let name = if name.is_empty() {
+            email.clone()
+        } else if has_many {
+            format!("{} <{}>", name, email)
+        } else {
+            name.to_string()
+        };
+        let mut identity = Object::with_capacity(4)
+            .with_property(Property::Name, name)
+            .with_property(Property::Email, email);
+        let template = &self.core.jmap.account_template;
+        for (property, signature) in [
+            (Property::TextSignature, &template.text_signature),
+            (Property::HtmlSignature, &template.html_signature),
+        ] {
+            if let Some(signature) = signature {
+                identity.set(property, signature.clone());
+            }
+        }
+        identity
+    }
+}
@@ -50,24 +50,33 @@ use tokio::sync::{mpsc, Notify};
 use trc::AddContext;
 use utils::{
     config::Config,
-    lru_cache::{LruCache, LruCached},
+    lru_cache::{LruCache, LruCacheRegistry, LruCached},
     map::ttl_dashmap::{TtlDashMap, TtlMap},
     snowflake::SnowflakeIdGenerator,
 };
 
+pub mod activity;
+pub mod address_book;
 pub mod api;
 pub mod auth;
 pub mod blob;
+pub mod calendar;
+pub mod calendar_event;
 pub mod changes;
+pub mod contact_card;
+pub mod deleted_email;
 pub mod email;
 pub mod identity;
 pub mod mailbox;
+pub mod mdn;
 pub mod principal;
 pub mod push;
 pub mod quota;
 pub mod services;
 pub mod sieve;
 pub mod submission;
+pub mod task;
+pub mod task_list;
 pub mod thread;
 pub mod vacation;
 pub mod websocket;
@@ -101,7 +110,8 @@ pub struct Inner {
     pub housekeeper_tx: mpsc::Sender<housekeeper::Event>,
     pub index_tx: Arc<Notify>,
 
-    pub cache_threads: LruCache<u32, Arc<Threads>>,
+    pub cache_threads: Arc<LruCache<u32, Arc<Threads>>>,
+    pub caches: LruCacheRegistry,
 }
 
 impl JMAP {
@@ -136,11 +146,13 @@ impl JMAP {
             state_tx,
             housekeeper_tx,
             index_tx: index_tx.clone(),
-            cache_threads: LruCache::with_capacity(
+            cache_threads: Arc::new(LruCache::with_capacity(
                 config.property("cache.thread.size").unwrap_or(2048),
-            ),
+            )),
+            caches: LruCacheRegistry::default(),
             config_version: 0.into(),
         };
+        inner.caches.register("thread", inner.cache_threads.clone());
 
         // Unpack webadmin
         if let Err(err) = inner.webadmin.unpack(&core.load().storage.blob).await {
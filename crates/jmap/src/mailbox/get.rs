@@ -8,7 +8,10 @@ use common::auth::AccessToken;
 use jmap_proto::{
     method::get::{GetRequest, GetResponse, RequestArguments},
     object::Object,
-    types::{acl::Acl, collection::Collection, keyword::Keyword, property::Property, value::Value},
+    types::{
+        acl::Acl, collection::Collection, keyword::Keyword, property::Property,
+        state::SyncPriority, value::Value,
+    },
 };
 use store::{ahash::AHashSet, query::Filter, roaring::RoaringBitmap};
 use trc::AddContext;
@@ -62,7 +65,10 @@ impl JMAP {
                     | Property::Role
                     | Property::SortOrder
                     | Property::Acl
+                    | Property::ShareWith
                     | Property::MyRights
+                    | Property::SyncPriority
+                    | Property::PreviewOnly
             )
         });
         let mut response = GetResponse {
@@ -113,6 +119,14 @@ impl JMAP {
                         .properties
                         .remove(property)
                         .unwrap_or(Value::UnsignedInt(0)),
+                    Property::SyncPriority => values
+                        .properties
+                        .remove(property)
+                        .unwrap_or_else(|| Value::Text("normal".to_string())),
+                    Property::PreviewOnly => values
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::Bool(false)),
                     Property::ParentId => values
                         .properties
                         .remove(property)
@@ -164,7 +178,7 @@ impl JMAP {
                     Property::MyRights => {
                         if access_token.is_shared(account_id) {
                             let acl = values.effective_acl(access_token);
-                            Object::with_capacity(9)
+                            Object::with_capacity(10)
                                 .with_property(Property::MayReadItems, acl.contains(Acl::ReadItems))
                                 .with_property(Property::MayAddItems, acl.contains(Acl::AddItems))
                                 .with_property(
@@ -183,9 +197,10 @@ impl JMAP {
                                 .with_property(Property::MayRename, acl.contains(Acl::Modify))
                                 .with_property(Property::MayDelete, acl.contains(Acl::Delete))
                                 .with_property(Property::MaySubmit, acl.contains(Acl::Submit))
+                                .with_property(Property::MayShare, acl.contains(Acl::Administer))
                                 .into()
                         } else {
-                            Object::with_capacity(9)
+                            Object::with_capacity(10)
                                 .with_property(Property::MayReadItems, true)
                                 .with_property(Property::MayAddItems, true)
                                 .with_property(Property::MayRemoveItems, true)
@@ -195,6 +210,7 @@ impl JMAP {
                                 .with_property(Property::MayRename, true)
                                 .with_property(Property::MayDelete, true)
                                 .with_property(Property::MaySubmit, true)
+                                .with_property(Property::MayShare, true)
                                 .into()
                         }
                     }
@@ -224,6 +240,16 @@ impl JMAP {
                         )
                         .await
                     }
+                    Property::ShareWith => self.share_with_get(
+                        values
+                            .properties
+                            .get(&Property::Acl)
+                            .and_then(|v| v.as_acl())
+                            .map(|v| &v[..])
+                            .unwrap_or_else(|| &[]),
+                        access_token,
+                        account_id,
+                    ),
 
                     _ => Value::Null,
                 };
@@ -403,6 +429,45 @@ impl JMAP {
             }))
     }
 
+    // Returns the highest sync priority of the mailboxes and whether
+    // all of them are marked as preview-only
+    pub async fn mailbox_sync_hints(
+        &self,
+        account_id: u32,
+        mailbox_ids: &[u32],
+    ) -> trc::Result<(SyncPriority, bool)> {
+        if mailbox_ids.is_empty() {
+            return Ok((SyncPriority::Normal, false));
+        }
+
+        let mut priority = SyncPriority::Low;
+        let mut preview_only = true;
+        for mailbox_id in mailbox_ids {
+            let mailbox = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::Mailbox,
+                    *mailbox_id,
+                    Property::Value,
+                )
+                .await?;
+            let properties = mailbox.as_ref().map(|mailbox| &mailbox.properties);
+            priority = priority.max(
+                properties
+                    .and_then(|properties| properties.get(&Property::SyncPriority))
+                    .and_then(|value| value.as_string())
+                    .and_then(SyncPriority::parse)
+                    .unwrap_or_default(),
+            );
+            preview_only &= matches!(
+                properties.and_then(|properties| properties.get(&Property::PreviewOnly)),
+                Some(Value::Bool(true))
+            );
+        }
+
+        Ok((priority, preview_only))
+    }
+
     pub async fn mailbox_get_by_role(
         &self,
         account_id: u32,
@@ -20,7 +20,7 @@ use jmap_proto::{
         collection::Collection,
         id::Id,
         property::Property,
-        state::StateChange,
+        state::{StateChange, SyncPriority},
         type_state::DataType,
         value::{MaybePatchValue, SetValue, Value},
     },
@@ -125,6 +125,13 @@ impl JMAP {
                             changes.log_insert(Collection::Mailbox, document_id);
                             ctx.mailbox_ids.insert(document_id);
                             ctx.response.created(id, document_id);
+                            if !ctx.is_shared {
+                                self.mailbox_supersede_deleted_subscription(
+                                    account_id,
+                                    document_id,
+                                )
+                                .await?;
+                            }
                         }
                         Err(err) if err.is_assertion_failure() => {
                             ctx.response.not_created.append(
@@ -178,7 +185,8 @@ impl JMAP {
                                 .with_description("You are not allowed to modify this mailbox."),
                         );
                         continue 'update;
-                    } else if object.properties.contains_key(&Property::Acl)
+                    } else if (object.properties.contains_key(&Property::Acl)
+                        || object.properties.contains_key(&Property::ShareWith))
                         && !acl.contains(Acl::Administer)
                     {
                         ctx.response.not_updated.append(
@@ -218,6 +226,13 @@ impl JMAP {
                             match self.core.storage.data.write(batch.build()).await {
                                 Ok(_) => {
                                     changes.log_update(Collection::Mailbox, document_id);
+                                    if !ctx.is_shared {
+                                        self.mailbox_supersede_deleted_subscription(
+                                            account_id,
+                                            document_id,
+                                        )
+                                        .await?;
+                                    }
                                 }
                                 Err(err) if err.is_assertion_failure() => {
                                     ctx.response.not_updated.append(id, SetError::forbidden().with_description(
@@ -449,6 +464,19 @@ impl JMAP {
                 }
             }
 
+            // Keep the subscription state of the deleted mailbox by name
+            let subscription = if !access_token.is_shared(account_id) {
+                let is_subscribed = matches!(
+                    mailbox.inner.get(&Property::IsSubscribed),
+                    Value::List(ids) if ids.contains(&Value::Id(account_id.into()))
+                );
+                self.mailbox_path(account_id, document_id)
+                    .await?
+                    .map(|path| (path, is_subscribed))
+            } else {
+                None
+            };
+
             let mut batch = BatchBuilder::new();
             batch
                 .with_account_id(account_id)
@@ -460,6 +488,21 @@ impl JMAP {
             match self.core.storage.data.write(batch.build()).await {
                 Ok(_) => {
                     changes.log_delete(Collection::Mailbox, document_id);
+                    if let Some((path, is_subscribed)) = subscription {
+                        self.mailbox_update_deleted_subscriptions(account_id, |subscriptions| {
+                            let has_subscription = subscriptions.contains(&path);
+                            if is_subscribed && !has_subscription {
+                                subscriptions.push(path.clone());
+                                true
+                            } else if !is_subscribed && has_subscription {
+                                subscriptions.retain(|name| name != &path);
+                                true
+                            } else {
+                                false
+                            }
+                        })
+                        .await?;
+                    }
                     Ok(Ok(did_remove_emails))
                 }
                 Err(err) if err.is_assertion_failure() => Ok(Err(SetError::forbidden()
@@ -555,6 +598,28 @@ impl JMAP {
                 (Property::SortOrder, MaybePatchValue::Value(Value::UnsignedInt(value))) => {
                     Value::UnsignedInt(value)
                 }
+                (Property::SyncPriority, MaybePatchValue::Value(Value::Text(value))) => {
+                    let priority = value.trim().to_lowercase();
+                    match SyncPriority::parse(&priority) {
+                        Some(SyncPriority::Normal) => Value::Null,
+                        Some(_) => Value::Text(priority),
+                        None => {
+                            return Ok(Err(SetError::invalid_properties()
+                                .with_property(Property::SyncPriority)
+                                .with_description(format!(
+                                    "Invalid sync priority {priority:?}."
+                                ))));
+                        }
+                    }
+                }
+                (Property::SyncPriority, MaybePatchValue::Value(Value::Null)) => Value::Null,
+                (Property::PreviewOnly, MaybePatchValue::Value(Value::Bool(value))) => {
+                    if value {
+                        Value::Bool(true)
+                    } else {
+                        Value::Null
+                    }
+                }
                 (Property::Acl, value) => {
                     match self
                         .acl_set(&mut changes, update.as_ref().map(|(_, obj)| obj), value)
@@ -566,6 +631,17 @@ impl JMAP {
                         }
                     }
                 }
+                (Property::ShareWith, value) => {
+                    match self
+                        .share_with_set(&mut changes, update.as_ref().map(|(_, obj)| obj), value)
+                        .await
+                    {
+                        Ok(_) => continue,
+                        Err(err) => {
+                            return Ok(Err(err));
+                        }
+                    }
+                }
 
                 _ => {
                     return Ok(Err(SetError::invalid_properties()
@@ -824,8 +900,9 @@ impl JMAP {
             .data
             .write(batch.build())
             .await
-            .caused_by(trc::location!())
-            .map(|_| mailbox_ids)
+            .caused_by(trc::location!())?;
+
+        Ok(mailbox_ids)
     }
 
     pub async fn mailbox_create_path(
@@ -0,0 +1,182 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    object::Object,
+    types::{collection::Collection, property::Property, value::Value},
+};
+use store::write::{
+    assert::{AssertValue, HashedValue},
+    BatchBuilder, Bincode, F_CLEAR, F_VALUE,
+};
+use trc::AddContext;
+
+use crate::JMAP;
+
+use super::INBOX_ID;
+
+const MAX_UPDATE_ATTEMPTS: usize = 10;
+
+// Subscriptions of existing mailboxes are stored in their IsSubscribed property.
+// Once a subscribed mailbox is deleted, its name is kept in a per-account list so
+// that IMAP clients can still list it as \NonExistent until it is unsubscribed or
+// a mailbox with the same name is created again.
+impl JMAP {
+    pub async fn mailbox_deleted_subscriptions(&self, account_id: u32) -> trc::Result<Vec<String>> {
+        self.get_property::<Bincode<Vec<String>>>(
+            account_id,
+            Collection::Principal,
+            0,
+            Property::Subscriptions,
+        )
+        .await
+        .caused_by(trc::location!())
+        .map(|subscriptions| {
+            subscriptions
+                .map(|subscriptions| subscriptions.inner)
+                .unwrap_or_default()
+        })
+    }
+
+    // Concurrent updates are detected by asserting the previous value of the list
+    pub async fn mailbox_update_deleted_subscriptions(
+        &self,
+        account_id: u32,
+        mut update: impl FnMut(&mut Vec<String>) -> bool,
+    ) -> trc::Result<bool> {
+        let mut attempts = 0;
+        loop {
+            let (assert_value, mut subscriptions) = match self
+                .get_property::<HashedValue<Bincode<Vec<String>>>>(
+                    account_id,
+                    Collection::Principal,
+                    0,
+                    Property::Subscriptions,
+                )
+                .await
+                .caused_by(trc::location!())?
+            {
+                Some(subscriptions) => (subscriptions.to_assert_value(), subscriptions.inner.inner),
+                None => (AssertValue::None, Vec::new()),
+            };
+            if !update(&mut subscriptions) {
+                return Ok(false);
+            }
+
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::Principal)
+                .update_document(0)
+                .assert_value(Property::Subscriptions, assert_value);
+            if !subscriptions.is_empty() {
+                batch.value(
+                    Property::Subscriptions,
+                    Bincode::new(subscriptions),
+                    F_VALUE,
+                );
+            } else {
+                batch.value(Property::Subscriptions, (), F_VALUE | F_CLEAR);
+            }
+            match self.write_batch(batch).await {
+                Ok(_) => return Ok(true),
+                Err(err) if err.is_assertion_failure() && attempts < MAX_UPDATE_ATTEMPTS => {
+                    attempts += 1;
+                }
+                Err(err) => return Err(err.caused_by(trc::location!())),
+            }
+        }
+    }
+
+    // Removes the subscription of a deleted mailbox, returns whether it existed
+    pub async fn mailbox_remove_deleted_subscription(
+        &self,
+        account_id: u32,
+        mailbox_name: &str,
+    ) -> trc::Result<bool> {
+        self.mailbox_update_deleted_subscriptions(account_id, |subscriptions| {
+            let num_subscriptions = subscriptions.len();
+            subscriptions.retain(|name| name != mailbox_name);
+            subscriptions.len() != num_subscriptions
+        })
+        .await
+    }
+
+    // A mailbox now uses this path, so any subscription of a deleted mailbox
+    // with the same name is superseded by its IsSubscribed property
+    pub async fn mailbox_supersede_deleted_subscription(
+        &self,
+        account_id: u32,
+        document_id: u32,
+    ) -> trc::Result<()> {
+        if self
+            .mailbox_deleted_subscriptions(account_id)
+            .await?
+            .is_empty()
+        {
+            return Ok(());
+        }
+
+        if let Some(path) = self.mailbox_path(account_id, document_id).await? {
+            self.mailbox_remove_deleted_subscription(account_id, &path)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // Returns the full path of a mailbox as presented to IMAP clients
+    pub async fn mailbox_path(
+        &self,
+        account_id: u32,
+        document_id: u32,
+    ) -> trc::Result<Option<String>> {
+        let mut path = Vec::new();
+        let mut next_id = Some(document_id);
+
+        while let Some(document_id) = next_id.take() {
+            if path.len() >= 100 {
+                return Ok(None);
+            }
+
+            let mailbox = if let Some(mailbox) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::Mailbox,
+                    document_id,
+                    Property::Value,
+                )
+                .await
+                .caused_by(trc::location!())?
+            {
+                mailbox
+            } else {
+                return Ok(None);
+            };
+            let parent_id = match mailbox.get(&Property::ParentId) {
+                Value::Id(parent_id) => parent_id.document_id(),
+                _ => 0,
+            };
+            if document_id == INBOX_ID && parent_id == 0 {
+                path.push("INBOX".to_string());
+            } else {
+                path.push(
+                    mailbox
+                        .get(&Property::Name)
+                        .as_string()
+                        .unwrap_or_default()
+                        .to_string(),
+                );
+            }
+            if parent_id > 0 {
+                next_id = Some(parent_id - 1);
+            }
+        }
+
+        path.reverse();
+        Ok(Some(path.join("/")))
+    }
+}
@@ -0,0 +1,284 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::{backend::internal::PrincipalField, QueryBy};
+use jmap_proto::{
+    object::{index::ObjectIndexBuilder, Object},
+    types::{blob::BlobId, collection::Collection, property::Property, value::Value},
+};
+use mail_builder::MessageBuilder;
+use mail_parser::MessageParser;
+use store::{
+    write::{
+        assert::{AssertValue, HashedValue},
+        now, BatchBuilder, BlobOp, DirectoryClass, F_VALUE,
+    },
+    BlobClass,
+};
+use trc::AddContext;
+
+use crate::{
+    email::ingest::{IngestEmail, IngestSource},
+    sieve::set::SCHEMA,
+    JMAP,
+};
+
+use super::{set::SCHEMA as MAILBOX_SCHEMA, INBOX_ID};
+
+impl JMAP {
+    // Applies the account template the first time an account logs in or is created.
+    // The template marker is claimed with an assertion so that concurrent first
+    // logins do not provision the account twice.
+    pub async fn account_apply_template(&self, account_id: u32) {
+        if self.core.jmap.account_template.is_empty() {
+            return;
+        }
+
+        match self.account_claim_template(account_id).await {
+            Ok(true) => {
+                if let Err(err) = self.account_provision_template(account_id).await {
+                    trc::error!(err
+                        .account_id(account_id)
+                        .details("Failed to apply account template"));
+                }
+            }
+            Ok(false) => {}
+            Err(err) => {
+                trc::error!(err
+                    .account_id(account_id)
+                    .details("Failed to claim account template"));
+            }
+        }
+    }
+
+    async fn account_claim_template(&self, account_id: u32) -> trc::Result<bool> {
+        if self
+            .get_property::<u64>(
+                account_id,
+                Collection::Principal,
+                0,
+                Property::AccountTemplate,
+            )
+            .await
+            .caused_by(trc::location!())?
+            .is_some()
+        {
+            return Ok(false);
+        }
+
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Principal)
+            .update_document(0)
+            .assert_value(Property::AccountTemplate, AssertValue::None)
+            .value(Property::AccountTemplate, now(), F_VALUE);
+        match self.write_batch(batch).await {
+            Ok(_) => Ok(true),
+            Err(err) if err.is_assertion_failure() => Ok(false),
+            Err(err) => Err(err.caused_by(trc::location!())),
+        }
+    }
+
+    async fn account_provision_template(&self, account_id: u32) -> trc::Result<()> {
+        let template = &self.core.jmap.account_template;
+
+        // Make sure the default folders exist before adding the template ones
+        self.mailbox_get_or_create(account_id)
+            .await
+            .caused_by(trc::location!())?;
+
+        // Create additional folders
+        for folder in &template.folders {
+            let document_id = if let Some((document_id, _)) = self
+                .mailbox_create_path(account_id, &folder.path)
+                .await
+                .caused_by(trc::location!())?
+            {
+                document_id
+            } else {
+                trc::error!(trc::StoreEvent::UnexpectedError
+                    .into_err()
+                    .account_id(account_id)
+                    .details("Invalid folder name in account template")
+                    .ctx(trc::Key::Path, folder.path.clone()));
+                continue;
+            };
+
+            // Assign the special-use role, unless it is already taken
+            if let Some(role) = folder.special_use.as_role() {
+                if self
+                    .mailbox_get_by_role(account_id, role)
+                    .await
+                    .caused_by(trc::location!())?
+                    .is_none()
+                {
+                    self.mailbox_set_role(account_id, document_id, role)
+                        .await
+                        .caused_by(trc::location!())?;
+                } else {
+                    trc::error!(trc::StoreEvent::UnexpectedError
+                        .into_err()
+                        .account_id(account_id)
+                        .details("Account template folder role is already in use")
+                        .ctx(trc::Key::Path, folder.path.clone()));
+                }
+            }
+        }
+
+        // Deliver the welcome message
+        if let Some(welcome) = &template.welcome_message {
+            let access_token = self
+                .core
+                .get_cached_access_token(account_id)
+                .await
+                .caused_by(trc::location!())?;
+            let principal = self
+                .core
+                .storage
+                .directory
+                .query(QueryBy::Id(account_id), false)
+                .await
+                .caused_by(trc::location!())?
+                .unwrap_or_default();
+            let to = principal
+                .iter_str(PrincipalField::Emails)
+                .next()
+                .map(|email| email.as_str())
+                .unwrap_or(access_token.name.as_str())
+                .to_string();
+
+            let mut builder = MessageBuilder::new()
+                .from(welcome.from.as_str())
+                .to(to.as_str())
+                .subject(welcome.subject.as_str())
+                .text_body(welcome.text_body.as_str());
+            if let Some(html_body) = &welcome.html_body {
+                builder = builder.html_body(html_body.as_str());
+            }
+            let raw_message = builder.write_to_vec().map_err(|err| {
+                trc::StoreEvent::UnexpectedError
+                    .into_err()
+                    .details("Failed to build welcome message")
+                    .reason(err)
+            })?;
+
+            self.email_ingest(IngestEmail {
+                raw_message: &raw_message,
+                message: MessageParser::new().parse(&raw_message),
+                resource: access_token.as_resource_token(),
+                mailbox_ids: vec![INBOX_ID],
+                keywords: vec![],
+                received_at: None,
+                source: IngestSource::Smtp,
+                encrypt: self.core.jmap.encrypt,
+                session_id: 0,
+            })
+            .await
+            .caused_by(trc::location!())?;
+        }
+
+        // Install and activate the starter Sieve script
+        if let Some((name, script)) = &template.sieve_script {
+            let mut script_bytes = script.as_bytes().to_vec();
+            let script_size = script_bytes.len();
+            match self.core.sieve.untrusted_compiler.compile(&script_bytes) {
+                Ok(compiled_script) => {
+                    script_bytes.extend(bincode::serialize(&compiled_script).unwrap_or_default());
+                }
+                Err(err) => {
+                    return Err(trc::SieveEvent::UnexpectedError
+                        .into_err()
+                        .account_id(account_id)
+                        .details("Failed to compile account template Sieve script")
+                        .reason(err));
+                }
+            }
+
+            // Write script blob
+            let blob_id = BlobId::new(
+                self.put_blob(account_id, &script_bytes, false)
+                    .await
+                    .caused_by(trc::location!())?
+                    .hash,
+                BlobClass::Linked {
+                    account_id,
+                    collection: Collection::SieveScript.into(),
+                    document_id: 0,
+                },
+            )
+            .with_section_size(script_size);
+
+            // Write record
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::SieveScript)
+                .create_document()
+                .add(DirectoryClass::UsedQuota(account_id), script_size as i64)
+                .set(
+                    BlobOp::Link {
+                        hash: blob_id.hash.clone(),
+                    },
+                    Vec::new(),
+                )
+                .custom(
+                    ObjectIndexBuilder::new(SCHEMA).with_changes(
+                        Object::with_capacity(3)
+                            .with_property(Property::Name, name.clone())
+                            .with_property(Property::IsActive, Value::Bool(false))
+                            .with_property(Property::BlobId, Value::BlobId(blob_id)),
+                    ),
+                );
+            let document_id = self
+                .write_batch_expect_id(batch)
+                .await
+                .caused_by(trc::location!())?;
+            self.sieve_activate_script(account_id, document_id.into())
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+
+    async fn mailbox_set_role(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        role: &str,
+    ) -> trc::Result<()> {
+        let current = self
+            .get_property::<HashedValue<Object<Value>>>(
+                account_id,
+                Collection::Mailbox,
+                document_id,
+                Property::Value,
+            )
+            .await?
+            .ok_or_else(|| {
+                trc::StoreEvent::NotFound
+                    .into_err()
+                    .caused_by(trc::location!())
+                    .document_id(document_id)
+            })?;
+
+        let mut changes = self.begin_changes(account_id).await?;
+        changes.log_update(Collection::Mailbox, document_id);
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::Mailbox)
+            .update_document(document_id)
+            .custom(
+                ObjectIndexBuilder::new(MAILBOX_SCHEMA)
+                    .with_current(current)
+                    .with_changes(Object::with_capacity(1).with_property(Property::Role, role)),
+            )
+            .custom(changes);
+        self.write_batch(batch).await.map(|_| ())
+    }
+}
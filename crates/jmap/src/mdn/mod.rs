@@ -0,0 +1,83 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    object::Object,
+    types::{property::Property, value::Value},
+};
+
+pub mod parse;
+pub mod send;
+
+pub const ACTION_MODES: &[&str] = &["manual-action", "automatic-action"];
+pub const SENDING_MODES: &[&str] = &["mdn-sent-manually", "mdn-sent-automatically"];
+pub const DISPOSITION_TYPES: &[&str] = &["deleted", "dispatched", "displayed", "processed"];
+
+pub struct Disposition<'x> {
+    pub action_mode: &'x str,
+    pub sending_mode: &'x str,
+    pub type_: &'x str,
+}
+
+impl<'x> Disposition<'x> {
+    pub fn from_object(disposition: &'x Object<Value>) -> Option<Self> {
+        let field = |name: &str| {
+            disposition
+                .properties
+                .get(&Property::_T(name.to_string()))
+                .and_then(|value| value.as_string())
+        };
+        let disposition = Disposition {
+            action_mode: field("actionMode")?,
+            sending_mode: field("sendingMode")?,
+            type_: field("type")?,
+        };
+
+        (ACTION_MODES.contains(&disposition.action_mode)
+            && SENDING_MODES.contains(&disposition.sending_mode)
+            && DISPOSITION_TYPES.contains(&disposition.type_))
+        .then_some(disposition)
+    }
+
+    pub fn parse(value: &'x str) -> Option<Self> {
+        // disposition-mode ";" disposition-type [ "/" disposition-modifier ]
+        let (modes, type_) = value.split_once(';')?;
+        let (action_mode, sending_mode) = modes.split_once('/')?;
+        let type_ = type_.split_once('/').map_or(type_, |(type_, _)| type_);
+
+        Some(Disposition {
+            action_mode: action_mode.trim(),
+            sending_mode: sending_mode.trim(),
+            type_: type_.trim(),
+        })
+    }
+
+    pub fn to_object(&self) -> Object<Value> {
+        Object::with_capacity(3)
+            .with_property(
+                Property::_T("actionMode".to_string()),
+                self.action_mode.to_ascii_lowercase(),
+            )
+            .with_property(
+                Property::_T("sendingMode".to_string()),
+                self.sending_mode.to_ascii_lowercase(),
+            )
+            .with_property(
+                Property::_T("type".to_string()),
+                self.type_.to_ascii_lowercase(),
+            )
+    }
+}
+
+impl std::fmt::Display for Disposition<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{}; {}",
+            self.action_mode, self.sending_mode, self.type_
+        )
+    }
+}
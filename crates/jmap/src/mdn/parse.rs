@@ -0,0 +1,201 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use jmap_proto::{
+    method::mdn::{MdnParseRequest, MdnParseResponse},
+    object::Object,
+    types::{collection::Collection, id::Id, property::Property, value::Value},
+};
+use mail_parser::{MessageParser, MimeHeaders, PartType};
+use store::query::Filter;
+use trc::AddContext;
+use utils::map::vec_map::VecMap;
+
+use crate::JMAP;
+
+use super::Disposition;
+
+impl JMAP {
+    pub async fn mdn_parse(
+        &self,
+        request: MdnParseRequest,
+        access_token: &AccessToken,
+    ) -> trc::Result<MdnParseResponse> {
+        if request.blob_ids.len() > self.core.jmap.mail_parse_max_items {
+            return Err(trc::JmapEvent::RequestTooLarge.into_err());
+        }
+        let account_id = request.account_id.document_id();
+        let mut response = MdnParseResponse {
+            account_id: request.account_id,
+            parsed: VecMap::with_capacity(request.blob_ids.len()),
+            not_parsable: vec![],
+            not_found: vec![],
+        };
+
+        for blob_id in request.blob_ids {
+            // Fetch raw message to parse
+            let raw_message = match self.blob_download(&blob_id, access_token).await? {
+                Some(raw_message) => raw_message,
+                None => {
+                    response.not_found.push(blob_id);
+                    continue;
+                }
+            };
+            let message = if let Some(message) = MessageParser::new().parse(&raw_message) {
+                message
+            } else {
+                response.not_parsable.push(blob_id);
+                continue;
+            };
+
+            // Locate the disposition notification report
+            let report = if let Some(report) = message.parts.iter().find(|part| {
+                part.content_type().map_or(false, |ct| {
+                    ct.ctype().eq_ignore_ascii_case("message")
+                        && ct.subtype().map_or(false, |st| {
+                            st.eq_ignore_ascii_case("disposition-notification")
+                        })
+                })
+            }) {
+                report.contents()
+            } else {
+                response.not_parsable.push(blob_id);
+                continue;
+            };
+            let fields = if let Some(fields) = MessageParser::new().parse_headers(report) {
+                fields
+            } else {
+                response.not_parsable.push(blob_id);
+                continue;
+            };
+
+            let mut mdn = Object::with_capacity(12);
+            let mut errors = Vec::new();
+            let mut extension_fields = Object::with_capacity(0);
+            let mut original_message_id = None;
+            for header in fields.headers() {
+                let name = header.name.as_str();
+                let value = std::str::from_utf8(&report[header.offset_start..header.offset_end])
+                    .unwrap_or_default()
+                    .split_ascii_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                let property = match name.to_ascii_lowercase().as_str() {
+                    "reporting-ua" => "reportingUA",
+                    "mdn-gateway" => "mdnGateway",
+                    "original-recipient" => "originalRecipient",
+                    "final-recipient" => "finalRecipient",
+                    "original-message-id" => {
+                        original_message_id = value
+                            .trim_start_matches('<')
+                            .trim_end_matches('>')
+                            .to_string()
+                            .into();
+                        "originalMessageId"
+                    }
+                    "disposition" => {
+                        if let Some(disposition) = Disposition::parse(&value) {
+                            mdn.append(
+                                Property::_T("disposition".to_string()),
+                                disposition.to_object(),
+                            );
+                        }
+                        continue;
+                    }
+                    "error" => {
+                        errors.push(Value::Text(value));
+                        continue;
+                    }
+                    _ => {
+                        extension_fields.append(Property::_T(name.to_string()), value);
+                        continue;
+                    }
+                };
+                mdn.append(Property::_T(property.to_string()), value);
+            }
+            if !mdn
+                .properties
+                .contains_key(&Property::_T("disposition".to_string()))
+            {
+                response.not_parsable.push(blob_id);
+                continue;
+            }
+
+            // Find the original message in the account
+            let mut for_email_id = Value::Null;
+            if let Some(message_id) = original_message_id.filter(|id| !id.is_empty()) {
+                if let Some(document_id) = self
+                    .core
+                    .storage
+                    .data
+                    .filter(
+                        account_id,
+                        Collection::Email,
+                        vec![Filter::eq(Property::MessageId, &message_id)],
+                    )
+                    .await
+                    .caused_by(trc::location!())?
+                    .results
+                    .min()
+                {
+                    if let Some(thread_id) = self
+                        .get_property::<u32>(
+                            account_id,
+                            Collection::Email,
+                            document_id,
+                            Property::ThreadId,
+                        )
+                        .await?
+                    {
+                        for_email_id = Value::Id(Id::from_parts(thread_id, document_id));
+                    }
+                }
+            }
+            mdn.append(Property::_T("forEmailId".to_string()), for_email_id);
+            mdn.append(
+                Property::_T("subject".to_string()),
+                message
+                    .subject()
+                    .map_or(Value::Null, |subject| Value::Text(subject.to_string())),
+            );
+            mdn.append(
+                Property::_T("textBody".to_string()),
+                message
+                    .body_text(0)
+                    .map_or(Value::Null, |text| Value::Text(text.into_owned())),
+            );
+            mdn.append(
+                Property::_T("includeOriginalMessage".to_string()),
+                message
+                    .parts
+                    .iter()
+                    .any(|part| matches!(part.body, PartType::Message(_))),
+            );
+            mdn.append(
+                Property::_T("error".to_string()),
+                if !errors.is_empty() {
+                    Value::List(errors)
+                } else {
+                    Value::Null
+                },
+            );
+            mdn.append(
+                Property::_T("extensionFields".to_string()),
+                if !extension_fields.properties.is_empty() {
+                    Value::Object(extension_fields)
+                } else {
+                    Value::Null
+                },
+            );
+
+            response.parsed.append(blob_id, mdn);
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,485 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{fmt::Write, sync::Arc};
+
+use common::listener::{stream::NullIo, ServerInstance};
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::{
+        mdn::{MdnSendRequest, MdnSendResponse},
+        set::{self, SetRequest},
+    },
+    object::Object,
+    request::{
+        method::{MethodFunction, MethodName, MethodObject},
+        reference::MaybeReference,
+        Call, RequestMethod,
+    },
+    types::{
+        collection::Collection,
+        id::Id,
+        keyword::Keyword,
+        property::Property,
+        value::{SetValue, Value},
+    },
+};
+use mail_builder::{
+    headers::{address::Address, content_type::ContentType, HeaderType},
+    mime::{BodyPart, MimePart},
+    MessageBuilder,
+};
+use mail_parser::{HeaderName, HeaderValue, MessageParser};
+use smtp::core::{Session, SessionData, State};
+use smtp_proto::{MailFrom, RcptTo};
+use store::write::Bincode;
+use utils::map::vec_map::VecMap;
+
+use crate::{email::metadata::MessageMetadata, identity::set::sanitize_email, JMAP};
+
+use super::Disposition;
+
+static MDN_PROPERTIES: &[&str] = &[
+    "forEmailId",
+    "subject",
+    "textBody",
+    "includeOriginalMessage",
+    "reportingUA",
+    "disposition",
+    "mdnGateway",
+    "originalRecipient",
+    "finalRecipient",
+    "originalMessageId",
+    "error",
+    "extensionFields",
+];
+
+struct MdnIdentity {
+    name: Option<String>,
+    email: String,
+}
+
+impl JMAP {
+    pub async fn mdn_send(
+        &self,
+        request: MdnSendRequest,
+        instance: &Arc<ServerInstance>,
+        next_call: &mut Option<Call<RequestMethod>>,
+    ) -> trc::Result<MdnSendResponse> {
+        let account_id = request.account_id.document_id();
+        if request.send.len() > self.core.jmap.set_max_objects {
+            return Err(trc::JmapEvent::RequestTooLarge.into_err());
+        }
+
+        // Obtain identity
+        let identity = self
+            .get_property::<Object<Value>>(
+                account_id,
+                Collection::Identity,
+                request.identity_id.document_id(),
+                Property::Value,
+            )
+            .await?
+            .and_then(|mut identity| {
+                Some(MdnIdentity {
+                    email: identity
+                        .properties
+                        .remove(&Property::Email)?
+                        .try_unwrap_string()?,
+                    name: identity
+                        .properties
+                        .remove(&Property::Name)
+                        .and_then(|name| name.try_unwrap_string())
+                        .filter(|name| !name.is_empty()),
+                })
+            })
+            .ok_or_else(|| {
+                trc::JmapEvent::InvalidArguments
+                    .into_err()
+                    .details("Identity not found.")
+            })?;
+
+        let mut response = MdnSendResponse {
+            account_id: request.account_id,
+            sent: VecMap::with_capacity(request.send.len()),
+            not_sent: VecMap::new(),
+        };
+        let mut sent_email_ids = VecMap::with_capacity(request.send.len());
+        for (create_id, mdn) in request.send {
+            match self.send_mdn(account_id, &identity, mdn, instance).await? {
+                Ok((email_id, sent)) => {
+                    sent_email_ids.append(create_id.clone(), email_id);
+                    response.sent.append(create_id, sent);
+                }
+                Err(err) => {
+                    response.not_sent.append(create_id, err);
+                }
+            }
+        }
+
+        // Flag the original messages as $mdnsent and apply any client requested updates
+        if !sent_email_ids.is_empty() {
+            let mut update: VecMap<Id, Object<SetValue>> = VecMap::new();
+            for (id, value) in request.on_success_update_email.unwrap_or_default() {
+                let id = match id {
+                    MaybeReference::Value(id) => id,
+                    MaybeReference::Reference(id_ref) => match sent_email_ids.get(&id_ref) {
+                        Some(id) => *id,
+                        None => continue,
+                    },
+                };
+                update.append(id, value);
+            }
+            for email_id in sent_email_ids.values() {
+                let object = update.get_mut_or_insert_with(*email_id, || Object {
+                    properties: VecMap::with_capacity(1),
+                });
+                if !object.properties.contains_key(&Property::Keywords) {
+                    object.properties.append(
+                        Property::Keywords,
+                        SetValue::Patch(vec![Value::Keyword(Keyword::MdnSent), Value::Bool(true)]),
+                    );
+                }
+            }
+
+            *next_call = Call {
+                id: String::new(),
+                name: MethodName::new(MethodObject::Email, MethodFunction::Set),
+                method: RequestMethod::Set(SetRequest {
+                    account_id: request.account_id,
+                    if_in_state: None,
+                    create: None,
+                    update: update.into(),
+                    destroy: None,
+                    arguments: set::RequestArguments::Email,
+                }),
+            }
+            .into();
+        }
+
+        Ok(response)
+    }
+
+    async fn send_mdn(
+        &self,
+        account_id: u32,
+        identity: &MdnIdentity,
+        mdn: Object<Value>,
+        instance: &Arc<ServerInstance>,
+    ) -> trc::Result<Result<(Id, Object<Value>), SetError>> {
+        // Validate properties
+        for property in mdn.properties.keys() {
+            if !matches!(property, Property::_T(name) if MDN_PROPERTIES.contains(&name.as_str())) {
+                return Ok(Err(SetError::invalid_properties()
+                    .with_property(property.clone())
+                    .with_description("Invalid MDN property.")));
+            }
+        }
+        let field = |name: &str| mdn.get(&Property::_T(name.to_string()));
+        let email_id = if let Some(email_id) = field("forEmailId")
+            .as_string()
+            .and_then(|id| Id::from_bytes(id.as_bytes()))
+        {
+            email_id
+        } else {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::_T("forEmailId".to_string()))
+                .with_description("Missing or invalid forEmailId.")));
+        };
+        let disposition = if let Some(disposition) = field("disposition")
+            .as_obj()
+            .and_then(Disposition::from_object)
+        {
+            disposition
+        } else {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::_T("disposition".to_string()))
+                .with_description("Missing or invalid disposition.")));
+        };
+        let include_original = field("includeOriginalMessage")
+            .as_bool()
+            .unwrap_or_default();
+
+        // Reject values that would inject additional fields into the report
+        for name in ["reportingUA", "mdnGateway", "originalRecipient"] {
+            if field(name)
+                .as_string()
+                .is_some_and(|value| !is_valid_field_value(value))
+            {
+                return Ok(Err(SetError::invalid_properties()
+                    .with_property(Property::_T(name.to_string()))
+                    .with_description("Field values cannot contain line breaks.")));
+            }
+        }
+        if field("error").as_list().is_some_and(|errors| {
+            errors
+                .iter()
+                .filter_map(|error| error.as_string())
+                .any(|error| !is_valid_field_value(error))
+        }) {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::_T("error".to_string()))
+                .with_description("Field values cannot contain line breaks.")));
+        }
+        if field("extensionFields").as_obj().is_some_and(|extensions| {
+            extensions.properties.iter().any(|(name, value)| {
+                !matches!(name, Property::_T(name) if is_valid_field_name(name))
+                    || value
+                        .as_string()
+                        .is_some_and(|value| !is_valid_field_value(value))
+            })
+        }) {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::_T("extensionFields".to_string()))
+                .with_description("Invalid extension field name or value.")));
+        }
+
+        // Make sure an MDN was not already sent for this message
+        let document_id = email_id.document_id();
+        if let Some(keywords) = self
+            .get_property::<Vec<Keyword>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::Keywords,
+            )
+            .await?
+        {
+            if keywords.contains(&Keyword::MdnSent) {
+                return Ok(Err(SetError::new(SetErrorType::MdnAlreadySent)
+                    .with_description("An MDN was already sent for this message.")));
+            }
+        } else {
+            return Ok(Err(SetError::not_found()
+                .with_property(Property::_T("forEmailId".to_string()))
+                .with_description("Email not found.")));
+        }
+
+        // Obtain original message
+        let raw_message = if let Some(raw_message) = self
+            .get_property::<Bincode<MessageMetadata>>(
+                account_id,
+                Collection::Email,
+                document_id,
+                Property::BodyStructure,
+            )
+            .await?
+        {
+            self.get_blob(&raw_message.inner.blob_hash, 0..usize::MAX)
+                .await?
+        } else {
+            None
+        };
+        let message = if let Some(message) = raw_message
+            .as_deref()
+            .and_then(|raw_message| MessageParser::new().parse(raw_message))
+        {
+            message
+        } else {
+            return Ok(Err(SetError::not_found()
+                .with_property(Property::_T("forEmailId".to_string()))
+                .with_description("Email not found.")));
+        };
+
+        // Obtain the address requesting the notification
+        let rcpt_to = if let Some(rcpt_to) = message
+            .header_as(
+                HeaderName::Other("Disposition-Notification-To".into()),
+                mail_parser::HeaderForm::Addresses,
+            )
+            .into_iter()
+            .find_map(|value| match value {
+                HeaderValue::Address(address) => address
+                    .first()
+                    .and_then(|addr| addr.address())
+                    .and_then(sanitize_email),
+                _ => None,
+            }) {
+            rcpt_to
+        } else {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::_T("forEmailId".to_string()))
+                .with_description(
+                    "Email does not request a disposition notification.",
+                )));
+        };
+
+        // Build the disposition notification fields
+        let original_message_id = message.message_id().map(|id| id.to_string());
+        let reporting_ua = field("reportingUA").as_string().map_or_else(
+            || {
+                format!(
+                    "{}; Stalwart JMAP",
+                    identity
+                        .email
+                        .rsplit_once('@')
+                        .map_or(identity.email.as_str(), |(_, domain)| domain)
+                )
+            },
+            |ua| ua.to_string(),
+        );
+        let final_recipient = format!("rfc822; {}", identity.email);
+        let mut report = String::with_capacity(256);
+        let _ = write!(report, "Reporting-UA: {reporting_ua}\r\n");
+        if let Some(gateway) = field("mdnGateway").as_string() {
+            let _ = write!(report, "MDN-Gateway: {gateway}\r\n");
+        }
+        if let Some(original_recipient) = field("originalRecipient").as_string() {
+            let _ = write!(report, "Original-Recipient: {original_recipient}\r\n");
+        }
+        let _ = write!(report, "Final-Recipient: {final_recipient}\r\n");
+        if let Some(message_id) = &original_message_id {
+            let _ = write!(report, "Original-Message-ID: <{message_id}>\r\n");
+        }
+        let _ = write!(report, "Disposition: {disposition}\r\n");
+        if let Some(errors) = field("error").as_list() {
+            for error in errors.iter().filter_map(|error| error.as_string()) {
+                let _ = write!(report, "Error: {error}\r\n");
+            }
+        }
+        if let Some(extensions) = field("extensionFields").as_obj() {
+            for (name, value) in extensions.properties.iter() {
+                if let (Property::_T(name), Some(value)) = (name, value.as_string()) {
+                    let _ = write!(report, "{name}: {value}\r\n");
+                }
+            }
+        }
+
+        // Build message
+        let original_subject = message.subject().unwrap_or_default();
+        let subject = field("subject").as_string().map_or_else(
+            || format!("Disposition notification: {original_subject}"),
+            |subject| subject.to_string(),
+        );
+        let text_body = field("textBody").as_string().map_or_else(
+            || {
+                format!(
+                    "The message \"{original_subject}\" was {}.\r\n",
+                    disposition.type_
+                )
+            },
+            |text| text.to_string(),
+        );
+        let original_part = if include_original {
+            MimePart::new(
+                ContentType::new("message/rfc822"),
+                BodyPart::Binary(message.raw_message().into()),
+            )
+        } else {
+            MimePart::new(
+                ContentType::new("text/rfc822-headers"),
+                BodyPart::Binary(
+                    message.raw_message()[..message.root_part().offset_body]
+                        .to_vec()
+                        .into(),
+                ),
+            )
+        };
+        let mut builder = MessageBuilder::new()
+            .from(Address::new_address(
+                identity.name.as_deref(),
+                identity.email.as_str(),
+            ))
+            .to(rcpt_to.as_str())
+            .subject(subject);
+        if let Some(message_id) = &original_message_id {
+            builder = builder
+                .in_reply_to(message_id.as_str())
+                .references(message_id.as_str());
+        }
+        if disposition.sending_mode == "mdn-sent-automatically" {
+            builder = builder.header("Auto-Submitted", HeaderType::Text("auto-replied".into()));
+        }
+        let mdn_message = builder
+            .body(MimePart::new(
+                ContentType::new("multipart/report")
+                    .attribute("report-type", "disposition-notification"),
+                BodyPart::Multipart(vec![
+                    MimePart::new(
+                        ContentType::new("text/plain"),
+                        BodyPart::Text(text_body.into()),
+                    ),
+                    MimePart::new(
+                        ContentType::new("message/disposition-notification"),
+                        BodyPart::Text(report.into()),
+                    ),
+                    original_part,
+                ]),
+            ))
+            .write_to_vec()
+            .unwrap_or_default();
+
+        // Begin local SMTP session
+        let mut session =
+            Session::<NullIo>::local(self.smtp.clone(), instance.clone(), SessionData::default());
+
+        // MAIL FROM
+        let _ = session
+            .handle_mail_from(MailFrom {
+                address: identity.email.clone(),
+                ..Default::default()
+            })
+            .await;
+        if let Some(error) = session.has_failed() {
+            return Ok(Err(SetError::new(SetErrorType::ForbiddenFrom)
+                .with_description(format!(
+                    "Server rejected MAIL-FROM: {}",
+                    error.trim()
+                ))));
+        }
+
+        // RCPT TO
+        let _ = session
+            .handle_rcpt_to(RcptTo {
+                address: rcpt_to,
+                ..Default::default()
+            })
+            .await;
+        if let Some(error) = session.has_failed() {
+            return Ok(Err(SetError::new(SetErrorType::ForbiddenToSend)
+                .with_description(format!(
+                    "Server rejected RCPT-TO: {}",
+                    error.trim()
+                ))));
+        }
+
+        // DATA
+        session.data.message = mdn_message;
+        let response = session.queue_message().await;
+        if !matches!(session.state, State::Accepted(_)) {
+            return Ok(Err(SetError::new(SetErrorType::ForbiddenToSend)
+                .with_description(format!(
+                    "Server rejected DATA: {}",
+                    std::str::from_utf8(&response).unwrap_or_default().trim()
+                ))));
+        }
+
+        // Return the properties set by the server
+        let mut sent = Object::with_capacity(4)
+            .with_property(Property::_T("finalRecipient".to_string()), final_recipient)
+            .with_property(Property::_T("reportingUA".to_string()), reporting_ua)
+            .with_property(
+                Property::_T("includeOriginalMessage".to_string()),
+                include_original,
+            );
+        if let Some(message_id) = original_message_id {
+            sent.append(
+                Property::_T("originalMessageId".to_string()),
+                format!("<{message_id}>"),
+            );
+        }
+
+        Ok(Ok((email_id, sent)))
+    }
+}
+
+fn is_valid_field_value(value: &str) -> bool {
+    !value.contains(['\r', '\n'])
+}
+
+fn is_valid_field_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|ch| ch.is_ascii_graphic() && ch != b':')
+}
@@ -9,7 +9,10 @@ use common::auth::AccessToken;
 use jmap_proto::{
     method::get::{GetRequest, GetResponse, RequestArguments},
     object::Object,
-    types::{collection::Collection, property::Property, type_state::DataType, value::Value},
+    types::{
+        collection::Collection, property::Property, state::SyncPriority, type_state::DataType,
+        value::Value,
+    },
 };
 use store::{
     write::{now, ValueClass},
@@ -34,6 +37,7 @@ impl JMAP {
             Property::VerificationCode,
             Property::Expires,
             Property::Types,
+            Property::SyncPriority,
         ]);
         let account_id = access_token.primary_id();
         let push_ids = self
@@ -88,6 +92,14 @@ impl JMAP {
                             "The 'url' and 'keys' properties are not readable".to_string(),
                         ));
                     }
+                    Property::SyncPriority => {
+                        result.append(
+                            Property::SyncPriority,
+                            push.properties
+                                .remove(&Property::SyncPriority)
+                                .unwrap_or_else(|| Value::Text("low".to_string())),
+                        );
+                    }
                     property => {
                         result.append(property.clone(), push.remove(property));
                     }
@@ -215,6 +227,14 @@ impl JMAP {
                         Bitmap::all()
                     };
 
+                    // Constrained clients can opt out of changes to lower priority mailboxes
+                    let priority = subscription
+                        .properties
+                        .get(&Property::SyncPriority)
+                        .and_then(|p| p.as_string())
+                        .and_then(SyncPriority::parse)
+                        .unwrap_or(SyncPriority::Low);
+
                     // Add verified subscription
                     subscriptions.push(UpdateSubscription::Verified(PushSubscription {
                         id: document_id,
@@ -222,6 +242,7 @@ impl JMAP {
                         expires,
                         types,
                         keys,
+                        priority,
                     }));
                 } else {
                     // Add unverified subscription
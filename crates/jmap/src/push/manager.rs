@@ -4,8 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use base64::{engine::general_purpose, Engine};
-use common::IPC_CHANNEL_BUFFER;
+use common::{config::jmap::settings::VapidKey, IPC_CHANNEL_BUFFER};
 use jmap_proto::types::id::Id;
 use store::ahash::{AHashMap, AHashSet};
 use tokio::sync::mpsc;
@@ -15,7 +14,7 @@ use crate::{api::StateChangeResponse, JmapInstance, LONG_SLUMBER};
 
 use super::{ece::ece_encrypt, EncryptionKeys, Event, PushServer, PushUpdate};
 
-use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE};
 use std::{
     collections::hash_map::Entry,
     time::{Duration, Instant},
@@ -44,6 +43,7 @@ pub fn spawn_push_manager(core: JmapInstance) -> mpsc::Sender<Event> {
             let push_timeout = core_.jmap.push_timeout;
             let push_verify_timeout = core_.jmap.push_verify_timeout;
             let push_throttle = core_.jmap.push_throttle;
+            let push_vapid = core_.jmap.push_vapid.clone();
 
             match event_or_timeout {
                 Ok(Some(event)) => match event {
@@ -75,6 +75,7 @@ pub fn spawn_push_manager(core: JmapInstance) -> mpsc::Sender<Event> {
                                         })
                                         .unwrap_or(true)
                                     {
+                                        let push_vapid = push_vapid.clone();
                                         tokio::spawn(async move {
                                             http_request(
                                                 url,
@@ -89,6 +90,7 @@ pub fn spawn_push_manager(core: JmapInstance) -> mpsc::Sender<Event> {
                                                 ),
                                                 keys,
                                                 push_timeout,
+                                                push_vapid,
                                             )
                                             .await;
                                         });
@@ -138,7 +140,12 @@ pub fn spawn_push_manager(core: JmapInstance) -> mpsc::Sender<Event> {
                                             .contains(&subscription.num_attempts)
                                             && last_request > push_attempt_interval))
                                 {
-                                    subscription.send(id, push_tx.clone(), push_timeout);
+                                    subscription.send(
+                                        id,
+                                        push_tx.clone(),
+                                        push_timeout,
+                                        push_vapid.clone(),
+                                    );
                                     retry_ids.remove(&id);
                                 } else {
                                     retry_ids.insert(id);
@@ -194,7 +201,12 @@ pub fn spawn_push_manager(core: JmapInstance) -> mpsc::Sender<Event> {
                                         && last_request >= push_attempt_interval))
                             {
                                 if subscription.num_attempts < push_attempts_max {
-                                    subscription.send(*retry_id, push_tx.clone(), push_timeout);
+                                    subscription.send(
+                                        *retry_id,
+                                        push_tx.clone(),
+                                        push_timeout,
+                                        push_vapid.clone(),
+                                    );
                                 } else {
                                     trc::event!(
                                         PushSubscription(PushSubscriptionEvent::Error),
@@ -236,7 +248,13 @@ pub fn spawn_push_manager(core: JmapInstance) -> mpsc::Sender<Event> {
 }
 
 impl PushServer {
-    fn send(&mut self, id: Id, push_tx: mpsc::Sender<Event>, push_timeout: Duration) {
+    fn send(
+        &mut self,
+        id: Id,
+        push_tx: mpsc::Sender<Event>,
+        push_timeout: Duration,
+        push_vapid: Option<VapidKey>,
+    ) {
         let url = self.url.clone();
         let keys = self.keys.clone();
         let state_changes = std::mem::take(&mut self.state_changes);
@@ -262,6 +280,7 @@ impl PushServer {
                         serde_json::to_string(&response).unwrap(),
                         keys,
                         push_timeout,
+                        push_vapid,
                     )
                     .await
                     {
@@ -278,9 +297,10 @@ impl PushServer {
 
 async fn http_request(
     url: String,
-    mut body: String,
+    body: String,
     keys: Option<EncryptionKeys>,
     push_timeout: Duration,
+    push_vapid: Option<VapidKey>,
 ) -> bool {
     let client_builder = reqwest::Client::builder().timeout(push_timeout);
 
@@ -294,13 +314,17 @@ async fn http_request(
         .header(CONTENT_TYPE, "application/json")
         .header("TTL", "86400");
 
-    if let Some(keys) = keys {
-        match ece_encrypt(&keys.p256dh, &keys.auth, body.as_bytes())
-            .map(|b| general_purpose::URL_SAFE.encode(b))
-        {
-            Ok(body_) => {
-                body = body_;
+    // Identify the application server to the push service (RFC 8292)
+    if let Some(authorization) = push_vapid.and_then(|vapid| vapid.authorization(&url)) {
+        client = client.header(AUTHORIZATION, authorization);
+    }
+
+    // Encrypt the payload (RFC 8291)
+    let body = if let Some(keys) = keys {
+        match ece_encrypt(&keys.p256dh, &keys.auth, body.as_bytes()) {
+            Ok(body) => {
                 client = client.header(CONTENT_ENCODING, "aes128gcm");
+                body
             }
             Err(err) => {
                 // Do not reattempt if encryption fails.
@@ -314,7 +338,9 @@ async fn http_request(
                 return true;
             }
         }
-    }
+    } else {
+        body.into_bytes()
+    };
 
     match client.body(body).send().await {
         Ok(response) => {
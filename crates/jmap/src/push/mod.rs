@@ -11,7 +11,11 @@ pub mod set;
 
 use std::time::Instant;
 
-use jmap_proto::types::{id::Id, state::StateChange, type_state::DataType};
+use jmap_proto::types::{
+    id::Id,
+    state::{StateChange, SyncPriority},
+    type_state::DataType,
+};
 use utils::map::bitmap::Bitmap;
 
 #[derive(Debug)]
@@ -32,6 +36,7 @@ pub struct PushSubscription {
     pub expires: u64,
     pub types: Bitmap<DataType>,
     pub keys: Option<EncryptionKeys>,
+    pub priority: SyncPriority,
 }
 
 #[derive(Debug, Clone)]
@@ -15,6 +15,7 @@ use jmap_proto::{
         collection::Collection,
         date::UTCDate,
         property::Property,
+        state::SyncPriority,
         type_state::DataType,
         value::{MaybePatchValue, Value},
     },
@@ -252,6 +253,11 @@ fn validate_push_value(
         {
             Value::List(value)
         }
+        (Property::SyncPriority, MaybePatchValue::Value(Value::Text(value)))
+            if SyncPriority::parse(&value).is_some() =>
+        {
+            Value::Text(value)
+        }
         (Property::VerificationCode, MaybePatchValue::Value(Value::Text(value)))
             if current.is_some() =>
         {
@@ -270,7 +276,7 @@ fn validate_push_value(
             }
         }
         (
-            Property::Keys | Property::Types | Property::VerificationCode,
+            Property::Keys | Property::Types | Property::VerificationCode | Property::SyncPriority,
             MaybePatchValue::Value(Value::Null),
         ) => Value::Null,
         (property, _) => {
@@ -0,0 +1,47 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::auth::AccessToken;
+use jmap_proto::{
+    method::changes::{ChangesRequest, ChangesResponse},
+    types::{id::Id, state::State},
+};
+
+use crate::JMAP;
+
+use super::quota_state;
+
+impl JMAP {
+    pub async fn quota_changes(
+        &self,
+        request: ChangesRequest,
+        access_token: &AccessToken,
+    ) -> trc::Result<ChangesResponse> {
+        let quotas = self
+            .account_quotas(request.account_id.document_id(), access_token)
+            .await?;
+        let new_state = State::Exact(quota_state(&quotas));
+
+        // Quota objects are never created or destroyed by the client, any
+        // change in usage or limits is reported as an update to all of them.
+        let updated = if request.since_state != new_state {
+            quotas.iter().map(|quota| Id::from(quota.id)).collect()
+        } else {
+            vec![]
+        };
+
+        Ok(ChangesResponse {
+            account_id: request.account_id,
+            old_state: request.since_state,
+            new_state,
+            has_more_changes: false,
+            created: vec![],
+            updated,
+            destroyed: vec![],
+            updated_properties: None,
+        })
+    }
+}
@@ -8,11 +8,13 @@ use common::auth::AccessToken;
 use jmap_proto::{
     method::get::{GetRequest, GetResponse, RequestArguments},
     object::Object,
-    types::{id::Id, property::Property, state::State, type_state::DataType, value::Value},
+    types::{id::Id, property::Property, state::State, value::Value},
 };
 
 use crate::JMAP;
 
+use super::quota_state;
+
 impl JMAP {
     pub async fn quota_get(
         &self,
@@ -33,46 +35,46 @@ impl JMAP {
             Property::Types,
         ]);
         let account_id = request.account_id.document_id();
-        let quota_ids = if access_token.quota > 0 {
-            vec![0u32]
-        } else {
-            vec![]
-        };
+        let quotas = self.account_quotas(account_id, access_token).await?;
         let ids = if let Some(ids) = ids {
             ids
         } else {
-            quota_ids.iter().map(|id| Id::from(*id)).collect()
+            quotas.iter().map(|quota| Id::from(quota.id)).collect()
         };
         let mut response = GetResponse {
             account_id: request.account_id.into(),
-            state: State::Initial.into(),
+            state: State::Exact(quota_state(&quotas)).into(),
             list: Vec::with_capacity(ids.len()),
             not_found: vec![],
         };
 
         for id in ids {
-            // Obtain the sieve script object
+            // Obtain the quota object
             let document_id = id.document_id();
-            if !quota_ids.contains(&document_id) {
+            let quota = if let Some(quota) = quotas.iter().find(|quota| quota.id == document_id) {
+                quota
+            } else {
                 response.not_found.push(id.into());
                 continue;
-            }
+            };
 
             let mut result = Object::with_capacity(properties.len());
             for property in &properties {
                 let value = match property {
                     Property::Id => Value::Id(id),
-                    Property::ResourceType => "octets".to_string().into(),
-                    Property::Used => (self.get_used_quota(account_id).await? as u64).into(),
-                    Property::HardLimit => access_token.quota.into(),
+                    Property::ResourceType => quota.resource_type.to_string().into(),
+                    Property::Used => quota.used.into(),
+                    Property::WarnLimit => quota.warn_limit.map_or(Value::Null, Value::from),
+                    Property::HardLimit => quota.hard_limit.into(),
                     Property::Scope => "account".to_string().into(),
                     Property::Name => access_token.name.clone().into(),
                     Property::Description => access_token.description.clone().into(),
-                    Property::Types => vec![
-                        Value::Text(DataType::Email.to_string()),
-                        Value::Text(DataType::SieveScript.to_string()),
-                    ]
-                    .into(),
+                    Property::Types => quota
+                        .types
+                        .iter()
+                        .map(|data_type| Value::Text(data_type.to_string()))
+                        .collect::<Vec<_>>()
+                        .into(),
 
                     _ => Value::Null,
                 };
@@ -4,5 +4,113 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use common::auth::{AccessToken, ResourceToken};
+use jmap_proto::types::{collection::Collection, state::StateChange, type_state::DataType};
+use store::blake3;
+use trc::AddContext;
+
+use crate::JMAP;
+
+pub mod changes;
 pub mod get;
 pub mod query;
+
+pub const QUOTA_OCTETS_ID: u32 = 0;
+pub const QUOTA_SIEVE_SCRIPTS_ID: u32 = 1;
+
+pub struct AccountQuota {
+    pub id: u32,
+    pub resource_type: &'static str,
+    pub used: u64,
+    pub warn_limit: Option<u64>,
+    pub hard_limit: u64,
+    pub types: &'static [DataType],
+}
+
+impl JMAP {
+    pub async fn account_quotas(
+        &self,
+        account_id: u32,
+        access_token: &AccessToken,
+    ) -> trc::Result<Vec<AccountQuota>> {
+        let mut quotas = Vec::with_capacity(2);
+
+        if access_token.quota > 0 {
+            quotas.push(AccountQuota {
+                id: QUOTA_OCTETS_ID,
+                resource_type: "octets",
+                used: self
+                    .get_used_quota(account_id)
+                    .await
+                    .caused_by(trc::location!())?
+                    .max(0) as u64,
+                warn_limit: self.quota_warn_limit(access_token.quota),
+                hard_limit: access_token.quota,
+                types: &[DataType::Email, DataType::SieveScript],
+            });
+        }
+
+        if self.core.jmap.sieve_max_scripts > 0 {
+            quotas.push(AccountQuota {
+                id: QUOTA_SIEVE_SCRIPTS_ID,
+                resource_type: "count",
+                used: self
+                    .get_document_ids(account_id, Collection::SieveScript)
+                    .await
+                    .caused_by(trc::location!())?
+                    .map_or(0, |ids| ids.len()),
+                warn_limit: None,
+                hard_limit: self.core.jmap.sieve_max_scripts as u64,
+                types: &[DataType::SieveScript],
+            });
+        }
+
+        Ok(quotas)
+    }
+
+    pub fn quota_warn_limit(&self, hard_limit: u64) -> Option<u64> {
+        let threshold = self.core.jmap.quota_warn_threshold;
+        if hard_limit > 0 && threshold > 0 && threshold < 100 {
+            Some(hard_limit * threshold / 100)
+        } else {
+            None
+        }
+    }
+
+    pub async fn notify_quota_threshold(
+        &self,
+        resource: &ResourceToken,
+        added_size: u64,
+        change_id: u64,
+    ) -> trc::Result<()> {
+        if let Some(warn_limit) = self.quota_warn_limit(resource.quota) {
+            let used = self
+                .get_used_quota(resource.account_id)
+                .await
+                .caused_by(trc::location!())?
+                .max(0) as u64;
+
+            if used >= warn_limit && used.saturating_sub(added_size) < warn_limit {
+                self.broadcast_state_change(
+                    StateChange::new(resource.account_id).with_change(DataType::Quota, change_id),
+                )
+                .await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn quota_state(quotas: &[AccountQuota]) -> u64 {
+    // Quotas are not stored in the changelog, the state is derived
+    // from the current usage and limits instead.
+    let mut hasher = blake3::Hasher::new();
+    for quota in quotas {
+        hasher.update(&quota.id.to_be_bytes());
+        hasher.update(&quota.used.to_be_bytes());
+        hasher.update(&quota.hard_limit.to_be_bytes());
+        hasher.update(&quota.warn_limit.unwrap_or_default().to_be_bytes());
+    }
+    u64::from_be_bytes(hasher.finalize().as_bytes()[..8].try_into().unwrap())
+}
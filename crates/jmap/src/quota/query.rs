@@ -12,23 +12,25 @@ use jmap_proto::{
 
 use crate::JMAP;
 
+use super::quota_state;
+
 impl JMAP {
     pub async fn quota_query(
         &self,
         request: QueryRequest<RequestArguments>,
         access_token: &AccessToken,
     ) -> trc::Result<QueryResponse> {
+        let quotas = self
+            .account_quotas(request.account_id.document_id(), access_token)
+            .await?;
+
         Ok(QueryResponse {
             account_id: request.account_id,
-            query_state: State::Initial,
+            query_state: State::Exact(quota_state(&quotas)),
             can_calculate_changes: false,
             position: 0,
-            ids: if access_token.quota > 0 {
-                vec![Id::new(0)]
-            } else {
-                vec![]
-            },
-            total: Some(1),
+            total: Some(quotas.len()),
+            ids: quotas.into_iter().map(|quota| Id::from(quota.id)).collect(),
             limit: None,
         })
 
@@ -67,6 +67,7 @@ pub struct Peer {
     pub gen_config: GenerationId,
     pub gen_lists: GenerationId,
     pub gen_permissions: GenerationId,
+    pub gen_tokens: GenerationId,
     pub state: State,
 
     // Heartbeat state
@@ -78,13 +79,14 @@ pub struct Peer {
     pub hb_is_full: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct PeerStatus {
     pub addr: IpAddr,
     pub epoch: EpochId,
     pub gen_config: GenerationId,
     pub gen_lists: GenerationId,
     pub gen_permissions: GenerationId,
+    pub gen_tokens: GenerationId,
 }
 
 impl From<&Peer> for PeerStatus {
@@ -95,6 +97,7 @@ impl From<&Peer> for PeerStatus {
             gen_config: peer.gen_config,
             gen_lists: peer.gen_lists,
             gen_permissions: peer.gen_permissions,
+            gen_tokens: peer.gen_tokens,
         }
     }
 }
@@ -112,6 +115,7 @@ impl From<&Gossiper> for PeerStatus {
                 .load(Ordering::Relaxed),
             gen_lists: core.network.blocked_ips.version.load(Ordering::Relaxed),
             gen_permissions: core.security.permissions_version.load(Ordering::Relaxed),
+            gen_tokens: core.security.access_tokens_version.load(Ordering::Relaxed),
         }
     }
 }
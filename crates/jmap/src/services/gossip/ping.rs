@@ -100,6 +100,7 @@ impl Gossiper {
         let mut update_config = false;
         let mut update_lists = false;
         let mut update_permissions = false;
+        let mut update_tokens = false;
 
         'outer: for (pos, peer) in peers.into_iter().enumerate() {
             if peer.addr == self.addr {
@@ -149,6 +150,18 @@ impl Gossiper {
                                     update_permissions = true;
                                 }
                             }
+                            if local_peer.gen_tokens != peer.gen_tokens {
+                                local_peer.gen_tokens = peer.gen_tokens;
+                                if local_peer.hb_sum > 0 {
+                                    trc::event!(
+                                        Cluster(ClusterEvent::PeerHasChanges),
+                                        RemoteIp = peer.addr,
+                                        Details = "access_tokens"
+                                    );
+
+                                    update_tokens = true;
+                                }
+                            }
                         }
 
                         continue 'outer;
@@ -176,6 +189,9 @@ impl Gossiper {
         if update_permissions {
             self.core.core.load().security.permissions.clear();
         }
+        if update_tokens {
+            self.core.core.load().security.access_tokens.clear();
+        }
 
         if update_config || update_lists {
             let core = self.core.core.clone();
@@ -185,7 +201,7 @@ impl Gossiper {
                 let result = if update_config {
                     core.load().reload().await
                 } else {
-                    core.load().reload_blocked_ips().await
+                    core.load().reload_ip_lists().await
                 };
                 match result {
                     Ok(result) => {
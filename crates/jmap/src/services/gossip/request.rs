@@ -11,7 +11,7 @@ use super::{EpochId, PeerStatus};
 use std::net::IpAddr;
 use utils::codec::leb128::Leb128_;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum Request {
     Ping(Vec<PeerStatus>),
     Pong(Vec<PeerStatus>),
@@ -23,10 +23,26 @@ impl Request {
     const PONG: u8 = 1;
     const LEAVE: u8 = 2;
 
+    // Messages with this flag set carry a protocol version byte after the flags,
+    // older nodes reject them instead of misreading the peer list
+    const FLAG_VERSIONED: u8 = 1 << 6;
+    const FLAG_IPV6: u8 = 1 << 7;
+
+    // Version 1 added the access tokens generation
+    const VERSION: u8 = 1;
+
     pub fn from_bytes(bytes: &[u8]) -> Option<Request> {
         let mut it = bytes.iter();
         let flags = it.next().copied()?;
-        let is_ipv6 = flags & (1 << 7) != 0;
+        let is_ipv6 = flags & Self::FLAG_IPV6 != 0;
+        let version = if flags & Self::FLAG_VERSIONED != 0 {
+            match it.next().copied()? {
+                version @ 1..=Self::VERSION => version,
+                _ => return None,
+            }
+        } else {
+            0
+        };
 
         let mut peers = Vec::with_capacity(bytes.len() / std::mem::size_of::<PeerStatus>());
         'outer: loop {
@@ -58,9 +74,10 @@ impl Request {
                 gen_config: it.next().copied()?,
                 gen_lists: it.next().copied()?,
                 gen_permissions: it.next().copied()?,
+                gen_tokens: if version >= 1 { it.next().copied()? } else { 0 },
             });
         }
-        match flags & !(1 << 7) {
+        match flags & !(Self::FLAG_IPV6 | Self::FLAG_VERSIONED) {
             0 => Request::Ping(peers),
             1 => Request::Pong(peers),
             2 => Request::Leave(peers),
@@ -86,10 +103,11 @@ impl Request {
 
         let is_ipv6 = peers.iter().any(|peer| peer.addr.is_ipv6());
         if is_ipv6 {
-            flag |= 1 << 7;
+            flag |= Self::FLAG_IPV6;
         }
 
-        bytes.push(flag);
+        bytes.push(flag | Self::FLAG_VERSIONED);
+        bytes.push(Self::VERSION);
 
         for peer in peers {
             if !is_ipv6 {
@@ -110,8 +128,84 @@ impl Request {
             bytes.push(peer.gen_config);
             bytes.push(peer.gen_lists);
             bytes.push(peer.gen_permissions);
+            bytes.push(peer.gen_tokens);
         }
 
         bytes
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{PeerStatus, Request};
+
+    fn peer(addr: &str, epoch: u64, generation: u8) -> PeerStatus {
+        PeerStatus {
+            addr: addr.parse().unwrap(),
+            epoch,
+            gen_config: generation,
+            gen_lists: generation + 1,
+            gen_permissions: generation + 2,
+            gen_tokens: generation + 3,
+        }
+    }
+
+    #[test]
+    fn encode_decode_request() {
+        for request in [
+            Request::Ping(vec![peer("10.0.0.1", 1, 0), peer("10.0.0.2", 300, 10)]),
+            Request::Pong(vec![peer("10.0.0.1", u64::MAX, 250)]),
+            Request::Leave(vec![peer("::1", 12345, 7), peer("10.0.0.3", 0, 1)]),
+        ] {
+            let expected = match &request {
+                Request::Leave(peers) => Request::Leave(
+                    peers
+                        .iter()
+                        .map(|peer| PeerStatus {
+                            addr: match peer.addr {
+                                IpAddr::V4(addr) => IpAddr::V6(addr.to_ipv6_mapped()),
+                                addr => addr,
+                            },
+                            ..peer.clone()
+                        })
+                        .collect(),
+                ),
+                Request::Ping(peers) => Request::Ping(peers.clone()),
+                Request::Pong(peers) => Request::Pong(peers.clone()),
+            };
+            assert_eq!(Request::from_bytes(&request.to_bytes()), Some(expected));
+        }
+    }
+
+    #[test]
+    fn decode_unversioned_request() {
+        // Messages from nodes that predate the access tokens generation
+        let mut bytes = vec![Request::PONG];
+        for (octets, epoch, generation) in [([10, 0, 0, 1], 1u8, 5u8), ([10, 0, 0, 2], 2, 9)] {
+            bytes.extend_from_slice(&octets);
+            bytes.push(epoch);
+            bytes.extend_from_slice(&[generation, generation + 1, generation + 2]);
+        }
+
+        assert_eq!(
+            Request::from_bytes(&bytes),
+            Some(Request::Pong(vec![
+                PeerStatus {
+                    gen_tokens: 0,
+                    ..peer("10.0.0.1", 1, 5)
+                },
+                PeerStatus {
+                    gen_tokens: 0,
+                    ..peer("10.0.0.2", 2, 9)
+                },
+            ]))
+        );
+
+        // Unknown protocol versions are rejected
+        let mut bytes = Request::Ping(vec![peer("10.0.0.1", 1, 0)]).to_bytes();
+        bytes[1] = Request::VERSION + 1;
+        assert_eq!(Request::from_bytes(&bytes), None);
+    }
+}
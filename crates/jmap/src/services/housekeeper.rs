@@ -6,10 +6,15 @@
 
 use std::{
     collections::BinaryHeap,
+    sync::Arc,
     time::{Duration, Instant, SystemTime},
 };
 
-use common::{config::telemetry::OtelMetrics, IPC_CHANNEL_BUFFER};
+use common::{
+    config::{feeds::FeedStates, health::HealthMonitor, telemetry::OtelMetrics},
+    scripts::training::migrate_tokenizer,
+    IPC_CHANNEL_BUFFER,
+};
 
 #[cfg(feature = "enterprise")]
 use common::telemetry::{
@@ -19,6 +24,7 @@ use common::telemetry::{
 
 use smtp::core::SMTP;
 use store::{
+    dispatch::replication::CHANGE_STREAM,
     write::{now, purge::PurgeStore},
     BlobStore, LookupStore, Store,
 };
@@ -57,6 +63,14 @@ enum ActionClass {
     Account,
     Store(usize),
     Acme(String),
+    IpFeed(usize),
+    IpExpiry,
+    ThreatFeed(usize),
+    RoleAddresses,
+    BayesTokenizer,
+    StoreHealth,
+    DiskSpace,
+    Replication,
     OtelMetrics,
     #[cfg(feature = "enterprise")]
     InternalMetrics,
@@ -74,6 +88,8 @@ struct Queue {
 
 #[cfg(feature = "enterprise")]
 const METRIC_ALERTS_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const IP_EXPIRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const ROLE_ADDRESS_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
 
 pub fn spawn_housekeeper(core: JmapInstance, mut rx: mpsc::Receiver<Event>) {
     tokio::spawn(async move {
@@ -105,6 +121,22 @@ pub fn spawn_housekeeper(core: JmapInstance, mut rx: mpsc::Receiver<Event>) {
                 );
             }
 
+            // IP feed imports
+            for (idx, feed) in core_.network.ip_feeds.iter().enumerate() {
+                queue.schedule(
+                    Instant::now() + feed.frequency.time_to_next(),
+                    ActionClass::IpFeed(idx),
+                );
+            }
+
+            // Expired IP list entries
+            queue.schedule(Instant::now() + IP_EXPIRY_INTERVAL, ActionClass::IpExpiry);
+
+            // Threat feeds
+            for idx in 0..core_.storage.feeds.len() {
+                queue.schedule(Instant::now(), ActionClass::ThreatFeed(idx));
+            }
+
             // OTEL Push Metrics
             if let Some(otel) = &core_.metrics.otel {
                 OtelMetrics::enable_errors();
@@ -114,6 +146,33 @@ pub fn spawn_housekeeper(core: JmapInstance, mut rx: mpsc::Receiver<Event>) {
             // Calculate expensive metrics
             queue.schedule(Instant::now(), ActionClass::CalculateMetrics);
 
+            // Required role addresses
+            queue.schedule(Instant::now(), ActionClass::RoleAddresses);
+
+            // Spam filter tokenizer settings
+            queue.schedule(Instant::now(), ActionClass::BayesTokenizer);
+
+            // Store health watchdog
+            if core_.storage.health.enable {
+                queue.schedule(
+                    Instant::now() + core_.storage.health.interval,
+                    ActionClass::StoreHealth,
+                );
+            }
+
+            // Disk space watchdog
+            if core_.storage.disk.enable {
+                queue.schedule(Instant::now(), ActionClass::DiskSpace);
+            }
+
+            // Change stream shipping
+            if core_.storage.replication.is_primary() {
+                queue.schedule(
+                    Instant::now() + core_.storage.replication.interval,
+                    ActionClass::Replication,
+                );
+            }
+
             // Add all ACME renewals to heap
             for provider in core_.tls.acme_providers.values() {
                 match core_.init_acme(provider).await {
@@ -163,6 +222,14 @@ pub fn spawn_housekeeper(core: JmapInstance, mut rx: mpsc::Receiver<Event>) {
         let metrics_history = SharedMetricHistory::default();
         let mut next_metric_update = Instant::now();
 
+        // Threat feed ETags and staleness
+        let feed_states = Arc::new(FeedStates::default());
+
+        // Store error rates and latency
+        let health_monitor = HealthMonitor::default();
+
+        // Whether the standby lag alert was raised
+
         loop {
             match tokio::time::timeout(queue.wake_up_time(), rx.recv()).await {
                 Ok(Some(event)) => match event {
@@ -183,6 +250,53 @@ pub fn spawn_housekeeper(core: JmapInstance, mut rx: mpsc::Receiver<Event>) {
                             _ => {}
                         }
 
+                        // Reload IP feed imports
+                        for (idx, feed) in core_.network.ip_feeds.iter().enumerate() {
+                            if !queue.has_action(&ActionClass::IpFeed(idx)) {
+                                queue.schedule(
+                                    Instant::now() + feed.frequency.time_to_next(),
+                                    ActionClass::IpFeed(idx),
+                                );
+                            }
+                        }
+
+                        // Reload threat feeds
+                        for idx in 0..core_.storage.feeds.len() {
+                            if !queue.has_action(&ActionClass::ThreatFeed(idx)) {
+                                queue.schedule(Instant::now(), ActionClass::ThreatFeed(idx));
+                            }
+                        }
+
+                        // Tokenizer settings might have changed
+                        if !queue.has_action(&ActionClass::BayesTokenizer) {
+                            queue.schedule(Instant::now(), ActionClass::BayesTokenizer);
+                        }
+
+                        // Reload store health watchdog
+                        if core_.storage.health.enable
+                            && !queue.has_action(&ActionClass::StoreHealth)
+                        {
+                            queue.schedule(
+                                Instant::now() + core_.storage.health.interval,
+                                ActionClass::StoreHealth,
+                            );
+                        }
+
+                        // Reload disk space watchdog
+                        if core_.storage.disk.enable && !queue.has_action(&ActionClass::DiskSpace) {
+                            queue.schedule(Instant::now(), ActionClass::DiskSpace);
+                        }
+
+                        // Reload change stream shipping
+                        if core_.storage.replication.is_primary()
+                            && !queue.has_action(&ActionClass::Replication)
+                        {
+                            queue.schedule(
+                                Instant::now() + core_.storage.replication.interval,
+                                ActionClass::Replication,
+                            );
+                        }
+
                         // SPDX-SnippetBegin
                         // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
                         // SPDX-License-Identifier: LicenseRef-SEL
@@ -242,6 +356,9 @@ pub fn spawn_housekeeper(core: JmapInstance, mut rx: mpsc::Receiver<Event>) {
                         queue.remove_action(&action);
                         queue.schedule(renew_at, action);
                     }
+                    Event::Purge(_) if core.core.load().storage.maintenance.is_enabled() => {
+                        // Purges are paused while the server is in maintenance mode
+                    }
                     Event::Purge(purge) => match purge {
                         PurgeType::Data(store) => {
                             // SPDX-SnippetBegin
@@ -385,11 +502,17 @@ pub fn spawn_housekeeper(core: JmapInstance, mut rx: mpsc::Receiver<Event>) {
                                 });
                             }
                             ActionClass::Account => {
-                                let jmap = JMAP::from(core.clone());
-                                tokio::spawn(async move {
-                                    trc::event!(Housekeeper(HousekeeperEvent::PurgeAccounts));
-                                    jmap.purge_accounts().await;
-                                });
+                                // Standby replicas receive the purges from the primary,
+                                // and purges are paused during maintenance
+                                if !core_.storage.replication.is_standby()
+                                    && !core_.storage.maintenance.is_enabled()
+                                {
+                                    let jmap = JMAP::from(core.clone());
+                                    tokio::spawn(async move {
+                                        trc::event!(Housekeeper(HousekeeperEvent::PurgeAccounts));
+                                        jmap.purge_accounts().await;
+                                    });
+                                }
                                 queue.schedule(
                                     Instant::now()
                                         + core_.jmap.account_purge_frequency.time_to_next(),
@@ -419,6 +542,11 @@ pub fn spawn_housekeeper(core: JmapInstance, mut rx: mpsc::Receiver<Event>) {
                                         Instant::now() + schedule.cron.time_to_next(),
                                         ActionClass::Store(idx),
                                     );
+                                    if core_.storage.replication.is_standby()
+                                        || core_.storage.maintenance.is_enabled()
+                                    {
+                                        continue;
+                                    }
                                     tokio::spawn(async move {
                                         let (class, result) = match schedule.store {
                                             PurgeStore::Data(store) => {
@@ -450,6 +578,240 @@ pub fn spawn_housekeeper(core: JmapInstance, mut rx: mpsc::Receiver<Event>) {
                                     });
                                 }
                             }
+                            ActionClass::IpFeed(idx) => {
+                                if let Some(feed) = core_.network.ip_feeds.get(idx).cloned() {
+                                    queue.schedule(
+                                        Instant::now() + feed.frequency.time_to_next(),
+                                        ActionClass::IpFeed(idx),
+                                    );
+
+                                    let core = core.clone();
+                                    tokio::spawn(async move {
+                                        let core_ = core.core.load_full();
+                                        match core_.import_ip_feed(&feed).await {
+                                            Ok(_) => {
+                                                reload_ip_lists(&core).await;
+                                            }
+                                            Err(err) => {
+                                                trc::error!(err
+                                                    .details("Failed to import IP feed.")
+                                                    .id(feed.id));
+                                            }
+                                        }
+                                    });
+                                }
+                            }
+                            ActionClass::ThreatFeed(idx) => {
+                                if let Some(feed) = core_.storage.feeds.get(idx).cloned() {
+                                    queue.schedule(
+                                        Instant::now() + feed.frequency,
+                                        ActionClass::ThreatFeed(idx),
+                                    );
+
+                                    let feed_states = feed_states.clone();
+                                    tokio::spawn(async move {
+                                        if let Err(err) = feed_states.refresh(&feed).await {
+                                            trc::error!(err
+                                                .details("Failed to refresh feed.")
+                                                .id(feed.id.clone()));
+                                        }
+                                    });
+                                }
+                            }
+                            ActionClass::IpExpiry => {
+                                queue.schedule(
+                                    Instant::now() + IP_EXPIRY_INTERVAL,
+                                    ActionClass::IpExpiry,
+                                );
+
+                                let core = core.clone();
+                                tokio::spawn(async move {
+                                    let core_ = core.core.load_full();
+                                    match core_.purge_expired_ip_entries().await {
+                                        Ok(0) => {}
+                                        Ok(_) => {
+                                            reload_ip_lists(&core).await;
+                                        }
+                                        Err(err) => {
+                                            trc::error!(err.details(
+                                                "Failed to purge expired IP list entries."
+                                            ));
+                                        }
+                                    }
+                                });
+                            }
+                            ActionClass::RoleAddresses => {
+                                queue.schedule(
+                                    Instant::now() + ROLE_ADDRESS_INTERVAL,
+                                    ActionClass::RoleAddresses,
+                                );
+
+                                let core = core_.clone();
+                                tokio::spawn(async move {
+                                    match core.missing_role_addresses().await {
+                                        Ok(addresses) => {
+                                            for address in addresses {
+                                                trc::event!(
+                                                    Housekeeper(
+                                                        HousekeeperEvent::RoleAddressMissing
+                                                    ),
+                                                    To = address,
+                                                );
+                                            }
+                                        }
+                                        Err(err) => {
+                                            trc::error!(err.details(
+                                                "Failed to verify required role addresses."
+                                            ));
+                                        }
+                                    }
+                                });
+                            }
+                            ActionClass::BayesTokenizer => {
+                                let core_ = core_.clone();
+                                let smtp_inner = core.smtp_inner.clone();
+                                tokio::spawn(async move {
+                                    let result = match core_.spam_filter_store(None).await {
+                                        Ok(store) => {
+                                            migrate_tokenizer(
+                                                &store,
+                                                &smtp_inner.script_cache.bayes_cache,
+                                                &core_.sieve.bayes,
+                                            )
+                                            .await
+                                        }
+                                        Err(err) => Err(err),
+                                    };
+
+                                    if let Err(err) = result {
+                                        trc::error!(err.details(
+                                            "Failed to migrate spam filter tokenizer settings."
+                                        ));
+                                    }
+                                });
+                            }
+                            ActionClass::StoreHealth => {
+                                let health = &core_.storage.health;
+                                if health.enable {
+                                    queue.schedule(
+                                        Instant::now() + health.interval,
+                                        ActionClass::StoreHealth,
+                                    );
+                                }
+
+                                if let Some(transition) = health_monitor.check(health) {
+                                    trc::event!(
+                                        Store(transition.event_type()),
+                                        Id = transition.store,
+                                        Details =
+                                            format!("Error rate {:.1}%", transition.error_rate),
+                                        Elapsed = transition.latency,
+                                    );
+
+                                    if let Some(alert) = &health.alert {
+                                        let smtp = SMTP {
+                                            core: core_.clone(),
+                                            inner: core.smtp_inner.clone(),
+                                        };
+                                        let from = alert.from_addr.clone();
+                                        let to = alert.to.clone();
+                                        let message = transition.build_alert(alert);
+                                        tokio::spawn(async move {
+                                            smtp.send_autogenerated(
+                                                from,
+                                                to.into_iter(),
+                                                message,
+                                                None,
+                                                0,
+                                            )
+                                            .await;
+                                        });
+                                    }
+                                }
+                            }
+                            ActionClass::DiskSpace => {
+                                let disk = &core_.storage.disk;
+                                if disk.enable {
+                                    queue.schedule(
+                                        Instant::now() + disk.interval,
+                                        ActionClass::DiskSpace,
+                                    );
+                                }
+
+                                if let Some(transition) = disk.check() {
+                                    trc::event!(
+                                        Store(transition.event_type()),
+                                        Path = transition.path.to_string_lossy().into_owned(),
+                                        Size = transition.usage.available,
+                                        Total = transition.usage.total,
+                                    );
+
+                                    if let Some(alert) = &disk.alert {
+                                        let smtp = SMTP {
+                                            core: core_.clone(),
+                                            inner: core.smtp_inner.clone(),
+                                        };
+                                        let from = alert.from_addr.clone();
+                                        let to = alert.to.clone();
+                                        let message = transition.build_alert(alert);
+                                        tokio::spawn(async move {
+                                            smtp.send_autogenerated(
+                                                from,
+                                                to.into_iter(),
+                                                message,
+                                                None,
+                                                0,
+                                            )
+                                            .await;
+                                        });
+                                    }
+                                }
+                            }
+                            ActionClass::Replication => {
+                                let replication = &core_.storage.replication;
+                                if !replication.is_primary() {
+                                    continue;
+                                }
+                                queue.schedule(
+                                    Instant::now() + replication.interval,
+                                    ActionClass::Replication,
+                                );
+
+                                if CHANGE_STREAM.try_start_shipping() {
+                                    let core = core_.clone();
+                                    tokio::spawn(async move {
+                                        let replication = &core.storage.replication;
+
+                                        // Alert once while the standby is lagging behind
+                                        match CHANGE_STREAM.status().await {
+                                            Ok(status) => {
+                                                let lagging = replication.lag_alert.is_some_and(
+                                                    |lag_alert| status.lag() >= lag_alert.as_secs(),
+                                                );
+                                                if CHANGE_STREAM.set_lagging(lagging) {
+                                                    trc::event!(
+                                                        Store(trc::StoreEvent::ReplicationLag),
+                                                        Elapsed = Duration::from_secs(status.lag()),
+                                                        Total = status.pending,
+                                                        Size = status.pending_size,
+                                                    );
+                                                }
+                                            }
+                                            Err(err) => {
+                                                trc::error!(err);
+                                            }
+                                        }
+
+                                        if let Err(err) = replication
+                                            .ship(&core.storage.lookup, &core.storage.blob)
+                                            .await
+                                        {
+                                            trc::error!(err);
+                                        }
+                                        CHANGE_STREAM.stop_shipping();
+                                    });
+                                }
+                            }
                             ActionClass::OtelMetrics => {
                                 if let Some(otel) = &core_.metrics.otel {
                                     queue.schedule(
@@ -636,6 +998,19 @@ pub fn spawn_housekeeper(core: JmapInstance, mut rx: mpsc::Receiver<Event>) {
     });
 }
 
+// Rebuilds the core so changes to the IP lists apply to all listeners
+async fn reload_ip_lists(core: &JmapInstance) {
+    let core = core.core.load_full();
+    match core.reload_ip_lists().await {
+        Ok(_) => {
+            core.network.blocked_ips.increment_version();
+        }
+        Err(err) => {
+            trc::error!(err.details("Failed to reload IP lists."));
+        }
+    }
+}
+
 impl Queue {
     pub fn schedule(&mut self, due: Instant, event: ActionClass) {
         trc::event!(
@@ -6,11 +6,16 @@
 
 use common::{DeliveryResult, IngestMessage};
 use directory::Permission;
-use jmap_proto::types::{state::StateChange, type_state::DataType};
+use jmap_proto::types::{
+    state::{StateChange, SyncPriority},
+    type_state::DataType,
+};
 use mail_parser::MessageParser;
 use store::ahash::AHashMap;
 
 use crate::{
+    activity::ActivityType,
+    calendar_event::itip::itip_from_message,
     email::ingest::{IngestEmail, IngestSource},
     mailbox::INBOX_ID,
     JMAP,
@@ -21,9 +26,7 @@ impl JMAP {
         // Read message
         let raw_message = match self
             .core
-            .storage
-            .blob
-            .get_blob(message.message_blob.as_slice(), 0..usize::MAX)
+            .get_queued_message(message.message_blob.as_slice(), 0..usize::MAX)
             .await
         {
             Ok(Some(raw_message)) => raw_message,
@@ -55,6 +58,19 @@ impl JMAP {
             }
         };
 
+        // Look for calendar scheduling messages
+        let itip = if self.core.jmap.calendar_imip
+            && raw_message
+                .windows(13)
+                .any(|window| window.eq_ignore_ascii_case(b"text/calendar"))
+        {
+            MessageParser::new()
+                .parse(&raw_message)
+                .and_then(|message| itip_from_message(&message))
+        } else {
+            None
+        };
+
         // Obtain the UIDs for each recipient
         let mut recipients = Vec::with_capacity(message.recipients.len());
         let mut deliver_names = AHashMap::with_capacity(message.recipients.len());
@@ -83,6 +99,17 @@ impl JMAP {
 
         // Deliver to each recipient
         for (uid, (status, rcpt)) in &mut deliver_names {
+            // Defer delivery while the account is in maintenance mode
+            if let Err(err) = self.core.storage.maintenance.assert_writable(*uid) {
+                trc::error!(err
+                    .ctx(trc::Key::To, rcpt.to_string())
+                    .span_id(message.session_id));
+                *status = DeliveryResult::TemporaryFailure {
+                    reason: self.core.storage.maintenance.message().to_string().into(),
+                };
+                continue;
+            }
+
             // Obtain access token
             let result = match self
                 .core
@@ -102,6 +129,7 @@ impl JMAP {
                                 &raw_message,
                                 &message.sender_address,
                                 rcpt,
+                                &message.verdict,
                                 message.session_id,
                                 active_script,
                             )
@@ -133,14 +161,57 @@ impl JMAP {
                 Ok(ingested_message) => {
                     // Notify state change
                     if ingested_message.change_id != u64::MAX {
+                        let (priority, preview_only) = self
+                            .mailbox_sync_hints(*uid, &ingested_message.mailbox_ids)
+                            .await
+                            .unwrap_or_else(|err| {
+                                trc::error!(err
+                                    .caused_by(trc::location!())
+                                    .span_id(message.session_id));
+                                (SyncPriority::Normal, false)
+                            });
+
                         self.broadcast_state_change(
                             StateChange::new(*uid)
                                 .with_change(DataType::EmailDelivery, ingested_message.change_id)
                                 .with_change(DataType::Email, ingested_message.change_id)
                                 .with_change(DataType::Mailbox, ingested_message.change_id)
-                                .with_change(DataType::Thread, ingested_message.change_id),
+                                .with_change(DataType::Thread, ingested_message.change_id)
+                                .with_sync_hints(priority, preview_only),
                         )
                         .await;
+
+                        // Record activity
+                        if let Err(err) = self
+                            .activity_log(*uid, ActivityType::Received, [ingested_message.id])
+                            .await
+                        {
+                            trc::error!(err.span_id(message.session_id));
+                        }
+
+                        // Update the calendar of the recipient
+                        if let Some(itip) = &itip {
+                            match self
+                                .calendar_event_ingest_itip(
+                                    *uid,
+                                    itip,
+                                    &message.sender_address,
+                                    rcpt,
+                                )
+                                .await
+                            {
+                                Ok(Some(state_change)) => {
+                                    self.broadcast_state_change(state_change).await;
+                                }
+                                Ok(None) => (),
+                                Err(err) => {
+                                    trc::error!(err
+                                        .account_id(*uid)
+                                        .span_id(message.session_id)
+                                        .details("Failed to process calendar scheduling message"));
+                                }
+                            }
+                        }
                     }
                 }
                 Err(err) => {
@@ -7,7 +7,11 @@
 use std::time::{Duration, Instant, SystemTime};
 
 use common::IPC_CHANNEL_BUFFER;
-use jmap_proto::types::{id::Id, state::StateChange, type_state::DataType};
+use jmap_proto::types::{
+    id::Id,
+    state::{StateChange, SyncPriority},
+    type_state::DataType,
+};
 use store::ahash::AHashMap;
 use tokio::sync::mpsc;
 use trc::ServerEvent;
@@ -46,15 +50,20 @@ struct Subscriber {
 
 #[derive(Debug)]
 pub enum SubscriberType {
-    Ipc { tx: mpsc::Sender<StateChange> },
-    Push { expires: u64 },
+    Ipc {
+        tx: mpsc::Sender<StateChange>,
+    },
+    Push {
+        expires: u64,
+        priority: SyncPriority,
+    },
 }
 
 impl Subscriber {
     fn is_valid(&self, current_time: u64) -> bool {
         match &self.subscription {
             SubscriberType::Ipc { tx } => !tx.is_closed(),
-            SubscriberType::Push { expires } => expires > &current_time,
+            SubscriberType::Push { expires, .. } => expires > &current_time,
         }
     }
 }
@@ -193,6 +202,7 @@ pub fn spawn_state_manager(core: JmapInstance, mut change_rx: mpsc::Receiver<Eve
                             .map(|d| d.as_secs())
                             .unwrap_or(0);
                         let mut push_ids = Vec::new();
+                        let mut preview_ids = Vec::new();
 
                         for (owner_account_id, allowed_types) in shared_accounts {
                             if let Some(subscribers) = subscribers.get(owner_account_id) {
@@ -218,6 +228,9 @@ pub fn spawn_state_manager(core: JmapInstance, mut change_rx: mpsc::Receiver<Eve
                                                             StateChange {
                                                                 account_id: state_change.account_id,
                                                                 types,
+                                                                priority: state_change.priority,
+                                                                preview_only: state_change
+                                                                    .preview_only,
                                                             },
                                                             SEND_TIMEOUT,
                                                         )
@@ -232,13 +245,28 @@ pub fn spawn_state_manager(core: JmapInstance, mut change_rx: mpsc::Receiver<Eve
                                                     }
                                                 });
                                             }
-                                            SubscriberType::Push { expires }
+                                            SubscriberType::Push { expires, priority }
                                                 if expires > &current_time =>
                                             {
-                                                push_ids.push(Id::from_parts(
+                                                // Changes to mailboxes below the priority of the
+                                                // subscription are not pushed, constrained clients
+                                                // are only notified of deliveries to preview-only
+                                                // mailboxes.
+                                                let id = Id::from_parts(
                                                     *owner_account_id,
                                                     (*subscriber_id).into(),
-                                                ));
+                                                );
+                                                if state_change.priority >= *priority {
+                                                    if *priority == SyncPriority::Low
+                                                        || !state_change.preview_only
+                                                    {
+                                                        push_ids.push(id);
+                                                    } else if types.iter().any(|(state_type, _)| {
+                                                        *state_type == DataType::EmailDelivery
+                                                    }) {
+                                                        preview_ids.push(id);
+                                                    }
+                                                }
                                             }
                                             _ => {
                                                 purge_needed = true;
@@ -249,20 +277,30 @@ pub fn spawn_state_manager(core: JmapInstance, mut change_rx: mpsc::Receiver<Eve
                             }
                         }
 
-                        if !push_ids.is_empty()
-                            && push_tx
-                                .send(crate::push::Event::Push {
-                                    ids: push_ids,
-                                    state_change,
-                                })
-                                .await
-                                .is_err()
+                        let preview_change = StateChange {
+                            types: state_change
+                                .types
+                                .iter()
+                                .filter(|(state_type, _)| *state_type == DataType::EmailDelivery)
+                                .copied()
+                                .collect(),
+                            ..state_change.clone()
+                        };
+                        for (ids, state_change) in
+                            [(push_ids, state_change), (preview_ids, preview_change)]
                         {
-                            trc::event!(
-                                Server(ServerEvent::ThreadError),
-                                Details = "Error sending push updates.",
-                                CausedBy = trc::location!()
-                            );
+                            if !ids.is_empty()
+                                && push_tx
+                                    .send(crate::push::Event::Push { ids, state_change })
+                                    .await
+                                    .is_err()
+                            {
+                                trc::event!(
+                                    Server(ServerEvent::ThreadError),
+                                    Details = "Error sending push updates.",
+                                    CausedBy = trc::location!()
+                                );
+                            }
                         }
                     }
                 }
@@ -323,6 +361,7 @@ pub fn spawn_state_manager(core: JmapInstance, mut change_rx: mpsc::Receiver<Eve
                                             types: verified.types,
                                             subscription: SubscriberType::Push {
                                                 expires: verified.expires,
+                                                priority: verified.priority,
                                             },
                                         },
                                     );
@@ -0,0 +1,71 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::config::scripts::{SIEVE_ADDRBOOK_DEFAULT_LIST, SIEVE_ADDRBOOK_LIST};
+use sieve::MatchAs;
+use trc::SieveEvent;
+
+use crate::JMAP;
+
+impl JMAP {
+    pub async fn sieve_list_contains(
+        &self,
+        account_id: u32,
+        lists: Vec<String>,
+        values: Vec<String>,
+        match_as: MatchAs,
+        session_id: u64,
+    ) -> bool {
+        for list in lists {
+            if list == SIEVE_ADDRBOOK_LIST || list == SIEVE_ADDRBOOK_DEFAULT_LIST {
+                // Addresses in the account's contact cards
+                for value in &values {
+                    let address = value.trim();
+                    if address.is_empty() {
+                        continue;
+                    }
+                    match self.core.address_book_contains(account_id, address).await {
+                        Ok(true) => return true,
+                        Ok(false) => {}
+                        Err(err) => {
+                            trc::error!(err.span_id(session_id).caused_by(trc::location!()));
+                        }
+                    }
+                }
+            } else if let Some(store) = self
+                .core
+                .storage
+                .lookups
+                .get(&list)
+                .filter(|_| self.core.sieve.untrusted_ext_lists.contains(&list))
+            {
+                for value in &values {
+                    let key = if !matches!(match_as, MatchAs::Lowercase) {
+                        value.clone()
+                    } else {
+                        value.to_lowercase()
+                    };
+
+                    match store.key_exists(key.into_bytes()).await {
+                        Ok(true) => return true,
+                        Ok(false) => {}
+                        Err(err) => {
+                            trc::error!(err.span_id(session_id).caused_by(trc::location!()));
+                        }
+                    }
+                }
+            } else {
+                trc::event!(
+                    Sieve(SieveEvent::ListNotFound),
+                    SpanId = session_id,
+                    Details = list,
+                );
+            }
+        }
+
+        false
+    }
+}
@@ -20,7 +20,7 @@ use store::{
 
 use crate::{sieve::SeenIds, JMAP};
 
-use super::ActiveScript;
+use super::{set::ObjectBlobId, ActiveScript};
 
 impl JMAP {
     pub async fn sieve_script_get(
@@ -180,6 +180,56 @@ impl JMAP {
         }
     }
 
+    pub async fn sieve_scripts_size(
+        &self,
+        account_id: u32,
+        exclude_id: Option<u32>,
+    ) -> trc::Result<u64> {
+        let script_ids = self
+            .get_document_ids(account_id, Collection::SieveScript)
+            .await?
+            .unwrap_or_default();
+        if script_ids.is_empty() {
+            return Ok(0);
+        }
+
+        Ok(self
+            .get_properties::<Object<Value>, _, _>(
+                account_id,
+                Collection::SieveScript,
+                &script_ids,
+                Property::Value,
+            )
+            .await?
+            .into_iter()
+            .filter(|(document_id, _)| Some(*document_id) != exclude_id)
+            .filter_map(|(_, script)| {
+                script
+                    .blob_id()
+                    .and_then(|blob_id| blob_id.section.as_ref())
+                    .map(|section| section.size as u64)
+            })
+            .sum())
+    }
+
+    // Returns true if adding a script of the given size would exceed the
+    // total size of Sieve scripts allowed per account
+    pub async fn sieve_scripts_over_quota(
+        &self,
+        account_id: u32,
+        exclude_id: Option<u32>,
+        size: u64,
+    ) -> trc::Result<bool> {
+        let max_scripts_size = self.core.jmap.sieve_max_scripts_size;
+        if max_scripts_size > 0 {
+            self.sieve_scripts_size(account_id, exclude_id)
+                .await
+                .map(|total| total + size > max_scripts_size)
+        } else {
+            Ok(false)
+        }
+    }
+
     #[allow(clippy::blocks_in_conditions)]
     async fn sieve_script_compile(
         &self,
@@ -0,0 +1,536 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::borrow::Cow;
+
+use common::listener::stream::NullIo;
+use directory::{backend::internal::PrincipalField, QueryBy};
+use jmap_proto::types::{
+    collection::Collection, id::Id, keyword::Keyword, property::Property, state::StateChange,
+    type_state::DataType,
+};
+use mail_parser::MessageParser;
+use sieve::{Event, Input, Mailbox, Recipient};
+use smtp::core::{Session, SessionAddress};
+use store::write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder, Bincode, F_VALUE};
+use trc::{AddContext, SieveEvent};
+
+use crate::{
+    email::{
+        ingest::{IngestEmail, IngestSource},
+        metadata::MessageMetadata,
+        set::TagManager,
+    },
+    mailbox::{INBOX_ID, TRASH_ID},
+    JMAP,
+};
+
+use super::ingest::is_valid_role;
+
+const MAX_RETRIES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImapSieveCause {
+    Append,
+    Copy,
+    Flag,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImapSieveEvent {
+    pub account_id: u32,
+    pub user: String,
+    pub cause: ImapSieveCause,
+    pub mailbox_name: String,
+    pub document_ids: Vec<u32>,
+    pub changed_flags: Vec<String>,
+    pub session_id: u64,
+}
+
+struct SieveMessage<'x> {
+    pub raw_message: Cow<'x, [u8]>,
+    pub file_into: Vec<u32>,
+    pub flags: Vec<Keyword>,
+}
+
+impl JMAP {
+    pub async fn sieve_script_imap_event(&self, event: ImapSieveEvent) -> trc::Result<()> {
+        // Scripts are only executed on IMAP events when the account has an imapsieve script
+        let account_id = event.account_id;
+        let script = if let Some(script) = self
+            .sieve_script_get_by_name(account_id, &self.core.jmap.sieve_imapsieve_script)
+            .await
+            .caused_by(trc::location!())?
+        {
+            script
+        } else {
+            return Ok(());
+        };
+        let access_token = self
+            .core
+            .get_cached_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let mailbox_ids = self
+            .mailbox_get_or_create(account_id)
+            .await
+            .caused_by(trc::location!())?;
+
+        // Obtain account name and email
+        let (full_name, mail_from) = self
+            .core
+            .storage
+            .directory
+            .query(QueryBy::Id(account_id), false)
+            .await
+            .caused_by(trc::location!())?
+            .map(|mut p| {
+                (
+                    p.description().unwrap_or_else(|| p.name()).to_string(),
+                    p.take_str_array(PrincipalField::Emails)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .next(),
+                )
+            })
+            .unwrap_or_default();
+        let mail_from = mail_from.unwrap_or_else(|| event.user.clone());
+        let changed_flags = event.changed_flags.join(" ");
+        let mut last_change_id = None;
+        let mut out_messages = 0;
+
+        for document_id in &event.document_ids {
+            // Fetch message
+            let raw_message = if let Some(raw_message) = self
+                .get_property::<Bincode<MessageMetadata>>(
+                    account_id,
+                    Collection::Email,
+                    *document_id,
+                    &Property::BodyStructure,
+                )
+                .await?
+            {
+                if let Some(raw_message) = self
+                    .get_blob(&raw_message.inner.blob_hash, 0..usize::MAX)
+                    .await?
+                {
+                    raw_message
+                } else {
+                    continue;
+                }
+            } else {
+                continue;
+            };
+            let message = if let Some(message) = MessageParser::new().parse(&raw_message) {
+                message
+            } else {
+                continue;
+            };
+
+            // Create Sieve instance, spamtest and virustest verdicts are only
+            // available at delivery time
+            let mut instance = self.core.sieve.untrusted_runtime.filter_parsed(message);
+            instance.set_user_full_name(&full_name);
+            instance.set_user_address(&mail_from);
+            instance.set_env_variable("phase", "post");
+            instance.set_env_variable(
+                "imap.cause",
+                match event.cause {
+                    ImapSieveCause::Append => "APPEND",
+                    ImapSieveCause::Copy => "COPY",
+                    ImapSieveCause::Flag => "FLAG",
+                },
+            );
+            instance.set_env_variable("imap.mailbox", event.mailbox_name.clone());
+            instance.set_env_variable("imap.changedflags", changed_flags.clone());
+            instance.set_env_variable("imap.user", event.user.clone());
+            instance.set_env_variable("imap.email", mail_from.clone());
+
+            let mut input = Input::script(
+                self.core.jmap.sieve_imapsieve_script.clone(),
+                script.clone(),
+            );
+            let mut messages: Vec<SieveMessage> = vec![SieveMessage {
+                raw_message: raw_message.as_slice().into(),
+                file_into: Vec::new(),
+                flags: Vec::new(),
+            }];
+
+            while let Some(result) = instance.run(input) {
+                match result {
+                    Ok(result) => match result {
+                        Event::IncludeScript { name, .. } => match &name {
+                            sieve::Script::Personal(name_) => {
+                                if let Ok(Some(script)) =
+                                    self.sieve_script_get_by_name(account_id, name_).await
+                                {
+                                    input = Input::script(name, script);
+                                } else {
+                                    input = false.into();
+                                }
+                            }
+                            sieve::Script::Global(name_) => {
+                                if let Some(script) = self
+                                    .core
+                                    .get_untrusted_sieve_script(name_, event.session_id)
+                                {
+                                    input = Input::script(name, script.clone());
+                                } else {
+                                    input = false.into();
+                                }
+                            }
+                        },
+                        Event::MailboxExists { mailboxes, .. } => {
+                            let mut result = !mailboxes.is_empty();
+                            for mailbox in mailboxes {
+                                let exists = match mailbox {
+                                    Mailbox::Name(name) => matches!(
+                                        self.mailbox_get_by_name(account_id, &name).await,
+                                        Ok(Some(_))
+                                    ),
+                                    Mailbox::Id(id) => matches!(
+                                        Id::from_bytes(id.as_bytes()),
+                                        Some(id) if mailbox_ids.contains(id.document_id())
+                                    ),
+                                };
+                                if !exists {
+                                    result = false;
+                                    break;
+                                }
+                            }
+                            input = result.into();
+                        }
+                        Event::Keep { .. } => {
+                            // The message is already stored in the mailbox
+                            input = true.into();
+                        }
+                        Event::Discard => {
+                            // RFC 6785 - Discarding a message flags it as \Deleted
+                            match self.sieve_flag_deleted(account_id, *document_id).await {
+                                Ok(Some(change_id)) => {
+                                    trc::event!(
+                                        Sieve(SieveEvent::ActionDiscard),
+                                        AccountId = account_id,
+                                        DocumentId = *document_id,
+                                        SpanId = event.session_id
+                                    );
+                                    last_change_id = Some(change_id);
+                                }
+                                Ok(None) => {}
+                                Err(err) => {
+                                    trc::error!(err.span_id(event.session_id));
+                                }
+                            }
+                            input = true.into();
+                        }
+                        Event::Reject { .. } => {
+                            // RFC 6785 - Reject and ereject are not allowed in IMAP events
+                            trc::event!(
+                                Sieve(SieveEvent::NotSupported),
+                                Details = "Reject is not allowed in IMAP events.",
+                                SpanId = event.session_id
+                            );
+                            input = true.into();
+                        }
+                        Event::FileInto {
+                            folder,
+                            flags,
+                            mailbox_id,
+                            special_use,
+                            create,
+                            message_id,
+                        } => {
+                            let mut target_id = mailbox_id
+                                .and_then(|m| Id::from_bytes(m.as_bytes()))
+                                .map(|id| id.document_id())
+                                .filter(|id| mailbox_ids.contains(*id))
+                                .unwrap_or(u32::MAX);
+
+                            // Find mailbox by role
+                            if let Some(special_use) = special_use {
+                                if target_id == u32::MAX {
+                                    let role = special_use.to_ascii_lowercase();
+                                    if role == "inbox" {
+                                        target_id = INBOX_ID;
+                                    } else if role == "trash" {
+                                        target_id = TRASH_ID;
+                                    } else if is_valid_role(&role) {
+                                        if let Ok(Some(mailbox_id)) =
+                                            self.mailbox_get_by_role(account_id, &role).await
+                                        {
+                                            target_id = mailbox_id;
+                                        }
+                                    }
+                                }
+                            }
+
+                            // Find mailbox by name
+                            if target_id == u32::MAX {
+                                if !create {
+                                    if let Ok(Some(document_id)) =
+                                        self.mailbox_get_by_name(account_id, &folder).await
+                                    {
+                                        target_id = document_id;
+                                    }
+                                } else if let Ok(Some((document_id, changes))) =
+                                    self.mailbox_create_path(account_id, &folder).await
+                                {
+                                    target_id = document_id;
+                                    if let Some(change_id) = changes {
+                                        last_change_id = Some(change_id);
+                                    }
+                                }
+                            }
+
+                            match messages.get_mut(message_id) {
+                                Some(message) if target_id != u32::MAX => {
+                                    message.flags = flags.into_iter().map(Keyword::from).collect();
+                                    if !message.file_into.contains(&target_id) {
+                                        message.file_into.push(target_id);
+                                    }
+                                }
+                                Some(_) => {
+                                    trc::event!(
+                                        Sieve(SieveEvent::UnexpectedError),
+                                        Details = "Mailbox not found.",
+                                        MailboxName = folder,
+                                        SpanId = event.session_id
+                                    );
+                                }
+                                None => {
+                                    trc::event!(
+                                        Sieve(SieveEvent::UnexpectedError),
+                                        Details = "Unknown message id.",
+                                        MessageId = message_id,
+                                        SpanId = event.session_id
+                                    );
+                                }
+                            }
+                            input = true.into();
+                        }
+                        Event::SendMessage {
+                            recipient,
+                            message_id,
+                            ..
+                        } => {
+                            input = true.into();
+                            if let Some(message) = messages.get(message_id) {
+                                let recipients = match recipient {
+                                    Recipient::Address(rcpt) => vec![SessionAddress::new(rcpt)],
+                                    Recipient::Group(rcpts) => {
+                                        rcpts.into_iter().map(SessionAddress::new).collect()
+                                    }
+                                    Recipient::List(_) => {
+                                        // Not yet implemented
+                                        continue;
+                                    }
+                                };
+
+                                if out_messages >= self.core.jmap.sieve_imapsieve_max_messages {
+                                    trc::event!(
+                                        Sieve(SieveEvent::QuotaExceeded),
+                                        From = mail_from.clone(),
+                                        To = recipients
+                                            .iter()
+                                            .map(|r| trc::Value::String(r.address_lcase.clone()))
+                                            .collect::<Vec<_>>(),
+                                        Limit = self.core.jmap.sieve_imapsieve_max_messages,
+                                        SpanId = event.session_id,
+                                    );
+                                } else if message.raw_message.len() <= self.core.jmap.mail_max_size
+                                {
+                                    out_messages += 1;
+                                    trc::event!(
+                                        Sieve(SieveEvent::SendMessage),
+                                        From = mail_from.clone(),
+                                        To = recipients
+                                            .iter()
+                                            .map(|r| trc::Value::String(r.address_lcase.clone()))
+                                            .collect::<Vec<_>>(),
+                                        Size = message.raw_message.len(),
+                                        SpanId = event.session_id
+                                    );
+
+                                    Session::<NullIo>::sieve(
+                                        self.smtp.clone(),
+                                        SessionAddress::new(mail_from.clone()),
+                                        recipients,
+                                        message.raw_message.to_vec(),
+                                        0,
+                                    )
+                                    .await
+                                    .queue_message()
+                                    .await;
+                                } else {
+                                    trc::event!(
+                                        Sieve(SieveEvent::MessageTooLarge),
+                                        From = mail_from.clone(),
+                                        To = recipients
+                                            .iter()
+                                            .map(|r| trc::Value::String(r.address_lcase.clone()))
+                                            .collect::<Vec<_>>(),
+                                        Size = message.raw_message.len(),
+                                        Limit = self.core.jmap.mail_max_size,
+                                        SpanId = event.session_id,
+                                    );
+                                }
+                            }
+                        }
+                        Event::CreatedMessage { message, .. } => {
+                            messages.push(SieveMessage {
+                                raw_message: message.into(),
+                                file_into: Vec::new(),
+                                flags: Vec::new(),
+                            });
+                            input = true.into();
+                        }
+                        Event::ListContains {
+                            lists,
+                            values,
+                            match_as,
+                        } => {
+                            input = self
+                                .sieve_list_contains(
+                                    account_id,
+                                    lists,
+                                    values,
+                                    match_as,
+                                    event.session_id,
+                                )
+                                .await
+                                .into();
+                        }
+                        Event::DuplicateId { .. }
+                        | Event::Function { .. }
+                        | Event::Notify { .. }
+                        | Event::SetEnvelope { .. } => {
+                            // Not allowed
+                            input = false.into();
+                        }
+                    },
+                    Err(err) => {
+                        trc::event!(
+                            Sieve(SieveEvent::RuntimeError),
+                            Reason = err.to_string(),
+                            SpanId = event.session_id
+                        );
+
+                        input = true.into();
+                    }
+                }
+            }
+
+            // File messages into the requested mailboxes
+            for sieve_message in messages {
+                if sieve_message.file_into.is_empty() {
+                    continue;
+                }
+
+                match self
+                    .email_ingest(IngestEmail {
+                        raw_message: &sieve_message.raw_message,
+                        message: MessageParser::new().parse(sieve_message.raw_message.as_ref()),
+                        resource: access_token.as_resource_token(),
+                        mailbox_ids: sieve_message.file_into,
+                        keywords: sieve_message.flags,
+                        received_at: None,
+                        source: IngestSource::Imap,
+                        encrypt: self.core.jmap.encrypt && self.core.jmap.encrypt_append,
+                        session_id: event.session_id,
+                    })
+                    .await
+                {
+                    Ok(email) => {
+                        last_change_id = Some(email.change_id);
+                    }
+                    Err(err) => {
+                        trc::error!(err.span_id(event.session_id));
+                    }
+                }
+            }
+        }
+
+        // Broadcast changes
+        if let Some(change_id) = last_change_id {
+            self.broadcast_state_change(
+                StateChange::new(account_id)
+                    .with_change(DataType::Email, change_id)
+                    .with_change(DataType::Mailbox, change_id)
+                    .with_change(DataType::Thread, change_id),
+            )
+            .await;
+        }
+
+        Ok(())
+    }
+
+    async fn sieve_flag_deleted(
+        &self,
+        account_id: u32,
+        document_id: u32,
+    ) -> trc::Result<Option<u64>> {
+        let mut try_count = 0;
+
+        loop {
+            // Obtain current keywords
+            let (mut keywords, thread_id) = if let (Some(keywords), Some(thread_id)) = (
+                self.get_property::<HashedValue<Vec<Keyword>>>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::Keywords,
+                )
+                .await?,
+                self.get_property::<u32>(
+                    account_id,
+                    Collection::Email,
+                    document_id,
+                    Property::ThreadId,
+                )
+                .await?,
+            ) {
+                (TagManager::new(keywords), thread_id)
+            } else {
+                return Ok(None);
+            };
+
+            keywords.update(Keyword::Deleted, true);
+            if !keywords.has_changes() {
+                return Ok(None);
+            }
+
+            // Write changes
+            let change_id = self
+                .assign_change_id(account_id)
+                .await
+                .caused_by(trc::location!())?;
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::Email)
+                .update_document(document_id);
+            keywords.update_batch(&mut batch, Property::Keywords);
+            batch.value(Property::Cid, change_id, F_VALUE);
+            match self.write_batch(batch).await {
+                Ok(_) => {
+                    let mut changelog = ChangeLogBuilder::with_change_id(change_id);
+                    changelog.log_update(Collection::Email, Id::from_parts(thread_id, document_id));
+                    return self
+                        .commit_changes(account_id, changelog)
+                        .await
+                        .caused_by(trc::location!())
+                        .map(Some);
+                }
+                Err(err) if err.is_assertion_failure() && try_count < MAX_RETRIES => {
+                    try_count += 1;
+                }
+                Err(err) => {
+                    return Err(err.caused_by(trc::location!()));
+                }
+            }
+        }
+    }
+}
@@ -6,12 +6,15 @@
 
 use std::borrow::Cow;
 
-use common::{auth::AccessToken, listener::stream::NullIo};
+use common::{auth::AccessToken, listener::stream::NullIo, scripts::verdict::FilterVerdict};
 use directory::{backend::internal::PrincipalField, QueryBy};
 use jmap_proto::types::{collection::Collection, id::Id, keyword::Keyword, property::Property};
 use mail_parser::MessageParser;
 use sieve::{Envelope, Event, Input, Mailbox, Recipient};
-use smtp::core::{Session, SessionAddress};
+use smtp::{
+    core::{Session, SessionAddress},
+    queue::DomainPart,
+};
 use store::{
     ahash::AHashSet,
     write::{now, BatchBuilder, Bincode, F_VALUE},
@@ -25,7 +28,11 @@ use crate::{
     JMAP,
 };
 
-use super::ActiveScript;
+use super::{
+    notify::SieveNotification,
+    vacation::{is_list_traffic, vacation_handle},
+    ActiveScript,
+};
 
 struct SieveMessage<'x> {
     pub raw_message: Cow<'x, [u8]>,
@@ -35,12 +42,14 @@ struct SieveMessage<'x> {
 
 impl JMAP {
     #[allow(clippy::blocks_in_conditions)]
+    #[allow(clippy::too_many_arguments)]
     pub async fn sieve_script_ingest(
         &self,
         access_token: &AccessToken,
         raw_message: &[u8],
         envelope_from: &str,
         envelope_to: &str,
+        verdict: &FilterVerdict,
         session_id: u64,
         mut active_script: ActiveScript,
     ) -> trc::Result<IngestedEmail> {
@@ -55,6 +64,9 @@ impl JMAP {
             );
         };
 
+        // Vacation responses are never sent to mailing lists
+        let is_list_traffic = is_list_traffic(&message, envelope_from);
+
         // Obtain mailboxIds
         let account_id = access_token.primary_id;
         let mailbox_ids = self
@@ -62,8 +74,18 @@ impl JMAP {
             .await
             .caused_by(trc::location!())?;
 
+        // Spam and antivirus verdicts for the spamtest and virustest extensions
+        let spam_status = self.sieve_spam_status(verdict);
+        let virus_status = self.sieve_virus_status(verdict);
+
         // Create Sieve instance
-        let mut instance = self.core.sieve.untrusted_runtime.filter_parsed(message);
+        let mut instance = self
+            .core
+            .sieve
+            .untrusted_runtime
+            .filter_parsed(message)
+            .with_spam_status(spam_status)
+            .with_virus_status(virus_status);
 
         // Set account name and email
         let mail_from = self
@@ -108,6 +130,7 @@ impl JMAP {
             blob_id: Default::default(),
             size: raw_message.len(),
             imap_uids: Vec::new(),
+            mailbox_ids: Vec::new(),
         };
 
         while let Some(event) = instance.run(input) {
@@ -208,6 +231,26 @@ impl JMAP {
                             input = false.into();
                         }
                     }
+                    Event::DuplicateId { id, expiry, last }
+                        if vacation_handle(&id, envelope_from, last).is_some() =>
+                    {
+                        input = if is_list_traffic {
+                            true
+                        } else {
+                            self.sieve_vacation_is_replied(
+                                account_id,
+                                envelope_from,
+                                vacation_handle(&id, envelope_from, last).unwrap_or_default(),
+                                expiry,
+                            )
+                            .await
+                            .unwrap_or_else(|err| {
+                                trc::error!(err.span_id(session_id));
+                                true
+                            })
+                        }
+                        .into();
+                    }
                     Event::DuplicateId { id, expiry, last } => {
                         let id_hash = SeenIdHash::new(&id, expiry + now);
                         let seen_id = active_script.seen_ids.ids.contains(&id_hash);
@@ -332,6 +375,13 @@ impl JMAP {
                             let recipients = match recipient {
                                 Recipient::Address(rcpt) => vec![SessionAddress::new(rcpt)],
                                 Recipient::Group(rcpts) => {
+                                    // Recipient groups are only produced by mailto notifications
+                                    if !self
+                                        .sieve_notify_allowed(account_id, "mailto", session_id)
+                                        .await
+                                    {
+                                        continue;
+                                    }
                                     rcpts.into_iter().map(SessionAddress::new).collect()
                                 }
                                 Recipient::List(_) => {
@@ -340,10 +390,28 @@ impl JMAP {
                                 }
                             };
 
+                            // Rewrite the sender of redirected messages using SRS, vacation
+                            // responses and notifications are sent from the account address.
+                            let mut return_path = mail_from.clone();
+                            if message_id == 0 {
+                                if let Some(srs_address) = self
+                                    .core
+                                    .srs_forward(
+                                        envelope_from,
+                                        recipients.iter().map(|rcpt| rcpt.domain.as_str()),
+                                        mail_from.domain_part(),
+                                    )
+                                    .await
+                                    .caused_by(trc::location!())?
+                                {
+                                    return_path = srs_address;
+                                }
+                            }
+
                             if message.raw_message.len() <= self.core.jmap.mail_max_size {
                                 trc::event!(
                                     Sieve(SieveEvent::SendMessage),
-                                    From = mail_from.clone(),
+                                    From = return_path.clone(),
                                     To = recipients
                                         .iter()
                                         .map(|r| trc::Value::String(r.address_lcase.clone()))
@@ -354,17 +422,18 @@ impl JMAP {
 
                                 Session::<NullIo>::sieve(
                                     self.smtp.clone(),
-                                    SessionAddress::new(mail_from.clone()),
+                                    SessionAddress::new(return_path),
                                     recipients,
                                     message.raw_message.to_vec(),
                                     0,
                                 )
+                                .await
                                 .queue_message()
                                 .await;
                             } else {
                                 trc::event!(
                                     Sieve(SieveEvent::MessageTooLarge),
-                                    From = mail_from.clone(),
+                                    From = return_path,
                                     To = recipients
                                         .iter()
                                         .map(|r| trc::Value::String(r.address_lcase.clone()))
@@ -385,10 +454,39 @@ impl JMAP {
                             continue;
                         }
                     }
-                    Event::ListContains { .. }
-                    | Event::Function { .. }
-                    | Event::Notify { .. }
-                    | Event::SetEnvelope { .. } => {
+                    Event::ListContains {
+                        lists,
+                        values,
+                        match_as,
+                    } => {
+                        input = self
+                            .sieve_list_contains(account_id, lists, values, match_as, session_id)
+                            .await
+                            .into();
+                    }
+                    Event::Notify {
+                        method,
+                        from,
+                        importance,
+                        options,
+                        message,
+                    } => {
+                        input = self
+                            .sieve_notify(
+                                account_id,
+                                SieveNotification {
+                                    method,
+                                    from,
+                                    importance,
+                                    options,
+                                    message,
+                                },
+                                session_id,
+                            )
+                            .await
+                            .into();
+                    }
+                    Event::Function { .. } | Event::SetEnvelope { .. } => {
                         // Not allowed
                         input = false.into();
                     }
@@ -461,8 +559,11 @@ impl JMAP {
                     })
                     .await
                 {
-                    Ok(ingested_message_) => {
+                    Ok(mut ingested_message_) => {
                         has_delivered = true;
+                        ingested_message_
+                            .mailbox_ids
+                            .extend(ingested_message.mailbox_ids);
                         ingested_message = ingested_message_;
                     }
                     Err(err) => {
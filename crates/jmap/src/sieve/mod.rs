@@ -10,10 +10,15 @@ use serde::ser::SerializeSeq;
 use sieve::Sieve;
 use store::{ahash::AHashSet, blake3, write::now};
 
+pub mod extlists;
 pub mod get;
+pub mod imapsieve;
 pub mod ingest;
+pub mod notify;
 pub mod query;
 pub mod set;
+pub mod spamtest;
+pub mod vacation;
 pub mod validate;
 
 pub struct ActiveScript {
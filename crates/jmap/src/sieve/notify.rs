@@ -0,0 +1,273 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::net::{IpAddr, SocketAddr};
+
+use reqwest::{header::CONTENT_TYPE, redirect::Policy, Url};
+use sieve::Importance;
+use trc::{AddContext, SieveEvent};
+
+use crate::JMAP;
+
+pub struct SieveNotification {
+    pub method: String,
+    pub from: Option<String>,
+    pub importance: Importance,
+    pub options: Vec<String>,
+    pub message: String,
+}
+
+impl JMAP {
+    pub async fn sieve_notify(
+        &self,
+        account_id: u32,
+        notification: SieveNotification,
+        session_id: u64,
+    ) -> bool {
+        // Only HTTP methods are handled here, mailto notifications are sent as messages
+        let scheme = notification
+            .method
+            .split_once(':')
+            .map(|(scheme, _)| scheme.to_ascii_lowercase())
+            .unwrap_or_default();
+        if !matches!(scheme.as_str(), "http" | "https")
+            || !self
+                .core
+                .sieve
+                .untrusted_notify_methods
+                .contains(scheme.as_str())
+        {
+            trc::event!(
+                Sieve(SieveEvent::NotSupported),
+                Url = notification.method,
+                SpanId = session_id,
+            );
+            return false;
+        }
+
+        // Enforce per-account rate limit
+        if !self
+            .sieve_notify_allowed(account_id, &notification.method, session_id)
+            .await
+        {
+            return false;
+        }
+
+        trc::event!(
+            Sieve(SieveEvent::SendNotification),
+            AccountId = account_id,
+            Url = notification.method.clone(),
+            SpanId = session_id,
+        );
+
+        // Deliver the notification in the background
+        let timeout = self.core.jmap.sieve_notify_timeout;
+        let allow_private = self.core.jmap.sieve_notify_allow_private;
+        tokio::spawn(async move {
+            if let Err(reason) = http_notify(&notification, timeout, allow_private).await {
+                trc::event!(
+                    Sieve(SieveEvent::NotificationError),
+                    AccountId = account_id,
+                    Url = notification.method,
+                    Reason = reason,
+                    SpanId = session_id,
+                );
+            }
+        });
+
+        true
+    }
+
+    // Applies to both HTTP and mailto notifications
+    pub async fn sieve_notify_allowed(
+        &self,
+        account_id: u32,
+        method: &str,
+        session_id: u64,
+    ) -> bool {
+        if let Some(rate) = &self.core.jmap.sieve_notify_rate {
+            match self
+                .core
+                .storage
+                .lookup
+                .is_rate_allowed(format!("snotify:{account_id}").as_bytes(), rate, false)
+                .await
+                .caused_by(trc::location!())
+            {
+                Ok(None) => true,
+                Ok(Some(_)) => {
+                    trc::event!(
+                        Sieve(SieveEvent::NotificationError),
+                        AccountId = account_id,
+                        Url = method.to_string(),
+                        Reason = "Rate limit exceeded",
+                        SpanId = session_id,
+                    );
+                    false
+                }
+                Err(err) => {
+                    trc::error!(err.span_id(session_id));
+                    false
+                }
+            }
+        } else {
+            true
+        }
+    }
+}
+
+async fn http_notify(
+    notification: &SieveNotification,
+    timeout: std::time::Duration,
+    allow_private: bool,
+) -> Result<(), String> {
+    // Redirects are not followed as they could point to internal addresses
+    let mut client_builder = reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(Policy::none());
+
+    // Resolve the host and pin the connection to the validated addresses,
+    // which prevents requests to internal services and DNS rebinding.
+    let url = Url::parse(&notification.method).map_err(|err| err.to_string())?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    let host = url.host_str().unwrap_or_default();
+    let addrs = if let Ok(ip) = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        vec![SocketAddr::new(ip, port)]
+    } else if !host.is_empty() {
+        let addrs = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|err| format!("Failed to resolve {host}: {err}"))?
+            .collect::<Vec<_>>();
+        if !addrs.is_empty() {
+            client_builder = client_builder.resolve_to_addrs(host, &addrs);
+        }
+        addrs
+    } else {
+        Vec::new()
+    };
+    if addrs.is_empty() {
+        return Err("No addresses found for host".to_string());
+    } else if let Some(addr) = addrs
+        .iter()
+        .find(|addr| !allow_private && !is_public_ip(addr.ip()))
+    {
+        return Err(format!("Address {} is not allowed", addr.ip()));
+    }
+
+    #[cfg(feature = "test_mode")]
+    let client_builder = client_builder.danger_accept_invalid_certs(true);
+
+    // Webhooks such as Slack expect a JSON payload, others such as ntfy plain text
+    let is_json = notification
+        .options
+        .iter()
+        .any(|option| option.eq_ignore_ascii_case("format=json"));
+    let priority = match notification.importance {
+        Importance::High => "high",
+        Importance::Normal => "default",
+        Importance::Low => "low",
+    };
+    let mut request = client_builder
+        .build()
+        .unwrap_or_default()
+        .post(&notification.method)
+        .header("Priority", priority);
+    if let Some(from) = &notification.from {
+        request = request.header("X-Sieve-From", from);
+    }
+    let request = if is_json {
+        request.header(CONTENT_TYPE, "application/json").body(
+            serde_json::json!({
+                "text": notification.message,
+                "from": notification.from,
+                "priority": priority,
+            })
+            .to_string(),
+        )
+    } else {
+        request
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(notification.message.clone())
+    };
+
+    match request.send().await {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => Err(format!("HTTP status {}", response.status())),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // Shared address space (RFC 6598)
+                || (octets[0] == 100 && (octets[1] & 0xc0) == 64)
+                // Reserved for future use
+                || octets[0] >= 240
+                || octets[0] == 0)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(ip));
+            }
+            let segments = ip.segments();
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local (fc00::/7) and link local (fe80::/10)
+                || (segments[0] & 0xfe00) == 0xfc00
+                || (segments[0] & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::is_public_ip;
+
+    #[test]
+    fn notify_public_ips() {
+        for (ip, expected) in [
+            ("8.8.8.8", true),
+            ("93.184.216.34", true),
+            ("2606:4700::1111", true),
+            ("127.0.0.1", false),
+            ("10.0.0.1", false),
+            ("172.16.5.4", false),
+            ("192.168.1.1", false),
+            ("169.254.169.254", false),
+            ("100.64.0.1", false),
+            ("0.0.0.0", false),
+            ("255.255.255.255", false),
+            ("::1", false),
+            ("::", false),
+            ("fd00::1", false),
+            ("fe80::1", false),
+            ("::ffff:127.0.0.1", false),
+            ("::ffff:8.8.8.8", true),
+        ] {
+            assert_eq!(
+                is_public_ip(ip.parse::<IpAddr>().unwrap()),
+                expected,
+                "{ip}"
+            );
+        }
+    }
+}
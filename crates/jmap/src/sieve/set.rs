@@ -33,6 +33,7 @@ use store::{
     },
     BlobClass,
 };
+use trc::AddContext;
 
 use crate::{api::http::HttpSessionData, JMAP};
 
@@ -76,6 +77,7 @@ impl JMAP {
 
         // Process creates
         let mut changes = ChangeLogBuilder::new();
+        let mut added_quota = 0;
         for (id, object) in request.unwrap_create() {
             if sieve_ids.len() as usize <= self.core.jmap.sieve_max_scripts {
                 match self
@@ -114,6 +116,7 @@ impl JMAP {
 
                         let document_id = self.write_batch_expect_id(batch).await?;
                         sieve_ids.insert(document_id);
+                        added_quota += script_size as i64;
                         changes.log_insert(Collection::SieveScript, document_id);
 
                         // Add result with updated blobId
@@ -194,6 +197,7 @@ impl JMAP {
                             .with_collection(Collection::SieveScript)
                             .update_document(document_id);
 
+                        let mut update_quota = 0;
                         let blob_id = if let Some(blob) = blob {
                             // Store blob
                             let blob_id = builder.changes_mut().unwrap().blob_id_mut().unwrap();
@@ -204,7 +208,7 @@ impl JMAP {
                             let blob_id = blob_id.clone();
 
                             // Update quota
-                            let update_quota = match script_size.cmp(&prev_script_size) {
+                            update_quota = match script_size.cmp(&prev_script_size) {
                                 std::cmp::Ordering::Greater => script_size - prev_script_size,
                                 std::cmp::Ordering::Less => -prev_script_size + script_size,
                                 std::cmp::Ordering::Equal => 0,
@@ -247,7 +251,9 @@ impl JMAP {
                         if !batch.is_empty() {
                             changes.log_update(Collection::SieveScript, document_id);
                             match self.core.storage.data.write(batch.build()).await {
-                                Ok(_) => (),
+                                Ok(_) => {
+                                    added_quota += update_quota;
+                                }
                                 Err(err) if err.is_assertion_failure() => {
                                     ctx.response.not_updated.append(id, SetError::forbidden().with_description(
                                         "Another process modified this sieve, please try again.",
@@ -337,7 +343,15 @@ impl JMAP {
 
         // Write changes
         if !changes.is_empty() {
-            ctx.response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
+            let change_id = self.commit_changes(account_id, changes).await?;
+            ctx.response.new_state = Some(change_id.into());
+
+            // Notify clients if the quota warning threshold was crossed
+            if added_quota > 0 {
+                self.notify_quota_threshold(&ctx.resource_token, added_quota as u64, change_id)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
         }
 
         Ok(ctx.response)
@@ -536,6 +550,19 @@ impl JMAP {
                         }
                     }
 
+                    // Check the total size of scripts
+                    if self
+                        .sieve_scripts_over_quota(
+                            ctx.resource_token.account_id,
+                            update.as_ref().map(|(document_id, _)| *document_id),
+                            bytes.len() as u64,
+                        )
+                        .await?
+                    {
+                        return Ok(Err(SetError::over_quota()
+                            .with_description("Total size of Sieve scripts exceeds quota.")));
+                    }
+
                     // Compile script
                     match self.core.sieve.untrusted_compiler.compile(&bytes) {
                         Ok(script) => {
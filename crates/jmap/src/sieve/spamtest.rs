@@ -0,0 +1,41 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::scripts::verdict::{FilterVerdict, VirusVerdict};
+use sieve::{SpamStatus, VirusStatus};
+
+use crate::JMAP;
+
+impl JMAP {
+    // Maps the score added by the spam filter to the spamtest scale (RFC 5235)
+    pub fn sieve_spam_status(&self, verdict: &FilterVerdict) -> SpamStatus {
+        match verdict.spam_score {
+            _ if verdict.is_spam => SpamStatus::Spam,
+            Some(score) if score <= 0.0 => SpamStatus::Ham,
+            Some(score) => {
+                let threshold = self.core.jmap.spam_score_threshold;
+                if threshold > 0.0 && score < threshold {
+                    SpamStatus::MaybeSpam(score / threshold)
+                } else {
+                    SpamStatus::Spam
+                }
+            }
+            None => SpamStatus::Unknown,
+        }
+    }
+
+    // Maps the verdict of the antivirus scanner to the virustest scale (RFC 5235)
+    pub fn sieve_virus_status(&self, verdict: &FilterVerdict) -> VirusStatus {
+        match verdict.virus {
+            VirusVerdict::Unknown => VirusStatus::Unknown,
+            VirusVerdict::Clean => VirusStatus::Clean,
+            VirusVerdict::Replaced => VirusStatus::Replaced,
+            VirusVerdict::Cured => VirusStatus::Cured,
+            VirusVerdict::MaybeVirus => VirusStatus::MaybeVirus,
+            VirusVerdict::Virus => VirusStatus::Virus,
+        }
+    }
+}
@@ -0,0 +1,127 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use mail_parser::{HeaderName, Message};
+use store::{blake3, write::now};
+use trc::AddContext;
+use utils::config::Rate;
+
+use crate::JMAP;
+
+// The vacation action tracks its replies with duplicate ids made of this prefix,
+// the lowercase envelope sender and the handle (or the reason if there is no handle)
+const VACATION_ID_PREFIX: &str = "_v";
+
+// Lookup store keys of vacation replies, the NUL byte keeps them apart from
+// the keys written by scripts and lookup lists
+const VACATION_KEY_PREFIX: &[u8] = b"vac\0";
+
+impl JMAP {
+    // Returns true when a vacation reply was already sent for this sender and handle
+    // within the requested period, otherwise records the reply time in the lookup store
+    // so that the period is honored across restarts and cluster nodes.
+    pub async fn sieve_vacation_is_replied(
+        &self,
+        account_id: u32,
+        sender: &str,
+        handle: &str,
+        period: u64,
+    ) -> trc::Result<bool> {
+        if period == 0 {
+            return Ok(false);
+        }
+
+        // Sender and handle are hashed separately so their boundary is part of the key
+        let mut key =
+            Vec::with_capacity(VACATION_KEY_PREFIX.len() + std::mem::size_of::<u32>() + 64);
+        key.extend_from_slice(VACATION_KEY_PREFIX);
+        key.extend_from_slice(&account_id.to_be_bytes());
+        key.extend_from_slice(blake3::hash(sender.to_ascii_lowercase().as_bytes()).as_bytes());
+        key.extend_from_slice(blake3::hash(handle.as_bytes()).as_bytes());
+
+        let lookup = &self.core.storage.lookup;
+        if lookup
+            .key_exists(key.clone())
+            .await
+            .caused_by(trc::location!())?
+        {
+            return Ok(true);
+        }
+
+        // Atomically claim the reply, concurrent deliveries for the same sender
+        // (or a delivery racing the key expiration) only send a single reply.
+        if lookup
+            .is_rate_allowed(
+                &key,
+                &Rate {
+                    requests: 1,
+                    period: Duration::from_secs(period),
+                },
+                false,
+            )
+            .await
+            .caused_by(trc::location!())?
+            .is_some()
+        {
+            return Ok(true);
+        }
+
+        lookup
+            .key_set(key, now().to_be_bytes().to_vec(), period.into())
+            .await
+            .caused_by(trc::location!())
+            .map(|_| false)
+    }
+}
+
+// Returns the handle of a duplicate id generated by the vacation action for the sender
+// of the message. The action never sets `:last`, and ids of the duplicate test only
+// match if the script spells out both the prefix and the envelope sender.
+pub fn vacation_handle<'x>(id: &'x str, sender: &str, last: bool) -> Option<&'x str> {
+    if last || sender.is_empty() {
+        return None;
+    }
+    id.strip_prefix(VACATION_ID_PREFIX)?
+        .strip_prefix(sender.to_ascii_lowercase().as_str())
+}
+
+// Mailing list and bulk traffic must not be answered (RFC 5230, section 4.6)
+pub fn is_list_traffic(message: &Message, envelope_from: &str) -> bool {
+    let sender = envelope_from
+        .split_once('@')
+        .map_or(envelope_from, |(local, _)| local)
+        .to_ascii_lowercase();
+    if ["listserv", "majordomo", "mailman", "noreply", "no-reply"].contains(&sender.as_str())
+        || sender.starts_with("owner-")
+        || sender.ends_with("-request")
+        || sender.ends_with("-owner")
+        || sender.ends_with("-bounces")
+    {
+        return true;
+    }
+
+    message
+        .root_part()
+        .headers()
+        .iter()
+        .any(|header| match &header.name {
+            HeaderName::ListArchive
+            | HeaderName::ListHelp
+            | HeaderName::ListId
+            | HeaderName::ListOwner
+            | HeaderName::ListPost
+            | HeaderName::ListSubscribe
+            | HeaderName::ListUnsubscribe => true,
+            HeaderName::Other(name) if name.eq_ignore_ascii_case("Precedence") => {
+                header.value.as_text().map_or(false, |v| {
+                    ["bulk", "list", "junk"].contains(&v.trim().to_ascii_lowercase().as_str())
+                })
+            }
+            _ => false,
+        })
+}
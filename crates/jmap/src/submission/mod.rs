@@ -7,3 +7,4 @@
 pub mod get;
 pub mod query;
 pub mod set;
+pub mod undo;
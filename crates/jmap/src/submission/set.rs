@@ -7,6 +7,7 @@
 use std::{collections::HashMap, sync::Arc};
 
 use common::listener::{stream::NullIo, ServerInstance};
+use directory::QueryBy;
 use jmap_proto::{
     error::set::{SetError, SetErrorType},
     method::set::{self, SetRequest, SetResponse},
@@ -34,7 +35,9 @@ use smtp_proto::{request::parser::Rfc5321Parser, MailFrom, RcptTo};
 use store::write::{assert::HashedValue, log::ChangeLogBuilder, now, BatchBuilder, Bincode};
 use utils::map::vec_map::VecMap;
 
-use crate::{email::metadata::MessageMetadata, identity::set::sanitize_email, JMAP};
+use crate::{
+    activity::ActivityType, email::metadata::MessageMetadata, identity::set::sanitize_email, JMAP,
+};
 
 pub static SCHEMA: &[IndexProperty] = &[
     IndexProperty::new(Property::UndoStatus).index_as(IndexAs::Text {
@@ -90,6 +93,18 @@ impl JMAP {
             }
         }
 
+        // Record activity
+        if let Err(err) = self
+            .activity_log(
+                account_id,
+                ActivityType::Sent,
+                success_email_ids.values().copied(),
+            )
+            .await
+        {
+            trc::error!(err.caused_by(trc::location!()));
+        }
+
         // Process updates
         'update: for (id, object) in request.unwrap_update() {
             // Make sure id won't be destroyed
@@ -147,11 +162,7 @@ impl JMAP {
 
             match undo_status {
                 Some(undo_status) if undo_status == "canceled" => {
-                    if let Some(queue_message) = self.smtp.read_message(queue_id).await {
-                        // Delete message from queue
-                        let message_due = queue_message.next_event().unwrap_or_default();
-                        queue_message.remove(&self.smtp, message_due).await;
-
+                    if self.cancel_submission(queue_id).await {
                         // Update record
                         let mut batch = BatchBuilder::new();
                         batch
@@ -208,6 +219,16 @@ impl JMAP {
                 )
                 .await?
             {
+                // Cancel scheduled messages that have not been released yet
+                if let (Value::UnsignedInt(queue_id), Value::Date(send_at)) = (
+                    submission.inner.get(&Property::MessageId),
+                    submission.inner.get(&Property::SendAt),
+                ) {
+                    if send_at.timestamp() > now() as i64 {
+                        self.cancel_submission(*queue_id).await;
+                    }
+                }
+
                 // Update record
                 let mut batch = BatchBuilder::new();
                 batch
@@ -286,6 +307,16 @@ impl JMAP {
         Ok(response)
     }
 
+    async fn cancel_submission(&self, queue_id: u64) -> bool {
+        if let Some(queue_message) = self.smtp.read_message(queue_id).await {
+            // Delete message from queue
+            let message_due = queue_message.next_event().unwrap_or_default();
+            queue_message.remove(&self.smtp, message_due).await
+        } else {
+            false
+        }
+    }
+
     async fn send_message(
         &self,
         account_id: u32,
@@ -434,8 +465,28 @@ impl JMAP {
                 .with_description("Identity not found.")));
         };
 
+        // Make sure the identity address is still assigned to the account in the directory
+        let principal = self
+            .core
+            .storage
+            .directory
+            .query(QueryBy::Id(account_id), true)
+            .await?
+            .unwrap_or_default();
+        if !self
+            .identity_addresses(&principal)
+            .await?
+            .iter()
+            .any(|address| address.eq_ignore_ascii_case(&identity_mail_from))
+        {
+            return Ok(Err(SetError::new(SetErrorType::ForbiddenFrom)
+                .with_description(
+                    "Identity e-mail address is no longer assigned to this account.",
+                )));
+        }
+
         // Make sure the envelope address matches the identity email address
-        let mail_from = if let Some(mail_from) = mail_from {
+        let mut mail_from = if let Some(mail_from) = mail_from {
             if !mail_from.address.eq_ignore_ascii_case(&identity_mail_from) {
                 return Ok(Err(SetError::new(SetErrorType::ForbiddenFrom)
                     .with_description(
@@ -521,16 +572,31 @@ impl JMAP {
             }
         }
 
+        // Obtain the release time, messages without one are held during the undo send window
+        let hold_for = if mail_from.hold_until > 0 {
+            mail_from.hold_until.saturating_sub(now())
+        } else if mail_from.hold_for > 0 {
+            mail_from.hold_for
+        } else {
+            self.submission_undo_send(account_id).await?
+        };
+        let max_delayed_send = self.core.jmap.mail_max_delayed_send.as_secs();
+        if hold_for > max_delayed_send && (mail_from.hold_until > 0 || mail_from.hold_for > 0) {
+            return Ok(Err(SetError::invalid_properties()
+                .with_property(Property::Envelope)
+                .with_description(format!(
+                    "Requested hold time exceeds maximum of {max_delayed_send} seconds."
+                ))));
+        }
+
+        // The hold is applied directly to the queued message
+        mail_from.hold_until = 0;
+        mail_from.hold_for = 0;
+
         // Update sendAt
         submission.append(
             Property::SendAt,
-            UTCDate::from_timestamp(if mail_from.hold_until > 0 {
-                mail_from.hold_until
-            } else if mail_from.hold_for > 0 {
-                mail_from.hold_for + now()
-            } else {
-                now()
-            } as i64),
+            UTCDate::from_timestamp((now() + hold_for) as i64),
         );
 
         // Obtain raw message
@@ -564,6 +630,9 @@ impl JMAP {
                     error.trim()
                 ))));
         }
+        if hold_for > 0 {
+            session.data.future_release = hold_for;
+        }
 
         // RCPT TO
         let mut responses = Vec::new();
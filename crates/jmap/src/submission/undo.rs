@@ -0,0 +1,98 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use common::auth::AccessToken;
+use directory::backend::internal::manage;
+use jmap_proto::types::{collection::Collection, property::Property};
+use serde_json::json;
+use store::write::{BatchBuilder, F_CLEAR, F_VALUE};
+use trc::AddContext;
+
+use crate::{
+    api::{http::ToHttpResponse, HttpResponse, JsonResponse},
+    JMAP,
+};
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AccountUndoSend {
+    #[serde(rename = "delay")]
+    pub delay: Option<u64>,
+}
+
+impl JMAP {
+    // Returns the number of seconds submissions are held for before being released,
+    // accounts that did not choose their own window use the server default.
+    pub async fn submission_undo_send(&self, account_id: u32) -> trc::Result<u64> {
+        self.get_property::<u64>(account_id, Collection::Principal, 0, Property::UndoSend)
+            .await
+            .caused_by(trc::location!())
+            .map(|delay| {
+                delay.unwrap_or_else(|| {
+                    self.core
+                        .jmap
+                        .mail_undo_send
+                        .map_or(0, |undo_send| undo_send.as_secs())
+                })
+            })
+    }
+
+    pub async fn handle_account_undo_send_get(
+        &self,
+        access_token: Arc<AccessToken>,
+    ) -> trc::Result<HttpResponse> {
+        let delay = self
+            .get_property::<u64>(
+                access_token.primary_id(),
+                Collection::Principal,
+                0,
+                Property::UndoSend,
+            )
+            .await?;
+
+        Ok(JsonResponse::new(json!({
+            "data": AccountUndoSend { delay },
+        }))
+        .into_http_response())
+    }
+
+    pub async fn handle_account_undo_send_post(
+        &self,
+        access_token: Arc<AccessToken>,
+        body: Option<Vec<u8>>,
+    ) -> trc::Result<HttpResponse> {
+        let request =
+            serde_json::from_slice::<AccountUndoSend>(body.as_deref().unwrap_or_default())
+                .map_err(|err| {
+                    trc::EventType::Resource(trc::ResourceEvent::BadParameters).from_json_error(err)
+                })?;
+
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(access_token.primary_id())
+            .with_collection(Collection::Principal)
+            .update_document(0);
+        if let Some(delay) = request.delay {
+            let max_delayed_send = self.core.jmap.mail_max_delayed_send.as_secs();
+            if delay > max_delayed_send {
+                return Err(manage::error(
+                    format!("Undo send delay exceeds maximum of {max_delayed_send} seconds."),
+                    None::<u32>,
+                ));
+            }
+            batch.value(Property::UndoSend, delay, F_VALUE);
+        } else {
+            batch.value(Property::UndoSend, (), F_VALUE | F_CLEAR);
+        }
+        self.core.storage.data.write(batch.build()).await?;
+
+        Ok(JsonResponse::new(json!({
+            "data": (),
+        }))
+        .into_http_response())
+    }
+}
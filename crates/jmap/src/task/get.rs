@@ -0,0 +1,117 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::Object,
+    types::{collection::Collection, date::UTCDate, property::Property, value::Value},
+};
+
+use crate::JMAP;
+
+use super::TaskSchedule;
+
+impl JMAP {
+    pub async fn task_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::TaskListId,
+            Property::Uid,
+            Property::Title,
+            Property::Description,
+            Property::Start,
+            Property::Due,
+            Property::EstimatedDuration,
+            Property::TimeZone,
+            Property::ShowWithoutTime,
+            Property::RecurrenceRules,
+            Property::Progress,
+            Property::PercentComplete,
+            Property::Priority,
+            Property::Keywords,
+            Property::UtcStart,
+            Property::UtcDue,
+        ]);
+        let account_id = request.account_id.document_id();
+        let task_ids = self
+            .get_document_ids(account_id, Collection::Task)
+            .await?
+            .unwrap_or_default();
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            task_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self.get_state(account_id, Collection::Task).await?.into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the task object
+            let document_id = id.document_id();
+            if !task_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let mut task = if let Some(task) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::Task,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                task
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let schedule = TaskSchedule::from_object(&task);
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    Property::ShowWithoutTime => task
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::Bool(false)),
+                    Property::Progress => task
+                        .properties
+                        .remove(property)
+                        .unwrap_or_else(|| Value::Text("needs-action".to_string())),
+                    Property::PercentComplete | Property::Priority => task
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::UnsignedInt(0)),
+                    // The first occurrence is returned for recurring tasks
+                    Property::UtcStart => schedule
+                        .utc_start()
+                        .map_or(Value::Null, |utc| Value::Date(UTCDate::from_timestamp(utc))),
+                    Property::UtcDue => schedule
+                        .utc_due()
+                        .map_or(Value::Null, |utc| Value::Date(UTCDate::from_timestamp(utc))),
+                    property => task.remove(property),
+                };
+                result.append(property.clone(), value);
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
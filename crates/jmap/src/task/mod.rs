@@ -0,0 +1,108 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use chrono::NaiveDateTime;
+use jmap_proto::{
+    object::Object,
+    types::{property::Property, value::Value},
+};
+
+use crate::calendar_event::{
+    parse_local_datetime, parse_recurrence_rules, EventSchedule, RecurrenceRule,
+};
+
+pub mod get;
+pub mod query;
+pub mod set;
+
+// Task timing resolved to UTC. As with events, the offset of zones that are not
+// resolved by the server is derived from the utcStart or utcDue supplied by the client.
+#[derive(Debug, Clone)]
+pub struct TaskSchedule {
+    pub start: Option<NaiveDateTime>,
+    pub due: Option<NaiveDateTime>,
+    pub offset: i64,
+    // Recurrences are anchored on the due date, or on the start date for tasks without one
+    anchor: Option<EventSchedule>,
+}
+
+impl TaskSchedule {
+    pub fn new(
+        start: Option<NaiveDateTime>,
+        due: Option<NaiveDateTime>,
+        offset: i64,
+        rule: Option<RecurrenceRule>,
+    ) -> Self {
+        TaskSchedule {
+            start,
+            due,
+            offset,
+            anchor: due.or(start).map(|anchor| EventSchedule {
+                start: anchor,
+                offset,
+                duration: 0,
+                rule,
+                time_zone: None,
+            }),
+        }
+    }
+
+    pub fn from_object(task: &Object<Value>) -> Self {
+        let local = |property: &Property| {
+            task.properties
+                .get(property)
+                .and_then(|value| value.as_string())
+                .and_then(parse_local_datetime)
+        };
+        let start = local(&Property::Start);
+        let due = local(&Property::Due);
+        let offset = [(due, Property::UtcDue), (start, Property::UtcStart)]
+            .into_iter()
+            .find_map(
+                |(local, property)| match (local, task.properties.get(&property)) {
+                    (Some(local), Some(Value::UnsignedInt(utc))) => {
+                        Some(*utc as i64 - local.and_utc().timestamp())
+                    }
+                    _ => None,
+                },
+            )
+            .unwrap_or(0);
+        let rule = task
+            .properties
+            .get(&Property::RecurrenceRules)
+            .and_then(|rules| parse_recurrence_rules(rules).ok())
+            .flatten();
+
+        TaskSchedule::new(start, due, offset, rule)
+    }
+
+    pub fn utc_start(&self) -> Option<i64> {
+        self.start
+            .map(|start| start.and_utc().timestamp() + self.offset)
+    }
+
+    pub fn utc_due(&self) -> Option<i64> {
+        self.due.map(|due| due.and_utc().timestamp() + self.offset)
+    }
+
+    pub fn is_recurring(&self) -> bool {
+        self.anchor
+            .as_ref()
+            .map_or(false, |anchor| anchor.rule.is_some())
+    }
+
+    // Last occurrence of the due (or start) date, or MAX_UTC_END for unbounded recurrences
+    pub fn utc_series_end(&self) -> Option<u64> {
+        self.anchor.as_ref().map(|anchor| anchor.utc_series_end())
+    }
+
+    // Returns the UTC timestamps of the due (or start) dates falling within [from, to)
+    pub fn occurrences(&self, from: i64, to: i64) -> impl Iterator<Item = i64> + '_ {
+        self.anchor
+            .iter()
+            .flat_map(move |anchor| anchor.occurrences(from, to).map(|(start, _)| start))
+    }
+}
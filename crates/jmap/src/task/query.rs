@@ -0,0 +1,137 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    method::query::{
+        Comparator, Filter, QueryRequest, QueryResponse, RequestArguments, SortProperty,
+    },
+    object::Object,
+    types::{collection::Collection, property::Property, value::Value},
+};
+use store::query::{self};
+
+use crate::JMAP;
+
+use super::TaskSchedule;
+
+impl JMAP {
+    pub async fn task_query(
+        &self,
+        mut request: QueryRequest<RequestArguments>,
+    ) -> trc::Result<QueryResponse> {
+        let account_id = request.account_id.document_id();
+        let mut filters = Vec::with_capacity(request.filter.len());
+        let mut range: Option<(i64, i64)> = None;
+
+        for cond in std::mem::take(&mut request.filter) {
+            match cond {
+                Filter::InTaskLists(ids) => {
+                    filters.push(query::Filter::Or);
+                    for id in ids {
+                        filters.push(query::Filter::eq(Property::TaskListId, id.document_id()));
+                    }
+                    filters.push(query::Filter::End);
+                }
+                // Tasks due within the [after, before) range, the utcEnd index holds
+                // the last due date of the series and is used to narrow down the results
+                Filter::After(after) => {
+                    let after = after.timestamp();
+                    range.get_or_insert((i64::MIN, i64::MAX)).0 = after;
+                    filters.push(query::Filter::ge(Property::UtcEnd, after.max(0) as u64));
+                }
+                Filter::Before(before) => {
+                    let before = before.timestamp();
+                    range.get_or_insert((i64::MIN, i64::MAX)).1 = before;
+                    filters.push(query::Filter::Or);
+                    filters.push(query::Filter::lt(Property::UtcDue, before.max(0) as u64));
+                    filters.push(query::Filter::lt(Property::UtcStart, before.max(0) as u64));
+                    filters.push(query::Filter::End);
+                }
+                Filter::Text(text) => {
+                    filters.push(query::Filter::Or);
+                    filters.push(query::Filter::has_text(Property::Title, &text));
+                    filters.push(query::Filter::has_text(Property::Description, &text));
+                    filters.push(query::Filter::End);
+                }
+                Filter::Title(title) => {
+                    filters.push(query::Filter::has_text(Property::Title, &title))
+                }
+                Filter::Uid(uid) => filters.push(query::Filter::eq(Property::Uid, uid)),
+                Filter::Progress(progress) => {
+                    filters.push(query::Filter::eq(Property::Progress, progress))
+                }
+                Filter::And | Filter::Or | Filter::Not | Filter::Close => {
+                    filters.push(cond.into());
+                }
+                other => {
+                    return Err(trc::JmapEvent::UnsupportedFilter
+                        .into_err()
+                        .details(other.to_string()))
+                }
+            }
+        }
+
+        let mut result_set = self.filter(account_id, Collection::Task, filters).await?;
+
+        // Expand recurring tasks and discard those without occurrences in the range
+        if let Some((after, before)) = range {
+            for (document_id, task) in self
+                .get_properties::<Object<Value>, _, _>(
+                    account_id,
+                    Collection::Task,
+                    &result_set.results,
+                    Property::Value,
+                )
+                .await?
+            {
+                if TaskSchedule::from_object(&task)
+                    .occurrences(after, before)
+                    .next()
+                    .is_none()
+                {
+                    result_set.results.remove(document_id);
+                }
+            }
+        }
+
+        let (response, paginate) = self.build_query_response(&result_set, &request).await?;
+
+        if let Some(paginate) = paginate {
+            // Parse sort criteria
+            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
+            for comparator in request
+                .sort
+                .and_then(|s| if !s.is_empty() { s.into() } else { None })
+                .unwrap_or_else(|| vec![Comparator::ascending(SortProperty::Due)])
+            {
+                comparators.push(match comparator.property {
+                    SortProperty::Due => {
+                        query::Comparator::field(Property::UtcDue, comparator.is_ascending)
+                    }
+                    SortProperty::Start => {
+                        query::Comparator::field(Property::UtcStart, comparator.is_ascending)
+                    }
+                    SortProperty::Priority => {
+                        query::Comparator::field(Property::Priority, comparator.is_ascending)
+                    }
+                    SortProperty::Uid => {
+                        query::Comparator::field(Property::Uid, comparator.is_ascending)
+                    }
+                    other => {
+                        return Err(trc::JmapEvent::UnsupportedSort
+                            .into_err()
+                            .details(other.to_string()))
+                    }
+                });
+            }
+
+            // Sort results
+            self.sort(result_set, comparators, paginate, response).await
+        } else {
+            Ok(response)
+        }
+    }
+}
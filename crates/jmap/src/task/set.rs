@@ -0,0 +1,422 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use chrono::DateTime;
+use jmap_proto::{
+    error::set::SetError,
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    object::{
+        index::{IndexAs, IndexProperty, ObjectIndexBuilder},
+        Object,
+    },
+    response::references::EvalObjectReferences,
+    types::{
+        collection::Collection,
+        property::Property,
+        state::StateChange,
+        type_state::DataType,
+        value::{MaybePatchValue, SetValue, Value},
+    },
+};
+use store::{
+    roaring::RoaringBitmap,
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder},
+};
+
+use crate::{
+    calendar_event::{
+        format_local_datetime, parse_duration, parse_local_datetime, parse_recurrence_rules,
+        set::generate_uid, time_zone_offset,
+    },
+    JMAP,
+};
+
+use super::TaskSchedule;
+
+pub static SCHEMA: &[IndexProperty] = &[
+    IndexProperty::new(Property::TaskListId)
+        .index_as(IndexAs::Integer)
+        .required(),
+    IndexProperty::new(Property::Uid)
+        .index_as(IndexAs::Text {
+            tokenize: false,
+            index: true,
+        })
+        .max_size(255)
+        .required(),
+    IndexProperty::new(Property::Title)
+        .index_as(IndexAs::Text {
+            tokenize: true,
+            index: true,
+        })
+        .max_size(1024),
+    IndexProperty::new(Property::Description)
+        .index_as(IndexAs::Text {
+            tokenize: true,
+            index: false,
+        })
+        .max_size(65536),
+    IndexProperty::new(Property::Progress).index_as(IndexAs::Text {
+        tokenize: false,
+        index: true,
+    }),
+    IndexProperty::new(Property::Priority).index_as(IndexAs::Integer),
+    IndexProperty::new(Property::UtcStart).index_as(IndexAs::LongInteger),
+    IndexProperty::new(Property::UtcDue).index_as(IndexAs::LongInteger),
+    IndexProperty::new(Property::UtcEnd).index_as(IndexAs::LongInteger),
+];
+
+impl JMAP {
+    pub async fn task_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let task_list_ids = self.task_list_get_or_create(account_id).await?;
+        let mut response = self
+            .prepare_set_response(&request, Collection::Task)
+            .await?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Process creates
+        let mut changes = ChangeLogBuilder::new();
+        for (id, object) in request.unwrap_create() {
+            match task_set_item(object, None, &task_list_ids, &response) {
+                Ok(builder) => {
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::Task)
+                        .create_document()
+                        .custom(builder);
+                    let document_id = self.write_batch_expect_id(batch).await?;
+                    changes.log_insert(Collection::Task, document_id);
+                    response.created(id, document_id);
+                }
+                Err(err) => {
+                    response.not_created.append(id, err);
+                }
+            }
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain task
+            let document_id = id.document_id();
+            let task = if let Some(task) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::Task,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                task
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+
+            match task_set_item(object, Some(task), &task_list_ids, &response) {
+                Ok(builder) => {
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::Task)
+                        .update_document(document_id)
+                        .custom(builder);
+                    if !batch.is_empty() {
+                        match self.core.storage.data.write(batch.build()).await {
+                            Ok(_) => {
+                                changes.log_update(Collection::Task, document_id);
+                            }
+                            Err(err) if err.is_assertion_failure() => {
+                                response.not_updated.append(
+                                    id,
+                                    SetError::forbidden().with_description(
+                                        "Another process modified this task, please try again.",
+                                    ),
+                                );
+                                continue 'update;
+                            }
+                            Err(err) => {
+                                return Err(err.caused_by(trc::location!()));
+                            }
+                        }
+                    }
+                    response.updated.append(id, None);
+                }
+                Err(err) => {
+                    response.not_updated.append(id, err);
+                }
+            }
+        }
+
+        // Process deletions
+        for id in will_destroy {
+            let document_id = id.document_id();
+            let task = if let Some(task) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::Task,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                task
+            } else {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            };
+
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::Task)
+                .delete_document(document_id)
+                .custom(ObjectIndexBuilder::new(SCHEMA).with_current(task));
+            match self.core.storage.data.write(batch.build()).await {
+                Ok(_) => {
+                    changes.log_delete(Collection::Task, document_id);
+                    response.destroyed.push(id);
+                }
+                Err(err) if err.is_assertion_failure() => {
+                    response.not_destroyed.append(
+                        id,
+                        SetError::forbidden().with_description(
+                            "Another process modified this task, please try again.",
+                        ),
+                    );
+                }
+                Err(err) => {
+                    return Err(err.caused_by(trc::location!()));
+                }
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            response.state_change = StateChange::new(account_id)
+                .with_change(DataType::Task, changes.change_id)
+                .into();
+            response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
+        }
+
+        Ok(response)
+    }
+}
+
+fn task_set_item(
+    changes_: Object<SetValue>,
+    current: Option<HashedValue<Object<Value>>>,
+    task_list_ids: &RoaringBitmap,
+    response: &SetResponse,
+) -> Result<ObjectIndexBuilder, SetError> {
+    let mut changes = Object::with_capacity(changes_.properties.len() + 4);
+    let mut utc_start = None;
+    let mut utc_due = None;
+
+    for (property, value) in changes_.properties {
+        let value = match (&property, response.eval_object_references(value)?) {
+            (Property::TaskListId, MaybePatchValue::Value(Value::Id(id))) => {
+                if !task_list_ids.contains(id.document_id()) {
+                    return Err(SetError::invalid_properties()
+                        .with_property(property)
+                        .with_description(format!("taskListId {id} does not exist.")));
+                }
+                Value::Id(id)
+            }
+            (Property::Uid, MaybePatchValue::Value(Value::Text(value))) if current.is_none() => {
+                Value::Text(value)
+            }
+            (
+                Property::Title | Property::Description,
+                MaybePatchValue::Value(Value::Text(value)),
+            ) => Value::Text(value),
+            (Property::Start | Property::Due, MaybePatchValue::Value(Value::Text(value)))
+                if parse_local_datetime(&value).is_some() =>
+            {
+                Value::Text(value)
+            }
+            (Property::EstimatedDuration, MaybePatchValue::Value(Value::Text(value)))
+                if parse_duration(&value).is_some() =>
+            {
+                Value::Text(value)
+            }
+            (Property::TimeZone, MaybePatchValue::Value(Value::Text(value)))
+                if value.len() < 255 =>
+            {
+                Value::Text(value)
+            }
+            (Property::Progress, MaybePatchValue::Value(Value::Text(value)))
+                if matches!(
+                    value.as_str(),
+                    "needs-action" | "in-process" | "completed" | "failed" | "cancelled"
+                ) =>
+            {
+                Value::Text(value)
+            }
+            (Property::PercentComplete, MaybePatchValue::Value(Value::UnsignedInt(value)))
+                if value <= 100 =>
+            {
+                Value::UnsignedInt(value)
+            }
+            (Property::Priority, MaybePatchValue::Value(Value::UnsignedInt(value)))
+                if value <= 9 =>
+            {
+                Value::UnsignedInt(value)
+            }
+            (Property::ShowWithoutTime, MaybePatchValue::Value(Value::Bool(value))) => {
+                Value::Bool(value)
+            }
+            (Property::RecurrenceRules, MaybePatchValue::Value(value)) => {
+                if let Err(err) = parse_recurrence_rules(&value) {
+                    return Err(SetError::invalid_properties()
+                        .with_property(property)
+                        .with_description(err));
+                }
+                value
+            }
+            (Property::UtcStart, MaybePatchValue::Value(Value::Date(value))) => {
+                utc_start = Some(value.timestamp());
+                continue;
+            }
+            (Property::UtcDue, MaybePatchValue::Value(Value::Date(value))) => {
+                utc_due = Some(value.timestamp());
+                continue;
+            }
+            (Property::Keywords | Property::_T(_), MaybePatchValue::Value(value)) => value,
+            (
+                Property::Title
+                | Property::Description
+                | Property::Start
+                | Property::Due
+                | Property::EstimatedDuration
+                | Property::TimeZone
+                | Property::Progress
+                | Property::PercentComplete
+                | Property::Priority
+                | Property::ShowWithoutTime,
+                MaybePatchValue::Value(Value::Null),
+            ) => Value::Null,
+            _ => {
+                return Err(SetError::invalid_properties()
+                    .with_property(property)
+                    .with_description("Invalid property or value.".to_string()))
+            }
+        };
+        changes.append(property, value);
+    }
+
+    // Validate task list
+    if current.is_none() && !changes.properties.contains_key(&Property::TaskListId) {
+        return Err(SetError::invalid_properties()
+            .with_property(Property::TaskListId)
+            .with_description("Task has to belong to a task list."));
+    }
+
+    // Generate a UID if missing
+    if current.is_none() && !changes.properties.contains_key(&Property::Uid) {
+        changes.append(Property::Uid, Value::Text(generate_uid()));
+    }
+
+    // Resolve the task start and due dates in UTC
+    let merged = |property: &Property| match changes.properties.get(property) {
+        Some(Value::Null) => None,
+        Some(value) => Some(value),
+        None => current
+            .as_ref()
+            .and_then(|current| current.inner.properties.get(property)),
+    };
+    let zone_offset =
+        time_zone_offset(merged(&Property::TimeZone).and_then(|value| value.as_string()));
+    let local = |property: &Property| {
+        merged(property)
+            .and_then(|value| value.as_string())
+            .and_then(parse_local_datetime)
+    };
+    let mut start = local(&Property::Start);
+    let mut due = local(&Property::Due);
+    let rule = merged(&Property::RecurrenceRules)
+        .and_then(|value| parse_recurrence_rules(value).ok())
+        .flatten();
+    let current_offset = current
+        .as_ref()
+        .map(|current| TaskSchedule::from_object(&current.inner).offset);
+    let offset = zone_offset
+        .or_else(|| {
+            [(utc_due, due), (utc_start, start)]
+                .into_iter()
+                .find_map(|(utc, local)| Some(utc? - local?.and_utc().timestamp()))
+        })
+        .or(current_offset)
+        .unwrap_or_default();
+
+    // Obtain local dates from utcStart or utcDue when only those were provided
+    for (utc, local, property, utc_property) in [
+        (utc_start, &mut start, Property::Start, Property::UtcStart),
+        (utc_due, &mut due, Property::Due, Property::UtcDue),
+    ] {
+        if let (Some(utc), None) = (utc, *local) {
+            let value = DateTime::from_timestamp(utc - offset, 0)
+                .map(|value| value.naive_utc())
+                .ok_or_else(|| {
+                    SetError::invalid_properties()
+                        .with_property(utc_property)
+                        .with_description("Invalid date.")
+                })?;
+            changes.set(property, Value::Text(format_local_datetime(&value)));
+            *local = Some(value);
+        }
+    }
+
+    if rule.is_some() && start.is_none() && due.is_none() {
+        return Err(SetError::invalid_properties()
+            .with_property(Property::RecurrenceRules)
+            .with_description("Recurring tasks require a start or due date."));
+    }
+    if matches!((start, due), (Some(start), Some(due)) if start > due) {
+        return Err(SetError::invalid_properties()
+            .with_property(Property::Due)
+            .with_description("Task cannot be due before it starts."));
+    }
+
+    let schedule = TaskSchedule::new(start, due, offset, rule);
+    for (property, value) in [
+        (
+            Property::UtcStart,
+            schedule.utc_start().map(|value| value.max(0) as u64),
+        ),
+        (
+            Property::UtcDue,
+            schedule.utc_due().map(|value| value.max(0) as u64),
+        ),
+        (Property::UtcEnd, schedule.utc_series_end()),
+    ] {
+        match value {
+            Some(value) => {
+                changes.set(property, Value::UnsignedInt(value));
+            }
+            None if current.is_some() => {
+                changes.set(property, Value::Null);
+            }
+            None => (),
+        }
+    }
+
+    ObjectIndexBuilder::new(SCHEMA)
+        .with_changes(changes)
+        .with_current_opt(current)
+        .validate()
+}
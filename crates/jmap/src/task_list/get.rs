@@ -0,0 +1,154 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    object::{index::ObjectIndexBuilder, Object},
+    types::{collection::Collection, property::Property, value::Value},
+};
+use store::{roaring::RoaringBitmap, write::BatchBuilder};
+use trc::AddContext;
+
+use crate::JMAP;
+
+use super::set::SCHEMA;
+
+pub const DEFAULT_TASK_LIST_ID: u32 = 0;
+
+impl JMAP {
+    pub async fn task_list_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::Name,
+            Property::Description,
+            Property::Color,
+            Property::SortOrder,
+            Property::IsSubscribed,
+            Property::IsDefault,
+            Property::TimeZone,
+            Property::MyRights,
+        ]);
+        let account_id = request.account_id.document_id();
+        let task_list_ids = self.task_list_get_or_create(account_id).await?;
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            task_list_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: self
+                .get_state(account_id, Collection::TaskList)
+                .await?
+                .into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the task list object
+            let document_id = id.document_id();
+            if !task_list_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let mut task_list = if let Some(task_list) = self
+                .get_property::<Object<Value>>(
+                    account_id,
+                    Collection::TaskList,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                task_list
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                let value = match property {
+                    Property::Id => Value::Id(id),
+                    Property::SortOrder => task_list
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::UnsignedInt(0)),
+                    Property::IsSubscribed => task_list
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::Bool(true)),
+                    Property::IsDefault => task_list
+                        .properties
+                        .remove(property)
+                        .unwrap_or(Value::Bool(false)),
+                    Property::MyRights => {
+                        // Task lists can only be accessed by their owner
+                        let mut rights = Object::with_capacity(7);
+                        for right in [
+                            "mayReadItems",
+                            "mayWriteAll",
+                            "mayWriteOwn",
+                            "mayUpdatePrivate",
+                            "mayRSVP",
+                            "mayAdmin",
+                            "mayDelete",
+                        ] {
+                            rights.append(Property::_T(right.to_string()), Value::Bool(true));
+                        }
+                        Value::Object(rights)
+                    }
+                    property => task_list.remove(property),
+                };
+                result.append(property.clone(), value);
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+
+    pub async fn task_list_get_or_create(&self, account_id: u32) -> trc::Result<RoaringBitmap> {
+        let mut task_list_ids = self
+            .get_document_ids(account_id, Collection::TaskList)
+            .await?
+            .unwrap_or_default();
+        if !task_list_ids.is_empty() {
+            return Ok(task_list_ids);
+        }
+
+        // Create the default task_list
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::TaskList)
+            .create_document_with_id(DEFAULT_TASK_LIST_ID)
+            .custom(
+                ObjectIndexBuilder::new(SCHEMA).with_changes(
+                    Object::with_capacity(2)
+                        .with_property(Property::Name, "Tasks")
+                        .with_property(Property::IsDefault, true),
+                ),
+            );
+        task_list_ids.insert(DEFAULT_TASK_LIST_ID);
+
+        self.core
+            .storage
+            .data
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())
+            .map(|_| task_list_ids)
+    }
+}
@@ -0,0 +1,320 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::set::{SetRequest, SetResponse},
+    object::{
+        index::{IndexAs, IndexProperty, ObjectIndexBuilder},
+        task::SetArguments,
+        Object,
+    },
+    response::references::EvalObjectReferences,
+    types::{
+        collection::Collection,
+        property::Property,
+        state::StateChange,
+        type_state::DataType,
+        value::{MaybePatchValue, SetValue, Value},
+    },
+};
+use store::{
+    query::Filter,
+    write::{assert::HashedValue, log::ChangeLogBuilder, BatchBuilder},
+};
+
+use crate::{task::set::SCHEMA as TASK_SCHEMA, JMAP};
+
+pub static SCHEMA: &[IndexProperty] = &[IndexProperty::new(Property::Name)
+    .index_as(IndexAs::Text {
+        tokenize: true,
+        index: true,
+    })
+    .max_size(255)
+    .required()];
+
+impl JMAP {
+    pub async fn task_list_set(
+        &self,
+        mut request: SetRequest<SetArguments>,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let on_destroy_remove_tasks = request.arguments.on_destroy_remove_tasks.unwrap_or(false);
+        let mut task_list_ids = self.task_list_get_or_create(account_id).await?;
+        let mut response = self
+            .prepare_set_response(&request, Collection::TaskList)
+            .await?;
+        let will_destroy = request.unwrap_destroy();
+
+        // Process creates
+        let mut changes = ChangeLogBuilder::new();
+        for (id, object) in request.unwrap_create() {
+            match task_list_set_item(object, None, &response) {
+                Ok(builder) => {
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::TaskList)
+                        .create_document()
+                        .custom(builder);
+                    let document_id = self.write_batch_expect_id(batch).await?;
+                    task_list_ids.insert(document_id);
+                    changes.log_insert(Collection::TaskList, document_id);
+                    response.created(id, document_id);
+                }
+                Err(err) => {
+                    response.not_created.append(id, err);
+                }
+            }
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain task list
+            let document_id = id.document_id();
+            let task_list = if let Some(task_list) = self
+                .get_property::<HashedValue<Object<Value>>>(
+                    account_id,
+                    Collection::TaskList,
+                    document_id,
+                    Property::Value,
+                )
+                .await?
+            {
+                task_list
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+
+            match task_list_set_item(object, Some(task_list), &response) {
+                Ok(builder) => {
+                    let mut batch = BatchBuilder::new();
+                    batch
+                        .with_account_id(account_id)
+                        .with_collection(Collection::TaskList)
+                        .update_document(document_id)
+                        .custom(builder);
+                    if !batch.is_empty() {
+                        match self.core.storage.data.write(batch.build()).await {
+                            Ok(_) => {
+                                changes.log_update(Collection::TaskList, document_id);
+                            }
+                            Err(err) if err.is_assertion_failure() => {
+                                response.not_updated.append(
+                                    id,
+                                    SetError::forbidden().with_description(
+                                        "Another process modified this task list, please try again.",
+                                    ),
+                                );
+                                continue 'update;
+                            }
+                            Err(err) => {
+                                return Err(err.caused_by(trc::location!()));
+                            }
+                        }
+                    }
+                    response.updated.append(id, None);
+                }
+                Err(err) => {
+                    response.not_updated.append(id, err);
+                }
+            }
+        }
+
+        // Process deletions
+        let mut did_remove_tasks = false;
+        for id in will_destroy {
+            let document_id = id.document_id();
+            if !task_list_ids.contains(document_id) {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            }
+
+            match self
+                .task_list_destroy(
+                    account_id,
+                    document_id,
+                    &mut changes,
+                    on_destroy_remove_tasks,
+                )
+                .await?
+            {
+                Ok(removed_tasks) => {
+                    did_remove_tasks |= removed_tasks;
+                    response.destroyed.push(id);
+                }
+                Err(err) => {
+                    response.not_destroyed.append(id, err);
+                }
+            }
+        }
+
+        // Write changes
+        if !changes.is_empty() {
+            let state_change =
+                StateChange::new(account_id).with_change(DataType::TaskList, changes.change_id);
+            response.state_change = if did_remove_tasks {
+                state_change.with_change(DataType::Task, changes.change_id)
+            } else {
+                state_change
+            }
+            .into();
+            response.new_state = Some(self.commit_changes(account_id, changes).await?.into());
+        }
+
+        Ok(response)
+    }
+
+    pub async fn task_list_destroy(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        changes: &mut ChangeLogBuilder,
+        remove_tasks: bool,
+    ) -> trc::Result<Result<bool, SetError>> {
+        // Fetch record
+        let task_list = if let Some(task_list) = self
+            .get_property::<HashedValue<Object<Value>>>(
+                account_id,
+                Collection::TaskList,
+                document_id,
+                Property::Value,
+            )
+            .await?
+        {
+            task_list
+        } else {
+            return Ok(Err(SetError::not_found()));
+        };
+
+        // Verify that the task list is empty
+        let task_ids = self
+            .filter(
+                account_id,
+                Collection::Task,
+                vec![Filter::eq(Property::TaskListId, document_id)],
+            )
+            .await?
+            .results;
+        let did_remove_tasks = !task_ids.is_empty();
+        if did_remove_tasks {
+            if !remove_tasks {
+                return Ok(Err(SetError::new(SetErrorType::TaskListHasTask)
+                    .with_description("Task list is not empty.")));
+            }
+
+            // Tasks belong to a single task list, so they are deleted along with it
+            for (task_id, task) in self
+                .get_properties::<HashedValue<Object<Value>>, _, _>(
+                    account_id,
+                    Collection::Task,
+                    &task_ids,
+                    Property::Value,
+                )
+                .await?
+            {
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::Task)
+                    .delete_document(task_id)
+                    .custom(ObjectIndexBuilder::new(TASK_SCHEMA).with_current(task));
+                match self.core.storage.data.write(batch.build()).await {
+                    Ok(_) => {
+                        changes.log_delete(Collection::Task, task_id);
+                    }
+                    Err(err) if err.is_assertion_failure() => {
+                        return Ok(Err(SetError::forbidden().with_description(concat!(
+                            "Another process modified a task in this task list ",
+                            "while deleting it, please try again."
+                        ))));
+                    }
+                    Err(err) => {
+                        return Err(err.caused_by(trc::location!()));
+                    }
+                }
+            }
+        }
+
+        // Delete task list
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::TaskList)
+            .delete_document(document_id)
+            .custom(ObjectIndexBuilder::new(SCHEMA).with_current(task_list));
+        match self.core.storage.data.write(batch.build()).await {
+            Ok(_) => {
+                changes.log_delete(Collection::TaskList, document_id);
+                Ok(Ok(did_remove_tasks))
+            }
+            Err(err) if err.is_assertion_failure() => Ok(Err(SetError::forbidden()
+                .with_description(concat!(
+                    "Another process modified this task list ",
+                    "while deleting it, please try again."
+                )))),
+            Err(err) => Err(err.caused_by(trc::location!())),
+        }
+    }
+}
+
+fn task_list_set_item(
+    changes_: Object<SetValue>,
+    current: Option<HashedValue<Object<Value>>>,
+    response: &SetResponse,
+) -> Result<ObjectIndexBuilder, SetError> {
+    let mut changes = Object::with_capacity(changes_.properties.len());
+    let is_default = current.as_ref().map_or(false, |current| {
+        matches!(
+            current.inner.properties.get(&Property::IsDefault),
+            Some(Value::Bool(true))
+        )
+    });
+
+    for (property, value) in changes_.properties {
+        let value = match (&property, response.eval_object_references(value)?) {
+            (Property::Name, MaybePatchValue::Value(Value::Text(value))) => Value::Text(value),
+            (
+                Property::Description | Property::Color | Property::TimeZone,
+                MaybePatchValue::Value(Value::Text(value)),
+            ) if value.len() < 2048 => Value::Text(value),
+            (Property::SortOrder, MaybePatchValue::Value(Value::UnsignedInt(value))) => {
+                Value::UnsignedInt(value)
+            }
+            (Property::IsSubscribed, MaybePatchValue::Value(Value::Bool(value))) => {
+                Value::Bool(value)
+            }
+            (
+                Property::Description | Property::Color | Property::TimeZone | Property::SortOrder,
+                MaybePatchValue::Value(Value::Null),
+            ) => Value::Null,
+            (Property::IsDefault, MaybePatchValue::Value(Value::Bool(value)))
+                if value == is_default =>
+            {
+                // Read-only, only accepted when unchanged
+                continue;
+            }
+            _ => {
+                return Err(SetError::invalid_properties()
+                    .with_property(property)
+                    .with_description("Invalid property or value.".to_string()))
+            }
+        };
+        changes.append(property, value);
+    }
+
+    ObjectIndexBuilder::new(SCHEMA)
+        .with_changes(changes)
+        .with_current_opt(current)
+        .validate()
+}
@@ -27,6 +27,7 @@ use store::write::{
     log::{Changes, LogInsert},
     BatchBuilder, BlobOp, DirectoryClass, F_CLEAR, F_VALUE,
 };
+use trc::AddContext;
 
 use crate::{
     sieve::set::{ObjectBlobId, SCHEMA},
@@ -226,6 +227,7 @@ impl JMAP {
             }
 
             // Create sieve script only if there are changes
+            let mut added_quota = 0;
             if build_script {
                 // Upload new blob
                 let hash = self
@@ -244,6 +246,7 @@ impl JMAP {
                 );
 
                 let script_size = blob_id.section.as_ref().unwrap().size as i64;
+                added_quota = script_size;
 
                 if let Some(current) = obj.current() {
                     let current_blob_id = current.inner.blob_id().ok_or_else(|| {
@@ -265,6 +268,7 @@ impl JMAP {
                         std::cmp::Ordering::Less => -current_script_size + script_size,
                         std::cmp::Ordering::Equal => 0,
                     };
+                    added_quota = quota;
                     if quota != 0 {
                         batch.add(DirectoryClass::UsedQuota(account_id), quota);
 
@@ -294,6 +298,13 @@ impl JMAP {
             let document_id = if !batch.is_empty() {
                 let ids = self.write_batch(batch).await?;
                 response.new_state = Some(change_id.into());
+
+                // Notify clients if the quota warning threshold was crossed
+                if added_quota > 0 {
+                    self.notify_quota_threshold(&resource_token, added_quota as u64, change_id)
+                        .await
+                        .caused_by(trc::location!())?;
+                }
                 match document_id {
                     Some(document_id) => document_id,
                     None => ids.last_document_id()?,
@@ -15,12 +15,12 @@ use jmap_proto::{
     request::websocket::{
         WebSocketMessage, WebSocketRequestError, WebSocketResponse, WebSocketStateChange,
     },
-    types::type_state::DataType,
+    types::{collection::Collection, state::State, type_state::DataType},
 };
 use tokio_tungstenite::WebSocketStream;
 use trc::JmapEvent;
 use tungstenite::Message;
-use utils::map::bitmap::Bitmap;
+use utils::map::{bitmap::Bitmap, vec_map::VecMap};
 
 use crate::{
     api::http::{HttpSessionData, ToRequestError},
@@ -72,6 +72,7 @@ impl JMAP {
 
         let mut changes = WebSocketStateChange::new(None);
         let mut change_types: Bitmap<DataType> = Bitmap::new();
+        let mut push_states: VecMap<u32, u64> = VecMap::new();
 
         loop {
             tokio::select! {
@@ -80,7 +81,7 @@ impl JMAP {
                         Ok(Some(Ok(event))) => {
                             match event {
                                 Message::Text(text) => {
-                                    let response = match WebSocketMessage::parse(
+                                    let response: Option<String> = match WebSocketMessage::parse(
                                         text.as_bytes(),
                                         self.core.jmap.request_max_calls,
                                         self.core.jmap.request_max_size,
@@ -95,7 +96,7 @@ impl JMAP {
                                                 .await;
 
                                             WebSocketResponse::from_response(response, request.id)
-                                            .to_json()
+                                            .to_json().into()
                                         }
                                         Ok(WebSocketMessage::PushEnable(push_enable)) => {
                                             change_types = if !push_enable.data_types.is_empty() {
@@ -103,24 +104,52 @@ impl JMAP {
                                             } else {
                                                 Bitmap::all()
                                             };
-                                            continue;
+
+                                            // Queue any changes missed since the client's last push state
+                                            if let Some(push_state) = push_enable.push_state.as_deref() {
+                                                for (account_id, change_id) in parse_push_state(push_state) {
+                                                    let change_id = match self
+                                                        .queue_missed_changes(
+                                                            &access_token,
+                                                            account_id,
+                                                            change_id,
+                                                            &change_types,
+                                                            &mut changes,
+                                                        )
+                                                        .await
+                                                    {
+                                                        Ok(change_id) => change_id,
+                                                        Err(err) => {
+                                                            trc::error!(err
+                                                                .details("Failed to obtain missed changes")
+                                                                .span_id(session.session_id));
+                                                            change_id
+                                                        }
+                                                    };
+                                                    let last_change_id = push_states.get_mut_or_insert(account_id);
+                                                    *last_change_id = (*last_change_id).max(change_id);
+                                                }
+                                            }
+                                            None
                                         }
                                         Ok(WebSocketMessage::PushDisable) => {
                                             change_types = Bitmap::new();
-                                            continue;
+                                            None
                                         }
                                         Err(err) => {
                                             let response = WebSocketRequestError::from(err.to_request_error()).to_json();
                                             trc::error!(err.details("Failed to parse WebSocket message").span_id(session.session_id));
-                                            response
+                                            response.into()
                                         },
                                     };
-                                    if let Err(err) = stream.send(Message::Text(response)).await {
-                                        trc::event!(Jmap(JmapEvent::WebsocketError),
-                                                    Details = "Failed to send text message",
-                                                    SpanId = session.session_id,
-                                                    Reason = err.to_string()
-                                        );
+                                    if let Some(response) = response {
+                                        if let Err(err) = stream.send(Message::Text(response)).await {
+                                            trc::event!(Jmap(JmapEvent::WebsocketError),
+                                                        Details = "Failed to send text message",
+                                                        SpanId = session.session_id,
+                                                        Reason = err.to_string()
+                                            );
+                                        }
                                     }
                                 }
                                 Message::Ping(bytes) => {
@@ -177,6 +206,8 @@ impl JMAP {
                                         .changed
                                         .get_mut_or_insert(state_change.account_id.into())
                                         .set(type_state, change_id.into());
+                                    let last_change_id = push_states.get_mut_or_insert(state_change.account_id);
+                                    *last_change_id = (*last_change_id).max(change_id);
                                 }
                             }
                     } else {
@@ -195,6 +226,7 @@ impl JMAP {
                 // Send any queued changes
                 let elapsed = last_changes_sent.elapsed();
                 if elapsed >= throttle {
+                    changes.push_state = build_push_state(&push_states).into();
                     if let Err(err) = stream.send(Message::Text(changes.to_json())).await {
                         trc::event!(
                             Jmap(JmapEvent::WebsocketError),
@@ -225,4 +257,64 @@ impl JMAP {
             }
         }
     }
+
+    async fn queue_missed_changes(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        since_change_id: u64,
+        change_types: &Bitmap<DataType>,
+        changes: &mut WebSocketStateChange,
+    ) -> trc::Result<u64> {
+        let mut last_change_id = since_change_id;
+        for collection in (0..Collection::None as u8).map(Collection::from) {
+            let data_type = match DataType::try_from(collection) {
+                Ok(data_type)
+                    if change_types.contains(data_type)
+                        && access_token.has_access(account_id, collection) =>
+                {
+                    data_type
+                }
+                _ => continue,
+            };
+
+            if let Some(change_id) = self
+                .core
+                .storage
+                .data
+                .get_last_change_id(account_id, collection)
+                .await?
+                .filter(|change_id| *change_id > since_change_id)
+            {
+                changes
+                    .changed
+                    .get_mut_or_insert(account_id.into())
+                    .set(data_type, State::Exact(change_id));
+                last_change_id = last_change_id.max(change_id);
+            }
+        }
+
+        Ok(last_change_id)
+    }
+}
+
+fn build_push_state(push_states: &VecMap<u32, u64>) -> String {
+    let mut push_state = String::with_capacity(push_states.len() * 12);
+    for (account_id, change_id) in push_states.iter() {
+        if !push_state.is_empty() {
+            push_state.push('.');
+        }
+        push_state.push_str(&format!("{account_id:x}-{change_id:x}"));
+    }
+    push_state
+}
+
+fn parse_push_state(push_state: &str) -> impl Iterator<Item = (u32, u64)> + '_ {
+    push_state.split('.').filter_map(|item| {
+        let (account_id, change_id) = item.split_once('-')?;
+        Some((
+            u32::from_str_radix(account_id, 16).ok()?,
+            u64::from_str_radix(change_id, 16).ok()?,
+        ))
+    })
 }
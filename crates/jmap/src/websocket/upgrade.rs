@@ -98,6 +98,7 @@ impl JMAP {
             content_type: "".into(),
             content_disposition: "".into(),
             cache_control: "".into(),
+            headers: vec![],
             body: HttpResponseBody::WebsocketUpgrade(derived_key),
         })
     }
@@ -6,8 +6,11 @@
 
 use std::time::Duration;
 
-use common::{config::server::ServerProtocol, manager::boot::BootManager, Ipc, IPC_CHANNEL_BUFFER};
-use directory::backend::internal::MigrateDirectory;
+use common::{
+    config::server::ServerProtocol,
+    manager::{boot::BootManager, migrate::SchemaMigration},
+    Ipc, IPC_CHANNEL_BUFFER,
+};
 use imap::core::{ImapSessionManager, IMAP};
 use jmap::{api::JmapSessionManager, services::gossip::spawn::GossiperBuilder, JMAP};
 use managesieve::core::ManageSieveSessionManager;
@@ -15,7 +18,7 @@ use pop3::Pop3SessionManager;
 use smtp::core::{SmtpSessionManager, SMTP};
 use tokio::sync::mpsc;
 use trc::Collector;
-use utils::wait_for_shutdown;
+use utils::{failed, wait_for_shutdown};
 
 #[cfg(not(target_env = "msvc"))]
 use jemallocator::Jemalloc;
@@ -33,6 +36,12 @@ async fn main() -> std::io::Result<()> {
     let mut config = init.config;
     let core = init.core;
 
+    // Migrate the store schema before any service accesses the store, a failed
+    // migration or a schema newer than this server supports aborts startup
+    if let Err(err) = core.load().storage.data.migrate().await {
+        failed(&format!("Store schema migration failed: {err:?}"));
+    }
+
     // Setup IPC channels
     let (delivery_tx, delivery_rx) = mpsc::channel(IPC_CHANNEL_BUFFER);
     let ipc = Ipc { delivery_tx };
@@ -57,12 +66,6 @@ async fn main() -> std::io::Result<()> {
     #[cfg(feature = "enterprise")]
     core.load().as_ref().log_license_details();
 
-    // Migrate directory
-    if let Err(err) = core.load().storage.data.migrate_directory().await {
-        trc::error!(err.details("Directory migration failed"));
-        std::process::exit(1);
-    }
-
     // Spawn servers
     let (shutdown_tx, shutdown_rx) = init.servers.spawn(|server, acceptor, shutdown_rx| {
         match &server.protocol {
@@ -151,6 +151,21 @@ impl<T: SessionStream> Session<T> {
             | Command::CheckScript
             | Command::Unauthenticate => {
                 if let State::Authenticated { access_token, .. } = &self.state {
+                    // Reject writes while the account is in maintenance mode
+                    if matches!(
+                        command.command,
+                        Command::PutScript
+                            | Command::SetActive
+                            | Command::DeleteScript
+                            | Command::RenameScript
+                    ) {
+                        self.jmap
+                            .core
+                            .storage
+                            .maintenance
+                            .assert_writable(access_token.primary_id())?;
+                    }
+
                     if let Some(rate) = &self.jmap.core.imap.rate_requests {
                         if self
                             .jmap
@@ -22,6 +22,16 @@ impl SessionManager for ManageSieveSessionManager {
         async move {
             // Create session
             let jmap = JMAP::from(self.imap.jmap_instance);
+            let greeting = jmap
+                .core
+                .eval_if::<String, _>(
+                    &jmap.core.network.sieve_greeting,
+                    &session,
+                    session.session_id,
+                )
+                .await
+                .filter(|greeting| !greeting.is_empty())
+                .unwrap_or_else(|| SERVER_GREETING.to_string());
             let mut session = Session {
                 receiver: Receiver::with_max_request_size(jmap.core.imap.max_request_size)
                     .with_start_state(receiver::State::Command { is_uid: false }),
@@ -36,7 +46,7 @@ impl SessionManager for ManageSieveSessionManager {
             };
 
             if session
-                .write(&session.handle_capability(SERVER_GREETING).await.unwrap())
+                .write(&session.handle_capability(greeting.clone()).await.unwrap())
                 .await
                 .is_ok()
                 && session.handle_conn().await
@@ -44,7 +54,7 @@ impl SessionManager for ManageSieveSessionManager {
             {
                 if let Ok(mut session) = session.into_tls().await {
                     let _ = session
-                        .write(&session.handle_capability(SERVER_GREETING).await.unwrap())
+                        .write(&session.handle_capability(greeting).await.unwrap())
                         .await;
                     session.handle_conn().await;
                 }
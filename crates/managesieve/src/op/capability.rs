@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::time::Instant;
+use std::{borrow::Cow, time::Instant};
 
 use common::listener::SessionStream;
 use jmap_proto::request::capability::Capabilities;
@@ -12,7 +12,10 @@ use jmap_proto::request::capability::Capabilities;
 use crate::core::{Session, StatusResponse};
 
 impl<T: SessionStream> Session<T> {
-    pub async fn handle_capability(&self, message: &'static str) -> trc::Result<Vec<u8>> {
+    pub async fn handle_capability(
+        &self,
+        message: impl Into<Cow<'static, str>>,
+    ) -> trc::Result<Vec<u8>> {
         let op_start = Instant::now();
 
         let mut response = Vec::with_capacity(128);
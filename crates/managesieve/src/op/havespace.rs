@@ -9,6 +9,7 @@ use std::time::Instant;
 use common::listener::SessionStream;
 use directory::Permission;
 use imap_proto::receiver::Request;
+use jmap_proto::types::collection::Collection;
 use trc::AddContext;
 
 use crate::core::{Command, ResponseCode, Session, StatusResponse};
@@ -46,7 +47,41 @@ impl<T: SessionStream> Session<T> {
         // Validate name
         let access_token = self.state.access_token();
         let account_id = access_token.primary_id();
-        self.validate_name(account_id, &name).await?;
+        let existing_id = self.validate_name(account_id, &name).await?;
+
+        // Validate script limits
+        if size > self.jmap.core.jmap.sieve_max_script_size {
+            return Err(trc::ManageSieveEvent::Error
+                .into_err()
+                .details("Script is too large.")
+                .code(ResponseCode::QuotaMaxSize));
+        }
+        if existing_id.is_none()
+            && self
+                .jmap
+                .get_document_ids(account_id, Collection::SieveScript)
+                .await
+                .caused_by(trc::location!())?
+                .map(|ids| ids.len() as usize)
+                .unwrap_or(0)
+                > self.jmap.core.jmap.sieve_max_scripts
+        {
+            return Err(trc::ManageSieveEvent::Error
+                .into_err()
+                .details("Too many scripts.")
+                .code(ResponseCode::QuotaMaxScripts));
+        }
+        if self
+            .jmap
+            .sieve_scripts_over_quota(account_id, existing_id, size as u64)
+            .await
+            .caused_by(trc::location!())?
+        {
+            return Err(trc::ManageSieveEvent::Error
+                .into_err()
+                .details("Total size of Sieve scripts exceeds quota.")
+                .code(ResponseCode::QuotaMaxSize));
+        }
 
         // Validate quota
         if access_token.quota == 0
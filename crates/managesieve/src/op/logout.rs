@@ -16,11 +16,15 @@ impl<T: AsyncRead + AsyncWrite> Session<T> {
             Elapsed = trc::Value::Duration(0)
         );
 
-        Ok(StatusResponse::ok(concat!(
-            "Stalwart ManageSieve v",
-            env!("CARGO_PKG_VERSION"),
-            " bids you farewell."
-        ))
+        Ok(StatusResponse::ok(if !self.jmap.core.network.hide_version {
+            concat!(
+                "Stalwart ManageSieve v",
+                env!("CARGO_PKG_VERSION"),
+                " bids you farewell."
+            )
+        } else {
+            "Stalwart ManageSieve bids you farewell."
+        })
         .into_bytes())
     }
 }
@@ -17,7 +17,11 @@ use jmap_proto::{
 use sieve::compiler::ErrorType;
 use store::{
     query::Filter,
-    write::{assert::HashedValue, log::LogInsert, BatchBuilder, BlobOp, DirectoryClass},
+    write::{
+        assert::HashedValue,
+        log::{Changes, LogInsert},
+        BatchBuilder, BlobOp, DirectoryClass,
+    },
     BlobClass,
 };
 use trc::AddContext;
@@ -100,7 +104,22 @@ impl<T: SessionStream> Session<T> {
         }
 
         // Validate name
-        if let Some(document_id) = self.validate_name(account_id, &name).await? {
+        let existing_id = self.validate_name(account_id, &name).await?;
+
+        // Check total scripts size
+        if self
+            .jmap
+            .sieve_scripts_over_quota(account_id, existing_id, script_size as u64)
+            .await
+            .caused_by(trc::location!())?
+        {
+            return Err(trc::ManageSieveEvent::Error
+                .into_err()
+                .details("Total size of Sieve scripts exceeds quota.")
+                .code(ResponseCode::QuotaMaxSize));
+        }
+
+        if let Some(document_id) = existing_id {
             // Obtain script values
             let script = self
                 .jmap
@@ -146,6 +165,7 @@ impl<T: SessionStream> Session<T> {
                 .with_account_id(account_id)
                 .with_collection(Collection::SieveScript)
                 .update_document(document_id)
+                .log(Changes::update([document_id]))
                 .clear(BlobOp::Link {
                     hash: prev_blob_id.hash.clone(),
                 })
@@ -87,6 +87,11 @@ impl BayesTokenCache {
             self.negative.lock().remove(hash);
         }
     }
+
+    pub fn clear(&self) {
+        self.positive.lock().clear();
+        self.negative.lock().clear();
+    }
 }
 
 impl Default for BayesTokenCache {
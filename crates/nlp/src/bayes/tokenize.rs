@@ -24,9 +24,28 @@ pub struct BayesTokenizer<'x> {
     tokenizer: TypesTokenizer<'x>,
     stemmer: Stemmer,
     stop_words: Option<&'static phf::Set<&'static str>>,
+    config: BayesTokenizerConfig,
     tokens: Vec<Cow<'x, str>>,
 }
 
+// Token segmentation and normalization settings. Token hashes depend on these
+// settings, so changing them invalidates previously learned tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BayesTokenizerConfig {
+    pub cjk: CjkSegmentation,
+    pub split_scripts: bool,
+    pub stemming: bool,
+    pub stop_words: bool,
+    pub strip_accents: bool,
+    pub fold_width: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CjkSegmentation {
+    Dictionary,
+    Bigram,
+}
+
 enum Stemmer {
     IndoEuropean(rust_stemmers::Stemmer),
     Mandarin,
@@ -36,6 +55,10 @@ enum Stemmer {
 
 impl<'x> BayesTokenizer<'x> {
     pub fn new(text: &'x str) -> Self {
+        Self::with_config(text, BayesTokenizerConfig::default())
+    }
+
+    pub fn with_config(text: &'x str, config: BayesTokenizerConfig) -> Self {
         // Detect language
         let (mut language, score) =
             LanguageDetector::detect_single(text).unwrap_or((Language::English, 1.0));
@@ -49,14 +72,74 @@ impl<'x> BayesTokenizer<'x> {
             stemmer: match language {
                 Language::Mandarin => Stemmer::Mandarin,
                 Language::Japanese => Stemmer::Japanese,
+                _ if !config.stemming => Stemmer::None,
                 _ => STEMMER_MAP[language as usize]
                     .map(|algo| Stemmer::IndoEuropean(rust_stemmers::Stemmer::create(algo)))
                     .unwrap_or(Stemmer::None),
             },
-            stop_words: STOP_WORDS[language as usize],
+            stop_words: if config.stop_words {
+                STOP_WORDS[language as usize]
+            } else {
+                None
+            },
+            config,
             tokens: vec![],
         }
     }
+
+    fn normalize(&self, word: &str) -> String {
+        if self.config.fold_width {
+            word.chars()
+                .flat_map(char::to_lowercase)
+                .map(fold_width)
+                .collect()
+        } else {
+            word.to_lowercase()
+        }
+    }
+
+    fn segment(&self, word: String, tokens: &mut Vec<Cow<'x, str>>) {
+        if self
+            .stop_words
+            .map_or(false, |sw| sw.contains(word.as_str()))
+        {
+            return;
+        }
+
+        let is_cjk = word.chars().any(is_cjk);
+        if is_cjk && self.config.cjk == CjkSegmentation::Bigram {
+            // Overlapping character bigrams do not depend on a dictionary and
+            // work equally well for Chinese, Japanese and Korean
+            let chars = word.chars().collect::<Vec<_>>();
+            if chars.len() == 1 {
+                tokens.push(word.into());
+            } else {
+                for pair in chars.windows(2) {
+                    tokens.push(pair.iter().collect::<String>().into());
+                }
+            }
+            return;
+        }
+
+        match &self.stemmer {
+            Stemmer::IndoEuropean(stemmer) => match stemmer.stem(&word) {
+                Cow::Borrowed(_) => tokens.push(word.into()),
+                Cow::Owned(stemmed_word) => tokens.push(stemmed_word.into()),
+            },
+            Stemmer::Mandarin if is_cjk || !self.config.split_scripts => {
+                tokens.extend(
+                    JIEBA
+                        .cut(&word, false)
+                        .into_iter()
+                        .map(|word| Cow::from(word.to_string())),
+                );
+            }
+            Stemmer::Japanese if is_cjk || !self.config.split_scripts => {
+                tokens.extend(tinysegmenter::tokenize(&word).into_iter().map(Cow::from));
+            }
+            Stemmer::Mandarin | Stemmer::Japanese | Stemmer::None => tokens.push(word.into()),
+        }
+    }
 }
 
 impl<'x> Iterator for BayesTokenizer<'x> {
@@ -72,43 +155,38 @@ impl<'x> Iterator for BayesTokenizer<'x> {
 
             let word: Cow<str> = match token.word {
                 TokenType::Alphabetic(word) => {
-                    let word = word.to_lowercase();
-                    if self
-                        .stop_words
-                        .map_or(false, |sw| sw.contains(word.as_str()))
-                    {
-                        continue;
-                    }
-                    match &self.stemmer {
-                        Stemmer::IndoEuropean(stemmer) => match stemmer.stem(&word) {
-                            Cow::Borrowed(_) => word.into(),
-                            Cow::Owned(stemmed_word) => stemmed_word.into(),
-                        },
-                        Stemmer::Mandarin => {
-                            let mut result = JIEBA.cut(&word, false).into_iter();
-                            if let Some(stemmed_word) = result.next() {
-                                let stemmed_word = stemmed_word.to_string();
-                                self.tokens = result
-                                    .rev()
-                                    .map(|word| Cow::from(word.to_string()))
-                                    .collect::<Vec<_>>();
-                                stemmed_word.into()
-                            } else {
-                                // This shouldn't happen, but just in case
-                                continue;
+                    let word = self.normalize(word);
+                    let mut tokens = Vec::new();
+                    if self.config.split_scripts {
+                        // Break words where the script changes between CJK and
+                        // other scripts, as Unicode word boundaries do
+                        let mut run = String::new();
+                        let mut run_is_cjk = false;
+                        for ch in word.chars() {
+                            if !run.is_empty() && is_cjk(ch) != run_is_cjk {
+                                self.segment(std::mem::take(&mut run), &mut tokens);
                             }
+                            run_is_cjk = is_cjk(ch);
+                            run.push(ch);
                         }
-                        Stemmer::Japanese => {
-                            let mut result = tinysegmenter::tokenize(&word).into_iter();
-                            if let Some(stemmed_word) = result.next() {
-                                self.tokens = result.rev().map(Cow::from).collect::<Vec<_>>();
-                                stemmed_word.into()
-                            } else {
-                                // This shouldn't happen, but just in case
-                                continue;
-                            }
+                        if !run.is_empty() {
+                            self.segment(run, &mut tokens);
                         }
-                        Stemmer::None => word.into(),
+                    } else {
+                        self.segment(word, &mut tokens);
+                    }
+
+                    if self.config.strip_accents {
+                        // Accents are removed after stemming, stemmers expect them
+                        tokens = tokens.into_iter().map(remove_accents).collect();
+                    }
+
+                    tokens.reverse();
+                    if let Some(word) = tokens.pop() {
+                        self.tokens = tokens;
+                        word
+                    } else {
+                        continue;
                     }
                 }
 
@@ -150,6 +228,93 @@ impl<'x> Iterator for BayesTokenizer<'x> {
     }
 }
 
+impl BayesTokenizerConfig {
+    // Stable identifier of the settings that affect token hashes
+    pub fn fingerprint(&self) -> u64 {
+        (match self.cjk {
+            CjkSegmentation::Dictionary => 0,
+            CjkSegmentation::Bigram => 1,
+        }) | (self.split_scripts as u64) << 1
+            | (self.stemming as u64) << 2
+            | (self.stop_words as u64) << 3
+            | (self.strip_accents as u64) << 4
+            | (self.fold_width as u64) << 5
+    }
+}
+
+impl Default for BayesTokenizerConfig {
+    fn default() -> Self {
+        Self {
+            cjk: CjkSegmentation::Dictionary,
+            split_scripts: false,
+            stemming: true,
+            stop_words: true,
+            strip_accents: false,
+            fold_width: false,
+        }
+    }
+}
+
+fn is_cjk(ch: char) -> bool {
+    matches!(ch as u32,
+        0x1100..=0x11FF // Hangul Jamo
+        | 0x3040..=0x30FF // Hiragana and Katakana
+        | 0x3130..=0x318F // Hangul Compatibility Jamo
+        | 0x3400..=0x4DBF // CJK Unified Ideographs Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF // Hangul Syllables
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0xFF66..=0xFF9F // Halfwidth Katakana
+        | 0x20000..=0x2FA1F // CJK Unified Ideographs Extensions B to F
+    )
+}
+
+// Maps full-width ASCII variants to their ASCII equivalents
+fn fold_width(ch: char) -> char {
+    match ch as u32 {
+        0xFF01..=0xFF5E => char::from_u32(ch as u32 - 0xFEE0).unwrap_or(ch),
+        0x3000 => ' ',
+        _ => ch,
+    }
+}
+
+// Removes diacritics from lowercase Latin letters and expands ligatures
+fn remove_accents(word: Cow<'_, str>) -> Cow<'_, str> {
+    if word.is_ascii() {
+        return word;
+    }
+
+    let mut result = String::with_capacity(word.len());
+    for ch in word.chars() {
+        match ch {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => result.push('a'),
+            'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => result.push('c'),
+            'ď' | 'đ' => result.push('d'),
+            'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => result.push('e'),
+            'ĝ' | 'ğ' | 'ġ' | 'ģ' => result.push('g'),
+            'ĥ' | 'ħ' => result.push('h'),
+            'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => result.push('i'),
+            'ĵ' => result.push('j'),
+            'ķ' => result.push('k'),
+            'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => result.push('l'),
+            'ñ' | 'ń' | 'ņ' | 'ň' => result.push('n'),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => result.push('o'),
+            'ŕ' | 'ŗ' | 'ř' => result.push('r'),
+            'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => result.push('s'),
+            'ţ' | 'ť' | 'ŧ' | 'ț' => result.push('t'),
+            'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => result.push('u'),
+            'ŵ' => result.push('w'),
+            'ý' | 'ÿ' | 'ŷ' => result.push('y'),
+            'ź' | 'ż' | 'ž' => result.push('z'),
+            'ß' => result.push_str("ss"),
+            'æ' => result.push_str("ae"),
+            'œ' => result.push_str("oe"),
+            _ => result.push(ch),
+        }
+    }
+    result.into()
+}
+
 fn number_to_tag(prefix: &str, num: &str) -> String {
     format!(
         "{}_{}_{}",
@@ -1147,7 +1312,7 @@ pub static SYMBOLS: phf::Set<char> = phf::phf_set! {
 mod tests {
     use std::borrow::Cow;
 
-    use crate::bayes::tokenize::BayesTokenizer;
+    use crate::bayes::tokenize::{BayesTokenizer, BayesTokenizerConfig, CjkSegmentation};
 
     #[test]
     fn bayes_tokenizer() {
@@ -1239,4 +1404,35 @@ mod tests {
             assert_eq!(input, expect,);
         }
     }
+
+    #[test]
+    fn bayes_tokenizer_config() {
+        let config = BayesTokenizerConfig {
+            cjk: CjkSegmentation::Bigram,
+            split_scripts: true,
+            stemming: false,
+            stop_words: true,
+            strip_accents: true,
+            fold_width: true,
+        };
+        assert_ne!(
+            config.fingerprint(),
+            BayesTokenizerConfig::default().fingerprint()
+        );
+
+        let inputs = [
+            ("己所不欲,勿施于人。", vec!["己所", "所不", "不欲", "勿施", "施于", "于人"]),
+            ("시작이 반이다", vec!["시작", "작이", "반이", "이다"]),
+            ("Stalwart邮件", vec!["stalwart", "邮件"]),
+            ("Ｆｒｅｅ Crème brûlée", vec!["free", "creme", "brulee"]),
+            ("Straße Œuvre", vec!["strasse", "oeuvre"]),
+        ];
+
+        for (input, expect) in inputs.iter() {
+            let input = BayesTokenizer::with_config(input, config).collect::<Vec<_>>();
+            let expect = expect.iter().copied().map(Cow::from).collect::<Vec<_>>();
+
+            assert_eq!(input, expect,);
+        }
+    }
 }
@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use super::Language;
+use super::{stemmer::Stemmer, Language};
 
 fn escape_char(c: char, string: &mut String) {
     match c {
@@ -28,9 +28,12 @@ fn escape_char_len(c: char) -> usize {
     }
 }
 
+pub const MAX_SNIPPET_LENGTH: usize = 255;
+
 pub struct Term {
     offset: usize,
     len: usize,
+    needle: usize,
 }
 
 pub fn generate_snippet(
@@ -38,6 +41,7 @@ pub fn generate_snippet(
     needles: &[impl AsRef<str>],
     language: Language,
     is_exact: bool,
+    context_length: usize,
 ) -> Option<String> {
     let mut terms = Vec::new();
     if is_exact {
@@ -48,23 +52,31 @@ pub fn generate_snippet(
                 .zip(tokens)
                 .all(|(needle, token)| needle.as_ref() == token.word.as_ref())
             {
-                for token in tokens {
+                for (needle, token) in tokens.iter().enumerate() {
                     terms.push(Term {
                         offset: token.from,
                         len: token.to - token.from,
+                        needle,
                     });
                 }
             }
         }
     } else {
-        for token in language.tokenize_text(text, 200) {
-            if needles.iter().any(|needle| {
+        // Match stemmed words as well, same as the full-text index does
+        for token in Stemmer::new(text, language, 200) {
+            if let Some(needle) = needles.iter().position(|needle| {
                 let needle = needle.as_ref();
-                needle == token.word.as_ref() || needle.len() > 2 && token.word.contains(needle)
+                needle == token.word.as_ref()
+                    || token
+                        .stemmed_word
+                        .as_ref()
+                        .map_or(false, |stemmed_word| needle == stemmed_word.as_ref())
+                    || needle.len() > 2 && token.word.contains(needle)
             }) {
                 terms.push(Term {
                     offset: token.from,
                     len: token.to - token.from,
+                    needle,
                 });
             }
         }
@@ -73,7 +85,35 @@ pub fn generate_snippet(
         return None;
     }
 
-    let mut snippet = String::with_capacity(text.len());
+    // Start at the earliest term that highlights the most distinct needles
+    let all_needles = terms
+        .iter()
+        .fold(0u64, |acc, term| acc | needle_mask(term.needle));
+    let mut best_snippet: Option<(String, u32)> = None;
+    for start in 0..terms.len() {
+        let (snippet, matched) = build_snippet(text, &terms[start..], context_length)?;
+        let num_matched = matched.count_ones();
+        if best_snippet
+            .as_ref()
+            .map_or(true, |(_, best_matched)| num_matched > *best_matched)
+        {
+            best_snippet = Some((snippet, num_matched));
+            if matched == all_needles {
+                break;
+            }
+        }
+    }
+
+    best_snippet.map(|(snippet, _)| snippet)
+}
+
+fn needle_mask(needle: usize) -> u64 {
+    1 << needle.min(63)
+}
+
+fn build_snippet(text: &str, terms: &[Term], context_length: usize) -> Option<(String, u64)> {
+    let mut snippet = String::with_capacity(MAX_SNIPPET_LENGTH + 1);
+    let mut matched = 0;
     let start_offset = terms.first()?.offset;
 
     if start_offset > 0 {
@@ -83,7 +123,7 @@ pub fn generate_snippet(
 
         if text.len() > 240 {
             for (pos, char) in text.get(0..start_offset)?.char_indices().rev() {
-                // Add up to 2 words or 40 characters of context
+                // Add up to 2 words or the configured number of characters of context
                 if char.is_whitespace() {
                     if !last_is_space {
                         word_count += 1;
@@ -96,7 +136,7 @@ pub fn generate_snippet(
                     last_is_space = false;
                 }
                 from_offset = pos;
-                if start_offset - from_offset >= 40 {
+                if start_offset - from_offset >= context_length {
                     break;
                 }
             }
@@ -119,13 +159,14 @@ pub fn generate_snippet(
     let mut terms = terms.iter().peekable();
 
     'outer: while let Some(term) = terms.next() {
-        if snippet.len() + ("<mark>".len() * 2) + term.len + 1 > 255 {
+        if snippet.len() + ("<mark>".len() * 2) + term.len + 1 > MAX_SNIPPET_LENGTH {
             break;
         }
 
         snippet.push_str("<mark>");
         snippet.push_str(text.get(term.offset..term.offset + term.len)?);
         snippet.push_str("</mark>");
+        matched |= needle_mask(term.needle);
 
         let next_offset = if let Some(next_term) = terms.peek() {
             next_term.offset
@@ -144,7 +185,7 @@ pub fn generate_snippet(
                 last_is_space = true;
             }
 
-            if snippet.len() + escape_char_len(char) <= 255 {
+            if snippet.len() + escape_char_len(char) <= MAX_SNIPPET_LENGTH {
                 escape_char(char, &mut snippet);
             } else {
                 break 'outer;
@@ -152,7 +193,7 @@ pub fn generate_snippet(
         }
     }
 
-    Some(snippet)
+    Some((snippet, matched))
 }
 
 #[cfg(test)]
@@ -197,10 +238,10 @@ mod tests {
                         vec!["your", "country"], 
                         vec![
                             concat!(
-                            "honorably seeking <mark>your</mark> assistance in the following ways. ", 
-                            "1) To provide any bank account where this money would be transferred into. 2) ",
-                            "To serve as the guardian of this fund. 3) To make arrangement for me to come ",
-                            "over to <mark>your</mark> "
+                            "over to <mark>your</mark> <mark>country</mark> to further my education and ",
+                            "to secure a residential permit for me in <mark>your</mark> <mark>country</mark>. ",
+                            "Moreover, I am willing to offer you 30 percent of the total sum as ",
+                            "compensation for "
                             )]
                     ),
                     (
@@ -257,7 +298,7 @@ mod tests {
 
                 for part in &parts {
                     if let Some(matched) =
-                        generate_snippet(part, &needles, Language::English, false)
+                        generate_snippet(part, &needles, Language::English, false, 40)
                     {
                         results.push(matched);
                     }
@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, time::Duration};
 
 use common::listener::SessionStream;
 use jmap::mailbox::{UidMailbox, INBOX_ID};
@@ -13,7 +13,8 @@ use jmap_proto::{
     types::{collection::Collection, property::Property, value::Value},
 };
 use store::{
-    ahash::AHashMap, write::key::DeserializeBigEndian, IndexKey, IterateParams, Serialize, U32_LEN,
+    ahash::AHashMap, roaring::RoaringBitmap, write::key::DeserializeBigEndian, IndexKey,
+    IterateParams, Serialize, U32_LEN,
 };
 use trc::AddContext;
 
@@ -26,6 +27,8 @@ pub struct Mailbox {
     pub uid_validity: u32,
     pub total: u32,
     pub size: u32,
+    pub retrieved: Vec<u32>,
+    pub expire: Option<Duration>,
 }
 
 pub struct Message {
@@ -33,10 +36,17 @@ pub struct Message {
     pub uid: u32,
     pub size: u32,
     pub deleted: bool,
+    pub retrieved: bool,
 }
 
 impl<T: SessionStream> Session<T> {
     pub async fn fetch_mailbox(&self, account_id: u32) -> trc::Result<Mailbox> {
+        let expire = self
+            .jmap
+            .core
+            .pop3_expire(Some(account_id), self.session_id)
+            .await;
+
         // Obtain message ids
         let message_ids = self
             .jmap
@@ -51,11 +61,16 @@ impl<T: SessionStream> Session<T> {
             .unwrap_or_default();
 
         if message_ids.is_empty() {
-            return Ok(Mailbox::default());
+            return Ok(Mailbox {
+                account_id,
+                expire,
+                ..Default::default()
+            });
         }
 
         let mut message_map = BTreeMap::new();
         let mut message_sizes = AHashMap::new();
+        let mut retrieved_ids = RoaringBitmap::new();
 
         // Obtain UID validity
         self.jmap
@@ -120,6 +135,43 @@ impl<T: SessionStream> Session<T> {
             .await
             .caused_by(trc::location!())?;
 
+        // Obtain messages that were already retrieved
+        if expire.is_some() {
+            self.jmap
+                .core
+                .storage
+                .data
+                .iterate(
+                    IterateParams::new(
+                        IndexKey {
+                            account_id,
+                            collection: Collection::Email.into(),
+                            document_id: message_ids.min().unwrap(),
+                            field: Property::RetrievedAt.into(),
+                            key: 0u64.serialize(),
+                        },
+                        IndexKey {
+                            account_id,
+                            collection: Collection::Email.into(),
+                            document_id: message_ids.max().unwrap(),
+                            field: Property::RetrievedAt.into(),
+                            key: u64::MAX.serialize(),
+                        },
+                    )
+                    .no_values(),
+                    |key, _| {
+                        let document_id = key.deserialize_be_u32(key.len() - U32_LEN)?;
+                        if message_ids.contains(document_id) {
+                            retrieved_ids.insert(document_id);
+                        }
+
+                        Ok(true)
+                    },
+                )
+                .await
+                .caused_by(trc::location!())?;
+        }
+
         // Sort by UID
         for (message_id, uid_mailbox) in self
             .jmap
@@ -145,6 +197,7 @@ impl<T: SessionStream> Session<T> {
             messages: Vec::with_capacity(message_map.len()),
             uid_validity,
             account_id,
+            expire,
             ..Default::default()
         };
         for (uid, id) in message_map {
@@ -154,6 +207,7 @@ impl<T: SessionStream> Session<T> {
                     uid,
                     size: *size,
                     deleted: false,
+                    retrieved: retrieved_ids.contains(id),
                 });
                 mailbox.total += 1;
                 mailbox.size += *size;
@@ -75,7 +75,10 @@ impl<T: SessionStream> Session<T> {
                     .validate_access_token("access_token", &token)
                     .await
                 {
-                    Ok((account_id, _, _)) => self.jmap.core.get_access_token(account_id).await,
+                    Ok((account_id, _, _)) => {
+                        self.jmap.account_login(account_id).await;
+                        self.jmap.core.get_access_token(account_id).await
+                    }
                     Err(err) => Err(err),
                 }
             }
@@ -8,8 +8,13 @@ use std::time::Instant;
 
 use common::listener::SessionStream;
 use directory::Permission;
-use jmap_proto::types::{state::StateChange, type_state::DataType};
-use store::roaring::RoaringBitmap;
+use jmap_proto::types::{
+    collection::Collection, property::Property, state::StateChange, type_state::DataType,
+};
+use store::{
+    roaring::RoaringBitmap,
+    write::{now, BatchBuilder, F_INDEX, F_VALUE},
+};
 use trc::AddContext;
 
 use crate::{protocol::response::Response, Session, State};
@@ -20,6 +25,11 @@ impl<T: SessionStream> Session<T> {
         self.state
             .access_token()
             .assert_has_permission(Permission::Pop3Dele)?;
+        self.jmap
+            .core
+            .storage
+            .maintenance
+            .assert_writable(self.state.mailbox().account_id)?;
 
         let op_start = Instant::now();
         let mailbox = self.state.mailbox_mut();
@@ -76,6 +86,34 @@ impl<T: SessionStream> Session<T> {
         let mut deleted_docs = Vec::new();
 
         if let State::Authenticated { mailbox, .. } = &self.state {
+            // Nothing is written while the account is in maintenance mode
+            let maintenance = &self.jmap.core.storage.maintenance;
+            let is_read_only = maintenance.is_read_only(mailbox.account_id);
+
+            // Record first retrievals for the expire policy
+            if !mailbox.retrieved.is_empty() && !is_read_only {
+                let retrieved_at = now();
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(mailbox.account_id)
+                    .with_collection(Collection::Email);
+                for message in &mailbox.messages {
+                    if !message.deleted && mailbox.retrieved.contains(&message.id) {
+                        batch.update_document(message.id).value(
+                            Property::RetrievedAt,
+                            retrieved_at,
+                            F_VALUE | F_INDEX,
+                        );
+                    }
+                }
+                if !batch.is_empty() {
+                    self.jmap
+                        .write_batch(batch)
+                        .await
+                        .caused_by(trc::location!())?;
+                }
+            }
+
             let mut deleted = RoaringBitmap::new();
             for message in &mailbox.messages {
                 if message.deleted {
@@ -84,7 +122,13 @@ impl<T: SessionStream> Session<T> {
                 }
             }
 
-            if !deleted.is_empty() {
+            if !deleted.is_empty() && is_read_only {
+                let response = Response::Err::<u32>(
+                    format!("Messages could not be deleted: {}", maintenance.message()).into(),
+                )
+                .serialize();
+                self.write_bytes(response).await?;
+            } else if !deleted.is_empty() {
                 let num_deleted = deleted.len();
                 let (changes, not_deleted) = self
                     .jmap
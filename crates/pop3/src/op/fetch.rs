@@ -49,6 +49,7 @@ impl<T: SessionStream> Session<T> {
                         Elapsed = op_start.elapsed()
                     );
 
+                    let document_id = message.id;
                     self.write_bytes(
                         Response::Message::<u32> {
                             bytes,
@@ -56,7 +57,22 @@ impl<T: SessionStream> Session<T> {
                         }
                         .serialize(),
                     )
-                    .await
+                    .await?;
+
+                    // Keep track of first retrievals for the expire policy
+                    let mailbox = self.state.mailbox_mut();
+                    if lines.is_none() && mailbox.expire.is_some() {
+                        if let Some(message) = mailbox
+                            .messages
+                            .get_mut(msg.saturating_sub(1) as usize)
+                            .filter(|message| !message.retrieved)
+                        {
+                            message.retrieved = true;
+                            mailbox.retrieved.push(document_id);
+                        }
+                    }
+
+                    Ok(())
                 } else {
                     Err(trc::Pop3Event::Error
                         .into_err()
@@ -8,7 +8,7 @@ use common::listener::SessionStream;
 
 use crate::{
     protocol::{response::Response, Mechanism},
-    Session,
+    Session, State,
 };
 
 pub mod authenticate;
@@ -24,6 +24,16 @@ impl<T: SessionStream> Session<T> {
             vec![Mechanism::OAuthBearer]
         };
 
+        // The expiration policy can vary per user before authentication
+        let (expire, expire_per_user) = if let State::Authenticated { mailbox, .. } = &self.state {
+            (mailbox.expire, false)
+        } else {
+            (
+                self.jmap.core.pop3_expire(None, self.session_id).await,
+                self.jmap.core.pop3_expire_per_account(),
+            )
+        };
+
         trc::event!(
             Pop3(trc::Pop3Event::Capabilities),
             SpanId = self.session_id,
@@ -36,6 +46,8 @@ impl<T: SessionStream> Session<T> {
             Response::Capability::<u32> {
                 mechanisms,
                 stls: !self.stream.is_tls(),
+                expire: expire.map(|expire| expire.as_secs().div_ceil(86400).max(1)),
+                expire_per_user,
             }
             .serialize(),
         )
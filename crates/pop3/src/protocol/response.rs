@@ -19,6 +19,8 @@ pub enum Response<T> {
     Capability {
         mechanisms: Vec<Mechanism>,
         stls: bool,
+        expire: Option<u64>,
+        expire_per_user: bool,
     },
 }
 
@@ -88,7 +90,12 @@ impl<T: Display> Response<T> {
                 buf.extend_from_slice(b".\r\n");
                 buf
             }
-            Response::Capability { mechanisms, stls } => {
+            Response::Capability {
+                mechanisms,
+                stls,
+                expire,
+                expire_per_user,
+            } => {
                 let mut buf = Vec::with_capacity(256);
                 buf.extend_from_slice(b"+OK Capability list follows\r\n");
                 if !mechanisms.is_empty() {
@@ -107,11 +114,18 @@ impl<T: Display> Response<T> {
                     buf.extend_from_slice(b"STLS\r\n");
                 }
 
+                let mut expire = expire
+                    .map(|days| format!("EXPIRE {days}"))
+                    .unwrap_or_else(|| "EXPIRE NEVER".to_string());
+                if *expire_per_user {
+                    expire.push_str(" USER");
+                }
+
                 for capa in [
                     "TOP",
                     "RESP-CODES",
                     "PIPELINING",
-                    "EXPIRE NEVER",
+                    expire.as_str(),
                     "UIDL",
                     "UTF8",
                     "IMPLEMENTATION Stalwart Mail Server",
@@ -189,6 +203,8 @@ mod tests {
                 Response::Capability {
                     mechanisms: vec![Mechanism::Plain, Mechanism::CramMd5],
                     stls: true,
+                    expire: None,
+                    expire_per_user: false,
                 },
                 concat!(
                     "+OK Capability list follows\r\n",
@@ -204,6 +220,44 @@ mod tests {
                     "IMPLEMENTATION Stalwart Mail Server\r\n.\r\n"
                 ),
             ),
+            (
+                Response::Capability {
+                    mechanisms: vec![Mechanism::OAuthBearer],
+                    stls: false,
+                    expire: Some(30),
+                    expire_per_user: false,
+                },
+                concat!(
+                    "+OK Capability list follows\r\n",
+                    "SASL OAUTHBEARER\r\n",
+                    "TOP\r\n",
+                    "RESP-CODES\r\n",
+                    "PIPELINING\r\n",
+                    "EXPIRE 30\r\n",
+                    "UIDL\r\n",
+                    "UTF8\r\n",
+                    "IMPLEMENTATION Stalwart Mail Server\r\n.\r\n"
+                ),
+            ),
+            (
+                Response::Capability {
+                    mechanisms: vec![Mechanism::OAuthBearer],
+                    stls: false,
+                    expire: Some(30),
+                    expire_per_user: true,
+                },
+                concat!(
+                    "+OK Capability list follows\r\n",
+                    "SASL OAUTHBEARER\r\n",
+                    "TOP\r\n",
+                    "RESP-CODES\r\n",
+                    "PIPELINING\r\n",
+                    "EXPIRE 30 USER\r\n",
+                    "UIDL\r\n",
+                    "UTF8\r\n",
+                    "IMPLEMENTATION Stalwart Mail Server\r\n.\r\n"
+                ),
+            ),
             (
                 Response::Message {
                     bytes: "Subject: test\r\n\r\n.\r\ntest.\r\n.test\r\na"
@@ -27,8 +27,20 @@ impl SessionManager for Pop3SessionManager {
         session: SessionData<T>,
     ) -> impl std::future::Future<Output = ()> + Send {
         async move {
+            let jmap = JMAP::from(self.pop3.jmap_instance);
+            let greeting = jmap
+                .core
+                .eval_if::<String, _>(
+                    &jmap.core.network.pop3_greeting,
+                    &session,
+                    session.session_id,
+                )
+                .await
+                .filter(|greeting| !greeting.is_empty())
+                .map(|greeting| format!("+OK {greeting}\r\n"))
+                .unwrap_or_else(|| format!("+OK {SERVER_GREETING}\r\n"));
             let mut session = Session {
-                jmap: JMAP::from(self.pop3.jmap_instance),
+                jmap,
                 imap: self.pop3.imap_inner,
                 instance: session.instance,
                 receiver: Parser::default(),
@@ -42,10 +54,7 @@ impl SessionManager for Pop3SessionManager {
                 session_id: session.session_id,
             };
 
-            if session
-                .write_bytes(SERVER_GREETING.as_bytes())
-                .await
-                .is_ok()
+            if session.write_bytes(greeting.into_bytes()).await.is_ok()
                 && session.handle_conn().await
                 && session.instance.acceptor.is_tls()
             {
@@ -15,6 +15,7 @@ use common::{
     config::{scripts::ScriptCache, smtp::auth::VerifyStrategy},
     listener::{
         limiter::{ConcurrencyLimiter, InFlight},
+        tarpit::TransferRate,
         ServerInstance,
     },
     Core, Ipc, SharedCore,
@@ -34,7 +35,7 @@ use utils::snowflake::SnowflakeIdGenerator;
 
 use crate::{
     inbound::auth::SaslToken,
-    queue::{self, DomainPart, QueueId},
+    queue::{self, throttle::AdaptiveLimiter, DomainPart, QueueId},
     reporting,
 };
 
@@ -78,6 +79,7 @@ pub struct SMTP {
 pub struct Inner {
     pub session_throttle: DashMap<ThrottleKey, ConcurrencyLimiter, ThrottleKeyHasherBuilder>,
     pub queue_throttle: DashMap<ThrottleKey, ConcurrencyLimiter, ThrottleKeyHasherBuilder>,
+    pub queue_adaptive: DashMap<String, AdaptiveLimiter>,
     pub queue_tx: mpsc::Sender<queue::Event>,
     pub report_tx: mpsc::Sender<reporting::Event>,
     pub queue_id_gen: SnowflakeIdGenerator,
@@ -128,10 +130,12 @@ pub struct SessionData {
     pub rcpt_to: Vec<SessionAddress>,
     pub rcpt_errors: usize,
     pub message: Vec<u8>,
+    pub message_size: usize,
 
     pub authenticated_as: String,
     pub authenticated_emails: Vec<String>,
     pub auth_errors: usize,
+    pub max_priority: Option<i16>,
 
     pub priority: i16,
     pub delivery_by: i64,
@@ -140,11 +144,14 @@ pub struct SessionData {
     pub valid_until: Instant,
     pub bytes_left: usize,
     pub messages_sent: usize,
+    pub transfer_rate: TransferRate,
+    pub early_talker: bool,
 
     pub iprev: Option<IprevOutput>,
     pub spf_ehlo: Option<SpfOutput>,
     pub spf_mail_from: Option<SpfOutput>,
     pub dnsbl_error: Option<Vec<u8>>,
+    pub lookup_tags: Vec<(String, String)>,
 }
 
 #[derive(Clone)]
@@ -160,6 +167,7 @@ pub struct SessionAddress {
 pub struct SessionParameters {
     // Global parameters
     pub timeout: Duration,
+    pub tarpit: Duration,
 
     // Ehlo parameters
     pub ehlo_require: bool,
@@ -176,6 +184,7 @@ pub struct SessionParameters {
     pub rcpt_errors_max: usize,
     pub rcpt_errors_wait: Duration,
     pub rcpt_max: usize,
+    pub rcpt_domains_max: usize,
     pub rcpt_dsn: bool,
     pub can_expn: bool,
     pub can_vrfy: bool,
@@ -212,17 +221,35 @@ impl SessionData {
             valid_until: Instant::now(),
             rcpt_errors: 0,
             message: Vec::with_capacity(0),
+            message_size: 0,
             auth_errors: 0,
+            max_priority: None,
             messages_sent: 0,
             bytes_left: 0,
+            transfer_rate: TransferRate::default(),
+            early_talker: false,
             delivery_by: 0,
             future_release: 0,
             iprev: None,
             spf_ehlo: None,
             spf_mail_from: None,
             dnsbl_error: None,
+            lookup_tags: Vec::new(),
         }
     }
+
+    // Number of distinct domains in the recipient list
+    pub fn rcpt_domains(&self) -> usize {
+        self.rcpt_to
+            .iter()
+            .enumerate()
+            .filter(|(pos, rcpt)| {
+                !self.rcpt_to[..*pos]
+                    .iter()
+                    .any(|prev| prev.domain == rcpt.domain)
+            })
+            .count()
+    }
 }
 
 impl Default for State {
@@ -292,6 +319,7 @@ impl Session<common::listener::stream::NullIo> {
             data,
             params: SessionParameters {
                 timeout: Default::default(),
+                tarpit: Default::default(),
                 ehlo_require: Default::default(),
                 ehlo_reject_non_fqdn: Default::default(),
                 auth_directory: Default::default(),
@@ -301,6 +329,7 @@ impl Session<common::listener::stream::NullIo> {
                 rcpt_errors_max: Default::default(),
                 rcpt_errors_wait: Default::default(),
                 rcpt_max: Default::default(),
+                rcpt_domains_max: Default::default(),
                 rcpt_dsn: Default::default(),
                 max_message_size: Default::default(),
                 auth_match_sender: false,
@@ -314,18 +343,35 @@ impl Session<common::listener::stream::NullIo> {
         }
     }
 
-    pub fn sieve(
+    pub async fn sieve(
         core: SMTP,
         mail_from: SessionAddress,
         rcpt_to: Vec<SessionAddress>,
         message: Vec<u8>,
         session_id: u64,
     ) -> Self {
-        Self::local(
+        let mut session = Self::local(
             core,
             SIEVE.clone(),
             SessionData::local(mail_from.into(), rcpt_to, message, session_id),
-        )
+        );
+
+        // Forwarded messages are stamped and ARC sealed using the server's hostname
+        if let Some(hostname) = session
+            .core
+            .core
+            .eval_if::<String, _>(
+                &session.core.core.smtp.session.connect.hostname,
+                &session,
+                session_id,
+            )
+            .await
+            .filter(|hostname| !hostname.is_empty())
+        {
+            session.hostname = hostname;
+        }
+
+        session
     }
 
     pub fn has_failed(&mut self) -> Option<String> {
@@ -362,20 +408,25 @@ impl SessionData {
             mail_from,
             rcpt_to,
             rcpt_errors: 0,
+            message_size: message.len(),
             message,
             authenticated_as: "local".into(),
             authenticated_emails: vec![],
             auth_errors: 0,
+            max_priority: None,
             priority: 0,
             delivery_by: 0,
             future_release: 0,
             valid_until: Instant::now(),
             bytes_left: 0,
             messages_sent: 0,
+            transfer_rate: TransferRate::default(),
+            early_talker: false,
             iprev: None,
             spf_ehlo: None,
             spf_mail_from: None,
             dnsbl_error: None,
+            lookup_tags: Vec::new(),
         }
     }
 }
@@ -405,6 +456,7 @@ impl Default for Inner {
         Self {
             session_throttle: Default::default(),
             queue_throttle: Default::default(),
+            queue_adaptive: Default::default(),
             queue_tx: mpsc::channel(1).0,
             report_tx: mpsc::channel(1).0,
             queue_id_gen: Default::default(),
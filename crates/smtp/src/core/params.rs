@@ -175,6 +175,12 @@ impl<T: SessionStream> Session<T> {
             .eval_if(&rc.max_recipients, self, self.data.session_id)
             .await
             .unwrap_or(100);
+        self.params.rcpt_domains_max = self
+            .core
+            .core
+            .eval_if(&rc.max_domains, self, self.data.session_id)
+            .await
+            .unwrap_or(0);
         self.params.rcpt_dsn = self
             .core
             .core
@@ -5,7 +5,7 @@
  */
 
 use common::{
-    config::smtp::{queue::QueueQuota, *},
+    config::smtp::{canonical::AddressCanonicalization, queue::QueueQuota, report::Report, *},
     expr::{functions::ResolveVariable, *},
     listener::{limiter::ConcurrencyLimiter, SessionStream},
 };
@@ -70,20 +70,29 @@ impl BuildHasher for ThrottleKeyHasherBuilder {
 }
 
 pub trait NewKey: Sized {
-    fn new_key(&self, e: &impl ResolveVariable) -> ThrottleKey;
+    fn new_key(&self, e: &impl ResolveVariable, canonical: &AddressCanonicalization)
+        -> ThrottleKey;
 }
 
 impl NewKey for QueueQuota {
-    fn new_key(&self, e: &impl ResolveVariable) -> ThrottleKey {
+    fn new_key(
+        &self,
+        e: &impl ResolveVariable,
+        canonical: &AddressCanonicalization,
+    ) -> ThrottleKey {
         let mut hasher = blake3::Hasher::new();
 
         if (self.keys & THROTTLE_RCPT) != 0 {
-            hasher.update(e.resolve_variable(V_RECIPIENT).to_string().as_bytes());
+            hasher.update(
+                canonical
+                    .address(e.resolve_variable(V_RECIPIENT).to_string().as_ref())
+                    .as_bytes(),
+            );
         }
         if (self.keys & THROTTLE_RCPT_DOMAIN) != 0 {
             hasher.update(
-                e.resolve_variable(V_RECIPIENT_DOMAIN)
-                    .to_string()
+                canonical
+                    .domain(e.resolve_variable(V_RECIPIENT_DOMAIN).to_string().as_ref())
                     .as_bytes(),
             );
         }
@@ -91,9 +100,9 @@ impl NewKey for QueueQuota {
             let sender = e.resolve_variable(V_SENDER).into_string();
             hasher.update(
                 if !sender.is_empty() {
-                    sender.as_ref()
+                    canonical.address(sender.as_ref())
                 } else {
-                    "<>"
+                    "<>".to_string()
                 }
                 .as_bytes(),
             );
@@ -102,9 +111,9 @@ impl NewKey for QueueQuota {
             let sender_domain = e.resolve_variable(V_SENDER_DOMAIN).into_string();
             hasher.update(
                 if !sender_domain.is_empty() {
-                    sender_domain.as_ref()
+                    canonical.domain(sender_domain.as_ref())
                 } else {
-                    "<>"
+                    "<>".to_string()
                 }
                 .as_bytes(),
             );
@@ -125,16 +134,24 @@ impl NewKey for QueueQuota {
 }
 
 impl NewKey for Throttle {
-    fn new_key(&self, e: &impl ResolveVariable) -> ThrottleKey {
+    fn new_key(
+        &self,
+        e: &impl ResolveVariable,
+        canonical: &AddressCanonicalization,
+    ) -> ThrottleKey {
         let mut hasher = blake3::Hasher::new();
 
         if (self.keys & THROTTLE_RCPT) != 0 {
-            hasher.update(e.resolve_variable(V_RECIPIENT).to_string().as_bytes());
+            hasher.update(
+                canonical
+                    .address(e.resolve_variable(V_RECIPIENT).to_string().as_ref())
+                    .as_bytes(),
+            );
         }
         if (self.keys & THROTTLE_RCPT_DOMAIN) != 0 {
             hasher.update(
-                e.resolve_variable(V_RECIPIENT_DOMAIN)
-                    .to_string()
+                canonical
+                    .domain(e.resolve_variable(V_RECIPIENT_DOMAIN).to_string().as_ref())
                     .as_bytes(),
             );
         }
@@ -142,9 +159,9 @@ impl NewKey for Throttle {
             let sender = e.resolve_variable(V_SENDER).into_string();
             hasher.update(
                 if !sender.is_empty() {
-                    sender.as_ref()
+                    canonical.address(sender.as_ref())
                 } else {
-                    "<>"
+                    "<>".to_string()
                 }
                 .as_bytes(),
             );
@@ -153,9 +170,9 @@ impl NewKey for Throttle {
             let sender_domain = e.resolve_variable(V_SENDER_DOMAIN).into_string();
             hasher.update(
                 if !sender_domain.is_empty() {
-                    sender_domain.as_ref()
+                    canonical.domain(sender_domain.as_ref())
                 } else {
-                    "<>"
+                    "<>".to_string()
                 }
                 .as_bytes(),
             );
@@ -229,7 +246,7 @@ impl<T: SessionStream> Session<T> {
                 }
 
                 // Build throttle key
-                let key = t.new_key(self);
+                let key = t.new_key(self, &self.core.core.smtp.canonical);
 
                 // Check concurrency
                 if let Some(concurrency) = &t.concurrency {
@@ -291,7 +308,7 @@ impl<T: SessionStream> Session<T> {
 
     pub async fn throttle_rcpt(&self, rcpt: &str, rate: &Rate, ctx: &str) -> bool {
         let mut hasher = blake3::Hasher::new();
-        hasher.update(rcpt.as_bytes());
+        hasher.update(self.core.core.smtp.canonical.address(rcpt).as_bytes());
         hasher.update(ctx.as_bytes());
         hasher.update(&rate.period.as_secs().to_ne_bytes()[..]);
         hasher.update(&rate.requests.to_ne_bytes()[..]);
@@ -305,6 +322,20 @@ impl<T: SessionStream> Session<T> {
             .unwrap_or_default()
             .is_none()
     }
+
+    pub async fn throttle_report_domain(&self, domain: &str, config: &Report, ctx: &str) -> bool {
+        match &config.domain_send {
+            Some(rate) => {
+                self.throttle_rcpt(
+                    &self.core.core.smtp.canonical.domain(domain),
+                    rate,
+                    &format!("{ctx}-domain"),
+                )
+                .await
+            }
+            None => true,
+        }
+    }
 }
 
 impl SMTP {
@@ -5,7 +5,10 @@
  */
 
 use common::listener::SessionStream;
-use directory::{backend::internal::PrincipalField, Permission};
+use directory::{
+    backend::internal::{manage::ManageDirectory, PrincipalField},
+    Permission, Principal, ROLE_ADMIN, ROLE_TENANT_ADMIN, ROLE_USER,
+};
 use mail_parser::decoders::base64::base64_decode;
 use mail_send::Credentials;
 use smtp_proto::{IntoString, AUTH_LOGIN, AUTH_OAUTHBEARER, AUTH_PLAIN, AUTH_XOAUTH2};
@@ -175,7 +178,7 @@ impl<T: SessionStream> Session<T> {
                     self.data.session_id,
                     &credentials,
                     self.data.remote_ip,
-                    false,
+                    true,
                 )
                 .await;
 
@@ -207,10 +210,18 @@ impl<T: SessionStream> Session<T> {
             match result {
                 Ok(principal) => {
                     self.data.authenticated_as = authenticated_as.to_lowercase();
-                    self.data.authenticated_emails = principal
-                        .iter_str(PrincipalField::Emails)
-                        .map(|e| e.trim().to_lowercase())
-                        .collect();
+                    self.data.authenticated_emails =
+                        match directory.sender_addresses(&principal).await {
+                            Ok(addresses) => addresses,
+                            Err(err) => {
+                                trc::error!(err.span_id(self.data.session_id));
+                                principal
+                                    .iter_str(PrincipalField::Emails)
+                                    .map(|e| e.trim().to_lowercase())
+                                    .collect()
+                            }
+                        };
+                    self.data.max_priority = self.role_max_priority(&principal).await;
                     self.eval_post_auth_params().await;
                     self.write(b"235 2.7.0 Authentication succeeded.\r\n")
                         .await?;
@@ -258,6 +269,44 @@ impl<T: SessionStream> Session<T> {
         Ok(false)
     }
 
+    // Returns the highest MT-PRIORITY allowed by the roles granted to the principal
+    async fn role_max_priority(&self, principal: &Principal) -> Option<i16> {
+        let role_priority = &self.core.core.smtp.session.mail.role_priority;
+        if role_priority.is_empty() {
+            return None;
+        }
+
+        let mut max_priority: Option<i16> = None;
+        for role_id in principal.iter_int(PrincipalField::Roles) {
+            let role_name = match role_id as u32 {
+                ROLE_ADMIN => Some("admin".to_string()),
+                ROLE_TENANT_ADMIN => Some("tenant-admin".to_string()),
+                ROLE_USER => Some("user".to_string()),
+                role_id => match self.core.core.storage.data.get_principal(role_id).await {
+                    Ok(role) => role
+                        .and_then(|mut role| role.take_str(PrincipalField::Name))
+                        .map(|name| name.to_lowercase()),
+                    Err(err) => {
+                        trc::error!(err
+                            .span_id(self.data.session_id)
+                            .caused_by(trc::location!()));
+                        None
+                    }
+                },
+            };
+
+            if let Some(priority) = role_name.and_then(|name| {
+                role_priority
+                    .iter()
+                    .find_map(|(role, priority)| (role == &name).then_some(*priority))
+            }) {
+                max_priority = Some(max_priority.map_or(priority, |max| max.max(priority)));
+            }
+        }
+
+        max_priority
+    }
+
     pub async fn auth_error(&mut self, response: &[u8]) -> Result<bool, ()> {
         tokio::time::sleep(self.params.auth_errors_wait).await;
         self.data.auth_errors += 1;
@@ -0,0 +1,409 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use common::{config::smtp::resolver::Bimi, psl};
+use mail_auth::{common::lru::DnsCache, dmarc::Policy, DmarcOutput, DmarcResult};
+use mail_builder::encoders::base64::base64_encode;
+use x509_parser::{
+    der_parser::oid,
+    extensions::GeneralName,
+    pem::Pem,
+    prelude::{ASN1Time, FromDer, X509Certificate},
+};
+
+use crate::core::SMTP;
+
+#[cfg(feature = "test_mode")]
+pub static BIMI_TEST_INDICATOR: parking_lot::Mutex<Vec<u8>> = parking_lot::Mutex::new(Vec::new());
+#[cfg(feature = "test_mode")]
+pub static BIMI_TEST_VMC: parking_lot::Mutex<Vec<u8>> = parking_lot::Mutex::new(Vec::new());
+
+#[cfg(not(feature = "test_mode"))]
+use common::HttpLimitResponse;
+
+#[cfg(not(feature = "test_mode"))]
+const BIMI_CACHE_TTL: Duration = Duration::from_secs(3600);
+#[cfg(not(feature = "test_mode"))]
+const MAX_VMC_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BimiOutput {
+    pub domain: String,
+    pub selector: String,
+    pub location: String,
+    pub authority: Option<String>,
+    pub indicator: Option<String>,
+}
+
+pub trait ParseBimi {
+    fn parse(data: &str) -> Result<Self, String>
+    where
+        Self: Sized;
+}
+
+impl ParseBimi for Bimi {
+    fn parse(data: &str) -> Result<Self, String> {
+        let mut bimi = Bimi::default();
+        let mut has_version = false;
+
+        for tag in data.split(';') {
+            let (key, value) = match tag.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None if tag.trim().is_empty() => continue,
+                None => return Err(format!("Invalid tag {:?}", tag.trim())),
+            };
+
+            match key {
+                "v" if value.eq_ignore_ascii_case("BIMI1") => {
+                    has_version = true;
+                }
+                "v" => {
+                    return Err(format!("Unsupported version {value:?}"));
+                }
+                "l" | "a" if !value.is_empty() => {
+                    if !value.starts_with("https://") {
+                        return Err(format!("Invalid URI {value:?}"));
+                    }
+                    if key == "l" {
+                        bimi.location = value.to_string().into();
+                    } else {
+                        bimi.authority = value.to_string().into();
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if has_version {
+            Ok(bimi)
+        } else {
+            Err("Missing version tag".to_string())
+        }
+    }
+}
+
+impl SMTP {
+    pub async fn verify_bimi(
+        &self,
+        dmarc_output: &DmarcOutput,
+        selector: Option<&str>,
+        session_id: u64,
+    ) -> Option<BimiOutput> {
+        // BIMI requires a DMARC pass on a domain with an enforcing policy
+        let domain = dmarc_output.domain();
+        let is_enforced = matches!(dmarc_output.policy(), Policy::Quarantine | Policy::Reject)
+            && dmarc_output
+                .dmarc_record()
+                .map_or(false, |record| record.pct == 100);
+        if domain.is_empty()
+            || !is_enforced
+            || !(matches!(dmarc_output.spf_result(), DmarcResult::Pass)
+                || matches!(dmarc_output.dkim_result(), DmarcResult::Pass))
+        {
+            return None;
+        }
+
+        let time = Instant::now();
+        let selector = selector.unwrap_or("default");
+        let config = &self.core.smtp.mail_auth.bimi;
+        let result = async {
+            // Lookup the BIMI record, falling back to the organizational domain
+            let mut record = self.bimi_lookup(selector, domain).await?;
+            if record.is_none() {
+                let org_domain = psl::domain_str(domain).unwrap_or(domain);
+                if org_domain != domain {
+                    record = self.bimi_lookup(selector, org_domain).await?;
+                }
+            }
+            let record = record.ok_or("No BIMI record found")?;
+            let location = record
+                .location
+                .clone()
+                .ok_or("BIMI record does not publish an indicator")?;
+
+            // Validate the Verified Mark Certificate
+            match &record.authority {
+                Some(authority) => {
+                    self.verify_vmc(authority, domain, config.timeout).await?;
+                }
+                None if config.require_vmc => {
+                    return Err("BIMI record does not publish a VMC".to_string());
+                }
+                None => (),
+            }
+
+            // Fetch the indicator
+            let indicator = if config.indicator {
+                self.fetch_bimi_indicator(&location, config.max_size, config.timeout)
+                    .await?
+                    .into()
+            } else {
+                None
+            };
+
+            Ok::<_, String>(BimiOutput {
+                domain: domain.to_string(),
+                selector: selector.to_string(),
+                location,
+                authority: record.authority.clone(),
+                indicator,
+            })
+        }
+        .await;
+
+        match result {
+            Ok(output) => {
+                trc::event!(
+                    Smtp(trc::SmtpEvent::BimiPass),
+                    SpanId = session_id,
+                    Domain = output.domain.clone(),
+                    Url = output.location.clone(),
+                    Elapsed = time.elapsed(),
+                );
+
+                Some(output)
+            }
+            Err(reason) => {
+                trc::event!(
+                    Smtp(trc::SmtpEvent::BimiFail),
+                    SpanId = session_id,
+                    Domain = domain.to_string(),
+                    Reason = reason,
+                    Elapsed = time.elapsed(),
+                );
+
+                None
+            }
+        }
+    }
+
+    pub async fn bimi_lookup(
+        &self,
+        selector: &str,
+        domain: &str,
+    ) -> Result<Option<Arc<Bimi>>, String> {
+        let key = format!("{selector}._bimi.{domain}.");
+        if let Some(value) = self.core.smtp.resolvers.cache.bimi.get(&key) {
+            return Ok(Some(value));
+        }
+
+        #[cfg(not(feature = "test_mode"))]
+        {
+            let record = match self
+                .core
+                .smtp
+                .resolvers
+                .dns
+                .txt_raw_lookup(key.as_str())
+                .await
+            {
+                Ok(record) => record,
+                Err(mail_auth::Error::DnsRecordNotFound(_)) => return Ok(None),
+                Err(err) => return Err(err.to_string()),
+            };
+            let record = std::str::from_utf8(&record).map_err(|err| err.to_string())?;
+            if !record.trim_start().starts_with("v=BIMI1") {
+                return Ok(None);
+            }
+
+            Ok(Some(self.core.smtp.resolvers.cache.bimi.insert(
+                key,
+                Arc::new(Bimi::parse(record)?),
+                Instant::now() + BIMI_CACHE_TTL,
+            )))
+        }
+
+        #[cfg(feature = "test_mode")]
+        Ok(None)
+    }
+
+    #[cfg(feature = "test_mode")]
+    pub fn bimi_add(&self, key: impl Into<String>, value: Bimi, valid_until: Instant) {
+        self.core
+            .smtp
+            .resolvers
+            .cache
+            .bimi
+            .insert(key.into(), Arc::new(value), valid_until);
+    }
+
+    #[allow(unused_variables)]
+    async fn fetch_bimi_indicator(
+        &self,
+        url: &str,
+        max_size: usize,
+        timeout: Duration,
+    ) -> Result<String, String> {
+        #[cfg(not(feature = "test_mode"))]
+        let bytes = fetch_https(url, max_size, timeout).await?;
+        #[cfg(feature = "test_mode")]
+        let bytes = BIMI_TEST_INDICATOR.lock().clone();
+
+        // Indicators must be SVG documents
+        let svg = std::str::from_utf8(&bytes).map_err(|_| "Indicator is not valid UTF-8")?;
+        if !svg.contains("<svg") || svg.contains("<script") {
+            return Err("Indicator is not a valid SVG document".to_string());
+        }
+
+        let encoded = base64_encode(svg.as_bytes()).map_err(|err| err.to_string())?;
+        let mut indicator = String::with_capacity(encoded.len() + (encoded.len() / 72) * 3);
+        for (pos, chunk) in encoded.chunks(72).enumerate() {
+            if pos > 0 {
+                indicator.push_str("\r\n\t");
+            }
+            indicator.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        }
+
+        Ok(indicator)
+    }
+
+    #[allow(unused_variables)]
+    async fn verify_vmc(&self, url: &str, domain: &str, timeout: Duration) -> Result<(), String> {
+        #[cfg(not(feature = "test_mode"))]
+        let bytes = fetch_https(url, MAX_VMC_SIZE, timeout).await?;
+        #[cfg(feature = "test_mode")]
+        let bytes = BIMI_TEST_VMC.lock().clone();
+
+        verify_vmc_chain(&bytes, domain, &self.core.smtp.mail_auth.bimi.vmc_roots)
+    }
+}
+
+// Verifies a Verified Mark Certificate chain: every certificate must be currently valid
+// and signed by the next one in the chain, the last one must be signed by one of the
+// configured VMC roots and the leaf must be issued for BIMI and cover the author domain.
+pub fn verify_vmc_chain(pem: &[u8], domain: &str, roots: &[Vec<u8>]) -> Result<(), String> {
+    let pems = Pem::iter_from_buffer(pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("Failed to parse VMC: {err}"))?;
+    let certs = pems
+        .iter()
+        .map(|pem| {
+            X509Certificate::from_der(&pem.contents)
+                .map(|(_, cert)| cert)
+                .map_err(|err| format!("Failed to parse VMC: {err}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let leaf = certs
+        .first()
+        .ok_or("VMC does not contain any certificates")?;
+    let roots = roots
+        .iter()
+        .filter_map(|der| X509Certificate::from_der(der).ok().map(|(_, cert)| cert))
+        .collect::<Vec<_>>();
+
+    // Validate the certificate chain
+    let now = ASN1Time::now();
+    for (pos, cert) in certs.iter().enumerate() {
+        if !cert.validity().is_valid_at(now) {
+            return Err("VMC has expired or is not yet valid".to_string());
+        }
+        if let Some(issuer) = certs.get(pos + 1) {
+            if !is_issued_by(cert, issuer) {
+                return Err("VMC chain is not valid".to_string());
+            }
+        } else if !roots
+            .iter()
+            .any(|root| root.validity().is_valid_at(now) && is_issued_by(cert, root))
+        {
+            return Err("VMC is not issued by a trusted mark certificate authority".to_string());
+        }
+    }
+
+    // Check that the certificate was issued for brand indicators
+    let bimi_oid = oid!(1.3.6 .1 .5 .5 .7 .3 .31);
+    if !leaf
+        .extended_key_usage()
+        .ok()
+        .flatten()
+        .map_or(false, |eku| eku.value.other.contains(&bimi_oid))
+    {
+        return Err("Certificate is not a VMC".to_string());
+    }
+
+    // Check that the certificate covers the author domain
+    let org_domain = psl::domain_str(domain).unwrap_or(domain);
+    if !leaf
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .map_or(false, |san| {
+            san.value.general_names.iter().any(|name| {
+                matches!(name, GeneralName::DNSName(name)
+                    if name.eq_ignore_ascii_case(domain)
+                        || name.eq_ignore_ascii_case(org_domain))
+            })
+        })
+    {
+        return Err(format!("VMC is not valid for {domain}"));
+    }
+
+    Ok(())
+}
+
+fn is_issued_by(cert: &X509Certificate<'_>, issuer: &X509Certificate<'_>) -> bool {
+    cert.issuer() == issuer.subject()
+        && issuer.is_ca()
+        && cert.verify_signature(Some(issuer.public_key())).is_ok()
+}
+
+#[cfg(not(feature = "test_mode"))]
+async fn fetch_https(url: &str, max_size: usize, timeout: Duration) -> Result<Vec<u8>, String> {
+    reqwest::Client::builder()
+        .user_agent(common::USER_AGENT)
+        .timeout(timeout)
+        .redirect(reqwest::redirect::Policy::none())
+        .https_only(true)
+        .build()
+        .map_err(|err| err.to_string())?
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?
+        .bytes_with_limit(max_size)
+        .await
+        .map_err(|err| err.to_string())?
+        .ok_or_else(|| format!("Resource at {url} is too large"))
+}
+
+impl BimiOutput {
+    pub fn write_header(&self, headers: &mut Vec<u8>) {
+        headers.extend_from_slice(b"BIMI-Location: v=BIMI1;\r\n\tl=");
+        headers.extend_from_slice(self.location.as_bytes());
+        if let Some(authority) = &self.authority {
+            headers.extend_from_slice(b";\r\n\ta=");
+            headers.extend_from_slice(authority.as_bytes());
+        }
+        headers.extend_from_slice(b"\r\n");
+
+        if let Some(indicator) = &self.indicator {
+            headers.extend_from_slice(b"BIMI-Indicator: ");
+            headers.extend_from_slice(indicator.as_bytes());
+            headers.extend_from_slice(b"\r\n");
+        }
+    }
+}
+
+// Obtains the selector from a BIMI-Selector header
+pub fn parse_bimi_selector(value: &[u8]) -> Option<&str> {
+    std::str::from_utf8(value)
+        .ok()?
+        .split(';')
+        .filter_map(|tag| tag.split_once('='))
+        .find(|(key, _)| key.trim() == "s")
+        .map(|(_, value)| value.trim())
+        .filter(|value| {
+            !value.is_empty()
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        })
+}
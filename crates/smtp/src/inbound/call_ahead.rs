@@ -0,0 +1,232 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Instant,
+};
+
+use common::{config::smtp::queue::RelayHost, listener::SessionStream};
+use mail_auth::IpLookupStrategy;
+use mail_send::smtp::AssertReply;
+use smtp_proto::{EhloResponse, Response, Severity};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use trc::SmtpEvent;
+
+use crate::{
+    core::Session,
+    outbound::client::{from_mail_send_error, SmtpClient, StartTlsResult},
+};
+
+impl<T: SessionStream> Session<T> {
+    // Probes the destination server with the last recipient and returns true
+    // when it was rejected. Verification results are cached in the lookup store,
+    // servers that cannot be reached or reply with a temporary error are not.
+    pub async fn is_rejected_by_call_ahead(&self) -> bool {
+        let config = &self.core.core.smtp.session.rcpt.call_ahead;
+        let host = if let Some(host) = self
+            .core
+            .core
+            .eval_if::<String, _>(&config.host, self, self.data.session_id)
+            .await
+            .and_then(|name| self.core.core.get_relay_host(&name, self.data.session_id))
+        {
+            host
+        } else {
+            return false;
+        };
+
+        // Look up previous verification results
+        let store = &self.core.core.storage.lookup;
+        let rcpt = self.data.rcpt_to.last().unwrap();
+        let key = format!("call-ahead:{}:{}", host.address, rcpt.address_lcase).into_bytes();
+        match store.key_get::<i64>(key.clone()).await {
+            Ok(Some(result)) => {
+                let is_rejected = result == 0;
+                trc::event!(
+                    Smtp(if is_rejected {
+                        SmtpEvent::CallAheadFailed
+                    } else {
+                        SmtpEvent::CallAheadPassed
+                    }),
+                    SpanId = self.data.session_id,
+                    Hostname = host.address.clone(),
+                    To = rcpt.address_lcase.clone(),
+                    Details = "Cached result",
+                );
+
+                return is_rejected;
+            }
+            Ok(None) => (),
+            Err(err) => {
+                trc::error!(err
+                    .span_id(self.data.session_id)
+                    .caused_by(trc::location!())
+                    .details("Failed to obtain call-ahead cache entry."));
+            }
+        }
+
+        // Probe the destination server
+        let time = Instant::now();
+        let (is_rejected, expires) =
+            match tokio::time::timeout(config.timeout, self.call_ahead(host, &rcpt.address))
+                .await
+                .unwrap_or(Err(mail_send::Error::Timeout))
+            {
+                Ok(response) if response.severity() == Severity::PositiveCompletion => {
+                    trc::event!(
+                        Smtp(SmtpEvent::CallAheadPassed),
+                        SpanId = self.data.session_id,
+                        Hostname = host.address.clone(),
+                        To = rcpt.address_lcase.clone(),
+                        Code = response.code,
+                        Elapsed = time.elapsed(),
+                    );
+
+                    (false, config.cache_positive)
+                }
+                Ok(response) if response.severity() == Severity::PermanentNegativeCompletion => {
+                    trc::event!(
+                        Smtp(SmtpEvent::CallAheadFailed),
+                        SpanId = self.data.session_id,
+                        Hostname = host.address.clone(),
+                        To = rcpt.address_lcase.clone(),
+                        Code = response.code,
+                        Details = response.message,
+                        Elapsed = time.elapsed(),
+                    );
+
+                    (true, config.cache_negative)
+                }
+                Ok(response) => {
+                    trc::event!(
+                        Smtp(SmtpEvent::CallAheadError),
+                        SpanId = self.data.session_id,
+                        Hostname = host.address.clone(),
+                        To = rcpt.address_lcase.clone(),
+                        Code = response.code,
+                        Details = response.message,
+                        Elapsed = time.elapsed(),
+                    );
+
+                    return false;
+                }
+                Err(err) => {
+                    trc::event!(
+                        Smtp(SmtpEvent::CallAheadError),
+                        SpanId = self.data.session_id,
+                        Hostname = host.address.clone(),
+                        To = rcpt.address_lcase.clone(),
+                        CausedBy = from_mail_send_error(&err),
+                        Elapsed = time.elapsed(),
+                    );
+
+                    return false;
+                }
+            };
+
+        // Cache result
+        if let Err(err) = store
+            .key_set(
+                key,
+                (!is_rejected as i64).to_be_bytes().to_vec(),
+                expires.as_secs().into(),
+            )
+            .await
+        {
+            trc::error!(err
+                .span_id(self.data.session_id)
+                .caused_by(trc::location!())
+                .details("Failed to update call-ahead cache entry."));
+        }
+
+        is_rejected
+    }
+
+    async fn call_ahead(
+        &self,
+        host: &RelayHost,
+        rcpt: &str,
+    ) -> mail_send::Result<Response<String>> {
+        let config = &self.core.core.smtp.session.rcpt.call_ahead;
+        let remote_ip = if let Ok(ip) = host.address.parse::<IpAddr>() {
+            ip
+        } else {
+            self.core
+                .ip_lookup(&host.address, IpLookupStrategy::Ipv4thenIpv6, 1)
+                .await
+                .ok()
+                .and_then(|ips| ips.into_iter().next())
+                .ok_or_else(|| {
+                    mail_send::Error::Io(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        format!("Failed to resolve {:?}", host.address),
+                    ))
+                })?
+        };
+        let mut client = SmtpClient::connect(
+            SocketAddr::new(remote_ip, host.port),
+            config.timeout,
+            self.data.session_id,
+        )
+        .await?;
+        let tls_connector = if host.tls_allow_invalid_certs {
+            &self.core.inner.connectors.dummy_verify
+        } else {
+            &self.core.inner.connectors.pki_verify
+        };
+
+        if host.tls_implicit {
+            let mut client = client.into_tls(tls_connector, &host.address).await?;
+            client.read().await?.assert_code(220)?;
+            self.say_ehlo(&mut client).await?;
+            self.verify_rcpt(client, rcpt).await
+        } else {
+            client.read().await?.assert_code(220)?;
+            let capabilities = self.say_ehlo(&mut client).await?;
+            match client
+                .try_start_tls(tls_connector, &host.address, &capabilities)
+                .await
+            {
+                StartTlsResult::Success { mut smtp_client } => {
+                    self.say_ehlo(&mut smtp_client).await?;
+                    self.verify_rcpt(smtp_client, rcpt).await
+                }
+                StartTlsResult::Unavailable { smtp_client, .. } if !host.tls_required => {
+                    self.verify_rcpt(smtp_client, rcpt).await
+                }
+                StartTlsResult::Unavailable { .. } => Err(mail_send::Error::MissingStartTls),
+                StartTlsResult::Error { error } => Err(error),
+            }
+        }
+    }
+
+    async fn say_ehlo<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        client: &mut SmtpClient<S>,
+    ) -> mail_send::Result<EhloResponse<String>> {
+        let cmd = format!("EHLO {}\r\n", self.hostname);
+        client.stream.write_all(cmd.as_bytes()).await?;
+        client.stream.flush().await?;
+        client.read_ehlo().await
+    }
+
+    async fn verify_rcpt<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        mut client: SmtpClient<S>,
+        rcpt: &str,
+    ) -> mail_send::Result<Response<String>> {
+        // Probes are sent with a null reverse-path to avoid bounces to the sender
+        client
+            .cmd(b"MAIL FROM:<>\r\n")
+            .await?
+            .assert_positive_completion()?;
+        let response = client.cmd(format!("RCPT TO:<{rcpt}>\r\n")).await?;
+        client.quit().await;
+
+        Ok(response)
+    }
+}
@@ -8,23 +8,25 @@ use std::{
     borrow::Cow,
     process::Stdio,
     sync::Arc,
-    time::{Duration, Instant, SystemTime},
+    time::{Duration, Instant},
 };
 
 use common::{
     config::smtp::{auth::VerifyStrategy, session::Stage},
     listener::SessionStream,
     psl,
-    scripts::ScriptModification,
+    scripts::{verdict::FilterVerdict, ScriptModification},
 };
 use mail_auth::{
     common::{headers::HeaderWriter, verify::VerifySignature},
     dmarc, AuthenticatedMessage, AuthenticationResults, DkimResult, DmarcResult, ReceivedSpf,
 };
 use mail_builder::headers::{date::Date, message_id::generate_message_id_header};
+use mail_parser::{Header, MessageParser};
 use sieve::runtime::Variable;
 use smtp_proto::{
-    MAIL_BY_RETURN, RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_NEVER, RCPT_NOTIFY_SUCCESS,
+    MAIL_BY_RETURN, MAIL_REQUIRETLS, RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_NEVER,
+    RCPT_NOTIFY_SUCCESS,
 };
 use store::write::now;
 use tokio::{io::AsyncWriteExt, process::Command};
@@ -34,16 +36,29 @@ use utils::config::Rate;
 use crate::{
     core::{Session, SessionAddress, State},
     inbound::milter::Modification,
-    queue::{self, Message, MessageSource, QueueEnvelope, Schedule},
+    queue::{self, Message, MessageSource, QueueEnvelope, Schedule, MAIL_TLS_OPTIONAL},
     scripts::ScriptResult,
 };
 
-use super::{ArcSeal, AuthResult, DkimSign};
+use super::{bimi::parse_bimi_selector, ArcSeal, AuthResult, DkimSign};
 
 impl<T: SessionStream> Session<T> {
     pub async fn queue_message(&mut self) -> Cow<'static, [u8]> {
+        // Remove status headers set by clients outside the trusted networks
+        let mut raw_message = std::mem::take(&mut self.data.message);
+        let is_trusted = self
+            .core
+            .core
+            .is_ip_trusted(&self.data.remote_ip, self.data.session_id)
+            .await;
+        if !is_trusted {
+            if let Some(stripped_message) = self.strip_status_headers(&raw_message) {
+                raw_message = stripped_message;
+            }
+        }
+
         // Authenticate message
-        let raw_message = Arc::new(std::mem::take(&mut self.data.message));
+        let raw_message = Arc::new(raw_message);
         let auth_message = if let Some(auth_message) = AuthenticatedMessage::parse_with_opts(
             &raw_message,
             self.core.core.smtp.mail_auth.dkim.strict,
@@ -58,6 +73,19 @@ impl<T: SessionStream> Session<T> {
             return (&b"550 5.7.7 Failed to parse message.\r\n"[..]).into();
         };
 
+        // Status headers are only trusted when added by peers in the trusted networks,
+        // verdicts of the local filter are collected from the script modifications
+        let mut verdict = FilterVerdict::default();
+        if is_trusted {
+            for (name, value) in &auth_message.headers {
+                if let (Ok(name), Ok(value)) =
+                    (std::str::from_utf8(name), std::str::from_utf8(value))
+                {
+                    verdict.add_header(&self.core.core.jmap, name, value);
+                }
+            }
+        }
+
         // Loop detection
         let dc = &self.core.core.smtp.session.data;
         let ac = &self.core.core.smtp.mail_auth;
@@ -229,9 +257,13 @@ impl<T: SessionStream> Session<T> {
         if let Some(iprev) = &self.data.iprev {
             auth_results = auth_results.with_iprev_result(iprev, self.data.remote_ip);
         }
+        if let Some(arc_output) = &arc_output {
+            auth_results = auth_results.with_arc_result(arc_output, self.data.remote_ip);
+        }
 
         // Verify DMARC
         let is_report = self.is_report();
+        let mut bimi_output = None;
         let (dmarc_result, dmarc_policy) = match &self.data.spf_mail_from {
             Some(spf_output) if dmarc.verify() => {
                 let time = Instant::now();
@@ -289,6 +321,27 @@ impl<T: SessionStream> Session<T> {
                     Elapsed = time.elapsed(),
                 );
 
+                // Verify BIMI
+                if pass
+                    && !is_report
+                    && self
+                        .core
+                        .core
+                        .eval_if(&ac.bimi.verify, self, self.data.session_id)
+                        .await
+                        .unwrap_or(false)
+                {
+                    let selector = auth_message
+                        .headers
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(b"BIMI-Selector"))
+                        .and_then(|(_, value)| parse_bimi_selector(value));
+                    bimi_output = self
+                        .core
+                        .verify_bimi(&dmarc_output, selector, self.data.session_id)
+                        .await;
+                }
+
                 // Send DMARC report
                 if dmarc_output.requested_reports() && !is_report {
                     self.send_dmarc_report(
@@ -338,6 +391,14 @@ impl<T: SessionStream> Session<T> {
             self.write_received(&mut headers, message_id)
         }
 
+        // Add HTTP lookup tags
+        for (name, value) in &self.data.lookup_tags {
+            headers.extend_from_slice(name.as_bytes());
+            headers.extend_from_slice(b": ");
+            headers.extend_from_slice(value.as_bytes());
+            headers.extend_from_slice(b"\r\n");
+        }
+
         // Add authentication results header
         if self
             .core
@@ -369,6 +430,11 @@ impl<T: SessionStream> Session<T> {
             }
         }
 
+        // Add BIMI headers
+        if let Some(bimi_output) = &bimi_output {
+            bimi_output.write_header(&mut headers);
+        }
+
         // ARC Seal
         if let (Some(arc_sealer), Some(arc_output)) = (arc_sealer, &arc_output) {
             if !dkim_output.is_empty() && arc_output.can_be_sealed() {
@@ -385,12 +451,25 @@ impl<T: SessionStream> Session<T> {
             }
         }
 
-        // Run Milter filters
+        // Remove any BIMI headers supplied by the sender
         let mut modifications = Vec::new();
+        for (name, _) in &auth_message.headers {
+            for bimi_header in ["BIMI-Location", "BIMI-Indicator"] {
+                if name.eq_ignore_ascii_case(bimi_header.as_bytes()) {
+                    modifications.push(Modification::ChangeHeader {
+                        index: 1,
+                        name: bimi_header.to_string(),
+                        value: String::new(),
+                    });
+                }
+            }
+        }
+
+        // Run Milter filters
         match self.run_milters(Stage::Data, (&auth_message).into()).await {
             Ok(modifications_) => {
                 if !modifications_.is_empty() {
-                    modifications = modifications_;
+                    modifications.extend(modifications_);
                 }
             }
             Err(response) => {
@@ -619,6 +698,7 @@ impl<T: SessionStream> Session<T> {
             for modification in modifications {
                 match modification {
                     ScriptModification::AddHeader { name, value } => {
+                        verdict.add_header(&self.core.core.jmap, &name, &value);
                         headers.extend_from_slice(name.as_bytes());
                         headers.extend_from_slice(b": ");
                         headers.extend_from_slice(value.as_bytes());
@@ -634,12 +714,52 @@ impl<T: SessionStream> Session<T> {
         }
 
         // Build message
-        let mail_from = self.data.mail_from.clone().unwrap();
+        let mut mail_from = self.data.mail_from.clone().unwrap();
+        mail_from.flags |= queue::message_class(&auth_message.headers);
+        mail_from.flags |= queue::verdict_flags(&verdict);
         let rcpt_to = std::mem::take(&mut self.data.rcpt_to);
         let mut message = self
             .build_message(mail_from, rcpt_to, message_id, self.data.session_id)
             .await;
 
+        // A "TLS-Required: No" header field asks for the recipient domain's TLS
+        // policies to be ignored, unless REQUIRETLS was requested (RFC 8689)
+        if (message.flags & MAIL_REQUIRETLS) == 0
+            && auth_message.headers.iter().any(|(name, value)| {
+                name.eq_ignore_ascii_case(b"TLS-Required")
+                    && std::str::from_utf8(value)
+                        .is_ok_and(|value| value.trim().eq_ignore_ascii_case("no"))
+            })
+        {
+            message.flags |= MAIL_TLS_OPTIONAL;
+        }
+
+        // Rewrite the sender of messages forwarded to external addresses, such as
+        // aliases rewritten by the RCPT stage, using SRS
+        if self.data.authenticated_as.is_empty() {
+            match self
+                .core
+                .core
+                .srs_forward(
+                    &message.return_path,
+                    message.domains.iter().map(|d| d.domain.as_str()),
+                    &self.hostname,
+                )
+                .await
+            {
+                Ok(Some(srs_address)) => {
+                    message.set_return_path(srs_address);
+                }
+                Ok(None) => (),
+                Err(err) => {
+                    trc::error!(err
+                        .span_id(self.data.session_id)
+                        .caused_by(trc::location!())
+                        .details("Failed to rewrite sender address."));
+                }
+            }
+        }
+
         // Add Return-Path
         if self
             .core
@@ -711,10 +831,39 @@ impl<T: SessionStream> Session<T> {
         // Update size
         message.size = raw_message.len() + headers.len();
 
+        // Large messages are not accepted while disk space is low
+        if let Some(max_size) = self.core.core.storage.disk.max_message_size() {
+            if message.size > max_size {
+                trc::event!(
+                    Smtp(SmtpEvent::InboundDeferred),
+                    SpanId = self.data.session_id,
+                    Reason = "Insufficient disk space",
+                    Size = message.size,
+                    Limit = max_size,
+                );
+
+                return (b"452 4.3.1 Insufficient system storage for message size.\r\n"[..]).into();
+            }
+        }
+
         // Verify queue quota
         if self.core.has_quota(&mut message).await {
             // Prepare webhook event
             let queue_id = message.queue_id;
+            let message_id = MessageParser::new()
+                .parse_headers(raw_message)
+                .and_then(|headers| headers.message_id().map(|id| id.to_string()));
+            let from = if !message.return_path.is_empty() {
+                trc::Value::String(message.return_path.to_string())
+            } else {
+                trc::Value::Static("<>")
+            };
+            let to = message
+                .recipients
+                .iter()
+                .map(|r| trc::Value::String(r.address_lcase.clone()))
+                .collect::<Vec<_>>();
+            let size = message.size;
 
             // Queue message
             let source = if self.data.authenticated_as.is_empty() {
@@ -732,6 +881,16 @@ impl<T: SessionStream> Session<T> {
                 )
                 .await
             {
+                trc::event!(
+                    Queue(trc::QueueEvent::MessageAccepted),
+                    SpanId = self.data.session_id,
+                    QueueId = queue_id,
+                    MessageId = message_id,
+                    From = from,
+                    To = to,
+                    Size = size,
+                );
+
                 self.state = State::Accepted(queue_id);
                 self.data.messages_sent += 1;
                 (b"250 2.0.0 Message queued for delivery.\r\n"[..]).into()
@@ -751,9 +910,7 @@ impl<T: SessionStream> Session<T> {
         span_id: u64,
     ) -> Message {
         // Build message
-        let created = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map_or(0, |d| d.as_secs());
+        let created = now();
         let mut message = Message {
             queue_id,
             span_id,
@@ -961,4 +1118,31 @@ impl<T: SessionStream> Session<T> {
         headers.extend_from_slice(Date::now().to_rfc822().as_bytes());
         headers.extend_from_slice(b"\r\n");
     }
+
+    // Untrusted clients could otherwise bypass the spam filter or the junk folder rules
+    fn strip_status_headers(&self, raw_message: &[u8]) -> Option<Vec<u8>> {
+        let jmap = &self.core.core.jmap;
+        let names = [
+            jmap.spam_header.as_ref().map(|(name, _)| name),
+            jmap.spam_score_header.as_ref(),
+            jmap.virus_header.as_ref(),
+        ];
+        let is_status_header =
+            |header: &Header<'_>| names.iter().flatten().any(|name| **name == header.name);
+        let message = MessageParser::new().parse_headers(raw_message)?;
+        let headers = message.root_part().headers();
+        if !headers.iter().any(is_status_header) {
+            return None;
+        }
+
+        let mut stripped_message = Vec::with_capacity(raw_message.len());
+        let mut offset = 0;
+        for header in headers.iter().filter(|header| is_status_header(header)) {
+            stripped_message.extend_from_slice(&raw_message[offset..header.offset_field()]);
+            offset = header.offset_end();
+        }
+        stripped_message.extend_from_slice(&raw_message[offset..]);
+
+        Some(stripped_message)
+    }
 }
@@ -4,7 +4,10 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::time::{Duration, Instant, SystemTime};
+use std::{
+    fmt::Write,
+    time::{Duration, Instant, SystemTime},
+};
 
 use crate::{core::Session, scripts::ScriptResult};
 use common::{
@@ -143,6 +146,7 @@ impl<T: SessionStream> Session<T> {
         let ec = &self.core.core.smtp.session.extensions;
         let ac = &self.core.core.smtp.session.auth;
         let dc = &self.core.core.smtp.session.data;
+        let rc = &self.core.core.smtp.session.rcpt;
 
         // Pipelining
         if self
@@ -291,6 +295,65 @@ impl<T: SessionStream> Session<T> {
         // Generate response
         let mut buf = Vec::with_capacity(64);
         response.write(&mut buf).ok();
+
+        // Limits
+        if self
+            .core
+            .core
+            .eval_if(&ec.limits, self, self.data.session_id)
+            .await
+            .unwrap_or(true)
+        {
+            let mut limits = String::new();
+            for (name, value) in [
+                (
+                    "RCPTMAX",
+                    self.core
+                        .core
+                        .eval_if::<usize, _>(&rc.max_recipients, self, self.data.session_id)
+                        .await
+                        .unwrap_or(100),
+                ),
+                (
+                    "MAILMAX",
+                    self.core
+                        .core
+                        .eval_if(&dc.max_messages, self, self.data.session_id)
+                        .await
+                        .unwrap_or(10),
+                ),
+                (
+                    "RCPTDOMAINMAX",
+                    self.core
+                        .core
+                        .eval_if(&rc.max_domains, self, self.data.session_id)
+                        .await
+                        .unwrap_or(0),
+                ),
+            ] {
+                if value > 0 {
+                    let _ = write!(limits, " {name}={value}");
+                }
+            }
+            if !limits.is_empty() {
+                add_ehlo_capability(&mut buf, &format!("LIMITS{limits}"));
+            }
+        }
+
         self.write(&buf).await
     }
 }
+
+// Appends a capability not supported by the EHLO response generator
+fn add_ehlo_capability(buf: &mut Vec<u8>, capability: &str) {
+    let last_line = buf[..buf.len().saturating_sub(2)]
+        .windows(2)
+        .rposition(|w| w == b"\r\n")
+        .map_or(0, |pos| pos + 2);
+    if buf[last_line..].starts_with(b"250 ") {
+        buf[last_line + 3] = b'-';
+        buf.extend_from_slice(b"250 ");
+        buf.extend_from_slice(capability.as_bytes());
+        buf.extend_from_slice(b"\r\n");
+    }
+}
@@ -0,0 +1,109 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::listener::SessionStream;
+use store::write::now;
+use trc::SmtpEvent;
+
+use crate::core::Session;
+
+impl<T: SessionStream> Session<T> {
+    pub async fn is_greylisted(&self) -> bool {
+        let config = &self.core.core.smtp.session.rcpt.greylist;
+        if !self
+            .core
+            .core
+            .eval_if(&config.enable, self, self.data.session_id)
+            .await
+            .unwrap_or(false)
+        {
+            return false;
+        }
+
+        // Networks that retried correctly in the past are not greylisted
+        let store = &self.core.core.storage.lookup;
+        let network = config.network(self.data.remote_ip);
+        let allow_key = format!("greylist:a:{network}").into_bytes();
+        match store.key_exists(allow_key.clone()).await {
+            Ok(true) => return false,
+            Ok(false) => (),
+            Err(err) => {
+                trc::error!(err
+                    .span_id(self.data.session_id)
+                    .caused_by(trc::location!())
+                    .details("Failed to obtain greylist allow-list entry."));
+                return false;
+            }
+        }
+
+        // Look up the network/sender/recipient triplet
+        let rcpt = self.data.rcpt_to.last().unwrap();
+        let sender = self
+            .data
+            .mail_from
+            .as_ref()
+            .map(|mail_from| mail_from.address_lcase.as_str())
+            .unwrap_or_default();
+        let canonical = &self.core.core.smtp.canonical;
+        let triplet_key = format!(
+            "greylist:t:{network}:{}:{}",
+            canonical.address(sender),
+            canonical.address(&rcpt.address_lcase)
+        )
+        .into_bytes();
+        let now = now();
+        let result = match store.key_get::<i64>(triplet_key.clone()).await {
+            Ok(Some(first_seen)) if now >= first_seen as u64 + config.delay.as_secs() => {
+                trc::event!(
+                    Smtp(SmtpEvent::GreylistPassed),
+                    SpanId = self.data.session_id,
+                    RemoteIp = self.data.remote_ip,
+                    From = sender.to_string(),
+                    To = rcpt.address_lcase.clone(),
+                    Elapsed = trc::Value::Duration((now - first_seen as u64) * 1000),
+                );
+
+                // Allow-list the network
+                store
+                    .key_set(allow_key, vec![], config.allow_expire.as_secs().into())
+                    .await
+                    .map(|_| false)
+            }
+            Ok(Some(_)) => Ok(true),
+            Ok(None) => store
+                .key_set(
+                    triplet_key,
+                    (now as i64).to_be_bytes().to_vec(),
+                    config.expire.as_secs().into(),
+                )
+                .await
+                .map(|_| true),
+            Err(err) => Err(err),
+        };
+
+        match result {
+            Ok(true) => {
+                trc::event!(
+                    Smtp(SmtpEvent::Greylisted),
+                    SpanId = self.data.session_id,
+                    RemoteIp = self.data.remote_ip,
+                    From = sender.to_string(),
+                    To = rcpt.address_lcase.clone(),
+                );
+
+                true
+            }
+            Ok(false) => false,
+            Err(err) => {
+                trc::error!(err
+                    .span_id(self.data.session_id)
+                    .caused_by(trc::location!())
+                    .details("Failed to update greylist entry."));
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Instant;
+
+use common::{
+    config::smtp::session::{HttpLookup, HttpLookupFallback, Stage},
+    listener::SessionStream,
+    HttpLimitResponse,
+};
+use serde::{Deserialize, Serialize};
+use trc::SmtpEvent;
+
+use crate::{
+    core::Session,
+    inbound::{hooks::SmtpResponse, FilterResponse},
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct Request {
+    pub stage: String,
+    pub client: Client,
+    pub envelope: Envelope,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Client {
+    pub ip: String,
+    pub port: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub helo: Option<String>,
+    #[serde(rename = "authenticatedAs")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authenticated_as: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Envelope {
+    pub from: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Response {
+    pub action: Action,
+    #[serde(default)]
+    pub response: Option<SmtpResponse>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum Action {
+    #[serde(rename = "accept")]
+    Accept,
+    #[serde(rename = "reject")]
+    Reject,
+    #[serde(rename = "tempfail")]
+    TempFail,
+}
+
+impl<T: SessionStream> Session<T> {
+    // Queries the HTTP lookup selected for the current sender or recipient and
+    // returns the tags to add to the message if the envelope was accepted.
+    pub async fn run_http_lookup(
+        &self,
+        stage: Stage,
+    ) -> Result<Vec<(String, String)>, FilterResponse> {
+        let config = &self.core.core.smtp.session;
+        let lookup = if let Some(lookup) = self
+            .core
+            .core
+            .eval_if::<String, _>(
+                if stage == Stage::Mail {
+                    &config.mail.http_lookup
+                } else {
+                    &config.rcpt.http_lookup
+                },
+                self,
+                self.data.session_id,
+            )
+            .await
+            .and_then(|id| {
+                config.http_lookups.get(&id).or_else(|| {
+                    trc::event!(
+                        Smtp(SmtpEvent::HttpLookupError),
+                        SpanId = self.data.session_id,
+                        Id = id,
+                        Reason = "HTTP lookup not found",
+                    );
+                    None
+                })
+            }) {
+            lookup
+        } else {
+            return Ok(Vec::new());
+        };
+
+        let time = Instant::now();
+        let (mut message, response) = match self.send_http_lookup(stage, lookup).await {
+            Ok(response) => match response.action {
+                Action::Accept => {
+                    trc::event!(
+                        Smtp(SmtpEvent::HttpLookupAccept),
+                        SpanId = self.data.session_id,
+                        Id = lookup.id.clone(),
+                        Details = response.tags.clone(),
+                        Elapsed = time.elapsed(),
+                    );
+
+                    return Ok(response
+                        .tags
+                        .into_iter()
+                        .map(|tag| (lookup.tag_header.clone(), tag.replace(['\r', '\n'], " ")))
+                        .collect());
+                }
+                Action::Reject => {
+                    trc::event!(
+                        Smtp(SmtpEvent::HttpLookupReject),
+                        SpanId = self.data.session_id,
+                        Id = lookup.id.clone(),
+                        Elapsed = time.elapsed(),
+                    );
+
+                    (reject_response(stage), response.response)
+                }
+                Action::TempFail => {
+                    trc::event!(
+                        Smtp(SmtpEvent::HttpLookupReject),
+                        SpanId = self.data.session_id,
+                        Id = lookup.id.clone(),
+                        Details = "tempfail",
+                        Elapsed = time.elapsed(),
+                    );
+
+                    (FilterResponse::temp_fail(), response.response)
+                }
+            },
+            Err(err) => {
+                trc::event!(
+                    Smtp(SmtpEvent::HttpLookupError),
+                    SpanId = self.data.session_id,
+                    Id = lookup.id.clone(),
+                    Reason = err,
+                    Elapsed = time.elapsed(),
+                );
+
+                match lookup.on_error {
+                    HttpLookupFallback::Accept => return Ok(Vec::new()),
+                    HttpLookupFallback::Reject => (reject_response(stage), None),
+                    HttpLookupFallback::TempFail => (FilterResponse::server_failure(), None),
+                }
+            }
+        };
+
+        if let Some(response) = response {
+            if let (Some(status), Some(text)) = (response.status, response.message) {
+                if let Some(enhanced) = response.enhanced_status {
+                    message.message = format!("{status} {enhanced} {text}\r\n").into();
+                } else {
+                    message.message = format!("{status} {text}\r\n").into();
+                }
+            }
+            message.disconnect = response.disconnect;
+        }
+
+        Err(message)
+    }
+
+    async fn send_http_lookup(
+        &self,
+        stage: Stage,
+        lookup: &HttpLookup,
+    ) -> Result<Response, String> {
+        let request = Request {
+            stage: if stage == Stage::Mail { "mail" } else { "rcpt" }.to_string(),
+            client: Client {
+                ip: self.data.remote_ip.to_string(),
+                port: self.data.remote_port,
+                helo: (!self.data.helo_domain.is_empty()).then(|| self.data.helo_domain.clone()),
+                authenticated_as: (!self.data.authenticated_as.is_empty())
+                    .then(|| self.data.authenticated_as.clone()),
+            },
+            envelope: Envelope {
+                from: self
+                    .data
+                    .mail_from
+                    .as_ref()
+                    .map(|from| from.address.clone())
+                    .unwrap_or_default(),
+                to: if stage == Stage::Rcpt {
+                    self.data.rcpt_to.last().map(|rcpt| rcpt.address.clone())
+                } else {
+                    None
+                },
+            },
+        };
+
+        let response = reqwest::Client::builder()
+            .timeout(lookup.timeout)
+            .danger_accept_invalid_certs(lookup.tls_allow_invalid_certs)
+            .build()
+            .map_err(|err| format!("Failed to create HTTP client: {}", err))?
+            .post(&lookup.url)
+            .headers(lookup.headers.clone())
+            .body(
+                serde_json::to_string(&request)
+                    .map_err(|err| format!("Failed to serialize lookup request: {}", err))?,
+            )
+            .send()
+            .await
+            .map_err(|err| format!("Lookup request failed: {err}"))?;
+
+        if response.status().is_success() {
+            serde_json::from_slice(
+                response
+                    .bytes_with_limit(lookup.max_response_size)
+                    .await
+                    .map_err(|err| format!("Failed to parse lookup response: {}", err))?
+                    .ok_or_else(|| "Lookup response too large".to_string())?
+                    .as_ref(),
+            )
+            .map_err(|err| format!("Failed to parse lookup response: {}", err))
+        } else {
+            Err(format!(
+                "Lookup request failed with code {}: {}",
+                response.status().as_u16(),
+                response.status().canonical_reason().unwrap_or("Unknown")
+            ))
+        }
+    }
+}
+
+fn reject_response(stage: Stage) -> FilterResponse {
+    FilterResponse {
+        message: if stage == Stage::Mail {
+            "550 5.7.1 Sender address rejected.\r\n".into()
+        } else {
+            "550 5.1.1 Recipient address rejected.\r\n".into()
+        },
+        disconnect: false,
+    }
+}
@@ -4,11 +4,12 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::time::{Duration, Instant, SystemTime};
+use std::time::{Duration, Instant};
 
 use common::{config::smtp::session::Stage, listener::SessionStream, scripts::ScriptModification};
 use mail_auth::{IprevOutput, IprevResult, SpfOutput, SpfResult};
 use smtp_proto::{MailFrom, MtPriority, MAIL_BY_NOTIFY, MAIL_BY_RETURN, MAIL_REQUIRETLS};
+use store::write::now;
 use trc::SmtpEvent;
 use utils::config::Rate;
 
@@ -42,7 +43,14 @@ impl<T: SessionStream> Session<T> {
             return self
                 .write(b"503 5.5.1 Multiple MAIL commands not allowed.\r\n")
                 .await;
-        } else if self.params.auth_require && self.data.authenticated_as.is_empty() {
+        } else if self.params.auth_require
+            && self.data.authenticated_as.is_empty()
+            && !self
+                .core
+                .core
+                .is_ip_trusted(&self.data.remote_ip, self.data.session_id)
+                .await
+        {
             trc::event!(
                 Smtp(SmtpEvent::MailFromUnauthenticated),
                 SpanId = self.data.session_id,
@@ -51,6 +59,63 @@ impl<T: SessionStream> Session<T> {
             return self
                 .write(b"503 5.5.1 You must authenticate first.\r\n")
                 .await;
+        } else if self.data.messages_sent
+            >= self
+                .core
+                .core
+                .eval_if(
+                    &self.core.core.smtp.session.data.max_messages,
+                    self,
+                    self.data.session_id,
+                )
+                .await
+                .unwrap_or(10)
+        {
+            trc::event!(
+                Smtp(SmtpEvent::TooManyMessages),
+                SpanId = self.data.session_id,
+                Limit = self.data.messages_sent
+            );
+
+            return self
+                .write(b"451 4.4.5 Maximum number of messages per session exceeded.\r\n")
+                .await;
+        } else if self.core.core.storage.maintenance.is_enabled() {
+            trc::event!(
+                Server(trc::ServerEvent::MaintenanceMode),
+                SpanId = self.data.session_id,
+            );
+
+            return self
+                .write(
+                    format!(
+                        "451 4.3.0 {}\r\n",
+                        self.core.core.storage.maintenance.message()
+                    )
+                    .as_bytes(),
+                )
+                .await;
+        } else if self.core.core.storage.health.defer_inbound() {
+            trc::event!(
+                Smtp(SmtpEvent::InboundDeferred),
+                SpanId = self.data.session_id,
+            );
+
+            return self
+                .write(
+                    b"451 4.3.0 Mail system temporarily unavailable, please try again later.\r\n",
+                )
+                .await;
+        } else if self.core.core.storage.disk.defer_inbound() {
+            trc::event!(
+                Smtp(SmtpEvent::InboundDeferred),
+                SpanId = self.data.session_id,
+                Reason = "Insufficient disk space",
+            );
+
+            return self
+                .write(b"452 4.3.1 Insufficient system storage, please try again later.\r\n")
+                .await;
         } else if self.data.iprev.is_none() && self.params.iprev.verify() {
             let time = Instant::now();
             let iprev = self
@@ -257,6 +322,17 @@ impl<T: SessionStream> Session<T> {
             }
         }
 
+        // HTTP lookup
+        match self.run_http_lookup(Stage::Mail).await {
+            Ok(tags) => {
+                self.data.lookup_tags = tags;
+            }
+            Err(message) => {
+                self.data.mail_from = None;
+                return self.write(message.message.as_bytes()).await;
+            }
+        }
+
         // Validate parameters
         let config = &self.core.core.smtp.session.extensions;
         let config_data = &self.core.core.smtp.session.data;
@@ -326,8 +402,33 @@ impl<T: SessionStream> Session<T> {
                 .await
                 .is_some()
             {
-                if (-6..6).contains(&from.mt_priority) {
-                    self.data.priority = from.mt_priority as i16;
+                if (-9..=9).contains(&from.mt_priority) {
+                    // Senders not authorized for the requested level are lowered to their maximum,
+                    // role based limits take precedence over the max-priority expression
+                    let max_priority = if let Some(max_priority) = self.data.max_priority {
+                        max_priority as i64
+                    } else {
+                        self.core
+                            .core
+                            .eval_if::<i64, _>(
+                                &self.core.core.smtp.session.mail.max_priority,
+                                self,
+                                self.data.session_id,
+                            )
+                            .await
+                            .unwrap_or(0)
+                    };
+                    if from.mt_priority > max_priority {
+                        trc::event!(
+                            Smtp(SmtpEvent::MtPriorityLowered),
+                            SpanId = self.data.session_id,
+                            Details = from.mt_priority,
+                            Limit = max_priority,
+                        );
+                        self.data.priority = max_priority.clamp(-9, 9) as i16;
+                    } else {
+                        self.data.priority = from.mt_priority as i16;
+                    }
                 } else {
                     trc::event!(
                         Smtp(SmtpEvent::MtPriorityInvalid),
@@ -367,7 +468,23 @@ impl<T: SessionStream> Session<T> {
             return self
                 .write(b"552 5.3.4 Message too big for system.\r\n")
                 .await;
+        } else if let Some(max_size) = self.core.core.storage.disk.max_message_size() {
+            if from.size > max_size {
+                trc::event!(
+                    Smtp(SmtpEvent::InboundDeferred),
+                    SpanId = self.data.session_id,
+                    Reason = "Insufficient disk space",
+                    Size = from.size,
+                    Limit = max_size,
+                );
+
+                self.data.mail_from = None;
+                return self
+                    .write(b"452 4.3.1 Insufficient system storage for message size.\r\n")
+                    .await;
+            }
         }
+        self.data.message_size = from.size;
         if from.hold_for != 0 || from.hold_until != 0 {
             if let Some(max_hold) = self
                 .core
@@ -379,9 +496,7 @@ impl<T: SessionStream> Session<T> {
                 let hold_for = if from.hold_for != 0 {
                     from.hold_for
                 } else {
-                    let now = SystemTime::now()
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .map_or(0, |d| d.as_secs());
+                    let now = now();
                     if from.hold_until > now {
                         from.hold_until - now
                     } else {
@@ -374,7 +374,7 @@ impl SessionData {
                     }
                 }
                 Modification::DeleteRcpt { recipient } => {
-                    let recipient = strip_brackets(&recipient);
+                    let recipient = strip_brackets(&recipient).to_lowercase();
                     self.rcpt_to.retain(|r| r.address_lcase != recipient);
                 }
                 Modification::ReplaceBody { value } => {
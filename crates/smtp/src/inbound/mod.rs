@@ -13,9 +13,13 @@ use mail_auth::{
 };
 
 pub mod auth;
+pub mod bimi;
+pub mod call_ahead;
 pub mod data;
 pub mod ehlo;
+pub mod greylist;
 pub mod hooks;
+pub mod http_lookup;
 pub mod mail;
 pub mod milter;
 pub mod rcpt;
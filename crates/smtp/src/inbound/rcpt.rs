@@ -39,7 +39,7 @@ impl<T: SessionStream> Session<T> {
                 SpanId = self.data.session_id,
                 Limit = self.params.rcpt_max,
             );
-            return self.write(b"451 4.5.3 Too many recipients.\r\n").await;
+            return self.write(b"452 4.5.3 Too many recipients.\r\n").await;
         }
 
         // Verify parameters
@@ -55,12 +55,45 @@ impl<T: SessionStream> Session<T> {
                 .await;
         }
 
+        // Reverse SRS addresses, addresses rewritten by other forwarders are left as is
+        let mut address = to.address;
+        let mut is_srs = false;
+        let mut reversed = self.core.core.smtp.mail_auth.srs.reverse(&address);
+        if !matches!(reversed, Ok(None)) && !self.is_srs_domain(address.domain_part()).await {
+            reversed = Ok(None);
+        }
+        match reversed {
+            Ok(Some(original)) => {
+                trc::event!(
+                    Smtp(SmtpEvent::RcptToRewritten),
+                    SpanId = self.data.session_id,
+                    Details = address,
+                    To = original.clone(),
+                );
+
+                // Bounces to SRS addresses are relayed back to the original sender
+                address = original;
+                is_srs = true;
+            }
+            Ok(None) => (),
+            Err(reason) => {
+                trc::event!(
+                    Smtp(SmtpEvent::InvalidRecipientAddress),
+                    SpanId = self.data.session_id,
+                    To = address,
+                    Reason = reason,
+                );
+
+                return self.rcpt_error(b"550 5.1.1 Invalid SRS address.\r\n").await;
+            }
+        }
+
         // Build RCPT
-        let address_lcase = to.address.to_lowercase();
+        let address_lcase = address.to_lowercase();
         let rcpt = SessionAddress {
             domain: address_lcase.domain_part().to_string(),
             address_lcase,
-            address: to.address,
+            address,
             flags: to.flags,
             dsn_info: to.orcpt,
         };
@@ -72,6 +105,20 @@ impl<T: SessionStream> Session<T> {
                 To = rcpt.address_lcase,
             );
             return self.write(b"250 2.1.5 OK\r\n").await;
+        } else if self.params.rcpt_domains_max > 0
+            && !self.data.rcpt_to.iter().any(|r| r.domain == rcpt.domain)
+            && self.data.rcpt_domains() >= self.params.rcpt_domains_max
+        {
+            trc::event!(
+                Smtp(SmtpEvent::TooManyRecipients),
+                SpanId = self.data.session_id,
+                To = rcpt.address_lcase,
+                Domain = rcpt.domain,
+                Limit = self.params.rcpt_domains_max,
+            );
+            return self
+                .write(b"452 4.5.3 Too many recipient domains.\r\n")
+                .await;
         }
         self.data.rcpt_to.push(rcpt);
 
@@ -232,17 +279,7 @@ impl<T: SessionStream> Session<T> {
                                     .await;
                             }
                         }
-                    } else if !self
-                        .core
-                        .core
-                        .eval_if(
-                            &self.core.core.smtp.session.rcpt.relay,
-                            self,
-                            self.data.session_id,
-                        )
-                        .await
-                        .unwrap_or(false)
-                    {
+                    } else if !is_srs && !self.is_relay_allowed().await {
                         trc::event!(
                             Smtp(SmtpEvent::RelayNotAllowed),
                             SpanId = self.data.session_id,
@@ -265,25 +302,97 @@ impl<T: SessionStream> Session<T> {
                         .await;
                 }
             }
-        } else if !self
+        } else if !is_srs && !self.is_relay_allowed().await {
+            trc::event!(
+                Smtp(SmtpEvent::RelayNotAllowed),
+                SpanId = self.data.session_id,
+                To = rcpt.address_lcase.clone(),
+            );
+
+            self.data.rcpt_to.pop();
+            return self.rcpt_error(b"550 5.1.2 Relay not allowed.\r\n").await;
+        }
+
+        // Enforce recipient domain limits
+        let rcpt = self.data.rcpt_to.last().unwrap();
+        let max_rcpts = self
             .core
             .core
             .eval_if(
-                &self.core.core.smtp.session.rcpt.relay,
+                &self.core.core.smtp.session.rcpt.max_recipients,
                 self,
                 self.data.session_id,
             )
             .await
-            .unwrap_or(false)
+            .unwrap_or(self.params.rcpt_max);
+        if self
+            .data
+            .rcpt_to
+            .iter()
+            .filter(|r| r.domain == rcpt.domain)
+            .count()
+            > max_rcpts
         {
             trc::event!(
-                Smtp(SmtpEvent::RelayNotAllowed),
+                Smtp(SmtpEvent::TooManyRecipients),
                 SpanId = self.data.session_id,
                 To = rcpt.address_lcase.clone(),
+                Limit = max_rcpts,
             );
 
             self.data.rcpt_to.pop();
-            return self.rcpt_error(b"550 5.1.2 Relay not allowed.\r\n").await;
+            return self
+                .write(b"452 4.5.3 Too many recipients for this domain.\r\n")
+                .await;
+        }
+        let max_message_size = self
+            .core
+            .core
+            .eval_if(
+                &self.core.core.smtp.session.data.max_message_size,
+                self,
+                self.data.session_id,
+            )
+            .await
+            .unwrap_or(25 * 1024 * 1024);
+        if self.data.message_size > max_message_size {
+            trc::event!(
+                Smtp(SmtpEvent::MessageTooLarge),
+                SpanId = self.data.session_id,
+                To = rcpt.address_lcase.clone(),
+                Size = self.data.message_size,
+                Limit = max_message_size,
+            );
+
+            self.data.rcpt_to.pop();
+            return self
+                .write(b"552 5.3.4 Message too big for this recipient.\r\n")
+                .await;
+        }
+
+        // Call-ahead verification
+        if self.is_rejected_by_call_ahead().await {
+            self.data.rcpt_to.pop();
+            return self
+                .rcpt_error(b"550 5.1.1 Mailbox does not exist.\r\n")
+                .await;
+        }
+
+        // HTTP lookup
+        let lookup_tags = match self.run_http_lookup(Stage::Rcpt).await {
+            Ok(tags) => tags,
+            Err(message) => {
+                self.data.rcpt_to.pop();
+                return self.write(message.message.as_bytes()).await;
+            }
+        };
+
+        // Greylisting
+        if self.is_greylisted().await {
+            self.data.rcpt_to.pop();
+            return self
+                .write(b"451 4.7.1 Greylisted, please try again later.\r\n")
+                .await;
         }
 
         if self.is_allowed().await {
@@ -305,9 +414,81 @@ impl<T: SessionStream> Session<T> {
                 .await;
         }
 
+        // The message size limit is the lowest limit among all recipients
+        self.params.max_message_size = if self.data.rcpt_to.len() > 1 {
+            std::cmp::min(self.params.max_message_size, max_message_size)
+        } else {
+            max_message_size
+        };
+
+        // Tags are only added for accepted recipients
+        for tag in lookup_tags {
+            if !self.data.lookup_tags.contains(&tag) {
+                self.data.lookup_tags.push(tag);
+            }
+        }
+
         self.write(b"250 2.1.5 OK\r\n").await
     }
 
+    async fn is_relay_allowed(&self) -> bool {
+        // Clients on trusted networks are always allowed to relay
+        self.core
+            .core
+            .is_ip_trusted(&self.data.remote_ip, self.data.session_id)
+            .await
+            || self
+                .core
+                .core
+                .eval_if(
+                    &self.core.core.smtp.session.rcpt.relay,
+                    self,
+                    self.data.session_id,
+                )
+                .await
+                .unwrap_or(false)
+    }
+
+    async fn is_srs_domain(&self, domain: &str) -> bool {
+        if self
+            .core
+            .core
+            .smtp
+            .mail_auth
+            .srs
+            .domain
+            .as_deref()
+            .is_some_and(|srs_domain| srs_domain.eq_ignore_ascii_case(domain))
+        {
+            return true;
+        }
+
+        if let Some(directory) = self
+            .core
+            .core
+            .eval_if::<String, _>(
+                &self.core.core.smtp.session.rcpt.directory,
+                self,
+                self.data.session_id,
+            )
+            .await
+            .and_then(|name| self.core.core.get_directory(&name))
+        {
+            match directory.is_local_domain(&domain.to_lowercase()).await {
+                Ok(is_local_domain) => is_local_domain,
+                Err(err) => {
+                    trc::error!(err
+                        .span_id(self.data.session_id)
+                        .caused_by(trc::location!())
+                        .details("Failed to verify SRS domain."));
+                    false
+                }
+            }
+        } else {
+            false
+        }
+    }
+
     async fn rcpt_error(&mut self, response: &[u8]) -> Result<(), ()> {
         tokio::time::sleep(self.params.rcpt_errors_wait).await;
         self.data.rcpt_errors += 1;
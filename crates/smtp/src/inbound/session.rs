@@ -436,9 +436,11 @@ impl<T: AsyncWrite + AsyncRead + Unpin> Session<T> {
         self.data.spf_mail_from = None;
         self.data.rcpt_to.clear();
         self.data.message = Vec::with_capacity(0);
+        self.data.message_size = 0;
         self.data.priority = 0;
         self.data.delivery_by = 0;
         self.data.future_release = 0;
+        self.data.lookup_tags.clear();
     }
 
     #[inline(always)]
@@ -501,6 +503,15 @@ impl<T: AsyncWrite + AsyncRead + Unpin> Session<T> {
             }
         }
     }
+
+    // Whether a request or message has been partially received
+    pub fn is_pending_request(&self) -> bool {
+        match &self.state {
+            State::Request(receiver) => !receiver.buf.is_empty(),
+            State::Accepted(_) | State::None => false,
+            _ => true,
+        }
+    }
 }
 
 impl<T: SessionStream> ResolveVariable for Session<T> {
@@ -4,11 +4,15 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use common::{
     config::smtp::session::Stage,
-    listener::{self, SessionManager, SessionStream},
+    listener::{
+        self,
+        tarpit::{EarlyTalkerAction, TransferRate},
+        SessionManager, SessionStream,
+    },
 };
 use tokio_rustls::server::TlsStream;
 use trc::{SecurityEvent, SmtpEvent};
@@ -136,6 +140,119 @@ impl<T: SessionStream> Session<T> {
             self.hostname = "localhost".to_string();
         }
 
+        // Tarpit clients and detect those talking before the greeting
+        self.params.tarpit = self.core.core.eval_tarpit(self, self.data.session_id).await;
+        let mut greeting_wait = self
+            .core
+            .core
+            .eval_greeting_wait(self, self.data.session_id)
+            .await;
+        self.data.transfer_rate =
+            TransferRate::new(self.core.core.network.tarpit.min_transfer_rate.clone());
+        if !greeting_wait.is_zero() {
+            match self
+                .core
+                .core
+                .is_known_good_client(self.data.remote_ip)
+                .await
+            {
+                Ok(true) => {
+                    greeting_wait = Duration::ZERO;
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    trc::error!(err
+                        .span_id(self.data.session_id)
+                        .caused_by(trc::location!())
+                        .details("Failed to check known good client cache."));
+                }
+            }
+        }
+        let early_data = if !greeting_wait.is_zero() {
+            match listener::tarpit::greeting_wait(&mut self.stream, greeting_wait).await {
+                Ok(early_data) => early_data,
+                Err(_) => return false,
+            }
+        } else {
+            Vec::new()
+        };
+        if early_data.is_empty() && !greeting_wait.is_zero() {
+            if let Err(err) = self
+                .core
+                .core
+                .set_known_good_client(self.data.remote_ip)
+                .await
+            {
+                trc::error!(err
+                    .span_id(self.data.session_id)
+                    .caused_by(trc::location!())
+                    .details("Failed to update known good client cache."));
+            }
+        } else if !early_data.is_empty() {
+            trc::event!(
+                Security(SecurityEvent::EarlyTalker),
+                SpanId = self.data.session_id,
+                ListenerId = self.instance.id.clone(),
+                RemoteIp = self.data.remote_ip,
+                Size = early_data.len(),
+            );
+
+            let is_banned = match self
+                .core
+                .core
+                .is_early_talker_fail2banned(self.data.remote_ip)
+                .await
+            {
+                Ok(true) => {
+                    trc::event!(
+                        Security(SecurityEvent::EarlyTalkerBan),
+                        SpanId = self.data.session_id,
+                        ListenerId = self.instance.id.clone(),
+                        RemoteIp = self.data.remote_ip,
+                    );
+                    true
+                }
+                Ok(false) => false,
+                Err(err) => {
+                    trc::error!(err
+                        .span_id(self.data.session_id)
+                        .caused_by(trc::location!())
+                        .details("Failed to check if IP should be banned."));
+                    false
+                }
+            };
+
+            let action = if !is_banned {
+                self.core
+                    .core
+                    .eval_early_talker_action(self, self.data.session_id)
+                    .await
+            } else {
+                EarlyTalkerAction::Reject
+            };
+            if action == EarlyTalkerAction::Reject {
+                let _ = self
+                    .write(
+                        format!(
+                            "554 5.5.0 {} Protocol violation: data sent before greeting.\r\n",
+                            self.hostname
+                        )
+                        .as_bytes(),
+                    )
+                    .await;
+                return false;
+            }
+
+            if action == EarlyTalkerAction::Score {
+                self.data.early_talker = true;
+            } else {
+                self.params.tarpit = self
+                    .params
+                    .tarpit
+                    .max(self.core.core.network.tarpit.early_talker_delay);
+            }
+        }
+
         // Obtain greeting
         let greeting = self
             .core
@@ -150,6 +267,15 @@ impl<T: SessionStream> Session<T> {
             return false;
         }
 
+        // Process any commands sent before the greeting
+        if !early_data.is_empty() {
+            self.data.bytes_left = self.data.bytes_left.saturating_sub(early_data.len());
+            tokio::time::sleep(self.params.tarpit).await;
+            if !matches!(self.ingest(&early_data).await, Ok(true)) {
+                return false;
+            }
+        }
+
         true
     }
 
@@ -165,10 +291,28 @@ impl<T: SessionStream> Session<T> {
                         match result {
                             Ok(Ok(bytes_read)) => {
                                 if bytes_read > 0 {
-                                    if Instant::now() < self.data.valid_until && bytes_read <= self.data.bytes_left  {
+                                    if !self.data.transfer_rate.record(bytes_read) {
+                                        self
+                                            .write(format!("421 4.7.0 {} Transfer rate too low.\r\n", self.hostname).as_bytes())
+                                            .await
+                                            .ok();
+
+                                        trc::event!(
+                                            Security(SecurityEvent::SlowTransfer),
+                                            SpanId = self.data.session_id,
+                                            RemoteIp = self.data.remote_ip,
+                                        );
+
+                                        break;
+                                    } else if Instant::now() < self.data.valid_until && bytes_read <= self.data.bytes_left  {
                                         self.data.bytes_left -= bytes_read;
+                                        if !self.params.tarpit.is_zero() {
+                                            tokio::time::sleep(self.params.tarpit).await;
+                                        }
                                         match self.ingest(&buf[..bytes_read]).await {
-                                            Ok(true) => (),
+                                            Ok(true) => {
+                                                self.data.transfer_rate.set_pending(self.is_pending_request());
+                                            }
                                             Ok(false) => {
                                                 return true;
                                             }
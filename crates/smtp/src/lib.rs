@@ -49,6 +49,7 @@ impl SMTP {
                 ThrottleKeyHasherBuilder::default(),
                 shard,
             ),
+            queue_adaptive: DashMap::with_capacity_and_shard_amount(capacity, shard),
             queue_tx,
             report_tx,
             queue_id_gen: config
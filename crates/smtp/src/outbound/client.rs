@@ -179,9 +179,7 @@ impl<T: AsyncRead + AsyncWrite + Unpin> SmtpClient<T> {
         match params
             .core
             .core
-            .storage
-            .blob
-            .get_blob(message.blob_hash.as_slice(), 0..usize::MAX)
+            .get_queued_message(message.blob_hash.as_slice(), 0..usize::MAX)
             .await
         {
             Ok(Some(raw_message)) => tokio::time::timeout(params.timeout_data, async {
@@ -672,5 +670,9 @@ pub(crate) fn from_error_status(status: &Status<(), Error>) -> trc::Error {
         Error::RateLimited => todo!(),
         Error::ConcurrencyLimited => todo!(),
         Error::Io(err) => event.details("I/O Error").reason(err),
+        Error::DeliveryTimeExpired => event.details("Delivery Time Expired"),
+        Error::RequireTlsError(err) => event
+            .details("REQUIRETLS Error")
+            .ctx(trc::Key::Reason, err.details.clone()),
     }
 }
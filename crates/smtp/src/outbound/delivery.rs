@@ -15,7 +15,7 @@ use mail_auth::{
     mta_sts::TlsRpt,
     report::tlsrpt::{FailureDetails, ResultType},
 };
-use smtp_proto::MAIL_REQUIRETLS;
+use smtp_proto::{MAIL_BY_NOTIFY, MAIL_BY_RETURN, MAIL_REQUIRETLS};
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
     time::{Duration, Instant},
@@ -25,7 +25,7 @@ use trc::{DaneEvent, DeliveryEvent, MtaStsEvent, ServerEvent, TlsRptEvent};
 
 use crate::{
     core::SMTP,
-    queue::{ErrorDetails, Message},
+    queue::{ErrorDetails, Message, MAIL_TLS_OPTIONAL},
     reporting::{tls::TlsRptOptions, PolicyType, TlsEvent},
 };
 
@@ -34,6 +34,9 @@ use crate::queue::{
     throttle, DeliveryAttempt, Domain, Error, Event, OnHold, QueueEnvelope, Status,
 };
 
+// Minimum time between delivery attempts when retrying before a DELIVERBY deadline
+const MIN_DEADLINE_RETRY: u64 = 30;
+
 impl DeliveryAttempt {
     pub async fn try_deliver(mut self, core: SMTP) {
         tokio::spawn(async move {
@@ -107,6 +110,7 @@ impl DeliveryAttempt {
         let span_id = message.span_id;
 
         // Send any due Delivery Status Notifications
+        message.log_delivery_events(false);
         core.send_dsn(&mut message).await;
 
         if has_pending_delivery {
@@ -158,6 +162,7 @@ impl DeliveryAttempt {
                     throttle::Error::Concurrency { limiter } => {
                         // Save changes to disk
                         let next_due = message.next_event_after(now());
+                        let priority = message.priority;
                         message.save_changes(&core, None, None).await;
 
                         trc::event!(
@@ -169,6 +174,7 @@ impl DeliveryAttempt {
                         Event::OnHold(OnHold {
                             next_due,
                             limiters: vec![limiter],
+                            priority,
                             message: self.event,
                         })
                     }
@@ -248,13 +254,16 @@ impl DeliveryAttempt {
                 }
             }
 
-            // Obtain next hop
-            let (mut remote_hosts, is_smtp) = match core
-                .core
-                .eval_if::<String, _>(&queue_config.next_hop, &envelope, message.span_id)
-                .await
-                .and_then(|name| core.core.get_relay_host(&name, message.span_id))
-            {
+            // Obtain next hop, routes in the smart host table take precedence
+            let next_hop = match queue_config.route(&domain.domain, &message.return_path_domain) {
+                Some(route) => Some(&route.host),
+                None => core
+                    .core
+                    .eval_if::<String, _>(&queue_config.next_hop, &envelope, message.span_id)
+                    .await
+                    .and_then(|name| core.core.get_relay_host(&name, message.span_id)),
+            };
+            let (mut remote_hosts, is_smtp) = match next_hop {
                 Some(next_hop) if next_hop.protocol == ServerProtocol::Http => {
                     // Deliver message locally
                     let delivery_result = message
@@ -293,6 +302,10 @@ impl DeliveryAttempt {
                     .unwrap_or(RequireOptional::Optional),
                 ..Default::default()
             };
+            if (message.flags & MAIL_TLS_OPTIONAL) != 0 {
+                // The sender asked for the recipient domain's TLS policies to be ignored
+                tls_strategy.mta_sts = RequireOptional::Disable;
+            }
             let allow_invalid_certs = core
                 .core
                 .eval_if(&queue_config.tls.invalid_certs, &envelope, message.span_id)
@@ -671,6 +684,16 @@ impl DeliveryAttempt {
                     .eval_if(&queue_config.tls.start, &envelope, message.span_id)
                     .await
                     .unwrap_or(RequireOptional::Optional);
+                if (message.flags & MAIL_REQUIRETLS) != 0 || remote_host.is_tls_required() {
+                    // Never fall back to plain-text when REQUIRETLS was requested
+                    // or when the relay requires TLS
+                    tls_strategy.tls = RequireOptional::Require;
+                } else if (message.flags & MAIL_TLS_OPTIONAL) != 0 {
+                    tls_strategy.dane = RequireOptional::Disable;
+                    if tls_strategy.is_tls_required() {
+                        tls_strategy.tls = RequireOptional::Optional;
+                    }
+                }
 
                 // Lookup DANE policy
                 let dane_policy = if tls_strategy.try_dane() && is_smtp {
@@ -852,6 +875,20 @@ impl DeliveryAttempt {
                         }
                     }
 
+                    // Apply adaptive throttling to hosts that recently signaled throttling
+                    if let Err(err) =
+                        core.is_allowed_adaptive(envelope.mx, &mut in_flight_host, message.span_id)
+                    {
+                        trc::event!(
+                            Delivery(DeliveryEvent::RateLimitExceeded),
+                            SpanId = message.span_id,
+                            Hostname = envelope.mx.to_string(),
+                            RemoteIp = remote_ip,
+                        );
+                        message.domains[domain_idx].set_throttle_error(err, &mut on_hold);
+                        continue 'next_domain;
+                    }
+
                     // Connect
                     let time = Instant::now();
                     let conn_timeout = core
@@ -947,6 +984,13 @@ impl DeliveryAttempt {
                             .eval_if(&queue_config.timeout.data, &envelope, message.span_id)
                             .await
                             .unwrap_or_else(|| Duration::from_secs(5 * 60)),
+                        deliver_by: if (message.flags & MAIL_BY_RETURN) != 0 {
+                            Some(domain.expires as i64 - now() as i64).filter(|by| *by > 0)
+                        } else if (message.flags & MAIL_BY_NOTIFY) != 0 {
+                            Some(domain.notify.due as i64 - now() as i64)
+                        } else {
+                            None
+                        },
                     };
 
                     // Prepare TLS connector
@@ -977,6 +1021,12 @@ impl DeliveryAttempt {
                                 Details = status.to_string(),
                             );
 
+                            core.adaptive_throttle_update(
+                                envelope.mx,
+                                &status,
+                                std::iter::empty(),
+                                message.span_id,
+                            );
                             last_status = status;
                             continue 'next_host;
                         }
@@ -1253,6 +1303,12 @@ impl DeliveryAttempt {
                                 Details = from_error_status(&status),
                             );
 
+                            core.adaptive_throttle_update(
+                                envelope.mx,
+                                &status,
+                                std::iter::empty(),
+                                message.span_id,
+                            );
                             last_status = status;
                             continue 'next_host;
                         }
@@ -1267,6 +1323,14 @@ impl DeliveryAttempt {
                             .await
                     };
 
+                    // Adapt the delivery rate to throttling signals from the remote host
+                    core.adaptive_throttle_update(
+                        envelope.mx,
+                        &delivery_result,
+                        recipients.iter().filter(|r| r.domain_idx == domain_idx),
+                        message.span_id,
+                    );
+
                     // Update status for the current domain and continue with the next one
                     let schedule = core
                         .core
@@ -1292,13 +1356,22 @@ impl DeliveryAttempt {
         }
         message.recipients = recipients;
 
+        // Retry within the DELIVERBY deadline
+        if (message.flags & MAIL_BY_RETURN) != 0 {
+            for domain in &mut message.domains {
+                domain.retry_before_deadline();
+            }
+        }
+
         // Send Delivery Status Notifications
+        message.log_delivery_events(true);
         core.send_dsn(&mut message).await;
 
         // Notify queue manager
         let result = if !on_hold.is_empty() {
             // Save changes to disk
             let next_due = message.next_event_after(now());
+            let priority = message.priority;
             message.save_changes(&core, None, None).await;
 
             trc::event!(
@@ -1309,6 +1382,7 @@ impl DeliveryAttempt {
             Event::OnHold(OnHold {
                 next_due,
                 limiters: on_hold,
+                priority,
                 message: self.event,
             })
         } else if let Some(due) = message.next_event() {
@@ -1357,6 +1431,27 @@ impl Message {
 
         for (idx, domain) in self.domains.iter_mut().enumerate() {
             match &domain.status {
+                Status::TemporaryFailure(_) | Status::Scheduled
+                    if domain.expires <= now && (self.flags & MAIL_BY_RETURN) != 0 =>
+                {
+                    trc::event!(
+                        Delivery(DeliveryEvent::Failed),
+                        SpanId = self.span_id,
+                        Domain = domain.domain.clone(),
+                        Reason = "Delivery time expired.",
+                    );
+
+                    // The delivery deadline is reported instead of the last error
+                    for rcpt in &mut self.recipients {
+                        if rcpt.domain_idx == idx
+                            && matches!(rcpt.status, Status::TemporaryFailure(_))
+                        {
+                            rcpt.status = Status::Scheduled;
+                        }
+                    }
+
+                    domain.status = Status::PermanentFailure(Error::DeliveryTimeExpired);
+                }
                 Status::TemporaryFailure(_) if domain.expires <= now => {
                     trc::event!(
                         Delivery(DeliveryEvent::Failed),
@@ -1376,11 +1471,13 @@ impl Message {
                         std::mem::replace(&mut domain.status, Status::Scheduled).into_permanent();
                 }
                 Status::Scheduled if domain.expires <= now => {
+                    let reason = "Queue rate limit exceeded.";
+
                     trc::event!(
                         Delivery(DeliveryEvent::Failed),
                         SpanId = self.span_id,
                         Domain = domain.domain.clone(),
-                        Reason = "Queue rate limit exceeded.",
+                        Reason = reason,
                     );
 
                     for rcpt in &mut self.recipients {
@@ -1390,9 +1487,15 @@ impl Message {
                         }
                     }
 
-                    domain.status = Status::PermanentFailure(Error::Io(
-                        "Queue rate limit exceeded.".to_string(),
-                    ));
+                    domain.status = Status::PermanentFailure(Error::Io(reason.to_string()));
+                }
+                Status::Scheduled
+                    if domain.notify.due <= now && (self.flags & MAIL_BY_NOTIFY) != 0 =>
+                {
+                    // No delivery attempt was made before the deadline, the delay
+                    // notification reports the expired delivery time.
+                    domain.status = Status::TemporaryFailure(Error::DeliveryTimeExpired);
+                    has_pending_delivery = true;
                 }
                 Status::Completed(_) | Status::PermanentFailure(_) => (),
                 _ => {
@@ -1421,4 +1524,18 @@ impl Domain {
             + schedule[std::cmp::min(self.retry.inner as usize, schedule.len() - 1)].as_secs();
         self.retry.inner += 1;
     }
+
+    /// Moves a retry scheduled past the delivery deadline to halfway through
+    /// the remaining time, so that further attempts are made before expiring
+    pub fn retry_before_deadline(&mut self) {
+        let now = now();
+        if matches!(
+            &self.status,
+            Status::TemporaryFailure(_) | Status::Scheduled
+        ) && self.retry.due >= self.expires
+            && self.expires.saturating_sub(now) >= MIN_DEADLINE_RETRY * 2
+        {
+            self.retry.due = now + (self.expires - now) / 2;
+        }
+    }
 }
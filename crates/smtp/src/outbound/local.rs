@@ -10,7 +10,8 @@ use tokio::sync::{mpsc, oneshot};
 use trc::ServerEvent;
 
 use crate::queue::{
-    Error, ErrorDetails, HostResponse, Message, Recipient, Status, RCPT_STATUS_CHANGED,
+    flags_verdict, Error, ErrorDetails, HostResponse, Message, Recipient, Status,
+    RCPT_STATUS_CHANGED,
 };
 
 impl Message {
@@ -48,6 +49,7 @@ impl Message {
                     recipients: recipient_addresses,
                     message_blob: self.blob_hash.clone(),
                     message_size: self.size,
+                    verdict: flags_verdict(self.flags),
                     session_id: self.span_id,
                 },
                 result_tx,
@@ -6,10 +6,16 @@
 
 use std::{
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
-use common::expr::{functions::ResolveVariable, V_MX};
+use common::{
+    config::smtp::queue::IpPoolStrategy,
+    expr::{functions::ResolveVariable, V_MX},
+};
 use mail_auth::{IpLookupStrategy, MX};
 use rand::{seq::SliceRandom, Rng};
 
@@ -120,48 +126,69 @@ impl SMTP {
                 remote_ips,
             };
 
-            // Obtain source IPv4 address
-            let source_ips = self
+            // Obtain the source IP pool, if any
+            let pool = self
                 .core
-                .eval_if::<Vec<Ipv4Addr>, _>(
-                    &self.core.smtp.queue.source_ip.ipv4,
-                    envelope,
-                    session_id,
-                )
+                .eval_if::<String, _>(&self.core.smtp.queue.source_ip.pool, envelope, session_id)
                 .await
-                .unwrap_or_default();
-            match source_ips.len().cmp(&1) {
-                std::cmp::Ordering::Equal => {
-                    result.source_ipv4 = IpAddr::from(*source_ips.first().unwrap()).into();
-                }
-                std::cmp::Ordering::Greater => {
-                    result.source_ipv4 =
-                        IpAddr::from(source_ips[rand::thread_rng().gen_range(0..source_ips.len())])
-                            .into();
+                .and_then(|id| self.core.smtp.queue.ip_pools.get(&id));
+
+            // Obtain source IPv4 address
+            if let Some(addr) =
+                pool.and_then(|pool| pool.strategy.select(&pool.ipv4, &pool.next_ipv4))
+            {
+                result.source_ipv4 = IpAddr::from(addr).into();
+            } else {
+                let source_ips = self
+                    .core
+                    .eval_if::<Vec<Ipv4Addr>, _>(
+                        &self.core.smtp.queue.source_ip.ipv4,
+                        envelope,
+                        session_id,
+                    )
+                    .await
+                    .unwrap_or_default();
+                match source_ips.len().cmp(&1) {
+                    std::cmp::Ordering::Equal => {
+                        result.source_ipv4 = IpAddr::from(*source_ips.first().unwrap()).into();
+                    }
+                    std::cmp::Ordering::Greater => {
+                        result.source_ipv4 = IpAddr::from(
+                            source_ips[rand::thread_rng().gen_range(0..source_ips.len())],
+                        )
+                        .into();
+                    }
+                    std::cmp::Ordering::Less => (),
                 }
-                std::cmp::Ordering::Less => (),
             }
 
             // Obtain source IPv6 address
-            let source_ips = self
-                .core
-                .eval_if::<Vec<Ipv6Addr>, _>(
-                    &self.core.smtp.queue.source_ip.ipv6,
-                    envelope,
-                    session_id,
-                )
-                .await
-                .unwrap_or_default();
-            match source_ips.len().cmp(&1) {
-                std::cmp::Ordering::Equal => {
-                    result.source_ipv6 = IpAddr::from(*source_ips.first().unwrap()).into();
-                }
-                std::cmp::Ordering::Greater => {
-                    result.source_ipv6 =
-                        IpAddr::from(source_ips[rand::thread_rng().gen_range(0..source_ips.len())])
-                            .into();
+            if let Some(addr) =
+                pool.and_then(|pool| pool.strategy.select(&pool.ipv6, &pool.next_ipv6))
+            {
+                result.source_ipv6 = IpAddr::from(addr).into();
+            } else {
+                let source_ips = self
+                    .core
+                    .eval_if::<Vec<Ipv6Addr>, _>(
+                        &self.core.smtp.queue.source_ip.ipv6,
+                        envelope,
+                        session_id,
+                    )
+                    .await
+                    .unwrap_or_default();
+                match source_ips.len().cmp(&1) {
+                    std::cmp::Ordering::Equal => {
+                        result.source_ipv6 = IpAddr::from(*source_ips.first().unwrap()).into();
+                    }
+                    std::cmp::Ordering::Greater => {
+                        result.source_ipv6 = IpAddr::from(
+                            source_ips[rand::thread_rng().gen_range(0..source_ips.len())],
+                        )
+                        .into();
+                    }
+                    std::cmp::Ordering::Less => (),
                 }
-                std::cmp::Ordering::Less => (),
             }
 
             Ok(result)
@@ -174,6 +201,34 @@ impl SMTP {
     }
 }
 
+pub trait SelectSourceIp {
+    fn select<T: Copy>(&self, addrs: &[(T, u32)], next: &AtomicUsize) -> Option<T>;
+}
+
+impl SelectSourceIp for IpPoolStrategy {
+    // Picks an address from a pool, addresses with a higher weight are
+    // selected proportionally more often.
+    fn select<T: Copy>(&self, addrs: &[(T, u32)], next: &AtomicUsize) -> Option<T> {
+        let total = addrs.iter().map(|(_, weight)| *weight as u64).sum::<u64>();
+        if total == 0 {
+            return None;
+        }
+
+        let mut pos = match self {
+            IpPoolStrategy::RoundRobin => next.fetch_add(1, Ordering::Relaxed) as u64 % total,
+            IpPoolStrategy::Random => rand::thread_rng().gen_range(0..total),
+        };
+        for (addr, weight) in addrs {
+            if pos < *weight as u64 {
+                return Some(*addr);
+            }
+            pos -= *weight as u64;
+        }
+
+        None
+    }
+}
+
 pub trait ToNextHop {
     fn to_remote_hosts<'x, 'y: 'x>(
         &'x self,
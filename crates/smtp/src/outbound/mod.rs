@@ -286,6 +286,14 @@ impl<'x> NextHop<'x> {
         }
     }
 
+    #[inline(always)]
+    fn is_tls_required(&self) -> bool {
+        match self {
+            NextHop::MX(_) => false,
+            NextHop::Relay(host) => host.tls_required,
+        }
+    }
+
     #[inline(always)]
     fn is_smtp(&self) -> bool {
         match self {
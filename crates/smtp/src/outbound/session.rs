@@ -7,9 +7,10 @@
 use common::config::smtp::queue::RequireOptional;
 use mail_send::Credentials;
 use smtp_proto::{
-    EhloResponse, Severity, EXT_CHUNKING, EXT_DSN, EXT_REQUIRE_TLS, EXT_SIZE, EXT_SMTP_UTF8,
-    MAIL_REQUIRETLS, MAIL_RET_FULL, MAIL_RET_HDRS, MAIL_SMTPUTF8, RCPT_NOTIFY_DELAY,
-    RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_NEVER, RCPT_NOTIFY_SUCCESS,
+    EhloResponse, Severity, EXT_CHUNKING, EXT_DELIVER_BY, EXT_DSN, EXT_MT_PRIORITY,
+    EXT_REQUIRE_TLS, EXT_SIZE, EXT_SMTP_UTF8, MAIL_BY_RETURN, MAIL_BY_TRACE, MAIL_REQUIRETLS,
+    MAIL_RET_FULL, MAIL_RET_HDRS, MAIL_SMTPUTF8, RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE,
+    RCPT_NOTIFY_NEVER, RCPT_NOTIFY_SUCCESS,
 };
 use std::time::Duration;
 use std::{fmt::Write, time::Instant};
@@ -36,6 +37,7 @@ pub struct SessionParams<'x> {
     pub timeout_mail: Duration,
     pub timeout_rcpt: Duration,
     pub timeout_data: Duration,
+    pub deliver_by: Option<i64>,
     pub session_id: u64,
 }
 
@@ -73,6 +75,21 @@ impl Message {
             }
         };
 
+        // The next hop must support REQUIRETLS (RFC 8689)
+        if self.has_flag(MAIL_REQUIRETLS) && !capabilities.has_capability(EXT_REQUIRE_TLS) {
+            trc::event!(
+                Delivery(DeliveryEvent::RequireTlsUnavailable),
+                SpanId = params.session_id,
+                Hostname = params.hostname.to_string(),
+            );
+
+            smtp_client.quit().await;
+            return Status::PermanentFailure(Error::RequireTlsError(ErrorDetails {
+                entity: params.hostname.to_string(),
+                details: "REQUIRETLS was not advertised by host".to_string(),
+            }));
+        }
+
         // Authenticate
         if let Some(credentials) = params.credentials {
             let time = Instant::now();
@@ -117,7 +134,7 @@ impl Message {
         // MAIL FROM
         let time = Instant::now();
         smtp_client.timeout = params.timeout_mail;
-        let cmd = self.build_mail_from(&capabilities);
+        let cmd = self.build_mail_from(&capabilities, params.deliver_by);
         match smtp_client.cmd(cmd.as_bytes()).await.and_then(|r| {
             if r.is_positive_completion() {
                 Ok(r)
@@ -389,7 +406,11 @@ impl Message {
         }
     }
 
-    fn build_mail_from(&self, capabilities: &EhloResponse<String>) -> String {
+    fn build_mail_from(
+        &self,
+        capabilities: &EhloResponse<String>,
+        deliver_by: Option<i64>,
+    ) -> String {
         let mut mail_from = String::with_capacity(self.return_path.len() + 60);
         let _ = write!(mail_from, "MAIL FROM:<{}>", self.return_path);
         if capabilities.has_capability(EXT_SIZE) {
@@ -411,6 +432,25 @@ impl Message {
                 let _ = write!(mail_from, " ENVID={env_id}");
             }
         }
+        if let Some(by) = deliver_by.filter(|_| capabilities.has_capability(EXT_DELIVER_BY)) {
+            let _ = write!(
+                mail_from,
+                " BY={by};{}{}",
+                if self.has_flag(MAIL_BY_RETURN) {
+                    "R"
+                } else {
+                    "N"
+                },
+                if self.has_flag(MAIL_BY_TRACE) {
+                    "T"
+                } else {
+                    ""
+                }
+            );
+        }
+        if self.priority != 0 && capabilities.has_capability(EXT_MT_PRIORITY) {
+            let _ = write!(mail_from, " MT-PRIORITY={}", self.priority);
+        }
 
         mail_from.push_str("\r\n");
         mail_from
@@ -21,7 +21,7 @@ use crate::outbound::client::from_error_status;
 
 use super::{
     Domain, Error, ErrorDetails, HostResponse, Message, MessageSource, QueueEnvelope, Recipient,
-    Status, RCPT_DSN_SENT, RCPT_STATUS_CHANGED,
+    Status, RCPT_DSN_SENT, RCPT_EVENT_SENT, RCPT_STATUS_CHANGED,
 };
 
 impl SMTP {
@@ -152,10 +152,122 @@ impl SMTP {
 }
 
 impl Message {
+    pub fn log_delivery_events(&mut self, attempted: bool) {
+        let now = now();
+
+        for rcpt in &mut self.recipients {
+            if rcpt.has_flag(RCPT_EVENT_SENT) {
+                continue;
+            }
+
+            let domain = &self.domains[rcpt.domain_idx];
+            let is_expired = domain.expires <= now
+                || matches!(
+                    &domain.status,
+                    Status::PermanentFailure(Error::DeliveryTimeExpired)
+                );
+            match &rcpt.status {
+                Status::Completed(response) => {
+                    trc::event!(
+                        Queue(trc::QueueEvent::MessageDelivered),
+                        SpanId = self.span_id,
+                        QueueId = self.queue_id,
+                        From = self.return_path_lcase.clone(),
+                        To = rcpt.address_lcase.clone(),
+                        Hostname = response.hostname.clone(),
+                        Code = response.response.code,
+                        Details = response.response.message.to_string(),
+                    );
+                }
+                Status::PermanentFailure(response) => {
+                    trc::event!(
+                        Queue(if is_expired {
+                            trc::QueueEvent::MessageExpired
+                        } else {
+                            trc::QueueEvent::MessageBounced
+                        }),
+                        SpanId = self.span_id,
+                        QueueId = self.queue_id,
+                        From = self.return_path_lcase.clone(),
+                        To = rcpt.address_lcase.clone(),
+                        Hostname = response.hostname.entity.clone(),
+                        Code = response.response.code,
+                        Details = response.response.message.to_string(),
+                        Total = domain.retry.inner,
+                    );
+                }
+                Status::Scheduled if matches!(&domain.status, Status::PermanentFailure(_)) => {
+                    trc::event!(
+                        Queue(if is_expired {
+                            trc::QueueEvent::MessageExpired
+                        } else {
+                            trc::QueueEvent::MessageBounced
+                        }),
+                        SpanId = self.span_id,
+                        QueueId = self.queue_id,
+                        From = self.return_path_lcase.clone(),
+                        To = rcpt.address_lcase.clone(),
+                        Details = from_error_status(&domain.status),
+                        Total = domain.retry.inner,
+                    );
+                }
+                Status::TemporaryFailure(response) if attempted => {
+                    trc::event!(
+                        Queue(trc::QueueEvent::MessageDeferred),
+                        SpanId = self.span_id,
+                        QueueId = self.queue_id,
+                        From = self.return_path_lcase.clone(),
+                        To = rcpt.address_lcase.clone(),
+                        Hostname = response.hostname.entity.clone(),
+                        Code = response.response.code,
+                        Details = response.response.message.to_string(),
+                        NextRetry = trc::Value::Timestamp(domain.retry.due),
+                        Expires = trc::Value::Timestamp(domain.expires),
+                        Total = domain.retry.inner,
+                    );
+                    continue;
+                }
+                Status::Scheduled
+                    if attempted && matches!(&domain.status, Status::TemporaryFailure(_)) =>
+                {
+                    trc::event!(
+                        Queue(trc::QueueEvent::MessageDeferred),
+                        SpanId = self.span_id,
+                        QueueId = self.queue_id,
+                        From = self.return_path_lcase.clone(),
+                        To = rcpt.address_lcase.clone(),
+                        Details = from_error_status(&domain.status),
+                        NextRetry = trc::Value::Timestamp(domain.retry.due),
+                        Expires = trc::Value::Timestamp(domain.expires),
+                        Total = domain.retry.inner,
+                    );
+                    continue;
+                }
+                _ => continue,
+            }
+
+            // Final states are only reported once
+            rcpt.flags |= RCPT_EVENT_SENT | RCPT_STATUS_CHANGED;
+        }
+    }
+
     pub async fn build_dsn(&mut self, core: &SMTP) -> Option<Vec<u8>> {
         let config = &core.core.smtp.queue;
         let now = now();
 
+        // Obtain the template for the sender's domain and language
+        let template_set = core
+            .core
+            .eval_if::<String, _>(&config.dsn.template_set, self, self.span_id)
+            .await
+            .unwrap_or_default();
+        let language = core
+            .core
+            .eval_if::<String, _>(&config.dsn.language, self, self.span_id)
+            .await
+            .unwrap_or_default();
+        let template = config.dsn.template(&template_set, &language);
+
         let mut txt_success = String::new();
         let mut txt_delay = String::new();
         let mut txt_failed = String::new();
@@ -174,7 +286,15 @@ impl Message {
                     }
                     rcpt.write_dsn(&mut dsn);
                     rcpt.status.write_dsn(&mut dsn);
-                    response.write_dsn_text(&rcpt.address, &mut txt_success);
+                    let mut reason = String::new();
+                    response.write_dsn_reason(&mut reason);
+                    write_dsn_recipient(
+                        &template.recipient_success,
+                        &rcpt.address,
+                        &reason,
+                        None,
+                        &mut txt_success,
+                    );
                 }
                 Status::TemporaryFailure(response)
                     if domain.notify.due <= now && rcpt.has_flag(RCPT_NOTIFY_DELAY) =>
@@ -182,7 +302,15 @@ impl Message {
                     rcpt.write_dsn(&mut dsn);
                     rcpt.status.write_dsn(&mut dsn);
                     domain.write_dsn_will_retry_until(&mut dsn);
-                    response.write_dsn_text(&rcpt.address, &mut txt_delay);
+                    let mut reason = String::new();
+                    response.write_dsn_reason(&mut reason);
+                    write_dsn_recipient(
+                        &template.recipient_delay,
+                        &rcpt.address,
+                        &reason,
+                        Some(domain),
+                        &mut txt_delay,
+                    );
                 }
                 Status::PermanentFailure(response) => {
                     rcpt.flags |= RCPT_DSN_SENT | RCPT_STATUS_CHANGED;
@@ -191,7 +319,15 @@ impl Message {
                     }
                     rcpt.write_dsn(&mut dsn);
                     rcpt.status.write_dsn(&mut dsn);
-                    response.write_dsn_text(&rcpt.address, &mut txt_failed);
+                    let mut reason = String::new();
+                    response.write_dsn_reason(&mut reason);
+                    write_dsn_recipient(
+                        &template.recipient_failure,
+                        &rcpt.address,
+                        &reason,
+                        None,
+                        &mut txt_failed,
+                    );
                 }
                 Status::Scheduled => {
                     // There is no status for this address, use the domain's status.
@@ -202,27 +338,47 @@ impl Message {
                                 continue;
                             }
                             rcpt.write_dsn(&mut dsn);
-                            domain.status.write_dsn(&mut dsn);
-                            err.write_dsn_text(&rcpt.address, &domain.domain, &mut txt_failed);
+                            domain.status.write_dsn(&domain.domain, &mut dsn);
+                            let mut reason = String::new();
+                            err.write_dsn_reason(&domain.domain, &mut reason);
+                            write_dsn_recipient(
+                                &template.recipient_failure,
+                                &rcpt.address,
+                                &reason,
+                                None,
+                                &mut txt_failed,
+                            );
                         }
                         Status::TemporaryFailure(err)
                             if domain.notify.due <= now && rcpt.has_flag(RCPT_NOTIFY_DELAY) =>
                         {
                             rcpt.write_dsn(&mut dsn);
-                            domain.status.write_dsn(&mut dsn);
+                            domain.status.write_dsn(&domain.domain, &mut dsn);
                             domain.write_dsn_will_retry_until(&mut dsn);
-                            err.write_dsn_text(&rcpt.address, &domain.domain, &mut txt_delay);
+                            let mut reason = String::new();
+                            err.write_dsn_reason(&domain.domain, &mut reason);
+                            write_dsn_recipient(
+                                &template.recipient_delay,
+                                &rcpt.address,
+                                &reason,
+                                Some(domain),
+                                &mut txt_delay,
+                            );
                         }
                         Status::Scheduled
                             if domain.notify.due <= now && rcpt.has_flag(RCPT_NOTIFY_DELAY) =>
                         {
                             // This case should not happen under normal circumstances
                             rcpt.write_dsn(&mut dsn);
-                            domain.status.write_dsn(&mut dsn);
+                            domain.status.write_dsn(&domain.domain, &mut dsn);
                             domain.write_dsn_will_retry_until(&mut dsn);
-                            Error::ConcurrencyLimited.write_dsn_text(
+                            let mut reason = String::new();
+                            Error::ConcurrencyLimited.write_dsn_reason(&domain.domain, &mut reason);
+                            write_dsn_recipient(
+                                &template.recipient_delay,
                                 &rcpt.address,
-                                &domain.domain,
+                                &reason,
+                                Some(domain),
                                 &mut txt_delay,
                             );
                         }
@@ -250,57 +406,43 @@ impl Message {
         let has_failure = !txt_failed.is_empty();
 
         let mut txt = String::with_capacity(txt_len + 128);
-        let (subject, is_mixed) = if has_success && !has_delay && !has_failure {
-            txt.push_str(
-                "Your message has been successfully delivered to the following recipients:\r\n\r\n",
-            );
-            ("Successfully delivered message", false)
+        let (subject, body, is_mixed) = if has_success && !has_delay && !has_failure {
+            (&template.subject_success, &template.body_success, false)
         } else if has_delay && !has_success && !has_failure {
-            txt.push_str("There was a temporary problem delivering your message to the following recipients:\r\n\r\n");
-            ("Warning: Delay in message delivery", false)
+            (&template.subject_delay, &template.body_delay, false)
         } else if has_failure && !has_success && !has_delay {
-            txt.push_str(
-                "Your message could not be delivered to the following recipients:\r\n\r\n",
-            );
-            ("Failed to deliver message", false)
+            (&template.subject_failure, &template.body_failure, false)
         } else if has_success {
-            txt.push_str("Your message has been partially delivered:\r\n\r\n");
-            ("Partially delivered message", true)
+            (&template.subject_partial, &template.body_partial, true)
         } else {
-            txt.push_str("Your message could not be delivered to some recipients:\r\n\r\n");
-            (
-                "Warning: Temporary and permanent failures during message delivery",
-                true,
-            )
+            (&template.subject_mixed, &template.body_mixed, true)
         };
-
-        if has_success {
-            if is_mixed {
-                txt.push_str(
-                    "    ----- Delivery to the following addresses was successful -----\r\n",
-                );
-            }
-
-            txt.push_str(&txt_success);
-            txt.push_str("\r\n");
-        }
-
-        if has_delay {
-            if is_mixed {
-                txt.push_str(
-                    "    ----- There was a temporary problem delivering to these addresses -----\r\n",
-                );
+        txt.push_str(body);
+        txt.push_str("\r\n\r\n");
+
+        for (has_section, section, section_txt) in [
+            (has_success, &template.section_success, &txt_success),
+            (has_delay, &template.section_delay, &txt_delay),
+            (has_failure, &template.section_failure, &txt_failed),
+        ] {
+            if has_section {
+                if is_mixed {
+                    let _ = write!(txt, "    ----- {section} -----\r\n");
+                }
+                txt.push_str(section_txt);
+                txt.push_str("\r\n");
             }
-            txt.push_str(&txt_delay);
-            txt.push_str("\r\n");
         }
 
-        if has_failure {
-            if is_mixed {
-                txt.push_str("    ----- Delivery to the following addresses failed -----\r\n");
-            }
-            txt.push_str(&txt_failed);
-            txt.push_str("\r\n");
+        // Add support contact
+        if let Some(contact) = core
+            .core
+            .eval_if::<String, _>(&config.dsn.support, self, self.span_id)
+            .await
+            .filter(|contact| !contact.is_empty())
+        {
+            txt.push_str(&template.support.replace("{contact}", &contact));
+            txt.push_str("\r\n\r\n");
         }
 
         // Update next delay notification time
@@ -361,9 +503,7 @@ impl Message {
         // Fetch up to 1024 bytes of message headers
         let headers = match core
             .core
-            .storage
-            .blob
-            .get_blob(self.blob_hash.as_slice(), 0..1024)
+            .get_queued_message(self.blob_hash.as_slice(), 0..1024)
             .await
         {
             Ok(Some(mut buf)) => {
@@ -417,7 +557,7 @@ impl Message {
             .header("To", HeaderType::Text(self.return_path.as_str().into()))
             .header("Auto-Submitted", HeaderType::Text("auto-generated".into()))
             .message_id(format!("<{}@{}>", make_boundary("."), reporting_mta))
-            .subject(subject)
+            .subject(subject.as_str())
             .body(MimePart::new(
                 ContentType::new("multipart/report").attribute("report-type", "delivery-status"),
                 BodyPart::Multipart(vec![
@@ -480,12 +620,54 @@ impl Message {
     }
 }
 
+// Writes a recipient line of the DSN text part, replacing the {recipient}, {reason},
+// {next_retry} and {retry_until} variables of the template.
+fn write_dsn_recipient(
+    template: &str,
+    addr: &str,
+    reason: &str,
+    domain: Option<&Domain>,
+    txt: &mut String,
+) {
+    let (next_retry, retry_until) = domain
+        .map(|domain| {
+            (
+                DateTime::from_timestamp(domain.retry.due as i64).to_rfc822(),
+                DateTime::from_timestamp(domain.expires as i64).to_rfc822(),
+            )
+        })
+        .unwrap_or_default();
+    let variables = [
+        ("recipient", addr),
+        ("reason", reason),
+        ("next_retry", next_retry.as_str()),
+        ("retry_until", retry_until.as_str()),
+    ];
+
+    let mut template = template;
+    while let Some(pos) = template.find('{') {
+        txt.push_str(&template[..pos]);
+        template = &template[pos + 1..];
+        if let Some((name, value)) = variables.iter().find(|(name, _)| {
+            template
+                .strip_prefix(name)
+                .is_some_and(|rest| rest.starts_with('}'))
+        }) {
+            txt.push_str(value);
+            template = &template[name.len() + 1..];
+        } else {
+            txt.push('{');
+        }
+    }
+    txt.push_str(template);
+    txt.push_str("\r\n");
+}
+
 impl HostResponse<String> {
-    fn write_dsn_text(&self, addr: &str, dsn: &mut String) {
+    fn write_dsn_reason(&self, dsn: &mut String) {
         let _ = write!(
             dsn,
-            "<{}> (delivered to '{}' with code {} ({}.{}.{}) '",
-            addr,
+            "delivered to '{}' with code {} ({}.{}.{}) '",
             self.hostname,
             self.response.code,
             self.response.esc[0],
@@ -493,13 +675,19 @@ impl HostResponse<String> {
             self.response.esc[2]
         );
         self.response.write_response(dsn);
-        dsn.push_str("')\r\n");
+        dsn.push('\'');
     }
 }
 
 impl HostResponse<ErrorDetails> {
     fn write_dsn_text(&self, addr: &str, dsn: &mut String) {
-        let _ = write!(dsn, "<{}> (host '{}' rejected ", addr, self.hostname.entity);
+        let _ = write!(dsn, "<{addr}> (");
+        self.write_dsn_reason(dsn);
+        dsn.push_str(")\r\n");
+    }
+
+    fn write_dsn_reason(&self, dsn: &mut String) {
+        let _ = write!(dsn, "host '{}' rejected ", self.hostname.entity);
 
         if !self.hostname.details.is_empty() {
             let _ = write!(dsn, "command '{}'", self.hostname.details,);
@@ -513,58 +701,75 @@ impl HostResponse<ErrorDetails> {
             self.response.code, self.response.esc[0], self.response.esc[1], self.response.esc[2]
         );
         self.response.write_response(dsn);
-        dsn.push_str("')\r\n");
+        dsn.push('\'');
     }
 }
 
 impl Error {
     fn write_dsn_text(&self, addr: &str, domain: &str, dsn: &mut String) {
-        match self {
+        let _ = write!(dsn, "<{addr}> (");
+        self.write_dsn_reason(domain, dsn);
+        dsn.push_str(")\r\n");
+    }
+
+    fn write_dsn_reason(&self, domain: &str, dsn: &mut String) {
+        if let Error::UnexpectedResponse(response) = self {
+            response.write_dsn_reason(dsn);
+        } else {
+            self.write_dsn_details(domain, dsn);
+        }
+    }
+
+    fn write_dsn_details(&self, domain: &str, dsn: &mut String) {
+        let _ = match self {
             Error::UnexpectedResponse(response) => {
-                response.write_dsn_text(addr, dsn);
-            }
-            Error::DnsError(err) => {
-                let _ = write!(dsn, "<{addr}> (failed to lookup '{domain}': {err})\r\n",);
-            }
-            Error::ConnectionError(details) => {
-                let _ = write!(
-                    dsn,
-                    "<{}> (connection to '{}' failed: {})\r\n",
-                    addr, details.entity, details.details
-                );
-            }
-            Error::TlsError(details) => {
-                let _ = write!(
-                    dsn,
-                    "<{}> (TLS error from '{}': {})\r\n",
-                    addr, details.entity, details.details
-                );
-            }
-            Error::DaneError(details) => {
-                let _ = write!(
-                    dsn,
-                    "<{}> (DANE failed to authenticate '{}': {})\r\n",
-                    addr, details.entity, details.details
-                );
+                response.response.write_response(dsn);
+                Ok(())
             }
+            Error::DnsError(err) => write!(dsn, "failed to lookup '{domain}': {err}"),
+            Error::ConnectionError(details) => write!(
+                dsn,
+                "connection to '{}' failed: {}",
+                details.entity, details.details
+            ),
+            Error::TlsError(details) => write!(
+                dsn,
+                "TLS error from '{}': {}",
+                details.entity, details.details
+            ),
+            Error::DaneError(details) => write!(
+                dsn,
+                "DANE failed to authenticate '{}': {}",
+                details.entity, details.details
+            ),
             Error::MtaStsError(details) => {
-                let _ = write!(
-                    dsn,
-                    "<{addr}> (MTA-STS failed to authenticate '{domain}': {details})\r\n",
-                );
-            }
-            Error::RateLimited => {
-                let _ = write!(dsn, "<{addr}> (rate limited)\r\n");
+                write!(dsn, "MTA-STS failed to authenticate '{domain}': {details}")
             }
+            Error::RateLimited => write!(dsn, "rate limited"),
             Error::ConcurrencyLimited => {
-                let _ = write!(
-                    dsn,
-                    "<{addr}> (too many concurrent connections to remote server)\r\n",
-                );
-            }
-            Error::Io(err) => {
-                let _ = write!(dsn, "<{addr}> (queue error: {err})\r\n");
+                write!(dsn, "too many concurrent connections to remote server")
             }
+            Error::Io(err) => write!(dsn, "queue error: {err}"),
+            Error::DeliveryTimeExpired => write!(dsn, "delivery time expired"),
+            Error::RequireTlsError(details) => write!(
+                dsn,
+                "REQUIRETLS failed for '{}': {}",
+                details.entity, details.details
+            ),
+        };
+    }
+
+    // Enhanced status code subject and detail as defined in RFC 3463
+    fn dsn_status(&self) -> (u8, u8) {
+        match self {
+            Error::UnexpectedResponse(_) => (0, 0),
+            Error::DnsError(_) => (4, 4),
+            Error::ConnectionError(_) => (4, 1),
+            Error::TlsError(_) | Error::DaneError(_) | Error::MtaStsError(_) => (7, 5),
+            Error::RateLimited | Error::ConcurrencyLimited => (4, 5),
+            Error::Io(_) => (3, 0),
+            Error::DeliveryTimeExpired => (4, 7),
+            Error::RequireTlsError(_) => (7, 30),
         }
     }
 }
@@ -680,10 +885,10 @@ impl Status<HostResponse<String>, HostResponse<ErrorDetails>> {
 }
 
 impl Status<(), Error> {
-    fn write_dsn(&self, dsn: &mut String) {
+    fn write_dsn(&self, domain: &str, dsn: &mut String) {
         self.write_dsn_action(dsn);
         self.write_dsn_status(dsn);
-        self.write_dsn_diagnostic(dsn);
+        self.write_dsn_diagnostic(domain, dsn);
         self.write_dsn_remote_mta(dsn);
     }
 
@@ -693,11 +898,16 @@ impl Status<(), Error> {
             if let Error::UnexpectedResponse(response) = err {
                 response.response.write_dsn_status(dsn);
             } else {
-                dsn.push_str(if matches!(self, Status::PermanentFailure(_)) {
-                    "5.0.0"
-                } else {
-                    "4.0.0"
-                });
+                let (subject, detail) = err.dsn_status();
+                let _ = write!(
+                    dsn,
+                    "{}.{subject}.{detail}",
+                    if matches!(self, Status::PermanentFailure(_)) {
+                        5
+                    } else {
+                        4
+                    }
+                );
             }
             dsn.push_str("\r\n");
         }
@@ -721,11 +931,18 @@ impl Status<(), Error> {
         }
     }
 
-    fn write_dsn_diagnostic(&self, dsn: &mut String) {
-        if let Status::PermanentFailure(Error::UnexpectedResponse(response))
-        | Status::TemporaryFailure(Error::UnexpectedResponse(response)) = self
-        {
-            response.response.write_dsn_diagnostic(dsn);
+    fn write_dsn_diagnostic(&self, domain: &str, dsn: &mut String) {
+        match self {
+            Status::PermanentFailure(Error::UnexpectedResponse(response))
+            | Status::TemporaryFailure(Error::UnexpectedResponse(response)) => {
+                response.response.write_dsn_diagnostic(dsn);
+            }
+            Status::PermanentFailure(err) | Status::TemporaryFailure(err) => {
+                dsn.push_str("Diagnostic-Code: X-Local;");
+                err.write_dsn_details(domain, dsn);
+                dsn.push_str("\r\n");
+            }
+            _ => (),
         }
     }
 }
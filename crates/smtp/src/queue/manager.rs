@@ -4,17 +4,22 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{sync::atomic::Ordering, time::Duration};
+use std::{cmp::Reverse, sync::atomic::Ordering, time::Duration};
 
 use store::write::now;
 use tokio::sync::mpsc;
+use utils::clock;
 
 use crate::core::{SmtpInstance, SMTP};
 
-use super::{spool::QueueEventLock, DeliveryAttempt, Event, Message, OnHold, Status};
+use super::{
+    spool::QueueEventLock, DeliveryAttempt, Event, Message, OnHold, Status, HELD_EVENT_DUE,
+    MAIL_HELD,
+};
 
 pub(crate) const SHORT_WAIT: Duration = Duration::from_millis(1);
 pub(crate) const LONG_WAIT: Duration = Duration::from_secs(86400 * 365);
+pub(crate) const STANDBY_WAIT: Duration = Duration::from_secs(60);
 
 pub struct Queue {
     pub core: SmtpInstance,
@@ -28,12 +33,15 @@ impl SpawnQueue for mpsc::Receiver<Event> {
             let mut queue = Queue::new(core);
 
             loop {
-                let on_hold = match tokio::time::timeout(queue.next_wake_up, self.recv()).await {
-                    Ok(Some(Event::OnHold(on_hold))) => on_hold.into(),
-                    Ok(Some(Event::Stop)) | Ok(None) => {
-                        break;
-                    }
-                    _ => None,
+                let on_hold = tokio::select! {
+                    event = self.recv() => match event {
+                        Some(Event::OnHold(on_hold)) => on_hold.into(),
+                        Some(Event::Stop) | None => {
+                            break;
+                        }
+                        _ => None,
+                    },
+                    _ = clock::sleep(queue.next_wake_up) => None,
                 };
 
                 queue.process_events().await;
@@ -57,47 +65,66 @@ impl Queue {
     }
 
     pub async fn process_events(&mut self) {
-        // Deliver any concurrency limited messages
+        // Standby replicas hold a copy of the primary's queue but do not deliver it
         let core = SMTP::from(self.core.clone());
+        if core.core.storage.replication.is_standby() {
+            self.next_wake_up = STANDBY_WAIT;
+            return;
+        }
+
+        // Deliver any concurrency limited messages
         while let Some(queue_event) = self.next_on_hold() {
             DeliveryAttempt::new(queue_event)
                 .try_deliver(core.clone())
                 .await;
         }
 
-        // Deliver scheduled messages
+        // Deliver scheduled messages, higher priority classes first
         let now = now();
         self.next_wake_up = LONG_WAIT;
+        let mut due_events = Vec::new();
         for queue_event in core.next_event().await {
             if queue_event.due <= now {
-                DeliveryAttempt::new(queue_event)
-                    .try_deliver(core.clone())
-                    .await;
+                due_events.push(queue_event);
             } else {
                 self.next_wake_up = Duration::from_secs(queue_event.due - now);
             }
         }
+        let priority = &core.core.smtp.queue.priority;
+        due_events.sort_by_key(|event| Reverse(priority.class(event.priority)));
+        for queue_event in due_events {
+            DeliveryAttempt::new(queue_event)
+                .try_deliver(core.clone())
+                .await;
+        }
     }
 
     pub fn on_hold(&mut self, message: OnHold<QueueEventLock>) {
         self.on_hold.push(OnHold {
             next_due: message.next_due,
             limiters: message.limiters,
+            priority: message.priority,
             message: message.message,
         });
     }
 
     pub fn next_on_hold(&mut self) -> Option<QueueEventLock> {
         let now = now();
-        self.on_hold
-            .iter()
-            .position(|o| {
-                o.limiters
+
+        // Pick the highest priority message that can be delivered, oldest first on ties
+        let mut next: Option<(usize, i16)> = None;
+        for (pos, o) in self.on_hold.iter().enumerate() {
+            if next.map_or(true, |(_, priority)| o.priority > priority)
+                && (o
+                    .limiters
                     .iter()
                     .any(|l| l.concurrent.load(Ordering::Relaxed) < l.max_concurrent)
-                    || o.next_due.map_or(false, |due| due <= now)
-            })
-            .map(|pos| self.on_hold.remove(pos).message)
+                    || o.next_due.map_or(false, |due| due <= now))
+            {
+                next = Some((pos, o.priority));
+            }
+        }
+        next.map(|(pos, _)| self.on_hold.remove(pos).message)
     }
 }
 
@@ -125,12 +152,20 @@ impl Message {
         }
 
         if has_events {
-            next_event.into()
+            if !self.is_held() {
+                next_event.into()
+            } else {
+                HELD_EVENT_DUE.into()
+            }
         } else {
             None
         }
     }
 
+    pub fn is_held(&self) -> bool {
+        (self.flags & MAIL_HELD) != 0
+    }
+
     pub fn next_delivery_event(&self) -> u64 {
         let mut next_delivery = now();
 
@@ -7,12 +7,13 @@
 use std::{
     fmt::Display,
     net::{IpAddr, Ipv4Addr},
-    time::{Duration, Instant, SystemTime},
+    time::{Duration, Instant},
 };
 
 use common::{
     expr::{self, functions::ResolveVariable, *},
     listener::limiter::{ConcurrencyLimiter, InFlight},
+    scripts::verdict::{FilterVerdict, VirusVerdict},
 };
 use serde::{Deserialize, Serialize};
 use smtp_proto::Response;
@@ -40,6 +41,7 @@ pub enum Event {
 pub struct OnHold<T> {
     pub next_due: Option<u64>,
     pub limiters: Vec<ConcurrencyLimiter>,
+    pub priority: i16,
     pub message: T,
 }
 
@@ -108,6 +110,28 @@ pub struct Recipient {
 
 pub const RCPT_DSN_SENT: u64 = 1 << 32;
 pub const RCPT_STATUS_CHANGED: u64 = 2 << 32;
+pub const RCPT_EVENT_SENT: u64 = 4 << 32;
+
+// Set on messages with a "TLS-Required: No" header field (RFC 8689)
+pub const MAIL_TLS_OPTIONAL: u64 = 1 << 32;
+
+// Set on messages put on hold by an administrator, their queue event is parked
+// at HELD_EVENT_DUE until the message is released
+pub const MAIL_HELD: u64 = 2 << 32;
+pub const HELD_EVENT_DUE: u64 = u64::MAX - 1;
+
+// Message class obtained from the header fields at queue time, available to
+// the queue schedule expressions as the "message_class" variable
+pub const MAIL_CLASS_LIST: u64 = 4 << 32;
+pub const MAIL_CLASS_BULK: u64 = 8 << 32;
+pub const MAIL_CLASS_AUTO: u64 = 16 << 32;
+
+// Spam filter and antivirus verdicts passed on to local delivery. The virus
+// verdict is stored in bits 44-46 and the spam score, in hundredths, in bits 48-63.
+pub const MAIL_SPAM: u64 = 1 << 40;
+pub const MAIL_SPAM_SCORE: u64 = 2 << 40;
+const MAIL_VIRUS_SHIFT: u64 = 44;
+const MAIL_SPAM_SCORE_SHIFT: u64 = 48;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Status<T, E> {
@@ -138,6 +162,8 @@ pub enum Error {
     RateLimited,
     ConcurrencyLimited,
     Io(String),
+    DeliveryTimeExpired,
+    RequireTlsError(ErrorDetails),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -284,12 +310,15 @@ impl<'x> ResolveVariable for QueueEnvelope<'x> {
                         Error::RateLimited => "rate",
                         Error::ConcurrencyLimited => "concurrency",
                         Error::Io(_) => "io",
+                        Error::DeliveryTimeExpired => "expired",
+                        Error::RequireTlsError(_) => "requiretls",
                     },
                 })
                 .unwrap_or_default()
                 .into(),
             V_MX => self.mx.into(),
             V_PRIORITY => self.message.priority.into(),
+            V_MESSAGE_CLASS => self.message.class().into(),
             V_REMOTE_IP => self.remote_ip.to_string().into(),
             V_LOCAL_IP => self.local_ip.to_string().into(),
             _ => "".into(),
@@ -309,11 +338,79 @@ impl ResolveVariable for Message {
                 .collect::<Vec<_>>()
                 .into(),
             V_PRIORITY => self.priority.into(),
+            V_MESSAGE_CLASS => self.class().into(),
             _ => "".into(),
         }
     }
 }
 
+impl Message {
+    pub fn set_return_path(&mut self, return_path: String) {
+        self.return_path_lcase = return_path.to_lowercase();
+        self.return_path_domain = self.return_path_lcase.domain_part().to_string();
+        self.return_path = return_path;
+    }
+
+    pub fn class(&self) -> &'static str {
+        if (self.flags & MAIL_CLASS_LIST) != 0 {
+            "list"
+        } else if (self.flags & MAIL_CLASS_BULK) != 0 {
+            "bulk"
+        } else if (self.flags & MAIL_CLASS_AUTO) != 0 {
+            "auto"
+        } else if self.return_path.is_empty() {
+            "bounce"
+        } else {
+            "normal"
+        }
+    }
+}
+
+// Encodes the spam filter and antivirus verdicts as message flags
+pub fn verdict_flags(verdict: &FilterVerdict) -> u64 {
+    let mut flags = (verdict.virus as u64) << MAIL_VIRUS_SHIFT;
+    if verdict.is_spam {
+        flags |= MAIL_SPAM;
+    }
+    if let Some(score) = verdict.spam_score {
+        let score = (score * 100.0)
+            .round()
+            .clamp(i16::MIN as f64, i16::MAX as f64) as i16;
+        flags |= MAIL_SPAM_SCORE | ((score as u16 as u64) << MAIL_SPAM_SCORE_SHIFT);
+    }
+    flags
+}
+
+pub fn flags_verdict(flags: u64) -> FilterVerdict {
+    FilterVerdict {
+        spam_score: ((flags & MAIL_SPAM_SCORE) != 0)
+            .then(|| ((flags >> MAIL_SPAM_SCORE_SHIFT) as u16 as i16) as f64 / 100.0),
+        is_spam: (flags & MAIL_SPAM) != 0,
+        virus: VirusVerdict::from_id(((flags >> MAIL_VIRUS_SHIFT) & 0x07) as u8),
+    }
+}
+
+// Classifies a message as mailing list, bulk or automatically generated traffic
+pub fn message_class(headers: &[(&[u8], &[u8])]) -> u64 {
+    let mut class = 0;
+    for (name, value) in headers {
+        let value = std::str::from_utf8(value).unwrap_or_default().trim();
+        if name.eq_ignore_ascii_case(b"List-Id") || name.eq_ignore_ascii_case(b"List-Unsubscribe") {
+            class |= MAIL_CLASS_LIST;
+        } else if name.eq_ignore_ascii_case(b"Precedence") {
+            if value.eq_ignore_ascii_case("list") {
+                class |= MAIL_CLASS_LIST;
+            } else if value.eq_ignore_ascii_case("bulk") || value.eq_ignore_ascii_case("junk") {
+                class |= MAIL_CLASS_BULK;
+            }
+        } else if name.eq_ignore_ascii_case(b"Auto-Submitted") && !value.eq_ignore_ascii_case("no")
+        {
+            class |= MAIL_CLASS_AUTO;
+        }
+    }
+    class
+}
+
 pub struct RecipientDomain<'x>(&'x str);
 
 impl<'x> RecipientDomain<'x> {
@@ -333,10 +430,7 @@ impl<'x> ResolveVariable for RecipientDomain<'x> {
 
 #[inline(always)]
 pub fn instant_to_timestamp(now: Instant, time: Instant) -> u64 {
-    SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .map_or(0, |d| d.as_secs())
-        + time.checked_duration_since(now).map_or(0, |d| d.as_secs())
+    store::write::now() + time.checked_duration_since(now).map_or(0, |d| d.as_secs())
 }
 
 pub trait InstantFromTimestamp {
@@ -346,9 +440,7 @@ pub trait InstantFromTimestamp {
 impl InstantFromTimestamp for u64 {
     fn to_instant(&self) -> Instant {
         let timestamp = *self;
-        let current_timestamp = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map_or(0, |d| d.as_secs());
+        let current_timestamp = now();
         if timestamp > current_timestamp {
             Instant::now() + Duration::from_secs(timestamp - current_timestamp)
         } else {
@@ -421,6 +513,16 @@ impl Display for Error {
             Error::Io(err) => {
                 write!(f, "Queue error: {err}")
             }
+            Error::DeliveryTimeExpired => {
+                write!(f, "Delivery time expired")
+            }
+            Error::RequireTlsError(details) => {
+                write!(
+                    f,
+                    "REQUIRETLS failed for '{}': {}",
+                    details.entity, details.details
+                )
+            }
         }
     }
 }
@@ -115,7 +115,7 @@ impl SMTP {
                 .await
                 .unwrap_or(false)
         {
-            let key = quota.new_key(envelope);
+            let key = quota.new_key(envelope, &self.core.smtp.canonical);
             if let Some(max_size) = quota.size {
                 let used_size = self
                     .core
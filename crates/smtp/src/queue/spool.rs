@@ -6,7 +6,7 @@
 
 use crate::queue::DomainPart;
 use std::borrow::Cow;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 use store::write::key::DeserializeBigEndian;
 use store::write::{now, BatchBuilder, Bincode, BlobOp, QueueClass, QueueEvent, ValueClass};
 use store::{Deserialize, IterateParams, Serialize, ValueKey, U64_LEN};
@@ -22,11 +22,16 @@ use super::{
 
 pub const LOCK_EXPIRY: u64 = 300;
 
+// Unlocked events store the message priority offset by this value in place of
+// the lock expiration, zero is kept for normal priority messages.
+const PRIORITY_OFFSET: i64 = 1000;
+
 #[derive(Debug)]
 pub struct QueueEventLock {
     pub due: u64,
     pub queue_id: u64,
     pub lock_expiry: u64,
+    pub priority: i16,
 }
 
 impl SMTP {
@@ -37,9 +42,7 @@ impl SMTP {
         return_path_domain: impl Into<String>,
         span_id: u64,
     ) -> Message {
-        let created = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map_or(0, |d| d.as_secs());
+        let created = now();
         Message {
             queue_id: self.inner.queue_id_gen.generate().unwrap_or(created),
             span_id,
@@ -77,10 +80,12 @@ impl SMTP {
             .iterate(
                 IterateParams::new(from_key, to_key).ascending(),
                 |key, value| {
+                    let lock_expiry = u64::deserialize(value)?;
                     let event = QueueEventLock {
                         due: key.deserialize_be_u64(0)?,
                         queue_id: key.deserialize_be_u64(U64_LEN)?,
-                        lock_expiry: u64::deserialize(value)?,
+                        lock_expiry,
+                        priority: event_priority(lock_expiry),
                     };
                     let do_continue = event.due <= now;
                     if event.lock_expiry < now {
@@ -186,13 +191,31 @@ impl Message {
         } else {
             raw_message.into()
         };
-        self.blob_hash = BlobHash::from(message.as_ref());
 
         // Generate id
         if self.size == 0 {
             self.size = message.len();
         }
 
+        // Encrypt message at rest
+        let encryption = &core.core.smtp.queue.encryption;
+        let message = if encryption.enable {
+            match encryption.encrypt(message.as_ref()) {
+                Ok(message) => Cow::Owned(message),
+                Err(err) => {
+                    trc::error!(err
+                        .details("Failed to encrypt message.")
+                        .span_id(session_id)
+                        .caused_by(trc::location!()));
+
+                    return false;
+                }
+            }
+        } else {
+            message
+        };
+        self.blob_hash = BlobHash::from(message.as_ref());
+
         // Reserve and write blob
         let mut batch = BatchBuilder::new();
         let reserve_until = now() + 120;
@@ -275,7 +298,7 @@ impl Message {
                     due: self.next_event().unwrap_or_default(),
                     queue_id: self.queue_id,
                 })),
-                0u64.serialize(),
+                event_value(self.priority).serialize(),
             )
             .clear(BlobOp::Reserve {
                 hash: self.blob_hash.clone(),
@@ -404,7 +427,7 @@ impl Message {
                         due: next_event,
                         queue_id: self.queue_id,
                     })),
-                    0u64.serialize(),
+                    event_value(self.priority).serialize(),
                 );
         }
 
@@ -473,3 +496,19 @@ impl Message {
                 .map_or(false, |(_, domain)| domains.contains(&domain.to_string()))
     }
 }
+
+fn event_value(priority: i16) -> u64 {
+    if priority != 0 {
+        (priority as i64 + PRIORITY_OFFSET) as u64
+    } else {
+        0
+    }
+}
+
+fn event_priority(value: u64) -> i16 {
+    if (1..PRIORITY_OFFSET as u64 * 2).contains(&value) {
+        (value as i64 - PRIORITY_OFFSET) as i16
+    } else {
+        0
+    }
+}
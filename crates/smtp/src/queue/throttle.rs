@@ -14,7 +14,7 @@ use store::write::now;
 
 use crate::core::{throttle::NewKey, SMTP};
 
-use super::{Domain, Status};
+use super::{Domain, Recipient, Status};
 
 #[derive(Debug)]
 pub enum Error {
@@ -22,6 +22,16 @@ pub enum Error {
     Rate { retry_at: u64 },
 }
 
+// Concurrency and minimum interval between connections for a remote host
+// that recently signaled throttling.
+#[derive(Debug)]
+pub struct AdaptiveLimiter {
+    pub limiter: ConcurrencyLimiter,
+    pub interval: u64,
+    pub next_attempt: u64,
+    pub last_change: u64,
+}
+
 impl SMTP {
     pub async fn is_allowed<'x>(
         &'x self,
@@ -37,7 +47,7 @@ impl SMTP {
                 .await
                 .unwrap_or(false)
         {
-            let key = throttle.new_key(envelope);
+            let key = throttle.new_key(envelope, &self.core.smtp.canonical);
 
             if let Some(rate) = &throttle.rate {
                 if let Ok(Some(next_refill)) = self
@@ -97,6 +107,132 @@ impl SMTP {
     }
 }
 
+impl SMTP {
+    pub fn is_allowed_adaptive(
+        &self,
+        host: &str,
+        in_flight: &mut Vec<InFlight>,
+        session_id: u64,
+    ) -> Result<(), Error> {
+        if !self.core.smtp.queue.adaptive_throttle.enable {
+            return Ok(());
+        }
+
+        if let Some(mut adaptive) = self.inner.queue_adaptive.get_mut(host) {
+            let now = now();
+            if adaptive.next_attempt > now {
+                trc::event!(
+                    Queue(trc::QueueEvent::RateLimitExceeded),
+                    SpanId = session_id,
+                    Hostname = host.to_string(),
+                    NextRetry = trc::Value::Timestamp(adaptive.next_attempt),
+                );
+
+                return Err(Error::Rate {
+                    retry_at: adaptive.next_attempt,
+                });
+            }
+
+            if let Some(inflight) = adaptive.limiter.is_allowed() {
+                in_flight.push(inflight);
+                adaptive.next_attempt = now + adaptive.interval;
+            } else {
+                trc::event!(
+                    Queue(trc::QueueEvent::ConcurrencyLimitExceeded),
+                    SpanId = session_id,
+                    Hostname = host.to_string(),
+                    Limit = adaptive.limiter.max_concurrent,
+                );
+
+                return Err(Error::Concurrency {
+                    limiter: adaptive.limiter.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn adaptive_throttle_update<'x>(
+        &self,
+        host: &str,
+        status: &Status<(), super::Error>,
+        mut recipients: impl Iterator<Item = &'x Recipient>,
+        session_id: u64,
+    ) {
+        let config = &self.core.smtp.queue.adaptive_throttle;
+        if !config.enable {
+            return;
+        }
+        let now = now();
+
+        let is_throttled = match status {
+            Status::TemporaryFailure(super::Error::UnexpectedResponse(response)) => {
+                config.codes.contains(&response.response.code)
+            }
+            Status::Completed(_) => recipients.any(|rcpt| {
+                matches!(&rcpt.status, Status::TemporaryFailure(response)
+                    if config.codes.contains(&response.response.code))
+            }),
+            _ => false,
+        };
+
+        if is_throttled {
+            // Halve concurrency and double the interval between connections
+            let mut adaptive = self
+                .inner
+                .queue_adaptive
+                .entry(host.to_string())
+                .or_insert_with(|| AdaptiveLimiter {
+                    limiter: ConcurrencyLimiter::new(config.max_concurrency),
+                    interval: 0,
+                    next_attempt: 0,
+                    last_change: 0,
+                });
+            adaptive.limiter.max_concurrent =
+                (adaptive.limiter.max_concurrent / 2).max(config.min_concurrency);
+            adaptive.interval =
+                (adaptive.interval * 2).clamp(1, config.max_interval.as_secs().max(1));
+            adaptive.next_attempt = now + adaptive.interval;
+            adaptive.last_change = now;
+
+            trc::event!(
+                Queue(trc::QueueEvent::AdaptiveThrottleReduced),
+                SpanId = session_id,
+                Hostname = host.to_string(),
+                Limit = adaptive.limiter.max_concurrent,
+                NextRetry = trc::Value::Timestamp(adaptive.next_attempt),
+            );
+        } else if !matches!(status, Status::TemporaryFailure(_)) {
+            // Recover one step after each period without throttling signals
+            let is_recovered = if let Some(mut adaptive) = self.inner.queue_adaptive.get_mut(host) {
+                if adaptive.last_change + config.recovery.as_secs() <= now {
+                    adaptive.limiter.max_concurrent =
+                        (adaptive.limiter.max_concurrent + 1).min(config.max_concurrency);
+                    adaptive.interval /= 2;
+                    adaptive.last_change = now;
+                    adaptive.limiter.max_concurrent >= config.max_concurrency
+                        && adaptive.interval == 0
+                } else {
+                    false
+                }
+            } else {
+                false
+            };
+
+            if is_recovered {
+                self.inner.queue_adaptive.remove(host);
+
+                trc::event!(
+                    Queue(trc::QueueEvent::AdaptiveThrottleRecovered),
+                    SpanId = session_id,
+                    Hostname = host.to_string(),
+                );
+            }
+        }
+    }
+}
+
 impl Domain {
     pub fn set_throttle_error(&mut self, err: Error, on_hold: &mut Vec<ConcurrencyLimiter>) {
         match err {
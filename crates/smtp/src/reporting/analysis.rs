@@ -280,61 +280,115 @@ impl SMTP {
                 };
 
                 // Store report
-                if let Some(expires_in) = &core.core.smtp.report.analysis.store {
-                    let expires = now() + expires_in.as_secs();
-                    let id = core.inner.queue_id_gen.generate().unwrap_or(expires);
-
-                    let mut batch = BatchBuilder::new();
-                    match report {
-                        Format::Dmarc(report) => {
-                            batch.set(
-                                ValueClass::Report(ReportClass::Dmarc { id, expires }),
-                                Bincode::new(IncomingReport {
-                                    from,
-                                    to,
-                                    subject,
-                                    report,
-                                })
-                                .serialize(),
-                            );
-                        }
-                        Format::Tls(report) => {
-                            batch.set(
-                                ValueClass::Report(ReportClass::Tls { id, expires }),
-                                Bincode::new(IncomingReport {
-                                    from,
-                                    to,
-                                    subject,
-                                    report,
-                                })
-                                .serialize(),
-                            );
-                        }
-                        Format::Arf(report) => {
-                            batch.set(
-                                ValueClass::Report(ReportClass::Arf { id, expires }),
-                                Bincode::new(IncomingReport {
-                                    from,
-                                    to,
-                                    subject,
-                                    report,
-                                })
-                                .serialize(),
-                            );
-                        }
-                    }
-                    let batch = batch.build();
-                    if let Err(err) = core.core.storage.data.write(batch).await {
-                        trc::error!(err
-                            .span_id(session_id)
-                            .caused_by(trc::location!())
-                            .details("Failed to write report"));
-                    }
-                }
+                core.store_incoming_report(report, from, to, subject, session_id)
+                    .await;
                 return;
             }
         });
     }
+
+    pub async fn ingest_tls_report(&self, data: &[u8], session_id: u64) -> trc::Result<()> {
+        // Reports submitted over HTTPS may be gzip compressed (RFC 8460 section 5.4)
+        let data = if data.starts_with(&[0x1f, 0x8b]) {
+            let mut buf = Vec::new();
+            GzDecoder::new(data).read_to_end(&mut buf).map_err(|err| {
+                trc::Error::new(trc::EventType::IncomingReport(
+                    IncomingReportEvent::DecompressError,
+                ))
+                .reason(err)
+                .caused_by(trc::location!())
+            })?;
+            Cow::Owned(buf)
+        } else {
+            Cow::Borrowed(data)
+        };
+
+        let report = TlsReport::parse_json(&data).map_err(|err| {
+            trc::Error::new(trc::EventType::IncomingReport(
+                IncomingReportEvent::TlsRpcParseFailed,
+            ))
+            .reason(format!("{err:?}"))
+            .caused_by(trc::location!())
+        })?;
+        report.log();
+
+        let from = report
+            .contact_info
+            .clone()
+            .or_else(|| report.organization_name.clone())
+            .unwrap_or_default();
+        let to = report
+            .policies
+            .iter()
+            .map(|p| p.policy.policy_domain.clone())
+            .collect();
+        let subject = report.report_id.clone();
+        self.store_incoming_report(Format::Tls(report), from, to, subject, session_id)
+            .await;
+
+        Ok(())
+    }
+
+    async fn store_incoming_report(
+        &self,
+        report: Format<Report, TlsReport, Feedback<'static>>,
+        from: String,
+        to: Vec<String>,
+        subject: String,
+        session_id: u64,
+    ) {
+        if let Some(expires_in) = &self.core.smtp.report.analysis.store {
+            let expires = now() + expires_in.as_secs();
+            let id = self.inner.queue_id_gen.generate().unwrap_or(expires);
+
+            let mut batch = BatchBuilder::new();
+            match report {
+                Format::Dmarc(report) => {
+                    batch.set(
+                        ValueClass::Report(ReportClass::Dmarc { id, expires }),
+                        Bincode::new(IncomingReport {
+                            from,
+                            to,
+                            subject,
+                            report,
+                        })
+                        .serialize(),
+                    );
+                }
+                Format::Tls(report) => {
+                    batch.set(
+                        ValueClass::Report(ReportClass::Tls { id, expires }),
+                        Bincode::new(IncomingReport {
+                            from,
+                            to,
+                            subject,
+                            report,
+                        })
+                        .serialize(),
+                    );
+                }
+                Format::Arf(report) => {
+                    batch.set(
+                        ValueClass::Report(ReportClass::Arf { id, expires }),
+                        Bincode::new(IncomingReport {
+                            from,
+                            to,
+                            subject,
+                            report,
+                        })
+                        .serialize(),
+                    );
+                }
+            }
+            let batch = batch.build();
+            if let Err(err) = self.core.storage.data.write(batch).await {
+                trc::error!(err
+                    .span_id(session_id)
+                    .caused_by(trc::location!())
+                    .details("Failed to write report"));
+            }
+        }
+    }
 }
 
 trait LogReport {
@@ -13,6 +13,8 @@ use utils::config::Rate;
 
 use crate::core::Session;
 
+use super::redact_headers;
+
 impl<T: SessionStream> Session<T> {
     pub async fn send_dkim_report(
         &self,
@@ -29,8 +31,13 @@ impl<T: SessionStream> Session<T> {
             return;
         };
 
-        // Throttle recipient
-        if !self.throttle_rcpt(rcpt, rate, "dkim").await {
+        // Throttle recipient and signing domain
+        let config = &self.core.core.smtp.report.dkim;
+        if !self.throttle_rcpt(rcpt, rate, "dkim").await
+            || !self
+                .throttle_report_domain(signature.domain(), config, "dkim")
+                .await
+        {
             trc::event!(
                 OutgoingReport(OutgoingReportEvent::DkimRateLimited),
                 SpanId = self.data.session_id,
@@ -44,7 +51,6 @@ impl<T: SessionStream> Session<T> {
             return;
         }
 
-        let config = &self.core.core.smtp.report.dkim;
         let from_addr = self
             .core
             .core
@@ -61,7 +67,10 @@ impl<T: SessionStream> Session<T> {
             .with_dkim_domain(signature.domain())
             .with_dkim_selector(signature.selector())
             .with_dkim_identity(signature.identity())
-            .with_headers(std::str::from_utf8(message.raw_headers()).unwrap_or_default())
+            .with_headers(redact_headers(
+                std::str::from_utf8(message.raw_headers()).unwrap_or_default(),
+                config.redact,
+            ))
             .write_rfc5322(
                 (
                     self.core
@@ -27,7 +27,9 @@ use crate::{
     queue::{DomainPart, RecipientDomain},
 };
 
-use super::{scheduler::ToHash, AggregateTimestamp, DmarcEvent, ReportLock, SerializedSize};
+use super::{
+    redact_headers, scheduler::ToHash, AggregateTimestamp, DmarcEvent, ReportLock, SerializedSize,
+};
 
 #[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct DmarcFormat {
@@ -72,9 +74,14 @@ impl<T: SessionStream> Session<T> {
                     if !rcpts.is_empty() {
                         let mut new_rcpts = Vec::with_capacity(rcpts.len());
 
-                        for rcpt in rcpts {
-                            if self.throttle_rcpt(rcpt.uri(), &failure_rate, "dmarc").await {
-                                new_rcpts.push(rcpt.uri());
+                        if self
+                            .throttle_report_domain(dmarc_output.domain(), config, "dmarc")
+                            .await
+                        {
+                            for rcpt in rcpts {
+                                if self.throttle_rcpt(rcpt.uri(), &failure_rate, "dmarc").await {
+                                    new_rcpts.push(rcpt.uri());
+                                }
                             }
                         }
 
@@ -121,7 +128,10 @@ impl<T: SessionStream> Session<T> {
                 let mut auth_failure = self
                     .new_auth_failure(AuthFailureType::Dmarc, rejected)
                     .with_authentication_results(auth_results.to_string())
-                    .with_headers(std::str::from_utf8(message.raw_headers()).unwrap_or_default());
+                    .with_headers(redact_headers(
+                        std::str::from_utf8(message.raw_headers()).unwrap_or_default(),
+                        config.redact,
+                    ));
 
                 // Report the first failed signature
                 let dkim_failed = if let (
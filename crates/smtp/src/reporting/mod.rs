@@ -4,11 +4,11 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{io, sync::Arc, time::SystemTime};
+use std::{borrow::Cow, io, sync::Arc, time::SystemTime};
 
 use common::{
     config::smtp::{
-        report::{AddressMatch, AggregateFrequency},
+        report::{AddressMatch, AggregateFrequency, ReportRedaction},
         resolver::{Policy, Tlsa},
     },
     expr::if_block::IfBlock,
@@ -397,3 +397,67 @@ impl ReportLock for QueueClass {
         })
     }
 }
+
+// Header fields kept when only the headers needed to identify the message are reported
+const MINIMAL_HEADERS: &[&str] = &["From", "Date", "Message-ID", "DKIM-Signature"];
+
+// Header fields whose addresses are not redacted as they are needed to match the message
+const UNREDACTED_HEADERS: &[&str] = &["Message-ID", "DKIM-Signature"];
+
+pub fn redact_headers(headers: &str, redact: ReportRedaction) -> Cow<'_, str> {
+    if redact == ReportRedaction::None {
+        return headers.into();
+    }
+
+    let mut result = String::with_capacity(headers.len());
+    let mut is_included = true;
+    let mut is_redacted = true;
+    for line in headers.split_inclusive('\n') {
+        if !line.starts_with([' ', '\t']) {
+            let name = line
+                .split_once(':')
+                .map(|(name, _)| name.trim())
+                .unwrap_or_default();
+            is_included = redact == ReportRedaction::Addresses
+                || MINIMAL_HEADERS
+                    .iter()
+                    .any(|header| header.eq_ignore_ascii_case(name));
+            is_redacted = !UNREDACTED_HEADERS
+                .iter()
+                .any(|header| header.eq_ignore_ascii_case(name));
+        }
+
+        if is_included {
+            if is_redacted {
+                redact_local_parts(line, &mut result);
+            } else {
+                result.push_str(line);
+            }
+        }
+    }
+
+    result.into()
+}
+
+fn redact_local_parts(text: &str, result: &mut String) {
+    let mut last_pos = 0;
+
+    for (pos, _) in text.match_indices('@') {
+        let start_pos = text[..pos]
+            .rfind(|ch: char| {
+                ch.is_ascii_whitespace()
+                    || matches!(
+                        ch,
+                        '<' | '>' | ',' | ':' | ';' | '(' | ')' | '"' | '[' | ']'
+                    )
+            })
+            .map_or(0, |pos| pos + 1);
+        if start_pos >= last_pos && start_pos < pos {
+            result.push_str(&text[last_pos..start_pos]);
+            result.push_str("redacted");
+            last_pos = pos;
+        }
+    }
+
+    result.push_str(&text[last_pos..]);
+}
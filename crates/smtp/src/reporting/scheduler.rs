@@ -8,7 +8,7 @@ use ahash::{AHashMap, RandomState};
 use common::Core;
 use mail_auth::dmarc::Dmarc;
 
-use std::time::{Duration, Instant, SystemTime};
+use std::time::{Duration, Instant};
 use store::{
     write::{now, BatchBuilder, QueueClass, ReportEvent, ValueClass},
     Deserialize, IterateParams, Key, Serialize, ValueKey,
@@ -17,7 +17,10 @@ use tokio::sync::mpsc;
 
 use crate::{
     core::{SmtpInstance, SMTP},
-    queue::{manager::LONG_WAIT, spool::LOCK_EXPIRY},
+    queue::{
+        manager::{LONG_WAIT, STANDBY_WAIT},
+        spool::LOCK_EXPIRY,
+    },
 };
 
 use super::{Event, ReportLock};
@@ -31,7 +34,12 @@ impl SpawnReport for mpsc::Receiver<Event> {
             loop {
                 // Read events
                 let now = now();
-                let events = next_report_event(&core.core.load_full()).await;
+                let core_ = core.core.load_full();
+                let events = if !core_.storage.replication.is_standby() {
+                    next_report_event(&core_).await
+                } else {
+                    vec![]
+                };
                 next_wake_up = events
                     .last()
                     .and_then(|e| match e {
@@ -42,7 +50,11 @@ impl SpawnReport for mpsc::Receiver<Event> {
                         }
                         _ => None,
                     })
-                    .unwrap_or(LONG_WAIT);
+                    .unwrap_or(if !core_.storage.replication.is_standby() {
+                        LONG_WAIT
+                    } else {
+                        STANDBY_WAIT
+                    });
 
                 let core = SMTP::from(core.clone());
                 let core_ = core.clone();
@@ -238,10 +250,7 @@ pub trait ToTimestamp {
 
 impl ToTimestamp for Duration {
     fn to_timestamp(&self) -> u64 {
-        SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map_or(0, |d| d.as_secs())
-            + self.as_secs()
+        now() + self.as_secs()
     }
 }
 
@@ -19,8 +19,13 @@ impl<T: SessionStream> Session<T> {
         rejected: bool,
         output: &SpfOutput,
     ) {
-        // Throttle recipient
-        if !self.throttle_rcpt(rcpt, rate, "spf").await {
+        // Throttle recipient and domain
+        let config = &self.core.core.smtp.report.spf;
+        if !self.throttle_rcpt(rcpt, rate, "spf").await
+            || !self
+                .throttle_report_domain(output.domain(), config, "spf")
+                .await
+        {
             trc::event!(
                 OutgoingReport(OutgoingReportEvent::SpfRateLimited),
                 SpanId = self.data.session_id,
@@ -35,7 +40,6 @@ impl<T: SessionStream> Session<T> {
         }
 
         // Generate report
-        let config = &self.core.core.smtp.report.spf;
         let from_addr = self
             .core
             .core
@@ -124,8 +124,9 @@ impl SMTP {
             }
         };
 
-        // Try delivering report over HTTP
+        // Deliver report to all HTTP destinations
         let mut rcpts = Vec::with_capacity(rua.len());
+        let mut is_http_delivered = false;
         for uri in &rua {
             match uri {
                 ReportUri::Http(uri) => {
@@ -137,8 +138,8 @@ impl SMTP {
                         #[cfg(feature = "test_mode")]
                         if uri == "https://127.0.0.1/tls" {
                             TLS_HTTP_REPORT.lock().extend_from_slice(&json);
-                            self.delete_tls_report(events).await;
-                            return;
+                            is_http_delivered = true;
+                            continue;
                         }
 
                         match client
@@ -157,8 +158,7 @@ impl SMTP {
                                         Code = response.status().as_u16(),
                                     );
 
-                                    self.delete_tls_report(events).await;
-                                    return;
+                                    is_http_delivered = true;
                                 } else {
                                     trc::event!(
                                         OutgoingReport(OutgoingReportEvent::SubmissionError),
@@ -230,7 +230,7 @@ impl SMTP {
                 span_id,
             )
             .await;
-        } else {
+        } else if !is_http_delivered {
             trc::event!(
                 OutgoingReport(OutgoingReportEvent::NoRecipientsFound),
                 SpanId = span_id,
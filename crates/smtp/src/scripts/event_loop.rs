@@ -13,9 +13,10 @@ use sieve::{
     Event, Input, MatchAs, Recipient, Sieve,
 };
 use smtp_proto::{
-    MAIL_BY_TRACE, MAIL_RET_FULL, MAIL_RET_HDRS, RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE,
-    RCPT_NOTIFY_NEVER, RCPT_NOTIFY_SUCCESS,
+    MAIL_BY_NOTIFY, MAIL_BY_RETURN, MAIL_BY_TRACE, MAIL_RET_FULL, MAIL_RET_HDRS, RCPT_NOTIFY_DELAY,
+    RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_NEVER, RCPT_NOTIFY_SUCCESS,
 };
+use store::write::now;
 use trc::SieveEvent;
 
 use crate::{
@@ -169,6 +170,30 @@ impl SMTP {
                             }
                         }
 
+                        // Rewrite the sender of forwarded messages using SRS
+                        if message_id == 0 {
+                            match self
+                                .core
+                                .srs_forward(
+                                    &message.return_path,
+                                    message.domains.iter().map(|d| d.domain.as_str()),
+                                    params.from_addr.domain_part(),
+                                )
+                                .await
+                            {
+                                Ok(Some(srs_address)) => {
+                                    message.set_return_path(srs_address);
+                                }
+                                Ok(None) => (),
+                                Err(err) => {
+                                    trc::error!(err
+                                        .span_id(session_id)
+                                        .caused_by(trc::location!())
+                                        .details("Failed to rewrite sender address."));
+                                }
+                            }
+                        }
+
                         // Set notify flags
                         let mut flags = 0;
                         match notify {
@@ -202,15 +227,18 @@ impl SMTP {
                                 if trace {
                                     message.flags |= MAIL_BY_TRACE;
                                 }
+                                let alimit = now() + rlimit;
                                 match mode {
                                     ByMode::Notify => {
+                                        message.flags |= MAIL_BY_NOTIFY;
                                         for domain in &mut message.domains {
-                                            domain.notify.due += rlimit;
+                                            domain.notify.due = alimit;
                                         }
                                     }
                                     ByMode::Return => {
+                                        message.flags |= MAIL_BY_RETURN;
                                         for domain in &mut message.domains {
-                                            domain.notify.due += rlimit;
+                                            domain.expires = alimit;
                                         }
                                     }
                                     ByMode::Default => (),
@@ -226,11 +254,13 @@ impl SMTP {
                                 }
                                 match mode {
                                     ByMode::Notify => {
+                                        message.flags |= MAIL_BY_NOTIFY;
                                         for domain in &mut message.domains {
                                             domain.notify.due = alimit as u64;
                                         }
                                     }
                                     ByMode::Return => {
+                                        message.flags |= MAIL_BY_RETURN;
                                         for domain in &mut message.domains {
                                             domain.expires = alimit as u64;
                                         }
@@ -47,6 +47,7 @@ impl<T: SessionStream> Session<T> {
             )
             .set_variable("tls.version", tls_version)
             .set_variable("tls.cipher", tls_cipher)
+            .set_variable("early_talker", self.data.early_talker)
             .set_variable("stage", stage);
         if let Some(ip_rev) = &self.data.iprev {
             params = params.set_variable("iprev.result", ip_rev.result().as_str());
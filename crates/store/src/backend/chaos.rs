@@ -0,0 +1,172 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// Fault injection for the store layer, only available in test builds.
+// Faults are drawn from a seeded RNG so test runs are reproducible.
+static CHAOS: Mutex<Option<Chaos>> = parking_lot::const_mutex(None);
+static INJECTED_LATENCY: AtomicU64 = AtomicU64::new(0);
+static INJECTED_ERRORS: AtomicU64 = AtomicU64::new(0);
+static INJECTED_CONFLICTS: AtomicU64 = AtomicU64::new(0);
+static INJECTED_COMMIT_ERRORS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Default)]
+pub struct ChaosConfig {
+    pub seed: u64,
+    pub latency: Duration,
+    pub latency_probability: f64,
+    pub error_probability: f64,
+    pub conflict_probability: f64,
+    pub commit_error_probability: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChaosStats {
+    pub latency: u64,
+    pub errors: u64,
+    pub conflicts: u64,
+    pub commit_errors: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChaosOp {
+    Read,
+    Write,
+    Commit,
+}
+
+struct Chaos {
+    config: ChaosConfig,
+    rng: StdRng,
+}
+
+enum Fault {
+    Latency(Duration),
+    Error,
+    Conflict,
+    CommitError,
+}
+
+impl ChaosConfig {
+    pub fn new(seed: u64) -> Self {
+        ChaosConfig {
+            seed,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_latency(mut self, latency: Duration, probability: f64) -> Self {
+        self.latency = latency;
+        self.latency_probability = probability;
+        self
+    }
+
+    pub fn with_errors(mut self, probability: f64) -> Self {
+        self.error_probability = probability;
+        self
+    }
+
+    pub fn with_conflicts(mut self, probability: f64) -> Self {
+        self.conflict_probability = probability;
+        self
+    }
+
+    // Writes are committed by the backend but reported as failed, as when the
+    // connection is lost before the commit is acknowledged.
+    pub fn with_commit_errors(mut self, probability: f64) -> Self {
+        self.commit_error_probability = probability;
+        self
+    }
+
+    pub fn enable(self) {
+        INJECTED_LATENCY.store(0, Ordering::Relaxed);
+        INJECTED_ERRORS.store(0, Ordering::Relaxed);
+        INJECTED_CONFLICTS.store(0, Ordering::Relaxed);
+        INJECTED_COMMIT_ERRORS.store(0, Ordering::Relaxed);
+        *CHAOS.lock() = Some(Chaos {
+            rng: StdRng::seed_from_u64(self.seed),
+            config: self,
+        });
+    }
+
+    pub fn disable() -> ChaosStats {
+        *CHAOS.lock() = None;
+        ChaosStats::current()
+    }
+}
+
+impl ChaosStats {
+    pub fn current() -> Self {
+        ChaosStats {
+            latency: INJECTED_LATENCY.load(Ordering::Relaxed),
+            errors: INJECTED_ERRORS.load(Ordering::Relaxed),
+            conflicts: INJECTED_CONFLICTS.load(Ordering::Relaxed),
+            commit_errors: INJECTED_COMMIT_ERRORS.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub(crate) async fn inject(op: ChaosOp) -> trc::Result<()> {
+    let fault = {
+        let mut chaos = CHAOS.lock();
+        let chaos = if let Some(chaos) = chaos.as_mut() {
+            chaos
+        } else {
+            return Ok(());
+        };
+
+        if op == ChaosOp::Commit {
+            // The backend already wrote the changes
+            chaos
+                .rng
+                .gen_bool(chaos.config.commit_error_probability)
+                .then_some(Fault::CommitError)
+        } else if op == ChaosOp::Write && chaos.rng.gen_bool(chaos.config.conflict_probability) {
+            // Conflicts only make sense for transactions
+            Some(Fault::Conflict)
+        } else if chaos.rng.gen_bool(chaos.config.error_probability) {
+            Some(Fault::Error)
+        } else if chaos.rng.gen_bool(chaos.config.latency_probability) {
+            Some(Fault::Latency(chaos.config.latency))
+        } else {
+            None
+        }
+    };
+
+    match fault {
+        Some(Fault::Conflict) => {
+            INJECTED_CONFLICTS.fetch_add(1, Ordering::Relaxed);
+            Err(trc::StoreEvent::AssertValueFailed
+                .into_err()
+                .details("Injected transaction conflict"))
+        }
+        Some(Fault::Error) => {
+            INJECTED_ERRORS.fetch_add(1, Ordering::Relaxed);
+            Err(trc::StoreEvent::PoolError
+                .into_err()
+                .details("Injected transient error"))
+        }
+        Some(Fault::CommitError) => {
+            INJECTED_COMMIT_ERRORS.fetch_add(1, Ordering::Relaxed);
+            Err(trc::StoreEvent::PoolError
+                .into_err()
+                .details("Injected error after commit"))
+        }
+        Some(Fault::Latency(latency)) => {
+            INJECTED_LATENCY.fetch_add(1, Ordering::Relaxed);
+            tokio::time::sleep(latency).await;
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+#[cfg(feature = "test_mode")]
+pub mod chaos;
 #[cfg(feature = "enterprise")]
 pub mod composite;
 #[cfg(feature = "elastic")]
@@ -108,6 +108,7 @@ impl MysqlStore {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_REPLICATION,
         ] {
             let table = char::from(table);
             conn.query_drop(format!(
@@ -96,6 +96,7 @@ impl PostgresStore {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_REPLICATION,
         ] {
             let table = char::from(table);
             conn.execute(
@@ -90,6 +90,7 @@ impl RocksDbStore {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_REPLICATION,
         ] {
             let cf_opts = Options::default();
             cfs.push(ColumnFamilyDescriptor::new(
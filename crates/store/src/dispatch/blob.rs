@@ -11,6 +11,11 @@ use utils::config::utils::ParseValue;
 
 use crate::{BlobBackend, BlobStore, CompressionAlgo, Store};
 
+use super::{
+    health::BLOB_HEALTH,
+    replication::{ChangeOp, CHANGE_STREAM},
+};
+
 impl BlobStore {
     pub async fn get_blob(&self, key: &[u8], range: Range<usize>) -> trc::Result<Option<Vec<u8>>> {
         let read_range = match self.compression {
@@ -41,10 +46,12 @@ impl BlobStore {
             BlobBackend::Composite(store) => store.get_blob(key, read_range).await,
         };
 
+        let elapsed = start_time.elapsed();
+        BLOB_HEALTH.record(&result, elapsed);
         trc::event!(
             Store(StoreEvent::BlobRead),
             Key = key,
-            Elapsed = start_time.elapsed(),
+            Elapsed = elapsed,
             Size = result
                 .as_ref()
                 .map_or(0, |data| data.as_ref().map_or(0, |data| data.len())),
@@ -122,13 +129,28 @@ impl BlobStore {
         }
         .caused_by(trc::location!());
 
+        let elapsed = start_time.elapsed();
+        BLOB_HEALTH.record(&result, elapsed);
         trc::event!(
             Store(StoreEvent::BlobWrite),
             Key = key,
-            Elapsed = start_time.elapsed(),
+            Elapsed = elapsed,
             Size = data.len(),
         );
 
+        // Blob contents are read back when shipped to the standby. Blobs are
+        // linked after they are written, so a blob written before a crash that
+        // was not recorded is also unreferenced on the primary.
+        if result.is_ok() && CHANGE_STREAM.captures_blob_store(self) {
+            CHANGE_STREAM
+                .capture(vec![ChangeOp::BlobPut {
+                    key: key.to_vec(),
+                    data: vec![],
+                }])
+                .await
+                .caused_by(trc::location!())?;
+        }
+
         result
     }
 
@@ -158,11 +180,16 @@ impl BlobStore {
         }
         .caused_by(trc::location!());
 
-        trc::event!(
-            Store(StoreEvent::BlobWrite),
-            Key = key,
-            Elapsed = start_time.elapsed(),
-        );
+        let elapsed = start_time.elapsed();
+        BLOB_HEALTH.record(&result, elapsed);
+        trc::event!(Store(StoreEvent::BlobWrite), Key = key, Elapsed = elapsed,);
+
+        if result.is_ok() && CHANGE_STREAM.captures_blob_store(self) {
+            CHANGE_STREAM
+                .capture(vec![ChangeOp::BlobDelete { key: key.to_vec() }])
+                .await
+                .caused_by(trc::location!())?;
+        }
 
         result
     }
@@ -0,0 +1,172 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+    time::Duration,
+};
+
+use trc::{EventType, StoreEvent};
+
+// Operation counters sampled by the health watchdog, shared by all stores
+// of the same kind.
+pub static DATA_HEALTH: HealthCounter = HealthCounter::new();
+pub static BLOB_HEALTH: HealthCounter = HealthCounter::new();
+static HEALTH_STATUS: AtomicU8 = AtomicU8::new(0);
+
+pub struct HealthCounter {
+    operations: AtomicU64,
+    errors: AtomicU64,
+    elapsed: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HealthSample {
+    pub operations: u64,
+    pub errors: u64,
+    pub elapsed: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HealthStatus {
+    #[default]
+    Healthy = 0,
+    Degraded = 1,
+    Critical = 2,
+}
+
+impl HealthCounter {
+    pub const fn new() -> Self {
+        HealthCounter {
+            operations: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            elapsed: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record<T>(&self, result: &trc::Result<T>, elapsed: Duration) {
+        self.operations.fetch_add(1, Ordering::Relaxed);
+        self.elapsed
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        if result.as_ref().is_err_and(is_backend_error) {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn sample(&self) -> HealthSample {
+        HealthSample {
+            operations: self.operations.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            elapsed: self.elapsed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for HealthCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthSample {
+    // Returns the operations recorded since a previous sample
+    pub fn since(&self, previous: &HealthSample) -> HealthSample {
+        HealthSample {
+            operations: self.operations.saturating_sub(previous.operations),
+            errors: self.errors.saturating_sub(previous.errors),
+            elapsed: self.elapsed.saturating_sub(previous.elapsed),
+        }
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        if self.operations > 0 {
+            self.errors as f64 * 100.0 / self.operations as f64
+        } else {
+            0.0
+        }
+    }
+
+    pub fn avg_latency(&self) -> Duration {
+        Duration::from_micros(self.elapsed.checked_div(self.operations).unwrap_or(0))
+    }
+}
+
+impl HealthStatus {
+    pub fn current() -> Self {
+        match HEALTH_STATUS.load(Ordering::Relaxed) {
+            1 => HealthStatus::Degraded,
+            2 => HealthStatus::Critical,
+            _ => HealthStatus::Healthy,
+        }
+    }
+
+    pub fn set(self) {
+        HEALTH_STATUS.store(self as u8, Ordering::Relaxed);
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Degraded => "degraded",
+            HealthStatus::Critical => "critical",
+        }
+    }
+}
+
+// Errors caused by the request itself, such as assertion failures or
+// missing keys, do not indicate a problem with the backend.
+fn is_backend_error(err: &trc::Error) -> bool {
+    !matches!(
+        err.inner,
+        EventType::Store(
+            StoreEvent::AssertValueFailed
+                | StoreEvent::NotFound
+                | StoreEvent::NotConfigured
+                | StoreEvent::NotSupported
+                | StoreEvent::DeserializeError
+        )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{HealthCounter, HealthSample};
+
+    #[test]
+    fn health_sample() {
+        let counter = HealthCounter::new();
+        let start = counter.sample();
+
+        counter.record(&Ok(()), Duration::from_millis(10));
+        counter.record::<()>(
+            &Err(trc::StoreEvent::SqliteError.into()),
+            Duration::from_millis(30),
+        );
+        counter.record::<()>(
+            &Err(trc::StoreEvent::AssertValueFailed.into()),
+            Duration::from_millis(20),
+        );
+        counter.record::<()>(
+            &Err(trc::StoreEvent::PoolError.into()),
+            Duration::from_millis(20),
+        );
+
+        let sample = counter.sample().since(&start);
+        assert_eq!(
+            sample,
+            HealthSample {
+                operations: 4,
+                errors: 2,
+                elapsed: 80_000,
+            }
+        );
+        assert_eq!(sample.error_rate(), 50.0);
+        assert_eq!(sample.avg_latency(), Duration::from_millis(20));
+        assert_eq!(HealthSample::default().error_rate(), 0.0);
+    }
+}
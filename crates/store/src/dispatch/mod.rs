@@ -4,13 +4,17 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::sync::Arc;
+
 use roaring::RoaringBitmap;
 
-use crate::Store;
+use crate::{BlobBackend, BlobStore, Store};
 
 pub mod blob;
 pub mod fts;
+pub mod health;
 pub mod lookup;
+pub mod replication;
 pub mod store;
 
 impl Store {
@@ -31,6 +35,38 @@ impl Store {
             Self::None => "none",
         }
     }
+
+    // Identifies the store instance, shared by all clones of the same store
+    pub fn instance_id(&self) -> usize {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::SQLite(store) => Arc::as_ptr(store) as usize,
+            #[cfg(feature = "foundation")]
+            Self::FoundationDb(store) => Arc::as_ptr(store) as usize,
+            #[cfg(feature = "postgres")]
+            Self::PostgreSQL(store) => Arc::as_ptr(store) as usize,
+            #[cfg(feature = "mysql")]
+            Self::MySQL(store) => Arc::as_ptr(store) as usize,
+            #[cfg(feature = "rocks")]
+            Self::RocksDb(store) => Arc::as_ptr(store) as usize,
+            #[cfg(all(feature = "enterprise", any(feature = "postgres", feature = "mysql")))]
+            Self::SQLReadReplica(store) => Arc::as_ptr(store) as usize,
+            Self::None => 0,
+        }
+    }
+}
+
+impl BlobStore {
+    pub fn instance_id(&self) -> usize {
+        match &self.backend {
+            BlobBackend::Store(store) => store.instance_id(),
+            BlobBackend::Fs(store) => Arc::as_ptr(store) as usize,
+            #[cfg(feature = "s3")]
+            BlobBackend::S3(store) => Arc::as_ptr(store) as usize,
+            #[cfg(feature = "enterprise")]
+            BlobBackend::Composite(store) => Arc::as_ptr(store) as usize,
+        }
+    }
 }
 
 #[allow(clippy::len_without_is_empty)]
@@ -0,0 +1,854 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use parking_lot::RwLock;
+use trc::{AddContext, StoreEvent};
+use utils::snowflake::SnowflakeIdGenerator;
+
+use crate::{
+    write::{
+        assert::AssertValue, key::DeserializeBigEndian, now, AnyClass, AnyKey, AssignedIds, Batch,
+        BatchBuilder, Bincode, BitmapClass, BitmapHash, MaybeDynamicId, MaybeDynamicValue,
+        Operation, SerializeWithId, TagValue, ValueClass, ValueOp,
+    },
+    BlobStore, Deserialize, IterateParams, Serialize, Store, ValueKey, SUBSPACE_REPLICATION,
+    U64_LEN,
+};
+
+// Changes committed to the primary data and blob stores. Each change record is
+// written to the data store in the same transaction as the change itself, so
+// unshipped changes survive restarts and all cluster nodes share one stream
+// ordered by the snowflake id of the record.
+pub static CHANGE_STREAM: ChangeStream = ChangeStream::new();
+
+// Sequence of the newest change record applied by a standby
+pub const APPLIED_KEY: &[u8] = b"storage.replication.applied";
+
+// Records are only shipped once they are older than this many milliseconds, so
+// that they are usually shipped in commit order. Records that commit after a
+// newer record was shipped are included in the next change set.
+const SETTLE_TIME: u64 = 5000;
+
+// Standbys keep a marker for each applied record, written in the same batch as
+// the changes, so that records shipped again are not applied twice.
+const APPLIED_RETENTION: Duration = Duration::from_secs(7 * 86400);
+
+const KEY_RECORD: u8 = 0;
+const KEY_RESYNC: u8 = 1;
+const KEY_SHIPPED: u8 = 2;
+const KEY_APPLIED: u8 = 3;
+
+// Milliseconds between the UNIX epoch and the snowflake id epoch
+const SNOWFLAKE_EPOCH: u64 = 1632280000 * 1000;
+const SNOWFLAKE_TIME_SHIFT: u64 = 21;
+
+pub struct ChangeStream {
+    data_id: AtomicUsize,
+    blob_id: AtomicUsize,
+    max_size: AtomicUsize,
+    shipping: AtomicBool,
+    lagging: AtomicBool,
+    target: RwLock<Option<StreamTarget>>,
+}
+
+struct StreamTarget {
+    store: Store,
+    ids: SnowflakeIdGenerator,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamStatus {
+    pub shipped: u64,
+    pub pending: usize,
+    pub pending_size: usize,
+    pub oldest: Option<u64>,
+    pub resync: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChangeSet {
+    pub records: Vec<ChangeRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChangeRecord {
+    pub seq: u64,
+    pub timestamp: u64,
+    pub ops: Vec<ChangeOp>,
+}
+
+// Operations with all ids assigned by the primary resolved, blob contents are
+// not stored and are read from the blob store when the change is shipped.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ChangeOp {
+    AccountId(u32),
+    Collection(u8),
+    DocumentId(u32),
+    ChangeId(u64),
+    Set {
+        subspace: u8,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Add {
+        subspace: u8,
+        key: Vec<u8>,
+        by: i64,
+    },
+    Clear {
+        subspace: u8,
+        key: Vec<u8>,
+    },
+    Index {
+        field: u8,
+        key: Vec<u8>,
+        set: bool,
+    },
+    Bitmap {
+        bitmap: ChangeBitmap,
+        set: bool,
+    },
+    Log {
+        value: Vec<u8>,
+    },
+    DeleteRange {
+        subspace: u8,
+        from: Vec<u8>,
+        to: Vec<u8>,
+    },
+    BlobPut {
+        key: Vec<u8>,
+        data: Vec<u8>,
+    },
+    BlobDelete {
+        key: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ChangeBitmap {
+    DocumentIds,
+    TagId { field: u8, id: u32 },
+    TagText { field: u8, text: Vec<u8> },
+    Text { field: u8, hash: [u8; 8], len: u8 },
+}
+
+// Batch operations captured before the write, serialized once the ids
+// assigned by the store are known.
+struct CapturedBatch {
+    seq: u64,
+    timestamp: u64,
+    ops: Vec<Operation>,
+}
+
+impl ChangeStream {
+    pub const fn new() -> Self {
+        ChangeStream {
+            data_id: AtomicUsize::new(0),
+            blob_id: AtomicUsize::new(0),
+            max_size: AtomicUsize::new(0),
+            shipping: AtomicBool::new(false),
+            lagging: AtomicBool::new(false),
+            target: parking_lot::const_rwlock(None),
+        }
+    }
+
+    // Starts capturing the changes written to the given stores
+    pub fn enable(&self, data: &Store, blob: &BlobStore, node_id: u64, max_size: usize) {
+        *self.target.write() = Some(StreamTarget {
+            store: data.clone(),
+            ids: SnowflakeIdGenerator::with_node_id(node_id),
+        });
+        self.max_size.store(max_size, Ordering::Relaxed);
+        self.data_id.store(data.instance_id(), Ordering::Relaxed);
+        self.blob_id.store(blob.instance_id(), Ordering::Relaxed);
+    }
+
+    pub fn disable(&self) {
+        self.data_id.store(0, Ordering::Relaxed);
+        self.blob_id.store(0, Ordering::Relaxed);
+        *self.target.write() = None;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.data_id.load(Ordering::Relaxed) != 0
+    }
+
+    // Writes to the stream itself are not captured
+    pub(crate) fn captures_batch(&self, store: &Store, batch: &Batch) -> bool {
+        let id = self.data_id.load(Ordering::Relaxed);
+        id != 0
+            && id == store.instance_id()
+            && !batch.ops.iter().all(|op| {
+                matches!(op, Operation::Value {
+                    class: ValueClass::Any(AnyClass { subspace, .. }),
+                    ..
+                } if *subspace == SUBSPACE_REPLICATION)
+            })
+    }
+
+    pub(crate) fn captures_range(&self, store: &Store, subspace: u8) -> bool {
+        let id = self.data_id.load(Ordering::Relaxed);
+        id != 0 && id == store.instance_id() && subspace != SUBSPACE_REPLICATION
+    }
+
+    pub(crate) fn captures_blob_store(&self, store: &BlobStore) -> bool {
+        let id = self.blob_id.load(Ordering::Relaxed);
+        id != 0 && id == store.instance_id()
+    }
+
+    // Adds the change record to the batch, so that it is committed atomically
+    // with the changes it describes.
+    pub(crate) fn capture_batch(&self, batch: &mut Batch) -> trc::Result<()> {
+        let seq = self.next_seq()?;
+        let captured = CapturedBatch {
+            seq,
+            timestamp: now(),
+            ops: batch.ops.clone(),
+        };
+        batch.ops.push(Operation::Value {
+            class: record_class(seq),
+            op: ValueOp::Set(MaybeDynamicValue::Dynamic(Arc::new(captured))),
+        });
+        Ok(())
+    }
+
+    // Writes a change record for blob and range operations, which are not
+    // part of a batch.
+    pub(crate) async fn capture(&self, ops: Vec<ChangeOp>) -> trc::Result<()> {
+        let seq = self.next_seq()?;
+        let store = self.store()?;
+        let mut batch = BatchBuilder::new();
+        batch.ops.push(Operation::Value {
+            class: record_class(seq),
+            op: ValueOp::Set(MaybeDynamicValue::Static(
+                Bincode::new(ChangeRecord {
+                    seq,
+                    timestamp: now(),
+                    ops,
+                })
+                .serialize(),
+            )),
+        });
+        store.write(batch.build()).await.map(|_| ())
+    }
+
+    // Returns the oldest settled changes up to the given size, always
+    // including at least one record.
+    pub async fn pending(&self, max_size: usize) -> trc::Result<Option<ChangeSet>> {
+        let store = self.store()?;
+        if self.is_resync_required(&store).await? {
+            return Ok(None);
+        }
+
+        // Acknowledged records are removed one by one, so records that
+        // committed after newer ones were shipped are still pending.
+        let settled = self
+            .store_ids(|ids| ids.past_id(Duration::from_millis(SETTLE_TIME)))
+            .unwrap_or_default();
+        let mut size = 0;
+        let mut records = Vec::new();
+        store
+            .iterate(
+                IterateParams::new(record_key(0), record_key(settled)),
+                |_, value| {
+                    if !records.is_empty() && size + value.len() > max_size {
+                        return Ok(false);
+                    }
+                    size += value.len();
+                    records.push(Bincode::<ChangeRecord>::deserialize(value)?.inner);
+                    Ok(true)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        if !records.is_empty() {
+            Ok(Some(ChangeSet { records }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Removes the records acknowledged by the standby
+    pub async fn acknowledge(&self, seqs: &[u64]) -> trc::Result<()> {
+        let store = self.store()?;
+        let shipped = self.shipped(&store).await?;
+        let mut batch = BatchBuilder::new();
+        for seq in seqs {
+            batch.ops.push(Operation::Value {
+                class: record_class(*seq),
+                op: ValueOp::Clear,
+            });
+        }
+        if let Some(seq) = seqs.iter().copied().max().filter(|seq| *seq > shipped) {
+            batch.ops.push(Operation::Value {
+                class: stream_class(KEY_SHIPPED),
+                op: ValueOp::Set(MaybeDynamicValue::Static(seq.serialize())),
+            });
+        }
+        if !batch.is_empty() {
+            store
+                .write(batch.build())
+                .await
+                .caused_by(trc::location!())?;
+        }
+        Ok(())
+    }
+
+    // Starts a new stream once the standby was resynced from a copy of
+    // the primary.
+    pub async fn reset(&self) -> trc::Result<()> {
+        let store = self.store()?;
+        store
+            .delete_range(record_key(0), record_key(u64::MAX))
+            .await
+            .caused_by(trc::location!())?;
+        let mut batch = BatchBuilder::new();
+        for key in [KEY_RESYNC, KEY_SHIPPED] {
+            batch.ops.push(Operation::Value {
+                class: stream_class(key),
+                op: ValueOp::Clear,
+            });
+        }
+        store
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())
+            .map(|_| ())
+    }
+
+    pub fn try_start_shipping(&self) -> bool {
+        self.shipping
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    pub fn stop_shipping(&self) {
+        self.shipping.store(false, Ordering::Release);
+    }
+
+    // Returns true when the standby started lagging behind
+    pub fn set_lagging(&self, lagging: bool) -> bool {
+        !self.lagging.swap(lagging, Ordering::Relaxed) && lagging
+    }
+
+    // Obtains the size of the unshipped changes, a resync is required once
+    // they exceed the configured limit.
+    pub async fn status(&self) -> trc::Result<StreamStatus> {
+        let store = self.store()?;
+        let mut status = StreamStatus {
+            shipped: self.shipped(&store).await?,
+            resync: self.is_resync_required(&store).await?,
+            ..Default::default()
+        };
+        if status.resync {
+            return Ok(status);
+        }
+
+        let max_size = self.max_size.load(Ordering::Relaxed);
+        store
+            .iterate(
+                IterateParams::new(record_key(0), record_key(u64::MAX)),
+                |key, value| {
+                    if status.oldest.is_none() {
+                        status.oldest = Some(seq_timestamp(key.deserialize_be_u64(1)?));
+                    }
+                    status.pending += 1;
+                    status.pending_size += value.len();
+                    Ok(status.pending_size <= max_size)
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        if status.pending_size > max_size {
+            trc::event!(
+                Store(StoreEvent::ReplicationOverflow),
+                Size = status.pending_size,
+                Limit = max_size,
+            );
+            self.require_resync(&store).await?;
+            status = StreamStatus {
+                shipped: status.shipped,
+                resync: true,
+                ..Default::default()
+            };
+        }
+
+        Ok(status)
+    }
+
+    async fn shipped(&self, store: &Store) -> trc::Result<u64> {
+        store
+            .get_value::<u64>(ValueKey::from(stream_class(KEY_SHIPPED)))
+            .await
+            .map(|seq| seq.unwrap_or_default())
+            .caused_by(trc::location!())
+    }
+
+    async fn is_resync_required(&self, store: &Store) -> trc::Result<bool> {
+        store
+            .get_value::<()>(ValueKey::from(stream_class(KEY_RESYNC)))
+            .await
+            .map(|value| value.is_some())
+            .caused_by(trc::location!())
+    }
+
+    async fn require_resync(&self, store: &Store) -> trc::Result<()> {
+        let mut batch = BatchBuilder::new();
+        batch.ops.push(Operation::Value {
+            class: stream_class(KEY_RESYNC),
+            op: ValueOp::Set(MaybeDynamicValue::Static(vec![])),
+        });
+        store
+            .write(batch.build())
+            .await
+            .caused_by(trc::location!())?;
+        store
+            .delete_range(record_key(0), record_key(u64::MAX))
+            .await
+            .caused_by(trc::location!())
+    }
+
+    fn next_seq(&self) -> trc::Result<u64> {
+        self.store_ids(|ids| ids.generate()).ok_or_else(|| {
+            StoreEvent::ReplicationError
+                .into_err()
+                .caused_by(trc::location!())
+                .details("Failed to generate change record id.")
+        })
+    }
+
+    fn store_ids(&self, f: impl FnOnce(&SnowflakeIdGenerator) -> Option<u64>) -> Option<u64> {
+        self.target
+            .read()
+            .as_ref()
+            .and_then(|target| f(&target.ids))
+    }
+
+    fn store(&self) -> trc::Result<Store> {
+        self.target
+            .read()
+            .as_ref()
+            .map(|target| target.store.clone())
+            .ok_or_else(|| {
+                StoreEvent::ReplicationError
+                    .into_err()
+                    .caused_by(trc::location!())
+                    .details("Replication is not enabled.")
+            })
+    }
+}
+
+impl Default for ChangeStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamStatus {
+    // Seconds since the oldest unshipped change was committed
+    pub fn lag(&self) -> u64 {
+        self.oldest
+            .map_or(0, |timestamp| now().saturating_sub(timestamp))
+    }
+}
+
+impl SerializeWithId for CapturedBatch {
+    fn serialize_with_id(&self, ids: &AssignedIds) -> trc::Result<Vec<u8>> {
+        Ok(Bincode::new(ChangeRecord {
+            seq: self.seq,
+            timestamp: self.timestamp,
+            ops: ChangeOp::from_batch(&self.ops, ids)?,
+        })
+        .serialize())
+    }
+}
+
+fn record_key(seq: u64) -> AnyKey<Vec<u8>> {
+    let mut key = Vec::with_capacity(U64_LEN + 1);
+    key.push(KEY_RECORD);
+    key.extend_from_slice(&seq.to_be_bytes());
+    AnyKey {
+        subspace: SUBSPACE_REPLICATION,
+        key,
+    }
+}
+
+fn applied_key(seq: u64) -> AnyKey<Vec<u8>> {
+    let mut key = Vec::with_capacity(U64_LEN + 1);
+    key.push(KEY_APPLIED);
+    key.extend_from_slice(&seq.to_be_bytes());
+    AnyKey {
+        subspace: SUBSPACE_REPLICATION,
+        key,
+    }
+}
+
+fn applied_class<T>(seq: u64) -> ValueClass<T> {
+    let key = applied_key(seq);
+    ValueClass::Any(AnyClass {
+        subspace: key.subspace,
+        key: key.key,
+    })
+}
+
+fn record_class<T>(seq: u64) -> ValueClass<T> {
+    let key = record_key(seq);
+    ValueClass::Any(AnyClass {
+        subspace: key.subspace,
+        key: key.key,
+    })
+}
+
+fn stream_class<T>(key: u8) -> ValueClass<T> {
+    ValueClass::Any(AnyClass {
+        subspace: SUBSPACE_REPLICATION,
+        key: vec![key],
+    })
+}
+
+// Seconds since the UNIX epoch at which a change record id was generated
+fn seq_timestamp(seq: u64) -> u64 {
+    ((seq >> SNOWFLAKE_TIME_SHIFT) + SNOWFLAKE_EPOCH) / 1000
+}
+
+impl ChangeOp {
+    pub(crate) fn from_batch(ops: &[Operation], ids: &AssignedIds) -> trc::Result<Vec<ChangeOp>> {
+        let mut changes = Vec::with_capacity(ops.len());
+        let mut account_id = u32::MAX;
+        let mut collection = u8::MAX;
+        let mut document_id = u32::MAX;
+        let mut next_document_id = 0;
+
+        for op in ops {
+            match op {
+                Operation::AccountId {
+                    account_id: account_id_,
+                } => {
+                    account_id = *account_id_;
+                    changes.push(ChangeOp::AccountId(account_id));
+                }
+                Operation::Collection {
+                    collection: collection_,
+                } => {
+                    collection = *collection_;
+                    changes.push(ChangeOp::Collection(collection));
+                }
+                Operation::DocumentId {
+                    document_id: document_id_,
+                } => {
+                    document_id = *document_id_;
+                    changes.push(ChangeOp::DocumentId(document_id));
+                }
+                Operation::ChangeId { change_id } => {
+                    changes.push(ChangeOp::ChangeId(*change_id));
+                }
+                Operation::AssertValue { .. } => {}
+                Operation::Value { class, op } => {
+                    let subspace = class.subspace(collection);
+                    let key = class.serialize(account_id, collection, document_id, 0, ids.into());
+                    changes.push(match op {
+                        ValueOp::Set(value) => ChangeOp::Set {
+                            subspace,
+                            key,
+                            value: value.resolve(ids)?.into_owned(),
+                        },
+                        ValueOp::AtomicAdd(by) | ValueOp::AddAndGet(by) => ChangeOp::Add {
+                            subspace,
+                            key,
+                            by: *by,
+                        },
+                        ValueOp::Clear => ChangeOp::Clear { subspace, key },
+                    });
+                }
+                Operation::Index { field, key, set } => {
+                    changes.push(ChangeOp::Index {
+                        field: *field,
+                        key: key.clone(),
+                        set: *set,
+                    });
+                }
+                Operation::Bitmap { class, set } => {
+                    // Ids assigned by the primary are replayed explicitly
+                    if *set && matches!(class, BitmapClass::DocumentIds) && document_id == u32::MAX
+                    {
+                        document_id = ids.get_document_id(next_document_id)?;
+                        next_document_id += 1;
+                        changes.push(ChangeOp::DocumentId(document_id));
+                    }
+
+                    changes.push(ChangeOp::Bitmap {
+                        bitmap: match class {
+                            BitmapClass::DocumentIds => ChangeBitmap::DocumentIds,
+                            BitmapClass::Tag {
+                                field,
+                                value: TagValue::Id(id),
+                            } => ChangeBitmap::TagId {
+                                field: *field,
+                                id: id.resolve(ids)?,
+                            },
+                            BitmapClass::Tag {
+                                field,
+                                value: TagValue::Text(text),
+                            } => ChangeBitmap::TagText {
+                                field: *field,
+                                text: text.clone(),
+                            },
+                            BitmapClass::Text { field, token } => ChangeBitmap::Text {
+                                field: *field,
+                                hash: token.hash,
+                                len: token.len,
+                            },
+                        },
+                        set: *set,
+                    });
+                }
+                Operation::Log { set } => {
+                    changes.push(ChangeOp::Log {
+                        value: set.resolve(ids)?.into_owned(),
+                    });
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
+    fn into_operation(self) -> Operation {
+        match self {
+            ChangeOp::AccountId(account_id) => Operation::AccountId { account_id },
+            ChangeOp::Collection(collection) => Operation::Collection { collection },
+            ChangeOp::DocumentId(document_id) => Operation::DocumentId { document_id },
+            ChangeOp::ChangeId(change_id) => Operation::ChangeId { change_id },
+            ChangeOp::Set {
+                subspace,
+                key,
+                value,
+            } => Operation::Value {
+                class: ValueClass::Any(crate::write::AnyClass { subspace, key }),
+                op: ValueOp::Set(MaybeDynamicValue::Static(value)),
+            },
+            ChangeOp::Add { subspace, key, by } => Operation::Value {
+                class: ValueClass::Any(crate::write::AnyClass { subspace, key }),
+                op: ValueOp::AtomicAdd(by),
+            },
+            ChangeOp::Clear { subspace, key } => Operation::Value {
+                class: ValueClass::Any(crate::write::AnyClass { subspace, key }),
+                op: ValueOp::Clear,
+            },
+            ChangeOp::Index { field, key, set } => Operation::Index { field, key, set },
+            ChangeOp::Bitmap { bitmap, set } => Operation::Bitmap {
+                class: match bitmap {
+                    ChangeBitmap::DocumentIds => BitmapClass::DocumentIds,
+                    ChangeBitmap::TagId { field, id } => BitmapClass::Tag {
+                        field,
+                        value: TagValue::Id(MaybeDynamicId::Static(id)),
+                    },
+                    ChangeBitmap::TagText { field, text } => BitmapClass::Tag {
+                        field,
+                        value: TagValue::Text(text),
+                    },
+                    ChangeBitmap::Text { field, hash, len } => BitmapClass::Text {
+                        field,
+                        token: BitmapHash { hash, len },
+                    },
+                },
+                set,
+            },
+            ChangeOp::Log { value } => Operation::Log {
+                set: MaybeDynamicValue::Static(value),
+            },
+            ChangeOp::DeleteRange { .. }
+            | ChangeOp::BlobPut { .. }
+            | ChangeOp::BlobDelete { .. } => {
+                unreachable!("Not a batch operation")
+            }
+        }
+    }
+}
+
+impl ChangeSet {
+    // Applies the change set on a standby in the order it was shipped, returning
+    // the sequence of the last record that is applied. Each record is applied at
+    // most once, so change sets that were not acknowledged can be shipped again.
+    // Blob and range operations are not part of the batch but are idempotent,
+    // they are repeated when a record is shipped again after a failure.
+    pub async fn apply(self, store: &Store, blob_store: &BlobStore) -> trc::Result<u64> {
+        let mut newest = applied_position(store).await.caused_by(trc::location!())?;
+        let mut last_seq = 0;
+
+        for record in self.records {
+            if is_applied(store, record.seq).await? {
+                last_seq = record.seq;
+                continue;
+            }
+
+            let mut batch = Batch {
+                ops: vec![Operation::AssertValue {
+                    class: applied_class(record.seq),
+                    assert_value: AssertValue::None,
+                }],
+            };
+            for op in record.ops {
+                match op {
+                    ChangeOp::BlobPut { key, data } => {
+                        blob_store
+                            .put_blob(&key, &data)
+                            .await
+                            .caused_by(trc::location!())?;
+                    }
+                    ChangeOp::BlobDelete { key } => {
+                        blob_store
+                            .delete_blob(&key)
+                            .await
+                            .caused_by(trc::location!())?;
+                    }
+                    ChangeOp::DeleteRange { subspace, from, to } => {
+                        store
+                            .delete_range(
+                                AnyKey {
+                                    subspace,
+                                    key: from,
+                                },
+                                AnyKey { subspace, key: to },
+                            )
+                            .await
+                            .caused_by(trc::location!())?;
+                    }
+                    op => {
+                        batch.ops.push(op.into_operation());
+                    }
+                }
+            }
+
+            batch.ops.push(Operation::Value {
+                class: applied_class(record.seq),
+                op: ValueOp::Set(MaybeDynamicValue::Static(vec![])),
+            });
+            if record.seq > newest {
+                newest = record.seq;
+                batch.ops.push(Operation::Value {
+                    class: ValueClass::Config(APPLIED_KEY.to_vec()),
+                    op: ValueOp::Set(MaybeDynamicValue::Static(newest.serialize())),
+                });
+            }
+            match store.write(batch).await {
+                Ok(_) => {}
+                // Applied concurrently by a retried shipment
+                Err(err) if err.is_assertion_failure() => {}
+                Err(err) => return Err(err.caused_by(trc::location!())),
+            }
+            last_seq = record.seq;
+        }
+
+        // Records older than the retention period are no longer shipped again
+        if let Some(expired) = SnowflakeIdGenerator::new().past_id(APPLIED_RETENTION) {
+            store
+                .delete_range(applied_key(0), applied_key(expired))
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(last_seq)
+    }
+}
+
+async fn is_applied(store: &Store, seq: u64) -> trc::Result<bool> {
+    store
+        .get_value::<()>(ValueKey::from(applied_class(seq)))
+        .await
+        .map(|value| value.is_some())
+        .caused_by(trc::location!())
+}
+
+// Returns the sequence of the last change record applied by this standby
+pub async fn applied_position(store: &Store) -> trc::Result<u64> {
+    store
+        .get_value::<u64>(ValueKey::from(ValueClass::Config(APPLIED_KEY.to_vec())))
+        .await
+        .map(|seq| seq.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        write::{AssignedIds, BatchBuilder, BitmapClass, MaybeDynamicId, TagValue},
+        SUBSPACE_REPLICATION,
+    };
+
+    use super::{record_key, seq_timestamp, ChangeBitmap, ChangeOp, ChangeStream};
+
+    #[test]
+    fn change_stream() {
+        // Dynamic ids are resolved and auto-assigned document ids replayed
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(1)
+            .with_collection(2)
+            .create_document()
+            .tag(3, TagValue::Id(MaybeDynamicId::Dynamic(0)), 0)
+            .add(crate::write::ValueClass::Property(4), 5);
+        let mut batch = batch.build();
+        let ids = AssignedIds {
+            document_ids: vec![7],
+            counter_ids: vec![],
+        };
+        let changes = ChangeOp::from_batch(&batch.ops, &ids).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                ChangeOp::AccountId(1),
+                ChangeOp::Collection(2),
+                ChangeOp::DocumentId(u32::MAX),
+                ChangeOp::DocumentId(7),
+                ChangeOp::Bitmap {
+                    bitmap: ChangeBitmap::DocumentIds,
+                    set: true
+                },
+                ChangeOp::Bitmap {
+                    bitmap: ChangeBitmap::TagId { field: 3, id: 7 },
+                    set: true
+                },
+                ChangeOp::Add {
+                    subspace: crate::SUBSPACE_PROPERTY,
+                    key: crate::write::ValueClass::<u32>::Property(4).serialize(1, 2, 7, 0, None),
+                    by: 5
+                },
+            ]
+        );
+        assert!(matches!(
+            changes[4].clone().into_operation(),
+            crate::write::Operation::Bitmap {
+                class: BitmapClass::DocumentIds,
+                set: true
+            }
+        ));
+
+        // Records are added to the batch and ordered by their id
+        let stream = ChangeStream::new();
+        stream.enable(&crate::Store::None, &Default::default(), 1, 1024);
+        let ops = batch.ops.len();
+        stream.capture_batch(&mut batch).unwrap();
+        assert_eq!(batch.ops.len(), ops + 1);
+        let seq = stream.next_seq().unwrap();
+        let key = record_key(seq);
+        assert_eq!(key.subspace, SUBSPACE_REPLICATION);
+        assert!(key.key < record_key(seq + 1).key);
+        assert!(seq_timestamp(seq).abs_diff(crate::write::now()) <= 1);
+        stream.disable();
+        assert!(!stream.is_enabled());
+    }
+}
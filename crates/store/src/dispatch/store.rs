@@ -22,7 +22,11 @@ use crate::{
     SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, SUBSPACE_INDEXES, SUBSPACE_LOGS, U32_LEN,
 };
 
-use super::DocumentSet;
+use super::{
+    health::DATA_HEALTH,
+    replication::{ChangeOp, CHANGE_STREAM},
+    DocumentSet,
+};
 
 #[cfg(feature = "test_mode")]
 #[allow(clippy::type_complexity)]
@@ -39,6 +43,11 @@ impl Store {
     where
         U: Deserialize + 'static,
     {
+        #[cfg(feature = "test_mode")]
+        crate::backend::chaos::inject(crate::backend::chaos::ChaosOp::Read)
+            .await
+            .caused_by(trc::location!())?;
+
         match self {
             #[cfg(feature = "sqlite")]
             Self::SQLite(store) => store.get_value(key).await,
@@ -106,6 +115,11 @@ impl Store {
         params: IterateParams<T>,
         cb: impl for<'x> FnMut(&'x [u8], &'x [u8]) -> trc::Result<bool> + Sync + Send,
     ) -> trc::Result<()> {
+        #[cfg(feature = "test_mode")]
+        crate::backend::chaos::inject(crate::backend::chaos::ChaosOp::Read)
+            .await
+            .caused_by(trc::location!())?;
+
         let start_time = Instant::now();
         let result = match self {
             #[cfg(feature = "sqlite")]
@@ -124,10 +138,9 @@ impl Store {
         }
         .caused_by(trc::location!());
 
-        trc::event!(
-            Store(StoreEvent::DataIterate),
-            Elapsed = start_time.elapsed(),
-        );
+        let elapsed = start_time.elapsed();
+        DATA_HEALTH.record(&result, elapsed);
+        trc::event!(Store(StoreEvent::DataIterate), Elapsed = elapsed,);
 
         result
     }
@@ -154,7 +167,7 @@ impl Store {
         .caused_by(trc::location!())
     }
 
-    pub async fn write(&self, batch: Batch) -> trc::Result<AssignedIds> {
+    pub async fn write(&self, mut batch: Batch) -> trc::Result<AssignedIds> {
         #[cfg(feature = "test_mode")]
         if std::env::var("PARANOID_WRITE").map_or(false, |v| v == "1") {
             let mut account_id = u32::MAX;
@@ -245,6 +258,18 @@ impl Store {
             return Ok(AssignedIds::default());
         }
 
+        #[cfg(feature = "test_mode")]
+        crate::backend::chaos::inject(crate::backend::chaos::ChaosOp::Write)
+            .await
+            .caused_by(trc::location!())?;
+
+        // Record the changes for the standby in the same transaction
+        if CHANGE_STREAM.captures_batch(self, &batch) {
+            CHANGE_STREAM
+                .capture_batch(&mut batch)
+                .caused_by(trc::location!())?;
+        }
+
         let start_time = Instant::now();
         let ops = batch.ops.len();
 
@@ -264,11 +289,16 @@ impl Store {
             Self::None => Err(trc::StoreEvent::NotConfigured.into()),
         };
 
-        trc::event!(
-            Store(StoreEvent::DataWrite),
-            Elapsed = start_time.elapsed(),
-            Total = ops,
-        );
+        let elapsed = start_time.elapsed();
+        DATA_HEALTH.record(&result, elapsed);
+        trc::event!(Store(StoreEvent::DataWrite), Elapsed = elapsed, Total = ops,);
+
+        #[cfg(feature = "test_mode")]
+        if result.is_ok() {
+            crate::backend::chaos::inject(crate::backend::chaos::ChaosOp::Commit)
+                .await
+                .caused_by(trc::location!())?;
+        }
 
         result
     }
@@ -323,6 +353,19 @@ impl Store {
     }
 
     pub async fn delete_range(&self, from: impl Key, to: impl Key) -> trc::Result<()> {
+        // Ranges are deleted on the standby once the primary deleted them as well,
+        // so the change is recorded before deleting the range.
+        if CHANGE_STREAM.captures_range(self, from.subspace()) {
+            CHANGE_STREAM
+                .capture(vec![ChangeOp::DeleteRange {
+                    subspace: from.subspace(),
+                    from: from.serialize(0),
+                    to: to.serialize(0),
+                }])
+                .await
+                .caused_by(trc::location!())?;
+        }
+
         match self {
             #[cfg(feature = "sqlite")]
             Self::SQLite(store) => store.delete_range(from, to).await,
@@ -442,8 +485,25 @@ impl Store {
             .caused_by(trc::location!())?;
         }
 
+        // Delete permissions granted to the account
+        self.delete_range(
+            ValueKey {
+                account_id: 0,
+                collection: 0,
+                document_id: 0,
+                class: ValueClass::Acl(account_id),
+            },
+            ValueKey {
+                account_id: u32::MAX,
+                collection: u8::MAX,
+                document_id: u32::MAX,
+                class: ValueClass::Acl(account_id),
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
         for (from_class, to_class) in [
-            (ValueClass::Acl(account_id), ValueClass::Acl(account_id + 1)),
             (ValueClass::Property(0), ValueClass::Property(0)),
             (
                 ValueClass::FtsIndex(BitmapHash {
@@ -581,6 +641,7 @@ impl Store {
             SUBSPACE_TELEMETRY_SPAN,
             SUBSPACE_TELEMETRY_METRIC,
             SUBSPACE_TELEMETRY_INDEX,
+            SUBSPACE_REPLICATION,
         ] {
             self.delete_range(
                 AnyKey {
@@ -767,6 +828,7 @@ impl Store {
             (SUBSPACE_TELEMETRY_SPAN, true),
             (SUBSPACE_TELEMETRY_METRIC, true),
             (SUBSPACE_TELEMETRY_INDEX, true),
+            (SUBSPACE_REPLICATION, true),
         ] {
             let from_key = crate::write::AnyKey {
                 subspace,
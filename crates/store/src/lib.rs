@@ -150,8 +150,8 @@ pub const SUBSPACE_FTS_INDEX: u8 = b'g';
 pub const SUBSPACE_TELEMETRY_SPAN: u8 = b'o';
 pub const SUBSPACE_TELEMETRY_INDEX: u8 = b'w';
 pub const SUBSPACE_TELEMETRY_METRIC: u8 = b'x';
+pub const SUBSPACE_REPLICATION: u8 = b'y';
 
-pub const SUBSPACE_RESERVED_1: u8 = b'y';
 pub const SUBSPACE_RESERVED_2: u8 = b'z';
 
 #[derive(Clone)]
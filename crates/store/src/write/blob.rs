@@ -115,6 +115,47 @@ impl Store {
         self.get_value::<()>(key).await.map(|v| v.is_some())
     }
 
+    pub async fn blob_links(
+        &self,
+        hash: impl AsRef<BlobHash> + Sync + Send,
+        account_id: u32,
+    ) -> trc::Result<Vec<(u8, u32)>> {
+        let from_key = ValueKey {
+            account_id,
+            collection: 0,
+            document_id: 0,
+            class: ValueClass::Blob(BlobOp::Link {
+                hash: hash.as_ref().clone(),
+            }),
+        };
+        let to_key = ValueKey {
+            account_id,
+            collection: u8::MAX,
+            document_id: u32::MAX,
+            class: ValueClass::Blob(BlobOp::Link {
+                hash: hash.as_ref().clone(),
+            }),
+        };
+        let mut links = Vec::new();
+        self.iterate(
+            IterateParams::new(from_key, to_key).ascending().no_values(),
+            |key, _| {
+                let collection = *key
+                    .get(BLOB_HASH_LEN + U32_LEN)
+                    .ok_or_else(|| trc::Error::corrupted_key(key, None, trc::location!()))?;
+                // Skip links to non-document ids
+                if collection != u8::MAX {
+                    links.push((collection, key.deserialize_be_u32(key.len() - U32_LEN)?));
+                }
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+        Ok(links)
+    }
+
     pub async fn purge_blobs(&self, blob_store: BlobStore) -> trc::Result<()> {
         // Remove expired temporary blobs
         let from_key = ValueKey {
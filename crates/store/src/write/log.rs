@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::sync::Arc;
+
 use ahash::AHashSet;
 use utils::{codec::leb128::Leb128Vec, map::vec_map::VecMap};
 
@@ -230,6 +232,6 @@ impl SerializeWithId for LogInsert {
 
 impl From<LogInsert> for MaybeDynamicValue {
     fn from(value: LogInsert) -> Self {
-        MaybeDynamicValue::Dynamic(Box::new(value))
+        MaybeDynamicValue::Dynamic(Arc::new(value))
     }
 }
@@ -10,7 +10,8 @@ use std::{
     fmt::{self, Formatter},
     hash::Hash,
     slice::Iter,
-    time::{Duration, SystemTime},
+    sync::Arc,
+    time::Duration,
 };
 
 use nlp::tokenizers::word::WordTokenizer;
@@ -41,9 +42,10 @@ pub trait ResolveId {
     fn resolve_id(&self, ids: Option<&AssignedIds>) -> u32;
 }
 
+#[derive(Clone)]
 pub enum MaybeDynamicValue {
     Static(Vec<u8>),
-    Dynamic(Box<dyn SerializeWithId>),
+    Dynamic(Arc<dyn SerializeWithId>),
 }
 
 #[derive(Debug, PartialEq, Clone, Copy, Eq, Hash)]
@@ -76,7 +78,7 @@ pub const F_INDEX: u32 = 1 << 1;
 pub const F_BITMAP: u32 = 1 << 2;
 pub const F_CLEAR: u32 = 1 << 3;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Batch {
     pub ops: Vec<Operation>,
 }
@@ -86,7 +88,7 @@ pub struct BatchBuilder {
     pub ops: Vec<Operation>,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Operation {
     AccountId {
         account_id: u32,
@@ -234,7 +236,7 @@ pub struct ReportEvent {
     pub domain: String,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum ValueOp {
     Set(MaybeDynamicValue),
     AtomicAdd(i64),
@@ -615,9 +617,7 @@ impl Operation {
 
 #[inline(always)]
 pub fn now() -> u64 {
-    SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .map_or(0, |d| d.as_secs())
+    utils::clock::timestamp()
 }
 
 impl<T> AsRef<ValueClass<T>> for ValueClass<T> {
@@ -856,7 +856,7 @@ impl From<MaybeDynamicId> for MaybeDynamicValue {
         match value {
             MaybeDynamicId::Static(id) => MaybeDynamicValue::Static(id.serialize()),
             MaybeDynamicId::Dynamic(idx) => {
-                MaybeDynamicValue::Dynamic(Box::new(DynamicDocumentId(idx)))
+                MaybeDynamicValue::Dynamic(Arc::new(DynamicDocumentId(idx)))
             }
         }
     }
@@ -175,6 +175,7 @@ impl HousekeeperEvent {
             HousekeeperEvent::PurgeAccounts => "Purging accounts",
             HousekeeperEvent::PurgeSessions => "Purging sessions",
             HousekeeperEvent::PurgeStore => "Purging store",
+            HousekeeperEvent::RoleAddressMissing => "Required role address missing",
         }
     }
 
@@ -186,6 +187,9 @@ impl HousekeeperEvent {
             HousekeeperEvent::PurgeAccounts => "Purging accounts",
             HousekeeperEvent::PurgeSessions => "Purging sessions",
             HousekeeperEvent::PurgeStore => "Purging store",
+            HousekeeperEvent::RoleAddressMissing => {
+                "A required role address such as postmaster or abuse would bounce"
+            }
         }
     }
 }
@@ -419,6 +423,17 @@ impl SmtpEvent {
             SmtpEvent::SpfFromFail => "SPF From check failed",
             SmtpEvent::DmarcPass => "DMARC check passed",
             SmtpEvent::DmarcFail => "DMARC check failed",
+            SmtpEvent::BimiPass => "BIMI check passed",
+            SmtpEvent::BimiFail => "BIMI check failed",
+            SmtpEvent::Greylisted => "Recipient greylisted",
+            SmtpEvent::GreylistPassed => "Greylisting check passed",
+            SmtpEvent::CallAheadPassed => "Call-ahead verification passed",
+            SmtpEvent::CallAheadFailed => "Call-ahead verification failed",
+            SmtpEvent::CallAheadError => "Call-ahead verification error",
+            SmtpEvent::HttpLookupAccept => "HTTP lookup accepted the transaction",
+            SmtpEvent::HttpLookupReject => "HTTP lookup rejected the transaction",
+            SmtpEvent::HttpLookupError => "HTTP lookup failed",
+            SmtpEvent::InboundDeferred => "Inbound message deferred",
             SmtpEvent::IprevPass => "IPREV check passed",
             SmtpEvent::IprevFail => "IPREV check failed",
             SmtpEvent::TooManyMessages => "Too many messages",
@@ -458,6 +473,7 @@ impl SmtpEvent {
             SmtpEvent::FutureReleaseInvalid => "Invalid FUTURE RELEASE parameter",
             SmtpEvent::MtPriorityDisabled => "MT-PRIORITY extension disabled",
             SmtpEvent::MtPriorityInvalid => "Invalid MT-PRIORITY parameter",
+            SmtpEvent::MtPriorityLowered => "MT-PRIORITY lowered",
             SmtpEvent::DsnDisabled => "DSN extension disabled",
             SmtpEvent::AuthNotAllowed => "Authentication not allowed",
             SmtpEvent::AuthMechanismNotSupported => "Auth mechanism not supported",
@@ -513,6 +529,23 @@ impl SmtpEvent {
             SmtpEvent::SpfFromFail => "MAIL FROM identity failed SPF check",
             SmtpEvent::DmarcPass => "Successful DMARC verification",
             SmtpEvent::DmarcFail => "Failed to verify DMARC policy",
+            SmtpEvent::BimiPass => "Brand indicator was validated and added to the message",
+            SmtpEvent::BimiFail => "Failed to validate the sender's brand indicator",
+            SmtpEvent::Greylisted => "The recipient was temporarily rejected by greylisting",
+            SmtpEvent::GreylistPassed => "The sender retried after the greylisting delay",
+            SmtpEvent::CallAheadPassed => "The destination server accepted the recipient",
+            SmtpEvent::CallAheadFailed => "The destination server rejected the recipient",
+            SmtpEvent::CallAheadError => {
+                "The destination server could not be reached to verify the recipient"
+            }
+            SmtpEvent::HttpLookupAccept => "The external HTTP endpoint accepted the envelope",
+            SmtpEvent::HttpLookupReject => "The external HTTP endpoint rejected the envelope",
+            SmtpEvent::HttpLookupError => {
+                "The external HTTP endpoint could not be queried, the failure policy was applied"
+            }
+            SmtpEvent::InboundDeferred => {
+                "The message was temporarily rejected while the store is unhealthy or low on disk space"
+            }
             SmtpEvent::IprevPass => "Reverse IP check passed",
             SmtpEvent::IprevFail => "Reverse IP check failed",
             SmtpEvent::TooManyMessages => {
@@ -576,6 +609,9 @@ impl SmtpEvent {
             SmtpEvent::FutureReleaseInvalid => "The FUTURE RELEASE parameter is invalid",
             SmtpEvent::MtPriorityDisabled => "The MT-PRIORITY extension is disabled",
             SmtpEvent::MtPriorityInvalid => "The MT-PRIORITY parameter is invalid",
+            SmtpEvent::MtPriorityLowered => {
+                "The requested priority exceeds the maximum allowed for the sender"
+            }
             SmtpEvent::DsnDisabled => "The DSN extension is disabled",
             SmtpEvent::AuthNotAllowed => "Authentication is not allowed on this listener",
             SmtpEvent::AuthMechanismNotSupported => {
@@ -640,6 +676,7 @@ impl DeliveryEvent {
             DeliveryEvent::StartTlsUnavailable => "STARTTLS unavailable",
             DeliveryEvent::StartTlsError => "STARTTLS error",
             DeliveryEvent::StartTlsDisabled => "STARTTLS disabled",
+            DeliveryEvent::RequireTlsUnavailable => "REQUIRETLS unavailable",
             DeliveryEvent::ImplicitTlsError => "Implicit TLS error",
             DeliveryEvent::ConcurrencyLimitExceeded => "Concurrency limit exceeded",
             DeliveryEvent::RateLimitExceeded => "Rate limit exceeded",
@@ -688,6 +725,7 @@ impl DeliveryEvent {
             DeliveryEvent::StartTls => "Requesting a TLS connection with the remote server",
             DeliveryEvent::StartTlsUnavailable => "The remote server does not support STARTTLS",
             DeliveryEvent::StartTlsError => "It was not possible to establish a TLS connection",
+            DeliveryEvent::RequireTlsUnavailable => "The remote server does not support REQUIRETLS",
             DeliveryEvent::StartTlsDisabled => {
                 "STARTTLS has been disabled in the configuration for this host"
             }
@@ -725,6 +763,13 @@ impl QueueEvent {
             QueueEvent::QueueReport => "Queued report for delivery",
             QueueEvent::QueueDsn => "Queued DSN for delivery",
             QueueEvent::QueueAutogenerated => "Queued autogenerated message for delivery",
+            QueueEvent::MessageAccepted => "Message accepted for delivery",
+            QueueEvent::MessageDelivered => "Message delivered to recipient",
+            QueueEvent::MessageDeferred => "Message delivery to recipient deferred",
+            QueueEvent::MessageBounced => "Message delivery to recipient failed",
+            QueueEvent::MessageExpired => "Message delivery to recipient expired",
+            QueueEvent::AdaptiveThrottleReduced => "Adaptive throttle reduced",
+            QueueEvent::AdaptiveThrottleRecovered => "Adaptive throttle recovered",
         }
     }
 
@@ -744,6 +789,23 @@ impl QueueEvent {
             QueueEvent::QueueReport => "A new report was queued for delivery",
             QueueEvent::QueueDsn => "A delivery status notification was queued for delivery",
             QueueEvent::QueueAutogenerated => "A system generated message was queued for delivery",
+            QueueEvent::MessageAccepted => {
+                "A message was accepted and queued, the event includes its Message-ID"
+            }
+            QueueEvent::MessageDelivered => "The message was delivered to the recipient",
+            QueueEvent::MessageDeferred => {
+                "Delivery to the recipient failed temporarily and will be retried"
+            }
+            QueueEvent::MessageBounced => "Delivery to the recipient failed permanently",
+            QueueEvent::MessageExpired => {
+                "The message could not be delivered to the recipient before it expired"
+            }
+            QueueEvent::AdaptiveThrottleReduced => {
+                "The remote host signaled throttling, concurrency and sending rate were reduced"
+            }
+            QueueEvent::AdaptiveThrottleRecovered => {
+                "Concurrency and sending rate for the remote host were restored"
+            }
         }
     }
 }
@@ -1021,6 +1083,7 @@ impl SpamEvent {
             SpamEvent::Classify => "Classifying message for spam",
             SpamEvent::ClassifyError => "Error classifying message for spam",
             SpamEvent::NotEnoughTrainingData => "Not enough training data for spam filter",
+            SpamEvent::TokenizerChanged => "Spam filter tokenizer settings changed",
         }
     }
 
@@ -1036,6 +1099,9 @@ impl SpamEvent {
             SpamEvent::NotEnoughTrainingData => {
                 "There is not enough training data for the spam filter"
             }
+            SpamEvent::TokenizerChanged => {
+                "The spam filter tokenizer settings have changed since the tokens were learned"
+            }
         }
     }
 }
@@ -1055,6 +1121,8 @@ impl SieveEvent {
             SieveEvent::UnexpectedError => "Unexpected Sieve error",
             SieveEvent::NotSupported => "Sieve action not supported",
             SieveEvent::QuotaExceeded => "Sieve quota exceeded",
+            SieveEvent::SendNotification => "Sieve sending notification",
+            SieveEvent::NotificationError => "Sieve notification failed",
         }
     }
 
@@ -1074,6 +1142,8 @@ impl SieveEvent {
             SieveEvent::UnexpectedError => "An unexpected error occurred with the Sieve script",
             SieveEvent::NotSupported => "The Sieve action is not supported",
             SieveEvent::QuotaExceeded => "The Sieve quota was exceeded",
+            SieveEvent::SendNotification => "The Sieve script is sending a notification",
+            SieveEvent::NotificationError => "The Sieve notification could not be delivered",
         }
     }
 }
@@ -1153,6 +1223,9 @@ impl ServerEvent {
             ServerEvent::StartupError => "Server startup error",
             ServerEvent::ThreadError => "Server thread error",
             ServerEvent::Licensing => "Server licensing event",
+            ServerEvent::MaintenanceMode => "Write rejected during maintenance",
+            ServerEvent::SchemaMigration => "Store schema migrated",
+            ServerEvent::SchemaMigrationLocked => "Store schema migration in progress",
         }
     }
 
@@ -1163,6 +1236,13 @@ impl ServerEvent {
             ServerEvent::StartupError => "An error occurred while starting the server",
             ServerEvent::ThreadError => "An error occurred with a server thread",
             ServerEvent::Licensing => "A licensing event occurred",
+            ServerEvent::MaintenanceMode => {
+                "A write operation was rejected because the server is in read-only maintenance mode"
+            }
+            ServerEvent::SchemaMigration => "A store schema migration step was applied",
+            ServerEvent::SchemaMigrationLocked => {
+                "Another node is migrating the store schema, waiting for it to finish"
+            }
         }
     }
 }
@@ -1274,6 +1354,7 @@ impl PurgeEvent {
             PurgeEvent::Error => "Purge error",
             PurgeEvent::PurgeActive => "Active purge in progress",
             PurgeEvent::AutoExpunge => "Auto-expunge executed",
+            PurgeEvent::Pop3Expire => "POP3 expire executed",
             PurgeEvent::TombstoneCleanup => "Tombstone cleanup executed",
         }
     }
@@ -1286,6 +1367,7 @@ impl PurgeEvent {
             PurgeEvent::Error => "An error occurred with the purge",
             PurgeEvent::PurgeActive => "An active purge is in progress",
             PurgeEvent::AutoExpunge => "Auto-expunge has been executed",
+            PurgeEvent::Pop3Expire => "Messages retrieved over POP3 have been expired",
             PurgeEvent::TombstoneCleanup => "Tombstone cleanup has been executed",
         }
     }
@@ -1538,6 +1620,7 @@ impl StoreEvent {
             StoreEvent::ElasticsearchError => "ElasticSearch error",
             StoreEvent::RedisError => "Redis error",
             StoreEvent::S3Error => "S3 error",
+            StoreEvent::HttpError => "HTTP error",
             StoreEvent::FilesystemError => "Filesystem error",
             StoreEvent::PoolError => "Connection pool error",
             StoreEvent::DataCorruption => "Data corruption detected",
@@ -1549,6 +1632,16 @@ impl StoreEvent {
             StoreEvent::UnexpectedError => "Unexpected store error",
             StoreEvent::CryptoError => "Store crypto error",
             StoreEvent::BlobMissingMarker => "Blob missing marker",
+            StoreEvent::HealthDegraded => "Store degraded",
+            StoreEvent::HealthCritical => "Store critical",
+            StoreEvent::HealthRecovered => "Store recovered",
+            StoreEvent::ReplicationError => "Replication error",
+            StoreEvent::ReplicationLag => "Replication lagging",
+            StoreEvent::ReplicationOverflow => "Replication buffer overflow",
+            StoreEvent::ReplicationPromoted => "Standby promoted",
+            StoreEvent::DiskSpaceLow => "Disk space low",
+            StoreEvent::DiskSpaceCritical => "Disk space critical",
+            StoreEvent::DiskSpaceRecovered => "Disk space recovered",
             StoreEvent::SqlQuery => "SQL query executed",
             StoreEvent::LdapQuery => "LDAP query executed",
             StoreEvent::LdapBind => "LDAP bind operation",
@@ -1572,6 +1665,7 @@ impl StoreEvent {
             StoreEvent::ElasticsearchError => "An ElasticSearch error occurred",
             StoreEvent::RedisError => "A Redis error occurred",
             StoreEvent::S3Error => "An S3 error occurred",
+            StoreEvent::HttpError => "An HTTP error occurred",
             StoreEvent::FilesystemError => "A filesystem error occurred",
             StoreEvent::PoolError => "A connection pool error occurred",
             StoreEvent::DataCorruption => "Data corruption was detected",
@@ -1583,6 +1677,32 @@ impl StoreEvent {
             StoreEvent::UnexpectedError => "An unexpected store error occurred",
             StoreEvent::CryptoError => "A store crypto error occurred",
             StoreEvent::BlobMissingMarker => "The blob is missing a marker",
+            StoreEvent::HealthDegraded => {
+                "The store error rate or latency exceeded the warning threshold"
+            }
+            StoreEvent::HealthCritical => {
+                "The store error rate or latency exceeded the critical threshold"
+            }
+            StoreEvent::HealthRecovered => "The store error rate and latency are back to normal",
+            StoreEvent::ReplicationError => {
+                "Changes could not be shipped to or applied on the standby"
+            }
+            StoreEvent::ReplicationLag => {
+                "The standby is behind the primary by more than the configured threshold"
+            }
+            StoreEvent::ReplicationOverflow => {
+                "The replication buffer is full and the standby requires a full resync"
+            }
+            StoreEvent::ReplicationPromoted => "The standby was promoted to primary",
+            StoreEvent::DiskSpaceLow => {
+                "The free space on a storage volume fell below the warning threshold"
+            }
+            StoreEvent::DiskSpaceCritical => {
+                "The free space on a storage volume fell below the critical threshold"
+            }
+            StoreEvent::DiskSpaceRecovered => {
+                "The free space on all storage volumes is back to normal"
+            }
             StoreEvent::SqlQuery => "An SQL query was executed",
             StoreEvent::LdapQuery => "An LDAP query was executed",
             StoreEvent::LdapBind => "An LDAP bind operation was executed",
@@ -1603,6 +1723,7 @@ impl MessageIngestEvent {
             MessageIngestEvent::ImapAppend => "Message appended via IMAP",
             MessageIngestEvent::JmapAppend => "Message appended via JMAP",
             MessageIngestEvent::Duplicate => "Skipping duplicate message",
+            MessageIngestEvent::Itip => "Calendar scheduling message processed",
             MessageIngestEvent::Error => "Message ingestion error",
         }
     }
@@ -1614,6 +1735,9 @@ impl MessageIngestEvent {
             MessageIngestEvent::ImapAppend => "The message has been appended via IMAP",
             MessageIngestEvent::JmapAppend => "The message has been appended via JMAP",
             MessageIngestEvent::Duplicate => "The message is a duplicate and has been skipped",
+            MessageIngestEvent::Itip => {
+                "A calendar invitation, cancellation or reply has been applied to the calendar"
+            }
             MessageIngestEvent::Error => "An error occurred while ingesting the message",
         }
     }
@@ -1763,6 +1887,7 @@ impl ResourceEvent {
             ResourceEvent::Error => "Resource error",
             ResourceEvent::DownloadExternal => "Downloading external resource",
             ResourceEvent::WebadminUnpacked => "Webadmin resource unpacked",
+            ResourceEvent::FeedStale => "Feed is stale",
         }
     }
 
@@ -1773,6 +1898,7 @@ impl ResourceEvent {
             ResourceEvent::Error => "An error occurred with the resource",
             ResourceEvent::DownloadExternal => "The external resource is being downloaded",
             ResourceEvent::WebadminUnpacked => "The webadmin resource has been unpacked",
+            ResourceEvent::FeedStale => "A feed has not been successfully fetched for too long",
         }
     }
 }
@@ -1785,6 +1911,10 @@ impl SecurityEvent {
             SecurityEvent::LoiterBan => "Banned due to loitering",
             SecurityEvent::IpBlocked => "Blocked IP address",
             SecurityEvent::Unauthorized => "Unauthorized access",
+            SecurityEvent::EarlyTalker => "Client talked before greeting",
+            SecurityEvent::EarlyTalkerBan => "Banned due to talking before greeting",
+            SecurityEvent::ScriptBan => "Banned by Sieve script",
+            SecurityEvent::SlowTransfer => "Transfer rate too low",
         }
     }
 
@@ -1799,6 +1929,14 @@ impl SecurityEvent {
             SecurityEvent::LoiterBan => "IP address was banned due to multiple loitering events",
             SecurityEvent::IpBlocked => "Rejected connection from blocked IP address",
             SecurityEvent::Unauthorized => "Account does not have permission to access resource",
+            SecurityEvent::EarlyTalker => "Client sent data before the server greeting",
+            SecurityEvent::EarlyTalkerBan => {
+                "IP address was banned due to multiple early talking events"
+            }
+            SecurityEvent::ScriptBan => "IP address was banned by a trusted Sieve script",
+            SecurityEvent::SlowTransfer => {
+                "Client was disconnected for sending a request below the minimum transfer rate"
+            }
         }
     }
 }
@@ -31,6 +31,7 @@ impl EventType {
                 | StoreEvent::ElasticsearchError
                 | StoreEvent::RedisError
                 | StoreEvent::S3Error
+                | StoreEvent::HttpError
                 | StoreEvent::FilesystemError
                 | StoreEvent::PoolError
                 | StoreEvent::DataCorruption
@@ -40,7 +41,17 @@ impl EventType {
                 | StoreEvent::NotSupported
                 | StoreEvent::UnexpectedError
                 | StoreEvent::CryptoError => Level::Error,
-                StoreEvent::BlobMissingMarker => Level::Warn,
+                StoreEvent::BlobMissingMarker
+                | StoreEvent::HealthDegraded
+                | StoreEvent::ReplicationLag
+                | StoreEvent::DiskSpaceLow => Level::Warn,
+                StoreEvent::HealthCritical
+                | StoreEvent::ReplicationError
+                | StoreEvent::ReplicationOverflow
+                | StoreEvent::DiskSpaceCritical => Level::Error,
+                StoreEvent::HealthRecovered
+                | StoreEvent::ReplicationPromoted
+                | StoreEvent::DiskSpaceRecovered => Level::Info,
             },
             EventType::Jmap(_) => Level::Debug,
             EventType::Imap(event) => match event {
@@ -137,6 +148,7 @@ impl EventType {
                 | SmtpEvent::FutureReleaseInvalid
                 | SmtpEvent::MtPriorityDisabled
                 | SmtpEvent::MtPriorityInvalid
+                | SmtpEvent::MtPriorityLowered
                 | SmtpEvent::DsnDisabled
                 | SmtpEvent::AuthExchangeTooLong
                 | SmtpEvent::AlreadyAuthenticated
@@ -176,6 +188,17 @@ impl EventType {
                 | SmtpEvent::SpfFromFail
                 | SmtpEvent::DmarcPass
                 | SmtpEvent::DmarcFail
+                | SmtpEvent::BimiPass
+                | SmtpEvent::BimiFail
+                | SmtpEvent::Greylisted
+                | SmtpEvent::GreylistPassed
+                | SmtpEvent::CallAheadPassed
+                | SmtpEvent::CallAheadFailed
+                | SmtpEvent::CallAheadError
+                | SmtpEvent::HttpLookupAccept
+                | SmtpEvent::HttpLookupReject
+                | SmtpEvent::HttpLookupError
+                | SmtpEvent::InboundDeferred
                 | SmtpEvent::IprevPass
                 | SmtpEvent::IprevFail
                 | SmtpEvent::TooManyMessages
@@ -249,6 +272,7 @@ impl EventType {
                 ResourceEvent::NotFound => Level::Debug,
                 ResourceEvent::BadParameters | ResourceEvent::Error => Level::Error,
                 ResourceEvent::DownloadExternal | ResourceEvent::WebadminUnpacked => Level::Info,
+                ResourceEvent::FeedStale => Level::Warn,
             },
             EventType::Arc(event) => match event {
                 ArcEvent::ChainTooLong
@@ -270,6 +294,7 @@ impl EventType {
                 PurgeEvent::Error => Level::Error,
                 PurgeEvent::PurgeActive
                 | PurgeEvent::AutoExpunge
+                | PurgeEvent::Pop3Expire
                 | PurgeEvent::TombstoneCleanup => Level::Debug,
             },
             EventType::Eval(event) => match event {
@@ -278,9 +303,12 @@ impl EventType {
                 EvalEvent::DirectoryNotFound => Level::Warn,
             },
             EventType::Server(event) => match event {
-                ServerEvent::Startup | ServerEvent::Shutdown | ServerEvent::Licensing => {
-                    Level::Info
-                }
+                ServerEvent::Startup
+                | ServerEvent::Shutdown
+                | ServerEvent::Licensing
+                | ServerEvent::MaintenanceMode
+                | ServerEvent::SchemaMigration
+                | ServerEvent::SchemaMigrationLocked => Level::Info,
                 ServerEvent::StartupError | ServerEvent::ThreadError => Level::Error,
             },
             EventType::Acme(event) => match event {
@@ -324,9 +352,10 @@ impl EventType {
                 SieveEvent::NotSupported
                 | SieveEvent::QuotaExceeded
                 | SieveEvent::ListNotFound
+                | SieveEvent::NotificationError
                 | SieveEvent::ScriptNotFound
                 | SieveEvent::MessageTooLarge => Level::Warn,
-                SieveEvent::SendMessage => Level::Info,
+                SieveEvent::SendMessage | SieveEvent::SendNotification => Level::Info,
                 SieveEvent::UnexpectedError => Level::Error,
                 SieveEvent::ActionAccept
                 | SieveEvent::RuntimeError
@@ -342,7 +371,7 @@ impl EventType {
                 | SpamEvent::Classify
                 | SpamEvent::NotEnoughTrainingData
                 | SpamEvent::TrainBalance => Level::Debug,
-                SpamEvent::ListUpdated => Level::Info,
+                SpamEvent::ListUpdated | SpamEvent::TokenizerChanged => Level::Info,
             },
             EventType::Http(event) => match event {
                 HttpEvent::ConnectionStart | HttpEvent::ConnectionEnd => Level::Debug,
@@ -375,6 +404,7 @@ impl EventType {
                 | HousekeeperEvent::PurgeStore
                 | HousekeeperEvent::Stop => Level::Info,
                 HousekeeperEvent::Schedule => Level::Debug,
+                HousekeeperEvent::RoleAddressMissing => Level::Warn,
             },
             EventType::FtsIndex(event) => match event {
                 FtsIndexEvent::Index => Level::Info,
@@ -446,6 +476,7 @@ impl EventType {
                 | DeliveryEvent::StartTlsUnavailable
                 | DeliveryEvent::StartTlsError
                 | DeliveryEvent::StartTlsDisabled
+                | DeliveryEvent::RequireTlsUnavailable
                 | DeliveryEvent::ImplicitTlsError
                 | DeliveryEvent::DoubleBounce => Level::Info,
                 DeliveryEvent::ConcurrencyLimitExceeded
@@ -471,7 +502,14 @@ impl EventType {
                 | QueueEvent::RateLimitExceeded
                 | QueueEvent::ConcurrencyLimitExceeded
                 | QueueEvent::Rescheduled
-                | QueueEvent::QuotaExceeded => Level::Info,
+                | QueueEvent::QuotaExceeded
+                | QueueEvent::MessageAccepted
+                | QueueEvent::MessageDelivered
+                | QueueEvent::MessageDeferred
+                | QueueEvent::MessageBounced
+                | QueueEvent::MessageExpired
+                | QueueEvent::AdaptiveThrottleReduced
+                | QueueEvent::AdaptiveThrottleRecovered => Level::Info,
                 QueueEvent::LockBusy | QueueEvent::Locked | QueueEvent::BlobNotFound => {
                     Level::Debug
                 }
@@ -529,7 +567,8 @@ impl EventType {
                 | MessageIngestEvent::Spam
                 | MessageIngestEvent::ImapAppend
                 | MessageIngestEvent::JmapAppend
-                | MessageIngestEvent::Duplicate => Level::Info,
+                | MessageIngestEvent::Duplicate
+                | MessageIngestEvent::Itip => Level::Info,
                 MessageIngestEvent::Error => Level::Error,
             },
             EventType::Security(_) => Level::Info,
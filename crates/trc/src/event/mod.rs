@@ -292,6 +292,16 @@ impl StoreEvent {
         match self {
             Self::AssertValueFailed => "Another process has modified the value",
             Self::BlobMissingMarker => "Blob is missing marker",
+            Self::HealthDegraded => "Store is degraded",
+            Self::HealthCritical => "Store is in a critical state",
+            Self::HealthRecovered => "Store has recovered",
+            Self::ReplicationError => "Replication error",
+            Self::ReplicationLag => "Standby is lagging behind",
+            Self::ReplicationOverflow => "Replication buffer overflow",
+            Self::ReplicationPromoted => "Standby promoted to primary",
+            Self::DiskSpaceLow => "Disk space is low",
+            Self::DiskSpaceCritical => "Disk space is critically low",
+            Self::DiskSpaceRecovered => "Disk space has recovered",
             Self::FoundationdbError => "FoundationDB error",
             Self::MysqlError => "MySQL error",
             Self::PostgresqlError => "PostgreSQL error",
@@ -301,6 +311,7 @@ impl StoreEvent {
             Self::ElasticsearchError => "ElasticSearch error",
             Self::RedisError => "Redis error",
             Self::S3Error => "S3 error",
+            Self::HttpError => "HTTP error",
             Self::FilesystemError => "Filesystem error",
             Self::PoolError => "Connection pool error",
             Self::DataCorruption => "Data corruption",
@@ -440,6 +440,7 @@ impl EventType {
                 | StoreEvent::ElasticsearchError
                 | StoreEvent::RedisError
                 | StoreEvent::S3Error
+                | StoreEvent::HttpError
                 | StoreEvent::FilesystemError
                 | StoreEvent::PoolError
                 | StoreEvent::DataCorruption
@@ -451,6 +452,16 @@ impl EventType {
                 | StoreEvent::UnexpectedError
                 | StoreEvent::CryptoError
                 | StoreEvent::BlobMissingMarker
+                | StoreEvent::HealthDegraded
+                | StoreEvent::HealthCritical
+                | StoreEvent::HealthRecovered
+                | StoreEvent::ReplicationError
+                | StoreEvent::ReplicationLag
+                | StoreEvent::ReplicationOverflow
+                | StoreEvent::ReplicationPromoted
+                | StoreEvent::DiskSpaceLow
+                | StoreEvent::DiskSpaceCritical
+                | StoreEvent::DiskSpaceRecovered
                 | StoreEvent::DataWrite
                 | StoreEvent::DataIterate
                 | StoreEvent::BlobRead
@@ -497,6 +508,17 @@ impl EventType {
                 | SmtpEvent::SpfFromFail
                 | SmtpEvent::DmarcPass
                 | SmtpEvent::DmarcFail
+                | SmtpEvent::BimiPass
+                | SmtpEvent::BimiFail
+                | SmtpEvent::Greylisted
+                | SmtpEvent::GreylistPassed
+                | SmtpEvent::CallAheadPassed
+                | SmtpEvent::CallAheadFailed
+                | SmtpEvent::CallAheadError
+                | SmtpEvent::HttpLookupAccept
+                | SmtpEvent::HttpLookupReject
+                | SmtpEvent::HttpLookupError
+                | SmtpEvent::InboundDeferred
                 | SmtpEvent::IprevPass
                 | SmtpEvent::IprevFail
                 | SmtpEvent::TooManyMessages
@@ -581,7 +603,8 @@ impl EventType {
                 | SpamEvent::TrainError
                 | SpamEvent::Classify
                 | SpamEvent::ClassifyError
-                | SpamEvent::NotEnoughTrainingData,
+                | SpamEvent::NotEnoughTrainingData
+                | SpamEvent::TokenizerChanged,
             ) => true,
             EventType::PushSubscription(_) => true,
             EventType::Cluster(
@@ -627,6 +650,7 @@ impl EventType {
                 | DeliveryEvent::StartTlsUnavailable
                 | DeliveryEvent::StartTlsError
                 | DeliveryEvent::StartTlsDisabled
+                | DeliveryEvent::RequireTlsUnavailable
                 | DeliveryEvent::ImplicitTlsError
                 | DeliveryEvent::ConcurrencyLimitExceeded
                 | DeliveryEvent::RateLimitExceeded
@@ -645,7 +669,14 @@ impl EventType {
                 | QueueEvent::BlobNotFound
                 | QueueEvent::RateLimitExceeded
                 | QueueEvent::ConcurrencyLimitExceeded
-                | QueueEvent::QuotaExceeded,
+                | QueueEvent::QuotaExceeded
+                | QueueEvent::MessageAccepted
+                | QueueEvent::MessageDelivered
+                | QueueEvent::MessageDeferred
+                | QueueEvent::MessageBounced
+                | QueueEvent::MessageExpired
+                | QueueEvent::AdaptiveThrottleReduced
+                | QueueEvent::AdaptiveThrottleRecovered,
             ) => true,
             EventType::TlsRpt(_) => false,
             EventType::MtaSts(
@@ -203,6 +203,10 @@ pub enum SecurityEvent {
     LoiterBan,
     IpBlocked,
     Unauthorized,
+    EarlyTalker,
+    EarlyTalkerBan,
+    ScriptBan,
+    SlowTransfer,
 }
 
 #[event_type]
@@ -230,6 +234,7 @@ pub enum HousekeeperEvent {
     PurgeAccounts,
     PurgeSessions,
     PurgeStore,
+    RoleAddressMissing,
 }
 
 #[event_type]
@@ -370,6 +375,17 @@ pub enum SmtpEvent {
     SpfFromFail,
     DmarcPass,
     DmarcFail,
+    BimiPass,
+    BimiFail,
+    Greylisted,
+    GreylistPassed,
+    CallAheadPassed,
+    CallAheadFailed,
+    CallAheadError,
+    HttpLookupAccept,
+    HttpLookupReject,
+    HttpLookupError,
+    InboundDeferred,
     IprevPass,
     IprevFail,
     TooManyMessages,
@@ -409,6 +425,7 @@ pub enum SmtpEvent {
     FutureReleaseInvalid,
     MtPriorityDisabled,
     MtPriorityInvalid,
+    MtPriorityLowered,
     DsnDisabled,
     AuthNotAllowed,
     AuthMechanismNotSupported,
@@ -462,6 +479,7 @@ pub enum DeliveryEvent {
     StartTlsUnavailable,
     StartTlsError,
     StartTlsDisabled,
+    RequireTlsUnavailable,
     ImplicitTlsError,
     ConcurrencyLimitExceeded,
     RateLimitExceeded,
@@ -487,6 +505,13 @@ pub enum QueueEvent {
     RateLimitExceeded,
     ConcurrencyLimitExceeded,
     QuotaExceeded,
+    MessageAccepted,
+    MessageDelivered,
+    MessageDeferred,
+    MessageBounced,
+    MessageExpired,
+    AdaptiveThrottleReduced,
+    AdaptiveThrottleRecovered,
 }
 
 #[event_type]
@@ -606,6 +631,7 @@ pub enum SpamEvent {
     Classify,
     ClassifyError,
     NotEnoughTrainingData,
+    TokenizerChanged,
 }
 
 #[event_type]
@@ -622,6 +648,8 @@ pub enum SieveEvent {
     UnexpectedError,
     NotSupported,
     QuotaExceeded,
+    SendNotification,
+    NotificationError,
 }
 
 #[event_type]
@@ -658,6 +686,9 @@ pub enum ServerEvent {
     StartupError,
     ThreadError,
     Licensing,
+    MaintenanceMode,
+    SchemaMigration,
+    SchemaMigrationLocked,
 }
 
 #[event_type]
@@ -710,6 +741,7 @@ pub enum PurgeEvent {
     Error,
     PurgeActive,
     AutoExpunge,
+    Pop3Expire,
     TombstoneCleanup,
 }
 
@@ -826,6 +858,7 @@ pub enum StoreEvent {
     ElasticsearchError,
     RedisError,
     S3Error,
+    HttpError,
     FilesystemError,
     PoolError,
     DataCorruption,
@@ -839,6 +872,16 @@ pub enum StoreEvent {
 
     // Warnings
     BlobMissingMarker,
+    HealthDegraded,
+    HealthCritical,
+    HealthRecovered,
+    ReplicationError,
+    ReplicationLag,
+    ReplicationOverflow,
+    ReplicationPromoted,
+    DiskSpaceLow,
+    DiskSpaceCritical,
+    DiskSpaceRecovered,
 
     // Traces
     DataWrite,
@@ -859,6 +902,7 @@ pub enum MessageIngestEvent {
     ImapAppend,
     JmapAppend,
     Duplicate,
+    Itip,
     Error,
 }
 
@@ -935,6 +979,7 @@ pub enum ResourceEvent {
     Error,
     DownloadExternal,
     WebadminUnpacked,
+    FeedStale,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
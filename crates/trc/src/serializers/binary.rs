@@ -859,6 +859,51 @@ impl EventType {
             EventType::Smtp(SmtpEvent::MailFromNotAllowed) => 551,
             EventType::Security(SecurityEvent::Unauthorized) => 552,
             EventType::Limit(LimitEvent::TenantQuota) => 553,
+            EventType::Purge(PurgeEvent::Pop3Expire) => 554,
+            EventType::Sieve(SieveEvent::SendNotification) => 555,
+            EventType::Sieve(SieveEvent::NotificationError) => 556,
+            EventType::Smtp(SmtpEvent::MtPriorityLowered) => 557,
+            EventType::Store(StoreEvent::HttpError) => 558,
+            EventType::MessageIngest(MessageIngestEvent::Itip) => 559,
+            EventType::Security(SecurityEvent::EarlyTalker) => 560,
+            EventType::Security(SecurityEvent::SlowTransfer) => 561,
+            EventType::Security(SecurityEvent::EarlyTalkerBan) => 562,
+            EventType::Resource(ResourceEvent::FeedStale) => 563,
+            EventType::Security(SecurityEvent::ScriptBan) => 564,
+            EventType::Smtp(SmtpEvent::BimiPass) => 565,
+            EventType::Smtp(SmtpEvent::BimiFail) => 566,
+            EventType::Smtp(SmtpEvent::Greylisted) => 567,
+            EventType::Smtp(SmtpEvent::GreylistPassed) => 568,
+            EventType::Housekeeper(HousekeeperEvent::RoleAddressMissing) => 569,
+            EventType::Store(StoreEvent::HealthDegraded) => 570,
+            EventType::Store(StoreEvent::HealthCritical) => 571,
+            EventType::Store(StoreEvent::HealthRecovered) => 572,
+            EventType::Smtp(SmtpEvent::InboundDeferred) => 573,
+            EventType::Server(ServerEvent::MaintenanceMode) => 574,
+            EventType::Store(StoreEvent::ReplicationError) => 575,
+            EventType::Store(StoreEvent::ReplicationLag) => 576,
+            EventType::Store(StoreEvent::ReplicationOverflow) => 577,
+            EventType::Store(StoreEvent::ReplicationPromoted) => 578,
+            EventType::Delivery(DeliveryEvent::RequireTlsUnavailable) => 579,
+            EventType::Spam(SpamEvent::TokenizerChanged) => 580,
+            EventType::Queue(QueueEvent::MessageAccepted) => 581,
+            EventType::Queue(QueueEvent::MessageDelivered) => 582,
+            EventType::Queue(QueueEvent::MessageDeferred) => 583,
+            EventType::Queue(QueueEvent::MessageBounced) => 584,
+            EventType::Queue(QueueEvent::MessageExpired) => 585,
+            EventType::Smtp(SmtpEvent::CallAheadPassed) => 586,
+            EventType::Smtp(SmtpEvent::CallAheadFailed) => 587,
+            EventType::Smtp(SmtpEvent::CallAheadError) => 588,
+            EventType::Smtp(SmtpEvent::HttpLookupAccept) => 589,
+            EventType::Smtp(SmtpEvent::HttpLookupReject) => 590,
+            EventType::Smtp(SmtpEvent::HttpLookupError) => 591,
+            EventType::Queue(QueueEvent::AdaptiveThrottleReduced) => 592,
+            EventType::Queue(QueueEvent::AdaptiveThrottleRecovered) => 593,
+            EventType::Store(StoreEvent::DiskSpaceLow) => 594,
+            EventType::Store(StoreEvent::DiskSpaceCritical) => 595,
+            EventType::Store(StoreEvent::DiskSpaceRecovered) => 596,
+            EventType::Server(ServerEvent::SchemaMigration) => 597,
+            EventType::Server(ServerEvent::SchemaMigrationLocked) => 598,
         }
     }
 
@@ -1458,6 +1503,51 @@ impl EventType {
             551 => Some(EventType::Smtp(SmtpEvent::MailFromNotAllowed)),
             552 => Some(EventType::Security(SecurityEvent::Unauthorized)),
             553 => Some(EventType::Limit(LimitEvent::TenantQuota)),
+            554 => Some(EventType::Purge(PurgeEvent::Pop3Expire)),
+            555 => Some(EventType::Sieve(SieveEvent::SendNotification)),
+            556 => Some(EventType::Sieve(SieveEvent::NotificationError)),
+            557 => Some(EventType::Smtp(SmtpEvent::MtPriorityLowered)),
+            558 => Some(EventType::Store(StoreEvent::HttpError)),
+            559 => Some(EventType::MessageIngest(MessageIngestEvent::Itip)),
+            560 => Some(EventType::Security(SecurityEvent::EarlyTalker)),
+            561 => Some(EventType::Security(SecurityEvent::SlowTransfer)),
+            562 => Some(EventType::Security(SecurityEvent::EarlyTalkerBan)),
+            563 => Some(EventType::Resource(ResourceEvent::FeedStale)),
+            564 => Some(EventType::Security(SecurityEvent::ScriptBan)),
+            565 => Some(EventType::Smtp(SmtpEvent::BimiPass)),
+            566 => Some(EventType::Smtp(SmtpEvent::BimiFail)),
+            567 => Some(EventType::Smtp(SmtpEvent::Greylisted)),
+            568 => Some(EventType::Smtp(SmtpEvent::GreylistPassed)),
+            569 => Some(EventType::Housekeeper(HousekeeperEvent::RoleAddressMissing)),
+            570 => Some(EventType::Store(StoreEvent::HealthDegraded)),
+            571 => Some(EventType::Store(StoreEvent::HealthCritical)),
+            572 => Some(EventType::Store(StoreEvent::HealthRecovered)),
+            573 => Some(EventType::Smtp(SmtpEvent::InboundDeferred)),
+            574 => Some(EventType::Server(ServerEvent::MaintenanceMode)),
+            575 => Some(EventType::Store(StoreEvent::ReplicationError)),
+            576 => Some(EventType::Store(StoreEvent::ReplicationLag)),
+            577 => Some(EventType::Store(StoreEvent::ReplicationOverflow)),
+            578 => Some(EventType::Store(StoreEvent::ReplicationPromoted)),
+            579 => Some(EventType::Delivery(DeliveryEvent::RequireTlsUnavailable)),
+            580 => Some(EventType::Spam(SpamEvent::TokenizerChanged)),
+            581 => Some(EventType::Queue(QueueEvent::MessageAccepted)),
+            582 => Some(EventType::Queue(QueueEvent::MessageDelivered)),
+            583 => Some(EventType::Queue(QueueEvent::MessageDeferred)),
+            584 => Some(EventType::Queue(QueueEvent::MessageBounced)),
+            585 => Some(EventType::Queue(QueueEvent::MessageExpired)),
+            586 => Some(EventType::Smtp(SmtpEvent::CallAheadPassed)),
+            587 => Some(EventType::Smtp(SmtpEvent::CallAheadFailed)),
+            588 => Some(EventType::Smtp(SmtpEvent::CallAheadError)),
+            589 => Some(EventType::Smtp(SmtpEvent::HttpLookupAccept)),
+            590 => Some(EventType::Smtp(SmtpEvent::HttpLookupReject)),
+            591 => Some(EventType::Smtp(SmtpEvent::HttpLookupError)),
+            592 => Some(EventType::Queue(QueueEvent::AdaptiveThrottleReduced)),
+            593 => Some(EventType::Queue(QueueEvent::AdaptiveThrottleRecovered)),
+            594 => Some(EventType::Store(StoreEvent::DiskSpaceLow)),
+            595 => Some(EventType::Store(StoreEvent::DiskSpaceCritical)),
+            596 => Some(EventType::Store(StoreEvent::DiskSpaceRecovered)),
+            597 => Some(EventType::Server(ServerEvent::SchemaMigration)),
+            598 => Some(EventType::Server(ServerEvent::SchemaMigrationLocked)),
             _ => None,
         }
     }
@@ -0,0 +1,92 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "test_mode")]
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    OnceLock,
+};
+
+#[cfg(feature = "test_mode")]
+use tokio::sync::Notify;
+
+// Milliseconds the clock has been fast-forwarded by the test suite
+#[cfg(feature = "test_mode")]
+static OFFSET: AtomicU64 = AtomicU64::new(0);
+
+// Wakes up timers waiting in [`sleep`] when the clock is advanced
+#[cfg(feature = "test_mode")]
+static ADVANCED: OnceLock<Notify> = OnceLock::new();
+
+/// Returns the current wall-clock time used by scheduled features
+/// (queue retries, purges, holds). In test mode the clock can be moved
+/// forward with [`advance`] so long-running schedules can be tested
+/// without sleeping.
+#[inline(always)]
+pub fn now() -> SystemTime {
+    #[cfg(feature = "test_mode")]
+    {
+        SystemTime::now() + Duration::from_millis(OFFSET.load(Ordering::Relaxed))
+    }
+
+    #[cfg(not(feature = "test_mode"))]
+    {
+        SystemTime::now()
+    }
+}
+
+/// Seconds elapsed since the UNIX epoch according to [`now`].
+#[inline(always)]
+pub fn timestamp() -> u64 {
+    now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Time elapsed since `time` according to [`now`].
+#[inline(always)]
+pub fn elapsed(time: SystemTime) -> Option<Duration> {
+    now().duration_since(time).ok()
+}
+
+/// Fast-forwards the clock. Time never moves backwards, as
+/// snowflake ids are derived from it and have to be unique.
+#[cfg(feature = "test_mode")]
+pub fn advance(duration: Duration) {
+    OFFSET.fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+    ADVANCED.get_or_init(Notify::new).notify_waiters();
+}
+
+/// Waits until `duration` has elapsed according to [`now`]. Timers that
+/// drive scheduled work should use this instead of `tokio::time::sleep`
+/// so that advancing the clock in tests fires them.
+pub async fn sleep(duration: Duration) {
+    #[cfg(feature = "test_mode")]
+    {
+        let deadline = now() + duration;
+        let advanced = ADVANCED.get_or_init(Notify::new);
+        loop {
+            // Register before reading the clock so no advance is missed
+            let notified = advanced.notified();
+            match deadline.duration_since(now()) {
+                Ok(remaining) if !remaining.is_zero() => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(remaining) => {}
+                        _ = notified => {}
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    #[cfg(not(feature = "test_mode"))]
+    {
+        tokio::time::sleep(duration).await;
+    }
+}
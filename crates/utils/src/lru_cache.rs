@@ -4,11 +4,24 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{borrow::Borrow, hash::Hash};
+use std::{
+    borrow::Borrow,
+    hash::Hash,
+    mem::size_of,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
-use parking_lot::Mutex;
+use parking_lot::{Mutex, MutexGuard, RwLock};
 
-pub type LruCache<K, V> = Mutex<lru_cache::LruCache<K, V, ahash::RandomState>>;
+pub struct LruCache<K: Hash + Eq, V> {
+    cache: Mutex<lru_cache::LruCache<K, V, ahash::RandomState>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
 
 pub trait LruCached<K, V>: Sized {
     fn with_capacity(capacity: usize) -> Self;
@@ -19,12 +32,38 @@ pub trait LruCached<K, V>: Sized {
     fn insert(&self, name: K, value: V) -> Option<V>;
 }
 
+pub trait LruCacheMetrics: Sync + Send {
+    fn stats(&self) -> LruCacheStats;
+    fn set_capacity(&self, capacity: usize);
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LruCacheStats {
+    pub name: String,
+    pub size: usize,
+    pub capacity: usize,
+    pub memory: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+#[derive(Default)]
+pub struct LruCacheRegistry {
+    caches: RwLock<Vec<(&'static str, Arc<dyn LruCacheMetrics>)>>,
+}
+
 impl<K: Hash + Eq, V: Clone> LruCached<K, V> for LruCache<K, V> {
     fn with_capacity(capacity: usize) -> Self {
-        Mutex::new(lru_cache::LruCache::with_hasher(
-            capacity,
-            ahash::RandomState::new(),
-        ))
+        LruCache {
+            cache: Mutex::new(lru_cache::LruCache::with_hasher(
+                capacity,
+                ahash::RandomState::new(),
+            )),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
     }
 
     fn get<Q>(&self, name: &Q) -> Option<V>
@@ -32,10 +71,123 @@ impl<K: Hash + Eq, V: Clone> LruCached<K, V> for LruCache<K, V> {
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.lock().get_mut(name).map(|entry| entry.clone())
+        let result = self.cache.lock().get_mut(name).map(|entry| entry.clone());
+        if result.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        result
     }
 
     fn insert(&self, name: K, item: V) -> Option<V> {
-        self.lock().insert(name, item)
+        let mut cache = self.cache.lock();
+        if cache.len() >= cache.capacity() && !cache.contains_key(&name) {
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        cache.insert(name, item)
+    }
+}
+
+impl<K: Hash + Eq, V> LruCache<K, V> {
+    // Estimated footprint of a cached entry: the key, the value, the linked list
+    // pointers and the hash table slot. Heap data owned by entries is not included.
+    const ENTRY_SIZE: usize = size_of::<K>() + size_of::<V>() + 3 * size_of::<usize>();
+
+    pub fn lock(&self) -> MutexGuard<'_, lru_cache::LruCache<K, V, ahash::RandomState>> {
+        self.cache.lock()
+    }
+}
+
+impl<K, V> LruCacheMetrics for LruCache<K, V>
+where
+    K: Hash + Eq + Sync + Send,
+    V: Sync + Send,
+{
+    fn stats(&self) -> LruCacheStats {
+        let cache = self.cache.lock();
+        LruCacheStats {
+            name: String::new(),
+            size: cache.len(),
+            capacity: cache.capacity(),
+            memory: cache.len() * Self::ENTRY_SIZE,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    fn set_capacity(&self, capacity: usize) {
+        let mut cache = self.cache.lock();
+        let evicted = cache.len().saturating_sub(capacity);
+        if evicted > 0 {
+            self.evictions.fetch_add(evicted as u64, Ordering::Relaxed);
+        }
+        cache.set_capacity(capacity);
+    }
+}
+
+impl LruCacheRegistry {
+    pub fn register(&self, name: &'static str, cache: Arc<dyn LruCacheMetrics>) {
+        self.caches.write().push((name, cache));
+    }
+
+    pub fn stats(&self) -> Vec<LruCacheStats> {
+        self.caches
+            .read()
+            .iter()
+            .map(|(name, cache)| LruCacheStats {
+                name: name.to_string(),
+                ..cache.stats()
+            })
+            .collect()
+    }
+
+    pub fn set_capacity(&self, name: &str, capacity: usize) -> Option<LruCacheStats> {
+        self.caches
+            .read()
+            .iter()
+            .find(|(cache_name, _)| *cache_name == name)
+            .map(|(name, cache)| {
+                cache.set_capacity(capacity);
+                LruCacheStats {
+                    name: name.to_string(),
+                    ..cache.stats()
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LruCache, LruCacheMetrics, LruCached};
+
+    #[test]
+    fn lru_cache_stats() {
+        let cache = LruCache::<u32, u32>::with_capacity(2);
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        assert_eq!(cache.get(&1), Some(1));
+        assert_eq!(cache.get(&3), None);
+        cache.insert(2, 3);
+        cache.insert(3, 3);
+        assert_eq!(cache.get(&1), None);
+
+        let stats = cache.stats();
+        assert_eq!(
+            (
+                stats.size,
+                stats.capacity,
+                stats.hits,
+                stats.misses,
+                stats.evictions
+            ),
+            (2, 2, 1, 2, 1)
+        );
+
+        cache.set_capacity(1);
+        let stats = cache.stats();
+        assert_eq!((stats.size, stats.capacity, stats.evictions), (1, 1, 2));
+        assert_eq!(stats.memory, LruCache::<u32, u32>::ENTRY_SIZE);
     }
 }
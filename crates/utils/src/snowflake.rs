@@ -9,6 +9,8 @@ use std::{
     time::{Duration, SystemTime},
 };
 
+use crate::clock;
+
 #[derive(Debug)]
 pub struct SnowflakeIdGenerator {
     epoch: SystemTime,
@@ -38,18 +40,14 @@ impl SnowflakeIdGenerator {
     }
 
     pub fn from_duration(period: Duration) -> Option<u64> {
-        (SystemTime::UNIX_EPOCH + Duration::from_secs(1632280000))
-            .elapsed()
-            .ok()
+        clock::elapsed(SystemTime::UNIX_EPOCH + Duration::from_secs(1632280000))
             .and_then(|elapsed| elapsed.checked_sub(period))
             .map(|elapsed| (elapsed.as_millis() as u64) << (SEQUENCE_LEN + NODE_ID_LEN))
     }
 
     pub fn from_timestamp(timestamp: u64) -> Option<u64> {
-        SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .ok()
-            .and_then(|now| now.as_secs().checked_sub(timestamp))
+        clock::timestamp()
+            .checked_sub(timestamp)
             .and_then(|diff| Self::from_duration(Duration::from_secs(diff)))
     }
 
@@ -63,16 +61,14 @@ impl SnowflakeIdGenerator {
 
     #[inline(always)]
     pub fn past_id(&self, period: Duration) -> Option<u64> {
-        self.epoch
-            .elapsed()
-            .ok()
+        clock::elapsed(self.epoch)
             .and_then(|elapsed| elapsed.checked_sub(period))
             .map(|elapsed| (elapsed.as_millis() as u64) << (SEQUENCE_LEN + NODE_ID_LEN))
     }
 
     #[inline(always)]
     pub fn generate(&self) -> Option<u64> {
-        let elapsed = self.epoch.elapsed().ok()?.as_millis() as u64;
+        let elapsed = clock::elapsed(self.epoch)?.as_millis() as u64;
         let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
 
         (elapsed << (SEQUENCE_LEN + NODE_ID_LEN)
@@ -0,0 +1,65 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+#![no_main]
+
+use imap_proto::{
+    protocol::ProtocolVersion,
+    receiver::{Error, Receiver, Request},
+    Command,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut receiver = Receiver::with_max_request_size(64 * 1024);
+    let mut bytes = data.iter();
+
+    loop {
+        match receiver.parse(&mut bytes) {
+            Ok(request) => {
+                for version in [ProtocolVersion::Rev1, ProtocolVersion::Rev2] {
+                    parse_request(request.clone(), version);
+                }
+            }
+            Err(Error::NeedsMoreData) => break,
+            Err(Error::NeedsLiteral { .. }) | Err(Error::Error { .. }) => {
+                if bytes.len() == 0 {
+                    break;
+                }
+            }
+        }
+    }
+});
+
+// Runs the same argument parser the IMAP session would use for the command
+fn parse_request(request: Request<Command>, version: ProtocolVersion) {
+    let _ = match request.command {
+        Command::Authenticate => request.parse_authenticate().map(|_| ()),
+        Command::Login => request.parse_login().map(|_| ()),
+        Command::Enable => request.parse_enable().map(|_| ()),
+        Command::Select | Command::Examine => request.parse_select(version).map(|_| ()),
+        Command::Create => request.parse_create(version).map(|_| ()),
+        Command::Delete => request.parse_delete(version).map(|_| ()),
+        Command::Rename => request.parse_rename(version).map(|_| ()),
+        Command::Subscribe | Command::Unsubscribe => request.parse_subscribe(version).map(|_| ()),
+        Command::List => request.parse_list(version).map(|_| ()),
+        Command::Lsub => request.parse_lsub().map(|_| ()),
+        Command::Status => request.parse_status(version).map(|_| ()),
+        Command::Append => request.parse_append(version).map(|_| ()),
+        Command::Search(_) => request.parse_search(version).map(|_| ()),
+        Command::Fetch(_) => request.parse_fetch().map(|_| ()),
+        Command::Store(_) => request.parse_store().map(|_| ()),
+        Command::Copy(_) | Command::Move(_) => request.parse_copy_move(version).map(|_| ()),
+        Command::Sort(_) => request.parse_sort().map(|_| ()),
+        Command::Thread(_) => request.parse_thread().map(|_| ()),
+        Command::SetAcl
+        | Command::DeleteAcl
+        | Command::GetAcl
+        | Command::ListRights
+        | Command::MyRights => request.parse_acl(version).map(|_| ()),
+        _ => Ok(()),
+    };
+}
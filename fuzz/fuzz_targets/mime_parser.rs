@@ -0,0 +1,25 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mail_parser::{MessageParser, MimeHeaders};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(message) = MessageParser::new().parse(data) {
+        // Touch the values that are indexed during ingestion
+        let _ = message.subject();
+        let _ = message.from();
+        let _ = message.message_id();
+        let _ = message.references();
+        for part in &message.parts {
+            let _ = part.content_type();
+            let _ = part.attachment_name();
+            let _ = part.text_contents();
+        }
+    }
+});
@@ -0,0 +1,26 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sieve::Compiler;
+
+fuzz_target!(|data: &[u8]| {
+    // Same limits as the defaults for untrusted scripts
+    let _ = Compiler::new()
+        .with_max_script_size(1024 * 1024)
+        .with_max_string_size(4096)
+        .with_max_variable_name_size(32)
+        .with_max_nested_blocks(15)
+        .with_max_nested_tests(15)
+        .with_max_nested_foreverypart(3)
+        .with_max_match_variables(30)
+        .with_max_local_variables(128)
+        .with_max_header_size(1024)
+        .with_max_includes(3)
+        .compile(data);
+});
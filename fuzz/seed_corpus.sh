@@ -0,0 +1,26 @@
+#!/bin/sh
+#
+# SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+#
+# SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+#
+# Populates the fuzzing corpus with inputs taken from the test suite.
+# Usage: ./seed_corpus.sh && cargo fuzz run imap_command
+
+set -e
+cd "$(dirname "$0")"
+
+mkdir -p corpus/imap_command corpus/sieve_parser corpus/mime_parser
+
+cp seeds/imap_command/* corpus/imap_command/
+
+for file in ../tests/resources/jmap/sieve/*.sieve \
+    ../tests/resources/smtp/sieve/*.sieve \
+    ../resources/config/spamfilter/scripts/*.sieve; do
+    cp "$file" "corpus/sieve_parser/$(basename "$(dirname "$file")")_$(basename "$file")"
+done
+
+for file in $(find ../tests/resources/smtp -name '*.eml') \
+    ../tests/resources/imap/*.txt; do
+    cp "$file" "corpus/mime_parser/$(basename "$(dirname "$file")")_$(basename "$file")"
+done
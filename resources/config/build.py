@@ -26,6 +26,7 @@ scripts = {
                "ip.sieve",
                "helo.sieve",
                "replies_in.sieve",
+               "contacts.sieve",
                "spamtrap.sieve",
                "bayes_classify.sieve",
                "url.sieve",
@@ -0,0 +1,228 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+// IMAP benchmarks against a running server. The mailbox is populated with
+// a reproducible set of messages so that results can be compared across runs.
+// The benchmarks are skipped unless BENCH_IMAP_ADDR points to a listening server.
+//
+// BENCH_IMAP_ADDR=127.0.0.1:143 BENCH_IMAP_USER=john BENCH_IMAP_PASS=secret \
+//   BENCH_MESSAGES=1000 BENCH_SEED=0 cargo bench --bench imap
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use generator::MessageGenerator;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, ReadHalf, WriteHalf},
+    net::TcpStream,
+};
+
+const FETCH_COMMANDS: &[(&str, &str)] = &[
+    ("fetch_flags", "FETCH 1:* (UID FLAGS RFC822.SIZE)"),
+    (
+        "fetch_envelope",
+        "FETCH 1:* (UID FLAGS INTERNALDATE ENVELOPE)",
+    ),
+    (
+        "fetch_headers",
+        "FETCH 1:* (UID BODY.PEEK[HEADER.FIELDS (FROM TO SUBJECT DATE)])",
+    ),
+    ("fetch_bodystructure", "FETCH 1:* (UID BODYSTRUCTURE)"),
+    ("fetch_full_100", "FETCH 1:100 (UID BODY.PEEK[])"),
+];
+
+const SEARCH_COMMANDS: &[(&str, &str)] = &[
+    ("search_flags", "UID SEARCH UNSEEN FLAGGED"),
+    ("search_from", "UID SEARCH FROM \"alice\""),
+    ("search_subject", "UID SEARCH SUBJECT \"budget\""),
+    ("search_text", "UID SEARCH TEXT \"invoice\""),
+    ("search_size", "UID SEARCH LARGER 100000"),
+    ("search_date", "UID SEARCH SINCE 1-Jan-2023 NOT ANSWERED"),
+];
+
+struct Session {
+    reader: BufReader<ReadHalf<TcpStream>>,
+    writer: WriteHalf<TcpStream>,
+    seq: usize,
+}
+
+fn imap_benchmarks(c: &mut Criterion) {
+    let addr = match std::env::var("BENCH_IMAP_ADDR") {
+        Ok(addr) => addr,
+        Err(_) => {
+            eprintln!("Skipping IMAP benchmarks: BENCH_IMAP_ADDR is not set.");
+            return;
+        }
+    };
+    let user = env_var("BENCH_IMAP_USER", "john");
+    let pass = env_var("BENCH_IMAP_PASS", "secret");
+    let num_messages: usize = env_var("BENCH_MESSAGES", "1000").parse().unwrap();
+    let seed: u64 = env_var("BENCH_SEED", "0").parse().unwrap();
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut session = match rt.block_on(Session::connect(&addr)) {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("Skipping IMAP benchmarks: failed to connect to {addr}: {err}");
+            return;
+        }
+    };
+    rt.block_on(session.command(&format!("LOGIN \"{user}\" \"{pass}\"")))
+        .expect("LOGIN failed");
+
+    // Populate the benchmark mailbox
+    let mailbox = format!("Bench {seed}-{num_messages}");
+    let append_mailbox = format!("Bench {seed}-append");
+    for mailbox in [&mailbox, &append_mailbox] {
+        let _ = rt.block_on(session.command(&format!("DELETE \"{mailbox}\"")));
+        rt.block_on(session.command(&format!("CREATE \"{mailbox}\"")))
+            .expect("CREATE failed");
+    }
+    for message in MessageGenerator::new(seed).take(num_messages) {
+        rt.block_on(session.append(&mailbox, &message.imap_flags(), &message.contents))
+            .expect("APPEND failed");
+    }
+
+    // Ingest into a separate mailbox so the fetch and search mailbox stays reproducible
+    let mut messages = MessageGenerator::new(seed);
+    let mut group = c.benchmark_group("ingest");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("append", |b| {
+        b.iter(|| {
+            let message = messages.next_message();
+            rt.block_on(session.append(&append_mailbox, &message.imap_flags(), &message.contents))
+                .expect("APPEND failed")
+        })
+    });
+    group.finish();
+
+    rt.block_on(session.command(&format!("SELECT \"{mailbox}\"")))
+        .expect("SELECT failed");
+    for (group_name, commands) in [("fetch", FETCH_COMMANDS), ("search", SEARCH_COMMANDS)] {
+        let mut group = c.benchmark_group(group_name);
+        for (name, command) in commands {
+            group.bench_function(*name, |b| {
+                b.iter(|| rt.block_on(session.command(command)).expect(command))
+            });
+        }
+        group.finish();
+    }
+
+    rt.block_on(session.command("UNSELECT"))
+        .expect("UNSELECT failed");
+    for mailbox in [&mailbox, &append_mailbox] {
+        rt.block_on(session.command(&format!("DELETE \"{mailbox}\"")))
+            .expect("DELETE failed");
+    }
+    let _ = rt.block_on(session.command("LOGOUT"));
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = imap_benchmarks
+}
+criterion_main!(benches);
+
+impl Session {
+    async fn connect(addr: &str) -> std::io::Result<Self> {
+        let (reader, writer) = tokio::io::split(TcpStream::connect(addr).await?);
+        let mut session = Session {
+            reader: BufReader::new(reader),
+            writer,
+            seq: 0,
+        };
+        // Greeting
+        session.read_line().await?;
+        Ok(session)
+    }
+
+    async fn command(&mut self, command: &str) -> Result<usize, String> {
+        let tag = self.next_tag();
+        self.writer
+            .write_all(format!("{tag} {command}\r\n").as_bytes())
+            .await
+            .map_err(|err| err.to_string())?;
+        self.read_tagged(&tag).await
+    }
+
+    async fn append(
+        &mut self,
+        mailbox: &str,
+        flags: &str,
+        contents: &[u8],
+    ) -> Result<usize, String> {
+        let tag = self.next_tag();
+        self.writer
+            .write_all(
+                format!(
+                    "{tag} APPEND \"{mailbox}\" ({flags}) {{{}+}}\r\n",
+                    contents.len()
+                )
+                .as_bytes(),
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+        self.writer
+            .write_all(contents)
+            .await
+            .map_err(|err| err.to_string())?;
+        self.writer
+            .write_all(b"\r\n")
+            .await
+            .map_err(|err| err.to_string())?;
+        self.read_tagged(&tag).await
+    }
+
+    // Reads until the tagged response, returns the number of bytes received
+    async fn read_tagged(&mut self, tag: &str) -> Result<usize, String> {
+        let mut bytes_read = 0;
+        loop {
+            let line = self.read_line().await.map_err(|err| err.to_string())?;
+            bytes_read += line.len();
+
+            // Skip literals
+            if let Some(size) = line
+                .trim_end()
+                .strip_suffix('}')
+                .and_then(|line| line.rsplit_once('{'))
+                .and_then(|(_, size)| size.parse::<u64>().ok())
+            {
+                bytes_read +=
+                    tokio::io::copy(&mut (&mut self.reader).take(size), &mut tokio::io::sink())
+                        .await
+                        .map_err(|err| err.to_string())? as usize;
+                continue;
+            }
+
+            if let Some(result) = line.strip_prefix(tag).and_then(|l| l.strip_prefix(' ')) {
+                return if result.starts_with("OK") {
+                    Ok(bytes_read)
+                } else {
+                    Err(result.trim_end().to_string())
+                };
+            }
+        }
+    }
+
+    async fn read_line(&mut self) -> std::io::Result<String> {
+        let mut line = Vec::new();
+        if self.reader.read_until(b'\n', &mut line).await? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(String::from_utf8_lossy(&line).into_owned())
+    }
+
+    fn next_tag(&mut self) -> String {
+        self.seq += 1;
+        format!("B{}", self.seq)
+    }
+}
+
+fn env_var(name: &str, default: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| default.to_string())
+}
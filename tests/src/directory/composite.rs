@@ -0,0 +1,144 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use directory::{
+    backend::internal::{manage::ManageDirectory, PrincipalField},
+    Directories, QueryBy,
+};
+use mail_send::Credentials;
+use store::Stores;
+
+use crate::{store::TempDir, AssertConfig};
+
+const CONFIG: &str = r#"
+[store."sqlite"]
+type = "sqlite"
+path = "{TMP}/composite.db"
+
+[directory."composite"]
+type = "composite"
+directories = ["current", "legacy"]
+
+[directory."composite".routing]
+"legacy.org" = "legacy"
+
+[directory."current"]
+type = "memory"
+
+[[directory."current".principals]]
+name = "john"
+class = "individual"
+secret = "12345"
+email = "john@example.org"
+email-list = ["info@example.org"]
+
+[[directory."current".principals]]
+name = "bill"
+class = "individual"
+secret = "abcde"
+email = ["bill@example.org", "bill@legacy.org"]
+
+[directory."legacy"]
+type = "memory"
+
+[[directory."legacy".principals]]
+name = "jane"
+class = "individual"
+secret = "abcde"
+email = ["jane@example.org", "jane@legacy.org"]
+email-list = ["info@example.org"]
+
+[[directory."legacy".principals]]
+name = "john"
+class = "individual"
+secret = "old-secret"
+email = "john@legacy.org"
+"#;
+
+#[tokio::test]
+async fn composite_directory() {
+    let temp_dir = TempDir::new("composite_directory_test", true);
+    let mut config =
+        utils::config::Config::new(CONFIG.replace("{TMP}", &temp_dir.path.to_string_lossy()))
+            .unwrap();
+    let stores = Stores::parse_all(&mut config).await;
+    let directories = Directories::parse(
+        &mut config,
+        &stores,
+        stores.stores.get("sqlite").unwrap().clone(),
+    )
+    .await;
+    config.assert_no_errors();
+    let directory = directories.directories.get("composite").unwrap();
+
+    // Principals are looked up in order
+    for (name, description) in [("john", "john@example.org"), ("jane", "jane@example.org")] {
+        let principal = directory
+            .query(QueryBy::Name(name), false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            principal
+                .iter_str(PrincipalField::Emails)
+                .next()
+                .map(|email| email.as_str()),
+            Some(description)
+        );
+    }
+    assert!(directory
+        .query(QueryBy::Name("unknown"), false)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Authentication falls back to the next directory
+    for (username, secret, expect) in [
+        ("john", "12345", true),
+        ("john", "old-secret", true),
+        ("jane", "abcde", true),
+        ("jane", "wrong", false),
+    ] {
+        assert_eq!(
+            directory
+                .query(
+                    QueryBy::Credentials(&Credentials::Plain {
+                        username: username.to_string(),
+                        secret: secret.to_string(),
+                    }),
+                    false,
+                )
+                .await
+                .unwrap()
+                .is_some(),
+            expect,
+            "{username}:{secret}"
+        );
+    }
+
+    // Addresses are merged across directories
+    let store = stores.stores.get("sqlite").unwrap();
+    let mut ids = directory.email_to_ids("info@example.org").await.unwrap();
+    ids.sort_unstable();
+    let mut expected_ids = vec![
+        store.get_principal_id("john").await.unwrap().unwrap(),
+        store.get_principal_id("jane").await.unwrap().unwrap(),
+    ];
+    expected_ids.sort_unstable();
+    assert_eq!(ids, expected_ids);
+    assert!(directory.rcpt("jane@example.org").await.unwrap());
+    assert!(directory.is_local_domain("example.org").await.unwrap());
+
+    // Routed domains are only resolved by their authoritative directory
+    assert!(directory.rcpt("jane@legacy.org").await.unwrap());
+    assert!(!directory.rcpt("bill@legacy.org").await.unwrap());
+    assert!(directory.rcpt("bill@example.org").await.unwrap());
+    assert!(directory
+        .query(QueryBy::Name("bill@legacy.org"), false)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,230 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use directory::{
+    backend::internal::{manage::ManageDirectory, PrincipalField},
+    Directories, QueryBy,
+};
+use http_body_util::{BodyExt, Full};
+use hyper::{body, server::conn::http1, service::service_fn, Method, StatusCode};
+use hyper_util::rt::TokioIo;
+use mail_send::Credentials;
+use store::Stores;
+use tokio::net::TcpListener;
+
+use crate::{store::TempDir, AssertConfig};
+
+const CONFIG: &str = r#"
+[store."sqlite"]
+type = "sqlite"
+path = "{TMP}/http.db"
+
+[directory."http"]
+type = "http"
+url = "http://127.0.0.1:8822"
+auth.username = "stalwart"
+auth.secret = "s3cr3t"
+
+[directory."http".endpoints]
+name = "/users/{name}"
+auth = "/auth/{name}"
+recipients = "/recipients/{address}"
+verify = "/verify/{address}"
+domains = "/domains/{domain}"
+
+[directory."failing"]
+type = "http"
+url = "http://127.0.0.1:8822"
+endpoints.name = "/fail/{name}"
+circuit-breaker.failures = 2
+circuit-breaker.wait = "1h"
+"#;
+
+const JOHN: &str = r#"{
+    "name": "john",
+    "type": "admin",
+    "description": "John Doe",
+    "secret": "12345",
+    "emails": ["john@example.org", "jdoe@example.org"],
+    "quota": 1000,
+    "memberOf": ["sales"]
+}"#;
+
+#[tokio::test]
+async fn http_directory() {
+    let requests = spawn_mock_http_directory().await;
+    let temp_dir = TempDir::new("http_directory_test", true);
+    let mut config =
+        utils::config::Config::new(CONFIG.replace("{TMP}", &temp_dir.path.to_string_lossy()))
+            .unwrap();
+    let stores = Stores::parse_all(&mut config).await;
+    let store = stores.stores.get("sqlite").unwrap().clone();
+    let directories = Directories::parse(&mut config, &stores, store.clone()).await;
+    config.assert_no_errors();
+    let directory = directories.directories.get("http").unwrap();
+
+    // Principals are mapped from the JSON response
+    let principal = directory
+        .query(QueryBy::Name("john"), true)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(principal.name(), "john");
+    assert_eq!(principal.description(), Some("John Doe"));
+    assert_eq!(principal.quota(), 1000);
+    assert_eq!(
+        principal
+            .iter_str(PrincipalField::Emails)
+            .map(|email| email.as_str())
+            .collect::<Vec<_>>(),
+        vec!["john@example.org", "jdoe@example.org"]
+    );
+    assert_eq!(
+        principal
+            .iter_int(PrincipalField::MemberOf)
+            .collect::<Vec<_>>(),
+        vec![store.get_principal_id("sales").await.unwrap().unwrap() as u64]
+    );
+    assert!(directory
+        .query(QueryBy::Name("unknown"), false)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Credentials are verified by the backend
+    for (username, secret, expect) in [
+        ("john", "12345", true),
+        ("john", "wrong", false),
+        ("unknown", "12345", false),
+    ] {
+        assert_eq!(
+            directory
+                .query(
+                    QueryBy::Credentials(&Credentials::Plain {
+                        username: username.to_string(),
+                        secret: secret.to_string(),
+                    }),
+                    false,
+                )
+                .await
+                .unwrap()
+                .is_some(),
+            expect,
+            "{username}:{secret}"
+        );
+    }
+
+    // Recipient and domain lookups
+    assert_eq!(
+        directory.email_to_ids("jdoe@example.org").await.unwrap(),
+        vec![principal.id()]
+    );
+    assert!(directory.rcpt("jdoe@example.org").await.unwrap());
+    assert!(!directory.rcpt("unknown@example.org").await.unwrap());
+    assert_eq!(
+        directory.vrfy("jdoe@example.org").await.unwrap(),
+        vec!["john@example.org".to_string()]
+    );
+    assert_eq!(
+        directory.expn("sales@example.org").await.unwrap(),
+        Vec::<String>::new()
+    );
+    assert!(directory.is_local_domain("example.org").await.unwrap());
+    assert!(!directory.is_local_domain("other.org").await.unwrap());
+
+    // Repeated backend failures open the circuit breaker
+    let directory = directories.directories.get("failing").unwrap();
+    let count = requests.load(Ordering::Relaxed);
+    for _ in 0..4 {
+        assert!(directory.query(QueryBy::Name("john"), false).await.is_err());
+    }
+    assert_eq!(requests.load(Ordering::Relaxed) - count, 2);
+}
+
+async fn spawn_mock_http_directory() -> Arc<AtomicUsize> {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let requests_ = requests.clone();
+    let listener = TcpListener::bind("127.0.0.1:8822")
+        .await
+        .unwrap_or_else(|e| {
+            panic!("Failed to bind mock HTTP directory to 127.0.0.1:8822: {e}");
+        });
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let requests = requests_.clone();
+
+            tokio::spawn(async move {
+                let _ = http1::Builder::new()
+                    .keep_alive(false)
+                    .serve_connection(
+                        TokioIo::new(stream),
+                        service_fn(move |req: hyper::Request<body::Incoming>| {
+                            requests.fetch_add(1, Ordering::Relaxed);
+
+                            async move {
+                                let authorized = req
+                                    .headers()
+                                    .get(hyper::header::AUTHORIZATION)
+                                    .is_some_and(|auth| auth == "Basic c3RhbHdhcnQ6czNjcjN0");
+                                let method = req.method().clone();
+                                let path = req.uri().path().to_string();
+                                let body = req.into_body().collect().await.unwrap().to_bytes();
+
+                                let (status, response) = match (method, path.as_str()) {
+                                    (_, path) if path.starts_with("/fail/") => {
+                                        (StatusCode::INTERNAL_SERVER_ERROR, "")
+                                    }
+                                    _ if !authorized => (StatusCode::UNAUTHORIZED, ""),
+                                    (Method::GET, "/users/john") => (StatusCode::OK, JOHN),
+                                    (Method::POST, "/auth/john") => {
+                                        let request =
+                                            serde_json::from_slice::<serde_json::Value>(&body)
+                                                .unwrap();
+                                        if request["username"] == "john"
+                                            && request["secret"] == "12345"
+                                        {
+                                            (StatusCode::OK, JOHN)
+                                        } else {
+                                            (StatusCode::UNAUTHORIZED, "")
+                                        }
+                                    }
+                                    (Method::GET, "/recipients/jdoe%40example.org") => {
+                                        (StatusCode::OK, r#"["john"]"#)
+                                    }
+                                    (Method::GET, "/recipients/unknown%40example.org") => {
+                                        (StatusCode::OK, "[]")
+                                    }
+                                    (Method::GET, "/verify/jdoe%40example.org") => {
+                                        (StatusCode::OK, r#""john@example.org""#)
+                                    }
+                                    (Method::GET, "/domains/example.org") => (StatusCode::OK, ""),
+                                    _ => (StatusCode::NOT_FOUND, ""),
+                                };
+
+                                Ok::<_, hyper::Error>(
+                                    hyper::Response::builder()
+                                        .status(status)
+                                        .header(hyper::header::CONTENT_TYPE, "application/json")
+                                        .body(Full::new(body::Bytes::from(response)))
+                                        .unwrap(),
+                                )
+                            }
+                        }),
+                    )
+                    .await;
+            });
+        }
+    });
+
+    requests
+}
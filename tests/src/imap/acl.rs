@@ -6,7 +6,7 @@
 
 use imap_proto::ResponseType;
 
-use crate::jmap::delivery::SmtpConnection;
+use crate::smtp::client::SmtpConnection;
 
 use super::{append::assert_append_message, AssertResult, ImapConnection, Type};
 
@@ -0,0 +1,219 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use ahash::AHashSet;
+use imap_proto::ResponseType;
+use store::rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{AssertResult, ImapConnection, Type};
+
+const NUM_SESSIONS: usize = 5;
+const NUM_OPS: usize = 40;
+const TAGS: [&[u8]; NUM_SESSIONS] = [b"_c0 ", b"_c1 ", b"_c2 ", b"_c3 ", b"_c4 "];
+
+#[derive(Debug, Default)]
+struct SessionLog {
+    appended: Vec<u32>,
+    copied: Vec<u32>,
+    expunged: Vec<u32>,
+}
+
+pub async fn test(imap: &mut ImapConnection) {
+    println!("Running IMAP concurrency tests...");
+
+    for mailbox in ["Race", "Race Copies"] {
+        imap.send(&format!("CREATE \"{mailbox}\"")).await;
+        imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    }
+
+    // Run random APPEND/COPY/EXPUNGE/STORE sequences concurrently against one mailbox
+    let mut handles = Vec::with_capacity(NUM_SESSIONS);
+    for (session_num, tag) in TAGS.into_iter().enumerate() {
+        handles.push(tokio::spawn(run_session(session_num as u64, tag)));
+    }
+    let mut logs = Vec::with_capacity(NUM_SESSIONS);
+    for handle in handles {
+        logs.push(handle.await.unwrap());
+    }
+
+    // UIDs must never be assigned twice, neither within nor across sessions
+    let mut appended = AHashSet::new();
+    let mut copied = AHashSet::new();
+    let mut expunged = AHashSet::new();
+    for log in &logs {
+        for uid in &log.appended {
+            assert!(appended.insert(*uid), "Duplicate APPENDUID {uid}: {logs:?}");
+        }
+        for uid in &log.copied {
+            assert!(copied.insert(*uid), "Duplicate COPYUID {uid}: {logs:?}");
+        }
+        expunged.extend(log.expunged.iter().copied());
+    }
+
+    // EXISTS and the UID set must match the operations that were performed
+    let expected = appended
+        .difference(&expunged)
+        .copied()
+        .collect::<AHashSet<_>>();
+    for (mailbox, expected) in [("Race", &expected), ("Race Copies", &copied)] {
+        imap.send(&format!("SELECT \"{mailbox}\"")).await;
+        let exists = parse_exists(&imap.assert_read(Type::Tagged, ResponseType::Ok).await);
+        imap.send("UID SEARCH ALL").await;
+        let uids = parse_search(&imap.assert_read(Type::Tagged, ResponseType::Ok).await);
+        assert_eq!(exists as usize, uids.len(), "{mailbox}: {uids:?}");
+        assert_eq!(&uids, expected, "{mailbox}");
+    }
+
+    imap.send("UNSELECT").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    for mailbox in ["Race", "Race Copies"] {
+        imap.send(&format!("DELETE \"{mailbox}\"")).await;
+        imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    }
+}
+
+async fn run_session(session_num: u64, tag: &'static [u8]) -> SessionLog {
+    let mut rng = StdRng::seed_from_u64(session_num);
+    let mut log = SessionLog::default();
+    let mut uid_next = 0;
+
+    let mut imap = ImapConnection::connect(tag).await;
+    imap.assert_read(Type::Untagged, ResponseType::Ok).await;
+    imap.send("AUTHENTICATE PLAIN {32+}\r\nAGpkb2VAZXhhbXBsZS5jb20Ac2VjcmV0")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("SELECT \"Race\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+
+    for op_num in 0..NUM_OPS {
+        let known_uid =
+            (!log.appended.is_empty()).then(|| log.appended[rng.gen_range(0..log.appended.len())]);
+
+        match (rng.gen_range(0..4), known_uid) {
+            (1, Some(uid)) => {
+                imap.send(&format!("UID COPY {uid} \"Race Copies\"")).await;
+                let response = imap.read(Type::Tagged).await;
+                if response
+                    .last()
+                    .unwrap()
+                    .starts_with(&format!("{}OK", std::str::from_utf8(tag).unwrap()))
+                {
+                    // The message could have been expunged by this session
+                    if !log.expunged.contains(&uid) {
+                        log.copied.push(response.into_copy_uid().parse().unwrap());
+                    }
+                }
+            }
+            (2, Some(uid)) => {
+                imap.send(&format!("UID STORE {uid} +FLAGS (\\Deleted)"))
+                    .await;
+                imap.read(Type::Tagged).await;
+                imap.send(&format!("UID EXPUNGE {uid}")).await;
+                imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+                if !log.expunged.contains(&uid) {
+                    log.expunged.push(uid);
+                }
+            }
+            (3, Some(uid)) => {
+                imap.send(&format!(
+                    "UID STORE {uid} +FLAGS (\\Seen $Race{session_num})"
+                ))
+                .await;
+                imap.read(Type::Tagged).await;
+            }
+            _ => {
+                let message = format!(
+                    "From: race{session_num}@example.com\r\nSubject: Race {op_num}\r\n\r\nTest\r\n"
+                );
+                imap.send(&format!("APPEND \"Race\" {{{}}}", message.len()))
+                    .await;
+                imap.assert_read(Type::Continuation, ResponseType::Ok).await;
+                imap.send_untagged(&message).await;
+                let uid = imap
+                    .assert_read(Type::Tagged, ResponseType::Ok)
+                    .await
+                    .into_append_uid()
+                    .parse::<u32>()
+                    .unwrap();
+                assert!(uid >= uid_next, "UID {uid} below UIDNEXT {uid_next}");
+                log.appended.push(uid);
+            }
+        }
+
+        // UIDNEXT must never go backwards
+        imap.send("STATUS \"Race\" (UIDNEXT MESSAGES)").await;
+        let new_uid_next = parse_uid_next(&imap.assert_read(Type::Tagged, ResponseType::Ok).await);
+        assert!(
+            new_uid_next >= uid_next,
+            "UIDNEXT went backwards: {uid_next} -> {new_uid_next}"
+        );
+        assert!(
+            log.appended.iter().all(|uid| *uid < new_uid_next),
+            "UIDNEXT {new_uid_next} not above assigned UIDs {:?}",
+            log.appended
+        );
+        uid_next = new_uid_next;
+    }
+
+    // The EXISTS count seen by the session must match its UID set after synchronizing
+    imap.send("NOOP").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("SELECT \"Race\"").await;
+    let exists = parse_exists(&imap.assert_read(Type::Tagged, ResponseType::Ok).await);
+    imap.send("UID SEARCH ALL").await;
+    let uids = parse_search(&imap.assert_read(Type::Tagged, ResponseType::Ok).await);
+    assert_eq!(
+        exists as usize,
+        uids.len(),
+        "Session {session_num}: {uids:?}"
+    );
+
+    imap.send("LOGOUT").await;
+    imap.assert_read(Type::Untagged, ResponseType::Bye).await;
+
+    log
+}
+
+fn parse_exists(lines: &[String]) -> u32 {
+    lines
+        .iter()
+        .find_map(|line| {
+            line.strip_prefix("* ")?
+                .strip_suffix(" EXISTS")?
+                .parse()
+                .ok()
+        })
+        .unwrap_or_else(|| panic!("No EXISTS found in {lines:?}"))
+}
+
+fn parse_uid_next(lines: &[String]) -> u32 {
+    lines
+        .iter()
+        .find_map(|line| {
+            let (_, value) = line.split_once("UIDNEXT ")?;
+            value
+                .split(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse()
+                .ok()
+        })
+        .unwrap_or_else(|| panic!("No UIDNEXT found in {lines:?}"))
+}
+
+fn parse_search(lines: &[String]) -> AHashSet<u32> {
+    let mut uids = AHashSet::new();
+    for line in lines {
+        if let Some(values) = line.strip_prefix("* SEARCH") {
+            for uid in values.split_ascii_whitespace() {
+                assert!(
+                    uids.insert(uid.parse().unwrap()),
+                    "Duplicate UID {uid} in {line:?}"
+                );
+            }
+        }
+    }
+    uids
+}
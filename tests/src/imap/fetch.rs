@@ -20,7 +20,7 @@ pub async fn test(imap: &mut ImapConnection, _imap_check: &mut ImapConnection) {
 
     // Fetch all properties available from JMAP
     imap.send(concat!(
-        "FETCH 10 (FLAGS INTERNALDATE PREVIEW EMAILID THREADID ",
+        "FETCH 10 (FLAGS INTERNALDATE PREVIEW EMAILID THREADID X-GM-MSGID X-GM-THRID ",
         "RFC822.SIZE UID ENVELOPE BODYSTRUCTURE)"
     ))
     .await;
@@ -32,6 +32,8 @@ pub async fn test(imap: &mut ImapConnection, _imap_check: &mut ImapConnection) {
         .assert_contains("INTERNALDATE")
         .assert_contains("THREADID (")
         .assert_contains("EMAILID (")
+        .assert_contains("X-GM-MSGID ")
+        .assert_contains("X-GM-THRID ")
         .assert_contains("but then I thought, why not do both?")
         .assert_contains(concat!(
             "ENVELOPE (\"Sat, 20 Nov 2021 14:22:01 -0800\" ",
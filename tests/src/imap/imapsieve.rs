@@ -0,0 +1,118 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use imap_proto::ResponseType;
+
+use super::{
+    append::assert_append_message, managesieve::SieveConnection, AssertResult, IMAPTest,
+    ImapConnection, Type,
+};
+
+const IMAPSIEVE_SCRIPT: &str = r#"require ["environment"];
+if environment :is "imap.mailbox" "Sieve Discard" {
+    discard;
+} elsif environment :is "imap.mailbox" "Sieve Redirect" {
+    redirect "foobar@example.com";
+}
+"#;
+
+pub async fn test(handle: &IMAPTest) {
+    println!("Running IMAPSieve tests...");
+
+    // Limit the number of outgoing messages per IMAP event
+    let original_core = handle.jmap.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.sieve_imapsieve_max_messages = 2;
+    handle.jmap.shared_core.store(core.into());
+
+    // Upload imapsieve script
+    let mut sieve = SieveConnection::connect().await;
+    sieve.assert_read(ResponseType::Ok).await;
+    sieve
+        .send("AUTHENTICATE \"PLAIN\" \"AGpkb2VAZXhhbXBsZS5jb20Ac2VjcmV0\"")
+        .await;
+    sieve.assert_read(ResponseType::Ok).await;
+    sieve
+        .send_literal("PUTSCRIPT \"imapsieve\" ", IMAPSIEVE_SCRIPT)
+        .await;
+    sieve.assert_read(ResponseType::Ok).await;
+
+    // Open a new session so the new limits are picked up
+    let mut imap_sieve = ImapConnection::connect(b"_s ").await;
+    imap_sieve
+        .assert_read(Type::Untagged, ResponseType::Ok)
+        .await;
+    imap_sieve
+        .send("AUTHENTICATE PLAIN {32+}\r\nAGpkb2VAZXhhbXBsZS5jb20Ac2VjcmV0")
+        .await;
+    imap_sieve.assert_read(Type::Tagged, ResponseType::Ok).await;
+    for mailbox in ["Sieve Discard", "Sieve Redirect"] {
+        imap_sieve.send(&format!("CREATE \"{mailbox}\"")).await;
+        imap_sieve.assert_read(Type::Tagged, ResponseType::Ok).await;
+    }
+
+    // Discarded messages should be flagged as deleted (RFC 6785)
+    assert_append_message(
+        &mut imap_sieve,
+        "Sieve Discard",
+        "From: john\r\n\r\ndiscard me",
+        ResponseType::Ok,
+    )
+    .await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    imap_sieve.send("SELECT \"Sieve Discard\"").await;
+    imap_sieve
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("1 EXISTS");
+    imap_sieve.send("FETCH 1 (FLAGS)").await;
+    imap_sieve
+        .assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_contains("\\Deleted");
+
+    // Obtain the recipient's message count
+    let mut imap_rcpt = ImapConnection::connect(b"_r ").await;
+    imap_rcpt
+        .assert_read(Type::Untagged, ResponseType::Ok)
+        .await;
+    imap_rcpt
+        .send("AUTHENTICATE PLAIN {36+}\r\nAGZvb2JhckBleGFtcGxlLmNvbQBzZWNyZXQ=")
+        .await;
+    imap_rcpt.assert_read(Type::Tagged, ResponseType::Ok).await;
+    let num_messages = inbox_message_count(&mut imap_rcpt).await;
+
+    // Copying four messages should only redirect two of them
+    imap_sieve.send("SELECT INBOX").await;
+    imap_sieve.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap_sieve.send("COPY 1:4 \"Sieve Redirect\"").await;
+    imap_sieve.assert_read(Type::Tagged, ResponseType::Ok).await;
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(inbox_message_count(&mut imap_rcpt).await, num_messages + 2);
+
+    // Remove test data
+    for mailbox in ["Sieve Discard", "Sieve Redirect"] {
+        imap_sieve.send(&format!("DELETE \"{mailbox}\"")).await;
+        imap_sieve.assert_read(Type::Tagged, ResponseType::Ok).await;
+    }
+    sieve.send("DELETESCRIPT \"imapsieve\"").await;
+    sieve.assert_read(ResponseType::Ok).await;
+    handle.jmap.shared_core.store(original_core);
+}
+
+async fn inbox_message_count(imap: &mut ImapConnection) -> usize {
+    imap.send("STATUS INBOX (MESSAGES)").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .iter()
+        .find_map(|line| {
+            line.split_once("MESSAGES ")
+                .and_then(|(_, count)| count.trim_end_matches(')').parse().ok())
+        })
+        .expect("Missing STATUS response")
+}
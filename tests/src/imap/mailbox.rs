@@ -265,6 +265,59 @@ pub async fn test(mut imap: &mut ImapConnection, mut imap_check: &mut ImapConnec
             .assert_folders([("INBOX", ["Subscribed", "HasNoChildren"])], true);
     }
 
+    // Subscriptions are retained after a mailbox is deleted
+    imap.send("SUBSCRIBE \"Fruit/Apple/Red\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("DELETE \"Fruit/Apple/Red\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("LIST (SUBSCRIBED) \"\" \"*\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_folders(
+            [
+                ("INBOX", ["Subscribed", ""]),
+                ("Fruit/Apple/Red", ["NonExistent", "Subscribed"]),
+            ],
+            true,
+        );
+    imap.send("LSUB \"\" \"*\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_folders([("INBOX", [""]), ("Fruit/Apple/Red", ["NoSelect"])], true);
+    imap.send("LIST \"\" \"Fruit/*\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_folders([("Fruit/Apple", [""])], true);
+
+    // Recreating the mailbox restores its subscription
+    imap.send("CREATE \"Fruit/Apple/Red\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("LIST (SUBSCRIBED) \"\" \"*\" RETURN (CHILDREN)")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_folders(
+            [
+                ("INBOX", ["Subscribed", "HasNoChildren"]),
+                ("Fruit/Apple/Red", ["Subscribed", "HasNoChildren"]),
+            ],
+            true,
+        );
+
+    // Subscriptions to deleted mailboxes can be removed
+    imap.send("DELETE \"Fruit/Apple/Red\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("UNSUBSCRIBE \"Fruit/Apple/Red\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+    imap.send("UNSUBSCRIBE \"Fruit/Apple/Red\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::No).await;
+    imap.send("LIST (SUBSCRIBED) \"\" \"*\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_folders([("INBOX", ["Subscribed"])], true);
+    imap.send("CREATE \"Fruit/Apple/Red\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+
     // LIST Filters
     imap.send("LIST \"\" \"%\"").await;
     imap.assert_read(Type::Tagged, ResponseType::Ok)
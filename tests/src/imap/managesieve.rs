@@ -15,9 +15,9 @@ use tokio::{
 };
 use tokio_rustls::client::TlsStream;
 
-use super::AssertResult;
+use super::{AssertResult, IMAPTest};
 
-pub async fn test() {
+pub async fn test(handle: &IMAPTest) {
     println!("Running ManageSieve tests...");
 
     // Connect to ManageSieve
@@ -126,6 +126,24 @@ pub async fn test() {
         .assert_contains("holidays")
         .assert_count("ACTIVE", 0);
 
+    // Writes are rejected while in maintenance mode
+    let original_core = handle.jmap.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.storage.maintenance.enable = true;
+    handle.jmap.shared_core.store(core.into());
+    let mut sieve_maintenance = SieveConnection::connect().await;
+    sieve_maintenance.assert_read(ResponseType::Ok).await;
+    sieve_maintenance
+        .send("AUTHENTICATE \"PLAIN\" \"AGpkb2VAZXhhbXBsZS5jb20Ac2VjcmV0\"")
+        .await;
+    sieve_maintenance.assert_read(ResponseType::Ok).await;
+    sieve_maintenance.send("DELETESCRIPT \"holidays\"").await;
+    sieve_maintenance
+        .assert_read(ResponseType::No)
+        .await
+        .assert_contains("TRYLATER");
+    handle.jmap.shared_core.store(original_core);
+
     // DeleteScript
     sieve.send("DELETESCRIPT \"holidays\"").await;
     sieve.assert_read(ResponseType::Ok).await;
@@ -8,10 +8,12 @@ pub mod acl;
 pub mod append;
 pub mod basic;
 pub mod body_structure;
+pub mod concurrency;
 pub mod condstore;
 pub mod copy_move;
 pub mod fetch;
 pub mod idle;
+pub mod imapsieve;
 pub mod mailbox;
 pub mod managesieve;
 pub mod pop;
@@ -88,6 +90,10 @@ tls.implicit = false
 [server.socket]
 reuse-addr = true
 
+[server.greeting]
+imap = [{if = "listener = 'imap'", then = "key_get('default', 'hostname') + ' ready (' + listener + ')'"},
+        {else = "'Stalwart IMAP4rev2 at your service.'"}]
+
 [server.tls]
 enable = true
 implicit = false
@@ -454,7 +460,9 @@ pub async fn imap_tests() {
     let mut imap_check = ImapConnection::connect(b"_y ").await;
     let mut imap = ImapConnection::connect(b"_x ").await;
     for imap in [&mut imap, &mut imap_check] {
-        imap.assert_read(Type::Untagged, ResponseType::Ok).await;
+        imap.assert_read(Type::Untagged, ResponseType::Ok)
+            .await
+            .assert_contains("imap.example.org ready (imap)");
     }
 
     // Unauthenticated tests
@@ -483,6 +491,8 @@ pub async fn imap_tests() {
     idle::test(&mut imap, &mut imap_check).await;
     condstore::test(&mut imap, &mut imap_check).await;
     acl::test(&mut imap, &mut imap_check).await;
+    concurrency::test(&mut imap).await;
+    imapsieve::test(&handle).await;
 
     // Logout
     for imap in [&mut imap, &mut imap_check] {
@@ -494,7 +504,7 @@ pub async fn imap_tests() {
     }
 
     // Run ManageSieve tests
-    managesieve::test().await;
+    managesieve::test(&handle).await;
 
     // Run POP3 tests
     pop::test().await;
@@ -13,7 +13,7 @@ use tokio::{
 };
 use tokio_rustls::client::TlsStream;
 
-use crate::{jmap::delivery::SmtpConnection, smtp::session::VerifyResponse};
+use crate::smtp::{client::SmtpConnection, session::VerifyResponse};
 
 pub async fn test() {
     println!("Running POP3 tests...");
@@ -0,0 +1,241 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap::api::management::{
+    bundle::{AccountBundle, SieveScriptBundle},
+    messages::ImportMessageResult,
+};
+use jmap_client::{email, mailbox::Role};
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, test_account_login},
+};
+
+use super::{JMAPTest, ManagementApi};
+
+const MBOX: &str = concat!(
+    "From alice@example.org Mon Jan  6 10:00:00 2020\n",
+    "From: Alice <alice@example.org>\n",
+    "To: bundle.dst@example.com\n",
+    "Subject: First\n",
+    "Status: RO\n",
+    "X-Keywords: $Forwarded, Project\n",
+    "\n",
+    "First message\n",
+    "\n",
+    "From bob@example.org Tue Jan  7 10:00:00 2020\n",
+    "From: Bob <bob@example.org>\n",
+    "To: bundle.dst@example.com\n",
+    "Subject: Second\n",
+    "\n",
+    "Second message\n",
+);
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running account export/import tests...");
+    let server = params.server.clone();
+    let api = ManagementApi::new(8899, "admin", "secret");
+
+    for (email, name) in [
+        ("bundle.src@example.com", "Bundle Source"),
+        ("bundle.dst@example.com", "Bundle Destination"),
+    ] {
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(email, "12345", name, &[email][..])
+            .await;
+    }
+
+    // Populate the source account
+    let client = test_account_login("bundle.src@example.com", "12345").await;
+    let projects_id = client
+        .mailbox_create("Projects", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+    client
+        .mailbox_create("Roadmap", Some(&projects_id), Role::None)
+        .await
+        .unwrap();
+    client
+        .identity_create("Bundle Source", "bundle.src@example.com")
+        .await
+        .unwrap();
+    client
+        .sieve_script_create(
+            "filters",
+            b"require \"fileinto\"; fileinto \"Projects\";".to_vec(),
+            true,
+        )
+        .await
+        .unwrap();
+    client
+        .vacation_response_create("Out of office", "Back next week".into(), None::<String>)
+        .await
+        .unwrap();
+
+    // Export the source account
+    let mut bundle = api
+        .get::<AccountBundle>("/api/principal/bundle.src@example.com/export")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert!(bundle
+        .mailboxes
+        .iter()
+        .any(|m| m.path == "Projects/Roadmap"));
+    assert!(bundle
+        .mailboxes
+        .iter()
+        .any(|m| m.path == "Inbox" && m.role.as_deref() == Some("inbox")));
+    assert_eq!(bundle.identities.len(), 1);
+    assert_eq!(bundle.sieve_scripts.len(), 1);
+    assert_eq!(bundle.sieve_scripts[0].name, "filters");
+    assert!(bundle.sieve_scripts[0].is_active);
+    assert!(bundle.sieve_scripts[0].script.contains("fileinto"));
+    let vacation = bundle.vacation_response.as_ref().unwrap();
+    assert!(vacation.is_enabled);
+    assert_eq!(vacation.subject.as_deref(), Some("Out of office"));
+
+    // Import into the destination account
+    bundle.identities[0].email = "bundle.dst@example.com".to_string();
+    assert_eq!(
+        api.post::<Vec<String>>("/api/principal/bundle.dst@example.com/import", &bundle)
+            .await
+            .unwrap()
+            .unwrap_data(),
+        Vec::<String>::new()
+    );
+
+    // The destination account should now have the same structure
+    let imported = api
+        .get::<AccountBundle>("/api/principal/bundle.dst@example.com/export")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(
+        imported
+            .mailboxes
+            .iter()
+            .map(|m| m.path.as_str())
+            .collect::<Vec<_>>(),
+        bundle
+            .mailboxes
+            .iter()
+            .map(|m| m.path.as_str())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(imported.identities.len(), 1);
+    assert_eq!(imported.identities[0].name, "Bundle Source");
+    assert_eq!(imported.identities[0].email, "bundle.dst@example.com");
+    assert_eq!(imported.sieve_scripts.len(), 1);
+    assert_eq!(
+        imported.sieve_scripts[0].script,
+        bundle.sieve_scripts[0].script
+    );
+    assert!(imported.sieve_scripts[0].is_active);
+    assert_eq!(
+        imported.vacation_response.unwrap().text_body.as_deref(),
+        Some("Back next week")
+    );
+
+    // Importing twice should update the existing identities and scripts
+    bundle.identities[0].text_signature = "Regards".to_string();
+    assert_eq!(
+        api.post::<Vec<String>>("/api/principal/bundle.dst@example.com/import", &bundle)
+            .await
+            .unwrap()
+            .unwrap_data(),
+        Vec::<String>::new()
+    );
+    let imported = api
+        .get::<AccountBundle>("/api/principal/bundle.dst@example.com/export")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(imported.identities.len(), 1);
+    assert_eq!(imported.identities[0].text_signature, "Regards");
+    assert_eq!(imported.sieve_scripts.len(), 1);
+    assert!(imported.sieve_scripts[0].is_active);
+
+    // Scripts that do not compile are rejected before being stored
+    let mut invalid = AccountBundle::default();
+    invalid.sieve_scripts.push(SieveScriptBundle {
+        name: "broken".to_string(),
+        is_active: false,
+        script: "if true {".to_string(),
+    });
+    let errors = api
+        .post::<Vec<String>>("/api/principal/bundle.dst@example.com/import", &invalid)
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert!(
+        errors
+            .iter()
+            .any(|err| err.contains("\"broken\" not imported")),
+        "{errors:?}"
+    );
+    assert_eq!(
+        api.get::<AccountBundle>("/api/principal/bundle.dst@example.com/export")
+            .await
+            .unwrap()
+            .unwrap_data()
+            .sieve_scripts
+            .len(),
+        1
+    );
+
+    // Bulk import an mbox file into a new folder
+    let (ids, uids): (Vec<_>, Vec<_>) = api
+        .post_raw::<Vec<ImportMessageResult>>(
+            "/api/principal/bundle.dst@example.com/messages?format=mbox&mailbox=Archive%2F2020",
+            MBOX.to_string(),
+        )
+        .await
+        .unwrap()
+        .unwrap_data()
+        .into_iter()
+        .map(|result| match result {
+            ImportMessageResult::Success { id, uid, .. } => (id, uid),
+            ImportMessageResult::Error { name, reason } => {
+                panic!("Message {name} not imported: {reason}")
+            }
+        })
+        .unzip();
+    assert_eq!(ids.len(), 2);
+    assert!(uids[0] < uids[1], "{uids:?}");
+    let client = test_account_login("bundle.dst@example.com", "12345").await;
+    for (id, received_at, expected_keywords) in [
+        (&ids[0], 1578304800, vec!["$forwarded", "$seen", "project"]),
+        (&ids[1], 1578391200, vec![]),
+    ] {
+        let email = client
+            .email_get(
+                id,
+                Some([email::Property::ReceivedAt, email::Property::Keywords]),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(email.received_at(), Some(received_at));
+        let mut keywords = email.keywords();
+        keywords.sort_unstable();
+        assert_eq!(keywords, expected_keywords);
+    }
+
+    // Remove test data
+    for email in ["bundle.src@example.com", "bundle.dst@example.com"] {
+        api.delete::<()>(&format!("/api/principal/{email}"))
+            .await
+            .unwrap()
+            .unwrap_data();
+    }
+    assert_is_empty(server).await;
+}
@@ -0,0 +1,163 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::config::jmap::settings::AccountTemplate;
+use jmap_client::{
+    mailbox::{self, Role},
+    sieve::query::{Comparator, Filter},
+};
+use utils::config::Config;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, test_account_login},
+    AssertConfig,
+};
+
+use super::{JMAPTest, ManagementApi};
+
+const TEMPLATE: &str = r#"
+[jmap.account.template.folders.projects]
+name = "Projects"
+
+[jmap.account.template.folders.archive]
+name = "Projects/Archive"
+special-use = "archive"
+
+[jmap.account.template.folders.receipts]
+name = "Receipts"
+
+[jmap.account.template.sieve]
+name = "starter"
+script = "require \"fileinto\"; if header :contains \"subject\" \"receipt\" { fileinto \"Receipts\"; }"
+
+[jmap.account.template.identity]
+text-signature = "-- Sent from Example Mail"
+
+[jmap.account.template.welcome]
+from = "welcome@example.com"
+subject = "Welcome to Example Mail"
+text-body = "Your mailbox is ready."
+"#;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running account template tests...");
+    let server = params.server.clone();
+
+    // Enable the account template
+    let original_core = params.server.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    let mut config = Config::new(TEMPLATE).unwrap();
+    core.jmap.account_template = AccountTemplate::parse(&mut config);
+    config.assert_no_errors();
+    params.server.shared_core.store(core.into());
+
+    let account_id = server
+        .core
+        .storage
+        .data
+        .create_test_user(
+            "template@example.com",
+            "12345",
+            "Template User",
+            &["template@example.com"][..],
+        )
+        .await;
+    let client = test_account_login("template@example.com", "12345").await;
+
+    // Template folders are created alongside the special-use folders
+    for name in ["Projects", "Archive", "Receipts"] {
+        assert_eq!(
+            client
+                .mailbox_query(mailbox::query::Filter::name(name).into(), None::<Vec<_>>)
+                .await
+                .unwrap()
+                .ids()
+                .len(),
+            1,
+            "missing folder {name}"
+        );
+    }
+    let inbox_id = client
+        .mailbox_query(
+            mailbox::query::Filter::role(Role::Inbox).into(),
+            None::<Vec<_>>,
+        )
+        .await
+        .unwrap()
+        .take_ids()
+        .pop()
+        .unwrap();
+
+    // Template folders are assigned their special-use role
+    let archive_id = client
+        .mailbox_query(
+            mailbox::query::Filter::role(Role::Archive).into(),
+            None::<Vec<_>>,
+        )
+        .await
+        .unwrap()
+        .take_ids()
+        .pop()
+        .unwrap();
+    assert_eq!(
+        client
+            .mailbox_get(&archive_id, None::<Vec<_>>)
+            .await
+            .unwrap()
+            .unwrap()
+            .name(),
+        Some("Archive")
+    );
+
+    // The template is only applied once
+    server.account_apply_template(account_id).await;
+    let _ = test_account_login("template@example.com", "12345").await;
+
+    // The welcome message is delivered to the inbox
+    let inbox = client
+        .mailbox_get(&inbox_id, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(inbox.total_emails(), 1);
+
+    // The starter Sieve script is installed and active
+    let script_ids = client
+        .sieve_script_query(Filter::is_active(true).into(), [Comparator::name()].into())
+        .await
+        .unwrap()
+        .take_ids();
+    assert_eq!(script_ids.len(), 1);
+    assert_eq!(
+        client
+            .sieve_script_get(&script_ids[0], None::<Vec<_>>)
+            .await
+            .unwrap()
+            .unwrap()
+            .name(),
+        Some("starter")
+    );
+
+    // The default identity includes the template signature
+    let mut request = client.build();
+    request.get_identity();
+    let identities = request.send_get_identity().await.unwrap().take_list();
+    assert_eq!(identities.len(), 1);
+    assert_eq!(
+        identities[0].text_signature(),
+        Some("-- Sent from Example Mail")
+    );
+
+    // Restore the original configuration and remove test data
+    params.server.shared_core.store(original_core);
+    ManagementApi::new(8899, "admin", "secret")
+        .delete::<()>("/api/principal/template@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_is_empty(server).await;
+}
@@ -0,0 +1,301 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_client::mailbox::{self, Role};
+use jmap_proto::types::id::Id;
+use serde_json::{json, Value};
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty, emails_purge_tombstoned, jmap_json_request, test_account_login,
+        ManagementApi,
+    },
+};
+
+use super::JMAPTest;
+
+const MESSAGE: &str = concat!(
+    "From: Bill Foobar <activity.sender@example.com>\r\n",
+    "To: Jane Doe <activity@example.com>\r\n",
+    "Subject: Status report\r\n",
+    "\r\n",
+    "All systems nominal.\r\n"
+);
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Activity tests...");
+    let server = params.server.clone();
+
+    // Keep at most three entries
+    let original_core = params.server.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.mail_activity_max_entries = 3;
+    params.server.shared_core.store(core.into());
+
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "activity@example.com",
+                "12345",
+                "Jane Doe",
+                &["activity@example.com"][..],
+            )
+            .await,
+    )
+    .to_string();
+    let account_id = account_id.as_str();
+    let client = test_account_login("activity@example.com", "12345").await;
+    let inbox_id = client
+        .mailbox_query(
+            mailbox::query::Filter::role(Role::Inbox).into(),
+            None::<Vec<_>>,
+        )
+        .await
+        .unwrap()
+        .take_ids()
+        .pop()
+        .unwrap();
+    let mut email_ids = Vec::new();
+    for num in 0..5 {
+        email_ids.push(
+            client
+                .email_import(
+                    MESSAGE
+                        .replace("Status report", &format!("Status report {num}"))
+                        .into_bytes(),
+                    [&inbox_id],
+                    None::<Vec<&str>>,
+                    None,
+                )
+                .await
+                .unwrap()
+                .take_id(),
+        );
+    }
+
+    // The log starts out empty
+    let response = activity_get(account_id).await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list"),
+        Some(&json!([])),
+        "Response: {response:?}"
+    );
+    let initial_state = string(&response, "/methodResponses/0/1/state");
+
+    // Flagging messages adds entries, most recent first
+    for email_id in &email_ids[..2] {
+        client
+            .email_set_keyword(email_id, "$flagged", true)
+            .await
+            .unwrap();
+    }
+    let response = activity_get(account_id).await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list"),
+        Some(&json!([
+            {
+                "id": activity_id(1),
+                "type": "flagged",
+                "emailId": &email_ids[1],
+            },
+            {
+                "id": activity_id(0),
+                "type": "flagged",
+                "emailId": &email_ids[0],
+            }
+        ])),
+        "Response: {response:?}"
+    );
+    let flagged_state = string(&response, "/methodResponses/0/1/state");
+    assert_ne!(flagged_state, initial_state);
+
+    // Email changes that are not logged leave the Activity state unchanged
+    client
+        .email_set_keyword(&email_ids[2], "$seen", true)
+        .await
+        .unwrap();
+    assert_eq!(
+        string(
+            &activity_get(account_id).await,
+            "/methodResponses/0/1/state"
+        ),
+        flagged_state
+    );
+    assert_changes(
+        &activity_changes(account_id, &flagged_state, None).await,
+        &[],
+        &[],
+    );
+
+    // Changes since the initial state
+    let response = activity_changes(account_id, &initial_state, None).await;
+    assert_changes(&response, &[0, 1], &[]);
+    assert_eq!(
+        string(&response, "/methodResponses/0/1/newState"),
+        flagged_state
+    );
+
+    // Changes are paginated by log update
+    let response = activity_changes(account_id, &initial_state, Some(1)).await;
+    assert_changes(&response, &[0], &[]);
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/hasMoreChanges"),
+        Some(&json!(true))
+    );
+    let response = activity_changes(
+        account_id,
+        &string(&response, "/methodResponses/0/1/newState"),
+        Some(1),
+    )
+    .await;
+    assert_changes(&response, &[1], &[]);
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/hasMoreChanges"),
+        Some(&json!(false))
+    );
+
+    // Entries over the limit are reported as destroyed
+    for email_id in &email_ids[2..4] {
+        client
+            .email_set_keyword(email_id, "$flagged", true)
+            .await
+            .unwrap();
+    }
+    assert_changes(
+        &activity_changes(account_id, &flagged_state, None).await,
+        &[2, 3],
+        &[0],
+    );
+
+    // Entries of purged messages are reported as destroyed
+    let response = activity_get(account_id).await;
+    let purge_state = string(&response, "/methodResponses/0/1/state");
+    client.email_destroy(&email_ids[1]).await.unwrap();
+    emails_purge_tombstoned(&server).await;
+    let response = activity_get(account_id).await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/list")
+            .and_then(|list| list.as_array())
+            .map(|list| list
+                .iter()
+                .map(|item| item["id"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()),
+        Some(vec![activity_id(3), activity_id(2)]),
+        "Response: {response:?}"
+    );
+    assert_changes(
+        &activity_changes(account_id, &purge_state, None).await,
+        &[],
+        &[1],
+    );
+
+    // Changes cannot be calculated once their entries are no longer tracked
+    client
+        .email_set_keyword(&email_ids[4], "$flagged", true)
+        .await
+        .unwrap();
+    for _ in 0..2 {
+        client
+            .email_set_keyword(&email_ids[0], "$flagged", false)
+            .await
+            .unwrap();
+        client
+            .email_set_keyword(&email_ids[0], "$flagged", true)
+            .await
+            .unwrap();
+    }
+    for state in [&initial_state, &flagged_state] {
+        let response = activity_changes(account_id, state, None).await;
+        assert_eq!(
+            string(&response, "/methodResponses/0/1/type"),
+            "cannotCalculateChanges"
+        );
+    }
+    assert_changes(
+        &activity_changes(account_id, &purge_state, None).await,
+        &[4, 5, 6],
+        &[1, 2, 3],
+    );
+
+    // Restore the original configuration and remove test data
+    params.server.shared_core.store(original_core);
+    for email_id in email_ids.iter().filter(|id| *id != &email_ids[1]) {
+        client.email_destroy(email_id).await.unwrap();
+    }
+    ManagementApi::new(8899, "admin", "secret")
+        .delete::<()>("/api/principal/activity@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_is_empty(server).await;
+}
+
+async fn activity_get(account_id: &str) -> Value {
+    jmap_json_request(
+        r#"[["Activity/get", {"accountId": "$$", "properties": ["id", "type", "emailId"]}, "0"]]"#
+            .replace("$$", account_id),
+        "activity@example.com",
+        "12345",
+    )
+    .await
+}
+
+async fn activity_changes(
+    account_id: &str,
+    since_state: &str,
+    max_changes: Option<usize>,
+) -> Value {
+    jmap_json_request(
+        json!([[
+            "Activity/changes",
+            {
+                "accountId": account_id,
+                "sinceState": since_state,
+                "maxChanges": max_changes,
+            },
+            "0"
+        ]])
+        .to_string(),
+        "activity@example.com",
+        "12345",
+    )
+    .await
+}
+
+fn assert_changes(response: &Value, created: &[u64], destroyed: &[u64]) {
+    for (property, ids) in [("created", created), ("destroyed", destroyed)] {
+        assert_eq!(
+            response.pointer(&format!("/methodResponses/0/1/{property}")),
+            Some(&json!(ids
+                .iter()
+                .map(|id| activity_id(*id))
+                .collect::<Vec<_>>())),
+            "Response: {response:?}"
+        );
+    }
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/updated"),
+        Some(&json!([])),
+        "Response: {response:?}"
+    );
+}
+
+fn activity_id(id: u64) -> String {
+    Id::from(id).to_string()
+}
+
+fn string(response: &Value, pointer: &str) -> String {
+    response
+        .pointer(pointer)
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Missing {pointer:?} in response: {response:?}"))
+        .to_string()
+}
@@ -6,6 +6,7 @@
 
 use jmap::mailbox::{INBOX_ID, TRASH_ID};
 use jmap_client::{
+    client::{Client, Credentials},
     core::{
         error::{MethodError, MethodErrorType},
         set::{SetError, SetErrorType},
@@ -20,7 +21,9 @@ use store::ahash::AHashMap;
 
 use crate::{
     directory::internal::TestInternalDirectory,
-    jmap::{assert_is_empty, mailbox::destroy_all_mailboxes, test_account_login},
+    jmap::{
+        assert_is_empty, jmap_json_request, mailbox::destroy_all_mailboxes, test_account_login,
+    },
 };
 
 use super::JMAPTest;
@@ -661,6 +664,151 @@ pub async fn test(params: &mut JMAPTest) {
         "Owned by jane in inbox"
     );
 
+    // The principals capability identifies the user's own principal
+    let session: serde_json::Value = serde_json::from_str(
+        &reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap()
+            .get("https://127.0.0.1:8899/.well-known/jmap")
+            .basic_auth("jane.smith@example.com", Some("abcde"))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        session
+            .pointer(&format!(
+                "/accounts/{jane_id}/accountCapabilities/urn:ietf:params:jmap:principals/currentUserPrincipalId"
+            ))
+            .and_then(|id| id.as_str()),
+        Some(jane_id.to_string().as_str()),
+        "{session}"
+    );
+
+    // Share Jane's inbox with John using shareWith
+    let response = jmap_json_request(
+        format!(
+            r#"[["Mailbox/set", {{"accountId": "{jane_id}", "update": {{"{inbox_id}": {{"shareWith/{john_id}": {{"mayReadItems": true, "maySetSeen": true}}}}}}}}, "0"]]"#
+        ),
+        "jane.smith@example.com",
+        "abcde",
+    )
+    .await;
+    assert!(
+        response
+            .pointer(&format!("/methodResponses/0/1/updated/{inbox_id}"))
+            .is_some(),
+        "{response}"
+    );
+
+    // The grants should be stored as ACLs, as seen by IMAP
+    let response = jmap_json_request(
+        format!(
+            r#"[["Mailbox/get", {{"accountId": "{jane_id}", "ids": ["{inbox_id}"], "properties": ["acl", "shareWith"]}}, "0"]]"#
+        ),
+        "jane.smith@example.com",
+        "abcde",
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/list/0/acl/jdoe@example.com")
+            .unwrap(),
+        &serde_json::json!(["read", "readItems", "modifyItems"])
+    );
+    let share_with = response
+        .pointer(&format!("/methodResponses/0/1/list/0/shareWith/{john_id}"))
+        .unwrap();
+    for (right, expected) in [
+        ("mayReadItems", true),
+        ("maySetSeen", true),
+        ("maySetKeywords", true),
+        ("mayAddItems", false),
+        ("mayShare", false),
+    ] {
+        assert_eq!(share_with[right], expected, "{right}: {share_with}");
+    }
+    assert_eq!(
+        john_client
+            .set_default_account_id(jane_id.to_string())
+            .email_get(
+                email_ids.get("jane").unwrap().first().unwrap(),
+                [Property::Subject].into(),
+            )
+            .await
+            .unwrap()
+            .unwrap()
+            .subject()
+            .unwrap(),
+        "Owned by jane in inbox"
+    );
+
+    // John should not be able to reshare the mailbox
+    let response = jmap_json_request(
+        format!(
+            r#"[["Mailbox/set", {{"accountId": "{jane_id}", "update": {{"{inbox_id}": {{"shareWith/{bill_id}": {{"mayReadItems": true}}}}}}}}, "0"]]"#
+        ),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert!(
+        response
+            .pointer(&format!("/methodResponses/0/1/notUpdated/{inbox_id}"))
+            .is_some(),
+        "{response}"
+    );
+
+    // Revoke a single right, then all access
+    for (patch, expected_acl) in [
+        (
+            format!(r#""shareWith/{john_id}/maySetSeen": false"#),
+            serde_json::json!({
+                "jdoe@example.com": ["read", "readItems"],
+                "bill@example.com": ["read", "readItems"]
+            }),
+        ),
+        (
+            format!(r#""shareWith/{john_id}": null"#),
+            serde_json::json!({"bill@example.com": ["read", "readItems"]}),
+        ),
+    ] {
+        jmap_json_request(
+            format!(
+                r#"[["Mailbox/set", {{"accountId": "{jane_id}", "update": {{"{inbox_id}": {{{patch}}}}}}}, "0"]]"#
+            ),
+            "jane.smith@example.com",
+            "abcde",
+        )
+        .await;
+        let response = jmap_json_request(
+            format!(
+                r#"[["Mailbox/get", {{"accountId": "{jane_id}", "ids": ["{inbox_id}"], "properties": ["acl"]}}, "0"]]"#
+            ),
+            "jane.smith@example.com",
+            "abcde",
+        )
+        .await;
+        assert_eq!(
+            response.pointer("/methodResponses/0/1/list/0/acl").unwrap(),
+            &expected_acl
+        );
+    }
+    assert_forbidden(
+        john_client
+            .set_default_account_id(jane_id.to_string())
+            .email_get(
+                email_ids.get("jane").unwrap().first().unwrap(),
+                [Property::Subject].into(),
+            )
+            .await,
+    );
+
     // Add John and Jane to the Sales group
     for name in ["jdoe@example.com", "jane.smith@example.com"] {
         server
@@ -778,6 +926,45 @@ pub async fn test(params: &mut JMAPTest) {
             .await,
     );
 
+    // Full delegates can open the delegated account
+    server
+        .core
+        .storage
+        .data
+        .add_to_group("jane.smith@example.com", "bill@example.com")
+        .await;
+    server.core.security.access_tokens.clear();
+    let delegate_client =
+        test_account_login("bill@example.com%jane.smith@example.com", "abcde").await;
+    assert_eq!(delegate_client.session().username(), "bill@example.com");
+    assert_eq!(delegate_client.default_account_id(), bill_id.to_string());
+    assert!(Client::new()
+        .credentials(Credentials::basic(
+            "bill@example.com%jdoe@example.com",
+            "12345"
+        ))
+        .accept_invalid_certs(true)
+        .connect("https://127.0.0.1:8899")
+        .await
+        .is_err());
+
+    // Group members cannot log in as the group
+    assert!(Client::new()
+        .credentials(Credentials::basic(
+            "sales@example.com%jdoe@example.com",
+            "12345"
+        ))
+        .accept_invalid_certs(true)
+        .connect("https://127.0.0.1:8899")
+        .await
+        .is_err());
+    server
+        .core
+        .storage
+        .data
+        .remove_from_group("jane.smith@example.com", "bill@example.com")
+        .await;
+
     // Destroy test account data
     for id in [john_id, bill_id, jane_id, sales_id] {
         params.client.set_default_account_id(id.to_string());
@@ -418,19 +418,18 @@ pub async fn test(params: &mut JMAPTest) {
 
     // Blob/lookup
     params.client.set_default_account_id(account_id.to_string());
+    let message = concat!(
+        "From: bill@example.com\r\n",
+        "To: jdoe@example.com\r\n",
+        "Subject: TPS Report\r\n",
+        "\r\n",
+        "I'm going to need those TPS reports ASAP. ",
+        "So, if you could do that, that'd be great."
+    );
     let blob_id = params
         .client
         .email_import(
-            concat!(
-                "From: bill@example.com\r\n",
-                "To: jdoe@example.com\r\n",
-                "Subject: TPS Report\r\n",
-                "\r\n",
-                "I'm going to need those TPS reports ASAP. ",
-                "So, if you could do that, that'd be great."
-            )
-            .as_bytes()
-            .to_vec(),
+            message.as_bytes().to_vec(),
             [&Id::from(INBOX_ID).to_string()],
             None::<Vec<&str>>,
             None,
@@ -479,6 +478,44 @@ pub async fn test(params: &mut JMAPTest) {
         );
     }
 
+    // Blob/lookup finds the Emails referencing an uploaded blob with the same contents
+    let uploaded_blob_id = jmap_json_request(
+        r#"[["Blob/upload", {"accountId": "$$", "create": {"abc": {"data": [{"data:asText": %%}]}}}, "R1"]]"#
+            .replace("$$", &account_id.to_string())
+            .replace("%%", &serde_json::to_string(message).unwrap()),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await
+    .pointer("/methodResponses/0/1/created/abc/id")
+    .and_then(|v| v.as_str())
+    .unwrap()
+    .to_string();
+    assert_ne!(uploaded_blob_id, blob_id);
+    let response = jmap_json_request(
+        r#"[["Blob/lookup", {"accountId": "$$", "typeNames": ["Email", "Mailbox"], "ids": ["%%"]}, "R1"]]"#
+            .replace("$$", &account_id.to_string())
+            .replace("%%", &uploaded_blob_id),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list/0/matchedIds/Mailbox"),
+        Some(&serde_json::json!([Id::from(INBOX_ID).to_string()])),
+        "Response: {response:#?}",
+    );
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/list/0/matchedIds/Email")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.len())
+            .unwrap_or_default(),
+        1,
+        "Response: {response:#?}",
+    );
+    server.core.storage.data.blob_expire_all().await;
+
     // Remove test data
     params.client.set_default_account_id(account_id.to_string());
     destroy_all_mailboxes(params).await;
@@ -0,0 +1,751 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::{Duration, Instant};
+
+use jmap_proto::types::id::Id;
+use serde_json::Value;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty, dav_request,
+        email_submission::{assert_message_delivery, spawn_mock_smtp_server, MockMessage},
+        jmap_json_request,
+        mailbox::destroy_all_mailboxes,
+        DavResponse,
+    },
+    smtp::client::SmtpConnection,
+};
+
+use super::JMAPTest;
+
+const DAV_EVENT: &str = concat!(
+    "BEGIN:VCALENDAR\r\n",
+    "VERSION:2.0\r\n",
+    "PRODID:-//Example Corp.//CalDAV Client//EN\r\n",
+    "BEGIN:VEVENT\r\n",
+    "UID:code-review@example.com\r\n",
+    "DTSTAMP:20240101T000000Z\r\n",
+    "DTSTART:20240112T120000Z\r\n",
+    "DURATION:PT1H\r\n",
+    "SUMMARY:Code review\r\n",
+    "END:VEVENT\r\n",
+    "END:VCALENDAR\r\n"
+);
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Calendar tests...");
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    )
+    .to_string();
+    let other_account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jane@example.com",
+                "abcde",
+                "Jane Smith",
+                &["jane@example.com"],
+            )
+            .await,
+    )
+    .to_string();
+
+    // The default calendar is created on first access
+    let response = request(
+        r#"[["Calendar/get", {"accountId": "$$"}, "0"]]"#,
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list/0/isDefault"),
+        Some(&Value::Bool(true)),
+        "Response: {response:?}"
+    );
+    let default_id = string(&response, "/methodResponses/0/1/list/0/id");
+
+    // Create a calendar and events
+    let response = request(
+        &r##"[["Calendar/set", {"accountId": "$$", "create": {"c1": {"name": "Work", "color": "#ff0000"}}}, "0"],
+            ["CalendarEvent/set", {"accountId": "$$", "create": {
+                "e1": {
+                    "calendarIds": {"#c1": true},
+                    "title": "Team meeting",
+                    "start": "2024-01-08T10:00:00",
+                    "timeZone": "Etc/GMT-1",
+                    "duration": "PT1H",
+                    "recurrenceRules": [{"@type": "RecurrenceRule", "frequency": "weekly", "count": 4}]
+                },
+                "e2": {
+                    "calendarIds": {"%%": true},
+                    "title": "Dentist appointment",
+                    "start": "2024-01-10T15:00:00",
+                    "duration": "PT30M",
+                    "status": "tentative"
+                },
+                "e3": {
+                    "calendarIds": {"%%": true},
+                    "title": "Lunch",
+                    "utcStart": "2024-01-15T09:30:00Z",
+                    "utcEnd": "2024-01-15T10:30:00Z",
+                    "freeBusyStatus": "free"
+                },
+                "e4": {
+                    "title": "No calendar",
+                    "start": "2024-01-10T15:00:00"
+                },
+                "e5": {
+                    "calendarIds": {"%%": true},
+                    "title": "Invalid rule",
+                    "start": "2024-01-10T15:00:00",
+                    "recurrenceRules": [{"frequency": "weekly", "byDay": [{"day": "mo"}]}]
+                }
+            }}, "1"]]"##
+            .replace("%%", &default_id),
+        &account_id,
+    )
+    .await;
+    let work_id = string(&response, "/methodResponses/0/1/created/c1/id");
+    let e1 = string(&response, "/methodResponses/1/1/created/e1/id");
+    let e2 = string(&response, "/methodResponses/1/1/created/e2/id");
+    let e3 = string(&response, "/methodResponses/1/1/created/e3/id");
+    for id in ["e4", "e5"] {
+        assert_eq!(
+            response
+                .pointer(&format!("/methodResponses/1/1/notCreated/{id}/type"))
+                .and_then(|v| v.as_str()),
+            Some("invalidProperties"),
+            "Response: {response:?}"
+        );
+    }
+
+    // Dates are resolved to UTC
+    let response = request(
+        &format!(
+            r#"[["CalendarEvent/get", {{"accountId": "$$", "ids": ["{e1}", "{e3}"],
+                "properties": ["uid", "start", "duration", "utcStart", "utcEnd"]}}, "0"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    for (pointer, expected) in [
+        (
+            "/methodResponses/0/1/list/0/utcStart",
+            "2024-01-08T09:00:00Z",
+        ),
+        ("/methodResponses/0/1/list/0/utcEnd", "2024-01-08T10:00:00Z"),
+        ("/methodResponses/0/1/list/1/start", "2024-01-15T09:30:00"),
+        ("/methodResponses/0/1/list/1/duration", "PT1H"),
+    ] {
+        assert_eq!(
+            response.pointer(pointer).and_then(|v| v.as_str()),
+            Some(expected),
+            "Pointer {pointer:?} Response: {response:?}"
+        );
+    }
+    assert!(
+        !string(&response, "/methodResponses/0/1/list/0/uid").is_empty(),
+        "Response: {response:?}"
+    );
+
+    // Query events
+    for (arguments, expected) in [
+        (
+            format!(r#""filter": {{"inCalendars": ["{work_id}"]}}"#),
+            vec![e1.as_str()],
+        ),
+        (
+            r#""filter": {"operator": "AND", "conditions": [
+                {"after": "2024-01-20T00:00:00Z"}, {"before": "2024-02-01T00:00:00Z"}]}"#
+                .to_string(),
+            vec![e1.as_str()],
+        ),
+        (
+            r#""filter": {"text": "dentist"}"#.to_string(),
+            vec![e2.as_str()],
+        ),
+        (
+            r#""sort": [{"property": "start", "isAscending": false}]"#.to_string(),
+            vec![e3.as_str(), e2.as_str(), e1.as_str()],
+        ),
+    ] {
+        let response = request(
+            &format!(r#"[["CalendarEvent/query", {{"accountId": "$$", {arguments}}}, "0"]]"#),
+            &account_id,
+        )
+        .await;
+        assert_eq!(
+            response
+                .pointer("/methodResponses/0/1/ids")
+                .and_then(|v| v.as_array())
+                .map(|ids| ids.iter().filter_map(|id| id.as_str()).collect::<Vec<_>>()),
+            Some(expected),
+            "Arguments {arguments} Response: {response:?}"
+        );
+    }
+
+    // Query events over CalDAV
+    for (calendar_id, start, end, expected) in [
+        (
+            &work_id,
+            "20240120T000000Z",
+            "20240201T000000Z",
+            &["SUMMARY:Team meeting"][..],
+        ),
+        (&work_id, "20240102T000000Z", "20240105T000000Z", &[][..]),
+        (
+            &default_id,
+            "20240110T000000Z",
+            "20240111T000000Z",
+            &["SUMMARY:Dentist appointment"][..],
+        ),
+    ] {
+        let response = dav_report(
+            &format!("/dav/calendars/jdoe@example.com/{calendar_id}/"),
+            &calendar_query(&format!(
+                r#"<C:comp-filter name="VEVENT"><C:time-range start="{start}" end="{end}"/></C:comp-filter>"#
+            )),
+        )
+        .await;
+        assert_eq!(response.status, 207, "Response: {}", response.body);
+        assert_eq!(
+            response.body.matches("<D:response>").count(),
+            expected.len(),
+            "Range {start}/{end} Response: {}",
+            response.body
+        );
+        for summary in expected {
+            assert!(
+                response.body.contains(summary),
+                "Response: {}",
+                response.body
+            );
+        }
+    }
+    let default_path = format!("/dav/calendars/jdoe@example.com/{default_id}/");
+    for (filter, status, expected, responses) in [
+        ("", 207, "SUMMARY:Lunch", 2),
+        (
+            r#"<C:comp-filter name="VTODO"/>"#,
+            207,
+            "</D:multistatus>",
+            0,
+        ),
+        (
+            r#"<C:comp-filter name="VEVENT"><C:prop-filter name="SUMMARY"/></C:comp-filter>"#,
+            403,
+            "supported-filter",
+            0,
+        ),
+        (
+            r#"<C:comp-filter name="VEVENT"><C:time-range start="2024-01-10"/></C:comp-filter>"#,
+            403,
+            "valid-filter",
+            0,
+        ),
+    ] {
+        let response = dav_report(&default_path, &calendar_query(filter)).await;
+        assert_eq!(
+            response.status, status,
+            "Filter {filter} Response: {}",
+            response.body
+        );
+        assert!(
+            response.body.contains(expected),
+            "Filter {filter} Response: {}",
+            response.body
+        );
+        assert_eq!(response.body.matches("<D:response>").count(), responses);
+    }
+
+    // Synchronize the default calendar over CalDAV
+    let response = dav_report(&default_path, &sync_collection("")).await;
+    assert_eq!(response.status, 207, "Response: {}", response.body);
+    assert_eq!(response.body.matches("<D:response>").count(), 2);
+    let initial_token = sync_token(&response);
+    let event_path = format!("{default_path}code-review@example.com.ics");
+    let response = dav_request(
+        "PUT",
+        &event_path,
+        &[("content-type", "text/calendar"), ("if-none-match", "*")],
+        DAV_EVENT,
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(response.status, 201, "Response: {}", response.body);
+    let etag = response
+        .headers
+        .get("etag")
+        .and_then(|value| value.to_str().ok())
+        .unwrap()
+        .to_string();
+    let response = dav_request("GET", &event_path, &[], "", "jdoe@example.com", "12345").await;
+    assert_eq!(response.status, 200, "Response: {}", response.body);
+    assert!(
+        response.body.contains("SUMMARY:Code review\r\n"),
+        "Response: {}",
+        response.body
+    );
+    let response = dav_report(&default_path, &sync_collection(&initial_token)).await;
+    assert_eq!(response.status, 207, "Response: {}", response.body);
+    assert_eq!(response.body.matches("<D:response>").count(), 1);
+    assert!(
+        response.body.contains(&format!(
+            "<D:href>{event_path}</D:href><D:propstat><D:prop><D:getetag>{}</D:getetag>",
+            etag.replace('"', "&quot;")
+        )),
+        "Response: {}",
+        response.body
+    );
+    let current_token = sync_token(&response);
+    assert_ne!(current_token, initial_token);
+    let response = dav_report(&default_path, &sync_collection(&current_token)).await;
+    assert_eq!(response.body.matches("<D:response>").count(), 0);
+    assert_eq!(sync_token(&response), current_token);
+
+    // Deleted events require a full resync
+    let response = dav_request("DELETE", &event_path, &[], "", "jdoe@example.com", "12345").await;
+    assert_eq!(response.status, 204, "Response: {}", response.body);
+    let response = dav_report(&default_path, &sync_collection(&current_token)).await;
+    assert_eq!(response.status, 403, "Response: {}", response.body);
+    assert!(response.body.contains("valid-sync-token"));
+    let response = dav_report(&default_path, &sync_collection("")).await;
+    assert_eq!(response.body.matches("<D:response>").count(), 2);
+    assert!(!response.body.contains(&event_path));
+
+    // Free-busy information is only available to users the calendars are shared with
+    let availability = format!(
+        r#"[["Principal/getAvailability", {{"accountId": "{other_account_id}", "id": "{account_id}",
+            "utcStart": "2024-01-01T00:00:00Z", "utcEnd": "2024-01-16T00:00:00Z"}}, "0"]]"#
+    );
+    let response = jmap_json_request(&availability, "jane@example.com", "abcde").await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/type")
+            .and_then(|v| v.as_str()),
+        Some("forbidden"),
+        "Response: {response:?}"
+    );
+    let response = freebusy_request(&["mailto:jdoe@example.com"]).await;
+    assert_eq!(response.status, 200, "Response: {}", response.body);
+    assert!(
+        response
+            .body
+            .contains("<C:request-status>3.8;No authority</C:request-status>"),
+        "Response: {}",
+        response.body
+    );
+    let response = request(
+        &format!(
+            r#"[["Calendar/set", {{"accountId": "$$", "update": {{
+                "{default_id}": {{"shareWith": {{"{other_account_id}": {{"mayReadFreeBusy": true}}}}}},
+                "{work_id}": {{"shareWith/{other_account_id}": {{"mayReadFreeBusy": true}}}}
+            }}}}, "0"],
+            ["Calendar/get", {{"accountId": "$$", "ids": ["{work_id}"],
+                "properties": ["shareWith", "includeInAvailability"]}}, "1"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response.pointer(&format!(
+            "/methodResponses/1/1/list/0/shareWith/{other_account_id}/mayReadFreeBusy"
+        )),
+        Some(&Value::Bool(true)),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer(&format!(
+            "/methodResponses/1/1/list/0/shareWith/{other_account_id}/mayReadItems"
+        )),
+        Some(&Value::Bool(false)),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/methodResponses/1/1/list/0/includeInAvailability"),
+        Some(&Value::String("all".to_string())),
+        "Response: {response:?}"
+    );
+    let response = jmap_json_request(&availability, "jane@example.com", "abcde").await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list"),
+        Some(&serde_json::json!([
+            {"utcStart": "2024-01-08T09:00:00Z", "utcEnd": "2024-01-08T10:00:00Z", "busyStatus": "confirmed"},
+            {"utcStart": "2024-01-10T15:00:00Z", "utcEnd": "2024-01-10T15:30:00Z", "busyStatus": "tentative"},
+            {"utcStart": "2024-01-15T09:00:00Z", "utcEnd": "2024-01-15T10:00:00Z", "busyStatus": "confirmed"}
+        ])),
+        "Response: {response:?}"
+    );
+
+    // Free-busy queries sent to the scheduling outbox return the same periods
+    let response =
+        freebusy_request(&["mailto:jdoe@example.com", "mailto:unknown@example.com"]).await;
+    assert_eq!(response.status, 200, "Response: {}", response.body);
+    for expected in [
+        "<C:request-status>2.0;Success</C:request-status>",
+        "FREEBUSY;FBTYPE=BUSY:20240108T090000Z/20240108T100000Z",
+        "FREEBUSY;FBTYPE=BUSY-TENTATIVE:20240110T150000Z/20240110T153000Z",
+        "FREEBUSY;FBTYPE=BUSY:20240115T090000Z/20240115T100000Z",
+        "<C:recipient><D:href>mailto:unknown@example.com</D:href></C:recipient><C:request-status>3.7;Invalid calendar user</C:request-status>",
+    ] {
+        assert!(response.body.contains(expected), "Response: {}", response.body);
+    }
+    assert_eq!(response.body.matches("FREEBUSY;").count(), 3);
+    let response = dav_request(
+        "POST",
+        "/dav/calendars/jane@example.com/outbox/",
+        &[("content-type", "text/calendar")],
+        "BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n",
+        "jane@example.com",
+        "abcde",
+    )
+    .await;
+    assert_eq!(response.status, 403, "Response: {}", response.body);
+    assert!(response.body.contains("valid-calendar-data"));
+
+    // Calendars can be excluded from the availability
+    request(
+        &format!(
+            r#"[["Calendar/set", {{"accountId": "$$", "update": {{
+                "{default_id}": {{"includeInAvailability": "none"}}
+            }}}}, "0"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    let response = jmap_json_request(&availability, "jane@example.com", "abcde").await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list"),
+        Some(&serde_json::json!([
+            {"utcStart": "2024-01-08T09:00:00Z", "utcEnd": "2024-01-08T10:00:00Z", "busyStatus": "confirmed"},
+            {"utcStart": "2024-01-15T09:00:00Z", "utcEnd": "2024-01-15T10:00:00Z", "busyStatus": "confirmed"}
+        ])),
+        "Response: {response:?}"
+    );
+    request(
+        &format!(
+            r#"[["Calendar/set", {{"accountId": "$$", "update": {{
+                "{default_id}": {{"includeInAvailability": "all", "shareWith": {{}}}}
+            }}}}, "0"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+
+    // Recurrences follow the DST changes of their time zone
+    let response = request(
+        &format!(
+            r#"[["CalendarEvent/set", {{"accountId": "$$", "create": {{
+                "dst": {{
+                    "calendarIds": {{"{work_id}": true}},
+                    "title": "Standup",
+                    "start": "2024-03-25T10:00:00",
+                    "timeZone": "Europe/Berlin",
+                    "duration": "PT15M",
+                    "recurrenceRules": [{{"@type": "RecurrenceRule", "frequency": "weekly", "count": 2}}]
+                }}
+            }}}}, "0"],
+            ["Principal/getAvailability", {{"accountId": "$$", "id": "$$",
+                "utcStart": "2024-03-20T00:00:00Z", "utcEnd": "2024-04-05T00:00:00Z"}}, "1"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/1/1/list"),
+        Some(&serde_json::json!([
+            {"utcStart": "2024-03-25T09:00:00Z", "utcEnd": "2024-03-25T09:15:00Z", "busyStatus": "confirmed"},
+            {"utcStart": "2024-04-01T08:00:00Z", "utcEnd": "2024-04-01T08:15:00Z", "busyStatus": "confirmed"}
+        ])),
+        "Response: {response:?}"
+    );
+
+    // Calendars with events cannot be destroyed unless requested
+    let response = request(
+        &format!(r#"[["Calendar/set", {{"accountId": "$$", "destroy": ["{work_id}"]}}, "0"]]"#),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer(&format!("/methodResponses/0/1/notDestroyed/{work_id}/type"))
+            .and_then(|v| v.as_str()),
+        Some("calendarHasEvent"),
+        "Response: {response:?}"
+    );
+    let response = request(
+        &format!(
+            r#"[["Calendar/set", {{"accountId": "$$", "destroy": ["{work_id}"], "onDestroyRemoveEvents": true}}, "0"],
+                ["CalendarEvent/get", {{"accountId": "$$", "ids": ["{e1}"]}}, "1"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/destroyed/0"),
+        Some(&Value::String(work_id.clone())),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/methodResponses/1/1/notFound/0"),
+        Some(&Value::String(e1.clone())),
+        "Response: {response:?}"
+    );
+
+    // Invitations received by email are added as tentative events
+    let (mut smtp_rx, smtp_settings) = spawn_mock_smtp_server();
+    server.core.smtp.resolvers.dns.ipv4_add(
+        "localhost",
+        vec!["127.0.0.1".parse().unwrap()],
+        Instant::now() + Duration::from_secs(10),
+    );
+    let mut lmtp = SmtpConnection::connect().await;
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        &itip_message("REQUEST", "CONFIRMED"),
+    )
+    .await;
+    let response = request(
+        r##"[["CalendarEvent/query", {"accountId": "$$", "filter": {"text": "budget"}}, "0"],
+            ["CalendarEvent/get", {"accountId": "$$", "#ids": {"resultOf": "0", "name": "CalendarEvent/query", "path": "/ids"},
+                "properties": ["calendarIds", "title", "status", "sequence", "participants"]}, "1"]]"##,
+        &account_id,
+    )
+    .await;
+    let invite_id = string(&response, "/methodResponses/0/1/ids/0");
+    for (pointer, expected) in [
+        (
+            "/methodResponses/1/1/list/0/calendarIds/0".to_string(),
+            Value::String(default_id.clone()),
+        ),
+        (
+            "/methodResponses/1/1/list/0/status".to_string(),
+            Value::String("tentative".to_string()),
+        ),
+        (
+            "/methodResponses/1/1/list/0/sequence".to_string(),
+            Value::from(1),
+        ),
+        (
+            "/methodResponses/1/1/list/0/participants/jdoe@example.com/participationStatus"
+                .to_string(),
+            Value::String("needs-action".to_string()),
+        ),
+        (
+            "/methodResponses/1/1/list/0/participants/bill@remote.org/roles/owner".to_string(),
+            Value::Bool(true),
+        ),
+    ] {
+        assert_eq!(
+            response.pointer(&pointer),
+            Some(&expected),
+            "Pointer {pointer:?} Response: {response:?}"
+        );
+    }
+
+    // Accepting the invitation sends a reply to the organizer
+    smtp_settings.lock().do_stop = true;
+    let response = request(
+        &format!(
+            r#"[["CalendarEvent/set", {{"accountId": "$$", "update": {{"{invite_id}": {{
+                "status": "confirmed",
+                "participants": {{
+                    "bill@remote.org": {{"@type": "Participant", "email": "bill@remote.org",
+                        "name": "Bill Lumbergh", "roles": {{"owner": true}}}},
+                    "jdoe@example.com": {{"@type": "Participant", "email": "jdoe@example.com",
+                        "roles": {{"attendee": true}}, "participationStatus": "accepted"}}
+                }}
+            }}}}}}, "0"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert!(
+        response
+            .pointer(&format!("/methodResponses/0/1/updated/{invite_id}"))
+            .is_some(),
+        "Response: {response:?}"
+    );
+    assert_message_delivery(
+        &mut smtp_rx,
+        MockMessage::new(
+            "<jdoe@example.com>",
+            ["<bill@remote.org>"],
+            "@PARTSTAT=ACCEPTED",
+        ),
+    )
+    .await;
+
+    // Cancellations from the organizer are applied to the event
+    lmtp.ingest(
+        "bill@remote.org",
+        &["jdoe@example.com"],
+        &itip_message("CANCEL", "CANCELLED"),
+    )
+    .await;
+    lmtp.quit().await;
+    let response = request(
+        &format!(
+            r#"[["CalendarEvent/get", {{"accountId": "$$", "ids": ["{invite_id}"],
+                "properties": ["status"]}}, "0"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list/0/status"),
+        Some(&Value::String("cancelled".to_string())),
+        "Response: {response:?}"
+    );
+
+    // Remove test data
+    params.client.set_default_account_id(&account_id);
+    destroy_all_mailboxes(params).await;
+    let response = request(
+        &format!(
+            r#"[["CalendarEvent/set", {{"accountId": "$$", "destroy": ["{e2}", "{e3}", "{invite_id}"]}}, "0"],
+                ["Calendar/set", {{"accountId": "$$", "destroy": ["{default_id}"]}}, "1"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/1/1/destroyed")
+            .and_then(|v| v.as_array())
+            .map(|ids| ids.len()),
+        Some(1),
+        "Response: {response:?}"
+    );
+    assert_is_empty(server).await;
+}
+
+async fn dav_report(path: &str, body: &str) -> DavResponse {
+    dav_request(
+        "REPORT",
+        path,
+        &[("content-type", "application/xml"), ("depth", "1")],
+        body,
+        "jdoe@example.com",
+        "12345",
+    )
+    .await
+}
+
+fn calendar_query(filter: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+        <C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+        <D:prop><D:getetag/><C:calendar-data/></D:prop>
+        <C:filter><C:comp-filter name="VCALENDAR">{filter}</C:comp-filter></C:filter>
+        </C:calendar-query>"#
+    )
+}
+
+fn sync_collection(sync_token: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+        <D:sync-collection xmlns:D="DAV:"><D:sync-token>{sync_token}</D:sync-token>
+        <D:sync-level>1</D:sync-level><D:prop><D:getetag/></D:prop></D:sync-collection>"#
+    )
+}
+
+fn sync_token(response: &DavResponse) -> String {
+    response
+        .body
+        .split_once("<D:sync-token>")
+        .and_then(|(_, token)| token.split_once("</D:sync-token>"))
+        .map(|(token, _)| token.to_string())
+        .unwrap_or_else(|| panic!("Missing sync token in response: {}", response.body))
+}
+
+// Free-busy query sent by jane@example.com
+async fn freebusy_request(attendees: &[&str]) -> DavResponse {
+    let mut request = concat!(
+        "BEGIN:VCALENDAR\r\n",
+        "VERSION:2.0\r\n",
+        "METHOD:REQUEST\r\n",
+        "BEGIN:VFREEBUSY\r\n",
+        "UID:freebusy-query@example.com\r\n",
+        "DTSTAMP:20240101T000000Z\r\n",
+        "DTSTART:20240101T000000Z\r\n",
+        "DTEND:20240116T000000Z\r\n",
+        "ORGANIZER:mailto:jane@example.com\r\n",
+    )
+    .to_string();
+    for attendee in attendees {
+        request.push_str(&format!("ATTENDEE:{attendee}\r\n"));
+    }
+    request.push_str("END:VFREEBUSY\r\nEND:VCALENDAR\r\n");
+
+    dav_request(
+        "POST",
+        "/dav/calendars/jane@example.com/outbox/",
+        &[("content-type", "text/calendar")],
+        request,
+        "jane@example.com",
+        "abcde",
+    )
+    .await
+}
+
+async fn request(body: &str, account_id: &str) -> Value {
+    jmap_json_request(body.replace("$$", account_id), "jdoe@example.com", "12345").await
+}
+
+fn string(response: &Value, pointer: &str) -> String {
+    response
+        .pointer(pointer)
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Missing {pointer:?} in response: {response:?}"))
+        .to_string()
+}
+
+fn itip_message(method: &str, status: &str) -> String {
+    format!(
+        concat!(
+            "From: Bill Lumbergh <bill@remote.org>\r\n",
+            "To: jdoe@example.com\r\n",
+            "Subject: Budget review\r\n",
+            "MIME-Version: 1.0\r\n",
+            "Content-Type: text/calendar; method={method}; charset=utf-8\r\n",
+            "\r\n",
+            "BEGIN:VCALENDAR\r\n",
+            "VERSION:2.0\r\n",
+            "METHOD:{method}\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:budget-review@remote.org\r\n",
+            "SEQUENCE:1\r\n",
+            "DTSTART:20240205T140000Z\r\n",
+            "DURATION:PT1H\r\n",
+            "SUMMARY:Budget review\r\n",
+            "STATUS:{status}\r\n",
+            "ORGANIZER;CN=Bill Lumbergh:mailto:bill@remote.org\r\n",
+            "ATTENDEE;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:jdoe@example.com\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n"
+        ),
+        method = method,
+        status = status
+    )
+}
@@ -0,0 +1,620 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::types::{collection::Collection, id::Id};
+use serde_json::Value;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, dav_request, jmap_json_request, DavResponse},
+};
+
+use super::JMAPTest;
+
+const VCARD: &str = concat!(
+    "BEGIN:VCARD\r\n",
+    "VERSION:4.0\r\n",
+    "UID:urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1\r\n",
+    "FN:Simon Perreault\r\n",
+    "N:Perreault;Simon;;;ing. jr,M.Sc.\r\n",
+    "EMAIL;TYPE=work:simon.perreault@viagenie.ca\r\n",
+    "TEL;VALUE=uri;TYPE=\"work,voice\";PREF=1:tel:+1-418-656-9254\r\n",
+    "END:VCARD\r\n"
+);
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Contacts tests...");
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    )
+    .to_string();
+    let jane_id = server
+        .core
+        .storage
+        .data
+        .create_test_user(
+            "jane@example.com",
+            "abcde",
+            "Jane Smith",
+            &["jane@example.com"],
+        )
+        .await;
+
+    // The default address book is created on first access
+    let response = request(
+        r#"[["AddressBook/get", {"accountId": "$$"}, "0"]]"#,
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list/0/isDefault"),
+        Some(&Value::Bool(true)),
+        "Response: {response:?}"
+    );
+    let default_id = string(&response, "/methodResponses/0/1/list/0/id");
+
+    // Create an address book and cards
+    let response = request(
+        &r##"[["AddressBook/set", {"accountId": "$$", "create": {"b1": {"name": "Friends"}}}, "0"],
+            ["ContactCard/set", {"accountId": "$$", "create": {
+                "c1": {
+                    "addressBookIds": {"#b1": true},
+                    "@type": "Card",
+                    "version": "1.0",
+                    "kind": "individual",
+                    "name": {"full": "Jane Smith"},
+                    "emails": {"e1": {"@type": "EmailAddress", "address": "Jane@Example.com"}}
+                },
+                "c2": {
+                    "addressBookIds": {"%%": true},
+                    "@type": "Card",
+                    "version": "1.0",
+                    "kind": "org",
+                    "organizations": {"o1": {"@type": "Organization", "name": "Acme Corp"}}
+                },
+                "c3": {
+                    "@type": "Card",
+                    "name": {"full": "No address book"}
+                },
+                "c4": {
+                    "addressBookIds": {"%%": true},
+                    "kind": "spaceship"
+                }
+            }}, "1"]]"##
+            .replace("%%", &default_id),
+        &account_id,
+    )
+    .await;
+    let friends_id = string(&response, "/methodResponses/0/1/created/b1/id");
+    let c1 = string(&response, "/methodResponses/1/1/created/c1/id");
+    let c2 = string(&response, "/methodResponses/1/1/created/c2/id");
+    for id in ["c3", "c4"] {
+        assert_eq!(
+            response
+                .pointer(&format!("/methodResponses/1/1/notCreated/{id}/type"))
+                .and_then(|v| v.as_str()),
+            Some("invalidProperties"),
+            "Response: {response:?}"
+        );
+    }
+
+    // Cards are returned as stored
+    let response = request(
+        &format!(r#"[["ContactCard/get", {{"accountId": "$$", "ids": ["{c1}"]}}, "0"]]"#),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list/0/emails/e1/address"),
+        Some(&Value::String("Jane@Example.com".to_string())),
+        "Response: {response:?}"
+    );
+    assert!(
+        !string(&response, "/methodResponses/0/1/list/0/uid").is_empty(),
+        "Response: {response:?}"
+    );
+
+    // Query cards
+    for (arguments, expected) in [
+        (
+            format!(r#""filter": {{"inAddressBook": "{friends_id}"}}"#),
+            vec![c1.as_str()],
+        ),
+        (
+            r#""filter": {"email": "jane@example.com"}"#.to_string(),
+            vec![c1.as_str()],
+        ),
+        (
+            r#""filter": {"text": "acme"}"#.to_string(),
+            vec![c2.as_str()],
+        ),
+        (
+            r#""filter": {"kind": "org"}"#.to_string(),
+            vec![c2.as_str()],
+        ),
+        (
+            r#""sort": [{"property": "name", "isAscending": true}]"#.to_string(),
+            vec![c2.as_str(), c1.as_str()],
+        ),
+    ] {
+        let response = request(
+            &format!(r#"[["ContactCard/query", {{"accountId": "$$", {arguments}}}, "0"]]"#),
+            &account_id,
+        )
+        .await;
+        assert_eq!(
+            response
+                .pointer("/methodResponses/0/1/ids")
+                .and_then(|v| v.as_array())
+                .map(|ids| ids.iter().filter_map(|id| id.as_str()).collect::<Vec<_>>()),
+            Some(expected),
+            "Arguments {arguments} Response: {response:?}"
+        );
+    }
+
+    // Parse a vCard
+    let response = request(
+        &format!(
+            r#"[["Blob/upload", {{"accountId": "$$", "create": {{"v1": {{"data": [{{"data:asText": {}}}], "type": "text/vcard"}}}}}}, "0"]]"#,
+            serde_json::to_string(VCARD).unwrap()
+        ),
+        &account_id,
+    )
+    .await;
+    let blob_id = string(&response, "/methodResponses/0/1/created/v1/id");
+    let response = request(
+        &format!(
+            r#"[["ContactCard/parse", {{"accountId": "$$", "blobIds": ["{blob_id}"]}}, "0"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    for (pointer, expected) in [
+        ("uid", "urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1"),
+        ("name/full", "Simon Perreault"),
+        ("emails/e1/address", "simon.perreault@viagenie.ca"),
+        ("phones/p1/number", "tel:+1-418-656-9254"),
+    ] {
+        let pointer = format!("/methodResponses/0/1/parsed/{blob_id}/{pointer}");
+        assert_eq!(
+            response.pointer(&pointer).and_then(|v| v.as_str()),
+            Some(expected),
+            "Pointer {pointer:?} Response: {response:?}"
+        );
+    }
+
+    // Share the address book with another user
+    let response = request(
+        &format!(
+            r#"[["AddressBook/set", {{"accountId": "$$", "update": {{"{friends_id}": {{"acl": {{"jane@example.com": ["read", "readItems"]}}}}}}}}, "0"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert!(
+        response
+            .pointer(&format!("/methodResponses/0/1/updated/{friends_id}"))
+            .is_some(),
+        "Response: {response:?}"
+    );
+    let response = jmap_json_request(
+        format!(
+            r#"[["ContactCard/query", {{"accountId": "{account_id}"}}, "0"],
+                ["ContactCard/get", {{"accountId": "{account_id}", "ids": ["{c1}", "{c2}"], "properties": ["name"]}}, "1"]]"#
+        ),
+        "jane@example.com",
+        "abcde",
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/ids"),
+        Some(&serde_json::json!([c1])),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/methodResponses/1/1/list/0/name/full"),
+        Some(&Value::String("Jane Smith".to_string())),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/methodResponses/1/1/notFound/0"),
+        Some(&Value::String(c2.clone())),
+        "Response: {response:?}"
+    );
+
+    // Address books with cards cannot be destroyed unless requested
+    let response = request(
+        &format!(
+            r#"[["AddressBook/set", {{"accountId": "$$", "destroy": ["{friends_id}"]}}, "0"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer(&format!(
+                "/methodResponses/0/1/notDestroyed/{friends_id}/type"
+            ))
+            .and_then(|v| v.as_str()),
+        Some("addressBookHasContents"),
+        "Response: {response:?}"
+    );
+    let response = request(
+        &format!(
+            r#"[["AddressBook/set", {{"accountId": "$$", "destroy": ["{friends_id}"], "onDestroyRemoveContents": true}}, "0"],
+                ["ContactCard/get", {{"accountId": "$$", "ids": ["{c1}"]}}, "1"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/destroyed/0"),
+        Some(&Value::String(friends_id.clone())),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/methodResponses/1/1/notFound/0"),
+        Some(&Value::String(c1.clone())),
+        "Response: {response:?}"
+    );
+
+    // DAV writes are rejected while in maintenance mode
+    let original_core = params.server.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.storage.maintenance.enable = true;
+    params.server.shared_core.store(core.into());
+    let card_path = format!("/dav/addressbooks/jdoe@example.com/{default_id}/maintenance.vcf");
+    let response = dav_request(
+        "PUT",
+        &card_path,
+        &[("content-type", "text/vcard")],
+        VCARD,
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(response.status, 503, "Response: {}", response.body);
+    for method in ["DELETE", "MKCOL", "PROPPATCH", "MOVE", "COPY"] {
+        let response = dav_request(method, &card_path, &[], "", "jdoe@example.com", "12345").await;
+        assert_eq!(response.status, 503, "{method}: {}", response.body);
+    }
+
+    // Reads are allowed but do not create the default address book
+    let response = dav_request(
+        "PROPFIND",
+        "/dav/addressbooks/jane@example.com/",
+        &[("depth", "1")],
+        "",
+        "jane@example.com",
+        "abcde",
+    )
+    .await;
+    assert_eq!(response.status, 207, "Response: {}", response.body);
+    assert!(server
+        .get_document_ids(jane_id, Collection::AddressBook)
+        .await
+        .unwrap()
+        .unwrap_or_default()
+        .is_empty());
+    params.server.shared_core.store(original_core);
+
+    // Obtain the initial CardDAV sync token
+    let book_path = format!("/dav/addressbooks/jdoe@example.com/{default_id}/");
+    let card_path = format!("{book_path}urn%3Auuid%3A4fbe8971-0bc3-424c-9c26-36c3e1eff6b1.vcf");
+    let response = dav_report(&book_path, &sync_collection("")).await;
+    assert_eq!(response.status, 207, "Response: {}", response.body);
+    assert_eq!(response.body.matches("<D:response>").count(), 1);
+    let initial_token = sync_token(&response);
+
+    // Create a card over CardDAV
+    let response = dav_request(
+        "PUT",
+        &card_path,
+        &[("content-type", "text/vcard"), ("if-none-match", "*")],
+        VCARD,
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(response.status, 201, "Response: {}", response.body);
+    let etag = header(&response, "etag");
+    let response = dav_request(
+        "PUT",
+        &card_path,
+        &[("content-type", "text/vcard"), ("if-none-match", "*")],
+        VCARD,
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(response.status, 412, "Response: {}", response.body);
+
+    // Fetch the card
+    let response = dav_request("GET", &card_path, &[], "", "jdoe@example.com", "12345").await;
+    assert_eq!(response.status, 200, "Response: {}", response.body);
+    assert_eq!(header(&response, "etag"), etag);
+    assert_eq!(
+        header(&response, "content-type"),
+        "text/vcard; charset=utf-8"
+    );
+    for line in [
+        "UID:urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1\r\n",
+        "FN:Simon Perreault\r\n",
+    ] {
+        assert!(response.body.contains(line), "Response: {}", response.body);
+    }
+
+    // Cards with a different UID or invalid data are rejected
+    let response = dav_request(
+        "PUT",
+        &format!("{book_path}other.vcf"),
+        &[("content-type", "text/vcard")],
+        VCARD,
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(response.status, 400, "Response: {}", response.body);
+    assert!(response.body.contains("no-uid-conflict"));
+    let response = dav_request(
+        "PUT",
+        &format!("{book_path}other.vcf"),
+        &[("content-type", "text/vcard")],
+        "not a vcard",
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(response.status, 403, "Response: {}", response.body);
+    assert!(response.body.contains("valid-address-data"));
+
+    // Update the card, stale entity tags are rejected
+    let updated_vcard = VCARD.replace("FN:Simon Perreault", "FN:Simon Perreault Jr");
+    let response = dav_request(
+        "PUT",
+        &card_path,
+        &[("content-type", "text/vcard"), ("if-match", &etag)],
+        updated_vcard.clone(),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(response.status, 204, "Response: {}", response.body);
+    let old_etag = etag;
+    let etag = header(&response, "etag");
+    assert_ne!(etag, old_etag);
+    let response = dav_request(
+        "PUT",
+        &card_path,
+        &[("content-type", "text/vcard"), ("if-match", &old_etag)],
+        updated_vcard,
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(response.status, 412, "Response: {}", response.body);
+
+    // Query the address book
+    for (filter, expected) in [
+        (
+            r#"<C:filter><C:prop-filter name="FN"><C:text-match>perreault</C:text-match></C:prop-filter></C:filter>"#,
+            1,
+        ),
+        (
+            r#"<C:filter><C:prop-filter name="EMAIL"><C:text-match>Simon.Perreault@viagenie.ca</C:text-match></C:prop-filter></C:filter>"#,
+            1,
+        ),
+        (
+            r#"<C:filter><C:prop-filter name="UID"><C:text-match>unknown</C:text-match></C:prop-filter></C:filter>"#,
+            0,
+        ),
+        ("", 2),
+    ] {
+        let response = dav_report(
+            &book_path,
+            &format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+                <C:addressbook-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+                <D:prop><D:getetag/><C:address-data/></D:prop>{filter}</C:addressbook-query>"#
+            ),
+        )
+        .await;
+        assert_eq!(response.status, 207, "Response: {}", response.body);
+        assert_eq!(
+            response.body.matches("<D:response>").count(),
+            expected,
+            "Filter {filter} Response: {}",
+            response.body
+        );
+        if expected == 1 {
+            assert_item(&response, &card_path, &etag);
+            assert!(
+                response.body.contains("FN:Simon Perreault Jr"),
+                "Response: {}",
+                response.body
+            );
+        }
+    }
+    let response = dav_report(
+        &book_path,
+        r#"<?xml version="1.0" encoding="utf-8"?>
+        <C:addressbook-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+        <C:filter><C:prop-filter name="TEL"><C:text-match>418</C:text-match></C:prop-filter></C:filter>
+        </C:addressbook-query>"#,
+    )
+    .await;
+    assert_eq!(response.status, 403, "Response: {}", response.body);
+    assert!(response.body.contains("supported-filter"));
+
+    // Fetch cards by href
+    let missing_path = format!("{book_path}missing.vcf");
+    let response = dav_report(
+        &book_path,
+        &format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+            <C:addressbook-multiget xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+            <D:prop><D:getetag/><C:address-data/></D:prop>
+            <D:href>{card_path}</D:href><D:href>{missing_path}</D:href>
+            </C:addressbook-multiget>"#
+        ),
+    )
+    .await;
+    assert_eq!(response.status, 207, "Response: {}", response.body);
+    assert_item(&response, &card_path, &etag);
+    assert!(
+        response.body.contains("FN:Simon Perreault Jr"),
+        "Response: {}",
+        response.body
+    );
+    assert!(
+        response.body.contains(&format!(
+            "<D:href>{missing_path}</D:href><D:status>HTTP/1.1 404 Not Found</D:status>"
+        )),
+        "Response: {}",
+        response.body
+    );
+
+    // Only changed cards are returned when syncing from a previous token
+    let response = dav_report(&book_path, &sync_collection(&initial_token)).await;
+    assert_eq!(response.status, 207, "Response: {}", response.body);
+    assert_eq!(response.body.matches("<D:response>").count(), 1);
+    assert_item(&response, &card_path, &etag);
+    let current_token = sync_token(&response);
+    assert_ne!(current_token, initial_token);
+    let response = dav_report(&book_path, &sync_collection(&current_token)).await;
+    assert_eq!(response.body.matches("<D:response>").count(), 0);
+    assert_eq!(sync_token(&response), current_token);
+    let response = dav_report(&book_path, &sync_collection("urn:invalid")).await;
+    assert_eq!(response.status, 403, "Response: {}", response.body);
+    assert!(response.body.contains("valid-sync-token"));
+
+    // Delete the card
+    let response = dav_request(
+        "DELETE",
+        &card_path,
+        &[("if-match", &old_etag)],
+        "",
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(response.status, 412, "Response: {}", response.body);
+    let response = dav_request(
+        "DELETE",
+        &card_path,
+        &[("if-match", &etag)],
+        "",
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(response.status, 204, "Response: {}", response.body);
+    for method in ["GET", "DELETE"] {
+        let response = dav_request(method, &card_path, &[], "", "jdoe@example.com", "12345").await;
+        assert_eq!(response.status, 404, "{method}: {}", response.body);
+    }
+
+    // Deletions require a full resync
+    let response = dav_report(&book_path, &sync_collection(&current_token)).await;
+    assert_eq!(response.status, 403, "Response: {}", response.body);
+    assert!(response.body.contains("valid-sync-token"));
+    let response = dav_report(&book_path, &sync_collection("")).await;
+    assert_eq!(response.status, 207, "Response: {}", response.body);
+    assert_eq!(response.body.matches("<D:response>").count(), 1);
+    assert!(!response.body.contains(&card_path));
+
+    // Remove test data
+    let response = request(
+        &format!(
+            r#"[["ContactCard/set", {{"accountId": "$$", "destroy": ["{c2}"]}}, "0"],
+                ["AddressBook/set", {{"accountId": "$$", "destroy": ["{default_id}"]}}, "1"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/1/1/destroyed")
+            .and_then(|v| v.as_array())
+            .map(|ids| ids.len()),
+        Some(1),
+        "Response: {response:?}"
+    );
+    server.core.storage.data.blob_expire_all().await;
+    assert_is_empty(server).await;
+}
+
+async fn dav_report(path: &str, body: &str) -> DavResponse {
+    dav_request(
+        "REPORT",
+        path,
+        &[("content-type", "application/xml"), ("depth", "1")],
+        body,
+        "jdoe@example.com",
+        "12345",
+    )
+    .await
+}
+
+fn sync_collection(sync_token: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+        <D:sync-collection xmlns:D="DAV:"><D:sync-token>{sync_token}</D:sync-token>
+        <D:sync-level>1</D:sync-level><D:prop><D:getetag/></D:prop></D:sync-collection>"#
+    )
+}
+
+fn sync_token(response: &DavResponse) -> String {
+    response
+        .body
+        .split_once("<D:sync-token>")
+        .and_then(|(_, token)| token.split_once("</D:sync-token>"))
+        .map(|(token, _)| token.to_string())
+        .unwrap_or_else(|| panic!("Missing sync token in response: {}", response.body))
+}
+
+fn header(response: &DavResponse, name: &str) -> String {
+    response
+        .headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_else(|| panic!("Missing {name:?} header in response: {}", response.body))
+        .to_string()
+}
+
+fn assert_item(response: &DavResponse, href: &str, etag: &str) {
+    assert!(
+        response.body.contains(&format!(
+            "<D:response><D:href>{href}</D:href><D:propstat><D:prop><D:getetag>{}</D:getetag>",
+            etag.replace('"', "&quot;")
+        )),
+        "Response: {}",
+        response.body
+    );
+}
+
+async fn request(body: &str, account_id: &str) -> Value {
+    jmap_json_request(body.replace("$$", account_id), "jdoe@example.com", "12345").await
+}
+
+fn string(response: &Value, pointer: &str) -> String {
+    response
+        .pointer(pointer)
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Missing {pointer:?} in response: {response:?}"))
+        .to_string()
+}
@@ -13,8 +13,7 @@ use jmap_proto::types::id::Id;
 use mail_parser::{MessageParser, MimeHeaders};
 
 use crate::{
-    directory::internal::TestInternalDirectory,
-    jmap::{delivery::SmtpConnection, ManagementApi},
+    directory::internal::TestInternalDirectory, jmap::ManagementApi, smtp::client::SmtpConnection,
 };
 
 use super::JMAPTest;
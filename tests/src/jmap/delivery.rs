@@ -4,19 +4,15 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::time::Duration;
-
+use common::listener::blocked::TrustedNetworks;
 use jmap::mailbox::{INBOX_ID, JUNK_ID};
 use jmap_proto::types::{collection::Collection, id::Id, property::Property};
-
-use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines, ReadHalf, WriteHalf},
-    net::TcpStream,
-};
+use utils::config::Config;
 
 use crate::{
     directory::internal::TestInternalDirectory,
     jmap::{assert_is_empty, mailbox::destroy_all_mailboxes},
+    smtp::client::{AssertResult, SmtpConnection},
 };
 
 use super::JMAPTest;
@@ -76,6 +72,14 @@ pub async fn test(params: &mut JMAPTest) {
         )
         .await;
 
+    // Spam status headers are only kept when delivered by trusted peers
+    let original_core = server.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    let mut config = Config::new("server.trusted-networks = [\"127.0.0.1\"]").unwrap();
+    core.network.trusted_networks = TrustedNetworks::parse(&mut config);
+    config.assert_no_errors();
+    server.shared_core.store(core.into());
+
     // Delivering to individuals
     let mut lmtp = SmtpConnection::connect().await;
     params.webhook.clear();
@@ -182,6 +186,19 @@ pub async fn test(params: &mut JMAPTest) {
     lmtp.vrfy("members@example.com", 5).await;
     lmtp.vrfy("non_existant@example.com", 5).await;
 
+    // Pipelined commands
+    lmtp.script(concat!(
+        "C: MAIL FROM:<bill@example.com>\n",
+        "C: RCPT TO:<jdoe@example.com>\n",
+        "C: RCPT TO:<non_existant@example.com>\n",
+        "C: RSET\n",
+        "S: 250\n",
+        "S: 250\n",
+        "S: 5\n",
+        "S: 250\n",
+    ))
+    .await;
+
     // Delivering to a mailing list
     lmtp.ingest(
         "bill@example.com",
@@ -293,6 +310,7 @@ pub async fn test(params: &mut JMAPTest) {
     }
 
     // Remove test data
+    server.shared_core.store(original_core);
     for account_id in [&account_id_1, &account_id_2, &account_id_3] {
         params.client.set_default_account_id(account_id);
         destroy_all_mailboxes(params).await;
@@ -303,224 +321,9 @@ pub async fn test(params: &mut JMAPTest) {
     params.webhook.assert_contains(&[
         "message-ingest.",
         "delivery.dsn",
+        "queue.message-accepted",
+        "queue.message-delivered",
         "\"from\": \"bill@example.com\"",
         "\"john.doe@example.com\"",
     ]);
 }
-
-pub struct SmtpConnection {
-    reader: Lines<BufReader<ReadHalf<TcpStream>>>,
-    writer: WriteHalf<TcpStream>,
-}
-
-impl SmtpConnection {
-    pub async fn ingest_with_code(
-        &mut self,
-        from: &str,
-        recipients: &[&str],
-        message: &str,
-        code: u8,
-    ) -> Vec<String> {
-        self.mail_from(from, 2).await;
-        for recipient in recipients {
-            self.rcpt_to(recipient, 2).await;
-        }
-        self.data(3).await;
-        let result = self.data_bytes(message, recipients.len(), code).await;
-        tokio::time::sleep(Duration::from_millis(500)).await;
-        result
-    }
-
-    pub async fn ingest(&mut self, from: &str, recipients: &[&str], message: &str) {
-        self.ingest_with_code(from, recipients, message, 2).await;
-    }
-
-    async fn ingest_chunked(
-        &mut self,
-        from: &str,
-        recipients: &[&str],
-        message: &str,
-        chunk_size: usize,
-    ) {
-        self.mail_from(from, 2).await;
-        for recipient in recipients {
-            self.rcpt_to(recipient, 2).await;
-        }
-        for chunk in message.as_bytes().chunks(chunk_size) {
-            self.bdat(std::str::from_utf8(chunk).unwrap(), 2).await;
-        }
-        self.bdat_last("", recipients.len(), 2).await;
-        tokio::time::sleep(Duration::from_millis(500)).await;
-    }
-
-    pub async fn connect() -> Self {
-        SmtpConnection::connect_port(11200).await
-    }
-
-    pub async fn connect_port(port: u16) -> Self {
-        let (reader, writer) = tokio::io::split(
-            TcpStream::connect(&format!("127.0.0.1:{port}"))
-                .await
-                .unwrap(),
-        );
-        let mut conn = SmtpConnection {
-            reader: BufReader::new(reader).lines(),
-            writer,
-        };
-        conn.read(1, 2).await;
-        conn.lhlo().await;
-        conn
-    }
-
-    pub async fn lhlo(&mut self) -> Vec<String> {
-        self.send("LHLO localhost").await;
-        self.read(1, 2).await
-    }
-
-    pub async fn mail_from(&mut self, sender: &str, code: u8) -> Vec<String> {
-        self.send(&format!("MAIL FROM:<{}>", sender)).await;
-        self.read(1, code).await
-    }
-
-    pub async fn rcpt_to(&mut self, rcpt: &str, code: u8) -> Vec<String> {
-        self.send(&format!("RCPT TO:<{}>", rcpt)).await;
-        self.read(1, code).await
-    }
-
-    pub async fn vrfy(&mut self, rcpt: &str, code: u8) -> Vec<String> {
-        self.send(&format!("VRFY {}", rcpt)).await;
-        self.read(1, code).await
-    }
-
-    pub async fn expn(&mut self, rcpt: &str, code: u8) -> Vec<String> {
-        self.send(&format!("EXPN {}", rcpt)).await;
-        self.read(1, code).await
-    }
-
-    pub async fn data(&mut self, code: u8) -> Vec<String> {
-        self.send("DATA").await;
-        self.read(1, code).await
-    }
-
-    pub async fn data_bytes(
-        &mut self,
-        message: &str,
-        num_responses: usize,
-        code: u8,
-    ) -> Vec<String> {
-        self.send_raw(message).await;
-        self.send_raw("\r\n.\r\n").await;
-        self.read(num_responses, code).await
-    }
-
-    pub async fn bdat(&mut self, chunk: &str, code: u8) -> Vec<String> {
-        self.send_raw(&format!("BDAT {}\r\n{}", chunk.len(), chunk))
-            .await;
-        self.read(1, code).await
-    }
-
-    pub async fn bdat_last(&mut self, chunk: &str, num_responses: usize, code: u8) -> Vec<String> {
-        self.send_raw(&format!("BDAT {} LAST\r\n{}", chunk.len(), chunk))
-            .await;
-        self.read(num_responses, code).await
-    }
-
-    pub async fn rset(&mut self) -> Vec<String> {
-        self.send("RSET").await;
-        self.read(1, 2).await
-    }
-
-    pub async fn noop(&mut self) -> Vec<String> {
-        self.send("NOOP").await;
-        self.read(1, 2).await
-    }
-
-    pub async fn quit(&mut self) -> Vec<String> {
-        self.send("QUIT").await;
-        self.read(1, 2).await
-    }
-
-    pub async fn read(&mut self, mut num_responses: usize, code: u8) -> Vec<String> {
-        let mut lines = Vec::new();
-        loop {
-            match tokio::time::timeout(Duration::from_millis(1500), self.reader.next_line()).await {
-                Ok(Ok(Some(line))) => {
-                    let is_done = line.as_bytes()[3] == b' ';
-                    //let c = println!("<- {:?}", line);
-                    lines.push(line);
-                    if is_done {
-                        num_responses -= 1;
-                        if num_responses != 0 {
-                            continue;
-                        }
-
-                        if code != u8::MAX {
-                            for line in &lines {
-                                if line.as_bytes()[0] - b'0' != code {
-                                    panic!("Expected completion code {}, got {:?}.", code, lines);
-                                }
-                            }
-                        }
-                        return lines;
-                    }
-                }
-                Ok(Ok(None)) => {
-                    panic!("Invalid response: {:?}.", lines);
-                }
-                Ok(Err(err)) => {
-                    panic!("Connection broken: {} ({:?})", err, lines);
-                }
-                Err(_) => panic!("Timeout while waiting for server response: {:?}", lines),
-            }
-        }
-    }
-
-    pub async fn send(&mut self, text: &str) {
-        //let c = println!("-> {:?}", text);
-        self.writer.write_all(text.as_bytes()).await.unwrap();
-        self.writer.write_all(b"\r\n").await.unwrap();
-    }
-
-    pub async fn send_raw(&mut self, text: &str) {
-        //let c = println!("-> {:?}", text);
-        self.writer.write_all(text.as_bytes()).await.unwrap();
-    }
-}
-
-pub trait AssertResult: Sized {
-    fn assert_contains(self, text: &str) -> Self;
-    fn assert_count(self, text: &str, occurrences: usize) -> Self;
-    fn assert_equals(self, text: &str) -> Self;
-}
-
-impl AssertResult for Vec<String> {
-    fn assert_contains(self, text: &str) -> Self {
-        for line in &self {
-            if line.contains(text) {
-                return self;
-            }
-        }
-        panic!("Expected response to contain {:?}, got {:?}", text, self);
-    }
-
-    fn assert_count(self, text: &str, occurrences: usize) -> Self {
-        assert_eq!(
-            self.iter().filter(|l| l.contains(text)).count(),
-            occurrences,
-            "Expected {} occurrences of {:?}, found {}.",
-            occurrences,
-            text,
-            self.iter().filter(|l| l.contains(text)).count()
-        );
-        self
-    }
-
-    fn assert_equals(self, text: &str) -> Self {
-        for line in &self {
-            if line == text {
-                return self;
-            }
-        }
-        panic!("Expected response to be {:?}, got {:?}", text, self);
-    }
-}
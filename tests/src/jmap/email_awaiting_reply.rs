@@ -0,0 +1,185 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_client::mailbox::{self, Role};
+use jmap_proto::types::id::Id;
+use store::write::now;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, jmap_json_request, test_account_login, ManagementApi},
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Email awaiting reply tests...");
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "reply.tracker@example.com",
+                "12345",
+                "Reply Tracker",
+                &["reply.tracker@example.com"][..],
+            )
+            .await,
+    )
+    .to_string();
+    let client = test_account_login("reply.tracker@example.com", "12345").await;
+    let mut mailbox_ids = Vec::new();
+    for role in [Role::Inbox, Role::Sent] {
+        mailbox_ids.push(
+            client
+                .mailbox_query(mailbox::query::Filter::role(role).into(), None::<Vec<_>>)
+                .await
+                .unwrap()
+                .take_ids()
+                .pop()
+                .unwrap(),
+        );
+    }
+    let (inbox_id, sent_id) = (&mailbox_ids[0], &mailbox_ids[1]);
+
+    // Import two sent messages, one of them sent five days ago
+    let mut sent_ids = Vec::new();
+    for (num, received_at) in [(1, now() - 5 * 86400), (2, now())] {
+        sent_ids.push(
+            client
+                .email_import(
+                    format!(
+                        concat!(
+                            "From: Reply Tracker <reply.tracker@example.com>\r\n",
+                            "To: Jane Doe <jane.doe@example.org>\r\n",
+                            "Subject: Proposal {num}\r\n",
+                            "Message-ID: <proposal-{num}@example.com>\r\n",
+                            "\r\n",
+                            "Any thoughts?\r\n"
+                        ),
+                        num = num
+                    )
+                    .into_bytes(),
+                    [sent_id],
+                    None::<Vec<&str>>,
+                    Some(received_at as i64),
+                )
+                .await
+                .unwrap()
+                .take_id(),
+        );
+    }
+
+    // Drafts are not awaiting a reply
+    client
+        .email_import(
+            concat!(
+                "From: Reply Tracker <reply.tracker@example.com>\r\n",
+                "To: Jane Doe <jane.doe@example.org>\r\n",
+                "Subject: Unfinished\r\n",
+                "\r\n",
+                "Work in progress\r\n"
+            )
+            .as_bytes()
+            .to_vec(),
+            [sent_id],
+            Some(["$draft"]),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(awaiting_reply(&account_id, 0).await, sent_ids);
+    assert_eq!(
+        awaiting_reply(&account_id, 3).await,
+        vec![sent_ids[0].clone()]
+    );
+
+    // A reply to the first message removes it from the results
+    client
+        .email_import(
+            concat!(
+                "From: Jane Doe <jane.doe@example.org>\r\n",
+                "To: Reply Tracker <reply.tracker@example.com>\r\n",
+                "Subject: Re: Proposal 1\r\n",
+                "Message-ID: <reply-1@example.org>\r\n",
+                "In-Reply-To: <proposal-1@example.com>\r\n",
+                "References: <proposal-1@example.com>\r\n",
+                "\r\n",
+                "Sounds good.\r\n"
+            )
+            .as_bytes()
+            .to_vec(),
+            [inbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        awaiting_reply(&account_id, 0).await,
+        vec![sent_ids[1].clone()]
+    );
+    assert!(awaiting_reply(&account_id, 3).await.is_empty());
+
+    // A follow-up on the second thread replaces the original message
+    let follow_up_id = client
+        .email_import(
+            concat!(
+                "From: Reply Tracker <reply.tracker@example.com>\r\n",
+                "To: Jane Doe <jane.doe@example.org>\r\n",
+                "Subject: Re: Proposal 2\r\n",
+                "Message-ID: <proposal-2-follow-up@example.com>\r\n",
+                "In-Reply-To: <proposal-2@example.com>\r\n",
+                "References: <proposal-2@example.com>\r\n",
+                "\r\n",
+                "Friendly reminder.\r\n"
+            )
+            .as_bytes()
+            .to_vec(),
+            [sent_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+    assert_eq!(awaiting_reply(&account_id, 0).await, vec![follow_up_id]);
+
+    // Remove test data
+    ManagementApi::new(8899, "admin", "secret")
+        .delete::<()>("/api/principal/reply.tracker@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_is_empty(server).await;
+}
+
+async fn awaiting_reply(account_id: &str, days: u32) -> Vec<String> {
+    let response = jmap_json_request(
+        format!(
+            concat!(
+                "[[\"Email/query\", {{\"accountId\": \"{account_id}\", ",
+                "\"filter\": {{\"awaitingReply\": {days}}}, ",
+                "\"sort\": [{{\"property\": \"receivedAt\", \"isAscending\": true}}]}}, \"0\"]]"
+            ),
+            account_id = account_id,
+            days = days
+        ),
+        "reply.tracker@example.com",
+        "12345",
+    )
+    .await;
+    response
+        .pointer("/methodResponses/0/1/ids")
+        .and_then(|ids| ids.as_array())
+        .unwrap_or_else(|| panic!("Unexpected response: {response:?}"))
+        .iter()
+        .map(|id| id.as_str().unwrap().to_string())
+        .collect::<Vec<_>>()
+}
@@ -203,6 +203,15 @@ pub async fn query(client: &mut Client) {
             vec![email::query::Comparator::from()],
             vec!["T10965"],
         ),
+        (
+            Filter::and(vec![
+                (email::query::Filter::header("X-Artist-Role".to_string(), Some("attributed"))),
+                (email::query::Filter::from("john")),
+                (email::query::Filter::cc("oil")),
+            ]),
+            vec![email::query::Comparator::from()],
+            vec!["T10965"],
+        ),
         (
             Filter::and(vec![
                 (email::query::Filter::all_in_thread_have_keyword("N")),
@@ -772,7 +781,7 @@ pub async fn create(client: &mut Client) {
                 format!(
                     concat!(
                         "Date: {}\nFrom: \"{}\" <artist@domain.com>\nCc: \"{}\" <cc@domain.com>\nMessage-ID: <{}>\n",
-                        "References: <{}>\nComments: {}\nSubject: [{}]",
+                        "References: <{}>\nComments: {}\nX-Artist-Role: {}\nSubject: [{}]",
                         " Year {}\n\n{}\n{}\n"
                     ),
                     DateTime::from_timestamp(sent_at as i64 + idx as i64).to_rfc822(),
@@ -781,6 +790,7 @@ pub async fn create(client: &mut Client) {
                     values_str["accession_number"],
                     values_int["year"],
                     values_str["artistRole"],
+                    values_str["artistRole"],
                     values_str["title"],
                     values_int["year"],
                     values_str["creditLine"],
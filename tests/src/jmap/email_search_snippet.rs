@@ -31,6 +31,7 @@ pub async fn test(params: &mut JMAPTest) {
     // Import test messages
     for email_name in [
         "html",
+        "html_style",
         "subpart",
         "mixed",
         "text_plain",
@@ -123,6 +124,12 @@ pub async fn test(params: &mut JMAPTest) {
             "Tieren, eine Beute der Hunde, der Adler, ja fast aller Raubtiere! ",
             "Unsere stete Angst ist är")),
         ),
+        (
+            Filter::text("sale").into(),
+            "html_style",
+            Some("Spring <mark>sale</mark>"),
+            Some("The <mark>sale</mark> starts today. "),
+        ),
         (
             Filter::text("es:galería vasto biblioteca").into(),
             "mixed",
@@ -4,19 +4,20 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{fs, path::PathBuf};
+use std::{fs, path::PathBuf, time::Duration};
 
 use crate::jmap::{assert_is_empty, mailbox::destroy_all_mailboxes};
 use ahash::AHashSet;
-use jmap::mailbox::INBOX_ID;
+use jmap::{mailbox::INBOX_ID, JMAP};
 use jmap_client::{
     client::Client,
-    core::set::{SetError, SetErrorType},
-    email::{self, Email},
+    core::set::{SetError, SetErrorType, SetObject},
+    email::{self, Email, EmailBodyPart},
     mailbox::Role,
     Error, Set,
 };
 use jmap_proto::types::id::Id;
+use utils::config::Rate;
 
 use super::{find_values, replace_blob_ids, replace_boundaries, replace_values, JMAPTest};
 
@@ -29,6 +30,7 @@ pub async fn test(params: &mut JMAPTest) {
 
     create(&mut params.client, &mailbox_id).await;
     update(&mut params.client, &mailbox_id).await;
+    draft_autosave(&server, &mut params.client, &mailbox_id).await;
 
     destroy_all_mailboxes(params).await;
     assert_is_empty(server).await;
@@ -295,6 +297,104 @@ async fn update(client: &mut Client, root_mailbox_id: &str) {
         .unwrap();
 }
 
+async fn draft_autosave(server: &JMAP, client: &mut Client, mailbox_id: &str) {
+    // Draft replacements are not limited by default
+    assert!(server.core.jmap.mail_draft_replace_rate.is_none());
+    let (draft_id, _) = save_draft(client, mailbox_id, None, "Draft").await;
+    let mut draft_id = draft_id.unwrap();
+    for num in 0..5 {
+        let (new_draft_id, destroyed) =
+            save_draft(client, mailbox_id, Some(&draft_id), &format!("Draft {num}")).await;
+        assert_eq!(destroyed, vec![draft_id.clone()]);
+        draft_id = new_draft_id.unwrap();
+    }
+    client.email_destroy(&draft_id).await.unwrap();
+
+    // Allow two draft replacements per hour
+    let original_core = server.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.mail_draft_replace_rate = Rate {
+        requests: 2,
+        period: Duration::from_secs(3600),
+    }
+    .into();
+    server.shared_core.store(core.into());
+
+    // Save a new draft
+    let (draft_id, destroyed) = save_draft(client, mailbox_id, None, "Hello").await;
+    let mut draft_id = draft_id.unwrap();
+    assert!(destroyed.is_empty());
+
+    // Autosaves replace the previous version with a new message
+    for text in ["Hello", "Hello world"] {
+        let (new_draft_id, destroyed) = save_draft(client, mailbox_id, Some(&draft_id), text).await;
+        let new_draft_id = new_draft_id.unwrap();
+        assert_ne!(new_draft_id, draft_id);
+        assert_eq!(destroyed, vec![draft_id.clone()]);
+        assert!(client
+            .email_get(&draft_id, None::<Vec<_>>)
+            .await
+            .unwrap()
+            .is_none());
+        draft_id = new_draft_id;
+    }
+
+    // Further replacements are rate limited and keep the previous version
+    let (new_draft_id, destroyed) =
+        save_draft(client, mailbox_id, Some(&draft_id), "Hello world!").await;
+    assert!(matches!(
+        new_draft_id,
+        Err(Error::Set(SetError {
+            type_: SetErrorType::RateLimit,
+            ..
+        }))
+    ));
+    assert!(destroyed.is_empty());
+    assert!(client
+        .email_get(&draft_id, None::<Vec<_>>)
+        .await
+        .unwrap()
+        .is_some());
+
+    client.email_destroy(&draft_id).await.unwrap();
+    server.shared_core.store(original_core);
+}
+
+async fn save_draft(
+    client: &mut Client,
+    mailbox_id: &str,
+    replace_id: Option<&str>,
+    text: &str,
+) -> (jmap_client::Result<String>, Vec<String>) {
+    let mut request = client.build();
+    let set_request = request.set_email();
+    let create_item = set_request.create();
+    create_item
+        .mailbox_ids([mailbox_id])
+        .keywords(["$draft"])
+        .subject("Autosaved draft")
+        .from(["jdoe@example.com"])
+        .to(["robert@example.com"])
+        .body_value("t".to_string(), text)
+        .body_value("h".to_string(), format!("<p>{text}</p>"))
+        .text_body(EmailBodyPart::new().part_id("t"))
+        .html_body(EmailBodyPart::new().part_id("h"));
+    let create_id = create_item.create_id().unwrap();
+    if let Some(replace_id) = replace_id {
+        set_request.destroy([replace_id]);
+    }
+    let mut response = request.send_set_email().await.unwrap();
+    (
+        response
+            .created(&create_id)
+            .map(|mut email| email.take_id()),
+        response
+            .destroyed_ids()
+            .map(|ids| ids.cloned().collect())
+            .unwrap_or_default(),
+    )
+}
+
 pub async fn assert_email_properties(
     client: &mut Client,
     message_id: &str,
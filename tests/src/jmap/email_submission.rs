@@ -6,6 +6,7 @@
 
 use ahash::AHashMap;
 use jmap_client::{
+    client::Client,
     core::set::{SetError, SetErrorType, SetObject},
     email_submission::{query::Filter, Address, Delivered, DeliveryStatus, Displayed, UndoStatus},
     mailbox::Role,
@@ -13,11 +14,12 @@ use jmap_client::{
 };
 use jmap_proto::types::id::Id;
 use mail_parser::DateTime;
+use serde_json::json;
 use std::{
     sync::Arc,
     time::{Duration, Instant},
 };
-use store::parking_lot::Mutex;
+use store::{parking_lot::Mutex, write::now};
 
 use tokio::{
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
@@ -27,7 +29,10 @@ use tokio::{
 
 use crate::{
     directory::internal::TestInternalDirectory,
-    jmap::{assert_is_empty, email_set::assert_email_properties, mailbox::destroy_all_mailboxes},
+    jmap::{
+        assert_is_empty, email_set::assert_email_properties, mailbox::destroy_all_mailboxes,
+        ManagementApi,
+    },
 };
 
 use super::JMAPTest;
@@ -120,6 +125,29 @@ pub async fn test(params: &mut JMAPTest) {
         .unwrap()
         .take_id();
 
+    // Addresses of the lists the account belongs to are provisioned as identities
+    server
+        .core
+        .storage
+        .data
+        .create_test_list("sales@example.com", "Sales", &["jdoe@example.com"])
+        .await;
+    server
+        .authenticate_plain("jdoe@example.com", "12345", "127.0.0.1".parse().unwrap(), 0)
+        .await
+        .unwrap();
+    assert!(identity_emails(client)
+        .await
+        .contains(&"sales@example.com".to_string()));
+    ManagementApi::new(8899, "admin", "secret")
+        .delete::<()>("/api/principal/sales@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert!(!identity_emails(client)
+        .await
+        .contains(&"sales@example.com".to_string()));
+
     // Create test mailboxes
     let mailbox_id = client
         .mailbox_create("JMAP EmailSubmission", None::<String>, Role::None)
@@ -430,6 +458,109 @@ pub async fn test(params: &mut JMAPTest) {
         ),])
     );
 
+    // Destroying a scheduled submission should remove it from the queue
+    client
+        .email_submission_destroy(&email_submission_id)
+        .await
+        .unwrap();
+    assert!(client
+        .email_submission_get(&email_submission_id, None)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Submissions are held during the undo send window and can be canceled
+    let original_core = server.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.mail_undo_send = Some(Duration::from_secs(30));
+    core.jmap.mail_max_delayed_send = Duration::from_secs(86400);
+    server.shared_core.store(core.into());
+    assert!(matches!(
+        client
+            .email_submission_create_envelope(
+                &email_id,
+                &identity_id,
+                Address::new("jdoe@example.com").parameter("HOLDFOR", Some("172800")),
+                ["jane_smith@remote.org"],
+            )
+            .await,
+        Err(Error::Set(SetError {
+            type_: SetErrorType::InvalidProperties,
+            ..
+        }))
+    ));
+    let email_submission_id = client
+        .email_submission_create(&email_id, &identity_id)
+        .await
+        .unwrap()
+        .take_id();
+    let email_submission = client
+        .email_submission_get(&email_submission_id, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(email_submission.send_at().unwrap() > now() as i64 + 20);
+    assert_eq!(
+        email_submission.undo_status().unwrap(),
+        &UndoStatus::Pending
+    );
+    client
+        .email_submission_change_status(&email_submission_id, UndoStatus::Canceled)
+        .await
+        .unwrap();
+    assert_eq!(
+        client
+            .email_submission_get(&email_submission_id, None)
+            .await
+            .unwrap()
+            .unwrap()
+            .undo_status()
+            .unwrap(),
+        &UndoStatus::Canceled
+    );
+    expect_nothing(&mut smtp_rx).await;
+
+    // Accounts can choose their own undo send window
+    let api = ManagementApi::new(8899, "jdoe@example.com", "12345");
+    api.post::<()>("/api/account/undo-send", &json!({"delay": 172800}))
+        .await
+        .unwrap()
+        .expect_error("Undo send delay exceeds maximum");
+    api.post::<()>("/api/account/undo-send", &json!({"delay": 600}))
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(
+        api.get::<serde_json::Value>("/api/account/undo-send")
+            .await
+            .unwrap()
+            .unwrap_data(),
+        json!({"delay": 600})
+    );
+    let email_submission_id = client
+        .email_submission_create(&email_id, &identity_id)
+        .await
+        .unwrap()
+        .take_id();
+    let send_at = client
+        .email_submission_get(&email_submission_id, None)
+        .await
+        .unwrap()
+        .unwrap()
+        .send_at()
+        .unwrap();
+    assert!(send_at > now() as i64 + 500, "sendAt: {send_at}");
+    client
+        .email_submission_change_status(&email_submission_id, UndoStatus::Canceled)
+        .await
+        .unwrap();
+    expect_nothing(&mut smtp_rx).await;
+    api.post::<()>("/api/account/undo-send", &json!({"delay": null}))
+        .await
+        .unwrap()
+        .unwrap_data();
+    server.shared_core.store(original_core);
+
     // Verify onSuccessUpdateEmail action
     let mut request = client.build();
     let set_request = request.set_email_submission();
@@ -491,6 +622,19 @@ pub async fn test(params: &mut JMAPTest) {
     assert_is_empty(server).await;
 }
 
+async fn identity_emails(client: &Client) -> Vec<String> {
+    let mut request = client.build();
+    request.get_identity();
+    request
+        .send_get_identity()
+        .await
+        .unwrap()
+        .take_list()
+        .into_iter()
+        .filter_map(|identity| identity.email().map(|email| email.to_string()))
+        .collect()
+}
+
 pub fn spawn_mock_smtp_server() -> (mpsc::Receiver<MockMessage>, Arc<Mutex<MockSMTPSettings>>) {
     // Create channels
     let (event_tx, event_rx) = mpsc::channel::<MockMessage>(100);
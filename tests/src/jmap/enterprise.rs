@@ -37,11 +37,11 @@ use utils::config::{cron::SimpleCron, Config};
 use crate::{
     directory::internal::TestInternalDirectory,
     imap::{ImapConnection, Type},
-    jmap::delivery::SmtpConnection,
+    smtp::client::{AssertResult, SmtpConnection},
     AssertConfig,
 };
 
-use super::{delivery::AssertResult, JMAPTest, ManagementApi};
+use super::{JMAPTest, ManagementApi};
 
 const METRICS_CONFIG: &str = r#"
 [metrics.alerts.expected]
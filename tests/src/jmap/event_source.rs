@@ -8,10 +8,8 @@ use std::time::Duration;
 
 use crate::{
     directory::internal::TestInternalDirectory,
-    jmap::{
-        assert_is_empty, delivery::SmtpConnection, mailbox::destroy_all_mailboxes,
-        test_account_login,
-    },
+    jmap::{assert_is_empty, mailbox::destroy_all_mailboxes, test_account_login},
+    smtp::client::SmtpConnection,
 };
 use futures::StreamExt;
 use jmap::mailbox::INBOX_ID;
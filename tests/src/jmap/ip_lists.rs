@@ -0,0 +1,244 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    net::IpAddr,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use common::listener::blocked::IpListEntry;
+use http_body_util::Full;
+use hyper::{body, server::conn::http1, service::service_fn, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::Deserialize;
+use serde_json::json;
+use store::write::now;
+use tokio::net::TcpListener;
+
+use super::{JMAPTest, ManagementApi, Response};
+
+const IP_FEED: &str = concat!(
+    "# Test feed\n",
+    "203.0.113.7 ; SBL1\n",
+    "203.0.113.64/26\n",
+    "not-an-address\n",
+);
+
+#[derive(Debug, Deserialize)]
+struct IpListItems {
+    total: usize,
+    items: Vec<IpListEntry>,
+}
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running IP list tests...");
+    let server = params.server.clone();
+    let api = ManagementApi::new(8899, "admin", "secret");
+    let core = server.shared_core.load_full();
+    let version = core.network.blocked_ips.version.load(Ordering::Relaxed);
+    spawn_mock_ip_feed().await;
+
+    // Entries added through the API take effect without reloading the core
+    api.post::<()>(
+        "/api/ip/blocked",
+        &json!([
+            {"address": "192.0.2.1", "comment": "spam source"},
+            {"address": "198.51.100.0/24"}
+        ]),
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+    api.post::<()>("/api/ip/allowed", &json!([{"address": "198.51.100.7"}]))
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert!(Arc::ptr_eq(&core, &server.shared_core.load_full()));
+    assert_ne!(
+        core.network.blocked_ips.version.load(Ordering::Relaxed),
+        version
+    );
+    assert!(core.is_ip_blocked(&ip("192.0.2.1")));
+    assert!(core.is_ip_blocked(&ip("198.51.100.20")));
+    assert!(core.is_ip_allowed(&ip("198.51.100.7")));
+    assert!(!core.is_ip_blocked(&ip("192.0.2.2")));
+    assert!(!core.is_ip_allowed(&ip("192.0.2.1")));
+
+    // Invalid addresses are rejected
+    assert!(matches!(
+        api.post::<()>("/api/ip/blocked", &json!([{"address": "192.0.2.300"}]))
+            .await
+            .unwrap(),
+        Response::RequestError(err) if err.status == 400
+    ));
+
+    // List entries
+    let mut list = api
+        .get::<IpListItems>("/api/ip/blocked")
+        .await
+        .unwrap()
+        .unwrap_data();
+    list.items.sort_by(|a, b| a.address.cmp(&b.address));
+    assert_eq!(list.total, 2);
+    assert_eq!(
+        list.items,
+        vec![
+            IpListEntry {
+                address: "192.0.2.1".to_string(),
+                comment: Some("spam source".to_string()),
+                expires: None,
+            },
+            IpListEntry {
+                address: "198.51.100.0/24".to_string(),
+                comment: None,
+                expires: None,
+            }
+        ]
+    );
+
+    // Removed entries stop matching
+    api.delete::<()>("/api/ip/blocked/198.51.100.0%2F24")
+        .await
+        .unwrap()
+        .unwrap_data();
+    api.delete::<()>("/api/ip/allowed/198.51.100.7")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert!(core.is_ip_blocked(&ip("192.0.2.1")));
+    assert!(!core.is_ip_blocked(&ip("198.51.100.20")));
+    assert!(!core.is_ip_allowed(&ip("198.51.100.7")));
+    assert!(api
+        .delete::<()>("/api/ip/blocked/198.51.100.0%2F24")
+        .await
+        .unwrap()
+        .try_unwrap_data()
+        .is_none());
+
+    // Import a feed
+    assert_eq!(
+        api.post::<usize>("/api/ip/feed/test", &())
+            .await
+            .unwrap()
+            .unwrap_data(),
+        2
+    );
+    assert!(core.is_ip_blocked(&ip("203.0.113.7")));
+    assert!(core.is_ip_blocked(&ip("203.0.113.100")));
+    assert!(!core.is_ip_blocked(&ip("203.0.113.8")));
+    let list = api
+        .get::<IpListItems>("/api/ip/blocked")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_eq!(list.total, 3);
+    let entry = list
+        .items
+        .iter()
+        .find(|entry| entry.address == "203.0.113.7")
+        .unwrap();
+    assert_eq!(entry.comment.as_deref(), Some("feed test"));
+    assert!(entry.expires.is_some_and(|expires| expires > now()));
+
+    // Failed fetches leave the lists unchanged
+    assert!(matches!(
+        api.post::<usize>("/api/ip/feed/broken", &())
+            .await
+            .unwrap(),
+        Response::RequestError(err) if err.status == 500
+    ));
+    assert!(api
+        .post::<usize>("/api/ip/feed/unknown", &())
+        .await
+        .unwrap()
+        .try_unwrap_data()
+        .is_none());
+    assert_eq!(
+        api.get::<IpListItems>("/api/ip/blocked")
+            .await
+            .unwrap()
+            .unwrap_data()
+            .total,
+        3
+    );
+
+    // Expired entries are hidden until the expiry task removes them
+    api.post::<()>(
+        "/api/ip/blocked",
+        &json!([{"address": "192.0.2.5", "expires": now() + 1}]),
+    )
+    .await
+    .unwrap()
+    .unwrap_data();
+    assert!(core.is_ip_blocked(&ip("192.0.2.5")));
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(!api
+        .get::<IpListItems>("/api/ip/blocked")
+        .await
+        .unwrap()
+        .unwrap_data()
+        .items
+        .iter()
+        .any(|entry| entry.address == "192.0.2.5"));
+    assert_eq!(core.purge_expired_ip_entries().await.unwrap(), 1);
+    assert_eq!(core.purge_expired_ip_entries().await.unwrap(), 0);
+    core.reload_ip_lists().await.unwrap();
+    assert!(!core.is_ip_blocked(&ip("192.0.2.5")));
+    assert!(core.is_ip_blocked(&ip("192.0.2.1")));
+    assert!(core.is_ip_blocked(&ip("203.0.113.7")));
+
+    // Clean up
+    for address in ["192.0.2.1", "203.0.113.7", "203.0.113.64%2F26"] {
+        api.delete::<()>(&format!("/api/ip/blocked/{address}"))
+            .await
+            .unwrap()
+            .unwrap_data();
+    }
+    assert!(!core.is_ip_blocked(&ip("192.0.2.1")));
+    assert!(!core.is_ip_blocked(&ip("203.0.113.100")));
+    assert!(Arc::ptr_eq(&core, &server.shared_core.load_full()));
+}
+
+fn ip(address: &str) -> IpAddr {
+    address.parse().unwrap()
+}
+
+async fn spawn_mock_ip_feed() {
+    let listener = TcpListener::bind("127.0.0.1:8823")
+        .await
+        .unwrap_or_else(|e| {
+            panic!("Failed to bind mock IP feed to 127.0.0.1:8823: {e}");
+        });
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+
+            tokio::spawn(async move {
+                let _ = http1::Builder::new()
+                    .keep_alive(false)
+                    .serve_connection(
+                        TokioIo::new(stream),
+                        service_fn(|req: hyper::Request<body::Incoming>| async move {
+                            let (status, response) = match req.uri().path() {
+                                "/ip-feed" => (StatusCode::OK, IP_FEED),
+                                _ => (StatusCode::INTERNAL_SERVER_ERROR, ""),
+                            };
+
+                            Ok::<_, hyper::Error>(
+                                hyper::Response::builder()
+                                    .status(status)
+                                    .body(Full::new(body::Bytes::from(response)))
+                                    .unwrap(),
+                            )
+                        }),
+                    )
+                    .await;
+            });
+        }
+    });
+}
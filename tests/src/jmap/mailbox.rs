@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use imap_proto::ResponseType;
+use jmap::JMAP;
 use jmap_client::{
     client::Client,
     core::{
@@ -17,7 +19,11 @@ use jmap_proto::types::{id::Id, state::State};
 use serde::{Deserialize, Serialize};
 use store::ahash::AHashMap;
 
-use crate::jmap::assert_is_empty;
+use crate::{
+    directory::internal::TestInternalDirectory,
+    imap::{AssertResult, ImapConnection, Type},
+    jmap::{assert_is_empty, jmap_json_request, ManagementApi},
+};
 
 use super::{wait_for_index, JMAPTest};
 
@@ -596,9 +602,93 @@ pub async fn test(params: &mut JMAPTest) {
 
     destroy_all_mailboxes(params).await;
     params.client.set_default_account_id(Id::from(1u64));
+    test_deleted_subscriptions(&server).await;
     assert_is_empty(server).await;
 }
 
+async fn test_deleted_subscriptions(server: &JMAP) {
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "subs@example.com",
+                "12345",
+                "Subscriber",
+                &["subs@example.com"][..],
+            )
+            .await,
+    )
+    .to_string();
+    let mut imap = ImapConnection::connect(b"_x ").await;
+    imap.assert_read(Type::Untagged, ResponseType::Ok).await;
+    imap.send("AUTHENTICATE PLAIN {32+}\r\nAHN1YnNAZXhhbXBsZS5jb20AMTIzNDU=")
+        .await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok).await;
+
+    // Deleting a subscribed mailbox through JMAP retains its subscription
+    let mailbox_id = create_subscribed_mailbox(&account_id, true).await;
+    destroy_mailbox(&account_id, &mailbox_id).await;
+    imap.send("LIST (SUBSCRIBED) \"\" \"*\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_folders(
+            [
+                ("INBOX", vec!["Subscribed"]),
+                ("Tombstone", vec!["NonExistent", "Subscribed"]),
+            ],
+            true,
+        );
+
+    // Recreating and unsubscribing the mailbox through JMAP supersedes it
+    let mailbox_id = create_subscribed_mailbox(&account_id, false).await;
+    destroy_mailbox(&account_id, &mailbox_id).await;
+    imap.send("LIST (SUBSCRIBED) \"\" \"*\"").await;
+    imap.assert_read(Type::Tagged, ResponseType::Ok)
+        .await
+        .assert_folders([("INBOX", ["Subscribed"])], true);
+
+    // Remove test data
+    ManagementApi::new(8899, "admin", "secret")
+        .delete::<()>("/api/principal/subs@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+}
+
+async fn create_subscribed_mailbox(account_id: &str, is_subscribed: bool) -> String {
+    let response = jmap_json_request(
+        format!(
+            r#"[["Mailbox/set", {{"accountId": "{account_id}", "create": {{"m1": {{"name": "Tombstone", "isSubscribed": {is_subscribed}}}}}}}, "0"]]"#
+        ),
+        "subs@example.com",
+        "12345",
+    )
+    .await;
+    response
+        .pointer("/methodResponses/0/1/created/m1/id")
+        .and_then(|id| id.as_str())
+        .unwrap_or_else(|| panic!("Mailbox not created: {response:?}"))
+        .to_string()
+}
+
+async fn destroy_mailbox(account_id: &str, mailbox_id: &str) {
+    let response = jmap_json_request(
+        format!(
+            r#"[["Mailbox/set", {{"accountId": "{account_id}", "destroy": ["{mailbox_id}"]}}, "0"]]"#
+        ),
+        "subs@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/destroyed/0"),
+        Some(&serde_json::Value::String(mailbox_id.to_string())),
+        "Response: {response:?}"
+    );
+}
+
 async fn create_test_mailboxes(client: &mut Client) -> AHashMap<String, String> {
     let mut mailbox_map = AHashMap::default();
     let mut request = client.build();
@@ -0,0 +1,229 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use jmap_client::mailbox::{self, Role};
+use jmap_proto::types::id::Id;
+use serde_json::Value;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, jmap_json_request, test_account_login, ManagementApi},
+};
+
+use super::JMAPTest;
+
+const MESSAGE: &str = concat!(
+    "From: Bill Foobar <mdn.sender@example.com>\r\n",
+    "To: Jane Doe <mdn.reader@example.com>\r\n",
+    "Subject: Quarterly report\r\n",
+    "Message-ID: <mdn-test-1@example.com>\r\n",
+    "Disposition-Notification-To: Bill Foobar <mdn.sender@example.com>\r\n",
+    "\r\n",
+    "Please confirm you received this.\r\n"
+);
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running MDN tests...");
+    let server = params.server.clone();
+    let mut account_ids = Vec::new();
+    for (email, name) in [
+        ("mdn.reader@example.com", "Jane Doe"),
+        ("mdn.sender@example.com", "Bill Foobar"),
+    ] {
+        account_ids.push(
+            Id::from(
+                server
+                    .core
+                    .storage
+                    .data
+                    .create_test_user(email, "12345", name, &[email][..])
+                    .await,
+            )
+            .to_string(),
+        );
+    }
+    let reader_id = account_ids[0].as_str();
+    let sender_id = account_ids[1].as_str();
+
+    // Import a message requesting a read receipt
+    let client = test_account_login("mdn.reader@example.com", "12345").await;
+    let inbox_id = client
+        .mailbox_query(
+            mailbox::query::Filter::role(Role::Inbox).into(),
+            None::<Vec<_>>,
+        )
+        .await
+        .unwrap()
+        .take_ids()
+        .pop()
+        .unwrap();
+    let email_id = client
+        .email_import(
+            MESSAGE.as_bytes().to_vec(),
+            [&inbox_id],
+            None::<Vec<&str>>,
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+    let response = request(
+        r#"[["Identity/get", {"accountId": "$$"}, "0"]]"#,
+        reader_id,
+        "mdn.reader@example.com",
+    )
+    .await;
+    let identity_id = string(&response, "/methodResponses/0/1/list/0/id");
+
+    // Line breaks and malformed extension field names are rejected
+    for (property, value) in [
+        ("reportingUA", r#""example.com\r\nX-Injected: yes""#),
+        ("error", r#"["failed\nX-Injected: yes"]"#),
+        ("extensionFields", r#"{"X-Bad Name": "value"}"#),
+        (
+            "extensionFields",
+            r#"{"X-Note": "value\r\nX-Injected: yes"}"#,
+        ),
+    ] {
+        let response = request(
+            &r##"[["MDN/send", {"accountId": "$$", "identityId": "%i", "send": {
+                "k1": {
+                    "forEmailId": "%e",
+                    "%p": %v,
+                    "disposition": {"actionMode": "manual-action", "sendingMode": "mdn-sent-manually", "type": "displayed"}
+                }
+            }}, "0"]]"##
+                .replace("%i", &identity_id)
+                .replace("%e", &email_id)
+                .replace("%p", property)
+                .replace("%v", value),
+            reader_id,
+            "mdn.reader@example.com",
+        )
+        .await;
+        assert_eq!(
+            string(&response, "/methodResponses/0/1/notSent/k1/type"),
+            "invalidProperties"
+        );
+        assert_eq!(
+            string(&response, "/methodResponses/0/1/notSent/k1/properties/0"),
+            property
+        );
+    }
+
+    // Send a read receipt, the original message should be flagged as $mdnsent
+    let send_request = r##"[["MDN/send", {"accountId": "$$", "identityId": "%i", "send": {
+            "k1": {
+                "forEmailId": "%e",
+                "subject": "Read receipt",
+                "textBody": "Your message was displayed.",
+                "disposition": {"actionMode": "manual-action", "sendingMode": "mdn-sent-manually", "type": "displayed"}
+            }
+        }, "onSuccessUpdateEmail": {"#k1": {"keywords/$seen": true}}}, "0"],
+        ["Email/get", {"accountId": "$$", "ids": ["%e"], "properties": ["keywords"]}, "1"]]"##
+        .replace("%i", &identity_id)
+        .replace("%e", &email_id);
+    let response = request(&send_request, reader_id, "mdn.reader@example.com").await;
+    assert_eq!(
+        string(&response, "/methodResponses/0/1/sent/k1/finalRecipient"),
+        "rfc822; mdn.reader@example.com"
+    );
+    assert_eq!(
+        string(&response, "/methodResponses/0/1/sent/k1/originalMessageId"),
+        "<mdn-test-1@example.com>"
+    );
+    assert_eq!(string(&response, "/methodResponses/1/0"), "Email/set");
+    assert_eq!(
+        response.pointer("/methodResponses/2/1/list/0/keywords"),
+        Some(&serde_json::json!({"$seen": true, "$mdnsent": true})),
+        "Response: {response:?}"
+    );
+
+    // Sending a second receipt for the same message is not allowed
+    let response = request(&send_request, reader_id, "mdn.reader@example.com").await;
+    assert_eq!(
+        string(&response, "/methodResponses/0/1/notSent/k1/type"),
+        "mdnAlreadySent"
+    );
+
+    // The receipt is delivered to the sender, who can parse it
+    let mut mdn_blob_id = None;
+    for _ in 0..50 {
+        let response = request(
+            r##"[["Email/query", {"accountId": "$$"}, "0"],
+                ["Email/get", {"accountId": "$$", "#ids": {"resultOf": "0", "name": "Email/query", "path": "/ids"}, "properties": ["blobId"]}, "1"]]"##,
+            sender_id,
+            "mdn.sender@example.com",
+        )
+        .await;
+        if let Some(blob_id) = response
+            .pointer("/methodResponses/1/1/list/0/blobId")
+            .and_then(|v| v.as_str())
+        {
+            mdn_blob_id = Some(blob_id.to_string());
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let mdn_blob_id = mdn_blob_id.expect("MDN was not delivered");
+    let response = request(
+        &r##"[["MDN/parse", {"accountId": "$$", "blobIds": ["%b"]}, "0"]]"##
+            .replace("%b", &mdn_blob_id),
+        sender_id,
+        "mdn.sender@example.com",
+    )
+    .await;
+    let parsed = format!("/methodResponses/0/1/parsed/{mdn_blob_id}");
+    assert_eq!(
+        response.pointer(&format!("{parsed}/disposition")),
+        Some(&serde_json::json!({
+            "actionMode": "manual-action",
+            "sendingMode": "mdn-sent-manually",
+            "type": "displayed"
+        })),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        string(&response, &format!("{parsed}/subject")),
+        "Read receipt"
+    );
+    assert_eq!(
+        string(&response, &format!("{parsed}/finalRecipient")),
+        "rfc822; mdn.reader@example.com"
+    );
+    assert_eq!(
+        string(&response, &format!("{parsed}/originalMessageId")),
+        "<mdn-test-1@example.com>"
+    );
+    assert_eq!(
+        response.pointer(&format!("{parsed}/forEmailId")),
+        Some(&Value::Null)
+    );
+
+    // Remove test data
+    let api = ManagementApi::new(8899, "admin", "secret");
+    for email in ["mdn.reader@example.com", "mdn.sender@example.com"] {
+        api.delete::<()>(&format!("/api/principal/{email}"))
+            .await
+            .unwrap()
+            .unwrap_data();
+    }
+    assert_is_empty(server).await;
+}
+
+async fn request(body: &str, account_id: &str, login: &str) -> Value {
+    jmap_json_request(body.replace("$$", account_id), login, "12345").await
+}
+
+fn string(response: &Value, pointer: &str) -> String {
+    response
+        .pointer(pointer)
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Missing {pointer:?} in response: {response:?}"))
+        .to_string()
+}
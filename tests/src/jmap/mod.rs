@@ -49,12 +49,18 @@ use crate::{
     add_test_certs, directory::internal::TestInternalDirectory, store::TempDir, AssertConfig,
 };
 
+pub mod account_bundle;
+pub mod account_template;
+pub mod activity;
 pub mod auth_acl;
 pub mod auth_limits;
 pub mod auth_oauth;
 pub mod blob;
+pub mod calendar;
+pub mod contacts;
 pub mod crypto;
 pub mod delivery;
+pub mod email_awaiting_reply;
 pub mod email_changes;
 pub mod email_copy;
 pub mod email_get;
@@ -66,13 +72,17 @@ pub mod email_set;
 pub mod email_submission;
 pub mod enterprise;
 pub mod event_source;
+pub mod ip_lists;
 pub mod mailbox;
+pub mod mdn;
 pub mod permissions;
 pub mod purge;
 pub mod push_subscription;
 pub mod quota;
+pub mod recycle_bin;
 pub mod sieve_script;
 pub mod stress_test;
+pub mod tasks;
 pub mod thread_get;
 pub mod thread_merge;
 pub mod vacation_response;
@@ -112,6 +122,13 @@ certificate = "default"
 [server.fail2ban]
 authentication = "101/5s"
 
+[server.ip-feed."test"]
+url = "http://127.0.0.1:8823/ip-feed"
+list = "blocked"
+
+[server.ip-feed."broken"]
+url = "http://127.0.0.1:8823/broken"
+
 [authentication]
 rate-limit = "100/2s"
 
@@ -239,6 +256,7 @@ attempts.interval = "500ms"
 
 [jmap.email]
 auto-expunge = "1s"
+submission.max-delayed-send = "99999999d"
 
 [jmap.protocol.changes]
 max-history = "1s"
@@ -288,7 +306,7 @@ disabled-events = ["network.*"]
 
 [webhook."test"]
 url = "http://127.0.0.1:8821/hook"
-events = ["auth.*", "delivery.dsn*", "message-ingest.*", "security.authentication-ban"]
+events = ["auth.*", "delivery.dsn*", "queue.message-*", "message-ingest.*", "security.authentication-ban"]
 signature-key = "ovos-moles"
 throttle = "100ms"
 
@@ -322,20 +340,30 @@ pub async fn jmap_tests() {
     email_copy::test(&mut params).await;
     thread_get::test(&mut params).await;
     thread_merge::test(&mut params).await;
+    email_awaiting_reply::test(&mut params).await;
     mailbox::test(&mut params).await;
     delivery::test(&mut params).await;
     auth_acl::test(&mut params).await;
     auth_limits::test(&mut params).await;
+    ip_lists::test(&mut params).await;
     auth_oauth::test(&mut params).await;
     event_source::test(&mut params).await;
     push_subscription::test(&mut params).await;
     sieve_script::test(&mut params).await;
     vacation_response::test(&mut params).await;
     email_submission::test(&mut params).await;
+    mdn::test(&mut params).await;
+    recycle_bin::test(&mut params).await;
+    activity::test(&mut params).await;
     websocket::test(&mut params).await;
     quota::test(&mut params).await;
     crypto::test(&mut params).await;
     blob::test(&mut params).await;
+    calendar::test(&mut params).await;
+    contacts::test(&mut params).await;
+    tasks::test(&mut params).await;
+    account_bundle::test(&mut params).await;
+    account_template::test(&mut params).await;
     permissions::test(&params).await;
     purge::test(&mut params).await;
     enterprise::test(&mut params).await;
@@ -641,7 +669,7 @@ pub async fn jmap_raw_request(body: impl AsRef<str>, username: &str, secret: &st
     );
 
     const BODY_TEMPLATE: &str = r#"{
-        "using": [ "urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail", "urn:ietf:params:jmap:quota" ],
+        "using": [ "urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail", "urn:ietf:params:jmap:quota", "urn:ietf:params:jmap:calendars", "urn:ietf:params:jmap:contacts", "urn:ietf:params:jmap:tasks", "urn:ietf:params:jmap:mdn" ],
         "methodCalls": $$
       }"#;
 
@@ -673,6 +701,43 @@ pub async fn jmap_json_request(
     serde_json::from_str(&jmap_raw_request(body, username, secret).await).unwrap()
 }
 
+pub struct DavResponse {
+    pub status: u16,
+    pub headers: header::HeaderMap,
+    pub body: String,
+}
+
+pub async fn dav_request(
+    method: &str,
+    path: &str,
+    headers: &[(&'static str, &str)],
+    body: impl Into<String>,
+    username: &str,
+    secret: &str,
+) -> DavResponse {
+    let mut request = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .timeout(Duration::from_millis(1000))
+        .build()
+        .unwrap()
+        .request(
+            reqwest::Method::from_bytes(method.as_bytes()).unwrap(),
+            format!("https://127.0.0.1:8899{path}"),
+        )
+        .basic_auth(username, Some(secret))
+        .body(body.into());
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = request.send().await.unwrap();
+
+    DavResponse {
+        status: response.status().as_u16(),
+        headers: response.headers().clone(),
+        body: response.text().await.unwrap(),
+    }
+}
+
 pub fn find_values(string: &str, name: &str) -> Vec<String> {
     let mut last_pos = 0;
     let mut values = Vec::new();
@@ -796,6 +861,19 @@ impl ManagementApi {
         })
     }
 
+    pub async fn post_raw<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        body: String,
+    ) -> Result<Response<T>, String> {
+        self.request_raw(Method::POST, query, Some(body))
+            .await
+            .map(|result| {
+                serde_json::from_str::<Response<T>>(&result)
+                    .unwrap_or_else(|err| panic!("{err}: {result}"))
+            })
+    }
+
     pub async fn patch<T: DeserializeOwned>(
         &self,
         query: &str,
@@ -830,6 +908,11 @@ impl ManagementApi {
                     .unwrap_or_else(|err| panic!("{err}: {result}"))
             })
     }
+
+    pub async fn get_raw(&self, query: &str) -> Result<String, String> {
+        self.request_raw(Method::GET, query, None).await
+    }
+
     pub async fn request<T: DeserializeOwned>(
         &self,
         method: Method,
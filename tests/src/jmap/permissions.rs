@@ -600,6 +600,7 @@ pub async fn test(params: &JMAPTest) {
                 recipients: vec!["john@foobar.org".to_string()],
                 message_blob: message_blob.clone(),
                 message_size: TEST_MESSAGE.len(),
+                verdict: Default::default(),
                 session_id: 0,
             })
             .await,
@@ -636,6 +637,7 @@ pub async fn test(params: &JMAPTest) {
                 recipients: vec!["john@foobar.org".to_string()],
                 message_blob: message_blob.clone(),
                 message_size: TEST_MESSAGE.len(),
+                verdict: Default::default(),
                 session_id: 0,
             })
             .await,
@@ -660,6 +662,7 @@ pub async fn test(params: &JMAPTest) {
                 recipients: vec!["john@foobar.org".to_string()],
                 message_blob,
                 message_size: TEST_MESSAGE.len(),
+                verdict: Default::default(),
                 session_id: 0,
             })
             .await,
@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use std::time::Duration;
+
 use ahash::AHashSet;
 use directory::{backend::internal::manage::ManageDirectory, QueryBy};
 use imap_proto::ResponseType;
@@ -13,8 +15,9 @@ use jmap::{
 };
 use jmap_proto::types::{collection::Collection, id::Id, property::Property};
 use store::{
-    write::{key::DeserializeBigEndian, TagValue},
-    IterateParams, LogKey, U32_LEN, U64_LEN,
+    roaring::RoaringBitmap,
+    write::{key::DeserializeBigEndian, now, BatchBuilder, TagValue, F_INDEX, F_VALUE},
+    IndexKey, IterateParams, LogKey, Serialize, U32_LEN, U64_LEN,
 };
 
 use crate::{
@@ -91,7 +94,7 @@ pub async fn test(params: &mut JMAPTest) {
 
         if pass == 1 {
             changes = get_changes(&server).await;
-            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            utils::clock::advance(std::time::Duration::from_secs(1));
         } else {
             break;
         }
@@ -194,6 +197,67 @@ pub async fn test(params: &mut JMAPTest) {
         );
     }
 
+    // Retrieved messages that are purged do not expire new messages reusing their id
+    let document_id = Id::from_bytes(message_ids[0].as_bytes())
+        .unwrap()
+        .document_id();
+    let retrieved_at = now() - 3600;
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(account_id)
+        .with_collection(Collection::Email)
+        .update_document(document_id)
+        .value(Property::RetrievedAt, retrieved_at, F_VALUE | F_INDEX);
+    server.write_batch(batch).await.unwrap();
+    assert_eq!(get_retrieved(&server, account_id).await, vec![document_id]);
+    server
+        .emails_tombstone(account_id, RoaringBitmap::from_iter([document_id]))
+        .await
+        .unwrap();
+    server.emails_purge_tombstoned(account_id).await.unwrap();
+    assert_eq!(get_retrieved(&server, account_id).await, vec![]);
+    let new_id = Id::from_bytes(
+        client
+            .email_import(
+                b"From: bill@example.com\r\nSubject: Unread\r\n\r\nNot retrieved yet".to_vec(),
+                [&inbox_id],
+                None::<Vec<&str>>,
+                None,
+            )
+            .await
+            .unwrap()
+            .take_id()
+            .as_bytes(),
+    )
+    .unwrap()
+    .document_id();
+    assert_eq!(new_id, document_id);
+
+    // Stale retrieval index entries are removed without expiring the message
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(account_id)
+        .with_collection(Collection::Email)
+        .update_document(document_id)
+        .value(Property::RetrievedAt, retrieved_at, F_INDEX);
+    server.write_batch(batch).await.unwrap();
+    server
+        .emails_pop3_expire(account_id, Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert_eq!(get_retrieved(&server, account_id).await, vec![]);
+    assert!(server
+        .get_tag(
+            account_id,
+            Collection::Email,
+            Property::MailboxIds,
+            TagValue::Id(INBOX_ID)
+        )
+        .await
+        .unwrap()
+        .unwrap()
+        .contains(document_id));
+
     // Delete account
     server
         .core
@@ -205,6 +269,40 @@ pub async fn test(params: &mut JMAPTest) {
     assert_is_empty(server).await;
 }
 
+async fn get_retrieved(server: &JMAP, account_id: u32) -> Vec<u32> {
+    let mut document_ids = Vec::new();
+    server
+        .core
+        .storage
+        .data
+        .iterate(
+            IterateParams::new(
+                IndexKey {
+                    account_id,
+                    collection: Collection::Email.into(),
+                    document_id: 0,
+                    field: Property::RetrievedAt.into(),
+                    key: 0u64.serialize(),
+                },
+                IndexKey {
+                    account_id,
+                    collection: Collection::Email.into(),
+                    document_id: u32::MAX,
+                    field: Property::RetrievedAt.into(),
+                    key: u64::MAX.serialize(),
+                },
+            )
+            .no_values(),
+            |key, _| {
+                document_ids.push(key.deserialize_be_u32(key.len() - U32_LEN).unwrap());
+                Ok(true)
+            },
+        )
+        .await
+        .unwrap();
+    document_ids
+}
+
 async fn get_changes(server: &JMAP) -> AHashSet<(u64, u8)> {
     let mut changes = AHashSet::new();
     server
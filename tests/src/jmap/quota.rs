@@ -7,16 +7,19 @@
 use crate::{
     directory::internal::TestInternalDirectory,
     jmap::{
-        assert_is_empty, delivery::SmtpConnection, emails_purge_tombstoned, jmap_raw_request,
-        mailbox::destroy_all_mailboxes, test_account_login,
+        assert_is_empty, emails_purge_tombstoned, jmap_raw_request, mailbox::destroy_all_mailboxes,
+        test_account_login,
     },
+    smtp::client::SmtpConnection,
 };
 use jmap::{blob::upload::DISABLE_UPLOAD_QUOTA, mailbox::INBOX_ID};
 use jmap_client::{
     core::set::{SetErrorType, SetObject},
     email::EmailBodyPart,
 };
-use jmap_proto::types::{collection::Collection, id::Id};
+use jmap_proto::types::{collection::Collection, id::Id, type_state::DataType};
+use std::time::Duration;
+use utils::map::bitmap::Bitmap;
 
 use super::JMAPTest;
 
@@ -129,6 +132,17 @@ pub async fn test(params: &mut JMAPTest) {
         "{}",
         response
     );
+    assert!(response.contains("\"warnLimit\":921"), "{}", response);
+    assert!(
+        response.contains("\"resourceType\":\"count\""),
+        "{}",
+        response
+    );
+    let initial_state = quota_state(&response);
+
+    // Quota/changes should not report changes while usage is unchanged
+    let response = quota_changes(account_id, &initial_state).await;
+    assert!(response.contains("\"updated\":[]"), "{}", response);
 
     // Test Email/import quota
     let inbox_id = Id::new(INBOX_ID as u64).to_string();
@@ -176,6 +190,15 @@ pub async fn test(params: &mut JMAPTest) {
     .await;
     assert!(response.contains("\"used\":1024"), "{}", response);
     assert!(response.contains("\"hardLimit\":1024"), "{}", response);
+    assert_ne!(quota_state(&response), initial_state);
+
+    // Quota/changes should report the octets quota as updated
+    let response = quota_changes(account_id, &initial_state).await;
+    assert!(
+        response.contains(&format!("\"updated\":[\"{}\",", Id::from(0u64))),
+        "{}",
+        response
+    );
 
     // Delete messages and check available quota
     for message_id in message_ids {
@@ -261,6 +284,13 @@ pub async fn test(params: &mut JMAPTest) {
                 .take_id(),
         );
     }
+    let mut quota_changes_rx = server
+        .subscribe_state_manager(
+            account_id.document_id(),
+            Bitmap::from_iter([DataType::Quota]),
+        )
+        .await
+        .unwrap();
     for id in other_message_ids.iter().take(2) {
         message_ids.push(
             client
@@ -276,6 +306,20 @@ pub async fn test(params: &mut JMAPTest) {
                 .take_id(),
         );
     }
+
+    // Copies crossing the warning threshold should notify clients
+    let change = tokio::time::timeout(Duration::from_secs(1), quota_changes_rx.recv())
+        .await
+        .expect("Missing quota state change")
+        .unwrap();
+    assert!(
+        change
+            .types
+            .iter()
+            .any(|(data_type, _)| *data_type == DataType::Quota),
+        "{change:?}"
+    );
+
     assert_over_quota(
         client
             .email_copy(
@@ -369,3 +413,24 @@ fn create_message_with_size(from: &str, to: &str, subject: &str, size: usize) ->
 
     message.into_bytes()
 }
+
+async fn quota_changes(account_id: Id, since_state: &str) -> String {
+    jmap_raw_request(
+        r#"[[ "Quota/changes", {
+            "accountId": "$$",
+            "sinceState": "%%"
+          }, "0" ]]"#
+            .replace("$$", &account_id.to_string())
+            .replace("%%", since_state),
+        "robert@example.com",
+        "aabbcc",
+    )
+    .await
+}
+
+fn quota_state(response: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(response).unwrap()["methodResponses"][0][1]["state"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
@@ -0,0 +1,271 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use futures::future::join_all;
+use jmap_client::mailbox::{self, Role};
+use jmap_proto::types::id::Id;
+use serde_json::Value;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, jmap_json_request, test_account_login, ManagementApi},
+};
+
+use super::JMAPTest;
+
+const MESSAGE: &str = concat!(
+    "From: Bill Foobar <recycle.sender@example.com>\r\n",
+    "To: Jane Doe <recycle@example.com>\r\n",
+    "Subject: Travel itinerary\r\n",
+    "Message-ID: <recycle-test-1@example.com>\r\n",
+    "\r\n",
+    "Your flight departs at 9am.\r\n"
+);
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running recycle bin tests...");
+    let server = params.server.clone();
+
+    // Enable the recycle bin
+    let original_core = params.server.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.mail_recycle_retention = Some(Duration::from_secs(86400));
+    params.server.shared_core.store(core.into());
+
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "recycle@example.com",
+                "12345",
+                "Jane Doe",
+                &["recycle@example.com"][..],
+            )
+            .await,
+    )
+    .to_string();
+    let account_id = account_id.as_str();
+    let client = test_account_login("recycle@example.com", "12345").await;
+    let inbox_id = client
+        .mailbox_query(
+            mailbox::query::Filter::role(Role::Inbox).into(),
+            None::<Vec<_>>,
+        )
+        .await
+        .unwrap()
+        .take_ids()
+        .pop()
+        .unwrap();
+    let folder_id = client
+        .mailbox_create("Travel", None::<String>, Role::None)
+        .await
+        .unwrap()
+        .take_id();
+
+    // Import a message and destroy it
+    let email_id = client
+        .email_import(
+            MESSAGE.as_bytes().to_vec(),
+            [&inbox_id, &folder_id],
+            Some(["$seen", "$flagged"]),
+            None,
+        )
+        .await
+        .unwrap()
+        .take_id();
+    client.email_destroy(&email_id).await.unwrap();
+
+    // The destroyed message is listed in the recycle bin
+    let response = request(
+        r#"[["DeletedEmail/get", {"accountId": "$$"}, "0"]]"#,
+        account_id,
+    )
+    .await;
+    let list = response
+        .pointer("/methodResponses/0/1/list")
+        .and_then(|v| v.as_array())
+        .unwrap_or_else(|| panic!("Missing list in response: {response:?}"));
+    assert_eq!(list.len(), 1, "Response: {response:?}");
+    let deleted_id = string(&response, "/methodResponses/0/1/list/0/id");
+    assert_eq!(
+        string(&response, "/methodResponses/0/1/list/0/subject"),
+        "Travel itinerary"
+    );
+    assert_eq!(
+        string(&response, "/methodResponses/0/1/list/0/from"),
+        "recycle.sender@example.com"
+    );
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list/0/keywords"),
+        Some(&serde_json::json!({"$seen": true, "$flagged": true})),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list/0/mailboxIds"),
+        Some(&serde_json::json!({&inbox_id: true, &folder_id: true})),
+        "Response: {response:?}"
+    );
+
+    // Restore the message, it should be placed back in its original mailboxes
+    let restore_request = r#"[["DeletedEmail/restore", {"accountId": "$$", "ids": ["%d"]}, "0"]]"#
+        .replace("%d", &deleted_id);
+    let response = request(&restore_request, account_id).await;
+    let restored_id = string(
+        &response,
+        &format!("/methodResponses/0/1/restored/{deleted_id}/id"),
+    );
+    let response = request(
+        &r#"[["Email/get", {"accountId": "$$", "ids": ["%e"], "properties": ["mailboxIds", "keywords", "subject"]}, "0"]]"#
+            .replace("%e", &restored_id),
+        account_id,
+    )
+    .await;
+    assert_eq!(
+        string(&response, "/methodResponses/0/1/list/0/subject"),
+        "Travel itinerary"
+    );
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list/0/keywords"),
+        Some(&serde_json::json!({"$seen": true, "$flagged": true})),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list/0/mailboxIds"),
+        Some(&serde_json::json!({&inbox_id: true, &folder_id: true})),
+        "Response: {response:?}"
+    );
+
+    // Restored messages are removed from the recycle bin
+    let response = request(&restore_request, account_id).await;
+    assert_eq!(
+        string(
+            &response,
+            &format!("/methodResponses/0/1/notRestored/{deleted_id}/type")
+        ),
+        "notFound"
+    );
+    let response = request(
+        r#"[["DeletedEmail/get", {"accountId": "$$"}, "0"]]"#,
+        account_id,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list"),
+        Some(&serde_json::json!([])),
+        "Response: {response:?}"
+    );
+
+    // Concurrent deletions do not overwrite each other's entries
+    let mut email_ids = Vec::new();
+    for num in 0..5 {
+        email_ids.push(
+            client
+                .email_import(
+                    MESSAGE
+                        .replace("Travel itinerary", &format!("Itinerary {num}"))
+                        .into_bytes(),
+                    [&inbox_id],
+                    None::<Vec<&str>>,
+                    None,
+                )
+                .await
+                .unwrap()
+                .take_id(),
+        );
+    }
+    join_all(email_ids.iter().map(|email_id| {
+        request_owned(
+            r#"[["Email/set", {"accountId": "$$", "destroy": ["%e"]}, "0"]]"#
+                .replace("%e", email_id),
+            account_id,
+        )
+    }))
+    .await;
+    let response = request(
+        r#"[["DeletedEmail/get", {"accountId": "$$"}, "0"]]"#,
+        account_id,
+    )
+    .await;
+    let deleted_ids = response
+        .pointer("/methodResponses/0/1/list")
+        .and_then(|v| v.as_array())
+        .unwrap_or_else(|| panic!("Missing list in response: {response:?}"))
+        .iter()
+        .map(|item| item["id"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(deleted_ids.len(), 5, "Response: {response:?}");
+
+    // Concurrent restores of the same entry only restore it once
+    let restore_request = r#"[["DeletedEmail/restore", {"accountId": "$$", "ids": ["%d"]}, "0"]]"#
+        .replace("%d", &deleted_ids[0]);
+    let responses =
+        join_all((0..2).map(|_| request_owned(restore_request.clone(), account_id))).await;
+    let mut restored_ids = responses
+        .iter()
+        .filter_map(|response| {
+            response
+                .pointer(&format!(
+                    "/methodResponses/0/1/restored/{}/id",
+                    deleted_ids[0]
+                ))
+                .and_then(|v| v.as_str())
+                .map(|id| id.to_string())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(restored_ids.len(), 1, "Responses: {responses:?}");
+
+    // Restore the remaining entries
+    for deleted_id in &deleted_ids[1..] {
+        let response = request(
+            &r#"[["DeletedEmail/restore", {"accountId": "$$", "ids": ["%d"]}, "0"]]"#
+                .replace("%d", deleted_id),
+            account_id,
+        )
+        .await;
+        restored_ids.push(string(
+            &response,
+            &format!("/methodResponses/0/1/restored/{deleted_id}/id"),
+        ));
+    }
+
+    // Restore the original configuration and remove test data
+    params.server.shared_core.store(original_core);
+    client.email_destroy(&restored_id).await.unwrap();
+    for restored_id in restored_ids {
+        client.email_destroy(&restored_id).await.unwrap();
+    }
+    ManagementApi::new(8899, "admin", "secret")
+        .delete::<()>("/api/principal/recycle@example.com")
+        .await
+        .unwrap()
+        .unwrap_data();
+    assert_is_empty(server).await;
+}
+
+async fn request(body: &str, account_id: &str) -> Value {
+    jmap_json_request(
+        body.replace("$$", account_id),
+        "recycle@example.com",
+        "12345",
+    )
+    .await
+}
+
+async fn request_owned(body: String, account_id: &str) -> Value {
+    request(&body, account_id).await
+}
+
+fn string(response: &Value, pointer: &str) -> String {
+    response
+        .pointer(pointer)
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Missing {pointer:?} in response: {response:?}"))
+        .to_string()
+}
@@ -4,6 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use common::{config::scripts::Scripting, listener::blocked::TrustedNetworks};
 use jmap_client::{
     core::set::{SetError, SetErrorType},
     email, mailbox,
@@ -16,15 +17,18 @@ use std::{
     path::PathBuf,
     time::{Duration, Instant},
 };
+use store::Stores;
+use utils::config::Config;
 
 use crate::{
     directory::internal::TestInternalDirectory,
     jmap::{
         assert_is_empty,
-        delivery::SmtpConnection,
         email_submission::{assert_message_delivery, spawn_mock_smtp_server, MockMessage},
+        jmap_json_request,
         mailbox::destroy_all_mailboxes,
     },
+    smtp::client::SmtpConnection,
 };
 
 use super::JMAPTest;
@@ -66,6 +70,19 @@ pub async fn test(params: &mut JMAPTest) {
         }))
     ));
 
+    // Obtain the current state
+    let response = jmap_json_request(
+        format!(r#"[["SieveScript/get", {{"accountId": "{account_id}", "ids": []}}, "0"]]"#),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    let since_state = response
+        .pointer("/methodResponses/0/1/state")
+        .and_then(|v| v.as_str())
+        .unwrap()
+        .to_string();
+
     // Create 5 Sieve scripts, all deactivated.
     let mut script_ids = Vec::new();
     for i in 0..5 {
@@ -87,6 +104,24 @@ pub async fn test(params: &mut JMAPTest) {
         .await
         .unwrap();
     assert_eq!(response.ids().len(), 5);
+
+    // Created scripts are reported by SieveScript/changes
+    let changes = jmap_json_request(
+        format!(
+            r#"[["SieveScript/changes", {{"accountId": "{account_id}", "sinceState": "{since_state}"}}, "0"]]"#
+        ),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    assert_eq!(
+        changes
+            .pointer("/methodResponses/0/1/created")
+            .and_then(|v| v.as_array())
+            .map(|ids| ids.len()),
+        Some(5),
+        "Response: {changes:?}"
+    );
     for (pos, id) in response.ids().iter().enumerate() {
         let script = client
             .sieve_script_get(id, None::<Vec<_>>)
@@ -425,6 +460,56 @@ pub async fn test(params: &mut JMAPTest) {
         "Redirected message was stored."
     );
 
+    // Redirects of foreign senders are rewritten using SRS, regardless of the
+    // requested DSN notifications
+    let original_core = server.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.smtp.mail_auth.srs.key = Some(ring::hmac::Key::new(
+        ring::hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+        b"srs-secret",
+    ));
+    let srs_address = core
+        .smtp
+        .mail_auth
+        .srs
+        .forward("bill@remote.org", "example.com")
+        .unwrap();
+    server.shared_core.store(core.into());
+    client
+        .sieve_script_create("test_redirect_srs", get_script("test_redirect_srs"), true)
+        .await
+        .unwrap();
+    for (sender, return_path) in [
+        ("bill@remote.org", format!("<{srs_address}>")),
+        ("jane@example.com", "<jdoe@example.com>".to_string()),
+    ] {
+        lmtp.ingest(
+            sender,
+            &["jdoe@example.com"],
+            &format!(
+                concat!(
+                    "From: {}\r\n",
+                    "To: jdoe@example.com\r\n",
+                    "Subject: TPS Report\r\n",
+                    "\r\n",
+                    "Did you get the memo?"
+                ),
+                sender
+            ),
+        )
+        .await;
+        assert_message_delivery(
+            &mut smtp_rx,
+            MockMessage::new(
+                return_path,
+                ["<jane@remote.org>".to_string()],
+                "@Did you get the memo?".to_string(),
+            ),
+        )
+        .await;
+    }
+    server.shared_core.store(original_core);
+
     // Run notify + editheader + notify + fcc tests
     client
         .sieve_script_create("test_notify_fcc", get_script("test_notify_fcc"), true)
@@ -508,6 +593,248 @@ pub async fn test(params: &mut JMAPTest) {
         panic!("Email {:?} not found in: {:#?}", subject, emails);
     }
 
+    // Run spamtest + virustest tests, verdicts are only accepted from trusted peers
+    let original_core = server.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    let mut config = Config::new("server.trusted-networks = [\"127.0.0.1\"]").unwrap();
+    core.network.trusted_networks = TrustedNetworks::parse(&mut config);
+    config.assert_no_errors();
+    server.shared_core.store(core.into());
+    let mut lmtp = SmtpConnection::connect().await;
+    client
+        .sieve_script_create("test_spamtest", get_script("test_spamtest"), true)
+        .await
+        .unwrap();
+    for (status, subject) in [
+        ("X-Spam-Status: No, score=-1.20\r\n", "Ham"),
+        ("X-Spam-Status: No, score=3.50\r\n", "Likely spam"),
+        (
+            "X-Virus-Status: Infected (Eicar-Test-Signature)\r\n",
+            "Virus",
+        ),
+    ] {
+        lmtp.ingest(
+            "bill@remote.org",
+            &["jdoe@example.com"],
+            &format!(
+                concat!(
+                    "{}",
+                    "From: bill@remote.org\r\n",
+                    "To: jdoe@example.com\r\n",
+                    "Subject: {}\r\n",
+                    "\r\n",
+                    "Did you get the memo?"
+                ),
+                status, subject
+            ),
+        )
+        .await;
+    }
+    let mailbox_id = client
+        .mailbox_query(
+            mailbox::query::Filter::name("Probably Spam").into(),
+            None::<Vec<_>>,
+        )
+        .await
+        .unwrap()
+        .take_ids()
+        .pop()
+        .expect("Probably Spam mailbox not found");
+    let mut request = client.build();
+    request
+        .get_email()
+        .properties([email::Property::MailboxIds, email::Property::Subject]);
+    let emails = request.send_get_email().await.unwrap().take_list();
+    for (subject, expected_mailbox) in [("Ham", false), ("Likely spam", true)] {
+        let email = emails
+            .iter()
+            .find(|email| email.subject() == Some(subject))
+            .unwrap_or_else(|| panic!("Email {subject:?} not found in: {emails:#?}"));
+        assert_eq!(
+            email.mailbox_ids().contains(&mailbox_id.as_str()),
+            expected_mailbox,
+            "{email:#?}"
+        );
+    }
+    assert!(
+        !emails.iter().any(|email| email.subject() == Some("Virus")),
+        "Infected message was delivered: {emails:#?}"
+    );
+    server.shared_core.store(original_core);
+
+    // Only lookup stores listed in the configuration are available to user scripts
+    let mut stores = Stores::default();
+    stores.lookup_stores = server.core.storage.lookups.clone();
+    let mut config = Config::new("sieve.untrusted.ext-lists = [\"auth\", \"missing\"]").unwrap();
+    let scripting = Scripting::parse(&mut config, &stores).await;
+    assert_eq!(
+        scripting.untrusted_ext_lists.iter().collect::<Vec<_>>(),
+        vec!["auth"]
+    );
+    assert_eq!(
+        config
+            .errors
+            .keys()
+            .filter(|key| key.starts_with("sieve.untrusted.ext-lists"))
+            .count(),
+        1,
+        "{:?}",
+        config.errors
+    );
+    for (list, is_valid) in [
+        ("urn:ietf:params:sieve:addrbook", true),
+        ("urn:ietf:params:sieve:addrbook:default", true),
+        ("auth", true),
+        ("sqlite", false),
+    ] {
+        assert_eq!(
+            scripting
+                .untrusted_compiler
+                .compile(
+                    format!(
+                        "require \"extlists\";\nif address :list \"from\" \"{list}\" {{ stop; }}"
+                    )
+                    .as_bytes()
+                )
+                .is_ok(),
+            is_valid,
+            "{list}"
+        );
+    }
+
+    // Run extlists tests, senders are looked up in the address book and lookup stores
+    let original_core = server.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.sieve = scripting;
+    server.shared_core.store(core.into());
+    let blocklist = server.core.storage.lookups.get("auth").unwrap().clone();
+    blocklist
+        .key_set(b"spammer@remote.org".to_vec(), b"1".to_vec(), None)
+        .await
+        .unwrap();
+    let response = jmap_json_request(
+        format!(r#"[["AddressBook/get", {{"accountId": "{account_id}"}}, "0"]]"#),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    let address_book_id = response
+        .pointer("/methodResponses/0/1/list/0/id")
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Missing address book in response: {response:?}"))
+        .to_string();
+    let response = jmap_json_request(
+        format!(
+            r#"[["ContactCard/set", {{"accountId": "{account_id}", "create": {{"c1": {{
+                "addressBookIds": {{"{address_book_id}": true}},
+                "@type": "Card",
+                "name": {{"full": "Bill Lumbergh"}},
+                "emails": {{"e1": {{"@type": "EmailAddress", "address": "Bill@Remote.org"}}}}
+            }}}}}}, "0"]]"#
+        ),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+    let card_id = response
+        .pointer("/methodResponses/0/1/created/c1/id")
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Missing card in response: {response:?}"))
+        .to_string();
+    client
+        .sieve_script_create("test_extlists", get_script("test_extlists"), true)
+        .await
+        .unwrap();
+    let mut lmtp = SmtpConnection::connect().await;
+    for (sender, subject) in [
+        ("bill@remote.org", "From a contact"),
+        ("Spammer@remote.org", "From a blocklisted sender"),
+        ("milton@remote.org", "From someone else"),
+    ] {
+        lmtp.ingest(
+            sender,
+            &["jdoe@example.com"],
+            &format!(
+                concat!(
+                    "From: {}\r\n",
+                    "To: jdoe@example.com\r\n",
+                    "Subject: {}\r\n",
+                    "\r\n",
+                    "Have you seen my stapler?"
+                ),
+                sender, subject
+            ),
+        )
+        .await;
+    }
+    lmtp.quit().await;
+    let mut request = client.build();
+    request
+        .get_email()
+        .properties([email::Property::MailboxIds, email::Property::Subject]);
+    let emails = request.send_get_email().await.unwrap().take_list();
+    for (subject, folder) in [
+        ("From a contact", "Contacts"),
+        ("From a blocklisted sender", "Blocklisted"),
+        ("From someone else", "Inbox"),
+    ] {
+        let email = emails
+            .iter()
+            .find(|email| email.subject() == Some(subject))
+            .unwrap_or_else(|| panic!("Email {subject:?} not found in: {emails:#?}"));
+        let mailbox_id = client
+            .mailbox_query(
+                mailbox::query::Filter::name(folder.to_string()).into(),
+                None::<Vec<_>>,
+            )
+            .await
+            .unwrap()
+            .take_ids()
+            .pop()
+            .unwrap_or_else(|| panic!("Mailbox {folder:?} not found"));
+        assert!(
+            email.mailbox_ids().contains(&mailbox_id.as_str()),
+            "Mailbox {folder:?} ({mailbox_id}) not found in: {email:#?}"
+        );
+    }
+    server.shared_core.store(original_core);
+    blocklist
+        .key_delete(b"spammer@remote.org".to_vec())
+        .await
+        .unwrap();
+    jmap_json_request(
+        format!(
+            r#"[["ContactCard/set", {{"accountId": "{account_id}", "destroy": ["{card_id}"]}}, "0"],
+                ["AddressBook/set", {{"accountId": "{account_id}", "destroy": ["{address_book_id}"]}}, "1"]]"#
+        ),
+        "jdoe@example.com",
+        "12345",
+    )
+    .await;
+
+    // Scripts created over JMAP count towards the total size quota
+    let original_core = params.server.shared_core.load_full();
+    let mut core = original_core.as_ref().clone();
+    core.jmap.sieve_max_scripts_size = server
+        .sieve_scripts_size(
+            Id::from_bytes(account_id.as_bytes()).unwrap().document_id(),
+            None,
+        )
+        .await
+        .unwrap()
+        + 10;
+    params.server.shared_core.store(core.into());
+    assert!(matches!(
+        client
+            .sieve_script_create("over_quota", get_script("validate_ok"), false)
+            .await,
+        Err(Error::Set(SetError {
+            type_: SetErrorType::OverQuota,
+            ..
+        }))
+    ));
+    params.server.shared_core.store(original_core);
+
     // Remove test data
     client.sieve_script_deactivate().await.unwrap();
     let mut request = client.build();
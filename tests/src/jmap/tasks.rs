@@ -0,0 +1,256 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::types::id::Id;
+use serde_json::Value;
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, jmap_json_request},
+};
+
+use super::JMAPTest;
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running Tasks tests...");
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(
+                "jdoe@example.com",
+                "12345",
+                "John Doe",
+                &["jdoe@example.com"],
+            )
+            .await,
+    )
+    .to_string();
+
+    // The default task list is created on first access
+    let response = request(
+        r#"[["TaskList/get", {"accountId": "$$"}, "0"]]"#,
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list/0/isDefault"),
+        Some(&Value::Bool(true)),
+        "Response: {response:?}"
+    );
+    let default_id = string(&response, "/methodResponses/0/1/list/0/id");
+
+    // Create a task list and tasks
+    let response = request(
+        &r##"[["TaskList/set", {"accountId": "$$", "create": {"l1": {"name": "Chores", "color": "#00ff00"}}}, "0"],
+            ["Task/set", {"accountId": "$$", "create": {
+                "t1": {
+                    "taskListId": "#l1",
+                    "title": "Take out the trash",
+                    "due": "2024-01-05T17:00:00",
+                    "timeZone": "Etc/GMT-1",
+                    "recurrenceRules": [{"@type": "RecurrenceRule", "frequency": "weekly", "count": 4}]
+                },
+                "t2": {
+                    "taskListId": "%%",
+                    "title": "File tax return",
+                    "description": "Collect all receipts first",
+                    "start": "2024-01-08T09:00:00",
+                    "due": "2024-01-10T12:00:00",
+                    "priority": 1
+                },
+                "t3": {
+                    "taskListId": "%%",
+                    "title": "Renew passport",
+                    "progress": "completed",
+                    "percentComplete": 100
+                },
+                "t4": {
+                    "title": "No task list"
+                },
+                "t5": {
+                    "taskListId": "%%",
+                    "title": "Invalid priority",
+                    "priority": 10
+                },
+                "t6": {
+                    "taskListId": "%%",
+                    "title": "Due before start",
+                    "start": "2024-01-10T09:00:00",
+                    "due": "2024-01-09T09:00:00"
+                }
+            }}, "1"]]"##
+            .replace("%%", &default_id),
+        &account_id,
+    )
+    .await;
+    let chores_id = string(&response, "/methodResponses/0/1/created/l1/id");
+    let t1 = string(&response, "/methodResponses/1/1/created/t1/id");
+    let t2 = string(&response, "/methodResponses/1/1/created/t2/id");
+    let t3 = string(&response, "/methodResponses/1/1/created/t3/id");
+    for id in ["t4", "t5", "t6"] {
+        assert_eq!(
+            response
+                .pointer(&format!("/methodResponses/1/1/notCreated/{id}/type"))
+                .and_then(|v| v.as_str()),
+            Some("invalidProperties"),
+            "Response: {response:?}"
+        );
+    }
+
+    // Dates are resolved to UTC and missing properties have defaults
+    let response = request(
+        &format!(
+            r#"[["Task/get", {{"accountId": "$$", "ids": ["{t1}", "{t2}"],
+                "properties": ["uid", "utcStart", "utcDue", "progress", "priority"]}}, "0"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    for (pointer, expected) in [
+        ("/methodResponses/0/1/list/0/utcDue", "2024-01-05T16:00:00Z"),
+        ("/methodResponses/0/1/list/0/progress", "needs-action"),
+        (
+            "/methodResponses/0/1/list/1/utcStart",
+            "2024-01-08T09:00:00Z",
+        ),
+        ("/methodResponses/0/1/list/1/utcDue", "2024-01-10T12:00:00Z"),
+    ] {
+        assert_eq!(
+            response.pointer(pointer).and_then(|v| v.as_str()),
+            Some(expected),
+            "Pointer {pointer:?} Response: {response:?}"
+        );
+    }
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/list/0/utcStart"),
+        Some(&Value::Null),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response
+            .pointer("/methodResponses/0/1/list/1/priority")
+            .and_then(|v| v.as_u64()),
+        Some(1),
+        "Response: {response:?}"
+    );
+    assert!(
+        !string(&response, "/methodResponses/0/1/list/0/uid").is_empty(),
+        "Response: {response:?}"
+    );
+
+    // Query tasks
+    for (arguments, expected) in [
+        (
+            format!(r#""filter": {{"inTaskLists": ["{chores_id}"]}}"#),
+            vec![t1.as_str()],
+        ),
+        (
+            r#""filter": {"after": "2024-01-20T00:00:00Z", "before": "2024-02-01T00:00:00Z"}"#
+                .to_string(),
+            vec![t1.as_str()],
+        ),
+        (
+            r#""filter": {"after": "2024-01-13T00:00:00Z", "before": "2024-01-19T00:00:00Z"}"#
+                .to_string(),
+            vec![],
+        ),
+        (
+            r#""filter": {"text": "receipts"}"#.to_string(),
+            vec![t2.as_str()],
+        ),
+        (
+            r#""filter": {"progress": "completed"}"#.to_string(),
+            vec![t3.as_str()],
+        ),
+        (
+            r#""filter": {"before": "2024-02-01T00:00:00Z"}, "sort": [{"property": "due", "isAscending": false}]"#
+                .to_string(),
+            vec![t2.as_str(), t1.as_str()],
+        ),
+    ] {
+        let response = request(
+            &format!(r#"[["Task/query", {{"accountId": "$$", {arguments}}}, "0"]]"#),
+            &account_id,
+        )
+        .await;
+        assert_eq!(
+            response
+                .pointer("/methodResponses/0/1/ids")
+                .and_then(|v| v.as_array())
+                .map(|ids| ids.iter().filter_map(|id| id.as_str()).collect::<Vec<_>>()),
+            Some(expected),
+            "Arguments {arguments} Response: {response:?}"
+        );
+    }
+
+    // Task lists with tasks cannot be destroyed unless requested
+    let response = request(
+        &format!(r#"[["TaskList/set", {{"accountId": "$$", "destroy": ["{chores_id}"]}}, "0"]]"#),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer(&format!(
+                "/methodResponses/0/1/notDestroyed/{chores_id}/type"
+            ))
+            .and_then(|v| v.as_str()),
+        Some("taskListHasTask"),
+        "Response: {response:?}"
+    );
+    let response = request(
+        &format!(
+            r#"[["TaskList/set", {{"accountId": "$$", "destroy": ["{chores_id}"], "onDestroyRemoveTasks": true}}, "0"],
+                ["Task/get", {{"accountId": "$$", "ids": ["{t1}"]}}, "1"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/1/destroyed/0"),
+        Some(&Value::String(chores_id.clone())),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/methodResponses/1/1/notFound/0"),
+        Some(&Value::String(t1.clone())),
+        "Response: {response:?}"
+    );
+
+    // Remove test data
+    let response = request(
+        &format!(
+            r#"[["Task/set", {{"accountId": "$$", "destroy": ["{t2}", "{t3}"]}}, "0"],
+                ["TaskList/set", {{"accountId": "$$", "destroy": ["{default_id}"]}}, "1"]]"#
+        ),
+        &account_id,
+    )
+    .await;
+    assert_eq!(
+        response
+            .pointer("/methodResponses/1/1/destroyed")
+            .and_then(|v| v.as_array())
+            .map(|ids| ids.len()),
+        Some(1),
+        "Response: {response:?}"
+    );
+    assert_is_empty(server).await;
+}
+
+async fn request(body: &str, account_id: &str) -> Value {
+    jmap_json_request(body.replace("$$", account_id), "jdoe@example.com", "12345").await
+}
+
+fn string(response: &Value, pointer: &str) -> String {
+    response
+        .pointer(pointer)
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Missing {pointer:?} in response: {response:?}"))
+        .to_string()
+}
@@ -6,6 +6,7 @@
 
 use chrono::{TimeDelta, Utc};
 
+use jmap::sieve::vacation::vacation_handle;
 use jmap_proto::types::id::Id;
 use std::time::Instant;
 
@@ -13,12 +14,12 @@ use crate::{
     directory::internal::TestInternalDirectory,
     jmap::{
         assert_is_empty,
-        delivery::SmtpConnection,
         email_submission::{
             assert_message_delivery, expect_nothing, spawn_mock_smtp_server, MockMessage,
         },
         mailbox::destroy_all_mailboxes,
     },
+    smtp::client::SmtpConnection,
 };
 
 use super::JMAPTest;
@@ -123,6 +124,23 @@ pub async fn test(params: &mut JMAPTest) {
 
     expect_nothing(&mut smtp_rx).await;
 
+    // Mailing list traffic should not trigger a vacation response
+    lmtp.ingest(
+        "tps-reports-bounces@remote.org",
+        &["jdoe@example.com"],
+        concat!(
+            "From: tps-reports@remote.org\r\n",
+            "To: jdoe@example.com\r\n",
+            "List-Id: <tps-reports.remote.org>\r\n",
+            "Subject: New cover sheets\r\n",
+            "\r\n",
+            "Please use the new cover sheets on all TPS reports.",
+        ),
+    )
+    .await;
+
+    expect_nothing(&mut smtp_rx).await;
+
     // Vacation responses should honor the configured date ranges
     client
         .vacation_response_set_dates(
@@ -178,6 +196,54 @@ pub async fn test(params: &mut JMAPTest) {
     )
     .await;
 
+    // Only ids generated by the vacation action for the sender are tracked as replies
+    for (id, last, expected) in [
+        (
+            "_vmilton@remote.orgOut of office",
+            false,
+            Some("Out of office"),
+        ),
+        ("_vmilton@remote.org", false, Some("")),
+        ("_vmilton@remote.orgOut of office", true, None),
+        ("_vacation-notice", false, None),
+        ("_vbill@remote.orgOut of office", false, None),
+    ] {
+        assert_eq!(
+            vacation_handle(id, "Milton@Remote.org", last),
+            expected,
+            "{id}"
+        );
+    }
+
+    // Concurrent deliveries should only send a single vacation response
+    let document_id = Id::from_bytes(account_id.as_bytes()).unwrap().document_id();
+    let results = futures::future::join_all(
+        (0..10)
+            .map(|_| server.sieve_vacation_is_replied(document_id, "milton@remote.org", "", 3600)),
+    )
+    .await;
+    assert_eq!(
+        results
+            .into_iter()
+            .filter(|is_replied| !is_replied.as_ref().unwrap())
+            .count(),
+        1
+    );
+    assert!(server
+        .sieve_vacation_is_replied(document_id, "Milton@remote.org", "", 3600)
+        .await
+        .unwrap());
+
+    // Replies are tracked separately for each handle
+    assert!(!server
+        .sieve_vacation_is_replied(document_id, "milton@remote.org", "stapler", 3600)
+        .await
+        .unwrap());
+    assert!(!server
+        .sieve_vacation_is_replied(document_id, "milton@remote.orgstapler", "", 3600)
+        .await
+        .unwrap());
+
     // Remove test data
     client.vacation_response_destroy().await.unwrap();
     destroy_all_mailboxes(params).await;
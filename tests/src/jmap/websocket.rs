@@ -12,6 +12,7 @@ use jmap_client::{
         response::{Response, TaggedMethodResponse},
         set::SetObject,
     },
+    event_source::Changes,
     TypeState,
 };
 use jmap_proto::types::id::Id;
@@ -98,7 +99,11 @@ pub async fn test(params: &mut JMAPTest) {
             .unwrap();
     }
     tokio::time::sleep(Duration::from_millis(500)).await;
-    assert_state(&mut stream_rx, &account_id, &[TypeState::Mailbox]).await;
+    let push_state = assert_state(&mut stream_rx, &account_id, &[TypeState::Mailbox])
+        .await
+        .id()
+        .expect("missing pushState")
+        .to_string();
     expect_nothing(&mut stream_rx).await;
 
     // Disable push notifications
@@ -118,6 +123,27 @@ pub async fn test(params: &mut JMAPTest) {
         .unwrap();
     expect_nothing(&mut stream_rx).await;
 
+    // Re-enabling push with the last pushState delivers the missed changes
+    client
+        .enable_push_ws(None::<Vec<_>>, Some(&push_state))
+        .await
+        .unwrap();
+    let new_push_state = assert_state(&mut stream_rx, &account_id, &[TypeState::Mailbox])
+        .await
+        .id()
+        .expect("missing pushState")
+        .to_string();
+    assert_ne!(push_state, new_push_state);
+    client.disable_push_ws().await.unwrap();
+
+    // No changes are missed since the latest pushState
+    client
+        .enable_push_ws(None::<Vec<_>>, Some(&new_push_state))
+        .await
+        .unwrap();
+    expect_nothing(&mut stream_rx).await;
+    client.disable_push_ws().await.unwrap();
+
     params.client.set_default_account_id(account_id);
     destroy_all_mailboxes(params).await;
     assert_is_empty(server).await;
@@ -141,7 +167,7 @@ async fn assert_state(
     stream_rx: &mut mpsc::Receiver<WebSocketMessage>,
     id: &str,
     state: &[TypeState],
-) {
+) -> Changes {
     match tokio::time::timeout(Duration::from_millis(700), stream_rx.recv()).await {
         Ok(Some(message)) => match message {
             WebSocketMessage::StateChange(changes) => {
@@ -153,6 +179,7 @@ async fn assert_state(
                         .collect::<AHashSet<&TypeState>>(),
                     state.iter().collect::<AHashSet<&TypeState>>()
                 );
+                changes
             }
             _ => panic!("Expected state change, got: {:?}", message),
         },
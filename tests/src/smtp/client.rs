@@ -0,0 +1,380 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use mail_send::smtp::tls::build_tls_connector;
+use rustls_pki_types::ServerName;
+use tokio::{
+    io::{
+        AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines, ReadHalf,
+        WriteHalf,
+    },
+    net::TcpStream,
+};
+
+pub trait AsyncStream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> AsyncStream for T {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Smtp,
+    Lmtp,
+}
+
+pub struct SmtpConnection {
+    protocol: Protocol,
+    reader: Lines<BufReader<ReadHalf<Box<dyn AsyncStream>>>>,
+    writer: WriteHalf<Box<dyn AsyncStream>>,
+}
+
+impl SmtpConnection {
+    pub async fn ingest_with_code(
+        &mut self,
+        from: &str,
+        recipients: &[&str],
+        message: &str,
+        code: u8,
+    ) -> Vec<String> {
+        self.mail_from(from, 2).await;
+        for recipient in recipients {
+            self.rcpt_to(recipient, 2).await;
+        }
+        self.data(3).await;
+        let result = self
+            .data_bytes(message, self.num_responses(recipients), code)
+            .await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        result
+    }
+
+    pub async fn ingest(&mut self, from: &str, recipients: &[&str], message: &str) {
+        self.ingest_with_code(from, recipients, message, 2).await;
+    }
+
+    pub async fn ingest_chunked(
+        &mut self,
+        from: &str,
+        recipients: &[&str],
+        message: &str,
+        chunk_size: usize,
+    ) {
+        self.mail_from(from, 2).await;
+        for recipient in recipients {
+            self.rcpt_to(recipient, 2).await;
+        }
+        for chunk in message.as_bytes().chunks(chunk_size) {
+            self.bdat(std::str::from_utf8(chunk).unwrap(), 2).await;
+        }
+        self.bdat_last("", self.num_responses(recipients), 2).await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    pub async fn connect() -> Self {
+        SmtpConnection::connect_port(11200).await
+    }
+
+    pub async fn connect_port(port: u16) -> Self {
+        let mut conn = SmtpConnection::connect_raw(port, Protocol::Lmtp, false).await;
+        conn.read(1, 2).await;
+        conn.lhlo().await;
+        conn
+    }
+
+    /// Connects to an SMTP listener, optionally using implicit TLS,
+    /// and greets the server with EHLO.
+    pub async fn connect_smtp(port: u16, implicit_tls: bool) -> Self {
+        let mut conn = SmtpConnection::connect_raw(port, Protocol::Smtp, implicit_tls).await;
+        conn.read(1, 2).await;
+        conn.ehlo().await;
+        conn
+    }
+
+    /// Opens a connection without reading the greeting, for scripted sessions.
+    pub async fn connect_raw(port: u16, protocol: Protocol, implicit_tls: bool) -> Self {
+        let stream = TcpStream::connect(&format!("127.0.0.1:{port}"))
+            .await
+            .unwrap();
+        let stream: Box<dyn AsyncStream> = if implicit_tls {
+            Box::new(tls_connect(stream).await)
+        } else {
+            Box::new(stream)
+        };
+        SmtpConnection::from_stream(stream, protocol)
+    }
+
+    fn from_stream(stream: Box<dyn AsyncStream>, protocol: Protocol) -> Self {
+        let (reader, writer) = tokio::io::split(stream);
+        SmtpConnection {
+            protocol,
+            reader: BufReader::new(reader).lines(),
+            writer,
+        }
+    }
+
+    /// Upgrades the connection with STARTTLS and greets the server again.
+    pub async fn starttls(mut self) -> Self {
+        self.send("STARTTLS").await;
+        self.read(1, 2).await;
+        let protocol = self.protocol;
+        let stream = self.reader.into_inner().into_inner().unsplit(self.writer);
+        let mut conn = SmtpConnection::from_stream(Box::new(tls_connect(stream).await), protocol);
+        conn.hello().await;
+        conn
+    }
+
+    pub async fn hello(&mut self) -> Vec<String> {
+        match self.protocol {
+            Protocol::Smtp => self.ehlo().await,
+            Protocol::Lmtp => self.lhlo().await,
+        }
+    }
+
+    pub async fn ehlo(&mut self) -> Vec<String> {
+        self.send("EHLO localhost").await;
+        self.read(1, 2).await
+    }
+
+    pub async fn lhlo(&mut self) -> Vec<String> {
+        self.send("LHLO localhost").await;
+        self.read(1, 2).await
+    }
+
+    pub async fn auth_plain(&mut self, login: &str, secret: &str, code: u8) -> Vec<String> {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+        self.send(&format!(
+            "AUTH PLAIN {}",
+            STANDARD.encode(format!("\0{login}\0{secret}"))
+        ))
+        .await;
+        self.read(1, code).await
+    }
+
+    pub async fn mail_from(&mut self, sender: &str, code: u8) -> Vec<String> {
+        self.send(&format!("MAIL FROM:<{}>", sender)).await;
+        self.read(1, code).await
+    }
+
+    pub async fn rcpt_to(&mut self, rcpt: &str, code: u8) -> Vec<String> {
+        self.send(&format!("RCPT TO:<{}>", rcpt)).await;
+        self.read(1, code).await
+    }
+
+    pub async fn vrfy(&mut self, rcpt: &str, code: u8) -> Vec<String> {
+        self.send(&format!("VRFY {}", rcpt)).await;
+        self.read(1, code).await
+    }
+
+    pub async fn expn(&mut self, rcpt: &str, code: u8) -> Vec<String> {
+        self.send(&format!("EXPN {}", rcpt)).await;
+        self.read(1, code).await
+    }
+
+    pub async fn data(&mut self, code: u8) -> Vec<String> {
+        self.send("DATA").await;
+        self.read(1, code).await
+    }
+
+    pub async fn data_bytes(
+        &mut self,
+        message: &str,
+        num_responses: usize,
+        code: u8,
+    ) -> Vec<String> {
+        self.send_raw(message).await;
+        self.send_raw("\r\n.\r\n").await;
+        self.read(num_responses, code).await
+    }
+
+    pub async fn bdat(&mut self, chunk: &str, code: u8) -> Vec<String> {
+        self.send_raw(&format!("BDAT {}\r\n{}", chunk.len(), chunk))
+            .await;
+        self.read(1, code).await
+    }
+
+    pub async fn bdat_last(&mut self, chunk: &str, num_responses: usize, code: u8) -> Vec<String> {
+        self.send_raw(&format!("BDAT {} LAST\r\n{}", chunk.len(), chunk))
+            .await;
+        self.read(num_responses, code).await
+    }
+
+    pub async fn rset(&mut self) -> Vec<String> {
+        self.send("RSET").await;
+        self.read(1, 2).await
+    }
+
+    pub async fn noop(&mut self) -> Vec<String> {
+        self.send("NOOP").await;
+        self.read(1, 2).await
+    }
+
+    pub async fn quit(&mut self) -> Vec<String> {
+        self.send("QUIT").await;
+        self.read(1, 2).await
+    }
+
+    /// Sends all commands in a single write and returns one response per command.
+    pub async fn pipeline(&mut self, commands: &[&str]) -> Vec<Vec<String>> {
+        let mut buf = String::new();
+        for command in commands {
+            buf.push_str(command);
+            buf.push_str("\r\n");
+        }
+        self.send_raw(&buf).await;
+
+        let mut responses = Vec::with_capacity(commands.len());
+        for _ in commands {
+            responses.push(self.read(1, u8::MAX).await);
+        }
+        responses
+    }
+
+    /// Runs a client/server exchange, where lines starting with `C:` are sent
+    /// to the server and lines starting with `S:` are the expected responses.
+    /// Consecutive `C:` lines are pipelined and each `S:` line has to match the
+    /// beginning of the last line of the next response, for example:
+    ///
+    /// ```text
+    /// C: MAIL FROM:<bill@example.com>
+    /// C: RCPT TO:<jdoe@example.com>
+    /// S: 250
+    /// S: 250 2.1.5
+    /// ```
+    pub async fn script(&mut self, script: &str) -> Vec<Vec<String>> {
+        let mut responses = Vec::new();
+        let mut commands = String::new();
+
+        for line in script.lines().map(|line| line.trim()) {
+            if let Some(command) = line.strip_prefix("C:") {
+                commands.push_str(command.strip_prefix(' ').unwrap_or(command));
+                commands.push_str("\r\n");
+            } else if let Some(expected) = line.strip_prefix("S:") {
+                if !commands.is_empty() {
+                    self.send_raw(&commands).await;
+                    commands.clear();
+                }
+                let expected = expected.trim();
+                let response = self.read(1, u8::MAX).await;
+                if !response.last().unwrap().starts_with(expected) {
+                    panic!("Expected response {expected:?}, got {response:?}.");
+                }
+                responses.push(response);
+            } else if !line.is_empty() {
+                panic!("Invalid script line {line:?}.");
+            }
+        }
+
+        if !commands.is_empty() {
+            self.send_raw(&commands).await;
+        }
+
+        responses
+    }
+
+    pub async fn read(&mut self, mut num_responses: usize, code: u8) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            match tokio::time::timeout(Duration::from_millis(1500), self.reader.next_line()).await {
+                Ok(Ok(Some(line))) => {
+                    let is_done = line.as_bytes()[3] == b' ';
+                    //let c = println!("<- {:?}", line);
+                    lines.push(line);
+                    if is_done {
+                        num_responses -= 1;
+                        if num_responses != 0 {
+                            continue;
+                        }
+
+                        if code != u8::MAX {
+                            for line in &lines {
+                                if line.as_bytes()[0] - b'0' != code {
+                                    panic!("Expected completion code {}, got {:?}.", code, lines);
+                                }
+                            }
+                        }
+                        return lines;
+                    }
+                }
+                Ok(Ok(None)) => {
+                    panic!("Invalid response: {:?}.", lines);
+                }
+                Ok(Err(err)) => {
+                    panic!("Connection broken: {} ({:?})", err, lines);
+                }
+                Err(_) => panic!("Timeout while waiting for server response: {:?}", lines),
+            }
+        }
+    }
+
+    pub async fn send(&mut self, text: &str) {
+        //let c = println!("-> {:?}", text);
+        self.writer.write_all(text.as_bytes()).await.unwrap();
+        self.writer.write_all(b"\r\n").await.unwrap();
+    }
+
+    pub async fn send_raw(&mut self, text: &str) {
+        //let c = println!("-> {:?}", text);
+        self.writer.write_all(text.as_bytes()).await.unwrap();
+    }
+
+    // LMTP returns one response per recipient after the message is received
+    fn num_responses(&self, recipients: &[&str]) -> usize {
+        match self.protocol {
+            Protocol::Smtp => 1,
+            Protocol::Lmtp => recipients.len(),
+        }
+    }
+}
+
+async fn tls_connect<T: AsyncRead + AsyncWrite + Unpin>(
+    stream: T,
+) -> tokio_rustls::client::TlsStream<T> {
+    build_tls_connector(true)
+        .connect(
+            ServerName::try_from("mx.example.org").unwrap().to_owned(),
+            stream,
+        )
+        .await
+        .unwrap()
+}
+
+pub trait AssertResult: Sized {
+    fn assert_contains(self, text: &str) -> Self;
+    fn assert_count(self, text: &str, occurrences: usize) -> Self;
+    fn assert_equals(self, text: &str) -> Self;
+}
+
+impl AssertResult for Vec<String> {
+    fn assert_contains(self, text: &str) -> Self {
+        for line in &self {
+            if line.contains(text) {
+                return self;
+            }
+        }
+        panic!("Expected response to contain {:?}, got {:?}", text, self);
+    }
+
+    fn assert_count(self, text: &str, occurrences: usize) -> Self {
+        assert_eq!(
+            self.iter().filter(|l| l.contains(text)).count(),
+            occurrences,
+            "Expected {} occurrences of {:?}, found {}.",
+            occurrences,
+            text,
+            self.iter().filter(|l| l.contains(text)).count()
+        );
+        self
+    }
+
+    fn assert_equals(self, text: &str) -> Self {
+        for line in &self {
+            if line == text {
+                return self;
+            }
+        }
+        panic!("Expected response to be {:?}, got {:?}", text, self);
+    }
+}
@@ -9,11 +9,16 @@ use std::{fs, net::IpAddr, path::PathBuf, sync::Arc, time::Duration};
 use common::{
     config::{
         server::{Listener, Server, ServerProtocol, Servers},
-        smtp::{throttle::parse_throttle, *},
+        smtp::{
+            queue::{IpPoolStrategy, QueueConfig},
+            throttle::parse_throttle,
+            *,
+        },
     },
     expr::{functions::ResolveVariable, if_block::*, tokenizer::TokenMap, *},
     Core,
 };
+use smtp::outbound::lookup::SelectSourceIp;
 use tokio::net::TcpSocket;
 
 use utils::config::{Config, Rate};
@@ -289,6 +294,129 @@ fn parse_throttles() {
     );
 }
 
+#[test]
+fn parse_ip_pools() {
+    let mut config = Config::new(
+        r#"
+[queue.ip-pool.bulk]
+strategy = "round-robin"
+v4 = ["192.0.2.1 3", "192.0.2.2"]
+v6 = ["2001:db8::1"]
+
+[queue.ip-pool.transactional]
+strategy = "random"
+v4 = ["198.51.100.1"]
+
+[queue.ip-pool.invalid]
+v4 = ["2001:db8::1", "192.0.2.1 0"]
+"#,
+    )
+    .unwrap();
+    let queue = QueueConfig::parse(&mut config);
+
+    let bulk = queue.ip_pools.get("bulk").unwrap();
+    assert_eq!(bulk.strategy, IpPoolStrategy::RoundRobin);
+    assert_eq!(bulk.ipv6, vec![("2001:db8::1".parse().unwrap(), 1)]);
+    let selected = (0..8)
+        .map(|_| {
+            bulk.strategy
+                .select(&bulk.ipv4, &bulk.next_ipv4)
+                .unwrap()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        selected,
+        [
+            "192.0.2.1",
+            "192.0.2.1",
+            "192.0.2.1",
+            "192.0.2.2",
+            "192.0.2.1",
+            "192.0.2.1",
+            "192.0.2.1",
+            "192.0.2.2"
+        ]
+    );
+
+    let transactional = queue.ip_pools.get("transactional").unwrap();
+    assert_eq!(transactional.strategy, IpPoolStrategy::Random);
+    assert_eq!(
+        transactional
+            .strategy
+            .select(&transactional.ipv4, &transactional.next_ipv4),
+        Some("198.51.100.1".parse().unwrap())
+    );
+    assert_eq!(
+        transactional
+            .strategy
+            .select(&transactional.ipv6, &transactional.next_ipv6),
+        None
+    );
+
+    assert!(queue.ip_pools.get("invalid").unwrap().ipv4.is_empty());
+    assert!(config.errors.contains_key("queue.ip-pool.invalid"));
+}
+
+#[test]
+fn parse_relay_routes() {
+    let mut config = Config::new(
+        r#"
+[queue.route.a-provider]
+address = "smtp.provider.net"
+port = 587
+recipient-domains = ["example.org", "*.example.net"]
+auth.username = "user"
+auth.secret = "secret"
+tls.implicit = false
+tls.required = true
+
+[queue.route.b-bulk]
+address = "bulk.provider.net"
+sender-domains = ["news.example.com"]
+
+[queue.route.c-invalid]
+address = "invalid.provider.net"
+"#,
+    )
+    .unwrap();
+    let queue = QueueConfig::parse(&mut config);
+
+    assert_eq!(
+        queue
+            .routes
+            .iter()
+            .map(|route| route.id.as_str())
+            .collect::<Vec<_>>(),
+        ["a-provider", "b-bulk"]
+    );
+    assert!(config.errors.contains_key("queue.route.c-invalid"));
+
+    for (rcpt_domain, sender_domain, expected) in [
+        ("example.org", "example.com", Some("smtp.provider.net")),
+        ("mx.example.net", "example.com", Some("smtp.provider.net")),
+        ("a.b.example.net", "news.example.com", Some("smtp.provider.net")),
+        ("example.net", "example.com", None),
+        ("otherexample.net", "example.com", None),
+        ("foobar.org", "news.example.com", Some("bulk.provider.net")),
+        ("foobar.org", "example.com", None),
+    ] {
+        assert_eq!(
+            queue
+                .route(rcpt_domain, sender_domain)
+                .map(|route| route.host.address.as_str()),
+            expected,
+            "{rcpt_domain} {sender_domain}"
+        );
+    }
+
+    let host = &queue.route("example.org", "").unwrap().host;
+    assert_eq!(host.port, 587);
+    assert!(host.auth.is_some());
+    assert!(!host.tls_implicit);
+    assert!(host.tls_required);
+}
+
 #[test]
 fn parse_servers() {
     let mut file = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -10,6 +10,7 @@ use ahash::AHashMap;
 use common::{
     scripts::{
         functions::html::{get_attribute, html_attr_tokens, html_img_area, html_to_tokens},
+        stats::SymbolStats,
         ScriptModification,
     },
     Core,
@@ -33,7 +34,11 @@ is-spam = "X-Spam-Status: Yes"
 [lookup.spam-config]
 add-spam = true
 add-spam-result = true
+add-spam-report = true
+symbol-stats = true
+symbol-stats-expiry = "86400"
 learn-enable = true
+allow-contacts = true
 #learn-balance = "0.9"
 learn-balance = "0.0"
 learn-ham-replies = true
@@ -42,6 +47,9 @@ learn-spam-threshold = "6.0"
 threshold-spam = "5.0"
 threshold-discard = 0
 threshold-reject = 0
+trap-penalty = "15.0"
+trap-block-ip = true
+trap-block-expiry = "86400"
 directory = ""
 lookup = ""
 
@@ -68,11 +76,16 @@ data = "spamdb"
 lookup = "spamdb"
 blob = "spamdb"
 fts = "spamdb"
+directory = "spamdb"
 
 [store."spamdb"]
 type = "sqlite"
 path = "{PATH}/test_antispam.db"
 
+[directory."spamdb"]
+type = "internal"
+store = "spamdb"
+
 #[store."redis"]
 #type = "redis"
 #url = "redis://127.0.0.1"
@@ -123,6 +136,7 @@ async fn antispam() {
         "rbl",
         "replies_out",
         "replies_in",
+        "contacts",
         "spamtrap",
         "bayes_classify",
         "reputation",
@@ -300,6 +314,9 @@ async fn antispam() {
                         "authenticated_as" => {
                             session.data.authenticated_as = value.to_string();
                         }
+                        "early_talker" => {
+                            session.data.early_talker = value == "1";
+                        }
                         "spf.result" | "spf_ehlo.result" => {
                             variables.insert(
                                 param.to_string(),
@@ -457,6 +474,25 @@ async fn antispam() {
                 ScriptResult::Discard => println!("Discard"),
             }
         }
+
+        if test_name == "spamtrap" {
+            // Spam trap senders should be blocked
+            assert!(core.core.is_ip_blocked(&"192.0.2.25".parse().unwrap()));
+        } else if test_name == "combined" {
+            // Triggered symbols should be counted
+            let stats = SymbolStats::fetch(&core.core.storage.lookup, 1)
+                .await
+                .unwrap();
+            assert!(!stats.is_empty());
+            for symbol in &stats {
+                assert!(symbol.hits > 0, "{symbol:?}");
+                assert_eq!(symbol.history.len(), 1, "{symbol:?}");
+                assert!(
+                    (symbol.average_score * symbol.hits as f64 - symbol.total_score).abs() < 0.01,
+                    "{symbol:?}"
+                );
+            }
+        }
     }
 }
 
@@ -58,8 +58,26 @@ description = "Mike Foobar"
 secret = "p4ssw0rd"
 email = "mike@test.com"
 
+[server]
+trusted-networks = ["10.0.1.0/24"]
+trusted-networks-lookup = "trusted"
+
+[lookup.trusted]
+"10.0.2.*" = true
+
 [session.rcpt]
 directory = "'local'"
+rewrite = [{if = "rcpt = 'alias@foobar.org'", then = "'john@external.org'"},
+           {else = false}]
+
+[auth.srs]
+enable = true
+secret = "srs-secret"
+domain = "srs.foobar.org"
+
+[session.auth]
+require = [{if = "remote_ip = '10.0.1.5'", then = true},
+           {else = false}]
 
 [session.data.limits]
 messages = [{if = "remote_ip = '10.0.0.1'", then = 1},
@@ -155,10 +173,7 @@ async fn data() {
     );
 
     // Maximum one message per session is allowed for 10.0.0.1
-    session.mail_from("john@doe.org", "250").await;
-    session.rcpt_to("bill@foobar.org", "250").await;
-    session.ingest(b"DATA\r\n").await.unwrap();
-    session.response().assert_code("451 4.4.5");
+    session.mail_from("john@doe.org", "451 4.4.5").await;
     session.rset().await;
 
     // Headers should be added to messages from 10.0.0.3
@@ -235,6 +250,73 @@ async fn data() {
         )
         .await;
 
+    // Spam status headers are removed from messages sent by untrusted clients
+    let message = "X-Spam-Status: No, score=-5.00\r\nSubject: Status\r\n\r\nTest";
+    session
+        .send_message("jane@foobar.org", &["mike@test.com"], message, "250")
+        .await;
+    qr.expect_message()
+        .await
+        .read_lines(&qr)
+        .await
+        .assert_not_contains("X-Spam-Status")
+        .assert_contains("Subject: Status");
+
+    // Clients on trusted networks can relay without authenticating
+    // and their spam status headers are kept
+    session.data.remote_ip_str = "10.0.1.5".to_string();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.eval_session_params().await;
+    session
+        .send_message("jane@foobar.org", &["john@external.org"], message, "250")
+        .await;
+    qr.expect_message()
+        .await
+        .read_lines(&qr)
+        .await
+        .assert_contains("X-Spam-Status: No, score=-5.00")
+        .assert_contains("Subject: Status");
+
+    // Trusted networks can also be listed in a lookup store
+    session.data.remote_ip_str = "10.0.2.7".to_string();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.eval_session_params().await;
+    session
+        .send_message("jane@foobar.org", &["john@external.org"], message, "250")
+        .await;
+    qr.expect_message()
+        .await
+        .read_lines(&qr)
+        .await
+        .assert_contains("X-Spam-Status: No, score=-5.00");
+
+    // Messages from foreign senders forwarded to external addresses are
+    // rewritten using SRS
+    session
+        .send_message("bill@remote.net", &["alias@foobar.org"], message, "250")
+        .await;
+    let queued = qr.expect_message().await;
+    assert_eq!(
+        queued.return_path,
+        core.core
+            .smtp
+            .mail_auth
+            .srs
+            .forward("bill@remote.net", "foobar.org")
+            .unwrap()
+    );
+    assert_eq!(queued.recipients[0].address, "john@external.org");
+
+    // Local deliveries and local senders are not rewritten
+    session
+        .send_message("bill@remote.net", &["mike@test.com"], message, "250")
+        .await;
+    assert_eq!(qr.expect_message().await.return_path, "bill@remote.net");
+    session
+        .send_message("jane@foobar.org", &["alias@foobar.org"], message, "250")
+        .await;
+    assert_eq!(qr.expect_message().await.return_path, "jane@foobar.org");
+
     // Make sure store is empty
     qr.clear_queue(&core).await;
     core.core
@@ -4,9 +4,15 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::time::{Duration, Instant};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-use common::{config::smtp::report::AggregateFrequency, Core};
+use common::{
+    config::smtp::{report::AggregateFrequency, resolver::Bimi},
+    Core,
+};
 
 use mail_auth::{
     common::{parse::TxtRecordParser, verify::DomainKey},
@@ -24,7 +30,10 @@ use crate::smtp::{
     session::{TestSession, VerifyResponse},
     TempDir, TestSMTP,
 };
-use smtp::core::{Inner, Session};
+use smtp::{
+    core::{Inner, Session},
+    inbound::bimi::{verify_vmc_chain, BIMI_TEST_INDICATOR, BIMI_TEST_VMC},
+};
 
 const CONFIG: &str = r#"
 [storage]
@@ -68,6 +77,7 @@ sign = "['rsa']"
 [report.dmarc]
 send = "[1, 1s]"
 sign = "['rsa']"
+redact = "headers"
 
 [report.dmarc.aggregate]
 send = "daily"
@@ -84,6 +94,11 @@ verify = "strict"
 [auth.arc]
 verify = "strict"
 
+[auth.bimi]
+verify = true
+indicator = true
+vmc-roots = "%{file:{VMC_ROOT}}%"
+
 [auth.dkim]
 verify = [{if = "sender_domain = 'test.net'", then = 'relaxed'},
          { else = 'strict' }]
@@ -97,7 +112,12 @@ async fn dmarc() {
 
     let mut inner = Inner::default();
     let tmp_dir = TempDir::new("smtp_dmarc_test", true);
-    let mut config = Config::new(tmp_dir.update_config(CONFIG.to_string() + SIGNATURES)).unwrap();
+    let mut config = Config::new(
+        tmp_dir
+            .update_config(CONFIG.to_string() + SIGNATURES)
+            .replace("{VMC_ROOT}", vmc_path("vmc_root.pem").to_str().unwrap()),
+    )
+    .unwrap();
     let stores = Stores::parse_all(&mut config).await;
     let core = Core::parse(&mut config, stores, Default::default()).await;
 
@@ -273,7 +293,11 @@ async fn dmarc() {
         .assert_contains("To: dmarc-failures@example.com")
         .assert_contains("Feedback-Type: auth-failure")
         .assert_contains("Auth-Failure: dmarc")
-        .assert_contains("dmarc=3Dnone");
+        .assert_contains("dmarc=3Dnone")
+        .assert_contains("From: redacted@example.com")
+        .assert_contains("DKIM-Signature: v=1; a=rsa-sha256; s=default; d=example.com")
+        .assert_not_contains("To: jdoe@example.com")
+        .assert_not_contains("Subject: TPS Report");
 
     // Expect DMARC aggregate report
     let report = rr.read_report().await.unwrap_dmarc();
@@ -293,7 +317,18 @@ async fn dmarc() {
         .await;
     qr.assert_no_events();
 
-    // Messages passing DMARC should be accepted
+    // Messages passing DMARC should be accepted and include the BIMI indicator
+    core.bimi_add(
+        "default._bimi.example.com.",
+        Bimi {
+            location: "https://example.com/bimi/logo.svg".to_string().into(),
+            authority: None,
+        },
+        Instant::now() + Duration::from_secs(5),
+    );
+    *BIMI_TEST_INDICATOR.lock() =
+        br#"<svg version="1.2" baseProfile="tiny-ps" xmlns="http://www.w3.org/2000/svg"></svg>"#
+            .to_vec();
     session
         .send_message(
             "bill@example.com",
@@ -309,5 +344,73 @@ async fn dmarc() {
         .assert_contains("dkim=pass")
         .assert_contains("spf=pass")
         .assert_contains("dmarc=pass")
-        .assert_contains("Received-SPF: pass");
+        .assert_contains("Received-SPF: pass")
+        .assert_contains("BIMI-Location: v=BIMI1;")
+        .assert_contains("l=https://example.com/bimi/logo.svg")
+        .assert_contains("BIMI-Indicator: PHN2ZyB2ZXJzaW9uPSIxLjIiIGJhc2VQcm9maWxlPSJ0aW55LXBzIiB4bWxucz0iaHR0cDov");
+    // Only VMCs issued by a configured mark certificate authority are accepted
+    let vmc = std::fs::read(vmc_path("vmc.pem")).unwrap();
+    let self_signed_vmc = std::fs::read(vmc_path("vmc_self_signed.pem")).unwrap();
+    let vmc_roots = &core.smtp.mail_auth.bimi.vmc_roots;
+    assert_eq!(vmc_roots.len(), 1);
+    assert_eq!(verify_vmc_chain(&vmc, "example.com", vmc_roots), Ok(()));
+    assert_eq!(
+        verify_vmc_chain(&vmc, "mail.example.com", vmc_roots),
+        Ok(())
+    );
+    assert!(verify_vmc_chain(&vmc, "example.org", vmc_roots).is_err());
+    assert!(verify_vmc_chain(&vmc, "example.com", &[]).is_err());
+    assert!(verify_vmc_chain(&self_signed_vmc, "example.com", vmc_roots).is_err());
+
+    // Self-signed VMCs are rejected
+    core.bimi_add(
+        "default._bimi.example.com.",
+        Bimi {
+            location: "https://example.com/bimi/logo.svg".to_string().into(),
+            authority: "https://example.com/bimi/vmc.pem".to_string().into(),
+        },
+        Instant::now() + Duration::from_secs(5),
+    );
+    *BIMI_TEST_VMC.lock() = self_signed_vmc;
+    session
+        .send_message(
+            "bill@example.com",
+            &["jdoe@example.com"],
+            "test:dkim",
+            "250",
+        )
+        .await;
+    qr.expect_message()
+        .await
+        .read_lines(&qr)
+        .await
+        .assert_contains("dmarc=pass")
+        .assert_not_contains("BIMI-Location");
+
+    // VMCs issued by a trusted authority are accepted
+    *BIMI_TEST_VMC.lock() = vmc;
+    session
+        .send_message(
+            "bill@example.com",
+            &["jdoe@example.com"],
+            "test:dkim",
+            "250",
+        )
+        .await;
+    qr.expect_message()
+        .await
+        .read_lines(&qr)
+        .await
+        .assert_contains("dmarc=pass")
+        .assert_contains("a=https://example.com/bimi/vmc.pem")
+        .assert_contains("BIMI-Indicator: ");
+}
+
+fn vmc_path(name: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push("smtp");
+    path.push("certs");
+    path.push(name);
+    path
 }
@@ -69,7 +69,9 @@ async fn ehlo() {
         .assert_contains("SIZE 1024")
         .assert_contains("MT-PRIORITY NSEP")
         .assert_contains("FUTURERELEASE 3600")
-        .assert_contains("STARTTLS");
+        .assert_contains("STARTTLS")
+        .assert_contains("LIMITS RCPTMAX=100 MAILMAX=10")
+        .assert_not_contains("RCPTDOMAINMAX");
 
     // SPF should be a Pass for 10.0.0.1
     assert_eq!(
@@ -6,15 +6,17 @@
 
 use std::time::{Duration, Instant};
 
-use common::Core;
+use common::{listener::tarpit::TransferRate, Core};
 use tokio::sync::watch;
 
 use smtp::core::{Inner, Session};
-use utils::config::Config;
+use store::Stores;
+use utils::config::{Config, Rate};
 
 use crate::smtp::{
     build_smtp,
     session::{TestSession, VerifyResponse},
+    TempDir,
 };
 
 const CONFIG: &str = r#"
@@ -25,6 +27,28 @@ timeout = [{if = "remote_ip = '10.0.0.2'", then = '500ms'},
            {else = '30m'}]
 duration = [{if = "remote_ip = '10.0.0.3'", then = '500ms'},
             {else = '60m'}]
+
+[server.tarpit]
+greeting-wait = [{if = "remote_ip = '10.0.0.4'", then = '100ms'},
+                 {else = false}]
+early-talker = '200ms'
+"#;
+
+const CONFIG_PREGREET: &str = r#"
+[storage]
+data = "sqlite"
+lookup = "sqlite"
+blob = "sqlite"
+fts = "sqlite"
+
+[store."sqlite"]
+type = "sqlite"
+path = "{TMP}/queue.db"
+
+[server.tarpit]
+greeting-wait = '100ms'
+early-talker-action = [{if = "remote_ip = '10.0.0.5'", then = 'score'},
+                       {else = 'reject'}]
 "#;
 
 #[tokio::test]
@@ -72,4 +96,101 @@ async fn limits() {
     session.write_rx("MAIL FROM:<this_is_a_long@command_over_10_chars.com>\r\n");
     session.handle_conn().await;
     session.response().assert_code("221 2.0.0");
+
+    // Slow transfer
+    session.data.transfer_rate = TransferRate::new(Some(Rate {
+        requests: 100,
+        period: Duration::from_millis(100),
+    }));
+    session.ingest(b"MAIL FROM:<slow").await.unwrap();
+    session
+        .data
+        .transfer_rate
+        .set_pending(session.is_pending_request());
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    session.write_rx("@example.com>\r\n");
+    session.handle_conn().await;
+    session.response().assert_code("421 4.7.0");
+
+    // Early talker
+    session.state = Default::default();
+    session.data.remote_ip_str = "10.0.0.4".to_string();
+    session.write_rx("EHLO mx.example.org\r\n");
+    let started = Instant::now();
+    assert!(session.init_conn().await);
+    assert!(started.elapsed() >= Duration::from_millis(300));
+    assert_eq!(session.params.tarpit, Duration::from_millis(200));
+    session
+        .response()
+        .assert_contains("220 ")
+        .assert_code("250 ");
+
+    // Reject early talkers
+    let mut config = Config::new(format!("{CONFIG}\nearly-talker-action = 'reject'\n")).unwrap();
+    let core = Core::parse(&mut config, Default::default(), Default::default()).await;
+    let mut session = Session::test(build_smtp(core, Inner::default()));
+    session.data.remote_ip_str = "10.0.0.4".to_string();
+    session.write_rx("EHLO mx.example.org\r\n");
+    assert!(!session.init_conn().await);
+    session.response().assert_code("554 5.5.0");
+}
+
+#[tokio::test]
+async fn pregreet() {
+    // Enable logging
+    crate::enable_logging();
+
+    let tmp_dir = TempDir::new("smtp_pregreet_test", true);
+    let mut config = Config::new(tmp_dir.update_config(CONFIG_PREGREET)).unwrap();
+    let stores = Stores::parse_all(&mut config).await;
+    let core = build_smtp(
+        Core::parse(&mut config, stores, Default::default()).await,
+        Inner::default(),
+    );
+
+    // Score early talkers
+    let mut session = Session::test(core.clone());
+    session.data.remote_ip_str = "10.0.0.5".to_string();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.write_rx("EHLO mx.example.org\r\n");
+    assert!(session.init_conn().await);
+    assert!(session.data.early_talker);
+    assert_eq!(session.params.tarpit, Duration::ZERO);
+    session
+        .response()
+        .assert_contains("220 ")
+        .assert_code("250 ");
+
+    // Early talkers are not added to the known good client cache
+    let mut session = Session::test(core.clone());
+    session.data.remote_ip_str = "10.0.0.5".to_string();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    let started = Instant::now();
+    assert!(session.init_conn().await);
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    assert!(!session.data.early_talker);
+
+    // Clients that wait for the greeting are not delayed again
+    let mut session = Session::test(core.clone());
+    session.data.remote_ip_str = "10.0.0.6".to_string();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    let started = Instant::now();
+    assert!(session.init_conn().await);
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    let mut session = Session::test(core.clone());
+    session.data.remote_ip_str = "10.0.0.6".to_string();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.write_rx("EHLO mx.example.org\r\n");
+    let started = Instant::now();
+    assert!(session.init_conn().await);
+    assert!(started.elapsed() < Duration::from_millis(100));
+    session.response().assert_code("220 ");
+
+    // Reject early talkers
+    let mut session = Session::test(core);
+    session.data.remote_ip_str = "10.0.0.7".to_string();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.write_rx("EHLO mx.example.org\r\n");
+    assert!(!session.init_conn().await);
+    session.response().assert_code("554 5.5.0");
 }
@@ -243,6 +243,26 @@ async fn mail() {
     assert_eq!(session.data.priority, -3);
     session.rset().await;
 
+    // Unauthenticated senders cannot raise the priority
+    session
+        .ingest(b"MAIL FROM:<jane@foobar.org> MT-PRIORITY=4\r\n")
+        .await
+        .unwrap();
+    session.response().assert_code("250");
+    assert_eq!(session.data.priority, 0);
+    session.rset().await;
+
+    // Role based limits override the max-priority expression
+    session.data.max_priority = Some(2);
+    session
+        .ingest(b"MAIL FROM:<jane@foobar.org> MT-PRIORITY=4\r\n")
+        .await
+        .unwrap();
+    session.response().assert_code("250");
+    assert_eq!(session.data.priority, 2);
+    session.rset().await;
+    session.data.max_priority = None;
+
     // Test REQUIRETLS extension
     session
         .ingest(b"MAIL FROM:<jane@foobar.org> REQUIRETLS\r\n")
@@ -324,3 +344,39 @@ async fn mail() {
     session.response().assert_code("501 5.5.4");
     session.rset().await;
 }
+
+#[tokio::test]
+async fn mail_maintenance() {
+    let tmp_dir = TempDir::new("smtp_mail_maintenance_test", true);
+    let mut config = Config::new(tmp_dir.update_config(
+        r#"
+[storage]
+data = "sqlite"
+lookup = "sqlite"
+blob = "sqlite"
+
+[server.maintenance]
+enable = true
+message = "Scheduled store maintenance"
+"#,
+    ))
+    .unwrap();
+    let stores = Stores::parse_all(&mut config).await;
+    let core = Core::parse(&mut config, stores, Default::default()).await;
+    assert!(core.storage.maintenance.is_enabled());
+
+    // Messages are deferred while in maintenance mode
+    let mut session = Session::test(build_smtp(Arc::new(core), Inner::default()));
+    session.data.remote_ip_str = "10.0.0.1".to_string();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.eval_session_params().await;
+    session.ingest(b"EHLO mx1.foobar.org\r\n").await.unwrap();
+    session.response().assert_code("250");
+    session
+        .ingest(b"MAIL FROM:<bill@foobar.org>\r\n")
+        .await
+        .unwrap();
+    session
+        .response()
+        .assert_contains("451 4.3.0 Scheduled store maintenance");
+}
@@ -445,7 +445,7 @@ fn milter_address_modifications() {
         .apply_milter_modifications(
             vec![
                 Modification::DeleteRcpt {
-                    recipient: "bill@example.org".to_string(),
+                    recipient: "<Bill@Example.org>".to_string(),
                 },
                 Modification::DeleteRcpt {
                     recipient: "<>".to_string(),
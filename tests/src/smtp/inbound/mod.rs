@@ -137,10 +137,20 @@ impl QueueReceiver {
     }
 
     pub async fn delivery_attempt(&mut self, queue_id: u64) -> DeliveryAttempt {
+        let due = self.message_due(queue_id).await;
+        let lock_expiry = self
+            .store
+            .get_value::<u64>(ValueKey::from(ValueClass::Queue(QueueClass::MessageEvent(
+                QueueEvent { due, queue_id },
+            ))))
+            .await
+            .unwrap()
+            .unwrap_or_default();
         DeliveryAttempt::new(QueueEventLock {
-            due: self.message_due(queue_id).await,
+            due,
             queue_id,
-            lock_expiry: 0,
+            lock_expiry,
+            priority: 0,
         })
     }
 
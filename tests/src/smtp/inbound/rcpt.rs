@@ -6,16 +6,24 @@
 
 use std::time::Duration;
 
-use common::Core;
+use common::{config::server::ServerProtocol, manager::webadmin::Resource, Core};
 
+use hyper::{body, server::conn::http1, service::service_fn};
+use hyper_util::rt::TokioIo;
+use jmap::api::http::{fetch_body, ToHttpResponse};
 use smtp_proto::{RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_SUCCESS};
 use store::Stores;
+use tokio::{net::TcpListener, sync::watch};
 use utils::config::Config;
 
-use smtp::core::{Inner, Session, State};
+use smtp::{
+    core::{Inner, Session, State},
+    inbound::http_lookup::{Action, Request, Response},
+};
 
 use crate::smtp::{
     build_smtp,
+    outbound::TestServer,
     session::{TestSession, VerifyResponse},
     TempDir,
 };
@@ -61,16 +69,38 @@ email = "mike@foobar.org"
 [session.rcpt]
 directory = "'local'"
 max-recipients = [{if = "remote_ip = '10.0.0.1'", then = 3},
-                {else = 5}]
+                  {if = "rcpt_domain = 'limited.org'", then = 1},
+                  {else = 5}]
+max-domains = [{if = "remote_ip = '10.0.0.4'", then = 2},
+               {else = 0}]
 relay = [{if = "remote_ip = '10.0.0.1'", then = false},
          {else = true}]
 
+[session.rcpt.role-routing]
+postmaster = "john@foobar.org"
+abuse = ["nobody@foobar.org", "jane@foobar.org"]
+
+[session.rcpt.greylist]
+enable = [{if = "remote_ip = '10.0.0.3'", then = true},
+          {else = false}]
+delay = "1h"
+expire = "4h"
+allow-list.expire = "1d"
+
 [session.rcpt.errors]
 total = [{if = "remote_ip = '10.0.0.1'", then = 3},
          {else = 100}]
 wait = [{if = "remote_ip = '10.0.0.1'", then = '5ms'},
         {else = '1s'}]
 
+[session.data.limits]
+size = [{if = "rcpt_domain = 'small.org'", then = 1024},
+        {else = 104857600}]
+
+[auth.srs]
+enable = true
+secret = "srs-secret"
+
 [session.extensions]
 dsn = [{if = "remote_ip = '10.0.0.1'", then = false},
        {else = true}]
@@ -131,7 +161,7 @@ async fn rcpt() {
     // Restore rate limit
     tokio::time::sleep(Duration::from_millis(1100)).await;
     session.rcpt_to("Mike@FooBar.org", "250").await;
-    session.rcpt_to("john@foobar.org", "451 4.5.3").await;
+    session.rcpt_to("john@foobar.org", "452 4.5.3").await;
 
     // Check recipients
     assert_eq!(session.data.rcpt_to.len(), 3);
@@ -163,4 +193,382 @@ async fn rcpt() {
     let rcpt = session.data.rcpt_to.last().unwrap();
     assert!((rcpt.flags & (RCPT_NOTIFY_DELAY | RCPT_NOTIFY_SUCCESS | RCPT_NOTIFY_FAILURE)) != 0);
     assert_eq!(rcpt.dsn_info.as_ref().unwrap(), "Jane.Doe@Foobar.org");
+
+    // Recipient limits per domain
+    session.rset().await;
+    session.mail_from("john@example.net", "250").await;
+    session.rcpt_to("jane@limited.org", "250").await;
+    session.rcpt_to("bill@limited.org", "452 4.5.3").await;
+    session.rcpt_to("bill@domain.com", "250").await;
+    assert_eq!(session.data.rcpt_to.len(), 2);
+
+    // Message size limits per domain
+    session.rset().await;
+    session
+        .mail_from("<john@example.net> SIZE=2048", "250")
+        .await;
+    session.rcpt_to("jane@small.org", "552 5.3.4").await;
+    session.rcpt_to("external@domain.com", "250").await;
+    assert_eq!(session.params.max_message_size, 104857600);
+    session.rset().await;
+    session.mail_from("john@example.net", "250").await;
+    session.rcpt_to("external@domain.com", "250").await;
+    session.rcpt_to("jane@small.org", "250").await;
+    assert_eq!(session.params.max_message_size, 1024);
+
+    // Bounces to SRS addresses are relayed to the original sender
+    let srs_address = session
+        .core
+        .core
+        .smtp
+        .mail_auth
+        .srs
+        .forward("jane@external.org", "foobar.org")
+        .unwrap();
+    session.data.remote_ip_str = "10.0.0.1".to_string();
+    session.eval_session_params().await;
+    session.rset().await;
+    session.mail_from("<>", "250").await;
+    session.rcpt_to(&srs_address, "250").await;
+    assert_eq!(
+        session.data.rcpt_to.last().unwrap().address,
+        "jane@external.org"
+    );
+    session
+        .rcpt_to(&srs_address.replace("=jane@", "=john@"), "550 5.1.1")
+        .await;
+    assert_eq!(session.data.rcpt_to.len(), 1);
+
+    // SRS addresses of other forwarders are not reversed
+    let foreign_srs_address = srs_address.replace("@foobar.org", "@forwarder.example");
+    session.data.remote_ip_str = "10.0.0.2".to_string();
+    session.eval_session_params().await;
+    session.rset().await;
+    session.mail_from("john@foobar.org", "250").await;
+    session.rcpt_to(&foreign_srs_address, "250").await;
+    assert_eq!(
+        session.data.rcpt_to.last().unwrap().address,
+        foreign_srs_address
+    );
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn rcpt_greylist() {
+    // Enable logging
+    crate::enable_logging();
+
+    let tmp_dir = TempDir::new("smtp_rcpt_greylist_test", true);
+    let mut config = Config::new(tmp_dir.update_config(CONFIG)).unwrap();
+    let stores = Stores::parse_all(&mut config).await;
+    let core = Core::parse(&mut config, stores, Default::default()).await;
+    let mut session = Session::test(build_smtp(core, Inner::default()));
+    session.ehlo("mx1.foobar.org").await;
+
+    // Greylisting
+    session.data.remote_ip_str = "10.0.0.3".to_string();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.eval_session_params().await;
+    session.mail_from("john@example.net", "250").await;
+    session.rcpt_to("jane@foobar.org", "451 4.7.1").await;
+    session.rcpt_to("jane@foobar.org", "451 4.7.1").await;
+
+    // Triplets that are not retried before they expire start over
+    utils::clock::advance(Duration::from_secs(5 * 3600));
+    session.rcpt_to("jane@foobar.org", "451 4.7.1").await;
+    utils::clock::advance(Duration::from_secs(1800));
+    session.rcpt_to("jane@foobar.org", "451 4.7.1").await;
+    utils::clock::advance(Duration::from_secs(1800));
+    session.rcpt_to("jane@foobar.org", "250").await;
+
+    // Hosts that retried correctly are allow-listed
+    session.rset().await;
+    session.mail_from("bill@example.net", "250").await;
+    session.rcpt_to("mike@foobar.org", "250").await;
+
+    // Until the allow-list entry expires
+    utils::clock::advance(Duration::from_secs(2 * 86400));
+    session.rset().await;
+    session.mail_from("bill@example.net", "250").await;
+    session.rcpt_to("mike@foobar.org", "451 4.7.1").await;
+}
+
+const CALL_AHEAD_LOCAL: &str = r#"
+[session.rcpt]
+relay = true
+
+[session.rcpt.call-ahead]
+host = [{if = "rcpt_domain = 'foobar.org'", then = "'primary'"},
+        {else = false}]
+
+[session.rcpt.errors]
+wait = "5ms"
+
+[remote.primary]
+address = "127.0.0.1"
+port = 9925
+protocol = 'smtp'
+
+[remote.primary.tls]
+implicit = false
+allow-invalid-certs = true
+"#;
+
+const CALL_AHEAD_REMOTE: &str = r#"
+[session.rcpt]
+relay = true
+
+[session.ehlo]
+reject-non-fqdn = false
+"#;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn rcpt_call_ahead() {
+    // Enable logging
+    crate::enable_logging();
+
+    // Start test server
+    let remote = TestServer::new("smtp_call_ahead_remote", CALL_AHEAD_REMOTE, true).await;
+    let _rx = remote.start(&[ServerProtocol::Smtp]).await;
+    let local = TestServer::new("smtp_call_ahead_local", CALL_AHEAD_LOCAL, true).await;
+
+    // Recipients rejected by the destination server are rejected at the border,
+    // temporary failures are accepted
+    let mut session = local.new_session();
+    session.data.remote_ip_str = "10.0.0.1".to_string();
+    session.eval_session_params().await;
+    session.ehlo("mx.test.org").await;
+    session.mail_from("john@test.org", "250").await;
+    session.rcpt_to("jane@foobar.org", "250").await;
+    session.rcpt_to("fail@foobar.org", "550 5.1.1").await;
+    session.rcpt_to("delay@foobar.org", "250").await;
+    session.rcpt_to("fail@example.org", "250").await;
+
+    // Only permanent results are cached
+    let lookup = &session.core.core.storage.lookup;
+    for (rcpt, expected) in [
+        ("jane@foobar.org", Some(1)),
+        ("fail@foobar.org", Some(0)),
+        ("delay@foobar.org", None),
+        ("fail@example.org", None),
+    ] {
+        assert_eq!(
+            lookup
+                .key_get::<i64>(format!("call-ahead:127.0.0.1:{rcpt}").into_bytes())
+                .await
+                .unwrap(),
+            expected,
+            "{rcpt}"
+        );
+    }
+}
+
+const HTTP_LOOKUP: &str = r#"
+[session.rcpt]
+relay = true
+http-lookup = [{if = "rcpt_domain = 'foobar.org'", then = "'crm'"},
+               {else = false}]
+
+[session.mail]
+http-lookup = "'crm'"
+
+[session.rcpt.errors]
+wait = "5ms"
+
+[session.http-lookup."crm"]
+url = "http://127.0.0.1:9334"
+timeout = "5s"
+
+[session.http-lookup."crm".options]
+on-error = "accept"
+tag-header = "X-CRM-Tag"
+"#;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn rcpt_http_lookup() {
+    // Enable logging
+    crate::enable_logging();
+
+    let _rx = spawn_mock_http_lookup_server();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let local = TestServer::new("smtp_http_lookup", HTTP_LOOKUP, true).await;
+
+    let mut session = local.new_session();
+    session.data.remote_ip_str = "10.0.0.1".to_string();
+    session.eval_session_params().await;
+    session.ehlo("mx.test.org").await;
+
+    // Senders can be rejected or temporarily deferred
+    session.mail_from("spammer@test.org", "550 5.7.1").await;
+    session.mail_from("busy@test.org", "451 4.3.5").await;
+    session.mail_from("john@test.org", "250").await;
+    assert_eq!(
+        session.data.lookup_tags,
+        vec![("X-CRM-Tag".to_string(), "sender-known".to_string())]
+    );
+
+    // Recipients can be rejected with a custom response and tagged
+    session.rcpt_to("jane@foobar.org", "250").await;
+    session.rcpt_to("unknown@foobar.org", "550 5.1.1").await;
+    session.rcpt_to("custom@foobar.org", "554 5.7.1").await;
+    session.rcpt_to("bill@foobar.org", "250").await;
+    session.rcpt_to("mike@example.org", "250").await;
+    assert_eq!(
+        session.data.lookup_tags,
+        vec![
+            ("X-CRM-Tag".to_string(), "sender-known".to_string()),
+            ("X-CRM-Tag".to_string(), "vip".to_string()),
+        ]
+    );
+
+    // Lookup errors fall back to the configured action
+    session.rcpt_to("error@foobar.org", "250").await;
+}
+
+fn spawn_mock_http_lookup_server() -> watch::Sender<bool> {
+    let (tx, rx) = watch::channel(true);
+
+    tokio::spawn(async move {
+        let listener = TcpListener::bind("127.0.0.1:9334")
+            .await
+            .unwrap_or_else(|e| {
+                panic!("Failed to bind mock HTTP lookup server to 127.0.0.1:9334: {e}");
+            });
+        let mut rx_ = rx.clone();
+        loop {
+            tokio::select! {
+                stream = listener.accept() => {
+                    match stream {
+                        Ok((stream, _)) => {
+                            let _ = http1::Builder::new()
+                            .keep_alive(false)
+                            .serve_connection(
+                                TokioIo::new(stream),
+                                service_fn(|mut req: hyper::Request<body::Incoming>| {
+                                    async move {
+                                        let request = serde_json::from_slice::<Request>(&fetch_body(&mut req, 1024 * 1024, 0).await.unwrap())
+                                        .unwrap();
+                                        let address = request.envelope.to.unwrap_or(request.envelope.from);
+                                        let body = match handle_http_lookup(&address) {
+                                            Some(response) => serde_json::to_string(&response).unwrap(),
+                                            None => "invalid".to_string(),
+                                        };
+
+                                        Ok::<_, hyper::Error>(
+                                            Resource::new("application/json", body.into_bytes())
+                                            .into_http_response().build(),
+                                        )
+                                    }
+                                }),
+                            )
+                            .await;
+                        }
+                        Err(err) => {
+                            panic!("Something went wrong: {err}" );
+                        }
+                    }
+                },
+                _ = rx_.changed() => {
+                    break;
+                }
+            };
+        }
+    });
+
+    tx
+}
+
+fn handle_http_lookup(address: &str) -> Option<Response> {
+    let (action, response, tags) = match address {
+        "spammer@test.org" | "unknown@foobar.org" => (Action::Reject, None, vec![]),
+        "busy@test.org" => (Action::TempFail, None, vec![]),
+        "john@test.org" => (Action::Accept, None, vec!["sender-known".to_string()]),
+        "bill@foobar.org" => (
+            Action::Accept,
+            None,
+            vec!["vip".to_string(), "sender-known".to_string()],
+        ),
+        "custom@foobar.org" => (
+            Action::Reject,
+            Some(smtp::inbound::hooks::SmtpResponse {
+                status: 554.into(),
+                enhanced_status: "5.7.1".to_string().into(),
+                message: "Not today".to_string().into(),
+                disconnect: false,
+            }),
+            vec![],
+        ),
+        "error@foobar.org" => return None,
+        _ => (Action::Accept, None, vec![]),
+    };
+
+    Some(Response {
+        action,
+        response,
+        tags,
+    })
+}
+
+#[tokio::test]
+async fn rcpt_domains() {
+    // Enable logging
+    crate::enable_logging();
+
+    let tmp_dir = TempDir::new("smtp_rcpt_domains_test", true);
+    let mut config = Config::new(tmp_dir.update_config(CONFIG)).unwrap();
+    let stores = Stores::parse_all(&mut config).await;
+    let core = Core::parse(&mut config, stores, Default::default()).await;
+    let mut session = Session::test(build_smtp(core, Inner::default()));
+    session.data.remote_ip_str = "10.0.0.4".to_string();
+    session.data.remote_ip = session.data.remote_ip_str.parse().unwrap();
+    session.eval_session_params().await;
+    session
+        .cmd("EHLO mx1.foobar.org", "250")
+        .await
+        .assert_contains("LIMITS RCPTMAX=5 MAILMAX=10 RCPTDOMAINMAX=2");
+
+    // Maximum number of recipient domains
+    session.mail_from("john@example.net", "250").await;
+    session.rcpt_to("jane@foobar.org", "250").await;
+    session.rcpt_to("bill@example.org", "250").await;
+    session.rcpt_to("mike@foobar.org", "250").await;
+    session.rcpt_to("bill@example.com", "452 4.5.3").await;
+    session.rcpt_to("jane@example.org", "250").await;
+}
+
+#[tokio::test]
+async fn rcpt_role_addresses() {
+    // Enable logging
+    crate::enable_logging();
+
+    let tmp_dir = TempDir::new("smtp_rcpt_role_test", true);
+    let mut config = Config::new(tmp_dir.update_config(CONFIG)).unwrap();
+    let stores = Stores::parse_all(&mut config).await;
+    let core = Core::parse(&mut config, stores, Default::default()).await;
+    let mut session = Session::test(build_smtp(core, Inner::default()));
+    session.ehlo("mx1.foobar.org").await;
+
+    // Role addresses are routed to the configured accounts
+    session.data.remote_ip_str = "10.0.0.2".to_string();
+    session.eval_session_params().await;
+    session.mail_from("john@example.net", "250").await;
+    session.rcpt_to("postmaster@foobar.org", "250").await;
+    session.rcpt_to("Abuse@foobar.org", "250").await;
+    session.rcpt_to("webmaster@foobar.org", "550 5.1.2").await;
+    assert_eq!(
+        session
+            .core
+            .core
+            .email_to_ids(
+                session.core.core.get_directory("local").unwrap(),
+                "abuse@foobar.org",
+                0
+            )
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
 }
@@ -18,10 +18,10 @@ use utils::config::Config;
 use crate::smtp::{
     build_smtp,
     inbound::TestMessage,
-    session::{TestSession, VerifyResponse},
+    session::{load_test_message, TestSession, VerifyResponse},
     TempDir, TestSMTP,
 };
-use smtp::core::{Inner, Session};
+use smtp::core::{Inner, Session, SessionAddress};
 
 pub const SIGNATURES: &str = "
 [signature.rsa]
@@ -94,6 +94,9 @@ description = "John Doe"
 secret = "secret"
 email = ["jdoe@example.com"]
 
+[session.connect]
+hostname = "'mx.example.com'"
+
 [session.rcpt]
 directory = "'local'"
 
@@ -207,7 +210,8 @@ async fn sign_and_seal() {
         .assert_contains("ARC-Seal: i=3; a=ed25519-sha256; s=ed; d=example.com; cv=pass;")
         .assert_contains(
             "ARC-Message-Signature: i=3; a=ed25519-sha256; s=ed; d=example.com; c=relaxed/simple;",
-        );
+        )
+        .assert_contains("arc=pass");
 
     // Test ARC sealing of a DKIM signed message
     session
@@ -221,4 +225,22 @@ async fn sign_and_seal() {
         .assert_contains(
             "ARC-Message-Signature: i=1; a=ed25519-sha256; s=ed; d=example.com; c=relaxed/simple;",
         );
+
+    // Test ARC sealing of a message forwarded by a Sieve redirect
+    Session::sieve(
+        session.core.clone(),
+        SessionAddress::new("jdoe@example.com".to_string()),
+        vec![SessionAddress::new("bill@foobar.org".to_string())],
+        load_test_message("dkim", "messages").into_bytes(),
+        0,
+    )
+    .await
+    .queue_message()
+    .await;
+    qr.expect_message()
+        .await
+        .read_lines(&qr)
+        .await
+        .assert_contains("ARC-Seal: i=1; a=ed25519-sha256; s=ed; d=example.com; cv=none;")
+        .assert_contains("ARC-Authentication-Results: i=1; mx.example.com;");
 }
@@ -46,6 +46,10 @@ max-recipients = 100
 [session.extensions]
 dsn = true
 future-release = "1h"
+
+[queue.encryption]
+enable = true
+key = "queue-secret"
 "#;
 
 const REMOTE: &str = r#"
@@ -200,6 +204,13 @@ async fn manage_queue() {
             panic!("Recipient {recipient} not found in message.");
         }
 
+        // Encrypted messages are returned decrypted
+        assert!(api
+            .get_raw(&format!("/api/store/blobs/{}", message.blob_hash))
+            .await
+            .unwrap()
+            .contains("From:"));
+
         // Validate status and datetimes
         let created = message.created.to_timestamp();
         let hold_for = (env_id.as_bytes().first().unwrap() - b'a' + 1) as i64 * 100;
@@ -277,6 +288,19 @@ async fn manage_queue() {
             format!("/api/queue/messages?after={test_search}"),
             vec!["d", "e", "f", "c"],
         ),
+        (
+            "/api/queue/messages?domain=example2.com".to_string(),
+            vec!["c"],
+        ),
+        (
+            "/api/queue/messages?status=temp_fail".to_string(),
+            vec!["f"],
+        ),
+        ("/api/queue/messages?min-age=3600".to_string(), vec![]),
+        (
+            "/api/queue/messages?max-age=3600".to_string(),
+            vec!["a", "b", "c", "d", "e", "f"],
+        ),
     ] {
         let expected_ids = HashSet::from_iter(expected_ids.into_iter().map(|s| s.to_string()));
         let ids = api
@@ -291,6 +315,57 @@ async fn manage_queue() {
         assert_eq!(ids, expected_ids, "failed for {query}");
     }
 
+    // Hold and release messages
+    assert!(api
+        .request::<bool>(
+            Method::PATCH,
+            &format!(
+                "/api/queue/messages/{}?action=hold",
+                id_map.get("b").unwrap()
+            )
+        )
+        .await
+        .unwrap()
+        .unwrap_data());
+    assert!(
+        api.get_messages(&[*id_map.get("b").unwrap()])
+            .await
+            .pop()
+            .unwrap()
+            .unwrap()
+            .held
+    );
+    assert_eq!(
+        api.request::<List<QueueId>>(Method::GET, "/api/queue/messages?status=held")
+            .await
+            .unwrap()
+            .unwrap_data()
+            .items,
+        vec![*id_map.get("b").unwrap()]
+    );
+    assert_eq!(
+        api.request::<usize>(
+            Method::PATCH,
+            "/api/queue/messages?action=release&status=held"
+        )
+        .await
+        .unwrap()
+        .unwrap_data(),
+        1
+    );
+    assert!(
+        !api.get_messages(&[*id_map.get("b").unwrap()])
+            .await
+            .pop()
+            .unwrap()
+            .unwrap()
+            .held
+    );
+    api.request::<usize>(Method::DELETE, "/api/queue/messages")
+        .await
+        .unwrap()
+        .expect_request_error("At least one filter");
+
     // Retry delivery
     for id in [id_map.get("e").unwrap(), id_map.get("f").unwrap()] {
         assert!(api
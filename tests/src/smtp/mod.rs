@@ -12,6 +12,7 @@ use smtp::core::{Inner, SMTP};
 use store::{BlobStore, Store};
 use tokio::sync::mpsc;
 
+pub mod client;
 pub mod config;
 pub mod inbound;
 pub mod lookup;
@@ -258,6 +258,7 @@ async fn dane_test() {
         cache: DnsRecordCache {
             tlsa: LruCache::with_capacity(10),
             mta_sts: LruCache::with_capacity(10),
+            bimi: LruCache::with_capacity(10),
         },
     };
     let r = SMTP {
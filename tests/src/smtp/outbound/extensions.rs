@@ -6,9 +6,13 @@
 
 use std::time::{Duration, Instant};
 
-use common::config::server::ServerProtocol;
+use common::{config::server::ServerProtocol, expr::if_block::IfBlock};
 use mail_auth::MX;
-use smtp_proto::{MAIL_REQUIRETLS, MAIL_RET_HDRS, MAIL_SMTPUTF8, RCPT_NOTIFY_NEVER};
+use smtp_proto::{
+    MAIL_BY_RETURN, MAIL_REQUIRETLS, MAIL_RET_HDRS, MAIL_SMTPUTF8, RCPT_NOTIFY_NEVER,
+};
+
+use smtp::queue::MAIL_TLS_OPTIONAL;
 
 use crate::smtp::{
     inbound::{TestMessage, TestQueueEvent},
@@ -22,6 +26,7 @@ relay = true
 
 [session.extensions]
 dsn = true
+deliver-by = "1h"
 "#;
 
 const REMOTE: &str = r#"
@@ -37,6 +42,7 @@ size = 1500
 [session.extensions]
 dsn = true
 requiretls = true
+deliver-by = "1h"
 
 [session.data.add-headers]
 received = true
@@ -158,4 +164,85 @@ async fn extensions() {
     assert!((message.flags & MAIL_REQUIRETLS) != 0);
     assert!((message.flags & MAIL_SMTPUTF8) != 0);
     assert!((message.recipients.last().unwrap().flags & RCPT_NOTIFY_NEVER) != 0);
+
+    // Test DELIVERBY extension
+    session
+        .send_message(
+            "<john@test.org> BY=3600;R",
+            &["<bill@foobar.org>"],
+            "test:no_dkim",
+            "250",
+        )
+        .await;
+    local
+        .qr
+        .expect_message_then_deliver()
+        .await
+        .try_deliver(core.clone())
+        .await;
+    local.qr.read_event().await.assert_reload();
+    let message = remote.qr.expect_message().await;
+    assert!((message.flags & MAIL_BY_RETURN) != 0);
+    assert!(message
+        .domains
+        .iter()
+        .all(|d| d.expires <= store::write::now() + 3600));
+
+    // Messages with a "TLS-Required: No" header ignore the recipient domain's TLS policies
+    session
+        .send_message(
+            "john@test.org",
+            &["bill@foobar.org"],
+            concat!(
+                "From: john@test.org\r\n",
+                "To: bill@foobar.org\r\n",
+                "TLS-Required: No\r\n",
+                "Subject: TLS optional\r\n",
+                "\r\n",
+                "test"
+            ),
+            "250",
+        )
+        .await;
+    let message = local.qr.expect_message().await;
+    assert!((message.flags & MAIL_TLS_OPTIONAL) != 0);
+    local
+        .qr
+        .delivery_attempt(message.queue_id)
+        .await
+        .try_deliver(core.clone())
+        .await;
+    local.qr.read_event().await.assert_reload();
+    remote.qr.expect_message().await;
+
+    // REQUIRETLS messages are not delivered to hosts that do not support it
+    let mut remote_core = remote.instance.core.load().as_ref().clone();
+    remote_core.smtp.session.extensions.requiretls =
+        IfBlock::new::<()>("session.extensions.requiretls", [], "false");
+    remote.instance.core.store(remote_core.into());
+    session
+        .send_message(
+            "<john@test.org> REQUIRETLS",
+            &["<bill@foobar.org>"],
+            "test:no_dkim",
+            "250",
+        )
+        .await;
+    local
+        .qr
+        .expect_message_then_deliver()
+        .await
+        .try_deliver(core.clone())
+        .await;
+    local
+        .qr
+        .expect_message()
+        .await
+        .read_lines(&local.qr)
+        .await
+        .assert_contains("REQUIRETLS failed for 'mx.foobar.org'")
+        .assert_contains("Action: failed")
+        .assert_contains("Status: 5.7.30");
+    local.qr.read_event().await.assert_reload();
+    remote.qr.assert_no_events();
 }
@@ -9,13 +9,20 @@ use std::{
     time::{Duration, Instant},
 };
 
-use common::config::{server::ServerProtocol, smtp::resolver::Policy};
+use common::{
+    config::{
+        server::ServerProtocol,
+        smtp::resolver::{Mode, Policy},
+    },
+    Core,
+};
 use mail_auth::{
     common::parse::TxtRecordParser,
     mta_sts::{MtaSts, ReportUri, TlsRpt},
     report::tlsrpt::ResultType,
     MX,
 };
+use utils::config::Config;
 
 use crate::smtp::{
     inbound::{TestMessage, TestQueueEvent, TestReportingEvent},
@@ -246,3 +253,42 @@ async fn mta_sts_verify() {
     );
     assert!(report.failure.is_none());
 }
+
+#[tokio::test]
+async fn mta_sts_policy_hosting() {
+    let mut config = Config::new(
+        r#"
+[session.mta-sts]
+mode = "testing"
+max-age = "1d"
+mx = ["mx1.example.net", "*.example.net"]
+
+[session.mta-sts.domain."example.org"]
+mode = "enforce"
+mx = ["mx.example.org"]
+id = "20240101000000"
+"#,
+    )
+    .unwrap();
+    let core = Core::parse(&mut config, Default::default(), Default::default()).await;
+
+    // Domain policy with an explicit id
+    let policy = core.build_mta_sts_policy("example.org").unwrap();
+    assert_eq!(policy.mode, Mode::Enforce);
+    assert_eq!(policy.id, "20240101000000");
+    assert_eq!(
+        policy.to_string(),
+        "version: STSv1\r\nmode: enforce\r\nmax_age: 604800\r\nmx: mx.example.org\r\n"
+    );
+
+    // Default policy with an id generated from its contents
+    let policy = core.build_mta_sts_policy("example.net").unwrap();
+    assert_eq!(policy.mode, Mode::Testing);
+    assert_eq!(policy.max_age, 86400);
+    assert!(!policy.id.is_empty());
+    assert!(policy.id.chars().all(|ch| ch.is_ascii_digit()));
+    assert_eq!(
+        policy.to_string(),
+        "version: STSv1\r\nmode: testing\r\nmax_age: 86400\r\nmx: mx1.example.net\r\nmx: *.example.net\r\n"
+    );
+}
@@ -10,13 +10,16 @@ use std::{
 };
 
 use mail_auth::MX;
+use smtp_proto::Response;
 use store::write::now;
 
 use crate::smtp::{
     inbound::TestQueueEvent, outbound::TestServer, queue::manager::new_message,
     session::TestSession,
 };
-use smtp::queue::{Domain, Message, QueueEnvelope, Schedule, Status};
+use smtp::queue::{
+    throttle, Domain, Error, ErrorDetails, HostResponse, Message, QueueEnvelope, Schedule, Status,
+};
 
 const CONFIG: &str = r#"
 [session.rcpt]
@@ -299,6 +302,100 @@ async fn throttle_outbound() {
     assert!(due > 0, "Due: {}", due);
 }
 
+const CONFIG_ADAPTIVE: &str = r#"
+[queue.outbound.adaptive-throttle]
+enable = true
+codes = [421]
+min-concurrency = 1
+max-concurrency = 4
+max-interval = "1m"
+recovery = "0s"
+"#;
+
+#[tokio::test]
+async fn throttle_outbound_adaptive() {
+    // Enable logging
+    crate::enable_logging();
+
+    let local = TestServer::new("smtp_throttle_adaptive", CONFIG_ADAPTIVE, true).await;
+    let core = local.build_smtp();
+    let host = "mx.test.org";
+    let throttled = Status::TemporaryFailure(Error::UnexpectedResponse(HostResponse {
+        hostname: ErrorDetails {
+            entity: host.to_string(),
+            details: "MAIL FROM:<john@foobar.org>".to_string(),
+        },
+        response: Response {
+            code: 421,
+            esc: [4, 7, 0],
+            message: "Too many connections".to_string(),
+        },
+    }));
+
+    // Hosts without throttling signals are not limited
+    let mut in_flight = vec![];
+    core.is_allowed_adaptive(host, &mut in_flight, 0).unwrap();
+    assert!(in_flight.is_empty());
+
+    // Other temporary failures do not reduce the sending rate
+    let mut deferred = throttled.clone();
+    if let Status::TemporaryFailure(Error::UnexpectedResponse(response)) = &mut deferred {
+        response.response.code = 452;
+    }
+    core.adaptive_throttle_update(host, &deferred, std::iter::empty(), 0);
+    assert!(core.inner.queue_adaptive.is_empty());
+
+    // A throttling response halves concurrency and delays the next connection
+    core.adaptive_throttle_update(host, &throttled, std::iter::empty(), 0);
+    assert_eq!(
+        core.inner
+            .queue_adaptive
+            .get(host)
+            .unwrap()
+            .limiter
+            .max_concurrent,
+        2
+    );
+    assert!(matches!(
+        core.is_allowed_adaptive(host, &mut in_flight, 0),
+        Err(throttle::Error::Rate { .. })
+    ));
+
+    // Concurrency is limited once the interval has elapsed
+    for _ in 0..2 {
+        core.inner
+            .queue_adaptive
+            .get_mut(host)
+            .unwrap()
+            .next_attempt = 0;
+        core.is_allowed_adaptive(host, &mut in_flight, 0).unwrap();
+    }
+    core.inner
+        .queue_adaptive
+        .get_mut(host)
+        .unwrap()
+        .next_attempt = 0;
+    assert!(matches!(
+        core.is_allowed_adaptive(host, &mut in_flight, 0),
+        Err(throttle::Error::Concurrency { .. })
+    ));
+    in_flight.clear();
+
+    // Successful deliveries slowly restore the original limits
+    core.adaptive_throttle_update(host, &Status::Completed(()), std::iter::empty(), 0);
+    assert_eq!(
+        core.inner
+            .queue_adaptive
+            .get(host)
+            .unwrap()
+            .limiter
+            .max_concurrent,
+        3
+    );
+    core.adaptive_throttle_update(host, &Status::Completed(()), std::iter::empty(), 0);
+    assert!(core.inner.queue_adaptive.is_empty());
+}
+
 pub trait TestQueueEnvelope<'x> {
     fn test(message: &'x Message, current_domain: usize, mx: &'x str) -> Self;
 }
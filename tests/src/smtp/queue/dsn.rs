@@ -4,13 +4,25 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{fs, path::PathBuf, time::SystemTime};
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
 
-use smtp_proto::{Response, RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE, RCPT_NOTIFY_SUCCESS};
+use smtp_proto::{
+    Response, MAIL_BY_NOTIFY, MAIL_BY_RETURN, RCPT_NOTIFY_DELAY, RCPT_NOTIFY_FAILURE,
+    RCPT_NOTIFY_SUCCESS,
+};
 use store::write::now;
 use utils::BlobHash;
 
-use crate::smtp::{inbound::sign::SIGNATURES, outbound::TestServer, QueueReceiver};
+use crate::smtp::{
+    inbound::{sign::SIGNATURES, TestMessage},
+    outbound::TestServer,
+    session::VerifyResponse,
+    QueueReceiver,
+};
 use smtp::queue::{
     Domain, Error, ErrorDetails, HostResponse, Message, Recipient, Schedule, Status,
 };
@@ -29,6 +41,17 @@ relay = true
 from-name = "'Mail Delivery Subsystem'"
 from-address = "'MAILER-DAEMON@example.org'"
 sign = "['rsa']"
+language = [{if = "sender_domain = 'foobar.es'", then = "'es'"},
+            {else = "'en'"}]
+support-contact = [{if = "sender_domain = 'foobar.es'", then = "'postmaster@example.org'"},
+                   {else = false}]
+template-set = [{if = "sender_domain = 'foobar.net'", then = "'acme'"},
+                {else = false}]
+
+[report.dsn.templates.acme.en]
+subject.delay = "Acme Mail: Your message is delayed"
+recipient.delay = "{recipient}: {reason}; next attempt on {next_retry}, giving up on {retry_until}"
+
 
 "#;
 
@@ -155,9 +178,128 @@ async fn generate_dsn() {
     let dsn_message = qr.expect_message().await;
     qr.compare_dsn(dsn_message, "mixed.eml").await;
 
+    // Localized DSN with support contact
+    message.return_path = "sender@foobar.es".to_string();
+    message.return_path_domain = "foobar.es".to_string();
+    message.domains[0].notify.due = now();
+    core.send_dsn(&mut message).await;
+    qr.expect_message()
+        .await
+        .read_lines(qr)
+        .await
+        .assert_contains("Subject: Aviso: Retraso en la entrega del mensaje")
+        .assert_contains("Se produjo un problema temporal al entregar su mensaje")
+        .assert_contains("postmaster@example.org")
+        .assert_contains("Diagnostic-Code: X-Local;connection to 'mx.domain.org' failed");
+
+    // Custom DSN templates for the sender's domain
+    message.return_path = "sender@foobar.net".to_string();
+    message.return_path_domain = "foobar.net".to_string();
+    message.domains[0].notify.due = now();
+    core.send_dsn(&mut message).await;
+    qr.expect_message()
+        .await
+        .read_lines(qr)
+        .await
+        .assert_contains("Subject: Acme Mail: Your message is delayed")
+        .assert_contains("There was a temporary problem delivering your message")
+        .assert_contains("john.doe@example.org: connection to 'mx.domain.org' failed: Connection timeout; next attempt on ")
+        .assert_contains(", giving up on ");
+
     // Load queue
     let queue = qr.read_queued_messages().await;
-    assert_eq!(queue.len(), 4);
+    assert_eq!(queue.len(), 6);
+}
+
+#[tokio::test]
+async fn deliver_by_dsn() {
+    // Enable logging
+    crate::enable_logging();
+
+    let dsn_original = "From: sender@foobar.org\r\nSubject: Test\r\n\r\nTest message\r\n";
+    let mut message = Message {
+        size: dsn_original.len(),
+        queue_id: 0,
+        span_id: 0,
+        created: now(),
+        return_path: "sender@foobar.org".to_string(),
+        return_path_lcase: "sender@foobar.org".to_string(),
+        return_path_domain: "foobar.org".to_string(),
+        recipients: vec![Recipient {
+            domain_idx: 0,
+            address: "foobar@example.org".to_string(),
+            address_lcase: "foobar@example.org".to_string(),
+            status: Status::Scheduled,
+            flags: RCPT_NOTIFY_FAILURE | RCPT_NOTIFY_DELAY,
+            orcpt: None,
+        }],
+        domains: vec![Domain {
+            domain: "example.org".to_string(),
+            retry: Schedule::later(Duration::from_secs(3600)),
+            notify: Schedule::now(),
+            expires: now() + 600,
+            status: Status::Scheduled,
+        }],
+        flags: MAIL_BY_NOTIFY,
+        env_id: None,
+        priority: 0,
+        blob_hash: BlobHash::from(dsn_original.as_bytes()),
+        quota_keys: vec![],
+    };
+
+    let mut local = TestServer::new(
+        "smtp_deliver_by_dsn_test",
+        CONFIG.to_string() + SIGNATURES,
+        true,
+    )
+    .await;
+    let core = local.build_smtp();
+    let qr = &mut local.qr;
+    qr.blob_store
+        .put_blob(message.blob_hash.as_slice(), dsn_original.as_bytes())
+        .await
+        .unwrap();
+
+    // BY=N, a delay DSN is sent when the deadline passes without a delivery attempt
+    assert!(message.has_pending_delivery());
+    assert_eq!(
+        message.domains[0].status,
+        Status::TemporaryFailure(Error::DeliveryTimeExpired)
+    );
+    core.send_dsn(&mut message).await;
+    qr.expect_message()
+        .await
+        .read_lines(qr)
+        .await
+        .assert_contains("Action: delayed")
+        .assert_contains("Status: 4.4.7")
+        .assert_contains("Diagnostic-Code: X-Local;delivery time expired");
+
+    // BY=R, retries are scheduled before the deadline
+    message.flags = MAIL_BY_RETURN;
+    message.domains[0].retry_before_deadline();
+    assert!([299, 300].contains(&(message.domains[0].retry.due - now())));
+    message.domains[0].expires = now() + 40;
+    message.domains[0].retry = Schedule::later(Duration::from_secs(3600));
+    message.domains[0].retry_before_deadline();
+    assert!(message.domains[0].retry.due > message.domains[0].expires);
+
+    // BY=R, a failure DSN is sent when the deadline passes
+    message.domains[0].expires = now() - 1;
+    message.domains[0].status = Status::TemporaryFailure(Error::ConcurrencyLimited);
+    assert!(!message.has_pending_delivery());
+    assert_eq!(
+        message.domains[0].status,
+        Status::PermanentFailure(Error::DeliveryTimeExpired)
+    );
+    core.send_dsn(&mut message).await;
+    qr.expect_message()
+        .await
+        .read_lines(qr)
+        .await
+        .assert_contains("Action: failed")
+        .assert_contains("Status: 5.4.7")
+        .assert_contains("Diagnostic-Code: X-Local;delivery time expired");
 }
 
 impl QueueReceiver {
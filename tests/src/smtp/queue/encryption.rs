@@ -0,0 +1,125 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::config::smtp::queue::QueueEncryption;
+use utils::config::Config;
+
+use crate::smtp::{
+    inbound::TestQueueEvent,
+    outbound::TestServer,
+    session::{TestSession, VerifyResponse},
+};
+
+const CONFIG: &str = r#"
+[session.ehlo]
+reject-non-fqdn = false
+
+[session.rcpt]
+relay = true
+
+[queue.encryption]
+enable = true
+key = "rotated-secret"
+previous-keys = ["original-secret"]
+"#;
+
+const MESSAGE: &str = concat!(
+    "From: john@test.org\r\n",
+    "To: bill@foobar.org\r\n",
+    "Subject: Secret plans\r\n",
+    "\r\n",
+    "Meet me at midnight."
+);
+
+#[tokio::test]
+async fn queue_encryption() {
+    // Enable logging
+    crate::enable_logging();
+
+    let mut local = TestServer::new("smtp_queue_encryption_test", CONFIG, true).await;
+    let core = local.build_smtp();
+    let mut session = local.new_session();
+    let qr = &mut local.qr;
+
+    session.data.remote_ip_str = "10.0.0.1".to_string();
+    session.eval_session_params().await;
+    session.ehlo("mx.test.org").await;
+    session
+        .send_message("john@test.org", &["bill@foobar.org"], MESSAGE, "250")
+        .await;
+
+    // The spooled message should not be readable from the blob store
+    let message = qr.expect_message().await;
+    let raw_message = qr
+        .blob_store
+        .get_blob(message.blob_hash.as_slice(), 0..usize::MAX)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(QueueEncryption::is_encrypted(&raw_message));
+    assert!(!String::from_utf8_lossy(&raw_message).contains("Secret plans"));
+
+    // Decryption is transparent
+    let decrypted = core
+        .core
+        .get_queued_message(message.blob_hash.as_slice(), 0..usize::MAX)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(String::from_utf8(decrypted)
+        .unwrap()
+        .contains("Subject: Secret plans"));
+
+    // The DSN should include the decrypted original headers
+    qr.delivery_attempt(message.queue_id)
+        .await
+        .try_deliver(core.clone())
+        .await;
+    let dsn = qr.expect_message().await;
+    let decrypted_dsn = String::from_utf8(
+        core.core
+            .get_queued_message(dsn.blob_hash.as_slice(), 0..usize::MAX)
+            .await
+            .unwrap()
+            .unwrap(),
+    )
+    .unwrap();
+    decrypted_dsn
+        .lines()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .assert_contains("Action: failed")
+        .assert_contains("Subject: Secret plans");
+    qr.read_event().await.assert_reload();
+    qr.clear_queue(&core).await;
+
+    // Messages encrypted with a previous key can be decrypted after rotation
+    let encryption_with_key = |key: &str, previous: &str| {
+        QueueEncryption::parse(
+            &mut Config::new(format!(
+                "[queue.encryption]\nenable = true\nkey = \"{key}\"\nprevious-keys = [{previous}]\n"
+            ))
+            .unwrap(),
+        )
+    };
+    let original = encryption_with_key("original-secret", "");
+    let encrypted = original.encrypt(MESSAGE.as_bytes()).unwrap();
+    assert_eq!(
+        encryption_with_key("rotated-secret", "\"original-secret\"")
+            .decrypt(encrypted.clone())
+            .unwrap(),
+        MESSAGE.as_bytes()
+    );
+    assert!(encryption_with_key("rotated-secret", "")
+        .decrypt(encrypted)
+        .is_err());
+
+    // Messages spooled before encryption was enabled are returned as-is
+    assert_eq!(
+        original.decrypt(MESSAGE.as_bytes().to_vec()).unwrap(),
+        MESSAGE.as_bytes()
+    );
+}
@@ -4,14 +4,15 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use mail_auth::hickory_resolver::proto::op::ResponseCode;
+use common::config::{server::ServerProtocol, smtp::queue::PriorityClass};
+use mail_auth::{hickory_resolver::proto::op::ResponseCode, MX};
 
-use smtp::queue::{Domain, Message, Schedule, Status};
+use smtp::queue::{manager::SpawnQueue, Domain, Message, Schedule, Status};
 use store::write::now;
 
-use crate::smtp::outbound::TestServer;
+use crate::smtp::{outbound::TestServer, session::TestSession};
 
 const CONFIG: &str = r#"
 [session.ehlo]
@@ -65,6 +66,133 @@ async fn queue_due() {
     qr.assert_queue_is_empty().await;
 }
 
+#[tokio::test]
+async fn queue_priority() {
+    // Enable logging
+    crate::enable_logging();
+
+    let local = TestServer::new("smtp_queue_priority_test", CONFIG, true).await;
+    let core = local.build_smtp();
+
+    for (queue_id, priority) in [(0, -4), (1, 0), (2, 5)] {
+        let mut message = new_message(queue_id);
+        message.priority = priority;
+        message.domains.push(domain("a", 0, 4, 5));
+        let due = message.next_delivery_event();
+        message.save_changes(&core, 0.into(), due.into()).await;
+    }
+
+    // Priorities are stored with the queue events
+    let priority = &core.core.smtp.queue.priority;
+    let mut events = core.next_event().await;
+    events.sort_by_key(|event| event.queue_id);
+    assert_eq!(
+        events
+            .iter()
+            .map(|event| (event.priority, priority.class(event.priority)))
+            .collect::<Vec<_>>(),
+        vec![
+            (-4, PriorityClass::Bulk),
+            (0, PriorityClass::Normal),
+            (5, PriorityClass::High)
+        ]
+    );
+
+    // Locked events are skipped until they expire
+    let event = events.pop().unwrap();
+    let queue_id = event.queue_id;
+    assert!(core.try_lock_event(event).await.is_some());
+    assert!(!core
+        .next_event()
+        .await
+        .iter()
+        .any(|event| event.queue_id == queue_id));
+
+    for queue_id in 0..3 {
+        let message = core.read_message(queue_id).await.unwrap();
+        let due = message.next_delivery_event();
+        message.remove(&core, due).await;
+    }
+    local.qr.assert_queue_is_empty().await;
+}
+
+const CONFIG_HOLD: &str = r#"
+[session.ehlo]
+reject-non-fqdn = false
+
+[session.rcpt]
+relay = true
+
+[session.extensions]
+future-release = "7d"
+
+[queue.schedule]
+retry = "1h"
+notify = "1d"
+expire = "5d"
+"#;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn queue_wake_up() {
+    // Enable logging
+    crate::enable_logging();
+
+    // Start remote test server
+    let mut remote = TestServer::new("smtp_queue_wake_up_remote", CONFIG, true).await;
+    let _rx = remote.start(&[ServerProtocol::Smtp]).await;
+    let remote_core = remote.build_smtp();
+
+    // Add mock DNS entries
+    let local = TestServer::new("smtp_queue_wake_up_local", CONFIG_HOLD, true).await;
+    let core = local.build_smtp();
+    core.core.smtp.resolvers.dns.mx_add(
+        "foobar.org",
+        vec![MX {
+            exchanges: vec!["mx1.foobar.org".to_string()],
+            preference: 10,
+        }],
+        Instant::now() + Duration::from_secs(10),
+    );
+    core.core.smtp.resolvers.dns.ipv4_add(
+        "mx1.foobar.org",
+        vec!["127.0.0.1".parse().unwrap()],
+        Instant::now() + Duration::from_secs(10),
+    );
+
+    // Hold a message for a day and let the queue manager schedule it
+    let mut session = local.new_session();
+    local.qr.queue_rx.spawn(local.instance.clone());
+    session.data.remote_ip_str = "10.0.0.1".to_string();
+    session.eval_session_params().await;
+    session.ehlo("mx.test.org").await;
+    session
+        .send_message(
+            "<john@test.org> HOLDFOR=86400",
+            &["bill@foobar.org"],
+            "test:no_dkim",
+            "250",
+        )
+        .await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    remote.qr.assert_no_events();
+
+    // Advancing the clock wakes up the queue manager
+    utils::clock::advance(Duration::from_secs(86400));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(
+        remote
+            .qr
+            .consume_message(&remote_core)
+            .await
+            .recipients
+            .into_iter()
+            .map(|r| r.address)
+            .collect::<Vec<_>>(),
+        vec!["bill@foobar.org".to_string()]
+    );
+}
+
 #[test]
 fn delivery_events() {
     let mut message = new_message(0);
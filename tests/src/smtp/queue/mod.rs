@@ -6,5 +6,6 @@
 
 pub mod concurrent;
 pub mod dsn;
+pub mod encryption;
 pub mod manager;
 pub mod retry;
@@ -34,6 +34,7 @@ expire = [{if = "sender_domain = 'test.org'", then = "6s"},
 "#;
 
 #[tokio::test]
+#[serial_test::serial]
 async fn queue_retry() {
     // Enable logging
     crate::enable_logging();
@@ -178,3 +179,172 @@ async fn queue_retry() {
     let schedule = qr.expect_message().await;
     assert!([3599, 3600].contains(&(schedule.domains.first().unwrap().notify.due - now())));
 }
+
+const CONFIG_CLOCK: &str = r#"
+[session.ehlo]
+reject-non-fqdn = false
+
+[session.rcpt]
+relay = true
+
+[queue.schedule]
+retry = "[1h, 2h, 3h]"
+notify = "[1h, 2h]"
+expire = "6h"
+"#;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn queue_retry_clock() {
+    // Enable logging
+    crate::enable_logging();
+
+    let mut local = TestServer::new("smtp_queue_retry_clock_test", CONFIG_CLOCK, true).await;
+    let core = local.build_smtp();
+    let mut session = local.new_session();
+    let qr = &mut local.qr;
+
+    session.data.remote_ip_str = "10.0.0.1".to_string();
+    session.eval_session_params().await;
+    session.ehlo("mx.test.org").await;
+    session
+        .send_message(
+            "john@test.org",
+            &["jane@_dns_error.org"],
+            "test:no_dkim",
+            "250",
+        )
+        .await;
+    let attempt = qr.expect_message_then_deliver().await;
+    let mut dsn = Vec::new();
+    let mut retries = Vec::new();
+    attempt.try_deliver(core.clone()).await;
+
+    // Advance the clock to each due event instead of waiting for it
+    loop {
+        match qr.try_read_event().await {
+            Some(Event::Reload) => {}
+            Some(Event::OnHold(_)) => unreachable!(),
+            None | Some(Event::Stop) => break,
+        }
+
+        let now = now();
+        let events = core.next_event().await;
+        if events.is_empty() {
+            break;
+        }
+        for event in events {
+            if event.due > now {
+                utils::clock::advance(Duration::from_secs(event.due - now));
+            }
+
+            let message = core.read_message(event.queue_id).await.unwrap();
+            if message.return_path.is_empty() {
+                message.clone().remove(&core, event.due).await;
+                dsn.push(message);
+            } else {
+                retries.push(event.due - now);
+                DeliveryAttempt::new(event).try_deliver(core.clone()).await;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+    qr.assert_queue_is_empty().await;
+    assert_eq!(retries, vec![3600, 7200, 10800]);
+    assert_eq!(dsn.len(), 3);
+    let mut dsn = dsn.into_iter();
+    for action in ["delayed", "delayed", "failed"] {
+        dsn.next()
+            .unwrap()
+            .read_lines(qr)
+            .await
+            .assert_contains("Final-Recipient: rfc822;jane@_dns_error.org")
+            .assert_contains(&format!("Action: {action}"));
+    }
+}
+
+const CONFIG_CLASS: &str = r#"
+[session.rcpt]
+relay = true
+
+[queue.schedule]
+retry = [{if = "message_class = 'list'", then = "[1h, 4h]"},
+         {else = "[1s, 2s]"}]
+notify = [{if = "rcpt_domain = 'internal.org'", then = "[10m]"},
+          {if = "message_class = 'list'", then = "[2d]"},
+          {else = "[1d]"}]
+expire = [{if = "rcpt_domain = 'internal.org'", then = "1h"},
+          {if = "message_class = 'list' || message_class = 'bulk'", then = "10d"},
+          {else = '5d'}]
+"#;
+
+#[tokio::test]
+async fn queue_schedule_class() {
+    // Enable logging
+    crate::enable_logging();
+
+    let mut local = TestServer::new("smtp_queue_schedule_class", CONFIG_CLASS, true).await;
+    let core = local.build_smtp();
+    let mut session = local.new_session();
+    let qr = &mut local.qr;
+
+    session.data.remote_ip_str = "10.0.0.1".to_string();
+    session.eval_session_params().await;
+    session.ehlo("mx.test.org").await;
+
+    // Schedules are selected by recipient domain and message class
+    session
+        .send_message(
+            "john@test.org",
+            &["bill@internal.org", "jane@foobar.org"],
+            concat!(
+                "From: john@test.org\r\n",
+                "To: bill@internal.org, jane@foobar.org\r\n",
+                "Subject: Weekly newsletter\r\n",
+                "List-Unsubscribe: <mailto:unsubscribe@test.org>\r\n",
+                "\r\n",
+                "Hello\r\n"
+            ),
+            "250",
+        )
+        .await;
+    let message = qr.expect_message().await;
+    let now = now();
+    assert_eq!(message.class(), "list");
+    for (domain, notify, expires) in [("internal.org", 600, 3600), ("foobar.org", 172800, 864000)] {
+        let domain = message.domains.iter().find(|d| d.domain == domain).unwrap();
+        assert!([notify - 1, notify].contains(&(domain.notify.due - now)));
+        assert!([expires - 1, expires].contains(&(domain.expires - now)));
+    }
+    qr.clear_queue(&core).await;
+
+    // Bulk messages
+    session
+        .send_message(
+            "john@test.org",
+            &["jane@foobar.org"],
+            concat!(
+                "From: john@test.org\r\n",
+                "To: jane@foobar.org\r\n",
+                "Subject: Special offer\r\n",
+                "Precedence: bulk\r\n",
+                "\r\n",
+                "Hello\r\n"
+            ),
+            "250",
+        )
+        .await;
+    let message = qr.expect_message().await;
+    assert_eq!(message.class(), "bulk");
+    assert!([863999, 864000].contains(&(message.domains[0].expires - now())));
+    qr.clear_queue(&core).await;
+
+    // Regular messages use the default schedule
+    session
+        .send_message("john@test.org", &["jane@foobar.org"], "test:no_dkim", "250")
+        .await;
+    let message = qr.expect_message().await;
+    assert_eq!(message.class(), "normal");
+    assert!([431999, 432000].contains(&(message.domains[0].expires - now())));
+    assert!([86399, 86400].contains(&(message.domains[0].notify.due - now())));
+}
@@ -8,9 +8,12 @@ use std::time::Duration;
 
 use crate::smtp::{inbound::TestQueueEvent, outbound::TestServer, session::TestSession};
 
+use jmap::api::management::report::{DmarcStats, TlsStats};
+use mail_auth::report::tlsrpt::TlsReport;
+use smtp::reporting::analysis::IncomingReport;
 use store::{
-    write::{ReportClass, ValueClass},
-    IterateParams, ValueKey,
+    write::{Bincode, ReportClass, ValueClass},
+    Deserialize, IterateParams, ValueKey,
 };
 
 const CONFIG: &str = r#"
@@ -36,6 +39,7 @@ async fn report_analyze() {
 
     // Create test message
     let mut session = local.new_session();
+    let smtp = local.build_smtp();
     let qr = &mut local.qr;
     session.data.remote_ip_str = "10.0.0.1".to_string();
     session.eval_session_params().await;
@@ -90,8 +94,66 @@ async fn report_analyze() {
         .unwrap();
     assert_eq!(total_reports, total_reports_received);
 
-    // Wait one second, purge, and make sure they are gone
-    tokio::time::sleep(Duration::from_secs(1)).await;
+    // Submit a TLS report over HTTPS
+    smtp.ingest_tls_report(TLS_HTTP_REPORT.as_bytes(), 0)
+        .await
+        .unwrap();
+    assert!(smtp.ingest_tls_report(b"not a report", 0).await.is_err());
+
+    // Aggregate statistics by source and policy domain
+    let mut dmarc_stats = DmarcStats::default();
+    let mut tls_stats = TlsStats::default();
+    qr.store
+        .iterate(
+            IterateParams::new(
+                ValueKey::from(ValueClass::Report(ReportClass::Tls { id: 0, expires: 0 })),
+                ValueKey::from(ValueClass::Report(ReportClass::Dmarc {
+                    id: u64::MAX,
+                    expires: u64::MAX,
+                })),
+            ),
+            |key, value| {
+                if key[0] == 0 {
+                    tls_stats.add_report(
+                        &Bincode::<IncomingReport<TlsReport>>::deserialize(value)?
+                            .inner
+                            .report,
+                        None,
+                    );
+                } else {
+                    dmarc_stats.add_report(
+                        &Bincode::<IncomingReport<mail_auth::report::Report>>::deserialize(value)?
+                            .inner
+                            .report,
+                        None,
+                    );
+                }
+                Ok(true)
+            },
+        )
+        .await
+        .unwrap();
+    let dmarc_stats = dmarc_stats.finish(0);
+    let tls_stats = tls_stats.finish(0);
+    assert_eq!(dmarc_stats.reports, 5);
+    assert!(!dmarc_stats.sources.is_empty());
+    assert_eq!(
+        dmarc_stats.messages,
+        dmarc_stats.sources.iter().map(|s| s.messages).sum::<u64>()
+    );
+    assert_eq!(tls_stats.reports, 3);
+    let domain = tls_stats
+        .domains
+        .iter()
+        .find(|d| d.policy_domain == "company-z.example")
+        .unwrap();
+    assert_eq!(domain.success, 1000);
+    assert_eq!(domain.failure, 15);
+    assert_eq!(domain.failures[0].sessions, 10);
+    assert_eq!(domain.reporters, vec!["Company-X".to_string()]);
+
+    // Wait two seconds, purge, and make sure they are gone
+    tokio::time::sleep(Duration::from_secs(2)).await;
     qr.store.purge_store().await.unwrap();
     let mut total_reports = 0;
     qr.store
@@ -119,3 +181,38 @@ async fn report_analyze() {
     qr.read_event().await.assert_reload();
     qr.last_queued_message().await;
 }
+
+const TLS_HTTP_REPORT: &str = r#"{
+  "organization-name": "Company-X",
+  "date-range": {
+    "start-datetime": "2016-04-01T00:00:00Z",
+    "end-datetime": "2016-04-01T23:59:59Z"
+  },
+  "contact-info": "sts-reporting@company-x.example",
+  "report-id": "5065427c-23d3-47ca-b6e0-946ea0e8c4be",
+  "policies": [{
+    "policy": {
+      "policy-type": "sts",
+      "policy-string": ["version: STSv1", "mode: testing",
+            "mx: *.mail.company-y.example", "max_age: 86400"],
+      "policy-domain": "company-z.example",
+      "mx-host": ["*.mail.company-y.example"]
+    },
+    "summary": {
+      "total-successful-session-count": 1000,
+      "total-failure-session-count": 15
+    },
+    "failure-details": [{
+      "result-type": "certificate-expired",
+      "sending-mta-ip": "2001:db8:abcd:0012::1",
+      "receiving-mx-hostname": "mx1.mail.company-y.example",
+      "failed-session-count": 5
+    }, {
+      "result-type": "starttls-not-supported",
+      "sending-mta-ip": "2001:db8:abcd:0013::1",
+      "receiving-mx-hostname": "mx2.mail.company-y.example",
+      "receiving-ip": "203.0.113.56",
+      "failed-session-count": 10
+    }]
+  }]
+}"#;
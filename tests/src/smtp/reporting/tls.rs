@@ -222,4 +222,34 @@ async fn report_tls() {
         assert_eq!(report.policies.len(), 1);
     }
     qr.assert_report_is_empty().await;
+
+    // Reports are submitted to every rua destination
+    TLS_HTTP_REPORT.lock().clear();
+    let tls_record = Arc::new(
+        TlsRpt::parse(b"v=TLSRPTv1;rua=https://127.0.0.1/tls,mailto:reports@foobar.org").unwrap(),
+    );
+    core.schedule_tls(Box::new(TlsEvent {
+        domain: "foobar.org".to_string(),
+        policy: smtp::reporting::PolicyType::None,
+        failure: None,
+        tls_record: tls_record.clone(),
+        interval: AggregateFrequency::Daily,
+    }))
+    .await;
+    let reports = qr.read_report_events().await;
+    assert_eq!(reports.len(), 1);
+    match reports.into_iter().next().unwrap() {
+        QueueClass::TlsReportHeader(event) => {
+            core.send_tls_aggregate_report(vec![event]).await;
+        }
+        _ => unreachable!(),
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!TLS_HTTP_REPORT.lock().is_empty());
+    qr.expect_message()
+        .await
+        .read_lines(qr)
+        .await
+        .assert_contains("To: <reports@foobar.org>");
+    qr.assert_report_is_empty().await;
 }
@@ -0,0 +1,184 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use store::{
+    backend::chaos::{ChaosConfig, ChaosStats},
+    write::{assert::AssertValue, BatchBuilder, DirectoryClass, ValueClass},
+    Store, ValueKey,
+};
+
+const MAX_RETRIES: usize = 100;
+
+pub async fn test(db: Store) {
+    println!("Running Store fault injection tests...");
+
+    // The same seed must produce the same sequence of faults
+    let mut runs = Vec::new();
+    for _ in 0..2 {
+        ChaosConfig::new(1234)
+            .with_conflicts(0.3)
+            .with_errors(0.1)
+            .enable();
+        let mut results = Vec::new();
+        for n in 0..100 {
+            results.push(
+                db.write(value_batch(n))
+                    .await
+                    .map_err(|err| err.is_assertion_failure())
+                    .map(|_| ()),
+            );
+        }
+        runs.push((results, ChaosConfig::disable()));
+    }
+    assert_eq!(runs[0], runs[1]);
+    let stats = runs[0].1;
+    assert!(stats.conflicts > 0, "{stats:?}");
+    assert!(stats.errors > 0, "{stats:?}");
+
+    // Writes retried on conflicts must eventually succeed
+    ChaosConfig::new(5678)
+        .with_conflicts(0.5)
+        .with_latency(Duration::from_millis(1), 0.2)
+        .enable();
+    for n in 0..100 {
+        let mut try_count = 0;
+        loop {
+            match db.write(value_batch(n)).await {
+                Ok(_) => break,
+                Err(err) if err.is_assertion_failure() && try_count < MAX_RETRIES => {
+                    try_count += 1;
+                }
+                Err(err) => panic!("Unexpected error: {err:?}"),
+            }
+        }
+    }
+    let stats = ChaosConfig::disable();
+    assert!(stats.conflicts > 0, "{stats:?}");
+    assert!(stats.latency > 0, "{stats:?}");
+    assert_eq!(ChaosStats::current(), stats);
+
+    // Faults are no longer injected once disabled
+    for n in 0..100 {
+        assert_eq!(
+            db.get_value::<String>(ValueKey {
+                account_id: 0,
+                collection: 0,
+                document_id: 0,
+                class: ValueClass::Config(format!("chaos{n}").into_bytes()),
+            })
+            .await
+            .unwrap(),
+            Some(format!("value{n}"))
+        );
+    }
+
+    // Writes are neither lost nor applied twice when retried after failing
+    // before or after being committed
+    let used_quota = db.get_counter(quota_key()).await.unwrap();
+    ChaosConfig::new(9012)
+        .with_conflicts(0.2)
+        .with_errors(0.2)
+        .with_commit_errors(0.3)
+        .enable();
+    for n in 0..100 {
+        let mut try_count = 0;
+        loop {
+            let mut batch = BatchBuilder::new();
+            batch
+                .with_account_id(0)
+                .with_collection(0)
+                .update_document(0)
+                .assert_value(once_class(n), AssertValue::None)
+                .set(once_class(n), b"1".to_vec())
+                .add(ValueClass::Directory(DirectoryClass::UsedQuota(0)), 1);
+            assert!(try_count < MAX_RETRIES, "Too many retries");
+            try_count += 1;
+
+            match db.write(batch.build_batch()).await {
+                Ok(_) => break,
+                Err(err) if err.is_assertion_failure() => {
+                    // Either an injected conflict or the write was committed
+                    // by a previous attempt
+                    if is_written(&db, n).await {
+                        break;
+                    }
+                }
+                Err(_) => {}
+            }
+        }
+    }
+    let stats = ChaosConfig::disable();
+    assert!(stats.conflicts > 0, "{stats:?}");
+    assert!(stats.errors > 0, "{stats:?}");
+    assert!(stats.commit_errors > 0, "{stats:?}");
+    for n in 0..100 {
+        assert!(is_written(&db, n).await, "Write {n} was lost");
+    }
+    assert_eq!(
+        db.get_counter(quota_key()).await.unwrap(),
+        used_quota + 100,
+        "Writes were applied more than once"
+    );
+
+    // Clean up
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(0)
+        .with_collection(0)
+        .update_document(0)
+        .add(ValueClass::Directory(DirectoryClass::UsedQuota(0)), -100);
+    for n in 0..100 {
+        batch
+            .clear(ValueClass::Config(format!("chaos{n}").into_bytes()))
+            .clear(once_class(n));
+    }
+    db.write(batch.build_batch()).await.unwrap();
+}
+
+fn value_batch(n: usize) -> store::write::Batch {
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(0)
+        .with_collection(0)
+        .update_document(0)
+        .set(
+            ValueClass::Config(format!("chaos{n}").into_bytes()),
+            format!("value{n}").into_bytes(),
+        );
+    batch.build_batch()
+}
+
+async fn is_written(db: &Store, n: usize) -> bool {
+    for _ in 0..MAX_RETRIES {
+        if let Ok(value) = db
+            .get_value::<String>(ValueKey {
+                account_id: 0,
+                collection: 0,
+                document_id: 0,
+                class: once_class(n),
+            })
+            .await
+        {
+            return value.is_some();
+        }
+    }
+    panic!("Too many retries");
+}
+
+fn once_class<T>(n: usize) -> ValueClass<T> {
+    ValueClass::Config(format!("once{n}").into_bytes())
+}
+
+fn quota_key() -> ValueKey<ValueClass<u32>> {
+    ValueKey {
+        account_id: 0,
+        collection: 0,
+        document_id: 0,
+        class: ValueClass::Directory(DirectoryClass::UsedQuota(0)),
+    }
+}
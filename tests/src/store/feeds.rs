@@ -0,0 +1,202 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use common::config::feeds::{FeedStates, ThreatFeed};
+use http_body_util::Full;
+use hyper::{body, server::conn::http1, service::service_fn, StatusCode};
+use hyper_util::rt::TokioIo;
+use store::{parking_lot::Mutex, LookupStore, Stores};
+use tokio::net::TcpListener;
+use utils::config::Config;
+
+use crate::{store::TempDir, AssertConfig};
+
+const CONFIG: &str = r#"
+[store."sqlite"]
+type = "sqlite"
+path = "{TMP}/feeds.db"
+
+[feed."test"]
+url = "http://127.0.0.1:8824/feed"
+type = "domain"
+store = "sqlite"
+prefix = "feed:"
+expires = "6s"
+
+[feed."broken"]
+url = "http://127.0.0.1:8824/broken"
+type = "domain"
+store = "sqlite"
+prefix = "broken:"
+"#;
+
+#[derive(Default)]
+struct MockFeed {
+    state: Mutex<MockFeedState>,
+}
+
+#[derive(Default)]
+struct MockFeedState {
+    contents: String,
+    version: usize,
+    fail: bool,
+    if_none_match: Option<String>,
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn feed_tests() {
+    let temp_dir = TempDir::new("feed_tests", true);
+    let mut config = Config::new(CONFIG.replace("{TMP}", &temp_dir.path.to_string_lossy()))
+        .unwrap()
+        .assert_no_errors();
+    let stores = Stores::parse_all(&mut config).await;
+    let store = stores.lookup_stores.get("sqlite").unwrap().clone();
+    let feeds = ThreatFeed::parse_all(&mut config, &stores.lookup_stores, &store);
+    assert!(config.errors.is_empty(), "{:?}", config.errors);
+    let feed = feeds.iter().find(|feed| feed.id == "test").unwrap();
+    let broken = feeds.iter().find(|feed| feed.id == "broken").unwrap();
+    let mock = spawn_mock_feed().await;
+    let states = FeedStates::default();
+
+    println!("Running threat feed tests...");
+
+    // The first download writes every entry
+    mock.update("a.example\nb.example\n");
+    assert_eq!(states.refresh(feed).await.unwrap(), 2);
+    assert_eq!(mock.if_none_match(), None);
+    assert_entries(&store, &["a.example", "b.example"], &["c.example"]).await;
+
+    // Unchanged feeds are not written again
+    assert_eq!(states.refresh(feed).await.unwrap(), 0);
+    assert_eq!(mock.if_none_match().as_deref(), Some("\"1\""));
+
+    // Later downloads only apply the differences
+    mock.update("b.example\nc.example\n");
+    assert_eq!(states.refresh(feed).await.unwrap(), 2);
+    assert_entries(&store, &["b.example", "c.example"], &["a.example"]).await;
+    mock.update("b.example\nc.example\nd.example\n");
+    assert_eq!(states.refresh(feed).await.unwrap(), 1);
+    assert_entries(&store, &["b.example", "c.example", "d.example"], &[]).await;
+
+    // Failed downloads keep the stored entries
+    mock.fail(true);
+    assert!(states.refresh(feed).await.is_err());
+    assert_entries(&store, &["b.example", "c.example", "d.example"], &[]).await;
+    mock.fail(false);
+    assert!(states.refresh(broken).await.is_err());
+    assert!(!store
+        .key_exists(b"broken:b.example".to_vec())
+        .await
+        .unwrap());
+
+    // Entries are written again once they are halfway to expiring
+    tokio::time::sleep(Duration::from_millis(3500)).await;
+    assert_eq!(states.refresh(feed).await.unwrap(), 3);
+    assert_eq!(mock.if_none_match(), None);
+    tokio::time::sleep(Duration::from_millis(3500)).await;
+    assert_entries(&store, &["b.example", "c.example", "d.example"], &[]).await;
+
+    temp_dir.delete();
+}
+
+async fn assert_entries(store: &LookupStore, present: &[&str], absent: &[&str]) {
+    for (entries, expected) in [(present, true), (absent, false)] {
+        for entry in entries {
+            assert_eq!(
+                store
+                    .key_exists(format!("feed:{entry}").into_bytes())
+                    .await
+                    .unwrap(),
+                expected,
+                "failed for {entry}"
+            );
+        }
+    }
+}
+
+impl MockFeed {
+    fn update(&self, contents: &str) {
+        let mut state = self.state.lock();
+        state.contents = contents.to_string();
+        state.version += 1;
+    }
+
+    fn fail(&self, fail: bool) {
+        self.state.lock().fail = fail;
+    }
+
+    fn if_none_match(&self) -> Option<String> {
+        self.state.lock().if_none_match.clone()
+    }
+
+    fn response(
+        &self,
+        path: &str,
+        if_none_match: Option<String>,
+    ) -> hyper::Response<Full<body::Bytes>> {
+        let mut state = self.state.lock();
+        let etag = format!("\"{}\"", state.version);
+        let status = if path != "/feed" || state.fail {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else if if_none_match.as_ref().is_some_and(|tag| tag == &etag) {
+            StatusCode::NOT_MODIFIED
+        } else {
+            StatusCode::OK
+        };
+        state.if_none_match = if_none_match;
+
+        hyper::Response::builder()
+            .status(status)
+            .header(hyper::header::ETAG, etag)
+            .body(Full::new(body::Bytes::from(if status == StatusCode::OK {
+                state.contents.clone()
+            } else {
+                String::new()
+            })))
+            .unwrap()
+    }
+}
+
+async fn spawn_mock_feed() -> Arc<MockFeed> {
+    let mock = Arc::new(MockFeed::default());
+    let mock_ = mock.clone();
+    let listener = TcpListener::bind("127.0.0.1:8824")
+        .await
+        .unwrap_or_else(|e| {
+            panic!("Failed to bind mock feed to 127.0.0.1:8824: {e}");
+        });
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mock = mock_.clone();
+
+            tokio::spawn(async move {
+                let _ = http1::Builder::new()
+                    .keep_alive(false)
+                    .serve_connection(
+                        TokioIo::new(stream),
+                        service_fn(move |req: hyper::Request<body::Incoming>| {
+                            let response = mock.response(
+                                req.uri().path(),
+                                req.headers()
+                                    .get(hyper::header::IF_NONE_MATCH)
+                                    .and_then(|tag| tag.to_str().ok())
+                                    .map(|tag| tag.to_string()),
+                            );
+
+                            async move { Ok::<_, hyper::Error>(response) }
+                        }),
+                    )
+                    .await;
+            });
+        }
+    });
+
+    mock
+}
@@ -5,7 +5,10 @@
  */
 
 use ahash::AHashSet;
-use common::{manager::backup::BackupParams, Core};
+use common::{
+    manager::{backup::BackupParams, restore::RestoreParams},
+    Core,
+};
 use jmap_proto::types::{collection::Collection, property::Property};
 use store::{
     rand,
@@ -234,7 +237,8 @@ pub async fn test(db: Store) {
     // Export store
     println!("Exporting store...");
     let temp_dir = TempDir::new("art_vandelay_tests", true);
-    core.backup(BackupParams::new(temp_dir.path.clone())).await;
+    let before_path = temp_dir.path.join("before");
+    core.backup(BackupParams::new(before_path.clone())).await;
 
     // Destroy store
     println!("Destroying store...");
@@ -243,7 +247,48 @@ pub async fn test(db: Store) {
 
     // Import store
     println!("Importing store...");
-    core.restore(temp_dir.path.clone()).await;
+    core.restore(RestoreParams::new(before_path.clone())).await;
+
+    // Verify hash
+    print!("Verifying store hash...");
+    snapshot.assert_is_eq(&Snapshot::new(&db).await);
+    println!(" GREAT SUCCESS!");
+
+    // Modify another account after the first snapshot
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(4)
+        .with_collection(0)
+        .update_document(0)
+        .set(ValueClass::Property(10), random_bytes(32));
+    db.write(batch.build()).await.unwrap();
+    let snapshot = Snapshot::new(&db).await;
+
+    // Wipe a single account and take a second snapshot
+    println!("Purging account...");
+    db.purge_account(3).await.unwrap();
+    let after_path = temp_dir.path.join("after");
+    core.backup(BackupParams::new(after_path.clone())).await;
+    std::fs::write(before_path.join("timestamp"), "1000").unwrap();
+    std::fs::write(after_path.join("timestamp"), "2000").unwrap();
+
+    // Restore the account as it was before the purge
+    println!("Restoring account to a point in time...");
+    core.restore(
+        RestoreParams::new(temp_dir.path.clone())
+            .with_account("3")
+            .with_timestamp(1500),
+    )
+    .await;
+
+    // Permissions granted to the account by others are not restored
+    let mut snapshot = snapshot;
+    let account_id = 3u32.to_be_bytes();
+    snapshot.keys.retain(|key| {
+        key.subspace != SUBSPACE_ACL
+            || key.key.get(..U32_LEN) != Some(&account_id[..])
+            || key.key.get(U32_LEN..U32_LEN * 2) == Some(&account_id[..])
+    });
 
     // Verify hash
     print!("Verifying store hash...");
@@ -6,7 +6,21 @@
 
 use std::time::Duration;
 
-use store::{LookupStore, Stores};
+use common::{
+    config::scripts::BayesConfig,
+    scripts::training::{
+        migrate_tokenizer, train_message, BayesTrainingData, ReputationEntry, SpamTrainingData,
+        TRAINING_DATA_VERSION,
+    },
+};
+use nlp::bayes::{
+    cache::BayesTokenCache,
+    tokenize::{BayesTokenizerConfig, CjkSegmentation},
+};
+use store::{
+    write::{key::KeySerializer, now},
+    LookupStore, Stores,
+};
 use utils::config::{Config, Rate};
 
 use crate::{
@@ -114,6 +128,128 @@ pub async fn lookup_tests() {
             .await
             .unwrap()
             .is_none());
+
+        // Test spam filter training data export and import
+        if let LookupStore::Store(_) = &store {
+            let data = SpamTrainingData {
+                version: TRAINING_DATA_VERSION,
+                bayes: BayesTrainingData {
+                    spam_learns: 2,
+                    ham_learns: 3,
+                    tokens: vec![(1, 2, 3, 4), (5, 6, 0, 1)],
+                },
+                reputation: vec![ReputationEntry {
+                    token: "i:192.0.2.1".to_string(),
+                    score: 4.0,
+                    count: 2,
+                    expires: now() + 3600,
+                }],
+            };
+            data.import(&store, false).await.unwrap();
+            assert_training_data(&SpamTrainingData::export(&store).await.unwrap(), &data);
+
+            // Merge with the existing training data
+            let mut other = data.clone();
+            other.reputation[0].score = 1.0;
+            other.reputation[0].count = 6;
+            other.import(&store, true).await.unwrap();
+            let merged = SpamTrainingData::export(&store).await.unwrap();
+            assert_eq!((merged.bayes.spam_learns, merged.bayes.ham_learns), (4, 6));
+            assert_eq!(merged.bayes.tokens, vec![(1, 2, 6, 8), (5, 6, 0, 2)]);
+            assert_eq!(merged.reputation[0].score, 1.75);
+            assert_eq!(merged.reputation[0].count, 8);
+
+            // Train the classifier from corpus messages
+            let cache =
+                BayesTokenCache::new(1024, Duration::from_secs(3600), Duration::from_secs(3600));
+            let learned = train_message(
+                &store,
+                &cache,
+                BayesTokenizerConfig::default(),
+                b"Subject: Cheap watches\r\n\r\nBuy cheap replica watches today at a discount",
+                true,
+                0,
+            )
+            .await
+            .unwrap();
+            assert!(learned > 0);
+            let limited = train_message(
+                &store,
+                &cache,
+                BayesTokenizerConfig::default(),
+                b"Subject: Meeting notes\r\n\r\nPlease find the meeting notes attached below",
+                false,
+                2,
+            )
+            .await
+            .unwrap();
+            assert!(limited > 0 && limited <= 2);
+            assert_eq!(
+                train_message(
+                    &store,
+                    &cache,
+                    BayesTokenizerConfig::default(),
+                    b"Subject: \r\n\r\n",
+                    false,
+                    0
+                )
+                .await
+                .unwrap(),
+                0
+            );
+            let trained = SpamTrainingData::export(&store).await.unwrap();
+            assert_eq!(
+                (trained.bayes.spam_learns, trained.bayes.ham_learns),
+                (5, 7)
+            );
+            assert!(trained.bayes.tokens.len() > merged.bayes.tokens.len());
+
+            // Changing the tokenizer settings removes the learned tokens when requested
+            let mut bayes_config = BayesConfig::default();
+            assert!(!migrate_tokenizer(&store, &cache, &bayes_config)
+                .await
+                .unwrap());
+            bayes_config.tokenizer.cjk = CjkSegmentation::Bigram;
+            assert!(migrate_tokenizer(&store, &cache, &bayes_config)
+                .await
+                .unwrap());
+            assert!(!migrate_tokenizer(&store, &cache, &bayes_config)
+                .await
+                .unwrap());
+            assert_eq!(
+                SpamTrainingData::export(&store).await.unwrap().bayes,
+                trained.bayes
+            );
+            bayes_config.tokenizer.cjk = CjkSegmentation::Dictionary;
+            bayes_config.reset_on_change = true;
+            assert!(migrate_tokenizer(&store, &cache, &bayes_config)
+                .await
+                .unwrap());
+            assert_eq!(
+                SpamTrainingData::export(&store).await.unwrap().bayes,
+                BayesTrainingData::default()
+            );
+            store
+                .key_delete("bayes:tokenizer".as_bytes().to_vec())
+                .await
+                .unwrap();
+
+            // Replace the existing training data
+            data.import(&store, false).await.unwrap();
+            assert_training_data(&SpamTrainingData::export(&store).await.unwrap(), &data);
+
+            for (h1, h2) in [(0u64, 0u64), (1, 2), (5, 6)] {
+                store
+                    .counter_delete(KeySerializer::new(16).write(h1).write(h2).finalize())
+                    .await
+                    .unwrap();
+            }
+            store
+                .key_delete("i:192.0.2.1".as_bytes().to_vec())
+                .await
+                .unwrap();
+        }
+
         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
         store.purge_lookup_store().await.unwrap();
         if let LookupStore::Store(store) = &store {
@@ -121,3 +257,15 @@ pub async fn lookup_tests() {
         }
     }
 }
+
+fn assert_training_data(data: &SpamTrainingData, expected: &SpamTrainingData) {
+    assert_eq!(data.bayes, expected.bayes);
+    assert_eq!(data.reputation.len(), expected.reputation.len());
+    for (entry, expected) in data.reputation.iter().zip(expected.reputation.iter()) {
+        assert_eq!(
+            (&entry.token, entry.score, entry.count),
+            (&expected.token, expected.score, expected.count)
+        );
+        assert!(entry.expires.abs_diff(expected.expires) <= 1);
+    }
+}
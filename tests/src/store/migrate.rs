@@ -0,0 +1,65 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use common::manager::migrate::{SchemaMigration, MIGRATIONS, SCHEMA_VERSION};
+use store::{
+    write::{AnyClass, BatchBuilder, ValueClass},
+    Store, SUBSPACE_DIRECTORY,
+};
+
+pub async fn test(db: Store) {
+    println!("Running schema migration tests...");
+
+    // A new store has all migrations pending
+    assert_eq!(db.schema_version().await.unwrap(), 0);
+    assert_eq!(
+        db.pending_migrations()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(_, migration)| migration)
+            .collect::<Vec<_>>(),
+        MIGRATIONS
+    );
+
+    // A failed migration releases the lock so it can be retried right away
+    let corrupt_principal = ValueClass::Any(AnyClass {
+        subspace: SUBSPACE_DIRECTORY,
+        key: vec![2, 1],
+    });
+    let mut batch = BatchBuilder::new();
+    batch.set(corrupt_principal.clone(), vec![1]);
+    db.write(batch.build()).await.unwrap();
+    assert!(db.migrate().await.is_err());
+    assert_eq!(db.schema_version().await.unwrap(), 0);
+    let mut batch = BatchBuilder::new();
+    batch.clear(corrupt_principal);
+    db.write(batch.build()).await.unwrap();
+
+    // Migrate concurrently, only one task should apply the migrations
+    let mut handles = Vec::new();
+    for _ in 0..4 {
+        let db = db.clone();
+        handles.push(tokio::spawn(async move { db.migrate().await }));
+    }
+    for handle in handles {
+        tokio::time::timeout(Duration::from_secs(30), handle)
+            .await
+            .expect("Migration lock was not released")
+            .unwrap()
+            .unwrap();
+    }
+    assert_eq!(db.schema_version().await.unwrap(), SCHEMA_VERSION);
+    assert!(db.pending_migrations().await.unwrap().is_empty());
+
+    // The migration lock can only be held by one node
+    assert!(db.try_lock_migration().await.unwrap().is_some());
+    assert!(db.try_lock_migration().await.unwrap().is_none());
+
+    db.destroy().await;
+}
@@ -6,10 +6,14 @@
 
 pub mod assign_id;
 pub mod blob;
+pub mod chaos;
+pub mod feeds;
 pub mod import_export;
 pub mod lookup;
+pub mod migrate;
 pub mod ops;
 pub mod query;
+pub mod replication;
 
 use std::io::Read;
 
@@ -93,7 +97,9 @@ pub async fn store_tests() {
 
     import_export::test(store.clone()).await;
     assign_id::test(store.clone()).await;
+    migrate::test(store.clone()).await;
     ops::test(store.clone()).await;
+    chaos::test(store.clone()).await;
     query::test(store.clone(), FtsStore::Store(store.clone()), insert).await;
 
     if insert {
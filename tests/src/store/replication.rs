@@ -0,0 +1,248 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use store::{
+    dispatch::replication::{applied_position, ChangeOp, ChangeRecord, ChangeSet, CHANGE_STREAM},
+    write::{now, AnyClass, BatchBuilder, Bincode, DirectoryClass, ValueClass},
+    BlobStore, Serialize, Store, Stores, ValueKey, SUBSPACE_REPLICATION,
+};
+use utils::{config::Config, snowflake::SnowflakeIdGenerator};
+
+use crate::{store::TempDir, AssertConfig};
+
+const CONFIG: &str = r#"
+[store."primary"]
+type = "sqlite"
+path = "{TMP}/primary.db"
+
+[store."standby"]
+type = "sqlite"
+path = "{TMP}/standby.db"
+"#;
+
+const SETTLE_TIME: Duration = Duration::from_millis(5100);
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn replication_tests() {
+    let temp_dir = TempDir::new("replication_tests", true);
+    let mut config = Config::new(CONFIG.replace("{TMP}", &temp_dir.path.to_string_lossy()))
+        .unwrap()
+        .assert_no_errors();
+    let mut stores = Stores::parse_all(&mut config).await;
+    let primary = stores.stores.remove("primary").unwrap();
+    let standby = stores.stores.remove("standby").unwrap();
+    let primary_blob: BlobStore = primary.clone().into();
+    let standby_blob: BlobStore = standby.clone().into();
+    CHANGE_STREAM.enable(&primary, &primary_blob, 1, 1024 * 1024);
+
+    println!("Running replication tests...");
+
+    // Changes written to the primary are applied to the standby
+    let mut batch = BatchBuilder::new();
+    batch
+        .with_account_id(0)
+        .with_collection(0)
+        .update_document(0)
+        .set(
+            ValueClass::Config(b"replicated".to_vec()),
+            b"value".to_vec(),
+        )
+        .add(ValueClass::Directory(DirectoryClass::UsedQuota(0)), 10);
+    primary.write(batch.build()).await.unwrap();
+    primary_blob.put_blob(b"blob", b"contents").await.unwrap();
+    let changes = ship(&primary, &standby, &standby_blob).await;
+    assert_eq!(changes.records.len(), 2);
+    assert_eq!(
+        standby
+            .get_value::<String>(ValueKey::from(ValueClass::Config(b"replicated".to_vec())))
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("value")
+    );
+    assert_eq!(used_quota(&standby).await, 10);
+    assert_eq!(
+        standby_blob
+            .get_blob(b"blob", 0..usize::MAX)
+            .await
+            .unwrap()
+            .as_deref(),
+        Some(b"contents".as_slice())
+    );
+    assert_eq!(
+        applied_position(&standby).await.unwrap(),
+        changes.records.last().unwrap().seq
+    );
+    assert!(CHANGE_STREAM.pending(usize::MAX).await.unwrap().is_none());
+
+    // Changes that commit after newer changes were shipped are shipped later
+    let late_seq = SnowflakeIdGenerator::with_node_id(1)
+        .past_id(Duration::from_secs(60))
+        .unwrap();
+    let mut batch = BatchBuilder::new();
+    batch.set(
+        ValueClass::Any(AnyClass {
+            subspace: SUBSPACE_REPLICATION,
+            key: record_key(late_seq),
+        }),
+        Bincode::new(ChangeRecord {
+            seq: late_seq,
+            timestamp: now(),
+            ops: vec![ChangeOp::Set {
+                subspace: store::SUBSPACE_PROPERTY,
+                key: b"late".to_vec(),
+                value: b"commit".to_vec(),
+            }],
+        })
+        .serialize(),
+    );
+    primary.write(batch.build()).await.unwrap();
+    let changes = ship(&primary, &standby, &standby_blob).await;
+    assert_eq!(changes.records.len(), 1);
+    assert_eq!(changes.records[0].seq, late_seq);
+    assert!(!CHANGE_STREAM.status().await.unwrap().resync);
+    assert_eq!(
+        standby
+            .get_value::<String>(ValueKey::from(ValueClass::Any(AnyClass {
+                subspace: store::SUBSPACE_PROPERTY,
+                key: b"late".to_vec(),
+            })))
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("commit")
+    );
+
+    // A change set that is shipped again after a crash is applied once
+    for num in 0..3 {
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(0)
+            .with_collection(0)
+            .update_document(0)
+            .add(ValueClass::Directory(DirectoryClass::UsedQuota(0)), 1);
+        primary.write(batch.build()).await.unwrap();
+        primary_blob
+            .put_blob(format!("blob{num}").as_bytes(), b"contents")
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(SETTLE_TIME).await;
+    let changes = with_blobs(
+        CHANGE_STREAM.pending(usize::MAX).await.unwrap().unwrap(),
+        &primary_blob,
+    )
+    .await;
+    assert_eq!(changes.records.len(), 6);
+    let partial = ChangeSet {
+        records: changes.records[..3].to_vec(),
+    };
+    assert_eq!(
+        partial.apply(&standby, &standby_blob).await.unwrap(),
+        changes.records[2].seq
+    );
+    assert_eq!(used_quota(&standby).await, 12);
+    let last_seq = changes.records.last().unwrap().seq;
+    let (first, second) = tokio::join!(
+        changes.clone().apply(&standby, &standby_blob),
+        changes.clone().apply(&standby, &standby_blob)
+    );
+    assert_eq!(first.unwrap(), last_seq);
+    assert_eq!(second.unwrap(), last_seq);
+    assert_eq!(used_quota(&standby).await, 13);
+    for num in 0..3 {
+        assert!(standby_blob
+            .get_blob(format!("blob{num}").as_bytes(), 0..usize::MAX)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    // Only the acknowledged records are removed from the stream
+    let seqs = changes
+        .records
+        .iter()
+        .map(|record| record.seq)
+        .collect::<Vec<_>>();
+    CHANGE_STREAM.acknowledge(&seqs[..3]).await.unwrap();
+    let pending = CHANGE_STREAM.pending(usize::MAX).await.unwrap().unwrap();
+    assert_eq!(
+        pending
+            .records
+            .iter()
+            .map(|record| record.seq)
+            .collect::<Vec<_>>(),
+        seqs[3..]
+    );
+    assert_eq!(
+        pending.apply(&standby, &standby_blob).await.unwrap(),
+        last_seq
+    );
+    CHANGE_STREAM.acknowledge(&seqs[3..]).await.unwrap();
+    assert!(CHANGE_STREAM.pending(usize::MAX).await.unwrap().is_none());
+    assert_eq!(used_quota(&standby).await, 13);
+
+    CHANGE_STREAM.disable();
+    temp_dir.delete();
+}
+
+// Ships the pending changes the way the primary does
+async fn ship(primary: &Store, standby: &Store, standby_blob: &BlobStore) -> ChangeSet {
+    tokio::time::sleep(SETTLE_TIME).await;
+    let changes = with_blobs(
+        CHANGE_STREAM.pending(usize::MAX).await.unwrap().unwrap(),
+        &primary.clone().into(),
+    )
+    .await;
+    let last_seq = changes.clone().apply(standby, standby_blob).await.unwrap();
+    assert_eq!(last_seq, changes.records.last().unwrap().seq);
+    CHANGE_STREAM
+        .acknowledge(
+            &changes
+                .records
+                .iter()
+                .map(|record| record.seq)
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .unwrap();
+    changes
+}
+
+async fn with_blobs(mut changes: ChangeSet, blob_store: &BlobStore) -> ChangeSet {
+    for record in &mut changes.records {
+        for op in &mut record.ops {
+            if let ChangeOp::BlobPut { key, data } = op {
+                *data = blob_store
+                    .get_blob(key, 0..usize::MAX)
+                    .await
+                    .unwrap()
+                    .unwrap();
+            }
+        }
+    }
+    changes
+}
+
+async fn used_quota(store: &Store) -> i64 {
+    store
+        .get_counter(ValueKey {
+            account_id: 0,
+            collection: 0,
+            document_id: 0,
+            class: ValueClass::Directory(DirectoryClass::UsedQuota(0)),
+        })
+        .await
+        .unwrap()
+}
+
+fn record_key(seq: u64) -> Vec<u8> {
+    let mut key = vec![0u8];
+    key.extend_from_slice(&seq.to_be_bytes());
+    key
+}